@@ -0,0 +1,632 @@
+//! Registry of named remote application entities.
+//!
+//! This module provides the `--remote` and `--config` options
+//! for tools which connect to other DICOM nodes,
+//! so that peers can be referenced by name
+//! instead of repeating their connection parameters.
+//!
+//! The configuration file is a small subset of TOML,
+//! with one `remote` table per remote AE
+//! and an optional table of defaults for the local AE:
+//!
+//! ```toml
+//! [defaults]
+//! calling_ae_title = "WORKSTATION1"
+//! max_pdu_length = 32768
+//!
+//! # the main archive
+//! [remote.PACS1]
+//! host = "pacs.example.com"
+//! port = 11112
+//! ae_title = "PACS1"
+//! tls = true
+//! max_pdu_length = 65536
+//! # the local AE title known to this node
+//! calling_ae_title = "WS1-PACS"
+//! ```
+//!
+//! Only `host` is required.
+//! `port` defaults to 104,
+//! and `ae_title` defaults to the name of the table.
+//! Remote tables may also omit the `remote.` prefix,
+//! as in the former `ae.toml` files (`[PACS1]`),
+//! so no remote AE can be named `defaults`.
+//!
+//! Options given on the command line take precedence
+//! over the remote AE entry,
+//! which takes precedence over the `defaults` table.
+//!
+//! Unless `--config` is given,
+//! the file is looked up in the following locations, in order:
+//!
+//! 1. the path in the environment variable `DICOM_RS_AE_CONFIG`;
+//! 2. `$XDG_CONFIG_HOME/dicom-rs/config.toml`;
+//! 3. `$HOME/.config/dicom-rs/config.toml`
+//!    (`%APPDATA%\dicom-rs\config.toml` on Windows);
+//! 4. `ae.toml` in the same directory, for compatibility.
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use snafu::prelude::*;
+
+/// The environment variable pointing to the configuration file
+pub const AE_CONFIG_ENV: &str = "DICOM_RS_AE_CONFIG";
+
+/// The default TCP port of a remote AE
+pub const DEFAULT_PORT: u16 = 104;
+
+/// An error which may occur when parsing an AE configuration file
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ParseAeConfigError {
+    /// line {line}: expected a table header or a `key = value` pair
+    Syntax { line: usize },
+    /// line {line}: key `{key}` is outside of a table
+    KeyOutsideTable { line: usize, key: String },
+    /// line {line}: unknown key `{key}`
+    UnknownKey { line: usize, key: String },
+    /// line {line}: invalid value for key `{key}`
+    InvalidValue { line: usize, key: String },
+    /// line {line}: table `{name}` is defined more than once
+    DuplicateAe { line: usize, name: String },
+    /// AE `{name}` has no host
+    MissingHost { name: String },
+}
+
+/// An error which may occur when resolving a remote AE
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum AeConfigError {
+    #[snafu(display("could not read AE configuration file {}", path.display()))]
+    ReadConfig {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    #[snafu(display("invalid AE configuration file {}", path.display()))]
+    ParseConfig {
+        source: ParseAeConfigError,
+        path: PathBuf,
+    },
+    /// unknown AE `{name}` in AE configuration
+    UnknownAe { name: String },
+    /// no peer address given
+    MissingAddress,
+}
+
+/// The connection parameters of a remote application entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteAe {
+    /// the host name or IP address
+    pub host: String,
+    /// the TCP port
+    pub port: u16,
+    /// the AE title of the remote node
+    pub ae_title: String,
+    /// whether the remote node expects DICOM over TLS
+    pub tls: bool,
+    /// the maximum PDU length to announce, if specified
+    pub max_pdu_length: Option<u32>,
+    /// the calling AE title to use with this node, if specified
+    pub calling_ae_title: Option<String>,
+}
+
+impl RemoteAe {
+    /// The address of the remote AE
+    /// in the form `«ae_title»@«host»:«port»`,
+    /// as accepted by the association options in `dicom-ul`.
+    pub fn address(&self) -> String {
+        format!("{}@{}:{}", self.ae_title, self.host, self.port)
+    }
+}
+
+/// The defaults of the local application entity,
+/// applying to all remote AEs
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LocalAeDefaults {
+    /// the calling AE title, if specified
+    pub calling_ae_title: Option<String>,
+    /// the maximum PDU length to announce, if specified
+    pub max_pdu_length: Option<u32>,
+}
+
+/// A collection of named remote application entities,
+/// plus the defaults of the local application entity
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AeRegistry {
+    entries: BTreeMap<String, RemoteAe>,
+    defaults: LocalAeDefaults,
+}
+
+impl AeRegistry {
+    /// Parse the contents of an AE configuration file.
+    pub fn parse(text: &str) -> Result<Self, ParseAeConfigError> {
+        /// an AE table still being read
+        struct Partial {
+            name: String,
+            host: Option<String>,
+            port: u16,
+            ae_title: Option<String>,
+            tls: bool,
+            max_pdu_length: Option<u32>,
+            calling_ae_title: Option<String>,
+        }
+
+        /// the table being read
+        enum Table {
+            Remote(Partial),
+            Defaults,
+        }
+
+        impl Partial {
+            fn finish(self) -> Result<(String, RemoteAe), ParseAeConfigError> {
+                let host = self.host.context(MissingHostSnafu { name: &self.name })?;
+                let ae_title = self.ae_title.unwrap_or_else(|| self.name.clone());
+                Ok((
+                    self.name,
+                    RemoteAe {
+                        host,
+                        port: self.port,
+                        ae_title,
+                        tls: self.tls,
+                        max_pdu_length: self.max_pdu_length,
+                        calling_ae_title: self.calling_ae_title,
+                    },
+                ))
+            }
+        }
+
+        let mut entries = BTreeMap::new();
+        let mut defaults = LocalAeDefaults::default();
+        let mut has_defaults = false;
+        let mut current: Option<Table> = None;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .context(SyntaxSnafu { line: line_no })?;
+                if let Some(Table::Remote(partial)) = current.take() {
+                    let (name, ae) = partial.finish()?;
+                    entries.insert(name, ae);
+                }
+                if header == "defaults" {
+                    ensure!(
+                        !has_defaults,
+                        DuplicateAeSnafu {
+                            line: line_no,
+                            name: header
+                        }
+                    );
+                    has_defaults = true;
+                    current = Some(Table::Defaults);
+                    continue;
+                }
+                let name = header
+                    .strip_prefix("remote.")
+                    .map(str::trim)
+                    .unwrap_or(header);
+                let name = unquote(name).unwrap_or(name).to_string();
+                ensure!(!name.is_empty(), SyntaxSnafu { line: line_no });
+                ensure!(
+                    !entries.contains_key(&name),
+                    DuplicateAeSnafu {
+                        line: line_no,
+                        name
+                    }
+                );
+                current = Some(Table::Remote(Partial {
+                    name,
+                    host: None,
+                    port: DEFAULT_PORT,
+                    ae_title: None,
+                    tls: false,
+                    max_pdu_length: None,
+                    calling_ae_title: None,
+                }));
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .context(SyntaxSnafu { line: line_no })?;
+            let (key, value) = (key.trim(), value.trim());
+            let table = current
+                .as_mut()
+                .context(KeyOutsideTableSnafu { line: line_no, key })?;
+            let invalid = || InvalidValueSnafu { line: line_no, key };
+            let partial = match table {
+                Table::Remote(partial) => partial,
+                Table::Defaults => {
+                    match key {
+                        "calling_ae_title" => {
+                            defaults.calling_ae_title =
+                                Some(unquote(value).context(invalid())?.to_string())
+                        }
+                        "max_pdu_length" => {
+                            defaults.max_pdu_length = Some(value.parse().ok().context(invalid())?)
+                        }
+                        _ => {
+                            return UnknownKeySnafu { line: line_no, key }.fail();
+                        }
+                    }
+                    continue;
+                }
+            };
+            match key {
+                "host" => partial.host = Some(unquote(value).context(invalid())?.to_string()),
+                "port" => partial.port = value.parse().ok().context(invalid())?,
+                "ae_title" => {
+                    partial.ae_title = Some(unquote(value).context(invalid())?.to_string())
+                }
+                "tls" => partial.tls = value.parse().ok().context(invalid())?,
+                "max_pdu_length" => {
+                    partial.max_pdu_length = Some(value.parse().ok().context(invalid())?)
+                }
+                "calling_ae_title" => {
+                    partial.calling_ae_title = Some(unquote(value).context(invalid())?.to_string())
+                }
+                _ => {
+                    return UnknownKeySnafu { line: line_no, key }.fail();
+                }
+            }
+        }
+        if let Some(Table::Remote(partial)) = current {
+            let (name, ae) = partial.finish()?;
+            entries.insert(name, ae);
+        }
+        Ok(AeRegistry { entries, defaults })
+    }
+
+    /// Read the AE configuration file at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AeConfigError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).context(ReadConfigSnafu { path })?;
+        AeRegistry::parse(&text).context(ParseConfigSnafu { path })
+    }
+
+    /// Read the configuration file
+    /// from the first of the default locations available.
+    ///
+    /// Returns an empty registry if there is no configuration file.
+    pub fn open_default() -> Result<Self, AeConfigError> {
+        if let Some(path) = std::env::var_os(AE_CONFIG_ENV) {
+            return AeRegistry::open(path);
+        }
+        let paths = default_config_path()
+            .into_iter()
+            .chain(legacy_config_path());
+        for path in paths {
+            if path.is_file() {
+                return AeRegistry::open(path);
+            }
+        }
+        Ok(AeRegistry::default())
+    }
+
+    /// Retrieve a remote AE by name.
+    pub fn get(&self, name: &str) -> Option<&RemoteAe> {
+        self.entries.get(name)
+    }
+
+    /// The defaults of the local AE.
+    pub fn defaults(&self) -> &LocalAeDefaults {
+        &self.defaults
+    }
+
+    /// Iterate over all remote AEs and their names, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RemoteAe)> {
+        self.entries.iter().map(|(name, ae)| (name.as_str(), ae))
+    }
+
+    /// The number of remote AEs in the registry.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the registry has no remote AEs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The default location of the user's configuration file,
+/// not considering the environment variable `DICOM_RS_AE_CONFIG`
+pub fn default_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// The former location of the user's AE configuration file
+fn legacy_config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("ae.toml"))
+}
+
+/// The directory of the user's DICOM-rs configuration
+fn config_dir() -> Option<PathBuf> {
+    let config_dir = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+    };
+    config_dir.map(|dir| dir.join("dicom-rs"))
+}
+
+/// Remove a trailing comment outside of quoted strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Remove the quotes around a string value
+fn unquote(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .filter(|v| !v.contains('"'))
+}
+
+/// Application options for referencing remote AEs by name
+#[derive(Args, Debug, Default, Clone)]
+pub struct AeConfigOptions {
+    /// Connect to the remote AE with this name in the configuration file,
+    /// instead of a socket address
+    #[arg(long = "remote", visible_alias = "to", value_name = "AE_NAME")]
+    pub to: Option<String>,
+
+    /// Path to the configuration file
+    /// [default: $DICOM_RS_AE_CONFIG or ~/.config/dicom-rs/config.toml]
+    #[arg(long = "config", visible_alias = "ae-config", value_name = "FILE")]
+    pub ae_config: Option<PathBuf>,
+}
+
+/// The peer to connect to, as resolved from the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peer {
+    /// the peer address, optionally with the called AE title
+    /// (`[«ae_title»@]«host»:«port»`)
+    pub address: String,
+    /// whether the peer expects DICOM over TLS,
+    /// as specified in the AE configuration
+    pub tls: bool,
+    /// the maximum PDU length specified in the configuration,
+    /// for the remote AE or as a default
+    pub max_pdu_length: Option<u32>,
+    /// the calling AE title specified in the configuration,
+    /// for the remote AE or as a default
+    pub calling_ae_title: Option<String>,
+}
+
+impl AeConfigOptions {
+    /// Load the AE configuration file
+    /// from the path given or the default locations.
+    pub fn registry(&self) -> Result<AeRegistry, AeConfigError> {
+        match &self.ae_config {
+            Some(path) => AeRegistry::open(path),
+            None => AeRegistry::open_default(),
+        }
+    }
+
+    /// Resolve the peer to connect to.
+    ///
+    /// `addr` is the address given as the first positional argument,
+    /// which may also be the name of a remote AE in the AE configuration
+    /// (names take precedence over host names).
+    /// With `--to`, no positional address is expected,
+    /// so `addr` is handed back as the second element of the tuple
+    /// for the application to treat as its next positional argument.
+    ///
+    /// The defaults of the local AE in the configuration
+    /// apply unless the remote AE overrides them.
+    pub fn resolve(&self, addr: Option<String>) -> Result<(Peer, Option<String>), AeConfigError> {
+        if let Some(name) = &self.to {
+            let registry = self.registry()?;
+            let ae = registry.get(name).context(UnknownAeSnafu { name })?;
+            return Ok((Peer::new(ae, registry.defaults()), addr));
+        }
+
+        let addr = addr.context(MissingAddressSnafu)?;
+        let registry = self.registry()?;
+        // only look up names which cannot be socket addresses
+        if !addr.contains(':') {
+            if let Some(ae) = registry.get(&addr) {
+                return Ok((Peer::new(ae, registry.defaults()), None));
+            }
+        }
+        let defaults = registry.defaults();
+        Ok((
+            Peer {
+                address: addr,
+                tls: false,
+                max_pdu_length: defaults.max_pdu_length,
+                calling_ae_title: defaults.calling_ae_title.clone(),
+            },
+            None,
+        ))
+    }
+}
+
+impl Peer {
+    /// The maximum PDU length to propose:
+    /// the one given on the command line,
+    /// or else the one configured for the remote AE,
+    /// or else the configured default of the local AE,
+    /// or else the built-in `default` of the tool.
+    pub fn max_pdu_length_or(&self, cli: Option<u32>, default: u32) -> u32 {
+        cli.or(self.max_pdu_length).unwrap_or(default)
+    }
+
+    /// Describe a remote AE as a peer,
+    /// filling in the defaults of the local AE.
+    fn new(ae: &RemoteAe, defaults: &LocalAeDefaults) -> Self {
+        Peer {
+            address: ae.address(),
+            tls: ae.tls,
+            max_pdu_length: ae.max_pdu_length.or(defaults.max_pdu_length),
+            calling_ae_title: ae
+                .calling_ae_title
+                .clone()
+                .or_else(|| defaults.calling_ae_title.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+[defaults]
+calling_ae_title = "LOCAL"
+max_pdu_length = 32768
+
+# the main archive
+[remote.PACS1]
+host = "pacs.example.com" # inline comment
+port = 11112
+ae_title = "MAIN#ARCHIVE"
+tls = true
+max_pdu_length = 65536
+calling_ae_title = "LOCAL-PACS"
+
+# a table in the former style
+[WORKSTATION]
+host = "10.0.0.12"
+"#;
+
+    #[test]
+    fn parse_ae_config() {
+        let registry = AeRegistry::parse(CONFIG).unwrap();
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry.get("PACS1"),
+            Some(&RemoteAe {
+                host: "pacs.example.com".to_string(),
+                port: 11112,
+                ae_title: "MAIN#ARCHIVE".to_string(),
+                tls: true,
+                max_pdu_length: Some(65536),
+                calling_ae_title: Some("LOCAL-PACS".to_string()),
+            })
+        );
+        assert_eq!(
+            registry.defaults(),
+            &LocalAeDefaults {
+                calling_ae_title: Some("LOCAL".to_string()),
+                max_pdu_length: Some(32768),
+            }
+        );
+        let workstation = registry.get("WORKSTATION").unwrap();
+        assert_eq!(workstation.port, DEFAULT_PORT);
+        assert!(!workstation.tls);
+        assert_eq!(workstation.address(), "WORKSTATION@10.0.0.12:104");
+        assert_eq!(registry.get("OTHER"), None);
+
+        assert!(matches!(
+            AeRegistry::parse("[A]\nhost = \"a\"\nport = many\n"),
+            Err(ParseAeConfigError::InvalidValue { line: 3, .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("host = \"a\"\n"),
+            Err(ParseAeConfigError::KeyOutsideTable { line: 1, .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[A]\nhost = \"a\"\nprot = 104\n"),
+            Err(ParseAeConfigError::UnknownKey { line: 3, .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[A]\nport = 104\n"),
+            Err(ParseAeConfigError::MissingHost { .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[A]\nhost = \"a\"\n[A]\nhost = \"b\"\n"),
+            Err(ParseAeConfigError::DuplicateAe { line: 3, .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[A\n"),
+            Err(ParseAeConfigError::Syntax { line: 1 })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[defaults]\nhost = \"a\"\n"),
+            Err(ParseAeConfigError::UnknownKey { line: 2, .. })
+        ));
+        assert!(matches!(
+            AeRegistry::parse("[remote.A]\nhost = \"a\"\n[A]\nhost = \"b\"\n"),
+            Err(ParseAeConfigError::DuplicateAe { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_peer() {
+        let path = std::env::temp_dir().join(format!(
+            "dicom-rs-aeconfig-test-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, CONFIG).unwrap();
+        let options = AeConfigOptions {
+            to: None,
+            ae_config: Some(path.clone()),
+        };
+
+        // plain socket addresses are passed through
+        let (peer, rest) = options
+            .resolve(Some("STORE-SCP@127.0.0.1:104".to_string()))
+            .unwrap();
+        assert_eq!(peer.address, "STORE-SCP@127.0.0.1:104");
+        assert!(!peer.tls);
+        assert_eq!(peer.calling_ae_title.as_deref(), Some("LOCAL"));
+        assert_eq!(rest, None);
+        // the command line takes precedence over the defaults
+        assert_eq!(peer.max_pdu_length, Some(32768));
+        assert_eq!(peer.max_pdu_length_or(Some(16384), 16378), 16384);
+        assert_eq!(peer.max_pdu_length_or(None, 16378), 32768);
+
+        // names in place of the address
+        let (peer, _) = options.resolve(Some("PACS1".to_string())).unwrap();
+        assert_eq!(peer.address, "MAIN#ARCHIVE@pacs.example.com:11112");
+        assert!(peer.tls);
+        assert_eq!(peer.max_pdu_length, Some(65536));
+        assert_eq!(peer.max_pdu_length_or(Some(16384), 16378), 16384);
+        assert_eq!(peer.calling_ae_title.as_deref(), Some("LOCAL-PACS"));
+
+        // with --to, the positional argument is handed back
+        let options = AeConfigOptions {
+            to: Some("WORKSTATION".to_string()),
+            ..options
+        };
+        let (peer, rest) = options.resolve(Some("file.dcm".to_string())).unwrap();
+        assert_eq!(peer.address, "WORKSTATION@10.0.0.12:104");
+        assert_eq!(peer.max_pdu_length, Some(32768));
+        assert_eq!(peer.calling_ae_title.as_deref(), Some("LOCAL"));
+        assert_eq!(rest.as_deref(), Some("file.dcm"));
+
+        let options = AeConfigOptions {
+            to: Some("NOBODY".to_string()),
+            ..options
+        };
+        assert!(matches!(
+            options.resolve(None),
+            Err(AeConfigError::UnknownAe { .. })
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,42 @@
+//! Bandwidth limits from command line arguments.
+//!
+//! A rate is a number of bytes per second,
+//! optionally followed by the decimal unit prefix `k`, `M` or `G`
+//! (e.g. `500k` or `2.5M`).
+
+/// Parse a number of bytes per second with an optional decimal unit prefix.
+pub fn parse_bandwidth(s: &str) -> Result<u64, String> {
+    let rate = s.trim();
+    let (number, multiplier) = match rate.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&rate[..i], 1e3),
+        Some((i, 'M')) => (&rate[..i], 1e6),
+        Some((i, 'G')) => (&rate[..i], 1e9),
+        _ => (rate, 1.),
+    };
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid bandwidth `{s}`"))?;
+    let bytes_per_second = (value * multiplier).round();
+    if bytes_per_second.is_nan() || bytes_per_second < 1. {
+        return Err(format!(
+            "bandwidth `{s}` must be at least 1 byte per second"
+        ));
+    }
+    Ok(bytes_per_second as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_bandwidth;
+
+    #[test]
+    fn parse_bandwidth_with_units() {
+        assert_eq!(parse_bandwidth("1500"), Ok(1_500));
+        assert_eq!(parse_bandwidth("500k"), Ok(500_000));
+        assert_eq!(parse_bandwidth("2.5M"), Ok(2_500_000));
+        assert_eq!(parse_bandwidth("1G"), Ok(1_000_000_000));
+        assert!(parse_bandwidth("0").is_err());
+        assert!(parse_bandwidth("fast").is_err());
+        assert!(parse_bandwidth("M").is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! Data set mutations from command line arguments.
+//!
+//! This module provides the `--set` and `--set-seq` options
+//! for tools which create or edit DICOM objects.
+//!
+//! - `--set «key»=«value»` sets a root attribute,
+//!   where `«key»` is a tag (e.g. `(0010,0020)` or `00100020`)
+//!   or a keyword (e.g. `PatientName`).
+//! - `--set-seq «path»=«value»` sets an attribute inside a sequence,
+//!   using the [attribute selector][1] syntax
+//!   (e.g. `ReferencedStudySequence[0].ReferencedSOPInstanceUID`).
+//!   Missing sequences and items are created in the process,
+//!   as long as each item index is at most
+//!   the number of items already in the sequence.
+//!
+//! Values are converted to the value representation
+//! of the attribute according to the standard data dictionary.
+//! Multiple values are separated by a backslash (`\`).
+//! An empty value results in an empty attribute.
+//!
+//! [1]: dicom_core::ops::AttributeSelector
+use std::str::FromStr;
+
+use clap::Args;
+use dicom_core::{
+    DataDictionary, PrimitiveValue, Tag, VR,
+    dictionary::{DataDictionaryEntry, ParseSelectorError},
+    ops::{ApplyOp, AttributeAction, AttributeOp, AttributeSelector},
+    smallvec::SmallVec,
+};
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::{InMemDicomObject, ops::ApplyError};
+use snafu::prelude::*;
+
+/// An error which may occur when parsing an attribute assignment
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ParseAssignmentError {
+    /// missing `=` between attribute and value
+    MissingValue,
+
+    #[snafu(display("could not resolve attribute `{key}`"))]
+    ParseSelector {
+        key: String,
+        source: ParseSelectorError,
+    },
+
+    #[snafu(display("`{key}` is not a root attribute, use --set-seq instead"))]
+    NotRootAttribute { key: String },
+
+    #[snafu(display("`{key}` does not refer to an attribute in a sequence, use --set instead"))]
+    NotNestedAttribute { key: String },
+}
+
+/// An error which may occur when applying attribute assignments to an object
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ApplyAssignmentError {
+    #[snafu(display("unsupported value representation {vr} for {selector}"))]
+    UnsupportedVr { selector: AttributeSelector, vr: VR },
+
+    #[snafu(display("could not parse `{value}` as {vr} for {selector}"))]
+    ParseValue {
+        selector: AttributeSelector,
+        vr: VR,
+        value: String,
+    },
+
+    #[snafu(display("could not set {selector}"))]
+    Apply {
+        selector: AttributeSelector,
+        source: ApplyError,
+    },
+}
+
+/// A single attribute assignment, of the form `«selector»=«value»`.
+///
+/// The selector is either a tag, a keyword,
+/// or a full attribute selector into nested data sets.
+/// The value is kept in its textual form
+/// until applied to an object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeAssignment {
+    /// the attribute to set
+    pub selector: AttributeSelector,
+    /// the new value in text form
+    pub value: String,
+}
+
+impl FromStr for AttributeAssignment {
+    type Err = ParseAssignmentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s.split_once('=').context(MissingValueSnafu)?;
+        let key = key.trim();
+        let selector = StandardDataDictionary
+            .parse_selector(key)
+            .context(ParseSelectorSnafu { key })?;
+        Ok(AttributeAssignment {
+            selector,
+            value: value.to_string(),
+        })
+    }
+}
+
+impl AttributeAssignment {
+    /// Whether the assignment targets an attribute at the root of the data set.
+    pub fn is_root(&self) -> bool {
+        self.selector.iter().count() == 1
+    }
+
+    /// The value representation of the attribute
+    /// according to the standard data dictionary.
+    ///
+    /// Attributes missing from the dictionary
+    /// are assumed to be of VR LO.
+    pub fn vr(&self) -> VR {
+        StandardDataDictionary
+            .by_tag(self.selector.last_tag())
+            .and_then(|e| e.vr().exact())
+            .unwrap_or(VR::LO)
+    }
+
+    /// Convert the textual value into a primitive value
+    /// fitting the attribute's [value representation](Self::vr).
+    pub fn to_value(&self) -> Result<PrimitiveValue, ApplyAssignmentError> {
+        text_to_value(&self.selector, self.vr(), &self.value)
+    }
+
+    /// Apply the assignment to the given object.
+    pub fn apply(&self, obj: &mut InMemDicomObject) -> Result<(), ApplyAssignmentError> {
+        let value = self.to_value()?;
+        obj.apply(AttributeOp::new(
+            self.selector.clone(),
+            AttributeAction::Set(value),
+        ))
+        .context(ApplySnafu {
+            selector: self.selector.clone(),
+        })
+    }
+}
+
+/// Parse a root attribute assignment, as in `--set`.
+pub fn parse_root_assignment(s: &str) -> Result<AttributeAssignment, ParseAssignmentError> {
+    let assignment: AttributeAssignment = s.parse()?;
+    ensure!(
+        assignment.is_root(),
+        NotRootAttributeSnafu {
+            key: assignment.selector.to_string()
+        }
+    );
+    Ok(assignment)
+}
+
+/// Parse a nested attribute assignment, as in `--set-seq`.
+fn parse_nested_assignment(s: &str) -> Result<AttributeAssignment, ParseAssignmentError> {
+    let assignment: AttributeAssignment = s.parse()?;
+    ensure!(
+        !assignment.is_root(),
+        NotNestedAttributeSnafu {
+            key: assignment.selector.to_string()
+        }
+    );
+    Ok(assignment)
+}
+
+/// Command line options for setting attributes in a DICOM object
+#[derive(Args, Debug, Default, Clone)]
+pub struct SetOptions {
+    /// Set a root attribute, by tag or keyword (e.g. `PatientName=DOE^JANE`, `(0010,0020)=12345`)
+    #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_root_assignment)]
+    pub set: Vec<AttributeAssignment>,
+
+    /// Set an attribute in a sequence (e.g. `ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3`)
+    #[arg(long = "set-seq", value_name = "PATH=VALUE", value_parser = parse_nested_assignment)]
+    pub set_seq: Vec<AttributeAssignment>,
+}
+
+impl SetOptions {
+    /// Whether no assignments were requested.
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.set_seq.is_empty()
+    }
+
+    /// Iterate over all assignments,
+    /// root attributes first.
+    pub fn assignments(&self) -> impl Iterator<Item = &AttributeAssignment> {
+        self.set.iter().chain(&self.set_seq)
+    }
+
+    /// Apply all assignments to the given object, in order.
+    pub fn apply(&self, obj: &mut InMemDicomObject) -> Result<(), ApplyAssignmentError> {
+        for assignment in self.assignments() {
+            assignment.apply(obj)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_all<T: FromStr>(
+    selector: &AttributeSelector,
+    vr: VR,
+    text: &str,
+) -> Result<SmallVec<[T; 2]>, ApplyAssignmentError> {
+    text.split('\\')
+        .map(|part| {
+            part.trim().parse().ok().context(ParseValueSnafu {
+                selector: selector.clone(),
+                vr,
+                value: part,
+            })
+        })
+        .collect()
+}
+
+fn text_to_value(
+    selector: &AttributeSelector,
+    vr: VR,
+    text: &str,
+) -> Result<PrimitiveValue, ApplyAssignmentError> {
+    if text.is_empty() {
+        return Ok(PrimitiveValue::Empty);
+    }
+
+    let value = match vr {
+        VR::AE
+        | VR::AS
+        | VR::CS
+        | VR::DA
+        | VR::DS
+        | VR::DT
+        | VR::IS
+        | VR::LO
+        | VR::PN
+        | VR::SH
+        | VR::TM
+        | VR::UC
+        | VR::UI => PrimitiveValue::Strs(text.split('\\').map(String::from).collect()),
+        // these cannot hold multiple values
+        VR::LT | VR::ST | VR::UR | VR::UT => PrimitiveValue::from(text),
+        VR::AT => {
+            let tags: Result<SmallVec<[Tag; 2]>, _> = text
+                .split('\\')
+                .map(|part| {
+                    StandardDataDictionary
+                        .parse_tag(part.trim())
+                        .context(ParseValueSnafu {
+                            selector: selector.clone(),
+                            vr,
+                            value: part,
+                        })
+                })
+                .collect();
+            PrimitiveValue::Tags(tags?)
+        }
+        VR::SS => PrimitiveValue::I16(parse_all(selector, vr, text)?),
+        VR::SL => PrimitiveValue::I32(parse_all(selector, vr, text)?),
+        VR::SV => PrimitiveValue::I64(parse_all(selector, vr, text)?),
+        VR::US => PrimitiveValue::U16(parse_all(selector, vr, text)?),
+        VR::UL => PrimitiveValue::U32(parse_all(selector, vr, text)?),
+        VR::UV => PrimitiveValue::U64(parse_all(selector, vr, text)?),
+        VR::FL => PrimitiveValue::F32(parse_all(selector, vr, text)?),
+        VR::FD => PrimitiveValue::F64(parse_all(selector, vr, text)?),
+        VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::SQ | VR::UN => {
+            return UnsupportedVrSnafu {
+                selector: selector.clone(),
+                vr,
+            }
+            .fail();
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::DataElement;
+    use dicom_dictionary_std::tags;
+
+    #[test]
+    fn parse_root_and_nested_assignments() {
+        let a = parse_root_assignment("PatientName=DOE^JANE").unwrap();
+        assert_eq!(a.selector, AttributeSelector::from(tags::PATIENT_NAME));
+        assert_eq!(a.value, "DOE^JANE");
+
+        let a = parse_root_assignment("(0010,0020)=12345").unwrap();
+        assert_eq!(a.selector, AttributeSelector::from(tags::PATIENT_ID));
+        assert_eq!(a.value, "12345");
+
+        // values may contain `=`
+        let a = parse_root_assignment("StudyDescription=a=b").unwrap();
+        assert_eq!(a.value, "a=b");
+
+        let a =
+            parse_nested_assignment("ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3")
+                .unwrap();
+        assert_eq!(
+            a.selector,
+            AttributeSelector::from((
+                tags::REFERENCED_STUDY_SEQUENCE,
+                0,
+                tags::REFERENCED_SOP_INSTANCE_UID
+            ))
+        );
+
+        assert!(matches!(
+            parse_root_assignment("PatientName"),
+            Err(ParseAssignmentError::MissingValue)
+        ));
+        assert!(matches!(
+            parse_root_assignment("NotAKeyword=1"),
+            Err(ParseAssignmentError::ParseSelector { .. })
+        ));
+        assert!(matches!(
+            parse_root_assignment("ReferencedStudySequence[0].ReferencedSOPInstanceUID=1"),
+            Err(ParseAssignmentError::NotRootAttribute { .. })
+        ));
+        assert!(matches!(
+            parse_nested_assignment("PatientName=DOE^JANE"),
+            Err(ParseAssignmentError::NotNestedAttribute { .. })
+        ));
+    }
+
+    #[test]
+    fn apply_set_options() {
+        let options = SetOptions {
+            set: vec![
+                parse_root_assignment("PatientName=DOE^JANE").unwrap(),
+                parse_root_assignment("(0010,0020)=12345").unwrap(),
+                parse_root_assignment("Rows=512").unwrap(),
+                parse_root_assignment("ImageType=ORIGINAL\\PRIMARY").unwrap(),
+            ],
+            set_seq: vec![
+                parse_nested_assignment(
+                    "ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3.4",
+                )
+                .unwrap(),
+                parse_nested_assignment("ReferencedStudySequence[1].ReferencedSOPInstanceUID=5.6")
+                    .unwrap(),
+            ],
+        };
+
+        let mut obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            "Doe^John",
+        )]);
+        options.apply(&mut obj).unwrap();
+
+        assert_eq!(
+            obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "DOE^JANE"
+        );
+        assert_eq!(
+            obj.get(tags::PATIENT_ID).unwrap().to_str().unwrap(),
+            "12345"
+        );
+        assert_eq!(
+            obj.get(tags::ROWS).unwrap().value().primitive(),
+            Some(&PrimitiveValue::from(512_u16))
+        );
+        assert_eq!(
+            obj.get(tags::IMAGE_TYPE)
+                .unwrap()
+                .to_multi_str()
+                .unwrap()
+                .as_ref(),
+            &["ORIGINAL".to_string(), "PRIMARY".to_string()]
+        );
+
+        let items = obj
+            .get(tags::REFERENCED_STUDY_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1]
+                .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "5.6"
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let mut obj = InMemDicomObject::new_empty();
+        let a = parse_root_assignment("Rows=many").unwrap();
+        assert!(matches!(
+            a.apply(&mut obj),
+            Err(ApplyAssignmentError::ParseValue { vr: VR::US, .. })
+        ));
+
+        // items cannot be skipped
+        let a = parse_nested_assignment("ReferencedStudySequence[2].ReferencedSOPInstanceUID=1")
+            .unwrap();
+        assert!(matches!(
+            a.apply(&mut obj),
+            Err(ApplyAssignmentError::Apply { .. })
+        ));
+    }
+}
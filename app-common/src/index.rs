@@ -0,0 +1,466 @@
+//! Persistent index of DICOM files in an SQLite database.
+//!
+//! The index keeps one row per SOP instance in the `instances` table,
+//! with the path and size of its file, its transfer syntax,
+//! and the attributes in [`index_tags`],
+//! which cover the keys of the patient, study, series and image levels
+//! of the query/retrieve information models.
+//! Attribute values are stored as text,
+//! with multiple values separated by a backslash as in DICOM.
+//! The views `patients`, `studies` and `series`
+//! summarize the instances of each entity at those levels,
+//! with the same columns as the inventories of `dicom-index`.
+//!
+//! `dicom-index` builds the index from directory trees,
+//! `dicom-storescp` adds the files it receives,
+//! and `dicom-qrscp` answers queries and retrievals from it.
+//! The database file may be used by these processes at the same time.
+//!
+//! This module is only available with the `sqlite` feature.
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use rusqlite::{Connection, Row, params_from_iter, types::Value};
+use snafu::prelude::*;
+
+/// The indexed attributes,
+/// with their value representation and the name of their column
+const COLUMNS: &[(Tag, VR, &str)] = &[
+    // patient level
+    (
+        tags::SPECIFIC_CHARACTER_SET,
+        VR::CS,
+        "specific_character_set",
+    ),
+    (tags::PATIENT_NAME, VR::PN, "patient_name"),
+    (tags::PATIENT_ID, VR::LO, "patient_id"),
+    (tags::PATIENT_BIRTH_DATE, VR::DA, "patient_birth_date"),
+    (tags::PATIENT_SEX, VR::CS, "patient_sex"),
+    // study level
+    (tags::STUDY_DATE, VR::DA, "study_date"),
+    (tags::STUDY_TIME, VR::TM, "study_time"),
+    (tags::ACCESSION_NUMBER, VR::SH, "accession_number"),
+    (
+        tags::REFERRING_PHYSICIAN_NAME,
+        VR::PN,
+        "referring_physician_name",
+    ),
+    (tags::STUDY_INSTANCE_UID, VR::UI, "study_instance_uid"),
+    (tags::STUDY_ID, VR::SH, "study_id"),
+    (tags::STUDY_DESCRIPTION, VR::LO, "study_description"),
+    // series level
+    (tags::MODALITY, VR::CS, "modality"),
+    (tags::SERIES_DESCRIPTION, VR::LO, "series_description"),
+    (tags::SERIES_INSTANCE_UID, VR::UI, "series_instance_uid"),
+    (tags::SERIES_NUMBER, VR::IS, "series_number"),
+    (tags::BODY_PART_EXAMINED, VR::CS, "body_part_examined"),
+    // image level
+    (tags::SOP_CLASS_UID, VR::UI, "sop_class_uid"),
+    (tags::SOP_INSTANCE_UID, VR::UI, "sop_instance_uid"),
+    (tags::INSTANCE_NUMBER, VR::IS, "instance_number"),
+    (tags::ROWS, VR::US, "rows"),
+    (tags::COLUMNS, VR::US, "columns"),
+    (tags::NUMBER_OF_FRAMES, VR::IS, "number_of_frames"),
+];
+
+/// The summaries of the instances of each patient, study and series
+const VIEWS: &str = r#"
+CREATE VIEW IF NOT EXISTS patients AS
+SELECT patient_id, min(patient_name) AS patient_name,
+    count(DISTINCT study_instance_uid) AS studies,
+    count(DISTINCT series_instance_uid) AS series,
+    count(*) AS instances, sum(size) AS size,
+    replace(group_concat(DISTINCT modality), ',', '\') AS modalities,
+    replace(group_concat(DISTINCT transfer_syntax), ',', '\') AS transfer_syntaxes
+FROM instances GROUP BY patient_id;
+CREATE VIEW IF NOT EXISTS studies AS
+SELECT min(patient_id) AS patient_id, min(patient_name) AS patient_name,
+    study_instance_uid, min(study_date) AS study_date,
+    min(study_description) AS study_description,
+    count(DISTINCT series_instance_uid) AS series,
+    count(*) AS instances, sum(size) AS size,
+    replace(group_concat(DISTINCT modality), ',', '\') AS modalities,
+    replace(group_concat(DISTINCT transfer_syntax), ',', '\') AS transfer_syntaxes
+FROM instances GROUP BY study_instance_uid;
+CREATE VIEW IF NOT EXISTS series AS
+SELECT min(patient_id) AS patient_id, min(patient_name) AS patient_name,
+    min(study_instance_uid) AS study_instance_uid, series_instance_uid,
+    min(series_number) AS series_number, min(modality) AS modality,
+    min(series_description) AS series_description,
+    count(*) AS instances, sum(size) AS size,
+    replace(group_concat(DISTINCT transfer_syntax), ',', '\') AS transfer_syntaxes
+FROM instances GROUP BY series_instance_uid;
+"#;
+
+/// How long to wait for other processes writing to the index
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The attributes kept in the index for each instance
+pub fn index_tags() -> impl Iterator<Item = Tag> {
+    COLUMNS.iter().map(|(tag, _, _)| *tag)
+}
+
+/// An error which may occur when using an index
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum IndexError {
+    #[snafu(display("could not open index {}", path.display()))]
+    Open {
+        source: rusqlite::Error,
+        path: PathBuf,
+    },
+    /// could not access the index
+    Database { source: rusqlite::Error },
+    /// missing SOP Instance UID
+    MissingSopInstanceUid,
+    #[snafu(display("path {} is not valid UTF-8", path.display()))]
+    NonUtf8Path { path: PathBuf },
+}
+
+/// A DICOM file recorded in the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedInstance {
+    /// the path to the DICOM file
+    pub path: PathBuf,
+    /// the size of the file in bytes
+    pub size: u64,
+    /// the transfer syntax of the file
+    pub transfer_syntax: String,
+    /// the indexed attributes of all levels
+    pub attributes: InMemDicomObject,
+}
+
+impl IndexedInstance {
+    /// Describe a DICOM file for the index,
+    /// keeping only the indexed attributes of the given data set.
+    ///
+    /// Fails if the data set has no SOP Instance UID.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        size: u64,
+        transfer_syntax: &str,
+        attributes: &InMemDicomObject,
+    ) -> Result<Self, IndexError> {
+        let attributes = InMemDicomObject::from_element_iter(
+            index_tags().filter_map(|tag| attributes.get(tag).cloned()),
+        );
+        ensure!(
+            text_of(&attributes, tags::SOP_INSTANCE_UID).is_some_and(|uid| !uid.is_empty()),
+            MissingSopInstanceUidSnafu
+        );
+        Ok(IndexedInstance {
+            path: path.into(),
+            size,
+            transfer_syntax: transfer_syntax.trim_end_matches(['\0', ' ']).to_string(),
+            attributes,
+        })
+    }
+
+    /// The value of an indexed attribute as text,
+    /// or an empty string if the attribute is missing
+    pub fn value(&self, tag: Tag) -> String {
+        text_of(&self.attributes, tag).unwrap_or_default()
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let mut attributes = InMemDicomObject::new_empty();
+        for (i, (tag, vr, _)) in COLUMNS.iter().enumerate() {
+            let Some(text) = row.get::<_, Option<String>>(i)? else {
+                continue;
+            };
+            let value = match vr {
+                VR::US => PrimitiveValue::U16(
+                    text.split('\\')
+                        .filter_map(|v| v.trim().parse().ok())
+                        .collect(),
+                ),
+                _ if text.contains('\\') => {
+                    PrimitiveValue::Strs(text.split('\\').map(str::to_string).collect())
+                }
+                _ => PrimitiveValue::Str(text),
+            };
+            attributes.put(DataElement::new(*tag, *vr, value));
+        }
+        let n = COLUMNS.len();
+        Ok(IndexedInstance {
+            transfer_syntax: row.get(n)?,
+            size: row.get::<_, i64>(n + 1)? as u64,
+            path: PathBuf::from(row.get::<_, String>(n + 2)?),
+            attributes,
+        })
+    }
+}
+
+/// The value of an attribute as text, without padding
+fn text_of(obj: &InMemDicomObject, tag: Tag) -> Option<String> {
+    obj.get(tag)
+        .and_then(|e| e.to_str().ok())
+        .map(|v| v.trim_end_matches(['\0', ' ']).to_string())
+}
+
+/// A persistent index of DICOM files.
+///
+/// See the [module-level documentation](self) for its contents.
+#[derive(Debug)]
+pub struct Index {
+    conn: Mutex<Connection>,
+}
+
+impl Index {
+    /// Open the index in the given database file,
+    /// creating it if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IndexError> {
+        let path = path.as_ref();
+        Connection::open(path)
+            .and_then(Self::from_connection)
+            .context(OpenSnafu { path })
+    }
+
+    /// Create an index in memory,
+    /// which is lost when dropped.
+    pub fn open_in_memory() -> Result<Self, IndexError> {
+        Connection::open_in_memory()
+            .and_then(Self::from_connection)
+            .context(DatabaseSnafu)
+    }
+
+    fn from_connection(conn: Connection) -> rusqlite::Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // let readers work while another process writes
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        let columns: Vec<_> = COLUMNS
+            .iter()
+            .map(|(tag, _, name)| {
+                if *tag == tags::SOP_INSTANCE_UID {
+                    format!("\"{name}\" TEXT PRIMARY KEY NOT NULL")
+                } else {
+                    format!("\"{name}\" TEXT")
+                }
+            })
+            .collect();
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS instances ({}, \
+             transfer_syntax TEXT NOT NULL, size INTEGER NOT NULL, path TEXT NOT NULL);
+             CREATE INDEX IF NOT EXISTS instances_patient ON instances (patient_id);
+             CREATE INDEX IF NOT EXISTS instances_study ON instances (study_instance_uid);
+             CREATE INDEX IF NOT EXISTS instances_series ON instances (series_instance_uid);
+             {VIEWS}",
+            columns.join(", ")
+        ))?;
+        Ok(Index {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record the given instances in a single transaction,
+    /// replacing those previously indexed with the same SOP Instance UID.
+    ///
+    /// Returns the number of instances recorded.
+    pub fn insert<'a>(
+        &self,
+        instances: impl IntoIterator<Item = &'a IndexedInstance>,
+    ) -> Result<usize, IndexError> {
+        let names: Vec<_> = COLUMNS
+            .iter()
+            .map(|(_, _, name)| format!("\"{name}\""))
+            .collect();
+        let sql = format!(
+            "INSERT OR REPLACE INTO instances ({}, transfer_syntax, size, path) VALUES ({})",
+            names.join(", "),
+            vec!["?"; COLUMNS.len() + 3].join(", ")
+        );
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().context(DatabaseSnafu)?;
+        let mut count = 0;
+        {
+            let mut statement = tx.prepare_cached(&sql).context(DatabaseSnafu)?;
+            for instance in instances {
+                let path = instance.path.to_str().context(NonUtf8PathSnafu {
+                    path: &instance.path,
+                })?;
+                let values = COLUMNS
+                    .iter()
+                    .map(|(tag, _, _)| match text_of(&instance.attributes, *tag) {
+                        Some(text) => Value::Text(text),
+                        None => Value::Null,
+                    })
+                    .chain([
+                        Value::Text(instance.transfer_syntax.clone()),
+                        Value::Integer(instance.size as i64),
+                        Value::Text(path.to_string()),
+                    ]);
+                statement
+                    .execute(params_from_iter(values))
+                    .context(DatabaseSnafu)?;
+                count += 1;
+            }
+        }
+        tx.commit().context(DatabaseSnafu)?;
+        Ok(count)
+    }
+
+    /// The number of indexed instances.
+    pub fn len(&self) -> Result<u64, IndexError> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT count(*) FROM instances", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map(|count| count as u64)
+        .context(DatabaseSnafu)
+    }
+
+    /// Whether the index has no instances.
+    pub fn is_empty(&self) -> Result<bool, IndexError> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// The indexed instances with any of the given values
+    /// in each of the given attributes,
+    /// in the order in which they were recorded.
+    ///
+    /// Values must match exactly,
+    /// and keys on attributes which are not indexed are ignored.
+    /// All instances are returned if no keys are given.
+    pub fn instances(
+        &self,
+        keys: &[(Tag, Vec<String>)],
+    ) -> Result<Vec<IndexedInstance>, IndexError> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        for (tag, values) in keys {
+            let Some((_, _, name)) = COLUMNS.iter().find(|(t, _, _)| t == tag) else {
+                continue;
+            };
+            conditions.push(format!(
+                "\"{name}\" IN ({})",
+                vec!["?"; values.len()].join(", ")
+            ));
+            params.extend(values.iter().cloned());
+        }
+        let names: Vec<_> = COLUMNS
+            .iter()
+            .map(|(_, _, name)| format!("\"{name}\""))
+            .collect();
+        let mut sql = format!(
+            "SELECT {}, transfer_syntax, size, path FROM instances",
+            names.join(", ")
+        );
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY rowid");
+
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare_cached(&sql).context(DatabaseSnafu)?;
+        statement
+            .query_map(params_from_iter(params), IndexedInstance::from_row)
+            .and_then(|rows| rows.collect())
+            .context(DatabaseSnafu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Index, IndexError, IndexedInstance};
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::InMemDicomObject;
+
+    fn instance(series: &str, sop_instance_uid: &str, modality: &str) -> IndexedInstance {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+            DataElement::new(tags::MODALITY, VR::CS, modality),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, series),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(512_u16)),
+            DataElement::new(tags::IMAGE_TYPE, VR::CS, "ORIGINAL\\PRIMARY"),
+        ]);
+        IndexedInstance::new(
+            format!("/archive/{sop_instance_uid}.dcm"),
+            1000,
+            "1.2.840.10008.1.2.1\0",
+            &obj,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn insert_and_query_instances() {
+        let index = Index::open_in_memory().unwrap();
+        assert!(index.is_empty().unwrap());
+
+        let instances = [
+            instance("2.25.10", "2.25.100", "CT"),
+            instance("2.25.10", "2.25.101", "CT"),
+            instance("2.25.20", "2.25.200", "SR"),
+        ];
+        // attributes which are not indexed are left out
+        assert!(instances[0].attributes.get(tags::IMAGE_TYPE).is_none());
+        assert_eq!(index.insert(&instances).unwrap(), 3);
+        // instances are replaced by SOP Instance UID
+        assert_eq!(index.insert(&instances[2..]).unwrap(), 1);
+        assert_eq!(index.len().unwrap(), 3);
+
+        let all = index.instances(&[]).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], instances[0]);
+        assert_eq!(all[0].transfer_syntax, "1.2.840.10008.1.2.1");
+        assert_eq!(
+            all[0].attributes.get(tags::ROWS).unwrap().uint16().unwrap(),
+            512
+        );
+        assert_eq!(all[2].value(tags::MODALITY), "SR");
+
+        let series = index
+            .instances(&[(
+                tags::SERIES_INSTANCE_UID,
+                vec!["2.25.10".to_string(), "2.25.30".to_string()],
+            )])
+            .unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[1].value(tags::SOP_INSTANCE_UID), "2.25.101");
+    }
+
+    #[test]
+    fn summarize_instances_in_views() {
+        let index = Index::open_in_memory().unwrap();
+        index
+            .insert(&[
+                instance("2.25.10", "2.25.100", "CT"),
+                instance("2.25.10", "2.25.101", "CT"),
+                instance("2.25.20", "2.25.200", "SR"),
+            ])
+            .unwrap();
+        let conn = index.conn.lock().unwrap();
+        let (series, instances, size, modalities): (i64, i64, i64, String) = conn
+            .query_row(
+                "SELECT series, instances, size, modalities FROM studies",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((series, instances, size), (2, 3, 3000));
+        assert_eq!(modalities, "CT\\SR");
+    }
+
+    #[test]
+    fn instance_without_sop_instance_uid() {
+        let obj =
+            InMemDicomObject::from_element_iter([DataElement::new(tags::MODALITY, VR::CS, "CT")]);
+        assert!(matches!(
+            IndexedInstance::new("1.dcm", 0, uids::EXPLICIT_VR_LITTLE_ENDIAN, &obj),
+            Err(IndexError::MissingSopInstanceUid)
+        ));
+    }
+}
@@ -1,3 +1,10 @@
+pub mod aeconfig;
+pub mod bandwidth;
+pub mod edit;
+#[cfg(feature = "sqlite")]
+pub mod index;
+pub mod path_template;
+
 use clap::Args;
 #[cfg(feature = "tls")]
 use rustls::{
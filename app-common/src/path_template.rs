@@ -0,0 +1,220 @@
+//! Output file paths from DICOM attributes.
+//!
+//! A [`PathTemplate`] is a relative file path
+//! with attribute placeholders in braces,
+//! such as `{PatientID}/{StudyInstanceUID}/{SOPInstanceUID}.dcm`.
+//! Each placeholder holds a keyword (e.g. `PatientID`)
+//! or a tag (e.g. `(0010,0020)` or `00100020`)
+//! and is replaced by the value of that attribute in the root data set.
+//!
+//! Values are made safe for use as file names:
+//! characters other than ASCII letters, digits, `.`, `-`, `_` and `^`
+//! are replaced by `_`,
+//! and missing or empty values become `UNKNOWN`.
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use dicom_core::{DataDictionary, Tag};
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::InMemDicomObject;
+use snafu::prelude::*;
+
+/// An error which may occur when parsing a path template
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ParsePathTemplateError {
+    /// empty path template
+    Empty,
+
+    #[snafu(display("unclosed placeholder at position {position}"))]
+    UnclosedPlaceholder { position: usize },
+
+    #[snafu(display("unexpected `}}` at position {position}"))]
+    UnexpectedBrace { position: usize },
+
+    #[snafu(display("unknown attribute `{key}` in placeholder"))]
+    UnknownAttribute { key: String },
+}
+
+/// A part of a path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Attribute(Tag),
+}
+
+/// A template for building file paths from the attributes of DICOM objects.
+///
+/// See the [module-level documentation](self) for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+impl PathTemplate {
+    /// The attributes referred to by the template, in order of appearance
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + '_ {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Attribute(tag) => Some(*tag),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Build the file path for the given DICOM object,
+    /// relative to the output directory.
+    pub fn render<D>(&self, obj: &InMemDicomObject<D>) -> PathBuf
+    where
+        D: DataDictionary + Clone,
+    {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Attribute(tag) => {
+                    let value = obj
+                        .get(*tag)
+                        .and_then(|e| e.to_str().ok())
+                        .map(|v| v.trim_matches(['\0', ' ']).to_string())
+                        .unwrap_or_default();
+                    out.push_str(&sanitize(&value));
+                }
+            }
+        }
+        PathBuf::from(out)
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = ParsePathTemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ensure!(!s.is_empty(), EmptySnafu);
+
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.char_indices();
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' => {
+                    let start = position + 1;
+                    let end = s[start..]
+                        .find('}')
+                        .map(|i| start + i)
+                        .context(UnclosedPlaceholderSnafu { position })?;
+                    let key = s[start..end].trim();
+                    let tag = StandardDataDictionary
+                        .parse_tag(key)
+                        .context(UnknownAttributeSnafu { key })?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Attribute(tag));
+                    // skip to the closing brace
+                    for (i, _) in chars.by_ref() {
+                        if i == end {
+                            break;
+                        }
+                    }
+                }
+                '}' => return UnexpectedBraceSnafu { position }.fail(),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(PathTemplate {
+            source: s.to_string(),
+            parts,
+        })
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Turn an attribute value into a safe file name component.
+fn sanitize(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | '^') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match value.as_str() {
+        "" => "UNKNOWN".to_string(),
+        "." | ".." => value.replace('.', "_"),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParsePathTemplateError, PathTemplate};
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
+    use std::path::PathBuf;
+
+    #[test]
+    fn render_path_template() {
+        let template: PathTemplate = "{PatientID}/{(0020,000D)}/{Modality}_{SOPInstanceUID}.dcm"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            template.tags().collect::<Vec<_>>(),
+            [
+                tags::PATIENT_ID,
+                tags::STUDY_INSTANCE_UID,
+                tags::MODALITY,
+                tags::SOP_INSTANCE_UID
+            ]
+        );
+
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_ID, VR::LO, "A/B 12"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1\0"),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.2"),
+        ]);
+        assert_eq!(
+            template.render(&obj),
+            PathBuf::from("A_B_12/2.25.1/UNKNOWN_2.25.2.dcm")
+        );
+
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::SOP_INSTANCE_UID,
+            VR::UI,
+            "..",
+        )]);
+        let template: PathTemplate = "{SOPInstanceUID}/x".parse().unwrap();
+        assert_eq!(template.render(&obj), PathBuf::from("__/x"));
+    }
+
+    #[test]
+    fn parse_bad_path_templates() {
+        assert!(matches!(
+            "".parse::<PathTemplate>(),
+            Err(ParsePathTemplateError::Empty)
+        ));
+        assert!(matches!(
+            "{PatientID".parse::<PathTemplate>(),
+            Err(ParsePathTemplateError::UnclosedPlaceholder { position: 0 })
+        ));
+        assert!(matches!(
+            "a}".parse::<PathTemplate>(),
+            Err(ParsePathTemplateError::UnexpectedBrace { position: 1 })
+        ));
+        assert!(matches!(
+            "{NotAnAttribute}.dcm".parse::<PathTemplate>(),
+            Err(ParsePathTemplateError::UnknownAttribute { .. })
+        ));
+    }
+}
@@ -20,6 +20,7 @@ use crate::{
 /// Moreover, a unique variant is defined for group length tags
 /// and another one for private creator tags.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum TagRange {
     /// Only a specific tag
     Single(Tag),
@@ -29,6 +30,18 @@ pub enum TagRange {
     /// The two rightmost digits of the _element_ portion are open:
     /// `(GGGG,EExx)`
     Element100(Tag),
+    /// Any other combination of open hexadecimal digits,
+    /// such as `(1000,xxx0)` for _Escape Triplet_
+    /// or `(1010,xxxx)` for _Zonal Map_.
+    ///
+    /// The open digits are zeroed out in `tag`,
+    /// whereas `mask` has all bits of the fixed digits set.
+    Masked {
+        /// the tag with the open digits zeroed out
+        tag: Tag,
+        /// the bit mask of the fixed digits
+        mask: Tag,
+    },
     /// Generic group length tag,
     /// refers to any attribute of the form `(GGGG,0000)`,
     /// _save for the following exceptions_
@@ -57,10 +70,66 @@ impl TagRange {
             TagRange::Single(tag) => tag,
             TagRange::Group100(tag) => tag,
             TagRange::Element100(tag) => tag,
+            TagRange::Masked { tag, .. } => tag,
             TagRange::GroupLength => Tag(0x0000, 0x0000),
             TagRange::PrivateCreator => Tag(0x0009, 0x0010),
         }
     }
+
+    /// Check whether the given tag belongs to this range.
+    ///
+    /// ```
+    /// # use dicom_core::Tag;
+    /// # use dicom_core::dictionary::TagRange;
+    /// let range: TagRange = "(1000,xxx1)".parse()?;
+    /// assert!(range.contains(Tag(0x1000, 0x0011)));
+    /// assert!(range.contains(Tag(0x1000, 0xFFF1)));
+    /// assert!(!range.contains(Tag(0x1000, 0x0012)));
+    /// # Ok::<(), dicom_core::dictionary::TagRangeParseError>(())
+    /// ```
+    pub fn contains(self, tag: Tag) -> bool {
+        match self {
+            TagRange::Single(t) => t == tag,
+            TagRange::Group100(t) => t == Tag(tag.0 & 0xFF00, tag.1),
+            TagRange::Element100(t) => t == Tag(tag.0, tag.1 & 0xFF00),
+            TagRange::Masked { tag: t, mask } => t == Tag(tag.0 & mask.0, tag.1 & mask.1),
+            TagRange::GroupLength => tag.1 == 0x0000,
+            TagRange::PrivateCreator => tag.0 & 1 == 1 && (0x0010..=0x00FF).contains(&tag.1),
+        }
+    }
+}
+
+impl std::fmt::Display for TagRange {
+    /// Writes the tag range in the form used by the standard,
+    /// such as `(60xx,3000)`.
+    /// Group length and private creator tags are written as
+    /// `(gggg,0000)` and `(gggg,0010-00FF)`, respectively.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (tag, mask) = match *self {
+            TagRange::Single(tag) => (tag, Tag(0xFFFF, 0xFFFF)),
+            TagRange::Group100(tag) => (tag, Tag(0xFF00, 0xFFFF)),
+            TagRange::Element100(tag) => (tag, Tag(0xFFFF, 0xFF00)),
+            TagRange::Masked { tag, mask } => (tag, mask),
+            TagRange::GroupLength => return f.write_str("(gggg,0000)"),
+            TagRange::PrivateCreator => return f.write_str("(gggg,0010-00FF)"),
+        };
+        let digits = |value: u16, mask: u16| -> String {
+            (0..4)
+                .rev()
+                .map(|i| {
+                    let shift = i * 4;
+                    if (mask >> shift) & 0xF == 0 {
+                        'x'
+                    } else {
+                        char::from_digit(u32::from((value >> shift) & 0xF), 16)
+                            .unwrap_or('0')
+                            .to_ascii_uppercase()
+                    }
+                })
+                .collect()
+        };
+        write!(f, "({},{})", digits(tag.0, mask.0), digits(tag.1, mask.1))
+    }
 }
 
 /// An error returned when parsing an invalid tag range.
@@ -114,28 +183,29 @@ impl FromStr for TagRange {
             InvalidElementLengthSnafu { got: elem.len() }
         );
 
-        match (&group.as_bytes()[2..], &elem.as_bytes()[2..]) {
-            (b"xx", b"xx") => UnsupportedTagRangeSnafu.fail(),
-            (b"xx", _) => {
-                // Group100
-                let group =
-                    u16::from_str_radix(&group[..2], 16).context(InvalidTagGroupSnafu)? << 8;
-                let elem = u16::from_str_radix(elem, 16).context(InvalidTagElementSnafu)?;
-                Ok(TagRange::Group100(Tag(group, elem)))
-            }
-            (_, b"xx") => {
-                // Element100
-                let group = u16::from_str_radix(group, 16).context(InvalidTagGroupSnafu)?;
-                let elem =
-                    u16::from_str_radix(&elem[..2], 16).context(InvalidTagElementSnafu)? << 8;
-                Ok(TagRange::Element100(Tag(group, elem)))
-            }
-            (_, _) => {
-                // single element
-                let group = u16::from_str_radix(group, 16).context(InvalidTagGroupSnafu)?;
-                let elem = u16::from_str_radix(elem, 16).context(InvalidTagElementSnafu)?;
-                Ok(TagRange::Single(Tag(group, elem)))
-            }
+        // open digits are zeroed out in both the value and the mask
+        let open = |part: &str| -> (String, u16) {
+            let value = part.replace('x', "0");
+            let mask = part
+                .chars()
+                .fold(0, |mask, c| (mask << 4) | if c == 'x' { 0 } else { 0xF });
+            (value, mask)
+        };
+        let (group, group_mask) = open(group);
+        let (elem, elem_mask) = open(elem);
+        let group = u16::from_str_radix(&group, 16).context(InvalidTagGroupSnafu)?;
+        let elem = u16::from_str_radix(&elem, 16).context(InvalidTagElementSnafu)?;
+        let tag = Tag(group, elem);
+
+        match (group_mask, elem_mask) {
+            (0, 0) => UnsupportedTagRangeSnafu.fail(),
+            (0xFFFF, 0xFFFF) => Ok(TagRange::Single(tag)),
+            (0xFF00, 0xFFFF) => Ok(TagRange::Group100(tag)),
+            (0xFFFF, 0xFF00) => Ok(TagRange::Element100(tag)),
+            (group_mask, elem_mask) => Ok(TagRange::Masked {
+                tag,
+                mask: Tag(group_mask, elem_mask),
+            }),
         }
     }
 }
@@ -216,6 +286,155 @@ impl VirtualVr {
     }
 }
 
+/// A value multiplicity (VM) specification,
+/// such as `1`, `1-3`, `2-n` or `2-2n`,
+/// describing the number of values that an attribute may contain.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_core::dictionary::Vm;
+/// let vm: Vm = "2-2n".parse()?;
+/// assert_eq!(vm, Vm::multiple_of(2));
+/// assert!(vm.accepts(4));
+/// assert!(!vm.accepts(3));
+/// assert_eq!(vm.to_string(), "2-2n");
+/// # Ok::<(), dicom_core::dictionary::VmParseError>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Vm {
+    /// the minimum number of values
+    pub min: u32,
+    /// the maximum number of values, `None` if unbounded
+    pub max: Option<u32>,
+    /// the number of values must be a multiple of this number
+    pub step: u32,
+}
+
+impl Vm {
+    /// Value multiplicity `1`
+    pub const ONE: Vm = Vm::exactly(1);
+    /// Value multiplicity `1-n`
+    pub const ONE_OR_MORE: Vm = Vm::at_least(1);
+
+    /// Value multiplicity of exactly `n` values.
+    pub const fn exactly(n: u32) -> Self {
+        Vm {
+            min: n,
+            max: Some(n),
+            step: 1,
+        }
+    }
+
+    /// Value multiplicity of `min-n`.
+    pub const fn at_least(min: u32) -> Self {
+        Vm {
+            min,
+            max: None,
+            step: 1,
+        }
+    }
+
+    /// Value multiplicity of `min-max`.
+    pub const fn between(min: u32, max: u32) -> Self {
+        Vm {
+            min,
+            max: Some(max),
+            step: 1,
+        }
+    }
+
+    /// Value multiplicity of `step-{step}n`,
+    /// any non-zero multiple of `step` values.
+    pub const fn multiple_of(step: u32) -> Self {
+        Vm {
+            min: step,
+            max: None,
+            step,
+        }
+    }
+
+    /// Check whether the given number of values
+    /// satisfies this value multiplicity.
+    pub fn accepts(&self, count: u32) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max) && count % self.step == 0
+    }
+}
+
+impl std::fmt::Display for Vm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", self.min),
+            Some(max) => write!(f, "{}-{}", self.min, max),
+            None if self.step > 1 => write!(f, "{}-{}n", self.min, self.step),
+            None => write!(f, "{}-n", self.min),
+        }
+    }
+}
+
+/// An error returned when parsing an invalid value multiplicity.
+#[derive(Debug, Snafu)]
+#[snafu(display("invalid value multiplicity `{}`", text))]
+pub struct VmParseError {
+    text: String,
+    backtrace: Backtrace,
+}
+
+impl FromStr for Vm {
+    type Err = VmParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || VmParseSnafu { text: s }.build();
+        let number = |text: &str| text.parse::<u32>().map_err(|_| invalid());
+        let Some((min, max)) = s.trim().split_once('-') else {
+            return Ok(Vm::exactly(number(s.trim())?));
+        };
+        let min = number(min)?;
+        match max {
+            "n" => Ok(Vm::at_least(min)),
+            max => match max.strip_suffix('n') {
+                Some(step) => {
+                    let step = number(step)?;
+                    ensure!(step > 0 && min % step == 0, VmParseSnafu { text: s });
+                    Ok(Vm {
+                        min,
+                        max: None,
+                        step,
+                    })
+                }
+                None => {
+                    let max = number(max)?;
+                    ensure!(max >= min, VmParseSnafu { text: s });
+                    Ok(Vm::between(min, max))
+                }
+            },
+        }
+    }
+}
+
+/// A violation of the value multiplicity declared for an attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VmViolation {
+    /// the tag of the offending element
+    pub tag: Tag,
+    /// the value multiplicity declared in the dictionary
+    pub expected: Vm,
+    /// the number of values found
+    pub found: u32,
+}
+
+impl std::fmt::Display for VmViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "element {} expected VM {}, found {} values",
+            self.tag, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for VmViolation {}
+
 /// An error during attribute selector parsing
 #[derive(Debug, Snafu)]
 pub struct ParseSelectorError(ParseSelectorErrorInner);
@@ -266,6 +485,16 @@ pub trait DataDictionary {
     /// Fetch a data element entry by its tag.
     fn by_tag(&self, tag: Tag) -> Option<&Self::Entry>;
 
+    /// Fetch the value multiplicity of the attribute with the given tag,
+    /// if known by the dictionary.
+    ///
+    /// The default implementation relies on [`DataDictionaryEntry::vm`].
+    /// Dictionaries with entries which do not hold a value multiplicity
+    /// may keep it aside and override this method.
+    fn vm_by_tag(&self, tag: Tag) -> Option<Vm> {
+        self.by_tag(tag).and_then(|entry| entry.vm())
+    }
+
     /// Fetch an entry by its usual alias
     /// (e.g. "PatientName" or "SOPInstanceUID").
     /// Aliases (or keyword)
@@ -405,6 +634,16 @@ pub trait DataDictionaryEntry {
     /// in which the representation of a value
     /// depends on surrounding context.
     fn vr(&self) -> VirtualVr;
+
+    /// The value multiplicity of the attribute,
+    /// if known by the entry.
+    ///
+    /// Entries of the [`DataDictionaryEntryRef`] and [`DataDictionaryEntryBuf`] types
+    /// do not hold a value multiplicity.
+    /// Use [`DataDictionary::vm_by_tag`] to obtain it from the dictionary instead.
+    fn vm(&self) -> Option<Vm> {
+        None
+    }
 }
 
 /// A data type for a dictionary entry with full ownership.
@@ -487,7 +726,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::TagRange;
+    use super::{TagRange, Vm};
     use crate::header::Tag;
 
     #[test]
@@ -503,5 +742,54 @@ mod tests {
 
         let tag: TagRange = "1234,56xx".parse().unwrap();
         assert_eq!(tag, TagRange::Element100(Tag(0x1234, 0x5600)));
+
+        let tag: TagRange = "(1000,xxx3)".parse().unwrap();
+        assert_eq!(
+            tag,
+            TagRange::Masked {
+                tag: Tag(0x1000, 0x0003),
+                mask: Tag(0xFFFF, 0x000F)
+            }
+        );
+        assert_eq!(tag.to_string(), "(1000,xxx3)");
+        assert!(tag.contains(Tag(0x1000, 0x0A53)));
+        assert!(!tag.contains(Tag(0x1001, 0x0A53)));
+
+        let tag: TagRange = "(1010,xxxx)".parse().unwrap();
+        assert!(tag.contains(Tag(0x1010, 0x1234)));
+        assert_eq!(tag.to_string(), "(1010,xxxx)");
+
+        assert_eq!(
+            "(60xx,3000)".parse::<TagRange>().unwrap().to_string(),
+            "(60xx,3000)"
+        );
+        assert!(TagRange::Group100(Tag(0x6000, 0x3000)).contains(Tag(0x60EE, 0x3000)));
+        assert!(TagRange::PrivateCreator.contains(Tag(0x0009, 0x0010)));
+        assert!("xxxx,xxxx".parse::<TagRange>().is_err());
+        assert!("12x4,567g".parse::<TagRange>().is_err());
+    }
+
+    #[test]
+    fn test_parse_vm() {
+        let vm: Vm = "1".parse().unwrap();
+        assert_eq!(vm, Vm::ONE);
+        let vm: Vm = "1-3".parse().unwrap();
+        assert_eq!(vm, Vm::between(1, 3));
+        assert!(vm.accepts(2) && !vm.accepts(4) && !vm.accepts(0));
+        let vm: Vm = "2-n".parse().unwrap();
+        assert_eq!(vm, Vm::at_least(2));
+        let vm: Vm = "3-3n".parse().unwrap();
+        assert_eq!(vm, Vm::multiple_of(3));
+        assert!(vm.accepts(6) && !vm.accepts(5));
+
+        for vm in ["16", "1-32", "6-n", "2-2n"] {
+            assert_eq!(vm.parse::<Vm>().unwrap().to_string(), vm);
+        }
+
+        assert!("".parse::<Vm>().is_err());
+        assert!("n".parse::<Vm>().is_err());
+        assert!("3-1".parse::<Vm>().is_err());
+        assert!("2-3n".parse::<Vm>().is_err());
+        assert!("1-0n".parse::<Vm>().is_err());
     }
 }
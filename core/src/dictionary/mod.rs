@@ -7,8 +7,9 @@ pub mod stub;
 mod uid;
 
 pub use data_element::{
-    DataDictionary, DataDictionaryEntry, DataDictionaryEntryBuf, DataDictionaryEntryRef, TagByName,
-    TagRange, VirtualVr,
+    DataDictionary, DataDictionaryEntry, DataDictionaryEntryBuf, DataDictionaryEntryRef,
+    ParseSelectorError, TagByName, TagRange, TagRangeParseError, VirtualVr, Vm, VmParseError,
+    VmViolation,
 };
 
 pub use uid::{UidDictionary, UidDictionaryEntry, UidDictionaryEntryRef, UidType};
@@ -121,6 +121,8 @@ pub enum UidType {
     LdapOid,
     /// Synchronization Frame of Reference
     SynchronizationFrameOfReference,
+    /// Well-known Frame of Reference
+    WellKnownFrameOfReference,
 }
 
 impl FromStr for UidType {
@@ -140,6 +142,7 @@ impl FromStr for UidType {
             "Mapping Resource" => Ok(UidType::MappingResource),
             "LDAP OID" => Ok(UidType::LdapOid),
             "Synchronization Frame of Reference" => Ok(UidType::SynchronizationFrameOfReference),
+            "Well-known Frame of Reference" => Ok(UidType::WellKnownFrameOfReference),
             _ => Err(()),
         }
     }
@@ -160,6 +163,7 @@ impl std::fmt::Display for UidType {
             UidType::MappingResource => "Mapping Resource",
             UidType::LdapOid => "LDAP OID",
             UidType::SynchronizationFrameOfReference => "Synchronization Frame of Reference",
+            UidType::WellKnownFrameOfReference => "Well-known Frame of Reference",
         };
         f.write_str(str)
     }
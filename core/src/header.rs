@@ -2,9 +2,10 @@
 //! It comprises a variety of basic data types, such as the DICOM attribute tag, the
 //! element header, and element composite types.
 
+use crate::dictionary::{DataDictionary, VmViolation};
 use crate::value::{
-    C, CastValueError, ConvertValueError, DataSetSequence, DicomDate, DicomDateTime, DicomTime,
-    InMemFragment, PrimitiveValue, Value,
+    AgeString, C, CastValueError, ConvertValueError, DataSetSequence, DecimalString, DicomDate,
+    DicomDateTime, DicomTime, InMemFragment, PersonName, PrimitiveValue, Value,
 };
 use num_traits::NumCast;
 use snafu::{Backtrace, Snafu, ensure};
@@ -230,6 +231,88 @@ impl<I, P> DataElement<I, P> {
         &self.value
     }
 
+    /// Check the number of values in this element
+    /// against the value multiplicity declared in the given dictionary.
+    ///
+    /// Elements unknown to the dictionary,
+    /// empty values, sequences,
+    /// and elements with a value representation
+    /// which cannot hold multiple values (such as `OB` or `UT`)
+    /// always pass this check.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::{DataElement, Tag, VR, dicom_value};
+    /// # use dicom_core::dictionary::{DataDictionary, DataDictionaryEntryRef, TagRange, Vm};
+    /// # struct Dict(DataDictionaryEntryRef<'static>, Vm);
+    /// # impl DataDictionary for Dict {
+    /// #     type Entry = DataDictionaryEntryRef<'static>;
+    /// #     fn by_name(&self, _: &str) -> Option<&Self::Entry> { Some(&self.0) }
+    /// #     fn by_tag(&self, _: Tag) -> Option<&Self::Entry> { Some(&self.0) }
+    /// #     fn vm_by_tag(&self, _: Tag) -> Option<Vm> { Some(self.1) }
+    /// # }
+    /// let dict = Dict(
+    ///     DataDictionaryEntryRef {
+    ///         tag: TagRange::Single(Tag(0x0020, 0x0037)),
+    ///         alias: "ImageOrientationPatient",
+    ///         vr: VR::DS.into(),
+    ///     },
+    ///     Vm::exactly(6),
+    /// );
+    /// let elem: DataElement = DataElement::new(
+    ///     Tag(0x0020, 0x0037),
+    ///     VR::DS,
+    ///     dicom_value!(Strs, ["1", "0", "0", "0", "1"]),
+    /// );
+    /// let violation = elem.validate_vm(&dict).unwrap_err();
+    /// assert_eq!(violation.expected, Vm::exactly(6));
+    /// assert_eq!(violation.found, 5);
+    /// ```
+    pub fn validate_vm<D>(&self, dict: &D) -> Result<(), VmViolation>
+    where
+        D: DataDictionary,
+    {
+        let Value::Primitive(value) = &self.value else {
+            return Ok(());
+        };
+        if value.is_empty()
+            || matches!(
+                self.vr(),
+                VR::OB
+                    | VR::OD
+                    | VR::OF
+                    | VR::OL
+                    | VR::OV
+                    | VR::OW
+                    | VR::UN
+                    | VR::LT
+                    | VR::ST
+                    | VR::UT
+                    | VR::UR
+            )
+        {
+            return Ok(());
+        }
+        let Some(expected) = dict.vm_by_tag(self.tag()) else {
+            return Ok(());
+        };
+        let found = match value {
+            // a single string may still contain multiple values
+            PrimitiveValue::Str(s) => s.split('\\').count() as u32,
+            value => value.multiplicity(),
+        };
+        if expected.accepts(found) {
+            Ok(())
+        } else {
+            Err(VmViolation {
+                tag: self.tag(),
+                expected,
+                found,
+            })
+        }
+    }
+
     /// Move the data value out of the element, discarding the rest. If the
     /// value is a sequence, its lifetime may still be bound to its original
     /// source.
@@ -517,6 +600,61 @@ where
         self.value().to_multi_datetime()
     }
 
+    /// Retrieve and convert the primitive value into an age string.
+    ///
+    /// If the value is a primitive, it will be converted into
+    /// an `AgeString` as described in [`PrimitiveValue::to_age`].
+    ///
+    /// Returns an error if the value is not primitive.
+    ///
+    pub fn to_age(&self) -> Result<AgeString, ConvertValueError> {
+        self.value().to_age()
+    }
+
+    /// Retrieve and convert the primitive value into a decimal string.
+    ///
+    /// If the value is a primitive, it will be converted into
+    /// a `DecimalString` as described in [`PrimitiveValue::to_decimal`].
+    ///
+    /// Returns an error if the value is not primitive.
+    ///
+    pub fn to_decimal(&self) -> Result<DecimalString, ConvertValueError> {
+        self.value().to_decimal()
+    }
+
+    /// Retrieve and convert the primitive value into a sequence of decimal strings.
+    ///
+    /// If the value is a primitive, it will be converted into
+    /// a vector of `DecimalString`s as described in [`PrimitiveValue::to_multi_decimal`].
+    ///
+    /// Returns an error if the value is not primitive.
+    ///
+    pub fn to_multi_decimal(&self) -> Result<Vec<DecimalString>, ConvertValueError> {
+        self.value().to_multi_decimal()
+    }
+
+    /// Retrieve and convert the primitive value into a person name.
+    ///
+    /// If the value is a primitive, it will be converted into
+    /// a `PersonName` as described in [`PrimitiveValue::to_person_name`].
+    ///
+    /// Returns an error if the value is not primitive.
+    ///
+    pub fn to_person_name(&self) -> Result<PersonName<'_>, ConvertValueError> {
+        self.value().to_person_name()
+    }
+
+    /// Retrieve and convert the primitive value into a sequence of person names.
+    ///
+    /// If the value is a primitive, it will be converted into
+    /// a vector of `PersonName` as described in [`PrimitiveValue::to_multi_person_name`].
+    ///
+    /// Returns an error if the value is not primitive.
+    ///
+    pub fn to_multi_person_name(&self) -> Result<Vec<PersonName<'_>>, ConvertValueError> {
+        self.value().to_multi_person_name()
+    }
+
     /// Retrieve the items stored in a sequence value.
     ///
     /// Returns `None` if the underlying value is not a data set sequence.
@@ -23,12 +23,15 @@
 //! - [`value`] holds definitions for values in standard DICOM elements,
 //!   with the awareness of multiplicity, representation,
 //!   and the possible presence of sequences.
+//! - [`uid`] generates new unique identifiers,
+//!   either derived from a UUID or under an organization root.
 //!
 
 pub mod dictionary;
 pub mod header;
 pub mod ops;
 pub mod prelude;
+pub mod uid;
 pub mod value;
 
 pub use dictionary::DataDictionary;
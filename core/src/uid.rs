@@ -0,0 +1,213 @@
+//! Generation of unique identifiers (UIDs).
+//!
+//! New DICOM objects, such as derived images and secondary captures,
+//! need unique identifiers of their own.
+//! By default, UIDs are created under the `2.25` root,
+//! which is reserved for UIDs derived from a UUID
+//! and does not require registering an organization root.
+//! A [`UidGenerator`] can be configured
+//! to create UIDs under an organization root instead.
+//!
+//! # Example
+//!
+//! ```
+//! use dicom_core::uid::{UidGenerator, new_sop_instance_uid};
+//!
+//! let uid = new_sop_instance_uid();
+//! assert!(uid.starts_with("2.25."));
+//!
+//! let generator = UidGenerator::with_root("1.2.826.0.1.3680043.10.1234")?;
+//! let uid = generator.generate();
+//! assert!(uid.starts_with("1.2.826.0.1.3680043.10.1234."));
+//! assert!(uid.len() <= 64);
+//! # Ok::<(), dicom_core::uid::Error>(())
+//! ```
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use snafu::{Backtrace, Snafu, ensure};
+
+/// The root of UIDs derived from a UUID.
+pub const UUID_ROOT: &str = "2.25";
+
+/// The maximum length of a UID.
+pub const MAX_UID_LENGTH: usize = 64;
+
+/// The number of characters reserved after an organization root,
+/// for the timestamp and random components of each UID
+const SUFFIX_LENGTH: usize = 24;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Invalid UID root '{}'", root))]
+    InvalidRoot { root: String, backtrace: Backtrace },
+    #[snafu(display(
+        "UID root '{}' is too long, at most {} characters are allowed",
+        root,
+        MAX_UID_LENGTH - SUFFIX_LENGTH - 1
+    ))]
+    RootTooLong { root: String, backtrace: Backtrace },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Check whether the given string is a valid UID:
+/// at most 64 characters long,
+/// made of numeric components separated by periods,
+/// none of which is empty or has a leading zero.
+pub fn is_valid_uid(uid: &str) -> bool {
+    uid.len() <= MAX_UID_LENGTH
+        && uid.split('.').all(|component| {
+            !component.is_empty()
+                && component.bytes().all(|b| b.is_ascii_digit())
+                && (component == "0" || !component.starts_with('0'))
+        })
+}
+
+/// A generator of unique identifiers under a given root.
+///
+/// Under the default `2.25` root,
+/// each UID is derived from 122 random bits,
+/// in the spirit of a version 4 UUID.
+/// Under any other root,
+/// each UID is made of the root,
+/// the current time in microseconds,
+/// and as many random digits as fit in 64 characters.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UidGenerator {
+    root: String,
+}
+
+impl Default for UidGenerator {
+    fn default() -> Self {
+        UidGenerator {
+            root: UUID_ROOT.to_string(),
+        }
+    }
+}
+
+impl UidGenerator {
+    /// Create a generator of UIDs under the `2.25` root.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a generator of UIDs under the given organization root.
+    ///
+    /// The root must be a valid UID,
+    /// short enough to leave room for the generated components.
+    pub fn with_root(root: impl Into<String>) -> Result<Self> {
+        let root = root.into();
+        let root = root.trim_end_matches(['.', '\0', ' ']).to_string();
+        ensure!(is_valid_uid(&root), InvalidRootSnafu { root });
+        ensure!(
+            root == UUID_ROOT || root.len() + 1 + SUFFIX_LENGTH <= MAX_UID_LENGTH,
+            RootTooLongSnafu { root }
+        );
+        Ok(UidGenerator { root })
+    }
+
+    /// The root of the generated UIDs.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Generate a new UID.
+    pub fn generate(&self) -> String {
+        let random = random_u128();
+        if self.root == UUID_ROOT {
+            return format!("{UUID_ROOT}.{}", random >> 6);
+        }
+
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+        let mut uid = format!("{}.{micros}.", self.root);
+        // a decimal number other than 0 never starts with a zero
+        let digits = (random | 1).to_string();
+        let len = (MAX_UID_LENGTH - uid.len()).min(digits.len());
+        uid.push_str(&digits[..len]);
+        uid
+    }
+}
+
+/// Generate a new UID under the `2.25` root.
+pub fn new_uid() -> String {
+    UidGenerator::default().generate()
+}
+
+/// Generate a new SOP instance UID under the `2.25` root.
+pub fn new_sop_instance_uid() -> String {
+    new_uid()
+}
+
+/// Generate a new series instance UID under the `2.25` root.
+pub fn new_series_uid() -> String {
+    new_uid()
+}
+
+/// Generate a new study instance UID under the `2.25` root.
+pub fn new_study_uid() -> String {
+    new_uid()
+}
+
+/// Produce 128 random bits from the standard library's hasher seeds,
+/// the current time, and a process-wide counter.
+fn random_u128() -> u128 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut parts = [0_u64; 2];
+    for part in &mut parts {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(time);
+        hasher.write_u64(count);
+        *part = hasher.finish();
+    }
+    (u128::from(parts[0]) << 64) | u128::from(parts[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_uids_are_valid_and_unique() {
+        let uid = new_uid();
+        assert!(uid.starts_with("2.25."));
+        assert!(is_valid_uid(&uid), "{uid}");
+        assert_ne!(new_sop_instance_uid(), new_sop_instance_uid());
+        assert_ne!(new_series_uid(), new_study_uid());
+
+        let generator = UidGenerator::with_root("1.2.826.0.1.3680043.10.1234.").unwrap();
+        assert_eq!(generator.root(), "1.2.826.0.1.3680043.10.1234");
+        let uid = generator.generate();
+        assert!(uid.starts_with("1.2.826.0.1.3680043.10.1234."));
+        assert_eq!(uid.len(), MAX_UID_LENGTH);
+        assert!(is_valid_uid(&uid), "{uid}");
+        assert_ne!(generator.generate(), generator.generate());
+    }
+
+    #[test]
+    fn validate_roots() {
+        assert!(is_valid_uid("1.2.840.10008.1.2"));
+        assert!(is_valid_uid("1.0.3"));
+        assert!(!is_valid_uid("1.02.3"));
+        assert!(!is_valid_uid("1..3"));
+        assert!(!is_valid_uid("1.2a.3"));
+        assert!(!is_valid_uid(""));
+
+        assert!(UidGenerator::with_root("1.02").is_err());
+        assert!(UidGenerator::with_root("1.2.3.4.5.6.7.8.9.10.11.12.13.14.15.16.17").is_err());
+        assert_eq!(
+            UidGenerator::with_root("2.25").unwrap(),
+            UidGenerator::new()
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Handling of DICOM values with the AS (age string) value representation
+//! as per PS3.5 sect 6.2.
+use chrono::{Datelike, Duration, NaiveDate};
+use snafu::{Backtrace, Snafu, ensure};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Age string '{}' must be 4 characters long", value))]
+    InvalidLength { value: String, backtrace: Backtrace },
+    #[snafu(display("Invalid number in age string '{}'", value))]
+    InvalidNumber { value: String, backtrace: Backtrace },
+    #[snafu(display("Invalid unit '{}' in age string, expected D, W, M or Y", unit))]
+    InvalidUnit { unit: char, backtrace: Backtrace },
+    #[snafu(display("Age {} is out of range, at most 999 is allowed", value))]
+    AgeOutOfRange { value: u32, backtrace: Backtrace },
+    #[snafu(display("Date {} is before the birth date {}", date, birth_date))]
+    BeforeBirth {
+        birth_date: NaiveDate,
+        date: NaiveDate,
+        backtrace: Backtrace,
+    },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The unit of an age string.
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum AgeUnit {
+    /// `D`
+    Days,
+    /// `W`
+    Weeks,
+    /// `M`
+    Months,
+    /// `Y`
+    Years,
+}
+
+impl AgeUnit {
+    /// The character representing this unit in an age string.
+    pub fn as_char(self) -> char {
+        match self {
+            AgeUnit::Days => 'D',
+            AgeUnit::Weeks => 'W',
+            AgeUnit::Months => 'M',
+            AgeUnit::Years => 'Y',
+        }
+    }
+
+    /// Obtain the unit represented by a character of an age string.
+    pub fn from_char(unit: char) -> Option<Self> {
+        match unit {
+            'D' => Some(AgeUnit::Days),
+            'W' => Some(AgeUnit::Weeks),
+            'M' => Some(AgeUnit::Months),
+            'Y' => Some(AgeUnit::Years),
+            _ => None,
+        }
+    }
+}
+
+/// A DICOM _Age String_ (AS value representation),
+/// such as `045Y` for 45 years or `003W` for 3 weeks.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_core::value::age::{AgeString, AgeUnit};
+/// let age = AgeString::from_text("045Y")?;
+/// assert_eq!(age.value(), 45);
+/// assert_eq!(age.unit(), AgeUnit::Years);
+/// assert_eq!(age.to_string(), "45 years");
+/// assert_eq!(age.to_dicom_string(), "045Y");
+///
+/// let age = AgeString::new(3, AgeUnit::Weeks)?;
+/// assert_eq!(age.to_duration(), chrono::Duration::days(21));
+/// # Ok::<(), dicom_core::value::age::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq)]
+pub struct AgeString {
+    value: u16,
+    unit: AgeUnit,
+}
+
+impl AgeString {
+    /// Create an age string from a number (at most 999) and a unit.
+    pub fn new(value: u16, unit: AgeUnit) -> Result<Self> {
+        ensure!(
+            value <= 999,
+            AgeOutOfRangeSnafu {
+                value: u32::from(value)
+            }
+        );
+        Ok(AgeString { value, unit })
+    }
+
+    /// Parse a DICOM formatted age string,
+    /// ignoring trailing whitespace and null characters.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let text = text.trim_end_matches([' ', '\0']);
+        ensure!(
+            text.len() == 4 && text.is_char_boundary(3),
+            InvalidLengthSnafu { value: text }
+        );
+        let (number, unit) = text.split_at(3);
+        ensure!(
+            number.bytes().all(|b| b.is_ascii_digit()),
+            InvalidNumberSnafu { value: text }
+        );
+        let unit = unit.chars().next().unwrap_or_default();
+        let unit = AgeUnit::from_char(unit).ok_or_else(|| InvalidUnitSnafu { unit }.build())?;
+        // three ASCII digits always fit
+        let value = number.parse().unwrap_or_default();
+        Ok(AgeString { value, unit })
+    }
+
+    /// Calculate the age at the given date of someone born on the given date,
+    /// picking the unit in the conventional way:
+    /// years from 2 years of age,
+    /// months from 2 months of age,
+    /// weeks from 2 weeks of age,
+    /// and days otherwise.
+    ///
+    /// ```
+    /// # use chrono::NaiveDate;
+    /// # use dicom_core::value::age::AgeString;
+    /// let birth_date = NaiveDate::from_ymd_opt(1980, 6, 15).unwrap();
+    /// let date = NaiveDate::from_ymd_opt(2025, 6, 14).unwrap();
+    /// let age = AgeString::from_dates(birth_date, date)?;
+    /// assert_eq!(age.to_dicom_string(), "044Y");
+    /// # Ok::<(), dicom_core::value::age::Error>(())
+    /// ```
+    pub fn from_dates(birth_date: NaiveDate, date: NaiveDate) -> Result<Self> {
+        ensure!(date >= birth_date, BeforeBirthSnafu { birth_date, date });
+        let mut months = (date.year() - birth_date.year()) * 12 + date.month() as i32
+            - birth_date.month() as i32;
+        if date.day() < birth_date.day() {
+            months -= 1;
+        }
+        let months = months.max(0) as u32;
+        let days = (date - birth_date).num_days() as u32;
+
+        let (value, unit) = if months >= 24 {
+            (months / 12, AgeUnit::Years)
+        } else if months >= 2 {
+            (months, AgeUnit::Months)
+        } else if days >= 14 {
+            (days / 7, AgeUnit::Weeks)
+        } else {
+            (days, AgeUnit::Days)
+        };
+        ensure!(value <= 999, AgeOutOfRangeSnafu { value });
+        Ok(AgeString {
+            value: value as u16,
+            unit,
+        })
+    }
+
+    /// The number of units.
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    /// The unit of the age.
+    pub fn unit(&self) -> AgeUnit {
+        self.unit
+    }
+
+    /// Convert the age into a DICOM formatted string, such as `045Y`.
+    pub fn to_dicom_string(&self) -> String {
+        format!("{:03}{}", self.value, self.unit.as_char())
+    }
+
+    /// Convert the age into a duration.
+    ///
+    /// Months and years are approximated by their average length
+    /// in the Gregorian calendar.
+    pub fn to_duration(&self) -> Duration {
+        let value = i64::from(self.value);
+        match self.unit {
+            AgeUnit::Days => Duration::days(value),
+            AgeUnit::Weeks => Duration::weeks(value),
+            AgeUnit::Months => Duration::seconds(value * 2_629_746),
+            AgeUnit::Years => Duration::seconds(value * 31_556_952),
+        }
+    }
+}
+
+impl FromStr for AgeString {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        AgeString::from_text(s)
+    }
+}
+
+impl Display for AgeString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let unit = match self.unit {
+            AgeUnit::Days => "day",
+            AgeUnit::Weeks => "week",
+            AgeUnit::Months => "month",
+            AgeUnit::Years => "year",
+        };
+        if self.value == 1 {
+            write!(f, "{} {}", self.value, unit)
+        } else {
+            write!(f, "{} {}s", self.value, unit)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_age_strings() {
+        let age = AgeString::from_text("045Y").unwrap();
+        assert_eq!((age.value(), age.unit()), (45, AgeUnit::Years));
+        let age: AgeString = "001D ".parse().unwrap();
+        assert_eq!((age.value(), age.unit()), (1, AgeUnit::Days));
+        assert_eq!(age.to_string(), "1 day");
+        assert_eq!(
+            AgeString::from_text("018M").unwrap().to_string(),
+            "18 months"
+        );
+        assert_eq!(
+            AgeString::from_text("000W").unwrap(),
+            AgeString::new(0, AgeUnit::Weeks).unwrap()
+        );
+
+        assert!(AgeString::from_text("45Y").is_err());
+        assert!(AgeString::from_text("045").is_err());
+        assert!(AgeString::from_text("04 Y").is_err());
+        assert!(AgeString::from_text("045y").is_err());
+        assert!(AgeString::from_text("045X").is_err());
+        assert!(AgeString::from_text("0é5").is_err());
+        assert!(AgeString::new(1000, AgeUnit::Days).is_err());
+    }
+
+    #[test]
+    fn age_to_dicom_string_and_duration() {
+        let age = AgeString::new(7, AgeUnit::Months).unwrap();
+        assert_eq!(age.to_dicom_string(), "007M");
+        assert_eq!(age.to_duration().num_days(), 213);
+        let age = AgeString::new(100, AgeUnit::Years).unwrap();
+        assert_eq!(age.to_dicom_string(), "100Y");
+        assert_eq!(age.to_duration().num_days(), 36524);
+        assert_eq!(
+            AgeString::new(12, AgeUnit::Days).unwrap().to_duration(),
+            Duration::days(12)
+        );
+    }
+
+    #[test]
+    fn age_from_dates() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let age = |birth, on| AgeString::from_dates(birth, on).unwrap().to_dicom_string();
+        assert_eq!(age(date(1980, 6, 15), date(2025, 6, 15)), "045Y");
+        assert_eq!(age(date(1980, 6, 15), date(2025, 6, 14)), "044Y");
+        assert_eq!(age(date(2023, 3, 31), date(2025, 3, 30)), "023M");
+        assert_eq!(age(date(2025, 1, 1), date(2025, 3, 1)), "002M");
+        assert_eq!(age(date(2025, 1, 1), date(2025, 2, 20)), "007W");
+        assert_eq!(age(date(2025, 1, 1), date(2025, 1, 14)), "013D");
+        assert_eq!(age(date(2025, 1, 1), date(2025, 1, 1)), "000D");
+        assert!(AgeString::from_dates(date(2025, 1, 2), date(2025, 1, 1)).is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Handling of DICOM values with the DS (decimal string) value representation
+//! as per PS3.5 sect 6.2.
+//!
+//! Decimal strings are kept in their original textual form,
+//! so that values such as `0.10` or `1.0E2`
+//! are written back exactly as they were read,
+//! even though they cannot be told apart from `0.1` or `100`
+//! once converted to a binary floating point number.
+use snafu::{Backtrace, Snafu, ensure};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The maximum length of a decimal string.
+pub const MAX_DECIMAL_STRING_LENGTH: usize = 16;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display(
+        "Decimal string '{}' is too long, at most {} characters are allowed",
+        value,
+        MAX_DECIMAL_STRING_LENGTH
+    ))]
+    InvalidLength { value: String, backtrace: Backtrace },
+    #[snafu(display("Invalid decimal string '{}'", value))]
+    InvalidNumber { value: String, backtrace: Backtrace },
+    #[snafu(display("Number {} cannot be represented as a decimal string", value))]
+    NotFinite { value: f64, backtrace: Backtrace },
+}
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A DICOM _Decimal String_ (DS value representation),
+/// such as `0.10` or `-1.5e-3`.
+///
+/// The original text of the value is retained,
+/// while [`to_f64`](DecimalString::to_f64)
+/// converts it into a number.
+/// As such, two decimal strings are only equal
+/// if their text is the same.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_core::value::decimal::DecimalString;
+/// let ds = DecimalString::from_text(" 0.10")?;
+/// assert_eq!(ds.as_str(), "0.10");
+/// assert_eq!(ds.to_f64(), 0.1);
+/// assert_ne!(ds, DecimalString::from_text("0.1")?);
+///
+/// let ds = DecimalString::from_f64(std::f64::consts::PI)?;
+/// assert_eq!(ds.as_str(), "3.14159265358979");
+/// # Ok::<(), dicom_core::value::decimal::Error>(())
+/// ```
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct DecimalString(String);
+
+impl DecimalString {
+    /// Parse a DICOM formatted decimal string,
+    /// ignoring leading and trailing whitespace and null characters.
+    ///
+    /// The text is made of at most 16 characters
+    /// denoting a fixed point or floating point number.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let text = text.trim_matches([' ', '\0']);
+        ensure!(
+            text.len() <= MAX_DECIMAL_STRING_LENGTH,
+            InvalidLengthSnafu { value: text }
+        );
+        ensure!(
+            !text.is_empty()
+                && text
+                    .bytes()
+                    .all(|b| b.is_ascii_digit() || matches!(b, b'+' | b'-' | b'.' | b'e' | b'E'))
+                && text.parse::<f64>().is_ok_and(f64::is_finite),
+            InvalidNumberSnafu { value: text }
+        );
+        Ok(DecimalString(text.to_string()))
+    }
+
+    /// Create a decimal string from a number,
+    /// using the shortest text which fits in 16 characters
+    /// and represents the number as closely as possible.
+    ///
+    /// Fails if the number is infinite or not a number.
+    pub fn from_f64(value: f64) -> Result<Self> {
+        ensure!(value.is_finite(), NotFiniteSnafu { value });
+
+        let candidates = [value.to_string(), format!("{value:e}")];
+        if let Some(text) = candidates
+            .into_iter()
+            .filter(|text| text.len() <= MAX_DECIMAL_STRING_LENGTH)
+            .min_by_key(|text| text.len())
+        {
+            return Ok(DecimalString(text));
+        }
+
+        // the number cannot be represented exactly,
+        // keep as many significant digits as fit in either notation
+        let fits = |text: &String| text.len() <= MAX_DECIMAL_STRING_LENGTH;
+        let fixed = (0..MAX_DECIMAL_STRING_LENGTH)
+            .rev()
+            .map(|precision| format!("{value:.precision$}"))
+            .find(fits)
+            .map(|text| {
+                if text.contains('.') {
+                    text.trim_end_matches('0').trim_end_matches('.').to_string()
+                } else {
+                    text
+                }
+            });
+        let scientific = (0..MAX_DECIMAL_STRING_LENGTH)
+            .rev()
+            .map(|precision| format!("{value:.precision$e}"))
+            .find(fits);
+        let text = fixed
+            .into_iter()
+            .chain(scientific)
+            .min_by(|a, b| {
+                let error = |text: &String| (text.parse::<f64>().unwrap_or_default() - value).abs();
+                error(a).total_cmp(&error(b))
+            })
+            .expect("a finite number always fits in scientific notation");
+        Ok(DecimalString(text))
+    }
+
+    /// The text of the decimal string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert the decimal string into a number.
+    pub fn to_f64(&self) -> f64 {
+        // validated on construction
+        self.0.parse().unwrap_or_default()
+    }
+
+    /// Retrieve the text of the decimal string.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl FromStr for DecimalString {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        DecimalString::from_text(s)
+    }
+}
+
+impl TryFrom<f64> for DecimalString {
+    type Error = Error;
+
+    fn try_from(value: f64) -> Result<Self> {
+        DecimalString::from_f64(value)
+    }
+}
+
+impl From<DecimalString> for f64 {
+    fn from(value: DecimalString) -> Self {
+        value.to_f64()
+    }
+}
+
+impl Display for DecimalString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_decimal_strings() {
+        let ds = DecimalString::from_text("0.10").unwrap();
+        assert_eq!(ds.as_str(), "0.10");
+        assert_eq!(ds.to_f64(), 0.1);
+        let ds: DecimalString = " -1.5E-3\0".parse().unwrap();
+        assert_eq!(ds.to_string(), "-1.5E-3");
+        assert_eq!(ds.to_f64(), -0.0015);
+        assert_eq!(
+            DecimalString::from_text("-1.0000000000001")
+                .unwrap()
+                .as_str(),
+            "-1.0000000000001"
+        );
+        assert_eq!(f64::from(DecimalString::from_text("+12").unwrap()), 12.);
+
+        assert!(DecimalString::from_text("").is_err());
+        assert!(DecimalString::from_text("1,5").is_err());
+        assert!(DecimalString::from_text("0x10").is_err());
+        assert!(DecimalString::from_text("inf").is_err());
+        assert!(DecimalString::from_text("1e400").is_err());
+        assert!(DecimalString::from_text("0.12345678901234567").is_err());
+    }
+
+    #[test]
+    fn decimal_strings_from_numbers() {
+        let ds = |value| DecimalString::from_f64(value).unwrap().into_string();
+        assert_eq!(ds(0.1), "0.1");
+        assert_eq!(ds(-2.), "-2");
+        assert_eq!(ds(1e300), "1e300");
+        assert_eq!(ds(1.5e-10), "1.5e-10");
+        assert_eq!(ds(0.1 + 0.2), "0.3");
+        assert_eq!(ds(-1.0 / 3.0), "-0.3333333333333");
+        assert_eq!(ds(1.0 / 3.0e10), "3.3333333333e-11");
+        assert_eq!(ds(123456789012345678.), "1.23456789012e17");
+        assert!(DecimalString::from_f64(f64::NAN).is_err());
+        assert!(DecimalString::try_from(f64::INFINITY).is_err());
+    }
+}
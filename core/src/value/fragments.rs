@@ -93,6 +93,7 @@ impl From<Vec<Fragments>> for PixelFragmentSequence<InMemFragment> {
             return PixelFragmentSequence {
                 offset_table: C::new(),
                 fragments: C::new(),
+                truncated: false,
             };
         }
 
@@ -121,6 +122,7 @@ impl From<Vec<Fragments>> for PixelFragmentSequence<InMemFragment> {
         PixelFragmentSequence {
             offset_table,
             fragments: C::from_vec(fragments),
+            truncated: false,
         }
     }
 }
@@ -5,6 +5,8 @@ use num_traits::NumCast;
 use smallvec::SmallVec;
 use std::{borrow::Cow, str::FromStr};
 
+pub mod age;
+pub mod decimal;
 pub mod deserialize;
 pub mod fragments;
 pub mod partial;
@@ -13,6 +15,8 @@ mod primitive;
 pub mod range;
 pub mod serialize;
 
+pub use self::age::{AgeString, AgeUnit};
+pub use self::decimal::DecimalString;
 pub use self::deserialize::Error as DeserializeError;
 pub use self::partial::{DicomDate, DicomDateTime, DicomTime, PreciseDateTime};
 pub use self::person_name::PersonName;
@@ -203,6 +207,7 @@ impl<I, P> Value<I, P> {
             Value::PixelSequence(v) => Value::PixelSequence(PixelFragmentSequence {
                 offset_table: v.offset_table.iter().copied().collect(),
                 fragments: v.fragments.iter().collect(),
+                truncated: v.truncated,
             }),
         }
     }
@@ -754,6 +759,42 @@ where
         }
     }
 
+    /// Retrieves the primitive value as a [`DecimalString`].
+    pub fn to_decimal(&self) -> Result<DecimalString, ConvertValueError> {
+        match self {
+            Value::Primitive(v) => v.to_decimal(),
+            _ => Err(ConvertValueError {
+                requested: "DecimalString",
+                original: self.value_type(),
+                cause: None,
+            }),
+        }
+    }
+
+    /// Retrieves the primitive value as a sequence of [`DecimalString`]s.
+    pub fn to_multi_decimal(&self) -> Result<Vec<DecimalString>, ConvertValueError> {
+        match self {
+            Value::Primitive(v) => v.to_multi_decimal(),
+            _ => Err(ConvertValueError {
+                requested: "DecimalString",
+                original: self.value_type(),
+                cause: None,
+            }),
+        }
+    }
+
+    /// Retrieves the primitive value as an [`AgeString`].
+    pub fn to_age(&self) -> Result<AgeString, ConvertValueError> {
+        match self {
+            Value::Primitive(v) => v.to_age(),
+            _ => Err(ConvertValueError {
+                requested: "AgeString",
+                original: self.value_type(),
+                cause: None,
+            }),
+        }
+    }
+
     /// Retrieves the primitive value as a [`PersonName`].
     pub fn to_person_name(&self) -> Result<PersonName<'_>, ConvertValueError> {
         match self {
@@ -765,6 +806,18 @@ where
             }),
         }
     }
+
+    /// Retrieves the primitive value as a sequence of [`PersonName`]s.
+    pub fn to_multi_person_name(&self) -> Result<Vec<PersonName<'_>>, ConvertValueError> {
+        match self {
+            Value::Primitive(v) => v.to_multi_person_name(),
+            _ => Err(ConvertValueError {
+                requested: "PersonName",
+                original: self.value_type(),
+                cause: None,
+            }),
+        }
+    }
 }
 
 /// Macro for implementing getters to single and multi-values,
@@ -1042,6 +1095,9 @@ pub struct PixelFragmentSequence<P> {
     offset_table: C<u32>,
     /// The sequence of pixel data fragments.
     fragments: C<P>,
+    /// Whether the last fragment was cut short
+    /// by the end of the data source.
+    truncated: bool,
 }
 
 impl<P> PixelFragmentSequence<P> {
@@ -1055,6 +1111,7 @@ impl<P> PixelFragmentSequence<P> {
         PixelFragmentSequence {
             offset_table: offset_table.into(),
             fragments: fragments.into(),
+            truncated: false,
         }
     }
 
@@ -1066,6 +1123,7 @@ impl<P> PixelFragmentSequence<P> {
         PixelFragmentSequence {
             offset_table: Default::default(),
             fragments: fragments.into(),
+            truncated: false,
         }
     }
 
@@ -1128,6 +1186,67 @@ impl<P> PixelFragmentSequence<P> {
     pub fn truncate(&mut self, limit: usize) {
         self.fragments.truncate(limit);
     }
+
+    /// Check whether the last fragment of this sequence is incomplete,
+    /// because the data source ended before the fragment did.
+    ///
+    /// Truncated pixel data is only read partially,
+    /// see [`complete_frames`](Self::complete_frames)
+    /// for the number of frames which can still be used.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Set whether the last fragment of this sequence is incomplete.
+    #[inline]
+    pub fn set_truncated(&mut self, truncated: bool) {
+        self.truncated = truncated;
+    }
+}
+
+impl<P> PixelFragmentSequence<P>
+where
+    P: AsRef<[u8]>,
+{
+    /// Determine how many of the first frames of this pixel data
+    /// are fully available,
+    /// given the total number of frames declared.
+    ///
+    /// This is `number_of_frames` unless the sequence is [truncated](Self::is_truncated),
+    /// in which case only the frames ending before the last fragment are complete.
+    /// Frames are located through the basic offset table if it is present,
+    /// or otherwise assumed to take one fragment each,
+    /// as long as there are no more fragments than frames.
+    /// If the frames cannot be located,
+    /// none of them is considered complete.
+    pub fn complete_frames(&self, number_of_frames: u32) -> u32 {
+        if !self.truncated {
+            return number_of_frames;
+        }
+        let Some((_, complete_fragments)) = self.fragments.split_last() else {
+            return 0;
+        };
+        if self.offset_table.is_empty() {
+            return if self.fragments.len() <= number_of_frames as usize {
+                complete_fragments.len() as u32
+            } else {
+                0
+            };
+        }
+        // the position of the incomplete fragment
+        // relative to the first byte of the first fragment item
+        let end: u64 = complete_fragments
+            .iter()
+            .map(|fragment| fragment.as_ref().len() as u64 + 8)
+            .sum();
+        self.offset_table
+            .iter()
+            .skip(1)
+            .take(number_of_frames.saturating_sub(1) as usize)
+            .take_while(|&&offset| offset as u64 <= end)
+            .count() as u32
+    }
 }
 
 impl<T, F, P> From<(T, F)> for PixelFragmentSequence<P>
@@ -1492,4 +1611,39 @@ mod tests {
         let fragments = v.into_fragments().unwrap();
         assert_eq!(&fragments[..], &[vec![0x55; 128]]);
     }
+
+    #[test]
+    fn pixel_fragment_sequence_complete_frames() {
+        // one fragment per frame, third frame cut short
+        let mut v = PixelFragmentSequence::new_fragments(vec![
+            vec![0x11; 16],
+            vec![0x22; 16],
+            vec![0x33; 4],
+        ]);
+        assert!(!v.is_truncated());
+        assert_eq!(v.complete_frames(4), 4);
+        v.set_truncated(true);
+        assert!(v.is_truncated());
+        assert_eq!(v.complete_frames(4), 2);
+
+        // two fragments per frame, located through the offset table
+        let mut v = PixelFragmentSequence::new(
+            vec![0, 48, 96],
+            vec![
+                vec![0x11; 16],
+                vec![0x11; 16],
+                vec![0x22; 16],
+                vec![0x22; 16],
+                vec![0x33; 4],
+            ],
+        );
+        v.set_truncated(true);
+        assert_eq!(v.complete_frames(3), 2);
+        assert_eq!(v.complete_frames(2), 1);
+
+        // frames cannot be located
+        let mut v = PixelFragmentSequence::new_fragments(vec![vec![0x11; 16], vec![0x11; 4]]);
+        v.set_truncated(true);
+        assert_eq!(v.complete_frames(1), 0);
+    }
 }
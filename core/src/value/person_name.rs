@@ -11,6 +11,14 @@ use std::mem;
 /// as possibly borrowed values.
 /// All name components are optional.
 ///
+/// These components form the alphabetic representation of the name.
+/// A name may also have an ideographic representation
+/// (e.g. in kanji or hanzi)
+/// and a phonetic representation
+/// (e.g. in hiragana or hangul),
+/// which follow the alphabetic one in the DICOM formatted string,
+/// each group delimited by a `'='`.
+///
 /// # Example
 ///
 /// A value of type `PersonName` can be obtained
@@ -23,14 +31,25 @@ use std::mem;
 /// assert_eq!(&dr_seuss.to_string(), "Dr. Theodor Seuss Geisel");
 /// assert_eq!(dr_seuss.prefix(), Some("Dr."));
 /// assert_eq!(dr_seuss.given(), Some("Theodor"));
+///
+/// let yamada = PersonName::from_text("Yamada^Tarou=山田^太郎=やまだ^たろう");
+/// assert_eq!(yamada.family(), Some("Yamada"));
+/// assert_eq!(yamada.ideographic().and_then(|pn| pn.family()), Some("山田"));
+/// assert_eq!(yamada.phonetic().and_then(|pn| pn.given()), Some("たろう"));
 /// ```
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq)]
 pub struct PersonName<'a> {
     prefix: Option<Cow<'a, str>>,
     family: Option<Cow<'a, str>>,
     middle: Option<Cow<'a, str>>,
     given: Option<Cow<'a, str>>,
     suffix: Option<Cow<'a, str>>,
+    /// the ideographic component group,
+    /// which does not have component groups of its own
+    ideographic: Option<Box<PersonName<'a>>>,
+    /// the phonetic component group,
+    /// which does not have component groups of its own
+    phonetic: Option<Box<PersonName<'a>>>,
 }
 
 /// A builder to construct a [`PersonName`] from its components.
@@ -94,12 +113,74 @@ impl<'a> PersonName<'a> {
     pub fn middle(&self) -> Option<&str> {
         self.middle.as_deref()
     }
+    /// Retrieve the ideographic representation of the name, if present
+    pub fn ideographic(&self) -> Option<&PersonName<'a>> {
+        self.ideographic.as_deref()
+    }
+    /// Retrieve the phonetic representation of the name, if present
+    pub fn phonetic(&self) -> Option<&PersonName<'a>> {
+        self.phonetic.as_deref()
+    }
+
+    /// Whether the alphabetic representation of the name
+    /// has no components.
+    pub fn is_empty(&self) -> bool {
+        self.prefix.is_none()
+            && self.family.is_none()
+            && self.middle.is_none()
+            && self.given.is_none()
+            && self.suffix.is_none()
+    }
+
+    /// Format the name for display,
+    /// as in the [`Display`] implementation
+    /// (e.g. `Dr. Theodor Seuss Geisel`),
+    /// using the ideographic or phonetic representation
+    /// when the alphabetic one is empty.
+    ///
+    /// ```
+    /// # use dicom_core::value::person_name::PersonName;
+    /// let pn = PersonName::from_text("=山田^太郎=やまだ^たろう");
+    /// assert_eq!(pn.to_display_string(), "太郎 山田");
+    /// ```
+    pub fn to_display_string(&self) -> String {
+        [Some(self), self.ideographic(), self.phonetic()]
+            .into_iter()
+            .flatten()
+            .find(|group| !group.is_empty())
+            .map(|group| group.to_string())
+            .unwrap_or_default()
+    }
+
     /// Convert the person name into a DICOM formatted string.
     ///
     /// Name components are interspersed with a `'^'` separator.
     /// Leading null components produce a separator,
     /// while trailing components do not.
+    /// The ideographic and phonetic component groups, if present,
+    /// follow after a `'='` separator.
     pub fn to_dicom_string(&self) -> String {
+        let mut name = self.components_to_dicom_string();
+        match (&self.ideographic, &self.phonetic) {
+            (ideographic, Some(phonetic)) => {
+                name.push('=');
+                if let Some(ideographic) = ideographic {
+                    name.push_str(&ideographic.components_to_dicom_string());
+                }
+                name.push('=');
+                name.push_str(&phonetic.components_to_dicom_string());
+            }
+            (Some(ideographic), None) => {
+                name.push('=');
+                name.push_str(&ideographic.components_to_dicom_string());
+            }
+            (None, None) => {}
+        }
+        name
+    }
+
+    /// Format the components of a single group
+    fn components_to_dicom_string(&self) -> String {
         let mut name = String::new();
 
         let components = &[
@@ -131,10 +212,29 @@ impl<'a> PersonName<'a> {
     ///
     /// The DICOM string representation is split by the `'^'` separator
     /// into its respective components.
+    /// Up to three component groups delimited by `'='`
+    /// are recognized, for the alphabetic,
+    /// ideographic, and phonetic representations of the name.
     /// When passing a text value to this function,
     /// ensure that it contains a single DICOM formatted name.
     pub fn from_text(slice: &'a str) -> PersonName<'a> {
-        let mut parts = slice.trim().split('^');
+        let mut groups = slice.trim().splitn(3, '=');
+        let mut name = Self::components_from_text(groups.next().unwrap_or_default());
+        let mut next_group = || {
+            groups
+                .next()
+                .map(Self::components_from_text)
+                .filter(|group| !group.is_empty())
+                .map(Box::new)
+        };
+        name.ideographic = next_group();
+        name.phonetic = next_group();
+        name
+    }
+
+    /// Parse the components of a single group
+    fn components_from_text(slice: &'a str) -> PersonName<'a> {
+        let mut parts = slice.split('^');
 
         macro_rules! get_component {
             () => {
@@ -156,6 +256,8 @@ impl<'a> PersonName<'a> {
             family,
             middle,
             suffix,
+            ideographic: None,
+            phonetic: None,
         }
     }
 
@@ -165,18 +267,21 @@ impl<'a> PersonName<'a> {
     pub fn builder() -> PersonNameBuilder<'a> {
         PersonNameBuilder::new()
     }
+
+    /// Discard the ideographic and phonetic component groups
+    fn into_components(self) -> PersonName<'a> {
+        PersonName {
+            ideographic: None,
+            phonetic: None,
+            ..self
+        }
+    }
 }
 
 impl<'a> PersonNameBuilder<'a> {
     pub fn new() -> PersonNameBuilder<'a> {
         PersonNameBuilder {
-            person_name: PersonName {
-                prefix: None,
-                family: None,
-                middle: None,
-                given: None,
-                suffix: None,
-            },
+            person_name: PersonName::default(),
         }
     }
 
@@ -210,6 +315,22 @@ impl<'a> PersonNameBuilder<'a> {
         self
     }
 
+    /// Insert or update the ideographic representation of the name.
+    ///
+    /// Component groups of the given name are discarded.
+    pub fn with_ideographic(&mut self, name: impl Into<PersonName<'a>>) -> &mut Self {
+        self.person_name.ideographic = Some(Box::new(name.into().into_components()));
+        self
+    }
+
+    /// Insert or update the phonetic representation of the name.
+    ///
+    /// Component groups of the given name are discarded.
+    pub fn with_phonetic(&mut self, name: impl Into<PersonName<'a>>) -> &mut Self {
+        self.person_name.phonetic = Some(Box::new(name.into().into_components()));
+        self
+    }
+
     /// Builds the person name with the accumulated components.
     ///
     /// This operation consumes the accumulated components, resetting the builder to
@@ -259,6 +380,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: Some("B.A. M.Div.".into()),
+            ..Default::default()
         };
         assert_eq!(
             p.to_dicom_string(),
@@ -270,6 +392,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: Some("B.A. M.Div.".into()),
+            ..Default::default()
         };
         assert_eq!(
             p.to_dicom_string(),
@@ -281,6 +404,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: None,
+            ..Default::default()
         };
         assert_eq!(p.to_dicom_string(), "Adams^John^Robert^Rev.".to_string());
         let p = PersonName {
@@ -289,6 +413,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: None,
+            ..Default::default()
         };
         assert_eq!(p.to_dicom_string(), "Adams^John^Robert".to_string());
         let p = PersonName::builder().with_middle("Robert").build();
@@ -313,6 +438,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: Some("B.A. M.Div.".into()),
+            ..Default::default()
         };
         assert_eq!(
             p.to_string(),
@@ -324,6 +450,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: Some("B.A. M.Div.".into()),
+            ..Default::default()
         };
         assert_eq!(p.to_string(), "John Robert Adams B.A. M.Div.".to_string());
         let p = PersonName {
@@ -332,6 +459,7 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: None,
+            ..Default::default()
         };
         assert_eq!(p.to_string(), "Rev. John Robert Adams".to_string());
         let p = PersonName {
@@ -340,11 +468,54 @@ mod tests {
             middle: Some("Robert".into()),
             family: Some("Adams".into()),
             suffix: None,
+            ..Default::default()
         };
         assert_eq!(p.to_string(), "John Robert Adams".to_string());
         let p = PersonName::builder().with_middle("Robert").build();
         assert_eq!(p.to_string(), "Robert".to_string());
     }
+    #[test]
+    fn person_name_component_groups() {
+        let pn = PersonName::from_text("Yamada^Tarou=山田^太郎=やまだ^たろう");
+        assert_eq!(pn.family(), Some("Yamada"));
+        assert_eq!(pn.given(), Some("Tarou"));
+        let ideographic = pn.ideographic().unwrap();
+        assert_eq!(ideographic.family(), Some("山田"));
+        assert_eq!(ideographic.given(), Some("太郎"));
+        assert_eq!(ideographic.ideographic(), None);
+        let phonetic = pn.phonetic().unwrap();
+        assert_eq!(phonetic.family(), Some("やまだ"));
+        assert_eq!(phonetic.given(), Some("たろう"));
+        assert_eq!(pn.to_display_string(), "Tarou Yamada");
+        assert_eq!(pn.to_dicom_string(), "Yamada^Tarou=山田^太郎=やまだ^たろう");
+
+        // only the phonetic group
+        let pn = PersonName::from_text("Hong^Gildong==홍^길동");
+        assert_eq!(pn.ideographic(), None);
+        assert_eq!(pn.phonetic().and_then(|p| p.given()), Some("길동"));
+        assert_eq!(pn.to_dicom_string(), "Hong^Gildong==홍^길동");
+
+        // empty groups are discarded
+        let pn = PersonName::from_text("Wang^XiaoDong=");
+        assert_eq!(pn.ideographic(), None);
+        assert_eq!(pn.to_dicom_string(), "Wang^XiaoDong");
+
+        // no alphabetic representation
+        let pn = PersonName::from_text("=王^小東");
+        assert!(pn.is_empty());
+        assert_eq!(pn.family(), None);
+        assert_eq!(pn.to_display_string(), "小東 王");
+        assert_eq!(pn.to_dicom_string(), "=王^小東");
+
+        let pn = PersonName::builder()
+            .with_family("Wang")
+            .with_given("XiaoDong")
+            .with_ideographic(PersonName::from_text("王^小東"))
+            .build();
+        assert_eq!(pn, PersonName::from_text("Wang^XiaoDong=王^小東"));
+        assert_eq!(PersonName::from_text("").to_display_string(), "");
+    }
+
     #[test]
     fn person_name_from_slice() {
         assert_eq!(
@@ -386,6 +557,7 @@ mod tests {
                 middle: Some("Robert".into()),
                 family: Some("Adams".into()),
                 suffix: Some("B.A. M.Div.".into()),
+                ..Default::default()
             }
         );
         assert_eq!(
@@ -396,6 +568,7 @@ mod tests {
                 middle: Some("Robert".into()),
                 family: Some("Adams".into()),
                 suffix: Some("B.A. M.Div.".into()),
+                ..Default::default()
             }
         );
         assert_eq!(
@@ -406,6 +579,7 @@ mod tests {
                 middle: None,
                 family: Some("Adams".into()),
                 suffix: None,
+                ..Default::default()
             }
         );
     }
@@ -4,6 +4,8 @@
 
 use super::{AsRange, DicomValueType};
 use crate::header::{HasLength, Length, Tag};
+use crate::value::age::AgeString;
+use crate::value::decimal::DecimalString;
 use crate::value::partial::{DateComponent, DicomDate, DicomDateTime, DicomTime};
 use crate::value::person_name::PersonName;
 use crate::value::range::{AmbiguousDtRangeParser, DateRange, DateTimeRange, TimeRange};
@@ -89,6 +91,16 @@ pub enum InvalidValueReadError {
         #[snafu(backtrace)]
         source: crate::value::range::Error,
     },
+    #[snafu(display("Failed to read text as an age string"))]
+    ParseAge {
+        #[snafu(backtrace)]
+        source: crate::value::age::Error,
+    },
+    #[snafu(display("Failed to read text as a decimal string"))]
+    ParseDecimal {
+        #[snafu(backtrace)]
+        source: crate::value::decimal::Error,
+    },
 }
 
 /// Error type for a failed attempt to modify an existing DICOM primitive value.
@@ -336,6 +348,39 @@ impl From<&[u8]> for PrimitiveValue {
     }
 }
 
+impl From<DateRange> for PrimitiveValue {
+    /// constructs a string value for range matching
+    fn from(range: DateRange) -> Self {
+        PrimitiveValue::Str(range.to_dicom_string())
+    }
+}
+
+impl From<TimeRange> for PrimitiveValue {
+    /// constructs a string value for range matching
+    fn from(range: TimeRange) -> Self {
+        PrimitiveValue::Str(range.to_dicom_string())
+    }
+}
+
+impl From<DateTimeRange> for PrimitiveValue {
+    /// constructs a string value for range matching
+    fn from(range: DateTimeRange) -> Self {
+        PrimitiveValue::Str(range.to_dicom_string())
+    }
+}
+
+impl From<AgeString> for PrimitiveValue {
+    fn from(age: AgeString) -> Self {
+        PrimitiveValue::Str(age.to_dicom_string())
+    }
+}
+
+impl From<DecimalString> for PrimitiveValue {
+    fn from(value: DecimalString) -> Self {
+        PrimitiveValue::Str(value.into_string())
+    }
+}
+
 impl From<PersonName<'_>> for PrimitiveValue {
     fn from(p: PersonName) -> Self {
         PrimitiveValue::Str(p.to_dicom_string())
@@ -3263,6 +3308,155 @@ impl PrimitiveValue {
         }
     }
 
+    /// Retrieve a single [`AgeString`][1] from this value.
+    ///
+    /// If the value is a string or sequence of strings,
+    /// the first string is parsed as an age string.
+    ///
+    /// [1]: super::age::AgeString
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::value::PrimitiveValue;
+    /// # use std::error::Error;
+    /// use dicom_core::value::AgeUnit;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let value = PrimitiveValue::from("045Y");
+    /// let age = value.to_age()?;
+    ///
+    /// assert_eq!(age.value(), 45);
+    /// assert_eq!(age.unit(), AgeUnit::Years);
+    /// assert_eq!(PrimitiveValue::from(age), value);
+    ///
+    /// assert!(PrimitiveValue::from("45 years").to_age().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_age(&self) -> Result<AgeString, ConvertValueError> {
+        let text = match self {
+            PrimitiveValue::Str(s) => s,
+            PrimitiveValue::Strs(s) if !s.is_empty() => &s[0],
+            _ => {
+                return Err(ConvertValueError {
+                    requested: "AgeString",
+                    original: self.value_type(),
+                    cause: None,
+                });
+            }
+        };
+        AgeString::from_text(text)
+            .context(ParseAgeSnafu)
+            .map_err(|err| ConvertValueError {
+                requested: "AgeString",
+                original: self.value_type(),
+                cause: Some(Box::from(err)),
+            })
+    }
+
+    /// Retrieve a single [`DecimalString`][1] from this value.
+    ///
+    /// If the value is a string or sequence of strings,
+    /// the first string is validated as a decimal string
+    /// and kept in its original form.
+    /// Numeric values are formatted into a decimal string
+    /// of at most 16 characters.
+    ///
+    /// [1]: super::decimal::DecimalString
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::value::PrimitiveValue;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let value = PrimitiveValue::from("0.10");
+    /// let ds = value.to_decimal()?;
+    ///
+    /// assert_eq!(ds.as_str(), "0.10");
+    /// assert_eq!(ds.to_f64(), 0.1);
+    /// assert_eq!(PrimitiveValue::from(ds), value);
+    ///
+    /// assert_eq!(PrimitiveValue::from(0.25_f64).to_decimal()?.as_str(), "0.25");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_decimal(&self) -> Result<DecimalString, ConvertValueError> {
+        self.to_multi_decimal()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ConvertValueError {
+                requested: "DecimalString",
+                original: self.value_type(),
+                cause: None,
+            })
+    }
+
+    /// Retrieve a sequence of [`DecimalString`][1]s from this value.
+    ///
+    /// Strings are split by the backslash (`'\\'`) character
+    /// and each part is kept in its original form,
+    /// while numeric values are formatted into decimal strings
+    /// of at most 16 characters.
+    ///
+    /// [1]: super::decimal::DecimalString
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::{dicom_value, value::PrimitiveValue};
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let value = dicom_value!(Strs, ["1.50", "-2.0E3"]);
+    /// let values = value.to_multi_decimal()?;
+    /// assert_eq!(values[0].as_str(), "1.50");
+    /// assert_eq!(values[1].to_f64(), -2000.);
+    ///
+    /// let value = dicom_value!(F64, [0.5, 1.0 / 3.0]);
+    /// let values = value.to_multi_decimal()?;
+    /// assert_eq!(values[0].as_str(), "0.5");
+    /// assert_eq!(values[1].as_str(), "0.33333333333333");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_multi_decimal(&self) -> Result<Vec<DecimalString>, ConvertValueError> {
+        let parse = |text: &str| {
+            DecimalString::from_text(text)
+                .context(ParseDecimalSnafu)
+                .map_err(|err| ConvertValueError {
+                    requested: "DecimalString",
+                    original: self.value_type(),
+                    cause: Some(Box::from(err)),
+                })
+        };
+        match self {
+            PrimitiveValue::Empty => Ok(Vec::new()),
+            PrimitiveValue::Str(s) => s.split('\\').map(parse).collect(),
+            PrimitiveValue::Strs(s) => s.iter().map(|s| parse(s)).collect(),
+            PrimitiveValue::Date(_)
+            | PrimitiveValue::DateTime(_)
+            | PrimitiveValue::Time(_)
+            | PrimitiveValue::Tags(_) => Err(ConvertValueError {
+                requested: "DecimalString",
+                original: self.value_type(),
+                cause: None,
+            }),
+            _ => self
+                .to_multi_float64()?
+                .into_iter()
+                .map(|value| {
+                    DecimalString::from_f64(value)
+                        .context(ParseDecimalSnafu)
+                        .map_err(|err| ConvertValueError {
+                            requested: "DecimalString",
+                            original: self.value_type(),
+                            cause: Some(Box::from(err)),
+                        })
+                })
+                .collect(),
+        }
+    }
+
     /// Retrieve a single [`PersonName`][1] from this value.
     ///
     /// If the value is a string or sequence of strings,
@@ -3313,6 +3507,52 @@ impl PrimitiveValue {
             }),
         }
     }
+
+    /// Retrieve all [`PersonName`][1]s from this value.
+    ///
+    /// If the value is a string or sequence of strings,
+    /// each string is split to obtain a `PersonName`.
+    /// A single string with multiple names delimited by `'\\'`
+    /// is split into each of the names.
+    ///
+    /// [1]: super::person_name::PersonName
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::value::PrimitiveValue;
+    /// # use dicom_core::dicom_value;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let value = dicom_value!(Strs, ["Adams^John", "Yamada^Tarou=山田^太郎"]);
+    /// let names = value.to_multi_person_name()?;
+    ///
+    /// assert_eq!(names.len(), 2);
+    /// assert_eq!(names[0].family(), Some("Adams"));
+    /// assert_eq!(names[1].ideographic().and_then(|pn| pn.given()), Some("太郎"));
+    ///
+    /// let value = PrimitiveValue::from("Adams^John\\Smith^Jane");
+    /// let names = value.to_multi_person_name()?;
+    /// assert_eq!(names[1].given(), Some("Jane"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_multi_person_name(&self) -> Result<Vec<PersonName<'_>>, ConvertValueError> {
+        match self {
+            PrimitiveValue::Empty => Ok(Vec::new()),
+            PrimitiveValue::Str(s) => Ok(s
+                .trim_end_matches(whitespace_or_null)
+                .split('\\')
+                .map(PersonName::from_text)
+                .collect()),
+            PrimitiveValue::Strs(s) => Ok(s.iter().map(|s| PersonName::from_text(s)).collect()),
+            _ => Err(ConvertValueError {
+                requested: "PersonName",
+                original: self.value_type(),
+                cause: None,
+            }),
+        }
+    }
 }
 
 /// Macro for implementing getters to single and multi-values of each variant.
@@ -4155,6 +4395,46 @@ impl PrimitiveValue {
             PrimitiveValue::Time(l) => l.truncate(limit),
         }
     }
+
+    /// Shrink the capacity of the value's storage
+    /// as much as possible,
+    /// releasing memory which is not in use.
+    ///
+    /// The value itself is not changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::value::PrimitiveValue;
+    /// let mut value = PrimitiveValue::from(String::with_capacity(64) + "1.2.3.4");
+    /// value.shrink_to_fit();
+    /// assert_eq!(value.to_str(), "1.2.3.4");
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            PrimitiveValue::Empty => { /* no-op */ }
+            PrimitiveValue::Str(s) => s.shrink_to_fit(),
+            PrimitiveValue::Strs(l) => {
+                for s in l.iter_mut() {
+                    s.shrink_to_fit();
+                }
+                l.shrink_to_fit()
+            }
+            PrimitiveValue::Tags(l) => l.shrink_to_fit(),
+            PrimitiveValue::U8(l) => l.shrink_to_fit(),
+            PrimitiveValue::I16(l) => l.shrink_to_fit(),
+            PrimitiveValue::U16(l) => l.shrink_to_fit(),
+            PrimitiveValue::I32(l) => l.shrink_to_fit(),
+            PrimitiveValue::U32(l) => l.shrink_to_fit(),
+            PrimitiveValue::I64(l) => l.shrink_to_fit(),
+            PrimitiveValue::U64(l) => l.shrink_to_fit(),
+            PrimitiveValue::F32(l) => l.shrink_to_fit(),
+            PrimitiveValue::F64(l) => l.shrink_to_fit(),
+            PrimitiveValue::Date(l) => l.shrink_to_fit(),
+            PrimitiveValue::DateTime(l) => l.shrink_to_fit(),
+            PrimitiveValue::Time(l) => l.shrink_to_fit(),
+        }
+    }
 }
 
 /// The output of this method is equivalent to calling the method `to_str`
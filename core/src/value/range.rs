@@ -1,8 +1,11 @@
 //! Handling of date, time, date-time ranges. Needed for range matching.
 //! Parsing into ranges happens via partial precision  structures (DicomDate, DicomTime,
 //! DicomDatime) so ranges can handle null components in date, time, date-time values.
-use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use std::ops::{Bound, RangeBounds};
 
 use crate::value::deserialize::{
     Error as DeserializeError, parse_date_partial, parse_datetime_partial, parse_time_partial,
@@ -724,6 +727,148 @@ impl DateTimeRange {
     }
 }
 
+impl DateRange {
+    /// Checks whether the given date is within the range, bounds included.
+    pub fn contains(&self, date: &NaiveDate) -> bool {
+        RangeBounds::contains(self, date)
+    }
+
+    /// Returns the time between the lower and upper bounds,
+    /// or `None` if the range is unbounded.
+    pub fn duration(&self) -> Option<Duration> {
+        Some(self.end? - self.start?)
+    }
+
+    /// Converts the range into a DICOM formatted string
+    /// suitable for range matching, such as `20230101-20230131`.
+    pub fn to_dicom_string(&self) -> String {
+        let format = |date: Option<NaiveDate>| {
+            date.map(|d| d.format("%Y%m%d").to_string())
+                .unwrap_or_default()
+        };
+        format!("{}-{}", format(self.start), format(self.end))
+    }
+}
+
+impl RangeBounds<NaiveDate> for DateRange {
+    fn start_bound(&self) -> Bound<&NaiveDate> {
+        self.start
+            .as_ref()
+            .map_or(Bound::Unbounded, Bound::Included)
+    }
+
+    fn end_bound(&self) -> Bound<&NaiveDate> {
+        self.end.as_ref().map_or(Bound::Unbounded, Bound::Included)
+    }
+}
+
+impl TimeRange {
+    /// Checks whether the given time is within the range, bounds included.
+    pub fn contains(&self, time: &NaiveTime) -> bool {
+        RangeBounds::contains(self, time)
+    }
+
+    /// Returns the time between the lower and upper bounds,
+    /// or `None` if the range is unbounded.
+    pub fn duration(&self) -> Option<Duration> {
+        Some(self.end? - self.start?)
+    }
+
+    /// Converts the range into a DICOM formatted string
+    /// suitable for range matching, such as `080000-103000`.
+    pub fn to_dicom_string(&self) -> String {
+        let format = |time: Option<NaiveTime>| time.map(format_time).unwrap_or_default();
+        format!("{}-{}", format(self.start), format(self.end))
+    }
+}
+
+impl RangeBounds<NaiveTime> for TimeRange {
+    fn start_bound(&self) -> Bound<&NaiveTime> {
+        self.start
+            .as_ref()
+            .map_or(Bound::Unbounded, Bound::Included)
+    }
+
+    fn end_bound(&self) -> Bound<&NaiveTime> {
+        self.end.as_ref().map_or(Bound::Unbounded, Bound::Included)
+    }
+}
+
+impl DateTimeRange {
+    /// Checks whether the given date-time is within the range, bounds included.
+    ///
+    /// When only one of the range and the date-time is time-zone aware,
+    /// the local date-time of the time-zone aware side is compared.
+    pub fn contains(&self, datetime: &PreciseDateTime) -> bool {
+        match (self, datetime) {
+            (DateTimeRange::TimeZone { start, end }, PreciseDateTime::TimeZone(dt)) => {
+                start.is_none_or(|start| start <= *dt) && end.is_none_or(|end| *dt <= end)
+            }
+            (DateTimeRange::TimeZone { start, end }, PreciseDateTime::Naive(dt)) => {
+                start.is_none_or(|start| start.naive_local() <= *dt)
+                    && end.is_none_or(|end| *dt <= end.naive_local())
+            }
+            (DateTimeRange::Naive { start, end }, datetime) => {
+                let dt = match datetime {
+                    PreciseDateTime::Naive(dt) => *dt,
+                    PreciseDateTime::TimeZone(dt) => dt.naive_local(),
+                };
+                start.is_none_or(|start| start <= dt) && end.is_none_or(|end| dt <= end)
+            }
+        }
+    }
+
+    /// Returns the time between the lower and upper bounds,
+    /// or `None` if the range is unbounded.
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            DateTimeRange::Naive { start, end } => Some((*end)? - (*start)?),
+            DateTimeRange::TimeZone { start, end } => Some((*end)? - (*start)?),
+        }
+    }
+
+    /// Converts the range into a DICOM formatted string
+    /// suitable for range matching,
+    /// such as `20230101080000-20230101103000`,
+    /// with the UTC offsets of time-zone aware bounds.
+    pub fn to_dicom_string(&self) -> String {
+        let (start, end) = match self {
+            DateTimeRange::Naive { start, end } => (
+                start.map(|dt| format_naive_datetime(&dt)),
+                end.map(|dt| format_naive_datetime(&dt)),
+            ),
+            DateTimeRange::TimeZone { start, end } => {
+                let format = |dt: DateTime<FixedOffset>| {
+                    format!(
+                        "{}{}",
+                        format_naive_datetime(&dt.naive_local()),
+                        dt.format("%z")
+                    )
+                };
+                (start.map(format), end.map(format))
+            }
+        };
+        format!("{}-{}", start.unwrap_or_default(), end.unwrap_or_default())
+    }
+}
+
+/// Formats a time as `HHMMSS`, or `HHMMSS.FFFFFF` if it has a fraction.
+fn format_time(time: NaiveTime) -> String {
+    if time.nanosecond() == 0 {
+        time.format("%H%M%S").to_string()
+    } else {
+        time.format("%H%M%S%.6f").to_string()
+    }
+}
+
+fn format_naive_datetime(datetime: &NaiveDateTime) -> String {
+    format!(
+        "{}{}",
+        datetime.date().format("%Y%m%d"),
+        format_time(datetime.time())
+    )
+}
+
 /**
  *  Looks for a range separator '-'.
  *  Returns a `DateRange`.
@@ -1439,6 +1584,87 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn range_matching_and_formatting() {
+        use crate::value::PrimitiveValue;
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let dr = parse_date_range(b"20230101-20230131").unwrap();
+        assert!(dr.contains(&date(2023, 1, 1)));
+        assert!(dr.contains(&date(2023, 1, 31)));
+        assert!(!dr.contains(&date(2023, 2, 1)));
+        assert_eq!(dr.duration(), Some(Duration::days(30)));
+        assert_eq!(dr.to_dicom_string(), "20230101-20230131");
+        assert_eq!(
+            DateRange::from_start(date(2023, 1, 1)).to_dicom_string(),
+            "20230101-"
+        );
+        let dr = DateRange::from_end(date(2023, 1, 1));
+        assert_eq!(dr.to_dicom_string(), "-20230101");
+        assert!(dr.contains(&date(1900, 1, 1)));
+        assert_eq!(dr.duration(), None);
+        assert_eq!(
+            parse_date_range(b"2023-202302").unwrap().to_dicom_string(),
+            "20230101-20230228"
+        );
+
+        let time = |h, m, s| NaiveTime::from_hms_opt(h, m, s).unwrap();
+        let tr = parse_time_range(b"0800-1030").unwrap();
+        assert!(tr.contains(&time(8, 0, 0)));
+        assert!(tr.contains(&time(10, 30, 59)));
+        assert!(!tr.contains(&time(10, 31, 0)));
+        assert_eq!(tr.to_dicom_string(), "080000-103059.999999");
+        assert_eq!(
+            TimeRange::from_start(time(8, 0, 0)).to_dicom_string(),
+            "080000-"
+        );
+        assert!(
+            TimeRange::from_start_to_end(time(8, 0, 0), time(9, 0, 0))
+                .unwrap()
+                .duration()
+                == Some(Duration::hours(1))
+        );
+
+        let dtr = DateTimeRange::from_start_to_end(
+            date(2023, 1, 1).and_time(time(8, 0, 0)),
+            date(2023, 1, 1).and_time(time(10, 30, 0)),
+        )
+        .unwrap();
+        assert_eq!(dtr.to_dicom_string(), "20230101080000-20230101103000");
+        assert!(dtr.contains(&PreciseDateTime::Naive(
+            date(2023, 1, 1).and_time(time(9, 0, 0))
+        )));
+        assert!(!dtr.contains(&PreciseDateTime::Naive(
+            date(2023, 1, 2).and_time(time(9, 0, 0))
+        )));
+        assert_eq!(dtr.duration(), Some(Duration::minutes(150)));
+
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let dtr = DateTimeRange::from_start_with_time_zone(
+            offset
+                .from_local_datetime(&date(2023, 1, 1).and_time(time(8, 0, 0)))
+                .unwrap(),
+        );
+        assert_eq!(dtr.to_dicom_string(), "20230101080000+0100-");
+        // 07:30 UTC is 08:30 in the time zone of the range
+        let utc = FixedOffset::east_opt(0).unwrap();
+        assert!(
+            dtr.contains(&PreciseDateTime::TimeZone(
+                utc.from_local_datetime(&date(2023, 1, 1).and_time(time(7, 30, 0)))
+                    .unwrap()
+            ))
+        );
+        assert!(!dtr.contains(&PreciseDateTime::Naive(
+            date(2023, 1, 1).and_time(time(7, 30, 0))
+        )));
+        assert_eq!(dtr.duration(), None);
+
+        assert_eq!(
+            PrimitiveValue::from(DateRange::from_start(date(2023, 1, 1))),
+            PrimitiveValue::from("20230101-")
+        );
+    }
+
     #[test]
     fn test_parse_date_range() {
         assert_eq!(
@@ -87,6 +87,8 @@ fn parse_entries<R: BufRead>(source: R) -> Result<Vec<Entry>> {
     let regex_tag = Regex::new(r"^\(([0-9A-F]{4}),([0-9A-F]{4})\)$")?;
     let regex_tag_group100 = Regex::new(r"^\(([0-9A-F]{2})00-[0-9A-F]{2}FF,([0-9A-F]{4})\)$")?;
     let regex_tag_element100 = Regex::new(r"^\(([0-9A-F]{4}),([0-9A-F]{2})00-[0-9A-F]{2}FF\)$")?;
+    // other repeating elements, written with open digits: (1000,xxx0)
+    let regex_tag_masked = Regex::new(r"^\(([0-9A-Fx]{4}),([0-9A-Fx]{4})\)$")?;
 
     for line in source.lines() {
         let line = line?;
@@ -140,6 +142,24 @@ fn parse_entries<R: BufRead>(source: R) -> Result<Vec<Entry>> {
                 .as_str();
             tag_type = TagType::Element100;
             format!("Tag(0x{group}, 0x{elem}00)")
+        } else if let Some(cap) = regex_tag_masked.captures(tag.as_str()) {
+            // tag range over arbitrary digits: (gggg, xxxe)
+            let group = cap.get(1).expect("capture group 1: group").as_str();
+            let elem = cap.get(2).expect("capture group 2: element").as_str();
+            let value = |part: &str| part.replace('x', "0");
+            let mask = |part: &str| {
+                part.chars()
+                    .map(|c| if c == 'x' { '0' } else { 'F' })
+                    .collect::<String>()
+            };
+            tag_type = TagType::Masked;
+            format!(
+                "Masked {{ tag: Tag(0x{}, 0x{}), mask: Tag(0x{}, 0x{}) }}",
+                value(group),
+                value(elem),
+                mask(group),
+                mask(elem)
+            )
         } else {
             panic!("invalid tag: {}", alias);
         };
@@ -164,6 +184,7 @@ enum TagType {
     Single,
     Group100,
     Element100,
+    Masked,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone, Serialize)]
@@ -214,7 +235,7 @@ where
 
     f.write_all(
         b"\n\
-    use dicom_core::dictionary::{DataDictionaryEntryRef, TagRange, TagRange::*, VirtualVr::*};\n\
+    use dicom_core::dictionary::{DataDictionaryEntryRef, TagRange, TagRange::*, VirtualVr::*, Vm};\n\
     use dicom_core::Tag;\n\
     use dicom_core::VR::*;\n\n",
     )?;
@@ -235,6 +256,7 @@ where
             TagType::Single => e.tag_declaration.clone(),
             TagType::Group100 => format!("Group100({})", e.tag_declaration),
             TagType::Element100 => format!("Element100({})", e.tag_declaration),
+            TagType::Masked => e.tag_declaration.clone(),
         };
 
         if e.is_retired
@@ -261,7 +283,7 @@ where
     f.write_all(
         b"\ntype E = DataDictionaryEntryRef<'static>;\n\n\
     #[rustfmt::skip]\n\
-    pub(crate) const ENTRIES: &[E] = &[\n",
+    pub(crate) const ENTRIES: &[(E, Vm)] = &[\n",
     )?;
     for e in &entries {
         if retired_options == RetiredOptions::Ignore && e.is_retired {
@@ -286,11 +308,47 @@ where
 
         writeln!(
             f,
-            "    E {{ tag: {}, alias: \"{}\", vr: {}{}{} }}, // {}",
-            tag_set, e.alias, vr1, vr2, vr3, e.obs
+            "    (E {{ tag: {}, alias: \"{}\", vr: {}{}{} }}, {}), // {}",
+            tag_set,
+            e.alias,
+            vr1,
+            vr2,
+            vr3,
+            vm_code(&e.vm),
+            e.obs
         )?;
     }
     f.write_all(b"];\n")?;
 
     Ok(())
 }
+
+/// Produce the Rust expression for the given value multiplicity,
+/// such as `Vm::exactly(2)` for `2` or `Vm::multiple_of(2)` for `2-2n`.
+fn vm_code(vm: &str) -> String {
+    let code = match vm.split_once('-') {
+        None if vm == "1" => Some("Vm::ONE".to_string()),
+        None => vm.parse::<u32>().ok().map(|n| format!("Vm::exactly({n})")),
+        Some(("1", "n")) => Some("Vm::ONE_OR_MORE".to_string()),
+        Some((min, "n")) => min
+            .parse::<u32>()
+            .ok()
+            .map(|min| format!("Vm::at_least({min})")),
+        Some((min, max)) => match max.strip_suffix('n') {
+            Some(step) if step == min => step
+                .parse::<u32>()
+                .ok()
+                .map(|step| format!("Vm::multiple_of({step})")),
+            Some(_) => None,
+            None => min
+                .parse::<u32>()
+                .ok()
+                .zip(max.parse::<u32>().ok())
+                .map(|(min, max)| format!("Vm::between({min}, {max})")),
+        },
+    };
+    code.unwrap_or_else(|| {
+        eprintln!("[WARN] Unsupported value multiplicity `{vm}`, assuming 1-n");
+        "Vm::ONE_OR_MORE".to_string()
+    })
+}
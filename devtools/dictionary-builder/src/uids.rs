@@ -1,8 +1,10 @@
 //! Dictionary builder for unique identifier (UID) entries.
 //!
-//! Currently includes all UIDs found in [PS3.6 table A-1][1].
+//! Currently includes all UIDs found in [PS3.6 table A-1][1]
+//! and the well-known frames of reference in [PS3.6 table A-2][2].
 //!
 //! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part06/chapter_A.html#table_A-1
+//! [2]: https://dicom.nema.org/medical/dicom/current/output/chtml/part06/chapter_A.html#table_A-2
 
 use std::{
     fs::{File, create_dir_all},
@@ -75,7 +77,13 @@ pub fn run(app: UidApp) -> Result<()> {
 
     // collect all UID values
 
-    let entries = retrieve_uid_values(&xml_data)?;
+    let mut entries = retrieve_uid_values(&xml_data, "A-1", None)?;
+    entries.extend(retrieve_uid_values(
+        &xml_data,
+        "A-2",
+        Some(UidType::WellKnownFrameOfReference),
+    )?);
+    entries.sort_by(|a, b| a.uid.cmp(&b.uid));
 
     to_code_file(dst, entries, retired_options, feature_gate)?;
 
@@ -91,7 +99,7 @@ struct UidEntry {
     retired: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 enum UidType {
     SopClass,
     MetaSopClass,
@@ -105,6 +113,7 @@ enum UidType {
     MappingResource,
     LdapOid,
     SynchronizationFrameOfReference,
+    WellKnownFrameOfReference,
 }
 
 impl FromStr for UidType {
@@ -124,6 +133,7 @@ impl FromStr for UidType {
             "Mapping Resource" => Ok(UidType::MappingResource),
             "LDAP OID" => Ok(UidType::LdapOid),
             "Synchronization Frame of Reference" => Ok(UidType::SynchronizationFrameOfReference),
+            "Well-known Frame of Reference" => Ok(UidType::WellKnownFrameOfReference),
             _ => Err(()),
         }
     }
@@ -144,13 +154,21 @@ impl std::fmt::Display for UidType {
             UidType::MappingResource => "Mapping Resource",
             UidType::LdapOid => "LDAP OID",
             UidType::SynchronizationFrameOfReference => "Synchronization Frame of Reference",
+            UidType::WellKnownFrameOfReference => "Well-known Frame of Reference",
         };
         f.write_str(str)
     }
 }
 
-/// Collects UID values from PS3.6 table A-1
-fn retrieve_uid_values(xml_data: &str) -> Result<Vec<UidEntry>> {
+/// Collects UID values from a table in PS3.6 chapter A.
+///
+/// If `fixed_type` is given, the table has no UID type column
+/// and all of its UIDs are of that type.
+fn retrieve_uid_values(
+    xml_data: &str,
+    table_label: &str,
+    fixed_type: Option<UidType>,
+) -> Result<Vec<UidEntry>> {
     let xml = parser::parse(xml_data)?;
     let doc = xml.as_document();
 
@@ -163,7 +181,9 @@ fn retrieve_uid_values(xml_data: &str) -> Result<Vec<UidEntry>> {
 
     let factory = Factory::new();
     let table_rows_xpath = factory
-        .build("//xmlns:chapter[@label='A']/xmlns:table[@label='A-1']/xmlns:tbody/xmlns:tr")
+        .build(&format!(
+            "//xmlns:chapter[@label='A']/xmlns:table[@label='{table_label}']/xmlns:tbody/xmlns:tr"
+        ))
         .context("Could not compile XPath to table")?;
     let xpath = table_rows_xpath.context("No XPath was compiled")?;
     let nodes = xpath.evaluate(&context, doc.root())?;
@@ -224,22 +244,34 @@ fn retrieve_uid_values(xml_data: &str) -> Result<Vec<UidEntry>> {
 
         let mut retired = false;
 
-        // get UID type first
-        let uid_type = type_xpath.evaluate(&context, elem)?;
-        let uid_type = uid_type.into_string();
-
-        // nothing but whitespace means that it is retired,
-        // and the content is in emphasis
-        let uid_type = if uid_type.trim().is_empty() {
-            retired = true;
-            type_xpath_retired.evaluate(&context, elem)?.into_string()
-        } else {
+        let uid_type = if let Some(uid_type) = fixed_type {
+            // nothing but whitespace means that it is retired,
+            // and the content is in emphasis
+            retired = uid_xpath
+                .evaluate(&context, elem)?
+                .into_string()
+                .trim()
+                .is_empty();
             uid_type
-        };
+        } else {
+            // get UID type first
+            let uid_type = type_xpath.evaluate(&context, elem)?;
+            let uid_type = uid_type.into_string();
+
+            // nothing but whitespace means that it is retired,
+            // and the content is in emphasis
+            let uid_type = if uid_type.trim().is_empty() {
+                retired = true;
+                type_xpath_retired.evaluate(&context, elem)?.into_string()
+            } else {
+                uid_type
+            };
 
-        let Ok(uid_type) = UidType::from_str(&uid_type) else {
-            eprintln!("Unsupported UID type `{uid_type}`");
-            continue;
+            let Ok(uid_type) = UidType::from_str(&uid_type) else {
+                eprintln!("Unsupported UID type `{uid_type}`");
+                continue;
+            };
+            uid_type
         };
 
         // get UID
@@ -389,6 +421,11 @@ where
             "SYNCHRONIZATION_FRAME_OF_REFERENCES",
             "synchronization-frame-of-reference",
         ),
+        (
+            UidType::WellKnownFrameOfReference,
+            "WELL_KNOWN_FRAMES_OF_REFERENCE",
+            "well-known-frame-of-reference",
+        ),
     ];
 
     for (typ, entries_name, feature_name) in listings {
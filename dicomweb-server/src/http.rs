@@ -0,0 +1,308 @@
+//! A minimal HTTP/1.1 implementation,
+//! sufficient for serving DICOMweb requests.
+//!
+//! Each connection serves a single request
+//! and is closed after the response is sent.
+use std::io::{BufRead, Read, Write};
+
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+/// The maximum size of the request line and headers, in bytes
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+
+/// The boundary used in `multipart/related` responses
+const BOUNDARY: &str = "dicom-rs-7f1c0a9e52d84b36a1e5d0c2b9f3e8a4";
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// failed to read request
+    ReadRequest { source: std::io::Error },
+    /// malformed request line
+    BadRequestLine,
+    /// malformed request header
+    BadHeader,
+    /// request header is too large
+    HeaderTooLarge,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An HTTP request, without its body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    /// the request method (e.g. `GET`)
+    pub method: String,
+    /// the decoded, non-empty segments of the request path
+    pub path: Vec<String>,
+    /// the decoded query parameters, in order
+    pub query: Vec<(String, String)>,
+    /// the request headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Read the request line and headers of a request.
+    ///
+    /// Returns `None` if the connection was closed before a request was sent.
+    pub fn read_from(reader: &mut impl BufRead) -> Result<Option<Request>> {
+        let mut total = 0;
+        let Some(request_line) = read_line(reader, &mut total)? else {
+            return Ok(None);
+        };
+
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return BadRequestLineSnafu.fail();
+        };
+        ensure!(version.starts_with("HTTP/1."), BadRequestLineSnafu);
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader, &mut total)?.context(BadHeaderSnafu)?;
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':').context(BadHeaderSnafu)?;
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        Ok(Some(Request {
+            method: method.to_string(),
+            path: path
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .map(|segment| percent_decode(segment, false))
+                .collect(),
+            query: query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (percent_decode(key, true), percent_decode(value, true))
+                })
+                .collect(),
+            headers,
+        }))
+    }
+
+    /// Retrieve the value of a header by its lowercase name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read a line terminated by CRLF (or LF),
+/// keeping track of the total number of bytes read
+fn read_line(reader: &mut impl BufRead, total: &mut usize) -> Result<Option<String>> {
+    let mut line = Vec::new();
+    let limit = (MAX_HEADER_SIZE - *total) as u64;
+    let n = reader
+        .by_ref()
+        .take(limit)
+        .read_until(b'\n', &mut line)
+        .context(ReadRequestSnafu)?;
+    if n == 0 {
+        return Ok(None);
+    }
+    *total += n;
+    ensure!(line.ends_with(b"\n"), HeaderTooLargeSnafu);
+    line.pop();
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| BadHeaderSnafu.build())
+}
+
+/// Decode percent-encoded octets in a URL component.
+///
+/// If `plus_as_space` is true, `+` is decoded as a space,
+/// as in query strings.
+pub fn percent_decode(text: &str, plus_as_space: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// An HTTP response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// the status code
+    pub status: u16,
+    /// the value of the `Content-Type` header
+    pub content_type: String,
+    /// the response body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Create a response with status 200.
+    pub fn ok(content_type: impl Into<String>, body: Vec<u8>) -> Self {
+        Response {
+            status: 200,
+            content_type: content_type.into(),
+            body,
+        }
+    }
+
+    /// Create a response without content.
+    pub fn no_content() -> Self {
+        Response {
+            status: 204,
+            content_type: String::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Create an error response with a plain text message.
+    pub fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8".to_string(),
+            body: format!("{message}\n").into_bytes(),
+        }
+    }
+
+    /// Create a `multipart/related` response
+    /// with one part per item, of the given media type.
+    pub fn multipart<I>(part_type: &str, parts: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let mut body = Vec::new();
+        for part in parts {
+            body.extend_from_slice(
+                format!("--{BOUNDARY}\r\nContent-Type: {part_type}\r\n\r\n").as_bytes(),
+            );
+            body.extend(part);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        Response::ok(
+            format!("multipart/related; type=\"{part_type}\"; boundary={BOUNDARY}"),
+            body,
+        )
+    }
+
+    /// Write the full response,
+    /// omitting the body if `head_only` is true.
+    pub fn write_to(&self, mut writer: impl Write, head_only: bool) -> std::io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            reason_phrase(self.status),
+            self.body.len()
+        );
+        if !self.content_type.is_empty() {
+            head.push_str(&format!("Content-Type: {}\r\n", self.content_type));
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes())?;
+        if !head_only {
+            writer.write_all(&self.body)?;
+        }
+        writer.flush()
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_request() {
+        let mut data: &[u8] =
+            b"GET /studies/1.2.3/series?Modality=CT&PatientName=DOE%5EJ*&limit=5 HTTP/1.1\r\n\
+            Host: localhost:8080\r\n\
+            Accept: application/dicom+json\r\n\
+            \r\n";
+        let request = Request::read_from(&mut data).unwrap().unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, vec!["studies", "1.2.3", "series"]);
+        assert_eq!(
+            request.query,
+            vec![
+                ("Modality".to_string(), "CT".to_string()),
+                ("PatientName".to_string(), "DOE^J*".to_string()),
+                ("limit".to_string(), "5".to_string()),
+            ]
+        );
+        assert_eq!(request.header("accept"), Some("application/dicom+json"));
+
+        // connection closed without a request
+        let mut data: &[u8] = b"";
+        assert_eq!(Request::read_from(&mut data).unwrap(), None);
+
+        let mut data: &[u8] = b"GET /studies\r\n\r\n";
+        assert!(matches!(
+            Request::read_from(&mut data),
+            Err(Error::BadRequestLine)
+        ));
+    }
+
+    #[test]
+    fn decode_percent_encoding() {
+        assert_eq!(percent_decode("DOE%5EJOHN", false), "DOE^JOHN");
+        assert_eq!(percent_decode("a+b%2", true), "a b%2");
+        assert_eq!(percent_decode("a+b", false), "a+b");
+        assert_eq!(percent_decode("%zz%", false), "%zz%");
+    }
+
+    #[test]
+    fn write_multipart_response() {
+        let response = Response::multipart("application/dicom", [b"one".to_vec(), b"two".to_vec()]);
+        let mut out = Vec::new();
+        response.write_to(&mut out, false).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(out.contains(&format!(
+            "Content-Type: multipart/related; type=\"application/dicom\"; boundary={BOUNDARY}\r\n"
+        )));
+        assert!(out.ends_with(&format!(
+            "--{BOUNDARY}\r\nContent-Type: application/dicom\r\n\r\none\r\n\
+             --{BOUNDARY}\r\nContent-Type: application/dicom\r\n\r\ntwo\r\n\
+             --{BOUNDARY}--\r\n"
+        )));
+    }
+}
@@ -0,0 +1,481 @@
+//! In-memory index of the DICOM files being served.
+//!
+//! Only the attributes needed for searching
+//! are kept in memory.
+//! The full files are read again from disk on retrieval.
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::{InMemDicomObject, OpenFileOptions};
+use snafu::{OptionExt, ResultExt, Snafu};
+use tracing::{debug, warn};
+
+/// Attributes returned in study level search results
+pub const STUDY_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::STUDY_DATE,
+    tags::STUDY_TIME,
+    tags::ACCESSION_NUMBER,
+    tags::REFERRING_PHYSICIAN_NAME,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::PATIENT_BIRTH_DATE,
+    tags::PATIENT_SEX,
+    tags::STUDY_INSTANCE_UID,
+    tags::STUDY_ID,
+    tags::STUDY_DESCRIPTION,
+    tags::MODALITIES_IN_STUDY,
+    tags::NUMBER_OF_STUDY_RELATED_SERIES,
+    tags::NUMBER_OF_STUDY_RELATED_INSTANCES,
+];
+
+/// Attributes returned in series level search results
+pub const SERIES_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::MODALITY,
+    tags::SERIES_DESCRIPTION,
+    tags::STUDY_INSTANCE_UID,
+    tags::SERIES_INSTANCE_UID,
+    tags::SERIES_NUMBER,
+    tags::NUMBER_OF_SERIES_RELATED_INSTANCES,
+];
+
+/// Attributes returned in instance level search results
+pub const INSTANCE_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::SOP_CLASS_UID,
+    tags::SOP_INSTANCE_UID,
+    tags::STUDY_INSTANCE_UID,
+    tags::SERIES_INSTANCE_UID,
+    tags::INSTANCE_NUMBER,
+    tags::ROWS,
+    tags::COLUMNS,
+    tags::BITS_ALLOCATED,
+    tags::NUMBER_OF_FRAMES,
+];
+
+/// Further attributes kept in the index for matching
+const EXTRA_TAGS: &[Tag] = &[
+    tags::BODY_PART_EXAMINED,
+    tags::PERFORMED_PROCEDURE_STEP_START_DATE,
+];
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("could not read DICOM file {}", path.display()))]
+    ReadFile {
+        #[snafu(source(from(dicom_object::ReadError, Box::new)))]
+        source: Box<dicom_object::ReadError>,
+        path: PathBuf,
+    },
+    #[snafu(display("could not read directory {}", path.display()))]
+    ReadDir {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    /// missing {name} in DICOM file
+    MissingUid { name: &'static str },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The query/retrieve level of a search
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Level {
+    Study,
+    Series,
+    Instance,
+}
+
+impl Level {
+    /// The attributes returned at this level by default
+    pub fn tags(self) -> &'static [Tag] {
+        match self {
+            Level::Study => STUDY_TAGS,
+            Level::Series => SERIES_TAGS,
+            Level::Instance => INSTANCE_TAGS,
+        }
+    }
+
+    fn key(self, instance: &Instance) -> &str {
+        match self {
+            Level::Study => &instance.study_instance_uid,
+            Level::Series => &instance.series_instance_uid,
+            Level::Instance => &instance.sop_instance_uid,
+        }
+    }
+}
+
+/// An indexed DICOM instance
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// the path to the DICOM file
+    pub path: PathBuf,
+    pub study_instance_uid: String,
+    pub series_instance_uid: String,
+    pub sop_instance_uid: String,
+    /// the study, series and instance level attributes of the instance
+    pub attributes: InMemDicomObject,
+}
+
+/// An index of DICOM instances by their unique identifiers
+#[derive(Debug, Default)]
+pub struct Index {
+    instances: Vec<Instance>,
+    /// position of each instance by SOP instance UID
+    by_sop_instance_uid: HashMap<String, usize>,
+}
+
+impl Index {
+    /// Index all DICOM files in the given directory and its subdirectories.
+    ///
+    /// Files which cannot be read as DICOM files are skipped.
+    pub fn scan(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut index = Index::default();
+        let mut dirs = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            let entries = std::fs::read_dir(&dir).context(ReadDirSnafu { path: &dir })?;
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| match entry {
+                    Ok(entry) => Some(entry.path()),
+                    Err(e) => {
+                        warn!("Error reading directory {}: {}", dir.display(), e);
+                        None
+                    }
+                })
+                .collect();
+            // index in a predictable order
+            paths.sort();
+            for path in paths {
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if let Err(e) = index.add_file(&path) {
+                    debug!(
+                        "Skipping {}: {}",
+                        path.display(),
+                        snafu::Report::from_error(e)
+                    );
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    /// Add the DICOM file at the given path to the index,
+    /// replacing any instance previously indexed with the same SOP instance UID.
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let obj = OpenFileOptions::new()
+            .read_until(tags::PIXEL_DATA)
+            .open_file(path)
+            .context(ReadFileSnafu { path })?;
+
+        let uid = |tag, name| -> Result<String> {
+            Ok(obj
+                .element_opt(tag)
+                .ok()
+                .flatten()
+                .and_then(|e| e.to_str().ok())
+                .context(MissingUidSnafu { name })?
+                .trim_end_matches(['\0', ' '])
+                .to_string())
+        };
+        let study_instance_uid = uid(tags::STUDY_INSTANCE_UID, "Study Instance UID")?;
+        let series_instance_uid = uid(tags::SERIES_INSTANCE_UID, "Series Instance UID")?;
+        let sop_instance_uid = uid(tags::SOP_INSTANCE_UID, "SOP Instance UID")?;
+
+        let attributes = InMemDicomObject::from_element_iter(
+            STUDY_TAGS
+                .iter()
+                .chain(SERIES_TAGS)
+                .chain(INSTANCE_TAGS)
+                .chain(EXTRA_TAGS)
+                .filter_map(|tag| obj.element_opt(*tag).ok().flatten())
+                .cloned(),
+        );
+
+        let instance = Instance {
+            path: path.to_path_buf(),
+            study_instance_uid,
+            series_instance_uid,
+            sop_instance_uid,
+            attributes,
+        };
+        match self.by_sop_instance_uid.get(&instance.sop_instance_uid) {
+            Some(&i) => self.instances[i] = instance,
+            None => {
+                self.by_sop_instance_uid
+                    .insert(instance.sop_instance_uid.clone(), self.instances.len());
+                self.instances.push(instance);
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of indexed instances.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Look up an instance by SOP instance UID.
+    pub fn instance(&self, sop_instance_uid: &str) -> Option<&Instance> {
+        self.by_sop_instance_uid
+            .get(sop_instance_uid)
+            .map(|&i| &self.instances[i])
+    }
+
+    /// Iterate over the instances,
+    /// optionally restricted to the given study and series.
+    pub fn instances<'a>(
+        &'a self,
+        study_instance_uid: Option<&str>,
+        series_instance_uid: Option<&str>,
+    ) -> impl Iterator<Item = &'a Instance> {
+        self.instances.iter().filter(move |instance| {
+            study_instance_uid.is_none_or(|uid| instance.study_instance_uid == uid)
+                && series_instance_uid.is_none_or(|uid| instance.series_instance_uid == uid)
+        })
+    }
+
+    /// Build one summary per entity at the given level,
+    /// in the order in which they were first indexed.
+    ///
+    /// Each summary contains all indexed attributes
+    /// of the entity's first instance,
+    /// as well as the attributes computed from the entity's instances
+    /// (such as the number of related instances).
+    pub fn summaries(
+        &self,
+        level: Level,
+        study_instance_uid: Option<&str>,
+        series_instance_uid: Option<&str>,
+    ) -> Vec<InMemDicomObject> {
+        let mut groups: Vec<Vec<&Instance>> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for instance in self.instances(study_instance_uid, series_instance_uid) {
+            let key = level.key(instance);
+            match positions.get(key) {
+                Some(&i) => groups[i].push(instance),
+                None => {
+                    positions.insert(key, groups.len());
+                    groups.push(vec![instance]);
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|group| {
+                let mut summary = group[0].attributes.clone();
+                match level {
+                    Level::Study => {
+                        let mut modalities: Vec<String> = Vec::new();
+                        let mut series: Vec<&str> = Vec::new();
+                        for instance in &group {
+                            if let Some(modality) = instance
+                                .attributes
+                                .element_opt(tags::MODALITY)
+                                .ok()
+                                .flatten()
+                                .and_then(|e| e.to_str().ok())
+                            {
+                                let modality = modality.trim();
+                                if !modalities.iter().any(|m| m == modality) {
+                                    modalities.push(modality.to_string());
+                                }
+                            }
+                            if !series.contains(&instance.series_instance_uid.as_str()) {
+                                series.push(&instance.series_instance_uid);
+                            }
+                        }
+                        summary.put(DataElement::new(
+                            tags::MODALITIES_IN_STUDY,
+                            VR::CS,
+                            PrimitiveValue::Strs(modalities.into()),
+                        ));
+                        summary.put(count_element(
+                            tags::NUMBER_OF_STUDY_RELATED_SERIES,
+                            series.len(),
+                        ));
+                        summary.put(count_element(
+                            tags::NUMBER_OF_STUDY_RELATED_INSTANCES,
+                            group.len(),
+                        ));
+                    }
+                    Level::Series => {
+                        summary.put(count_element(
+                            tags::NUMBER_OF_SERIES_RELATED_INSTANCES,
+                            group.len(),
+                        ));
+                    }
+                    Level::Instance => {}
+                }
+                summary
+            })
+            .collect()
+    }
+}
+
+fn count_element(tag: Tag, count: usize) -> DataElement<InMemDicomObject> {
+    DataElement::new(tag, VR::IS, PrimitiveValue::from(count as i32))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use dicom_dictionary_std::uids;
+    use dicom_object::meta::FileMetaTableBuilder;
+
+    /// Create a temporary directory with a fresh name
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "dicom-dicomweb-server-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a small 2x2 secondary capture image,
+    /// with 2 frames of 8-bit monochrome pixel data
+    pub(crate) fn write_instance(
+        path: &Path,
+        study_instance_uid: &str,
+        series_instance_uid: &str,
+        sop_instance_uid: &str,
+        modality: &str,
+    ) {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from(uids::SECONDARY_CAPTURE_IMAGE_STORAGE),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(sop_instance_uid),
+            ),
+            DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from("20240315")),
+            DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from(modality)),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from("P-0001")),
+            DataElement::new(
+                tags::STUDY_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(study_instance_uid),
+            ),
+            DataElement::new(
+                tags::SERIES_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(series_instance_uid),
+            ),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, PrimitiveValue::from(1_u16)),
+            DataElement::new(
+                tags::PHOTOMETRIC_INTERPRETATION,
+                VR::CS,
+                PrimitiveValue::from("MONOCHROME2"),
+            ),
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, PrimitiveValue::from("2")),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::COLUMNS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::BITS_STORED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::HIGH_BIT, VR::US, PrimitiveValue::from(7_u16)),
+            DataElement::new(
+                tags::PIXEL_REPRESENTATION,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0_u8, 64, 128, 255, 10, 20, 30, 40]),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid),
+        )
+        .unwrap();
+        obj.write_to_file(path).unwrap();
+    }
+
+    #[test]
+    fn scan_and_summarize() {
+        let dir = temp_dir("index");
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        write_instance(&dir.join("1.dcm"), "1.1", "1.1.1", "1.1.1.1", "CT");
+        write_instance(&dir.join("sub/2.dcm"), "1.1", "1.1.1", "1.1.1.2", "CT");
+        write_instance(&dir.join("sub/3.dcm"), "1.1", "1.1.2", "1.1.2.1", "SR");
+        std::fs::write(dir.join("README.txt"), "not a DICOM file").unwrap();
+
+        let index = Index::scan(&dir).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.instance("1.1.1.2").unwrap().path,
+            dir.join("sub/2.dcm")
+        );
+
+        let studies = index.summaries(Level::Study, None, None);
+        assert_eq!(studies.len(), 1);
+        let study = &studies[0];
+        let mut modalities = study
+            .element(tags::MODALITIES_IN_STUDY)
+            .unwrap()
+            .to_multi_str()
+            .unwrap()
+            .to_vec();
+        modalities.sort();
+        assert_eq!(modalities, vec!["CT", "SR"]);
+        assert_eq!(
+            study
+                .element(tags::NUMBER_OF_STUDY_RELATED_SERIES)
+                .unwrap()
+                .to_int::<u32>()
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            study
+                .element(tags::NUMBER_OF_STUDY_RELATED_INSTANCES)
+                .unwrap()
+                .to_int::<u32>()
+                .unwrap(),
+            3
+        );
+
+        let series = index.summaries(Level::Series, Some("1.1"), None);
+        assert_eq!(series.len(), 2);
+        let counts: Vec<u32> = series
+            .iter()
+            .map(|s| {
+                s.element(tags::NUMBER_OF_SERIES_RELATED_INSTANCES)
+                    .unwrap()
+                    .to_int()
+                    .unwrap()
+            })
+            .collect();
+        let mut counts_sorted = counts.clone();
+        counts_sorted.sort();
+        assert_eq!(counts_sorted, vec![1, 2]);
+
+        assert_eq!(
+            index
+                .summaries(Level::Instance, Some("1.1"), Some("1.1.2"))
+                .len(),
+            1
+        );
+        assert!(index.summaries(Level::Series, Some("9.9"), None).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
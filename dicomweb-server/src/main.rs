@@ -0,0 +1,148 @@
+//! A lightweight DICOMweb server,
+//! exposing a directory of DICOM files
+//! through QIDO-RS and WADO-RS.
+use std::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::Parser;
+use snafu::{Report, ResultExt, Snafu, Whatever};
+use tracing::{Level, debug, error, info, warn};
+
+mod http;
+mod index;
+mod query;
+mod service;
+
+use http::{Request, Response};
+use index::Index;
+
+/// Serve a directory of DICOM files over DICOMweb (QIDO-RS and WADO-RS)
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The directory containing the DICOM files to serve
+    /// (e.g. the output directory of a storage SCP),
+    /// which is indexed recursively on start-up
+    directory: PathBuf,
+    /// The socket address to listen on
+    #[arg(short = 'l', long = "listen", default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Timeout for reading each request, in seconds
+    #[arg(long = "read-timeout", default_value = "30")]
+    read_timeout: u64,
+    /// Verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+#[derive(Debug, Snafu)]
+enum Error {
+    /// failed to index DICOM files
+    Index { source: index::Error },
+    #[snafu(display("could not listen on {}", address))]
+    Listen {
+        source: std::io::Error,
+        address: String,
+    },
+}
+
+fn main() {
+    let app = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if app.verbose {
+                Level::DEBUG
+            } else {
+                Level::INFO
+            })
+            .finish(),
+    )
+    .whatever_context("Could not set up global logging subscriber")
+    .unwrap_or_else(|e: Whatever| {
+        eprintln!("[ERROR] {}", Report::from_error(e));
+    });
+
+    run(app).unwrap_or_else(|e| {
+        error!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Error> {
+    let App {
+        directory,
+        listen,
+        read_timeout,
+        verbose: _,
+    } = app;
+
+    let index = Index::scan(&directory).context(IndexSnafu)?;
+    info!(
+        "Indexed {} instances in {}",
+        index.len(),
+        directory.display()
+    );
+    let index = Arc::new(index);
+
+    let listener = TcpListener::bind(&listen).context(ListenSnafu { address: &listen })?;
+    info!("Listening on http://{}", listen);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let index = Arc::clone(&index);
+        std::thread::spawn(move || {
+            if let Err(e) = serve_connection(&index, stream, Duration::from_secs(read_timeout)) {
+                debug!("Connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Serve a single request on the given connection
+fn serve_connection(
+    index: &Index,
+    stream: TcpStream,
+    read_timeout: Duration,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(read_timeout))?;
+    let mut reader = BufReader::new(&stream);
+    let (response, head_only) = match Request::read_from(&mut reader) {
+        Ok(Some(request)) => {
+            let response = service::handle(index, &request);
+            debug!(
+                "{} /{} -> {}",
+                request.method,
+                request.path.join("/"),
+                response.status
+            );
+            (response, request.method == "HEAD")
+        }
+        Ok(None) => return Ok(()),
+        Err(e) => (Response::error(400, Report::from_error(e)), false),
+    };
+    response.write_to(&stream, head_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
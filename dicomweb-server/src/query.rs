@@ -0,0 +1,234 @@
+//! QIDO-RS query parameters and attribute matching.
+use dicom_core::{DataDictionary, Tag, VR};
+use dicom_dictionary_std::StandardDataDictionary;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, Snafu};
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// unknown attribute `{key}` in query
+    UnknownAttribute { key: String },
+    /// invalid value for query parameter `{key}`
+    InvalidParameter { key: String },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A search request
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Query {
+    /// attribute values to match, by tag
+    pub filters: Vec<(Tag, String)>,
+    /// additional attributes to include in the results
+    pub include_fields: Vec<Tag>,
+    /// whether to include all available attributes in the results
+    pub include_all: bool,
+    /// the maximum number of results
+    pub limit: Option<usize>,
+    /// the number of results to skip
+    pub offset: usize,
+}
+
+impl Query {
+    /// Interpret the query parameters of a search request.
+    pub fn from_params(params: &[(String, String)]) -> Result<Self> {
+        let mut query = Query::default();
+        for (key, value) in params {
+            match key.as_str() {
+                "limit" => {
+                    query.limit = Some(value.parse().ok().context(InvalidParameterSnafu { key })?)
+                }
+                "offset" => {
+                    query.offset = value.parse().ok().context(InvalidParameterSnafu { key })?
+                }
+                "includefield" => {
+                    for field in value.split(',') {
+                        if field == "all" {
+                            query.include_all = true;
+                        } else {
+                            query.include_fields.push(parse_attribute(field)?);
+                        }
+                    }
+                }
+                // fuzzy matching is not supported,
+                // which is allowed by the standard
+                "fuzzymatching" => {}
+                _ => {
+                    let tag = parse_attribute(key)?;
+                    if value.is_empty() {
+                        // universal matching, only requests the attribute
+                        query.include_fields.push(tag);
+                    } else {
+                        query.filters.push((tag, value.clone()));
+                    }
+                }
+            }
+        }
+        Ok(query)
+    }
+
+    /// Check whether the given object matches all filters.
+    pub fn matches(&self, obj: &InMemDicomObject) -> bool {
+        self.filters.iter().all(|(tag, pattern)| {
+            let Some(element) = obj.element_opt(*tag).ok().flatten() else {
+                return false;
+            };
+            let Ok(values) = element.to_multi_str() else {
+                return false;
+            };
+            values.iter().any(|value| {
+                match_value(element.vr(), value.trim_end_matches(['\0', ' ']), pattern)
+            })
+        })
+    }
+
+    /// Keep only the attributes to be returned in the results,
+    /// given the default attributes of the query level.
+    pub fn project(&self, obj: InMemDicomObject, default_tags: &[Tag]) -> InMemDicomObject {
+        if self.include_all {
+            return obj;
+        }
+        InMemDicomObject::from_element_iter(obj.into_iter().filter(|e| {
+            let tag = e.header().tag;
+            default_tags.contains(&tag)
+                || self.include_fields.contains(&tag)
+                || self.filters.iter().any(|(t, _)| *t == tag)
+        }))
+    }
+}
+
+/// Interpret an attribute in a query,
+/// either as a keyword or as 8 hexadecimal digits
+fn parse_attribute(key: &str) -> Result<Tag> {
+    if key.len() == 8 && key.bytes().all(|b| b.is_ascii_hexdigit()) {
+        let group = u16::from_str_radix(&key[..4], 16).unwrap();
+        let element = u16::from_str_radix(&key[4..], 16).unwrap();
+        return Ok(Tag(group, element));
+    }
+    StandardDataDictionary
+        .parse_tag(key)
+        .context(UnknownAttributeSnafu { key })
+}
+
+/// Match a single attribute value against a query value,
+/// as in the matching rules of QIDO-RS.
+///
+/// - UIDs support list of UID matching (separated by `,` or `\`);
+/// - dates and times support range matching;
+/// - other values support wildcard matching (`*` and `?`),
+///   case insensitive for person names.
+pub fn match_value(vr: VR, value: &str, pattern: &str) -> bool {
+    match vr {
+        VR::UI => pattern
+            .split([',', '\\'])
+            .any(|uid| uid.trim_end_matches('\0') == value),
+        VR::DA | VR::TM | VR::DT if pattern.contains('-') => {
+            let (lower, upper) = pattern.split_once('-').unwrap_or((pattern, pattern));
+            (lower.is_empty() || value >= lower)
+                && (upper.is_empty() || value.get(..upper.len()).unwrap_or(value) <= upper)
+        }
+        VR::PN => wildcard_match(&value.to_lowercase(), &pattern.to_lowercase()),
+        _ => wildcard_match(value, pattern),
+    }
+}
+
+/// Match text against a pattern in which
+/// `*` matches any sequence of characters
+/// and `?` matches a single character
+fn wildcard_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // position of the last `*` in the pattern and the text position it matched
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_dictionary_std::tags;
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_query_params() {
+        let query = Query::from_params(&params(&[
+            ("PatientName", "DOE*"),
+            ("00080060", "CT"),
+            ("StudyDescription", ""),
+            ("includefield", "00100030,SeriesNumber"),
+            ("limit", "10"),
+            ("offset", "5"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            query.filters,
+            vec![
+                (tags::PATIENT_NAME, "DOE*".to_string()),
+                (tags::MODALITY, "CT".to_string()),
+            ]
+        );
+        assert_eq!(
+            query.include_fields,
+            vec![
+                tags::STUDY_DESCRIPTION,
+                tags::PATIENT_BIRTH_DATE,
+                tags::SERIES_NUMBER
+            ]
+        );
+        assert_eq!(query.limit, Some(10));
+        assert_eq!(query.offset, 5);
+
+        assert!(matches!(
+            Query::from_params(&params(&[("NotAnAttribute", "1")])),
+            Err(Error::UnknownAttribute { .. })
+        ));
+        assert!(matches!(
+            Query::from_params(&params(&[("limit", "many")])),
+            Err(Error::InvalidParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn attribute_matching() {
+        assert!(match_value(VR::PN, "Doe^John", "DOE^*"));
+        assert!(match_value(VR::LO, "P-0001", "P-000?"));
+        assert!(!match_value(VR::LO, "P-0001", "p-0001"));
+        assert!(match_value(VR::LO, "abcabd", "*abd"));
+        assert!(!match_value(VR::LO, "abc", "*abd"));
+        assert!(match_value(VR::UI, "1.2.3", "1.2.4,1.2.3"));
+        assert!(!match_value(VR::UI, "1.2.3", "1.2"));
+        assert!(match_value(VR::DA, "20240315", "20240101-20241231"));
+        assert!(match_value(VR::DA, "20240315", "20240315-"));
+        assert!(match_value(VR::DA, "20240315", "-20240315"));
+        assert!(!match_value(VR::DA, "20240315", "20240316-"));
+        assert!(match_value(VR::DA, "20240315", "20240315"));
+        assert!(match_value(VR::TM, "101500.123", "1000-1030"));
+    }
+}
@@ -0,0 +1,525 @@
+//! The DICOMweb services (QIDO-RS and WADO-RS) on top of an [`Index`].
+//!
+//! Supported resources, relative to the server root:
+//!
+//! - search: `/studies`, `/series`, `/instances`,
+//!   `/studies/{study}/series`, `/studies/{study}/instances`,
+//!   `/studies/{study}/series/{series}/instances`;
+//! - retrieval of instances: `/studies/{study}`,
+//!   `/studies/{study}/series/{series}`,
+//!   `/studies/{study}/series/{series}/instances/{instance}`;
+//! - retrieval of metadata: any of the above followed by `/metadata`;
+//! - retrieval of frames:
+//!   `/studies/{study}/series/{series}/instances/{instance}/frames/{frames}`;
+//! - rendered JPEG or PNG images, as negotiated through the `Accept` header:
+//!   `/studies/{study}/series/{series}/instances/{instance}/rendered`
+//!   and `.../frames/{frame}/rendered`.
+use std::{io::Cursor, path::PathBuf};
+
+use dicom_dictionary_std::tags;
+use dicom_object::{InMemDicomObject, OpenFileOptions, open_file};
+use dicom_pixeldata::{
+    BitDepthOption, PixelDecoder, RenderingPipeline,
+    image::{DynamicImage, ImageError, ImageFormat, codecs::jpeg::JpegEncoder},
+};
+use snafu::{OptionExt, Report, ResultExt, Snafu, ensure};
+
+use crate::{
+    http::{Request, Response},
+    index::{Index, Instance, Level},
+    query::{self, Query},
+};
+
+/// The JPEG quality of rendered images, unless requested otherwise
+const DEFAULT_QUALITY: u8 = 90;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    /// resource not found
+    NotFound,
+    /// method not allowed
+    MethodNotAllowed,
+    /// invalid search query
+    Query { source: query::Error },
+    /// invalid frame list `{frames}`
+    InvalidFrameList { frames: String },
+    /// frame #{frame} does not exist
+    FrameNotFound { frame: u32 },
+    /// rendering more than one frame is not supported
+    MultipleFramesRendered,
+    /// invalid rendered image quality
+    InvalidQuality,
+    /// none of the accepted media types can be rendered
+    NotAcceptable,
+    #[snafu(display("could not read DICOM file {}", path.display()))]
+    ReadFile {
+        #[snafu(source(from(dicom_object::ReadError, Box::new)))]
+        source: Box<dicom_object::ReadError>,
+        path: PathBuf,
+    },
+    #[snafu(display("could not read file {}", path.display()))]
+    ReadBytes {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+    /// failed to decode pixel data
+    DecodePixelData {
+        #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
+        source: Box<dicom_pixeldata::Error>,
+    },
+    /// failed to convert pixel data to image
+    ConvertImage {
+        #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
+        source: Box<dicom_pixeldata::Error>,
+    },
+    /// failed to encode rendered image
+    EncodeImage {
+        #[snafu(source(from(ImageError, Box::new)))]
+        source: Box<ImageError>,
+    },
+    /// failed to serialize DICOM JSON
+    SerializeJson { source: serde_json::Error },
+}
+
+impl Error {
+    /// The HTTP status code to respond with
+    fn status(&self) -> u16 {
+        match self {
+            Error::NotFound | Error::FrameNotFound { .. } => 404,
+            Error::MethodNotAllowed => 405,
+            Error::NotAcceptable => 406,
+            Error::Query { .. }
+            | Error::InvalidFrameList { .. }
+            | Error::MultipleFramesRendered
+            | Error::InvalidQuality => 400,
+            Error::ReadFile { .. }
+            | Error::ReadBytes { .. }
+            | Error::DecodePixelData { .. }
+            | Error::ConvertImage { .. }
+            | Error::EncodeImage { .. }
+            | Error::SerializeJson { .. } => 500,
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Respond to a DICOMweb request.
+pub fn handle(index: &Index, request: &Request) -> Response {
+    route(index, request).unwrap_or_else(|e| {
+        let status = e.status();
+        Response::error(status, Report::from_error(e))
+    })
+}
+
+fn route(index: &Index, request: &Request) -> Result<Response> {
+    ensure!(
+        request.method == "GET" || request.method == "HEAD",
+        MethodNotAllowedSnafu
+    );
+
+    let path: Vec<&str> = request.path.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        // QIDO-RS
+        ["studies"] => search(index, Level::Study, None, None, request),
+        ["series"] => search(index, Level::Series, None, None, request),
+        ["instances"] => search(index, Level::Instance, None, None, request),
+        ["studies", study, "series"] => search(index, Level::Series, Some(study), None, request),
+        ["studies", study, "instances"] => {
+            search(index, Level::Instance, Some(study), None, request)
+        }
+        ["studies", study, "series", series, "instances"] => {
+            search(index, Level::Instance, Some(study), Some(series), request)
+        }
+
+        // WADO-RS
+        ["studies", study] => retrieve(&select(index, study, None, None)?),
+        ["studies", study, "metadata"] => metadata(&select(index, study, None, None)?),
+        ["studies", study, "series", series] => {
+            retrieve(&select(index, study, Some(series), None)?)
+        }
+        ["studies", study, "series", series, "metadata"] => {
+            metadata(&select(index, study, Some(series), None)?)
+        }
+        ["studies", study, "series", series, "instances", instance] => {
+            retrieve(&select(index, study, Some(series), Some(instance))?)
+        }
+        [
+            "studies",
+            study,
+            "series",
+            series,
+            "instances",
+            instance,
+            "metadata",
+        ] => metadata(&select(index, study, Some(series), Some(instance))?),
+        [
+            "studies",
+            study,
+            "series",
+            series,
+            "instances",
+            instance,
+            "rendered",
+        ] => {
+            let instances = select(index, study, Some(series), Some(instance))?;
+            rendered(instances[0], 1, request)
+        }
+        [
+            "studies",
+            study,
+            "series",
+            series,
+            "instances",
+            instance,
+            "frames",
+            frames,
+        ] => {
+            let instances = select(index, study, Some(series), Some(instance))?;
+            retrieve_frames(instances[0], &parse_frame_list(frames)?)
+        }
+        [
+            "studies",
+            study,
+            "series",
+            series,
+            "instances",
+            instance,
+            "frames",
+            frames,
+            "rendered",
+        ] => {
+            let instances = select(index, study, Some(series), Some(instance))?;
+            let [frame] = parse_frame_list(frames)?[..] else {
+                return MultipleFramesRenderedSnafu.fail();
+            };
+            rendered(instances[0], frame, request)
+        }
+        _ => NotFoundSnafu.fail(),
+    }
+}
+
+/// Search for entities at the given level
+fn search(
+    index: &Index,
+    level: Level,
+    study_instance_uid: Option<&str>,
+    series_instance_uid: Option<&str>,
+    request: &Request,
+) -> Result<Response> {
+    let query = Query::from_params(&request.query).context(QuerySnafu)?;
+
+    let results: Vec<InMemDicomObject> = index
+        .summaries(level, study_instance_uid, series_instance_uid)
+        .into_iter()
+        .filter(|summary| query.matches(summary))
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|summary| query.project(summary, level.tags()))
+        .collect();
+
+    if results.is_empty() {
+        return Ok(Response::no_content());
+    }
+    let body = dicom_json::to_vec(results).context(SerializeJsonSnafu)?;
+    Ok(Response::ok("application/dicom+json", body))
+}
+
+/// Select the instances of a study, series or single instance
+fn select<'a>(
+    index: &'a Index,
+    study_instance_uid: &str,
+    series_instance_uid: Option<&str>,
+    sop_instance_uid: Option<&str>,
+) -> Result<Vec<&'a Instance>> {
+    let instances: Vec<_> = match sop_instance_uid {
+        Some(uid) => index
+            .instance(uid)
+            .filter(|instance| {
+                instance.study_instance_uid == study_instance_uid
+                    && series_instance_uid.is_none_or(|uid| instance.series_instance_uid == uid)
+            })
+            .into_iter()
+            .collect(),
+        None => index
+            .instances(Some(study_instance_uid), series_instance_uid)
+            .collect(),
+    };
+    ensure!(!instances.is_empty(), NotFoundSnafu);
+    Ok(instances)
+}
+
+/// Retrieve the instances as they are stored
+fn retrieve(instances: &[&Instance]) -> Result<Response> {
+    let parts = instances
+        .iter()
+        .map(|instance| {
+            std::fs::read(&instance.path).context(ReadBytesSnafu {
+                path: &instance.path,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Response::multipart("application/dicom", parts))
+}
+
+/// Retrieve the attributes of the instances, excluding pixel data
+fn metadata(instances: &[&Instance]) -> Result<Response> {
+    let objects = instances
+        .iter()
+        .map(|instance| {
+            OpenFileOptions::new()
+                .read_until(tags::PIXEL_DATA)
+                .open_file(&instance.path)
+                .map(|obj| obj.into_inner())
+                .context(ReadFileSnafu {
+                    path: &instance.path,
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let body = dicom_json::to_vec(objects).context(SerializeJsonSnafu)?;
+    Ok(Response::ok("application/dicom+json", body))
+}
+
+/// Parse a comma separated list of frame numbers (starting at 1)
+fn parse_frame_list(frames: &str) -> Result<Vec<u32>> {
+    frames
+        .split(',')
+        .map(|frame| frame.trim().parse().ok().filter(|&frame: &u32| frame > 0))
+        .collect::<Option<Vec<_>>>()
+        .context(InvalidFrameListSnafu { frames })
+}
+
+/// Check that the frame exists in the instance
+fn check_frame(instance: &Instance, frame: u32) -> Result<()> {
+    let number_of_frames = instance
+        .attributes
+        .element_opt(tags::NUMBER_OF_FRAMES)
+        .ok()
+        .flatten()
+        .and_then(|e| e.to_int::<u32>().ok())
+        .unwrap_or(1);
+    ensure!(frame <= number_of_frames, FrameNotFoundSnafu { frame });
+    Ok(())
+}
+
+/// Retrieve the decoded (native) pixel data of the given frames
+fn retrieve_frames(instance: &Instance, frames: &[u32]) -> Result<Response> {
+    for &frame in frames {
+        check_frame(instance, frame)?;
+    }
+    let obj = open_file(&instance.path).context(ReadFileSnafu {
+        path: &instance.path,
+    })?;
+    let parts = frames
+        .iter()
+        .map(|&frame| {
+            let pixel = obj
+                .decode_pixel_data_frame(frame - 1)
+                .context(DecodePixelDataSnafu)?;
+            Ok(pixel.data().to_vec())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Response::multipart("application/octet-stream", parts))
+}
+
+/// Render a frame of the instance as a JPEG image
+fn rendered(instance: &Instance, frame: u32, request: &Request) -> Result<Response> {
+    let quality = match request.query.iter().find(|(key, _)| key == "quality") {
+        Some((_, value)) => value
+            .parse()
+            .ok()
+            .filter(|q| (1..=100).contains(q))
+            .context(InvalidQualitySnafu)?,
+        None => DEFAULT_QUALITY,
+    };
+    let format = rendered_format(request.header("accept"))?;
+    check_frame(instance, frame)?;
+
+    let obj = open_file(&instance.path).context(ReadFileSnafu {
+        path: &instance.path,
+    })?;
+    let pixel = obj
+        .decode_pixel_data_frame(frame - 1)
+        .context(DecodePixelDataSnafu)?;
+    let image = RenderingPipeline::new()
+        .with_bit_depth(BitDepthOption::Force8Bit)
+        .render(&pixel, 0)
+        .context(ConvertImageSnafu)?;
+    // JPEG supports neither alpha channels nor 16-bit samples
+    let image = if image.color().has_color() {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    } else {
+        DynamicImage::ImageLuma8(image.to_luma8())
+    };
+
+    let mut body = Vec::new();
+    match format {
+        ImageFormat::Png => image
+            .write_to(&mut Cursor::new(&mut body), ImageFormat::Png)
+            .context(EncodeImageSnafu)?,
+        _ => JpegEncoder::new_with_quality(&mut Cursor::new(&mut body), quality)
+            .encode_image(&image)
+            .context(EncodeImageSnafu)?,
+    }
+    Ok(Response::ok(format.to_mime_type(), body))
+}
+
+/// Choose the format of a rendered image
+/// from the media ranges in the `Accept` header,
+/// in order of appearance (quality values are not considered).
+///
+/// JPEG is chosen if the header is absent or accepts any image.
+fn rendered_format(accept: Option<&str>) -> Result<ImageFormat> {
+    let Some(accept) = accept else {
+        return Ok(ImageFormat::Jpeg);
+    };
+    accept
+        .split(',')
+        .map(|range| range.split(';').next().unwrap_or_default().trim())
+        .find_map(|media_type| match media_type {
+            "image/jpeg" | "image/*" | "*/*" => Some(ImageFormat::Jpeg),
+            "image/png" => Some(ImageFormat::Png),
+            _ => None,
+        })
+        .context(NotAcceptableSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::{temp_dir, write_instance};
+
+    fn get(uri: &str) -> Request {
+        let data = format!("GET {uri} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        Request::read_from(&mut data.as_bytes()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn search_and_retrieve() {
+        let dir = temp_dir("service");
+        write_instance(&dir.join("1.dcm"), "1.1", "1.1.1", "1.1.1.1", "CT");
+        write_instance(&dir.join("2.dcm"), "1.1", "1.1.2", "1.1.2.1", "SR");
+        let index = Index::scan(&dir).unwrap();
+
+        // QIDO-RS
+        let response = handle(&index, &get("/studies?PatientName=doe*"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "application/dicom+json");
+        let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        let studies = json.as_array().unwrap();
+        assert_eq!(studies.len(), 1);
+        assert_eq!(studies[0]["0020000D"]["Value"][0], "1.1");
+        assert_eq!(studies[0]["00201206"]["Value"][0], 2);
+        // not a study level attribute by default
+        assert!(studies[0].get("00080016").is_none());
+
+        assert_eq!(
+            handle(&index, &get("/studies?PatientID=nobody")).status,
+            204
+        );
+        assert_eq!(handle(&index, &get("/studies?Unknown=1")).status, 400);
+
+        let response = handle(&index, &get("/studies/1.1/series?Modality=SR"));
+        let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["0020000E"]["Value"][0], "1.1.2");
+
+        let response = handle(&index, &get("/instances?limit=1&offset=1"));
+        let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["00080018"]["Value"][0], "1.1.2.1");
+
+        // WADO-RS
+        let response = handle(&index, &get("/studies/1.1"));
+        assert_eq!(response.status, 200);
+        assert!(
+            response
+                .content_type
+                .starts_with("multipart/related; type=\"application/dicom\"")
+        );
+        let dcm = std::fs::read(dir.join("1.dcm")).unwrap();
+        assert!(
+            response
+                .body
+                .windows(dcm.len())
+                .any(|window| window == dcm.as_slice())
+        );
+
+        assert_eq!(
+            handle(&index, &get("/studies/1.1/series/1.1.2/instances/1.1.1.1")).status,
+            404
+        );
+
+        let response = handle(&index, &get("/studies/1.1/series/1.1.1/metadata"));
+        assert_eq!(response.status, 200);
+        let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(json[0]["00280010"]["Value"][0], 2);
+        assert!(json[0].get("7FE00010").is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn retrieve_frames_and_rendered() {
+        let dir = temp_dir("frames");
+        write_instance(&dir.join("1.dcm"), "1.1", "1.1.1", "1.1.1.1", "OT");
+        let index = Index::scan(&dir).unwrap();
+        let base = "/studies/1.1/series/1.1.1/instances/1.1.1.1";
+
+        let response = handle(&index, &get(&format!("{base}/frames/2")));
+        assert_eq!(response.status, 200);
+        assert!(
+            response
+                .content_type
+                .starts_with("multipart/related; type=\"application/octet-stream\"")
+        );
+        assert!(
+            response
+                .body
+                .windows(8)
+                .any(|w| w == b"\r\n\r\n\x0a\x14\x1e\x28")
+        );
+
+        assert_eq!(
+            handle(&index, &get(&format!("{base}/frames/3"))).status,
+            404
+        );
+        assert_eq!(
+            handle(&index, &get(&format!("{base}/frames/0"))).status,
+            400
+        );
+
+        for uri in [
+            format!("{base}/rendered"),
+            format!("{base}/frames/2/rendered?quality=50"),
+        ] {
+            let response = handle(&index, &get(&uri));
+            assert_eq!(response.status, 200, "{uri}");
+            assert_eq!(response.content_type, "image/jpeg");
+            assert_eq!(&response.body[..2], &[0xFF, 0xD8]);
+        }
+        assert_eq!(
+            handle(&index, &get(&format!("{base}/frames/1,2/rendered"))).status,
+            400
+        );
+
+        let mut request = get(&format!("{base}/rendered"));
+        request.headers.push((
+            "accept".to_string(),
+            "image/webp, image/png;q=0.9".to_string(),
+        ));
+        let response = handle(&index, &request);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, "image/png");
+        assert_eq!(&response.body[..4], b"\x89PNG");
+        request.headers[1].1 = "application/pdf".to_string();
+        assert_eq!(handle(&index, &request).status, 406);
+
+        let mut post = get(base);
+        post.method = "POST".to_string();
+        assert_eq!(handle(&index, &post).status, 405);
+        assert_eq!(handle(&index, &get("/nothing/here")).status, 404);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::tags::ENTRIES;
 use dicom_core::VR;
-use dicom_core::dictionary::{DataDictionary, DataDictionaryEntryRef, TagRange::*, VirtualVr};
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntryRef, TagRange::*, VirtualVr, Vm};
 use dicom_core::header::Tag;
 use once_cell::sync::Lazy;
 use std::collections::{HashMap, HashSet};
@@ -29,15 +29,21 @@ pub fn registry() -> &'static StandardDataDictionaryRegistry {
 #[derive(Debug)]
 pub struct StandardDataDictionaryRegistry {
     /// mapping: name → entry
-    by_name: HashMap<&'static str, &'static DataDictionaryEntryRef<'static>>,
+    by_name: HashMap<&'static str, &'static Entry>,
     /// mapping: tag → entry
-    by_tag: HashMap<Tag, &'static DataDictionaryEntryRef<'static>>,
+    by_tag: HashMap<Tag, &'static Entry>,
     /// repeating elements of the form (ggxx, eeee). The `xx` portion is zeroed.
     repeating_ggxx: HashSet<Tag>,
     /// repeating elements of the form (gggg, eexx). The `xx` portion is zeroed.
     repeating_eexx: HashSet<Tag>,
+    /// repeating elements with other open digits, such as (gggg, xxxe)
+    repeating_masked: Vec<&'static Entry>,
 }
 
+/// A dictionary entry alongside its value multiplicity,
+/// which is kept outside of the entry
+type Entry = (DataDictionaryEntryRef<'static>, Vm);
+
 impl StandardDataDictionaryRegistry {
     fn new() -> StandardDataDictionaryRegistry {
         StandardDataDictionaryRegistry {
@@ -45,39 +51,135 @@ impl StandardDataDictionaryRegistry {
             by_tag: HashMap::with_capacity(5000),
             repeating_ggxx: HashSet::with_capacity(75),
             repeating_eexx: HashSet::new(),
+            repeating_masked: Vec::new(),
         }
     }
 
     /// record the given dictionary entry reference
-    fn index(&mut self, entry: &'static DataDictionaryEntryRef<'static>) -> &mut Self {
-        self.by_name.insert(entry.alias, entry);
-        self.by_tag.insert(entry.tag.inner(), entry);
-        match entry.tag {
+    fn index(&mut self, entry: &'static Entry) -> &mut Self {
+        match entry.0.tag {
             Group100(tag) => {
                 self.repeating_ggxx.insert(tag);
             }
             Element100(tag) => {
                 self.repeating_eexx.insert(tag);
             }
+            Masked { .. } => {
+                // neither indexed by tag nor by name,
+                // as the zeroed out tag and the alias
+                // belong to a single tag attribute
+                self.repeating_masked.push(entry);
+                return self;
+            }
             _ => {}
         }
+        self.by_name.insert(entry.0.alias, entry);
+        self.by_tag.insert(entry.0.tag.inner(), entry);
         self
     }
 }
 
 /// Generic Group Length dictionary entry.
-static GROUP_LENGTH_ENTRY: DataDictionaryEntryRef<'static> = DataDictionaryEntryRef {
-    tag: GroupLength,
-    alias: "GenericGroupLength",
-    vr: VirtualVr::Exact(VR::UL),
-};
+static GROUP_LENGTH_ENTRY: Entry = (
+    DataDictionaryEntryRef {
+        tag: GroupLength,
+        alias: "GenericGroupLength",
+        vr: VirtualVr::Exact(VR::UL),
+    },
+    Vm::ONE,
+);
 
 /// Generic Private Creator dictionary entry.
-static PRIVATE_CREATOR_ENTRY: DataDictionaryEntryRef<'static> = DataDictionaryEntryRef {
-    tag: PrivateCreator,
-    alias: "PrivateCreator",
-    vr: VirtualVr::Exact(VR::LO),
-};
+static PRIVATE_CREATOR_ENTRY: Entry = (
+    DataDictionaryEntryRef {
+        tag: PrivateCreator,
+        alias: "PrivateCreator",
+        vr: VirtualVr::Exact(VR::LO),
+    },
+    Vm::ONE,
+);
+
+/// Retired repeating attributes from the compression groups,
+/// which are not part of the generated entries.
+static COMPRESSION_ENTRIES: [Entry; 7] = [
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0000),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "EscapeTriplet",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::exactly(3),
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0001),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "RunLengthTriplet",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::exactly(3),
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0002),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "HuffmanTableSize",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::ONE,
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0003),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "HuffmanTableTriplet",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::exactly(3),
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0004),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "ShiftTableSize",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::ONE,
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1000, 0x0005),
+                mask: Tag(0xFFFF, 0x000F),
+            },
+            alias: "ShiftTableTriplet",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::exactly(3),
+    ),
+    (
+        DataDictionaryEntryRef {
+            tag: Masked {
+                tag: Tag(0x1010, 0x0000),
+                mask: Tag(0xFFFF, 0x0000),
+            },
+            alias: "ZonalMap",
+            vr: VirtualVr::Exact(VR::US),
+        },
+        Vm::ONE_OR_MORE,
+    ),
+];
 
 /// A data element dictionary which consults
 /// the library's global DICOM attribute registry.
@@ -91,7 +193,7 @@ static PRIVATE_CREATOR_ENTRY: DataDictionaryEntryRef<'static> = DataDictionaryEn
 pub struct StandardDataDictionary;
 
 impl StandardDataDictionary {
-    fn indexed_tag(tag: Tag) -> Option<&'static DataDictionaryEntryRef<'static>> {
+    fn indexed_tag(tag: Tag) -> Option<&'static Entry> {
         let r = registry();
 
         r.by_tag
@@ -107,6 +209,12 @@ impl StandardDataDictionary {
                 if r.repeating_eexx.contains(&elem_trimmed) {
                     return r.by_tag.get(&elem_trimmed);
                 }
+                // check tags repeating in other digits
+                if tag.element() != 0x0000 {
+                    if let Some(entry) = r.repeating_masked.iter().find(|e| e.0.tag.contains(tag)) {
+                        return Some(entry);
+                    }
+                }
 
                 None
             })
@@ -130,11 +238,15 @@ impl DataDictionary for StandardDataDictionary {
     type Entry = DataDictionaryEntryRef<'static>;
 
     fn by_name(&self, name: &str) -> Option<&Self::Entry> {
-        registry().by_name.get(name).cloned()
+        registry().by_name.get(name).map(|(entry, _)| entry)
     }
 
     fn by_tag(&self, tag: Tag) -> Option<&Self::Entry> {
-        StandardDataDictionary::indexed_tag(tag)
+        StandardDataDictionary::indexed_tag(tag).map(|(entry, _)| entry)
+    }
+
+    fn vm_by_tag(&self, tag: Tag) -> Option<Vm> {
+        StandardDataDictionary::indexed_tag(tag).map(|(_, vm)| *vm)
     }
 }
 
@@ -142,11 +254,15 @@ impl DataDictionary for &'_ StandardDataDictionary {
     type Entry = DataDictionaryEntryRef<'static>;
 
     fn by_name(&self, name: &str) -> Option<&'static DataDictionaryEntryRef<'static>> {
-        registry().by_name.get(name).cloned()
+        registry().by_name.get(name).map(|(entry, _)| entry)
     }
 
     fn by_tag(&self, tag: Tag) -> Option<&'static DataDictionaryEntryRef<'static>> {
-        StandardDataDictionary::indexed_tag(tag)
+        StandardDataDictionary::indexed_tag(tag).map(|(entry, _)| entry)
+    }
+
+    fn vm_by_tag(&self, tag: Tag) -> Option<Vm> {
+        StandardDataDictionary::indexed_tag(tag).map(|(_, vm)| *vm)
     }
 }
 
@@ -164,6 +280,9 @@ fn init_dictionary() -> StandardDataDictionaryRegistry {
     // generic group length is not a generated entry,
     // inserting it manually
     d.by_name.insert("GenericGroupLength", &GROUP_LENGTH_ENTRY);
+    for entry in &COMPRESSION_ENTRIES {
+        d.index(entry);
+    }
     d
 }
 
@@ -172,7 +291,9 @@ mod tests {
     use crate::tags;
 
     use super::StandardDataDictionary;
-    use dicom_core::dictionary::{DataDictionary, DataDictionaryEntryRef, TagRange::*, VirtualVr};
+    use dicom_core::dictionary::{
+        DataDictionary, DataDictionaryEntryRef, TagRange::*, VirtualVr, Vm,
+    };
     use dicom_core::header::{Tag, VR};
     use dicom_core::ops::AttributeSelector;
 
@@ -222,6 +343,63 @@ mod tests {
         assert_eq!(overlay_data.tag, Group100(Tag(0x6000, 0x3000)));
         assert_eq!(overlay_data.alias, "OverlayData");
         assert!(overlay_data.vr == VirtualVr::Ox);
+
+        // value multiplicity is kept aside from the entries
+        assert_eq!(
+            dict.vm_by_tag(tags::IMAGE_ORIENTATION_PATIENT),
+            Some(Vm::exactly(6))
+        );
+        assert_eq!(dict.vm_by_tag(tags::OPERATORS_NAME), Some(Vm::ONE_OR_MORE));
+        assert_eq!(dict.vm_by_tag(Tag(0x7FE0, 0x0000)), Some(Vm::ONE));
+    }
+
+    #[test]
+    fn has_repeating_elements() {
+        let dict = StandardDataDictionary;
+
+        // curve data (50xx,3000)
+        let curve_data = dict
+            .by_tag(Tag(0x5002, 0x3000))
+            .expect("Curve Data attribute should exist");
+        assert_eq!(curve_data.alias, "CurveData");
+        assert_eq!(curve_data.tag.to_string(), "(50xx,3000)");
+
+        // source image IDs (0020,31xx)
+        let source_image_ids = dict
+            .by_tag(Tag(0x0020, 0x3105))
+            .expect("Source Image IDs attribute should exist");
+        assert_eq!(source_image_ids.alias, "SourceImageIDs");
+
+        // compression data (1000,xxxy)
+        let escape_triplet = dict
+            .by_tag(Tag(0x1000, 0x0120))
+            .expect("Escape Triplet attribute should exist");
+        assert_eq!(escape_triplet.alias, "EscapeTriplet");
+        assert_eq!(dict.vm_by_tag(Tag(0x1000, 0x0120)), Some(Vm::exactly(3)));
+        let shift_table_size = dict
+            .by_tag(Tag(0x1000, 0xFFF4))
+            .expect("Shift Table Size attribute should exist");
+        assert_eq!(shift_table_size.alias, "ShiftTableSize");
+        assert_eq!(shift_table_size.tag.to_string(), "(1000,xxx4)");
+        assert_eq!(dict.by_tag(Tag(0x1000, 0x0016)), None);
+        assert_eq!(
+            dict.by_tag(Tag(0x1000, 0x0000)).unwrap().alias,
+            "GenericGroupLength"
+        );
+
+        let zonal_map = dict
+            .by_tag(Tag(0x1010, 0x0234))
+            .expect("Zonal Map attribute should exist");
+        assert_eq!(zonal_map.alias, "ZonalMap");
+        assert_eq!(zonal_map.vr, VirtualVr::Exact(VR::US));
+
+        // aliases resolve to the single tag attributes
+        assert_eq!(dict.parse_tag("ZonalMap"), Some(Tag(0x1010, 0x0004)));
+        assert_eq!(dict.parse_tag("EscapeTriplet"), Some(Tag(0x1000, 0x0010)));
+        assert_eq!(
+            dict.by_name("ShiftTableSize").map(|e| e.tag),
+            Some(Single(Tag(0x1000, 0x0014)))
+        );
     }
 
     #[test]
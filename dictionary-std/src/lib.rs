@@ -9,9 +9,20 @@
 //!   DICOM attributes specified in the standard,
 //!   and it will be used by default in most other abstractions available.
 //!   When not using private tags, this dictionary should suffice.
+//! - [`private`]: Dictionaries of private attributes,
+//!   which can be loaded from vendor dictionary files at run time
+//!   and registered globally.
+//! - [`runtime`]: Data element dictionaries
+//!   loaded from external files at run time,
+//!   which can be layered over the standard dictionary.
 //! - `sop_class` (requires Cargo feature **sop-class**):
 //!   Contains information about DICOM Service-Object Pair (SOP) classes
 //!   and their respective unique identifiers.
+//! - `uid_dictionary` (requires Cargo feature **uid-dictionary**):
+//!   Contains information about all normative DICOM unique identifiers
+//!   of SOP classes, transfer syntaxes, well-known instances,
+//!   and well-known frames of reference,
+//!   searchable regardless of their category.
 //!
 //! The records in these dictionaries are typically collected
 //! from [DICOM PS3.6] directly,
@@ -30,15 +41,21 @@
 //! - [`tags`], which map an attribute alias to a DICOM tag
 //! - [`uids`], for various normative DICOM unique identifiers
 pub mod data_element;
+pub mod private;
+pub mod runtime;
 
 #[cfg(feature = "sop-class")]
 pub mod sop_class;
 pub mod tags;
+#[cfg(feature = "uid-dictionary")]
+pub mod uid_dictionary;
 pub mod uids;
 
 pub use data_element::{StandardDataDictionary, StandardDataDictionaryRegistry};
 #[cfg(feature = "sop-class")]
 pub use sop_class::StandardSopClassDictionary;
+#[cfg(feature = "uid-dictionary")]
+pub use uid_dictionary::StandardUidDictionary;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,582 @@
+//! Private data element dictionaries.
+//!
+//! Private attributes are not identified by their tag alone.
+//! Each private attribute lives in a block of elements
+//! reserved by a _Private Creator_ element,
+//! so that a dictionary entry is identified by
+//! the private creator, the group number,
+//! and the offset of the element in the block.
+//! For example, attribute (0029,xx08) of the `SIEMENS CSA HEADER` creator
+//! is found at (0029,1008) if the creator reserved block `10`.
+//!
+//! Vendor dictionaries can be loaded at run time
+//! into a [`PrivateDictionary`],
+//! from a tab separated file in the format of DCMTK's `private.dic`
+//! or from JSON (with the `json` Cargo feature),
+//! and then registered globally
+//! into the [private dictionary registry](registry).
+//!
+//! # Example
+//!
+//! ```
+//! use dicom_core::Tag;
+//! use dicom_core::dictionary::DataDictionaryEntry;
+//! use dicom_dictionary_std::private::{self, PrivateDictionary};
+//!
+//! let dict = PrivateDictionary::from_tsv(
+//!     "(0029,\"SIEMENS CSA HEADER\",08)\tCS\tCSAImageHeaderType\t1\tPRIVATE\n".as_bytes(),
+//! )?;
+//! private::registry().register(dict);
+//!
+//! let entry = private::registry()
+//!     .by_tag("SIEMENS CSA HEADER", Tag(0x0029, 0x1008))
+//!     .expect("entry should be registered");
+//! assert_eq!(entry.alias(), "CSAImageHeaderType");
+//! # Ok::<(), dicom_dictionary_std::private::Error>(())
+//! ```
+use dicom_core::dictionary::{DataDictionaryEntry, TagRange, VirtualVr, Vm};
+use dicom_core::header::GroupNumber;
+use dicom_core::{Tag, VR};
+use once_cell::sync::Lazy;
+use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::RwLock;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Could not read private dictionary"))]
+    ReadDictionary {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Invalid private dictionary entry on line {}: {}", line, reason))]
+    InvalidEntry {
+        line: usize,
+        reason: String,
+        backtrace: Backtrace,
+    },
+    #[cfg(feature = "json")]
+    #[snafu(display("Invalid JSON private dictionary"))]
+    ParseJson {
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+    #[cfg(feature = "json")]
+    #[snafu(display("Invalid JSON private dictionary entry #{}: {}", index, reason))]
+    InvalidJsonEntry {
+        index: usize,
+        reason: String,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A dictionary entry for a private attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrivateDictionaryEntry {
+    /// The private creator reserving the block of the attribute
+    pub creator: String,
+    /// The group number of the attribute
+    pub group: GroupNumber,
+    /// The offset of the element in the block reserved by the private creator
+    pub offset: u8,
+    /// The alias of the attribute, with no spaces, usually in UpperCamelCase
+    pub alias: String,
+    /// The value representation of the attribute
+    pub vr: VirtualVr,
+    /// The value multiplicity of the attribute
+    pub vm: Vm,
+}
+
+impl PrivateDictionaryEntry {
+    /// Obtain the tag of this attribute
+    /// when its private creator reserved the given block.
+    ///
+    /// The block is the element number of the private creator,
+    /// between `0x10` and `0xFF`.
+    pub fn tag_in_block(&self, block: u8) -> Tag {
+        Tag(self.group, (u16::from(block) << 8) | u16::from(self.offset))
+    }
+}
+
+impl DataDictionaryEntry for PrivateDictionaryEntry {
+    fn tag_range(&self) -> TagRange {
+        TagRange::Masked {
+            tag: Tag(self.group, u16::from(self.offset)),
+            mask: Tag(0xFFFF, 0x00FF),
+        }
+    }
+
+    fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    fn vr(&self) -> VirtualVr {
+        self.vr
+    }
+
+    fn vm(&self) -> Option<Vm> {
+        Some(self.vm)
+    }
+}
+
+/// The key of a private dictionary entry:
+/// private creator, group number and element offset.
+type Key = (String, GroupNumber, u8);
+
+/// A dictionary of private attributes,
+/// indexed by private creator, group number, and element offset.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrivateDictionary {
+    entries: HashMap<Key, PrivateDictionaryEntry>,
+}
+
+impl PrivateDictionary {
+    /// Create an empty private dictionary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert an entry into the dictionary,
+    /// returning the previous entry for the same attribute, if any.
+    pub fn insert(&mut self, entry: PrivateDictionaryEntry) -> Option<PrivateDictionaryEntry> {
+        let key = (
+            trim_creator(&entry.creator).to_string(),
+            entry.group,
+            entry.offset,
+        );
+        self.entries.insert(key, entry)
+    }
+
+    /// Fetch the entry of the attribute
+    /// of the given private creator at the given group and element offset.
+    pub fn get(
+        &self,
+        creator: &str,
+        group: GroupNumber,
+        offset: u8,
+    ) -> Option<&PrivateDictionaryEntry> {
+        self.entries
+            .get(&(trim_creator(creator).to_string(), group, offset))
+    }
+
+    /// Fetch the entry of the private attribute with the given tag,
+    /// given the private creator which reserved its block.
+    pub fn by_tag(&self, creator: &str, tag: Tag) -> Option<&PrivateDictionaryEntry> {
+        if tag.group() % 2 == 0 || tag.element() < 0x1000 {
+            return None;
+        }
+        self.get(creator, tag.group(), (tag.element() & 0xFF) as u8)
+    }
+
+    /// Fetch an entry by its private creator and alias.
+    pub fn by_name(&self, creator: &str, alias: &str) -> Option<&PrivateDictionaryEntry> {
+        let creator = trim_creator(creator);
+        self.entries
+            .values()
+            .find(|e| trim_creator(&e.creator) == creator && e.alias == alias)
+    }
+
+    /// The number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the dictionary has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all entries of the dictionary, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &PrivateDictionaryEntry> {
+        self.entries.values()
+    }
+
+    /// Read a private dictionary in the tab separated format
+    /// of DCMTK's `private.dic`.
+    ///
+    /// Each line holds a tag of the form `(gggg,"creator",ee)`,
+    /// the value representation, the alias,
+    /// the value multiplicity, and optionally the source of the entry,
+    /// separated by tabs.
+    /// Empty lines and lines starting with `#` are ignored.
+    /// For example (with tabs shown as `→`):
+    ///
+    /// ```none
+    /// (0019,"GEMS_ACQU_01",0c)→SS→GEFrequencyDirection→1→PRIVATE
+    /// ```
+    pub fn from_tsv(reader: impl BufRead) -> Result<Self> {
+        let mut dict = PrivateDictionary::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.context(ReadDictionarySnafu)?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                InvalidEntrySnafu {
+                    line: i + 1,
+                    reason,
+                }
+                .build()
+            };
+            let mut fields = line.split('\t').map(str::trim);
+            let (tag, vr, alias, vm) =
+                match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                    (Some(tag), Some(vr), Some(alias), Some(vm)) => (tag, vr, alias, vm),
+                    _ => return Err(invalid("expected at least 4 tab separated fields")),
+                };
+            let (group, creator, offset) = parse_private_tag(tag).map_err(invalid)?;
+            let entry = PrivateDictionaryEntry {
+                creator: creator.to_string(),
+                group,
+                offset,
+                alias: alias.to_string(),
+                vr: parse_vr(vr).map_err(invalid)?,
+                vm: vm.parse().ok().context(InvalidEntrySnafu {
+                    line: i + 1,
+                    reason: format!("invalid value multiplicity `{vm}`"),
+                })?,
+            };
+            dict.insert(entry);
+        }
+        Ok(dict)
+    }
+
+    /// Read a private dictionary from JSON.
+    ///
+    /// The JSON document is an array of objects
+    /// with the private creator, group number, element offset
+    /// (both in hexadecimal), value representation, alias,
+    /// and optionally the value multiplicity (`1` by default).
+    ///
+    /// ```json
+    /// [
+    ///   {
+    ///     "creator": "SIEMENS CSA HEADER",
+    ///     "group": "0029",
+    ///     "element": "xx10",
+    ///     "vr": "OB",
+    ///     "keyword": "CSAImageHeaderInfo",
+    ///     "vm": "1"
+    ///   }
+    /// ]
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn from_json(reader: impl std::io::Read) -> Result<Self> {
+        use serde_json::Value;
+
+        let json: Vec<serde_json::Map<String, Value>> =
+            serde_json::from_reader(reader).context(ParseJsonSnafu)?;
+        let mut dict = PrivateDictionary::new();
+        for (index, entry) in json.into_iter().enumerate() {
+            let invalid = |reason: &str| {
+                InvalidJsonEntrySnafu {
+                    index,
+                    reason: reason.to_string(),
+                }
+                .build()
+            };
+            let field = |name: &str| -> Result<&str> {
+                entry
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| invalid(&format!("missing string field `{name}`")))
+            };
+            let creator = field("creator")?;
+            let group = parse_group(field("group")?).map_err(invalid)?;
+            let offset = parse_offset(field("element")?).map_err(invalid)?;
+            let vr = parse_vr(field("vr")?).map_err(invalid)?;
+            let alias = field("keyword").or_else(|_| field("alias"))?;
+            let vm = match entry.get("vm").and_then(Value::as_str) {
+                Some(vm) => vm
+                    .parse()
+                    .map_err(|_| invalid(&format!("invalid value multiplicity `{vm}`")))?,
+                None => Vm::ONE,
+            };
+            dict.insert(PrivateDictionaryEntry {
+                creator: creator.to_string(),
+                group,
+                offset,
+                alias: alias.to_string(),
+                vr,
+                vm,
+            });
+        }
+        Ok(dict)
+    }
+
+    /// Read a private dictionary from a file,
+    /// as JSON if the file name ends with `.json`
+    /// (requires the `json` Cargo feature),
+    /// or in the tab separated format otherwise.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).context(ReadDictionarySnafu)?;
+        let reader = std::io::BufReader::new(file);
+        #[cfg(feature = "json")]
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            return PrivateDictionary::from_json(reader);
+        }
+        PrivateDictionary::from_tsv(reader)
+    }
+}
+
+impl Extend<PrivateDictionaryEntry> for PrivateDictionary {
+    fn extend<T: IntoIterator<Item = PrivateDictionaryEntry>>(&mut self, iter: T) {
+        for entry in iter {
+            self.insert(entry);
+        }
+    }
+}
+
+impl FromIterator<PrivateDictionaryEntry> for PrivateDictionary {
+    fn from_iter<T: IntoIterator<Item = PrivateDictionaryEntry>>(iter: T) -> Self {
+        let mut dict = PrivateDictionary::new();
+        dict.extend(iter);
+        dict
+    }
+}
+
+static REGISTRY: Lazy<PrivateDictionaryRegistry> = Lazy::new(PrivateDictionaryRegistry::default);
+
+/// Retrieve the global private dictionary registry.
+///
+/// The registry starts empty.
+/// Private dictionaries registered here
+/// are used to describe private attributes
+/// wherever the library looks them up,
+/// such as in [`InMemDicomObject::private_entry`][1].
+///
+/// [1]: https://docs.rs/dicom-object/latest/dicom_object/mem/struct.InMemDicomObject.html#method.private_entry
+#[inline]
+pub fn registry() -> &'static PrivateDictionaryRegistry {
+    &REGISTRY
+}
+
+/// A thread safe collection of private dictionaries,
+/// into which vendor dictionaries can be registered at run time.
+#[derive(Debug, Default)]
+pub struct PrivateDictionaryRegistry {
+    dict: RwLock<PrivateDictionary>,
+}
+
+impl PrivateDictionaryRegistry {
+    /// Register all entries of the given private dictionary,
+    /// replacing any previously registered entries for the same attributes.
+    pub fn register(&self, dict: PrivateDictionary) {
+        let mut current = self.dict.write().unwrap_or_else(|e| e.into_inner());
+        current.extend(dict.entries.into_values());
+    }
+
+    /// Read a private dictionary from a file
+    /// and register all of its entries.
+    ///
+    /// See [`PrivateDictionary::open`] for the supported formats.
+    pub fn register_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.register(PrivateDictionary::open(path)?);
+        Ok(())
+    }
+
+    /// Fetch the entry of the attribute
+    /// of the given private creator at the given group and element offset.
+    pub fn get(
+        &self,
+        creator: &str,
+        group: GroupNumber,
+        offset: u8,
+    ) -> Option<PrivateDictionaryEntry> {
+        let dict = self.dict.read().unwrap_or_else(|e| e.into_inner());
+        dict.get(creator, group, offset).cloned()
+    }
+
+    /// Fetch the entry of the private attribute with the given tag,
+    /// given the private creator which reserved its block.
+    pub fn by_tag(&self, creator: &str, tag: Tag) -> Option<PrivateDictionaryEntry> {
+        let dict = self.dict.read().unwrap_or_else(|e| e.into_inner());
+        dict.by_tag(creator, tag).cloned()
+    }
+
+    /// Fetch an entry by its private creator and alias.
+    pub fn by_name(&self, creator: &str, alias: &str) -> Option<PrivateDictionaryEntry> {
+        let dict = self.dict.read().unwrap_or_else(|e| e.into_inner());
+        dict.by_name(creator, alias).cloned()
+    }
+
+    /// The number of registered entries.
+    pub fn len(&self) -> usize {
+        self.dict.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Whether no entries were registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn trim_creator(creator: &str) -> &str {
+    creator.trim_end_matches([' ', '\0'])
+}
+
+/// Parse a private tag of the form `(gggg,"creator",ee)`
+fn parse_private_tag(text: &str) -> Result<(GroupNumber, &str, u8), &'static str> {
+    let text = text
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .ok_or("tag should be enclosed in parentheses")?;
+    let (group, rest) = text.split_once(',').ok_or("missing private creator")?;
+    let (creator, offset) = rest.rsplit_once(',').ok_or("missing element offset")?;
+    let creator = creator
+        .strip_prefix('"')
+        .and_then(|c| c.strip_suffix('"'))
+        .ok_or("private creator should be quoted")?;
+    Ok((parse_group(group)?, creator, parse_offset(offset)?))
+}
+
+fn parse_group(text: &str) -> Result<GroupNumber, &'static str> {
+    let group = u16::from_str_radix(text.trim_start_matches("0x"), 16)
+        .map_err(|_| "invalid group number")?;
+    if group % 2 == 0 {
+        return Err("group number of a private attribute should be odd");
+    }
+    Ok(group)
+}
+
+/// Parse an element offset, either as `ee` or as `xxee`
+fn parse_offset(text: &str) -> Result<u8, &'static str> {
+    let text = match text.len() {
+        4 if text.starts_with("xx") => &text[2..],
+        _ => text,
+    };
+    if text.len() != 2 {
+        return Err("element offset should have 2 hexadecimal digits");
+    }
+    u8::from_str_radix(text, 16).map_err(|_| "invalid element offset")
+}
+
+pub(crate) fn parse_vr(text: &str) -> Result<VirtualVr, &'static str> {
+    match text {
+        "xs" => Ok(VirtualVr::Xs),
+        "ox" => Ok(VirtualVr::Ox),
+        "px" => Ok(VirtualVr::Px),
+        "lt" => Ok(VirtualVr::Lt),
+        "up" => Ok(VirtualVr::Exact(VR::UL)),
+        vr => vr
+            .parse::<VR>()
+            .map(VirtualVr::Exact)
+            .map_err(|_| "invalid value representation"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "\
+# a few vendor attributes
+(0029,\"SIEMENS CSA HEADER\",08)\tCS\tCSAImageHeaderType\t1\tPRIVATE
+(0029,\"SIEMENS CSA HEADER\",10)\tOB\tCSAImageHeaderInfo\t1\tPRIVATE
+
+(0019,\"GEMS_ACQU_01\",0c)\tSS\tGEFrequencyDirection\t1\tPRIVATE
+(2005,\"Philips MR Imaging DD 001\",xx0e)\tFL\tPhilipsSpectralSelectiveExcitationPulse\t1-n
+";
+
+    #[test]
+    fn read_tsv_dictionary() {
+        let dict = PrivateDictionary::from_tsv(TSV.as_bytes()).unwrap();
+        assert_eq!(dict.len(), 4);
+
+        let entry = dict.get("SIEMENS CSA HEADER", 0x0029, 0x10).unwrap();
+        assert_eq!(entry.alias, "CSAImageHeaderInfo");
+        assert_eq!(entry.vr, VirtualVr::Exact(VR::OB));
+        assert_eq!(entry.tag_in_block(0x11), Tag(0x0029, 0x1110));
+        assert_eq!(entry.tag_range().to_string(), "(0029,xx10)");
+
+        let entry = dict
+            .by_tag("Philips MR Imaging DD 001 ", Tag(0x2005, 0x140E))
+            .unwrap();
+        assert_eq!(entry.vm, Vm::ONE_OR_MORE);
+        assert_eq!(
+            dict.by_name("GEMS_ACQU_01", "GEFrequencyDirection")
+                .unwrap()
+                .tag_in_block(0x10),
+            Tag(0x0019, 0x100C)
+        );
+        assert_eq!(dict.by_tag("GEMS_ACQU_01", Tag(0x0019, 0x000C)), None);
+        assert_eq!(dict.by_tag("GEMS_ACQU_02", Tag(0x0019, 0x100C)), None);
+
+        let err = PrivateDictionary::from_tsv("(0028,\"X\",01)\tCS\tA\t1\n".as_bytes());
+        assert!(matches!(err, Err(Error::InvalidEntry { line: 1, .. })));
+        let err = PrivateDictionary::from_tsv("\n(0029,X,01)\tCS\tA\t1\n".as_bytes());
+        assert!(matches!(err, Err(Error::InvalidEntry { line: 2, .. })));
+        let err = PrivateDictionary::from_tsv("(0029,\"X\",01)\tCS\tA\n".as_bytes());
+        assert!(err.is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn read_json_dictionary() {
+        let json = r#"[
+            {
+                "creator": "SIEMENS CSA HEADER",
+                "group": "0029",
+                "element": "xx10",
+                "vr": "OB",
+                "keyword": "CSAImageHeaderInfo"
+            },
+            {
+                "creator": "GEMS_ACQU_01",
+                "group": "0019",
+                "element": "0c",
+                "vr": "SS",
+                "keyword": "GEFrequencyDirection",
+                "vm": "1-2"
+            }
+        ]"#;
+        let dict = PrivateDictionary::from_json(json.as_bytes()).unwrap();
+        assert_eq!(dict.len(), 2);
+        let entry = dict.get("GEMS_ACQU_01", 0x0019, 0x0C).unwrap();
+        assert_eq!(entry.vm, Vm::between(1, 2));
+        assert_eq!(
+            dict.get("SIEMENS CSA HEADER", 0x0029, 0x10).unwrap().vm,
+            Vm::ONE
+        );
+
+        let err = PrivateDictionary::from_json(r#"[{"creator": "A"}]"#.as_bytes());
+        assert!(matches!(err, Err(Error::InvalidJsonEntry { index: 0, .. })));
+    }
+
+    #[test]
+    fn register_dictionaries() {
+        let registry = PrivateDictionaryRegistry::default();
+        assert!(registry.is_empty());
+        registry.register(PrivateDictionary::from_tsv(TSV.as_bytes()).unwrap());
+        registry.register(
+            [PrivateDictionaryEntry {
+                creator: "SIEMENS CSA HEADER".to_string(),
+                group: 0x0029,
+                offset: 0x08,
+                alias: "ImageHeaderType".to_string(),
+                vr: VR::CS.into(),
+                vm: Vm::ONE,
+            }]
+            .into_iter()
+            .collect(),
+        );
+        assert_eq!(registry.len(), 4);
+        assert_eq!(
+            registry
+                .by_tag("SIEMENS CSA HEADER", Tag(0x0029, 0x1208))
+                .unwrap()
+                .alias,
+            "ImageHeaderType"
+        );
+    }
+}
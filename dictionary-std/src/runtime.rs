@@ -0,0 +1,588 @@
+//! Data element dictionaries loaded at run time.
+//!
+//! A [`RuntimeDataDictionary`] holds attribute entries
+//! read from external files,
+//! such as site specific attributes
+//! or attributes of a draft supplement to the standard,
+//! so that they can be recognized without recompiling the program.
+//! Entries can be read from a tab separated file
+//! in the format of DCMTK's `dicom.dic`,
+//! or from JSON (with the `json` Cargo feature).
+//!
+//! By default, the dictionary is layered over the [standard dictionary]:
+//! attributes not found in the loaded entries
+//! are looked up in the standard dictionary,
+//! and loaded entries take precedence over standard ones.
+//!
+//! [standard dictionary]: crate::StandardDataDictionary
+//!
+//! The dictionary can then be used wherever a data dictionary is expected,
+//! such as when opening a DICOM file
+//! with a custom dictionary in `dicom-object`.
+//!
+//! # Example
+//!
+//! ```
+//! use dicom_core::Tag;
+//! use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+//! use dicom_dictionary_std::runtime::RuntimeDataDictionary;
+//!
+//! let mut dict = RuntimeDataDictionary::new();
+//! dict.read_dic("(0009,0010)\tLO\tSiteStudyCode\t1\tSITE\n".as_bytes())?;
+//!
+//! // loaded attribute
+//! assert_eq!(dict.by_tag(Tag(0x0009, 0x0010)).map(|e| e.alias()), Some("SiteStudyCode"));
+//! // standard attribute
+//! assert_eq!(dict.by_name("PatientName").map(|e| e.tag()), Some(Tag(0x0010, 0x0010)));
+//! # Ok::<(), dicom_dictionary_std::runtime::Error>(())
+//! ```
+use crate::StandardDataDictionary;
+use crate::private::parse_vr;
+use dicom_core::Tag;
+use dicom_core::dictionary::{
+    DataDictionary, DataDictionaryEntryBuf, DataDictionaryEntryRef, TagRange, Vm,
+};
+use snafu::{Backtrace, ResultExt, Snafu};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    #[snafu(display("Could not read data dictionary"))]
+    ReadDictionary {
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Invalid data dictionary entry on line {}: {}", line, reason))]
+    InvalidEntry {
+        line: usize,
+        reason: String,
+        backtrace: Backtrace,
+    },
+    #[cfg(feature = "json")]
+    #[snafu(display("Invalid JSON data dictionary"))]
+    ParseJson {
+        source: serde_json::Error,
+        backtrace: Backtrace,
+    },
+    #[cfg(feature = "json")]
+    #[snafu(display("Invalid JSON data dictionary entry #{}: {}", index, reason))]
+    InvalidJsonEntry {
+        index: usize,
+        reason: String,
+        backtrace: Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A data element dictionary with entries loaded at run time,
+/// optionally layered over the standard data dictionary.
+///
+/// The dictionary is cheap to clone,
+/// as clones share the same entries until one of them is modified.
+/// Since the dictionary entries expose their aliases
+/// as static string slices,
+/// the text of loaded entries is kept
+/// for the remaining lifetime of the program.
+/// It is therefore intended for dictionaries
+/// which are loaded once, usually when the program starts.
+#[derive(Debug, Clone)]
+pub struct RuntimeDataDictionary {
+    registry: Arc<Registry>,
+}
+
+#[derive(Debug, Clone)]
+struct Registry {
+    entries: Vec<DataDictionaryEntryRef<'static>>,
+    /// the value multiplicity of each entry, if known
+    vms: Vec<Option<Vm>>,
+    /// mapping: tag → entry index, for single tag entries
+    by_tag: HashMap<Tag, usize>,
+    /// mapping: name → entry index
+    by_name: HashMap<&'static str, usize>,
+    /// indices of the entries with a tag range
+    repeating: Vec<usize>,
+    /// whether to fall back to the standard dictionary
+    standard: bool,
+}
+
+impl Default for RuntimeDataDictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeDataDictionary {
+    /// Create a dictionary without loaded entries,
+    /// layered over the standard data dictionary.
+    pub fn new() -> Self {
+        Self::with_standard(true)
+    }
+
+    /// Create a dictionary without loaded entries
+    /// which does not fall back to the standard data dictionary.
+    pub fn new_standalone() -> Self {
+        Self::with_standard(false)
+    }
+
+    fn with_standard(standard: bool) -> Self {
+        RuntimeDataDictionary {
+            registry: Arc::new(Registry {
+                entries: Vec::new(),
+                vms: Vec::new(),
+                by_tag: HashMap::new(),
+                by_name: HashMap::new(),
+                repeating: Vec::new(),
+                standard,
+            }),
+        }
+    }
+
+    /// Read a dictionary from a file,
+    /// layered over the standard data dictionary.
+    ///
+    /// See [`load_file`](Self::load_file) for the supported formats.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let mut dict = Self::new();
+        dict.load_file(path)?;
+        Ok(dict)
+    }
+
+    /// Whether attributes not loaded into this dictionary
+    /// are looked up in the standard data dictionary.
+    pub fn is_layered(&self) -> bool {
+        self.registry.standard
+    }
+
+    /// The number of entries loaded into this dictionary,
+    /// not counting those of the standard data dictionary.
+    pub fn len(&self) -> usize {
+        self.registry.entries.len()
+    }
+
+    /// Whether no entries were loaded into this dictionary.
+    pub fn is_empty(&self) -> bool {
+        self.registry.entries.is_empty()
+    }
+
+    /// Iterate over the entries loaded into this dictionary.
+    pub fn iter(&self) -> impl Iterator<Item = &DataDictionaryEntryRef<'static>> {
+        self.registry.entries.iter()
+    }
+
+    /// Add an entry to the dictionary,
+    /// replacing any loaded entry with the same tag or tag range.
+    ///
+    /// The value multiplicity of the attribute is left unknown.
+    /// See [`insert_with_vm`](Self::insert_with_vm).
+    pub fn insert(&mut self, entry: DataDictionaryEntryBuf) {
+        self.insert_impl(entry, None);
+    }
+
+    /// Add an entry to the dictionary
+    /// with the value multiplicity of the attribute,
+    /// replacing any loaded entry with the same tag or tag range.
+    pub fn insert_with_vm(&mut self, entry: DataDictionaryEntryBuf, vm: Vm) {
+        self.insert_impl(entry, Some(vm));
+    }
+
+    fn insert_impl(&mut self, entry: DataDictionaryEntryBuf, vm: Option<Vm>) {
+        let registry = Arc::make_mut(&mut self.registry);
+        let entry = DataDictionaryEntryRef {
+            tag: entry.tag,
+            alias: Box::leak(entry.alias.into_boxed_str()),
+            vr: entry.vr,
+        };
+
+        let existing = match entry.tag {
+            TagRange::Single(tag) => registry.by_tag.get(&tag).copied(),
+            range => registry
+                .repeating
+                .iter()
+                .copied()
+                .find(|&i| registry.entries[i].tag == range),
+        };
+        let index = if let Some(index) = existing {
+            registry.by_name.remove(registry.entries[index].alias);
+            registry.entries[index] = entry;
+            registry.vms[index] = vm;
+            index
+        } else {
+            registry.entries.push(entry);
+            registry.vms.push(vm);
+            let index = registry.entries.len() - 1;
+            match registry.entries[index].tag {
+                TagRange::Single(tag) => {
+                    registry.by_tag.insert(tag, index);
+                }
+                _ => registry.repeating.push(index),
+            }
+            index
+        };
+        registry
+            .by_name
+            .insert(registry.entries[index].alias, index);
+    }
+
+    /// Read entries in the tab separated format of DCMTK's `dicom.dic`
+    /// into the dictionary.
+    ///
+    /// Each line holds the tag or tag range,
+    /// the value representation, the alias,
+    /// the value multiplicity, and optionally the source of the entry,
+    /// separated by tabs.
+    /// Tags may have open digits, as in `(60xx,0010)`,
+    /// or ranges of digits, as in `(6000-60FF,0010)`.
+    /// Empty lines and lines starting with `#` are ignored,
+    /// as are entries for items and delimiters (VR `na`)
+    /// and entries sourced from `PRIVATE`, `GENERIC` or `ILLEGAL`.
+    /// Aliases prefixed with `RETIRED_` are recorded without the prefix.
+    /// For example (with tabs shown as `→`):
+    ///
+    /// ```none
+    /// (0009,0010)→LO→SiteStudyCode→1→SITE
+    /// ```
+    pub fn read_dic(&mut self, reader: impl BufRead) -> Result<()> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.context(ReadDictionarySnafu)?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| {
+                InvalidEntrySnafu {
+                    line: i + 1,
+                    reason,
+                }
+                .build()
+            };
+            let fields: Vec<_> = line.split('\t').map(str::trim).collect();
+            let [tag, vr, alias, vm, rest @ ..] = &fields[..] else {
+                return Err(invalid("expected at least 4 tab separated fields"));
+            };
+            if matches!(rest.first(), Some(&("PRIVATE" | "GENERIC" | "ILLEGAL"))) || *vr == "na" {
+                continue;
+            }
+            let entry = DataDictionaryEntryBuf {
+                tag: parse_tag_range(tag).map_err(invalid)?,
+                alias: alias.trim_start_matches("RETIRED_").to_string(),
+                vr: parse_vr(vr).map_err(invalid)?,
+            };
+            let vm = vm
+                .parse()
+                .map_err(|_| invalid(&format!("invalid value multiplicity `{vm}`")))?;
+            self.insert_with_vm(entry, vm);
+        }
+        Ok(())
+    }
+
+    /// Read entries from JSON into the dictionary.
+    ///
+    /// The JSON document is an array of objects
+    /// with the tag or tag range, value representation, keyword,
+    /// and optionally the value multiplicity (`1` by default).
+    /// Tags are written as in [`read_dic`](Self::read_dic),
+    /// with or without parentheses.
+    ///
+    /// ```json
+    /// [
+    ///   {
+    ///     "tag": "(0009,0010)",
+    ///     "vr": "LO",
+    ///     "keyword": "SiteStudyCode",
+    ///     "vm": "1"
+    ///   }
+    /// ]
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn read_json(&mut self, reader: impl std::io::Read) -> Result<()> {
+        use dicom_core::dictionary::Vm;
+        use serde_json::Value;
+
+        let json: Vec<serde_json::Map<String, Value>> =
+            serde_json::from_reader(reader).context(ParseJsonSnafu)?;
+        for (index, entry) in json.into_iter().enumerate() {
+            let invalid = |reason: &str| {
+                InvalidJsonEntrySnafu {
+                    index,
+                    reason: reason.to_string(),
+                }
+                .build()
+            };
+            let field = |name: &str| -> Result<&str> {
+                entry
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| invalid(&format!("missing string field `{name}`")))
+            };
+            let tag = parse_tag_range(field("tag")?).map_err(invalid)?;
+            let vr = parse_vr(field("vr")?).map_err(invalid)?;
+            let alias = field("keyword").or_else(|_| field("alias"))?;
+            let vm = match entry.get("vm").and_then(Value::as_str) {
+                Some(vm) => vm
+                    .parse()
+                    .map_err(|_| invalid(&format!("invalid value multiplicity `{vm}`")))?,
+                None => Vm::ONE,
+            };
+            self.insert_with_vm(
+                DataDictionaryEntryBuf {
+                    tag,
+                    alias: alias.to_string(),
+                    vr,
+                },
+                vm,
+            );
+        }
+        Ok(())
+    }
+
+    /// Read entries from a file into the dictionary,
+    /// as JSON if the file name ends with `.json`
+    /// (requires the `json` Cargo feature),
+    /// or in the format of DCMTK's `dicom.dic` otherwise.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).context(ReadDictionarySnafu)?;
+        let reader = std::io::BufReader::new(file);
+        #[cfg(feature = "json")]
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            return self.read_json(reader);
+        }
+        self.read_dic(reader)
+    }
+}
+
+impl DataDictionary for RuntimeDataDictionary {
+    type Entry = DataDictionaryEntryRef<'static>;
+
+    fn by_name(&self, name: &str) -> Option<&Self::Entry> {
+        let registry = &*self.registry;
+        registry
+            .by_name
+            .get(name)
+            .map(|&i| &registry.entries[i])
+            .or_else(|| {
+                if registry.standard {
+                    StandardDataDictionary.by_name(name)
+                } else {
+                    None
+                }
+            })
+    }
+
+    fn by_tag(&self, tag: Tag) -> Option<&Self::Entry> {
+        let registry = &*self.registry;
+        match registry.index_of(tag) {
+            Some(i) => Some(&registry.entries[i]),
+            None if registry.standard => StandardDataDictionary.by_tag(tag),
+            None => None,
+        }
+    }
+
+    fn vm_by_tag(&self, tag: Tag) -> Option<Vm> {
+        let registry = &*self.registry;
+        match registry.index_of(tag) {
+            Some(i) => registry.vms[i],
+            None if registry.standard => StandardDataDictionary.vm_by_tag(tag),
+            None => None,
+        }
+    }
+}
+
+impl Registry {
+    /// The index of the loaded entry for the given tag
+    fn index_of(&self, tag: Tag) -> Option<usize> {
+        self.by_tag.get(&tag).copied().or_else(|| {
+            self.repeating
+                .iter()
+                .copied()
+                .find(|&i| self.entries[i].tag.contains(tag))
+        })
+    }
+}
+
+/// Parse a tag or tag range,
+/// also accepting DCMTK's digit ranges such as `(6000-60FF,0010)`
+fn parse_tag_range(text: &str) -> Result<TagRange, &'static str> {
+    let text = text
+        .strip_prefix('(')
+        .and_then(|t| t.strip_suffix(')'))
+        .unwrap_or(text);
+    if text.contains('"') {
+        return Err("private attributes belong in a private dictionary");
+    }
+    let (group, element) = match text.split_once(',') {
+        Some(parts) => parts,
+        // no separator, as in `ggggeeee`
+        None if text.len() == 8 && text.is_char_boundary(4) => text.split_at(4),
+        None => return Err("invalid tag"),
+    };
+    let group = open_digits(group)?;
+    let element = open_digits(element)?;
+    format!("{group},{element}")
+        .parse()
+        .map_err(|_| "invalid tag")
+}
+
+/// Turn a range of hexadecimal digits such as `6000-60FF`
+/// into the equivalent text with open digits, such as `60xx`
+fn open_digits(text: &str) -> Result<String, &'static str> {
+    let Some((start, end)) = text.split_once('-') else {
+        return Ok(text.to_ascii_uppercase().replace('X', "x"));
+    };
+    if start.len() != 4 || end.len() != 4 {
+        return Err("invalid tag range");
+    }
+    let mut open = false;
+    start
+        .chars()
+        .zip(end.chars())
+        .map(|(a, b)| {
+            let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+            if a == b && !open {
+                Ok(a)
+            } else if a == '0' && b == 'F' {
+                open = true;
+                Ok('x')
+            } else {
+                Err("unsupported tag range")
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::VR;
+    use dicom_core::dictionary::{DataDictionaryEntry, VirtualVr, Vm};
+
+    const DIC: &str = "\
+# site specific attributes
+(0009,0010)\tLO\tSiteStudyCode\t1\tSITE
+(0009,1001)\tDS\tSiteCalibrationFactors\t2-2n\tSITE
+(7000-70FF,0010)\tUS\tSiteOverlayRows\t1\tSITE
+(0011,10xx)\tSH\tSiteLabels\t1-n\tSITE
+(0010,0010)\tPN\tRETIRED_NameOfPatient\t1\tDRAFT
+
+(fffe,e000)\tna\tItem\t1\tDICOM
+(0029,\"SOME CREATOR\",01)\tCS\tSomething\t1\tPRIVATE
+";
+
+    #[test]
+    fn read_dic_layered_over_standard() {
+        let mut dict = RuntimeDataDictionary::new();
+        dict.read_dic(DIC.as_bytes()).unwrap();
+        assert_eq!(dict.len(), 5);
+        assert!(dict.is_layered());
+
+        let entry = dict.by_tag(Tag(0x0009, 0x1001)).unwrap();
+        assert_eq!(entry.alias, "SiteCalibrationFactors");
+        assert_eq!(entry.vr, VirtualVr::Exact(VR::DS));
+        assert_eq!(
+            dict.vm_by_tag(Tag(0x0009, 0x1001)).map(|vm| vm.to_string()),
+            Some("2-2n".to_string())
+        );
+
+        assert_eq!(
+            dict.by_tag(Tag(0x7042, 0x0010)).map(|e| e.alias),
+            Some("SiteOverlayRows")
+        );
+        assert_eq!(
+            dict.by_tag(Tag(0x0011, 0x1042)).map(|e| e.alias),
+            Some("SiteLabels")
+        );
+        assert_eq!(
+            dict.by_name("SiteLabels").map(|e| e.tag),
+            Some(TagRange::Element100(Tag(0x0011, 0x1000)))
+        );
+
+        // loaded entries take precedence
+        assert_eq!(
+            dict.by_tag(Tag(0x0010, 0x0010)).map(|e| e.alias),
+            Some("NameOfPatient")
+        );
+        // the standard dictionary still resolves the other attributes
+        assert_eq!(
+            dict.by_name("PatientID").map(|e| e.tag()),
+            Some(Tag(0x0010, 0x0020))
+        );
+        assert_eq!(dict.by_tag(Tag(0xFFFE, 0xE000)), None);
+
+        // clones share the entries until modified
+        let mut other = dict.clone();
+        other.insert(DataDictionaryEntryBuf {
+            tag: TagRange::Single(Tag(0x0009, 0x0010)),
+            alias: "SiteStudyIdentifier".to_string(),
+            vr: VR::LO.into(),
+        });
+        assert_eq!(other.len(), 5);
+        assert_eq!(other.vm_by_tag(Tag(0x0009, 0x0010)), None);
+        assert_eq!(dict.vm_by_tag(Tag(0x0009, 0x0010)), Some(Vm::ONE));
+        assert_eq!(other.by_name("SiteStudyCode"), None);
+        assert_eq!(
+            dict.by_tag(Tag(0x0009, 0x0010)).map(|e| e.alias),
+            Some("SiteStudyCode")
+        );
+
+        let standalone = RuntimeDataDictionary::new_standalone();
+        assert_eq!(standalone.by_name("PatientName"), None);
+    }
+
+    #[test]
+    fn parse_tag_ranges() {
+        assert_eq!(
+            parse_tag_range("(6000-60FF,3000)"),
+            Ok(TagRange::Group100(Tag(0x6000, 0x3000)))
+        );
+        assert_eq!(
+            parse_tag_range("6001-61ff,0010"),
+            Err("unsupported tag range")
+        );
+        assert_eq!(
+            parse_tag_range("00091001"),
+            Ok(TagRange::Single(Tag(0x0009, 0x1001)))
+        );
+        assert_eq!(
+            parse_tag_range("(1000,xxx0)"),
+            Ok(TagRange::Masked {
+                tag: Tag(0x1000, 0x0000),
+                mask: Tag(0xFFFF, 0x000F)
+            })
+        );
+        assert!(parse_tag_range("(0029,\"CREATOR\",01)").is_err());
+
+        let mut dict = RuntimeDataDictionary::new_standalone();
+        let err = dict.read_dic("(0009,0010)\tLO\tSiteStudyCode\tmany\n".as_bytes());
+        assert!(matches!(err, Err(Error::InvalidEntry { line: 1, .. })));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn read_json_dictionary() {
+        let mut dict = RuntimeDataDictionary::new_standalone();
+        dict.read_json(
+            r#"[
+                {"tag": "(0009,0010)", "vr": "LO", "keyword": "SiteStudyCode"},
+                {"tag": "0009xx20", "vr": "xs", "keyword": "SiteValues", "vm": "1-n"}
+            ]"#
+            .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(dict.len(), 2);
+        assert_eq!(dict.vm_by_tag(Tag(0x0009, 0x0010)), Some(Vm::ONE));
+        let entry = dict.by_name("SiteValues").unwrap();
+        assert_eq!(entry.vr, VirtualVr::Xs);
+        assert!(entry.tag.contains(Tag(0x0009, 0x4220)));
+
+        let err = dict.read_json(r#"[{"tag": "(0009,0030)"}]"#.as_bytes());
+        assert!(matches!(err, Err(Error::InvalidJsonEntry { index: 0, .. })));
+    }
+}
@@ -30,7 +30,7 @@ pub struct StandardUidRegistry {
 }
 
 impl StandardUidRegistry {
-    fn new() -> StandardUidRegistry {
+    pub(crate) fn new() -> StandardUidRegistry {
         StandardUidRegistry {
             by_keyword: HashMap::new(),
             by_uid: HashMap::new(),
@@ -38,7 +38,10 @@ impl StandardUidRegistry {
     }
 
     /// record all of the given dictionary entries
-    fn index_all(&mut self, entries: &'static [UidDictionaryEntryRef<'static>]) -> &mut Self {
+    pub(crate) fn index_all(
+        &mut self,
+        entries: &'static [UidDictionaryEntryRef<'static>],
+    ) -> &mut Self {
         let entries_by_keyword = entries.iter().map(|e| (e.alias, e));
         self.by_keyword.extend(entries_by_keyword);
 
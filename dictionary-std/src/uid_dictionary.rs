@@ -0,0 +1,146 @@
+//! Dictionary of all normative DICOM unique identifiers
+
+use dicom_core::dictionary::{UidDictionary, UidDictionaryEntryRef, UidType};
+use once_cell::sync::Lazy;
+
+use crate::sop_class::StandardUidRegistry;
+
+static DICT: Lazy<StandardUidRegistry> = Lazy::new(init_dictionary);
+
+/// Retrieve a singleton instance of the standard UID registry.
+///
+/// Note that one does not generally have to call this
+/// unless when retrieving the underlying registry is important.
+/// The unit type [`StandardUidDictionary`]
+/// already provides a lazy loaded singleton implementing the necessary traits.
+#[inline]
+pub fn registry() -> &'static StandardUidRegistry {
+    &DICT
+}
+
+/// A UID dictionary which consults
+/// the library's global registry of normative DICOM UIDs:
+/// SOP classes, meta SOP classes, transfer syntaxes,
+/// well-known SOP instances,
+/// and well-known (synchronization) frames of reference.
+///
+/// Unlike [`StandardSopClassDictionary`](crate::StandardSopClassDictionary),
+/// UIDs are looked up regardless of their category,
+/// which can be inspected through the entry's `type`.
+/// Trailing spaces and null characters in the UID are ignored,
+/// so that values read from a DICOM object can be passed as is.
+///
+/// The dictionary index is automatically initialized upon the first use.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_core::dictionary::{UidDictionary, UidType};
+/// # use dicom_dictionary_std::StandardUidDictionary;
+/// let entry = StandardUidDictionary.by_uid("1.2.840.10008.1.2.1\0").unwrap();
+/// assert_eq!(entry.name, "Explicit VR Little Endian");
+/// assert_eq!(entry.r#type, UidType::TransferSyntax);
+///
+/// let entry = StandardUidDictionary.by_keyword("CTImageStorage").unwrap();
+/// assert_eq!(entry.uid, "1.2.840.10008.5.1.4.1.1.2");
+/// assert_eq!(
+///     StandardUidDictionary.name_of("1.2.840.10008.1.4.1.1"),
+///     Some("Talairach Brain Atlas Frame of Reference"),
+/// );
+/// ```
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct StandardUidDictionary;
+
+impl StandardUidDictionary {
+    /// Fetch the full name of the given UID, if it is known.
+    #[inline]
+    pub fn name_of(&self, uid: &str) -> Option<&'static str> {
+        DICT.by_uid(trim_uid(uid)).map(|e| e.name)
+    }
+
+    /// Fetch an entry by its UID, only if it is of the given category.
+    pub fn by_uid_of_type(
+        &self,
+        uid: &str,
+        r#type: UidType,
+    ) -> Option<&'static UidDictionaryEntryRef<'static>> {
+        DICT.by_uid(trim_uid(uid)).filter(|e| e.r#type == r#type)
+    }
+}
+
+impl UidDictionary for StandardUidDictionary {
+    type Entry = UidDictionaryEntryRef<'static>;
+
+    #[inline]
+    fn by_keyword(&self, keyword: &str) -> Option<&Self::Entry> {
+        DICT.by_keyword(keyword)
+    }
+
+    #[inline]
+    fn by_uid(&self, uid: &str) -> Option<&Self::Entry> {
+        DICT.by_uid(trim_uid(uid))
+    }
+}
+
+#[inline]
+fn trim_uid(uid: &str) -> &str {
+    uid.trim_end_matches(['\0', ' '])
+}
+
+fn init_dictionary() -> StandardUidRegistry {
+    let mut d = StandardUidRegistry::new();
+
+    d.index_all(crate::uids::SOP_CLASSES)
+        .index_all(crate::uids::META_SOP_CLASSES)
+        .index_all(crate::uids::TRANSFER_SYNTAXES)
+        .index_all(crate::uids::WELL_KNOWN_SOP_INSTANCES)
+        .index_all(crate::uids::SYNCHRONIZATION_FRAME_OF_REFERENCES)
+        .index_all(crate::uids::WELL_KNOWN_FRAMES_OF_REFERENCE);
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StandardUidDictionary;
+    use dicom_core::dictionary::{UidDictionary, UidType};
+
+    #[test]
+    fn can_fetch_uids_of_all_categories() {
+        let dict = StandardUidDictionary;
+
+        let entry = dict.by_uid("1.2.840.10008.5.1.4.1.1.4").unwrap();
+        assert_eq!(entry.alias, "MRImageStorage");
+        assert_eq!(entry.r#type, UidType::SopClass);
+
+        let entry = dict.by_keyword("JPEG2000Lossless").unwrap();
+        assert_eq!(entry.uid, crate::uids::JPEG2000_LOSSLESS);
+        assert_eq!(entry.r#type, UidType::TransferSyntax);
+
+        let entry = dict.by_uid(crate::uids::MODALITY_WORKLIST_INFORMATION_MODEL_FIND);
+        assert_eq!(
+            entry.map(|e| e.name),
+            Some("Modality Worklist Information Model - FIND")
+        );
+
+        let entry = dict.by_uid("1.2.840.10008.5.1.1.18 ").unwrap();
+        assert_eq!(entry.r#type, UidType::MetaSopClass);
+        assert_eq!(
+            dict.by_uid("1.2.840.10008.1.20.1.1").map(|e| e.r#type),
+            Some(UidType::WellKnownSopInstance)
+        );
+        assert_eq!(
+            dict.by_uid("1.2.840.10008.15.1.1").map(|e| e.r#type),
+            Some(UidType::SynchronizationFrameOfReference)
+        );
+        assert_eq!(
+            dict.by_uid(crate::uids::ICBM452T1).map(|e| e.r#type),
+            Some(UidType::WellKnownFrameOfReference)
+        );
+
+        assert_eq!(
+            dict.by_uid_of_type(crate::uids::VERIFICATION, UidType::TransferSyntax),
+            None
+        );
+        assert_eq!(dict.name_of("1.2.3.4"), None);
+    }
+}
@@ -230,6 +230,90 @@ pub const STORAGE_COMMITMENT_PULL_MODEL_INSTANCE: &str = "1.2.840.10008.1.20.2.1
 /// SOP Class: Media Storage Directory Storage
 #[rustfmt::skip]
 pub const MEDIA_STORAGE_DIRECTORY_STORAGE: &str = "1.2.840.10008.1.3.10";
+/// Well-known Frame of Reference: Talairach Brain Atlas Frame of Reference
+#[rustfmt::skip]
+pub const TALAIRACH_BRAIN_ATLAS: &str = "1.2.840.10008.1.4.1.1";
+/// Well-known Frame of Reference: SPM2 GRAY Frame of Reference
+#[rustfmt::skip]
+pub const SPM2GRAY: &str = "1.2.840.10008.1.4.1.10";
+/// Well-known Frame of Reference: SPM2 WHITE Frame of Reference
+#[rustfmt::skip]
+pub const SPM2WHITE: &str = "1.2.840.10008.1.4.1.11";
+/// Well-known Frame of Reference: SPM2 CSF Frame of Reference
+#[rustfmt::skip]
+pub const SPM2CSF: &str = "1.2.840.10008.1.4.1.12";
+/// Well-known Frame of Reference: SPM2 BRAINMASK Frame of Reference
+#[rustfmt::skip]
+pub const SPM2BRAINMASK: &str = "1.2.840.10008.1.4.1.13";
+/// Well-known Frame of Reference: SPM2 AVG305T1 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2AVG305T1: &str = "1.2.840.10008.1.4.1.14";
+/// Well-known Frame of Reference: SPM2 AVG152T1 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2AVG152T1: &str = "1.2.840.10008.1.4.1.15";
+/// Well-known Frame of Reference: SPM2 AVG152T2 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2AVG152T2: &str = "1.2.840.10008.1.4.1.16";
+/// Well-known Frame of Reference: SPM2 AVG152PD Frame of Reference
+#[rustfmt::skip]
+pub const SPM2AVG152PD: &str = "1.2.840.10008.1.4.1.17";
+/// Well-known Frame of Reference: SPM2 SINGLESUBJT1 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2SINGLESUBJT1: &str = "1.2.840.10008.1.4.1.18";
+/// Well-known Frame of Reference: SPM2 T1 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2T1: &str = "1.2.840.10008.1.4.1.2";
+/// Well-known Frame of Reference: SPM2 T2 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2T2: &str = "1.2.840.10008.1.4.1.3";
+/// Well-known Frame of Reference: SPM2 PD Frame of Reference
+#[rustfmt::skip]
+pub const SPM2PD: &str = "1.2.840.10008.1.4.1.4";
+/// Well-known Frame of Reference: SPM2 EPI Frame of Reference
+#[rustfmt::skip]
+pub const SPM2EPI: &str = "1.2.840.10008.1.4.1.5";
+/// Well-known Frame of Reference: SPM2 FIL T1 Frame of Reference
+#[rustfmt::skip]
+pub const SPM2FILT1: &str = "1.2.840.10008.1.4.1.6";
+/// Well-known Frame of Reference: SPM2 PET Frame of Reference
+#[rustfmt::skip]
+pub const SPM2PET: &str = "1.2.840.10008.1.4.1.7";
+/// Well-known Frame of Reference: SPM2 TRANSM Frame of Reference
+#[rustfmt::skip]
+pub const SPM2TRANSM: &str = "1.2.840.10008.1.4.1.8";
+/// Well-known Frame of Reference: SPM2 SPECT Frame of Reference
+#[rustfmt::skip]
+pub const SPM2SPECT: &str = "1.2.840.10008.1.4.1.9";
+/// Well-known Frame of Reference: ICBM 452 T1 Frame of Reference
+#[rustfmt::skip]
+pub const ICBM452T1: &str = "1.2.840.10008.1.4.2.1";
+/// Well-known Frame of Reference: ICBM Single Subject MRI Frame of Reference
+#[rustfmt::skip]
+pub const ICBM_SINGLE_SUBJECT_MRI: &str = "1.2.840.10008.1.4.2.2";
+/// Well-known Frame of Reference: IEC 61217 Fixed Coordinate System Frame of Reference
+#[rustfmt::skip]
+pub const IEC61217_FIXED_COORDINATE_SYSTEM: &str = "1.2.840.10008.1.4.3.1";
+/// Well-known Frame of Reference: Standard Robotic-Arm Coordinate System Frame of Reference
+#[rustfmt::skip]
+pub const STANDARD_ROBOTIC_ARM_COORDINATE_SYSTEM: &str = "1.2.840.10008.1.4.3.2";
+/// Well-known Frame of Reference: IEC 61217 Table Top Coordinate System Frame of Reference
+#[rustfmt::skip]
+pub const IEC61217_TABLE_TOP_COORDINATE_SYSTEM: &str = "1.2.840.10008.1.4.3.3";
+/// Well-known Frame of Reference: SRI24 Frame of Reference
+#[rustfmt::skip]
+pub const SRI24: &str = "1.2.840.10008.1.4.4.1";
+/// Well-known Frame of Reference: Colin27 Frame of Reference
+#[rustfmt::skip]
+pub const COLIN27: &str = "1.2.840.10008.1.4.5.1";
+/// Well-known Frame of Reference: LPBA40/AIR Frame of Reference
+#[rustfmt::skip]
+pub const LPBA40AIR: &str = "1.2.840.10008.1.4.6.1";
+/// Well-known Frame of Reference: LPBA40/FLIRT Frame of Reference
+#[rustfmt::skip]
+pub const LPBA40FLIRT: &str = "1.2.840.10008.1.4.6.2";
+/// Well-known Frame of Reference: LPBA40/SPM5 Frame of Reference
+#[rustfmt::skip]
+pub const LPBA40SPM5: &str = "1.2.840.10008.1.4.6.3";
 /// SOP Class: Procedural Event Logging SOP Class
 #[rustfmt::skip]
 pub const PROCEDURAL_EVENT_LOGGING: &str = "1.2.840.10008.1.40";
@@ -1993,3 +2077,36 @@ pub(crate) const LDAP_OIDS: &[E] = &[
 pub(crate) const SYNCHRONIZATION_FRAME_OF_REFERENCES: &[E] = &[
     E::new("1.2.840.10008.15.1.1", "Universal Coordinated Time", "UTC", SynchronizationFrameOfReference, false),
 ];
+
+#[rustfmt::skip]
+#[cfg(feature = "well-known-frame-of-reference")]
+pub(crate) const WELL_KNOWN_FRAMES_OF_REFERENCE: &[E] = &[
+    E::new("1.2.840.10008.1.4.1.1", "Talairach Brain Atlas Frame of Reference", "TalairachBrainAtlas", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.10", "SPM2 GRAY Frame of Reference", "SPM2GRAY", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.11", "SPM2 WHITE Frame of Reference", "SPM2WHITE", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.12", "SPM2 CSF Frame of Reference", "SPM2CSF", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.13", "SPM2 BRAINMASK Frame of Reference", "SPM2BRAINMASK", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.14", "SPM2 AVG305T1 Frame of Reference", "SPM2AVG305T1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.15", "SPM2 AVG152T1 Frame of Reference", "SPM2AVG152T1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.16", "SPM2 AVG152T2 Frame of Reference", "SPM2AVG152T2", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.17", "SPM2 AVG152PD Frame of Reference", "SPM2AVG152PD", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.18", "SPM2 SINGLESUBJT1 Frame of Reference", "SPM2SINGLESUBJT1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.2", "SPM2 T1 Frame of Reference", "SPM2T1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.3", "SPM2 T2 Frame of Reference", "SPM2T2", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.4", "SPM2 PD Frame of Reference", "SPM2PD", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.5", "SPM2 EPI Frame of Reference", "SPM2EPI", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.6", "SPM2 FIL T1 Frame of Reference", "SPM2FILT1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.7", "SPM2 PET Frame of Reference", "SPM2PET", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.8", "SPM2 TRANSM Frame of Reference", "SPM2TRANSM", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.1.9", "SPM2 SPECT Frame of Reference", "SPM2SPECT", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.2.1", "ICBM 452 T1 Frame of Reference", "ICBM452T1", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.2.2", "ICBM Single Subject MRI Frame of Reference", "ICBMSingleSubjectMRI", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.3.1", "IEC 61217 Fixed Coordinate System Frame of Reference", "IEC61217FixedCoordinateSystem", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.3.2", "Standard Robotic-Arm Coordinate System Frame of Reference", "StandardRoboticArmCoordinateSystem", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.3.3", "IEC 61217 Table Top Coordinate System Frame of Reference", "IEC61217TableTopCoordinateSystem", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.4.1", "SRI24 Frame of Reference", "SRI24", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.5.1", "Colin27 Frame of Reference", "Colin27", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.6.1", "LPBA40/AIR Frame of Reference", "LPBA40AIR", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.6.2", "LPBA40/FLIRT Frame of Reference", "LPBA40FLIRT", WellKnownFrameOfReference, false),
+    E::new("1.2.840.10008.1.4.6.3", "LPBA40/SPM5 Frame of Reference", "LPBA40SPM5", WellKnownFrameOfReference, false),
+];
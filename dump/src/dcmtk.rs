@@ -0,0 +1,486 @@
+//! Dumping in the output format of DCMTK's `dcmdump`
+//!
+//! Each element is printed in a single line of the form
+//! `(gggg,eeee) VR value # length, vm Name`,
+//! with the value padded to 40 characters,
+//! so that the output can be compared to the one of `dcmdump`
+//! with the usual text processing tools.
+//!
+//! Differences which remain:
+//! well-known UIDs are replaced with the keywords of this library's UID dictionary,
+//! which do not always match DCMTK's (e.g. for transfer syntaxes),
+//! and floating point numbers are printed
+//! with the shortest representation which reads back to the same value.
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::header::{HasLength, Header, Length};
+use dicom_core::value::{PrimitiveValue, Value as DicomValue};
+use dicom_core::{Tag, VR};
+#[cfg(feature = "sop-class")]
+use dicom_dictionary_std::StandardUidDictionary;
+use dicom_dictionary_std::uids;
+use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_object::mem::{InMemDicomObject, InMemElement};
+use dicom_object::{FileDicomObject, StandardDataDictionary};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use std::io::{Result as IoResult, Write};
+
+/// The width of the value column, as in `dcmdump`
+const VALUE_WIDTH: usize = 40;
+
+/// The maximum length of a printed value
+/// before it is shortened (`dcmdump`'s `+L` option disables this)
+const MAX_VALUE_LENGTH: usize = 70;
+
+/// How values are shortened in the output.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Limits {
+    /// do not shorten text values
+    pub no_text_limit: bool,
+    /// do not shorten any values
+    pub no_limit: bool,
+}
+
+impl Limits {
+    fn max_length(&self, vr: VR) -> Option<usize> {
+        let is_text = matches!(
+            vr,
+            VR::AE
+                | VR::AS
+                | VR::CS
+                | VR::DA
+                | VR::DS
+                | VR::DT
+                | VR::IS
+                | VR::LO
+                | VR::LT
+                | VR::PN
+                | VR::SH
+                | VR::ST
+                | VR::TM
+                | VR::UC
+                | VR::UI
+                | VR::UR
+                | VR::UT
+        );
+        if self.no_limit || (is_text && self.no_text_limit) {
+            None
+        } else {
+            Some(MAX_VALUE_LENGTH)
+        }
+    }
+}
+
+/// Dump a DICOM file, both file meta group and main data set.
+pub(crate) fn dump_file<W, D>(
+    to: &mut W,
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    limits: Limits,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    writeln!(to)?;
+    writeln!(to, "# Dicom-File-Format")?;
+    writeln!(to)?;
+    writeln!(to, "# Dicom-Meta-Information-Header")?;
+    writeln!(
+        to,
+        "# Used TransferSyntax: {}",
+        transfer_syntax_name(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+    )?;
+    for elem in obj.meta().to_element_iter() {
+        let DicomValue::Primitive(value) = elem.value() else {
+            continue;
+        };
+        let tag = elem.tag();
+        dump_primitive(
+            to,
+            0,
+            tag,
+            elem.vr(),
+            value,
+            Length::defined(value.calculate_byte_len() as u32),
+            element_name(tag, None),
+            limits,
+        )?;
+    }
+    writeln!(to)?;
+    writeln!(to, "# Dicom-Data-Set")?;
+    writeln!(
+        to,
+        "# Used TransferSyntax: {}",
+        transfer_syntax_name(obj.meta().transfer_syntax())
+    )?;
+    dump_object(to, obj, 0, limits)
+}
+
+/// Dump the elements of a DICOM object at the given nesting level.
+pub(crate) fn dump_object<W, D>(
+    to: &mut W,
+    obj: &InMemDicomObject<D>,
+    level: usize,
+    limits: Limits,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    for elem in obj {
+        let private_entry = obj.private_entry(elem.tag());
+        let name = element_name(elem.tag(), private_entry.as_ref().map(|e| e.alias.as_str()));
+        dump_element(to, elem, name, level, limits)?;
+    }
+    Ok(())
+}
+
+fn dump_element<W, D>(
+    to: &mut W,
+    elem: &InMemElement<D>,
+    name: &str,
+    level: usize,
+    limits: Limits,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    let tag = elem.tag();
+    let len = elem.header().len;
+    match elem.value() {
+        DicomValue::Primitive(value) => {
+            dump_primitive(to, level, tag, elem.vr(), value, len, name, limits)
+        }
+        DicomValue::Sequence(seq) => {
+            let items = seq.items();
+            let info = format!(
+                "(Sequence with {} length #={})",
+                if len.is_undefined() {
+                    "undefined"
+                } else {
+                    "explicit"
+                },
+                items.len()
+            );
+            write_line(to, level, tag, "SQ", &info, len, 1, name)?;
+            for item in items {
+                let item_len = item.length();
+                let info = format!(
+                    "(Item with {} length #={})",
+                    if item_len.is_undefined() {
+                        "undefined"
+                    } else {
+                        "explicit"
+                    },
+                    item.into_iter().count()
+                );
+                write_line(
+                    to,
+                    level + 1,
+                    Tag(0xFFFE, 0xE000),
+                    "na",
+                    &info,
+                    item_len,
+                    1,
+                    "Item",
+                )?;
+                dump_object(to, item, level + 2, limits)?;
+                let info = if item_len.is_undefined() {
+                    "(ItemDelimitationItem)"
+                } else {
+                    "(ItemDelimitationItem for re-encoding)"
+                };
+                write_line(
+                    to,
+                    level + 1,
+                    Tag(0xFFFE, 0xE00D),
+                    "na",
+                    info,
+                    Length(0),
+                    0,
+                    "ItemDelimitationItem",
+                )?;
+            }
+            let info = if len.is_undefined() {
+                "(SequenceDelimitationItem)"
+            } else {
+                "(SequenceDelimitationItem for re-encod.)"
+            };
+            write_line(
+                to,
+                level,
+                Tag(0xFFFE, 0xE0DD),
+                "na",
+                info,
+                Length(0),
+                0,
+                "SequenceDelimitationItem",
+            )
+        }
+        DicomValue::PixelSequence(seq) => {
+            let fragments = seq.fragments();
+            let info = format!("(PixelSequence #={})", fragments.len() + 1);
+            write_line(
+                to,
+                level,
+                tag,
+                elem.vr().to_string(),
+                &info,
+                Length::UNDEFINED,
+                1,
+                name,
+            )?;
+            let offset_table: Vec<u8> = seq
+                .offset_table()
+                .iter()
+                .flat_map(|offset| offset.to_le_bytes())
+                .collect();
+            for fragment in
+                std::iter::once(&offset_table[..]).chain(fragments.iter().map(|f| &f[..]))
+            {
+                let info = if fragment.is_empty() {
+                    "(no value available)".to_string()
+                } else {
+                    join_values(
+                        fragment.iter().map(|b| format!("{b:02x}")),
+                        limits.max_length(VR::OB),
+                    )
+                };
+                write_line(
+                    to,
+                    level + 1,
+                    Tag(0xFFFE, 0xE000),
+                    "pi",
+                    &info,
+                    Length(fragment.len() as u32),
+                    1,
+                    "Item",
+                )?;
+            }
+            write_line(
+                to,
+                level,
+                Tag(0xFFFE, 0xE0DD),
+                "na",
+                "(SequenceDelimitationItem)",
+                Length(0),
+                0,
+                "SequenceDelimitationItem",
+            )
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dump_primitive<W>(
+    to: &mut W,
+    level: usize,
+    tag: Tag,
+    vr: VR,
+    value: &PrimitiveValue,
+    len: Length,
+    name: &str,
+    limits: Limits,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+{
+    let (info, vm) = if value.multiplicity() == 0 {
+        ("(no value available)".to_string(), 0)
+    } else {
+        let vm = match vr {
+            VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::UN => 1,
+            _ => value.multiplicity(),
+        };
+        (format_value(value, vr, limits.max_length(vr)), vm)
+    };
+    // values are always padded to an even length when encoded
+    let len = match len.get() {
+        Some(len) => Length(len + (len & 1)),
+        None => len,
+    };
+    write_line(to, level, tag, vr.to_string(), &info, len, vm, name)
+}
+
+/// Write a line with the tag, VR, value, length, multiplicity and name.
+#[allow(clippy::too_many_arguments)]
+fn write_line<W>(
+    to: &mut W,
+    level: usize,
+    tag: Tag,
+    vr: impl AsRef<str>,
+    info: &str,
+    len: Length,
+    vm: u32,
+    name: &str,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+{
+    let len = match len.get() {
+        Some(len) => format!("{len:>3}"),
+        None => "u/l".to_string(),
+    };
+    writeln!(
+        to,
+        "{:indent$}({:04x},{:04x}) {} {:<VALUE_WIDTH$} # {},{:>2} {}",
+        "",
+        tag.group(),
+        tag.element(),
+        vr.as_ref(),
+        info,
+        len,
+        vm,
+        name,
+        indent = level * 2,
+    )
+}
+
+/// Format a primitive value as `dcmdump` does:
+/// text in square brackets,
+/// numbers in decimal and binary data in hexadecimal,
+/// with multiple values separated by backslashes.
+fn format_value(value: &PrimitiveValue, vr: VR, max_length: Option<usize>) -> String {
+    use PrimitiveValue::*;
+
+    match (value, vr) {
+        (U8(values), VR::OW) => join_values(
+            values.chunks(2).map(|w| {
+                format!(
+                    "{:04x}",
+                    u16::from_le_bytes([w[0], *w.get(1).unwrap_or(&0)])
+                )
+            }),
+            max_length,
+        ),
+        (U8(values), _) => join_values(values.iter().map(|v| format!("{v:02x}")), max_length),
+        (U16(values), VR::OW) => join_values(values.iter().map(|v| format!("{v:04x}")), max_length),
+        (U32(values), VR::OL) => join_values(values.iter().map(|v| format!("{v:08x}")), max_length),
+        (U64(values), VR::OV) => {
+            join_values(values.iter().map(|v| format!("{v:016x}")), max_length)
+        }
+        (U16(values), _) => join_values(values, max_length),
+        (I16(values), _) => join_values(values, max_length),
+        (U32(values), _) => join_values(values, max_length),
+        (I32(values), _) => join_values(values, max_length),
+        (U64(values), _) => join_values(values, max_length),
+        (I64(values), _) => join_values(values, max_length),
+        (F32(values), _) => join_values(values, max_length),
+        (F64(values), _) => join_values(values, max_length),
+        (Tags(values), _) => join_values(
+            values
+                .iter()
+                .map(|t| format!("({:04x},{:04x})", t.group(), t.element())),
+            max_length,
+        ),
+        (Strs(values), VR::UI) if values.len() == 1 && uid_keyword(&values[0]).is_some() => {
+            format!("={}", uid_keyword(&values[0]).unwrap_or_default())
+        }
+        (Str(value), VR::UI) if uid_keyword(value).is_some() => {
+            format!("={}", uid_keyword(value).unwrap_or_default())
+        }
+        (Date(values), _) => bracketed(
+            &values
+                .iter()
+                .map(|v| v.to_encoded())
+                .collect::<Vec<_>>()
+                .join("\\"),
+            max_length,
+        ),
+        (Time(values), _) => bracketed(
+            &values
+                .iter()
+                .map(|v| v.to_encoded())
+                .collect::<Vec<_>>()
+                .join("\\"),
+            max_length,
+        ),
+        (DateTime(values), _) => bracketed(
+            &values
+                .iter()
+                .map(|v| v.to_encoded())
+                .collect::<Vec<_>>()
+                .join("\\"),
+            max_length,
+        ),
+        (Str(_), _) | (Strs(_), _) => bracketed(&value.to_str(), max_length),
+        (Empty, _) => "(no value available)".to_string(),
+    }
+}
+
+/// Put a text value in square brackets, shortening it if necessary.
+fn bracketed(text: &str, max_length: Option<usize>) -> String {
+    shorten(format!("[{text}]"), max_length)
+}
+
+/// Join values with backslashes,
+/// stopping early once the maximum length is reached.
+fn join_values<I>(values: I, max_length: Option<usize>) -> String
+where
+    I: IntoIterator,
+    I::Item: std::fmt::Display,
+{
+    let mut out = String::new();
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            out.push('\\');
+        }
+        out.push_str(&value.to_string());
+        if max_length.is_some_and(|max| out.len() > max) {
+            break;
+        }
+    }
+    shorten(out, max_length)
+}
+
+/// Cut a value which is longer than the maximum length,
+/// ending it with `...`.
+fn shorten(text: String, max_length: Option<usize>) -> String {
+    match max_length {
+        Some(max) if text.chars().count() > max => {
+            text.chars().take(max - 3).chain("...".chars()).collect()
+        }
+        _ => text,
+    }
+}
+
+/// Determine the name of an element as printed by `dcmdump`.
+fn element_name(tag: Tag, private_alias: Option<&str>) -> &str {
+    if let Some(alias) = private_alias {
+        return alias;
+    }
+    if tag.group() % 2 == 1 && (0x0010..=0x00FF).contains(&tag.element()) {
+        return "PrivateCreator";
+    }
+    StandardDataDictionary
+        .by_tag(tag)
+        .map(DataDictionaryEntry::alias)
+        .unwrap_or("Unknown Tag & Data")
+}
+
+/// The name of a transfer syntax as printed by `dcmdump`.
+fn transfer_syntax_name(uid: &str) -> &str {
+    let uid = uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+    match uid {
+        uids::IMPLICIT_VR_LITTLE_ENDIAN => "Little Endian Implicit",
+        uids::EXPLICIT_VR_LITTLE_ENDIAN => "Little Endian Explicit",
+        // Explicit VR Big Endian (retired)
+        "1.2.840.10008.1.2.2" => "Big Endian Explicit",
+        uids::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN => "Deflated Explicit VR Little Endian",
+        _ => TransferSyntaxRegistry
+            .get(uid)
+            .map(|ts| ts.name())
+            .unwrap_or("Unknown Transfer Syntax"),
+    }
+}
+
+/// Translate a normative DICOM UID to its keyword
+#[cfg(feature = "sop-class")]
+fn uid_keyword(uid: &str) -> Option<&'static str> {
+    use dicom_core::dictionary::UidDictionary;
+    StandardUidDictionary.by_uid(uid).map(|e| e.alias)
+}
+#[cfg(not(feature = "sop-class"))]
+fn uid_keyword(_uid: &str) -> Option<&'static str> {
+    None
+}
@@ -35,13 +35,11 @@
 #[cfg(feature = "cli")]
 use clap::ValueEnum;
 use dicom_core::VR;
-#[cfg(feature = "sop-class")]
-use dicom_core::dictionary::UidDictionary;
 use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
 use dicom_core::header::Header;
 use dicom_core::value::{PrimitiveValue, Value as DicomValue};
 #[cfg(feature = "sop-class")]
-use dicom_dictionary_std::StandardSopClassDictionary;
+use dicom_dictionary_std::StandardUidDictionary;
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_json::DicomJson;
 use dicom_object::mem::{InMemDicomObject, InMemElement};
@@ -53,6 +51,9 @@ use std::fmt::{self, Display, Formatter};
 use std::io::{Result as IoResult, Write, stdout};
 use std::str::FromStr;
 
+mod dcmtk;
+mod pydicom;
+
 #[derive(Clone, Debug, PartialEq, Default)]
 #[cfg_attr(feature = "cli", derive(ValueEnum))]
 pub enum DumpFormat {
@@ -71,6 +72,27 @@ pub enum DumpFormat {
     /// DICOM part 18 chapter F JSON format,
     /// provided via [`dicom_json`]
     Json,
+    /// Text dump in the layout of DCMTK's `dcmdump`
+    ///
+    /// Each element is printed as
+    /// `(gggg,eeee) VR [value] # length, vm Name`,
+    /// so that scripts and test suites based on the output of `dcmdump`
+    /// can be used with little to no changes.
+    /// Like in `dcmdump`,
+    /// values longer than 70 characters are shortened
+    /// unless the respective limits are lifted,
+    /// and the output is never colored.
+    Dcmtk,
+    /// Text dump in the layout of pydicom's `print(ds)`
+    ///
+    /// Each element is printed as
+    /// `(gggg,eeee) Element Name   VR: value`,
+    /// with values written as Python literals,
+    /// to ease the comparison with the outputs of pydicom.
+    /// Element names are spelled out from the element keywords,
+    /// so they may differ slightly from the names printed by pydicom,
+    /// and the output is never colored.
+    Pydicom,
 }
 
 /// Options and flags to configure how to dump a DICOM file or object.
@@ -241,6 +263,15 @@ impl DumpOptions {
                 serde_json::to_writer_pretty(stdout(), &json_obj)?;
                 Ok(())
             }
+            DumpFormat::Dcmtk => dcmtk::dump_file(
+                &mut to,
+                obj,
+                dcmtk::Limits {
+                    no_text_limit,
+                    no_limit,
+                },
+            ),
+            DumpFormat::Pydicom => pydicom::dump_file(&mut to, obj),
         }
     }
 
@@ -297,6 +328,23 @@ impl DumpOptions {
                 serde_json::to_writer_pretty(to, &json_obj)?;
                 Ok(())
             }
+            DumpFormat::Dcmtk => {
+                let (no_text_limit, no_limit) = if to_stdout {
+                    (self.no_text_limit, self.no_limit)
+                } else {
+                    (true, true)
+                };
+                dcmtk::dump_object(
+                    &mut to,
+                    obj,
+                    0,
+                    dcmtk::Limits {
+                        no_text_limit,
+                        no_limit,
+                    },
+                )
+            }
+            DumpFormat::Pydicom => pydicom::dump_object(&mut to, obj, 0),
         }
     }
 }
@@ -462,18 +510,7 @@ where
         .media_storage_sop_class_uid
         .trim_end_matches(whitespace_or_null);
 
-    #[cfg(feature = "sop-class")]
-    #[inline]
-    fn translate_sop_class(uid: &str) -> Option<&'static str> {
-        StandardSopClassDictionary.by_uid(uid).map(|e| e.name)
-    }
-    #[cfg(not(feature = "sop-class"))]
-    #[inline]
-    fn translate_sop_class(_uid: &str) -> Option<&'static str> {
-        None
-    }
-
-    if let Some(name) = translate_sop_class(sop_class_uid) {
+    if let Some(name) = uid_name(sop_class_uid) {
         writeln!(
             to,
             "{}: {} ({})",
@@ -505,11 +542,13 @@ where
             ts.name()
         )?;
     } else {
+        let ts_uid = meta.transfer_syntax.trim_end_matches(whitespace_or_null);
         writeln!(
             to,
-            "{}: {} («UNKNOWN»)",
+            "{}: {} ({})",
             "Transfer Syntax".if_supports_color(Stream::Stdout, |v| v.bold()),
-            meta.transfer_syntax.trim_end_matches(whitespace_or_null)
+            ts_uid,
+            uid_name(ts_uid).unwrap_or("«UNKNOWN»"),
         )?;
     }
     writeln!(
@@ -591,7 +630,17 @@ where
     D: DataDictionary,
 {
     for elem in obj {
-        dump_element(&mut *to, elem, width, depth, no_text_limit, no_limit)?;
+        // describe private attributes from the registered private dictionaries
+        let private_entry = obj.private_entry(elem.tag());
+        dump_element_with_alias(
+            &mut *to,
+            elem,
+            private_entry.as_ref().map(|e| e.alias.as_str()),
+            width,
+            depth,
+            no_text_limit,
+            no_limit,
+        )?;
     }
 
     Ok(())
@@ -605,14 +654,33 @@ pub fn dump_element<W, D>(
     no_text_limit: bool,
     no_limit: bool,
 ) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    dump_element_with_alias(to, elem, None, width, depth, no_text_limit, no_limit)
+}
+
+fn dump_element_with_alias<W, D>(
+    to: &mut W,
+    elem: &InMemElement<D>,
+    alias: Option<&str>,
+    width: u32,
+    depth: u32,
+    no_text_limit: bool,
+    no_limit: bool,
+) -> IoResult<()>
 where
     W: ?Sized + Write,
     D: DataDictionary,
 {
     let indent = vec![b' '; (depth * 2) as usize];
-    let tag_alias = StandardDataDictionary
-        .by_tag(elem.tag())
-        .map(DataDictionaryEntry::alias)
+    let tag_alias = alias
+        .or_else(|| {
+            StandardDataDictionary
+                .by_tag(elem.tag())
+                .map(DataDictionaryEntry::alias)
+        })
         .unwrap_or("«Unknown Attribute»");
     to.write_all(&indent)?;
     let vm = match elem.vr() {
@@ -717,6 +785,18 @@ where
     Ok(())
 }
 
+/// Translate a normative DICOM UID to its name
+#[cfg(feature = "sop-class")]
+#[inline]
+pub(crate) fn uid_name(uid: &str) -> Option<&'static str> {
+    StandardUidDictionary.name_of(uid)
+}
+#[cfg(not(feature = "sop-class"))]
+#[inline]
+pub(crate) fn uid_name(_uid: &str) -> Option<&'static str> {
+    None
+}
+
 fn dump_item<W, D>(
     to: &mut W,
     item: &InMemDicomObject<D>,
@@ -839,6 +919,10 @@ fn value_summary(
                 }
             }
         }
+        (Strs(values), VR::UI) if values.len() == 1 && uid_name(&values[0]).is_some() => {
+            uid_summary(&values[0], max_characters)
+        }
+        (Str(value), VR::UI) if uid_name(value).is_some() => uid_summary(value, max_characters),
         (Strs(values), _) => DumpValue::Str(format_value_list(
             values
                 .iter()
@@ -893,6 +977,17 @@ fn offset_table_summary(data: &[u32], max_characters: Option<u32>) -> String {
     }
 }
 
+/// Format a known UID value alongside its name
+fn uid_summary(uid: &str, max_characters: Option<u32>) -> DumpValue<String> {
+    let uid = uid.trim_end_matches(whitespace_or_null);
+    let txt = format!("\"{}\" ({})", uid, uid_name(uid).unwrap_or_default());
+    if let Some(max) = max_characters {
+        DumpValue::Str(cut_str(&txt, max).to_string())
+    } else {
+        DumpValue::Str(txt)
+    }
+}
+
 fn format_value_list<I>(values: I, max_characters: Option<u32>, quoted: bool) -> String
 where
     I: IntoIterator,
@@ -1113,6 +1208,197 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "sop-class")]
+    #[test]
+    fn dump_known_uids_with_their_names() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.840.10008.5.1.4.1.1.2\0"),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.888.123"),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert!(
+            lines[0].ends_with(": \"1.2.840.10008.5.1.4.1.1.2\" (CT Image Storage)"),
+            "unexpected line: {}",
+            lines[0]
+        );
+        assert!(lines[1].ends_with(": \"1.2.888.123\""));
+    }
+
+    #[test]
+    fn dump_private_attributes_with_registered_alias() {
+        use dicom_core::Tag;
+        use dicom_dictionary_std::private::{self, PrivateDictionary};
+
+        private::registry().register(
+            PrivateDictionary::from_tsv(
+                "(0019,\"DUMP TEST CREATOR\",0c)\tSS\tDumpTestDirection\t1\n".as_bytes(),
+            )
+            .unwrap(),
+        );
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                Tag(0x0019, 0x0010),
+                VR::LO,
+                PrimitiveValue::from("DUMP TEST CREATOR"),
+            ),
+            DataElement::new(Tag(0x0019, 0x100C), VR::SS, PrimitiveValue::from(1_i16)),
+            DataElement::new(Tag(0x0019, 0x100D), VR::SS, PrimitiveValue::from(2_i16)),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .color_mode(ColorMode::Never)
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert!(lines[1].starts_with("(0019,100C) DumpTestDirection"));
+        assert!(lines[2].starts_with("(0019,100D) «Unknown Attribute»"));
+    }
+
+    #[test]
+    fn dump_dcmtk() {
+        use dicom_core::value::DataSetSequence;
+
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from("1.2.888.124"),
+        )]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::IMAGE_TYPE,
+                VR::CS,
+                dicom_core::dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.888.123"),
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::Empty),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_core::dicom_value!(Strs, ["0.5", "0.5"]),
+            ),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(512_u16)),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0x00_u8, 0x7F, 0xFF, 0x10]),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .format(crate::DumpFormat::Dcmtk)
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "(0008,0008) CS [ORIGINAL\\PRIMARY]                       #  16, 2 ImageType",
+                "(0008,0018) UI [1.2.888.123]                            #  12, 1 SOPInstanceUID",
+                "(0008,1140) SQ (Sequence with undefined length #=1)     # u/l, 1 ReferencedImageSequence",
+                "  (fffe,e000) na (Item with undefined length #=1)         # u/l, 1 Item",
+                "    (0008,1155) UI [1.2.888.124]                            #  12, 1 ReferencedSOPInstanceUID",
+                "  (fffe,e00d) na (ItemDelimitationItem)                   #   0, 0 ItemDelimitationItem",
+                "(fffe,e0dd) na (SequenceDelimitationItem)               #   0, 0 SequenceDelimitationItem",
+                "(0010,0010) PN (no value available)                     #   0, 0 PatientName",
+                "(0028,0010) US 512                                      #   2, 1 Rows",
+                "(0028,0030) DS [0.5\\0.5]                                #   8, 2 PixelSpacing",
+                "(7fe0,0010) OB 00\\7f\\ff\\10                              #   4, 1 PixelData",
+            ]
+        );
+    }
+
+    #[test]
+    fn dump_pydicom() {
+        use dicom_core::value::DataSetSequence;
+
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::REFERENCED_SOP_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::from("1.2.888.124"),
+        )]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::IMAGE_TYPE,
+                VR::CS,
+                dicom_core::dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.888.123"),
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("O'Neil")),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+            DataElement::new(tags::SLICE_THICKNESS, VR::DS, PrimitiveValue::from("5.0")),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_core::dicom_value!(Strs, ["0.5", "0.5"]),
+            ),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(512_u16)),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0x00_u8, 0x41, 0xFF, 0x10]),
+            ),
+        ]);
+
+        let mut out = Vec::new();
+        DumpOptions::new()
+            .format(crate::DumpFormat::Pydicom)
+            .dump_object_to(&mut out, &obj)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "(0008,0008) Image Type                          CS: ['ORIGINAL', 'PRIMARY']",
+                "(0008,0018) SOP Instance UID                    UI: 1.2.888.123",
+                "(0008,1140)  Referenced Image Sequence  1 item(s) ---- ",
+                "   (0008,1155) Referenced SOP Instance UID         UI: 1.2.888.124",
+                "   ---------",
+                "(0010,0010) Patient Name                        PN: \"O'Neil\"",
+                "(0018,0050) Slice Thickness                     DS: '5.0'",
+                "(0028,0010) Rows                                US: 512",
+                "(0028,0030) Pixel Spacing                       DS: [0.5, 0.5]",
+                "(7FE0,0010) Pixel Data                          OB: b'\\x00A\\xff\\x10'",
+            ]
+        );
+    }
+
     #[test]
     fn dump_json() {
         // create object
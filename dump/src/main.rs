@@ -2,10 +2,10 @@
 //! by printing it in a human readable format.
 use clap::Parser;
 use dicom_core::Tag;
-use dicom_dictionary_std::tags;
+use dicom_dictionary_std::{private, tags};
 use dicom_dump::{ColorMode, DumpFormat, DumpOptions};
 use dicom_object::{OpenFileOptions, StandardDataDictionary, file::OddLengthStrategy};
-use snafu::{Report, Whatever};
+use snafu::{Report, ResultExt, Whatever};
 use std::io::{ErrorKind, IsTerminal};
 use std::path::PathBuf;
 
@@ -63,6 +63,14 @@ struct App {
     #[arg(value_enum)]
     #[clap(short = 'f', long = "format", default_value = "text")]
     format: DumpFormat,
+    /// Load a private data element dictionary
+    /// to describe private attributes
+    /// (can be used multiple times).
+    ///
+    /// Files ending in `.json` are read as JSON,
+    /// otherwise in the tab separated format of DCMTK's `private.dic`
+    #[clap(long = "private-dict")]
+    private_dict: Vec<PathBuf>,
 }
 
 fn parse_strategy(s: &str) -> Result<OddLengthStrategy, &'static str> {
@@ -101,8 +109,17 @@ fn run() -> Result<(), Whatever> {
         color,
         fail_first,
         format,
+        private_dict,
     } = App::parse();
 
+    for path in &private_dict {
+        private::registry()
+            .register_file(path)
+            .with_whatever_context(|_| {
+                format!("Could not load private dictionary {}", path.display())
+            })?;
+    }
+
     let width = width
         .or_else(|| terminal_size::terminal_size().map(|(width, _)| width.0 as u32))
         .unwrap_or(120);
@@ -110,8 +127,13 @@ fn run() -> Result<(), Whatever> {
     let mut options = DumpOptions::new();
     options
         .no_text_limit(no_text_limit)
-        // No limit when output is not a terminal
-        .no_limit(if !is_terminal() { true } else { no_limit })
+        // No limit when output is not a terminal,
+        // except in the dcmdump layout, which shortens values all the same
+        .no_limit(if !is_terminal() && format != DumpFormat::Dcmtk {
+            true
+        } else {
+            no_limit
+        })
         .width(width)
         .color_mode(color)
         .format(format);
@@ -0,0 +1,348 @@
+//! Dumping in the layout of pydicom's `print(ds)`
+//!
+//! Each element is printed in a single line of the form
+//! `(gggg,eeee) Element Name   VR: value`,
+//! with the name padded to 35 characters
+//! and the value written as a Python literal
+//! (quoted text, numbers, lists and byte strings).
+//! Sequence items are indented by 3 spaces
+//! and followed by a `---------` separator.
+//!
+//! Since this library's data dictionary only records element keywords,
+//! the element names are obtained by spelling out the keyword
+//! (e.g. `Patient Name` for _PatientName_,
+//! where pydicom prints `Patient's Name`).
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::header::Header;
+use dicom_core::value::{PrimitiveValue, Value as DicomValue};
+use dicom_core::{Tag, VR};
+use dicom_object::mem::InMemDicomObject;
+use dicom_object::{FileDicomObject, StandardDataDictionary};
+use std::io::{Result as IoResult, Write};
+
+use crate::uid_name;
+
+/// The width of the element name column, as in pydicom
+const NAME_WIDTH: usize = 35;
+
+/// The maximum number of bytes or values to show,
+/// as in pydicom
+const MAX_VALUES: usize = 16;
+
+/// The indentation of each nesting level
+const INDENT: &str = "   ";
+
+/// Dump a DICOM file, both file meta group and main data set.
+pub(crate) fn dump_file<W, D>(
+    to: &mut W,
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    writeln!(to, "Dataset.file_meta -------------------------------")?;
+    for elem in obj.meta().to_element_iter() {
+        if let DicomValue::Primitive(value) = elem.value() {
+            write_line(
+                to,
+                0,
+                elem.tag(),
+                elem.vr(),
+                value,
+                &element_name(elem.tag(), None),
+            )?;
+        }
+    }
+    writeln!(to, "-------------------------------------------------")?;
+    dump_object(to, obj, 0)
+}
+
+/// Dump the elements of a DICOM object at the given nesting level.
+pub(crate) fn dump_object<W, D>(to: &mut W, obj: &InMemDicomObject<D>, level: usize) -> IoResult<()>
+where
+    W: ?Sized + Write,
+    D: DataDictionary,
+{
+    for elem in obj {
+        let tag = elem.tag();
+        let private_entry = obj.private_entry(tag);
+        let name = element_name(tag, private_entry.as_ref().map(|e| e.alias.as_str()));
+        match elem.value() {
+            DicomValue::Primitive(value) => write_line(to, level, tag, elem.vr(), value, &name)?,
+            DicomValue::Sequence(seq) => {
+                writeln!(
+                    to,
+                    "{}{}  {}  {} item(s) ---- ",
+                    INDENT.repeat(level),
+                    format_tag(tag),
+                    name,
+                    seq.items().len()
+                )?;
+                for item in seq.items() {
+                    dump_object(to, item, level + 1)?;
+                    writeln!(to, "{}---------", INDENT.repeat(level + 1))?;
+                }
+            }
+            DicomValue::PixelSequence(seq) => {
+                // pydicom keeps encapsulated pixel data as a byte string,
+                // including the item headers and the sequence delimiter
+                let len = 8
+                    + seq.offset_table().len() * 4
+                    + seq.fragments().iter().map(|f| f.len() + 8).sum::<usize>()
+                    + 8;
+                writeln!(
+                    to,
+                    "{}{} {:<NAME_WIDTH$} {}: Array of {} elements",
+                    INDENT.repeat(level),
+                    format_tag(tag),
+                    name,
+                    elem.vr(),
+                    len
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write a line with the tag, name, VR and value of a primitive element.
+fn write_line<W>(
+    to: &mut W,
+    level: usize,
+    tag: Tag,
+    vr: VR,
+    value: &PrimitiveValue,
+    name: &str,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+{
+    let name: String = name.chars().take(NAME_WIDTH).collect();
+    writeln!(
+        to,
+        "{}{} {:<NAME_WIDTH$} {}: {}",
+        INDENT.repeat(level),
+        format_tag(tag),
+        name,
+        vr,
+        format_value(value, vr)
+    )
+}
+
+fn format_tag(tag: Tag) -> String {
+    format!("({:04X},{:04X})", tag.group(), tag.element())
+}
+
+/// Format a primitive value as pydicom's representation of element values.
+fn format_value(value: &PrimitiveValue, vr: VR) -> String {
+    use PrimitiveValue::*;
+
+    if value.multiplicity() == 0 {
+        return if is_text(vr) {
+            "''".to_string()
+        } else {
+            "None".to_string()
+        };
+    }
+
+    match (value, vr) {
+        // byte strings
+        (U8(values), _) => bytes_literal(values),
+        (_, VR::OB | VR::OW | VR::OF | VR::OD | VR::OL | VR::OV | VR::UN) => {
+            bytes_literal(&value.to_bytes())
+        }
+        (U16(values), _) => list(values.iter().map(ToString::to_string)),
+        (I16(values), _) => list(values.iter().map(ToString::to_string)),
+        (U32(values), _) => list(values.iter().map(ToString::to_string)),
+        (I32(values), _) => list(values.iter().map(ToString::to_string)),
+        (U64(values), _) => list(values.iter().map(ToString::to_string)),
+        (I64(values), _) => list(values.iter().map(ToString::to_string)),
+        (F32(values), _) => list(values.iter().map(|v| float_literal(*v as f64))),
+        (F64(values), _) => list(values.iter().map(|v| float_literal(*v))),
+        (Tags(values), _) => list(values.iter().map(|t| format_tag(*t))),
+        (Date(values), _) => text_list(values.iter().map(|v| v.to_encoded())),
+        (Time(values), _) => text_list(values.iter().map(|v| v.to_encoded())),
+        (DateTime(values), _) => text_list(values.iter().map(|v| v.to_encoded())),
+        (Str(_) | Strs(_), _) => {
+            let values = value.to_multi_str();
+            let values = values
+                .iter()
+                .map(|v| v.trim_end_matches([' ', '\0']).to_string());
+            match vr {
+                // a single UID is shown by its name if known
+                VR::UI if value.multiplicity() == 1 => {
+                    let uid = value.to_str();
+                    uid_name(&uid).map_or_else(|| uid.to_string(), str::to_string)
+                }
+                // multiple numbers are listed as numbers
+                VR::DS | VR::IS if value.multiplicity() > 1 => list(values),
+                _ => text_list(values),
+            }
+        }
+        (Empty, _) => "None".to_string(),
+    }
+}
+
+fn is_text(vr: VR) -> bool {
+    matches!(
+        vr,
+        VR::AE
+            | VR::AS
+            | VR::CS
+            | VR::DA
+            | VR::DS
+            | VR::DT
+            | VR::IS
+            | VR::LO
+            | VR::LT
+            | VR::PN
+            | VR::SH
+            | VR::ST
+            | VR::TM
+            | VR::UC
+            | VR::UI
+            | VR::UR
+            | VR::UT
+    )
+}
+
+/// Write a Python list of the given values,
+/// or the value alone if there is only one.
+fn list(values: impl ExactSizeIterator<Item = String>) -> String {
+    if values.len() > MAX_VALUES {
+        return format!("Array of {} elements", values.len());
+    }
+    let mut values: Vec<_> = values.collect();
+    if values.len() == 1 {
+        values.pop().unwrap_or_default()
+    } else {
+        format!("[{}]", values.join(", "))
+    }
+}
+
+/// Write text values as quoted Python strings.
+fn text_list(values: impl ExactSizeIterator<Item = String>) -> String {
+    list(values.map(|v| str_literal(&v)))
+}
+
+/// Write a Python string literal,
+/// in single quotes unless the text contains single quotes only.
+fn str_literal(text: &str) -> String {
+    let quote = if text.contains('\'') && !text.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push(quote);
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Write a Python byte string literal,
+/// or the number of bytes if there are too many.
+fn bytes_literal(bytes: &[u8]) -> String {
+    if bytes.len() > MAX_VALUES {
+        return format!("Array of {} elements", bytes.len());
+    }
+    let mut out = String::from("b'");
+    for &b in bytes {
+        match b {
+            b'\\' => out.push_str("\\\\"),
+            b'\'' => out.push_str("\\'"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7E => out.push(b as char),
+            _ => out.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Write a floating point number as Python does,
+/// always with a fractional part or exponent.
+fn float_literal(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0. { "inf" } else { "-inf" }.to_string()
+    } else if value.fract() == 0. && value.abs() < 1e16 {
+        format!("{value:.1}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Determine the name of an element as printed by pydicom,
+/// spelling out the keyword of the element.
+fn element_name(tag: Tag, private_alias: Option<&str>) -> String {
+    if let Some(alias) = private_alias {
+        return format!("[{}]", spell_out(alias));
+    }
+    if tag.group() % 2 == 1 {
+        return if (0x0010..=0x00FF).contains(&tag.element()) {
+            "Private Creator".to_string()
+        } else {
+            "Private tag data".to_string()
+        };
+    }
+    StandardDataDictionary
+        .by_tag(tag)
+        .map(|e| spell_out(e.alias()))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Separate the words of an UpperCamelCase keyword,
+/// keeping acronyms together (`SOPClassUID` becomes `SOP Class UID`).
+fn spell_out(keyword: &str) -> String {
+    let chars: Vec<char> = keyword.chars().collect();
+    let mut out = String::with_capacity(keyword.len() + 8);
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_ascii_uppercase() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                out.push(' ');
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spell_out;
+
+    #[test]
+    fn spell_out_keywords() {
+        assert_eq!(spell_out("PatientName"), "Patient Name");
+        assert_eq!(spell_out("SOPClassUID"), "SOP Class UID");
+        assert_eq!(
+            spell_out("FileMetaInformationGroupLength"),
+            "File Meta Information Group Length"
+        );
+        assert_eq!(spell_out("RTPlanDate"), "RT Plan Date");
+        assert_eq!(spell_out("Rows"), "Rows");
+    }
+}
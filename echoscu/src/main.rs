@@ -1,4 +1,5 @@
 use clap::Parser;
+use dicom_app_common::aeconfig::AeConfigOptions;
 use dicom_core::{DataElement, VR, dicom_value};
 use dicom_dictionary_std::{tags, uids};
 use dicom_object::{StandardDataDictionary, mem::InMemDicomObject};
@@ -16,8 +17,11 @@ use tracing::{Level, debug, error, info, warn};
 struct App {
     /// socket address to SCP,
     /// optionally with AE title
-    /// (example: "QUERY-SCP@127.0.0.1:1045")
-    addr: String,
+    /// (example: "QUERY-SCP@127.0.0.1:1045"),
+    /// or the name of an AE in the configuration file
+    /// (omit if `--remote` is given)
+    #[arg(required_unless_present = "to")]
+    addr: Option<String>,
     /// verbose mode
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
@@ -25,12 +29,16 @@ struct App {
     #[arg(short = 'm', long = "message-id", default_value = "1")]
     message_id: u16,
     /// the calling AE title
-    #[arg(long = "calling-ae-title", default_value = "ECHOSCU")]
-    calling_ae_title: String,
+    /// [default: as in the configuration, or ECHOSCU]
+    #[arg(long = "calling-ae-title")]
+    calling_ae_title: Option<String>,
     /// the called Application Entity title,
     /// overrides AE title in address if present [default: ANY-SCP]
     #[arg(long = "called-ae-title")]
     called_ae_title: Option<String>,
+
+    #[command(flatten, next_help_heading = "AE Configuration Options")]
+    ae_config: AeConfigOptions,
 }
 
 fn main() {
@@ -47,6 +55,7 @@ fn run() -> Result<(), Whatever> {
         message_id,
         called_ae_title,
         calling_ae_title,
+        ae_config,
     } = App::parse();
 
     tracing::subscriber::set_global_default(
@@ -59,9 +68,26 @@ fn run() -> Result<(), Whatever> {
         eprintln!("[ERROR] {}", snafu::Report::from_error(e));
     });
 
+    let (peer, rest) = ae_config
+        .resolve(addr)
+        .whatever_context("Could not resolve the SCP")?;
+    if let Some(rest) = rest {
+        whatever!("unexpected argument '{rest}'");
+    }
+    if peer.tls {
+        whatever!("TLS connections are not supported by this tool");
+    }
+    let addr = peer.address;
+    let calling_ae_title = calling_ae_title
+        .or(peer.calling_ae_title)
+        .unwrap_or_else(|| "ECHOSCU".to_string());
+
     let mut association_opt = ClientAssociationOptions::new()
         .with_abstract_syntax("1.2.840.10008.1.1")
         .calling_ae_title(calling_ae_title);
+    if let Some(max_pdu_length) = peer.max_pdu_length {
+        association_opt = association_opt.max_pdu_length(max_pdu_length);
+    }
     if let Some(called_ae_title) = called_ae_title {
         association_opt = association_opt.called_ae_title(called_ae_title);
     }
@@ -59,6 +59,13 @@ pub enum DecodeError {
     #[snafu(display("Frame of pixel data is missing or out of bounds"))]
     FrameRangeOutOfBounds,
 
+    /// The boundaries of the requested frame
+    /// could not be determined in the encapsulated pixel data,
+    /// usually because there is no basic offset table
+    /// and the codestream format was not recognized.
+    #[snafu(display("Cannot determine frame boundaries in encapsulated pixel data"))]
+    FrameBoundaries,
+
     /// A required attribute is missing
     /// from the DICOM object representing the image.
     #[snafu(display("Missing required attribute `{}`", name))]
@@ -192,6 +199,17 @@ pub trait PixelDataObject {
     /// Returns `None` if no pixel data is found.
     fn raw_pixel_data(&self) -> Option<RawPixelData>;
 
+    /// Return the number of frames which are fully available,
+    /// starting from the first one.
+    ///
+    /// This is less than the _Number Of Frames_
+    /// if the encapsulated pixel data was truncated
+    /// by the end of the data source.
+    /// The default implementation assumes that all frames are available.
+    fn complete_frames(&self) -> u32 {
+        self.number_of_frames().unwrap_or(1)
+    }
+
     /// Return the pixel data of a specific frame as a byte slice/vector,
     /// in its encoded form.
     ///
@@ -214,11 +232,15 @@ pub trait PixelDataObject {
             }
             // Other cases of multi-frame objects
             Some(number_of_fragments) => {
+                let offset_table = self.offset_table().unwrap_or_default();
+                if offset_table.is_empty() {
+                    return scan_frame_fragments(self, frame, number_of_fragments);
+                }
+
                 // In this case we look up the basic offset table
                 // and gather all of the frame's fragments in a single vector.
                 // Note: not the most efficient way to do this,
                 // consider optimizing later with byte chunk readers
-                let offset_table = self.offset_table()?;
                 let base_offset = offset_table.get(frame as usize).copied();
                 let base_offset = if frame == 0 {
                     base_offset.unwrap_or(0) as usize
@@ -286,6 +308,149 @@ pub trait PixelDataObject {
     }
 }
 
+/// Byte sequences which can only appear at the start of
+/// an encapsulated frame's codestream.
+const FRAME_START_MARKERS: [&[u8]; 4] = [
+    // JPEG and JPEG-LS: SOI followed by another marker
+    &[0xFF, 0xD8, 0xFF],
+    // JPEG 2000 codestream: SOC followed by SIZ
+    &[0xFF, 0x4F, 0xFF, 0x51],
+    // JPEG 2000 JP2 file signature box
+    &[
+        0, 0, 0, 0x0C, b'j', b'P', b' ', b' ', 0x0D, 0x0A, 0x87, 0x0A,
+    ],
+    // JPEG XL container signature box
+    &[
+        0, 0, 0, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A,
+    ],
+];
+
+/// The signature of the codestream at the start of an encapsulated frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameSignature {
+    /// One of the [`FRAME_START_MARKERS`]
+    Marker(&'static [u8]),
+    /// A bare JPEG XL codestream of the given width and height.
+    ///
+    /// The codestream signature is only 2 bytes long,
+    /// so the size header which follows it is part of the signature.
+    JpegXlCodestream(u32, u32),
+}
+
+impl FrameSignature {
+    /// Recognize the signature at the start of a fragment.
+    fn of(fragment: &[u8]) -> Option<Self> {
+        if let Some(marker) = FRAME_START_MARKERS
+            .into_iter()
+            .find(|marker| fragment.starts_with(marker))
+        {
+            return Some(FrameSignature::Marker(marker));
+        }
+        let (width, height) = jpeg_xl_codestream_size(fragment)?;
+        Some(FrameSignature::JpegXlCodestream(width, height))
+    }
+}
+
+/// Read the image size in the header of a bare JPEG XL codestream,
+/// as a pair of width and height.
+///
+/// Returns `None` if the data does not start with
+/// the JPEG XL codestream signature `FF 0A`
+/// followed by a complete size header.
+fn jpeg_xl_codestream_size(data: &[u8]) -> Option<(u32, u32)> {
+    let data = data.strip_prefix(&[0xFF, 0x0A])?;
+    // the codestream is read from the least significant bit of each byte
+    let mut position = 0;
+    let mut read_bits = |count: u32| -> Option<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = data.get(position / 8)?;
+            value |= u32::from((byte >> (position % 8)) & 1) << i;
+            position += 1;
+        }
+        Some(value)
+    };
+
+    let small = read_bits(1)? == 1;
+    let height = if small {
+        (read_bits(5)? + 1) * 8
+    } else {
+        let count = [9, 13, 18, 30][read_bits(2)? as usize];
+        read_bits(count)? + 1
+    };
+    let ratio = read_bits(3)?;
+    let h = u64::from(height);
+    let width = match ratio {
+        0 if small => u64::from((read_bits(5)? + 1) * 8),
+        0 => {
+            let count = [9, 13, 18, 30][read_bits(2)? as usize];
+            u64::from(read_bits(count)?) + 1
+        }
+        1 => h,
+        2 => h * 12 / 10,
+        3 => h * 4 / 3,
+        4 => h * 3 / 2,
+        5 => h * 16 / 9,
+        6 => h * 5 / 4,
+        _ => h * 2,
+    };
+    Some((u32::try_from(width).ok()?, height))
+}
+
+/// Gather the fragments of a frame in encapsulated pixel data
+/// without a basic offset table.
+///
+/// The frame boundaries are identified by the fragments
+/// which start with the same codestream signature as the first fragment.
+/// Returns `None` if the frame does not exist
+/// or the pixel data does not start with a known signature.
+fn scan_frame_fragments<O>(obj: &O, frame: u32, number_of_fragments: u32) -> Option<Cow<'_, [u8]>>
+where
+    O: PixelDataObject + ?Sized,
+{
+    let number_of_frames = obj.number_of_frames().unwrap_or(1);
+    if frame >= number_of_frames {
+        return None;
+    }
+
+    let mut fragments = Vec::new();
+    if number_of_frames == 1 {
+        // all fragments belong to the single frame
+        for idx in 0..number_of_fragments as usize {
+            fragments.push(obj.fragment(idx)?);
+        }
+    } else {
+        let first = obj.fragment(0)?;
+        let signature = FrameSignature::of(&first)?;
+
+        let mut current_frame = 0;
+        if frame == 0 {
+            fragments.push(first);
+        }
+        for idx in 1..number_of_fragments as usize {
+            let fragment = obj.fragment(idx)?;
+            if FrameSignature::of(&fragment) == Some(signature) {
+                current_frame += 1;
+                if current_frame > frame {
+                    break;
+                }
+            }
+            if current_frame == frame {
+                fragments.push(fragment);
+            }
+        }
+        if fragments.is_empty() {
+            return None;
+        }
+    }
+
+    if fragments.len() == 1 {
+        fragments.pop()
+    } else {
+        Some(Cow::Owned(fragments.concat()))
+    }
+}
+
 /// Custom options when encoding pixel data into an encapsulated form.
 #[derive(Debug, Default, Clone)]
 #[non_exhaustive]
@@ -311,6 +476,15 @@ pub struct EncodeOptions {
     /// If this option is not specified,
     /// the actual effort is decided by the underlying adapter.
     pub effort: Option<u8>,
+
+    /// The intended compression ratio of the output image,
+    /// as the size of the native pixel data
+    /// divided by the size of the encoded pixel data.
+    /// It is ignored if the transfer syntax only supports lossless compression.
+    /// Encoders supporting this option
+    /// should give it precedence over `quality`.
+    /// Encoders are not required to support this option.
+    pub compression_ratio: Option<f32>,
 }
 
 impl EncodeOptions {
@@ -608,7 +782,7 @@ mod tests {
 
     impl PixelDataObject for TestDataObject {
         fn transfer_syntax_uid(&self) -> &str {
-            &self.ts_uid
+            self.ts_uid
         }
 
         fn rows(&self) -> Option<u16> {
@@ -762,4 +936,103 @@ mod tests {
         assert_eq!(obj.frame_pixel_data(1), Some(vec![0x77; 24].into()));
         assert_eq!(obj.frame_pixel_data(2), Some(vec![0x99; 36].into()));
     }
+
+    /// Frame pixel data can be retrieved from an object
+    /// with encapsulated pixel data and no basic offset table,
+    /// by looking for the start of each frame's codestream.
+    #[test]
+    fn frame_pixel_data_in_object_encapsulated_without_offset_table() {
+        let soi = |n: u8| vec![0xFF, 0xD8, 0xFF, 0xE0, n];
+        let obj = TestDataObject {
+            ts_uid: "1.2.840.10008.1.2.4.50",
+            rows: 64,
+            columns: 64,
+            bits_allocated: 8,
+            bits_stored: 8,
+            samples_per_pixel: 1,
+            photometric_interpretation: "MONOCHROME2",
+            number_of_frames: 3,
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new_fragments(vec![
+                // frame 0 spans 2 fragments
+                soi(0),
+                vec![0x11; 6],
+                // frame 1 spans 1 fragment
+                soi(1),
+                // frame 2 spans 3 fragments
+                soi(2),
+                vec![0x22; 4],
+                vec![0x33; 4],
+            ])),
+        };
+
+        let frame_0: Vec<u8> = soi(0).into_iter().chain([0x11; 6]).collect();
+        assert_eq!(obj.frame_pixel_data(0), Some(frame_0.into()));
+        assert_eq!(obj.frame_pixel_data(1), Some(soi(1).into()));
+        let frame_2: Vec<u8> = soi(2)
+            .into_iter()
+            .chain([0x22; 4])
+            .chain([0x33; 4])
+            .collect();
+        assert_eq!(obj.frame_pixel_data(2), Some(frame_2.into()));
+        assert_eq!(obj.frame_pixel_data(3), None);
+    }
+
+    /// Frame boundaries cannot be found without a basic offset table
+    /// if the codestream format is not recognized.
+    #[test]
+    fn frame_pixel_data_in_object_encapsulated_unknown_codestream() {
+        let obj = TestDataObject {
+            ts_uid: "9.9.999.9999.9.9.99",
+            rows: 64,
+            columns: 64,
+            bits_allocated: 8,
+            bits_stored: 8,
+            samples_per_pixel: 1,
+            photometric_interpretation: "MONOCHROME2",
+            number_of_frames: 2,
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new_fragments(vec![
+                vec![0x01; 4],
+                vec![0x02; 4],
+                vec![0x03; 4],
+            ])),
+        };
+
+        assert_eq!(obj.frame_pixel_data(0), None);
+        assert_eq!(obj.frame_pixel_data(1), None);
+    }
+
+    /// A bare JPEG XL codestream signature only starts a frame
+    /// if the size header which follows matches the first frame's.
+    #[test]
+    fn frame_pixel_data_in_object_encapsulated_jpeg_xl_codestream() {
+        // signature and size header of a 64x64 codestream
+        let start = |n: u8| vec![0xFF, 0x0A, 0x4F, 0x00, n];
+        let obj = TestDataObject {
+            ts_uid: "1.2.840.10008.1.2.4.110",
+            rows: 64,
+            columns: 64,
+            bits_allocated: 8,
+            bits_stored: 8,
+            samples_per_pixel: 1,
+            photometric_interpretation: "MONOCHROME2",
+            number_of_frames: 2,
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new_fragments(vec![
+                start(0),
+                // continuation which happens to start with FF 0A
+                vec![0xFF, 0x0A, 0x00, 0x00],
+                start(1),
+            ])),
+        };
+
+        assert_eq!(super::jpeg_xl_codestream_size(&start(0)), Some((64, 64)));
+        let frame_0: Vec<u8> = start(0)
+            .into_iter()
+            .chain([0xFF, 0x0A, 0x00, 0x00])
+            .collect();
+        assert_eq!(obj.frame_pixel_data(0), Some(frame_0.into()));
+        assert_eq!(obj.frame_pixel_data(1), Some(start(1).into()));
+    }
 }
@@ -366,7 +366,7 @@ mod tests {
     #[test]
     fn decode_explicit_vr_be() {
         let dec = ExplicitVRBigEndianDecoder::default();
-        let mut cursor = Cursor::new(RAW.as_ref());
+        let mut cursor = Cursor::new(RAW);
 
         fn read_n<'a>(cursor: &mut Cursor<&'a [u8]>, n: usize) -> &'a [u8] {
             let pos = cursor.position() as usize;
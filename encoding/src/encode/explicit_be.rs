@@ -2,7 +2,7 @@
 
 use crate::encode::basic::BigEndianBasicEncoder;
 use crate::encode::{
-    BasicEncode, Encode, Result, WriteHeaderSnafu, WriteHeaderTooLongSnafu,
+    BasicEncode, Encode, Result, WriteBytesSnafu, WriteHeaderSnafu, WriteHeaderTooLongSnafu,
     WriteItemDelimiterSnafu, WriteItemHeaderSnafu, WriteOffsetTableSnafu,
     WriteSequenceDelimiterSnafu, WriteTagSnafu,
 };
@@ -202,6 +202,39 @@ impl Encode for ExplicitVRBigEndianEncoder {
         self.basic.encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr<W>(
+        &self,
+        mut to: W,
+        vr: VR,
+        value: &PrimitiveValue,
+    ) -> Result<usize>
+    where
+        W: Write,
+    {
+        let word_len = match vr {
+            VR::AT | VR::OW | VR::SS | VR::US => 2,
+            VR::FL | VR::OF | VR::OL | VR::SL | VR::UL => 4,
+            VR::FD | VR::OD | VR::OV | VR::SV | VR::UV => 8,
+            _ => 1,
+        };
+        match value {
+            // raw bytes are in little endian, so each word is reversed
+            PrimitiveValue::U8(bytes) if word_len > 1 && bytes.len() % word_len == 0 => {
+                let mut buf = [0; 4096];
+                for chunk in bytes.chunks(buf.len()) {
+                    let buf = &mut buf[..chunk.len()];
+                    buf.copy_from_slice(chunk);
+                    for word in buf.chunks_exact_mut(word_len) {
+                        word.reverse();
+                    }
+                    to.write_all(buf).context(WriteBytesSnafu)?;
+                }
+                Ok(bytes.len())
+            }
+            _ => self.basic.encode_primitive(to, value),
+        }
+    }
+
     fn encode_offset_table<W>(&self, mut to: W, offset_table: &[u32]) -> Result<usize>
     where
         W: Write,
@@ -220,7 +253,7 @@ mod tests {
     use super::ExplicitVRBigEndianEncoder;
     use crate::encode::Encode;
     use dicom_core::header::{DataElementHeader, Length};
-    use dicom_core::{Tag, VR};
+    use dicom_core::{PrimitiveValue, Tag, VR};
     use std::io::{Cursor, Write};
 
     type Result = std::result::Result<(), Box<dyn std::error::Error>>;
@@ -474,7 +507,7 @@ mod tests {
             let to = writer.position() as usize;
 
             // Compare the current slice
-            if &writer.get_ref()[from..to] != &RAW[from..to] {
+            if writer.get_ref()[from..to] != RAW[from..to] {
                 panic!(
                     "Failure on ({:04x},{:04x})  {:?}  {:02x?}\n\
                     Expected: {:02x?}",
@@ -621,7 +654,7 @@ mod tests {
             &[0xAB, 0x54, 0xA9, 0x8C, 0xEB, 0x1F, 0x0A, 0xD2],
         );
 
-        assert_eq!(&buf[..], &RAW[..]);
+        assert_eq!(&buf[..], RAW);
     }
 
     // manually crafting some DICOM sequence/item delimiters
@@ -671,4 +704,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn encode_raw_bytes_by_vr() -> Result {
+        let enc = ExplicitVRBigEndianEncoder::default();
+        let bytes = PrimitiveValue::from(vec![0x01_u8, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+
+        let mut out = Vec::new();
+        assert_eq!(enc.encode_primitive_with_vr(&mut out, VR::OW, &bytes)?, 8);
+        assert_eq!(out, [0x02, 0x01, 0x04, 0x03, 0x06, 0x05, 0x08, 0x07]);
+
+        out.clear();
+        enc.encode_primitive_with_vr(&mut out, VR::OF, &bytes)?;
+        assert_eq!(out, [0x04, 0x03, 0x02, 0x01, 0x08, 0x07, 0x06, 0x05]);
+
+        out.clear();
+        enc.encode_primitive_with_vr(&mut out, VR::OD, &bytes)?;
+        assert_eq!(out, [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+
+        // byte values and typed values are written as usual
+        out.clear();
+        enc.encode_primitive_with_vr(&mut out, VR::OB, &bytes)?;
+        assert_eq!(out, [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+
+        out.clear();
+        enc.encode_primitive_with_vr(&mut out, VR::OW, &PrimitiveValue::from([0x0102_u16]))?;
+        assert_eq!(out, [0x01, 0x02]);
+
+        Ok(())
+    }
 }
@@ -1,7 +1,7 @@
 //! This module contains all DICOM data element encoding logic.
 use byteordered::Endianness;
 use dicom_core::value::serialize::{encode_date, encode_datetime, encode_time};
-use dicom_core::{DataElementHeader, PrimitiveValue, Tag};
+use dicom_core::{DataElementHeader, PrimitiveValue, Tag, VR};
 use snafu::{Backtrace, ResultExt, Snafu};
 use std::fmt;
 use std::io::{self, Write};
@@ -329,6 +329,24 @@ pub trait Encode {
     where
         W: Write;
 
+    /// Encode and write the primitive value of a data element
+    /// with the given value representation to the given destination.
+    ///
+    /// Unlike [`encode_primitive`](Encode::encode_primitive),
+    /// this considers the value representation
+    /// of values given as raw bytes (`PrimitiveValue::U8`),
+    /// such as pixel data in `OW`,
+    /// which are assumed to be in little endian.
+    /// The default implementation writes these bytes as they are,
+    /// which is correct for little endian encoders.
+    fn encode_primitive_with_vr<W>(&self, to: W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        let _ = vr;
+        self.encode_primitive(to, value)
+    }
+
     /// Encode and write a DICOM pixel data offset table
     /// to the given destination.
     ///
@@ -387,6 +405,13 @@ where
         (**self).encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr<W>(&self, to: W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        (**self).encode_primitive_with_vr(to, vr, value)
+    }
+
     fn encode_offset_table<W>(&self, to: W, offset_table: &[u32]) -> Result<usize>
     where
         W: Write,
@@ -441,6 +466,13 @@ where
         (**self).encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr<W>(&self, to: W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        (**self).encode_primitive_with_vr(to, vr, value)
+    }
+
     fn encode_offset_table<W>(&self, to: W, offset_table: &[u32]) -> Result<usize>
     where
         W: Write,
@@ -488,6 +520,21 @@ pub trait EncodeTo<W: ?Sized> {
     where
         W: Write;
 
+    /// Encode and write the primitive value of a data element
+    /// with the given value representation to the given destination.
+    ///
+    /// See [`Encode::encode_primitive_with_vr`] for the difference
+    /// with [`encode_primitive`](EncodeTo::encode_primitive).
+    /// The default implementation writes values as
+    /// [`encode_primitive`](EncodeTo::encode_primitive) does.
+    fn encode_primitive_with_vr(&self, to: &mut W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        let _ = vr;
+        self.encode_primitive(to, value)
+    }
+
     /// Encode and write a DICOM pixel data offset table
     /// to the given destination.
     ///
@@ -547,6 +594,13 @@ where
         (**self).encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr(&self, to: &mut W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        (**self).encode_primitive_with_vr(to, vr, value)
+    }
+
     fn encode_offset_table(&self, to: &mut W, offset_table: &[u32]) -> Result<usize>
     where
         W: Write,
@@ -602,6 +656,13 @@ where
         (**self).encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr(&self, to: &mut W, vr: VR, value: &PrimitiveValue) -> Result<usize>
+    where
+        W: Write,
+    {
+        (**self).encode_primitive_with_vr(to, vr, value)
+    }
+
     fn encode_offset_table(&self, to: &mut W, offset_table: &[u32]) -> Result<usize>
     where
         W: Write,
@@ -744,6 +805,15 @@ where
         self.inner.encode_primitive(to, value)
     }
 
+    fn encode_primitive_with_vr(
+        &self,
+        to: &mut W,
+        vr: VR,
+        value: &PrimitiveValue,
+    ) -> Result<usize> {
+        self.inner.encode_primitive_with_vr(to, vr, value)
+    }
+
     fn encode_offset_table(&self, to: &mut W, offset_table: &[u32]) -> Result<usize> {
         self.inner.encode_offset_table(to, offset_table)
     }
@@ -16,19 +16,30 @@
 //! | ISO-IR 127 (ISO-8859-6): The Latin/Arabic character set | ✓ | ✓ |
 //! | ISO-IR 138 (ISO-8859-8): The Latin/Hebrew character set | ✓ | ✓ |
 //! | ISO-IR 144 (ISO-8859-5): The Latin/Cyrillic character set | ✓ | ✓ |
-//! | ISO-IR 148 (ISO-8859-9): Latin no. 5, the Turkish character set  | x | x |
+//! | ISO-IR 148 (ISO-8859-9): Latin no. 5, the Turkish character set  | ✓ | ✓ |
 //! | ISO-IR 149 (WINDOWS_949): The KS X 1001 character set (Korean) | ✓ | ✓ |
-//! | ISO-IR 159: The JIS X 0212-1990 character set (supplementary Japanese characters) | x | x |
+//! | ISO-IR 159: The JIS X 0212-1990 character set (supplementary Japanese characters) | ✓ | x |
 //! | ISO-IR 166 (WINDOWS_874): The TIS 620-2533 character set (Thai) | ✓ | ✓ |
 //! | ISO-IR 192: The Unicode character set based on the UTF-8 encoding | ✓ | ✓ |
 //! | GB18030: The Simplified Chinese character set | ✓ | ✓ |
 //! | GB2312: Simplified Chinese character set | ✓ | ✓ |
 //! | GBK: Simplified Chinese character set | ✓ | ✓ |
+//!
+//! Multi-valued _Specific Character Set_ attributes,
+//! which enable ISO 2022 code extensions
+//! (such as `\ISO 2022 IR 87` for Japanese or `\ISO 2022 IR 149` for Korean),
+//! are also supported
+//! through [`SpecificCharacterSet::from_codes`].
+//! In this case, text is decoded and encoded
+//! by switching between the declared character sets through escape sequences
+//! as described in PS3.5 section 6.1.2.5.
+//! ISO-IR 159 is only supported in this mode.
+//!
 //! These capabilities are available through [`SpecificCharacterSet`].
 
 use encoding::all::{
-    GB18030, GBK, ISO_2022_JP, ISO_8859_1, ISO_8859_2, ISO_8859_3, ISO_8859_4, ISO_8859_5,
-    ISO_8859_6, ISO_8859_7, ISO_8859_8, UTF_8, WINDOWS_31J, WINDOWS_874, WINDOWS_949,
+    EUC_JP, GB18030, GBK, ISO_2022_JP, ISO_8859_1, ISO_8859_2, ISO_8859_3, ISO_8859_4, ISO_8859_5,
+    ISO_8859_6, ISO_8859_7, ISO_8859_8, UTF_8, WINDOWS_31J, WINDOWS_874, WINDOWS_949, WINDOWS_1254,
 };
 use encoding::{DecoderTrap, EncoderTrap, Encoding, RawDecoder, StringWriter};
 use snafu::{Backtrace, Snafu};
@@ -172,6 +183,60 @@ impl SpecificCharacterSet {
     pub fn from_code(code: &str) -> Option<Self> {
         CharsetImpl::from_code(code).map(SpecificCharacterSet)
     }
+
+    /// Obtain the specific character set identified by
+    /// all values of a _Specific Character Set_ (0008, 0005) attribute.
+    ///
+    /// With a single value, this is equivalent to [`from_code`](Self::from_code),
+    /// except that an empty value refers to the default character set.
+    /// With multiple values,
+    /// a character set with ISO 2022 code extensions is created,
+    /// in which case all values must be defined terms
+    /// of the form `ISO 2022 IR <number>`
+    /// (the first value may also be empty, referring to the default repertoire).
+    ///
+    /// Returns `None` if any of the values is not supported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dicom_encoding::text::{SpecificCharacterSet, TextCodec};
+    ///
+    /// let character_set = SpecificCharacterSet::from_codes(["", "ISO 2022 IR 87"]).unwrap();
+    /// assert_eq!(
+    ///     character_set.decode(b"Yamada^Tarou=\x1b$B;3ED\x1b(B^\x1b$BB@O:\x1b(B").unwrap(),
+    ///     "Yamada^Tarou=山田^太郎",
+    /// );
+    /// ```
+    pub fn from_codes<I, S>(codes: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let codes: Vec<S> = codes.into_iter().collect();
+        match codes.as_slice() {
+            [] => Some(SpecificCharacterSet::default()),
+            [code] if code.as_ref().trim().is_empty() => Some(SpecificCharacterSet::default()),
+            [code] => Self::from_code(code.as_ref()),
+            [first, rest @ ..] => {
+                if rest.len() >= MAX_ISO_2022_TERMS {
+                    return None;
+                }
+                let mut terms = [None; MAX_ISO_2022_TERMS];
+                let first = first.as_ref().trim();
+                if !first.is_empty() {
+                    terms[0] = Some(Iso2022Term::from_code(first)?);
+                }
+                for (slot, code) in terms[1..].iter_mut().zip(rest) {
+                    *slot = Some(Iso2022Term::from_code(code.as_ref().trim())?);
+                }
+                Some(SpecificCharacterSet(CharsetImpl::Iso2022(Iso2022Terms {
+                    terms,
+                    len: codes.len() as u8,
+                })))
+            }
+        }
+    }
 }
 
 impl TextCodec for SpecificCharacterSet {
@@ -219,6 +284,8 @@ enum CharsetImpl {
     IsoIr138,
     /// **ISO-IR 144** (ISO-8859-5): The Latin/Cyrillic character set.
     IsoIr144,
+    /// **ISO-IR 148** (ISO-8859-9): Latin alphabet no. 5, the Turkish character set.
+    IsoIr148,
     /// **ISO-IR 149**: The Korean character set.
     IsoIr149,
     /// **ISO-IR 166**: The Thai character set.
@@ -229,6 +296,8 @@ enum CharsetImpl {
     Gb18030,
     /// **Gbk**: The Simplified Chinese character set.
     Gbk,
+    /// A combination of character sets through ISO 2022 code extensions.
+    Iso2022(Iso2022Terms),
     // Support for more text encodings is tracked in issue #40.
 }
 
@@ -251,6 +320,7 @@ impl CharsetImpl {
             "ISO_IR_127" | "ISO_IR 127" | "ISO 2022 IR 127" => Some(IsoIr127),
             "ISO_IR_138" | "ISO_IR 138" | "ISO 2022 IR 138" => Some(IsoIr138),
             "ISO_IR_144" | "ISO_IR 144" | "ISO 2022 IR 144" => Some(IsoIr144),
+            "ISO_IR_148" | "ISO_IR 148" | "ISO 2022 IR 148" => Some(IsoIr148),
             "ISO_IR_149" | "ISO_IR 149" | "ISO 2022 IR 149" => Some(IsoIr149),
             "ISO_IR_166" | "ISO_IR 166" | "ISO 2022 IR 166" => Some(IsoIr166),
             "ISO_IR_192" | "ISO_IR 192" => Some(IsoIr192),
@@ -263,6 +333,9 @@ impl CharsetImpl {
 
 impl TextCodec for CharsetImpl {
     fn name(&self) -> Cow<'static, str> {
+        if let CharsetImpl::Iso2022(terms) = self {
+            return Cow::Owned(terms.name());
+        }
         Cow::Borrowed(match self {
             CharsetImpl::Default => "ISO_IR 6",
             CharsetImpl::IsoIr13 => "ISO_IR 13",
@@ -275,11 +348,13 @@ impl TextCodec for CharsetImpl {
             CharsetImpl::IsoIr127 => "ISO_IR 127",
             CharsetImpl::IsoIr138 => "ISO_IR 138",
             CharsetImpl::IsoIr144 => "ISO_IR 144",
+            CharsetImpl::IsoIr148 => "ISO_IR 148",
             CharsetImpl::IsoIr149 => "ISO_IR 149",
             CharsetImpl::IsoIr166 => "ISO_IR 166",
             CharsetImpl::IsoIr192 => "ISO_IR 192",
             CharsetImpl::Gb18030 => "GB18030",
             CharsetImpl::Gbk => "GBK",
+            CharsetImpl::Iso2022(_) => unreachable!(),
         })
     }
 
@@ -296,11 +371,13 @@ impl TextCodec for CharsetImpl {
             CharsetImpl::IsoIr127 => IsoIr127CharacterSetCodec.decode(text),
             CharsetImpl::IsoIr138 => IsoIr138CharacterSetCodec.decode(text),
             CharsetImpl::IsoIr144 => IsoIr144CharacterSetCodec.decode(text),
+            CharsetImpl::IsoIr148 => IsoIr148CharacterSetCodec.decode(text),
             CharsetImpl::IsoIr149 => IsoIr149CharacterSetCodec.decode(text),
             CharsetImpl::IsoIr166 => IsoIr166CharacterSetCodec.decode(text),
             CharsetImpl::IsoIr192 => Utf8CharacterSetCodec.decode(text),
             CharsetImpl::Gb18030 => Gb18030CharacterSetCodec.decode(text),
             CharsetImpl::Gbk => GBKCharacterSetCodec.decode(text),
+            CharsetImpl::Iso2022(terms) => terms.decode(text),
         }
     }
 
@@ -317,11 +394,13 @@ impl TextCodec for CharsetImpl {
             CharsetImpl::IsoIr127 => IsoIr127CharacterSetCodec.encode(text),
             CharsetImpl::IsoIr138 => IsoIr138CharacterSetCodec.encode(text),
             CharsetImpl::IsoIr144 => IsoIr144CharacterSetCodec.encode(text),
+            CharsetImpl::IsoIr148 => IsoIr148CharacterSetCodec.encode(text),
             CharsetImpl::IsoIr149 => IsoIr149CharacterSetCodec.encode(text),
             CharsetImpl::IsoIr166 => IsoIr166CharacterSetCodec.encode(text),
             CharsetImpl::IsoIr192 => Utf8CharacterSetCodec.encode(text),
             CharsetImpl::Gb18030 => Gb18030CharacterSetCodec.encode(text),
             CharsetImpl::Gbk => GBKCharacterSetCodec.encode(text),
+            CharsetImpl::Iso2022(terms) => terms.encode(text),
         }
     }
 }
@@ -331,7 +410,12 @@ fn decode_text_trap(
     input: &[u8],
     output: &mut dyn StringWriter,
 ) -> bool {
-    let c = input[0];
+    write_octal_escape(input[0], output);
+    true
+}
+
+/// Write a byte which could not be decoded as an octal escape sequence.
+fn write_octal_escape(c: u8, output: &mut dyn StringWriter) {
     let o0 = c & 7;
     let o1 = (c & 56) >> 3;
     let o2 = (c & 192) >> 6;
@@ -339,7 +423,6 @@ fn decode_text_trap(
     output.write_char((o2 + b'0') as char);
     output.write_char((o1 + b'0') as char);
     output.write_char((o0 + b'0') as char);
-    true
 }
 
 /// Create and implement a character set type using the `encoding` crate.
@@ -403,12 +486,476 @@ decl_character_set!(IsoIr126CharacterSetCodec, "ISO_IR 126", ISO_8859_7);
 decl_character_set!(IsoIr127CharacterSetCodec, "ISO_IR 127", ISO_8859_6);
 decl_character_set!(IsoIr138CharacterSetCodec, "ISO_IR 138", ISO_8859_8);
 decl_character_set!(IsoIr144CharacterSetCodec, "ISO_IR 144", ISO_8859_5);
+// Windows-1254 is the superset of ISO-8859-9 in the WHATWG encoding standard
+decl_character_set!(IsoIr148CharacterSetCodec, "ISO_IR 148", WINDOWS_1254);
 decl_character_set!(IsoIr149CharacterSetCodec, "ISO_IR 149", WINDOWS_949);
 decl_character_set!(IsoIr166CharacterSetCodec, "ISO_IR 166", WINDOWS_874);
 decl_character_set!(Utf8CharacterSetCodec, "ISO_IR 192", UTF_8);
 decl_character_set!(Gb18030CharacterSetCodec, "GB18030", GB18030);
 decl_character_set!(GBKCharacterSetCodec, "GBK", GBK);
 
+/// The maximum number of values in a _Specific Character Set_
+/// with code extensions supported by this implementation.
+const MAX_ISO_2022_TERMS: usize = 8;
+
+/// A defined term of a single-byte or multi-byte character set
+/// with ISO 2022 code extensions.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+enum Iso2022Term {
+    IsoIr6,
+    IsoIr13,
+    IsoIr87,
+    IsoIr100,
+    IsoIr101,
+    IsoIr109,
+    IsoIr110,
+    IsoIr126,
+    IsoIr127,
+    IsoIr138,
+    IsoIr144,
+    IsoIr148,
+    IsoIr149,
+    IsoIr159,
+    IsoIr166,
+    IsoIr58,
+}
+
+impl Iso2022Term {
+    fn from_code(code: &str) -> Option<Self> {
+        use self::Iso2022Term::*;
+        match code {
+            "ISO 2022 IR 6" => Some(IsoIr6),
+            "ISO 2022 IR 13" => Some(IsoIr13),
+            "ISO 2022 IR 87" => Some(IsoIr87),
+            "ISO 2022 IR 100" => Some(IsoIr100),
+            "ISO 2022 IR 101" => Some(IsoIr101),
+            "ISO 2022 IR 109" => Some(IsoIr109),
+            "ISO 2022 IR 110" => Some(IsoIr110),
+            "ISO 2022 IR 126" => Some(IsoIr126),
+            "ISO 2022 IR 127" => Some(IsoIr127),
+            "ISO 2022 IR 138" => Some(IsoIr138),
+            "ISO 2022 IR 144" => Some(IsoIr144),
+            "ISO 2022 IR 148" => Some(IsoIr148),
+            "ISO 2022 IR 149" => Some(IsoIr149),
+            "ISO 2022 IR 159" => Some(IsoIr159),
+            "ISO 2022 IR 166" => Some(IsoIr166),
+            "ISO 2022 GBK" | "ISO 2022 IR 58" => Some(IsoIr58),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        use self::Iso2022Term::*;
+        match self {
+            IsoIr6 => "ISO 2022 IR 6",
+            IsoIr13 => "ISO 2022 IR 13",
+            IsoIr87 => "ISO 2022 IR 87",
+            IsoIr100 => "ISO 2022 IR 100",
+            IsoIr101 => "ISO 2022 IR 101",
+            IsoIr109 => "ISO 2022 IR 109",
+            IsoIr110 => "ISO 2022 IR 110",
+            IsoIr126 => "ISO 2022 IR 126",
+            IsoIr127 => "ISO 2022 IR 127",
+            IsoIr138 => "ISO 2022 IR 138",
+            IsoIr144 => "ISO 2022 IR 144",
+            IsoIr148 => "ISO 2022 IR 148",
+            IsoIr149 => "ISO 2022 IR 149",
+            IsoIr159 => "ISO 2022 IR 159",
+            IsoIr166 => "ISO 2022 IR 166",
+            IsoIr58 => "ISO 2022 IR 58",
+        }
+    }
+
+    /// The graphic set which this term designates to the G0 code element.
+    fn g0(self) -> Option<GraphicSet> {
+        use self::Iso2022Term::*;
+        match self {
+            IsoIr13 => Some(GraphicSet::JisRoman),
+            IsoIr87 => Some(GraphicSet::JisX0208),
+            IsoIr159 => Some(GraphicSet::JisX0212),
+            IsoIr149 | IsoIr58 => None,
+            _ => Some(GraphicSet::Ascii),
+        }
+    }
+
+    /// The graphic set which this term designates to the G1 code element.
+    fn g1(self) -> Option<GraphicSet> {
+        use self::Iso2022Term::*;
+        match self {
+            IsoIr6 | IsoIr87 | IsoIr159 => None,
+            IsoIr13 => Some(GraphicSet::JisKatakana),
+            IsoIr100 => Some(GraphicSet::Iso8859(b'A')),
+            IsoIr101 => Some(GraphicSet::Iso8859(b'B')),
+            IsoIr109 => Some(GraphicSet::Iso8859(b'C')),
+            IsoIr110 => Some(GraphicSet::Iso8859(b'D')),
+            IsoIr126 => Some(GraphicSet::Iso8859(b'F')),
+            IsoIr127 => Some(GraphicSet::Iso8859(b'G')),
+            IsoIr138 => Some(GraphicSet::Iso8859(b'H')),
+            IsoIr144 => Some(GraphicSet::Iso8859(b'L')),
+            IsoIr148 => Some(GraphicSet::Iso8859(b'M')),
+            IsoIr166 => Some(GraphicSet::Iso8859(b'T')),
+            IsoIr149 => Some(GraphicSet::KsX1001),
+            IsoIr58 => Some(GraphicSet::Gb2312),
+        }
+    }
+}
+
+/// A graphic character set which can be invoked
+/// through an ISO 2022 escape sequence.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum GraphicSet {
+    /// ISO-IR 6 in G0
+    Ascii,
+    /// JIS X 0201 Romaji in G0
+    JisRoman,
+    /// JIS X 0201 Katakana in G1
+    JisKatakana,
+    /// The right-hand part of an ISO 8859 character set in G1,
+    /// identified by the final byte of its escape sequence
+    Iso8859(u8),
+    /// JIS X 0208 in G0
+    JisX0208,
+    /// JIS X 0212 in G0
+    JisX0212,
+    /// KS X 1001 in G1
+    KsX1001,
+    /// GB 2312 in G1
+    Gb2312,
+}
+
+impl GraphicSet {
+    /// All escape sequences recognized when decoding text,
+    /// and the graphic sets which they designate.
+    const ESCAPE_SEQUENCES: &'static [(&'static [u8], GraphicSet)] = &[
+        (b"\x1b(B", GraphicSet::Ascii),
+        (b"\x1b(J", GraphicSet::JisRoman),
+        (b"\x1b)I", GraphicSet::JisKatakana),
+        (b"\x1b-A", GraphicSet::Iso8859(b'A')),
+        (b"\x1b-B", GraphicSet::Iso8859(b'B')),
+        (b"\x1b-C", GraphicSet::Iso8859(b'C')),
+        (b"\x1b-D", GraphicSet::Iso8859(b'D')),
+        (b"\x1b-F", GraphicSet::Iso8859(b'F')),
+        (b"\x1b-G", GraphicSet::Iso8859(b'G')),
+        (b"\x1b-H", GraphicSet::Iso8859(b'H')),
+        (b"\x1b-L", GraphicSet::Iso8859(b'L')),
+        (b"\x1b-M", GraphicSet::Iso8859(b'M')),
+        (b"\x1b-T", GraphicSet::Iso8859(b'T')),
+        (b"\x1b$B", GraphicSet::JisX0208),
+        (b"\x1b$@", GraphicSet::JisX0208),
+        (b"\x1b$(D", GraphicSet::JisX0212),
+        (b"\x1b$)C", GraphicSet::KsX1001),
+        (b"\x1b$)A", GraphicSet::Gb2312),
+    ];
+
+    /// Match an escape sequence at the start of the given text,
+    /// returning the designated graphic set and the length of the sequence.
+    fn from_escape(text: &[u8]) -> Option<(GraphicSet, usize)> {
+        Self::ESCAPE_SEQUENCES
+            .iter()
+            .find(|(seq, _)| text.starts_with(seq))
+            .map(|(seq, set)| (*set, seq.len()))
+    }
+
+    /// The escape sequence which designates this graphic set.
+    fn escape(self) -> &'static [u8] {
+        Self::ESCAPE_SEQUENCES
+            .iter()
+            .find(|(_, set)| *set == self)
+            .map(|(seq, _)| *seq)
+            .unwrap()
+    }
+
+    /// Whether this graphic set is invoked into the G1 code element.
+    fn is_g1(self) -> bool {
+        matches!(
+            self,
+            GraphicSet::JisKatakana
+                | GraphicSet::Iso8859(_)
+                | GraphicSet::KsX1001
+                | GraphicSet::Gb2312
+        )
+    }
+
+    /// Whether this graphic set uses two bytes per character.
+    fn is_multi_byte(self) -> bool {
+        matches!(
+            self,
+            GraphicSet::JisX0208 | GraphicSet::JisX0212 | GraphicSet::KsX1001 | GraphicSet::Gb2312
+        )
+    }
+
+    /// The single-byte encoding of an ISO 8859 right-hand part.
+    fn iso_8859_encoding(final_byte: u8) -> &'static dyn Encoding {
+        match final_byte {
+            b'A' => ISO_8859_1,
+            b'B' => ISO_8859_2,
+            b'C' => ISO_8859_3,
+            b'D' => ISO_8859_4,
+            b'F' => ISO_8859_7,
+            b'G' => ISO_8859_6,
+            b'H' => ISO_8859_8,
+            b'L' => ISO_8859_5,
+            b'M' => WINDOWS_1254,
+            _ => WINDOWS_874,
+        }
+    }
+
+    /// Decode a single character in this graphic set,
+    /// returning `None` if the bytes do not form a valid character.
+    fn decode_char(self, bytes: &[u8]) -> Option<String> {
+        let decoded = match (self, bytes) {
+            (GraphicSet::Ascii | GraphicSet::JisRoman, [b]) if b.is_ascii() => {
+                return Some(char::from(*b).to_string());
+            }
+            (GraphicSet::JisKatakana, [b @ 0xA1..=0xDF]) => {
+                return char::from_u32(0xFF61 + u32::from(b - 0xA1)).map(String::from);
+            }
+            (GraphicSet::Iso8859(f), [b @ 0xA0..=0xFF]) => {
+                Self::iso_8859_encoding(f).decode(&[*b], DecoderTrap::Strict)
+            }
+            (GraphicSet::JisX0208, [b0, b1]) => {
+                EUC_JP.decode(&[b0 | 0x80, b1 | 0x80], DecoderTrap::Strict)
+            }
+            (GraphicSet::JisX0212, [b0, b1]) => {
+                EUC_JP.decode(&[0x8F, b0 | 0x80, b1 | 0x80], DecoderTrap::Strict)
+            }
+            (GraphicSet::KsX1001, [b0, b1]) => WINDOWS_949.decode(&[*b0, *b1], DecoderTrap::Strict),
+            (GraphicSet::Gb2312, [b0, b1]) => GBK.decode(&[*b0, *b1], DecoderTrap::Strict),
+            _ => return None,
+        };
+        decoded.ok()
+    }
+
+    /// Encode a single character in this graphic set into `out`,
+    /// returning `false` if the character is not in this set.
+    fn encode_char(self, c: char, out: &mut Vec<u8>) -> bool {
+        let mut buf = [0; 4];
+        let c_str = &*c.encode_utf8(&mut buf);
+        let encoded = match self {
+            GraphicSet::Ascii | GraphicSet::JisRoman => {
+                if c.is_ascii() {
+                    out.push(c as u8);
+                    return true;
+                }
+                return false;
+            }
+            GraphicSet::JisKatakana => {
+                if let '\u{FF61}'..='\u{FF9F}' = c {
+                    out.push((c as u32 - 0xFF61) as u8 + 0xA1);
+                    return true;
+                }
+                return false;
+            }
+            // decoding only
+            GraphicSet::JisX0212 => return false,
+            GraphicSet::Iso8859(f) => Self::iso_8859_encoding(f).encode(c_str, EncoderTrap::Strict),
+            GraphicSet::JisX0208 => EUC_JP.encode(c_str, EncoderTrap::Strict),
+            GraphicSet::KsX1001 => WINDOWS_949.encode(c_str, EncoderTrap::Strict),
+            GraphicSet::Gb2312 => GBK.encode(c_str, EncoderTrap::Strict),
+        };
+        match (self, encoded.as_deref()) {
+            (GraphicSet::Iso8859(_), Ok([b @ 0xA0..=0xFF])) => out.push(*b),
+            (GraphicSet::JisX0208, Ok([b0 @ 0xA1..=0xFE, b1 @ 0xA1..=0xFE])) => {
+                out.extend([b0 & 0x7F, b1 & 0x7F])
+            }
+            (_, Ok([b0 @ 0xA1..=0xFE, b1 @ 0xA1..=0xFE])) if self.is_multi_byte() => {
+                out.extend([*b0, *b1])
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// A sequence of character sets
+/// combined through ISO 2022 code extensions,
+/// as declared by a multi-valued _Specific Character Set_.
+///
+/// The first term defines the initial state of the code elements,
+/// which is restored at the end of each value
+/// and before delimiters and control characters.
+/// `None` in the first position stands for an empty first value,
+/// which is equivalent to ISO 2022 IR 6.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+struct Iso2022Terms {
+    terms: [Option<Iso2022Term>; MAX_ISO_2022_TERMS],
+    len: u8,
+}
+
+impl Iso2022Terms {
+    fn iter(&self) -> impl Iterator<Item = Iso2022Term> + '_ {
+        self.terms[..self.len as usize]
+            .iter()
+            .map(|term| term.unwrap_or(Iso2022Term::IsoIr6))
+    }
+
+    fn name(&self) -> String {
+        self.terms[..self.len as usize]
+            .iter()
+            .map(|term| term.map(Iso2022Term::code).unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\\")
+    }
+
+    /// The graphic sets in G0 and G1 at the beginning of each value.
+    fn initial_state(&self) -> (GraphicSet, Option<GraphicSet>) {
+        let first = self.terms[0].unwrap_or(Iso2022Term::IsoIr6);
+        (first.g0().unwrap_or(GraphicSet::Ascii), first.g1())
+    }
+
+    fn decode(&self, text: &[u8]) -> DecodeResult<String> {
+        let initial = self.initial_state();
+        let (mut g0, mut g1) = initial;
+        let mut out = String::with_capacity(text.len());
+        let mut i = 0;
+        while i < text.len() {
+            let c = text[i];
+            if c == 0x1B {
+                if let Some((set, len)) = GraphicSet::from_escape(&text[i..]) {
+                    if set.is_g1() {
+                        g1 = Some(set);
+                    } else {
+                        g0 = set;
+                    }
+                    i += len;
+                    continue;
+                }
+            }
+            let char_len = match (c, g0, g1) {
+                (0x21..=0x7E, g0, _) if g0.is_multi_byte() => 2,
+                (0x80..=0xFF, _, Some(g1)) if g1.is_multi_byte() => 2,
+                _ => 1,
+            };
+            let decoded = text.get(i..i + char_len).and_then(|bytes| match c {
+                0x00..=0x7F if char_len == 1 => {
+                    if c < 0x20 || (!g0.is_multi_byte() && matches!(c, b'\\' | b'^' | b'=')) {
+                        // delimiters and control characters
+                        // reset the code elements to their initial state
+                        (g0, g1) = initial;
+                    }
+                    Some(char::from(c).to_string())
+                }
+                0x00..=0x7F => g0.decode_char(bytes),
+                _ => g1.and_then(|g1| g1.decode_char(bytes)),
+            });
+            match decoded {
+                Some(decoded) => {
+                    out.push_str(&decoded);
+                    i += char_len;
+                }
+                None => {
+                    write_octal_escape(c, &mut out);
+                    i += 1;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn encode(&self, text: &str) -> EncodeResult<Vec<u8>> {
+        let initial = self.initial_state();
+        let (mut g0, mut g1) = initial;
+        let mut out = Vec::with_capacity(text.len());
+
+        // switch back to the initial state where required
+        let reset = |g0: &mut GraphicSet, g1: &mut Option<GraphicSet>, out: &mut Vec<u8>| {
+            if *g0 != initial.0 {
+                out.extend_from_slice(initial.0.escape());
+                *g0 = initial.0;
+            }
+            if *g1 != initial.1 {
+                if let Some(set) = initial.1 {
+                    out.extend_from_slice(set.escape());
+                }
+                *g1 = initial.1;
+            }
+        };
+
+        for c in text.chars() {
+            if c < ' ' || matches!(c, '\\' | '^' | '=') {
+                reset(&mut g0, &mut g1, &mut out);
+                out.push(c as u8);
+                continue;
+            }
+            if g0.encode_char(c, &mut out) || g1.is_some_and(|g1| g1.encode_char(c, &mut out)) {
+                continue;
+            }
+            let designated = self
+                .iter()
+                .flat_map(|term| [term.g0(), term.g1()])
+                .flatten()
+                .find(|set| {
+                    let mut encoded = Vec::new();
+                    if !set.encode_char(c, &mut encoded) {
+                        return false;
+                    }
+                    out.extend_from_slice(set.escape());
+                    out.extend(encoded);
+                    true
+                });
+            match designated {
+                Some(set) if set.is_g1() => g1 = Some(set),
+                Some(set) => g0 = set,
+                None => {
+                    return EncodeCustomSnafu {
+                        message: format!("character {:?} cannot be encoded in {}", c, self.name()),
+                    }
+                    .fail();
+                }
+            }
+        }
+        reset(&mut g0, &mut g1, &mut out);
+        Ok(out)
+    }
+}
+
+/// Split a text value into its individual values
+/// by the backslash character (`\`),
+/// without breaking multi-byte characters
+/// of ISO 2022 code extensions apart.
+///
+/// Unlike a naive split on byte `0x5C`,
+/// this function ignores delimiters
+/// while a multi-byte character set is designated to the G0 code element
+/// (such as JIS X 0208 via `ESC $ B`).
+///
+/// # Example
+///
+/// ```
+/// # use dicom_encoding::text::split_values;
+/// let values: Vec<&[u8]> = split_values(b"ABC\\\x1b$B\x3b\x5c\x1b(B\\DEF").collect();
+/// assert_eq!(values, [&b"ABC"[..], b"\x1b$B\x3b\x5c\x1b(B", b"DEF"]);
+/// ```
+pub fn split_values(text: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = Some(text);
+    std::iter::from_fn(move || {
+        let text = rest?;
+        let mut multi_byte = false;
+        let mut i = 0;
+        while i < text.len() {
+            match text[i] {
+                0x1B => {
+                    if let Some((set, len)) = GraphicSet::from_escape(&text[i..]) {
+                        if !set.is_g1() {
+                            multi_byte = set.is_multi_byte();
+                        }
+                        i += len;
+                        continue;
+                    }
+                }
+                b'\\' if !multi_byte => {
+                    rest = Some(&text[i + 1..]);
+                    return Some(&text[..i]);
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        rest = None;
+        Some(text)
+    })
+}
+
 /// The result of a text validation procedure (please see [`validate_iso_8859`]).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TextValidationOutcome {
@@ -585,6 +1132,86 @@ mod tests {
         test_codec(&codec, "мозг 2мм", b"\xDC\xDE\xD7\xD3\x202\xDC\xDC");
     }
 
+    #[test]
+    fn iso_ir_148_baseline() {
+        let codec = SpecificCharacterSet::from_code("ISO_IR 148").unwrap();
+        test_codec(codec, "Çağlar^Şükrü", b"\xC7a\xF0lar^\xDE\xFCkr\xFC");
+    }
+
+    #[test]
+    fn iso_2022_ir_87_extensions() {
+        let codec = SpecificCharacterSet::from_codes(["", "ISO 2022 IR 87"]).unwrap();
+        assert_eq!(codec.name(), "\\ISO 2022 IR 87");
+        test_codec(
+            &codec,
+            "Yamada^Tarou=山田^太郎=やまだ^たろう",
+            b"Yamada^Tarou=\x1b$B;3ED\x1b(B^\x1b$BB@O:\x1b(B=\x1b$B$d$^$@\x1b(B^\x1b$B$?$m$&\x1b(B",
+        );
+    }
+
+    #[test]
+    fn iso_2022_ir_13_ir_87_extensions() {
+        let codec = SpecificCharacterSet::from_codes(["ISO 2022 IR 13", "ISO 2022 IR 87"]).unwrap();
+        test_codec(
+            &codec,
+            "ﾔﾏﾀﾞ^ﾀﾛｳ=山田^太郎=やまだ^たろう",
+            b"\xd4\xcf\xc0\xde^\xc0\xdb\xb3=\x1b$B;3ED\x1b(J^\x1b$BB@O:\x1b(J=\x1b$B$d$^$@\x1b(J^\x1b$B$?$m$&\x1b(J",
+        );
+    }
+
+    #[test]
+    fn iso_2022_ir_149_extensions() {
+        let codec = SpecificCharacterSet::from_codes(["", "ISO 2022 IR 149"]).unwrap();
+        test_codec(
+            &codec,
+            "Hong^Gildong=洪^吉洞=홍^길동",
+            b"Hong^Gildong=\x1b$)C\xfb\xf3^\x1b$)C\xd1\xce\xd4\xd7=\x1b$)C\xc8\xab^\x1b$)C\xb1\xe6\xb5\xbf",
+        );
+    }
+
+    #[test]
+    fn iso_2022_ir_100_ir_144_extensions() {
+        let codec =
+            SpecificCharacterSet::from_codes(["ISO 2022 IR 100", "ISO 2022 IR 144"]).unwrap();
+        test_codec(
+            &codec,
+            "Günther^Иван",
+            b"G\xfcnther^\x1b-L\xb8\xd2\xd0\xdd\x1b-A",
+        );
+        // characters outside of all declared sets cannot be encoded
+        assert!(codec.encode("山田").is_err());
+    }
+
+    #[test]
+    fn specific_character_set_from_codes() {
+        assert_eq!(
+            SpecificCharacterSet::from_codes([""]),
+            Some(SpecificCharacterSet::default())
+        );
+        assert_eq!(
+            SpecificCharacterSet::from_codes(["ISO_IR 192"]),
+            Some(SpecificCharacterSet::ISO_IR_192)
+        );
+        assert_eq!(
+            SpecificCharacterSet::from_codes(["ISO 2022 IR 6", "ISO 2022 IR 87"])
+                .map(|cs| cs.name()),
+            Some("ISO 2022 IR 6\\ISO 2022 IR 87".into())
+        );
+        assert_eq!(SpecificCharacterSet::from_codes(["", "ISO_IR 87"]), None);
+        assert_eq!(
+            SpecificCharacterSet::from_codes(["", "ISO 2022 IR 999"]),
+            None
+        );
+    }
+
+    #[test]
+    fn split_values_with_code_extensions() {
+        let values: Vec<_> = split_values(b"A\\\x1b$B\x5c\x5c\x1b(B\\").collect();
+        assert_eq!(values, [&b"A"[..], b"\x1b$B\x5c\x5c\x1b(B", b""]);
+        let values: Vec<_> = split_values(b"").collect();
+        assert_eq!(values, [&b""[..]]);
+    }
+
     #[test]
     fn iso_ir_149_baseline() {
         let codec = SpecificCharacterSet(CharsetImpl::IsoIr149);
@@ -486,6 +486,11 @@ impl<D, R, W> TransferSyntax<D, R, W> {
         self.byte_order
     }
 
+    /// Check whether this transfer syntax uses explicit value representations.
+    pub const fn is_explicit_vr(&self) -> bool {
+        self.explicit_vr
+    }
+
     /// Obtain this transfer syntax' codec specification.
     pub fn codec(&self) -> &Codec<D, R, W> {
         &self.codec
@@ -549,6 +554,27 @@ impl<D, R, W> TransferSyntax<D, R, W> {
         )
     }
 
+    /// Check whether data sets in this transfer syntax can be fully decoded,
+    /// including the pixel data.
+    ///
+    /// This is equivalent to [`can_decode_all`](Self::can_decode_all).
+    pub fn can_decode(&self) -> bool {
+        self.can_decode_all()
+    }
+
+    /// Check whether data sets can be fully encoded in this transfer syntax,
+    /// including the pixel data.
+    ///
+    /// For transfer syntaxes with encapsulated pixel data,
+    /// this means that a pixel data writer is available,
+    /// so that native pixel data can be compressed on write.
+    pub fn can_encode(&self) -> bool {
+        matches!(
+            self.codec,
+            Codec::None | Codec::Dataset(Some(_)) | Codec::EncapsulatedPixelData(_, Some(_))
+        )
+    }
+
     /// Retrieve the appropriate data element decoder for this transfer syntax.
     /// Can yield none if decoding is not supported.
     ///
@@ -1,7 +1,8 @@
 use clap::Parser;
+use dicom_app_common::aeconfig::AeConfigOptions;
 use dicom_core::dicom_value;
 use dicom_core::{DataElement, PrimitiveValue, VR};
-use dicom_dictionary_std::{tags, uids};
+use dicom_dictionary_std::{StandardUidDictionary, tags, uids};
 use dicom_dump::DumpOptions;
 use dicom_encoding::transfer_syntax;
 use dicom_object::{StandardDataDictionary, mem::InMemDicomObject, open_file};
@@ -24,8 +25,11 @@ mod query;
 #[derive(Debug, Parser)]
 #[command(version)]
 struct App {
-    /// socket address to FIND SCP (example: "127.0.0.1:1045")
-    addr: String,
+    /// socket address to FIND SCP (example: "127.0.0.1:1045"),
+    /// or the name of an AE in the configuration file
+    /// (omit if `--remote` is given)
+    #[arg(required_unless_present = "to")]
+    addr: Option<String>,
     /// a DICOM file representing the query object
     file: Option<PathBuf>,
     /// a file containing lines of queries
@@ -39,18 +43,19 @@ struct App {
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
     /// the calling AE title
-    #[arg(long = "calling-ae-title", default_value = "FIND-SCU")]
-    calling_ae_title: String,
+    /// [default: as in the configuration, or FIND-SCU]
+    #[arg(long = "calling-ae-title")]
+    calling_ae_title: Option<String>,
     /// the called AE title
     #[arg(long = "called-ae-title")]
     called_ae_title: Option<String>,
     /// the maximum PDU length
+    /// [default: from the AE configuration, or 16378]
     #[arg(
         long = "max-pdu-length",
-        default_value = "16378",
         value_parser(clap::value_parser!(u32).range(1018..))
     )]
-    max_pdu_length: u32,
+    max_pdu_length: Option<u32>,
 
     /// use patient root information model
     #[arg(short = 'P', long, conflicts_with = "study", conflicts_with = "mwl")]
@@ -66,6 +71,9 @@ struct App {
         conflicts_with = "patient"
     )]
     mwl: bool,
+
+    #[command(flatten, next_help_heading = "AE Configuration Options")]
+    ae_config: AeConfigOptions,
 }
 
 fn main() {
@@ -91,6 +99,11 @@ enum Error {
     /// Could not dump DICOM output
     DumpOutput { source: std::io::Error },
 
+    /// Could not resolve the FIND SCP
+    AeConfig {
+        source: dicom_app_common::aeconfig::AeConfigError,
+    },
+
     #[snafu(whatever, display("{}", message))]
     Other {
         message: String,
@@ -186,6 +199,7 @@ fn run() -> Result<(), Error> {
         patient,
         study,
         mwl,
+        ae_config,
     } = App::parse();
 
     tracing::subscriber::set_global_default(
@@ -197,6 +211,22 @@ fn run() -> Result<(), Error> {
         error!("{}", snafu::Report::from_error(e));
     });
 
+    let (peer, first_file) = ae_config.resolve(addr).context(AeConfigSnafu)?;
+    let max_pdu_length = peer.max_pdu_length_or(max_pdu_length, 16378);
+    let addr = peer.address;
+    let calling_ae_title = calling_ae_title
+        .or(peer.calling_ae_title)
+        .unwrap_or_else(|| "FIND-SCU".to_string());
+    if peer.tls {
+        whatever!("TLS connections are not supported by this tool");
+    }
+    let file = match (first_file, file) {
+        (Some(_), Some(file)) => {
+            whatever!("unexpected argument '{}'", file.display());
+        }
+        (first_file, file) => first_file.map(PathBuf::from).or(file),
+    };
+
     let dcm_query = build_query(file, query_file, query, patient, study, mwl, verbose)?;
 
     let abstract_syntax = match (patient, study, mwl) {
@@ -213,7 +243,13 @@ fn run() -> Result<(), Error> {
     };
 
     if verbose {
-        info!("Establishing association with '{}'...", &addr);
+        info!(
+            "Establishing association with '{}' for {}...",
+            &addr,
+            StandardUidDictionary
+                .name_of(abstract_syntax)
+                .unwrap_or(abstract_syntax)
+        );
     }
 
     let mut scu_opt = ClientAssociationOptions::new()
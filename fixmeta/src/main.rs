@@ -0,0 +1,77 @@
+//! A CLI tool for repairing the file meta group of DICOM files.
+//!
+//! This command line tool reads a DICOM file in lenient mode,
+//! detecting the actual encoding of the data set
+//! when the file meta group is missing
+//! or its transfer syntax contradicts the data set,
+//! and saves the file again with a conforming file meta group,
+//! re-encoding the data set in the declared transfer syntax if necessary.
+//! All problems found while reading are reported.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_object::OpenFileOptions;
+
+/// Repair the file meta group of a DICOM file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Path to the DICOM file to repair
+    file: PathBuf,
+    /// Path to the output file
+    /// (default is to overwrite the input file if anything was repaired)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// Only report the problems found, without saving the file
+    #[arg(short = 'n', long = "dry-run")]
+    dry_run: bool,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    let App {
+        file,
+        output,
+        dry_run,
+    } = App::parse();
+
+    let (obj, report) = OpenFileOptions::new()
+        .lenient(true)
+        .open_file_with_report(&file)
+        .unwrap_or_else(|e| {
+            tracing::error!("{}", snafu::Report::from_error(e));
+            std::process::exit(-1);
+        });
+
+    for warning in &report.warnings {
+        println!("{}: {warning}", file.display());
+    }
+    if dry_run {
+        return;
+    }
+
+    let output = match output {
+        Some(output) => output,
+        None if report.warnings.is_empty() => {
+            println!("{}: nothing to repair", file.display());
+            return;
+        }
+        None => file,
+    };
+
+    obj.write_to_file(&output).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+
+    println!(
+        "Saved {} with transfer syntax {}",
+        output.display(),
+        obj.meta().transfer_syntax()
+    );
+}
@@ -11,31 +11,77 @@
 //! with the same SOP instance UID and SOP class UID as the base file,
 //! encoded in Explicit VR Little Endian.
 //!
+//! H.264 and HEVC video streams (`.h264`, `.h265`, ...)
+//! are encapsulated as is in the matching video transfer syntax,
+//! with the image pixel and cine attributes taken from the stream.
+//!
+//! Alternatively, with `--secondary-capture`,
+//! the image is wrapped into a new Secondary Capture Image Storage object
+//! with newly generated UIDs
+//! (optionally under an organization root given with `--uid-root`),
+//! optionally copying the patient and study attributes
+//! from a template DICOM file.
+//!
 //! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.7.6.3.html
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
+use dicom_app_common::edit::SetOptions;
 use dicom_core::{
     DataElement, DicomValue, VR,
+    uid::UidGenerator,
     value::{PixelFragmentSequence, PrimitiveValue},
 };
 use dicom_dictionary_std::tags;
 use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, open_file};
+use dicom_pixeldata::{
+    secondary_capture::{SecondaryCaptureOptions, secondary_capture_from_image},
+    video::{SetVideoStream, VideoCodec, VideoOptions},
+};
 use image::DynamicImage;
+use snafu::ResultExt;
 
 type Result<T, E = snafu::Whatever> = std::result::Result<T, E>;
 
-/// Convert and replace a DICOM file's image with another image
+/// Convert and replace a DICOM file's image with another image,
+/// or create a new secondary capture DICOM file from an image
 #[derive(Debug, Parser)]
 #[command(version)]
 struct App {
     /// Path to the base DICOM file to read
-    dcm_file: PathBuf,
+    #[arg(required_unless_present = "secondary_capture")]
+    dcm_file: Option<PathBuf>,
     /// Path to the image file to replace the DICOM file
-    img_file: PathBuf,
+    /// (or an H.264/HEVC video stream)
+    #[arg(required_unless_present = "secondary_capture")]
+    img_file: Option<PathBuf>,
+    /// Create a new secondary capture DICOM file from the given image file
+    /// instead of replacing the image of a base DICOM file
+    #[arg(
+        long,
+        alias = "sc",
+        value_name = "IMG_FILE",
+        conflicts_with_all = ["dcm_file", "img_file", "retain_implementation"]
+    )]
+    secondary_capture: Option<PathBuf>,
+    /// Copy the patient and study attributes of the secondary capture
+    /// from this DICOM file
+    #[arg(long, requires = "secondary_capture")]
+    template: Option<PathBuf>,
+    /// Generate the UIDs of the secondary capture
+    /// under this organization root
+    /// (default is to derive them from a UUID under `2.25`)
+    #[arg(
+        long,
+        value_name = "ROOT",
+        requires = "secondary_capture",
+        value_parser = parse_uid_root
+    )]
+    uid_root: Option<UidGenerator>,
     /// Path to the output image
-    /// (default is to replace input extension with `.new.dcm`)
+    /// (default is to replace input extension with `.new.dcm`,
+    /// or with `.dcm` in secondary capture mode)
     #[arg(short = 'o', long = "out")]
     output: Option<PathBuf>,
     /// Override the transfer syntax UID (pixel data is not converted)
@@ -45,12 +91,18 @@ struct App {
     /// instead of writing native pixel data
     #[arg(long)]
     encapsulate: bool,
+    /// Frame rate of the video stream in frames per second
+    /// (default is to use the rate declared in the stream, or 30)
+    #[arg(long)]
+    frame_rate: Option<f64>,
     /// Retain the implementation class UID and version name from base DICOM
     #[arg(long)]
     retain_implementation: bool,
     /// Print more information about the image and the output file
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
+    #[command(flatten)]
+    set: SetOptions,
 }
 
 fn main() {
@@ -62,26 +114,66 @@ fn main() {
     let App {
         dcm_file,
         img_file,
+        secondary_capture,
+        template,
+        uid_root,
         output,
         encapsulate,
         transfer_syntax,
+        frame_rate,
         retain_implementation,
         verbose,
+        set,
     } = App::parse();
 
-    let output = output.unwrap_or_else(|| {
-        let mut path = dcm_file.clone();
-        path.set_extension("new.dcm");
-        path
-    });
+    let new_object = secondary_capture.is_some();
+    let (mut obj, img_file, output) = if let Some(img_file) = secondary_capture {
+        let output = output.unwrap_or_else(|| img_file.with_extension("dcm"));
+        let obj = new_secondary_capture(&img_file, template.as_deref(), uid_root, verbose)
+            .unwrap_or_else(|e| {
+                tracing::error!("{}", snafu::Report::from_error(e));
+                std::process::exit(-2);
+            });
+        (obj, img_file, output)
+    } else {
+        // both are required by the CLI outside of secondary capture mode
+        let (Some(dcm_file), Some(img_file)) = (dcm_file, img_file) else {
+            unreachable!();
+        };
+        let output = output.unwrap_or_else(|| {
+            let mut path = dcm_file.clone();
+            path.set_extension("new.dcm");
+            path
+        });
 
-    let mut obj = open_file(&dcm_file).unwrap_or_else(|e| {
-        tracing::error!("{}", snafu::Report::from_error(e));
-        std::process::exit(-1);
-    });
+        let obj = open_file(&dcm_file).unwrap_or_else(|e| {
+            tracing::error!("{}", snafu::Report::from_error(e));
+            std::process::exit(-1);
+        });
+        (obj, img_file, output)
+    };
+
+    let video_codec = video_codec_of(&img_file);
 
-    if encapsulate {
+    if video_codec.is_some() && new_object {
+        tracing::error!("Video streams cannot be stored as a secondary capture");
+        std::process::exit(-2);
+    }
+
+    if let Some(codec) = video_codec {
+        inject_video(
+            &mut obj,
+            codec,
+            img_file,
+            frame_rate,
+            transfer_syntax.clone(),
+            verbose,
+        )
+    } else if encapsulate {
         inject_encapsulated(&mut obj, img_file, verbose)
+    } else if new_object {
+        // pixel data is already in place
+        Ok(())
     } else {
         inject_image(&mut obj, img_file, verbose)
     }
@@ -90,6 +182,11 @@ fn main() {
         std::process::exit(-2);
     });
 
+    set.apply(&mut obj).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+
     let class_uid = obj.meta().media_storage_sop_class_uid.clone();
 
     let mut meta_builder = FileMetaTableBuilder::new()
@@ -98,6 +195,11 @@ fn main() {
         .transfer_syntax("1.2.840.10008.1.2.1")
         .media_storage_sop_class_uid(class_uid);
 
+    if video_codec.is_some() {
+        // video streams are kept in their own transfer syntax
+        meta_builder = meta_builder.transfer_syntax(obj.meta().transfer_syntax());
+    }
+
     if let Some(ts) = transfer_syntax {
         meta_builder = meta_builder.transfer_syntax(ts);
     }
@@ -130,6 +232,56 @@ fn main() {
     }
 }
 
+fn parse_uid_root(root: &str) -> Result<UidGenerator, String> {
+    UidGenerator::with_root(root).map_err(|e| e.to_string())
+}
+
+/// Create a secondary capture object from an image file,
+/// with the patient and study attributes of an optional template file.
+fn new_secondary_capture(
+    img_file: &Path,
+    template: Option<&Path>,
+    uid_generator: Option<UidGenerator>,
+    verbose: bool,
+) -> Result<DefaultDicomObject> {
+    let img = image::ImageReader::open(img_file)
+        .with_whatever_context(|_| format!("Could not open {}", img_file.display()))?
+        .with_guessed_format()
+        .with_whatever_context(|_| format!("Could not read {}", img_file.display()))?;
+    let format = img.format();
+    let img = img
+        .decode()
+        .with_whatever_context(|_| format!("Could not decode {}", img_file.display()))?;
+
+    if verbose {
+        println!("{}x{} {:?} image", img.width(), img.height(), img.color());
+    }
+
+    let mut options = SecondaryCaptureOptions::new();
+    if let Some(uid_generator) = uid_generator {
+        options = options.with_uid_generator(uid_generator);
+    }
+    if let Some(template) = template {
+        let template = open_file(template)
+            .with_whatever_context(|_| format!("Could not read {}", template.display()))?;
+        options = options.with_template(&template);
+    }
+
+    let mut obj = secondary_capture_from_image(&img, &options)
+        .whatever_context("Could not create secondary capture")?;
+
+    if format == Some(image::ImageFormat::Jpeg) {
+        // the samples have been through lossy compression before
+        obj.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION,
+            VR::CS,
+            PrimitiveValue::from("01"),
+        ));
+    }
+
+    Ok(obj)
+}
+
 fn inject_image(obj: &mut DefaultDicomObject, img_file: PathBuf, verbose: bool) -> Result<()> {
     let image_reader = image::ImageReader::open(img_file).unwrap_or_else(|e| {
         tracing::error!("{}", snafu::Report::from_error(e));
@@ -220,6 +372,49 @@ fn inject_encapsulated(
     Ok(())
 }
 
+/// Identify a video stream file by its extension.
+fn video_codec_of(path: &std::path::Path) -> Option<VideoCodec> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    match extension.as_str() {
+        "h264" | "264" | "avc" => Some(VideoCodec::H264),
+        "h265" | "265" | "hevc" => Some(VideoCodec::Hevc),
+        _ => None,
+    }
+}
+
+fn inject_video(
+    obj: &mut DefaultDicomObject,
+    codec: VideoCodec,
+    video_file: PathBuf,
+    frame_rate: Option<f64>,
+    transfer_syntax: Option<String>,
+    verbose: bool,
+) -> Result<()> {
+    let stream = std::fs::read(&video_file)
+        .with_whatever_context(|_| format!("Could not read {}", video_file.display()))?;
+
+    let mut options = VideoOptions::new();
+    if let Some(frame_rate) = frame_rate {
+        options = options.with_frame_rate(frame_rate);
+    }
+    if let Some(ts) = transfer_syntax {
+        options = options.with_transfer_syntax(ts);
+    }
+
+    let info = obj
+        .set_video_stream_with_options(codec, stream, &options)
+        .whatever_context("Could not encapsulate video stream")?;
+
+    if verbose {
+        println!(
+            "{}x{} {:?} video, {} frames",
+            info.width, info.height, codec, info.number_of_frames
+        );
+    }
+
+    Ok(())
+}
+
 fn update_from_img(obj: &mut DefaultDicomObject, img: &DynamicImage, verbose: bool) {
     let width = img.width();
     let height = img.height();
@@ -311,4 +506,114 @@ mod tests {
     fn verify_cli() {
         App::command().debug_assert();
     }
+
+    #[test]
+    fn secondary_capture_from_cli() {
+        use clap::Parser;
+
+        let app = App::parse_from([
+            "dicom-fromimage",
+            "--secondary-capture",
+            "photo.jpg",
+            "--template",
+            "study.dcm",
+        ]);
+        assert_eq!(app.secondary_capture.as_deref(), Some("photo.jpg".as_ref()));
+        assert_eq!(app.template.as_deref(), Some("study.dcm".as_ref()));
+        assert_eq!(app.dcm_file, None);
+        assert_eq!(app.uid_root, None);
+
+        let app = App::parse_from([
+            "dicom-fromimage",
+            "--sc",
+            "photo.jpg",
+            "--uid-root",
+            "1.2.826.0.1.3680043.10.1234",
+        ]);
+        assert_eq!(
+            app.uid_root.as_ref().map(|g| g.root()),
+            Some("1.2.826.0.1.3680043.10.1234")
+        );
+        assert!(
+            App::try_parse_from(["dicom-fromimage", "--sc", "photo.jpg", "--uid-root", "1.02"])
+                .is_err()
+        );
+
+        // a base file and an image are required otherwise
+        assert!(App::try_parse_from(["dicom-fromimage", "image.png"]).is_err());
+        assert!(App::try_parse_from(["dicom-fromimage", "--template", "study.dcm"]).is_err());
+        assert!(
+            App::try_parse_from([
+                "dicom-fromimage",
+                "base.dcm",
+                "image.png",
+                "--secondary-capture",
+                "photo.jpg"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn set_attributes_from_cli() {
+        use clap::Parser;
+        use dicom_core::{DataElement, VR};
+        use dicom_dictionary_std::tags;
+        use dicom_object::InMemDicomObject;
+
+        let app = App::parse_from([
+            "dicom-fromimage",
+            "--sc",
+            "photo.jpg",
+            "--set",
+            "PatientName=DOE^JANE",
+            "--set",
+            "SeriesNumber=2",
+            "--set-seq",
+            "ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3",
+        ]);
+        let mut obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            "Doe^John",
+        )]);
+        app.set.apply(&mut obj).unwrap();
+
+        assert_eq!(
+            obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "DOE^JANE"
+        );
+        assert_eq!(
+            obj.get(tags::SERIES_NUMBER)
+                .unwrap()
+                .to_int::<i32>()
+                .unwrap(),
+            2
+        );
+        let items = obj
+            .get(tags::REFERENCED_STUDY_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(
+            items[0]
+                .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.3"
+        );
+
+        // nested paths are only accepted by --set-seq
+        assert!(
+            App::try_parse_from([
+                "dicom-fromimage",
+                "--sc",
+                "photo.jpg",
+                "--set",
+                "ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3",
+            ])
+            .is_err()
+        );
+    }
 }
@@ -0,0 +1,374 @@
+//! Aggregation of scanned DICOM files
+//! into an inventory of patients, studies, series or instances,
+//! and writing the inventory in tabular form.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Result as IoResult, Write};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use dicom_core::Tag;
+use dicom_dictionary_std::tags;
+use dicom_object::scan::ScannedFile;
+
+/// The attributes read from each file
+pub const INDEX_TAGS: [Tag; 12] = [
+    tags::SOP_CLASS_UID,
+    tags::SOP_INSTANCE_UID,
+    tags::STUDY_DATE,
+    tags::MODALITY,
+    tags::STUDY_DESCRIPTION,
+    tags::SERIES_DESCRIPTION,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::STUDY_INSTANCE_UID,
+    tags::SERIES_INSTANCE_UID,
+    tags::SERIES_NUMBER,
+    tags::INSTANCE_NUMBER,
+];
+
+/// The level of the information model to list
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Level {
+    /// One row per patient
+    Patient,
+    /// One row per study
+    Study,
+    /// One row per series
+    Series,
+    /// One row per instance (SOP instance UID)
+    Instance,
+}
+
+/// The output format of the inventory
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Comma separated values, with a header line
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    /// An SQLite database with one row per instance
+    /// and a view for each of the other levels,
+    /// which can be served by `dicom-qrscp`
+    /// (requires an output file)
+    Sqlite,
+}
+
+/// A value in a row of the inventory
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    Text(String),
+    Number(u64),
+    List(Vec<String>),
+}
+
+/// One entry of the inventory,
+/// covering all files of the same patient, study, series or instance
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Entry {
+    pub patient_id: String,
+    pub patient_name: String,
+    pub study_instance_uid: String,
+    pub study_date: String,
+    pub study_description: String,
+    pub series_instance_uid: String,
+    pub series_number: String,
+    pub series_description: String,
+    pub modality: String,
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+    pub path: PathBuf,
+    /// the distinct studies found
+    pub studies: BTreeSet<String>,
+    /// the distinct series found
+    pub series: BTreeSet<String>,
+    /// the number of files found
+    pub instances: u64,
+    /// the total size of the files in bytes
+    pub size: u64,
+    pub modalities: BTreeSet<String>,
+    pub transfer_syntaxes: BTreeSet<String>,
+}
+
+impl Entry {
+    /// The columns of this entry at the given level
+    pub fn row(&self, level: Level) -> Vec<(&'static str, Field)> {
+        let text = |value: &str| Field::Text(value.to_string());
+        let list = |values: &BTreeSet<String>| Field::List(values.iter().cloned().collect());
+        let mut row = vec![
+            ("patient_id", text(&self.patient_id)),
+            ("patient_name", text(&self.patient_name)),
+        ];
+        match level {
+            Level::Patient => row.extend([
+                ("studies", Field::Number(self.studies.len() as u64)),
+                ("series", Field::Number(self.series.len() as u64)),
+                ("instances", Field::Number(self.instances)),
+                ("size", Field::Number(self.size)),
+                ("modalities", list(&self.modalities)),
+                ("transfer_syntaxes", list(&self.transfer_syntaxes)),
+            ]),
+            Level::Study => row.extend([
+                ("study_instance_uid", text(&self.study_instance_uid)),
+                ("study_date", text(&self.study_date)),
+                ("study_description", text(&self.study_description)),
+                ("series", Field::Number(self.series.len() as u64)),
+                ("instances", Field::Number(self.instances)),
+                ("size", Field::Number(self.size)),
+                ("modalities", list(&self.modalities)),
+                ("transfer_syntaxes", list(&self.transfer_syntaxes)),
+            ]),
+            Level::Series => row.extend([
+                ("study_instance_uid", text(&self.study_instance_uid)),
+                ("series_instance_uid", text(&self.series_instance_uid)),
+                ("series_number", text(&self.series_number)),
+                ("modality", text(&self.modality)),
+                ("series_description", text(&self.series_description)),
+                ("instances", Field::Number(self.instances)),
+                ("size", Field::Number(self.size)),
+                ("transfer_syntaxes", list(&self.transfer_syntaxes)),
+            ]),
+            Level::Instance => row.extend([
+                ("study_instance_uid", text(&self.study_instance_uid)),
+                ("series_instance_uid", text(&self.series_instance_uid)),
+                ("sop_instance_uid", text(&self.sop_instance_uid)),
+                ("sop_class_uid", text(&self.sop_class_uid)),
+                ("modality", text(&self.modality)),
+                ("transfer_syntax", list(&self.transfer_syntaxes)),
+                ("size", Field::Number(self.size)),
+                ("path", text(&self.path.display().to_string())),
+            ]),
+        }
+        row
+    }
+}
+
+/// An inventory of DICOM files at a given level
+#[derive(Debug)]
+pub struct Inventory {
+    level: Level,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Inventory {
+    /// Create an empty inventory at the given level.
+    pub fn new(level: Level) -> Self {
+        Inventory {
+            level,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// The entries of the inventory,
+    /// sorted by their identifier at the inventory level
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.values()
+    }
+
+    /// Add a scanned file of the given size in bytes to the inventory.
+    pub fn add(&mut self, file: &ScannedFile, size: u64) {
+        let attr = |tag| {
+            file.attributes
+                .get(tag)
+                .and_then(|e| e.to_str().ok())
+                .map(|v| v.trim_end_matches(['\0', ' ']).to_string())
+                .unwrap_or_default()
+        };
+        let study_instance_uid = attr(tags::STUDY_INSTANCE_UID);
+        let series_instance_uid = attr(tags::SERIES_INSTANCE_UID);
+        let sop_instance_uid = attr(tags::SOP_INSTANCE_UID);
+        let key = match self.level {
+            Level::Patient => attr(tags::PATIENT_ID),
+            Level::Study => study_instance_uid.clone(),
+            Level::Series => series_instance_uid.clone(),
+            Level::Instance => sop_instance_uid.clone(),
+        };
+
+        let entry = self.entries.entry(key).or_insert_with(|| Entry {
+            patient_id: attr(tags::PATIENT_ID),
+            patient_name: attr(tags::PATIENT_NAME),
+            study_instance_uid: study_instance_uid.clone(),
+            study_date: attr(tags::STUDY_DATE),
+            study_description: attr(tags::STUDY_DESCRIPTION),
+            series_instance_uid: series_instance_uid.clone(),
+            series_number: attr(tags::SERIES_NUMBER),
+            series_description: attr(tags::SERIES_DESCRIPTION),
+            modality: attr(tags::MODALITY),
+            sop_class_uid: attr(tags::SOP_CLASS_UID),
+            sop_instance_uid,
+            path: file.path.clone(),
+            ..Default::default()
+        });
+        entry.studies.insert(study_instance_uid);
+        entry.series.insert(series_instance_uid);
+        entry.instances += 1;
+        entry.size += size;
+        let modality = attr(tags::MODALITY);
+        if !modality.is_empty() {
+            entry.modalities.insert(modality);
+        }
+        entry.transfer_syntaxes.insert(
+            file.meta
+                .transfer_syntax()
+                .trim_end_matches(['\0', ' '])
+                .to_string(),
+        );
+    }
+
+    /// Write the inventory as CSV.
+    pub fn write_csv<W>(&self, to: &mut W) -> IoResult<()>
+    where
+        W: ?Sized + Write,
+    {
+        write_csv(to, self.level, self.entries().map(|e| e.row(self.level)))
+    }
+
+    /// Write the inventory as JSON lines.
+    pub fn write_jsonl<W>(&self, to: &mut W) -> IoResult<()>
+    where
+        W: ?Sized + Write,
+    {
+        write_jsonl(to, self.entries().map(|e| e.row(self.level)))
+    }
+}
+
+fn columns(level: Level) -> Vec<&'static str> {
+    Entry::default()
+        .row(level)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+fn write_csv<W>(
+    to: &mut W,
+    level: Level,
+    rows: impl Iterator<Item = Vec<(&'static str, Field)>>,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+{
+    writeln!(to, "{}", columns(level).join(","))?;
+    for row in rows {
+        let values: Vec<_> = row
+            .into_iter()
+            .map(|(_, field)| match field {
+                Field::Text(value) => csv_value(&value),
+                Field::Number(value) => value.to_string(),
+                // multiple values are separated by backslashes,
+                // as in DICOM
+                Field::List(values) => csv_value(&values.join("\\")),
+            })
+            .collect();
+        writeln!(to, "{}", values.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV value if necessary.
+fn csv_value(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_jsonl<W>(
+    to: &mut W,
+    rows: impl Iterator<Item = Vec<(&'static str, Field)>>,
+) -> IoResult<()>
+where
+    W: ?Sized + Write,
+{
+    for row in rows {
+        let members: Vec<_> = row
+            .into_iter()
+            .map(|(name, field)| {
+                let value = match field {
+                    Field::Text(value) => serde_json::Value::from(value),
+                    Field::Number(value) => serde_json::Value::from(value),
+                    Field::List(values) => serde_json::Value::from(values),
+                };
+                format!("\"{name}\":{value}")
+            })
+            .collect();
+        writeln!(to, "{{{}}}", members.join(","))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Inventory, Level};
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::scan::ScannedFile;
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+
+    fn scanned_file(series: &str, sop_instance_uid: &str, modality: &str) -> ScannedFile {
+        ScannedFile {
+            path: format!("{sop_instance_uid}.dcm").into(),
+            has_preamble: true,
+            meta: FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid)
+                .build()
+                .unwrap(),
+            attributes: InMemDicomObject::from_element_iter([
+                DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+                DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+                DataElement::new(tags::MODALITY, VR::CS, modality),
+                DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+                DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+                DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+                DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, series),
+            ]),
+        }
+    }
+
+    fn inventory(level: Level) -> Inventory {
+        let mut inventory = Inventory::new(level);
+        inventory.add(&scanned_file("2.25.10", "2.25.100", "CT"), 1000);
+        inventory.add(&scanned_file("2.25.10", "2.25.101", "CT"), 2000);
+        inventory.add(&scanned_file("2.25.20", "2.25.200", "SR"), 500);
+        inventory
+    }
+
+    fn write_csv(level: Level) -> String {
+        let mut out = Vec::new();
+        inventory(level).write_csv(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn inventory_csv() {
+        assert_eq!(
+            write_csv(Level::Study),
+            "patient_id,patient_name,study_instance_uid,study_date,study_description,\
+             series,instances,size,modalities,transfer_syntaxes\n\
+             12345,Doe^John,2.25.1,,,2,3,3500,CT\\SR,1.2.840.10008.1.2.1\n"
+        );
+        let series = write_csv(Level::Series);
+        let lines: Vec<_> = series.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "12345,Doe^John,2.25.1,2.25.10,,CT,,2,3000,1.2.840.10008.1.2.1"
+        );
+    }
+
+    #[test]
+    fn inventory_jsonl() {
+        let mut out = Vec::new();
+        inventory(Level::Patient).write_jsonl(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"patient_id\":\"12345\",\"patient_name\":\"Doe^John\",\
+             \"studies\":1,\"series\":2,\"instances\":3,\"size\":3500,\
+             \"modalities\":[\"CT\",\"SR\"],\
+             \"transfer_syntaxes\":[\"1.2.840.10008.1.2.1\"]}\n"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! A CLI tool for making an inventory of the DICOM files in a directory tree.
+//!
+//! The files are scanned in parallel with a [`Scanner`],
+//! reading only the attributes needed,
+//! and aggregated by patient, study, series or instance,
+//! with the number of files, their total size,
+//! the modalities and the transfer syntaxes found.
+//! The inventory is written as CSV or JSON lines.
+//! Alternatively, every instance is recorded in an SQLite database,
+//! which is the persistent index also used by `dicom-storescp` and `dicom-qrscp`.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_app_common::index::{Index, IndexedInstance, index_tags};
+use dicom_object::scan::Scanner;
+use snafu::{OptionExt, ResultExt, Whatever};
+
+mod inventory;
+
+use inventory::{Format, INDEX_TAGS, Inventory, Level};
+
+/// The number of instances recorded in each transaction
+const INDEX_BATCH_SIZE: usize = 1000;
+
+/// Make an inventory of the DICOM files in a directory tree
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The directories to scan for DICOM files (or individual files)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// The level of the inventory
+    /// (the sqlite format has all levels)
+    #[arg(short = 'l', long = "level", value_enum, default_value = "series")]
+    level: Level,
+    /// The output format
+    #[arg(short = 'f', long = "format", value_enum, default_value = "csv")]
+    format: Format,
+    /// The output file (default is to print to standard output);
+    /// instances are added to the database if it already exists
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// The number of threads reading files
+    /// (default is the available parallelism)
+    #[arg(short = 'j', long = "threads")]
+    threads: Option<usize>,
+    /// Also accept DICOM files without the 128-byte preamble
+    #[arg(long = "no-preamble")]
+    no_preamble: bool,
+    /// Follow symbolic links
+    #[arg(long = "follow-links")]
+    follow_links: bool,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_writer(std::io::stderr)
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", snafu::Report::from_error(e));
+    });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        paths,
+        level,
+        format,
+        output,
+        threads,
+        no_preamble,
+        follow_links,
+    } = app;
+
+    let index = match format {
+        Format::Sqlite => {
+            let output = output
+                .as_ref()
+                .whatever_context("the sqlite format needs an output file")?;
+            Some(
+                Index::open(output)
+                    .with_whatever_context(|_| format!("could not open {}", output.display()))?,
+            )
+        }
+        Format::Csv | Format::Jsonl => None,
+    };
+
+    let mut scanner = Scanner::new()
+        .with_tags(INDEX_TAGS.into_iter().chain(index_tags()))
+        .with_no_preamble(no_preamble)
+        .with_follow_links(follow_links);
+    if let Some(threads) = threads {
+        scanner = scanner.with_threads(threads);
+    }
+
+    let mut inventory = Inventory::new(level);
+    let mut batch = Vec::new();
+    let mut count = 0;
+    for path in &paths {
+        for result in scanner.scan(path) {
+            match result {
+                Ok(file) => {
+                    let size = std::fs::metadata(&file.path).map_or(0, |m| m.len());
+                    count += 1;
+                    let Some(index) = &index else {
+                        inventory.add(&file, size);
+                        continue;
+                    };
+                    // the index is read from other working directories
+                    let path = std::fs::canonicalize(&file.path).unwrap_or(file.path);
+                    match IndexedInstance::new(
+                        &path,
+                        size,
+                        file.meta.transfer_syntax(),
+                        &file.attributes,
+                    ) {
+                        Ok(instance) => batch.push(instance),
+                        Err(e) => {
+                            tracing::warn!("{}: {}", path.display(), snafu::Report::from_error(e));
+                            continue;
+                        }
+                    }
+                    if batch.len() >= INDEX_BATCH_SIZE {
+                        index
+                            .insert(&batch)
+                            .whatever_context("could not write to the index")?;
+                        batch.clear();
+                    }
+                }
+                Err(e) => tracing::warn!("{}", snafu::Report::from_error(e)),
+            }
+        }
+    }
+    tracing::info!("Indexed {count} DICOM file(s)");
+
+    if let Some(index) = &index {
+        index
+            .insert(&batch)
+            .whatever_context("could not write to the index")?;
+        return Ok(());
+    }
+
+    let write = |to: &mut dyn Write| match format {
+        Format::Csv => inventory.write_csv(to),
+        Format::Jsonl => inventory.write_jsonl(to),
+        Format::Sqlite => unreachable!("the index is written as files are scanned"),
+    };
+    match &output {
+        Some(output) => {
+            let file = File::create(output)
+                .with_whatever_context(|_| format!("could not create {}", output.display()))?;
+            let mut to = BufWriter::new(file);
+            write(&mut to)
+                .and_then(|_| to.flush())
+                .with_whatever_context(|_| format!("could not write {}", output.display()))?;
+        }
+        None => {
+            let mut to = BufWriter::new(std::io::stdout().lock());
+            write(&mut to)
+                .and_then(|_| to.flush())
+                .whatever_context("could not write inventory")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -536,6 +536,44 @@ mod tests {
         )
     }
 
+    #[test]
+    fn can_parse_person_name_component_groups() {
+        let serialized = serde_json::json!({
+            "00100010": {
+                "vr": "PN",
+                "Value": [
+                    {
+                        "Alphabetic": "Yamada^Tarou",
+                        "Ideographic": "山田^太郎",
+                        "Phonetic": "やまだ^たろう"
+                    },
+                    { "Alphabetic": "Wang^XiaoDong" },
+                    { "Phonetic": "Smith^John" },
+                    {}
+                ]
+            }
+        });
+
+        let obj: InMemDicomObject = super::from_value(serialized).unwrap();
+        let tag = Tag(0x0010, 0x0010);
+        assert_eq!(
+            obj.get(tag),
+            Some(&DataElement::new(
+                tag,
+                VR::PN,
+                dicom_value!(
+                    Strs,
+                    [
+                        "Yamada^Tarou=山田^太郎=やまだ^たろう",
+                        "Wang^XiaoDong",
+                        "==Smith^John",
+                        "",
+                    ]
+                )
+            )),
+        )
+    }
+
     #[test]
     fn can_resolve_bulk_data() {
         let serialized = serde_json::json!({
@@ -4,10 +4,12 @@ use std::str::FromStr;
 
 use serde::Deserialize;
 
+/// A person name in DICOM JSON,
+/// in which every component group is optional
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct DicomJsonPerson {
     #[serde(rename = "Alphabetic")]
-    alphabetic: String,
+    alphabetic: Option<String>,
     #[serde(rename = "Ideographic")]
     ideographic: Option<String>,
     #[serde(rename = "Phonetic")]
@@ -16,28 +18,15 @@ pub struct DicomJsonPerson {
 
 impl fmt::Display for DicomJsonPerson {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DicomJsonPerson {
-                alphabetic,
-                ideographic: None,
-                phonetic: None,
-            } => write!(f, "{alphabetic}"),
-            DicomJsonPerson {
-                alphabetic,
-                ideographic: Some(ideographic),
-                phonetic: None,
-            } => write!(f, "{alphabetic}={ideographic}"),
-            DicomJsonPerson {
-                alphabetic,
-                ideographic: None,
-                phonetic: Some(phonetic),
-            } => write!(f, "{alphabetic}=={phonetic}"),
-            DicomJsonPerson {
-                alphabetic,
-                ideographic: Some(ideographic),
-                phonetic: Some(phonetic),
-            } => write!(f, "{alphabetic}={ideographic}={phonetic}"),
-        }
+        let groups = [&self.alphabetic, &self.ideographic, &self.phonetic]
+            .map(|group| group.as_deref().unwrap_or_default());
+        // trailing empty component groups are omitted,
+        // along with their delimiters
+        let len = groups
+            .iter()
+            .rposition(|group| !group.is_empty())
+            .map_or(0, |i| i + 1);
+        f.write_str(&groups[..len].join("="))
     }
 }
 
@@ -47,6 +47,36 @@
 //! # Ok::<(), serde_json::Error>(())
 //! ```
 //!
+//! The same conversions are available as methods of [`InMemDicomObject`][obj]
+//! by importing the extension trait [`DicomJsonExt`]:
+//!
+//! ```
+//! # use dicom_core::VR;
+//! # use dicom_object::mem::{InMemDicomObject, InMemElement};
+//! # use dicom_dictionary_std::tags;
+//! use dicom_json::DicomJsonExt;
+//!
+//! let obj = InMemDicomObject::from_element_iter([
+//!     InMemElement::new(tags::PATIENT_NAME, VR::PN, "Yamada^Tarou=山田^太郎"),
+//! ]);
+//! let json = obj.to_json_value()?;
+//! assert_eq!(
+//!     json,
+//!     serde_json::json!({
+//!         "00100010": {
+//!             "vr": "PN",
+//!             "Value": [{ "Alphabetic": "Yamada^Tarou", "Ideographic": "山田^太郎" }]
+//!         }
+//!     }),
+//! );
+//!
+//! let bytes = serde_json::to_vec(&json)?;
+//! assert_eq!(InMemDicomObject::from_json_slice(&bytes)?, obj);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! [obj]: dicom_object::InMemDicomObject
+//!
 //! Use the [`DicomJson`] wrapper type
 //! for greater control on how to serialize or deserialize data:
 //!
@@ -80,6 +110,9 @@
 mod de;
 mod ser;
 
+use dicom_core::DataDictionary;
+use dicom_object::InMemDicomObject;
+
 pub use crate::de::{from_reader, from_slice, from_str, from_value};
 pub use crate::ser::{to_string, to_string_pretty, to_value, to_vec, to_writer};
 
@@ -214,3 +247,29 @@ impl<T> DicomJson<T> {
         &self.0
     }
 }
+
+/// Extension trait for converting in-memory DICOM objects
+/// to and from the DICOM JSON Model.
+///
+/// This is a convenience over [`to_value`] and [`from_slice`].
+pub trait DicomJsonExt: Sized {
+    /// Serialize the object as a serde JSON value
+    /// in the DICOM JSON Model.
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error>;
+
+    /// Deserialize an object from a byte slice of DICOM JSON.
+    fn from_json_slice(slice: &[u8]) -> Result<Self, serde_json::Error>;
+}
+
+impl<D> DicomJsonExt for InMemDicomObject<D>
+where
+    D: Default + Clone + DataDictionary,
+{
+    fn to_json_value(&self) -> Result<serde_json::Value, serde_json::Error> {
+        to_value(self)
+    }
+
+    fn from_json_slice(slice: &[u8]) -> Result<Self, serde_json::Error> {
+        from_slice(slice)
+    }
+}
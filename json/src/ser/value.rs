@@ -192,16 +192,31 @@ impl Serialize for AsPersonNames<'_> {
 /// Wrapper type for a string
 /// to be interpreted as a person's name.
 ///
+/// The name is split into its component groups
+/// (alphabetic, ideographic, and phonetic),
+/// and empty component groups are left out.
+///
 /// Should only used for the value representation PN.
 #[derive(Debug, Clone, Serialize)]
 pub struct PersonNameDef<'a> {
-    #[serde(rename = "Alphabetic")]
-    alphabetic: &'a str,
+    #[serde(rename = "Alphabetic", skip_serializing_if = "Option::is_none")]
+    alphabetic: Option<&'a str>,
+    #[serde(rename = "Ideographic", skip_serializing_if = "Option::is_none")]
+    ideographic: Option<&'a str>,
+    #[serde(rename = "Phonetic", skip_serializing_if = "Option::is_none")]
+    phonetic: Option<&'a str>,
 }
 
 impl<'a> From<&'a str> for PersonNameDef<'a> {
     fn from(value: &'a str) -> Self {
-        PersonNameDef { alphabetic: value }
+        let mut groups = value
+            .splitn(3, '=')
+            .map(|group| Some(group).filter(|g| !g.is_empty()));
+        PersonNameDef {
+            alphabetic: groups.next().flatten(),
+            ideographic: groups.next().flatten(),
+            phonetic: groups.next().flatten(),
+        }
     }
 }
 
@@ -244,6 +259,33 @@ mod tests {
         assert_eq!(json, Value::Array(vec![Value::from("20230613")]));
     }
 
+    #[test]
+    fn serialize_person_name_component_groups() {
+        let v = dicom_value!(
+            Strs,
+            [
+                "Yamada^Tarou=山田^太郎=やまだ^たろう",
+                "Wang^XiaoDong==",
+                "==Smith^John",
+                "",
+            ]
+        );
+        let json = serde_json::to_value(AsPersonNames(&v)).unwrap();
+        assert_eq!(
+            json,
+            json!([
+                {
+                    "Alphabetic": "Yamada^Tarou",
+                    "Ideographic": "山田^太郎",
+                    "Phonetic": "やまだ^たろう",
+                },
+                { "Alphabetic": "Wang^XiaoDong" },
+                { "Phonetic": "Smith^John" },
+                {},
+            ]),
+        );
+    }
+
     #[test]
     fn serialize_primitive_value_as_numbers() {
         let v = PrimitiveValue::from(23.5_f64);
@@ -0,0 +1,113 @@
+//! A CLI tool for creating a DICOMDIR for a DICOM file-set.
+//!
+//! This command line tool walks through a directory tree,
+//! gathering the DICOM files found
+//! into patient, study, series, and instance directory records,
+//! and saves the resulting DICOMDIR file in the root directory.
+//!
+//! Files which are not DICOM files,
+//! or have a path which cannot be recorded in a DICOMDIR,
+//! are skipped.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use dicom_dictionary_std::tags;
+use dicom_object::{OpenFileOptions, dicomdir::DicomdirBuilder};
+use walkdir::WalkDir;
+
+/// Create a DICOMDIR for a directory of DICOM files
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Path to the root directory of the file-set
+    dir: PathBuf,
+    /// Path to the output DICOMDIR file
+    /// (default is `DICOMDIR` in the root directory)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// The File-set ID to record in the DICOMDIR
+    #[arg(long)]
+    file_set_id: Option<String>,
+    /// Print more information about the files found
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    let App {
+        dir,
+        output,
+        file_set_id,
+        verbose,
+    } = App::parse();
+
+    let output = output.unwrap_or_else(|| dir.join("DICOMDIR"));
+
+    let mut builder = DicomdirBuilder::new();
+    if let Some(file_set_id) = file_set_id {
+        builder = builder.file_set_id(file_set_id);
+    }
+
+    let mut count = 0;
+    for entry in WalkDir::new(&dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let path = entry.path();
+        if path == output || path.file_name().is_some_and(|name| name == "DICOMDIR") {
+            continue;
+        }
+        let file_id = path.strip_prefix(&dir).unwrap_or(path);
+
+        // the pixel data is not needed for the directory records
+        let obj = match OpenFileOptions::new()
+            .read_until(tags::PIXEL_DATA)
+            .open_file(path)
+        {
+            Ok(obj) => obj,
+            Err(e) => {
+                if verbose {
+                    tracing::warn!(
+                        "Skipping {}: {}",
+                        path.display(),
+                        snafu::Report::from_error(e)
+                    );
+                }
+                continue;
+            }
+        };
+
+        if let Err(e) = builder.add_file(file_id, &obj) {
+            tracing::warn!(
+                "Skipping {}: {}",
+                path.display(),
+                snafu::Report::from_error(e)
+            );
+            continue;
+        }
+        if verbose {
+            println!("Added {}", file_id.display());
+        }
+        count += 1;
+    }
+
+    let dicomdir = builder.build().unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-1);
+    });
+
+    dicomdir.write_to_file(&output).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+
+    println!("DICOMDIR with {count} files saved to {}", output.display());
+}
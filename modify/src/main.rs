@@ -0,0 +1,326 @@
+//! A CLI tool for editing DICOM files in constant memory.
+//!
+//! This command line tool removes, renames and sets attributes
+//! and strips the pixel data of a DICOM file.
+//! The data set is read, edited and written one token at a time
+//! through the filters of [`dicom_parser::filter`],
+//! so files of any size are edited
+//! without loading them into memory.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use dicom_app_common::edit::SetOptions;
+use dicom_core::{DataDictionary, Tag};
+use dicom_dictionary_std::{StandardDataDictionary, tags};
+use dicom_object::FileMetaTable;
+use dicom_parser::dataset::{DataSetReader, DataSetWriter, pipe};
+use dicom_parser::filter::{DropTags, FilterChain, PutElement, RenameTag, TruncatePixelData};
+use dicom_transfer_syntax_registry::{TransferSyntaxIndex, TransferSyntaxRegistry};
+use snafu::{OptionExt, ResultExt, Whatever, ensure_whatever};
+
+mod nested;
+use nested::SetNested;
+
+/// Edit the attributes of a DICOM file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// Path to the DICOM file to modify
+    file: PathBuf,
+    /// Path to the output file
+    /// (default is to overwrite the input file)
+    #[arg(short = 'o', long = "out")]
+    output: Option<PathBuf>,
+    /// Remove an attribute at any depth, by tag or keyword
+    #[arg(short = 'r', long = "remove", value_name = "KEY", value_parser = parse_tag)]
+    remove: Vec<Tag>,
+    /// Change the tag of an attribute at any depth (e.g. `(0009,1001)=(0009,1002)`)
+    #[arg(long = "rename", value_name = "KEY=KEY", value_parser = parse_rename)]
+    rename: Vec<(Tag, Tag)>,
+    #[command(flatten)]
+    set: SetOptions,
+    /// Replace the pixel data with an empty value
+    #[arg(long = "strip-pixel-data")]
+    strip_pixel_data: bool,
+}
+
+fn parse_tag(s: &str) -> Result<Tag, String> {
+    StandardDataDictionary
+        .parse_tag(s.trim())
+        .ok_or_else(|| format!("could not resolve attribute `{s}`"))
+}
+
+fn parse_rename(s: &str) -> Result<(Tag, Tag), String> {
+    let (from, to) = s.split_once('=').ok_or("missing `=` between attributes")?;
+    Ok((parse_tag(from)?, parse_tag(to)?))
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        file,
+        output,
+        remove,
+        rename,
+        set,
+        strip_pixel_data,
+    } = app;
+
+    let SetOptions { set, set_seq } = set;
+    let mut values = Vec::with_capacity(set.len());
+    for assignment in &set {
+        let tag = assignment.selector.last_tag();
+        ensure_whatever!(
+            tag.group() != 0x0002,
+            "cannot set {tag} of the file meta group"
+        );
+        let value = assignment
+            .to_value()
+            .with_whatever_context(|_| format!("invalid value for {tag}"))?;
+        values.push((tag, assignment.vr(), value));
+    }
+
+    let mut source = BufReader::new(
+        File::open(&file)
+            .with_whatever_context(|_| format!("could not open {}", file.display()))?,
+    );
+    let mut preamble = [0; 128];
+    source
+        .read_exact(&mut preamble)
+        .whatever_context("could not read preamble")?;
+    let mut meta = FileMetaTable::from_reader(&mut source)
+        .whatever_context("could not read file meta group")?;
+    let ts = TransferSyntaxRegistry
+        .get(meta.transfer_syntax())
+        .with_whatever_context(|| format!("unknown transfer syntax {}", meta.transfer_syntax()))?;
+
+    // keep the file meta group consistent with the data set
+    for (tag, _, value) in &values {
+        match *tag {
+            tags::SOP_CLASS_UID => meta.media_storage_sop_class_uid = value.to_str().into(),
+            tags::SOP_INSTANCE_UID => meta.media_storage_sop_instance_uid = value.to_str().into(),
+            _ => continue,
+        }
+        meta.update_information_group_length();
+    }
+
+    // write to a temporary file next to the destination,
+    // which also allows overwriting the input file
+    let output = output.unwrap_or_else(|| file.clone());
+    let tmp_output = TemporaryFile::next_to(&output);
+    let mut to =
+        BufWriter::new(File::create(tmp_output.path()).with_whatever_context(|_| {
+            format!("could not create {}", tmp_output.path().display())
+        })?);
+    to.write_all(&preamble)
+        .and_then(|_| to.write_all(b"DICM"))
+        .whatever_context("could not write preamble")?;
+    meta.write(&mut to)
+        .whatever_context("could not write file meta group")?;
+
+    let reader =
+        DataSetReader::new_with_ts(source, ts).whatever_context("could not read data set")?;
+    let mut writer =
+        DataSetWriter::with_ts(&mut to, ts).whatever_context("could not write data set")?;
+
+    let nested_error = RefCell::new(None);
+    let mut chain = FilterChain::new(reader).with_filter(DropTags::new(remove));
+    for (from, to) in rename {
+        chain = chain.with_filter(RenameTag::new(from, to));
+    }
+    for (tag, vr, value) in values {
+        chain = chain.with_filter(PutElement::new(tag, vr, value));
+    }
+    if !set_seq.is_empty() {
+        chain = chain.with_filter(SetNested::new(set_seq, &nested_error));
+    }
+    if strip_pixel_data {
+        chain = chain.with_filter(TruncatePixelData::new());
+    }
+
+    pipe(chain, &mut writer).whatever_context("could not modify data set")?;
+    if let Some(e) = nested_error.into_inner() {
+        return Err(e).whatever_context("could not set attribute in sequence");
+    }
+    drop(writer);
+    to.flush().whatever_context("could not write data set")?;
+    drop(to);
+
+    tmp_output
+        .persist(&output)
+        .with_whatever_context(|_| format!("could not save {}", output.display()))?;
+    println!("Saved {}", output.display());
+    Ok(())
+}
+
+/// The temporary file to write before replacing the output file,
+/// removed when dropped unless it was persisted.
+struct TemporaryFile {
+    path: Option<PathBuf>,
+}
+
+impl TemporaryFile {
+    /// Reserve a temporary file name next to the output file.
+    fn next_to(output: &Path) -> Self {
+        let mut name = output.file_name().unwrap_or_default().to_os_string();
+        name.push(".tmp");
+        TemporaryFile {
+            path: Some(output.with_file_name(name)),
+        }
+    }
+
+    fn path(&self) -> &Path {
+        self.path.as_deref().unwrap()
+    }
+
+    /// Replace the output file with the temporary file.
+    fn persist(mut self, output: &Path) -> std::io::Result<()> {
+        std::fs::rename(self.path(), output)?;
+        self.path = None;
+        Ok(())
+    }
+}
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::uids;
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject, open_file};
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+
+    #[test]
+    fn set_root_and_nested_attributes() {
+        let dir = std::env::temp_dir().join(format!("dicom-modify-set-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("in.dcm");
+        let output = dir.join("out.dcm");
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+        ]);
+        obj.with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .unwrap()
+            .write_to_file(&input)
+            .unwrap();
+
+        let app = App::try_parse_from([
+            "dicom-modify".as_ref(),
+            input.as_os_str(),
+            "-o".as_ref(),
+            output.as_os_str(),
+            "--set".as_ref(),
+            "PatientName=DOE^JANE".as_ref(),
+            "--set-seq".as_ref(),
+            "ReferencedStudySequence[0].ReferencedSOPInstanceUID=1.2.3".as_ref(),
+            "--set-seq".as_ref(),
+            "ReferencedStudySequence[1].ReferencedSOPInstanceUID=4.5".as_ref(),
+        ])
+        .unwrap();
+        run(app).unwrap();
+
+        let obj = open_file(&output).unwrap();
+        assert_eq!(
+            obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "DOE^JANE"
+        );
+        let items = obj
+            .get(tags::REFERENCED_STUDY_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1]
+                .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "4.5"
+        );
+
+        // existing sequences are edited in place
+        let app = App::try_parse_from([
+            "dicom-modify".as_ref(),
+            output.as_os_str(),
+            "--set-seq".as_ref(),
+            "ReferencedStudySequence[0].ReferencedSOPClassUID=1.2.840.10008.3.1.2.3.1".as_ref(),
+        ])
+        .unwrap();
+        run(app).unwrap();
+        let obj = open_file(&output).unwrap();
+        let items = obj
+            .get(tags::REFERENCED_STUDY_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0]
+                .get(tags::REFERENCED_SOP_CLASS_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.840.10008.3.1.2.3.1"
+        );
+        assert_eq!(
+            items[0]
+                .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.3"
+        );
+
+        // a failed edit leaves the file as it was, without a temporary file
+        let app = App::try_parse_from([
+            "dicom-modify".as_ref(),
+            output.as_os_str(),
+            "--set-seq".as_ref(),
+            "PatientName[0].ReferencedSOPInstanceUID=1.2.3".as_ref(),
+        ])
+        .unwrap();
+        assert!(run(app).is_err());
+        assert!(!dir.join("out.dcm.tmp").exists());
+        assert_eq!(
+            open_file(&output)
+                .unwrap()
+                .get(tags::PATIENT_NAME)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "DOE^JANE"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+//! Setting attributes inside sequences.
+//!
+//! A nested assignment needs the whole sequence it targets,
+//! so the root sequences named by `--set-seq`
+//! are the only part of the data set held in memory:
+//! their tokens are collected into an object,
+//! the assignments are applied to it,
+//! and the edited sequence is emitted in its place.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use dicom_app_common::edit::{ApplyAssignmentError, AttributeAssignment};
+use dicom_core::{DataElement, Tag, VR, ops::AttributeSelectorStep, value::DataSetSequence};
+use dicom_object::{InMemDicomObject, mem::InMemElement};
+use dicom_parser::dataset::{DataToken, IntoTokens};
+use dicom_parser::filter::{FilterOutput, TokenContext, TokenFilter};
+
+/// A filter applying nested attribute assignments
+/// to the root sequences of the data set,
+/// creating the sequences which do not exist yet.
+///
+/// The first error is stored in `error`,
+/// as filters cannot fail.
+#[derive(Debug)]
+pub struct SetNested<'a> {
+    /// the assignments still to apply, by root sequence
+    pending: BTreeMap<Tag, Vec<AttributeAssignment>>,
+    /// the tokens of the sequence being collected,
+    /// with the current nesting depth
+    collecting: Option<(Tag, Vec<DataToken>, usize)>,
+    error: &'a RefCell<Option<SetNestedError>>,
+}
+
+/// Why a nested assignment could not be applied
+#[derive(Debug)]
+pub enum SetNestedError {
+    /// the attribute at the root of the path is not a sequence
+    NotSequence(Tag),
+    /// the sequence holds encapsulated pixel data
+    Unsupported(Tag),
+    Apply(ApplyAssignmentError),
+}
+
+impl std::fmt::Display for SetNestedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetNestedError::NotSequence(tag) => write!(f, "{tag} is not a sequence"),
+            SetNestedError::Unsupported(tag) => {
+                write!(
+                    f,
+                    "cannot edit {tag}, which contains encapsulated pixel data"
+                )
+            }
+            SetNestedError::Apply(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SetNestedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SetNestedError::Apply(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> SetNested<'a> {
+    /// Create a filter applying the given nested assignments.
+    pub fn new(
+        assignments: impl IntoIterator<Item = AttributeAssignment>,
+        error: &'a RefCell<Option<SetNestedError>>,
+    ) -> Self {
+        let mut pending: BTreeMap<Tag, Vec<AttributeAssignment>> = BTreeMap::new();
+        for assignment in assignments {
+            let root = match assignment.selector.first_step() {
+                AttributeSelectorStep::Tag(tag) | AttributeSelectorStep::Nested { tag, .. } => *tag,
+            };
+            pending.entry(root).or_default().push(assignment);
+        }
+        SetNested {
+            pending,
+            collecting: None,
+            error,
+        }
+    }
+
+    fn fail(&self, error: SetNestedError) {
+        self.error.borrow_mut().get_or_insert(error);
+    }
+
+    /// Apply the assignments to the sequence with the given tag
+    /// and emit the result.
+    fn emit(&mut self, tag: Tag, tokens: Option<Vec<DataToken>>, output: &mut FilterOutput) {
+        let assignments = self.pending.remove(&tag).unwrap_or_default();
+        let mut obj = InMemDicomObject::new_empty();
+        if let Some(tokens) = tokens {
+            match read_sequence(tag, &mut tokens.iter().skip(1).cloned()) {
+                Some(sequence) => {
+                    obj.put(sequence);
+                }
+                None => {
+                    self.fail(SetNestedError::Unsupported(tag));
+                    tokens.into_iter().for_each(|token| output.push(token));
+                    return;
+                }
+            }
+        }
+        for assignment in assignments {
+            if let Err(e) = assignment.apply(&mut obj) {
+                self.fail(SetNestedError::Apply(e));
+            }
+        }
+        if let Some(sequence) = obj.take(tag) {
+            sequence.into_tokens().for_each(|token| output.push(token));
+        }
+    }
+}
+
+impl TokenFilter for SetNested<'_> {
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput) {
+        if let Some((_, tokens, depth)) = &mut self.collecting {
+            match token {
+                DataToken::SequenceStart { .. }
+                | DataToken::PixelSequenceStart
+                | DataToken::ItemStart { .. } => *depth += 1,
+                DataToken::SequenceEnd | DataToken::ItemEnd => *depth -= 1,
+                _ => {}
+            }
+            tokens.push(token);
+            if *depth == 0 {
+                let (tag, tokens, _) = self.collecting.take().unwrap();
+                self.emit(tag, Some(tokens), output);
+            }
+            return;
+        }
+
+        if context.is_root() {
+            let tag = match &token {
+                DataToken::ElementHeader(header) => Some(header.tag),
+                DataToken::SequenceStart { tag, .. } => Some(*tag),
+                DataToken::PixelSequenceStart => Some(Tag(0x7FE0, 0x0010)),
+                _ => None,
+            };
+            if let Some(tag) = tag {
+                // create the missing sequences which come before this element
+                while let Some(first) = self.pending.keys().next().copied().filter(|t| *t < tag) {
+                    self.emit(first, None, output);
+                }
+                if self.pending.contains_key(&tag) {
+                    if let DataToken::SequenceStart { .. } = token {
+                        self.collecting = Some((tag, vec![token], 1));
+                        return;
+                    }
+                    self.pending.remove(&tag);
+                    self.fail(SetNestedError::NotSequence(tag));
+                }
+            }
+        }
+        output.push(token);
+    }
+
+    fn finish(&mut self, output: &mut FilterOutput) {
+        while let Some(first) = self.pending.keys().next().copied() {
+            self.emit(first, None, output);
+        }
+    }
+}
+
+/// Build a sequence element from its tokens,
+/// after the start of the sequence.
+///
+/// Returns `None` if the tokens hold encapsulated pixel data
+/// or are not well formed.
+fn read_sequence(tag: Tag, tokens: &mut dyn Iterator<Item = DataToken>) -> Option<InMemElement> {
+    let mut items = Vec::new();
+    loop {
+        match tokens.next()? {
+            DataToken::ItemStart { .. } => items.push(read_item(tokens)?),
+            DataToken::SequenceEnd => {
+                return Some(DataElement::new(tag, VR::SQ, DataSetSequence::from(items)));
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Build an item from its tokens, after the start of the item.
+fn read_item(tokens: &mut dyn Iterator<Item = DataToken>) -> Option<InMemDicomObject> {
+    let mut obj = InMemDicomObject::new_empty();
+    loop {
+        match tokens.next()? {
+            DataToken::ElementHeader(header) => {
+                let DataToken::PrimitiveValue(value) = tokens.next()? else {
+                    return None;
+                };
+                obj.put(DataElement::new(header.tag, header.vr, value));
+            }
+            DataToken::SequenceStart { tag, .. } => {
+                obj.put(read_sequence(tag, tokens)?);
+            }
+            DataToken::ItemEnd => return Some(obj),
+            _ => return None,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use clap::Parser;
+use dicom_app_common::aeconfig::AeConfigOptions;
 use dicom_core::dicom_value;
 use dicom_core::{DataElement, PrimitiveValue, VR};
 use dicom_dictionary_std::{tags, uids};
@@ -26,12 +27,21 @@ mod query;
 mod store_async;
 use store_async::run_store_async;
 
+/// The calling AE title if neither given nor configured
+const DEFAULT_CALLING_AE_TITLE: &str = "STORE-SCP";
+
+/// The maximum PDU length if neither given nor configured
+const DEFAULT_MAX_PDU_LENGTH: u32 = 16384;
+
 /// DICOM C-MOVE SCU
 #[derive(Debug, Parser, Clone)]
 #[command(version)]
 struct App {
-    /// socket address to MOVE SCP (example: "127.0.0.1:1045")
-    addr: String,
+    /// socket address to MOVE SCP (example: "127.0.0.1:1045"),
+    /// or the name of an AE in the configuration file
+    /// (omit if `--remote` is given)
+    #[arg(required_unless_present = "to")]
+    addr: Option<String>,
     /// a DICOM file representing the query object
     file: Option<PathBuf>,
     /// a file containing lines of queries
@@ -44,9 +54,11 @@ struct App {
     /// verbose mode
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
-    /// the calling AE title
-    #[arg(long = "calling-ae-title", default_value = "STORE-SCP")]
-    calling_ae_title: String,
+    /// the calling AE title,
+    /// also the AE title of the storage SCP
+    /// [default: as in the configuration, or STORE-SCP]
+    #[arg(long = "calling-ae-title")]
+    calling_ae_title: Option<String>,
     /// the called AE title
     #[arg(long = "called-ae-title")]
     called_ae_title: Option<String>,
@@ -55,12 +67,12 @@ struct App {
     move_destination: String,
 
     /// the maximum PDU length
+    /// [default: from the AE configuration, or 16384]
     #[arg(
         long = "max-pdu-length",
-        default_value = "16384",
         value_parser(clap::value_parser!(u32).range(4096..=131_072))
     )]
-    max_pdu_length: u32,
+    max_pdu_length: Option<u32>,
     /// Output directory for incoming objects
     #[arg(short = 'o', default_value = ".")]
     out_dir: PathBuf,
@@ -84,10 +96,13 @@ struct App {
     /// Accept unknown SOP classes
     #[arg(long)]
     promiscuous: bool,
+
+    #[command(flatten, next_help_heading = "AE Configuration Options")]
+    ae_config: AeConfigOptions,
 }
 
 fn main() {
-    let app = App::parse();
+    let mut app = App::parse();
 
     tracing::subscriber::set_global_default(
         tracing_subscriber::FmtSubscriber::builder()
@@ -110,7 +125,17 @@ fn main() {
         None
     };
 
-    if Some(app.move_destination.clone()) != Some(app.calling_ae_title.clone()) {
+    // the calling AE title decides whether to run the storage SCP,
+    // so it is resolved against the configuration first
+    if app.calling_ae_title.is_none() {
+        app.calling_ae_title = app
+            .ae_config
+            .resolve(app.addr.clone())
+            .ok()
+            .and_then(|(peer, _)| peer.calling_ae_title);
+    }
+
+    if app.move_destination != app.calling_ae_title() {
         run_move_scu(app.clone(), progress).unwrap_or_else(|err| {
             error!("{}", snafu::Report::from_error(err));
             std::process::exit(-2);
@@ -152,6 +177,15 @@ fn main() {
     }
 }
 
+impl App {
+    /// The calling AE title, as given or configured
+    fn calling_ae_title(&self) -> &str {
+        self.calling_ae_title
+            .as_deref()
+            .unwrap_or(DEFAULT_CALLING_AE_TITLE)
+    }
+}
+
 async fn run_async(args: App, progress: Option<ProgressBar>) -> Result<bool, snafu::Whatever> {
     use std::sync::Arc;
     let args = Arc::new(args);
@@ -169,7 +203,7 @@ async fn run_async(args: App, progress: Option<ProgressBar>) -> Result<bool, sna
     if args.verbose {
         info!(
             "{} listening on: tcp://{listen_addr}",
-            &args.calling_ae_title,
+            args.calling_ae_title(),
         );
     }
 
@@ -197,6 +231,11 @@ enum Error {
     /// Could not dump DICOM output
     DumpOutput { source: std::io::Error },
 
+    /// Could not resolve the MOVE SCP
+    AeConfig {
+        source: dicom_app_common::aeconfig::AeConfigError,
+    },
+
     #[snafu(whatever, display("{}", message))]
     Other {
         message: String,
@@ -277,8 +316,22 @@ fn run_move_scu(app: App, progress: Option<ProgressBar>) -> Result<bool, Error>
         strict: _,
         uncompressed_only: _,
         promiscuous: _,
+        ae_config,
     } = app;
 
+    let (peer, first_file) = ae_config.resolve(addr).context(AeConfigSnafu)?;
+    let max_pdu_length = peer.max_pdu_length_or(max_pdu_length, DEFAULT_MAX_PDU_LENGTH);
+    let addr = peer.address;
+    if peer.tls {
+        whatever!("TLS connections are not supported by this tool");
+    }
+    let file = match (first_file, file) {
+        (Some(_), Some(file)) => {
+            whatever!("unexpected argument '{}'", file.display());
+        }
+        (first_file, file) => first_file.map(PathBuf::from).or(file),
+    };
+
     let dcm_query = build_query(file, query_file, query, verbose)?;
 
     let abstract_syntax = match (patient, study) {
@@ -299,7 +352,11 @@ fn run_move_scu(app: App, progress: Option<ProgressBar>) -> Result<bool, Error>
 
     let mut scu_opt = ClientAssociationOptions::new()
         .with_abstract_syntax(abstract_syntax)
-        .calling_ae_title(calling_ae_title)
+        .calling_ae_title(
+            calling_ae_title
+                .as_deref()
+                .unwrap_or(DEFAULT_CALLING_AE_TITLE),
+        )
         .max_pdu_length(max_pdu_length);
 
     if let Some(called_ae_title) = called_ae_title {
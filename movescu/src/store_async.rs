@@ -107,6 +107,7 @@ pub async fn run_store_async(
         patient: _,
         study: _,
         move_destination: _,
+        ae_config: _,
     } = args;
     let verbose = *verbose;
 
@@ -117,9 +118,13 @@ pub async fn run_store_async(
 
     let mut options = dicom_ul::association::ServerAssociationOptions::new()
         .accept_any()
-        .ae_title(calling_ae_title)
+        .ae_title(
+            calling_ae_title
+                .as_deref()
+                .unwrap_or(crate::DEFAULT_CALLING_AE_TITLE),
+        )
         .strict(*strict)
-        .max_pdu_length(*max_pdu_length)
+        .max_pdu_length(max_pdu_length.unwrap_or(crate::DEFAULT_MAX_PDU_LENGTH))
         .promiscuous(*promiscuous);
 
     if *uncompressed_only {
@@ -0,0 +1,555 @@
+//! A Modality Worklist SCU command line interface,
+//! behaving as a simple modality simulator.
+//!
+//! The worklist is queried for the scheduled procedure steps
+//! matching the given date, modality, and station,
+//! which are printed or saved as DICOM JSON files.
+//! Performed procedure steps can then be created with MPPS N-CREATE
+//! for selected items, so as to test RIS integrations.
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use dicom_app_common::aeconfig::AeConfigOptions;
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR, value::DataSetSequence};
+use dicom_dictionary_std::{tags, uids};
+use dicom_dump::DumpOptions;
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    association::client::ClientAssociationOptions,
+    dimse::{
+        find::FindScu,
+        mpps::{MPPS_SOP_CLASS_UID, MppsScu},
+        query::MatchingType,
+    },
+};
+use snafu::{Whatever, prelude::*};
+use tracing::{Level, debug, error, info, warn};
+
+/// DICOM Modality Worklist SCU
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// socket address to the worklist SCP,
+    /// optionally with AE title
+    /// (example: "RIS@127.0.0.1:104"),
+    /// or the name of an AE in the configuration file
+    /// (omit if `--remote` is given)
+    #[arg(required_unless_present = "to")]
+    addr: Option<String>,
+    /// verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// the calling AE title,
+    /// also used as the performed station AE title
+    /// [default: as in the configuration, or MWLSCU]
+    #[arg(long = "calling-ae-title")]
+    calling_ae_title: Option<String>,
+    /// the called Application Entity title,
+    /// overrides AE title in address if present [default: ANY-SCP]
+    #[arg(long = "called-ae-title")]
+    called_ae_title: Option<String>,
+
+    /// the scheduled procedure step start date
+    /// (YYYYMMDD, a range such as 20240101-20240131, or `today`)
+    #[arg(short = 'd', long = "date")]
+    date: Option<String>,
+    /// the modality of the scheduled procedure steps (e.g. CT)
+    #[arg(short = 'm', long = "modality")]
+    modality: Option<String>,
+    /// the scheduled station AE title
+    #[arg(short = 's', long = "station")]
+    station: Option<String>,
+    /// stop after this number of items,
+    /// cancelling the query
+    #[arg(long = "limit")]
+    limit: Option<usize>,
+
+    /// print the full worklist items instead of a summary
+    #[arg(long = "dump")]
+    dump: bool,
+    /// save each worklist item as a DICOM JSON file in this directory
+    #[arg(short = 'o', long = "output", value_name = "DIR")]
+    output: Option<PathBuf>,
+    /// create a performed procedure step (MPPS N-CREATE)
+    /// for the worklist items with these numbers,
+    /// starting from 1
+    #[arg(long = "mpps", value_name = "ITEM", value_delimiter = ',')]
+    mpps: Vec<usize>,
+
+    #[command(flatten, next_help_heading = "AE Configuration Options")]
+    ae_config: AeConfigOptions,
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    })
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        addr,
+        verbose,
+        calling_ae_title,
+        called_ae_title,
+        date,
+        modality,
+        station,
+        limit,
+        dump,
+        output,
+        mpps,
+        ae_config,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .whatever_context("Could not set up global logging subscriber")
+    .unwrap_or_else(|e: Whatever| {
+        eprintln!("[ERROR] {}", snafu::Report::from_error(e));
+    });
+
+    let date = date.map(|date| {
+        if date == "today" {
+            chrono::Local::now().format("%Y%m%d").to_string()
+        } else {
+            date
+        }
+    });
+    let query = build_query(date.as_deref(), modality.as_deref(), station.as_deref())?;
+
+    let (peer, rest) = ae_config
+        .resolve(addr)
+        .whatever_context("Could not resolve the worklist SCP")?;
+    if let Some(rest) = rest {
+        whatever!("unexpected argument '{rest}'");
+    }
+    if peer.tls {
+        whatever!("TLS connections are not supported by this tool");
+    }
+    let addr = peer.address;
+    let calling_ae_title = calling_ae_title
+        .or(peer.calling_ae_title)
+        .unwrap_or_else(|| "MWLSCU".to_string());
+
+    let mut association_opt = ClientAssociationOptions::new()
+        .with_abstract_syntax(uids::MODALITY_WORKLIST_INFORMATION_MODEL_FIND)
+        .calling_ae_title(calling_ae_title.clone());
+    if !mpps.is_empty() {
+        association_opt = association_opt.with_abstract_syntax(MPPS_SOP_CLASS_UID);
+    }
+    if let Some(max_pdu_length) = peer.max_pdu_length {
+        association_opt = association_opt.max_pdu_length(max_pdu_length);
+    }
+    if let Some(called_ae_title) = called_ae_title {
+        association_opt = association_opt.called_ae_title(called_ae_title);
+    }
+    let mut association = association_opt
+        .establish_with(&addr)
+        .whatever_context("Could not establish association with SCP")?;
+    debug!("Association with {} successful", addr);
+
+    let mut items = Vec::new();
+    {
+        let mut scu = FindScu::new(
+            &mut association,
+            uids::MODALITY_WORKLIST_INFORMATION_MODEL_FIND,
+        )
+        .whatever_context("Modality worklist not accepted by SCP")?;
+        let responses = scu
+            .find(&query)
+            .whatever_context("Could not send worklist query")?;
+        // dropping the responses early cancels the query
+        for item in responses.take(limit.unwrap_or(usize::MAX)) {
+            let item = item.whatever_context("Worklist query failed")?;
+            items.push(item.identifier);
+        }
+    }
+    info!("{} worklist item(s) found", items.len());
+
+    for (i, item) in items.iter().enumerate() {
+        let number = i + 1;
+        if dump {
+            println!("------------------------ Item #{number} ------------------------");
+            DumpOptions::new()
+                .dump_object(item)
+                .whatever_context("Could not dump worklist item")?;
+        } else {
+            println!("{}", summary(number, item));
+        }
+        if let Some(output) = &output {
+            save_item(output, number, item)?;
+        }
+    }
+
+    if !mpps.is_empty() {
+        let mut scu =
+            MppsScu::new(&mut association).whatever_context("MPPS not accepted by SCP")?;
+        let now = chrono::Local::now();
+        let date = now.format("%Y%m%d").to_string();
+        let time = now.format("%H%M%S").to_string();
+        for number in mpps {
+            let Some(item) = number.checked_sub(1).and_then(|i| items.get(i)) else {
+                warn!("No worklist item #{number}, skipping");
+                continue;
+            };
+            let attributes = mpps_attributes(item, &calling_ae_title, &date, &time);
+            let sop_instance_uid = generate_uid();
+            let rsp = scu
+                .create(Some(&sop_instance_uid), attributes)
+                .whatever_context("Could not create performed procedure step")?;
+            if rsp.is_success() {
+                info!(
+                    "✓ Created performed procedure step {} for item #{number}",
+                    rsp.sop_instance_uid.as_deref().unwrap_or(&sop_instance_uid)
+                );
+            } else {
+                warn!(
+                    "Could not create performed procedure step for item #{number} (status {:04X}H{})",
+                    rsp.status,
+                    rsp.error_comment
+                        .map(|c| format!(": {c}"))
+                        .unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    // release association
+    let _ = association.release();
+
+    Ok(())
+}
+
+/// Build a worklist query identifier
+/// requesting the attributes shown and used by this tool,
+/// constrained by the filters given.
+fn build_query(
+    date: Option<&str>,
+    modality: Option<&str>,
+    station: Option<&str>,
+) -> Result<InMemDicomObject, Whatever> {
+    let key = |tag: Tag, vr: VR, value: Option<&str>| -> Result<_, Whatever> {
+        let value = value.unwrap_or_default();
+        ensure_whatever!(
+            MatchingType::classify(vr, value).is_some(),
+            "invalid value `{value}` for {tag}"
+        );
+        Ok(DataElement::new(tag, vr, PrimitiveValue::from(value)))
+    };
+
+    let step = InMemDicomObject::from_element_iter([
+        key(tags::MODALITY, VR::CS, modality)?,
+        key(tags::SCHEDULED_STATION_AE_TITLE, VR::AE, station)?,
+        key(tags::SCHEDULED_PROCEDURE_STEP_START_DATE, VR::DA, date)?,
+        key(tags::SCHEDULED_PROCEDURE_STEP_START_TIME, VR::TM, None)?,
+        key(tags::SCHEDULED_PERFORMING_PHYSICIAN_NAME, VR::PN, None)?,
+        key(tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION, VR::LO, None)?,
+        key(tags::SCHEDULED_PROCEDURE_STEP_ID, VR::SH, None)?,
+        key(tags::SCHEDULED_STATION_NAME, VR::SH, None)?,
+    ]);
+
+    let mut query = InMemDicomObject::from_element_iter([
+        key(tags::ACCESSION_NUMBER, VR::SH, None)?,
+        key(tags::REFERRING_PHYSICIAN_NAME, VR::PN, None)?,
+        key(tags::PATIENT_NAME, VR::PN, None)?,
+        key(tags::PATIENT_ID, VR::LO, None)?,
+        key(tags::PATIENT_BIRTH_DATE, VR::DA, None)?,
+        key(tags::PATIENT_SEX, VR::CS, None)?,
+        key(tags::STUDY_INSTANCE_UID, VR::UI, None)?,
+        key(tags::REQUESTED_PROCEDURE_ID, VR::SH, None)?,
+        key(tags::REQUESTED_PROCEDURE_DESCRIPTION, VR::LO, None)?,
+    ]);
+    query.put(DataElement::new(
+        tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+        VR::SQ,
+        DataSetSequence::from(vec![step]),
+    ));
+    Ok(query)
+}
+
+/// Retrieve the first item of the scheduled procedure step sequence
+fn scheduled_step(item: &InMemDicomObject) -> Option<&InMemDicomObject> {
+    item.get(tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE)?
+        .items()?
+        .first()
+}
+
+/// Retrieve an attribute as text, trimmed, or an empty string
+fn text(obj: Option<&InMemDicomObject>, tag: Tag) -> String {
+    obj.and_then(|obj| obj.get(tag))
+        .and_then(|e| e.to_str().ok())
+        .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+        .unwrap_or_default()
+}
+
+/// Describe a worklist item in a single line
+fn summary(number: usize, item: &InMemDicomObject) -> String {
+    let step = scheduled_step(item);
+    format!(
+        "#{number} {} {} {} {} | {} [{}] | accession {} | {}",
+        text(step, tags::SCHEDULED_PROCEDURE_STEP_START_DATE),
+        text(step, tags::SCHEDULED_PROCEDURE_STEP_START_TIME),
+        text(step, tags::MODALITY),
+        text(step, tags::SCHEDULED_STATION_AE_TITLE),
+        text(Some(item), tags::PATIENT_NAME),
+        text(Some(item), tags::PATIENT_ID),
+        text(Some(item), tags::ACCESSION_NUMBER),
+        text(step, tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION),
+    )
+}
+
+/// Save a worklist item as a DICOM JSON file
+fn save_item(output: &Path, number: usize, item: &InMemDicomObject) -> Result<(), Whatever> {
+    std::fs::create_dir_all(output)
+        .with_whatever_context(|_| format!("Could not create directory {}", output.display()))?;
+    let path = output.join(format!("item-{number:04}.json"));
+    let json = dicom_json::to_vec(item).whatever_context("Could not serialize worklist item")?;
+    std::fs::write(&path, json)
+        .with_whatever_context(|_| format!("Could not write {}", path.display()))?;
+    debug!("Saved worklist item #{number} to {}", path.display());
+    Ok(())
+}
+
+/// Build the attributes of a new performed procedure step
+/// for the given worklist item,
+/// as a modality starting the procedure would
+/// (IHE Scheduled Workflow, RAD-6).
+///
+/// _Performed Procedure Step Status_ is set by the MPPS SCU.
+fn mpps_attributes(
+    item: &InMemDicomObject,
+    station_ae_title: &str,
+    date: &str,
+    time: &str,
+) -> InMemDicomObject {
+    let step = scheduled_step(item);
+    let element = |tag, vr, value: String| DataElement::new(tag, vr, PrimitiveValue::from(value));
+    let empty_sequence = |tag| {
+        DataElement::new(
+            tag,
+            VR::SQ,
+            DataSetSequence::from(Vec::<InMemDicomObject>::new()),
+        )
+    };
+
+    let scheduled_step_attributes = InMemDicomObject::from_element_iter([
+        element(
+            tags::STUDY_INSTANCE_UID,
+            VR::UI,
+            text(Some(item), tags::STUDY_INSTANCE_UID),
+        ),
+        empty_sequence(tags::REFERENCED_STUDY_SEQUENCE),
+        element(
+            tags::ACCESSION_NUMBER,
+            VR::SH,
+            text(Some(item), tags::ACCESSION_NUMBER),
+        ),
+        element(
+            tags::REQUESTED_PROCEDURE_ID,
+            VR::SH,
+            text(Some(item), tags::REQUESTED_PROCEDURE_ID),
+        ),
+        element(
+            tags::REQUESTED_PROCEDURE_DESCRIPTION,
+            VR::LO,
+            text(Some(item), tags::REQUESTED_PROCEDURE_DESCRIPTION),
+        ),
+        element(
+            tags::SCHEDULED_PROCEDURE_STEP_ID,
+            VR::SH,
+            text(step, tags::SCHEDULED_PROCEDURE_STEP_ID),
+        ),
+        element(
+            tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION,
+            VR::LO,
+            text(step, tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION),
+        ),
+    ]);
+
+    InMemDicomObject::from_element_iter([
+        DataElement::new(
+            tags::SCHEDULED_STEP_ATTRIBUTES_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![scheduled_step_attributes]),
+        ),
+        element(
+            tags::PATIENT_NAME,
+            VR::PN,
+            text(Some(item), tags::PATIENT_NAME),
+        ),
+        element(tags::PATIENT_ID, VR::LO, text(Some(item), tags::PATIENT_ID)),
+        element(
+            tags::PATIENT_BIRTH_DATE,
+            VR::DA,
+            text(Some(item), tags::PATIENT_BIRTH_DATE),
+        ),
+        element(
+            tags::PATIENT_SEX,
+            VR::CS,
+            text(Some(item), tags::PATIENT_SEX),
+        ),
+        empty_sequence(tags::REFERENCED_PATIENT_SEQUENCE),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_ID,
+            VR::SH,
+            text(step, tags::SCHEDULED_PROCEDURE_STEP_ID),
+        ),
+        element(
+            tags::PERFORMED_STATION_AE_TITLE,
+            VR::AE,
+            station_ae_title.to_string(),
+        ),
+        element(
+            tags::PERFORMED_STATION_NAME,
+            VR::SH,
+            text(step, tags::SCHEDULED_STATION_NAME),
+        ),
+        element(tags::PERFORMED_LOCATION, VR::SH, String::new()),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_START_DATE,
+            VR::DA,
+            date.to_string(),
+        ),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_START_TIME,
+            VR::TM,
+            time.to_string(),
+        ),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_END_DATE,
+            VR::DA,
+            String::new(),
+        ),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_END_TIME,
+            VR::TM,
+            String::new(),
+        ),
+        element(
+            tags::PERFORMED_PROCEDURE_STEP_DESCRIPTION,
+            VR::LO,
+            text(step, tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION),
+        ),
+        element(
+            tags::PERFORMED_PROCEDURE_TYPE_DESCRIPTION,
+            VR::LO,
+            String::new(),
+        ),
+        empty_sequence(tags::PROCEDURE_CODE_SEQUENCE),
+        element(tags::MODALITY, VR::CS, text(step, tags::MODALITY)),
+        element(tags::STUDY_ID, VR::SH, String::new()),
+        empty_sequence(tags::PERFORMED_PROTOCOL_CODE_SEQUENCE),
+        empty_sequence(tags::PERFORMED_SERIES_SEQUENCE),
+    ])
+}
+
+/// Create a new random UID under the `2.25` root.
+fn generate_uid() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let state = std::collections::hash_map::RandomState::new();
+    let mut value = 0u128;
+    for i in 0..2 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(i);
+        hasher.write_u128(time);
+        value = value << 64 | u128::from(hasher.finish());
+    }
+    format!("2.25.{value}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+
+    fn worklist_item() -> InMemDicomObject {
+        let step = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+            DataElement::new(tags::SCHEDULED_STATION_AE_TITLE, VR::AE, "CT01"),
+            DataElement::new(
+                tags::SCHEDULED_PROCEDURE_STEP_START_DATE,
+                VR::DA,
+                "20240105",
+            ),
+            DataElement::new(tags::SCHEDULED_PROCEDURE_STEP_START_TIME, VR::TM, "0930"),
+            DataElement::new(tags::SCHEDULED_PROCEDURE_STEP_ID, VR::SH, "SPS1"),
+            DataElement::new(
+                tags::SCHEDULED_PROCEDURE_STEP_DESCRIPTION,
+                VR::LO,
+                "CHEST CT",
+            ),
+        ]);
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "DOE^JOHN"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "P123"),
+            DataElement::new(tags::ACCESSION_NUMBER, VR::SH, "A456 "),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.7"),
+            DataElement::new(
+                tags::SCHEDULED_PROCEDURE_STEP_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![step]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn worklist_query() {
+        let query = build_query(Some("20240101-20240131"), Some("CT"), None).unwrap();
+        let step = scheduled_step(&query).unwrap();
+        assert_eq!(
+            text(Some(step), tags::SCHEDULED_PROCEDURE_STEP_START_DATE),
+            "20240101-20240131"
+        );
+        assert_eq!(text(Some(step), tags::MODALITY), "CT");
+        // return keys with universal matching
+        assert_eq!(text(Some(step), tags::SCHEDULED_STATION_AE_TITLE), "");
+        assert!(query.get(tags::PATIENT_NAME).is_some());
+
+        assert!(build_query(Some("2024*"), None, None).is_err());
+    }
+
+    #[test]
+    fn summarize_item() {
+        assert_eq!(
+            summary(2, &worklist_item()),
+            "#2 20240105 0930 CT CT01 | DOE^JOHN [P123] | accession A456 | CHEST CT"
+        );
+    }
+
+    #[test]
+    fn mpps_from_worklist_item() {
+        let mpps = mpps_attributes(&worklist_item(), "MWLSCU", "20240105", "094500");
+        let scheduled = mpps
+            .get(tags::SCHEDULED_STEP_ATTRIBUTES_SEQUENCE)
+            .and_then(|e| e.items())
+            .and_then(|items| items.first());
+        assert_eq!(text(scheduled, tags::STUDY_INSTANCE_UID), "2.25.7");
+        assert_eq!(text(scheduled, tags::ACCESSION_NUMBER), "A456");
+        assert_eq!(text(scheduled, tags::SCHEDULED_PROCEDURE_STEP_ID), "SPS1");
+        assert_eq!(text(Some(&mpps), tags::PATIENT_ID), "P123");
+        assert_eq!(text(Some(&mpps), tags::PERFORMED_PROCEDURE_STEP_ID), "SPS1");
+        assert_eq!(
+            text(Some(&mpps), tags::PERFORMED_STATION_AE_TITLE),
+            "MWLSCU"
+        );
+        assert_eq!(
+            text(Some(&mpps), tags::PERFORMED_PROCEDURE_STEP_START_TIME),
+            "094500"
+        );
+        assert_eq!(text(Some(&mpps), tags::MODALITY), "CT");
+    }
+}
@@ -0,0 +1,698 @@
+//! Coded concepts, as per [PS3.3 section 8][1].
+//!
+//! A coded concept is identified by a code value
+//! and the designator of the coding scheme which defines it,
+//! and is described by a human readable code meaning.
+//! In DICOM objects, coded concepts appear as items
+//! of code sequences such as _Concept Name Code Sequence_,
+//! with the attributes _Code Value_, _Coding Scheme Designator_,
+//! _Coding Scheme Version_ and _Code Meaning_.
+//!
+//! [`Code`] converts between these items and a plain value,
+//! and the submodules [`dcm`], [`sct`], [`ln`] and [`ucum`]
+//! provide a selection of codes commonly used
+//! in structured reports and worklists.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_core::{Tag, header::Header};
+//! # use dicom_dictionary_std::tags;
+//! # use dicom_object::InMemDicomObject;
+//! use dicom_object::code::{Code, dcm, sct};
+//!
+//! let mut obj = InMemDicomObject::new_empty();
+//! obj.put(dcm::FINDING.to_sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE));
+//!
+//! let code = Code::from_sequence(&obj, tags::CONCEPT_NAME_CODE_SEQUENCE)?;
+//! assert_eq!(code, dcm::FINDING);
+//! assert_eq!(code.to_string(), r#"(121071, DCM, "Finding")"#);
+//!
+//! // codes of the same concept compare equal regardless of their meaning
+//! let left = Code::new("7771000", "SCT", "Left side");
+//! assert!(left.is_same_concept(&sct::LEFT));
+//! # Ok::<(), dicom_object::code::CodeError>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/chapter_8.html
+use dicom_core::dictionary::DataDictionary;
+use dicom_core::value::DataSetSequence;
+use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use snafu::{OptionExt, Snafu};
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::mem::{InMemDicomObject, InMemElement};
+
+/// Designator of the DICOM Controlled Terminology coding scheme
+pub const DCM: &str = "DCM";
+/// Designator of the SNOMED CT coding scheme
+pub const SCT: &str = "SCT";
+/// Designator of the LOINC coding scheme
+pub const LN: &str = "LN";
+/// Designator of the Unified Code for Units of Measure
+pub const UCUM: &str = "UCUM";
+
+/// The maximum length of a code value in _Code Value_ (SH)
+const MAX_SHORT_CODE_VALUE_LENGTH: usize = 16;
+
+/// An error which may occur when reading a coded concept
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum CodeError {
+    /// Missing code sequence {tag}
+    MissingSequence { tag: Tag },
+    /// Code sequence {tag} has no items
+    EmptySequence { tag: Tag },
+    /// Missing code value
+    MissingCodeValue,
+    /// Missing attribute {tag} in code item
+    MissingAttribute { tag: Tag },
+    /// Attribute {tag} in code item is not text
+    InvalidAttribute { tag: Tag },
+}
+
+/// A coded concept:
+/// the triplet of code value, coding scheme designator and code meaning,
+/// plus the coding scheme version when needed.
+///
+/// Two codes compare equal only if all of their fields are equal.
+/// Use [`is_same_concept`](Code::is_same_concept)
+/// to compare codes regardless of their meaning.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Code {
+    /// The code value
+    pub value: Cow<'static, str>,
+    /// The coding scheme designator, such as `DCM` or `SCT`
+    pub scheme: Cow<'static, str>,
+    /// The coding scheme version, if needed to resolve the code
+    pub scheme_version: Option<Cow<'static, str>>,
+    /// The code meaning
+    pub meaning: Cow<'static, str>,
+}
+
+impl Code {
+    /// Create a new code
+    /// from its value, coding scheme designator and meaning.
+    pub fn new(
+        value: impl Into<Cow<'static, str>>,
+        scheme: impl Into<Cow<'static, str>>,
+        meaning: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Code {
+            value: value.into(),
+            scheme: scheme.into(),
+            scheme_version: None,
+            meaning: meaning.into(),
+        }
+    }
+
+    /// Create a new code from static strings,
+    /// usable in constant declarations.
+    pub const fn new_static(
+        value: &'static str,
+        scheme: &'static str,
+        meaning: &'static str,
+    ) -> Self {
+        Code {
+            value: Cow::Borrowed(value),
+            scheme: Cow::Borrowed(scheme),
+            scheme_version: None,
+            meaning: Cow::Borrowed(meaning),
+        }
+    }
+
+    /// Set the coding scheme version of the code.
+    pub fn with_scheme_version(mut self, version: impl Into<Cow<'static, str>>) -> Self {
+        self.scheme_version = Some(version.into());
+        self
+    }
+
+    /// Whether both codes identify the same concept,
+    /// as in having the same code value and coding scheme designator,
+    /// regardless of their meaning or coding scheme version.
+    pub fn is_same_concept(&self, other: &Code) -> bool {
+        self.value == other.value && self.scheme == other.scheme
+    }
+
+    /// Read a code from an item of a code sequence.
+    ///
+    /// The code value is taken from _Code Value_,
+    /// _Long Code Value_, or _URN Code Value_, whichever is present.
+    pub fn from_item<D>(item: &InMemDicomObject<D>) -> Result<Self, CodeError>
+    where
+        D: DataDictionary + Clone,
+    {
+        let text = |tag: Tag| -> Result<Option<String>, CodeError> {
+            item.get(tag)
+                .map(|e| {
+                    e.to_str()
+                        .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+                        .ok()
+                        .context(InvalidAttributeSnafu { tag })
+                })
+                .transpose()
+        };
+        let required = |tag: Tag| -> Result<String, CodeError> {
+            text(tag)?.context(MissingAttributeSnafu { tag })
+        };
+
+        let value = match text(tags::CODE_VALUE)? {
+            Some(value) => value,
+            None => match text(tags::LONG_CODE_VALUE)? {
+                Some(value) => value,
+                None => text(tags::URN_CODE_VALUE)?.context(MissingCodeValueSnafu)?,
+            },
+        };
+        Ok(Code {
+            value: value.into(),
+            scheme: required(tags::CODING_SCHEME_DESIGNATOR)?.into(),
+            scheme_version: text(tags::CODING_SCHEME_VERSION)?.map(Cow::from),
+            meaning: required(tags::CODE_MEANING)?.into(),
+        })
+    }
+
+    /// Read the code in the first item of the given code sequence.
+    pub fn from_sequence<D>(obj: &InMemDicomObject<D>, tag: Tag) -> Result<Self, CodeError>
+    where
+        D: DataDictionary + Clone,
+    {
+        let mut codes = Code::all_from_sequence(obj, tag)?;
+        if codes.is_empty() {
+            return EmptySequenceSnafu { tag }.fail();
+        }
+        codes.swap_remove(0)
+    }
+
+    /// Read the codes in all items of the given code sequence.
+    pub fn all_from_sequence<D>(
+        obj: &InMemDicomObject<D>,
+        tag: Tag,
+    ) -> Result<Vec<Result<Self, CodeError>>, CodeError>
+    where
+        D: DataDictionary + Clone,
+    {
+        let items = obj
+            .get(tag)
+            .and_then(|e| e.items())
+            .context(MissingSequenceSnafu { tag })?;
+        Ok(items.iter().map(Code::from_item).collect())
+    }
+
+    /// Create a code sequence item with this code.
+    ///
+    /// The code value is placed in _Code Value_,
+    /// or in _Long Code Value_ if it is longer than 16 characters,
+    /// or in _URN Code Value_ if it is a URN or URL.
+    pub fn to_item(&self) -> InMemDicomObject {
+        let value = &*self.value;
+        let value_element = if value.starts_with("urn:") || value.contains("://") {
+            DataElement::new(tags::URN_CODE_VALUE, VR::UR, PrimitiveValue::from(value))
+        } else if value.len() > MAX_SHORT_CODE_VALUE_LENGTH {
+            DataElement::new(tags::LONG_CODE_VALUE, VR::UC, PrimitiveValue::from(value))
+        } else {
+            DataElement::new(tags::CODE_VALUE, VR::SH, PrimitiveValue::from(value))
+        };
+        let mut item = InMemDicomObject::from_element_iter([
+            value_element,
+            DataElement::new(
+                tags::CODING_SCHEME_DESIGNATOR,
+                VR::SH,
+                PrimitiveValue::from(&*self.scheme),
+            ),
+            DataElement::new(
+                tags::CODE_MEANING,
+                VR::LO,
+                PrimitiveValue::from(&*self.meaning),
+            ),
+        ]);
+        if let Some(version) = &self.scheme_version {
+            item.put_str(tags::CODING_SCHEME_VERSION, VR::SH, &**version);
+        }
+        item
+    }
+
+    /// Create a code sequence element with a single item of this code.
+    pub fn to_sequence_element(&self, tag: Tag) -> InMemElement {
+        Code::sequence_element(tag, [self])
+    }
+
+    /// Create a code sequence element with one item per code.
+    pub fn sequence_element<'a>(
+        tag: Tag,
+        codes: impl IntoIterator<Item = &'a Code>,
+    ) -> InMemElement {
+        let items: Vec<_> = codes.into_iter().map(Code::to_item).collect();
+        DataElement::new(tag, VR::SQ, DataSetSequence::new(items, Length::UNDEFINED))
+    }
+}
+
+impl<D> TryFrom<&InMemDicomObject<D>> for Code
+where
+    D: DataDictionary + Clone,
+{
+    type Error = CodeError;
+
+    fn try_from(item: &InMemDicomObject<D>) -> Result<Self, Self::Error> {
+        Code::from_item(item)
+    }
+}
+
+impl From<&Code> for InMemDicomObject {
+    fn from(code: &Code) -> Self {
+        code.to_item()
+    }
+}
+
+impl fmt::Display for Code {
+    /// Format the code in the triplet notation of the standard,
+    /// as in `(121071, DCM, "Finding")`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}", self.value, self.scheme)?;
+        if let Some(version) = &self.scheme_version {
+            write!(f, " [{version}]")?;
+        }
+        write!(f, ", \"{}\")", self.meaning)
+    }
+}
+
+/// All codes declared in this module, for look-ups by value.
+static COMMON_CODES: &[&Code] = &[
+    &dcm::ALGORITHM_NAME,
+    &dcm::ALGORITHM_VERSION,
+    &dcm::IMAGE_LIBRARY,
+    &dcm::TRACKING_IDENTIFIER,
+    &dcm::TRACKING_UNIQUE_IDENTIFIER,
+    &dcm::SEGMENTATION,
+    &dcm::OBSERVER_TYPE,
+    &dcm::PERSON,
+    &dcm::DEVICE,
+    &dcm::PERSON_OBSERVER_NAME,
+    &dcm::DEVICE_OBSERVER_UID,
+    &dcm::DEVICE_OBSERVER_NAME,
+    &dcm::SUBJECT_CLASS,
+    &dcm::PATIENT,
+    &dcm::LANGUAGE_OF_CONTENT_ITEM_AND_DESCENDANTS,
+    &dcm::PROCEDURE_REPORTED,
+    &dcm::HISTORY,
+    &dcm::FINDINGS,
+    &dcm::FINDING,
+    &dcm::IMPRESSION,
+    &dcm::RECOMMENDATION,
+    &dcm::CONCLUSION,
+    &dcm::COMMENT,
+    &dcm::SOURCE_IMAGE_FOR_IMAGE_PROCESSING_OPERATION,
+    &dcm::MEASUREMENT_GROUP,
+    &dcm::IMAGING_MEASUREMENT_REPORT,
+    &dcm::IMAGING_MEASUREMENTS,
+    &dcm::IMAGE_LIBRARY_GROUP,
+    &dcm::COMPUTED_RADIOGRAPHY,
+    &dcm::COMPUTED_TOMOGRAPHY,
+    &dcm::DIGITAL_RADIOGRAPHY,
+    &dcm::MAGNETIC_RESONANCE,
+    &dcm::MAMMOGRAPHY,
+    &dcm::NUCLEAR_MEDICINE,
+    &dcm::POSITRON_EMISSION_TOMOGRAPHY,
+    &dcm::ULTRASOUND,
+    &dcm::X_RAY_ANGIOGRAPHY,
+    &sct::RIGHT,
+    &sct::LEFT,
+    &sct::RIGHT_AND_LEFT,
+    &sct::LATERALITY,
+    &sct::FINDING_SITE,
+    &sct::ASSOCIATED_MORPHOLOGY,
+    &sct::HEAD,
+    &sct::NECK,
+    &sct::BRAIN,
+    &sct::CHEST,
+    &sct::HEART,
+    &sct::LUNG,
+    &sct::BREAST,
+    &sct::ABDOMEN,
+    &sct::LIVER,
+    &sct::KIDNEY,
+    &sct::MASS,
+    &sct::NODULE,
+    &sct::TISSUE,
+    &sct::ANATOMICAL_STRUCTURE,
+    &sct::MORPHOLOGICALLY_ALTERED_STRUCTURE,
+    &sct::LENGTH,
+    &sct::DIAMETER,
+    &sct::AREA,
+    &sct::VOLUME,
+    &sct::LONG_AXIS,
+    &sct::SHORT_AXIS,
+    &sct::QUANTITY,
+    &sct::MALE,
+    &sct::FEMALE,
+    &sct::YES,
+    &sct::NO,
+    &ln::DIAGNOSTIC_IMAGING_REPORT,
+    &ln::HISTORY,
+    &ln::CURRENT_PROCEDURE_DESCRIPTIONS,
+    &ln::INDICATIONS_FOR_PROCEDURE,
+    &ln::CONCLUSIONS,
+    &ln::HEART_RATE,
+    &ln::SYSTOLIC_BLOOD_PRESSURE,
+    &ln::DIASTOLIC_BLOOD_PRESSURE,
+    &ln::BODY_WEIGHT,
+    &ln::BODY_HEIGHT,
+    &ucum::NO_UNITS,
+    &ucum::PERCENT,
+    &ucum::MILLIMETER,
+    &ucum::CENTIMETER,
+    &ucum::SQUARE_MILLIMETER,
+    &ucum::MILLILITER,
+    &ucum::SECOND,
+    &ucum::KILOGRAM,
+    &ucum::CENTIMETER_PER_SECOND,
+    &ucum::SUV_BODY_WEIGHT,
+];
+
+/// Look up one of the codes declared in this module
+/// by its coding scheme designator and code value.
+///
+/// ```
+/// # use dicom_object::code::{self, sct};
+/// assert_eq!(code::lookup("SCT", "24028007"), Some(&sct::RIGHT));
+/// assert_eq!(code::lookup("SCT", "0"), None);
+/// ```
+pub fn lookup(scheme: &str, value: &str) -> Option<&'static Code> {
+    COMMON_CODES
+        .iter()
+        .find(|code| code.scheme == scheme && code.value == value)
+        .copied()
+}
+
+/// Common codes of the DICOM Controlled Terminology ([PS3.16 Annex D][1]).
+///
+/// [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part16/chapter_D.html
+pub mod dcm {
+    use super::{Code, DCM};
+
+    /// (111001, DCM, "Algorithm Name")
+    pub const ALGORITHM_NAME: Code = Code::new_static("111001", DCM, "Algorithm Name");
+    /// (111003, DCM, "Algorithm Version")
+    pub const ALGORITHM_VERSION: Code = Code::new_static("111003", DCM, "Algorithm Version");
+    /// (111028, DCM, "Image Library")
+    pub const IMAGE_LIBRARY: Code = Code::new_static("111028", DCM, "Image Library");
+    /// (112039, DCM, "Tracking Identifier")
+    pub const TRACKING_IDENTIFIER: Code = Code::new_static("112039", DCM, "Tracking Identifier");
+    /// (112040, DCM, "Tracking Unique Identifier")
+    pub const TRACKING_UNIQUE_IDENTIFIER: Code =
+        Code::new_static("112040", DCM, "Tracking Unique Identifier");
+    /// (113076, DCM, "Segmentation")
+    pub const SEGMENTATION: Code = Code::new_static("113076", DCM, "Segmentation");
+    /// (121005, DCM, "Observer Type")
+    pub const OBSERVER_TYPE: Code = Code::new_static("121005", DCM, "Observer Type");
+    /// (121006, DCM, "Person")
+    pub const PERSON: Code = Code::new_static("121006", DCM, "Person");
+    /// (121007, DCM, "Device")
+    pub const DEVICE: Code = Code::new_static("121007", DCM, "Device");
+    /// (121008, DCM, "Person Observer Name")
+    pub const PERSON_OBSERVER_NAME: Code = Code::new_static("121008", DCM, "Person Observer Name");
+    /// (121012, DCM, "Device Observer UID")
+    pub const DEVICE_OBSERVER_UID: Code = Code::new_static("121012", DCM, "Device Observer UID");
+    /// (121013, DCM, "Device Observer Name")
+    pub const DEVICE_OBSERVER_NAME: Code = Code::new_static("121013", DCM, "Device Observer Name");
+    /// (121024, DCM, "Subject Class")
+    pub const SUBJECT_CLASS: Code = Code::new_static("121024", DCM, "Subject Class");
+    /// (121025, DCM, "Patient")
+    pub const PATIENT: Code = Code::new_static("121025", DCM, "Patient");
+    /// (121049, DCM, "Language of Content Item and Descendants")
+    pub const LANGUAGE_OF_CONTENT_ITEM_AND_DESCENDANTS: Code =
+        Code::new_static("121049", DCM, "Language of Content Item and Descendants");
+    /// (121058, DCM, "Procedure reported")
+    pub const PROCEDURE_REPORTED: Code = Code::new_static("121058", DCM, "Procedure reported");
+    /// (121060, DCM, "History")
+    pub const HISTORY: Code = Code::new_static("121060", DCM, "History");
+    /// (121070, DCM, "Findings")
+    pub const FINDINGS: Code = Code::new_static("121070", DCM, "Findings");
+    /// (121071, DCM, "Finding")
+    pub const FINDING: Code = Code::new_static("121071", DCM, "Finding");
+    /// (121073, DCM, "Impression")
+    pub const IMPRESSION: Code = Code::new_static("121073", DCM, "Impression");
+    /// (121075, DCM, "Recommendation")
+    pub const RECOMMENDATION: Code = Code::new_static("121075", DCM, "Recommendation");
+    /// (121077, DCM, "Conclusion")
+    pub const CONCLUSION: Code = Code::new_static("121077", DCM, "Conclusion");
+    /// (121106, DCM, "Comment")
+    pub const COMMENT: Code = Code::new_static("121106", DCM, "Comment");
+    /// (121322, DCM, "Source image for image processing operation")
+    pub const SOURCE_IMAGE_FOR_IMAGE_PROCESSING_OPERATION: Code =
+        Code::new_static("121322", DCM, "Source image for image processing operation");
+    /// (125007, DCM, "Measurement Group")
+    pub const MEASUREMENT_GROUP: Code = Code::new_static("125007", DCM, "Measurement Group");
+    /// (126000, DCM, "Imaging Measurement Report")
+    pub const IMAGING_MEASUREMENT_REPORT: Code =
+        Code::new_static("126000", DCM, "Imaging Measurement Report");
+    /// (126010, DCM, "Imaging Measurements")
+    pub const IMAGING_MEASUREMENTS: Code = Code::new_static("126010", DCM, "Imaging Measurements");
+    /// (126200, DCM, "Image Library Group")
+    pub const IMAGE_LIBRARY_GROUP: Code = Code::new_static("126200", DCM, "Image Library Group");
+
+    // acquisition modalities (CID 29)
+
+    /// (CR, DCM, "Computed Radiography")
+    pub const COMPUTED_RADIOGRAPHY: Code = Code::new_static("CR", DCM, "Computed Radiography");
+    /// (CT, DCM, "Computed Tomography")
+    pub const COMPUTED_TOMOGRAPHY: Code = Code::new_static("CT", DCM, "Computed Tomography");
+    /// (DX, DCM, "Digital Radiography")
+    pub const DIGITAL_RADIOGRAPHY: Code = Code::new_static("DX", DCM, "Digital Radiography");
+    /// (MR, DCM, "Magnetic Resonance")
+    pub const MAGNETIC_RESONANCE: Code = Code::new_static("MR", DCM, "Magnetic Resonance");
+    /// (MG, DCM, "Mammography")
+    pub const MAMMOGRAPHY: Code = Code::new_static("MG", DCM, "Mammography");
+    /// (NM, DCM, "Nuclear Medicine")
+    pub const NUCLEAR_MEDICINE: Code = Code::new_static("NM", DCM, "Nuclear Medicine");
+    /// (PT, DCM, "Positron emission tomography")
+    pub const POSITRON_EMISSION_TOMOGRAPHY: Code =
+        Code::new_static("PT", DCM, "Positron emission tomography");
+    /// (US, DCM, "Ultrasound")
+    pub const ULTRASOUND: Code = Code::new_static("US", DCM, "Ultrasound");
+    /// (XA, DCM, "X-Ray Angiography")
+    pub const X_RAY_ANGIOGRAPHY: Code = Code::new_static("XA", DCM, "X-Ray Angiography");
+}
+
+/// Common codes of SNOMED CT.
+pub mod sct {
+    use super::{Code, SCT};
+
+    /// (24028007, SCT, "Right")
+    pub const RIGHT: Code = Code::new_static("24028007", SCT, "Right");
+    /// (7771000, SCT, "Left")
+    pub const LEFT: Code = Code::new_static("7771000", SCT, "Left");
+    /// (51440002, SCT, "Right and left")
+    pub const RIGHT_AND_LEFT: Code = Code::new_static("51440002", SCT, "Right and left");
+    /// (272741003, SCT, "Laterality")
+    pub const LATERALITY: Code = Code::new_static("272741003", SCT, "Laterality");
+    /// (363698007, SCT, "Finding Site")
+    pub const FINDING_SITE: Code = Code::new_static("363698007", SCT, "Finding Site");
+    /// (116676008, SCT, "Associated Morphology")
+    pub const ASSOCIATED_MORPHOLOGY: Code =
+        Code::new_static("116676008", SCT, "Associated Morphology");
+    /// (69536005, SCT, "Head")
+    pub const HEAD: Code = Code::new_static("69536005", SCT, "Head");
+    /// (45048000, SCT, "Neck")
+    pub const NECK: Code = Code::new_static("45048000", SCT, "Neck");
+    /// (12738006, SCT, "Brain")
+    pub const BRAIN: Code = Code::new_static("12738006", SCT, "Brain");
+    /// (51185008, SCT, "Chest")
+    pub const CHEST: Code = Code::new_static("51185008", SCT, "Chest");
+    /// (80891009, SCT, "Heart")
+    pub const HEART: Code = Code::new_static("80891009", SCT, "Heart");
+    /// (39607008, SCT, "Lung")
+    pub const LUNG: Code = Code::new_static("39607008", SCT, "Lung");
+    /// (76752008, SCT, "Breast")
+    pub const BREAST: Code = Code::new_static("76752008", SCT, "Breast");
+    /// (818983003, SCT, "Abdomen")
+    pub const ABDOMEN: Code = Code::new_static("818983003", SCT, "Abdomen");
+    /// (10200004, SCT, "Liver")
+    pub const LIVER: Code = Code::new_static("10200004", SCT, "Liver");
+    /// (64033007, SCT, "Kidney")
+    pub const KIDNEY: Code = Code::new_static("64033007", SCT, "Kidney");
+    /// (4147007, SCT, "Mass")
+    pub const MASS: Code = Code::new_static("4147007", SCT, "Mass");
+    /// (27925004, SCT, "Nodule")
+    pub const NODULE: Code = Code::new_static("27925004", SCT, "Nodule");
+    /// (85756007, SCT, "Tissue")
+    pub const TISSUE: Code = Code::new_static("85756007", SCT, "Tissue");
+    /// (123037004, SCT, "Anatomical Structure")
+    pub const ANATOMICAL_STRUCTURE: Code =
+        Code::new_static("123037004", SCT, "Anatomical Structure");
+    /// (49755003, SCT, "Morphologically Altered Structure")
+    pub const MORPHOLOGICALLY_ALTERED_STRUCTURE: Code =
+        Code::new_static("49755003", SCT, "Morphologically Altered Structure");
+    /// (410668003, SCT, "Length")
+    pub const LENGTH: Code = Code::new_static("410668003", SCT, "Length");
+    /// (81827009, SCT, "Diameter")
+    pub const DIAMETER: Code = Code::new_static("81827009", SCT, "Diameter");
+    /// (42798000, SCT, "Area")
+    pub const AREA: Code = Code::new_static("42798000", SCT, "Area");
+    /// (118565006, SCT, "Volume")
+    pub const VOLUME: Code = Code::new_static("118565006", SCT, "Volume");
+    /// (103339001, SCT, "Long Axis")
+    pub const LONG_AXIS: Code = Code::new_static("103339001", SCT, "Long Axis");
+    /// (103340004, SCT, "Short Axis")
+    pub const SHORT_AXIS: Code = Code::new_static("103340004", SCT, "Short Axis");
+    /// (246205007, SCT, "Quantity")
+    pub const QUANTITY: Code = Code::new_static("246205007", SCT, "Quantity");
+    /// (248153007, SCT, "Male")
+    pub const MALE: Code = Code::new_static("248153007", SCT, "Male");
+    /// (248152002, SCT, "Female")
+    pub const FEMALE: Code = Code::new_static("248152002", SCT, "Female");
+    /// (373066001, SCT, "Yes")
+    pub const YES: Code = Code::new_static("373066001", SCT, "Yes");
+    /// (373067005, SCT, "No")
+    pub const NO: Code = Code::new_static("373067005", SCT, "No");
+}
+
+/// Common codes of LOINC.
+pub mod ln {
+    use super::{Code, LN};
+
+    /// (18748-4, LN, "Diagnostic Imaging Report")
+    pub const DIAGNOSTIC_IMAGING_REPORT: Code =
+        Code::new_static("18748-4", LN, "Diagnostic Imaging Report");
+    /// (11329-0, LN, "History")
+    pub const HISTORY: Code = Code::new_static("11329-0", LN, "History");
+    /// (55111-9, LN, "Current Procedure Descriptions")
+    pub const CURRENT_PROCEDURE_DESCRIPTIONS: Code =
+        Code::new_static("55111-9", LN, "Current Procedure Descriptions");
+    /// (18785-6, LN, "Indications for Procedure")
+    pub const INDICATIONS_FOR_PROCEDURE: Code =
+        Code::new_static("18785-6", LN, "Indications for Procedure");
+    /// (55110-1, LN, "Conclusions")
+    pub const CONCLUSIONS: Code = Code::new_static("55110-1", LN, "Conclusions");
+    /// (8867-4, LN, "Heart rate")
+    pub const HEART_RATE: Code = Code::new_static("8867-4", LN, "Heart rate");
+    /// (8480-6, LN, "Systolic blood pressure")
+    pub const SYSTOLIC_BLOOD_PRESSURE: Code =
+        Code::new_static("8480-6", LN, "Systolic blood pressure");
+    /// (8462-4, LN, "Diastolic blood pressure")
+    pub const DIASTOLIC_BLOOD_PRESSURE: Code =
+        Code::new_static("8462-4", LN, "Diastolic blood pressure");
+    /// (29463-7, LN, "Body weight")
+    pub const BODY_WEIGHT: Code = Code::new_static("29463-7", LN, "Body weight");
+    /// (8302-2, LN, "Body height")
+    pub const BODY_HEIGHT: Code = Code::new_static("8302-2", LN, "Body height");
+}
+
+/// Common units of measurement of the Unified Code for Units of Measure.
+pub mod ucum {
+    use super::{Code, UCUM};
+
+    /// (1, UCUM, "no units")
+    pub const NO_UNITS: Code = Code::new_static("1", UCUM, "no units");
+    /// (%, UCUM, "Percent")
+    pub const PERCENT: Code = Code::new_static("%", UCUM, "Percent");
+    /// (mm, UCUM, "millimeter")
+    pub const MILLIMETER: Code = Code::new_static("mm", UCUM, "millimeter");
+    /// (cm, UCUM, "centimeter")
+    pub const CENTIMETER: Code = Code::new_static("cm", UCUM, "centimeter");
+    /// (mm2, UCUM, "square millimeter")
+    pub const SQUARE_MILLIMETER: Code = Code::new_static("mm2", UCUM, "square millimeter");
+    /// (ml, UCUM, "milliliter")
+    pub const MILLILITER: Code = Code::new_static("ml", UCUM, "milliliter");
+    /// (s, UCUM, "second")
+    pub const SECOND: Code = Code::new_static("s", UCUM, "second");
+    /// (kg, UCUM, "kilogram")
+    pub const KILOGRAM: Code = Code::new_static("kg", UCUM, "kilogram");
+    /// (cm/s, UCUM, "centimeter per second")
+    pub const CENTIMETER_PER_SECOND: Code = Code::new_static("cm/s", UCUM, "centimeter per second");
+    /// ({SUVbw}g/ml, UCUM, "Standardized Uptake Value body weight")
+    pub const SUV_BODY_WEIGHT: Code =
+        Code::new_static("{SUVbw}g/ml", UCUM, "Standardized Uptake Value body weight");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_to_and_from_items() {
+        let item = dcm::FINDING.to_item();
+        assert_eq!(
+            item.get(tags::CODE_VALUE).unwrap().to_str().unwrap(),
+            "121071"
+        );
+        assert_eq!(Code::from_item(&item).unwrap(), dcm::FINDING);
+
+        let code = Code::new("1.2.3.4.5.6.7.8.9.10", "99LOCAL", "Local concept")
+            .with_scheme_version("2024");
+        let item = InMemDicomObject::from(&code);
+        assert!(item.get(tags::CODE_VALUE).is_none());
+        assert!(item.get(tags::LONG_CODE_VALUE).is_some());
+        assert_eq!(Code::try_from(&item).unwrap(), code);
+        assert_eq!(
+            code.to_string(),
+            r#"(1.2.3.4.5.6.7.8.9.10, 99LOCAL [2024], "Local concept")"#
+        );
+
+        let code = Code::new("urn:oid:2.16.840.1.113883.6.96", "URN", "URN code");
+        let item = code.to_item();
+        assert!(item.get(tags::URN_CODE_VALUE).is_some());
+        assert_eq!(Code::from_item(&item).unwrap(), code);
+
+        // code values are trimmed of padding
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::CODE_VALUE, VR::SH, PrimitiveValue::from("CT ")),
+            DataElement::new(
+                tags::CODING_SCHEME_DESIGNATOR,
+                VR::SH,
+                PrimitiveValue::from("DCM "),
+            ),
+            DataElement::new(tags::CODE_MEANING, VR::LO, PrimitiveValue::from("CT")),
+        ]);
+        let code = Code::from_item(&item).unwrap();
+        assert!(code.is_same_concept(&dcm::COMPUTED_TOMOGRAPHY));
+        assert_ne!(code, dcm::COMPUTED_TOMOGRAPHY);
+
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::CODE_VALUE,
+            VR::SH,
+            PrimitiveValue::from("CT"),
+        )]);
+        assert!(matches!(
+            Code::from_item(&item),
+            Err(CodeError::MissingAttribute { tag }) if tag == tags::CODING_SCHEME_DESIGNATOR
+        ));
+        assert!(matches!(
+            Code::from_item(&InMemDicomObject::new_empty()),
+            Err(CodeError::MissingCodeValue)
+        ));
+    }
+
+    #[test]
+    fn code_sequences() {
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put(Code::sequence_element(
+            tags::ANATOMIC_REGION_SEQUENCE,
+            [&sct::CHEST, &sct::ABDOMEN],
+        ));
+        let codes: Vec<_> = Code::all_from_sequence(&obj, tags::ANATOMIC_REGION_SEQUENCE)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(codes, vec![sct::CHEST, sct::ABDOMEN]);
+        assert_eq!(
+            Code::from_sequence(&obj, tags::ANATOMIC_REGION_SEQUENCE).unwrap(),
+            sct::CHEST
+        );
+        assert!(matches!(
+            Code::from_sequence(&obj, tags::CONCEPT_NAME_CODE_SEQUENCE),
+            Err(CodeError::MissingSequence { .. })
+        ));
+
+        obj.put(Code::sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE, []));
+        assert!(matches!(
+            Code::from_sequence(&obj, tags::CONCEPT_NAME_CODE_SEQUENCE),
+            Err(CodeError::EmptySequence { .. })
+        ));
+
+        assert_eq!(lookup(LN, "8867-4"), Some(&ln::HEART_RATE));
+        assert_eq!(lookup(UCUM, "mm"), Some(&ucum::MILLIMETER));
+    }
+}
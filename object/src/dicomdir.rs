@@ -0,0 +1,960 @@
+//! DICOMDIR API:
+//! reading and creating media storage directories.
+//!
+//! A DICOMDIR file describes the contents of a DICOM file-set
+//! as a hierarchy of directory records
+//! (patients, their studies, series, and instances),
+//! in which the leaf records reference the files of the file-set.
+//!
+//! # Reading
+//!
+//! [`Dicomdir`] reads a DICOMDIR file into a tree of [`DirectoryRecord`]s,
+//! and resolves the files they reference
+//! relative to the directory containing the DICOMDIR.
+//!
+//! ```no_run
+//! use dicom_object::dicomdir::{Dicomdir, RecordType};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dicomdir = Dicomdir::open_file("media/DICOMDIR")?;
+//! for record in dicomdir.iter() {
+//!     if record.record_type() == &RecordType::Image {
+//!         let path = dicomdir.resolve(record).unwrap();
+//!         println!("{}", path.display());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Creating
+//!
+//! [`DicomdirBuilder`] gathers the key attributes of the files added to it
+//! into patient, study, series, and instance records,
+//! and then produces a DICOMDIR object
+//! with all directory record offsets in place.
+//!
+//! ```no_run
+//! use dicom_object::{dicomdir::DicomdirBuilder, open_file};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut builder = DicomdirBuilder::new().file_set_id("MYMEDIA");
+//! // file IDs are relative to the root of the file-set
+//! builder.add_file("IMAGES/IM0001", &open_file("media/IMAGES/IM0001")?)?;
+//! builder.add_file("IMAGES/IM0002", &open_file("media/IMAGES/IM0002")?)?;
+//! builder.build()?.write_to_file("media/DICOMDIR")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR, value::DataSetSequence};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::TransferSyntaxIndex;
+use dicom_parser::dataset::{DataSetReader, DataToken};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::Backtrace;
+use snafu::prelude::*;
+
+use crate::{
+    DefaultDicomObject, FileMetaTable, FileMetaTableBuilder, InMemDicomObject, OpenFileOptions,
+};
+use dicom_core::uid::new_sop_instance_uid;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error which may occur when reading or creating a DICOMDIR
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for the DICOMDIR API
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    #[snafu(display("Could not read file '{}'", filename.display()))]
+    ReadFile {
+        filename: PathBuf,
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not read DICOMDIR object
+    ReadObject {
+        #[snafu(backtrace, source(from(crate::ReadError, Box::from)))]
+        source: Box<crate::ReadError>,
+    },
+    /// Could not parse meta group data set
+    ParseMetaDataSet {
+        #[snafu(backtrace, source(from(crate::meta::Error, Box::new)))]
+        source: Box<crate::meta::Error>,
+    },
+    /// Unrecognized transfer syntax {ts_uid}
+    UnrecognizedTransferSyntax {
+        ts_uid: String,
+        backtrace: Backtrace,
+    },
+    /// Could not read data set token
+    ReadToken {
+        #[snafu(backtrace, source(from(dicom_parser::dataset::read::Error, Box::from)))]
+        source: Box<dicom_parser::dataset::read::Error>,
+    },
+    /// Missing Directory Record Sequence
+    MissingRecordSequence { backtrace: Backtrace },
+    /// No directory record at offset {offset}
+    InvalidRecordOffset { offset: u32, backtrace: Backtrace },
+    /// Directory record at offset {offset} is referenced more than once
+    RecordCycle { offset: u32, backtrace: Backtrace },
+    /// Missing Directory Record Type in record at offset {offset}
+    MissingRecordType { offset: u32, backtrace: Backtrace },
+    #[snafu(display("Invalid file ID '{}': {}", file_id.display(), reason))]
+    InvalidFileId {
+        file_id: PathBuf,
+        reason: &'static str,
+        backtrace: Backtrace,
+    },
+    #[snafu(display("Missing attribute {} in file '{}'", tag, file_id.display()))]
+    MissingAttribute {
+        tag: Tag,
+        file_id: PathBuf,
+        backtrace: Backtrace,
+    },
+    /// Could not write DICOMDIR object
+    WriteObject {
+        #[snafu(backtrace, source(from(crate::WriteError, Box::from)))]
+        source: Box<crate::WriteError>,
+    },
+    /// Could not build DICOMDIR file meta group
+    BuildMeta {
+        #[snafu(backtrace, source(from(crate::WithMetaError, Box::from)))]
+        source: Box<crate::WithMetaError>,
+    },
+}
+
+/// The type of a directory record,
+/// as in the _Directory Record Type_ attribute.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum RecordType {
+    /// `PATIENT`
+    Patient,
+    /// `STUDY`
+    Study,
+    /// `SERIES`
+    Series,
+    /// `IMAGE`
+    Image,
+    /// Any other record type,
+    /// such as `SR DOCUMENT`, `PRESENTATION`, or `PRIVATE`
+    Other(String),
+}
+
+impl RecordType {
+    /// Interpret the value of a _Directory Record Type_ attribute.
+    pub fn from_code(code: &str) -> Self {
+        match code.trim_end_matches([' ', '\0']) {
+            "PATIENT" => RecordType::Patient,
+            "STUDY" => RecordType::Study,
+            "SERIES" => RecordType::Series,
+            "IMAGE" => RecordType::Image,
+            code => RecordType::Other(code.to_string()),
+        }
+    }
+
+    /// Obtain the code of this record type,
+    /// as in the _Directory Record Type_ attribute.
+    pub fn as_str(&self) -> &str {
+        match self {
+            RecordType::Patient => "PATIENT",
+            RecordType::Study => "STUDY",
+            RecordType::Series => "SERIES",
+            RecordType::Image => "IMAGE",
+            RecordType::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A directory record in a DICOMDIR,
+/// with the records of its lower-level directory entity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryRecord {
+    /// the type of record
+    record_type: RecordType,
+    /// the record's attributes,
+    /// excluding those describing the directory structure
+    keys: InMemDicomObject,
+    /// the records of the lower-level directory entity
+    children: Vec<DirectoryRecord>,
+}
+
+impl DirectoryRecord {
+    /// Create a new directory record
+    /// from its type and key attributes.
+    pub fn new(record_type: RecordType, keys: InMemDicomObject) -> Self {
+        DirectoryRecord {
+            record_type,
+            keys,
+            children: Vec::new(),
+        }
+    }
+
+    /// Retrieve the type of record.
+    pub fn record_type(&self) -> &RecordType {
+        &self.record_type
+    }
+
+    /// Retrieve the record's attributes,
+    /// such as _Patient ID_ in a patient record
+    /// or _Referenced File ID_ in an image record.
+    ///
+    /// The attributes describing the directory structure
+    /// (offsets, record in-use flag and record type)
+    /// are not included.
+    pub fn keys(&self) -> &InMemDicomObject {
+        &self.keys
+    }
+
+    /// Retrieve the records of the lower-level directory entity.
+    pub fn children(&self) -> &[DirectoryRecord] {
+        &self.children
+    }
+
+    /// Add a record to the lower-level directory entity.
+    pub fn push_child(&mut self, record: DirectoryRecord) {
+        self.children.push(record);
+    }
+
+    /// Retrieve the file referenced by this record,
+    /// as a path relative to the root of the file-set.
+    pub fn referenced_file_id(&self) -> Option<PathBuf> {
+        let components = self
+            .keys
+            .get(tags::REFERENCED_FILE_ID)?
+            .to_multi_str()
+            .ok()?;
+        Some(
+            components
+                .iter()
+                .map(|c| c.trim_end_matches([' ', '\0']))
+                .collect(),
+        )
+    }
+
+    /// Retrieve the SOP Class UID of the referenced file.
+    pub fn referenced_sop_class_uid(&self) -> Option<String> {
+        self.key_str(tags::REFERENCED_SOP_CLASS_UID_IN_FILE)
+    }
+
+    /// Retrieve the SOP Instance UID of the referenced file.
+    pub fn referenced_sop_instance_uid(&self) -> Option<String> {
+        self.key_str(tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE)
+    }
+
+    /// Retrieve the transfer syntax UID of the referenced file.
+    pub fn referenced_transfer_syntax_uid(&self) -> Option<String> {
+        self.key_str(tags::REFERENCED_TRANSFER_SYNTAX_UID_IN_FILE)
+    }
+
+    fn key_str(&self, tag: Tag) -> Option<String> {
+        let value = self.keys.get(tag)?.to_str().ok()?;
+        Some(value.trim_end_matches([' ', '\0']).to_string())
+    }
+}
+
+/// A DICOMDIR file read into memory,
+/// with its directory records arranged in a tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dicomdir {
+    /// the directory against which referenced files are resolved
+    base_dir: PathBuf,
+    /// the file meta group
+    meta: FileMetaTable,
+    /// the File-set ID
+    file_set_id: Option<String>,
+    /// the records of the root directory entity
+    records: Vec<DirectoryRecord>,
+}
+
+impl Dicomdir {
+    /// Read a DICOMDIR file.
+    ///
+    /// Referenced files are resolved
+    /// relative to the directory containing the DICOMDIR file.
+    /// Records which are not in use are left out.
+    pub fn open_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).context(ReadFileSnafu { filename: path })?;
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::from_bytes(&data, base_dir)
+    }
+
+    /// Read a DICOMDIR from the full contents of a DICOMDIR file,
+    /// resolving referenced files relative to `base_dir`.
+    pub fn from_bytes(data: &[u8], base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let obj = OpenFileOptions::new()
+            .from_reader(strip_preamble(data))
+            .context(ReadObjectSnafu)?;
+        let offsets = record_offsets(data)?;
+        let (meta, obj) = (obj.meta().clone(), obj.into_inner());
+
+        let items = obj
+            .get(tags::DIRECTORY_RECORD_SEQUENCE)
+            .and_then(|e| e.items())
+            .context(MissingRecordSequenceSnafu)?;
+
+        let by_offset: HashMap<u32, &InMemDicomObject> =
+            offsets.into_iter().zip(items.iter()).collect();
+        let mut visited = vec![];
+        let first = uint_key(
+            &obj,
+            tags::OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        );
+        let records = read_records(&by_offset, first, &mut visited)?;
+
+        let file_set_id = obj
+            .get(tags::FILE_SET_ID)
+            .and_then(|e| e.to_str().ok())
+            .map(|id| id.trim_end_matches([' ', '\0']).to_string())
+            .filter(|id| !id.is_empty());
+
+        Ok(Dicomdir {
+            base_dir: base_dir.into(),
+            meta,
+            file_set_id,
+            records,
+        })
+    }
+
+    /// Retrieve the file meta group of the DICOMDIR.
+    pub fn meta(&self) -> &FileMetaTable {
+        &self.meta
+    }
+
+    /// Retrieve the File-set ID, if defined.
+    pub fn file_set_id(&self) -> Option<&str> {
+        self.file_set_id.as_deref()
+    }
+
+    /// Retrieve the directory against which referenced files are resolved.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Retrieve the records of the root directory entity
+    /// (usually patient records).
+    pub fn records(&self) -> &[DirectoryRecord] {
+        &self.records
+    }
+
+    /// Obtain an iterator over all directory records,
+    /// each record followed by the records of its lower-level entity.
+    pub fn iter(&self) -> impl Iterator<Item = &DirectoryRecord> + '_ {
+        let mut stack: Vec<&DirectoryRecord> = self.records.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let record = stack.pop()?;
+            stack.extend(record.children.iter().rev());
+            Some(record)
+        })
+    }
+
+    /// Resolve the path to the file referenced by the given record.
+    pub fn resolve(&self, record: &DirectoryRecord) -> Option<PathBuf> {
+        record.referenced_file_id().map(|id| self.base_dir.join(id))
+    }
+
+    /// Obtain an iterator over the paths to all referenced files.
+    pub fn referenced_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.iter().filter_map(|record| self.resolve(record))
+    }
+
+    /// Open the file referenced by the given record.
+    ///
+    /// Returns `Ok(None)` if the record does not reference a file.
+    pub fn open_referenced(&self, record: &DirectoryRecord) -> Result<Option<DefaultDicomObject>> {
+        self.resolve(record)
+            .map(|path| crate::open_file(path).context(ReadObjectSnafu))
+            .transpose()
+            .map_err(Error)
+    }
+}
+
+/// Build the records of a directory entity,
+/// following the chain of records starting at `offset`
+fn read_records(
+    by_offset: &HashMap<u32, &InMemDicomObject>,
+    mut offset: u32,
+    visited: &mut Vec<u32>,
+) -> Result<Vec<DirectoryRecord>> {
+    let mut records = Vec::new();
+    while offset != 0 {
+        ensure!(!visited.contains(&offset), RecordCycleSnafu { offset });
+        visited.push(offset);
+        let item = *by_offset
+            .get(&offset)
+            .context(InvalidRecordOffsetSnafu { offset })?;
+
+        let next = uint_key(item, tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD);
+        let lower = uint_key(
+            item,
+            tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+        );
+        let in_use = item
+            .get(tags::RECORD_IN_USE_FLAG)
+            .and_then(|e| e.to_int::<u16>().ok())
+            .unwrap_or(0xFFFF);
+
+        if in_use != 0 {
+            let record_type = item
+                .get(tags::DIRECTORY_RECORD_TYPE)
+                .and_then(|e| e.to_str().ok())
+                .map(|code| RecordType::from_code(&code))
+                .context(MissingRecordTypeSnafu { offset })?;
+            let keys = InMemDicomObject::from_element_iter(
+                item.iter()
+                    .filter(|e| !STRUCTURE_TAGS.contains(&e.header().tag))
+                    .cloned(),
+            );
+            records.push(DirectoryRecord {
+                record_type,
+                keys,
+                children: read_records(by_offset, lower, visited)?,
+            });
+        }
+        offset = next;
+    }
+    Ok(records)
+}
+
+/// The attributes of a directory record
+/// which describe the directory structure
+const STRUCTURE_TAGS: [Tag; 4] = [
+    tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD,
+    tags::RECORD_IN_USE_FLAG,
+    tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+    tags::DIRECTORY_RECORD_TYPE,
+];
+
+fn uint_key(obj: &InMemDicomObject, tag: Tag) -> u32 {
+    obj.get(tag)
+        .and_then(|e| e.to_int::<u32>().ok())
+        .unwrap_or(0)
+}
+
+fn strip_preamble(data: &[u8]) -> &[u8] {
+    if data.len() >= 132 && &data[128..132] == b"DICM" {
+        &data[128..]
+    } else {
+        data
+    }
+}
+
+/// Collect the byte offsets of the items in the Directory Record Sequence,
+/// relative to the beginning of the file
+fn record_offsets(data: &[u8]) -> Result<Vec<u32>> {
+    let mut rest = strip_preamble(data);
+    let meta = FileMetaTable::from_reader(&mut rest).context(ParseMetaDataSetSnafu)?;
+    let dataset_start = (data.len() - rest.len()) as u64;
+    let ts_uid = meta.transfer_syntax();
+    let ts = TransferSyntaxRegistry
+        .get(ts_uid)
+        .context(UnrecognizedTransferSyntaxSnafu { ts_uid })?;
+    let mut dataset = DataSetReader::new_with_ts(rest, ts).context(ReadTokenSnafu)?;
+
+    let mut offsets = Vec::new();
+    // the tags of the sequences currently open
+    let mut sequences = Vec::new();
+    while let Some(token) = dataset.next() {
+        match token.context(ReadTokenSnafu)? {
+            DataToken::SequenceStart { tag, .. } => sequences.push(tag),
+            DataToken::PixelSequenceStart => sequences.push(tags::PIXEL_DATA),
+            DataToken::SequenceEnd => {
+                sequences.pop();
+            }
+            DataToken::ItemStart { .. } if sequences == [tags::DIRECTORY_RECORD_SEQUENCE] => {
+                // the decoder is positioned right after the 8-byte item header
+                offsets.push((dataset_start + dataset.decoder_position() - 8) as u32);
+            }
+            _ => {}
+        }
+    }
+    Ok(offsets)
+}
+
+/// The key attributes copied to each type of directory record,
+/// with their value representations
+const PATIENT_KEYS: &[(Tag, VR)] = &[
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS),
+    (tags::PATIENT_NAME, VR::PN),
+    (tags::PATIENT_ID, VR::LO),
+];
+const STUDY_KEYS: &[(Tag, VR)] = &[
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS),
+    (tags::STUDY_DATE, VR::DA),
+    (tags::STUDY_TIME, VR::TM),
+    (tags::ACCESSION_NUMBER, VR::SH),
+    (tags::STUDY_DESCRIPTION, VR::LO),
+    (tags::STUDY_INSTANCE_UID, VR::UI),
+    (tags::STUDY_ID, VR::SH),
+];
+const SERIES_KEYS: &[(Tag, VR)] = &[
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS),
+    (tags::MODALITY, VR::CS),
+    (tags::SERIES_INSTANCE_UID, VR::UI),
+    (tags::SERIES_NUMBER, VR::IS),
+];
+const INSTANCE_KEYS: &[(Tag, VR)] = &[
+    (tags::SPECIFIC_CHARACTER_SET, VR::CS),
+    (tags::INSTANCE_NUMBER, VR::IS),
+];
+
+/// A builder for a DICOMDIR
+/// describing a file-set in a patient, study, series, instance hierarchy.
+///
+/// See the [module-level documentation](crate::dicomdir) for an example.
+#[derive(Debug, Default, Clone)]
+pub struct DicomdirBuilder {
+    /// the File-set ID
+    file_set_id: Option<String>,
+    /// the SOP Instance UID of the DICOMDIR
+    sop_instance_uid: Option<String>,
+    /// the patient records gathered so far
+    patients: Vec<DirectoryRecord>,
+}
+
+impl DicomdirBuilder {
+    /// Create a new DICOMDIR builder with no records.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the File-set ID of the DICOMDIR.
+    pub fn file_set_id(mut self, file_set_id: impl Into<String>) -> Self {
+        self.file_set_id = Some(file_set_id.into());
+        self
+    }
+
+    /// Set the SOP Instance UID of the DICOMDIR.
+    ///
+    /// If not set, a new UID is generated.
+    pub fn sop_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.sop_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Add a file of the file-set to the directory,
+    /// creating the patient, study, and series records as needed.
+    ///
+    /// `file_id` is the path to the file relative to the root of the file-set.
+    /// It must be made of at most 8 components,
+    /// each with 1 to 8 characters among
+    /// uppercase letters, digits, and the underscore.
+    pub fn add_file(&mut self, file_id: impl AsRef<Path>, obj: &DefaultDicomObject) -> Result<()> {
+        let file_id = file_id.as_ref();
+        let components = file_id_components(file_id)?;
+
+        let patient_id = required_str(obj, tags::PATIENT_ID, file_id)?;
+        let study_uid = required_str(obj, tags::STUDY_INSTANCE_UID, file_id)?;
+        let series_uid = required_str(obj, tags::SERIES_INSTANCE_UID, file_id)?;
+
+        let patient = find_or_insert(&mut self.patients, tags::PATIENT_ID, &patient_id, || {
+            DirectoryRecord::new(RecordType::Patient, copy_keys(obj, PATIENT_KEYS))
+        });
+        let study = find_or_insert(
+            &mut patient.children,
+            tags::STUDY_INSTANCE_UID,
+            &study_uid,
+            || DirectoryRecord::new(RecordType::Study, copy_keys(obj, STUDY_KEYS)),
+        );
+        let series = find_or_insert(
+            &mut study.children,
+            tags::SERIES_INSTANCE_UID,
+            &series_uid,
+            || DirectoryRecord::new(RecordType::Series, copy_keys(obj, SERIES_KEYS)),
+        );
+
+        let record_type = match obj.get(tags::MODALITY).and_then(|e| e.to_str().ok()) {
+            Some(modality) if modality == "SR" => RecordType::Other("SR DOCUMENT".to_string()),
+            Some(modality) if modality == "PR" => RecordType::Other("PRESENTATION".to_string()),
+            _ => RecordType::Image,
+        };
+        let mut keys = copy_keys(obj, INSTANCE_KEYS);
+        let meta = obj.meta();
+        keys.put(DataElement::new(
+            tags::REFERENCED_FILE_ID,
+            VR::CS,
+            PrimitiveValue::Strs(components.into_iter().collect()),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_SOP_CLASS_UID_IN_FILE,
+            VR::UI,
+            meta.media_storage_sop_class_uid(),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE,
+            VR::UI,
+            meta.media_storage_sop_instance_uid(),
+        ));
+        keys.put(DataElement::new(
+            tags::REFERENCED_TRANSFER_SYNTAX_UID_IN_FILE,
+            VR::UI,
+            meta.transfer_syntax(),
+        ));
+        series.push_child(DirectoryRecord::new(record_type, keys));
+        Ok(())
+    }
+
+    /// Add a record to the root directory entity,
+    /// such as a patient record built separately.
+    pub fn add_record(&mut self, record: DirectoryRecord) {
+        self.patients.push(record);
+    }
+
+    /// Create the DICOMDIR object,
+    /// encoded in _Explicit VR Little Endian_.
+    pub fn build(self) -> Result<DefaultDicomObject> {
+        // lay down the records with placeholder offsets,
+        // which do not change the size of the encoded object
+        let mut flat = Vec::new();
+        flatten(&self.patients, &mut flat);
+        let placeholders = vec![0; flat.len()];
+        let obj = self.assemble(&flat, &placeholders)?;
+
+        let mut data = Vec::new();
+        obj.write_all(&mut data).context(WriteObjectSnafu)?;
+        let offsets = record_offsets(&data)?;
+        self.assemble(&flat, &offsets)
+    }
+
+    /// Assemble the DICOMDIR object
+    /// with the given record offsets.
+    fn assemble(&self, flat: &[FlatRecord<'_>], offsets: &[u32]) -> Result<DefaultDicomObject> {
+        let offset_of = |i: Option<usize>| i.map(|i| offsets[i]).unwrap_or(0);
+        let items: Vec<InMemDicomObject> = flat
+            .iter()
+            .map(|record| {
+                let mut item = record.record.keys.clone();
+                item.put(DataElement::new(
+                    tags::OFFSET_OF_THE_NEXT_DIRECTORY_RECORD,
+                    VR::UL,
+                    PrimitiveValue::from(offset_of(record.next)),
+                ));
+                item.put(DataElement::new(
+                    tags::RECORD_IN_USE_FLAG,
+                    VR::US,
+                    PrimitiveValue::from(0xFFFF_u16),
+                ));
+                item.put(DataElement::new(
+                    tags::OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+                    VR::UL,
+                    PrimitiveValue::from(offset_of(record.first_child)),
+                ));
+                item.put(DataElement::new(
+                    tags::DIRECTORY_RECORD_TYPE,
+                    VR::CS,
+                    record.record.record_type.as_str(),
+                ));
+                item
+            })
+            .collect();
+
+        let first_root = (!flat.is_empty()).then_some(0);
+        let last_root = flat.iter().rposition(|record| record.root);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::FILE_SET_ID,
+                VR::CS,
+                self.file_set_id.as_deref().unwrap_or_default(),
+            ),
+            DataElement::new(
+                tags::OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+                VR::UL,
+                PrimitiveValue::from(offset_of(first_root)),
+            ),
+            DataElement::new(
+                tags::OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+                VR::UL,
+                PrimitiveValue::from(offset_of(last_root)),
+            ),
+            DataElement::new(
+                tags::FILE_SET_CONSISTENCY_FLAG,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ),
+            DataElement::new(
+                tags::DIRECTORY_RECORD_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(items),
+            ),
+        ]);
+
+        let sop_instance_uid = self
+            .sop_instance_uid
+            .clone()
+            .unwrap_or_else(new_sop_instance_uid);
+        obj.with_meta(
+            FileMetaTableBuilder::new()
+                .media_storage_sop_class_uid(uids::MEDIA_STORAGE_DIRECTORY_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid)
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN),
+        )
+        .context(BuildMetaSnafu)
+        .map_err(Error)
+    }
+}
+
+/// A directory record in the order of the Directory Record Sequence,
+/// with the positions of its related records
+struct FlatRecord<'a> {
+    record: &'a DirectoryRecord,
+    /// whether the record belongs to the root directory entity
+    root: bool,
+    /// position of the next record in the same directory entity
+    next: Option<usize>,
+    /// position of the first record in the lower-level directory entity
+    first_child: Option<usize>,
+}
+
+/// Lay out the records of a directory entity depth-first
+fn flatten<'a>(records: &'a [DirectoryRecord], out: &mut Vec<FlatRecord<'a>>) {
+    let root = out.is_empty();
+    let mut previous: Option<usize> = None;
+    for record in records {
+        let index = out.len();
+        if let Some(previous) = previous {
+            out[previous].next = Some(index);
+        }
+        out.push(FlatRecord {
+            record,
+            root,
+            next: None,
+            first_child: None,
+        });
+        if !record.children.is_empty() {
+            out[index].first_child = Some(out.len());
+            flatten(&record.children, out);
+        }
+        previous = Some(index);
+    }
+}
+
+fn find_or_insert<'a>(
+    records: &'a mut Vec<DirectoryRecord>,
+    tag: Tag,
+    key: &str,
+    new: impl FnOnce() -> DirectoryRecord,
+) -> &'a mut DirectoryRecord {
+    let position = records
+        .iter()
+        .position(|record| record.key_str(tag).as_deref() == Some(key));
+    match position {
+        Some(i) => &mut records[i],
+        None => {
+            records.push(new());
+            records.last_mut().unwrap()
+        }
+    }
+}
+
+/// Copy the given attributes from a file,
+/// leaving them empty if not present
+fn copy_keys(obj: &DefaultDicomObject, keys: &[(Tag, VR)]) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter(keys.iter().filter_map(|&(tag, vr)| {
+        match obj.get(tag) {
+            Some(e) => Some(e.clone()),
+            // specific character set is only needed when declared
+            None if tag == tags::SPECIFIC_CHARACTER_SET => None,
+            None => Some(DataElement::new(tag, vr, PrimitiveValue::Empty)),
+        }
+    }))
+}
+
+fn required_str(obj: &DefaultDicomObject, tag: Tag, file_id: &Path) -> Result<String> {
+    obj.get(tag)
+        .and_then(|e| e.to_str().ok())
+        .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+        .filter(|s| !s.is_empty())
+        .context(MissingAttributeSnafu { tag, file_id })
+        .map_err(Error)
+}
+
+/// Split a file ID into its components,
+/// checking that it can be recorded in a DICOMDIR
+fn file_id_components(file_id: &Path) -> Result<Vec<String>> {
+    let mut components = Vec::new();
+    for component in file_id.components() {
+        let Component::Normal(component) = component else {
+            return InvalidFileIdSnafu {
+                file_id,
+                reason: "file ID must be a relative path",
+            }
+            .fail()
+            .map_err(Error);
+        };
+        let component = component.to_str().unwrap_or_default();
+        ensure!(
+            (1..=8).contains(&component.len())
+                && component
+                    .bytes()
+                    .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_'),
+            InvalidFileIdSnafu {
+                file_id,
+                reason: "components must have 1 to 8 uppercase letters, digits, or underscores",
+            }
+        );
+        components.push(component.to_string());
+    }
+    ensure!(
+        (1..=8).contains(&components.len()),
+        InvalidFileIdSnafu {
+            file_id,
+            reason: "file ID must have 1 to 8 components",
+        }
+    );
+    Ok(components)
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+
+    use super::{Dicomdir, DicomdirBuilder, RecordType};
+    use crate::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+
+    fn instance(patient_id: &str, study: &str, series: &str, sop: &str) -> DefaultDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop),
+            DataElement::new(tags::STUDY_DATE, VR::DA, "20240101"),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, patient_id),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, study),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, series),
+            DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn build_and_read_dicomdir() {
+        let mut builder = DicomdirBuilder::new()
+            .file_set_id("TESTSET")
+            .sop_instance_uid("2.25.1");
+        builder
+            .add_file("A/IM1", &instance("P1", "1.1", "1.1.1", "1.1.1.1"))
+            .unwrap();
+        builder
+            .add_file("A/IM2", &instance("P1", "1.1", "1.1.1", "1.1.1.2"))
+            .unwrap();
+        builder
+            .add_file("A/IM3", &instance("P1", "1.1", "1.1.2", "1.1.2.1"))
+            .unwrap();
+        builder
+            .add_file("B/IM1", &instance("P2", "2.1", "2.1.1", "2.1.1.1"))
+            .unwrap();
+        let obj = builder.build().unwrap();
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::MEDIA_STORAGE_DIRECTORY_STORAGE
+        );
+
+        let mut data = Vec::new();
+        obj.write_all(&mut data).unwrap();
+
+        let dicomdir = Dicomdir::from_bytes(&data, "media").unwrap();
+        assert_eq!(dicomdir.file_set_id(), Some("TESTSET"));
+        assert_eq!(dicomdir.records().len(), 2);
+        let p1 = &dicomdir.records()[0];
+        assert_eq!(p1.record_type(), &RecordType::Patient);
+        assert_eq!(
+            p1.keys().get(tags::PATIENT_ID).unwrap().to_str().unwrap(),
+            "P1"
+        );
+        let study = &p1.children()[0];
+        assert_eq!(study.record_type(), &RecordType::Study);
+        assert_eq!(study.children().len(), 2);
+        assert_eq!(study.children()[0].children().len(), 2);
+
+        let image = &study.children()[0].children()[1];
+        assert_eq!(image.record_type(), &RecordType::Image);
+        assert_eq!(
+            image.referenced_sop_instance_uid().as_deref(),
+            Some("1.1.1.2")
+        );
+        assert_eq!(
+            dicomdir.resolve(image),
+            Some(std::path::Path::new("media/A/IM2").to_path_buf())
+        );
+
+        // 2 patients, 2 studies, 3 series, 4 images
+        assert_eq!(dicomdir.iter().count(), 11);
+        let files: Vec<_> = dicomdir.referenced_files().collect();
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[3], std::path::Path::new("media/B/IM1"));
+    }
+
+    #[test]
+    fn reject_nonconformant_file_ids() {
+        let mut builder = DicomdirBuilder::new();
+        let obj = instance("P1", "1.1", "1.1.1", "1.1.1.1");
+        assert!(builder.add_file("a/im1.dcm", &obj).is_err());
+        assert!(builder.add_file("/A/IM1", &obj).is_err());
+        assert!(builder.add_file("A/VERYLONGNAME", &obj).is_err());
+        assert!(builder.add_file("A/IM1", &obj).is_ok());
+
+        let mut obj = instance("P1", "1.1", "1.1.1", "1.1.1.1");
+        obj.remove_element(tags::SERIES_INSTANCE_UID);
+        assert!(builder.add_file("A/IM2", &obj).is_err());
+    }
+
+    #[test]
+    fn skip_records_not_in_use() {
+        let mut builder = DicomdirBuilder::new().sop_instance_uid("2.25.1");
+        builder
+            .add_file("IM1", &instance("P1", "1.1", "1.1.1", "1.1.1.1"))
+            .unwrap();
+        builder
+            .add_file("IM2", &instance("P2", "2.1", "2.1.1", "2.1.1.1"))
+            .unwrap();
+        let mut obj = builder.build().unwrap();
+
+        // mark the first patient's record as inactive
+        obj.update_value(tags::DIRECTORY_RECORD_SEQUENCE, |value| {
+            let items = value.items_mut().unwrap();
+            items[0].put(DataElement::new(
+                tags::RECORD_IN_USE_FLAG,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ));
+        });
+        let mut data = Vec::new();
+        obj.write_all(&mut data).unwrap();
+
+        let dicomdir = Dicomdir::from_bytes(&data, "").unwrap();
+        assert_eq!(dicomdir.records().len(), 1);
+        assert_eq!(
+            dicomdir.records()[0]
+                .keys()
+                .get(tags::PATIENT_ID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "P2"
+        );
+    }
+}
@@ -1,12 +1,15 @@
-use dicom_core::{DataDictionary, Tag};
-use dicom_dictionary_std::StandardDataDictionary;
-use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom_core::{DataDictionary, Tag, VR};
+use dicom_dictionary_std::{StandardDataDictionary, tags, uids};
+use dicom_encoding::Endianness;
+use dicom_encoding::transfer_syntax::{TransferSyntax, TransferSyntaxIndex};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 
 // re-export public options from dicom_parser
 pub use dicom_parser::dataset::read::OddLengthStrategy;
 pub use dicom_parser::stateful::decode::CharacterSetOverride;
 
+use dicom_parser::dataset::read::SkipElements;
+
 use crate::{DefaultDicomObject, ReadError};
 use std::io::Read;
 use std::path::Path;
@@ -28,6 +31,9 @@ where
 ///
 /// This function assumes the standard file encoding structure: 128-byte
 /// preamble, file meta group, and the rest of the data set.
+///
+/// With the Cargo feature `gzip`,
+/// gzip-compressed files are also detected and decompressed while reading.
 pub fn open_file<P>(path: P) -> Result<DefaultDicomObject>
 where
     P: AsRef<Path>,
@@ -35,6 +41,183 @@ where
     OpenFileOptions::new().open_file(path)
 }
 
+/// The basic encoding of a data set:
+/// whether value representations are explicit, and the byte order.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct DataSetEncoding {
+    /// Whether the value representation of each element is explicit
+    pub explicit_vr: bool,
+    /// The byte order of the data set
+    pub endianness: Endianness,
+}
+
+impl DataSetEncoding {
+    /// The encoding of _Implicit VR Little Endian_.
+    pub const IMPLICIT_VR_LITTLE_ENDIAN: Self = DataSetEncoding {
+        explicit_vr: false,
+        endianness: Endianness::Little,
+    };
+    /// The encoding of _Explicit VR Little Endian_.
+    pub const EXPLICIT_VR_LITTLE_ENDIAN: Self = DataSetEncoding {
+        explicit_vr: true,
+        endianness: Endianness::Little,
+    };
+    /// The encoding of _Explicit VR Big Endian_.
+    pub const EXPLICIT_VR_BIG_ENDIAN: Self = DataSetEncoding {
+        explicit_vr: true,
+        endianness: Endianness::Big,
+    };
+
+    /// Obtain the encoding of the data set in the given transfer syntax.
+    pub fn of(ts: &TransferSyntax) -> Self {
+        DataSetEncoding {
+            explicit_vr: ts.is_explicit_vr(),
+            endianness: ts.endianness(),
+        }
+    }
+
+    /// The UID of the native uncompressed transfer syntax with this encoding,
+    /// or `None` for implicit VR big endian,
+    /// which is not a standard encoding.
+    pub fn native_transfer_syntax(&self) -> Option<&'static str> {
+        match (self.explicit_vr, self.endianness) {
+            (false, Endianness::Little) => Some(uids::IMPLICIT_VR_LITTLE_ENDIAN),
+            (true, Endianness::Little) => Some(uids::EXPLICIT_VR_LITTLE_ENDIAN),
+            // Explicit VR Big Endian (retired)
+            (true, Endianness::Big) => Some("1.2.840.10008.1.2.2"),
+            (false, Endianness::Big) => None,
+        }
+    }
+}
+
+/// Detect the encoding of a data set from its first bytes,
+/// without relying on a file meta group.
+///
+/// The bytes must start at the first element header of the data set,
+/// such as right after the file meta group.
+/// The headers of the first elements are read
+/// as in each of the standard encodings
+/// (implicit VR little endian, explicit VR little endian,
+/// and explicit VR big endian),
+/// and the encoding which reads the most elements
+/// with plausible value representations, lengths, and ascending tags
+/// is chosen.
+/// A few hundred bytes are usually enough to tell them apart.
+///
+/// Returns `None` if no element could be read in any encoding.
+///
+/// # Example
+///
+/// ```
+/// # use dicom_object::file::{DataSetEncoding, detect_encoding};
+/// // (0010,0010) PN, 8 bytes: "Doe^John"
+/// let data = b"\x10\x00\x10\x00PN\x08\x00Doe^John";
+/// assert_eq!(
+///     detect_encoding(data),
+///     Some(DataSetEncoding::EXPLICIT_VR_LITTLE_ENDIAN),
+/// );
+/// ```
+pub fn detect_encoding(data: &[u8]) -> Option<DataSetEncoding> {
+    // on ties, prefer explicit VR, of which headers are more constrained
+    let mut best = None;
+    let mut best_count = 0;
+    for encoding in [
+        DataSetEncoding::EXPLICIT_VR_LITTLE_ENDIAN,
+        DataSetEncoding::IMPLICIT_VR_LITTLE_ENDIAN,
+        DataSetEncoding::EXPLICIT_VR_BIG_ENDIAN,
+    ] {
+        let count = count_plausible_elements(data, encoding);
+        if count > best_count {
+            best = Some(encoding);
+            best_count = count;
+        }
+    }
+    best
+}
+
+/// Count the consecutive element headers at the start of a data set
+/// which are plausible in the given encoding.
+fn count_plausible_elements(data: &[u8], encoding: DataSetEncoding) -> usize {
+    /// the maximum number of elements to look into
+    const MAX_ELEMENTS: usize = 16;
+
+    let u16_at = |pos: usize| {
+        let bytes = [data[pos], data[pos + 1]];
+        match encoding.endianness {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        }
+    };
+    let u32_at = |pos: usize| {
+        let bytes = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+        match encoding.endianness {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        }
+    };
+
+    let mut count = 0;
+    let mut pos = 0;
+    let mut last_tag = None;
+    while count < MAX_ELEMENTS && pos + 8 <= data.len() {
+        let tag = Tag(u16_at(pos), u16_at(pos + 2));
+        if tag.group() == 0xFFFE || last_tag.is_some_and(|last| tag <= last) {
+            break;
+        }
+        let (header_len, len) = if encoding.explicit_vr {
+            let Some(vr) = VR::from_binary([data[pos + 4], data[pos + 5]]) else {
+                break;
+            };
+            if has_short_length(vr) {
+                (8, u32::from(u16_at(pos + 6)))
+            } else if pos + 12 <= data.len() {
+                (12, u32_at(pos + 8))
+            } else {
+                break;
+            }
+        } else {
+            (8, u32_at(pos + 4))
+        };
+        count += 1;
+        if len == u32::MAX {
+            // cannot skip elements of undefined length
+            break;
+        }
+        last_tag = Some(tag);
+        pos = pos.saturating_add(header_len + len as usize);
+    }
+    count
+}
+
+/// Whether the value length of an element with this VR
+/// is encoded in 16 bits in explicit VR.
+fn has_short_length(vr: VR) -> bool {
+    matches!(
+        vr,
+        VR::AE
+            | VR::AS
+            | VR::AT
+            | VR::CS
+            | VR::DA
+            | VR::DS
+            | VR::DT
+            | VR::FL
+            | VR::FD
+            | VR::IS
+            | VR::LO
+            | VR::LT
+            | VR::PN
+            | VR::SH
+            | VR::SL
+            | VR::SS
+            | VR::ST
+            | VR::TM
+            | VR::UI
+            | VR::UL
+            | VR::US
+    )
+}
+
 /// A builder type for opening a DICOM file with additional options.
 ///
 /// This builder exposes additional properties
@@ -65,6 +248,10 @@ pub struct OpenFileOptions<D = StandardDataDictionary, T = TransferSyntaxRegistr
     read_preamble: ReadPreamble,
     odd_length: OddLengthStrategy,
     charset_override: CharacterSetOverride,
+    skip: SkipElements,
+    skip_pixel_data: bool,
+    max_bytes: Option<u64>,
+    lenient: bool,
 }
 
 impl OpenFileOptions {
@@ -101,12 +288,26 @@ impl<D, T> OpenFileOptions<D, T> {
         self
     }
 
+    /// Set the operation to stop reading the root data set
+    /// once the given number of data set bytes is reached.
+    ///
+    /// The reading process ends before the first element in the root data set
+    /// which does not end within the first `max_bytes` bytes
+    /// after the file meta group.
+    /// Elements of undefined length are only checked against their start,
+    /// so these are still read in full once started.
+    pub fn stop_after_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
     /// Set the operation to read all elements of the data set to the end.
     ///
     /// This is the default behavior.
     pub fn read_all(mut self) -> Self {
         self.read_until = None;
         self.read_to = None;
+        self.max_bytes = None;
         self
     }
 
@@ -122,6 +323,81 @@ impl<D, T> OpenFileOptions<D, T> {
         self
     }
 
+    /// Set the operation to skip data elements with any of the given tags.
+    ///
+    /// The values of these elements are passed over
+    /// without ever being read into memory,
+    /// and the elements will be absent from the output.
+    /// This applies at any level of the data set,
+    /// including inside sequence items.
+    ///
+    /// Can be called multiple times to skip more elements.
+    pub fn skip_tags(mut self, tags: &[Tag]) -> Self {
+        self.skip = self.skip.tags(tags);
+        self
+    }
+
+    /// Set the operation to skip all data elements
+    /// in any of the given groups,
+    /// such as `0x6000` for the first overlay plane.
+    ///
+    /// The values of these elements are passed over
+    /// without ever being read into memory,
+    /// and the elements will be absent from the output.
+    /// This applies at any level of the data set,
+    /// including inside sequence items.
+    ///
+    /// Can be called multiple times to skip more groups.
+    pub fn skip_groups(mut self, groups: &[u16]) -> Self {
+        self.skip = self.skip.groups(groups);
+        self
+    }
+
+    /// Set whether to skip the pixel data of the object
+    /// (_Pixel Data_, _Float Pixel Data_, and _Double Float Pixel Data_).
+    ///
+    /// Unlike [`read_until`](Self::read_until),
+    /// elements after the pixel data are still read.
+    pub fn skip_pixel_data(mut self, skip: bool) -> Self {
+        self.skip_pixel_data = skip;
+        self
+    }
+
+    /// Set whether to recover from common violations of the standard
+    /// instead of failing.
+    ///
+    /// In lenient mode:
+    ///
+    /// - a missing preamble is tolerated
+    ///   even if [`ReadPreamble::Always`] was requested;
+    /// - a file without the magic code and file meta group
+    ///   is read as a bare data set,
+    ///   with a file meta group built from its contents;
+    /// - a data set encoded with implicit VR
+    ///   when the transfer syntax declares explicit VR (or vice versa),
+    ///   or in another byte order,
+    ///   is read with the encoding [detected](detect_encoding)
+    ///   from its first elements;
+    /// - a data set which cannot be read to the end,
+    ///   such as a truncated file,
+    ///   keeps all elements read up to the failure,
+    ///   discarding the element which could not be read in full.
+    ///
+    /// Each of these, along with elements of odd length,
+    /// non-sequence elements of undefined length
+    /// (which are read as sequences),
+    /// and elements appearing more than once in the same data set
+    /// (of which the last one is kept),
+    /// is recorded as a [warning](ReadReport::warnings)
+    /// in the report of [`open_file_with_report`](Self::open_file_with_report)
+    /// and [`from_reader_with_report`](Self::from_reader_with_report).
+    ///
+    /// Lenient mode is disabled by default.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
     /// Set an override on how text values are decoded.
     pub fn charset_override(mut self, option: CharacterSetOverride) -> Self {
         self.charset_override = option;
@@ -141,6 +417,10 @@ impl<D, T> OpenFileOptions<D, T> {
             ts_index,
             odd_length: self.odd_length,
             charset_override: self.charset_override,
+            skip: self.skip,
+            skip_pixel_data: self.skip_pixel_data,
+            max_bytes: self.max_bytes,
+            lenient: self.lenient,
         }
     }
 
@@ -167,6 +447,10 @@ impl<D, T> OpenFileOptions<D, T> {
             ts_index: self.ts_index,
             odd_length: self.odd_length,
             charset_override: self.charset_override,
+            skip: self.skip,
+            skip_pixel_data: self.skip_pixel_data,
+            max_bytes: self.max_bytes,
+            lenient: self.lenient,
         }
     }
 
@@ -178,7 +462,22 @@ impl<D, T> OpenFileOptions<D, T> {
         D: Clone,
         T: TransferSyntaxIndex,
     {
-        DefaultDicomObject::open_file_with_all_options(
+        self.open_file_with_report(path).map(|(obj, _)| obj)
+    }
+
+    /// Open the file at the given path,
+    /// also reporting which parts of the data set were not read,
+    /// and which deviations from the standard were recovered from.
+    pub fn open_file_with_report<P>(self, path: P) -> Result<(DefaultDicomObject<D>, ReadReport)>
+    where
+        P: AsRef<Path>,
+        D: DataDictionary,
+        D: Clone,
+        T: TransferSyntaxIndex,
+    {
+        let mut report = ReadReport::default();
+        let skip = self.skip_elements();
+        let obj = DefaultDicomObject::open_file_with_all_options(
             path,
             self.data_dictionary,
             self.ts_index,
@@ -187,7 +486,12 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_preamble,
             self.odd_length,
             self.charset_override,
-        )
+            skip,
+            self.max_bytes,
+            self.lenient,
+            &mut report,
+        )?;
+        Ok((obj, report))
     }
 
     /// Obtain a DICOM object by reading from a byte source.
@@ -202,7 +506,26 @@ impl<D, T> OpenFileOptions<D, T> {
         D: Clone,
         T: TransferSyntaxIndex,
     {
-        DefaultDicomObject::from_reader_with_all_options(
+        self.from_reader_with_report(from).map(|(obj, _)| obj)
+    }
+
+    /// Obtain a DICOM object by reading from a byte source,
+    /// also reporting which parts of the data set were not read,
+    /// and which deviations from the standard were recovered from.
+    ///
+    /// This method assumes
+    /// the standard file encoding structure without the preamble:
+    /// file meta group, followed by the rest of the data set.
+    pub fn from_reader_with_report<R>(self, from: R) -> Result<(DefaultDicomObject<D>, ReadReport)>
+    where
+        R: Read,
+        D: DataDictionary,
+        D: Clone,
+        T: TransferSyntaxIndex,
+    {
+        let mut report = ReadReport::default();
+        let skip = self.skip_elements();
+        let obj = DefaultDicomObject::from_reader_with_all_options(
             from,
             self.data_dictionary,
             self.ts_index,
@@ -211,7 +534,121 @@ impl<D, T> OpenFileOptions<D, T> {
             self.read_preamble,
             self.odd_length,
             self.charset_override,
-        )
+            skip,
+            self.max_bytes,
+            self.lenient,
+            &mut report,
+        )?;
+        Ok((obj, report))
+    }
+
+    /// The full set of data elements to skip
+    fn skip_elements(&self) -> SkipElements {
+        if self.skip_pixel_data {
+            self.skip.clone().tags(&[
+                tags::PIXEL_DATA,
+                tags::FLOAT_PIXEL_DATA,
+                tags::DOUBLE_FLOAT_PIXEL_DATA,
+            ])
+        } else {
+            self.skip.clone()
+        }
+    }
+}
+
+/// A report of the parts of a DICOM data set
+/// which were not read into the resulting object,
+/// as a consequence of the options in [`OpenFileOptions`],
+/// and of the problems found while reading in lenient mode.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct ReadReport {
+    /// The tags of the data elements which were skipped,
+    /// at any level of the data set,
+    /// in order of first appearance.
+    pub skipped: Vec<Tag>,
+    /// The tag of the element in the root data set
+    /// at which reading stopped, if it stopped early.
+    /// This element and all elements after it were not read.
+    pub stopped_at: Option<Tag>,
+    /// The deviations from the standard which were recovered from
+    /// in [lenient](OpenFileOptions::lenient) mode,
+    /// in order of appearance.
+    /// Always empty if lenient mode is disabled.
+    pub warnings: Vec<ReadWarning>,
+}
+
+impl ReadReport {
+    /// Check whether the data set was read in full.
+    ///
+    /// This is not the case if reading stopped early
+    /// due to an [unexpected end](ReadWarning::UnexpectedEnd).
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty()
+            && self.stopped_at.is_none()
+            && !self
+                .warnings
+                .iter()
+                .any(|w| matches!(w, ReadWarning::UnexpectedEnd { .. }))
+    }
+}
+
+/// A deviation from the DICOM standard
+/// which was recovered from while reading in
+/// [lenient](OpenFileOptions::lenient) mode.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ReadWarning {
+    /// The file does not start with the 128-byte preamble.
+    MissingPreamble,
+    /// The file does not have the magic code nor the file meta group,
+    /// so the data set was read with the given transfer syntax.
+    MissingFileMetaGroup { transfer_syntax: String },
+    /// The data set is not encoded
+    /// as declared by the transfer syntax in the file meta group,
+    /// so it was read with another transfer syntax.
+    TransferSyntaxMismatch { declared: String, used: String },
+    /// The element has an odd value length.
+    OddLength { tag: Tag, len: u32 },
+    /// The element is not a sequence but has an undefined length,
+    /// so it was read as a sequence.
+    UndefinedLength { tag: Tag },
+    /// The element appears more than once in the same data set.
+    /// Only the last occurrence was kept.
+    DuplicateElement { tag: Tag },
+    /// The data set could not be read to the end,
+    /// so it ended before the element with the given tag
+    /// (or at the point of failure, if no element was being read).
+    UnexpectedEnd { tag: Option<Tag>, reason: String },
+}
+
+impl std::fmt::Display for ReadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadWarning::MissingPreamble => f.write_str("missing preamble"),
+            ReadWarning::MissingFileMetaGroup { transfer_syntax } => write!(
+                f,
+                "missing file meta group, data set read as {transfer_syntax}"
+            ),
+            ReadWarning::TransferSyntaxMismatch { declared, used } => write!(
+                f,
+                "data set not encoded as declared transfer syntax {declared}, read as {used}"
+            ),
+            ReadWarning::OddLength { tag, len } => {
+                write!(f, "element {tag} has odd length {len}")
+            }
+            ReadWarning::UndefinedLength { tag } => {
+                write!(f, "non-sequence element {tag} has undefined length")
+            }
+            ReadWarning::DuplicateElement { tag } => write!(f, "duplicate element {tag}"),
+            ReadWarning::UnexpectedEnd {
+                tag: Some(tag),
+                reason,
+            } => write!(f, "data set ended early at element {tag}: {reason}"),
+            ReadWarning::UnexpectedEnd { tag: None, reason } => {
+                write!(f, "data set ended early: {reason}")
+            }
+        }
     }
 }
 
@@ -232,3 +669,52 @@ pub enum ReadPreamble {
     /// thus assuming that the original source always has it.
     Always,
 }
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_encoding::TransferSyntaxIndex;
+    use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+
+    use super::{DataSetEncoding, detect_encoding};
+    use crate::InMemDicomObject;
+
+    #[test]
+    fn detect_encoding_of_data_sets() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 100"),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OW,
+                PrimitiveValue::U16(vec![0x0102; 8].into()),
+            ),
+        ]);
+        for (uid, encoding) in [
+            (
+                uids::IMPLICIT_VR_LITTLE_ENDIAN,
+                DataSetEncoding::IMPLICIT_VR_LITTLE_ENDIAN,
+            ),
+            (
+                uids::EXPLICIT_VR_LITTLE_ENDIAN,
+                DataSetEncoding::EXPLICIT_VR_LITTLE_ENDIAN,
+            ),
+            (
+                "1.2.840.10008.1.2.2",
+                DataSetEncoding::EXPLICIT_VR_BIG_ENDIAN,
+            ),
+        ] {
+            let ts = TransferSyntaxRegistry.get(uid).unwrap();
+            let mut data = Vec::new();
+            obj.write_dataset_with_ts(&mut data, ts).unwrap();
+            assert_eq!(detect_encoding(&data), Some(encoding), "{uid}");
+            assert_eq!(DataSetEncoding::of(ts), encoding);
+            assert_eq!(encoding.native_transfer_syntax(), Some(uid));
+        }
+
+        assert_eq!(detect_encoding(b"DICM"), None);
+    }
+}
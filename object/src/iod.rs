@@ -0,0 +1,832 @@
+//! Builders of new objects of common information object definitions.
+//!
+//! Each builder creates a new DICOM object of one IOD,
+//! with the mandatory modules already populated:
+//! new UIDs are generated for the instance and its series
+//! (and its study, unless one is given or copied from a template),
+//! creation dates and times are set to the current time,
+//! type 2 attributes which are not known are added empty,
+//! and the _Image Pixel_ module is filled in from an [`ImagePixels`],
+//! after checking that its attributes agree with the pixel buffer
+//! and with the constraints of the IOD.
+//!
+//! - [`SecondaryCaptureBuilder`] creates _Secondary Capture Image_ objects,
+//!   for any grayscale or color image.
+//! - [`CtImageBuilder`] creates _CT Image_ objects,
+//!   with the _Frame of Reference_, _Image Plane_ and _CT Image_ modules.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::iod::{CtImageBuilder, ImagePixels, ImagePlane};
+//! use dicom_object::open_file;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let study = open_file("study.dcm")?;
+//! // Hounsfield units offset by 1024
+//! let samples = vec![1024_u16; 512 * 512];
+//! let pixels = ImagePixels::monochrome16(512, 512, samples).with_bits_stored(12);
+//! let plane = ImagePlane::new([-250., -250., 0.], [1., 0., 0., 0., 1., 0.], [0.98, 0.98])
+//!     .with_slice_thickness(2.5);
+//! let obj = CtImageBuilder::new(pixels, plane)
+//!     .with_template(&study)
+//!     .with_rescale(1., -1024.)
+//!     .with_instance_number(1)
+//!     .build()?;
+//! obj.write_to_file("ct.dcm")?;
+//! # Ok(())
+//! # }
+//! ```
+use dicom_core::value::decimal::DecimalString;
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR, chrono, uid::UidGenerator};
+use dicom_dictionary_std::{tags, uids};
+use snafu::{ResultExt, Snafu, ensure};
+
+use crate::mem::InMemElement;
+use crate::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+
+/// An error which may occur when building a new object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for the IOD builders
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Image has no rows or no columns
+    EmptyImage,
+    #[snafu(display(
+        "Pixel data has {found} samples, but {rows}x{columns} pixels of {samples_per_pixel} samples require {expected}"
+    ))]
+    PixelDataLength {
+        rows: u16,
+        columns: u16,
+        samples_per_pixel: u16,
+        expected: usize,
+        found: usize,
+    },
+    /// Bits Stored {bits_stored} is not within 1 and Bits Allocated {bits_allocated}
+    InvalidBitsStored {
+        bits_stored: u16,
+        bits_allocated: u16,
+    },
+    #[snafu(display(
+        "Photometric interpretation {photometric_interpretation} does not apply to {samples_per_pixel} samples per pixel"
+    ))]
+    InvalidPhotometricInterpretation {
+        photometric_interpretation: String,
+        samples_per_pixel: u16,
+    },
+    /// The {iod} IOD requires {requirement}
+    UnsupportedPixels {
+        iod: &'static str,
+        requirement: &'static str,
+    },
+    /// Invalid number for attribute {tag}
+    InvalidNumber {
+        tag: Tag,
+        source: dicom_core::value::decimal::Error,
+    },
+    /// Could not create the file meta group
+    CreateMeta {
+        #[snafu(source(from(crate::WithMetaError, Box::new)))]
+        source: Box<crate::WithMetaError>,
+    },
+}
+
+/// Alias for the result of building a new object.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The attributes of the _Patient_ and _General Study_ modules
+/// (plus the character set they are encoded in)
+/// which are copied from a template object.
+const CONTEXT_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::ISSUER_OF_PATIENT_ID,
+    tags::PATIENT_BIRTH_DATE,
+    tags::PATIENT_SEX,
+    tags::PATIENT_AGE,
+    tags::STUDY_INSTANCE_UID,
+    tags::STUDY_DATE,
+    tags::STUDY_TIME,
+    tags::REFERRING_PHYSICIAN_NAME,
+    tags::STUDY_ID,
+    tags::ACCESSION_NUMBER,
+    tags::STUDY_DESCRIPTION,
+];
+
+/// The type 2 attributes shared by the image IODs,
+/// which are added empty if not known.
+const TYPE2_TAGS: &[(Tag, VR)] = &[
+    (tags::PATIENT_NAME, VR::PN),
+    (tags::PATIENT_ID, VR::LO),
+    (tags::PATIENT_BIRTH_DATE, VR::DA),
+    (tags::PATIENT_SEX, VR::CS),
+    (tags::STUDY_DATE, VR::DA),
+    (tags::STUDY_TIME, VR::TM),
+    (tags::REFERRING_PHYSICIAN_NAME, VR::PN),
+    (tags::STUDY_ID, VR::SH),
+    (tags::ACCESSION_NUMBER, VR::SH),
+    (tags::SERIES_NUMBER, VR::IS),
+    (tags::INSTANCE_NUMBER, VR::IS),
+];
+
+/// The pixel samples of a single frame image
+/// and the attributes of the _Image Pixel_ module describing them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePixels {
+    rows: u16,
+    columns: u16,
+    samples_per_pixel: u16,
+    photometric_interpretation: String,
+    bits_allocated: u16,
+    bits_stored: u16,
+    pixel_representation: u16,
+    data: PrimitiveValue,
+}
+
+impl ImagePixels {
+    /// Grayscale pixels of 8 bits (`MONOCHROME2`),
+    /// in row-major order.
+    pub fn monochrome8(rows: u16, columns: u16, samples: Vec<u8>) -> Self {
+        Self::new(
+            rows,
+            columns,
+            1,
+            "MONOCHROME2",
+            8,
+            0,
+            PrimitiveValue::U8(samples.into()),
+        )
+    }
+
+    /// Grayscale pixels of 16 bits (`MONOCHROME2`),
+    /// in row-major order.
+    pub fn monochrome16(rows: u16, columns: u16, samples: Vec<u16>) -> Self {
+        Self::new(
+            rows,
+            columns,
+            1,
+            "MONOCHROME2",
+            16,
+            0,
+            PrimitiveValue::U16(samples.into()),
+        )
+    }
+
+    /// Grayscale pixels of 16 bits in two's complement (`MONOCHROME2`),
+    /// in row-major order.
+    pub fn signed16(rows: u16, columns: u16, samples: Vec<i16>) -> Self {
+        let samples = samples.into_iter().map(|v| v as u16).collect();
+        Self::new(
+            rows,
+            columns,
+            1,
+            "MONOCHROME2",
+            16,
+            1,
+            PrimitiveValue::U16(samples),
+        )
+    }
+
+    /// Color pixels of 8 bits per sample (`RGB`),
+    /// in row-major order with the samples of each pixel interleaved.
+    pub fn rgb8(rows: u16, columns: u16, samples: Vec<u8>) -> Self {
+        Self::new(
+            rows,
+            columns,
+            3,
+            "RGB",
+            8,
+            0,
+            PrimitiveValue::U8(samples.into()),
+        )
+    }
+
+    /// Color pixels of 16 bits per sample (`RGB`),
+    /// in row-major order with the samples of each pixel interleaved.
+    pub fn rgb16(rows: u16, columns: u16, samples: Vec<u16>) -> Self {
+        Self::new(
+            rows,
+            columns,
+            3,
+            "RGB",
+            16,
+            0,
+            PrimitiveValue::U16(samples.into()),
+        )
+    }
+
+    fn new(
+        rows: u16,
+        columns: u16,
+        samples_per_pixel: u16,
+        photometric_interpretation: &str,
+        bits: u16,
+        pixel_representation: u16,
+        data: PrimitiveValue,
+    ) -> Self {
+        ImagePixels {
+            rows,
+            columns,
+            samples_per_pixel,
+            photometric_interpretation: photometric_interpretation.to_string(),
+            bits_allocated: bits,
+            bits_stored: bits,
+            pixel_representation,
+            data,
+        }
+    }
+
+    /// Declare how many bits of each sample are used,
+    /// such as 12 for most CT scanners.
+    pub fn with_bits_stored(mut self, bits_stored: u16) -> Self {
+        self.bits_stored = bits_stored;
+        self
+    }
+
+    /// Override the photometric interpretation,
+    /// such as `MONOCHROME1` for grayscale images
+    /// in which the minimum sample value is displayed as white.
+    pub fn with_photometric_interpretation(mut self, value: impl Into<String>) -> Self {
+        self.photometric_interpretation = value.into();
+        self
+    }
+
+    /// Check that the attributes agree with the pixel buffer.
+    fn check(&self) -> Result<()> {
+        ensure!(self.rows > 0 && self.columns > 0, EmptyImageSnafu);
+        let expected = self.rows as usize * self.columns as usize * self.samples_per_pixel as usize;
+        ensure!(
+            self.data.multiplicity() as usize == expected,
+            PixelDataLengthSnafu {
+                rows: self.rows,
+                columns: self.columns,
+                samples_per_pixel: self.samples_per_pixel,
+                expected,
+                found: self.data.multiplicity() as usize,
+            }
+        );
+        ensure!(
+            (1..=self.bits_allocated).contains(&self.bits_stored),
+            InvalidBitsStoredSnafu {
+                bits_stored: self.bits_stored,
+                bits_allocated: self.bits_allocated,
+            }
+        );
+        let monochrome = matches!(
+            self.photometric_interpretation.as_str(),
+            "MONOCHROME1" | "MONOCHROME2"
+        );
+        ensure!(
+            monochrome == (self.samples_per_pixel == 1),
+            InvalidPhotometricInterpretationSnafu {
+                photometric_interpretation: &self.photometric_interpretation,
+                samples_per_pixel: self.samples_per_pixel,
+            }
+        );
+        Ok(())
+    }
+
+    /// Set the attributes of the _Image Pixel_ module,
+    /// including the pixel data.
+    fn put_into(&self, obj: &mut InMemDicomObject) {
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            PrimitiveValue::from(self.photometric_interpretation.as_str()),
+        ));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, self.samples_per_pixel));
+        if self.samples_per_pixel > 1 {
+            obj.put(us(tags::PLANAR_CONFIGURATION, 0));
+        }
+        obj.put(us(tags::ROWS, self.rows));
+        obj.put(us(tags::COLUMNS, self.columns));
+        obj.put(us(tags::BITS_ALLOCATED, self.bits_allocated));
+        obj.put(us(tags::BITS_STORED, self.bits_stored));
+        obj.put(us(tags::HIGH_BIT, self.bits_stored - 1));
+        obj.put(us(tags::PIXEL_REPRESENTATION, self.pixel_representation));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            if self.bits_allocated == 8 {
+                VR::OB
+            } else {
+                VR::OW
+            },
+            self.data.clone(),
+        ));
+    }
+}
+
+/// The position and orientation of an image in the patient,
+/// as in the _Image Plane_ module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePlane {
+    /// The _Image Position (Patient)_,
+    /// the coordinates in millimeters of the center of the first pixel
+    pub position: [f64; 3],
+    /// The _Image Orientation (Patient)_,
+    /// the direction cosines of the first row and of the first column
+    pub orientation: [f64; 6],
+    /// The _Pixel Spacing_ in millimeters,
+    /// between rows and then between columns
+    pub pixel_spacing: [f64; 2],
+    /// The _Slice Thickness_ in millimeters, if known
+    pub slice_thickness: Option<f64>,
+}
+
+impl ImagePlane {
+    /// Describe the plane of an image.
+    pub fn new(position: [f64; 3], orientation: [f64; 6], pixel_spacing: [f64; 2]) -> Self {
+        ImagePlane {
+            position,
+            orientation,
+            pixel_spacing,
+            slice_thickness: None,
+        }
+    }
+
+    /// Set the slice thickness.
+    pub fn with_slice_thickness(mut self, slice_thickness: f64) -> Self {
+        self.slice_thickness = Some(slice_thickness);
+        self
+    }
+
+    /// Set the attributes of the _Image Plane_ module.
+    fn put_into(&self, obj: &mut InMemDicomObject) -> Result<()> {
+        obj.put(ds(tags::IMAGE_POSITION_PATIENT, &self.position)?);
+        obj.put(ds(tags::IMAGE_ORIENTATION_PATIENT, &self.orientation)?);
+        obj.put(ds(tags::PIXEL_SPACING, &self.pixel_spacing)?);
+        obj.put(match self.slice_thickness {
+            Some(thickness) => ds(tags::SLICE_THICKNESS, &[thickness])?,
+            None => DataElement::new(tags::SLICE_THICKNESS, VR::DS, PrimitiveValue::Empty),
+        });
+        Ok(())
+    }
+}
+
+/// The identification and context of a new object,
+/// common to all builders.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Common {
+    sop_instance_uid: Option<String>,
+    series_instance_uid: Option<String>,
+    study_instance_uid: Option<String>,
+    uid_generator: UidGenerator,
+    series_number: Option<i32>,
+    instance_number: Option<i32>,
+    series_description: Option<String>,
+    /// the patient and study attributes copied from a template object
+    context: Vec<InMemElement>,
+    /// other attributes to put into the object
+    elements: Vec<InMemElement>,
+}
+
+impl Common {
+    /// Create the object with the modules shared by all image IODs:
+    /// _Patient_, _General Study_, _General Series_, _General Image_
+    /// and _SOP Common_.
+    fn start(&self, sop_class_uid: &str, modality: &str, image_type: &str) -> InMemDicomObject {
+        let mut obj = InMemDicomObject::new_empty();
+
+        for elem in &self.context {
+            obj.put(elem.clone());
+        }
+        for (tag, vr) in TYPE2_TAGS {
+            if obj.get(*tag).is_none() {
+                obj.put(DataElement::new(*tag, *vr, PrimitiveValue::Empty));
+            }
+        }
+
+        let study_instance_uid = match &self.study_instance_uid {
+            Some(uid) => uid.clone(),
+            None => obj
+                .get(tags::STUDY_INSTANCE_UID)
+                .and_then(|e| e.to_str().ok())
+                .map(|uid| uid.to_string())
+                .unwrap_or_else(|| self.uid_generator.generate()),
+        };
+        let series_instance_uid = self
+            .series_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+        let sop_instance_uid = self
+            .sop_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+
+        let now = chrono::Local::now();
+        let date = now.format("%Y%m%d").to_string();
+        let time = now.format("%H%M%S").to_string();
+
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+        for elem in [
+            // SOP Common
+            str_elem(tags::SOP_CLASS_UID, VR::UI, sop_class_uid),
+            str_elem(tags::SOP_INSTANCE_UID, VR::UI, &sop_instance_uid),
+            str_elem(tags::INSTANCE_CREATION_DATE, VR::DA, &date),
+            str_elem(tags::INSTANCE_CREATION_TIME, VR::TM, &time),
+            // General Study
+            str_elem(tags::STUDY_INSTANCE_UID, VR::UI, &study_instance_uid),
+            // General Series
+            str_elem(tags::SERIES_INSTANCE_UID, VR::UI, &series_instance_uid),
+            str_elem(tags::MODALITY, VR::CS, modality),
+            // General Image
+            str_elem(tags::IMAGE_TYPE, VR::CS, image_type),
+            str_elem(tags::CONTENT_DATE, VR::DA, &date),
+            str_elem(tags::CONTENT_TIME, VR::TM, &time),
+        ] {
+            obj.put(elem);
+        }
+        for (tag, value) in [
+            (tags::SERIES_NUMBER, self.series_number),
+            (tags::INSTANCE_NUMBER, self.instance_number),
+        ] {
+            if let Some(value) = value {
+                obj.put(DataElement::new(
+                    tag,
+                    VR::IS,
+                    PrimitiveValue::from(value.to_string()),
+                ));
+            }
+        }
+        if let Some(description) = &self.series_description {
+            obj.put(str_elem(tags::SERIES_DESCRIPTION, VR::LO, description));
+        }
+        obj
+    }
+
+    /// Put the extra attributes into the object
+    /// and add the file meta group.
+    fn finish(&self, mut obj: InMemDicomObject) -> Result<DefaultDicomObject> {
+        for elem in &self.elements {
+            obj.put(elem.clone());
+        }
+        Ok(obj
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .context(CreateMetaSnafu)?)
+    }
+}
+
+/// Implement the builder methods common to all builders,
+/// which hold a `common` field.
+macro_rules! common_builder_methods {
+    () => {
+        /// Copy the patient and study attributes
+        /// (_Patient Name_, _Patient ID_, _Study Instance UID_,
+        /// _Accession Number_, and so on)
+        /// from the given DICOM object,
+        /// so that the new object belongs to the same study.
+        pub fn with_template(mut self, template: &InMemDicomObject) -> Self {
+            self.common.context = CONTEXT_TAGS
+                .iter()
+                .filter_map(|tag| template.get(*tag).cloned())
+                .collect();
+            self
+        }
+
+        /// Set the SOP instance UID of the new object.
+        pub fn with_sop_instance_uid(mut self, uid: impl Into<String>) -> Self {
+            self.common.sop_instance_uid = Some(uid.into());
+            self
+        }
+
+        /// Set the series instance UID of the new object.
+        pub fn with_series_instance_uid(mut self, uid: impl Into<String>) -> Self {
+            self.common.series_instance_uid = Some(uid.into());
+            self
+        }
+
+        /// Set the study instance UID of the new object,
+        /// overriding the one in the template.
+        pub fn with_study_instance_uid(mut self, uid: impl Into<String>) -> Self {
+            self.common.study_instance_uid = Some(uid.into());
+            self
+        }
+
+        /// Set the generator of the UIDs which are not given,
+        /// such as one under an organization root.
+        pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+            self.common.uid_generator = uid_generator;
+            self
+        }
+
+        /// Set the series number.
+        pub fn with_series_number(mut self, number: i32) -> Self {
+            self.common.series_number = Some(number);
+            self
+        }
+
+        /// Set the instance number.
+        pub fn with_instance_number(mut self, number: i32) -> Self {
+            self.common.instance_number = Some(number);
+            self
+        }
+
+        /// Set the series description.
+        pub fn with_series_description(mut self, description: impl Into<String>) -> Self {
+            self.common.series_description = Some(description.into());
+            self
+        }
+
+        /// Put any other attribute into the new object,
+        /// replacing the one set by the builder, if any.
+        pub fn with_element(mut self, elem: InMemElement) -> Self {
+            self.common.elements.push(elem);
+            self
+        }
+    };
+}
+
+/// A builder of new [Secondary Capture Image][1] objects.
+///
+/// [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.8.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecondaryCaptureBuilder {
+    pixels: ImagePixels,
+    modality: String,
+    conversion_type: String,
+    common: Common,
+}
+
+impl SecondaryCaptureBuilder {
+    /// Create a builder of a secondary capture of the given pixels,
+    /// with the `OT` modality and the `WSD` (workstation) conversion type.
+    pub fn new(pixels: ImagePixels) -> Self {
+        SecondaryCaptureBuilder {
+            pixels,
+            modality: "OT".to_string(),
+            conversion_type: "WSD".to_string(),
+            common: Common::default(),
+        }
+    }
+
+    /// Set the conversion type code
+    /// (e.g. `DI` for digitized images, `SI` for scanned images).
+    pub fn with_conversion_type(mut self, conversion_type: impl Into<String>) -> Self {
+        self.conversion_type = conversion_type.into();
+        self
+    }
+
+    /// Set the modality of the series (`OT` by default).
+    pub fn with_modality(mut self, modality: impl Into<String>) -> Self {
+        self.modality = modality.into();
+        self
+    }
+
+    common_builder_methods!();
+
+    /// Create the secondary capture object,
+    /// encoded in Explicit VR Little Endian.
+    pub fn build(&self) -> Result<DefaultDicomObject> {
+        self.pixels.check()?;
+
+        let mut obj = self.common.start(
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            &self.modality,
+            "DERIVED\\SECONDARY",
+        );
+        if obj.get(tags::PATIENT_ORIENTATION).is_none() {
+            obj.put(DataElement::new(
+                tags::PATIENT_ORIENTATION,
+                VR::CS,
+                PrimitiveValue::Empty,
+            ));
+        }
+        // SC Equipment
+        obj.put(DataElement::new(
+            tags::CONVERSION_TYPE,
+            VR::CS,
+            PrimitiveValue::from(self.conversion_type.as_str()),
+        ));
+        self.pixels.put_into(&mut obj);
+
+        self.common.finish(obj)
+    }
+}
+
+/// A builder of new [CT Image][1] objects.
+///
+/// Besides the modules of all images,
+/// the new object has a new frame of reference (unless one is given),
+/// the _Image Plane_ and the _CT Image_ modules.
+/// The pixels must be 16-bit grayscale samples
+/// of 12 to 16 bits stored.
+///
+/// [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.3.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct CtImageBuilder {
+    pixels: ImagePixels,
+    plane: ImagePlane,
+    frame_of_reference_uid: Option<String>,
+    image_type: String,
+    rescale: (f64, f64),
+    kvp: Option<f64>,
+    common: Common,
+}
+
+impl CtImageBuilder {
+    /// Create a builder of a CT image of the given pixels in the given plane,
+    /// of the `ORIGINAL\PRIMARY\AXIAL` type
+    /// and with no rescale (slope of 1 and intercept of 0).
+    pub fn new(pixels: ImagePixels, plane: ImagePlane) -> Self {
+        CtImageBuilder {
+            pixels,
+            plane,
+            frame_of_reference_uid: None,
+            image_type: "ORIGINAL\\PRIMARY\\AXIAL".to_string(),
+            rescale: (1., 0.),
+            kvp: None,
+            common: Common::default(),
+        }
+    }
+
+    /// Set the rescale slope and intercept
+    /// which convert the stored samples into Hounsfield units.
+    pub fn with_rescale(mut self, slope: f64, intercept: f64) -> Self {
+        self.rescale = (slope, intercept);
+        self
+    }
+
+    /// Set the frame of reference UID,
+    /// shared by the images of the same acquisition.
+    pub fn with_frame_of_reference_uid(mut self, uid: impl Into<String>) -> Self {
+        self.frame_of_reference_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the image type,
+    /// such as `DERIVED\SECONDARY\REFORMATTED`.
+    pub fn with_image_type(mut self, image_type: impl Into<String>) -> Self {
+        self.image_type = image_type.into();
+        self
+    }
+
+    /// Set the peak kilovoltage of the X-ray generator.
+    pub fn with_kvp(mut self, kvp: f64) -> Self {
+        self.kvp = Some(kvp);
+        self
+    }
+
+    common_builder_methods!();
+
+    /// Create the CT image object,
+    /// encoded in Explicit VR Little Endian.
+    pub fn build(&self) -> Result<DefaultDicomObject> {
+        self.pixels.check()?;
+        let requirement = if self.pixels.samples_per_pixel != 1 {
+            Some("grayscale pixels")
+        } else if self.pixels.bits_allocated != 16 {
+            Some("16 bits allocated")
+        } else if self.pixels.bits_stored < 12 {
+            Some("12 to 16 bits stored")
+        } else {
+            None
+        };
+        if let Some(requirement) = requirement {
+            return UnsupportedPixelsSnafu {
+                iod: "CT Image",
+                requirement,
+            }
+            .fail()
+            .map_err(Into::into);
+        }
+
+        let mut obj = self
+            .common
+            .start(uids::CT_IMAGE_STORAGE, "CT", &self.image_type);
+        // Frame of Reference
+        let frame_of_reference_uid = self
+            .frame_of_reference_uid
+            .clone()
+            .unwrap_or_else(|| self.common.uid_generator.generate());
+        obj.put(DataElement::new(
+            tags::FRAME_OF_REFERENCE_UID,
+            VR::UI,
+            PrimitiveValue::from(frame_of_reference_uid),
+        ));
+        // General Equipment and CT Image,
+        // which are type 2 unless known
+        for (tag, vr) in [
+            (tags::POSITION_REFERENCE_INDICATOR, VR::LO),
+            (tags::MANUFACTURER, VR::LO),
+            (tags::ACQUISITION_NUMBER, VR::IS),
+        ] {
+            obj.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+        }
+        obj.put(match self.kvp {
+            Some(kvp) => ds(tags::KVP, &[kvp])?,
+            None => DataElement::new(tags::KVP, VR::DS, PrimitiveValue::Empty),
+        });
+        let (slope, intercept) = self.rescale;
+        obj.put(ds(tags::RESCALE_SLOPE, &[slope])?);
+        obj.put(ds(tags::RESCALE_INTERCEPT, &[intercept])?);
+        self.plane.put_into(&mut obj)?;
+        self.pixels.put_into(&mut obj);
+
+        self.common.finish(obj)
+    }
+}
+
+/// Create a decimal string attribute from one or more numbers.
+fn ds(tag: Tag, values: &[f64]) -> Result<InMemElement> {
+    let values = values
+        .iter()
+        .map(|&value| DecimalString::from_f64(value).map(DecimalString::into_string))
+        .collect::<Result<_, _>>()
+        .context(InvalidNumberSnafu { tag })?;
+    Ok(DataElement::new(tag, VR::DS, PrimitiveValue::Strs(values)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_secondary_capture() {
+        let template = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::MODALITY, VR::CS, "US"),
+        ]);
+        let obj = SecondaryCaptureBuilder::new(ImagePixels::rgb8(2, 3, vec![0; 18]))
+            .with_template(&template)
+            .with_series_number(7)
+            .build()
+            .unwrap();
+
+        let str_of = |tag| obj.get(tag).unwrap().to_str().unwrap().to_string();
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        assert_eq!(
+            obj.meta().media_storage_sop_instance_uid(),
+            str_of(tags::SOP_INSTANCE_UID)
+        );
+        assert_eq!(str_of(tags::PATIENT_NAME), "Doe^John");
+        assert_eq!(str_of(tags::PATIENT_ID), "");
+        assert_eq!(str_of(tags::STUDY_INSTANCE_UID), "2.25.1");
+        assert_eq!(str_of(tags::MODALITY), "OT");
+        assert_eq!(str_of(tags::SERIES_NUMBER), "7");
+        assert_eq!(str_of(tags::PHOTOMETRIC_INTERPRETATION), "RGB");
+        assert_eq!(obj.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 2);
+        assert_eq!(obj.get(tags::COLUMNS).unwrap().to_int::<u16>().unwrap(), 3);
+        assert!(obj.get(tags::PLANAR_CONFIGURATION).is_some());
+        assert!(obj.get(tags::PATIENT_ORIENTATION).is_some());
+
+        // the buffer does not have the samples of all pixels
+        assert!(
+            SecondaryCaptureBuilder::new(ImagePixels::rgb8(2, 3, vec![0; 6]))
+                .build()
+                .is_err()
+        );
+        assert!(
+            SecondaryCaptureBuilder::new(
+                ImagePixels::monochrome8(1, 1, vec![0]).with_photometric_interpretation("RGB")
+            )
+            .build()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn build_ct_image() {
+        let pixels = ImagePixels::signed16(2, 2, vec![-1000, 0, 40, 1000]).with_bits_stored(12);
+        let plane = ImagePlane::new([-10., -10., 5.5], [1., 0., 0., 0., 1., 0.], [0.5, 0.5]);
+        let obj = CtImageBuilder::new(pixels.clone(), plane.clone())
+            .with_frame_of_reference_uid("2.25.2")
+            .with_element(DataElement::new(tags::MANUFACTURER, VR::LO, "ACME"))
+            .build()
+            .unwrap();
+
+        let str_of = |tag| obj.get(tag).unwrap().to_str().unwrap().to_string();
+        assert_eq!(str_of(tags::SOP_CLASS_UID), uids::CT_IMAGE_STORAGE);
+        assert_eq!(str_of(tags::MODALITY), "CT");
+        assert_eq!(str_of(tags::FRAME_OF_REFERENCE_UID), "2.25.2");
+        assert_eq!(str_of(tags::MANUFACTURER), "ACME");
+        assert_eq!(str_of(tags::KVP), "");
+        assert_eq!(str_of(tags::IMAGE_POSITION_PATIENT), "-10\\-10\\5.5");
+        assert_eq!(str_of(tags::RESCALE_SLOPE), "1");
+        assert_eq!(str_of(tags::HIGH_BIT), "11");
+        assert_eq!(str_of(tags::PIXEL_REPRESENTATION), "1");
+        let samples: Vec<u16> = obj.get(tags::PIXEL_DATA).unwrap().to_multi_int().unwrap();
+        assert_eq!(samples[0] as i16, -1000);
+
+        // CT images are 16-bit grayscale
+        assert!(
+            CtImageBuilder::new(ImagePixels::monochrome8(2, 2, vec![0; 4]), plane.clone())
+                .build()
+                .is_err()
+        );
+        assert!(
+            CtImageBuilder::new(pixels.with_bits_stored(10), plane)
+                .build()
+                .is_err()
+        );
+    }
+}
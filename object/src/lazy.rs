@@ -0,0 +1,848 @@
+//! Lazy DICOM object API:
+//! random access to the data elements of a DICOM file
+//! without loading the entire data set in memory.
+//!
+//! When opening a [`LazyDicomObject`],
+//! the data set is scanned once to build an index
+//! of the root data set's elements,
+//! recording their headers and their positions in the source.
+//! Small values are decoded right away,
+//! whereas large values (such as pixel data, waveforms, or private blobs)
+//! and data set sequences
+//! are only read from the source when accessed.
+//! This makes it cheap to read a few attributes from a very large file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::lazy::LazyDicomObject;
+//! use dicom_dictionary_std::tags;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut obj = LazyDicomObject::open_file("big_multiframe.dcm")?;
+//!
+//! // the patient name was decoded while indexing
+//! let patient_name = obj.element(tags::PATIENT_NAME)?.to_str()?.to_string();
+//!
+//! // pixel data is only read now
+//! let pixel_data = obj.element(tags::PIXEL_DATA)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The data set needs to be encoded with a transfer syntax
+//! which does not require the data set to be decoded as a whole
+//! (deflated transfer syntaxes are not supported).
+//!
+//! # Memory-mapped files
+//!
+//! With the **Cargo feature `mmap`**,
+//! files can also be opened by mapping them into memory
+//! (see [`MappedDicomObject`]).
+//! The raw bytes of any value can then be borrowed from the mapping
+//! through [`mapped_value`](LazyDicomObject::mapped_value),
+//! without first copying them into a [`PrimitiveValue`](dicom_core::PrimitiveValue).
+//! Since mapped pages are backed by the file,
+//! they can be reclaimed by the operating system at any time,
+//! which keeps the memory footprint low
+//! when scanning the pixel data of many large files.
+//!
+//! ```no_run
+//! # #[cfg(feature = "mmap")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use dicom_dictionary_std::tags;
+//! use dicom_object::lazy::{MappedDicomObject, MappedValue};
+//!
+//! let obj = MappedDicomObject::open_file_mmap("big_multiframe.dcm")?;
+//! match obj.mapped_value(tags::PIXEL_DATA) {
+//!     Some(MappedValue::Primitive(bytes)) => println!("{} bytes of native pixel data", bytes.len()),
+//!     Some(MappedValue::Fragments { fragments, .. }) => println!("{} fragments", fragments.len()),
+//!     None => println!("no pixel data"),
+//! }
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "mmap"))]
+//! # fn main() {}
+//! ```
+
+#[cfg(feature = "mmap")]
+use std::io::Cursor;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use dicom_core::{DataDictionary, Length, Tag, VR, header::DataElementHeader};
+use dicom_dictionary_std::{StandardDataDictionary, tags};
+use dicom_encoding::{Codec, TransferSyntax, TransferSyntaxIndex, text::SpecificCharacterSet};
+use dicom_parser::dataset::{DataSetReader, LazyDataToken, lazy_read::LazyDataSetReader};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use snafu::Backtrace;
+use snafu::prelude::*;
+
+use crate::{FileDicomObject, FileMetaTable, InMemDicomObject, mem::InMemElement};
+
+/// The default maximum length of a value
+/// for it to be decoded while indexing the data set.
+pub const DEFAULT_MAX_EAGER_LENGTH: u32 = 4096;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error which may occur when using a lazy DICOM object
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for the lazy DICOM object API
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    #[snafu(display("Could not open file '{}'", filename.display()))]
+    OpenFile {
+        filename: std::path::PathBuf,
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not read preamble bytes
+    ReadPreambleBytes {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not parse meta group data set
+    ParseMetaDataSet {
+        #[snafu(backtrace, source(from(crate::meta::Error, Box::new)))]
+        source: Box<crate::meta::Error>,
+    },
+    /// Unrecognized transfer syntax {ts_uid}
+    UnrecognizedTransferSyntax {
+        ts_uid: String,
+        backtrace: Backtrace,
+    },
+    /// Unsupported lazy reading for transfer syntax `{uid}` ({name})
+    UnsupportedTransferSyntax {
+        uid: &'static str,
+        name: &'static str,
+        backtrace: Backtrace,
+    },
+    /// Could not create data set parser
+    CreateParser {
+        #[snafu(
+            backtrace,
+            source(from(dicom_parser::dataset::lazy_read::Error, Box::from))
+        )]
+        source: Box<dicom_parser::dataset::lazy_read::Error>,
+    },
+    /// Could not read data set token
+    ReadToken {
+        #[snafu(
+            backtrace,
+            source(from(dicom_parser::dataset::lazy_read::Error, Box::from))
+        )]
+        source: Box<dicom_parser::dataset::lazy_read::Error>,
+    },
+    /// Could not skip value
+    SkipValue {
+        #[snafu(
+            backtrace,
+            source(from(dicom_parser::stateful::decode::Error, Box::from))
+        )]
+        source: Box<dicom_parser::stateful::decode::Error>,
+    },
+    /// Could not decode the value of {tag}
+    DecodeValue {
+        tag: Tag,
+        #[snafu(backtrace, source(from(dicom_parser::dataset::Error, Box::from)))]
+        source: Box<dicom_parser::dataset::Error>,
+    },
+    /// Could not seek to the value of {tag}
+    SeekValue {
+        tag: Tag,
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not create data set parser for {tag}
+    CreateValueParser {
+        tag: Tag,
+        #[snafu(backtrace, source(from(dicom_parser::dataset::read::Error, Box::from)))]
+        source: Box<dicom_parser::dataset::read::Error>,
+    },
+    /// Could not read the value of {tag}
+    ReadValue {
+        tag: Tag,
+        #[snafu(backtrace, source(from(crate::ReadError, Box::from)))]
+        source: Box<crate::ReadError>,
+    },
+    /// Missing data element {tag} at the recorded position
+    MissingElement { tag: Tag, backtrace: Backtrace },
+    #[cfg(feature = "mmap")]
+    #[snafu(display("Could not map file '{}' into memory", filename.display()))]
+    MapFile {
+        filename: std::path::PathBuf,
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// No such data element with tag {tag}
+    NoSuchDataElementTag { tag: Tag, backtrace: Backtrace },
+}
+
+/// An entry in the index of a lazy DICOM object
+#[derive(Debug, Clone)]
+struct LazyEntry<D> {
+    /// the header of the data element
+    header: DataElementHeader,
+    /// the position of the data element header in the source
+    position: u64,
+    /// the data element, if already loaded
+    element: Option<InMemElement<D>>,
+    /// where the bytes of the value are in the source,
+    /// unless it is a data set sequence
+    #[cfg_attr(not(feature = "mmap"), allow(dead_code))]
+    location: Option<ValueLocation>,
+}
+
+/// The location of the bytes of a value in the source
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "mmap"), allow(dead_code))]
+enum ValueLocation {
+    /// a primitive value, by position and length
+    Primitive(u64, u32),
+    /// the items of encapsulated pixel data by position and length,
+    /// starting with the basic offset table
+    Fragments(Vec<(u64, u32)>),
+}
+
+/// A DICOM file object which reads data element values on demand.
+///
+/// See the [module-level documentation](crate::lazy) for more details.
+#[derive(Debug)]
+pub struct LazyDicomObject<S, D = StandardDataDictionary> {
+    /// the random access data source
+    source: S,
+    /// the file meta group
+    meta: FileMetaTable,
+    /// the specific character set declared in the main data set
+    charset: SpecificCharacterSet,
+    /// data element dictionary
+    dict: D,
+    /// the index of the root data set's elements
+    entries: BTreeMap<Tag, LazyEntry<D>>,
+}
+
+impl LazyDicomObject<BufReader<File>> {
+    /// Open a DICOM file and index its data set,
+    /// deferring the decoding of values
+    /// longer than [`DEFAULT_MAX_EAGER_LENGTH`].
+    ///
+    /// The 128-byte preamble is skipped if found.
+    pub fn open_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_file_with(path, StandardDataDictionary, DEFAULT_MAX_EAGER_LENGTH)
+    }
+}
+
+impl<S> LazyDicomObject<S>
+where
+    S: Read + Seek,
+{
+    /// Index a DICOM file from a random access source,
+    /// deferring the decoding of values
+    /// longer than [`DEFAULT_MAX_EAGER_LENGTH`].
+    ///
+    /// The 128-byte preamble is skipped if found.
+    pub fn from_reader(src: S) -> Result<Self> {
+        Self::from_reader_with(src, StandardDataDictionary, DEFAULT_MAX_EAGER_LENGTH)
+    }
+}
+
+impl<D> LazyDicomObject<BufReader<File>, D>
+where
+    D: DataDictionary + Clone,
+{
+    /// Open a DICOM file and index its data set
+    /// using the given data element dictionary,
+    /// deferring the decoding of values longer than `max_eager_len` bytes.
+    pub fn open_file_with(path: impl AsRef<Path>, dict: D, max_eager_len: u32) -> Result<Self> {
+        let filename = path.as_ref();
+        let file = File::open(filename).context(OpenFileSnafu { filename })?;
+        Self::from_reader_with(BufReader::new(file), dict, max_eager_len)
+    }
+}
+
+/// A lazy DICOM object reading from a file mapped into memory.
+#[cfg(feature = "mmap")]
+pub type MappedDicomObject<D = StandardDataDictionary> = LazyDicomObject<Cursor<Mmap>, D>;
+
+/// The raw bytes of a value borrowed from a memory-mapped file,
+/// as encoded in the file's transfer syntax.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum MappedValue<'a> {
+    /// The bytes of a primitive value, including any trailing padding
+    Primitive(&'a [u8]),
+    /// The items of encapsulated pixel data
+    Fragments {
+        /// the basic offset table (empty if absent)
+        offset_table: &'a [u8],
+        /// the pixel data fragments
+        fragments: Vec<&'a [u8]>,
+    },
+}
+
+#[cfg(feature = "mmap")]
+impl MappedDicomObject {
+    /// Map a DICOM file into memory and index its data set,
+    /// deferring the decoding of values
+    /// longer than [`DEFAULT_MAX_EAGER_LENGTH`].
+    ///
+    /// The 128-byte preamble is skipped if found.
+    ///
+    /// The file must not be modified or truncated
+    /// while the object is alive,
+    /// otherwise reading its values may yield inconsistent data
+    /// or crash the process.
+    pub fn open_file_mmap(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_file_mmap_with(path, StandardDataDictionary, DEFAULT_MAX_EAGER_LENGTH)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<D> MappedDicomObject<D>
+where
+    D: DataDictionary + Clone,
+{
+    /// Map a DICOM file into memory and index its data set
+    /// using the given data element dictionary,
+    /// deferring the decoding of values longer than `max_eager_len` bytes.
+    ///
+    /// See [`open_file_mmap`](MappedDicomObject::open_file_mmap)
+    /// for the requirements on the file.
+    pub fn open_file_mmap_with(
+        path: impl AsRef<Path>,
+        dict: D,
+        max_eager_len: u32,
+    ) -> Result<Self> {
+        let filename = path.as_ref();
+        let file = File::open(filename).context(OpenFileSnafu { filename })?;
+        // SAFETY: the file is required not to be modified while mapped,
+        // as documented in `open_file_mmap`
+        let mmap = unsafe { Mmap::map(&file) }.context(MapFileSnafu { filename })?;
+        Self::from_reader_with(Cursor::new(mmap), dict, max_eager_len)
+    }
+
+    /// Borrow the raw bytes of the value with the given tag
+    /// from the mapped file, without decoding or copying them.
+    ///
+    /// Returns `None` if the element does not exist
+    /// or is a data set sequence.
+    pub fn mapped_value(&self, tag: Tag) -> Option<MappedValue<'_>> {
+        let data: &[u8] = self.source.get_ref();
+        let range = |position: u64, len: u32| {
+            let start = usize::try_from(position).ok()?;
+            data.get(start..start.checked_add(len as usize)?)
+        };
+        match self.entries.get(&tag)?.location.as_ref()? {
+            ValueLocation::Primitive(position, len) => {
+                range(*position, *len).map(MappedValue::Primitive)
+            }
+            ValueLocation::Fragments(items) => {
+                let mut items = items.iter().map(|(position, len)| range(*position, *len));
+                let offset_table = items.next().flatten().unwrap_or_default();
+                let fragments = items.collect::<Option<Vec<_>>>()?;
+                Some(MappedValue::Fragments {
+                    offset_table,
+                    fragments,
+                })
+            }
+        }
+    }
+}
+
+impl<S, D> LazyDicomObject<S, D>
+where
+    S: Read + Seek,
+    D: DataDictionary + Clone,
+{
+    /// Index a DICOM file from a random access source
+    /// using the given data element dictionary,
+    /// deferring the decoding of values longer than `max_eager_len` bytes.
+    ///
+    /// Data set sequences are always deferred.
+    pub fn from_reader_with(mut src: S, dict: D, max_eager_len: u32) -> Result<Self> {
+        // skip the preamble if present
+        let start = src.stream_position().context(ReadPreambleBytesSnafu)?;
+        let mut buf = Vec::with_capacity(132);
+        (&mut src)
+            .take(132)
+            .read_to_end(&mut buf)
+            .context(ReadPreambleBytesSnafu)?;
+        let offset = if buf.len() == 132 && &buf[128..] == b"DICM" {
+            128
+        } else {
+            0
+        };
+        src.seek(SeekFrom::Start(start + offset))
+            .context(ReadPreambleBytesSnafu)?;
+
+        let meta = FileMetaTable::from_reader(&mut src).context(ParseMetaDataSetSnafu)?;
+        let ts = lookup_ts(&meta)?;
+
+        let mut entries = BTreeMap::new();
+        let mut charset = SpecificCharacterSet::default();
+        let mut dataset =
+            LazyDataSetReader::new_with_ts(&mut src, ts).context(CreateParserSnafu)?;
+        // nesting level of the tokens being read
+        let mut depth = 0_u32;
+        // whether the tokens are of the root encapsulated pixel data
+        let mut in_pixel_sequence = false;
+        loop {
+            let position = dataset.position();
+            let Some(token) = dataset.advance() else {
+                break;
+            };
+            let token = token.context(ReadTokenSnafu)?;
+            match token {
+                LazyDataToken::ElementHeader(header) if depth == 0 => {
+                    entries.insert(
+                        header.tag,
+                        LazyEntry {
+                            header,
+                            position,
+                            element: None,
+                            location: None,
+                        },
+                    );
+                }
+                LazyDataToken::SequenceStart { tag, len } => {
+                    if depth == 0 {
+                        entries.insert(
+                            tag,
+                            LazyEntry {
+                                header: DataElementHeader::new(tag, VR::SQ, len),
+                                position,
+                                element: None,
+                                location: None,
+                            },
+                        );
+                    }
+                    depth += 1;
+                }
+                LazyDataToken::PixelSequenceStart => {
+                    if depth == 0 {
+                        entries.insert(
+                            tags::PIXEL_DATA,
+                            LazyEntry {
+                                header: DataElementHeader::new(
+                                    tags::PIXEL_DATA,
+                                    VR::OB,
+                                    Length::UNDEFINED,
+                                ),
+                                position,
+                                element: None,
+                                location: Some(ValueLocation::Fragments(Vec::new())),
+                            },
+                        );
+                        in_pixel_sequence = true;
+                    }
+                    depth += 1;
+                }
+                LazyDataToken::SequenceEnd => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        in_pixel_sequence = false;
+                    }
+                }
+                LazyDataToken::LazyItemValue { len, .. } if depth == 1 && in_pixel_sequence => {
+                    if let Some(LazyEntry {
+                        location: Some(ValueLocation::Fragments(fragments)),
+                        ..
+                    }) = entries.get_mut(&tags::PIXEL_DATA)
+                    {
+                        fragments.push((position, len));
+                    }
+                    token.skip().context(SkipValueSnafu)?;
+                }
+                LazyDataToken::LazyValue { header, .. } if depth == 0 => {
+                    if let Some(entry) = entries.get_mut(&header.tag) {
+                        entry.location = Some(ValueLocation::Primitive(position, header.len.0));
+                    }
+                    if header.len.0 > max_eager_len {
+                        token.skip().context(SkipValueSnafu)?;
+                        continue;
+                    }
+                    let value = token
+                        .into_value()
+                        .context(DecodeValueSnafu { tag: header.tag })?;
+                    if header.tag == tags::SPECIFIC_CHARACTER_SET {
+                        if let Some(cs) =
+                            SpecificCharacterSet::from_codes(value.to_str().split('\\'))
+                        {
+                            charset = cs;
+                        }
+                    }
+                    if let Some(entry) = entries.get_mut(&header.tag) {
+                        entry.element = Some(InMemElement::new_with_len(
+                            header.tag, header.vr, header.len, value,
+                        ));
+                    }
+                }
+                token => token.skip().context(SkipValueSnafu)?,
+            }
+        }
+
+        drop(dataset);
+
+        Ok(LazyDicomObject {
+            source: src,
+            meta,
+            charset,
+            dict,
+            entries,
+        })
+    }
+
+    /// Retrieve the file meta group.
+    pub fn meta(&self) -> &FileMetaTable {
+        &self.meta
+    }
+
+    /// Obtain an iterator over the tags of the root data set's elements.
+    pub fn tags(&self) -> impl Iterator<Item = Tag> + '_ {
+        self.entries.keys().copied()
+    }
+
+    /// Retrieve the header of the data element with the given tag,
+    /// without reading its value.
+    pub fn header(&self, tag: Tag) -> Option<DataElementHeader> {
+        self.entries.get(&tag).map(|entry| entry.header)
+    }
+
+    /// Check whether the value of the data element with the given tag
+    /// is currently held in memory.
+    pub fn is_loaded(&self, tag: Tag) -> bool {
+        self.entries
+            .get(&tag)
+            .is_some_and(|entry| entry.element.is_some())
+    }
+
+    /// Retrieve the data element with the given tag,
+    /// reading its value from the source if it was not loaded yet.
+    ///
+    /// Returns an error if the element does not exist.
+    pub fn element(&mut self, tag: Tag) -> Result<&InMemElement<D>> {
+        self.element_opt(tag)?
+            .context(NoSuchDataElementTagSnafu { tag })
+            .map_err(Error)
+    }
+
+    /// Retrieve the data element with the given tag if it exists,
+    /// reading its value from the source if it was not loaded yet.
+    pub fn element_opt(&mut self, tag: Tag) -> Result<Option<&InMemElement<D>>> {
+        let Some(entry) = self.entries.get(&tag) else {
+            return Ok(None);
+        };
+        if entry.element.is_none() {
+            let element = self.read_element(tag, entry.position)?;
+            self.entries.get_mut(&tag).unwrap().element = Some(element);
+        }
+        Ok(self.entries[&tag].element.as_ref())
+    }
+
+    /// Release the value of the data element with the given tag from memory.
+    /// It will be read again from the source on the next access.
+    pub fn unload(&mut self, tag: Tag) {
+        if let Some(entry) = self.entries.get_mut(&tag) {
+            entry.element = None;
+        }
+    }
+
+    /// Read all remaining values
+    /// and turn this into a DICOM object fully held in memory.
+    pub fn into_in_mem(mut self) -> Result<FileDicomObject<InMemDicomObject<D>>> {
+        let mut elements = Vec::with_capacity(self.entries.len());
+        for (tag, entry) in std::mem::take(&mut self.entries) {
+            elements.push(match entry.element {
+                Some(element) => element,
+                None => self.read_element(tag, entry.position)?,
+            });
+        }
+        Ok(FileDicomObject {
+            meta: self.meta,
+            obj: InMemDicomObject::from_iter_with_dict(elements, self.dict),
+        })
+    }
+
+    /// Retrieve the underlying data source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Read a single data element from the given position of the source.
+    fn read_element(&mut self, tag: Tag, position: u64) -> Result<InMemElement<D>> {
+        let ts = lookup_ts(&self.meta)?;
+        self.source
+            .seek(SeekFrom::Start(position))
+            .context(SeekValueSnafu { tag })?;
+        let mut dataset = DataSetReader::new_with_ts_cs(&mut self.source, ts, self.charset.clone())
+            .context(CreateValueParserSnafu { tag })?;
+        let mut obj = InMemDicomObject::build_object(
+            &mut dataset,
+            self.dict.clone(),
+            false,
+            Length::UNDEFINED,
+            None,
+            Some(tag),
+        )
+        .context(ReadValueSnafu { tag })?;
+        let element = obj
+            .take_element(tag)
+            .ok()
+            .context(MissingElementSnafu { tag })?;
+        Ok(element)
+    }
+}
+
+/// Look up the transfer syntax declared in the file meta group,
+/// ensuring that the data set can be read at random.
+fn lookup_ts(meta: &FileMetaTable) -> Result<&'static TransferSyntax> {
+    let ts_uid = meta.transfer_syntax();
+    let ts = TransferSyntaxRegistry
+        .get(ts_uid)
+        .context(UnrecognizedTransferSyntaxSnafu { ts_uid })?;
+    ensure!(
+        matches!(ts.codec(), Codec::None | Codec::EncapsulatedPixelData(..)),
+        UnsupportedTransferSyntaxSnafu {
+            uid: ts.uid(),
+            name: ts.name(),
+        }
+    );
+    Ok(ts)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use dicom_core::{
+        DataElement, PrimitiveValue, VR, dicom_value,
+        value::{DataSetSequence, PixelFragmentSequence, Value},
+    };
+    use dicom_dictionary_std::{tags, uids};
+
+    use super::LazyDicomObject;
+    use crate::{FileMetaTableBuilder, InMemDicomObject};
+
+    fn test_file(ts: &str) -> Vec<u8> {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 192"),
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.123456789"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Müller^José"),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+                ])]),
+            ),
+            DataElement::new(tags::ROWS, VR::US, dicom_value!(U16, 64)),
+            DataElement::new(tags::COLUMNS, VR::US, dicom_value!(U16, 128)),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0x55_u8; 64 * 128]),
+            ),
+        ]);
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(ts)
+                    .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                    .media_storage_sop_instance_uid("2.25.123456789"),
+            )
+            .unwrap();
+        let mut out = Vec::new();
+        file_obj.write_all(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn read_values_on_demand() {
+        for ts in [
+            uids::EXPLICIT_VR_LITTLE_ENDIAN,
+            uids::IMPLICIT_VR_LITTLE_ENDIAN,
+        ] {
+            let mut obj = LazyDicomObject::from_reader(Cursor::new(test_file(ts))).unwrap();
+            assert_eq!(obj.meta().transfer_syntax(), ts);
+            assert_eq!(obj.tags().count(), 8);
+
+            // small values were read while indexing
+            assert!(obj.is_loaded(tags::PATIENT_NAME));
+            assert_eq!(
+                obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+                "Müller^José"
+            );
+
+            // large values and sequences are deferred
+            assert!(!obj.is_loaded(tags::PIXEL_DATA));
+            assert!(!obj.is_loaded(tags::REFERENCED_IMAGE_SEQUENCE));
+            assert_eq!(obj.header(tags::PIXEL_DATA).unwrap().len.0, 64 * 128);
+
+            let pixel_data = obj.element(tags::PIXEL_DATA).unwrap();
+            assert_eq!(pixel_data.to_bytes().unwrap().len(), 64 * 128);
+            assert!(obj.is_loaded(tags::PIXEL_DATA));
+
+            let items = obj
+                .element(tags::REFERENCED_IMAGE_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap();
+            assert_eq!(items.len(), 1);
+            assert_eq!(
+                items[0]
+                    .element(tags::REFERENCED_SOP_INSTANCE_UID)
+                    .unwrap()
+                    .to_str()
+                    .unwrap(),
+                "2.25.1"
+            );
+
+            obj.unload(tags::PIXEL_DATA);
+            assert!(!obj.is_loaded(tags::PIXEL_DATA));
+
+            assert!(obj.element(tags::STUDY_DATE).is_err());
+            assert!(obj.element_opt(tags::STUDY_DATE).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn read_encapsulated_pixel_data_on_demand() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.123456789"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                Value::PixelSequence(PixelFragmentSequence::new(
+                    vec![],
+                    vec![vec![0x11_u8; 6000], vec![0x22_u8; 16]],
+                )),
+            ),
+        ]);
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(uids::JPEG_BASELINE8_BIT)
+                    .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                    .media_storage_sop_instance_uid("2.25.123456789"),
+            )
+            .unwrap();
+        let mut data = Vec::new();
+        file_obj.write_all(&mut data).unwrap();
+
+        let mut obj = LazyDicomObject::from_reader(Cursor::new(data)).unwrap();
+        assert!(!obj.is_loaded(tags::PIXEL_DATA));
+        assert!(obj.header(tags::PIXEL_DATA).unwrap().len.is_undefined());
+
+        let fragments = obj.element(tags::PIXEL_DATA).unwrap().fragments().unwrap();
+        assert_eq!(fragments.len(), 2);
+        assert_eq!(fragments[0].len(), 6000);
+        assert_eq!(fragments[1], vec![0x22_u8; 16]);
+    }
+
+    #[test]
+    fn lazy_object_into_in_mem() {
+        let data = test_file(uids::EXPLICIT_VR_LITTLE_ENDIAN);
+        let expected = crate::from_reader(&data[128..]).unwrap();
+
+        let obj = LazyDicomObject::from_reader(Cursor::new(data)).unwrap();
+        let obj = obj.into_in_mem().unwrap();
+        assert_eq!(obj.meta(), expected.meta());
+        for tag in [
+            tags::SPECIFIC_CHARACTER_SET,
+            tags::PATIENT_NAME,
+            tags::ROWS,
+            tags::COLUMNS,
+            tags::PIXEL_DATA,
+        ] {
+            assert_eq!(obj.element(tag).unwrap(), expected.element(tag).unwrap());
+        }
+        let items = obj
+            .element(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        let expected_items = expected
+            .element(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items, expected_items);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn borrow_values_from_mapped_file() {
+        use super::{MappedDicomObject, MappedValue};
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&test_file(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .unwrap();
+
+        let mut obj = MappedDicomObject::open_file_mmap(file.path()).unwrap();
+        assert!(!obj.is_loaded(tags::PIXEL_DATA));
+        assert_eq!(
+            obj.mapped_value(tags::PIXEL_DATA),
+            Some(MappedValue::Primitive(&[0x55_u8; 64 * 128][..]))
+        );
+        assert_eq!(
+            obj.mapped_value(tags::ROWS),
+            Some(MappedValue::Primitive(&[64, 0][..]))
+        );
+        assert_eq!(obj.mapped_value(tags::REFERENCED_IMAGE_SEQUENCE), None);
+        assert_eq!(obj.mapped_value(tags::STUDY_DATE), None);
+        // values can still be decoded
+        assert_eq!(
+            obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Müller^José"
+        );
+
+        // encapsulated pixel data
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.123456789"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                Value::PixelSequence(PixelFragmentSequence::new(
+                    vec![0],
+                    vec![vec![0x11_u8; 6000], vec![0x22_u8; 16]],
+                )),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::JPEG_BASELINE8_BIT)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.123456789"),
+        )
+        .unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        obj.write_to_file(file.path()).unwrap();
+
+        let obj = MappedDicomObject::open_file_mmap(file.path()).unwrap();
+        let Some(MappedValue::Fragments {
+            offset_table,
+            fragments,
+        }) = obj.mapped_value(tags::PIXEL_DATA)
+        else {
+            panic!("expected encapsulated pixel data");
+        };
+        assert_eq!(offset_table, &[0, 0, 0, 0]);
+        assert_eq!(fragments, vec![&[0x11_u8; 6000][..], &[0x22_u8; 16][..]]);
+    }
+}
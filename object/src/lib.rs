@@ -28,6 +28,21 @@
 //!   including pixel data.
 //!   To read DICOM data sets in smaller portions,
 //!   you can use the [DICOM collector API](collector).
+//!   To access individual elements of a large file at random
+//!   without reading all values up front,
+//!   you can use a [`LazyDicomObject`](lazy::LazyDicomObject),
+//!   which can also read from memory-mapped files
+//!   with **Cargo feature `mmap`**.
+//!   Conversely, large files can be written incrementally
+//!   with the [DICOM stream writer API](stream).
+//! - Key attributes of all DICOM files in a directory tree
+//!   can be read in parallel with a [`Scanner`](scan::Scanner).
+//! - Coded concepts in code sequence items
+//!   can be read and written through [`Code`](code::Code),
+//!   which the [`code`] module complements with commonly used codes.
+//! - New objects of common IODs, such as secondary captures and CT images,
+//!   can be created with their mandatory modules already populated
+//!   through the builders in the [`iod`] module.
 //!
 //! # Encodings
 //!
@@ -41,6 +56,15 @@
 //! (such as _Deflated Explicit VR Little Endian_),
 //! enable **Cargo feature `deflated`**.
 //!
+//! Enabling **Cargo feature `gzip`** makes [`open_file`]
+//! (and [`OpenFileOptions::open_file`])
+//! detect gzip-compressed files (such as `.dcm.gz` files)
+//! and decompress them on the fly while reading.
+//!
+//! Enabling **Cargo feature `xml`** adds conversion
+//! between in-memory objects and the Native DICOM Model in XML
+//! (see the [`xml`](crate::xml) module).
+//!
 //! When working with imaging data,
 //! consider using the [`dicom-pixeldata`] crate,
 //! which offers methods to convert pixel data from objects
@@ -185,16 +209,38 @@
 //! # }
 //! # run().unwrap();
 //! ```
+//!
+//! ## DICOM JSON
+//!
+//! Conversion between in-memory objects and the [DICOM JSON Model]
+//! is provided by the [`dicom-json`] crate,
+//! which also extends `InMemDicomObject`
+//! with the methods `to_json_value` and `from_json_slice`
+//! through the trait `DicomJsonExt`.
+//!
+//! [DICOM JSON Model]: https://dicom.nema.org/medical/dicom/current/output/chtml/part18/chapter_F.html
+//! [`dicom-json`]: https://docs.rs/dicom-json
+pub mod code;
 pub mod collector;
+pub mod dicomdir;
 pub mod file;
+pub mod iod;
+pub mod lazy;
 pub mod mem;
 pub mod meta;
 pub mod ops;
+pub mod remap;
+pub mod scan;
+pub mod stream;
 pub mod tokens;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 pub use crate::collector::{DicomCollector, DicomCollectorOptions};
 pub use crate::file::{OpenFileOptions, from_reader, open_file};
-pub use crate::mem::InMemDicomObject;
+pub use crate::mem::{
+    AttributeModification, ElementEncoding, InMemDicomObject, ModificationReason,
+};
 pub use crate::meta::{FileMetaTable, FileMetaTableBuilder};
 pub use dicom_core::Tag;
 use dicom_core::ops::{AttributeSelector, AttributeSelectorStep};
@@ -685,6 +731,11 @@ pub enum ReadError {
         backtrace: Backtrace,
         source: std::io::Error,
     },
+    /// Could not read the start of the data set
+    ReadDataSetStart {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
     #[snafu(display("Could not parse meta group data set"))]
     ParseMetaDataSet {
         #[snafu(backtrace)]
@@ -815,6 +866,23 @@ pub enum PrivateElementError {
     NoSpace { group: GroupNumber },
 }
 
+/// An error which may occur when converting a DICOM object
+/// to another specific character set,
+/// such as through [`convert_character_set`](crate::InMemDicomObject::convert_character_set).
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+#[snafu(visibility(pub(crate)))]
+pub enum ConvertCharacterSetError {
+    /// A text value cannot be represented in the target character set
+    #[snafu(display("Value of {} cannot be represented in character set {}", tag, charset))]
+    UnrepresentableText {
+        tag: Tag,
+        charset: String,
+        #[snafu(backtrace)]
+        source: dicom_encoding::text::EncodeTextError,
+    },
+}
+
 /// An error which may occur when looking up a DICOM object's attributes.
 #[derive(Debug, Snafu)]
 #[non_exhaustive]
@@ -1387,6 +1455,20 @@ where
         }
     }
 
+    /// Return the number of frames which are fully available,
+    /// which are fewer than the NumberOfFrames attribute
+    /// if the encapsulated pixel data was truncated
+    fn complete_frames(&self) -> u32 {
+        let number_of_frames = self.number_of_frames().unwrap_or(1);
+        match (**self)
+            .get(dicom_dictionary_std::tags::PIXEL_DATA)
+            .map(|e| e.value())
+        {
+            Some(DicomValue::PixelSequence(v)) => v.complete_frames(number_of_frames),
+            _ => number_of_frames,
+        }
+    }
+
     /// Return a specific encoded pixel fragment by index as a `Vec<u8>`
     /// or `None` if no pixel data is found.
     ///
@@ -1736,4 +1818,55 @@ mod tests {
         // no more frames
         assert_eq!(PixelDataObject::frame_pixel_data(&obj, 1), None);
     }
+
+    /// Encapsulated pixel data cut short by the end of the file
+    /// is read partially.
+    #[test]
+    fn read_truncated_pixel_data() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "3"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                dicom_core::value::PixelFragmentSequence::new_fragments(vec![
+                    vec![0x11; 16],
+                    vec![0x22; 16],
+                    vec![0x33; 16],
+                ]),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::JPEG_BASELINE8_BIT)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.1"),
+        )
+        .unwrap();
+        let mut bytes = Vec::new();
+        obj.write_all(&mut bytes).unwrap();
+        assert_eq!(PixelDataObject::complete_frames(&obj), 3);
+
+        // cut the sequence delimiter and 10 bytes of the last fragment
+        bytes.truncate(bytes.len() - 18);
+        let obj = crate::from_reader(&bytes[128..]).unwrap();
+        let pixel_data = obj.get(tags::PIXEL_DATA).unwrap();
+        let fragments = pixel_data.value().fragments().unwrap();
+        assert_eq!(fragments.len(), 3);
+        assert_eq!(fragments[2], vec![0x33; 6]);
+        let dicom_core::DicomValue::PixelSequence(seq) = pixel_data.value() else {
+            panic!("expected encapsulated pixel data");
+        };
+        assert!(seq.is_truncated());
+        assert_eq!(PixelDataObject::complete_frames(&obj), 2);
+        assert_eq!(
+            PixelDataObject::frame_pixel_data(&obj, 1).as_deref(),
+            Some(&[0x22; 16][..])
+        );
+    }
 }
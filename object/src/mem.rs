@@ -36,45 +36,62 @@
 //! If necessary, this number can be obtained via the [`HasLength`] trait.
 //! However, any modifications made to the object will reset this length
 //! to [_undefined_](dicom_core::Length::UNDEFINED).
+//!
+//! Cloning an in-memory DICOM object is cheap:
+//! the clones share their data elements
+//! (including large values such as pixel data)
+//! until they are modified,
+//! at which point only the modified element is copied.
+//! Identical elements within an object can share their memory in the same way
+//! through [`deduplicate_values`](InMemDicomObject::deduplicate_values).
 use dicom_core::ops::{
     ApplyOp, AttributeAction, AttributeOp, AttributeSelector, AttributeSelectorStep,
 };
 use dicom_encoding::Codec;
-use dicom_parser::dataset::read::{DataSetReaderOptions, OddLengthStrategy};
-use dicom_parser::dataset::write::DataSetWriterOptions;
+use dicom_parser::dataset::read::{DataSetReaderOptions, OddLengthStrategy, SkipElements};
+use dicom_parser::dataset::write::{DataSetWriterOptions, ExplicitLengthSqItemStrategy};
 use dicom_parser::stateful::decode::CharacterSetOverride;
 use itertools::Itertools;
 use smallvec::SmallVec;
 use snafu::{OptionExt, ResultExt, ensure};
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::Write;
+use std::io::{BufRead, BufReader, BufWriter, Read};
 use std::path::Path;
-use std::{collections::BTreeMap, io::Write};
+use std::sync::Arc;
 
-use crate::file::ReadPreamble;
+use crate::file::{DataSetEncoding, ReadPreamble, ReadReport, ReadWarning, detect_encoding};
 use crate::ops::{
     ApplyError, ApplyResult, IncompatibleTypesSnafu, ModifySnafu, UnsupportedActionSnafu,
 };
 use crate::{
-    AccessByNameError, AccessError, AtAccessError, BuildMetaTableSnafu, CreateParserSnafu,
-    CreatePrinterSnafu, DicomObject, ElementNotFoundSnafu, FileDicomObject, InvalidGroupSnafu,
-    MissingElementValueSnafu, MissingLeafElementSnafu, NoSpaceSnafu, NoSuchAttributeNameSnafu,
-    NoSuchDataElementAliasSnafu, NoSuchDataElementTagSnafu, NotASequenceSnafu, OpenFileSnafu,
-    ParseMetaDataSetSnafu, ParseSopAttributeSnafu, PrematureEndSnafu, PrepareMetaTableSnafu,
-    PrintDataSetSnafu, PrivateCreatorNotFoundSnafu, PrivateElementError, ReadError, ReadFileSnafu,
-    ReadPreambleBytesSnafu, ReadTokenSnafu, ReadUnrecognizedTransferSyntaxSnafu,
+    AccessByNameError, AccessError, AtAccessError, BuildMetaTableSnafu, ConvertCharacterSetError,
+    CreateParserSnafu, CreatePrinterSnafu, DicomObject, ElementNotFoundSnafu, FileDicomObject,
+    InvalidGroupSnafu, MissingElementValueSnafu, MissingLeafElementSnafu, NoSpaceSnafu,
+    NoSuchAttributeNameSnafu, NoSuchDataElementAliasSnafu, NoSuchDataElementTagSnafu,
+    NotASequenceSnafu, OpenFileSnafu, ParseMetaDataSetSnafu, ParseSopAttributeSnafu,
+    PrematureEndSnafu, PrepareMetaTableSnafu, PrintDataSetSnafu, PrintMetaDataSetSnafu,
+    PrivateCreatorNotFoundSnafu, PrivateElementError, ReadDataSetStartSnafu, ReadError,
+    ReadFileSnafu, ReadPreambleBytesSnafu, ReadTokenSnafu, ReadUnrecognizedTransferSyntaxSnafu,
     ReadUnsupportedTransferSyntaxSnafu, ReadUnsupportedTransferSyntaxWithSuggestionSnafu,
-    UnexpectedTokenSnafu, WithMetaError, WriteError,
+    UnexpectedTokenSnafu, UnrepresentableTextSnafu, WithMetaError, WriteError, WriteMagicCodeSnafu,
+    WritePreambleSnafu, WriteUnrecognizedTransferSyntaxSnafu,
 };
 use crate::{FileMetaTableBuilder, meta::FileMetaTable};
-use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry, VirtualVr, VmViolation};
 use dicom_core::header::{GroupNumber, HasLength, Header};
 use dicom_core::value::{C, DataSetSequence, PixelFragmentSequence, Value, ValueType};
-use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR};
+use dicom_core::{DataElement, DataElementHeader, Length, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::private::{self, PrivateDictionaryEntry};
 use dicom_dictionary_std::{StandardDataDictionary, tags, uids};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
-use dicom_encoding::{TransferSyntax, encode::EncodeTo, text::SpecificCharacterSet};
+use dicom_encoding::{
+    TransferSyntax,
+    encode::EncodeTo,
+    text::{SpecificCharacterSet, TextCodec},
+};
 use dicom_parser::dataset::{DataSetReader, DataToken, IntoTokensOptions};
 use dicom_parser::{
     StatefulDecode,
@@ -96,10 +113,11 @@ type ParserResult<T> = std::result::Result<T, ParserError>;
 ///
 /// See the [module-level documentation](self)
 /// for more details.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct InMemDicomObject<D = StandardDataDictionary> {
-    /// the element map
-    entries: BTreeMap<Tag, InMemElement<D>>,
+    /// the element map,
+    /// with elements shared between clones of the object until modified
+    entries: BTreeMap<Tag, Arc<InMemElement<D>>>,
     /// the data dictionary
     dict: D,
     /// The length of the DICOM object in bytes.
@@ -110,6 +128,149 @@ pub struct InMemDicomObject<D = StandardDataDictionary> {
     /// because changing the character set may change the length in bytes of
     /// stored text. It has to be public for now because we need
     pub(crate) charset_changed: bool,
+    /// The encoding details of the elements as originally read from a source,
+    /// used for reproducing them faithfully
+    original_encoding: BTreeMap<Tag, ElementEncoding>,
+    /// The attributes as they were before being modified
+    /// (`None` if they were absent),
+    /// only recorded if modification tracking is enabled
+    modifications: Option<BTreeMap<Tag, Option<Arc<InMemElement<D>>>>>,
+}
+
+/// The encoding details of a data element
+/// as it was originally read from a DICOM data source.
+///
+/// These are retained by [`InMemDicomObject`]
+/// so that unmodified elements can be written back exactly as they were
+/// (see [`write_dataset_exact_with_ts`](InMemDicomObject::write_dataset_exact_with_ts)).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ElementEncoding {
+    vr: VR,
+    len: Length,
+    vr_inferred: bool,
+}
+
+impl ElementEncoding {
+    /// The value representation of the element in the source.
+    pub fn vr(&self) -> VR {
+        self.vr
+    }
+
+    /// The length of the element's value in the source,
+    /// which is undefined for sequences of undefined length.
+    pub fn len(&self) -> Length {
+        self.len
+    }
+
+    /// Whether the value representation was not read from the source,
+    /// but inferred from the data dictionary
+    /// due to the data set being in an implicit VR transfer syntax.
+    pub fn is_vr_inferred(&self) -> bool {
+        self.vr_inferred
+    }
+
+    /// Whether the element value had an odd length in the source,
+    /// and was thus not padded to an even length.
+    pub fn is_odd_length(&self) -> bool {
+        self.len.get().is_some_and(|l| l % 2 == 1)
+    }
+
+    fn from_header(header: &DataElementHeader) -> Self {
+        ElementEncoding {
+            vr: header.vr,
+            len: header.len,
+            vr_inferred: false,
+        }
+    }
+
+    /// Whether the given element header still matches this encoding.
+    fn matches(&self, header: &DataElementHeader) -> bool {
+        // compare raw lengths, since undefined lengths are never equal
+        self.vr == header.vr && self.len.0 == header.len.0
+    }
+}
+
+/// The reason for modifying the attributes of an object,
+/// as recorded in an item of the _Original Attributes Sequence_.
+#[derive(Debug, Copy, Clone, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ModificationReason {
+    /// The values were replaced
+    /// to conform to local or institutional policy (`COERCE`),
+    /// such as when de-identifying an object
+    /// or coercing patient identifiers on import.
+    #[default]
+    Coerce,
+    /// The values were replaced
+    /// because they were incorrect (`CORRECT`).
+    Correct,
+}
+
+impl ModificationReason {
+    /// The defined term of this reason.
+    pub fn code(self) -> &'static str {
+        match self {
+            ModificationReason::Coerce => "COERCE",
+            ModificationReason::Correct => "CORRECT",
+        }
+    }
+}
+
+/// Details of a modification of the attributes of an object,
+/// recorded alongside the original values
+/// in an item of the _Original Attributes Sequence_
+/// (see [`record_original_attributes`](InMemDicomObject::record_original_attributes)).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeModification {
+    /// The reason for the modification
+    pub reason: ModificationReason,
+    /// The system which modified the attributes
+    pub modifying_system: String,
+    /// The source that provided the object
+    /// with the previous values, if known
+    pub source_of_previous_values: Option<String>,
+}
+
+impl AttributeModification {
+    /// Describe a modification made by the given system.
+    pub fn new(reason: ModificationReason, modifying_system: impl Into<String>) -> Self {
+        AttributeModification {
+            reason,
+            modifying_system: modifying_system.into(),
+            source_of_previous_values: None,
+        }
+    }
+
+    /// Set the source that provided the original values.
+    pub fn with_source_of_previous_values(mut self, source: impl Into<String>) -> Self {
+        self.source_of_previous_values = Some(source.into());
+        self
+    }
+}
+
+impl<D> std::fmt::Debug for InMemDicomObject<D>
+where
+    D: std::fmt::Debug,
+{
+    // This implementation leaves out the original encoding details.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemDicomObject")
+            .field("entries", &self.entries)
+            .field("dict", &self.dict)
+            .field("len", &self.len)
+            .field("charset_changed", &self.charset_changed)
+            .field("modifications", &self.modifications)
+            .finish()
+    }
+}
+
+/// Whether values of the given VR are encoded
+/// according to the specific character set.
+fn is_charset_dependent(vr: VR) -> bool {
+    matches!(
+        vr,
+        VR::SH | VR::LO | VR::ST | VR::LT | VR::UC | VR::UT | VR::PN
+    )
 }
 
 impl<D> PartialEq for InMemDicomObject<D> {
@@ -270,6 +431,8 @@ impl InMemDicomObject<StandardDataDictionary> {
             dict: StandardDataDictionary,
             len: Length::UNDEFINED,
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         }
     }
 
@@ -372,6 +535,8 @@ where
                 dict,
                 len: Length::UNDEFINED,
                 charset_changed: false,
+                original_encoding: BTreeMap::new(),
+                modifications: None,
             },
         }
     }
@@ -414,6 +579,10 @@ where
             ReadPreamble::Auto,
             Default::default(),
             Default::default(),
+            Default::default(),
+            None,
+            false,
+            &mut ReadReport::default(),
         )
     }
 
@@ -424,21 +593,95 @@ where
         ts_index: R,
         read_until: Option<Tag>,
         read_to: Option<Tag>,
-        mut read_preamble: ReadPreamble,
+        read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
         charset_override: CharacterSetOverride,
+        skip: SkipElements,
+        max_bytes: Option<u64>,
+        lenient: bool,
+        report: &mut ReadReport,
     ) -> Result<Self, ReadError>
     where
         P: AsRef<Path>,
         R: TransferSyntaxIndex,
     {
         let path = path.as_ref();
+        #[cfg_attr(not(feature = "gzip"), allow(unused_mut))]
         let mut file =
             BufReader::new(File::open(path).with_context(|_| OpenFileSnafu { filename: path })?);
 
-        if read_preamble == ReadPreamble::Auto {
-            read_preamble = Self::detect_preamble(&mut file)
+        #[cfg(feature = "gzip")]
+        if Self::detect_gzip(&mut file).with_context(|_| ReadFileSnafu { filename: path })? {
+            let file = BufReader::new(flate2::bufread::MultiGzDecoder::new(file));
+            return Self::open_buffered_with_all_options(
+                path,
+                file,
+                dict,
+                ts_index,
+                read_until,
+                read_to,
+                read_preamble,
+                odd_length,
+                charset_override,
+                skip,
+                max_bytes,
+                lenient,
+                report,
+            );
+        }
+
+        Self::open_buffered_with_all_options(
+            path,
+            file,
+            dict,
+            ts_index,
+            read_until,
+            read_to,
+            read_preamble,
+            odd_length,
+            charset_override,
+            skip,
+            max_bytes,
+            lenient,
+            report,
+        )
+    }
+
+    /// Read a DICOM object from an opened file,
+    /// skipping the preamble according to `read_preamble`.
+    #[allow(clippy::too_many_arguments)]
+    fn open_buffered_with_all_options<S, R>(
+        path: &Path,
+        mut file: BufReader<S>,
+        dict: D,
+        ts_index: R,
+        read_until: Option<Tag>,
+        read_to: Option<Tag>,
+        mut read_preamble: ReadPreamble,
+        odd_length: OddLengthStrategy,
+        charset_override: CharacterSetOverride,
+        skip: SkipElements,
+        max_bytes: Option<u64>,
+        lenient: bool,
+        report: &mut ReadReport,
+    ) -> Result<Self, ReadError>
+    where
+        S: Read,
+        R: TransferSyntaxIndex,
+    {
+        if read_preamble == ReadPreamble::Auto || lenient {
+            let detected = Self::detect_preamble(&mut file)
                 .with_context(|_| ReadFileSnafu { filename: path })?;
+            if lenient && detected == ReadPreamble::Never && read_preamble != ReadPreamble::Never {
+                report.warnings.push(ReadWarning::MissingPreamble);
+            }
+            read_preamble = match detected {
+                // without a magic code,
+                // lenient reading takes the file as a bare data set
+                ReadPreamble::Auto if lenient => ReadPreamble::Never,
+                ReadPreamble::Auto => read_preamble,
+                detected => detected,
+            };
         }
 
         if read_preamble == ReadPreamble::Auto || read_preamble == ReadPreamble::Always {
@@ -456,6 +699,10 @@ where
             read_to,
             odd_length,
             charset_override,
+            skip,
+            max_bytes,
+            lenient,
+            report,
         )
     }
 
@@ -500,6 +747,10 @@ where
             ReadPreamble::Auto,
             Default::default(),
             Default::default(),
+            Default::default(),
+            None,
+            false,
+            &mut ReadReport::default(),
         )
     }
 
@@ -513,6 +764,10 @@ where
         mut read_preamble: ReadPreamble,
         odd_length: OddLengthStrategy,
         charset_override: CharacterSetOverride,
+        skip: SkipElements,
+        max_bytes: Option<u64>,
+        lenient: bool,
+        report: &mut ReadReport,
     ) -> Result<Self, ReadError>
     where
         S: Read,
@@ -520,8 +775,11 @@ where
     {
         let mut file = BufReader::new(src);
 
-        if read_preamble == ReadPreamble::Auto {
-            read_preamble = Self::detect_preamble(&mut file).context(ReadPreambleBytesSnafu)?;
+        if read_preamble == ReadPreamble::Auto || lenient {
+            let detected = Self::detect_preamble(&mut file).context(ReadPreambleBytesSnafu)?;
+            if detected != ReadPreamble::Auto {
+                read_preamble = detected;
+            }
         }
 
         if read_preamble == ReadPreamble::Always {
@@ -539,6 +797,10 @@ where
             read_to,
             odd_length,
             charset_override,
+            skip,
+            max_bytes,
+            lenient,
+            report,
         )
     }
 
@@ -567,6 +829,16 @@ where
         Ok(ReadPreamble::Auto)
     }
 
+    /// Check whether the source starts with the gzip magic number.
+    #[cfg(feature = "gzip")]
+    fn detect_gzip<S>(reader: &mut BufReader<S>) -> std::io::Result<bool>
+    where
+        S: Read,
+    {
+        let buf = reader.fill_buf()?;
+        Ok(buf.starts_with(&[0x1F, 0x8B]))
+    }
+
     /// Common implementation for reading the file meta group
     /// and the main data set (expects no preamble and no magic code),
     /// according to the file's transfer syntax and the given options.
@@ -574,6 +846,7 @@ where
     /// If Media Storage SOP Class UID or Media Storage SOP Instance UID
     /// are missing in the file meta group,
     /// this function will attempt to populate them from the main data set.
+    #[allow(clippy::too_many_arguments)]
     fn read_parts_with_all_options_impl<S, R>(
         mut src: BufReader<S>,
         dict: D,
@@ -582,97 +855,507 @@ where
         read_to: Option<Tag>,
         odd_length: OddLengthStrategy,
         charset_override: CharacterSetOverride,
+        skip: SkipElements,
+        max_bytes: Option<u64>,
+        lenient: bool,
+        report: &mut ReadReport,
     ) -> Result<Self, ReadError>
     where
         S: Read,
         R: TransferSyntaxIndex,
     {
+        // without the magic code,
+        // lenient reading takes the source as a bare data set
+        let has_meta = !lenient
+            || src
+                .fill_buf()
+                .context(ReadDataSetStartSnafu)?
+                .starts_with(b"DICM");
+
         // read metadata header
-        let mut meta = FileMetaTable::from_reader(&mut src).context(ParseMetaDataSetSnafu)?;
+        let meta = if has_meta {
+            Some(FileMetaTable::from_reader(&mut src).context(ParseMetaDataSetSnafu)?)
+        } else {
+            None
+        };
 
-        let ts_uid = meta.transfer_syntax();
-        // read rest of data according to metadata, feed it to object
-        if let Some(ts) = ts_index.get(ts_uid) {
-            let mut options = DataSetReaderOptions::default();
-            options.odd_length = odd_length;
-            options.charset_override = charset_override;
-
-            let obj = match ts.codec() {
-                Codec::Dataset(Some(adapter)) => {
-                    let adapter = adapter.adapt_reader(Box::new(src));
-                    let mut dataset = DataSetReader::new_with_ts_options(adapter, ts, options)
-                        .context(CreateParserSnafu)?;
-                    InMemDicomObject::build_object(
-                        &mut dataset,
-                        dict,
-                        false,
-                        Length::UNDEFINED,
-                        read_until,
-                        read_to,
-                    )?
-                }
-                Codec::Dataset(None) => {
-                    if ts_uid == uids::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN
-                        || ts_uid == uids::JPIP_REFERENCED_DEFLATE
-                        || ts_uid == uids::JPIPHTJ2K_REFERENCED_DEFLATE
-                    {
-                        return ReadUnsupportedTransferSyntaxWithSuggestionSnafu {
-                            uid: ts.uid(),
-                            name: ts.name(),
-                            feature_name: "dicom-transfer-syntax-registry/deflate",
-                        }
-                        .fail();
+        let ts_uid = meta
+            .as_ref()
+            .map_or(uids::IMPLICIT_VR_LITTLE_ENDIAN, |meta| {
+                meta.transfer_syntax()
+            });
+        let Some(mut ts) = ts_index.get(ts_uid) else {
+            return ReadUnrecognizedTransferSyntaxSnafu {
+                uid: ts_uid.to_string(),
+            }
+            .fail();
+        };
+
+        if lenient && matches!(ts.codec(), Codec::None | Codec::EncapsulatedPixelData(..)) {
+            let detected = detect_encoding(src.fill_buf().context(ReadDataSetStartSnafu)?);
+            if let Some(detected) = detected.filter(|e| *e != DataSetEncoding::of(ts)) {
+                if let Some(found) = detected
+                    .native_transfer_syntax()
+                    .and_then(|uid| ts_index.get(uid))
+                {
+                    if has_meta {
+                        report.warnings.push(ReadWarning::TransferSyntaxMismatch {
+                            declared: ts.uid().to_string(),
+                            used: found.uid().to_string(),
+                        });
                     }
+                    ts = found;
+                }
+            }
+        }
+        if !has_meta {
+            report.warnings.push(ReadWarning::MissingFileMetaGroup {
+                transfer_syntax: ts.uid().to_string(),
+            });
+        }
 
-                    return ReadUnsupportedTransferSyntaxSnafu {
+        // read rest of data according to metadata, feed it to object
+        let mut options = DataSetReaderOptions::default();
+        options.odd_length = odd_length;
+        options.charset_override = charset_override;
+
+        let mut obj = match ts.codec() {
+            Codec::Dataset(Some(adapter)) => {
+                let adapter = adapter.adapt_reader(Box::new(src));
+                let mut dataset = DataSetReader::new_with_ts_options(adapter, ts, options)
+                    .context(CreateParserSnafu)?
+                    .skip_elements(skip);
+                let mut tokens =
+                    StopCondition::new(&mut dataset, read_until, read_to, max_bytes, lenient);
+                let obj = InMemDicomObject::build_object(
+                    &mut tokens,
+                    dict,
+                    false,
+                    Length::UNDEFINED,
+                    read_until,
+                    read_to,
+                )?;
+                tokens.finish(report);
+                report.skipped = dataset.skipped_tags().to_vec();
+                obj
+            }
+            Codec::Dataset(None) => {
+                if ts_uid == uids::DEFLATED_EXPLICIT_VR_LITTLE_ENDIAN
+                    || ts_uid == uids::JPIP_REFERENCED_DEFLATE
+                    || ts_uid == uids::JPIPHTJ2K_REFERENCED_DEFLATE
+                {
+                    return ReadUnsupportedTransferSyntaxWithSuggestionSnafu {
                         uid: ts.uid(),
                         name: ts.name(),
+                        feature_name: "dicom-transfer-syntax-registry/deflate",
                     }
                     .fail();
                 }
-                Codec::None | Codec::EncapsulatedPixelData(..) => {
-                    let mut dataset = DataSetReader::new_with_ts_options(src, ts, options)
-                        .context(CreateParserSnafu)?;
-                    InMemDicomObject::build_object(
-                        &mut dataset,
-                        dict,
-                        false,
-                        Length::UNDEFINED,
-                        read_until,
-                        read_to,
-                    )?
-                }
-            };
 
-            // if Media Storage SOP Class UID is empty attempt to infer from SOP Class UID
-            if meta.media_storage_sop_class_uid().is_empty() {
-                if let Some(elem) = obj.get(tags::SOP_CLASS_UID) {
-                    meta.media_storage_sop_class_uid = elem
-                        .value()
-                        .to_str()
-                        .context(ParseSopAttributeSnafu)?
-                        .to_string();
+                return ReadUnsupportedTransferSyntaxSnafu {
+                    uid: ts.uid(),
+                    name: ts.name(),
                 }
+                .fail();
+            }
+            Codec::None | Codec::EncapsulatedPixelData(..) => {
+                let mut dataset = DataSetReader::new_with_ts_options(src, ts, options)
+                    .context(CreateParserSnafu)?
+                    .skip_elements(skip);
+                let mut tokens =
+                    StopCondition::new(&mut dataset, read_until, read_to, max_bytes, lenient);
+                let obj = InMemDicomObject::build_object(
+                    &mut tokens,
+                    dict,
+                    false,
+                    Length::UNDEFINED,
+                    read_until,
+                    read_to,
+                )?;
+                tokens.finish(report);
+                report.skipped = dataset.skipped_tags().to_vec();
+                obj
+            }
+        };
+        if !ts.is_explicit_vr() {
+            obj.mark_vr_inferred();
+        }
+
+        let mut meta = match meta {
+            Some(meta) => meta,
+            None => FileMetaTableBuilder::new()
+                .transfer_syntax(ts.uid())
+                .media_storage_sop_class_uid("")
+                .media_storage_sop_instance_uid("")
+                .build()
+                .context(ParseMetaDataSetSnafu)?,
+        };
+
+        let mut inferred = !has_meta;
+
+        // if Media Storage SOP Class UID is empty attempt to infer from SOP Class UID
+        if meta.media_storage_sop_class_uid().is_empty() {
+            if let Some(elem) = obj.get(tags::SOP_CLASS_UID) {
+                meta.media_storage_sop_class_uid = elem
+                    .value()
+                    .to_str()
+                    .context(ParseSopAttributeSnafu)?
+                    .to_string();
+                inferred = true;
+            }
+        }
+
+        // if Media Storage SOP Instance UID is empty attempt to infer from SOP Instance UID
+        if meta.media_storage_sop_instance_uid().is_empty() {
+            if let Some(elem) = obj.get(tags::SOP_INSTANCE_UID) {
+                meta.media_storage_sop_instance_uid = elem
+                    .value()
+                    .to_str()
+                    .context(ParseSopAttributeSnafu)?
+                    .to_string();
+                inferred = true;
             }
+        }
+
+        if inferred {
+            meta.update_information_group_length();
+        }
+
+        Ok(FileDicomObject { meta, obj })
+    }
+
+    /// Write the entire object as a DICOM file into the given writer,
+    /// reproducing the original encoding of the data set where possible.
+    ///
+    /// Preamble, magic code, and file meta group will be included
+    /// before the inner object.
+    /// The data set is written in the transfer syntax
+    /// declared in the file meta table,
+    /// as described in
+    /// [`write_dataset_exact_with_ts`](InMemDicomObject::write_dataset_exact_with_ts).
+    /// Unlike [`write_all`](FileDicomObject::write_all),
+    /// unmodified elements are kept byte for byte as they were read,
+    /// which is necessary for preserving digital signatures.
+    pub fn write_exact_to(&self, to: impl Write) -> Result<(), WriteError> {
+        let mut to = BufWriter::new(to);
+
+        // write preamble
+        to.write_all(&[0_u8; 128][..]).context(WritePreambleSnafu)?;
+
+        // write magic sequence
+        to.write_all(b"DICM").context(WriteMagicCodeSnafu)?;
+
+        // write meta group
+        self.meta.write(&mut to).context(PrintMetaDataSetSnafu)?;
+
+        let ts_uid = self.meta.transfer_syntax();
+        let ts =
+            TransferSyntaxRegistry
+                .get(ts_uid)
+                .context(WriteUnrecognizedTransferSyntaxSnafu {
+                    uid: ts_uid.to_string(),
+                })?;
+        self.obj.write_dataset_exact_with_ts(&mut to, ts)
+    }
+}
+
+/// The data set writer options for reproducing the original encoding.
+fn exact_writer_options() -> DataSetWriterOptions {
+    DataSetWriterOptions::default()
+        .explicit_length_sq_item_strategy(ExplicitLengthSqItemStrategy::NoChange)
+        .preserve_odd_lengths(true)
+}
+
+/// A writer which only counts the number of bytes written.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Modify the value of an element in place,
+/// keeping the value length recorded in its header.
+fn update_keeping_length<D>(
+    elem: &mut InMemElement<D>,
+    f: impl FnOnce(&mut Value<InMemDicomObject<D>, InMemFragment>),
+) {
+    let placeholder = DataElement::empty(elem.tag(), elem.vr());
+    let (header, mut value) = std::mem::replace(elem, placeholder).into_parts();
+    f(&mut value);
+    *elem = DataElement::new_with_len(header.tag, header.vr, header.len, value);
+}
+
+/// An element with a text value,
+/// compared and hashed by its header and text,
+/// so that identical elements can share their memory.
+struct TextElement<D>(Arc<InMemElement<D>>);
+
+impl<D> PartialEq for TextElement<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.header() == other.0.header()
+            && self.0.value().primitive() == other.0.value().primitive()
+    }
+}
+
+impl<D> Eq for TextElement<D> {}
+
+impl<D> std::hash::Hash for TextElement<D> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let header = self.0.header();
+        header.tag.hash(state);
+        header.vr.hash(state);
+        header.len.0.hash(state);
+        self.0.value().to_str().ok().hash(state);
+    }
+}
+
+/// Describe an error along with all of its sources in a single line.
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut text = e.to_string();
+    let mut source = e.source();
+    while let Some(e) = source {
+        text.push_str(": ");
+        text.push_str(&e.to_string());
+        source = e.source();
+    }
+    text
+}
+
+/// Data set token iterator adapter
+/// which ends the root data set at the first element
+/// not satisfying the given stop conditions,
+/// while keeping track of where it stopped.
+///
+/// In lenient mode,
+/// it also records the deviations from the standard found along the way,
+/// and ends the data set gracefully at the first reading error,
+/// closing all open sequences and items.
+struct StopCondition<'a, S> {
+    dataset: &'a mut DataSetReader<S>,
+    read_until: Option<Tag>,
+    read_to: Option<Tag>,
+    max_bytes: Option<u64>,
+    /// the current sequence nesting level
+    depth: u32,
+    /// the tag of the root element at which reading stopped
+    stopped_at: Option<Tag>,
+    /// the state of lenient reading, if enabled
+    lenient: Option<Lenient>,
+}
+
+/// The state of lenient data set reading.
+#[derive(Debug, Default)]
+struct Lenient {
+    warnings: Vec<ReadWarning>,
+    /// the tags of the elements found in the root data set
+    root: BTreeSet<Tag>,
+    /// the sequences and items open at the current position
+    open: Vec<OpenLevel>,
+    /// the tokens to produce before reading more
+    queue: VecDeque<DataToken>,
+    /// whether the data set ended early
+    ended: bool,
+}
+
+/// A sequence or item open while reading in lenient mode.
+#[derive(Debug)]
+enum OpenLevel {
+    Sequence,
+    /// an item, with the tags of the elements found in it
+    Item(BTreeSet<Tag>),
+}
 
-            // if Media Storage SOP Instance UID is empty attempt to infer from SOP Instance UID
-            if meta.media_storage_sop_instance_uid().is_empty() {
-                if let Some(elem) = obj.get(tags::SOP_INSTANCE_UID) {
-                    meta.media_storage_sop_instance_uid = elem
-                        .value()
-                        .to_str()
-                        .context(ParseSopAttributeSnafu)?
-                        .to_string();
+impl Lenient {
+    /// Record the deviations in a token
+    /// and keep track of the data set structure.
+    fn observe(&mut self, token: &DataToken) {
+        let tag = match token {
+            DataToken::ElementHeader(header) => {
+                if let Some(len) = header.len.get().filter(|len| len % 2 == 1) {
+                    self.warnings.push(ReadWarning::OddLength {
+                        tag: header.tag,
+                        len,
+                    });
+                }
+                Some(header.tag)
+            }
+            DataToken::SequenceStart { tag, len } => {
+                let not_a_sequence = StandardDataDictionary
+                    .by_tag(*tag)
+                    .is_some_and(|entry| entry.vr() != VirtualVr::Exact(VR::SQ));
+                if len.is_undefined() && not_a_sequence {
+                    self.warnings
+                        .push(ReadWarning::UndefinedLength { tag: *tag });
                 }
+                Some(*tag)
+            }
+            DataToken::PixelSequenceStart => Some(tags::PIXEL_DATA),
+            _ => None,
+        };
+        if let Some(tag) = tag {
+            let seen = match self.open.last_mut() {
+                Some(OpenLevel::Item(seen)) => seen,
+                _ => &mut self.root,
+            };
+            if !seen.insert(tag) {
+                self.warnings.push(ReadWarning::DuplicateElement { tag });
+            }
+        }
+        match token {
+            DataToken::SequenceStart { .. } | DataToken::PixelSequenceStart => {
+                self.open.push(OpenLevel::Sequence)
+            }
+            DataToken::ItemStart { .. } => self.open.push(OpenLevel::Item(BTreeSet::new())),
+            DataToken::ItemEnd | DataToken::SequenceEnd => {
+                self.open.pop();
             }
+            _ => {}
+        }
+    }
 
-            Ok(FileDicomObject { meta, obj })
-        } else {
-            ReadUnrecognizedTransferSyntaxSnafu {
-                uid: ts_uid.to_string(),
+    /// End the data set early,
+    /// queuing the tokens closing all open sequences and items.
+    fn end(&mut self, tag: Option<Tag>, reason: String) {
+        self.warnings
+            .push(ReadWarning::UnexpectedEnd { tag, reason });
+        self.queue.clear();
+        while let Some(level) = self.open.pop() {
+            self.queue.push_back(match level {
+                OpenLevel::Sequence => DataToken::SequenceEnd,
+                OpenLevel::Item(_) => DataToken::ItemEnd,
+            });
+        }
+        self.ended = true;
+    }
+}
+
+impl<'a, S> StopCondition<'a, S> {
+    fn new(
+        dataset: &'a mut DataSetReader<S>,
+        read_until: Option<Tag>,
+        read_to: Option<Tag>,
+        max_bytes: Option<u64>,
+        lenient: bool,
+    ) -> Self {
+        StopCondition {
+            dataset,
+            read_until,
+            read_to,
+            max_bytes,
+            depth: 0,
+            stopped_at: None,
+            lenient: lenient.then(Lenient::default),
+        }
+    }
+
+    /// Record where reading stopped and the warnings found in the report.
+    fn finish(self, report: &mut ReadReport) {
+        report.stopped_at = self.stopped_at;
+        if let Some(lenient) = self.lenient {
+            report.warnings.extend(lenient.warnings);
+        }
+    }
+}
+
+impl<S> StopCondition<'_, S>
+where
+    S: StatefulDecode,
+{
+    /// Fetch the next token from the data set,
+    /// ending it gracefully on failure in lenient mode.
+    fn next_token(&mut self, tag: Option<Tag>) -> Option<ParserResult<DataToken>> {
+        let Some(lenient) = &mut self.lenient else {
+            return self.dataset.next();
+        };
+        match self.dataset.next() {
+            Some(Ok(token)) => Some(Ok(token)),
+            Some(Err(e)) => {
+                lenient.end(tag, error_chain(&e));
+                lenient.queue.pop_front().map(Ok)
+            }
+            None if tag.is_some() || !lenient.open.is_empty() => {
+                lenient.end(tag, "unexpected end of data".to_string());
+                lenient.queue.pop_front().map(Ok)
+            }
+            None => None,
+        }
+    }
+}
+
+impl<S> Iterator for StopCondition<'_, S>
+where
+    S: StatefulDecode,
+{
+    type Item = ParserResult<DataToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped_at.is_some() {
+            return None;
+        }
+        if let Some(lenient) = &mut self.lenient {
+            if let Some(token) = lenient.queue.pop_front() {
+                return Some(Ok(token));
+            }
+            if lenient.ended {
+                return None;
+            }
+        }
+        let token = match self.next_token(None)? {
+            Ok(token) => token,
+            Err(e) => return Some(Err(e)),
+        };
+        if self.lenient.as_ref().is_some_and(|lenient| lenient.ended) {
+            return Some(Ok(token));
+        }
+        let element = match &token {
+            DataToken::ElementHeader(header) => Some((header.tag, header.len)),
+            DataToken::SequenceStart { tag, len } => Some((*tag, *len)),
+            DataToken::PixelSequenceStart => Some((tags::PIXEL_DATA, Length::UNDEFINED)),
+            _ => None,
+        };
+        if let (Some((tag, len)), 0) = (element, self.depth) {
+            // the position right after the element header
+            let position = self.dataset.decoder_position();
+            let stop = self.read_until.is_some_and(|t| t <= tag)
+                || self.read_to.is_some_and(|t| t < tag)
+                || self
+                    .max_bytes
+                    .is_some_and(|max| position + u64::from(len.get().unwrap_or(0)) > max);
+            if stop {
+                self.stopped_at = Some(tag);
+                return None;
+            }
+        }
+        match token {
+            DataToken::SequenceStart { .. } | DataToken::PixelSequenceStart => self.depth += 1,
+            DataToken::SequenceEnd => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        if let Some(lenient) = &mut self.lenient {
+            lenient.observe(&token);
+            if let DataToken::ElementHeader(header) = &token {
+                // read the value right away,
+                // so that the element is discarded as a whole if it is truncated
+                match self.next_token(Some(header.tag))? {
+                    Ok(value) if self.lenient.as_ref().is_some_and(|l| !l.ended) => {
+                        if let Some(lenient) = &mut self.lenient {
+                            lenient.queue.push_back(value);
+                        }
+                    }
+                    // the element was discarded
+                    Ok(token) => return Some(Ok(token)),
+                    Err(e) => return Some(Err(e)),
+                }
             }
-            .fail()
         }
+        Some(Ok(token))
     }
 }
 
@@ -686,6 +1369,8 @@ impl FileDicomObject<InMemDicomObject<StandardDataDictionary>> {
                 dict: StandardDataDictionary,
                 len: Length::UNDEFINED,
                 charset_changed: false,
+                original_encoding: BTreeMap::new(),
+                modifications: None,
             },
         }
     }
@@ -703,20 +1388,33 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         }
     }
 
+    /// Obtain a reference to the data dictionary of this object.
+    #[cfg(feature = "xml")]
+    pub(crate) fn dictionary(&self) -> &D {
+        &self.dict
+    }
+
     /// Construct a DICOM object from an iterator of structured elements.
     pub fn from_element_source_with_dict<I>(iter: I, dict: D) -> Result<Self>
     where
         I: IntoIterator<Item = Result<InMemElement<D>>>,
     {
-        let entries: Result<_> = iter.into_iter().map_ok(|e| (e.tag(), e)).collect();
+        let entries: Result<_> = iter
+            .into_iter()
+            .map_ok(|e| (e.tag(), Arc::new(e)))
+            .collect();
         Ok(InMemDicomObject {
             entries: entries?,
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         })
     }
 
@@ -725,12 +1423,14 @@ where
     where
         I: IntoIterator<Item = InMemElement<D>>,
     {
-        let entries = iter.into_iter().map(|e| (e.tag(), e)).collect();
+        let entries = iter.into_iter().map(|e| (e.tag(), Arc::new(e))).collect();
         InMemDicomObject {
             entries,
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         }
     }
 
@@ -754,13 +1454,17 @@ where
                     calculated_length += if l.is_defined() { even_len(l.0) } else { 0 } + 8;
                 }
 
-                (e.tag(), e)
+                (e.tag(), Arc::new(e))
             })
             .collect();
 
         entries.insert(
             Tag(0, 0),
-            InMemElement::new(Tag(0, 0), VR::UL, PrimitiveValue::from(calculated_length)),
+            Arc::new(InMemElement::new(
+                Tag(0, 0),
+                VR::UL,
+                PrimitiveValue::from(calculated_length),
+            )),
         );
 
         InMemDicomObject {
@@ -768,6 +1472,8 @@ where
             dict,
             len: Length::UNDEFINED,
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         }
     }
 
@@ -864,6 +1570,12 @@ where
                 )
             }
         }
+        .map(|mut obj| {
+            if !ts.is_explicit_vr() {
+                obj.mark_vr_inferred();
+            }
+            obj
+        })
     }
 
     // Standard methods follow. They are not placed as a trait implementation
@@ -878,6 +1590,7 @@ where
     pub fn element(&self, tag: Tag) -> Result<&InMemElement<D>> {
         self.entries
             .get(&tag)
+            .map(|e| &**e)
             .context(NoSuchDataElementTagSnafu { tag })
     }
 
@@ -896,6 +1609,7 @@ where
         let tag = self.lookup_name(name)?;
         self.entries
             .get(&tag)
+            .map(|e| &**e)
             .with_context(|| NoSuchDataElementAliasSnafu {
                 tag,
                 alias: name.to_string(),
@@ -918,7 +1632,7 @@ where
     /// If the element does not exist,
     /// `None` is returned.
     pub fn get(&self, tag: Tag) -> Option<&InMemElement<D>> {
-        self.entries.get(&tag)
+        self.entries.get(&tag).map(|e| &**e)
     }
 
     // Get a mutable reference to a particular DICOM attribute from this object by tag.
@@ -926,7 +1640,18 @@ where
     // Should be private as it would allow a user to change the tag of an
     // element and diverge from the dictionary
     fn get_mut(&mut self, tag: Tag) -> Option<&mut InMemElement<D>> {
-        self.entries.get_mut(&tag)
+        self.entries.get_mut(&tag).map(Arc::make_mut)
+    }
+
+    /// Record the current version of an attribute about to be modified,
+    /// if modifications are tracked
+    /// and the attribute was not modified before.
+    fn record_original(&mut self, tag: Tag) {
+        if let Some(modifications) = &mut self.modifications {
+            modifications
+                .entry(tag)
+                .or_insert_with(|| self.entries.get(&tag).cloned());
+        }
     }
 
     /// Retrieve a particular DICOM element that might not exist by its name.
@@ -1034,7 +1759,10 @@ where
     pub fn put_element(&mut self, elt: InMemElement<D>) -> Option<InMemElement<D>> {
         self.len = Length::UNDEFINED;
         self.invalidate_if_charset_changed(elt.tag());
-        self.entries.insert(elt.tag(), elt)
+        self.record_original(elt.tag());
+        self.entries
+            .insert(elt.tag(), Arc::new(elt))
+            .map(Arc::unwrap_or_clone)
     }
 
     /// Insert a private element into the dataset, replacing (and returning) any
@@ -1124,21 +1852,72 @@ where
         self.put_element(DataElement::new(tag, vr, string.into()))
     }
 
-    /// Remove a DICOM element by its tag,
-    /// reporting whether it was present.
-    pub fn remove_element(&mut self, tag: Tag) -> bool {
-        if self.entries.remove(&tag).is_some() {
-            self.len = Length::UNDEFINED;
-            true
-        } else {
-            false
-        }
-    }
-
-    /// Remove a DICOM element by its keyword,
-    /// reporting whether it was present.
+    /// Insert an _Icon Image Sequence_ holding a single preview image,
+    /// replacing (and returning) any previous icon image.
+    ///
+    /// `pixels` must contain the 8-bit samples of the icon,
+    /// `rows` × `columns` × `samples_per_pixel` bytes interleaved by pixel.
+    /// The icon is described as _MONOCHROME2_ for one sample per pixel,
+    /// or as _RGB_ otherwise.
+    /// DICOM recommends icons of no more than 128 × 128 pixels.
+    pub fn put_icon_image(
+        &mut self,
+        rows: u16,
+        columns: u16,
+        samples_per_pixel: u16,
+        pixels: Vec<u8>,
+    ) -> Option<InMemElement<D>> {
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let mut icon = InMemDicomObject::new_empty_with_dict(self.dict.clone());
+        icon.put(us(tags::SAMPLES_PER_PIXEL, samples_per_pixel));
+        icon.put_str(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            if samples_per_pixel == 1 {
+                "MONOCHROME2"
+            } else {
+                "RGB"
+            },
+        );
+        if samples_per_pixel > 1 {
+            icon.put(us(tags::PLANAR_CONFIGURATION, 0));
+        }
+        icon.put(us(tags::ROWS, rows));
+        icon.put(us(tags::COLUMNS, columns));
+        icon.put(us(tags::BITS_ALLOCATED, 8));
+        icon.put(us(tags::BITS_STORED, 8));
+        icon.put(us(tags::HIGH_BIT, 7));
+        icon.put(us(tags::PIXEL_REPRESENTATION, 0));
+        icon.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(pixels),
+        ));
+
+        self.put_element(DataElement::new(
+            tags::ICON_IMAGE_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![icon]),
+        ))
+    }
+
+    /// Remove a DICOM element by its tag,
+    /// reporting whether it was present.
+    pub fn remove_element(&mut self, tag: Tag) -> bool {
+        self.record_original(tag);
+        if self.entries.remove(&tag).is_some() {
+            self.len = Length::UNDEFINED;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove a DICOM element by its keyword,
+    /// reporting whether it was present.
     pub fn remove_element_by_name(&mut self, name: &str) -> Result<bool, AccessByNameError> {
         let tag = self.lookup_name(name)?;
+        self.record_original(tag);
         Ok(self.entries.remove(&tag).is_some()).inspect(|removed| {
             if *removed {
                 self.len = Length::UNDEFINED;
@@ -1148,8 +1927,10 @@ where
 
     /// Remove and return a particular DICOM element by its tag.
     pub fn take_element(&mut self, tag: Tag) -> Result<InMemElement<D>> {
+        self.record_original(tag);
         self.entries
             .remove(&tag)
+            .map(Arc::unwrap_or_clone)
             .inspect(|_e| {
                 self.len = Length::UNDEFINED;
             })
@@ -1160,9 +1941,13 @@ where
     /// if it is present,
     /// returns `None` otherwise.
     pub fn take(&mut self, tag: Tag) -> Option<InMemElement<D>> {
-        self.entries.remove(&tag).inspect(|_e| {
-            self.len = Length::UNDEFINED;
-        })
+        self.record_original(tag);
+        self.entries
+            .remove(&tag)
+            .map(Arc::unwrap_or_clone)
+            .inspect(|_e| {
+                self.len = Length::UNDEFINED;
+            })
     }
 
     /// Remove and return a particular DICOM element by its name.
@@ -1171,8 +1956,10 @@ where
         name: &str,
     ) -> Result<InMemElement<D>, AccessByNameError> {
         let tag = self.lookup_name(name)?;
+        self.record_original(tag);
         self.entries
             .remove(&tag)
+            .map(Arc::unwrap_or_clone)
             .inspect(|_e| {
                 self.len = Length::UNDEFINED;
             })
@@ -1188,8 +1975,251 @@ where
     /// The elements are visited in ascending tag order,
     /// and those for which `f(&element)` returns `false` are removed.
     pub fn retain(&mut self, mut f: impl FnMut(&InMemElement<D>) -> bool) {
-        self.entries.retain(|_, elem| f(elem));
+        let modifications = &mut self.modifications;
+        self.entries.retain(|tag, elem| {
+            let keep = f(elem);
+            if let (false, Some(modifications)) = (keep, &mut *modifications) {
+                modifications
+                    .entry(*tag)
+                    .or_insert_with(|| Some(elem.clone()));
+            }
+            keep
+        });
+        self.len = Length::UNDEFINED;
+    }
+
+    /// Release the memory reserved but not in use
+    /// by the values of this object,
+    /// including those nested in its sequences.
+    ///
+    /// Elements shared with clones of this object are left as they are,
+    /// so that they remain shared.
+    /// The values and their encoding are not changed.
+    pub fn shrink_to_fit(&mut self) {
+        for elem in self.entries.values_mut() {
+            let Some(elem) = Arc::get_mut(elem) else {
+                continue;
+            };
+            update_keeping_length(elem, |value| match value {
+                Value::Primitive(value) => value.shrink_to_fit(),
+                Value::Sequence(seq) => {
+                    for item in seq.items_mut().iter_mut() {
+                        item.shrink_to_fit();
+                    }
+                    seq.items_mut().shrink_to_fit();
+                }
+                Value::PixelSequence(seq) => {
+                    seq.offset_table_mut().shrink_to_fit();
+                    for fragment in seq.fragments_mut().iter_mut() {
+                        fragment.shrink_to_fit();
+                    }
+                    seq.fragments_mut().shrink_to_fit();
+                }
+            });
+        }
+    }
+
+    /// Make identical text elements throughout this object share their memory,
+    /// so that each distinct element is held in memory only once.
+    ///
+    /// This greatly reduces the memory used by objects
+    /// repeating the same values in many sequence items,
+    /// such as the UIDs in the per-frame functional groups
+    /// of enhanced multi-frame objects.
+    /// Elements are identical if they have the same tag, VR, length and text.
+    /// As with clones of the object,
+    /// an element stops being shared once it is modified.
+    /// Sequences shared with clones of this object are copied in the process,
+    /// but their elements remain shared.
+    ///
+    /// Returns the number of elements which now share the memory of another one.
+    pub fn deduplicate_values(&mut self) -> usize {
+        self.deduplicate_values_with(&mut HashSet::new())
+    }
+
+    fn deduplicate_values_with(&mut self, seen: &mut HashSet<TextElement<D>>) -> usize {
+        let mut count = 0;
+        for elem in self.entries.values_mut() {
+            match elem.value() {
+                Value::Primitive(PrimitiveValue::Str(_) | PrimitiveValue::Strs(_)) => {
+                    match seen.get(&TextElement(Arc::clone(elem))) {
+                        Some(TextElement(shared)) if !Arc::ptr_eq(shared, elem) => {
+                            *elem = Arc::clone(shared);
+                            count += 1;
+                        }
+                        Some(_) => {}
+                        None => {
+                            seen.insert(TextElement(Arc::clone(elem)));
+                        }
+                    }
+                }
+                Value::Sequence(_) => {
+                    update_keeping_length(Arc::make_mut(elem), |value| {
+                        if let Value::Sequence(seq) = value {
+                            for item in seq.items_mut().iter_mut() {
+                                count += item.deduplicate_values_with(seen);
+                            }
+                        }
+                    });
+                }
+                _ => {}
+            }
+        }
+        count
+    }
+
+    /// Start recording the previous versions
+    /// of the attributes modified or removed from now on,
+    /// so that they can be kept in the _Original Attributes Sequence_
+    /// (see [`record_original_attributes`](Self::record_original_attributes)).
+    ///
+    /// Only the attributes at the root of this object are tracked:
+    /// a change inside a sequence records the sequence as a whole.
+    /// Nothing changes if modifications are already tracked.
+    pub fn track_modifications(&mut self) {
+        self.modifications.get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Whether modifications of this object are being tracked.
+    pub fn is_tracking_modifications(&self) -> bool {
+        self.modifications.is_some()
+    }
+
+    /// Iterate over the attributes modified since modifications are tracked,
+    /// or since original attributes were last recorded,
+    /// in ascending tag order.
+    ///
+    /// Each item is the tag of the attribute
+    /// and its previous version,
+    /// or `None` if the attribute was absent before.
+    /// Attributes which were changed back to their previous value
+    /// are left out.
+    pub fn modified_attributes(&self) -> impl Iterator<Item = (Tag, Option<&InMemElement<D>>)> {
+        self.modifications
+            .iter()
+            .flatten()
+            .filter(|(tag, original)| {
+                **tag != tags::ORIGINAL_ATTRIBUTES_SEQUENCE
+                    && match (original, self.entries.get(tag)) {
+                        (Some(original), Some(current)) => {
+                            original.vr() != current.vr() || original.value() != current.value()
+                        }
+                        (None, None) => false,
+                        _ => true,
+                    }
+            })
+            .map(|(tag, original)| (*tag, original.as_deref()))
+    }
+
+    /// Record the previous versions of the modified attributes
+    /// in a new item of the _Original Attributes Sequence_ (0400,0561),
+    /// along with the details of the modification,
+    /// then continue tracking modifications from the current state.
+    ///
+    /// Attributes which were absent before being added
+    /// are recorded with an empty value.
+    /// This should be called once all changes are made
+    /// and before the object is saved.
+    ///
+    /// Returns `true` if an item was added,
+    /// or `false` if no attribute was modified
+    /// or modifications are not tracked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::{DataElement, VR};
+    /// # use dicom_dictionary_std::tags;
+    /// # use dicom_object::InMemDicomObject;
+    /// use dicom_object::{AttributeModification, ModificationReason};
+    ///
+    /// let mut obj = InMemDicomObject::from_element_iter([
+    ///     DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+    /// ]);
+    /// obj.track_modifications();
+    /// obj.put_str(tags::PATIENT_ID, VR::LO, "ANON0001");
+    ///
+    /// let modification = AttributeModification::new(ModificationReason::Coerce, "ANONYMIZER");
+    /// assert!(obj.record_original_attributes(&modification));
+    ///
+    /// let items = obj.get(tags::ORIGINAL_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap();
+    /// let modified = items[0].get(tags::MODIFIED_ATTRIBUTES_SEQUENCE).unwrap().items().unwrap();
+    /// assert_eq!(modified[0].get(tags::PATIENT_ID).unwrap().to_str()?, "12345");
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn record_original_attributes(&mut self, modification: &AttributeModification) -> bool {
+        let originals: Vec<_> = self
+            .modified_attributes()
+            .map(|(tag, original)| match original {
+                Some(original) => original.clone(),
+                None => DataElement::empty(tag, self.entries[&tag].vr()),
+            })
+            .collect();
+        let Some(modifications) = &mut self.modifications else {
+            return false;
+        };
+        modifications.clear();
+        if originals.is_empty() {
+            return false;
+        }
+
+        let now = dicom_core::chrono::Local::now().format("%Y%m%d%H%M%S%.6f%z");
+        let mut item = InMemDicomObject::from_iter_with_dict(
+            [
+                DataElement::new(
+                    tags::MODIFIED_ATTRIBUTES_SEQUENCE,
+                    VR::SQ,
+                    DataSetSequence::from(vec![InMemDicomObject::from_iter_with_dict(
+                        originals,
+                        self.dict.clone(),
+                    )]),
+                ),
+                DataElement::new(
+                    tags::ATTRIBUTE_MODIFICATION_DATE_TIME,
+                    VR::DT,
+                    PrimitiveValue::from(now.to_string()),
+                ),
+                DataElement::new(
+                    tags::MODIFYING_SYSTEM,
+                    VR::LO,
+                    PrimitiveValue::from(modification.modifying_system.as_str()),
+                ),
+                DataElement::new(
+                    tags::REASON_FOR_THE_ATTRIBUTE_MODIFICATION,
+                    VR::CS,
+                    PrimitiveValue::from(modification.reason.code()),
+                ),
+            ],
+            self.dict.clone(),
+        );
+        item.put(match &modification.source_of_previous_values {
+            Some(source) => DataElement::new(
+                tags::SOURCE_OF_PREVIOUS_VALUES,
+                VR::LO,
+                PrimitiveValue::from(source.as_str()),
+            ),
+            None => DataElement::empty(tags::SOURCE_OF_PREVIOUS_VALUES, VR::LO),
+        });
+
+        // append to the existing sequence, if any
+        let mut items = match self.entries.remove(&tags::ORIGINAL_ATTRIBUTES_SEQUENCE) {
+            Some(e) => match Arc::unwrap_or_clone(e).into_value() {
+                Value::Sequence(seq) => seq.into_items().into_vec(),
+                _ => Vec::new(),
+            },
+            None => Vec::new(),
+        };
+        items.push(item);
+        self.entries.insert(
+            tags::ORIGINAL_ATTRIBUTES_SEQUENCE,
+            Arc::new(DataElement::new(
+                tags::ORIGINAL_ATTRIBUTES_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(items),
+            )),
+        );
         self.len = Length::UNDEFINED;
+        true
     }
 
     /// Obtain a temporary mutable reference to a DICOM value by tag,
@@ -1225,7 +2255,8 @@ where
         f: impl FnMut(&mut Value<InMemDicomObject<D>, InMemFragment>),
     ) -> bool {
         self.invalidate_if_charset_changed(tag);
-        if let Some(e) = self.entries.get_mut(&tag) {
+        self.record_original(tag);
+        if let Some(e) = self.get_mut(tag) {
             e.update_value(f);
             self.len = Length::UNDEFINED;
             true
@@ -1288,6 +2319,8 @@ where
         selector: impl Into<AttributeSelector>,
         f: impl FnMut(&mut Value<InMemDicomObject<D>, InMemFragment>),
     ) -> Result<(), AtAccessError> {
+        let selector: AttributeSelector = selector.into();
+        self.record_original(root_tag(&selector));
         self.entry_at_mut(selector)
             .map(|e| e.update_value(f))
             .map(|_| {
@@ -1369,7 +2402,114 @@ where
         unreachable!()
     }
 
+    /// Insert a DICOM value at the position described by the given selector,
+    /// creating any missing intermediate sequences and items on the way.
+    ///
+    /// Items missing before the selected item index are created empty.
+    /// The value representation of a new element
+    /// is taken from the data element dictionary,
+    /// whereas the value representation of an existing element is kept,
+    /// unless the new value is a data set sequence.
+    /// Returns the previous value of the element, if it existed.
+    ///
+    /// Returns an error if any of the intermediate steps
+    /// is an element other than a data set sequence.
+    ///
+    /// See the documentation of [`AttributeSelector`] for more information
+    /// on how to write attribute selectors.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::PrimitiveValue;
+    /// # use dicom_dictionary_std::tags;
+    /// # use dicom_object::InMemDicomObject;
+    /// let mut obj = InMemDicomObject::new_empty();
+    /// // creates the sequence and its second functional group item
+    /// let selector = (
+    ///     tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+    ///     1,
+    ///     tags::FRAME_CONTENT_SEQUENCE,
+    ///     tags::STACK_ID,
+    /// );
+    /// obj.put_at(selector, PrimitiveValue::from("1"))?;
+    ///
+    /// assert_eq!(obj.value_at(selector)?.to_str()?, "1");
+    /// assert_eq!(
+    ///     obj.get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE).unwrap().items().unwrap().len(),
+    ///     2,
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn put_at(
+        &mut self,
+        selector: impl Into<AttributeSelector>,
+        value: impl Into<Value<InMemDicomObject<D>, InMemFragment>>,
+    ) -> Result<Option<Value<InMemDicomObject<D>, InMemFragment>>, AtAccessError> {
+        let selector: AttributeSelector = selector.into();
+        let value = value.into();
+        let dict = self.dict.clone();
+        self.record_original(root_tag(&selector));
+
+        let mut obj = self;
+        for (i, step) in selector.iter().enumerate() {
+            match step {
+                // reached the leaf
+                AttributeSelectorStep::Tag(tag) => {
+                    let vr = if matches!(value, Value::Sequence(_)) {
+                        VR::SQ
+                    } else if let Some(e) = obj.entries.get(tag) {
+                        e.vr()
+                    } else {
+                        dict.by_tag(*tag)
+                            .and_then(|entry| entry.vr().exact())
+                            .unwrap_or(VR::UN)
+                    };
+                    let old = obj.put(DataElement::new(*tag, vr, value));
+                    return Ok(old.map(|e| e.into_value()));
+                }
+                // navigate further down, creating what is missing
+                AttributeSelectorStep::Nested { tag, item } => {
+                    if !obj.entries.contains_key(tag) {
+                        let vr = dict
+                            .by_tag(*tag)
+                            .and_then(|entry| entry.vr().exact())
+                            .unwrap_or(VR::SQ);
+                        ensure!(
+                            vr == VR::SQ || vr == VR::UN,
+                            NotASequenceSnafu {
+                                selector: selector.clone(),
+                                step_index: i as u32,
+                            }
+                        );
+                        obj.put(DataElement::new(*tag, VR::SQ, DataSetSequence::empty()));
+                    }
+                    obj.len = Length::UNDEFINED;
+
+                    let items = obj
+                        .get_mut(*tag)
+                        .expect("sequence element should exist at this point")
+                        .items_mut()
+                        .with_context(|| NotASequenceSnafu {
+                            selector: selector.clone(),
+                            step_index: i as u32,
+                        })?;
+
+                    while items.len() <= *item as usize {
+                        items.push(InMemDicomObject::new_empty_with_dict(dict.clone()));
+                    }
+                    obj = &mut items[*item as usize];
+                }
+            }
+        }
+
+        unreachable!()
+    }
+
     /// Change the 'specific_character_set' tag to ISO_IR 192, marking the dataset as UTF-8
+    ///
+    /// See [`convert_character_set`](Self::convert_character_set)
+    /// for a conversion which also covers nested data sets.
     pub fn convert_to_utf8(&mut self) {
         self.put(DataElement::new(
             tags::SPECIFIC_CHARACTER_SET,
@@ -1378,6 +2518,110 @@ where
         ));
     }
 
+    /// Convert the text of this object to the given specific character set.
+    ///
+    /// All text values which depend on the character set,
+    /// including those in nested data set sequences,
+    /// are checked to be representable in the new character set,
+    /// and _Specific Character Set_ (0008,0005) is updated
+    /// in the data set and in any sequence item which declares its own.
+    /// Selecting the default character repertoire removes the attribute.
+    /// Since text is kept decoded in memory,
+    /// the values will be encoded with the new character set
+    /// the next time the object is written.
+    ///
+    /// If any value cannot be represented in the new character set,
+    /// an error is returned and the object is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::{DataElement, VR};
+    /// # use dicom_dictionary_std::tags;
+    /// # use dicom_encoding::text::SpecificCharacterSet;
+    /// # use dicom_object::InMemDicomObject;
+    /// let mut obj = InMemDicomObject::from_element_iter([
+    ///     DataElement::new(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 144"),
+    ///     DataElement::new(tags::PATIENT_NAME, VR::PN, "Иванков^Андрей"),
+    /// ]);
+    /// obj.convert_character_set(SpecificCharacterSet::ISO_IR_192)?;
+    /// assert_eq!(obj.get(tags::SPECIFIC_CHARACTER_SET).unwrap().to_str()?, "ISO_IR 192");
+    ///
+    /// // not representable in Latin alphabet no. 1
+    /// assert!(obj.convert_character_set(SpecificCharacterSet::ISO_IR_100).is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn convert_character_set(
+        &mut self,
+        charset: SpecificCharacterSet,
+    ) -> Result<(), ConvertCharacterSetError> {
+        self.check_character_set(&charset)?;
+        self.apply_character_set(&charset, true);
+        Ok(())
+    }
+
+    fn check_character_set(
+        &self,
+        charset: &SpecificCharacterSet,
+    ) -> Result<(), ConvertCharacterSetError> {
+        for elem in self.entries.values() {
+            match elem.value() {
+                Value::Primitive(value) if is_charset_dependent(elem.vr()) => {
+                    for text in value.to_multi_str().iter() {
+                        charset.encode(text).context(UnrepresentableTextSnafu {
+                            tag: elem.tag(),
+                            charset: charset.name(),
+                        })?;
+                    }
+                }
+                Value::Sequence(seq) => {
+                    for item in seq.items() {
+                        item.check_character_set(charset)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_character_set(&mut self, charset: &SpecificCharacterSet, root: bool) {
+        // items only declare a character set if they override the parent's
+        if root || self.entries.contains_key(&tags::SPECIFIC_CHARACTER_SET) {
+            if *charset == SpecificCharacterSet::default() {
+                self.remove_element(tags::SPECIFIC_CHARACTER_SET);
+            } else {
+                let codes: C<String> = charset.name().split('\\').map(String::from).collect();
+                self.put(DataElement::new(
+                    tags::SPECIFIC_CHARACTER_SET,
+                    VR::CS,
+                    PrimitiveValue::Strs(codes),
+                ));
+            }
+        }
+        // the encoded lengths of the text values are no longer known
+        let entries = &self.entries;
+        self.original_encoding.retain(|tag, encoding| {
+            *tag != tags::SPECIFIC_CHARACTER_SET
+                && !(is_charset_dependent(encoding.vr())
+                    && entries
+                        .get(tag)
+                        .is_some_and(|e| e.value().primitive().is_some()))
+        });
+        self.charset_changed = true;
+        self.len = Length::UNDEFINED;
+        for elem in self.entries.values_mut() {
+            if elem.value().items().is_none() {
+                continue;
+            }
+            if let Some(items) = Arc::make_mut(elem).items_mut() {
+                for item in items {
+                    item.apply_character_set(charset, false);
+                }
+            }
+        }
+    }
+
     /// Get a DataElement by AttributeSelector
     ///
     /// If the element or other intermediate elements do not exist, the method will return an error.
@@ -1452,13 +2696,12 @@ where
                 }
                 // navigate further down
                 AttributeSelectorStep::Nested { tag, item } => {
-                    let e =
-                        obj.entries
-                            .get_mut(tag)
-                            .with_context(|| crate::MissingSequenceSnafu {
-                                selector: selector.clone(),
-                                step_index: i as u32,
-                            })?;
+                    let e = obj
+                        .get_mut(*tag)
+                        .with_context(|| crate::MissingSequenceSnafu {
+                            selector: selector.clone(),
+                            step_index: i as u32,
+                        })?;
 
                     // get items
                     let items = e.items_mut().with_context(|| NotASequenceSnafu {
@@ -1522,6 +2765,7 @@ where
     fn apply(&mut self, op: AttributeOp) -> ApplyResult {
         let AttributeOp { selector, action } = op;
         let dict = self.dict.clone();
+        self.record_original(root_tag(&selector));
 
         let mut obj = self;
         for (i, step) in selector.iter().enumerate() {
@@ -1556,8 +2800,7 @@ where
 
                     // get items
                     let items = obj
-                        .entries
-                        .get_mut(tag)
+                        .get_mut(*tag)
                         .expect("sequence element should exist at this point")
                         .items_mut()
                         .ok_or_else(|| ApplyError::NotASequence {
@@ -1594,13 +2837,13 @@ where
                 if let Some(e) = self.entries.get_mut(&tag) {
                     let vr = e.vr();
                     // replace element
-                    *e = DataElement::empty(tag, vr);
+                    *e = Arc::new(DataElement::empty(tag, vr));
                     self.len = Length::UNDEFINED;
                 }
                 Ok(())
             }
             AttributeAction::SetVr(new_vr) => {
-                if let Some(e) = self.entries.remove(&tag) {
+                if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
                     let (header, value) = e.into_parts();
                     let e = DataElement::new(header.tag, new_vr, value);
                     self.put(e);
@@ -1671,7 +2914,7 @@ where
             } else {
                 Value::from(new_value)
             };
-            *e = DataElement::new(tag, vr, new_value);
+            *e = Arc::new(DataElement::new(tag, vr, new_value));
             self.len = Length::UNDEFINED;
         } else {
             // infer VR from tag
@@ -1700,7 +2943,7 @@ where
     }
 
     fn apply_push_str_impl(&mut self, tag: Tag, string: Cow<'static, str>) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1734,7 +2977,7 @@ where
     }
 
     fn apply_push_i32_impl(&mut self, tag: Tag, integer: i32) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1767,7 +3010,7 @@ where
     }
 
     fn apply_push_u32_impl(&mut self, tag: Tag, integer: u32) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1800,7 +3043,7 @@ where
     }
 
     fn apply_push_i16_impl(&mut self, tag: Tag, integer: i16) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1833,7 +3076,7 @@ where
     }
 
     fn apply_push_u16_impl(&mut self, tag: Tag, integer: u16) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1866,7 +3109,7 @@ where
     }
 
     fn apply_push_f32_impl(&mut self, tag: Tag, number: f32) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -1899,7 +3142,7 @@ where
     }
 
     fn apply_push_f64_impl(&mut self, tag: Tag, number: f64) -> ApplyResult {
-        if let Some(e) = self.entries.remove(&tag) {
+        if let Some(e) = self.entries.remove(&tag).map(Arc::unwrap_or_clone) {
             let (header, value) = e.into_parts();
             match value {
                 Value::Primitive(mut v) => {
@@ -2035,6 +3278,7 @@ where
         dset_writer
             .write_sequence(self.into_tokens_with_options(required_options))
             .context(PrintDataSetSnafu)?;
+        dset_writer.flush().context(PrintDataSetSnafu)?;
 
         Ok(())
     }
@@ -2075,6 +3319,153 @@ where
         self.write_dataset_with_ts_cs_options(to, ts, SpecificCharacterSet::default(), options)
     }
 
+    /// Write this object's data set into the given writer,
+    /// with the specified transfer syntax,
+    /// reproducing the original encoding of its elements where possible.
+    ///
+    /// Elements which were read from a source and were not modified since
+    /// are written with the same lengths as they had originally,
+    /// including odd value lengths
+    /// and explicit lengths of sequences and items.
+    /// Sequences and items whose size in bytes changed in the meantime
+    /// are written with undefined length instead,
+    /// and new or modified values are padded as usual.
+    ///
+    /// When the transfer syntax is the same as the one of the source,
+    /// the output for an unmodified object is byte-identical to the data set read.
+    /// Note that the file meta group is not part of the data set.
+    pub fn write_dataset_exact_with_ts<W>(
+        &self,
+        to: W,
+        ts: &TransferSyntax,
+    ) -> Result<(), WriteError>
+    where
+        W: Write,
+    {
+        let cs = self.declared_charset().unwrap_or_default();
+        let exact = self.to_exact_form(ts, &cs)?;
+        let options = exact_writer_options();
+
+        if let Codec::Dataset(Some(adapter)) = ts.codec() {
+            let adapter = adapter.adapt_writer(Box::new(to));
+            let mut dset_writer =
+                DataSetWriter::with_ts_options(adapter, ts, options).context(CreatePrinterSnafu)?;
+            dset_writer
+                .write_sequence((&exact).into_tokens())
+                .context(PrintDataSetSnafu)?;
+            dset_writer.flush().context(PrintDataSetSnafu)?;
+        } else {
+            let mut dset_writer =
+                DataSetWriter::with_ts_options(to, ts, options).context(CreatePrinterSnafu)?;
+            dset_writer
+                .write_sequence((&exact).into_tokens())
+                .context(PrintDataSetSnafu)?;
+            dset_writer.flush().context(PrintDataSetSnafu)?;
+        }
+
+        Ok(())
+    }
+
+    /// Obtain a copy of this object
+    /// in which lengths which cannot be reproduced exactly are normalized:
+    /// odd value lengths not originating from the source are made even,
+    /// and sequence and item lengths which no longer match their content
+    /// are made undefined.
+    fn to_exact_form(
+        &self,
+        ts: &TransferSyntax,
+        cs: &SpecificCharacterSet,
+    ) -> Result<Self, WriteError> {
+        let mut entries = BTreeMap::new();
+        for (tag, elem) in &self.entries {
+            let header = *elem.header();
+            let original = self
+                .original_encoding
+                .get(tag)
+                .is_some_and(|e| e.matches(&header));
+            let elem = match elem.value() {
+                Value::Primitive(value) => match header.len.get() {
+                    // not as in the source, ensure that it is padded
+                    Some(len) if len % 2 == 1 && !original => Arc::new(InMemElement::new_with_len(
+                        *tag,
+                        header.vr,
+                        Length(len + 1),
+                        value.clone(),
+                    )),
+                    _ => elem.clone(),
+                },
+                Value::Sequence(seq) => {
+                    let mut items = seq
+                        .items()
+                        .iter()
+                        .map(|item| item.to_exact_form(ts, cs))
+                        .collect::<Result<C<_>, _>>()?;
+                    let mut seq_len = 0;
+                    for item in &mut items {
+                        let item_len = item.exact_byte_len(ts, cs)?;
+                        if item.len.get().is_some_and(|len| u64::from(len) != item_len) {
+                            item.len = Length::UNDEFINED;
+                        }
+                        // item header, plus item delimiter if undefined
+                        seq_len += item_len + if item.len.is_defined() { 8 } else { 16 };
+                    }
+                    let len = if header
+                        .len
+                        .get()
+                        .is_some_and(|len| u64::from(len) == seq_len)
+                    {
+                        header.len
+                    } else {
+                        Length::UNDEFINED
+                    };
+                    Arc::new(InMemElement::new_with_len(
+                        *tag,
+                        VR::SQ,
+                        len,
+                        Value::Sequence(DataSetSequence::new(items, len)),
+                    ))
+                }
+                Value::PixelSequence(_) => elem.clone(),
+            };
+            entries.insert(*tag, elem);
+        }
+
+        Ok(InMemDicomObject {
+            entries,
+            dict: self.dict.clone(),
+            len: self.len,
+            charset_changed: false,
+            original_encoding: self.original_encoding.clone(),
+            modifications: None,
+        })
+    }
+
+    /// Calculate the number of bytes of this object (in exact form)
+    /// when encoded with the given transfer syntax and character set.
+    fn exact_byte_len(
+        &self,
+        ts: &TransferSyntax,
+        cs: &SpecificCharacterSet,
+    ) -> Result<u64, WriteError> {
+        let mut counter = ByteCounter(0);
+        let mut dset_writer =
+            DataSetWriter::with_ts_cs_options(&mut counter, ts, cs.clone(), exact_writer_options())
+                .context(CreatePrinterSnafu)?;
+        dset_writer
+            .write_sequence(self.into_tokens())
+            .context(PrintDataSetSnafu)?;
+        drop(dset_writer);
+        Ok(counter.0)
+    }
+
+    /// Retrieve the character set declared by _Specific Character Set_,
+    /// if present and supported.
+    fn declared_charset(&self) -> Option<SpecificCharacterSet> {
+        let elem = self.get(tags::SPECIFIC_CHARACTER_SET)?;
+        let codes = elem.value().to_multi_str().ok()?;
+        SpecificCharacterSet::from_codes(codes.iter())
+    }
+
     /// Encapsulate this object to contain a file meta group
     /// as described exactly by the given table.
     ///
@@ -2148,10 +3539,66 @@ where
         self.entries.keys().copied()
     }
 
+    /// Check the value multiplicity of every element in this object,
+    /// including those in nested sequence items,
+    /// against the object's data dictionary.
+    ///
+    /// Returns all violations found, in element order,
+    /// where each element is followed by the violations in its items.
+    /// See [`DataElement::validate_vm`] for the elements which are checked.
+    pub fn validate_vm(&self) -> Vec<VmViolation> {
+        let mut violations = Vec::new();
+        self.validate_vm_into(&mut violations);
+        violations
+    }
+
+    fn validate_vm_into(&self, violations: &mut Vec<VmViolation>) {
+        for elem in self.entries.values() {
+            if let Err(violation) = elem.validate_vm(&self.dict) {
+                violations.push(violation);
+            }
+            if let Value::Sequence(seq) = elem.value() {
+                for item in seq.items() {
+                    item.validate_vm_into(violations);
+                }
+            }
+        }
+    }
+
+    /// Retrieve the encoding details of the element with the given tag,
+    /// as it was originally read from a data source.
+    ///
+    /// Returns `None` if the element was not read from a source,
+    /// such as when it was inserted afterwards.
+    /// Note that these details are not updated when an element is modified.
+    pub fn original_encoding(&self, tag: Tag) -> Option<&ElementEncoding> {
+        self.original_encoding.get(&tag)
+    }
+
+    /// Mark the value representations of all elements read from the source
+    /// as inferred, recursively.
+    pub(crate) fn mark_vr_inferred(&mut self) {
+        for encoding in self.original_encoding.values_mut() {
+            encoding.vr_inferred = true;
+        }
+        for elem in self.entries.values_mut() {
+            if elem.items().is_none() {
+                continue;
+            }
+            update_keeping_length(Arc::make_mut(elem), |value| {
+                if let Value::Sequence(seq) = value {
+                    for item in seq.items_mut() {
+                        item.mark_vr_inferred();
+                    }
+                }
+            });
+        }
+    }
+
     // private methods
 
     /// Build an object by consuming a data set parser.
-    fn build_object<I>(
+    pub(crate) fn build_object<I>(
         dataset: &mut I,
         dict: D,
         in_item: bool,
@@ -2162,7 +3609,8 @@ where
     where
         I: ?Sized + Iterator<Item = ParserResult<DataToken>>,
     {
-        let mut entries: BTreeMap<Tag, InMemElement<D>> = BTreeMap::new();
+        let mut entries: BTreeMap<Tag, Arc<InMemElement<D>>> = BTreeMap::new();
+        let mut original_encoding: BTreeMap<Tag, ElementEncoding> = BTreeMap::new();
         // perform a structured parsing of incoming tokens
         while let Some(token) = dataset.next() {
             let elem = match token.context(ReadTokenSnafu)? {
@@ -2189,6 +3637,8 @@ where
                         break;
                     }
 
+                    original_encoding.insert(header.tag, ElementEncoding::from_header(&header));
+
                     // fetch respective value, place it in the entries
                     let next_token = dataset.next().context(MissingElementValueSnafu)?;
                     match next_token.context(ReadTokenSnafu)? {
@@ -2213,6 +3663,11 @@ where
                         break;
                     }
 
+                    original_encoding.insert(
+                        tag,
+                        ElementEncoding::from_header(&DataElementHeader::new(tag, VR::SQ, len)),
+                    );
+
                     // delegate sequence building to another function
                     let items = Self::build_sequence(tag, len, &mut *dataset, &dict)?;
                     DataElement::new_with_len(
@@ -2229,11 +3684,13 @@ where
                         dict,
                         len,
                         charset_changed: false,
+                        original_encoding,
+                        modifications: None,
                     });
                 }
                 token => return UnexpectedTokenSnafu { token }.fail(),
             };
-            entries.insert(elem.tag(), elem);
+            entries.insert(elem.tag(), Arc::new(elem));
         }
 
         Ok(InMemDicomObject {
@@ -2241,6 +3698,8 @@ where
             dict,
             len,
             charset_changed: false,
+            original_encoding,
+            modifications: None,
         })
     }
 
@@ -2259,6 +3718,9 @@ where
         let mut offset_table = None;
 
         let mut fragments = C::new();
+        // the length of the current item
+        let mut item_len = Length::UNDEFINED;
+        let mut truncated = false;
 
         for token in dataset {
             match token.context(ReadTokenSnafu)? {
@@ -2266,6 +3728,10 @@ where
                     offset_table = Some(table);
                 }
                 DataToken::ItemValue(data) => {
+                    // the data source ended in the middle of the fragment
+                    truncated = item_len
+                        .get()
+                        .is_some_and(|len| (data.len() as u64) < u64::from(len));
                     fragments.push(data);
                 }
                 DataToken::ItemEnd => {
@@ -2276,7 +3742,9 @@ where
                         offset_table = Some(Vec::new())
                     }
                 }
-                DataToken::ItemStart { len: _ } => { /* no-op */ }
+                DataToken::ItemStart { len } => {
+                    item_len = len;
+                }
                 DataToken::SequenceEnd => {
                     // end of pixel data
                     break;
@@ -2291,10 +3759,9 @@ where
             }
         }
 
-        Ok(Value::PixelSequence(PixelFragmentSequence::new(
-            offset_table.unwrap_or_default(),
-            fragments,
-        )))
+        let mut value = PixelFragmentSequence::new(offset_table.unwrap_or_default(), fragments);
+        value.set_truncated(truncated);
+        Ok(Value::PixelSequence(value))
     }
 
     /// Build a DICOM sequence by consuming a data set parser.
@@ -2339,6 +3806,51 @@ where
     }
 }
 
+impl<D> InMemDicomObject<D> {
+    /// Get the private creator which reserved the block of the given private attribute.
+    ///
+    /// Returns `None` if the tag is not of a private data element
+    /// or the object does not have the respective private creator element.
+    pub fn private_creator(&self, tag: Tag) -> Option<Cow<'_, str>> {
+        let block = tag.element() >> 8;
+        if tag.group() % 2 == 0 || block == 0 {
+            return None;
+        }
+        let creator = self.entries.get(&Tag(tag.group(), block))?.to_str().ok()?;
+        Some(match creator {
+            Cow::Borrowed(c) => Cow::Borrowed(c.trim_end_matches(['\0', ' '])),
+            Cow::Owned(c) => Cow::Owned(c.trim_end_matches(['\0', ' ']).to_string()),
+        })
+    }
+
+    /// Look up the given private attribute
+    /// in the [private dictionary registry](dicom_dictionary_std::private::registry),
+    /// using the private creator found in this object.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # use dicom_core::{DataElement, VR, PrimitiveValue, Tag};
+    /// # use dicom_dictionary_std::private::{self, PrivateDictionary};
+    /// # use dicom_object::InMemDicomObject;
+    /// private::registry().register(PrivateDictionary::from_tsv(
+    ///     "(0019,\"GEMS_ACQU_01\",0c)\tSS\tGEFrequencyDirection\t1\n".as_bytes(),
+    /// )?);
+    ///
+    /// let ds = InMemDicomObject::from_element_iter([
+    ///     DataElement::new(Tag(0x0019, 0x0010), VR::LO, "GEMS_ACQU_01"),
+    ///     DataElement::new(Tag(0x0019, 0x100c), VR::SS, PrimitiveValue::from(1_i16)),
+    /// ]);
+    /// let entry = ds.private_entry(Tag(0x0019, 0x100c)).unwrap();
+    /// assert_eq!(entry.alias, "GEFrequencyDirection");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn private_entry(&self, tag: Tag) -> Option<PrivateDictionaryEntry> {
+        let creator = self.private_creator(tag)?;
+        private::registry().by_tag(&creator, tag)
+    }
+}
+
 impl<D> ApplyOp for InMemDicomObject<D>
 where
     D: DataDictionary,
@@ -2354,14 +3866,57 @@ where
 
 impl<'a, D> IntoIterator for &'a InMemDicomObject<D> {
     type Item = &'a InMemElement<D>;
-    type IntoIter = ::std::collections::btree_map::Values<'a, Tag, InMemElement<D>>;
+    type IntoIter = Elements<'a, D>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.entries.values()
+        Elements {
+            inner: self.entries.values(),
+        }
+    }
+}
+
+/// Iterator over the elements of an in-memory DICOM object by reference.
+#[derive(Debug)]
+pub struct Elements<'a, D> {
+    inner: ::std::collections::btree_map::Values<'a, Tag, Arc<InMemElement<D>>>,
+}
+
+impl<D> Clone for Elements<'_, D> {
+    fn clone(&self) -> Self {
+        Elements {
+            inner: self.inner.clone(),
+        }
     }
 }
 
-impl<D> IntoIterator for InMemDicomObject<D> {
+impl<'a, D> Iterator for Elements<'a, D> {
+    type Item = &'a InMemElement<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|e| &**e)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+
+    fn count(self) -> usize {
+        self.inner.count()
+    }
+}
+
+impl<D> DoubleEndedIterator for Elements<'_, D> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|e| &**e)
+    }
+}
+
+impl<D> ExactSizeIterator for Elements<'_, D> {}
+
+impl<D> IntoIterator for InMemDicomObject<D>
+where
+    D: Clone,
+{
     type Item = InMemElement<D>;
     type IntoIter = Iter<D>;
 
@@ -2375,14 +3930,18 @@ impl<D> IntoIterator for InMemDicomObject<D> {
 /// Base iterator type for an in-memory DICOM object.
 #[derive(Debug)]
 pub struct Iter<D> {
-    inner: ::std::collections::btree_map::IntoIter<Tag, InMemElement<D>>,
+    inner: ::std::collections::btree_map::IntoIter<Tag, Arc<InMemElement<D>>>,
 }
 
-impl<D> Iterator for Iter<D> {
+impl<D> Iterator for Iter<D>
+where
+    D: Clone,
+{
     type Item = InMemElement<D>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|x| x.1)
+        // elements still shared with other objects are copied
+        self.inner.next().map(|x| Arc::unwrap_or_clone(x.1))
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -2400,7 +3959,20 @@ impl<D> Extend<InMemElement<D>> for InMemDicomObject<D> {
         I: IntoIterator<Item = InMemElement<D>>,
     {
         self.len = Length::UNDEFINED;
-        self.entries.extend(iter.into_iter().map(|e| (e.tag(), e)))
+        for elem in iter {
+            let tag = elem.tag();
+            let previous = self.entries.insert(tag, Arc::new(elem));
+            if let Some(modifications) = &mut self.modifications {
+                modifications.entry(tag).or_insert(previous);
+            }
+        }
+    }
+}
+
+/// The tag of the root data set element targeted by an attribute selector.
+fn root_tag(selector: &AttributeSelector) -> Tag {
+    match selector.first_step() {
+        AttributeSelectorStep::Tag(tag) | AttributeSelectorStep::Nested { tag, .. } => *tag,
     }
 }
 
@@ -2411,7 +3983,7 @@ fn even_len(l: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{DicomAttribute as _, open_file};
+    use crate::{DicomAttribute as _, OpenFileOptions, open_file};
     use byteordered::Endianness;
     use dicom_core::chrono::FixedOffset;
     use dicom_core::value::{DicomDate, DicomDateTime, DicomTime};
@@ -2585,6 +4157,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn round_trip_native_transfer_syntaxes() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::MODALITY, VR::CS, "OT"),
+            DataElement::new(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "1.2.3.4"),
+                ])]),
+            ),
+            DataElement::new(
+                tags::DATA_COLLECTION_CENTER_PATIENT,
+                VR::FD,
+                PrimitiveValue::F64([1.5, -2., 3.25][..].into()),
+            ),
+            DataElement::new(
+                tags::FRAME_INCREMENT_POINTER,
+                VR::AT,
+                PrimitiveValue::from(Tag(0x0018, 0x1063)),
+            ),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(2_u16)),
+            // raw bytes in values of multi-byte words
+            DataElement::new(
+                tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+                VR::OW,
+                PrimitiveValue::from(vec![1_u8, 2, 3, 4]),
+            ),
+            DataElement::new(
+                tags::FLOAT_PIXEL_DATA,
+                VR::OF,
+                PrimitiveValue::from(vec![1_u8, 2, 3, 4, 5, 6, 7, 8]),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OW,
+                PrimitiveValue::U16([0x0102, 0x0304].into()),
+            ),
+        ]);
+
+        for uid in [
+            uids::IMPLICIT_VR_LITTLE_ENDIAN,
+            uids::EXPLICIT_VR_LITTLE_ENDIAN,
+            // Explicit VR Big Endian
+            "1.2.840.10008.1.2.2",
+        ] {
+            let ts = TransferSyntaxRegistry.get(uid).unwrap();
+            let mut out = Vec::new();
+            obj.write_dataset_with_ts(&mut out, ts).unwrap();
+            let read = InMemDicomObject::read_dataset_with_ts(&out[..], ts).unwrap();
+
+            assert_eq!(
+                read.tags().collect::<Vec<_>>(),
+                obj.tags().collect::<Vec<_>>(),
+                "{uid}"
+            );
+            for elem in &obj {
+                let tag = elem.tag();
+                let read_elem = read.get(tag).unwrap();
+                if elem.items().is_some() {
+                    let uid_of = |elem: &InMemElement| {
+                        elem.items().unwrap()[0]
+                            .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                            .unwrap()
+                            .to_str()
+                            .unwrap()
+                            .into_owned()
+                    };
+                    assert_eq!(uid_of(read_elem), uid_of(elem), "{uid} {tag}");
+                } else {
+                    assert_eq!(
+                        read_elem.value().to_bytes().unwrap(),
+                        elem.value().to_bytes().unwrap(),
+                        "{uid} {tag}"
+                    );
+                }
+            }
+
+            if ts.endianness() == Endianness::Big {
+                assert_eq!(&out[out.len() - 4..], [0x01, 0x02, 0x03, 0x04]);
+            }
+        }
+    }
+
     #[test]
     fn inmem_object_write_dataset_encapsulated_pixel_data() {
         let mut obj = InMemDicomObject::new_empty();
@@ -2665,6 +4321,76 @@ mod tests {
         );
     }
 
+    /// converting an object to UTF-8
+    /// re-encodes text at all levels and updates Specific Character Set
+    #[test]
+    fn inmem_object_convert_character_set() {
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 144"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Иванков^Андрей"),
+        ]);
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SPECIFIC_CHARACTER_SET, VR::CS, "ISO_IR 100"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Simões^João"),
+            DataElement::new(
+                tags::OTHER_PATIENT_I_DS_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+        ]);
+
+        // Cyrillic cannot be represented in Latin-1, nothing changes
+        let original = obj.clone();
+        assert!(
+            obj.convert_character_set(SpecificCharacterSet::from_code("ISO_IR 100").unwrap())
+                .is_err()
+        );
+        assert_obj_eq(&obj, &original);
+
+        obj.convert_character_set(SpecificCharacterSet::ISO_IR_192)
+            .unwrap();
+        assert_eq!(
+            obj.get(tags::SPECIFIC_CHARACTER_SET)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "ISO_IR 192"
+        );
+        let item = &obj
+            .get(tags::OTHER_PATIENT_I_DS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            item.get(tags::SPECIFIC_CHARACTER_SET)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "ISO_IR 192"
+        );
+
+        let ts = TransferSyntaxRegistry
+            .get(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .unwrap();
+        let mut out = Vec::new();
+        obj.write_dataset_with_ts(&mut out, ts).unwrap();
+        let simoes = "Simões^João".as_bytes();
+        assert!(out.windows(simoes.len()).any(|w| w == simoes));
+        let ivankov = "Иванков^Андрей".as_bytes();
+        assert!(out.windows(ivankov.len()).any(|w| w == ivankov));
+
+        let read_obj = InMemDicomObject::read_dataset_with_ts(&out[..], ts).unwrap();
+        let item = &read_obj
+            .get(tags::OTHER_PATIENT_I_DS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            item.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Иванков^Андрей"
+        );
+    }
+
     /// writing a DICOM date time into an object
     /// should include value padding
     #[test]
@@ -2877,25 +4603,303 @@ mod tests {
                 .unwrap(),
         );
 
-        // create temporary file path and write object to that file
-        let dir = tempfile::tempdir().unwrap();
-        let mut file_path = dir.keep();
-        file_path.push(format!("{sop_instance_uid}.dcm"));
+        // create temporary file path and write object to that file
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_path = dir.keep();
+        file_path.push(format!("{sop_instance_uid}.dcm"));
+
+        file_object.write_to_file(&file_path).unwrap();
+
+        // read the file back to validate the outcome
+        let saved_object = open_file(file_path).unwrap();
+
+        // verify that the empty string media storage sop instance and class UIDs have been inferred from the sop instance and class UID
+        assert_eq!(
+            saved_object.meta().media_storage_sop_instance_uid(),
+            sop_instance_uid
+        );
+        assert_eq!(
+            saved_object.meta().media_storage_sop_class_uid(),
+            sop_class_uid
+        );
+    }
+
+    /// Gzip-compressed files are decompressed while opening.
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn open_gzip_compressed_file() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, "1.2.840.10008.5.1.4.1.1.7"),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.6625071548706"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![7_u8; 64]),
+            ),
+        ]);
+        let file_object = obj
+            .with_meta(FileMetaTableBuilder::default().transfer_syntax("1.2.840.10008.1.2.1"))
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        file_object.write_all(&mut bytes).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("object.dcm.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            File::create(&file_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(&bytes).unwrap();
+        encoder.finish().unwrap();
+
+        let saved_object = open_file(&file_path).unwrap();
+        assert_eq!(saved_object.meta(), file_object.meta());
+        assert_eq!(
+            saved_object
+                .element(tags::PATIENT_NAME)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Doe^John"
+        );
+        assert_eq!(
+            &*saved_object
+                .element(tags::PIXEL_DATA)
+                .unwrap()
+                .to_bytes()
+                .unwrap(),
+            &[7_u8; 64][..]
+        );
+    }
+
+    #[test]
+    fn read_skipping_tags_and_groups() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, "1.2.840.10008.5.1.4.1.1.7"),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.6625071548706"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(Tag(0x6000, 0x0010), VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(
+                Tag(0x6000, 0x3000),
+                VR::OB,
+                PrimitiveValue::from(vec![1_u8; 8]),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![7_u8; 64]),
+            ),
+            DataElement::new(
+                tags::DATA_SET_TRAILING_PADDING,
+                VR::OB,
+                PrimitiveValue::from(vec![0_u8; 2]),
+            ),
+        ]);
+        let file_object = obj
+            .with_meta(FileMetaTableBuilder::default().transfer_syntax("1.2.840.10008.1.2.1"))
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        file_object.write_all(&mut bytes).unwrap();
+
+        let obj = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .skip_tags(&[tags::PIXEL_DATA])
+            .skip_groups(&[0x6000])
+            .from_reader(&bytes[..])
+            .unwrap();
+
+        assert_eq!(
+            obj.element(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        assert!(obj.element_opt(tags::PIXEL_DATA).unwrap().is_none());
+        assert!(obj.element_opt(Tag(0x6000, 0x0010)).unwrap().is_none());
+        assert!(obj.element_opt(Tag(0x6000, 0x3000)).unwrap().is_none());
+        // reading continues after skipped elements
+        assert!(
+            obj.element_opt(tags::DATA_SET_TRAILING_PADDING)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn read_with_stop_conditions_and_report() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, "1.2.840.10008.5.1.4.1.1.7"),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.6625071548706"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![7_u8; 64]),
+            ),
+            DataElement::new(
+                tags::DATA_SET_TRAILING_PADDING,
+                VR::OB,
+                PrimitiveValue::from(vec![0_u8; 2]),
+            ),
+        ]);
+        let file_object = obj
+            .with_meta(FileMetaTableBuilder::default().transfer_syntax("1.2.840.10008.1.2.1"))
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        file_object.write_all(&mut bytes).unwrap();
+
+        // skip pixel data, continue reading after it
+        let (obj, report) = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .skip_pixel_data(true)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert!(obj.element_opt(tags::PIXEL_DATA).unwrap().is_none());
+        assert!(
+            obj.element_opt(tags::DATA_SET_TRAILING_PADDING)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(report.skipped, vec![tags::PIXEL_DATA]);
+        assert_eq!(report.stopped_at, None);
+        assert!(!report.is_complete());
+
+        // stop before pixel data
+        let (obj, report) = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .read_until(tags::PIXEL_DATA)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert!(obj.element_opt(tags::ROWS).unwrap().is_some());
+        assert!(obj.element_opt(tags::PIXEL_DATA).unwrap().is_none());
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.stopped_at, Some(tags::PIXEL_DATA));
+
+        // stop once the pixel data would exceed the byte limit:
+        // SOP Class UID (8 + 26), SOP Instance UID (8 + 18),
+        // Patient Name (8 + 8), Rows (8 + 2)
+        let (obj, report) = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .stop_after_bytes(100)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert!(obj.element_opt(tags::ROWS).unwrap().is_some());
+        assert!(obj.element_opt(tags::PIXEL_DATA).unwrap().is_none());
+        assert_eq!(report.stopped_at, Some(tags::PIXEL_DATA));
+
+        let (obj, report) = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .stop_after_bytes(80)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert!(obj.element_opt(tags::PATIENT_NAME).unwrap().is_some());
+        assert!(obj.element_opt(tags::ROWS).unwrap().is_none());
+        assert_eq!(report.stopped_at, Some(tags::ROWS));
+
+        let (_, report) = OpenFileOptions::new()
+            .read_preamble(ReadPreamble::Always)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert!(report.is_complete());
+    }
 
-        file_object.write_to_file(&file_path).unwrap();
+    #[test]
+    fn read_leniently() {
+        use crate::file::ReadWarning;
 
-        // read the file back to validate the outcome
-        let saved_object = open_file(file_path).unwrap();
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+            .media_storage_sop_instance_uid("2.25.1")
+            .build()
+            .unwrap();
+        let mut bytes = b"DICM".to_vec();
+        meta.write(&mut bytes).unwrap();
+        // implicit VR little endian, despite the declared transfer syntax
+        bytes.extend_from_slice(b"\x10\x00\x10\x00\x08\x00\x00\x00Doe^John");
+        // duplicate element
+        bytes.extend_from_slice(b"\x10\x00\x10\x00\x08\x00\x00\x00Roe^Jane");
+        // odd length
+        bytes.extend_from_slice(b"\x10\x00\x20\x00\x05\x00\x00\x0012345");
+        // truncated value
+        bytes.extend_from_slice(b"\x10\x00\x00\x40\x14\x00\x00\x00None");
+
+        assert!(OpenFileOptions::new().from_reader(&bytes[..]).is_err());
+
+        let (obj, report) = OpenFileOptions::new()
+            .lenient(true)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
+        assert_eq!(
+            obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Roe^Jane"
+        );
+        assert_eq!(
+            obj.get(tags::PATIENT_ID).unwrap().to_str().unwrap(),
+            "12345"
+        );
+        assert!(obj.get(tags::PATIENT_COMMENTS).is_none());
+        assert_eq!(report.warnings.len(), 4);
+        assert_eq!(
+            &report.warnings[..3],
+            &[
+                ReadWarning::TransferSyntaxMismatch {
+                    declared: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                    used: uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string(),
+                },
+                ReadWarning::DuplicateElement {
+                    tag: tags::PATIENT_NAME
+                },
+                ReadWarning::OddLength {
+                    tag: tags::PATIENT_ID,
+                    len: 5
+                },
+            ]
+        );
+        assert!(matches!(
+            report.warnings[3],
+            ReadWarning::UnexpectedEnd {
+                tag: Some(tags::PATIENT_COMMENTS),
+                ..
+            }
+        ));
+        assert!(!report.is_complete());
 
-        // verify that the empty string media storage sop instance and class UIDs have been inferred from the sop instance and class UID
+        // a bare data set, without magic code nor file meta group
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.2"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+        ]);
+        let mut bytes = Vec::new();
+        let ts = TransferSyntaxRegistry
+            .get(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .unwrap();
+        obj.write_dataset_with_ts(&mut bytes, ts).unwrap();
+
+        let (file, report) = OpenFileOptions::new()
+            .lenient(true)
+            .from_reader_with_report(&bytes[..])
+            .unwrap();
         assert_eq!(
-            saved_object.meta().media_storage_sop_instance_uid(),
-            sop_instance_uid
+            report.warnings,
+            [ReadWarning::MissingFileMetaGroup {
+                transfer_syntax: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+            }]
         );
         assert_eq!(
-            saved_object.meta().media_storage_sop_class_uid(),
-            sop_class_uid
+            file.meta().transfer_syntax(),
+            uids::EXPLICIT_VR_LITTLE_ENDIAN
         );
+        assert_eq!(file.meta().media_storage_sop_instance_uid(), "2.25.2");
+        assert_eq!(file.get(tags::PATIENT_NAME), obj.get(tags::PATIENT_NAME));
     }
 
     #[test]
@@ -3655,6 +5659,160 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn put_icon_image_creates_sequence() {
+        let mut obj =
+            InMemDicomObject::from_element_iter([DataElement::new(tags::MODALITY, VR::CS, "MR")]);
+
+        assert!(
+            obj.put_icon_image(2, 3, 1, vec![0, 1, 2, 3, 4, 5])
+                .is_none()
+        );
+        // replaces the previous icon
+        assert!(obj.put_icon_image(1, 2, 3, vec![0; 6]).is_some());
+
+        let items = obj.get(tags::ICON_IMAGE_SEQUENCE).unwrap().items().unwrap();
+        assert_eq!(items.len(), 1);
+        let icon = &items[0];
+        assert_eq!(icon.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 1);
+        assert_eq!(icon.get(tags::COLUMNS).unwrap().to_int::<u16>().unwrap(), 2);
+        assert_eq!(
+            icon.get(tags::PHOTOMETRIC_INTERPRETATION)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "RGB"
+        );
+        assert_eq!(
+            icon.get(tags::PLANAR_CONFIGURATION)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            &*icon.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap(),
+            &[0; 6]
+        );
+    }
+
+    #[test]
+    fn put_at_creates_sequences_and_items() {
+        let mut obj =
+            InMemDicomObject::from_element_iter([DataElement::new(tags::MODALITY, VR::CS, "MR")]);
+
+        let selector = (
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            2,
+            tags::PLANE_POSITION_SEQUENCE,
+            tags::IMAGE_POSITION_PATIENT,
+        );
+        let old = obj
+            .put_at(selector, dicom_value!(Strs, ["0", "0", "1.5"]))
+            .unwrap();
+        assert_eq!(old, None);
+
+        let items = obj
+            .get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        // missing items before the selected one are created empty
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].tags().count(), 0);
+        let e = obj
+            .entry_at((
+                tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+                2,
+                tags::PLANE_POSITION_SEQUENCE,
+                tags::IMAGE_POSITION_PATIENT,
+            ))
+            .unwrap();
+        // VR is taken from the dictionary
+        assert_eq!(e.vr(), VR::DS);
+        assert_eq!(e.to_multi_float64().unwrap(), vec![0., 0., 1.5]);
+
+        // replace the value of an existing element
+        let old = obj
+            .put_at(selector, dicom_value!(Strs, ["1", "1", "1"]))
+            .unwrap();
+        assert_eq!(old.unwrap().to_str().unwrap(), "0\\0\\1.5");
+        assert_eq!(obj.value_at(selector).unwrap().to_str().unwrap(), "1\\1\\1");
+
+        // cannot navigate into an element which is not a sequence
+        assert!(matches!(
+            obj.put_at(
+                (tags::MODALITY, tags::PATIENT_ID),
+                PrimitiveValue::from("1")
+            ),
+            Err(AtAccessError::NotASequence { step_index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn write_dataset_exact_reproduces_original_encoding() {
+        let ts = TransferSyntaxRegistry
+            .get(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .unwrap();
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            // (0008,1140) ReferencedImageSequence, explicit length 20
+            0x08, 0x00, 0x40, 0x11, b'S', b'Q', 0x00, 0x00, 0x14, 0x00, 0x00, 0x00,
+            // item, explicit length 12
+            0xfe, 0xff, 0x00, 0xe0, 0x0c, 0x00, 0x00, 0x00,
+            // (0008,1155) ReferencedSOPInstanceUID "1.2"
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x04, 0x00, b'1', b'.', b'2', 0x00,
+            // (0010,0010) PatientName "Doe", odd length
+            0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x03, 0x00, b'D', b'o', b'e',
+        ];
+        let mut obj = InMemDicomObject::read_dataset_with_ts(data, ts).unwrap();
+
+        let encoding = obj.original_encoding(tags::PATIENT_NAME).unwrap();
+        assert_eq!(encoding.vr(), VR::PN);
+        assert!(encoding.is_odd_length());
+        assert!(!encoding.is_vr_inferred());
+
+        let mut out = Vec::new();
+        obj.write_dataset_exact_with_ts(&mut out, ts).unwrap();
+        assert_eq!(out, data);
+
+        // regular writing does not preserve lengths
+        let mut out = Vec::new();
+        obj.write_dataset_with_ts(&mut out, ts).unwrap();
+        assert_ne!(out, data);
+
+        // change the size of the item,
+        // its length and the sequence length can no longer be kept
+        obj.put_at(
+            (
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                0,
+                tags::REFERENCED_SOP_INSTANCE_UID,
+            ),
+            PrimitiveValue::from("1.2.3.4"),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        obj.write_dataset_exact_with_ts(&mut out, ts).unwrap();
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            // (0008,1140) ReferencedImageSequence, undefined length
+            0x08, 0x00, 0x40, 0x11, b'S', b'Q', 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+            // item, undefined length
+            0xfe, 0xff, 0x00, 0xe0, 0xff, 0xff, 0xff, 0xff,
+            // (0008,1155) ReferencedSOPInstanceUID "1.2.3.4", padded
+            0x08, 0x00, 0x55, 0x11, b'U', b'I', 0x08, 0x00,
+            b'1', b'.', b'2', b'.', b'3', b'.', b'4', 0x00,
+            // item delimiter
+            0xfe, 0xff, 0x0d, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // sequence delimiter
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // (0010,0010) PatientName "Doe", still unmodified
+            0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x03, 0x00, b'D', b'o', b'e',
+        ];
+        assert_eq!(out, expected);
+    }
+
     /// Test that constructive operations create items if necessary.
     #[test]
     fn constructive_op() {
@@ -3857,7 +6015,7 @@ mod tests {
 
     #[test]
     fn inmem_obj_reset_defined_length() {
-        let mut entries: BTreeMap<Tag, InMemElement<StandardDataDictionary>> = BTreeMap::new();
+        let mut entries: BTreeMap<Tag, Arc<InMemElement<StandardDataDictionary>>> = BTreeMap::new();
 
         let patient_name =
             DataElement::new(tags::PATIENT_NAME, VR::CS, PrimitiveValue::from("Doe^John"));
@@ -3868,7 +6026,7 @@ mod tests {
             PrimitiveValue::from("Test study"),
         );
 
-        entries.insert(tags::PATIENT_NAME, patient_name.clone());
+        entries.insert(tags::PATIENT_NAME, Arc::new(patient_name.clone()));
 
         // create object and force an arbitrary defined Length value
         let obj = InMemDicomObject::<StandardDataDictionary> {
@@ -3876,6 +6034,8 @@ mod tests {
             dict: StandardDataDictionary,
             len: Length(1),
             charset_changed: false,
+            original_encoding: BTreeMap::new(),
+            modifications: None,
         };
 
         assert!(obj.length().is_defined());
@@ -4367,6 +6527,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn private_entries_from_registry() {
+        use dicom_dictionary_std::private::PrivateDictionary;
+
+        dicom_dictionary_std::private::registry().register(
+            PrivateDictionary::from_tsv(
+                "(0029,\"TEST PRIVATE REGISTRY\",08)\tCS\tTestImageType\t1\n".as_bytes(),
+            )
+            .unwrap(),
+        );
+        let ds = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                Tag(0x0029, 0x0010),
+                VR::LO,
+                PrimitiveValue::from("OTHER CREATOR"),
+            ),
+            DataElement::new(
+                Tag(0x0029, 0x0011),
+                VR::LO,
+                PrimitiveValue::from("TEST PRIVATE REGISTRY "),
+            ),
+            DataElement::new(Tag(0x0029, 0x1108), VR::CS, PrimitiveValue::from("M")),
+            DataElement::new(Tag(0x0029, 0x1008), VR::CS, PrimitiveValue::from("M")),
+        ]);
+
+        assert_eq!(
+            ds.private_creator(Tag(0x0029, 0x1108)).as_deref(),
+            Some("TEST PRIVATE REGISTRY")
+        );
+        assert_eq!(ds.private_creator(Tag(0x0029, 0x0010)), None);
+        assert_eq!(ds.private_creator(Tag(0x0028, 0x1108)), None);
+        let entry = ds.private_entry(Tag(0x0029, 0x1108)).unwrap();
+        assert_eq!(entry.alias, "TestImageType");
+        assert_eq!(entry.tag_in_block(0x11), Tag(0x0029, 0x1108));
+        assert_eq!(ds.private_entry(Tag(0x0029, 0x1008)), None);
+    }
+
     #[test]
     fn private_element_group_full() {
         let mut ds = InMemDicomObject::from_element_iter(
@@ -4508,4 +6705,285 @@ mod tests {
 
         assert_obj_eq(&obj_read_to, &obj_read_until);
     }
+
+    #[test]
+    fn access_by_name_with_runtime_dictionary() {
+        use dicom_dictionary_std::runtime::RuntimeDataDictionary;
+
+        let mut dict = RuntimeDataDictionary::new();
+        dict.read_dic("(0009,0010)\tLO\tSiteStudyCode\t1\tSITE\n".as_bytes())
+            .unwrap();
+        let mut obj = InMemDicomObject::new_empty_with_dict(dict);
+        obj.put(DataElement::new(
+            Tag(0x0009, 0x0010),
+            VR::LO,
+            PrimitiveValue::from("A-1234"),
+        ));
+        obj.put(DataElement::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            PrimitiveValue::from("Doe^John"),
+        ));
+
+        assert_eq!(
+            obj.element_by_name("SiteStudyCode")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "A-1234"
+        );
+        assert_eq!(
+            obj.element_by_name("PatientName")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "Doe^John"
+        );
+    }
+
+    #[test]
+    fn validate_vm_against_dictionary() {
+        use dicom_core::dictionary::Vm;
+
+        let item = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::PIXEL_SPACING,
+            VR::DS,
+            PrimitiveValue::from("0.5\\0.5\\0.5"),
+        )]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(
+                tags::IMAGE_ORIENTATION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["1", "0", "0", "0", "1"]),
+            ),
+            DataElement::new(
+                tags::IMAGE_POSITION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["0", "0", "0"]),
+            ),
+            DataElement::new(tags::WINDOW_CENTER, VR::DS, PrimitiveValue::Empty),
+            DataElement::new(
+                tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+        ]);
+
+        assert_eq!(
+            obj.validate_vm(),
+            vec![
+                VmViolation {
+                    tag: tags::IMAGE_ORIENTATION_PATIENT,
+                    expected: Vm::exactly(6),
+                    found: 5,
+                },
+                VmViolation {
+                    tag: tags::PIXEL_SPACING,
+                    expected: Vm::exactly(2),
+                    found: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn record_original_attributes_of_modified_elements() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::STUDY_ID, VR::SH, "STUDY1"),
+        ]);
+        let modification = AttributeModification::new(ModificationReason::Coerce, "TEST");
+
+        // nothing is recorded unless modifications are tracked
+        obj.put_str(tags::STUDY_ID, VR::SH, "STUDY2");
+        assert!(!obj.record_original_attributes(&modification));
+
+        obj.track_modifications();
+        obj.put_str(tags::PATIENT_NAME, VR::PN, "Anonymous");
+        obj.put_str(tags::PATIENT_NAME, VR::PN, "Anonymous^2");
+        obj.remove_element(tags::STUDY_ID);
+        obj.apply(AttributeOp::new(
+            tags::PATIENT_ID,
+            AttributeAction::SetStr("ANON".into()),
+        ))
+        .unwrap();
+        obj.apply(AttributeOp::new(
+            tags::PATIENT_ID,
+            AttributeAction::SetStr("12345".into()),
+        ))
+        .unwrap();
+        obj.put_str(tags::PATIENT_COMMENTS, VR::LT, "de-identified");
+
+        let modified: Vec<_> = obj
+            .modified_attributes()
+            .map(|(tag, original)| (tag, original.map(|e| e.to_str().unwrap().into_owned())))
+            .collect();
+        assert_eq!(
+            modified,
+            vec![
+                (tags::PATIENT_NAME, Some("Doe^John".to_string())),
+                (tags::PATIENT_COMMENTS, None),
+                (tags::STUDY_ID, Some("STUDY2".to_string())),
+            ]
+        );
+
+        assert!(obj.record_original_attributes(&modification));
+        assert_eq!(obj.modified_attributes().count(), 0);
+        obj.put_str(tags::PATIENT_NAME, VR::PN, "Anonymous^3");
+        assert!(
+            obj.record_original_attributes(&modification.with_source_of_previous_values("SITE"))
+        );
+
+        let items = obj
+            .get(tags::ORIGINAL_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 2);
+
+        let item = &items[0];
+        assert_eq!(
+            item.get(tags::MODIFYING_SYSTEM).unwrap().to_str().unwrap(),
+            "TEST"
+        );
+        assert_eq!(
+            item.get(tags::REASON_FOR_THE_ATTRIBUTE_MODIFICATION)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "COERCE"
+        );
+        assert!(
+            item.get(tags::SOURCE_OF_PREVIOUS_VALUES)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(item.get(tags::ATTRIBUTE_MODIFICATION_DATE_TIME).is_some());
+        let originals = &item
+            .get(tags::MODIFIED_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            originals.tags().collect::<Vec<_>>(),
+            vec![tags::PATIENT_NAME, tags::PATIENT_COMMENTS, tags::STUDY_ID]
+        );
+        assert_eq!(
+            originals.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        assert!(originals.get(tags::PATIENT_COMMENTS).unwrap().is_empty());
+
+        let item = &items[1];
+        assert_eq!(
+            item.get(tags::SOURCE_OF_PREVIOUS_VALUES)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "SITE"
+        );
+        let originals = &item
+            .get(tags::MODIFIED_ATTRIBUTES_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert_eq!(
+            originals.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Anonymous^2"
+        );
+    }
+
+    #[test]
+    fn deduplicate_and_shrink_values() {
+        let items = (1..=3)
+            .map(|i| {
+                InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "2.25.1234"),
+                    DataElement::new(tags::REFERENCED_FRAME_NUMBER, VR::IS, i.to_string()),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1234"),
+            DataElement::new_with_len(
+                tags::REFERENCED_IMAGE_SEQUENCE,
+                VR::SQ,
+                Length(150),
+                DataSetSequence::new(items, Length(150)),
+            ),
+        ]);
+        let original = obj.clone();
+
+        assert_eq!(obj.deduplicate_values(), 2);
+        assert_eq!(obj.deduplicate_values(), 0);
+        let items = obj
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        let uid = items[0].get(tags::REFERENCED_SOP_INSTANCE_UID).unwrap();
+        assert!(std::ptr::eq(
+            uid,
+            items[2].get(tags::REFERENCED_SOP_INSTANCE_UID).unwrap()
+        ));
+        // different tags are not shared
+        assert!(!std::ptr::eq(uid, obj.get(tags::SOP_INSTANCE_UID).unwrap()));
+
+        obj.shrink_to_fit();
+        assert_eq!(obj, original);
+        assert_eq!(
+            obj.get(tags::REFERENCED_IMAGE_SEQUENCE).unwrap().length(),
+            Length(150)
+        );
+    }
+
+    #[test]
+    fn clones_share_elements_until_modified() {
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0x55_u8; 1024]),
+            ),
+        ]);
+        let copy = obj.clone();
+        assert!(std::ptr::eq(
+            obj.get(tags::PIXEL_DATA).unwrap(),
+            copy.get(tags::PIXEL_DATA).unwrap()
+        ));
+
+        obj.update_value(tags::PIXEL_DATA, |value| {
+            value.primitive_mut().unwrap().truncate(512);
+        });
+        obj.put_str(tags::PATIENT_NAME, VR::PN, "Doe^Jane");
+        assert_eq!(
+            obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap().len(),
+            512
+        );
+        assert_eq!(
+            copy.get(tags::PIXEL_DATA)
+                .unwrap()
+                .to_bytes()
+                .unwrap()
+                .len(),
+            1024
+        );
+        assert_eq!(
+            copy.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+
+        // consuming an object which is not shared does not copy its elements
+        let pixel_data = copy
+            .get(tags::PIXEL_DATA)
+            .unwrap()
+            .to_bytes()
+            .unwrap()
+            .as_ptr();
+        let elements: Vec<_> = copy.into_iter().collect();
+        assert_eq!(elements[1].to_bytes().unwrap().as_ptr(), pixel_data);
+    }
 }
@@ -0,0 +1,385 @@
+//! Consistent remapping of instance UIDs across DICOM objects.
+//!
+//! A [`UidRemapper`] replaces the study, series and SOP instance UIDs
+//! of any number of objects with new UIDs,
+//! along with the UIDs referencing them
+//! (such as _Referenced SOP Instance UID_) at any depth.
+//! The same original UID is always replaced by the same new UID,
+//! so that the relationships between the objects are kept.
+//! The mapping can be saved to a file
+//! and loaded again to continue remapping in consistence with it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_object::remap::UidRemapper;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut remapper = UidRemapper::new();
+//! for path in ["1.dcm", "2.dcm"] {
+//!     let mut obj = open_file(path)?;
+//!     remapper.remap_file(&mut obj);
+//!     obj.write_to_file(path)?;
+//! }
+//! remapper.save_map("uids.txt")?;
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dicom_core::header::Header;
+use dicom_core::value::{C, Value};
+use dicom_core::{DataDictionary, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use snafu::{ResultExt, Snafu};
+
+use crate::mem::InMemElement;
+use crate::{FileDicomObject, InMemDicomObject};
+
+/// The attributes remapped by default,
+/// holding UIDs of study, series, SOP and frame of reference instances
+/// or referring to them.
+pub const DEFAULT_REMAP_TAGS: [Tag; 12] = [
+    tags::STUDY_INSTANCE_UID,
+    tags::SERIES_INSTANCE_UID,
+    tags::SOP_INSTANCE_UID,
+    tags::FRAME_OF_REFERENCE_UID,
+    tags::REFERENCED_SOP_INSTANCE_UID,
+    tags::REFERENCED_SOP_INSTANCE_UID_IN_FILE,
+    tags::REFERENCED_FRAME_OF_REFERENCE_UID,
+    tags::SOURCE_FRAME_OF_REFERENCE_UID,
+    tags::SYNCHRONIZATION_FRAME_OF_REFERENCE_UID,
+    tags::DIMENSION_ORGANIZATION_UID,
+    tags::CONCATENATION_UID,
+    tags::IRRADIATION_EVENT_UID,
+];
+
+/// An error which may occur while loading or saving a UID map
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for UID maps
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    #[snafu(display("Could not open UID map file '{}'", path.display()))]
+    OpenFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Could not read the UID map
+    ReadLine { source: std::io::Error },
+    #[snafu(display("Invalid UID map entry at line {line}"))]
+    ParseEntry { line: usize },
+    /// Could not write the UID map
+    WriteEntry { source: std::io::Error },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Create a new UID under the `2.25` root,
+/// from a random 128-bit number.
+pub fn new_uid() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let state = std::collections::hash_map::RandomState::new();
+    let mut value = 0u128;
+    for i in 0..2 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(i);
+        hasher.write_u128(time);
+        hasher.write_u64(count);
+        value = value << 64 | u128::from(hasher.finish());
+    }
+    format!("2.25.{value}")
+}
+
+/// A consistent mapping from original UIDs to new UIDs,
+/// applied to the instance UIDs of DICOM objects.
+///
+/// See the [module-level documentation](self) for more details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UidRemapper {
+    map: BTreeMap<String, String>,
+    tags: Vec<Tag>,
+}
+
+impl Default for UidRemapper {
+    fn default() -> Self {
+        UidRemapper {
+            map: BTreeMap::new(),
+            tags: DEFAULT_REMAP_TAGS.to_vec(),
+        }
+    }
+}
+
+impl UidRemapper {
+    /// Create a remapper with an empty mapping,
+    /// remapping the [default attributes](DEFAULT_REMAP_TAGS).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the attributes to remap, at any depth.
+    ///
+    /// Only attributes with the UI value representation are changed.
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = Tag>) -> Self {
+        self.tags = tags.into_iter().collect();
+        self
+    }
+
+    /// Read a mapping saved with [`write_map`](Self::write_map),
+    /// remapping the [default attributes](DEFAULT_REMAP_TAGS).
+    ///
+    /// Each line holds an original UID and its replacement,
+    /// separated by `=`.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn read_map(reader: impl Read) -> Result<Self> {
+        let mut remapper = UidRemapper::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.context(ReadLineSnafu)?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (original, new) = line
+                .split_once('=')
+                .filter(|(original, new)| !original.trim().is_empty() && !new.trim().is_empty())
+                .ok_or(Error(InnerError::ParseEntry { line: i + 1 }))?;
+            remapper.insert(original.trim(), new.trim());
+        }
+        Ok(remapper)
+    }
+
+    /// Load a mapping from a file,
+    /// as in [`read_map`](Self::read_map).
+    pub fn open_map(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).context(OpenFileSnafu { path })?;
+        Self::read_map(file)
+    }
+
+    /// Write the mapping, one original UID and its replacement per line.
+    pub fn write_map(&self, writer: impl Write) -> Result<()> {
+        let mut writer = BufWriter::new(writer);
+        for (original, new) in &self.map {
+            writeln!(writer, "{original}={new}").context(WriteEntrySnafu)?;
+        }
+        writer.flush().context(WriteEntrySnafu)?;
+        Ok(())
+    }
+
+    /// Save the mapping to a file,
+    /// as in [`write_map`](Self::write_map).
+    pub fn save_map(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path).context(OpenFileSnafu { path })?;
+        self.write_map(file)
+    }
+
+    /// Retrieve the replacement of a UID, if it was mapped.
+    pub fn get(&self, uid: &str) -> Option<&str> {
+        self.map.get(trim_uid(uid)).map(String::as_str)
+    }
+
+    /// Map a UID to the given replacement.
+    pub fn insert(&mut self, original: impl Into<String>, new: impl Into<String>) {
+        self.map.insert(original.into(), new.into());
+    }
+
+    /// Retrieve the replacement of a UID,
+    /// mapping it to a [new UID](new_uid) if it was not mapped before.
+    pub fn map_uid(&mut self, uid: &str) -> String {
+        let uid = trim_uid(uid);
+        if uid.is_empty() {
+            return String::new();
+        }
+        self.map
+            .entry(uid.to_string())
+            .or_insert_with(new_uid)
+            .clone()
+    }
+
+    /// The number of UIDs mapped.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check whether no UID is mapped.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over the original UIDs and their replacements,
+    /// in order of original UID.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.map.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Replace the UIDs of the remapped attributes in a data set,
+    /// including those nested in its sequences.
+    ///
+    /// Returns the number of attributes changed.
+    pub fn remap<D>(&mut self, obj: &mut InMemDicomObject<D>) -> usize
+    where
+        D: DataDictionary + Clone,
+    {
+        let targets: Vec<Tag> = obj
+            .iter()
+            .filter(|e| self.affects(e))
+            .map(|e| e.tag())
+            .collect();
+        let mut count = 0;
+        for tag in targets {
+            obj.update_value(tag, |value| match value {
+                Value::Primitive(v) => {
+                    let uids: C<String> = v
+                        .to_multi_str()
+                        .iter()
+                        .map(|uid| self.map_uid(uid))
+                        .collect();
+                    *v = PrimitiveValue::Strs(uids);
+                    count += 1;
+                }
+                Value::Sequence(seq) => {
+                    for item in seq.items_mut().iter_mut() {
+                        count += self.remap(item);
+                    }
+                }
+                Value::PixelSequence(_) => {}
+            });
+        }
+        count
+    }
+
+    /// Replace the UIDs of the remapped attributes in a DICOM file,
+    /// as in [`remap`](Self::remap),
+    /// also updating the _Media Storage SOP Instance UID_
+    /// of the file meta group.
+    ///
+    /// Returns the number of attributes changed in the data set.
+    pub fn remap_file<D>(&mut self, obj: &mut FileDicomObject<InMemDicomObject<D>>) -> usize
+    where
+        D: DataDictionary + Clone,
+    {
+        let count = self.remap(obj);
+        if self.tags.contains(&tags::SOP_INSTANCE_UID) {
+            let meta = obj.meta_mut();
+            meta.media_storage_sop_instance_uid =
+                self.map_uid(&meta.media_storage_sop_instance_uid);
+            meta.update_information_group_length();
+        }
+        count
+    }
+
+    /// Check whether an element holds UIDs to remap,
+    /// either directly or in its sequence items.
+    fn affects<D>(&self, elem: &InMemElement<D>) -> bool
+    where
+        D: DataDictionary + Clone,
+    {
+        match elem.value() {
+            Value::Primitive(PrimitiveValue::Empty) => false,
+            Value::Primitive(_) => elem.vr() == VR::UI && self.tags.contains(&elem.tag()),
+            Value::Sequence(seq) => seq
+                .items()
+                .iter()
+                .any(|item| item.iter().any(|e| self.affects(e))),
+            Value::PixelSequence(_) => false,
+        }
+    }
+}
+
+/// Remove the padding of a UID.
+fn trim_uid(uid: &str) -> &str {
+    uid.trim_end_matches(['\0', ' ']).trim_start()
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::{tags, uids};
+
+    use super::UidRemapper;
+    use crate::{FileMetaTableBuilder, InMemDicomObject};
+
+    fn uid(obj: &InMemDicomObject, tag: dicom_core::Tag) -> String {
+        obj.get(tag).unwrap().to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn remap_uids_consistently() {
+        let image = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "1.2.3.1\0"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "1.2.3.0"),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("1.2.3.1"),
+        )
+        .unwrap();
+        let mut report = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "1.2.3.2"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3"),
+            DataElement::new(
+                tags::CURRENT_REQUESTED_PROCEDURE_EVIDENCE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(
+                        tags::REFERENCED_SOP_CLASS_UID,
+                        VR::UI,
+                        uids::CT_IMAGE_STORAGE,
+                    ),
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "1.2.3.1"),
+                ])]),
+            ),
+        ]);
+
+        let mut remapper = UidRemapper::new();
+        // reference seen before the instance itself
+        assert_eq!(remapper.remap(&mut report), 3);
+        let mut image = image;
+        assert_eq!(remapper.remap_file(&mut image), 3);
+        assert_eq!(remapper.len(), 4);
+
+        let new_image_uid = uid(&image, tags::SOP_INSTANCE_UID);
+        assert!(new_image_uid.starts_with("2.25."));
+        assert_eq!(remapper.get("1.2.3.1"), Some(new_image_uid.as_str()));
+        assert_eq!(image.meta().media_storage_sop_instance_uid(), new_image_uid);
+        assert_eq!(uid(&image, tags::SOP_CLASS_UID), uids::CT_IMAGE_STORAGE);
+        assert_eq!(
+            uid(&image, tags::STUDY_INSTANCE_UID),
+            uid(&report, tags::STUDY_INSTANCE_UID)
+        );
+        let reference = report
+            .entry_at((
+                tags::CURRENT_REQUESTED_PROCEDURE_EVIDENCE_SEQUENCE,
+                0,
+                tags::REFERENCED_SOP_INSTANCE_UID,
+            ))
+            .unwrap();
+        assert_eq!(reference.to_str().unwrap(), new_image_uid);
+
+        // the mapping survives a round trip
+        let mut saved = Vec::new();
+        remapper.write_map(&mut saved).unwrap();
+        let loaded = UidRemapper::read_map(&saved[..]).unwrap();
+        assert_eq!(loaded, remapper);
+        assert!(UidRemapper::read_map(&b"1.2.3\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Parallel scanning of directory trees for DICOM files.
+//!
+//! A [`Scanner`] walks a directory tree,
+//! identifies DICOM files by the `DICM` magic code
+//! which follows the 128-byte preamble
+//! (or starts the file, if [files without a preamble](Scanner::with_no_preamble) are allowed),
+//! and reads a configurable set of key attributes from each of them
+//! with a pool of worker threads.
+//! Results are sent over a channel as soon as they are available,
+//! in no particular order.
+//! Files which are not DICOM files are silently skipped.
+//!
+//! Only the file meta group and the root data set up to the last key attribute
+//! are read from each file,
+//! so scanning is much faster than opening each file in full.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_dictionary_std::tags;
+//! use dicom_object::scan::Scanner;
+//!
+//! let results = Scanner::new()
+//!     .with_tags([tags::STUDY_INSTANCE_UID, tags::SOP_INSTANCE_UID])
+//!     .with_threads(8)
+//!     .scan("/data/archive");
+//! for result in results {
+//!     match result {
+//!         Ok(file) => {
+//!             let uid = file
+//!                 .attributes
+//!                 .get(tags::SOP_INSTANCE_UID)
+//!                 .and_then(|e| e.to_str().ok())
+//!                 .unwrap_or_default();
+//!             println!("{}: {}", file.path.display(), uid);
+//!         }
+//!         Err(e) => eprintln!("{e}"),
+//!     }
+//! }
+//! ```
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use dicom_core::Tag;
+use dicom_dictionary_std::tags;
+use snafu::{ResultExt, Snafu};
+use walkdir::WalkDir;
+
+use crate::file::ReadPreamble;
+use crate::{FileDicomObject, FileMetaTable, InMemDicomObject, OpenFileOptions};
+
+/// The key attributes read by default from each file found.
+pub const DEFAULT_SCAN_TAGS: [Tag; 8] = [
+    tags::SOP_CLASS_UID,
+    tags::SOP_INSTANCE_UID,
+    tags::MODALITY,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::STUDY_INSTANCE_UID,
+    tags::SERIES_INSTANCE_UID,
+    tags::INSTANCE_NUMBER,
+];
+
+/// An error which may occur while scanning a directory tree
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for directory scanning
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Could not walk the directory tree
+    WalkDirectory { source: walkdir::Error },
+    #[snafu(display("Could not read file '{}'", path.display()))]
+    ReadMagic {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not read DICOM file '{}'", path.display()))]
+    ReadFile {
+        path: PathBuf,
+        #[snafu(source(from(crate::ReadError, Box::new)))]
+        source: Box<crate::ReadError>,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The outcome of scanning a single DICOM file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScannedFile {
+    /// The path to the file
+    pub path: PathBuf,
+    /// Whether the file starts with the 128-byte preamble
+    pub has_preamble: bool,
+    /// The file meta group
+    pub meta: FileMetaTable,
+    /// The key attributes found in the root data set
+    pub attributes: InMemDicomObject,
+}
+
+/// A directory scanner,
+/// reading key attributes of all DICOM files in a directory tree
+/// from multiple threads.
+///
+/// See the [module-level documentation](self) for more details.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    tags: Vec<Tag>,
+    threads: usize,
+    no_preamble: bool,
+    follow_links: bool,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner {
+            tags: DEFAULT_SCAN_TAGS.to_vec(),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            no_preamble: false,
+            follow_links: false,
+        }
+    }
+}
+
+impl Scanner {
+    /// Create a scanner reading the [default key attributes](DEFAULT_SCAN_TAGS)
+    /// with as many threads as the available parallelism.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the key attributes to read from each file.
+    pub fn with_tags(mut self, tags: impl IntoIterator<Item = Tag>) -> Self {
+        self.tags = tags.into_iter().collect();
+        self.tags.sort();
+        self.tags.dedup();
+        self
+    }
+
+    /// Set the number of worker threads reading files.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Set whether to also accept files without the 128-byte preamble,
+    /// which start with the `DICM` magic code right away.
+    ///
+    /// These are not accepted by default.
+    pub fn with_no_preamble(mut self, no_preamble: bool) -> Self {
+        self.no_preamble = no_preamble;
+        self
+    }
+
+    /// Set whether to follow symbolic links while walking the directory tree.
+    ///
+    /// Symbolic links are not followed by default.
+    pub fn with_follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Start scanning the directory tree at the given path
+    /// (or just the given file, if it is not a directory)
+    /// in the background,
+    /// returning the channel on which the results are sent.
+    ///
+    /// The channel is closed once all files were scanned.
+    /// Dropping the receiver stops the scan early.
+    pub fn scan(&self, root: impl AsRef<Path>) -> Receiver<Result<ScannedFile>> {
+        let (path_tx, path_rx) = mpsc::sync_channel::<PathBuf>(self.threads * 4);
+        let path_rx = Arc::new(Mutex::new(path_rx));
+        let (tx, rx) = mpsc::channel();
+
+        for _ in 0..self.threads {
+            let scanner = self.clone();
+            let paths = Arc::clone(&path_rx);
+            let tx = tx.clone();
+            thread::spawn(move || {
+                loop {
+                    let next = paths.lock().ok().and_then(|paths| paths.recv().ok());
+                    let Some(path) = next else {
+                        break;
+                    };
+                    let result = match scanner.scan_file(path) {
+                        Ok(None) => continue,
+                        Ok(Some(file)) => Ok(file),
+                        Err(e) => Err(e),
+                    };
+                    if tx.send(result).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let walk = WalkDir::new(root.as_ref()).follow_links(self.follow_links);
+        thread::spawn(move || {
+            for entry in walk {
+                let sent = match entry {
+                    Ok(entry) if entry.file_type().is_file() => {
+                        path_tx.send(entry.into_path()).is_ok()
+                    }
+                    Ok(_) => true,
+                    Err(e) => tx
+                        .send(Err(Error(InnerError::WalkDirectory { source: e })))
+                        .is_ok(),
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Scan a single file,
+    /// reading its key attributes if it is a DICOM file.
+    ///
+    /// Returns `Ok(None)` if the file is not recognized as a DICOM file.
+    pub fn scan_file(&self, path: impl Into<PathBuf>) -> Result<Option<ScannedFile>> {
+        let path = path.into();
+        let Some(has_preamble) = self.sniff(&path).context(ReadMagicSnafu { path: &path })? else {
+            return Ok(None);
+        };
+
+        let mut options = OpenFileOptions::new().read_preamble(if has_preamble {
+            ReadPreamble::Always
+        } else {
+            ReadPreamble::Never
+        });
+        if let Some(last) = self.tags.last() {
+            options = options.read_to(*last);
+        }
+        let file = options
+            .open_file(&path)
+            .context(ReadFileSnafu { path: &path })?;
+        let FileDicomObject {
+            meta,
+            obj: mut attributes,
+        } = file;
+        attributes.retain(|e| self.tags.binary_search(&e.header().tag).is_ok());
+
+        Ok(Some(ScannedFile {
+            path,
+            has_preamble,
+            meta,
+            attributes,
+        }))
+    }
+
+    /// Look for the magic code in a file,
+    /// returning whether it follows a preamble,
+    /// or `None` if it was not found.
+    fn sniff(&self, path: &Path) -> std::io::Result<Option<bool>> {
+        let mut buf = Vec::with_capacity(132);
+        File::open(path)?.take(132).read_to_end(&mut buf)?;
+        if buf.len() == 132 && &buf[128..] == b"DICM" {
+            Ok(Some(true))
+        } else if self.no_preamble && buf.starts_with(b"DICM") {
+            Ok(Some(false))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::{tags, uids};
+
+    use super::Scanner;
+    use crate::{FileMetaTableBuilder, InMemDicomObject};
+
+    fn dicom_file(sop_instance_uid: &str) -> Vec<u8> {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid),
+        )
+        .unwrap();
+        let mut out = Vec::new();
+        obj.write_all(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn scan_directory_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        std::fs::write(dir.path().join("1.dcm"), dicom_file("2.25.1")).unwrap();
+        std::fs::write(dir.path().join("a/b/2"), dicom_file("2.25.2")).unwrap();
+        std::fs::write(dir.path().join("a/3.dcm"), &dicom_file("2.25.3")[128..]).unwrap();
+        std::fs::write(dir.path().join("a/notes.txt"), "not DICOM").unwrap();
+
+        let scan = |scanner: Scanner| {
+            scanner
+                .scan(dir.path())
+                .into_iter()
+                .map(|result| {
+                    let file = result.unwrap();
+                    let uid = file
+                        .attributes
+                        .get(tags::SOP_INSTANCE_UID)
+                        .unwrap()
+                        .to_str()
+                        .unwrap()
+                        .to_string();
+                    (uid, file)
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let files = scan(Scanner::new().with_threads(2));
+        assert_eq!(files.keys().collect::<Vec<_>>(), ["2.25.1", "2.25.2"]);
+        let file = &files["2.25.2"];
+        assert_eq!(file.path, dir.path().join("a/b/2"));
+        assert!(file.has_preamble);
+        assert_eq!(file.meta.media_storage_sop_instance_uid(), "2.25.2");
+        assert_eq!(
+            file.attributes
+                .get(tags::PATIENT_ID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "12345"
+        );
+
+        let files = scan(
+            Scanner::new()
+                .with_tags([tags::SOP_INSTANCE_UID])
+                .with_no_preamble(true),
+        );
+        assert_eq!(
+            files.keys().collect::<Vec<_>>(),
+            ["2.25.1", "2.25.2", "2.25.3"]
+        );
+        let file = &files["2.25.3"];
+        assert!(!file.has_preamble);
+        assert_eq!(
+            file.attributes.tags().collect::<Vec<_>>(),
+            [tags::SOP_INSTANCE_UID]
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! DICOM stream writer API:
+//! high-level construct for writing DICOM data sets incrementally.
+//!
+//! Unlike [`FileDicomObject::write_to_file`](crate::FileDicomObject::write_to_file),
+//! the stream writer does not require the whole data set
+//! to be built in memory beforehand.
+//! Elements are written to the output as soon as they are given,
+//! and sequences and encapsulated pixel data
+//! can be opened, filled, and closed piece by piece.
+//! This makes it possible to produce very large files,
+//! such as multi-frame images,
+//! while keeping only one frame in memory at a time.
+//!
+//! Elements must be given in ascending tag order,
+//! which is not verified by the writer.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_core::{DataElement, PrimitiveValue, VR};
+//! # use dicom_dictionary_std::{tags, uids};
+//! # use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+//! use dicom_object::stream::DicomStreamWriter;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let meta = FileMetaTableBuilder::new()
+//!     .transfer_syntax(uids::JPEG_BASELINE8_BIT)
+//!     .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+//!     .media_storage_sop_instance_uid("2.25.243207517575468262078462927615214028616")
+//!     .build()?;
+//!
+//! let mut writer = DicomStreamWriter::create("out.dcm", &meta)?;
+//! writer.write_element(DataElement::<InMemDicomObject>::new(
+//!     tags::PATIENT_NAME,
+//!     VR::PN,
+//!     PrimitiveValue::from("Doe^John"),
+//! ))?;
+//! // ... remaining attributes
+//!
+//! writer.start_pixel_data(&[])?;
+//! # let frames: Vec<Vec<u8>> = vec![];
+//! for frame in frames {
+//!     // each frame is written as soon as it is available
+//!     writer.write_fragment(&frame)?;
+//! }
+//! writer.end_pixel_data()?;
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use dicom_core::{DataElement, Tag, header::HasLength};
+use dicom_encoding::{Codec, TransferSyntax, TransferSyntaxIndex, transfer_syntax::DynEncoder};
+use dicom_parser::dataset::{DataSetWriter, IntoTokens};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::Backtrace;
+use snafu::prelude::*;
+
+use crate::FileMetaTable;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error which may occur when using the DICOM stream writer
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for the stream writer API
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    #[snafu(display("Could not create file '{}'", filename.display()))]
+    CreateFile {
+        filename: std::path::PathBuf,
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not write preamble and magic code
+    WritePreamble {
+        backtrace: Backtrace,
+        source: std::io::Error,
+    },
+    /// Could not write file meta group
+    WriteMeta {
+        #[snafu(backtrace, source(from(crate::meta::Error, Box::new)))]
+        source: Box<crate::meta::Error>,
+    },
+    /// Unrecognized transfer syntax {ts_uid}
+    UnrecognizedTransferSyntax {
+        ts_uid: String,
+        backtrace: Backtrace,
+    },
+    /// Unsupported transfer syntax {ts_uid} for stream writing
+    UnsupportedTransferSyntax {
+        ts_uid: &'static str,
+        backtrace: Backtrace,
+    },
+    /// Could not write data set
+    WriteDataSet {
+        #[snafu(
+            backtrace,
+            source(from(dicom_parser::dataset::write::Error, Box::from))
+        )]
+        source: Box<dicom_parser::dataset::write::Error>,
+    },
+    /// Cannot {operation} in the current state of the writer
+    IllegalState {
+        operation: &'static str,
+        backtrace: Backtrace,
+    },
+    /// Could not finish writing: a sequence, item or pixel data element is still open
+    Unfinished { backtrace: Backtrace },
+}
+
+/// A level of nesting in the data set being written.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Nesting {
+    Sequence,
+    Item,
+    PixelData,
+}
+
+/// A push-style writer of DICOM data sets.
+///
+/// See the [module-level documentation](crate::stream) for more details.
+pub struct DicomStreamWriter<'w, W: 'w> {
+    writer: DataSetWriter<W, DynEncoder<'w, W>>,
+    nesting: Vec<Nesting>,
+}
+
+impl<W> std::fmt::Debug for DicomStreamWriter<'_, W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DicomStreamWriter")
+            .field("nesting", &self.nesting)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DicomStreamWriter<'static, BufWriter<File>> {
+    /// Create a new DICOM file at the given path
+    /// and write its preamble and file meta group.
+    ///
+    /// The data set is encoded in the transfer syntax
+    /// specified in the file meta table.
+    pub fn create(path: impl AsRef<Path>, meta: &FileMetaTable) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).context(CreateFileSnafu { filename: path })?;
+        Self::new(BufWriter::new(file), meta)
+    }
+}
+
+impl<'w, W: 'w> DicomStreamWriter<'w, W>
+where
+    W: Write,
+{
+    /// Create a stream writer which writes a full DICOM file
+    /// to the given writer,
+    /// starting with the preamble, magic code and file meta group.
+    ///
+    /// The data set is encoded in the transfer syntax
+    /// specified in the file meta table.
+    pub fn new(mut to: W, meta: &FileMetaTable) -> Result<Self> {
+        let ts_uid = meta.transfer_syntax();
+        let ts = TransferSyntaxRegistry
+            .get(ts_uid)
+            .context(UnrecognizedTransferSyntaxSnafu { ts_uid })?;
+
+        to.write_all(&[0_u8; 128][..]).context(WritePreambleSnafu)?;
+        to.write_all(b"DICM").context(WritePreambleSnafu)?;
+        meta.write(&mut to).context(WriteMetaSnafu)?;
+
+        Self::new_dataset(to, ts)
+    }
+
+    /// Create a stream writer which writes a data set
+    /// in the given transfer syntax,
+    /// without preamble, magic code, nor file meta group.
+    ///
+    /// Transfer syntaxes which require a data set adapter,
+    /// such as _Deflated Explicit VR Little Endian_,
+    /// are not supported.
+    pub fn new_dataset(to: W, ts: &TransferSyntax) -> Result<Self> {
+        ensure!(
+            !matches!(ts.codec(), Codec::Dataset(_)),
+            UnsupportedTransferSyntaxSnafu { ts_uid: ts.uid() }
+        );
+        let writer = DataSetWriter::with_ts(to, ts).context(WriteDataSetSnafu)?;
+        Ok(DicomStreamWriter {
+            writer,
+            nesting: Vec::new(),
+        })
+    }
+
+    fn check_state(&self, operation: &'static str, accepted: &[Option<Nesting>]) -> Result<()> {
+        ensure!(
+            accepted.contains(&self.nesting.last().copied()),
+            IllegalStateSnafu { operation }
+        );
+        Ok(())
+    }
+
+    /// Write a whole data element,
+    /// including all of its sequence items or pixel data fragments.
+    ///
+    /// Elements can be written at the root of the data set
+    /// or inside a sequence item.
+    pub fn write_element<I, P>(&mut self, element: DataElement<I, P>) -> Result<()>
+    where
+        I: IntoTokens + HasLength,
+        P: AsRef<[u8]>,
+    {
+        self.check_state("write an element", &[None, Some(Nesting::Item)])?;
+        self.writer
+            .write_element(element)
+            .context(WriteDataSetSnafu)?;
+        Ok(())
+    }
+
+    /// Write all of the given data elements in order.
+    pub fn write_elements<I, P>(
+        &mut self,
+        elements: impl IntoIterator<Item = DataElement<I, P>>,
+    ) -> Result<()>
+    where
+        I: IntoTokens + HasLength,
+        P: AsRef<[u8]>,
+    {
+        for element in elements {
+            self.write_element(element)?;
+        }
+        Ok(())
+    }
+
+    /// Begin a sequence element with the given tag.
+    ///
+    /// The sequence is written with an undefined length.
+    pub fn start_sequence(&mut self, tag: Tag) -> Result<()> {
+        self.check_state("start a sequence", &[None, Some(Nesting::Item)])?;
+        self.writer.start_sequence(tag).context(WriteDataSetSnafu)?;
+        self.nesting.push(Nesting::Sequence);
+        Ok(())
+    }
+
+    /// Begin a new item in the current sequence.
+    pub fn start_item(&mut self) -> Result<()> {
+        self.check_state("start an item", &[Some(Nesting::Sequence)])?;
+        self.writer.start_item().context(WriteDataSetSnafu)?;
+        self.nesting.push(Nesting::Item);
+        Ok(())
+    }
+
+    /// Close the current sequence item.
+    pub fn end_item(&mut self) -> Result<()> {
+        self.check_state("end an item", &[Some(Nesting::Item)])?;
+        self.writer.end_item().context(WriteDataSetSnafu)?;
+        self.nesting.pop();
+        Ok(())
+    }
+
+    /// Close the current sequence.
+    pub fn end_sequence(&mut self) -> Result<()> {
+        self.check_state("end a sequence", &[Some(Nesting::Sequence)])?;
+        self.writer.end_sequence().context(WriteDataSetSnafu)?;
+        self.nesting.pop();
+        Ok(())
+    }
+
+    /// Begin an encapsulated _Pixel Data_ element,
+    /// with the given basic offset table.
+    ///
+    /// The offset table may be empty,
+    /// which is usually the case when the sizes of the frames
+    /// are not known in advance.
+    pub fn start_pixel_data(&mut self, offset_table: &[u32]) -> Result<()> {
+        self.check_state("start pixel data", &[None, Some(Nesting::Item)])?;
+        self.writer
+            .start_pixel_sequence(offset_table)
+            .context(WriteDataSetSnafu)?;
+        self.nesting.push(Nesting::PixelData);
+        Ok(())
+    }
+
+    /// Write a fragment of encapsulated pixel data.
+    pub fn write_fragment(&mut self, fragment: &[u8]) -> Result<()> {
+        self.check_state("write a fragment", &[Some(Nesting::PixelData)])?;
+        self.writer
+            .write_fragment(fragment)
+            .context(WriteDataSetSnafu)?;
+        Ok(())
+    }
+
+    /// Close the encapsulated _Pixel Data_ element.
+    pub fn end_pixel_data(&mut self) -> Result<()> {
+        self.check_state("end pixel data", &[Some(Nesting::PixelData)])?;
+        self.writer.end_sequence().context(WriteDataSetSnafu)?;
+        self.nesting.pop();
+        Ok(())
+    }
+
+    /// Finish writing the data set,
+    /// flushing and returning the inner writer.
+    ///
+    /// Fails if any sequence, item, or pixel data element is still open.
+    pub fn finish(mut self) -> Result<W> {
+        ensure!(self.nesting.is_empty(), UnfinishedSnafu);
+        self.writer.flush().context(WriteDataSetSnafu)?;
+        Ok(self.writer.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+
+    use super::DicomStreamWriter;
+    use crate::{FileMetaTableBuilder, InMemDicomObject, from_reader};
+
+    #[test]
+    fn stream_write_file() {
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax(uids::ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+            .media_storage_sop_instance_uid("2.25.243207517575468262078462927615214028616")
+            .build()
+            .unwrap();
+
+        let patient_name = DataElement::<InMemDicomObject>::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            PrimitiveValue::from("Doe^John"),
+        );
+
+        let mut writer = DicomStreamWriter::new(Vec::new(), &meta).unwrap();
+        writer.write_element(patient_name.clone()).unwrap();
+        writer
+            .start_sequence(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap();
+        // cannot write an element directly in a sequence
+        assert!(writer.write_element(patient_name.clone()).is_err());
+        writer.start_item().unwrap();
+        writer
+            .write_element(DataElement::<InMemDicomObject>::new(
+                tags::REFERENCED_SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("1.2.3.4"),
+            ))
+            .unwrap();
+        writer.end_item().unwrap();
+        writer.end_sequence().unwrap();
+        writer.start_pixel_data(&[]).unwrap();
+        writer.write_fragment(&[1, 2, 3, 4]).unwrap();
+        writer.write_fragment(&[5, 6, 7, 8]).unwrap();
+        // cannot finish with pixel data still open
+        assert!(writer.end_sequence().is_err());
+        writer.end_pixel_data().unwrap();
+        let out = writer.finish().unwrap();
+
+        let read = from_reader(&out[128..]).unwrap();
+        assert_eq!(
+            read.meta().transfer_syntax(),
+            uids::ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN
+        );
+        let pixel_data = read.get(tags::PIXEL_DATA).unwrap();
+        let fragments = pixel_data.fragments().unwrap();
+        assert_eq!(fragments, &[vec![1, 2, 3, 4], vec![5, 6, 7, 8]]);
+
+        // the rest of the data set was written as well
+        assert_eq!(
+            read.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "Doe^John"
+        );
+        let items = read
+            .get(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0]
+                .get(tags::REFERENCED_SOP_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "1.2.3.4"
+        );
+    }
+
+    #[test]
+    fn stream_write_unfinished() {
+        let meta = FileMetaTableBuilder::new()
+            .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+            .media_storage_sop_instance_uid("2.25.243207517575468262078462927615214028616")
+            .build()
+            .unwrap();
+
+        let mut writer = DicomStreamWriter::new(Vec::new(), &meta).unwrap();
+        // no sequence to end
+        assert!(writer.end_sequence().is_err());
+        writer
+            .start_sequence(tags::REFERENCED_IMAGE_SEQUENCE)
+            .unwrap();
+        assert!(writer.finish().is_err());
+    }
+}
@@ -85,7 +85,10 @@ where
     }
 }
 
-impl<D> IntoTokens for InMemDicomObject<D> {
+impl<D> IntoTokens for InMemDicomObject<D>
+where
+    D: Clone,
+{
     type Iter = InMemObjectTokens<<InMemDicomObject<D> as IntoIterator>::IntoIter>;
 
     fn into_tokens(self) -> Self::Iter {
@@ -0,0 +1,937 @@
+//! Conversion of in-memory DICOM objects
+//! to and from the [Native DICOM Model][1] in XML.
+//!
+//! This module requires the Cargo feature `xml`.
+//! See [`InMemDicomObject::to_xml`] and [`InMemDicomObject::from_xml`].
+//!
+//! Values are written according to their value representation:
+//! person names are split into their component groups and components,
+//! sequences are written as numbered items,
+//! and binary values are written as Base64 encoded inline binaries
+//! unless a bulk data URI is provided for them.
+//! Standard attributes are annotated with their keyword,
+//! and private attributes with their private creator.
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part19/chapter_A.html
+use base64::Engine;
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry};
+use dicom_core::header::Header;
+use dicom_core::value::{C, DataSetSequence, Value};
+use dicom_core::{PrimitiveValue, Tag, VR};
+use snafu::{OptionExt, Snafu, ensure};
+use std::fmt::Write as _;
+
+use crate::mem::{InMemDicomObject, InMemElement};
+
+/// The names of the person name components,
+/// in the order in which they appear in a component group
+const PERSON_NAME_COMPONENTS: [&str; 5] = [
+    "FamilyName",
+    "GivenName",
+    "MiddleName",
+    "NamePrefix",
+    "NameSuffix",
+];
+
+/// The names of the person name component groups
+const PERSON_NAME_GROUPS: [&str; 3] = ["Alphabetic", "Ideographic", "Phonetic"];
+
+/// An error which may occur when converting a DICOM object to XML
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum ToXmlError {
+    /// Encapsulated pixel data in {tag} can only be written as bulk data
+    EncapsulatedPixelData { tag: Tag },
+}
+
+/// An error which may occur when reading a DICOM object from XML
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum FromXmlError {
+    /// Malformed XML at byte {position}: {message}
+    Syntax {
+        position: usize,
+        message: &'static str,
+    },
+    /// Unexpected XML element `{name}`
+    UnexpectedElement { name: String },
+    /// Missing XML attribute `{attribute}` in element `{element}`
+    MissingAttribute {
+        element: String,
+        attribute: &'static str,
+    },
+    /// Invalid attribute tag `{value}`
+    InvalidTag { value: String },
+    /// Invalid value representation `{value}`
+    InvalidVr { value: String },
+    /// Invalid value `{value}` in attribute {tag}
+    InvalidValue { tag: Tag, value: String },
+    /// Invalid inline binary data in attribute {tag}
+    InvalidInlineBinary { tag: Tag },
+}
+
+impl<D> InMemDicomObject<D>
+where
+    D: DataDictionary,
+    D: Clone,
+{
+    /// Convert this object into an XML document
+    /// in the Native DICOM Model.
+    ///
+    /// All binary values are written as inline binaries.
+    /// Use [`to_xml_with`](Self::to_xml_with)
+    /// to refer to bulk data by URI instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_core::{DataElement, VR};
+    /// # use dicom_dictionary_std::tags;
+    /// # use dicom_object::InMemDicomObject;
+    /// let obj = InMemDicomObject::from_element_iter([
+    ///     DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+    /// ]);
+    /// let xml = obj.to_xml()?;
+    /// assert!(xml.contains(r#"<DicomAttribute tag="00100010" vr="PN" keyword="PatientName">"#));
+    /// assert!(xml.contains("<FamilyName>Doe</FamilyName>"));
+    ///
+    /// let back = InMemDicomObject::from_xml(&xml)?;
+    /// assert_eq!(back, obj);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_xml(&self) -> Result<String, ToXmlError> {
+        self.to_xml_with(|_| None)
+    }
+
+    /// Convert this object into an XML document
+    /// in the Native DICOM Model,
+    /// choosing which attributes are written as bulk data.
+    ///
+    /// `bulk_data_uri` is called for every attribute
+    /// with a binary value or encapsulated pixel data,
+    /// including those in nested data sets.
+    /// If it returns a URI,
+    /// the attribute is written as a `BulkData` reference to that URI
+    /// instead of an inline binary.
+    /// Encapsulated pixel data can only be written as bulk data.
+    pub fn to_xml_with<F>(&self, mut bulk_data_uri: F) -> Result<String, ToXmlError>
+    where
+        F: FnMut(&InMemElement<D>) -> Option<String>,
+    {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<NativeDicomModel xml:space=\"preserve\">\n");
+        write_data_set(&mut out, self, 1, &mut bulk_data_uri)?;
+        out.push_str("</NativeDicomModel>\n");
+        Ok(out)
+    }
+}
+
+impl<D> InMemDicomObject<D>
+where
+    D: DataDictionary,
+    D: Clone,
+    D: Default,
+{
+    /// Read an object from an XML document in the Native DICOM Model.
+    ///
+    /// Attributes referring to bulk data are read with an empty value.
+    /// Use [`from_xml_with`](Self::from_xml_with)
+    /// to resolve bulk data URIs.
+    pub fn from_xml(xml: &str) -> Result<Self, FromXmlError> {
+        Self::from_xml_with(xml, |_, _| None)
+    }
+
+    /// Read an object from an XML document in the Native DICOM Model,
+    /// resolving bulk data references.
+    ///
+    /// `resolve_bulk_data` is called with the tag and URI
+    /// of every attribute referring to bulk data.
+    /// The bytes returned become the value of the attribute,
+    /// and the value is left empty if it returns `None`.
+    pub fn from_xml_with<F>(xml: &str, mut resolve_bulk_data: F) -> Result<Self, FromXmlError>
+    where
+        F: FnMut(Tag, &str) -> Option<Vec<u8>>,
+    {
+        let root = XmlParser::new(xml).parse_document()?;
+        ensure!(
+            root.name == "NativeDicomModel",
+            UnexpectedElementSnafu { name: root.name }
+        );
+        read_data_set(&root, &mut resolve_bulk_data)
+    }
+}
+
+fn write_data_set<D, F>(
+    out: &mut String,
+    obj: &InMemDicomObject<D>,
+    depth: usize,
+    bulk_data_uri: &mut F,
+) -> Result<(), ToXmlError>
+where
+    D: DataDictionary + Clone,
+    F: FnMut(&InMemElement<D>) -> Option<String>,
+{
+    for elem in obj {
+        let tag = elem.tag();
+        let vr = elem.vr();
+        indent(out, depth);
+        let _ = write!(
+            out,
+            "<DicomAttribute tag=\"{:04X}{:04X}\" vr=\"{}\"",
+            tag.group(),
+            tag.element(),
+            vr
+        );
+        if tag.group() % 2 == 1 {
+            if let Some(creator) = obj.private_creator(tag) {
+                out.push_str(" privateCreator=\"");
+                escape_into(out, &creator);
+                out.push('"');
+            }
+        } else if let Some(entry) = obj.dictionary().by_tag(tag) {
+            let _ = write!(out, " keyword=\"{}\"", entry.alias());
+        }
+
+        let is_bulk = matches!(elem.value(), Value::PixelSequence(_))
+            || matches!(
+                vr,
+                VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::UN
+            );
+        let uri = if is_bulk { bulk_data_uri(elem) } else { None };
+
+        match (elem.value(), uri) {
+            (_, Some(uri)) => {
+                out.push_str(">\n");
+                indent(out, depth + 1);
+                out.push_str("<BulkData uri=\"");
+                escape_into(out, &uri);
+                out.push_str("\"/>\n");
+            }
+            (Value::PixelSequence(_), None) => {
+                return EncapsulatedPixelDataSnafu { tag }.fail();
+            }
+            (Value::Sequence(seq), None) => {
+                out.push_str(">\n");
+                for (i, item) in seq.items().iter().enumerate() {
+                    indent(out, depth + 1);
+                    let _ = writeln!(out, "<Item number=\"{}\">", i + 1);
+                    write_data_set(out, item, depth + 2, bulk_data_uri)?;
+                    indent(out, depth + 1);
+                    out.push_str("</Item>\n");
+                }
+            }
+            (Value::Primitive(PrimitiveValue::Empty), None) => {
+                out.push_str("/>\n");
+                continue;
+            }
+            (Value::Primitive(value), None) if is_bulk => {
+                out.push_str(">\n");
+                indent(out, depth + 1);
+                out.push_str("<InlineBinary>");
+                out.push_str(&base64::engine::general_purpose::STANDARD.encode(value.to_bytes()));
+                out.push_str("</InlineBinary>\n");
+            }
+            (Value::Primitive(value), None) if vr == VR::PN => {
+                out.push_str(">\n");
+                for (i, name) in value.to_multi_str().iter().enumerate() {
+                    write_person_name(out, name, i + 1, depth + 1);
+                }
+            }
+            (Value::Primitive(value), None) => {
+                out.push_str(">\n");
+                let values: Vec<String> = match value {
+                    PrimitiveValue::Tags(tags) => tags
+                        .iter()
+                        .map(|t| format!("{:04X}{:04X}", t.group(), t.element()))
+                        .collect(),
+                    _ => value.to_multi_str().into_owned(),
+                };
+                for (i, v) in values.iter().enumerate() {
+                    indent(out, depth + 1);
+                    let _ = write!(out, "<Value number=\"{}\">", i + 1);
+                    // text values may be padded to even length
+                    escape_into(out, v.trim_end_matches(['\0', ' ']));
+                    out.push_str("</Value>\n");
+                }
+            }
+        }
+        indent(out, depth);
+        out.push_str("</DicomAttribute>\n");
+    }
+    Ok(())
+}
+
+fn write_person_name(out: &mut String, name: &str, number: usize, depth: usize) {
+    indent(out, depth);
+    let _ = write!(out, "<PersonName number=\"{number}\"");
+    let name = name.trim_end_matches(['\0', ' ']);
+    if name.is_empty() {
+        out.push_str("/>\n");
+        return;
+    }
+    out.push_str(">\n");
+    for (group_name, group) in PERSON_NAME_GROUPS.iter().zip(name.splitn(3, '=')) {
+        if group.is_empty() {
+            continue;
+        }
+        indent(out, depth + 1);
+        let _ = writeln!(out, "<{group_name}>");
+        for (component_name, component) in PERSON_NAME_COMPONENTS.iter().zip(group.splitn(5, '^')) {
+            if component.is_empty() {
+                continue;
+            }
+            indent(out, depth + 2);
+            let _ = write!(out, "<{component_name}>");
+            escape_into(out, component);
+            let _ = writeln!(out, "</{component_name}>");
+        }
+        indent(out, depth + 1);
+        let _ = writeln!(out, "</{group_name}>");
+    }
+    indent(out, depth);
+    out.push_str("</PersonName>\n");
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+/// Append text to the output, escaping XML special characters
+fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn read_data_set<D, F>(
+    node: &XmlNode,
+    resolve_bulk_data: &mut F,
+) -> Result<InMemDicomObject<D>, FromXmlError>
+where
+    D: DataDictionary + Clone + Default,
+    F: FnMut(Tag, &str) -> Option<Vec<u8>>,
+{
+    let mut obj = InMemDicomObject::new_empty_with_dict(D::default());
+    for child in &node.children {
+        ensure!(
+            child.name == "DicomAttribute",
+            UnexpectedElementSnafu { name: &child.name }
+        );
+        obj.put(read_attribute(child, resolve_bulk_data)?);
+    }
+    Ok(obj)
+}
+
+fn read_attribute<D, F>(
+    node: &XmlNode,
+    resolve_bulk_data: &mut F,
+) -> Result<InMemElement<D>, FromXmlError>
+where
+    D: DataDictionary + Clone + Default,
+    F: FnMut(Tag, &str) -> Option<Vec<u8>>,
+{
+    let tag = node.required_attribute("tag")?;
+    let tag = parse_tag(tag).context(InvalidTagSnafu { value: tag })?;
+    let vr = node.required_attribute("vr")?;
+    let vr: VR = vr.parse().ok().context(InvalidVrSnafu { value: vr })?;
+
+    // bulk data and inline binaries stand alone
+    if let Some(child) = node.children.iter().find(|c| c.name == "BulkData") {
+        let uri = child.required_attribute("uri")?;
+        let value = match resolve_bulk_data(tag, uri) {
+            Some(bytes) => PrimitiveValue::from(bytes),
+            None => PrimitiveValue::Empty,
+        };
+        return Ok(InMemElement::new(tag, vr, value));
+    }
+    if let Some(child) = node.children.iter().find(|c| c.name == "InlineBinary") {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(child.text.trim())
+            .ok()
+            .context(InvalidInlineBinarySnafu { tag })?;
+        return Ok(InMemElement::new(tag, vr, PrimitiveValue::from(bytes)));
+    }
+
+    if vr == VR::SQ {
+        let items = node
+            .numbered_children("Item")?
+            .into_iter()
+            .map(|item| read_data_set(item, resolve_bulk_data))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(InMemElement::new(tag, vr, DataSetSequence::from(items)));
+    }
+
+    let value = if vr == VR::PN {
+        let names: C<String> = node
+            .numbered_children("PersonName")?
+            .into_iter()
+            .map(read_person_name)
+            .collect();
+        if names.is_empty() {
+            PrimitiveValue::Empty
+        } else {
+            strings(names)
+        }
+    } else {
+        let values = node.numbered_children("Value")?;
+        let texts = values.iter().map(|v| v.text.as_str());
+        if values.is_empty() {
+            PrimitiveValue::Empty
+        } else {
+            parse_values(tag, vr, texts)?
+        }
+    };
+    Ok(InMemElement::new(tag, vr, value))
+}
+
+fn read_person_name(node: &XmlNode) -> String {
+    let groups = PERSON_NAME_GROUPS.map(|group_name| {
+        let Some(group) = node.children.iter().find(|c| c.name == group_name) else {
+            return String::new();
+        };
+        let components = PERSON_NAME_COMPONENTS.map(|component_name| {
+            group
+                .children
+                .iter()
+                .find(|c| c.name == component_name)
+                .map(|c| c.text.as_str())
+                .unwrap_or_default()
+        });
+        // trailing empty components are omitted along with their delimiters
+        let len = components
+            .iter()
+            .rposition(|c| !c.is_empty())
+            .map_or(0, |i| i + 1);
+        components[..len].join("^")
+    });
+    let len = groups
+        .iter()
+        .rposition(|g| !g.is_empty())
+        .map_or(0, |i| i + 1);
+    groups[..len].join("=")
+}
+
+/// Interpret the text of the `Value` elements of an attribute
+fn parse_values<'a>(
+    tag: Tag,
+    vr: VR,
+    texts: impl Iterator<Item = &'a str>,
+) -> Result<PrimitiveValue, FromXmlError> {
+    fn parse_all<'a, T: std::str::FromStr>(
+        tag: Tag,
+        texts: impl Iterator<Item = &'a str>,
+    ) -> Result<C<T>, FromXmlError> {
+        texts
+            .map(|text| {
+                text.trim()
+                    .parse()
+                    .ok()
+                    .context(InvalidValueSnafu { tag, value: text })
+            })
+            .collect()
+    }
+
+    Ok(match vr {
+        VR::AT => PrimitiveValue::Tags(
+            texts
+                .map(|text| parse_tag(text.trim()).context(InvalidValueSnafu { tag, value: text }))
+                .collect::<Result<_, _>>()?,
+        ),
+        VR::FL => PrimitiveValue::F32(parse_all(tag, texts)?),
+        VR::FD => PrimitiveValue::F64(parse_all(tag, texts)?),
+        VR::SL => PrimitiveValue::I32(parse_all(tag, texts)?),
+        VR::SS => PrimitiveValue::I16(parse_all(tag, texts)?),
+        VR::SV => PrimitiveValue::I64(parse_all(tag, texts)?),
+        VR::UL => PrimitiveValue::U32(parse_all(tag, texts)?),
+        VR::US => PrimitiveValue::U16(parse_all(tag, texts)?),
+        VR::UV => PrimitiveValue::U64(parse_all(tag, texts)?),
+        _ => strings(texts.map(str::to_string).collect()),
+    })
+}
+
+/// Build a textual value,
+/// using a single string if there is only one value
+fn strings(mut values: C<String>) -> PrimitiveValue {
+    if values.len() == 1 {
+        PrimitiveValue::Str(values.remove(0))
+    } else {
+        PrimitiveValue::Strs(values)
+    }
+}
+
+/// Parse a tag in the form `GGGGEEEE`
+fn parse_tag(text: &str) -> Option<Tag> {
+    if text.len() != 8 || !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let group = u16::from_str_radix(&text[..4], 16).ok()?;
+    let element = u16::from_str_radix(&text[4..], 16).ok()?;
+    Some(Tag(group, element))
+}
+
+/// An XML element with its attributes, child elements, and text content
+#[derive(Debug, Default)]
+struct XmlNode {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<XmlNode>,
+    text: String,
+}
+
+impl XmlNode {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn required_attribute(&self, name: &'static str) -> Result<&str, FromXmlError> {
+        self.attribute(name).context(MissingAttributeSnafu {
+            element: &self.name,
+            attribute: name,
+        })
+    }
+
+    /// Collect the child elements with the given name,
+    /// ordered by their `number` attribute if present
+    fn numbered_children(&self, name: &str) -> Result<Vec<&XmlNode>, FromXmlError> {
+        let mut children = Vec::new();
+        for child in &self.children {
+            ensure!(
+                child.name == name,
+                UnexpectedElementSnafu { name: &child.name }
+            );
+            let number = child
+                .attribute("number")
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or(u32::MAX);
+            children.push((number, child));
+        }
+        // stable sort keeps document order for unnumbered elements
+        children.sort_by_key(|(number, _)| *number);
+        Ok(children.into_iter().map(|(_, child)| child).collect())
+    }
+}
+
+/// A minimal non-validating XML parser,
+/// sufficient for reading Native DICOM Model documents
+struct XmlParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlParser<'a> {
+    fn new(input: &'a str) -> Self {
+        XmlParser { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T, FromXmlError> {
+        SyntaxSnafu {
+            position: self.pos,
+            message,
+        }
+        .fail()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip everything up to and including the given delimiter
+    fn skip_past(&mut self, delimiter: &str) -> Result<&'a str, FromXmlError> {
+        match self.rest().find(delimiter) {
+            Some(i) => {
+                let skipped = &self.rest()[..i];
+                self.pos += i + delimiter.len();
+                Ok(skipped)
+            }
+            None => self.error("unexpected end of document"),
+        }
+    }
+
+    /// Skip the prolog and any comments or processing instructions
+    fn skip_misc(&mut self) -> Result<(), FromXmlError> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_document(mut self) -> Result<XmlNode, FromXmlError> {
+        // byte order mark
+        if self.rest().starts_with('\u{feff}') {
+            self.pos += '\u{feff}'.len_utf8();
+        }
+        self.skip_misc()?;
+        let root = self.parse_element()?;
+        self.skip_misc()?;
+        if !self.rest().is_empty() {
+            return self.error("unexpected content after root element");
+        }
+        Ok(root)
+    }
+
+    fn parse_name(&mut self) -> Result<&'a str, FromXmlError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return self.error("expected a name");
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn parse_element(&mut self) -> Result<XmlNode, FromXmlError> {
+        if !self.rest().starts_with('<') {
+            return self.error("expected an element");
+        }
+        self.pos += 1;
+        let mut node = XmlNode {
+            name: self.parse_name()?.to_string(),
+            ..Default::default()
+        };
+
+        // attributes
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(node);
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let name = self.parse_name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return self.error("expected `=` after attribute name");
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return self.error("expected a quoted attribute value"),
+            };
+            self.pos += 1;
+            let start = self.pos;
+            let value = match self.rest().find(quote) {
+                Some(i) => &self.rest()[..i],
+                None => return self.error("unterminated attribute value"),
+            };
+            self.pos += value.len() + 1;
+            let value = unescape(value).context(SyntaxSnafu {
+                position: start,
+                message: "invalid character reference",
+            })?;
+            node.attributes.push((name.to_string(), value));
+        }
+
+        // content
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let name = self.parse_name()?;
+                if name != node.name {
+                    return self.error("mismatched closing tag");
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return self.error("expected `>`");
+                }
+                self.pos += 1;
+                return Ok(node);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                let text = self.skip_past("]]>")?;
+                node.text.push_str(text);
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with('<') {
+                let child = self.parse_element()?;
+                node.children.push(child);
+            } else if rest.is_empty() {
+                return self.error("unexpected end of document");
+            } else {
+                let start = self.pos;
+                let len = rest.find('<').unwrap_or(rest.len());
+                self.pos += len;
+                let text = unescape(&rest[..len]).context(SyntaxSnafu {
+                    position: start,
+                    message: "invalid character reference",
+                })?;
+                node.text.push_str(&text);
+            }
+        }
+    }
+}
+
+/// Resolve entity and character references in XML text
+fn unescape(text: &str) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest.find(';')?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()?
+                } else {
+                    entity.strip_prefix('#')?.parse().ok()?
+                };
+                char::from_u32(code)?
+            }
+        };
+        out.push(c);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::PixelFragmentSequence;
+    use dicom_core::{DataElement, dicom_value};
+    use dicom_dictionary_std::tags;
+
+    fn sample() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::IMAGE_TYPE,
+                VR::CS,
+                dicom_value!(Strs, ["ORIGINAL", "PRIMARY"]),
+            ),
+            DataElement::new(tags::STUDY_DESCRIPTION, VR::LO, "Head & Neck <CT>"),
+            DataElement::new(
+                tags::PATIENT_NAME,
+                VR::PN,
+                dicom_value!(
+                    Strs,
+                    ["Yamada^Tarou=山田^太郎=やまだ^たろう", "Doe^John^^Dr."]
+                ),
+            ),
+            DataElement::new(tags::PATIENT_AGE, VR::AS, PrimitiveValue::Empty),
+            DataElement::new(tags::ROWS, VR::US, dicom_value!(U16, [512])),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_value!(Strs, ["0.5", "0.5"]),
+            ),
+            DataElement::new(
+                tags::REAL_WORLD_VALUE_SLOPE,
+                VR::FD,
+                dicom_value!(F64, [1.5]),
+            ),
+            DataElement::new(
+                tags::FRAME_INCREMENT_POINTER,
+                VR::AT,
+                PrimitiveValue::Tags([tags::FRAME_TIME].into_iter().collect()),
+            ),
+            DataElement::new(
+                tags::REFERENCED_SERIES_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "1.2.3.4"),
+                ])]),
+            ),
+            DataElement::new(Tag(0x0009, 0x0010), VR::LO, "ACME 1.0"),
+            DataElement::new(Tag(0x0009, 0x1001), VR::SH, "private"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![1_u8, 2, 3, 4]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn write_native_dicom_model() {
+        let xml = sample().to_xml().unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<NativeDicomModel"));
+        assert!(xml.contains(
+            "  <DicomAttribute tag=\"00080008\" vr=\"CS\" keyword=\"ImageType\">\n    \
+             <Value number=\"1\">ORIGINAL</Value>\n    \
+             <Value number=\"2\">PRIMARY</Value>\n  \
+             </DicomAttribute>\n"
+        ));
+        assert!(xml.contains("<Value number=\"1\">Head &amp; Neck &lt;CT&gt;</Value>"));
+        assert!(xml.contains(
+            "    <PersonName number=\"1\">\n      \
+             <Alphabetic>\n        \
+             <FamilyName>Yamada</FamilyName>\n        \
+             <GivenName>Tarou</GivenName>\n      \
+             </Alphabetic>\n      \
+             <Ideographic>\n        \
+             <FamilyName>山田</FamilyName>\n"
+        ));
+        assert!(xml.contains("<NamePrefix>Dr.</NamePrefix>"));
+        assert!(
+            xml.contains("<DicomAttribute tag=\"00101010\" vr=\"AS\" keyword=\"PatientAge\"/>")
+        );
+        assert!(xml.contains("<Value number=\"1\">00181063</Value>"));
+        assert!(xml.contains("<Item number=\"1\">\n      <DicomAttribute tag=\"0020000E\""));
+        assert!(
+            xml.contains("<DicomAttribute tag=\"00091001\" vr=\"SH\" privateCreator=\"ACME 1.0\">")
+        );
+        assert!(xml.contains("<InlineBinary>AQIDBA==</InlineBinary>"));
+
+        // bulk data
+        let xml = sample()
+            .to_xml_with(|e| {
+                (e.tag() == tags::PIXEL_DATA).then(|| "http://example.com/bulk/7fe00010".into())
+            })
+            .unwrap();
+        assert!(xml.contains("<BulkData uri=\"http://example.com/bulk/7fe00010\"/>"));
+        assert!(!xml.contains("InlineBinary"));
+
+        // encapsulated pixel data needs a URI
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PixelFragmentSequence::new_fragments(vec![vec![1, 2, 3, 4]]),
+        )]);
+        assert!(matches!(
+            obj.to_xml(),
+            Err(ToXmlError::EncapsulatedPixelData { .. })
+        ));
+    }
+
+    /// Compare two objects,
+    /// looking into the sequence separately
+    /// since elements of undefined length never compare equal
+    fn assert_round_trip(mut back: InMemDicomObject, obj: &InMemDicomObject) {
+        let mut obj = obj.clone();
+        let items = |obj: &mut InMemDicomObject| {
+            obj.take_element(tags::REFERENCED_SERIES_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap()
+                .to_vec()
+        };
+        assert_eq!(items(&mut back), items(&mut obj));
+        assert_eq!(back, obj);
+    }
+
+    #[test]
+    fn round_trip_native_dicom_model() {
+        let obj = sample();
+        let xml = obj.to_xml().unwrap();
+        let back = InMemDicomObject::<dicom_dictionary_std::StandardDataDictionary>::from_xml(&xml)
+            .unwrap();
+        assert_round_trip(back, &obj);
+
+        // resolving bulk data
+        let xml = obj
+            .to_xml_with(|e| (e.tag() == tags::PIXEL_DATA).then(|| "bulk/1".into()))
+            .unwrap();
+        let back: InMemDicomObject = InMemDicomObject::from_xml(&xml).unwrap();
+        assert_eq!(
+            back.get(tags::PIXEL_DATA).map(|e| e.value().primitive()),
+            Some(Some(&PrimitiveValue::Empty))
+        );
+        let back: InMemDicomObject = InMemDicomObject::from_xml_with(&xml, |tag, uri| {
+            assert_eq!(tag, tags::PIXEL_DATA);
+            assert_eq!(uri, "bulk/1");
+            Some(vec![1, 2, 3, 4])
+        })
+        .unwrap();
+        assert_round_trip(back, &obj);
+    }
+
+    #[test]
+    fn read_native_dicom_model() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- from a validator -->
+<NativeDicomModel xml:space="preserve">
+  <DicomAttribute keyword="InstanceNumber" tag="00200013" vr="IS">
+    <Value number="2">7</Value>
+    <Value number="1">5</Value>
+  </DicomAttribute>
+  <DicomAttribute tag='00100020' vr='LO'><Value number="1"><![CDATA[A<1>]]></Value></DicomAttribute>
+  <DicomAttribute tag="00100010" vr="PN">
+    <PersonName number="1"><Phonetic><FamilyName>Smith</FamilyName></Phonetic></PersonName>
+  </DicomAttribute>
+</NativeDicomModel>
+"#;
+        let obj: InMemDicomObject = InMemDicomObject::from_xml(xml).unwrap();
+        assert_eq!(
+            obj.get(tags::INSTANCE_NUMBER)
+                .unwrap()
+                .value()
+                .to_multi_str()
+                .unwrap(),
+            &["5", "7"][..]
+        );
+        assert_eq!(obj.get(tags::PATIENT_ID).unwrap().to_str().unwrap(), "A<1>");
+        assert_eq!(
+            obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(),
+            "==Smith"
+        );
+
+        assert!(matches!(
+            InMemDicomObject::<dicom_dictionary_std::StandardDataDictionary>::from_xml("<Other/>"),
+            Err(FromXmlError::UnexpectedElement { .. })
+        ));
+        assert!(matches!(
+            InMemDicomObject::<dicom_dictionary_std::StandardDataDictionary>::from_xml(
+                "<NativeDicomModel><DicomAttribute tag=\"00100020\"/></NativeDicomModel>"
+            ),
+            Err(FromXmlError::MissingAttribute {
+                attribute: "vr",
+                ..
+            })
+        ));
+        assert!(matches!(
+            InMemDicomObject::<dicom_dictionary_std::StandardDataDictionary>::from_xml(
+                "<NativeDicomModel><DicomAttribute tag=\"00280010\" vr=\"US\"><Value number=\"1\">x</Value></DicomAttribute></NativeDicomModel>"
+            ),
+            Err(FromXmlError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            InMemDicomObject::<dicom_dictionary_std::StandardDataDictionary>::from_xml(
+                "<NativeDicomModel><DicomAttribute>"
+            ),
+            Err(FromXmlError::Syntax { .. })
+        ));
+    }
+}
@@ -312,6 +312,15 @@ where
         self.parser
     }
 
+    /// Retrieve the current position of the inner stateful decoder.
+    ///
+    /// When called in between tokens,
+    /// this is the position at which the next token starts,
+    /// unless a token was peeked.
+    pub fn position(&self) -> u64 {
+        self.parser.position()
+    }
+
     /// Advance and retrieve the next DICOM data token.
     ///
     /// **Note:** For the data set to be successfully parsed,
@@ -1400,7 +1409,7 @@ mod tests {
             // -- 32 --
         ];
 
-        let ground_truth = vec![
+        let ground_truth = [
             DataToken::SequenceStart {
                 tag: Tag(0x0018, 0x6011),
                 len: Length::UNDEFINED,
@@ -1418,7 +1427,7 @@ mod tests {
         let parser = StatefulDecoder::new(
             &mut cursor,
             ExplicitVRLittleEndianDecoder::default(),
-            LittleEndianBasicDecoder::default(),
+            LittleEndianBasicDecoder,
             SpecificCharacterSet::default(),
         );
         let mut dset_reader = LazyDataSetReader::new(parser);
@@ -1493,7 +1502,7 @@ mod tests {
             0x00, // padding
         ];
 
-        let ground_truth = vec![
+        let ground_truth = [
             DataToken::ElementHeader(DataElementHeader {
                 tag: Tag(0x0008, 0x0016),
                 vr: VR::UI,
@@ -1,11 +1,99 @@
 //! Interpretation of DICOM data sets as streams of tokens.
+//!
+//! A data set is represented here as a flat stream of [`DataToken`]s.
+//! This lets custom processors such as filters, splitters, or signers
+//! work on data sets of any size
+//! without building an in-memory object first.
+//!
+//! # Token stream
+//!
+//! The tokens of a data set follow its structure:
+//!
+//! - a primitive data element is an [`ElementHeader`](DataToken::ElementHeader)
+//!   followed by its [`PrimitiveValue`](DataToken::PrimitiveValue);
+//! - a sequence is a [`SequenceStart`](DataToken::SequenceStart),
+//!   then for each item an [`ItemStart`](DataToken::ItemStart),
+//!   the tokens of the item's data elements, and an [`ItemEnd`](DataToken::ItemEnd),
+//!   and finally a [`SequenceEnd`](DataToken::SequenceEnd);
+//! - encapsulated pixel data is a [`PixelSequenceStart`](DataToken::PixelSequenceStart),
+//!   then the basic offset table item,
+//!   with an [`OffsetTable`](DataToken::OffsetTable) token unless the table is empty,
+//!   then each fragment as an item with an [`ItemValue`](DataToken::ItemValue),
+//!   and finally a [`SequenceEnd`](DataToken::SequenceEnd).
+//!
+//! The end tokens of sequences and items are always present,
+//! regardless of whether their lengths are explicit or undefined.
+//!
+//! A [`DataSetReader`] produces the tokens of a data set from a byte source,
+//! optionally along with their [positions](DataSetReader::positioned) in the source,
+//! and a [`DataSetWriter`] encodes tokens into a byte sink.
+//! In between, tokens can be transformed with the usual iterator adapters,
+//! and [`pipe`] writes the outcome of such a chain.
+//!
+//! # Example
+//!
+//! Copy a data set while replacing the value of _Patient Name_:
+//!
+//! ```
+//! # use dicom_core::{Tag, VR, header::{DataElementHeader, Length}, PrimitiveValue};
+//! # use dicom_encoding::decode::basic::LittleEndianBasicDecoder;
+//! # use dicom_encoding::decode::explicit_le::ExplicitVRLittleEndianDecoder;
+//! # use dicom_encoding::encode::{EncoderFor, explicit_le::ExplicitVRLittleEndianEncoder};
+//! # use dicom_encoding::text::SpecificCharacterSet;
+//! use dicom_parser::StatefulDecoder;
+//! use dicom_parser::dataset::{DataSetReader, DataSetWriter, DataToken, pipe};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut input = Vec::new();
+//! # DataSetWriter::new(&mut input, EncoderFor::new(ExplicitVRLittleEndianEncoder::default()))
+//! #     .write_sequence([
+//! #         DataToken::ElementHeader(DataElementHeader::new(Tag(0x0010, 0x0010), VR::PN, Length(8))),
+//! #         DataToken::PrimitiveValue(PrimitiveValue::from("Doe^John")),
+//! #     ])?;
+//! let decoder = StatefulDecoder::new(
+//!     &input[..],
+//!     ExplicitVRLittleEndianDecoder::default(),
+//!     LittleEndianBasicDecoder,
+//!     SpecificCharacterSet::default(),
+//! );
+//! let mut reader = DataSetReader::new(decoder, Default::default());
+//!
+//! let mut output = Vec::new();
+//! let mut writer = DataSetWriter::new(
+//!     &mut output,
+//!     EncoderFor::new(ExplicitVRLittleEndianEncoder::default()),
+//! );
+//!
+//! let mut in_patient_name = false;
+//! let tokens = reader.positioned().map(|token| {
+//!     let (position, token) = token?;
+//!     Ok(match token {
+//!         DataToken::ElementHeader(header) => {
+//!             println!("{} at byte {position}", header.tag);
+//!             in_patient_name = header.tag == Tag(0x0010, 0x0010);
+//!             DataToken::ElementHeader(header)
+//!         }
+//!         DataToken::PrimitiveValue(_) if in_patient_name => {
+//!             DataToken::PrimitiveValue(PrimitiveValue::from("Anonymous"))
+//!         }
+//!         token => token,
+//!     })
+//! });
+//! pipe(tokens, &mut writer)?;
+//! # drop(writer);
+//! # assert_eq!(&output[8..], b"Anonymous ");
+//! # Ok(())
+//! # }
+//! ```
 use crate::stateful::decode;
 use dicom_core::header::{DataElementHeader, HasLength, Length, VR};
 use dicom_core::value::{DicomValueType, PrimitiveValue};
 use dicom_core::{DataElement, Tag, value::Value};
+use dicom_encoding::encode::EncodeTo;
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::default::Default;
 use std::fmt;
+use std::io::Write;
 
 pub mod lazy_read;
 pub mod read;
@@ -31,6 +119,43 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// An error which may occur when [piping](pipe) data set tokens into a writer.
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum PipeError {
+    /// Could not read data set token
+    PipeReadToken {
+        #[snafu(backtrace)]
+        source: read::Error,
+    },
+    /// Could not write data set token
+    PipeWriteToken {
+        #[snafu(backtrace)]
+        source: write::Error,
+    },
+}
+
+/// Write a stream of data set tokens with a data set writer,
+/// such as the tokens of a [`DataSetReader`]
+/// after passing through a chain of transformations.
+///
+/// Writing stops at the first error,
+/// whether in the stream or while writing.
+/// The writer is flushed once all tokens are written.
+pub fn pipe<I, W, E>(tokens: I, writer: &mut DataSetWriter<W, E>) -> Result<(), PipeError>
+where
+    I: IntoIterator<Item = Result<DataToken, read::Error>>,
+    W: Write,
+    E: EncodeTo<W>,
+{
+    for token in tokens {
+        writer
+            .write(token.context(PipeReadTokenSnafu)?)
+            .context(PipeWriteTokenSnafu)?;
+    }
+    writer.flush().context(PipeWriteTokenSnafu)
+}
+
 /// A token of a DICOM data set stream. This is part of the interpretation of a
 /// data set as a stream of symbols, which may either represent data headers or
 /// actual value data.
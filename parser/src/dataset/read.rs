@@ -199,6 +199,49 @@ impl DataSetReaderOptions {
     }
 }
 
+/// A set of data elements which the data set reader should skip entirely.
+///
+/// The values of skipped elements are passed over
+/// without being read into memory,
+/// and no tokens are produced for them.
+/// Elements are skipped at any level of the data set,
+/// including inside sequence items.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq)]
+pub struct SkipElements {
+    tags: Vec<Tag>,
+    groups: Vec<u16>,
+}
+
+impl SkipElements {
+    /// Create an empty set, which does not skip any element.
+    pub fn new() -> Self {
+        SkipElements::default()
+    }
+
+    /// Add data elements with the given tags to the set.
+    pub fn tags(mut self, tags: &[Tag]) -> Self {
+        self.tags.extend_from_slice(tags);
+        self
+    }
+
+    /// Add all data elements in the given groups to the set.
+    pub fn groups(mut self, groups: &[u16]) -> Self {
+        self.groups.extend_from_slice(groups);
+        self
+    }
+
+    /// Check whether no element is skipped.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.groups.is_empty()
+    }
+
+    /// Check whether data elements with the given tag are skipped.
+    pub fn contains(&self, tag: Tag) -> bool {
+        // item and delimitation items are never skipped
+        tag.group() != 0xFFFE && (self.tags.contains(&tag) || self.groups.contains(&tag.group()))
+    }
+}
+
 /// A higher-level reader for retrieving structure in a DICOM data set from an
 /// arbitrary data source.
 #[derive(Debug)]
@@ -220,8 +263,19 @@ pub struct DataSetReader<S> {
     hard_break: bool,
     /// last decoded header
     last_header: Option<DataElementHeader>,
-    /// if a peek was taken, this holds the token peeked
-    peek: Option<DataToken>,
+    /// if a peek was taken, this holds the token peeked and its position
+    peek: Option<(u64, DataToken)>,
+    /// the position of the first byte of the last token produced
+    token_position: u64,
+    /// the data elements to skip
+    skip: SkipElements,
+    /// the sequence depth to return to
+    /// while skipping an element of undefined length
+    skip_depth: Option<usize>,
+    /// the tags of the elements skipped so far
+    skipped: Vec<Tag>,
+    /// whether the source ended in the middle of a pixel data fragment
+    truncated: bool,
 }
 
 impl<R> DataSetReader<DynStatefulDecoder<R>> {
@@ -303,6 +357,11 @@ impl<R> DataSetReader<DynStatefulDecoder<R>> {
             hard_break: false,
             last_header: None,
             peek: None,
+            token_position: 0,
+            skip: SkipElements::default(),
+            skip_depth: None,
+            skipped: Vec::new(),
+            truncated: false,
         })
     }
 }
@@ -320,8 +379,88 @@ impl<S> DataSetReader<S> {
             hard_break: false,
             last_header: None,
             peek: None,
+            token_position: 0,
+            skip: SkipElements::default(),
+            skip_depth: None,
+            skipped: Vec::new(),
+            truncated: false,
         }
     }
+
+    /// Set the data elements which this reader should skip entirely.
+    ///
+    /// See [`SkipElements`] for more details.
+    pub fn skip_elements(mut self, skip: SkipElements) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Retrieve the tags of the data elements skipped so far,
+    /// in order of first appearance.
+    pub fn skipped_tags(&self) -> &[Tag] {
+        &self.skipped
+    }
+
+    /// Check whether the source ended in the middle of
+    /// a fragment of encapsulated pixel data.
+    ///
+    /// Instead of failing,
+    /// the reader then produces the bytes of the fragment which were available,
+    /// closes all open items and sequences,
+    /// and ends the data set.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<S> DataSetReader<S>
+where
+    S: StatefulDecode,
+{
+    /// Retrieve the current position of the inner stateful decoder.
+    ///
+    /// When called in between tokens,
+    /// this is the position right after the last token read,
+    /// unless a token was peeked.
+    pub fn decoder_position(&self) -> u64 {
+        self.parser.position()
+    }
+
+    /// Retrieve the position of the first byte of the last token produced,
+    /// in the same terms as [`decoder_position`](Self::decoder_position).
+    ///
+    /// Tokens which are not encoded in the source,
+    /// such as the end of a sequence or item with an explicit length,
+    /// are positioned right after the last byte of what they close.
+    /// Peeking a token does not change this position.
+    pub fn token_position(&self) -> u64 {
+        self.token_position
+    }
+
+    /// Obtain an iterator over the tokens of this reader
+    /// paired with their [positions](Self::token_position).
+    pub fn positioned(&mut self) -> Positioned<'_, S> {
+        Positioned { reader: self }
+    }
+}
+
+/// An iterator over data set tokens and their positions,
+/// created by [`DataSetReader::positioned`].
+#[derive(Debug)]
+pub struct Positioned<'a, S> {
+    reader: &'a mut DataSetReader<S>,
+}
+
+impl<S> Iterator for Positioned<'_, S>
+where
+    S: StatefulDecode,
+{
+    type Item = Result<(u64, DataToken)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.reader.next()?;
+        Some(token.map(|token| (self.reader.token_position, token)))
+    }
 }
 
 impl<S> Iterator for DataSetReader<S>
@@ -331,13 +470,52 @@ where
     type Item = Result<DataToken>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        // if there was a peek, consume peeked token
+        if let Some((position, token)) = self.peek.take() {
+            self.token_position = position;
+            return Some(Ok(token));
+        }
+        if self.skip.is_empty() {
+            return self.read_token();
+        }
+        loop {
+            let token = match self.read_token()? {
+                Ok(token) => token,
+                Err(e) => return Some(Err(e)),
+            };
+            match self.skip_token(&token) {
+                Ok(true) => continue,
+                Ok(false) => return Some(Ok(token)),
+                Err(e) => {
+                    self.hard_break = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl<S> DataSetReader<S>
+where
+    S: StatefulDecode,
+{
+    /// Read the next token from the source,
+    /// regardless of the elements to skip.
+    fn read_token(&mut self) -> Option<Result<DataToken>> {
         loop {
             if self.hard_break {
                 return None;
             }
-            // if there was a peek, consume peeked token
-            if let Some(token) = self.peek.take() {
-                return Some(Ok(token));
+            self.token_position = self.parser.position();
+
+            if self.truncated {
+                // the source has ended, close everything still open
+                return self.seq_delimiters.pop().map(|sd| {
+                    Ok(match sd.typ {
+                        SeqTokenType::Sequence => DataToken::SequenceEnd,
+                        SeqTokenType::Item => DataToken::ItemEnd,
+                    })
+                });
             }
 
             // item or sequence delimitation logic for explicit lengths
@@ -444,7 +622,17 @@ where
                     None => return Some(UndefinedItemLengthSnafu.fail()),
                 };
 
-                if self.offset_table_next {
+                if self.offset_table_next && self.skip_depth.is_some() {
+                    // offset table of skipped pixel data
+                    self.offset_table_next = false;
+                    self.delimiter_check_pending = true;
+                    Some(
+                        self.parser
+                            .skip_bytes(len as u32)
+                            .map(|_| DataToken::OffsetTable(Vec::new()))
+                            .context(ReadItemValueSnafu { len: len as u32 }),
+                    )
+                } else if self.offset_table_next {
                     // offset table
                     let mut offset_table = Vec::with_capacity(len);
 
@@ -459,20 +647,33 @@ where
                             Err(e) => Err(e).context(ReadItemValueSnafu { len: len as u32 }),
                         },
                     )
+                } else if self.skip_depth.is_some() {
+                    // item value of skipped pixel data
+                    self.delimiter_check_pending = true;
+                    Some(
+                        self.parser
+                            .skip_bytes(len as u32)
+                            .map(|_| DataToken::ItemValue(Vec::new()))
+                            .context(ReadItemValueSnafu { len: len as u32 }),
+                    )
                 } else {
                     // item value
                     let mut value = Vec::with_capacity(len);
 
                     // need to pop item delimiter on the next iteration
                     self.delimiter_check_pending = true;
-                    Some(
-                        self.parser
-                            .read_to_vec(len as u32, &mut value)
-                            .map(|_| Ok(DataToken::ItemValue(value)))
-                            .unwrap_or_else(|e| {
-                                Err(e).context(ReadItemValueSnafu { len: len as u32 })
-                            }),
-                    )
+                    if let Err(e) = self.parser.read_to_vec(len as u32, &mut value) {
+                        return Some(Err(e).context(ReadItemValueSnafu { len: len as u32 }));
+                    }
+                    if value.len() < len {
+                        tracing::warn!(
+                            "Pixel data fragment truncated, only {} of {} bytes available",
+                            value.len(),
+                            len
+                        );
+                        self.truncated = true;
+                    }
+                    Some(Ok(DataToken::ItemValue(value)))
                 }
             } else if let Some(header) = self.last_header {
                 if header.is_encapsulated_pixeldata() {
@@ -660,6 +861,71 @@ impl<S> DataSetReader<S>
 where
     S: StatefulDecode,
 {
+    /// Handle the elements to skip after reading the given token.
+    ///
+    /// Returns `true` if the token belongs to a skipped element
+    /// and should not be produced.
+    fn skip_token(&mut self, token: &DataToken) -> Result<bool> {
+        let skipping = self.skip_depth.is_some();
+        match token {
+            DataToken::ElementHeader(header) if skipping || self.skip.contains(header.tag) => {
+                if !skipping {
+                    self.record_skipped(header.tag);
+                }
+                // pass over the primitive value
+                self.parser
+                    .skip_bytes(header.len.0)
+                    .context(ReadValueSnafu {
+                        len: header.len.0,
+                        tag: header.tag,
+                    })?;
+                self.last_header = None;
+                // sequences can end after this element
+                self.delimiter_check_pending = true;
+                Ok(true)
+            }
+            DataToken::SequenceStart { tag, len } if skipping || self.skip.contains(*tag) => {
+                if !skipping {
+                    self.record_skipped(*tag);
+                }
+                if let Some(len) = len.get() {
+                    // pass over the whole sequence at once
+                    self.parser
+                        .skip_bytes(len)
+                        .context(ReadValueSnafu { len, tag: *tag })?;
+                    self.seq_delimiters.pop();
+                    self.in_sequence = false;
+                    // sequences can end after this element
+                    self.delimiter_check_pending = true;
+                } else if !skipping {
+                    // walk through the sequence until it ends
+                    self.skip_depth = Some(self.seq_delimiters.len() - 1);
+                }
+                Ok(true)
+            }
+            DataToken::PixelSequenceStart
+                if !skipping && self.skip.contains(Tag(0x7FE0, 0x0010)) =>
+            {
+                self.record_skipped(Tag(0x7FE0, 0x0010));
+                // walk through the fragments until the pixel sequence ends
+                self.skip_depth = Some(self.seq_delimiters.len());
+                Ok(true)
+            }
+            DataToken::SequenceEnd if self.skip_depth == Some(self.seq_delimiters.len()) => {
+                // end of the skipped element
+                self.skip_depth = None;
+                Ok(true)
+            }
+            _ => Ok(skipping),
+        }
+    }
+
+    fn record_skipped(&mut self, tag: Tag) {
+        if !self.skipped.contains(&tag) {
+            self.skipped.push(tag);
+        }
+    }
+
     /// Peek the next token from the source by
     /// reading a new token in the first call.
     /// Subsequent calls to `peek` will return the same token
@@ -668,15 +934,17 @@ where
     pub fn peek(&mut self) -> Result<Option<&DataToken>> {
         if self.peek.is_none() {
             // try to read the next token
+            let position = self.token_position;
             match self.next() {
                 None => return Ok(None),
                 Some(Err(e)) => return Err(e),
                 Some(Ok(token)) => {
-                    self.peek = Some(token);
+                    self.peek = Some((self.token_position, token));
+                    self.token_position = position;
                 }
             }
         }
-        Ok(self.peek.as_ref())
+        Ok(self.peek.as_ref().map(|(_, token)| token))
     }
 
     fn update_seq_delimiters(&mut self) -> Result<Option<DataToken>> {
@@ -756,7 +1024,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{DataSetReader, DataToken, StatefulDecode};
+    use super::{DataSetReader, DataToken, SkipElements, StatefulDecode};
     use crate::dataset::read::{DataSetReaderOptions, OddLengthStrategy};
     use crate::stateful::decode::StatefulDecoder;
     use dicom_core::header::{DataElementHeader, Length};
@@ -1235,6 +1503,52 @@ mod tests {
         validate_read_data_explicit_vr(DATA, ground_truth);
     }
 
+    #[test]
+    fn read_truncated_pixel_data_fragment() {
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            0xe0, 0x7f, 0x10, 0x00, // (7FE0, 0010) PixelData
+            b'O', b'B', // VR
+            0x00, 0x00, // reserved
+            0xff, 0xff, 0xff, 0xff, // length: undefined
+            // -- 12 -- Basic offset table
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0x00, 0x00, 0x00, 0x00, // item length: 0
+            // -- 20 -- First fragment of pixel data
+            0xfe, 0xff, 0x00, 0xe0, // item start tag
+            0x20, 0x00, 0x00, 0x00, // item length: 32
+            // -- 28 -- Compressed Fragment, cut short
+            0x99, 0x99, 0x99, 0x99, 0x99, 0x99, 0x99, 0x99,
+            0x99, 0x99, 0x99, 0x99,
+        ];
+
+        let mut cursor = DATA;
+        let parser = StatefulDecoder::new(
+            &mut cursor,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let mut dset_reader = DataSetReader::new(parser, Default::default());
+        let tokens: Vec<_> = dset_reader
+            .by_ref()
+            .collect::<Result<_, _>>()
+            .expect("truncated fragment should not fail");
+        assert_eq!(
+            tokens,
+            vec![
+                DataToken::PixelSequenceStart,
+                DataToken::ItemStart { len: Length(0) },
+                DataToken::ItemEnd,
+                DataToken::ItemStart { len: Length(32) },
+                DataToken::ItemValue(vec![0x99; 12]),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+            ]
+        );
+        assert!(dset_reader.is_truncated());
+    }
+
     #[test]
     fn read_dataset_in_dataset() {
         #[rustfmt::skip]
@@ -1350,7 +1664,7 @@ mod tests {
             b'T', b'E', b'S', b'T', // value = "TEST"
         ];
 
-        let ground_truth = vec![
+        let ground_truth = [
             DataToken::SequenceStart {
                 tag: Tag(0x0018, 0x6011),
                 len: Length::UNDEFINED,
@@ -1521,4 +1835,240 @@ mod tests {
             token
         );
     }
+
+    fn validate_read_data_explicit_vr_skipping<I>(data: &[u8], skip: SkipElements, ground_truth: I)
+    where
+        I: IntoIterator<Item = DataToken>,
+    {
+        let mut cursor = data;
+        let parser = StatefulDecoder::new(
+            &mut cursor,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let dset_reader = DataSetReader::new(parser, Default::default()).skip_elements(skip);
+        validate_data_set_reader(data, dset_reader, ground_truth);
+    }
+
+    #[test]
+    fn skip_elements_by_tag_and_group() {
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            // (0008,0060) Modality, len = 2, value = "MR"
+            0x08, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'M', b'R',
+            // (0008,2218) AnatomicRegionSequence, len = 18
+            0x08, 0x00, 0x18, 0x22, b'S', b'Q', 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
+            // item, len = 10
+            0xfe, 0xff, 0x00, 0xe0, 0x0a, 0x00, 0x00, 0x00,
+            // (0008,0100) CodeValue, len = 2
+            0x08, 0x00, 0x00, 0x01, b'S', b'H', 0x02, 0x00, b'T', b'1',
+            // (0020,4000) ImageComments, len = 4, value = "TEST"
+            0x20, 0x00, 0x00, 0x40, b'L', b'T', 0x04, 0x00, b'T', b'E', b'S', b'T',
+            // (6000,0010) OverlayRows, len = 2
+            0x00, 0x60, 0x10, 0x00, b'U', b'S', 0x02, 0x00, 0x40, 0x00,
+        ];
+
+        // skip a defined length sequence and a group
+        validate_read_data_explicit_vr_skipping(
+            DATA,
+            SkipElements::new()
+                .tags(&[Tag(0x0008, 0x2218)])
+                .groups(&[0x6000]),
+            vec![
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: Tag(0x0008, 0x0060),
+                    vr: VR::CS,
+                    len: Length(2),
+                }),
+                DataToken::PrimitiveValue(PrimitiveValue::Str("MR".into())),
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: Tag(0x0020, 0x4000),
+                    vr: VR::LT,
+                    len: Length(4),
+                }),
+                DataToken::PrimitiveValue(PrimitiveValue::Str("TEST".into())),
+            ],
+        );
+
+        // skip elements inside a sequence item
+        validate_read_data_explicit_vr_skipping(
+            DATA,
+            SkipElements::new().tags(&[Tag(0x0008, 0x0100), Tag(0x0020, 0x4000)]),
+            vec![
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: Tag(0x0008, 0x0060),
+                    vr: VR::CS,
+                    len: Length(2),
+                }),
+                DataToken::PrimitiveValue(PrimitiveValue::Str("MR".into())),
+                DataToken::SequenceStart {
+                    tag: Tag(0x0008, 0x2218),
+                    len: Length(18),
+                },
+                DataToken::ItemStart { len: Length(10) },
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: Tag(0x6000, 0x0010),
+                    vr: VR::US,
+                    len: Length(2),
+                }),
+                DataToken::PrimitiveValue(PrimitiveValue::U16([0x40].as_ref().into())),
+            ],
+        );
+    }
+
+    #[test]
+    fn skip_undefined_length_sequence() {
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            // (0018,6011) SequenceOfUltrasoundRegions, len = undefined
+            0x18, 0x00, 0x11, 0x60, b'S', b'Q', 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+            // item, len = undefined
+            0xfe, 0xff, 0x00, 0xe0, 0xff, 0xff, 0xff, 0xff,
+            // (0040,0555) AcquisitionContextSequence, len = undefined
+            0x40, 0x00, 0x55, 0x05, b'S', b'Q', 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+            // sequence end
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // (0018,6012) RegionSpatialFormat, len = 2, value = 1
+            0x18, 0x00, 0x12, 0x60, b'U', b'S', 0x02, 0x00, 0x01, 0x00,
+            // item end
+            0xfe, 0xff, 0x0d, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // sequence end
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // (0020,4000) ImageComments, len = 4, value = "TEST"
+            0x20, 0x00, 0x00, 0x40, b'L', b'T', 0x04, 0x00, b'T', b'E', b'S', b'T',
+        ];
+
+        validate_read_data_explicit_vr_skipping(
+            DATA,
+            SkipElements::new().tags(&[Tag(0x0018, 0x6011)]),
+            vec![
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: Tag(0x0020, 0x4000),
+                    vr: VR::LT,
+                    len: Length(4),
+                }),
+                DataToken::PrimitiveValue(PrimitiveValue::Str("TEST".into())),
+            ],
+        );
+    }
+
+    #[test]
+    fn skip_encapsulated_pixeldata() {
+        #[rustfmt::skip]
+        static DATA: &[u8] = &[
+            // (7FE0,0010) PixelData, len = undefined
+            0xe0, 0x7f, 0x10, 0x00, b'O', b'B', 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+            // basic offset table, len = 4
+            0xfe, 0xff, 0x00, 0xe0, 0x04, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            // fragment, len = 8
+            0xfe, 0xff, 0x00, 0xe0, 0x08, 0x00, 0x00, 0x00,
+            0x99, 0x99, 0x99, 0x99, 0x99, 0x99, 0x99, 0x99,
+            // sequence end
+            0xfe, 0xff, 0xdd, 0xe0, 0x00, 0x00, 0x00, 0x00,
+            // (fffc,fffc) DataSetTrailingPadding, len = 2
+            0xfc, 0xff, 0xfc, 0xff, b'O', b'B', 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+
+        validate_read_data_explicit_vr_skipping(
+            DATA,
+            SkipElements::new().tags(&[Tag(0x7FE0, 0x0010)]),
+            vec![
+                DataToken::ElementHeader(DataElementHeader::new(
+                    Tag(0xfffc, 0xfffc),
+                    VR::OB,
+                    Length(2),
+                )),
+                DataToken::PrimitiveValue(PrimitiveValue::U8([0x00; 2].as_ref().into())),
+            ],
+        );
+    }
+
+    #[test]
+    fn read_token_positions() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            // (0010,0010) PN, 8 bytes
+            0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x08, 0x00,
+            b'D', b'o', b'e', b'^', b'J', b'o', b'h', b'n',
+            // (0040,A730) SQ, defined length of 36 bytes
+            0x40, 0x00, 0x30, 0xA7, b'S', b'Q', 0x00, 0x00, 0x24, 0x00, 0x00, 0x00,
+            // item, defined length of 28 bytes
+            0xFE, 0xFF, 0x00, 0xE0, 0x1C, 0x00, 0x00, 0x00,
+            // (0040,A010) CS, 8 bytes
+            0x40, 0x00, 0x10, 0xA0, b'C', b'S', 0x08, 0x00,
+            b'C', b'O', b'N', b'T', b'A', b'I', b'N', b'S',
+            // (0040,A040) CS, 4 bytes
+            0x40, 0x00, 0x40, 0xA0, b'C', b'S', 0x04, 0x00,
+            b'T', b'E', b'X', b'T',
+        ];
+        let parser = StatefulDecoder::new(
+            data,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+        let mut reader = DataSetReader::new(parser, Default::default());
+
+        assert!(matches!(
+            reader.next(),
+            Some(Ok(DataToken::ElementHeader(_)))
+        ));
+        assert_eq!(reader.token_position(), 0);
+        // peeking does not move the token position
+        assert!(matches!(
+            reader.peek(),
+            Ok(Some(DataToken::PrimitiveValue(_)))
+        ));
+        assert_eq!(reader.token_position(), 0);
+
+        let positions: Vec<_> = reader
+            .positioned()
+            .map(|token| {
+                let (position, token) = token.unwrap();
+                (position, token.to_string())
+            })
+            .collect();
+        let value = |s: &str| {
+            DataToken::PrimitiveValue(PrimitiveValue::Strs(smallvec::smallvec![s.to_string()]))
+        };
+        let expected = [
+            (8, value("Doe^John")),
+            (
+                16,
+                DataToken::SequenceStart {
+                    tag: Tag(0x0040, 0xA730),
+                    len: Length(36),
+                },
+            ),
+            (28, DataToken::ItemStart { len: Length(28) }),
+            (
+                36,
+                DataToken::ElementHeader(DataElementHeader::new(
+                    Tag(0x0040, 0xA010),
+                    VR::CS,
+                    Length(8),
+                )),
+            ),
+            (44, value("CONTAINS")),
+            (
+                52,
+                DataToken::ElementHeader(DataElementHeader::new(
+                    Tag(0x0040, 0xA040),
+                    VR::CS,
+                    Length(4),
+                )),
+            ),
+            (60, value("TEXT")),
+            // implicit ends have no bytes of their own
+            (64, DataToken::ItemEnd),
+            (64, DataToken::SequenceEnd),
+        ]
+        .map(|(position, token)| (position, token.to_string()));
+        assert_eq!(positions, expected);
+    }
 }
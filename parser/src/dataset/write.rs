@@ -3,13 +3,16 @@
 //! This module contains a mid-level abstraction for printing DICOM data sets
 //! sequentially.
 //! The [`DataSetWriter`] receives data tokens to be encoded and written
-//! to a writer.
+//! to a writer,
+//! either one at a time via [`write`](DataSetWriter::write)
+//! or through the methods for writing whole elements,
+//! sequences, and pixel data fragments.
 //! In this process, the writer will also adapt values
 //! to the necessary DICOM encoding rules.
-use crate::dataset::{DataToken, SeqTokenType};
+use crate::dataset::{DataToken, IntoTokens, SeqTokenType};
 use crate::stateful::encode::StatefulEncoder;
-use dicom_core::header::Header;
-use dicom_core::{DataElementHeader, Length, Tag, VR};
+use dicom_core::header::{HasLength, Header};
+use dicom_core::{DataElement, DataElementHeader, Length, PrimitiveValue, Tag, VR};
 use dicom_encoding::TransferSyntax;
 use dicom_encoding::encode::EncodeTo;
 use dicom_encoding::text::SpecificCharacterSet;
@@ -120,12 +123,52 @@ pub enum ExplicitLengthSqItemStrategy {
     // Recalculate,
 }
 
+/// A strategy for writing group length elements (gggg,0000).
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum GroupLengthStrategy {
+    /// Group length elements are written as they are encountered
+    /// in the data set, without any change.
+    ///
+    /// This is the default behavior.
+    #[default]
+    NoChange,
+    /// A group length element is written
+    /// at the start of each group in the root data set,
+    /// with its value calculated from the encoded elements of that group.
+    /// Existing group length elements in the root data set are replaced.
+    ///
+    /// Since the length of a group is only known once the group is complete,
+    /// the writer keeps the encoded elements of the current group in memory
+    /// until the next group begins or the writer is flushed.
+    /// As such, [`flush`](DataSetWriter::flush) must be called
+    /// after the last token is written.
+    ///
+    /// This strategy is only supported by writers
+    /// created from a transfer syntax,
+    /// such as through [`with_ts_options`](DataSetWriter::with_ts_options).
+    /// Other writers behave as in [`NoChange`](GroupLengthStrategy::NoChange).
+    Recalculate,
+}
+
 /// The set of options for the data set writer.
 #[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
 #[non_exhaustive]
 pub struct DataSetWriterOptions {
     /// What to do with sequences and items with explicit lengths.
     pub explicit_length_sq_item_strategy: ExplicitLengthSqItemStrategy,
+    /// Whether to retain odd value lengths.
+    ///
+    /// When enabled, primitive values with an odd length
+    /// which matches their element header exactly
+    /// are written without padding.
+    /// This is meant for reproducing data sets
+    /// as they were originally encoded,
+    /// and should otherwise be left disabled (the default),
+    /// since DICOM requires values to have an even length.
+    pub preserve_odd_lengths: bool,
+    /// What to do with group length elements.
+    pub group_length_strategy: GroupLengthStrategy,
 }
 
 impl DataSetWriterOptions {
@@ -137,6 +180,48 @@ impl DataSetWriterOptions {
         self.explicit_length_sq_item_strategy = exp_length;
         self
     }
+
+    /// Replace whether odd value lengths are retained in the options.
+    pub fn preserve_odd_lengths(mut self, preserve: bool) -> Self {
+        self.preserve_odd_lengths = preserve;
+        self
+    }
+
+    /// Replace the write strategy for group length elements of the options.
+    pub fn group_length_strategy(mut self, group_length: GroupLengthStrategy) -> Self {
+        self.group_length_strategy = group_length;
+        self
+    }
+}
+
+/// The state for writing group length elements,
+/// holding the encoded elements of the current group
+/// until its length is known.
+struct GroupBuffer {
+    /// the group of the elements currently in the buffer
+    group: Option<u16>,
+    /// whether the value of a replaced group length element is to be skipped
+    skip_value: bool,
+    printer: StatefulEncoder<Vec<u8>, DynEncoder<'static, Vec<u8>>>,
+}
+
+impl std::fmt::Debug for GroupBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupBuffer")
+            .field("group", &self.group)
+            .field("skip_value", &self.skip_value)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GroupBuffer {
+    fn new(encoder: DynEncoder<'static, Vec<u8>>, charset: SpecificCharacterSet) -> Self {
+        GroupBuffer {
+            group: None,
+            skip_value: false,
+            printer: StatefulEncoder::new(Vec::new(), encoder, charset),
+        }
+    }
 }
 
 /// A stateful device for printing a DICOM data set in sequential order.
@@ -148,6 +233,8 @@ pub struct DataSetWriter<W, E, T = SpecificCharacterSet> {
     seq_tokens: Vec<SeqToken>,
     last_de: Option<DataElementHeader>,
     options: DataSetWriterOptions,
+    /// only present when writing group lengths
+    group_buffer: Option<GroupBuffer>,
 }
 
 impl<'w, W: 'w> DataSetWriter<W, DynEncoder<'w, W>>
@@ -179,16 +266,7 @@ where
         ts: &TransferSyntax,
         options: DataSetWriterOptions,
     ) -> Result<Self> {
-        let encoder = ts.encoder_for().context(UnsupportedTransferSyntaxSnafu {
-            ts_uid: ts.uid(),
-            ts_alias: ts.name(),
-        })?;
-        Ok(DataSetWriter::new_with_codec_options(
-            to,
-            encoder,
-            SpecificCharacterSet::default(),
-            options,
-        ))
+        Self::with_ts_cs_options(to, ts, SpecificCharacterSet::default(), options)
     }
 
     /// Create a new data set writer
@@ -204,9 +282,18 @@ where
             ts_uid: ts.uid(),
             ts_alias: ts.name(),
         })?;
-        Ok(DataSetWriter::new_with_codec_options(
-            to, encoder, charset, options,
-        ))
+        let group_buffer = if options.group_length_strategy == GroupLengthStrategy::Recalculate {
+            let encoder = ts.encoder_for().context(UnsupportedTransferSyntaxSnafu {
+                ts_uid: ts.uid(),
+                ts_alias: ts.name(),
+            })?;
+            Some(GroupBuffer::new(encoder, charset.clone()))
+        } else {
+            None
+        };
+        let mut writer = DataSetWriter::new_with_codec_options(to, encoder, charset, options);
+        writer.group_buffer = group_buffer;
+        Ok(writer)
     }
 }
 
@@ -227,6 +314,7 @@ impl<W, E> DataSetWriter<W, E> {
             seq_tokens: Vec::new(),
             last_de: None,
             options,
+            group_buffer: None,
         }
     }
 }
@@ -253,8 +341,16 @@ impl<W, E, T> DataSetWriter<W, E, T> {
             seq_tokens: Vec::new(),
             last_de: None,
             options,
+            group_buffer: None,
         }
     }
+
+    /// Retrieve the inner writer, discarding the data set writer.
+    ///
+    /// Any sequence or item still open is left unterminated.
+    pub fn into_inner(self) -> W {
+        self.printer.into_inner()
+    }
 }
 
 impl<W, E> DataSetWriter<W, E>
@@ -277,6 +373,11 @@ where
 
     /// Feed the given data set token for writing the data set.
     pub fn write(&mut self, token: DataToken) -> Result<()> {
+        if self.group_buffer.is_some() && self.seq_tokens.is_empty() && self.track_group(&token)? {
+            // token was a replaced group length, do not write
+            return Ok(());
+        }
+
         match token {
             DataToken::SequenceStart { tag, len, .. } => {
                 match self.options.explicit_length_sq_item_strategy {
@@ -376,67 +477,239 @@ where
         }
     }
 
-    fn write_impl(&mut self, token: &DataToken) -> Result<()> {
-        match token {
-            DataToken::ElementHeader(header) => {
-                self.printer
-                    .encode_element_header(*header)
-                    .context(WriteHeaderSnafu { tag: header.tag })?;
-            }
-            DataToken::SequenceStart { tag, len } => {
-                self.printer
-                    .encode_element_header(DataElementHeader::new(*tag, VR::SQ, *len))
-                    .context(WriteHeaderSnafu { tag: *tag })?;
-            }
-            DataToken::PixelSequenceStart => {
-                let tag = Tag(0x7fe0, 0x0010);
-                self.printer
-                    .encode_element_header(DataElementHeader::new(tag, VR::OB, Length::UNDEFINED))
-                    .context(WriteHeaderSnafu { tag })?;
-            }
-            DataToken::SequenceEnd => {
-                self.printer
-                    .encode_sequence_delimiter()
-                    .context(WriteSequenceDelimiterSnafu)?;
-            }
-            DataToken::ItemStart { len } => {
-                self.printer
-                    .encode_item_header(len.0)
-                    .context(WriteItemHeaderSnafu)?;
-            }
-            DataToken::ItemEnd => {
-                self.printer
-                    .encode_item_delimiter()
-                    .context(WriteItemDelimiterSnafu)?;
+    /// Keep track of the group of the root data set being written,
+    /// writing the group length and the elements of the previous group
+    /// once a new group begins.
+    ///
+    /// Returns `true` if the token should not be written,
+    /// because it belongs to a group length element to replace.
+    fn track_group(&mut self, token: &DataToken) -> Result<bool> {
+        let tag = match token {
+            DataToken::ElementHeader(header) => header.tag,
+            DataToken::SequenceStart { tag, .. } => *tag,
+            DataToken::PixelSequenceStart => Tag(0x7fe0, 0x0010),
+            DataToken::PrimitiveValue(_) => {
+                let buffer = self.group_buffer.as_mut().unwrap();
+                return Ok(std::mem::take(&mut buffer.skip_value));
             }
-            DataToken::PrimitiveValue(value) => {
-                let last_de = self.last_de.take().with_context(|| UnexpectedTokenSnafu {
-                    token: token.clone(),
-                })?;
+            _ => return Ok(false),
+        };
 
-                self.printer
-                    .encode_primitive_element(&last_de, value)
-                    .context(WriteValueSnafu)?;
-                self.last_de = None;
-            }
-            DataToken::OffsetTable(table) => {
-                self.printer
-                    .encode_offset_table(table)
-                    .context(WriteValueSnafu)?;
-            }
-            DataToken::ItemValue(data) => {
-                self.printer.write_bytes(data).context(WriteValueSnafu)?;
-            }
+        if self.group_buffer.as_ref().unwrap().group != Some(tag.group()) {
+            self.write_group()?;
+            self.group_buffer.as_mut().unwrap().group = Some(tag.group());
+        }
+        if tag.element() == 0 {
+            // group length element, will be replaced
+            self.group_buffer.as_mut().unwrap().skip_value = true;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Write the group length element and the elements
+    /// of the buffered group, if any.
+    fn write_group(&mut self) -> Result<()> {
+        let Some(buffer) = self.group_buffer.as_mut() else {
+            return Ok(());
+        };
+        let Some(group) = buffer.group.take() else {
+            return Ok(());
+        };
+        let data = std::mem::take(buffer.printer.inner_mut());
+
+        let tag = Tag(group, 0x0000);
+        self.printer
+            .encode_primitive_element(
+                &DataElementHeader::new(tag, VR::UL, Length(4)),
+                &PrimitiveValue::from(data.len() as u32),
+            )
+            .context(WriteValueSnafu)?;
+        self.printer.write_raw_bytes(&data).context(WriteValueSnafu)
+    }
+
+    fn write_impl(&mut self, token: &DataToken) -> Result<()> {
+        let preserve_odd_lengths = self.options.preserve_odd_lengths;
+        if let Some(buffer) = &mut self.group_buffer {
+            write_token(
+                &mut buffer.printer,
+                token,
+                &mut self.last_de,
+                preserve_odd_lengths,
+            )
+        } else {
+            write_token(
+                &mut self.printer,
+                token,
+                &mut self.last_de,
+                preserve_odd_lengths,
+            )
+        }
+    }
+
+    /// Write a whole data element,
+    /// including all of its sequence items or pixel data fragments.
+    pub fn write_element<I, P>(&mut self, element: DataElement<I, P>) -> Result<()>
+    where
+        I: IntoTokens + HasLength,
+        P: AsRef<[u8]>,
+    {
+        self.write_sequence(element.into_tokens())
+    }
+
+    /// Begin a data set sequence with the given tag,
+    /// of undefined length.
+    ///
+    /// Each item is then started with [`start_item`](Self::start_item)
+    /// and the sequence is closed with [`end_sequence`](Self::end_sequence).
+    pub fn start_sequence(&mut self, tag: Tag) -> Result<()> {
+        self.write(DataToken::SequenceStart {
+            tag,
+            len: Length::UNDEFINED,
+        })
+    }
+
+    /// Begin a new item of undefined length in the current sequence.
+    pub fn start_item(&mut self) -> Result<()> {
+        self.write(DataToken::ItemStart {
+            len: Length::UNDEFINED,
+        })
+    }
+
+    /// Close the current sequence item.
+    pub fn end_item(&mut self) -> Result<()> {
+        self.write(DataToken::ItemEnd)
+    }
+
+    /// Close the current sequence,
+    /// or the current encapsulated pixel data element.
+    pub fn end_sequence(&mut self) -> Result<()> {
+        self.write(DataToken::SequenceEnd)
+    }
+
+    /// Begin an encapsulated pixel data element,
+    /// writing the given basic offset table right away.
+    ///
+    /// The offset table may be empty.
+    /// Each fragment is then written with
+    /// [`write_fragment`](Self::write_fragment),
+    /// and the element is closed with [`end_sequence`](Self::end_sequence).
+    pub fn start_pixel_sequence(&mut self, offset_table: &[u32]) -> Result<()> {
+        self.write(DataToken::PixelSequenceStart)?;
+        self.write(DataToken::ItemStart {
+            len: Length(offset_table.len() as u32 * 4),
+        })?;
+        if !offset_table.is_empty() {
+            self.write(DataToken::OffsetTable(offset_table.to_vec()))?;
+        }
+        self.write(DataToken::ItemEnd)
+    }
+
+    /// Write a pixel data fragment
+    /// in the current encapsulated pixel data element.
+    ///
+    /// Fragments of odd length are padded with a trailing zero.
+    pub fn write_fragment(&mut self, data: &[u8]) -> Result<()> {
+        if let Some(buffer) = &mut self.group_buffer {
+            write_fragment(&mut buffer.printer, data)
+        } else {
+            write_fragment(&mut self.printer, data)
         }
-        Ok(())
     }
 
-    /// Flush the inner writer
+    /// Flush the inner writer.
+    ///
+    /// When writing group lengths,
+    /// this also writes the elements of the last group.
     pub fn flush(&mut self) -> Result<()> {
+        self.write_group()?;
         self.printer.flush().context(FlushBufferSnafu)
     }
 }
 
+/// Write a single data set token with the given stateful encoder.
+fn write_token<W, E>(
+    printer: &mut StatefulEncoder<W, E>,
+    token: &DataToken,
+    last_de: &mut Option<DataElementHeader>,
+    preserve_odd_lengths: bool,
+) -> Result<()>
+where
+    W: Write,
+    E: EncodeTo<W>,
+{
+    match token {
+        DataToken::ElementHeader(header) => {
+            printer
+                .encode_element_header(*header)
+                .context(WriteHeaderSnafu { tag: header.tag })?;
+        }
+        DataToken::SequenceStart { tag, len } => {
+            printer
+                .encode_element_header(DataElementHeader::new(*tag, VR::SQ, *len))
+                .context(WriteHeaderSnafu { tag: *tag })?;
+        }
+        DataToken::PixelSequenceStart => {
+            let tag = Tag(0x7fe0, 0x0010);
+            printer
+                .encode_element_header(DataElementHeader::new(tag, VR::OB, Length::UNDEFINED))
+                .context(WriteHeaderSnafu { tag })?;
+        }
+        DataToken::SequenceEnd => {
+            printer
+                .encode_sequence_delimiter()
+                .context(WriteSequenceDelimiterSnafu)?;
+        }
+        DataToken::ItemStart { len } => {
+            printer
+                .encode_item_header(len.0)
+                .context(WriteItemHeaderSnafu)?;
+        }
+        DataToken::ItemEnd => {
+            printer
+                .encode_item_delimiter()
+                .context(WriteItemDelimiterSnafu)?;
+        }
+        DataToken::PrimitiveValue(value) => {
+            let header = last_de.take().with_context(|| UnexpectedTokenSnafu {
+                token: token.clone(),
+            })?;
+
+            if preserve_odd_lengths {
+                printer
+                    .encode_primitive_element_exact(&header, value)
+                    .context(WriteValueSnafu)?;
+            } else {
+                printer
+                    .encode_primitive_element(&header, value)
+                    .context(WriteValueSnafu)?;
+            }
+        }
+        DataToken::OffsetTable(table) => {
+            printer
+                .encode_offset_table(table)
+                .context(WriteValueSnafu)?;
+        }
+        DataToken::ItemValue(data) => {
+            printer.write_bytes(data).context(WriteValueSnafu)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a pixel data fragment with the given stateful encoder.
+fn write_fragment<W, E>(printer: &mut StatefulEncoder<W, E>, data: &[u8]) -> Result<()>
+where
+    W: Write,
+    E: EncodeTo<W>,
+{
+    let len = data.len() as u32;
+    printer
+        .encode_item_header(len + len % 2)
+        .context(WriteItemHeaderSnafu)?;
+    printer.write_bytes(data).context(WriteValueSnafu)
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::DataToken;
@@ -554,6 +827,7 @@ mod tests {
 
         let no_change = DataSetWriterOptions {
             explicit_length_sq_item_strategy: ExplicitLengthSqItemStrategy::NoChange,
+            ..Default::default()
         };
         validate_dataset_writer(tokens.clone(), GROUND_TRUTH_NO_CHANGE, no_change);
         validate_dataset_writer(
@@ -673,6 +947,7 @@ mod tests {
 
         let no_change = DataSetWriterOptions {
             explicit_length_sq_item_strategy: ExplicitLengthSqItemStrategy::NoChange,
+            ..Default::default()
         };
         validate_dataset_writer(tokens.clone(), GROUND_TRUTH, no_change);
         validate_dataset_writer(tokens, GROUND_TRUTH, DataSetWriterOptions::default());
@@ -768,6 +1043,7 @@ mod tests {
         ];
         let no_change = DataSetWriterOptions {
             explicit_length_sq_item_strategy: ExplicitLengthSqItemStrategy::NoChange,
+            ..Default::default()
         };
 
         validate_dataset_writer(tokens.clone(), GROUND_TRUTH_NO_CHANGE, no_change);
@@ -828,8 +1104,164 @@ mod tests {
         ];
         let no_change = DataSetWriterOptions {
             explicit_length_sq_item_strategy: ExplicitLengthSqItemStrategy::NoChange,
+            ..Default::default()
         };
         validate_dataset_writer(tokens.clone(), GROUND_TRUTH, no_change);
         validate_dataset_writer(tokens, GROUND_TRUTH, DataSetWriterOptions::default());
     }
+
+    #[test]
+    fn write_with_push_methods() {
+        use dicom_core::DataElement;
+        use dicom_core::header::EmptyObject;
+
+        let encoder = || EncoderFor::new(ExplicitVRLittleEndianEncoder::default());
+
+        // expected output from data set tokens
+        let mut expected: Vec<u8> = vec![];
+        let mut dset_writer = DataSetWriter::new(&mut expected, encoder());
+        dset_writer
+            .write_sequence(vec![
+                DataToken::SequenceStart {
+                    tag: Tag(0x0018, 0x6011),
+                    len: Length::UNDEFINED,
+                },
+                DataToken::ItemStart {
+                    len: Length::UNDEFINED,
+                },
+                DataToken::ElementHeader(DataElementHeader::new(
+                    Tag(0x0018, 0x6012),
+                    VR::US,
+                    Length(2),
+                )),
+                DataToken::PrimitiveValue(PrimitiveValue::from(1_u16)),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+                DataToken::PixelSequenceStart,
+                DataToken::ItemStart { len: Length(4) },
+                DataToken::OffsetTable(vec![0]),
+                DataToken::ItemEnd,
+                DataToken::ItemStart { len: Length(4) },
+                DataToken::ItemValue(vec![0x99, 0x99, 0x99, 0x00]),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+            ])
+            .unwrap();
+
+        let mut raw_out: Vec<u8> = vec![];
+        let mut dset_writer = DataSetWriter::new(&mut raw_out, encoder());
+        dset_writer.start_sequence(Tag(0x0018, 0x6011)).unwrap();
+        dset_writer.start_item().unwrap();
+        dset_writer
+            .write_element(DataElement::<EmptyObject, [u8; 0]>::new(
+                Tag(0x0018, 0x6012),
+                VR::US,
+                PrimitiveValue::from(1_u16),
+            ))
+            .unwrap();
+        dset_writer.end_item().unwrap();
+        dset_writer.end_sequence().unwrap();
+        dset_writer.start_pixel_sequence(&[0]).unwrap();
+        // odd length fragment is padded
+        dset_writer.write_fragment(&[0x99, 0x99, 0x99]).unwrap();
+        dset_writer.end_sequence().unwrap();
+
+        assert_eq!(raw_out, expected);
+    }
+
+    #[test]
+    fn write_group_lengths() {
+        use super::GroupLengthStrategy;
+        use dicom_encoding::{Codec, TransferSyntax};
+
+        let ts: TransferSyntax = TransferSyntax::new_ele(
+            "1.2.840.10008.1.2.1",
+            "Explicit VR Little Endian",
+            Codec::None,
+        );
+        let tokens = vec![
+            // existing group length, to be replaced
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0008, 0x0000),
+                VR::UL,
+                Length(4),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from(999_u32)),
+            // Recognition Code (retired)
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0008, 0x0010),
+                VR::SH,
+                Length(4),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from("ACR ")),
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0008, 0x0060),
+                VR::CS,
+                Length(2),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from("MR")),
+            // private creator and unknown private element
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0009, 0x0010),
+                VR::LO,
+                Length(4),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from("ACME")),
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0009, 0x1001),
+                VR::UN,
+                Length(4),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from(vec![1_u8, 2, 3, 4])),
+            DataToken::ElementHeader(DataElementHeader::new(
+                Tag(0x0010, 0x0010),
+                VR::PN,
+                Length(8),
+            )),
+            DataToken::PrimitiveValue(PrimitiveValue::from("Doe^John")),
+        ];
+
+        #[rustfmt::skip]
+        let expected = vec![
+            // (0008,0000) UL 22
+            0x08, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00, 0x16, 0x00, 0x00, 0x00,
+            // (0008,0010) SH "ACR "
+            0x08, 0x00, 0x10, 0x00, b'S', b'H', 0x04, 0x00, b'A', b'C', b'R', b' ',
+            // (0008,0060) CS "MR"
+            0x08, 0x00, 0x60, 0x00, b'C', b'S', 0x02, 0x00, b'M', b'R',
+            // (0009,0000) UL 28
+            0x09, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00, 0x1c, 0x00, 0x00, 0x00,
+            // (0009,0010) LO "ACME"
+            0x09, 0x00, 0x10, 0x00, b'L', b'O', 0x04, 0x00, b'A', b'C', b'M', b'E',
+            // (0009,1001) UN, kept as is
+            0x09, 0x00, 0x01, 0x10, b'U', b'N', 0x00, 0x00, 0x04, 0x00, 0x00, 0x00,
+            0x01, 0x02, 0x03, 0x04,
+            // (0010,0000) UL 16
+            0x10, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00, 0x10, 0x00, 0x00, 0x00,
+            // (0010,0010) PN "Doe^John"
+            0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x08, 0x00,
+            b'D', b'o', b'e', b'^', b'J', b'o', b'h', b'n',
+        ];
+
+        let mut raw_out: Vec<u8> = vec![];
+        let options =
+            DataSetWriterOptions::default().group_length_strategy(GroupLengthStrategy::Recalculate);
+        let mut dset_writer = DataSetWriter::with_ts_options(&mut raw_out, &ts, options).unwrap();
+        dset_writer.write_sequence(tokens.clone()).unwrap();
+        dset_writer.flush().unwrap();
+        drop(dset_writer);
+        assert_eq!(raw_out, expected);
+
+        // without the option, tokens are written verbatim
+        let mut raw_out: Vec<u8> = vec![];
+        let mut dset_writer = DataSetWriter::with_ts(&mut raw_out, &ts).unwrap();
+        dset_writer.write_sequence(tokens).unwrap();
+        drop(dset_writer);
+        assert_eq!(
+            &raw_out[..8],
+            &[0x08, 0x00, 0x00, 0x00, b'U', b'L', 0x04, 0x00]
+        );
+        assert_eq!(&raw_out[8..12], &999_u32.to_le_bytes());
+        assert_eq!(raw_out.len(), expected.len() - 24);
+    }
 }
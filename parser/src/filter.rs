@@ -0,0 +1,935 @@
+//! Streaming filters over data set tokens.
+//!
+//! A [`FilterChain`] wraps a stream of [`DataToken`]s,
+//! such as the tokens of a [`DataSetReader`](crate::DataSetReader),
+//! and passes each token through a sequence of [`TokenFilter`]s.
+//! Each filter may let a token through, replace it,
+//! emit more tokens in its place,
+//! or drop the element which the token starts.
+//! Since tokens go through the chain one at a time,
+//! piping the chain into a [`DataSetWriter`](crate::dataset::DataSetWriter)
+//! with [`pipe`](crate::dataset::pipe)
+//! edits data sets of any size in constant memory,
+//! except where a filter needs to hold elements back to reorder them.
+//!
+//! The following filters are provided:
+//!
+//! - [`DropTags`] removes elements with the given tags at any depth;
+//! - [`RenameTag`] changes the tag of elements;
+//! - [`MapValues`] transforms the headers and values of primitive elements;
+//! - [`PutElement`] sets or adds a primitive element in the root data set;
+//! - [`TruncatePixelData`] empties the pixel data of the root data set.
+//!
+//! Custom filters implement [`TokenFilter`],
+//! which is also implemented for closures
+//! of the form `FnMut(DataToken, &TokenContext, &mut FilterOutput)`.
+//!
+//! Filters do not need to keep element lengths consistent:
+//! the data set writer calculates the lengths of primitive values,
+//! and by default writes sequences and items with undefined length.
+//! They are however responsible for keeping elements in ascending order of tag.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_core::{Tag, VR, header::{DataElementHeader, Length}, PrimitiveValue};
+//! use dicom_parser::dataset::DataToken;
+//! use dicom_parser::filter::{DropTags, FilterChain, MapValues};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let tokens = vec![
+//!     DataToken::ElementHeader(DataElementHeader::new(Tag(0x0010, 0x0010), VR::PN, Length(8))),
+//!     DataToken::PrimitiveValue(PrimitiveValue::from("Doe^John")),
+//!     DataToken::ElementHeader(DataElementHeader::new(Tag(0x0010, 0x0020), VR::LO, Length(6))),
+//!     DataToken::PrimitiveValue(PrimitiveValue::from("123456")),
+//! ];
+//!
+//! let filtered: Vec<_> = FilterChain::new(tokens.into_iter().map(Ok::<_, std::io::Error>))
+//!     // remove Patient Name
+//!     .with_filter(DropTags::new([Tag(0x0010, 0x0010)]))
+//!     // keep only the last digits of Patient ID
+//!     .with_filter(MapValues::new(|header: &mut DataElementHeader, value: PrimitiveValue| {
+//!         if header.tag == Tag(0x0010, 0x0020) {
+//!             PrimitiveValue::from(value.to_str()[3..].to_string())
+//!         } else {
+//!             value
+//!         }
+//!     }))
+//!     .collect::<Result<_, _>>()?;
+//!
+//! assert_eq!(filtered.len(), 2);
+//! assert_eq!(filtered[1], DataToken::PrimitiveValue(PrimitiveValue::from("456")));
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt;
+
+use dicom_core::header::{DataElementHeader, Length};
+use dicom_core::{PrimitiveValue, Tag, VR};
+
+use crate::dataset::DataToken;
+
+/// The tag of the _Pixel Data_ attribute.
+const PIXEL_DATA: Tag = Tag(0x7FE0, 0x0010);
+
+/// A transformation of a stream of data set tokens.
+///
+/// Filters are composed with a [`FilterChain`].
+pub trait TokenFilter {
+    /// Process the next token of the stream,
+    /// pushing the tokens to emit in its place into `output`.
+    ///
+    /// Pushing nothing removes the token from the stream.
+    /// To remove a whole element,
+    /// call [`drop_element`](FilterOutput::drop_element)
+    /// while processing the token which starts it.
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput);
+
+    /// Push any remaining tokens into `output`
+    /// once the stream has ended.
+    ///
+    /// The default implementation emits nothing.
+    fn finish(&mut self, output: &mut FilterOutput) {
+        let _ = output;
+    }
+}
+
+impl<F> TokenFilter for F
+where
+    F: FnMut(DataToken, &TokenContext, &mut FilterOutput),
+{
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput) {
+        self(token, context, output)
+    }
+}
+
+/// The position of a token in the data set,
+/// as seen by a filter.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TokenContext {
+    /// the tags of the enclosing sequences
+    sequences: Vec<Tag>,
+    /// the header of the primitive element of the current token
+    header: Option<DataElementHeader>,
+    /// the sequence started by the previous token
+    entering: Option<Tag>,
+}
+
+impl TokenContext {
+    /// The tags of the sequences enclosing the current token,
+    /// from the outermost to the innermost.
+    ///
+    /// Encapsulated pixel data counts as a sequence
+    /// with the tag of _Pixel Data_.
+    /// The start and end tokens of a sequence are not enclosed by it.
+    pub fn sequences(&self) -> &[Tag] {
+        &self.sequences
+    }
+
+    /// Whether the current token belongs to the root data set.
+    pub fn is_root(&self) -> bool {
+        self.sequences.is_empty()
+    }
+
+    /// The header of the primitive element
+    /// which the current token belongs to,
+    /// if it is an element header or a primitive value.
+    pub fn header(&self) -> Option<&DataElementHeader> {
+        self.header.as_ref()
+    }
+
+    /// Update the context to the position of the given token.
+    fn update(&mut self, token: &DataToken) {
+        if let Some(tag) = self.entering.take() {
+            self.sequences.push(tag);
+        }
+        match token {
+            DataToken::ElementHeader(header) => self.header = Some(*header),
+            DataToken::PrimitiveValue(_) => {}
+            DataToken::SequenceStart { tag, .. } => {
+                self.header = None;
+                self.entering = Some(*tag);
+            }
+            DataToken::PixelSequenceStart => {
+                self.header = None;
+                self.entering = Some(PIXEL_DATA);
+            }
+            DataToken::SequenceEnd => {
+                self.header = None;
+                self.sequences.pop();
+            }
+            DataToken::ItemStart { .. }
+            | DataToken::ItemEnd
+            | DataToken::ItemValue(_)
+            | DataToken::OffsetTable(_) => self.header = None,
+        }
+    }
+}
+
+/// The tokens emitted by a filter for the token being processed.
+#[derive(Debug, Default)]
+pub struct FilterOutput {
+    tokens: Vec<DataToken>,
+    drop_element: bool,
+}
+
+impl FilterOutput {
+    /// Emit a token.
+    pub fn push(&mut self, token: DataToken) {
+        self.tokens.push(token);
+    }
+
+    /// Drop the remaining tokens of the element or item
+    /// which the token being processed starts,
+    /// without passing them to the filter.
+    ///
+    /// This has no effect unless the token is an element header,
+    /// the start of a sequence or of encapsulated pixel data,
+    /// or the start of an item.
+    pub fn drop_element(&mut self) {
+        self.drop_element = true;
+    }
+}
+
+/// The tokens left to drop by a filter stage.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Skip {
+    Nothing,
+    /// the value of a primitive element
+    Value,
+    /// the rest of a sequence or item,
+    /// at the given nesting depth
+    Nested(usize),
+}
+
+/// A filter along with its state in the chain.
+struct Stage<'a> {
+    filter: Box<dyn TokenFilter + 'a>,
+    context: TokenContext,
+    output: FilterOutput,
+    skip: Skip,
+}
+
+impl Stage<'_> {
+    /// Pass a token to the filter, unless it is being dropped.
+    fn process(&mut self, token: DataToken) {
+        self.context.update(&token);
+        match self.skip {
+            Skip::Nothing => {}
+            Skip::Value => {
+                self.skip = Skip::Nothing;
+                return;
+            }
+            Skip::Nested(depth) => {
+                let depth = match token {
+                    DataToken::SequenceStart { .. }
+                    | DataToken::PixelSequenceStart
+                    | DataToken::ItemStart { .. } => depth + 1,
+                    DataToken::SequenceEnd | DataToken::ItemEnd => depth - 1,
+                    _ => depth,
+                };
+                self.skip = if depth == 0 {
+                    Skip::Nothing
+                } else {
+                    Skip::Nested(depth)
+                };
+                return;
+            }
+        }
+
+        let skip = match token {
+            DataToken::ElementHeader(_) => Skip::Value,
+            DataToken::SequenceStart { .. }
+            | DataToken::PixelSequenceStart
+            | DataToken::ItemStart { .. } => Skip::Nested(1),
+            _ => Skip::Nothing,
+        };
+        self.filter.filter(token, &self.context, &mut self.output);
+        if std::mem::take(&mut self.output.drop_element) {
+            self.skip = skip;
+        }
+    }
+}
+
+/// A stream of data set tokens passing through a chain of filters.
+///
+/// The chain is an iterator of results,
+/// so that errors of the source stream are passed through as they are.
+/// See the [module-level documentation](self) for more details.
+pub struct FilterChain<'a, I> {
+    tokens: I,
+    stages: Vec<Stage<'a>>,
+    queue: VecDeque<DataToken>,
+    finished: bool,
+}
+
+impl<I> fmt::Debug for FilterChain<'_, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterChain")
+            .field("filters", &self.stages.len())
+            .field("queue", &self.queue)
+            .field("finished", &self.finished)
+            .finish()
+    }
+}
+
+impl<'a, I> FilterChain<'a, I> {
+    /// Create a chain without filters over the given tokens.
+    pub fn new<T>(tokens: T) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        FilterChain {
+            tokens: tokens.into_iter(),
+            stages: Vec::new(),
+            queue: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Append a filter to the chain,
+    /// which receives the tokens emitted by the filters before it.
+    pub fn with_filter(mut self, filter: impl TokenFilter + 'a) -> Self {
+        self.stages.push(Stage {
+            filter: Box::new(filter),
+            context: TokenContext::default(),
+            output: FilterOutput::default(),
+            skip: Skip::Nothing,
+        });
+        self
+    }
+}
+
+impl<I, E> Iterator for FilterChain<'_, I>
+where
+    I: Iterator<Item = Result<DataToken, E>>,
+{
+    type Item = Result<DataToken, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(token) = self.queue.pop_front() {
+                return Some(Ok(token));
+            }
+            if self.finished {
+                return None;
+            }
+            match self.tokens.next() {
+                Some(Ok(token)) => run(&mut self.stages, token, &mut self.queue),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.finished = true;
+                    for i in 0..self.stages.len() {
+                        let (stage, rest) = self.stages[i..].split_first_mut().unwrap();
+                        stage.filter.finish(&mut stage.output);
+                        for token in stage.output.tokens.drain(..) {
+                            run(rest, token, &mut self.queue);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pass a token through the given filter stages,
+/// queuing the tokens which come out of the last one.
+fn run(stages: &mut [Stage<'_>], token: DataToken, queue: &mut VecDeque<DataToken>) {
+    let Some((stage, rest)) = stages.split_first_mut() else {
+        queue.push_back(token);
+        return;
+    };
+    stage.process(token);
+    for token in stage.output.tokens.drain(..) {
+        run(rest, token, queue);
+    }
+}
+
+/// The tag of the element which the token starts, if any.
+fn element_tag(token: &DataToken) -> Option<Tag> {
+    match token {
+        DataToken::ElementHeader(header) => Some(header.tag),
+        DataToken::SequenceStart { tag, .. } => Some(*tag),
+        DataToken::PixelSequenceStart => Some(PIXEL_DATA),
+        _ => None,
+    }
+}
+
+/// A filter removing all elements with the given tags,
+/// at any depth.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DropTags {
+    tags: BTreeSet<Tag>,
+}
+
+impl DropTags {
+    /// Create a filter removing the elements with the given tags.
+    pub fn new(tags: impl IntoIterator<Item = Tag>) -> Self {
+        DropTags {
+            tags: tags.into_iter().collect(),
+        }
+    }
+}
+
+impl TokenFilter for DropTags {
+    fn filter(&mut self, token: DataToken, _context: &TokenContext, output: &mut FilterOutput) {
+        match element_tag(&token) {
+            Some(tag) if self.tags.contains(&tag) => output.drop_element(),
+            _ => output.push(token),
+        }
+    }
+}
+
+/// A filter changing the tag of elements,
+/// at any depth.
+///
+/// The value representation and the value of each element are kept.
+/// The renamed element is moved to its new position
+/// in the data set or item containing it,
+/// replacing any element which already had the new tag.
+/// To do so, the elements with tags between the old and the new tag
+/// are held in memory until the end of that range.
+/// Encapsulated pixel data cannot be renamed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameTag {
+    from: Tag,
+    to: Tag,
+    /// the open data set levels, from the root to the innermost item
+    levels: Vec<RenameLevel>,
+}
+
+/// The elements held by [`RenameTag`] in a data set or item.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct RenameLevel {
+    /// the tag of each held element, whether it was renamed, and its tokens
+    held: Vec<(Tag, bool, Vec<DataToken>)>,
+    /// whether the tokens of the current element are held
+    holding: bool,
+}
+
+impl RenameTag {
+    /// Create a filter changing the tag of elements from `from` to `to`.
+    pub fn new(from: Tag, to: Tag) -> Self {
+        RenameTag {
+            from,
+            to,
+            levels: vec![RenameLevel::default()],
+        }
+    }
+
+    /// Emit a token to the innermost element being held,
+    /// or to the output if there is none.
+    fn emit(&mut self, token: DataToken, output: &mut FilterOutput) {
+        match self.levels.iter_mut().rev().find(|level| level.holding) {
+            Some(level) => level.held.last_mut().unwrap().2.push(token),
+            None => output.push(token),
+        }
+    }
+
+    /// Emit the elements held in the innermost level in order of tag.
+    fn release(&mut self, output: &mut FilterOutput) {
+        let level = self.levels.last_mut().unwrap();
+        level.holding = false;
+        let mut held = std::mem::take(&mut level.held);
+        // the renamed element comes first among elements with the same tag
+        held.sort_by_key(|(tag, renamed, _)| (*tag, !*renamed));
+        held.dedup_by_key(|(tag, _, _)| *tag);
+        for (_, _, tokens) in held {
+            for token in tokens {
+                self.emit(token, output);
+            }
+        }
+    }
+}
+
+impl TokenFilter for RenameTag {
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput) {
+        let in_pixel_data = context.sequences().last() == Some(&PIXEL_DATA);
+        let Some(tag) = element_tag(&token) else {
+            match token {
+                DataToken::ItemStart { .. } if !in_pixel_data => {
+                    self.emit(token, output);
+                    self.levels.push(RenameLevel::default());
+                }
+                DataToken::ItemEnd if !in_pixel_data => {
+                    self.release(output);
+                    self.levels.pop();
+                    self.emit(token, output);
+                }
+                token => self.emit(token, output),
+            }
+            return;
+        };
+
+        let (token, renamed) = match token {
+            DataToken::ElementHeader(header) if tag == self.from => (
+                DataToken::ElementHeader(DataElementHeader {
+                    tag: self.to,
+                    ..header
+                }),
+                true,
+            ),
+            DataToken::SequenceStart { len, .. } if tag == self.from => {
+                (DataToken::SequenceStart { tag: self.to, len }, true)
+            }
+            token => (token, false),
+        };
+        let level = self.levels.last_mut().unwrap();
+        level.holding = false;
+        let (low, high) = (self.from.min(self.to), self.from.max(self.to));
+        if (low..=high).contains(&tag) {
+            let tag = if renamed { self.to } else { tag };
+            level.held.push((tag, renamed, vec![token]));
+            level.holding = true;
+            return;
+        }
+        if tag > high {
+            self.release(output);
+        }
+        self.emit(token, output);
+    }
+
+    fn finish(&mut self, output: &mut FilterOutput) {
+        self.release(output);
+    }
+}
+
+/// A filter transforming the primitive elements of the data set,
+/// at any depth.
+///
+/// The function receives the header and the value of each primitive element,
+/// and returns the new value.
+/// The header may be modified as well,
+/// for example to change the value representation,
+/// so the function is responsible for keeping it consistent with the value.
+/// The header's length is not used by the data set writer.
+pub struct MapValues<F> {
+    f: F,
+    header: Option<DataElementHeader>,
+}
+
+impl<F> fmt::Debug for MapValues<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapValues")
+            .field("header", &self.header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> MapValues<F>
+where
+    F: FnMut(&mut DataElementHeader, PrimitiveValue) -> PrimitiveValue,
+{
+    /// Create a filter transforming primitive elements with the given function.
+    pub fn new(f: F) -> Self {
+        MapValues { f, header: None }
+    }
+}
+
+impl<F> TokenFilter for MapValues<F>
+where
+    F: FnMut(&mut DataElementHeader, PrimitiveValue) -> PrimitiveValue,
+{
+    fn filter(&mut self, token: DataToken, _context: &TokenContext, output: &mut FilterOutput) {
+        match token {
+            // hold the header until the value is known
+            DataToken::ElementHeader(header) => self.header = Some(header),
+            DataToken::PrimitiveValue(value) => {
+                if let Some(mut header) = self.header.take() {
+                    let value = (self.f)(&mut header, value);
+                    output.push(DataToken::ElementHeader(header));
+                    output.push(DataToken::PrimitiveValue(value));
+                } else {
+                    output.push(DataToken::PrimitiveValue(value));
+                }
+            }
+            token => output.push(token),
+        }
+    }
+}
+
+/// A filter setting a primitive element in the root data set,
+/// replacing the existing element with the same tag
+/// or adding it in its place by ascending order of tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PutElement {
+    header: DataElementHeader,
+    value: Option<PrimitiveValue>,
+}
+
+impl PutElement {
+    /// Create a filter setting the element with the given tag,
+    /// value representation and value.
+    pub fn new(tag: Tag, vr: VR, value: impl Into<PrimitiveValue>) -> Self {
+        let value = value.into();
+        PutElement {
+            header: DataElementHeader::new(tag, vr, Length(value.calculate_byte_len() as u32)),
+            value: Some(value),
+        }
+    }
+
+    /// Emit the new element, unless it was emitted already.
+    fn put(&mut self, output: &mut FilterOutput) {
+        if let Some(value) = self.value.take() {
+            output.push(DataToken::ElementHeader(self.header));
+            output.push(DataToken::PrimitiveValue(value));
+        }
+    }
+}
+
+impl TokenFilter for PutElement {
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput) {
+        if self.value.is_some() && context.is_root() {
+            match element_tag(&token) {
+                Some(tag) if tag == self.header.tag => {
+                    self.put(output);
+                    output.drop_element();
+                    return;
+                }
+                Some(tag) if tag > self.header.tag => self.put(output),
+                _ => {}
+            }
+        }
+        output.push(token);
+    }
+
+    fn finish(&mut self, output: &mut FilterOutput) {
+        self.put(output);
+    }
+}
+
+/// A filter emptying the _Pixel Data_ of the root data set.
+///
+/// Native pixel data is given an empty value.
+/// Encapsulated pixel data keeps an empty basic offset table
+/// and loses all of its fragments.
+/// Other attributes describing the pixel data are not changed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TruncatePixelData {
+    /// the number of items seen in the current encapsulated pixel data
+    items: usize,
+}
+
+impl TruncatePixelData {
+    /// Create a filter emptying the pixel data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TokenFilter for TruncatePixelData {
+    fn filter(&mut self, token: DataToken, context: &TokenContext, output: &mut FilterOutput) {
+        let in_pixel_sequence = context.sequences() == [PIXEL_DATA];
+        match token {
+            DataToken::PrimitiveValue(_)
+                if context.is_root() && context.header().map(|h| h.tag) == Some(PIXEL_DATA) =>
+            {
+                output.push(DataToken::PrimitiveValue(PrimitiveValue::Empty));
+            }
+            DataToken::PixelSequenceStart if context.is_root() => {
+                self.items = 0;
+                output.push(token);
+            }
+            DataToken::ItemStart { .. } if in_pixel_sequence => {
+                self.items += 1;
+                if self.items == 1 {
+                    output.push(DataToken::ItemStart { len: Length(0) });
+                } else {
+                    output.drop_element();
+                }
+            }
+            DataToken::OffsetTable(_) | DataToken::ItemValue(_) if in_pixel_sequence => {}
+            token => output.push(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn header(tag: Tag, vr: VR, len: u32) -> DataToken {
+        DataToken::ElementHeader(DataElementHeader::new(tag, vr, Length(len)))
+    }
+
+    fn value(value: impl Into<PrimitiveValue>) -> DataToken {
+        DataToken::PrimitiveValue(value.into())
+    }
+
+    type TestChain<'a> = FilterChain<'a, std::vec::IntoIter<Result<DataToken, Infallible>>>;
+
+    fn filter_all<'a>(
+        tokens: &[DataToken],
+        chain: impl FnOnce(TestChain<'a>) -> TestChain<'a>,
+    ) -> Vec<DataToken> {
+        let tokens: Vec<_> = tokens.iter().cloned().map(Ok).collect();
+        chain(FilterChain::new(tokens))
+            .map(|token| token.unwrap())
+            .collect()
+    }
+
+    /// A data set with a nested element to drop,
+    /// in a sequence between two primitive elements.
+    fn nested_data_set() -> Vec<DataToken> {
+        vec![
+            header(Tag(0x0008, 0x0060), VR::CS, 2),
+            value("CT"),
+            DataToken::SequenceStart {
+                tag: Tag(0x0008, 0x1115),
+                len: Length::UNDEFINED,
+            },
+            DataToken::ItemStart {
+                len: Length::UNDEFINED,
+            },
+            header(Tag(0x0008, 0x1150), VR::UI, 4),
+            value("1.2"),
+            header(Tag(0x0010, 0x0010), VR::PN, 8),
+            value("Doe^John"),
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+            header(Tag(0x0010, 0x0010), VR::PN, 8),
+            value("Doe^John"),
+            header(Tag(0x0010, 0x0020), VR::LO, 2),
+            value("42"),
+        ]
+    }
+
+    #[test]
+    fn drop_and_rename_elements() {
+        let tokens = nested_data_set();
+
+        let out = filter_all(&tokens, |chain| {
+            chain
+                .with_filter(DropTags::new([Tag(0x0010, 0x0010)]))
+                .with_filter(RenameTag::new(Tag(0x0008, 0x1115), Tag(0x0008, 0x1140)))
+        });
+        assert_eq!(
+            out,
+            vec![
+                header(Tag(0x0008, 0x0060), VR::CS, 2),
+                value("CT"),
+                DataToken::SequenceStart {
+                    tag: Tag(0x0008, 0x1140),
+                    len: Length::UNDEFINED,
+                },
+                DataToken::ItemStart {
+                    len: Length::UNDEFINED,
+                },
+                header(Tag(0x0008, 0x1150), VR::UI, 4),
+                value("1.2"),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+                header(Tag(0x0010, 0x0020), VR::LO, 2),
+                value("42"),
+            ]
+        );
+
+        // drop the whole sequence
+        let out = filter_all(&tokens, |chain| {
+            chain.with_filter(DropTags::new([Tag(0x0008, 0x1115)]))
+        });
+        assert_eq!(out.len(), 6);
+        assert_eq!(&out[..2], &tokens[..2]);
+        assert_eq!(&out[2..], &tokens[10..]);
+    }
+
+    #[test]
+    fn rename_elements_out_of_order() {
+        let tokens = nested_data_set();
+
+        // move Modality after Patient Name, replacing Patient ID
+        let out = filter_all(&tokens, |chain| {
+            chain.with_filter(RenameTag::new(Tag(0x0008, 0x0060), Tag(0x0010, 0x0020)))
+        });
+        assert_eq!(&out[..8], &tokens[2..10]);
+        assert_eq!(
+            &out[8..],
+            &[
+                header(Tag(0x0010, 0x0010), VR::PN, 8),
+                value("Doe^John"),
+                header(Tag(0x0010, 0x0020), VR::CS, 2),
+                value("CT"),
+            ]
+        );
+
+        // move the nested Patient Name before Referenced SOP Class UID
+        let out = filter_all(&tokens, |chain| {
+            chain.with_filter(RenameTag::new(Tag(0x0010, 0x0010), Tag(0x0008, 0x0020)))
+        });
+        assert_eq!(
+            out,
+            vec![
+                header(Tag(0x0008, 0x0020), VR::PN, 8),
+                value("Doe^John"),
+                header(Tag(0x0008, 0x0060), VR::CS, 2),
+                value("CT"),
+                DataToken::SequenceStart {
+                    tag: Tag(0x0008, 0x1115),
+                    len: Length::UNDEFINED,
+                },
+                DataToken::ItemStart {
+                    len: Length::UNDEFINED,
+                },
+                header(Tag(0x0008, 0x0020), VR::PN, 8),
+                value("Doe^John"),
+                header(Tag(0x0008, 0x1150), VR::UI, 4),
+                value("1.2"),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+                header(Tag(0x0010, 0x0020), VR::LO, 2),
+                value("42"),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_with_context() {
+        // a closure recording the context of each value
+        let mut contexts = Vec::new();
+        let out = filter_all(&nested_data_set(), |chain| {
+            chain.with_filter(
+                |token: DataToken, context: &TokenContext, output: &mut FilterOutput| {
+                    if let DataToken::PrimitiveValue(_) = token {
+                        contexts
+                            .push((context.sequences().to_vec(), context.header().unwrap().tag));
+                    }
+                    output.push(token);
+                },
+            )
+        });
+        assert_eq!(out, nested_data_set());
+        assert_eq!(
+            contexts,
+            vec![
+                (vec![], Tag(0x0008, 0x0060)),
+                (vec![Tag(0x0008, 0x1115)], Tag(0x0008, 0x1150)),
+                (vec![Tag(0x0008, 0x1115)], Tag(0x0010, 0x0010)),
+                (vec![], Tag(0x0010, 0x0010)),
+                (vec![], Tag(0x0010, 0x0020)),
+            ]
+        );
+
+        // drop items
+        let out = filter_all(&nested_data_set(), |chain| {
+            chain.with_filter(
+                |token: DataToken, _context: &TokenContext, output: &mut FilterOutput| {
+                    if let DataToken::ItemStart { .. } = token {
+                        output.drop_element();
+                    } else {
+                        output.push(token);
+                    }
+                },
+            )
+        });
+        assert_eq!(
+            out[2..4],
+            [
+                DataToken::SequenceStart {
+                    tag: Tag(0x0008, 0x1115),
+                    len: Length::UNDEFINED,
+                },
+                DataToken::SequenceEnd,
+            ]
+        );
+        assert_eq!(out.len(), 8);
+    }
+
+    #[test]
+    fn put_elements_and_map_values() {
+        let out = filter_all(&nested_data_set(), |chain| {
+            chain
+                // replaced
+                .with_filter(PutElement::new(Tag(0x0010, 0x0010), VR::PN, "Anonymous"))
+                // inserted in order
+                .with_filter(PutElement::new(Tag(0x0008, 0x0050), VR::SH, "A1"))
+                // appended
+                .with_filter(PutElement::new(Tag(0x0020, 0x0013), VR::IS, "1"))
+                .with_filter(MapValues::new(|header: &mut DataElementHeader, value| {
+                    if header.tag == Tag(0x0010, 0x0020) {
+                        header.vr = VR::SH;
+                        PrimitiveValue::from("43")
+                    } else {
+                        value
+                    }
+                }))
+        });
+
+        assert_eq!(
+            out,
+            vec![
+                header(Tag(0x0008, 0x0050), VR::SH, 2),
+                value("A1"),
+                header(Tag(0x0008, 0x0060), VR::CS, 2),
+                value("CT"),
+                DataToken::SequenceStart {
+                    tag: Tag(0x0008, 0x1115),
+                    len: Length::UNDEFINED,
+                },
+                DataToken::ItemStart {
+                    len: Length::UNDEFINED,
+                },
+                header(Tag(0x0008, 0x1150), VR::UI, 4),
+                value("1.2"),
+                // nested elements are not replaced
+                header(Tag(0x0010, 0x0010), VR::PN, 8),
+                value("Doe^John"),
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+                header(Tag(0x0010, 0x0010), VR::PN, 9),
+                value("Anonymous"),
+                header(Tag(0x0010, 0x0020), VR::SH, 2),
+                value("43"),
+                header(Tag(0x0020, 0x0013), VR::IS, 1),
+                value("1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncate_pixel_data() {
+        let native = [
+            header(Tag(0x0028, 0x0010), VR::US, 2),
+            value(2_u16),
+            header(PIXEL_DATA, VR::OB, 4),
+            value(vec![1_u8, 2, 3, 4]),
+        ];
+        let out = filter_all(&native, |chain| chain.with_filter(TruncatePixelData::new()));
+        assert_eq!(
+            out,
+            [
+                header(Tag(0x0028, 0x0010), VR::US, 2),
+                value(2_u16),
+                header(PIXEL_DATA, VR::OB, 4),
+                DataToken::PrimitiveValue(PrimitiveValue::Empty),
+            ]
+        );
+
+        let encapsulated = [
+            DataToken::PixelSequenceStart,
+            DataToken::ItemStart { len: Length(8) },
+            DataToken::OffsetTable(vec![0, 12]),
+            DataToken::ItemEnd,
+            DataToken::ItemStart { len: Length(4) },
+            DataToken::ItemValue(vec![1, 2, 3, 4]),
+            DataToken::ItemEnd,
+            DataToken::ItemStart { len: Length(2) },
+            DataToken::ItemValue(vec![5, 6]),
+            DataToken::ItemEnd,
+            DataToken::SequenceEnd,
+        ];
+        let out = filter_all(&encapsulated, |chain| {
+            chain.with_filter(TruncatePixelData::new())
+        });
+        assert_eq!(
+            out,
+            [
+                DataToken::PixelSequenceStart,
+                DataToken::ItemStart { len: Length(0) },
+                DataToken::ItemEnd,
+                DataToken::SequenceEnd,
+            ]
+        );
+    }
+}
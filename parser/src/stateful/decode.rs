@@ -13,8 +13,8 @@ use dicom_encoding::decode::basic::{BasicDecoder, LittleEndianBasicDecoder};
 use dicom_encoding::decode::explicit_le::ExplicitVRLittleEndianDecoder;
 use dicom_encoding::decode::{BasicDecode, DecodeFrom};
 use dicom_encoding::text::{
-    DefaultCharacterSetCodec, SpecificCharacterSet, TextCodec, TextValidationOutcome, validate_da,
-    validate_dt, validate_tm,
+    DefaultCharacterSetCodec, SpecificCharacterSet, TextCodec, TextValidationOutcome, split_values,
+    validate_da, validate_dt, validate_tm,
 };
 use dicom_encoding::transfer_syntax::{DynDecoder, TransferSyntax};
 use smallvec::smallvec;
@@ -482,8 +482,8 @@ where
         };
 
         let parts: Result<_> = if use_charset_declared {
-            self.buffer
-                .split(|v| *v == b'\\')
+            // code extensions may contain the delimiter in multi-byte characters
+            split_values(&self.buffer)
                 .map(|slice| {
                     self.text.decode(slice).context(DecodeTextSnafu {
                         position: self.position,
@@ -873,11 +873,9 @@ where
             // Edge case handling strategies for
             // unsupported specific character sets should probably be considered
             // in the future. See #40 for discussion.
-            if let Some(charset) = parts.first().map(|x| x.as_ref()).and_then(|name| {
-                SpecificCharacterSet::from_code(name).or_else(|| {
-                    tracing::warn!("Unsupported character set `{}`, ignoring", name);
-                    None
-                })
+            if let Some(charset) = SpecificCharacterSet::from_codes(parts.iter()).or_else(|| {
+                tracing::warn!("Unsupported character set `{}`, ignoring", parts.join("\\"));
+                None
             }) {
                 self.set_character_set(charset)?;
             }
@@ -1305,6 +1303,41 @@ mod tests {
         assert_eq!(decoder.text.name(), "ISO_IR 192",);
     }
 
+    /// Test that the stateful decoder switches to ISO 2022 code extensions
+    /// after a multi-valued Specific Character Set
+    /// and splits values without breaking multi-byte characters apart.
+    #[test]
+    fn update_character_set_with_code_extensions() {
+        let mut raw: Vec<u8> = vec![
+            // Tag: (0008,0005) Specific Character Set, VR: CS, Length: 16
+            0x08, 0x00, 0x05, 0x00, b'C', b'S', 0x10, 0x00,
+        ];
+        raw.extend(b"\\ISO 2022 IR 87 ");
+        // Tag: (0010,0010) Patient Name, VR: PN, Length: 26
+        raw.extend([0x10, 0x00, 0x10, 0x00, b'P', b'N', 0x1a, 0x00]);
+        // "Yamada=山田", then a kanji which contains byte 0x5C
+        raw.extend(b"Yamada=\x1b$B;3ED\x1b(B\\\x1b$B;\\\x1b(B");
+
+        let mut cursor = &raw[..];
+        let mut decoder = StatefulDecoder::new(
+            &mut cursor,
+            ExplicitVRLittleEndianDecoder::default(),
+            LittleEndianBasicDecoder,
+            SpecificCharacterSet::default(),
+        );
+
+        let header = decoder.decode_header().unwrap();
+        decoder.read_value_preserved(&header).unwrap();
+        assert_eq!(decoder.text.name(), "\\ISO 2022 IR 87");
+
+        let header = decoder.decode_header().unwrap();
+        let value = decoder.read_value(&header).unwrap();
+        assert_eq!(value.multiplicity(), 2);
+        let values = value.to_multi_str();
+        assert_eq!(values[0], "Yamada=山田");
+        assert_eq!(values[1].chars().count(), 1);
+    }
+
     #[test]
     fn decode_data_elements_with_position() {
         let data = {
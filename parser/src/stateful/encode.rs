@@ -11,6 +11,7 @@ use dicom_encoding::{
     text::{DefaultCharacterSetCodec, SpecificCharacterSet, TextCodec},
 };
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu};
+use std::borrow::Cow;
 use std::io::Write;
 
 #[derive(Debug, Snafu)]
@@ -76,6 +77,16 @@ impl<W, E, T> StatefulEncoder<W, E, T> {
             buffer: Vec::with_capacity(128),
         }
     }
+
+    /// Retrieve the inner writer, discarding the encoder.
+    pub fn into_inner(self) -> W {
+        self.to
+    }
+
+    /// Retrieve a mutable reference to the inner writer.
+    pub(crate) fn inner_mut(&mut self) -> &mut W {
+        &mut self.to
+    }
 }
 
 impl<'s> DynStatefulEncoder<'s> {
@@ -101,6 +112,12 @@ where
         if let Some(len) = de.len.get() {
             de.len = Length(even_len(len))
         }
+        self.encode_element_header_unpadded(de)
+    }
+
+    /// Encode and write a data element header as is,
+    /// even if it declares an odd length.
+    fn encode_element_header_unpadded(&mut self, de: DataElementHeader) -> Result<()> {
         let bytes = self
             .encoder
             .encode_element_header(&mut self.to, de)
@@ -214,15 +231,41 @@ where
         &mut self,
         de: &DataElementHeader,
         value: &PrimitiveValue,
+    ) -> Result<()> {
+        self.encode_primitive_element_impl(de, value, false)
+    }
+
+    /// Encode and write a data element with a primitive value,
+    /// retaining its length if it is odd.
+    ///
+    /// This method works like [`encode_primitive_element`](Self::encode_primitive_element),
+    /// except that no padding is applied
+    /// when the length property of the header is odd
+    /// and matches the byte length of the encoded value exactly.
+    /// This is meant for reproducing data elements
+    /// which were originally encoded with an odd length.
+    pub fn encode_primitive_element_exact(
+        &mut self,
+        de: &DataElementHeader,
+        value: &PrimitiveValue,
+    ) -> Result<()> {
+        self.encode_primitive_element_impl(de, value, true)
+    }
+
+    fn encode_primitive_element_impl(
+        &mut self,
+        de: &DataElementHeader,
+        value: &PrimitiveValue,
+        exact: bool,
     ) -> Result<()> {
         // intercept string encoding calls to use the text codec
         match value {
             PrimitiveValue::Str(text) => {
-                self.encode_text_element(text, *de)?;
+                self.encode_text_element(text, *de, exact)?;
                 Ok(())
             }
             PrimitiveValue::Strs(texts) => {
-                self.encode_texts_element(&texts[..], *de)?;
+                self.encode_texts_element(&texts[..], *de, exact)?;
                 Ok(())
             }
             _ => {
@@ -233,20 +276,27 @@ where
                 }
 
                 let byte_len = value.calculate_byte_len();
-                self.encode_element_header(DataElementHeader {
+                let keep_odd = exact && keeps_odd_len(de, byte_len);
+                let header = DataElementHeader {
                     tag: de.tag,
                     vr: de.vr,
                     len: Length(byte_len as u32),
-                })?;
+                };
+                if keep_odd {
+                    self.encode_element_header_unpadded(header)?;
+                } else {
+                    self.encode_element_header(header)?;
+                }
 
-                let bytes = self.encoder.encode_primitive(&mut self.to, value).context(
-                    EncodeDataSnafu {
+                let bytes = self
+                    .encoder
+                    .encode_primitive_with_vr(&mut self.to, de.vr, value)
+                    .context(EncodeDataSnafu {
                         position: self.bytes_written,
-                    },
-                )?;
+                    })?;
 
                 self.bytes_written += bytes as u64;
-                if bytes % 2 != 0 {
+                if bytes % 2 != 0 && !keep_odd {
                     let padding = match de.vr {
                         VR::DA | VR::DT | VR::TM => b' ',
                         _ => 0,
@@ -262,25 +312,42 @@ where
         }
     }
 
-    fn try_new_codec(&mut self, name: &str) {
-        if let Some(codec) = SpecificCharacterSet::from_code(name) {
+    fn try_new_codec<I, S>(&mut self, codes: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let codes: Vec<S> = codes.into_iter().collect();
+        if let Some(codec) = SpecificCharacterSet::from_codes(&codes) {
             self.text = codec;
         } else {
-            tracing::warn!("Unsupported character set `{}`, ignoring", name);
+            tracing::warn!(
+                "Unsupported character set `{}`, ignoring",
+                codes
+                    .iter()
+                    .map(|code| code.as_ref())
+                    .collect::<Vec<_>>()
+                    .join("\\")
+            );
         }
     }
 
-    fn encode_text_element(&mut self, text: &str, de: DataElementHeader) -> Result<()> {
+    fn encode_text_element(
+        &mut self,
+        text: &str,
+        de: DataElementHeader,
+        exact: bool,
+    ) -> Result<()> {
         // encode it in memory first so that we know the real length
         let mut encoded_value = self.convert_text_untrailed(text, de.vr)?;
         // pad to even length
-        if encoded_value.len() % 2 == 1 {
+        if encoded_value.len() % 2 == 1 && !(exact && keeps_odd_len(&de, encoded_value.len())) {
             let pad = if de.vr == VR::UI { b'\0' } else { b' ' };
             encoded_value.push(pad);
         }
 
         // now we can write the header with the correct length
-        self.encode_element_header(DataElementHeader {
+        self.encode_element_header_unpadded(DataElementHeader {
             tag: de.tag,
             vr: de.vr,
             len: Length(encoded_value.len() as u32),
@@ -295,13 +362,18 @@ where
         // if element is Specific Character Set,
         // update the text codec
         if de.tag == Tag(0x0008, 0x0005) {
-            self.try_new_codec(text);
+            self.try_new_codec(text.split('\\'));
         }
 
         Ok(())
     }
 
-    fn encode_texts_element<S>(&mut self, texts: &[S], de: DataElementHeader) -> Result<()>
+    fn encode_texts_element<S>(
+        &mut self,
+        texts: &[S],
+        de: DataElementHeader,
+        exact: bool,
+    ) -> Result<()>
     where
         S: AsRef<str>,
     {
@@ -314,13 +386,13 @@ where
             }
         }
         // pad to even length
-        if self.buffer.len() % 2 == 1 {
+        if self.buffer.len() % 2 == 1 && !(exact && keeps_odd_len(&de, self.buffer.len())) {
             let pad = if de.vr == VR::UI { b'\0' } else { b' ' };
             self.buffer.push(pad);
         }
 
         // now we can write the header with the correct length
-        self.encode_element_header(DataElementHeader {
+        self.encode_element_header_unpadded(DataElementHeader {
             tag: de.tag,
             vr: de.vr,
             len: Length(self.buffer.len() as u32),
@@ -336,9 +408,7 @@ where
         // if element is Specific Character Set,
         // update the text codec
         if de.tag == Tag(0x0008, 0x0005) {
-            if let Some(charset_name) = texts.first() {
-                self.try_new_codec(charset_name.as_ref());
-            }
+            self.try_new_codec(texts);
         }
 
         Ok(())
@@ -385,7 +455,21 @@ where
             | PrimitiveValue::U64(_)
             | PrimitiveValue::F32(_)
             | PrimitiveValue::F64(_) => {
-                let textual_value = value.to_str();
+                // keep decimal strings within their maximum length
+                let decimals = match de.vr {
+                    VR::DS => value.to_multi_decimal().ok(),
+                    _ => None,
+                };
+                let textual_value = match decimals {
+                    Some(values) => Cow::Owned(
+                        values
+                            .iter()
+                            .map(|v| v.as_str())
+                            .collect::<Vec<_>>()
+                            .join("\\"),
+                    ),
+                    None => value.to_str(),
+                };
                 self.encode_element_header(DataElementHeader {
                     tag: de.tag,
                     vr: de.vr,
@@ -426,6 +510,13 @@ fn even_len(l: u32) -> u32 {
     (l + 1) & !1
 }
 
+/// Whether the header declares an odd length
+/// which matches the given byte length exactly.
+#[inline]
+fn keeps_odd_len(de: &DataElementHeader, byte_len: usize) -> bool {
+    byte_len % 2 == 1 && de.len.get() == Some(byte_len as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use dicom_core::{
@@ -547,6 +638,34 @@ mod tests {
         )
     }
 
+    /// Binary DS values are encoded as decimal strings
+    /// of at most 16 characters
+    #[test]
+    fn encode_binary_element_ds() {
+        let element = DataElement::new(
+            Tag(0x0028, 0x0030),
+            VR::DS,
+            DicomValue::new(dicom_value!(F64, [0.1 + 0.2, 1.0 / 3.0])),
+        );
+
+        let mut out: Vec<_> = Vec::new();
+
+        {
+            let mut encoder = StatefulEncoder::new(
+                &mut out,
+                EncoderFor::new(ExplicitVRLittleEndianEncoder::default()),
+                SpecificCharacterSet::default(),
+            );
+
+            encoder
+                .encode_primitive_element(element.header(), element.value().primitive().unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(&out[..8], &[0x28, 0x00, 0x30, 0x00, b'D', b'S', 0x14, 0x00]);
+        assert_eq!(&out[8..], b"0.3\\0.33333333333333");
+    }
+
     /// Odd lengthed item values are encoded with even padding
     #[test]
     fn encode_odd_length_item_bytes() {
@@ -610,6 +729,50 @@ mod tests {
         )
     }
 
+    /// Odd lengthed values declared as such in the header
+    /// are kept unpadded when encoding exactly
+    #[test]
+    fn encode_odd_length_element_exact() {
+        let mut out: Vec<_> = Vec::new();
+
+        {
+            let mut encoder = StatefulEncoder::new(
+                &mut out,
+                EncoderFor::new(ExplicitVRLittleEndianEncoder::default()),
+                SpecificCharacterSet::default(),
+            );
+
+            // odd length declared, kept as is
+            encoder
+                .encode_primitive_element_exact(
+                    &DataElementHeader::new(Tag(0x0010, 0x0010), VR::PN, Length(3)),
+                    &dicom_value!(Strs, ["Doe"]),
+                )
+                .unwrap();
+            // length does not match the value, padded as usual
+            encoder
+                .encode_primitive_element_exact(
+                    &DataElementHeader::new(Tag(0x0010, 0x0020), VR::LO, Length(4)),
+                    &PrimitiveValue::from("ABC"),
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            &out,
+            &[
+                0x10, 0x00, 0x10, 0x00, // tag (0x0010, 0x0010)
+                b'P', b'N', // VR
+                0x03, 0x00, // length
+                b'D', b'o', b'e', // value
+                0x10, 0x00, 0x20, 0x00, // tag (0x0010, 0x0020)
+                b'L', b'O', // VR
+                0x04, 0x00, // length
+                b'A', b'B', b'C', b' ', // value
+            ],
+        )
+    }
+
     #[test]
     fn test_even_len() {
         use super::even_len;
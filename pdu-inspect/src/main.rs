@@ -0,0 +1,230 @@
+//! A CLI tool for inspecting captures of DICOM upper layer PDUs.
+//!
+//! Captures are recorded by associations
+//! given a [`PduCapture`](dicom_ul::capture::PduCapture)
+//! on establishment.
+//! Each PDU is printed with its time relative to the first one,
+//! its direction, and its contents in structured form
+//! or as a hexadecimal dump.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use dicom_ul::{
+    capture::{CaptureReader, CapturedPdu, Direction},
+    pdu::{PDataValue, Pdu},
+};
+use snafu::{ResultExt, Whatever};
+
+/// Print the PDUs of a DICOM association capture file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The capture file
+    file: PathBuf,
+    /// Print a single line per PDU
+    #[arg(short = 's', long = "summary", conflicts_with = "hex")]
+    summary: bool,
+    /// Print a hexadecimal dump of each PDU
+    #[arg(short = 'x', long = "hex")]
+    hex: bool,
+    /// Only print PDUs in this direction
+    #[arg(long = "only", value_parser = parse_direction)]
+    only: Option<Direction>,
+}
+
+fn parse_direction(s: &str) -> Result<Direction, String> {
+    match s {
+        "sent" => Ok(Direction::Sent),
+        "received" => Ok(Direction::Received),
+        _ => Err(format!(
+            "invalid direction `{s}`, expected `sent` or `received`"
+        )),
+    }
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        file,
+        summary,
+        hex,
+        only,
+    } = app;
+
+    let reader = CaptureReader::open(&file)
+        .with_whatever_context(|_| format!("Could not open capture {}", file.display()))?;
+
+    let mut start = None;
+    for (i, record) in reader.enumerate() {
+        let record = record.whatever_context("Could not read capture")?;
+        let start = *start.get_or_insert(record.timestamp);
+        if only.is_some_and(|direction| direction != record.direction) {
+            continue;
+        }
+        let elapsed = record
+            .timestamp
+            .duration_since(start)
+            .unwrap_or(Duration::ZERO);
+        println!("{}", header(i + 1, elapsed, &record));
+        if summary {
+            continue;
+        }
+        if hex {
+            print!("{}", hex_dump(&record.data));
+        } else {
+            match record.pdu() {
+                Ok(pdu) => print!("{}", describe(&pdu)),
+                Err(e) => println!("  (could not decode PDU: {})", snafu::Report::from_error(e)),
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// The line at the start of each PDU:
+/// its number, the time since the start of the capture,
+/// its direction, and a short description of the PDU
+fn header(number: usize, elapsed: Duration, record: &CapturedPdu) -> String {
+    let arrow = match record.direction {
+        Direction::Sent => ">>",
+        Direction::Received => "<<",
+    };
+    let description = match record.pdu() {
+        Ok(pdu @ Pdu::PData { .. }) => pdu.short_description().to_string(),
+        Ok(pdu) => pdu_name(&pdu).to_string(),
+        Err(_) => "(invalid PDU)".to_string(),
+    };
+    format!(
+        "#{number} {:>4}.{:06} {arrow} {description} ({} bytes)",
+        elapsed.as_secs(),
+        elapsed.subsec_micros(),
+        record.data.len(),
+    )
+}
+
+/// The name of the kind of PDU
+fn pdu_name(pdu: &Pdu) -> &'static str {
+    match pdu {
+        Pdu::Unknown { .. } => "Unknown",
+        Pdu::AssociationRQ(_) => "A-ASSOCIATE-RQ",
+        Pdu::AssociationAC(_) => "A-ASSOCIATE-AC",
+        Pdu::AssociationRJ(_) => "A-ASSOCIATE-RJ",
+        Pdu::PData { .. } => "P-DATA-TF",
+        Pdu::ReleaseRQ => "A-RELEASE-RQ",
+        Pdu::ReleaseRP => "A-RELEASE-RP",
+        Pdu::AbortRQ { .. } => "A-ABORT",
+    }
+}
+
+/// Describe a PDU in structured form, indented.
+///
+/// The values of P-Data PDUs are only described by their headers
+/// and first few bytes, so as to keep the output readable.
+fn describe(pdu: &Pdu) -> String {
+    let text = match pdu {
+        Pdu::PData { data } => data.iter().map(describe_pdv).collect(),
+        Pdu::Unknown { pdu_type, data } => {
+            format!("Unknown PDU type {pdu_type:#04X}\n{}", hex_dump(data))
+        }
+        pdu => format!("{pdu:#?}\n"),
+    };
+    text.lines().map(|line| format!("  {line}\n")).collect()
+}
+
+/// Describe a presentation data value by its header and first bytes
+fn describe_pdv(pdv: &PDataValue) -> String {
+    let last = if pdv.is_last { ", last" } else { "" };
+    let preview: Vec<_> = pdv
+        .data
+        .iter()
+        .take(16)
+        .map(|b| format!("{b:02X}"))
+        .collect();
+    let more = if pdv.data.len() > 16 { " ..." } else { "" };
+    format!(
+        "PDV {{ presentation context {}, {:?}{last}, {} bytes }}\n  {}{more}\n",
+        pdv.presentation_context_id,
+        pdv.value_type,
+        pdv.data.len(),
+        preview.join(" "),
+    )
+}
+
+/// Dump the given bytes in hexadecimal,
+/// 16 bytes per line with their offset and ASCII representation.
+fn hex_dump(data: &[u8]) -> String {
+    data.chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<_> = chunk.iter().map(|b| format!("{b:02X}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!("  {:08X}  {:<47}  {ascii}\n", i * 16, hex.join(" "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+    use dicom_ul::pdu::PDataValueType;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+
+    #[test]
+    fn print_pdus() {
+        let record = CapturedPdu {
+            direction: Direction::Received,
+            timestamp: UNIX_EPOCH,
+            data: vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00],
+        };
+        assert_eq!(
+            header(3, Duration::from_micros(1_500_000), &record),
+            "#3    1.500000 << A-RELEASE-RQ (10 bytes)"
+        );
+
+        assert_eq!(
+            hex_dump(b"\x01\x00DICOM-RS"),
+            "  00000000  01 00 44 49 43 4F 4D 2D 52 53                    ..DICOM-RS\n"
+        );
+
+        let pdu = Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id: 1,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: vec![0xAB; 20],
+            }],
+        };
+        let text = describe(&pdu);
+        assert!(text.starts_with("  PDV { presentation context 1, Command, last, 20 bytes }\n"));
+        assert!(text.ends_with("AB AB ...\n"));
+    }
+}
@@ -6,6 +6,8 @@ use dicom_object::{FileDicomObject, InMemDicomObject, mem::InMemElement};
 use snafu::{Backtrace, OptionExt, ResultExt, Snafu, ensure};
 use std::fmt;
 
+use crate::{VoiLutFunction, WindowLevel};
+
 /// An enum for a DICOM attribute which can be retrieved
 /// for the purposes of decoding pixel data.
 ///
@@ -33,6 +35,17 @@ pub enum AttributeName {
     LutDescriptor,
     LutData,
     LutExplanation,
+    RedPaletteColorLutDescriptor,
+    GreenPaletteColorLutDescriptor,
+    BluePaletteColorLutDescriptor,
+    RedPaletteColorLutData,
+    GreenPaletteColorLutData,
+    BluePaletteColorLutData,
+    ImagePositionPatient,
+    ImageOrientationPatient,
+    PixelSpacing,
+    SliceThickness,
+    SpacingBetweenSlices,
 }
 
 impl std::fmt::Display for AttributeName {
@@ -207,6 +220,203 @@ fn get_from_per_frame<D: DataDictionary + Clone>(
     items.peek().is_some().then_some(items)
 }
 
+/// Fetch an attribute applicable to a single frame,
+/// looking it up in the frame's item of the Per-Frame Functional Groups Sequence,
+/// then in the Shared Functional Groups Sequence,
+/// and finally at the root of the object.
+fn get_for_frame<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    frame: u32,
+    selector: [Tag; 2],
+) -> Option<&InMemElement<D>> {
+    fn from_group<D: DataDictionary + Clone>(
+        group: &InMemDicomObject<D>,
+        selector: [Tag; 2],
+    ) -> Option<&InMemElement<D>> {
+        group
+            .get(selector[0])
+            .and_then(|inner| inner.items()?.first()?.get(selector[1]))
+    }
+
+    obj.get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+        .and_then(|e| e.items()?.get(frame as usize))
+        .and_then(|group| from_group(group, selector))
+        .or_else(|| {
+            obj.get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)
+                .and_then(|e| e.items()?.first())
+                .and_then(|group| from_group(group, selector))
+        })
+        .or_else(|| obj.get(selector[1]))
+}
+
+/// Spatial and display information applicable to a single frame,
+/// resolved from the _Per-Frame_ and _Shared Functional Groups Sequences_
+/// of enhanced multi-frame objects,
+/// or from the root of the object otherwise.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_object::open_file;
+/// use dicom_pixeldata::FrameInfo;
+///
+/// let obj = open_file("enhanced_ct.dcm")?;
+/// for info in FrameInfo::all_from_object(&obj)? {
+///     println!("{:?}", info.image_position_patient);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FrameInfo {
+    /// the _Image Position (Patient)_
+    pub image_position_patient: Option<[f64; 3]>,
+    /// the _Image Orientation (Patient)_,
+    /// the direction cosines of the first row and the first column
+    pub image_orientation_patient: Option<[f64; 6]>,
+    /// the _Pixel Spacing_, as the spacing between rows
+    /// followed by the spacing between columns
+    pub pixel_spacing: Option<[f64; 2]>,
+    /// the _Slice Thickness_
+    pub slice_thickness: Option<f64>,
+    /// the _Spacing Between Slices_
+    pub spacing_between_slices: Option<f64>,
+    /// the first window of the frame
+    pub window: Option<WindowLevel>,
+    /// the _VOI LUT Function_ of the frame, if recognized
+    pub voi_lut_function: Option<VoiLutFunction>,
+    /// the _Rescale Slope_
+    pub rescale_slope: Option<f64>,
+    /// the _Rescale Intercept_
+    pub rescale_intercept: Option<f64>,
+}
+
+impl FrameInfo {
+    /// Resolve the information of the given frame (starting at 0).
+    ///
+    /// Returns an error if the frame is out of range
+    /// or if any of the attributes found is malformed.
+    pub fn from_object<D: DataDictionary + Clone>(
+        obj: &FileDicomObject<InMemDicomObject<D>>,
+        frame: u32,
+    ) -> Result<Self> {
+        let number_of_frames = number_of_frames(obj)?;
+        ensure!(
+            frame < number_of_frames,
+            InvalidValueSnafu {
+                name: AttributeName::NumberOfFrames,
+                value: number_of_frames.to_string(),
+            }
+        );
+
+        let float = |selector: [Tag; 2], name: AttributeName| -> Result<Option<f64>> {
+            get_for_frame(obj, frame, selector)
+                .filter(|e| !e.is_empty())
+                .map(|e| e.to_float64().context(ConvertValueSnafu { name }))
+                .transpose()
+        };
+
+        let window_center = float(
+            [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_CENTER],
+            AttributeName::WindowCenter,
+        )?;
+        let window_width = float(
+            [tags::FRAME_VOILUT_SEQUENCE, tags::WINDOW_WIDTH],
+            AttributeName::WindowWidth,
+        )?;
+        let voi_lut_function = get_for_frame(
+            obj,
+            frame,
+            [tags::FRAME_VOILUT_SEQUENCE, tags::VOILUT_FUNCTION],
+        )
+        .and_then(|e| e.string().ok())
+        .and_then(|v| VoiLutFunction::try_from(v.trim()).ok());
+
+        Ok(FrameInfo {
+            image_position_patient: multi_float_for_frame(
+                obj,
+                frame,
+                [tags::PLANE_POSITION_SEQUENCE, tags::IMAGE_POSITION_PATIENT],
+                AttributeName::ImagePositionPatient,
+            )?,
+            image_orientation_patient: multi_float_for_frame(
+                obj,
+                frame,
+                [
+                    tags::PLANE_ORIENTATION_SEQUENCE,
+                    tags::IMAGE_ORIENTATION_PATIENT,
+                ],
+                AttributeName::ImageOrientationPatient,
+            )?,
+            pixel_spacing: multi_float_for_frame(
+                obj,
+                frame,
+                [tags::PIXEL_MEASURES_SEQUENCE, tags::PIXEL_SPACING],
+                AttributeName::PixelSpacing,
+            )?,
+            slice_thickness: float(
+                [tags::PIXEL_MEASURES_SEQUENCE, tags::SLICE_THICKNESS],
+                AttributeName::SliceThickness,
+            )?,
+            spacing_between_slices: float(
+                [tags::PIXEL_MEASURES_SEQUENCE, tags::SPACING_BETWEEN_SLICES],
+                AttributeName::SpacingBetweenSlices,
+            )?,
+            window: window_center
+                .zip(window_width)
+                .map(|(center, width)| WindowLevel { center, width }),
+            voi_lut_function,
+            rescale_slope: float(
+                [
+                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
+                    tags::RESCALE_SLOPE,
+                ],
+                AttributeName::RescaleSlope,
+            )?,
+            rescale_intercept: float(
+                [
+                    tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
+                    tags::RESCALE_INTERCEPT,
+                ],
+                AttributeName::RescaleIntercept,
+            )?,
+        })
+    }
+
+    /// Resolve the information of every frame in the object.
+    pub fn all_from_object<D: DataDictionary + Clone>(
+        obj: &FileDicomObject<InMemDicomObject<D>>,
+    ) -> Result<Vec<Self>> {
+        (0..number_of_frames(obj)?)
+            .map(|frame| FrameInfo::from_object(obj, frame))
+            .collect()
+    }
+}
+
+/// Fetch a multi-valued floating point attribute for a single frame,
+/// failing if it does not have exactly `N` values.
+fn multi_float_for_frame<const N: usize, D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    frame: u32,
+    selector: [Tag; 2],
+    name: AttributeName,
+) -> Result<Option<[f64; N]>> {
+    let Some(elem) = get_for_frame(obj, frame, selector).filter(|e| !e.is_empty()) else {
+        return Ok(None);
+    };
+    let values: Vec<f64> = elem
+        .to_multi_float64()
+        .context(ConvertValueSnafu { name })?;
+    let values = <[f64; N]>::try_from(values).map_err(|values| {
+        InvalidValueSnafu {
+            name,
+            value: format!("{values:?}"),
+        }
+        .build()
+    })?;
+    Ok(Some(values))
+}
+
 /// Get the RescaleIntercept from the DICOM object or returns 0
 pub fn rescale_intercept<D: DataDictionary + Clone>(
     obj: &FileDicomObject<InMemDicomObject<D>>,
@@ -769,6 +979,288 @@ pub fn voi_lut_sequence<D: DataDictionary + Clone>(
         })
 }
 
+/// A decoded representation of the red, green, and blue
+/// _Palette Color Lookup Tables_ of an image.
+///
+/// These tables are used to map the stored values of images
+/// with the _PALETTE COLOR_ photometric interpretation to RGB colors,
+/// and as supplemental palettes of monochrome images
+/// with a _Pixel Presentation_ of _COLOR_ or _MIXED_.
+///
+/// See [section C.7.6.3.1.5][1] of the standard for more details.
+///
+/// [1]: https://dicom.nema.org/medical/dicom/2024d/output/chtml/part03/sect_C.7.6.3.html#sect_C.7.6.3.1.5
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteColorLut {
+    /// The first stored pixel value mapped by the tables.
+    /// All values below this are mapped to the first entry of each table.
+    pub first_mapped_value: i32,
+    /// Number of bits for each entry in the tables, either 8 or 16
+    pub bits: u8,
+    /// The red lookup table data, one entry per mapped value
+    pub red: Vec<u16>,
+    /// The green lookup table data, one entry per mapped value
+    pub green: Vec<u16>,
+    /// The blue lookup table data, one entry per mapped value
+    pub blue: Vec<u16>,
+}
+
+impl PaletteColorLut {
+    /// Map a stored pixel value to its red, green, and blue entries.
+    ///
+    /// Values past the last entry of a table
+    /// are mapped to that last entry.
+    pub fn get(&self, value: i32) -> [u16; 3] {
+        let index = value as i64 - self.first_mapped_value as i64;
+        let entry = |table: &[u16]| table[index.clamp(0, table.len() as i64 - 1) as usize];
+        [entry(&self.red), entry(&self.green), entry(&self.blue)]
+    }
+
+    /// Check whether the given stored pixel value
+    /// is within the range of values mapped by the tables.
+    pub fn contains(&self, value: i32) -> bool {
+        let index = value as i64 - self.first_mapped_value as i64;
+        index >= 0 && (index as usize) < self.red.len()
+    }
+}
+
+/// Expand the segments of a _Segmented Palette Color Lookup Table Data_
+/// attribute, as described in [section C.7.9.2][1] of the standard.
+///
+/// Returns `None` if the segments are malformed.
+///
+/// [1]: https://dicom.nema.org/medical/dicom/2024d/output/chtml/part03/sect_C.7.9.2.html
+fn expand_segmented_lut(data: &[u16], bits: u8) -> Option<Vec<u16>> {
+    fn expand(
+        data: &[u16],
+        bits: u8,
+        mut pos: usize,
+        count: Option<usize>,
+        out: &mut Vec<u16>,
+    ) -> Option<()> {
+        let mut segments = 0;
+        while pos < data.len() && count.is_none_or(|count| segments < count) {
+            let length = *data.get(pos + 1)? as usize;
+            match data[pos] {
+                // discrete segment
+                0 => {
+                    out.extend_from_slice(data.get(pos + 2..pos + 2 + length)?);
+                    pos += 2 + length;
+                }
+                // linear segment, starting from the last value
+                1 => {
+                    let y0 = *out.last()? as f64;
+                    let y1 = *data.get(pos + 2)? as f64;
+                    out.extend(
+                        (1..=length)
+                            .map(|i| (y0 + (y1 - y0) * i as f64 / length as f64).round() as u16),
+                    );
+                    pos += 3;
+                }
+                // indirect segment, which shall not refer to other indirect segments
+                2 if count.is_none() => {
+                    let offset =
+                        *data.get(pos + 2)? as usize | (*data.get(pos + 3)? as usize) << bits;
+                    expand(data, bits, offset, Some(length), out)?;
+                    pos += 4;
+                }
+                _ => return None,
+            }
+            segments += 1;
+        }
+        Some(())
+    }
+
+    let mut out = Vec::new();
+    expand(data, bits, 0, None, &mut out)?;
+    Some(out)
+}
+
+/// Parse one channel of the palette color lookup tables,
+/// returning the first mapped value, the number of bits per entry,
+/// and the table data.
+fn palette_color_lut_channel<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    [descriptor_tag, data_tag, segmented_tag]: [Tag; 3],
+    [descriptor_name, data_name]: [AttributeName; 2],
+) -> Result<Option<(i32, u8, Vec<u16>)>> {
+    let Some(descriptor) = obj.element_opt(descriptor_tag).context(RetrieveSnafu {
+        name: descriptor_name,
+    })?
+    else {
+        return Ok(None);
+    };
+    let descriptor: Vec<i32> = descriptor.to_multi_int().context(ConvertValueSnafu {
+        name: descriptor_name,
+    })?;
+    ensure!(
+        descriptor.len() == 3,
+        InvalidValueSnafu {
+            name: descriptor_name,
+            value: format!("value with multiplicity {}", descriptor.len()),
+        }
+    );
+    // a number of entries of 0 stands for 65536,
+    // and may have been read as a signed value
+    let len = match descriptor[0] as u16 {
+        0 => 0x1_0000,
+        len => len as usize,
+    };
+    let first_mapped_value = descriptor[1];
+    ensure!(
+        descriptor[2] == 8 || descriptor[2] == 16,
+        InvalidValueSnafu {
+            name: descriptor_name,
+            value: format!("value with bits per entry {}", descriptor[2]),
+        }
+    );
+    let bits = descriptor[2] as u8;
+
+    let mut data = if let Some(segmented) = obj
+        .element_opt(segmented_tag)
+        .context(RetrieveSnafu { name: data_name })?
+    {
+        let data = segmented
+            .uint16_slice()
+            .context(CastValueSnafu { name: data_name })?;
+        if bits == 8 {
+            // 8-bit segments are packed two per word
+            let data: Vec<u16> = data
+                .iter()
+                .flat_map(|w| w.to_le_bytes())
+                .map(u16::from)
+                .collect();
+            expand_segmented_lut(&data, bits)
+        } else {
+            expand_segmented_lut(data, bits)
+        }
+        .context(InvalidValueSnafu {
+            name: data_name,
+            value: "malformed segmented lookup table",
+        })?
+    } else {
+        let data = obj
+            .element_opt(data_tag)
+            .context(RetrieveSnafu { name: data_name })?
+            .context(MissingRequiredSnafu { name: data_name })?
+            .uint16_slice()
+            .context(CastValueSnafu { name: data_name })?;
+        if bits == 8 && data.len() < len {
+            // 8-bit entries packed two per word
+            data.iter()
+                .flat_map(|w| w.to_le_bytes())
+                .map(u16::from)
+                .collect()
+        } else if bits == 8 && data.iter().any(|&v| v > 0xFF) {
+            // 8-bit entries stored in the high byte of each word
+            data.iter().map(|&v| v >> 8).collect()
+        } else {
+            data.to_vec()
+        }
+    };
+
+    ensure!(
+        !data.is_empty(),
+        InvalidValueSnafu {
+            name: data_name,
+            value: "empty lookup table",
+        }
+    );
+    data.truncate(len);
+
+    Ok(Some((first_mapped_value, bits, data)))
+}
+
+/// Get the red, green, and blue palette color lookup tables
+/// from the DICOM object.
+///
+/// Both the plain and the segmented forms of the
+/// _Palette Color Lookup Table Data_ attributes are supported.
+/// Returns `Ok(None)` if the object has no palette color lookup tables.
+pub fn palette_color_lut<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Result<Option<PaletteColorLut>> {
+    let Some((first_mapped_value, bits, red)) = palette_color_lut_channel(
+        obj,
+        [
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::RedPaletteColorLutDescriptor,
+            AttributeName::RedPaletteColorLutData,
+        ],
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let channel = |tags, names: [AttributeName; 2]| {
+        let (first, channel_bits, data) = palette_color_lut_channel(obj, tags, names)?
+            .context(MissingRequiredSnafu { name: names[0] })?;
+        ensure!(
+            first == first_mapped_value && channel_bits == bits,
+            InvalidValueSnafu {
+                name: names[0],
+                value: "value different from the red palette descriptor",
+            }
+        );
+        Ok(data)
+    };
+
+    let green = channel(
+        [
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::GreenPaletteColorLutDescriptor,
+            AttributeName::GreenPaletteColorLutData,
+        ],
+    )?;
+    let blue = channel(
+        [
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            tags::SEGMENTED_BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+        ],
+        [
+            AttributeName::BluePaletteColorLutDescriptor,
+            AttributeName::BluePaletteColorLutData,
+        ],
+    )?;
+
+    Ok(Some(PaletteColorLut {
+        first_mapped_value,
+        bits,
+        red,
+        green,
+        blue,
+    }))
+}
+
+/// Check whether the _Pixel Presentation_ of the DICOM object
+/// is either _COLOR_ or _MIXED_,
+/// meaning that a supplemental palette color lookup table
+/// should be applied to monochrome pixel data.
+pub fn has_supplemental_palette<D: DataDictionary + Clone>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> bool {
+    obj.element_opt(tags::PIXEL_PRESENTATION)
+        .ok()
+        .flatten()
+        .and_then(|e| e.string().ok())
+        .map(|s| {
+            matches!(
+                s.trim_matches(|c: char| c.is_whitespace() || c == '\0'),
+                "COLOR" | "MIXED"
+            )
+        })
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::rescale_intercept;
@@ -940,4 +1432,192 @@ mod tests {
         // Check the fn still returns the correct value, falling back to SharedFunctionalGroupsSequence
         assert_eq!(rescale_intercept(&dcm), vec![3.0]);
     }
+
+    #[test]
+    fn expand_segmented_palette_lut() {
+        use super::expand_segmented_lut;
+
+        // discrete, linear, then indirect segments
+        let data = [0, 2, 10, 20, 1, 4, 60, 2, 2, 0, 0];
+        assert_eq!(
+            expand_segmented_lut(&data, 16),
+            Some(vec![10, 20, 30, 40, 50, 60, 10, 20, 30, 40, 50, 60])
+        );
+
+        // linear segment without a previous value
+        assert_eq!(expand_segmented_lut(&[1, 4, 60], 16), None);
+        // truncated discrete segment
+        assert_eq!(expand_segmented_lut(&[0, 4, 1, 2], 16), None);
+        // unknown opcode
+        assert_eq!(expand_segmented_lut(&[3, 1, 0], 16), None);
+    }
+
+    #[test]
+    fn get_palette_color_lut_with_packed_8bit_entries() {
+        use super::palette_color_lut;
+
+        let mut dcm = dummy_dicom();
+        assert_eq!(palette_color_lut(&dcm).unwrap(), None);
+
+        for (descriptor, data) in [
+            (
+                tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+                tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            ),
+            (
+                tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+                tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            ),
+        ] {
+            dcm.put(DataElement::new(
+                descriptor,
+                VR::US,
+                PrimitiveValue::from([4_u16, 0, 8]),
+            ));
+            dcm.put(DataElement::new(
+                data,
+                VR::OW,
+                PrimitiveValue::U16(vec![0x2010, 0x4030].into()),
+            ));
+        }
+        // the blue table is required as well
+        assert!(palette_color_lut(&dcm).is_err());
+
+        dcm.put(DataElement::new(
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            VR::US,
+            PrimitiveValue::from([4_u16, 0, 8]),
+        ));
+        // one entry per word
+        dcm.put(DataElement::new(
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![1, 2, 3, 4].into()),
+        ));
+        let lut = palette_color_lut(&dcm).unwrap().unwrap();
+        assert_eq!(lut.bits, 8);
+        assert_eq!(lut.red, vec![0x10, 0x20, 0x30, 0x40]);
+        assert_eq!(lut.blue, vec![1, 2, 3, 4]);
+        assert_eq!(lut.get(2), [0x30, 0x30, 3]);
+        assert_eq!(lut.get(10), [0x40, 0x40, 4]);
+        assert!(lut.contains(3));
+        assert!(!lut.contains(4));
+    }
+
+    #[test]
+    fn frame_info_merges_functional_groups() {
+        use super::FrameInfo;
+        use crate::{VoiLutFunction, WindowLevel};
+
+        let mut dcm = dummy_dicom();
+        dcm.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        let item = |seq, elements: Vec<DataElement<InMemDicomObject>>| {
+            DataElement::new(
+                seq,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter(elements)]),
+            )
+        };
+        // spacing and window are shared by all frames
+        dcm.put(item(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            vec![
+                item(
+                    tags::PIXEL_MEASURES_SEQUENCE,
+                    vec![
+                        DataElement::new(
+                            tags::PIXEL_SPACING,
+                            VR::DS,
+                            dicom_value!(F64, [0.5, 0.25]),
+                        ),
+                        DataElement::new(tags::SLICE_THICKNESS, VR::DS, "2"),
+                    ],
+                ),
+                item(
+                    tags::FRAME_VOILUT_SEQUENCE,
+                    vec![
+                        DataElement::new(tags::WINDOW_CENTER, VR::DS, "40"),
+                        DataElement::new(tags::WINDOW_WIDTH, VR::DS, "400"),
+                        DataElement::new(tags::VOILUT_FUNCTION, VR::CS, "SIGMOID"),
+                    ],
+                ),
+            ],
+        ));
+        // position is specific to each frame,
+        // and the second frame overrides the window
+        let per_frame = (0..2)
+            .map(|i| {
+                let mut elements = vec![item(
+                    tags::PLANE_POSITION_SEQUENCE,
+                    vec![DataElement::new(
+                        tags::IMAGE_POSITION_PATIENT,
+                        VR::DS,
+                        dicom_value!(F64, [-100., -80., i as f64 * 2.]),
+                    )],
+                )];
+                if i == 1 {
+                    elements.push(item(
+                        tags::FRAME_VOILUT_SEQUENCE,
+                        vec![
+                            DataElement::new(tags::WINDOW_CENTER, VR::DS, "300"),
+                            DataElement::new(tags::WINDOW_WIDTH, VR::DS, "1500"),
+                        ],
+                    ));
+                }
+                InMemDicomObject::from_element_iter(elements)
+            })
+            .collect::<Vec<_>>();
+        dcm.put(DataElement::new(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(per_frame),
+        ));
+        // found at the root of the object
+        dcm.put(DataElement::new(
+            tags::IMAGE_ORIENTATION_PATIENT,
+            VR::DS,
+            dicom_value!(F64, [1., 0., 0., 0., 1., 0.]),
+        ));
+
+        let frames = FrameInfo::all_from_object(&dcm).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].image_position_patient, Some([-100., -80., 0.]));
+        assert_eq!(frames[1].image_position_patient, Some([-100., -80., 2.]));
+        for info in &frames {
+            assert_eq!(info.pixel_spacing, Some([0.5, 0.25]));
+            assert_eq!(info.slice_thickness, Some(2.));
+            assert_eq!(info.spacing_between_slices, None);
+            assert_eq!(
+                info.image_orientation_patient,
+                Some([1., 0., 0., 0., 1., 0.])
+            );
+            assert_eq!(info.voi_lut_function, Some(VoiLutFunction::Sigmoid));
+            assert_eq!(info.rescale_slope, None);
+        }
+        assert_eq!(
+            frames[0].window,
+            Some(WindowLevel {
+                center: 40.,
+                width: 400.
+            })
+        );
+        assert_eq!(
+            frames[1].window,
+            Some(WindowLevel {
+                center: 300.,
+                width: 1500.
+            })
+        );
+
+        // out of range
+        assert!(FrameInfo::from_object(&dcm, 2).is_err());
+
+        // malformed value
+        dcm.put(DataElement::new(
+            tags::IMAGE_ORIENTATION_PATIENT,
+            VR::DS,
+            dicom_value!(F64, [1., 0., 0.]),
+        ));
+        assert!(FrameInfo::from_object(&dcm, 0).is_err());
+    }
 }
@@ -0,0 +1,128 @@
+//! A CLI tool for computing integrity digests of DICOM files,
+//! of their decoded pixel data and of their data set.
+use clap::Parser;
+use dicom_core::{DataDictionary, Tag};
+use dicom_dictionary_std::{StandardDataDictionary, tags};
+use dicom_object::open_file;
+use dicom_pixeldata::digest::{DigestOptions, dataset_digest, pixel_data_digest};
+use snafu::{Report, Whatever, whatever};
+use std::path::PathBuf;
+
+/// Compute SHA-256 digests of the decoded pixel data and of the data set of DICOM files
+///
+/// Each line of the output holds the pixel data digest,
+/// the data set digest and the file path.
+/// Files without pixel data have `-` for the pixel data digest.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The DICOM files to hash
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Only compute the digest of the decoded pixel data
+    #[arg(short = 'p', long = "pixel-data", conflicts_with = "dataset")]
+    pixel_data: bool,
+    /// Only compute the digest of the data set
+    #[arg(short = 'd', long = "dataset")]
+    dataset: bool,
+    /// Exclude an attribute from the data set digest
+    /// (by keyword or tag, can be repeated)
+    #[arg(short = 'e', long = "exclude", value_parser = parse_tag)]
+    exclude: Vec<Tag>,
+    /// Do not exclude the attributes which usually change on transcoding
+    /// (pixel data is always excluded)
+    #[arg(long = "no-default-excludes")]
+    no_default_excludes: bool,
+    /// Exclude private attributes from the data set digest
+    #[arg(long = "no-private")]
+    no_private: bool,
+}
+
+fn parse_tag(s: &str) -> Result<Tag, String> {
+    StandardDataDictionary
+        .parse_tag(s)
+        .ok_or_else(|| format!("unknown attribute `{s}`"))
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        files,
+        pixel_data,
+        dataset,
+        exclude,
+        no_default_excludes,
+        no_private,
+    } = App::parse();
+
+    let mut options = DigestOptions::new().with_private(!no_private);
+    if no_default_excludes {
+        options = options.with_exclude([tags::PIXEL_DATA]);
+    }
+    for tag in exclude {
+        options = options.with_excluded(tag);
+    }
+
+    let mut failed = 0;
+    for file in &files {
+        let obj = match open_file(file) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!(
+                    "Could not open {}: {}",
+                    file.display(),
+                    Report::from_error(e)
+                );
+                failed += 1;
+                continue;
+            }
+        };
+
+        let mut columns = Vec::new();
+        if !dataset {
+            if obj.get(tags::PIXEL_DATA).is_none() {
+                columns.push("-".to_string());
+            } else {
+                match pixel_data_digest(&obj) {
+                    Ok(digest) => columns.push(digest.to_string()),
+                    Err(e) => {
+                        eprintln!(
+                            "Could not decode the pixel data of {}: {}",
+                            file.display(),
+                            Report::from_error(e)
+                        );
+                        failed += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        if !pixel_data {
+            columns.push(dataset_digest(&obj, &options).to_string());
+        }
+        columns.push(file.display().to_string());
+        println!("{}", columns.join("  "));
+    }
+
+    if failed > 0 {
+        whatever!("Could not hash {failed} file(s)");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,92 @@
+//! A CLI tool for merging single-frame DICOM files
+//! into a multi-frame DICOM file.
+use clap::Parser;
+use dicom_object::open_file;
+use dicom_pixeldata::multiframe::{MultiFrameOptions, merge_frames};
+use snafu::{Report, ResultExt, Whatever};
+use std::path::PathBuf;
+use tracing::Level;
+
+/// Merge single-frame DICOM files into one multi-frame file
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The single-frame DICOM files to merge,
+    /// ordered by their instance number
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+    /// The output file
+    #[clap(short = 'o', long = "output")]
+    output: PathBuf,
+    /// The SOP class UID of the new file
+    /// (default is the multi-frame counterpart of the original SOP class)
+    #[clap(long = "sop-class")]
+    sop_class_uid: Option<String>,
+    /// The series instance UID of the new file (default is to generate one)
+    #[clap(long = "series-uid")]
+    series_instance_uid: Option<String>,
+
+    /// Verbose mode
+    #[clap(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        files,
+        output,
+        sop_class_uid,
+        series_instance_uid,
+        verbose,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", snafu::Report::from_error(e));
+    });
+
+    let mut options = MultiFrameOptions::new();
+    if let Some(uid) = sop_class_uid {
+        options = options.with_sop_class_uid(uid);
+    }
+    if let Some(uid) = series_instance_uid {
+        options = options.with_series_instance_uid(uid);
+    }
+
+    let instances = files
+        .iter()
+        .map(|file| {
+            open_file(file).with_whatever_context(|_| format!("Could not open {}", file.display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let obj = merge_frames(&instances, &options).whatever_context("Could not merge the frames")?;
+    obj.write_to_file(&output)
+        .with_whatever_context(|_| format!("Could not write {}", output.display()))?;
+    if verbose {
+        tracing::info!("{} files -> {}", files.len(), output.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,106 @@
+//! A CLI tool for splitting a multi-frame DICOM file
+//! into single-frame DICOM files.
+use clap::Parser;
+use dicom_object::open_file;
+use dicom_pixeldata::multiframe::{MultiFrameOptions, split_frames};
+use snafu::{Report, ResultExt, Whatever};
+use std::path::PathBuf;
+use tracing::Level;
+
+/// Split a multi-frame DICOM file into one file per frame
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The multi-frame DICOM file to split
+    file: PathBuf,
+    /// The directory in which to write the new files
+    /// (default is the directory of the original file),
+    /// named after the original file and the frame number
+    #[clap(short = 'o', long = "out-dir")]
+    out_dir: Option<PathBuf>,
+    /// The SOP class UID of the new files
+    /// (default is the single-frame counterpart of the original SOP class)
+    #[clap(long = "sop-class")]
+    sop_class_uid: Option<String>,
+    /// The series instance UID of the new files (default is to generate one)
+    #[clap(long = "series-uid")]
+    series_instance_uid: Option<String>,
+
+    /// Verbose mode
+    #[clap(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        eprintln!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        file,
+        out_dir,
+        sop_class_uid,
+        series_instance_uid,
+        verbose,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("{}", snafu::Report::from_error(e));
+    });
+
+    let mut options = MultiFrameOptions::new();
+    if let Some(uid) = sop_class_uid {
+        options = options.with_sop_class_uid(uid);
+    }
+    if let Some(uid) = series_instance_uid {
+        options = options.with_series_instance_uid(uid);
+    }
+
+    let obj =
+        open_file(&file).with_whatever_context(|_| format!("Could not open {}", file.display()))?;
+    let instances = split_frames(&obj, &options).whatever_context("Could not split the frames")?;
+
+    let out_dir = out_dir.unwrap_or_else(|| {
+        file.parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let digits = instances.len().to_string().len().max(4);
+    for (i, instance) in instances.iter().enumerate() {
+        let output = out_dir.join(format!("{stem}_{:0digits$}.dcm", i + 1));
+        instance
+            .write_to_file(&output)
+            .with_whatever_context(|_| format!("Could not write {}", output.display()))?;
+        tracing::debug!("{}", output.display());
+    }
+    if verbose {
+        tracing::info!(
+            "{} -> {} files in {}",
+            file.display(),
+            instances.len(),
+            out_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
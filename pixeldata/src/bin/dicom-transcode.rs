@@ -1,4 +1,4 @@
-//! A CLI tool for transcoding a DICOM file
+//! A CLI tool for transcoding DICOM files
 //! to another transfer syntax.
 use clap::Parser;
 use dicom_dictionary_std::uids;
@@ -8,7 +8,7 @@ use dicom_object::open_file;
 use dicom_pixeldata::Transcode;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use snafu::{OptionExt, Report, Whatever};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tracing::Level;
 
 /// Exit code for when an error emerged while reading the DICOM file.
@@ -20,14 +20,21 @@ const ERROR_WRITE: i32 = -4;
 /// Exit code for when an error emerged while writing the file.
 const ERROR_OTHER: i32 = -128;
 
-/// Transcode a DICOM file
+/// Transcode DICOM files
 #[derive(Debug, Parser)]
 #[command(version)]
 struct App {
-    file: PathBuf,
-    /// The output file (default is to change the extension to .new.dcm)
-    #[clap(short = 'o', long = "output")]
+    /// The DICOM file(s) to transcode
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+    /// The output file (default is to change the extension to .new.dcm),
+    /// only applicable when transcoding a single file
+    #[clap(short = 'o', long = "output", conflicts_with = "out_dir")]
     output: Option<PathBuf>,
+    /// The directory in which to write the transcoded files,
+    /// keeping their original file names
+    #[clap(long = "out-dir")]
+    out_dir: Option<PathBuf>,
 
     /// The encoding quality (from 0 to 100)
     #[clap(long = "quality")]
@@ -35,6 +42,9 @@ struct App {
     /// The encoding effort (from 0 to 100)
     #[clap(long = "effort")]
     effort: Option<u8>,
+    /// The target compression ratio, for lossy encodings which support it
+    #[clap(long = "ratio")]
+    ratio: Option<f32>,
 
     /// Target transfer syntax
     #[clap(flatten)]
@@ -80,6 +90,16 @@ struct TargetTransferSyntax {
     #[clap(long = "jpeg-ls")]
     jpeg_ls: bool,
 
+    /// Transcode to JPEG 2000 lossless
+    #[cfg(feature = "jpeg2k-encoder")]
+    #[clap(long = "jpeg2000-lossless")]
+    jpeg2000_lossless: bool,
+
+    /// Transcode to JPEG 2000
+    #[cfg(feature = "jpeg2k-encoder")]
+    #[clap(long = "jpeg2000")]
+    jpeg2000: bool,
+
     /// Transcode to JPEG XL lossless
     #[cfg(feature = "jpegxl")]
     #[clap(long = "jpeg-xl-lossless")]
@@ -110,6 +130,10 @@ impl TargetTransferSyntax {
                     jpeg_ls_lossless: false,
                 #[cfg(feature = "charls")]
                     jpeg_ls: false,
+                #[cfg(feature = "jpeg2k-encoder")]
+                    jpeg2000_lossless: false,
+                #[cfg(feature = "jpeg2k-encoder")]
+                    jpeg2000: false,
                 #[cfg(feature = "jpegxl")]
                     jpeg_xl_lossless: false,
                 #[cfg(feature = "jpegxl")]
@@ -152,6 +176,19 @@ impl TargetTransferSyntax {
             TargetTransferSyntax { jpeg_ls: true, .. } => TransferSyntaxRegistry
                 .get(uids::JPEGLS_NEAR_LOSSLESS)
                 .whatever_context("Missing specifier for JPEG-LS Near-Lossless"),
+            // JPEG 2000 lossless
+            #[cfg(feature = "jpeg2k-encoder")]
+            TargetTransferSyntax {
+                jpeg2000_lossless: true,
+                ..
+            } => TransferSyntaxRegistry
+                .get(uids::JPEG2000_LOSSLESS)
+                .whatever_context("Missing specifier for JPEG 2000 Lossless"),
+            // JPEG 2000
+            #[cfg(feature = "jpeg2k-encoder")]
+            TargetTransferSyntax { jpeg2000: true, .. } => TransferSyntaxRegistry
+                .get(uids::JPEG2000)
+                .whatever_context("Missing specifier for JPEG 2000"),
             // JPEG XL lossless
             #[cfg(feature = "jpegxl")]
             TargetTransferSyntax {
@@ -189,10 +226,12 @@ fn main() {
 
 fn run() -> Result<(), Whatever> {
     let App {
-        file,
+        files,
         output,
+        out_dir,
         quality,
         effort,
+        ratio,
         target_ts,
         retain_implementation,
         verbose,
@@ -207,16 +246,9 @@ fn run() -> Result<(), Whatever> {
         eprintln!("{}", snafu::Report::from_error(e));
     });
 
-    let output = output.unwrap_or_else(|| {
-        let mut file = file.clone();
-        file.set_extension("new.dcm");
-        file
-    });
-
-    let mut obj = open_file(file).unwrap_or_else(|e| {
-        eprintln!("{}", Report::from_error(e));
-        std::process::exit(ERROR_READ);
-    });
+    if output.is_some() && files.len() > 1 {
+        snafu::whatever!("Option --output can only be used with a single file, use --out-dir");
+    }
 
     // lookup transfer syntax
     let ts = target_ts.resolve()?;
@@ -224,11 +256,56 @@ fn run() -> Result<(), Whatever> {
     let mut options = EncodeOptions::default();
     options.quality = quality;
     options.effort = effort;
+    options.compression_ratio = ratio;
 
-    obj.transcode_with_options(ts, options).unwrap_or_else(|e| {
-        eprintln!("{}", Report::from_error(e));
-        std::process::exit(ERROR_TRANSCODE);
-    });
+    let mut status = 0;
+    for file in files {
+        let output = match (&output, &out_dir) {
+            (Some(output), _) => output.clone(),
+            (None, Some(out_dir)) => out_dir.join(file.file_name().unwrap_or_default()),
+            (None, None) => {
+                let mut file = file.clone();
+                file.set_extension("new.dcm");
+                file
+            }
+        };
+
+        if let Err(code) =
+            transcode_file(&file, &output, ts, options.clone(), retain_implementation)
+        {
+            if status == 0 {
+                status = code;
+            }
+        } else if verbose {
+            tracing::info!("{} -> {}", file.display(), output.display());
+        }
+    }
+
+    if status != 0 {
+        std::process::exit(status);
+    }
+
+    Ok(())
+}
+
+/// Transcode a single file,
+/// returning the exit code on failure.
+fn transcode_file(
+    file: &Path,
+    output: &Path,
+    ts: &TransferSyntax,
+    options: EncodeOptions,
+    retain_implementation: bool,
+) -> Result<(), i32> {
+    let mut obj = open_file(file).map_err(|e| {
+        eprintln!("{}: {}", file.display(), Report::from_error(e));
+        ERROR_READ
+    })?;
+
+    obj.transcode_with_options(ts, options).map_err(|e| {
+        eprintln!("{}: {}", file.display(), Report::from_error(e));
+        ERROR_TRANSCODE
+    })?;
 
     // override implementation class UID and version name
     if !retain_implementation {
@@ -240,12 +317,10 @@ fn run() -> Result<(), Whatever> {
     }
 
     // write to file
-    obj.write_to_file(output).unwrap_or_else(|e| {
-        eprintln!("{}", Report::from_error(e));
-        std::process::exit(ERROR_WRITE);
-    });
-
-    Ok(())
+    obj.write_to_file(output).map_err(|e| {
+        eprintln!("{}: {}", output.display(), Report::from_error(e));
+        ERROR_WRITE
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,358 @@
+//! Integrity digests of pixel data and data sets
+//!
+//! [`pixel_data_digest`] computes a SHA-256 digest
+//! of the decoded pixel data of an object,
+//! so that the same image content has the same digest
+//! regardless of the transfer syntax in which it is encoded.
+//! The digest covers the image dimensions,
+//! the number of frames, the samples per pixel,
+//! the bits allocated, the pixel representation
+//! and the decoded samples, with color samples interleaved.
+//! Note that lossy compression changes the samples,
+//! and some decoders also change the photometric interpretation
+//! (e.g. from _YBR_FULL_422_ to _RGB_),
+//! so only lossless transcoding is expected to retain the pixel data digest.
+//!
+//! [`dataset_digest`] computes a SHA-256 digest
+//! of a canonical encoding of the data set
+//! made of the attributes' tags, value representations and values,
+//! independent of the transfer syntax and character set,
+//! excluding the pixel data
+//! and the attributes listed in the [`DigestOptions`].
+//! By default,
+//! these are the attributes which change on transcoding or re-signing
+//! without changing the content of the instance
+//! (see [`DEFAULT_VOLATILE_TAGS`]).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::digest::{DigestOptions, dataset_digest, pixel_data_digest};
+//!
+//! let original = open_file("original.dcm")?;
+//! let transcoded = open_file("transcoded.dcm")?;
+//! assert_eq!(pixel_data_digest(&original)?, pixel_data_digest(&transcoded)?);
+//! let options = DigestOptions::new();
+//! assert_eq!(
+//!     dataset_digest(&original, &options),
+//!     dataset_digest(&transcoded, &options),
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::fmt;
+
+use dicom_core::{DataDictionary, DicomValue, PrimitiveValue, Tag, header::Header};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use sha2::{Digest as _, Sha256};
+
+use crate::{PixelDecoder, PlanarConfiguration, Result};
+
+/// Attributes excluded from data set digests by default.
+///
+/// These are the pixel data attributes
+/// and the number of frames,
+/// which are covered by [`pixel_data_digest`] instead,
+/// the attributes which may change when the pixel data is transcoded,
+/// and those related to the creation of the file or its digital signatures.
+pub const DEFAULT_VOLATILE_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::INSTANCE_CREATION_DATE,
+    tags::INSTANCE_CREATION_TIME,
+    tags::INSTANCE_CREATOR_UID,
+    tags::PHOTOMETRIC_INTERPRETATION,
+    tags::PLANAR_CONFIGURATION,
+    tags::NUMBER_OF_FRAMES,
+    tags::LOSSY_IMAGE_COMPRESSION,
+    tags::LOSSY_IMAGE_COMPRESSION_RATIO,
+    tags::LOSSY_IMAGE_COMPRESSION_METHOD,
+    tags::ENCRYPTED_ATTRIBUTES_SEQUENCE,
+    tags::ORIGINAL_ATTRIBUTES_SEQUENCE,
+    tags::MAC_PARAMETERS_SEQUENCE,
+    tags::EXTENDED_OFFSET_TABLE,
+    tags::EXTENDED_OFFSET_TABLE_LENGTHS,
+    tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH,
+    tags::FLOAT_PIXEL_DATA,
+    tags::DOUBLE_FLOAT_PIXEL_DATA,
+    tags::PIXEL_DATA,
+    tags::DIGITAL_SIGNATURES_SEQUENCE,
+    tags::DATA_SET_TRAILING_PADDING,
+];
+
+/// A SHA-256 digest,
+/// displayed as a lowercase hexadecimal string.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+    /// The bytes of the digest
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Options for computing data set digests
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestOptions {
+    exclude: Vec<Tag>,
+    include_private: bool,
+}
+
+impl Default for DigestOptions {
+    fn default() -> Self {
+        DigestOptions {
+            exclude: DEFAULT_VOLATILE_TAGS.to_vec(),
+            include_private: true,
+        }
+    }
+}
+
+impl DigestOptions {
+    /// Create options excluding the [default volatile attributes](DEFAULT_VOLATILE_TAGS)
+    /// and including private attributes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the attributes to exclude from the digest,
+    /// at any nesting level,
+    /// replacing the default ones.
+    pub fn with_exclude(mut self, exclude: impl IntoIterator<Item = Tag>) -> Self {
+        self.exclude = exclude.into_iter().collect();
+        self
+    }
+
+    /// Exclude an attribute from the digest in addition to the others.
+    pub fn with_excluded(mut self, tag: Tag) -> Self {
+        self.exclude.push(tag);
+        self
+    }
+
+    /// Set whether to include private attributes in the digest.
+    ///
+    /// They are included by default.
+    pub fn with_private(mut self, include_private: bool) -> Self {
+        self.include_private = include_private;
+        self
+    }
+
+    fn is_excluded(&self, tag: Tag) -> bool {
+        // group length attributes depend on the encoding
+        tag.element() == 0x0000
+            || (!self.include_private && tag.group() % 2 == 1)
+            || self.exclude.contains(&tag)
+    }
+}
+
+/// Compute the digest of the decoded pixel data of an object.
+///
+/// Fails if the object has no pixel data
+/// or the pixel data cannot be decoded.
+pub fn pixel_data_digest<O>(obj: &O) -> Result<Digest>
+where
+    O: PixelDecoder,
+{
+    let pixel = obj.decode_pixel_data()?;
+    let mut hasher = Sha256::new();
+    hasher.update(pixel.rows().to_le_bytes());
+    hasher.update(pixel.columns().to_le_bytes());
+    hasher.update(pixel.number_of_frames().to_le_bytes());
+    hasher.update(pixel.samples_per_pixel().to_le_bytes());
+    hasher.update(pixel.bits_allocated().to_le_bytes());
+    hasher.update([pixel.pixel_representation() as u8]);
+
+    let samples = pixel.samples_per_pixel() as usize;
+    if samples > 1 && pixel.planar_configuration() == PlanarConfiguration::PixelFirst {
+        // interleave the color planes of each frame
+        let sample_len = (pixel.bits_allocated() as usize).div_ceil(8);
+        let pixels = pixel.rows() as usize * pixel.columns() as usize;
+        let frame_len = pixels * samples * sample_len;
+        let mut interleaved = Vec::with_capacity(frame_len);
+        for frame in pixel.data().chunks(frame_len) {
+            interleaved.clear();
+            for i in 0..pixels {
+                for plane in 0..samples {
+                    let at = (plane * pixels + i) * sample_len;
+                    interleaved
+                        .extend_from_slice(frame.get(at..at + sample_len).unwrap_or_default());
+                }
+            }
+            hasher.update(&interleaved);
+        }
+    } else {
+        hasher.update(pixel.data());
+    }
+    Ok(Digest(hasher.finalize().into()))
+}
+
+/// Compute the digest of the canonical encoding of a data set,
+/// excluding the pixel data and the attributes given in the options.
+///
+/// The file meta group is never part of the digest.
+pub fn dataset_digest<D>(obj: &InMemDicomObject<D>, options: &DigestOptions) -> Digest
+where
+    D: DataDictionary + Clone,
+{
+    let mut hasher = Sha256::new();
+    hash_dataset(&mut hasher, obj, options);
+    Digest(hasher.finalize().into())
+}
+
+fn hash_dataset<D>(hasher: &mut Sha256, obj: &InMemDicomObject<D>, options: &DigestOptions)
+where
+    D: DataDictionary + Clone,
+{
+    for elem in obj {
+        let tag = elem.tag();
+        if tag.group() == 0x0002 || options.is_excluded(tag) {
+            continue;
+        }
+        hasher.update(tag.group().to_le_bytes());
+        hasher.update(tag.element().to_le_bytes());
+        hasher.update(elem.vr().to_bytes());
+        match elem.value() {
+            DicomValue::Primitive(value) => {
+                let bytes = value_bytes(value);
+                hasher.update((bytes.len() as u64).to_le_bytes());
+                hasher.update(&bytes);
+            }
+            DicomValue::Sequence(seq) => {
+                hasher.update((seq.items().len() as u64).to_le_bytes());
+                for item in seq.items() {
+                    hasher.update(b"item");
+                    hash_dataset(hasher, item, options);
+                    hasher.update(b"end");
+                }
+            }
+            DicomValue::PixelSequence(seq) => {
+                // only reached if the pixel data is not excluded
+                for fragment in seq.fragments() {
+                    hasher.update((fragment.len() as u64).to_le_bytes());
+                    hasher.update(fragment);
+                }
+            }
+        }
+    }
+}
+
+/// The canonical bytes of a primitive value:
+/// text in UTF-8 without padding, with values separated by backslashes,
+/// and binary values in little endian.
+fn value_bytes(value: &PrimitiveValue) -> Vec<u8> {
+    fn le<const N: usize, T: Copy>(values: &[T], to_le: fn(T) -> [u8; N]) -> Vec<u8> {
+        values.iter().flat_map(|v| to_le(*v)).collect()
+    }
+    match value {
+        PrimitiveValue::Empty => Vec::new(),
+        PrimitiveValue::U8(values) => values.to_vec(),
+        PrimitiveValue::I16(values) => le(values, i16::to_le_bytes),
+        PrimitiveValue::U16(values) => le(values, u16::to_le_bytes),
+        PrimitiveValue::I32(values) => le(values, i32::to_le_bytes),
+        PrimitiveValue::U32(values) => le(values, u32::to_le_bytes),
+        PrimitiveValue::I64(values) => le(values, i64::to_le_bytes),
+        PrimitiveValue::U64(values) => le(values, u64::to_le_bytes),
+        PrimitiveValue::F32(values) => le(values, f32::to_le_bytes),
+        PrimitiveValue::F64(values) => le(values, f64::to_le_bytes),
+        PrimitiveValue::Tags(values) => values
+            .iter()
+            .flat_map(|t| {
+                let [g0, g1] = t.group().to_le_bytes();
+                let [e0, e1] = t.element().to_le_bytes();
+                [g0, g1, e0, e1]
+            })
+            .collect(),
+        _ => value
+            .to_multi_str()
+            .iter()
+            .map(|v| v.trim_matches(['\0', ' ']))
+            .collect::<Vec<_>>()
+            .join("\\")
+            .into_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigestOptions, dataset_digest, pixel_data_digest};
+    use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+
+    fn image(planar: bool, pixels: Vec<u8>, instance_creation_time: &str) -> DefaultDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::INSTANCE_CREATION_TIME, VR::TM, instance_creation_time),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John "),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, dicom_value!(U16, [3])),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "RGB"),
+            DataElement::new(
+                tags::PLANAR_CONFIGURATION,
+                VR::US,
+                dicom_value!(U16, [planar as u16]),
+            ),
+            DataElement::new(tags::ROWS, VR::US, dicom_value!(U16, [1])),
+            DataElement::new(tags::COLUMNS, VR::US, dicom_value!(U16, [2])),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, dicom_value!(U16, [8])),
+            DataElement::new(tags::BITS_STORED, VR::US, dicom_value!(U16, [8])),
+            DataElement::new(tags::HIGH_BIT, VR::US, dicom_value!(U16, [7])),
+            DataElement::new(tags::PIXEL_REPRESENTATION, VR::US, dicom_value!(U16, [0])),
+            DataElement::new(tags::PIXEL_DATA, VR::OB, PrimitiveValue::from(pixels)),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.1"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn digests_ignore_layout_and_volatile_attributes() {
+        let interleaved = image(false, vec![1, 2, 3, 4, 5, 6], "101010");
+        let planar = image(true, vec![1, 4, 2, 5, 3, 6], "121212");
+        let different = image(false, vec![1, 2, 3, 4, 5, 7], "101010");
+
+        let digest = pixel_data_digest(&interleaved).unwrap();
+        assert_eq!(digest, pixel_data_digest(&planar).unwrap());
+        assert_ne!(digest, pixel_data_digest(&different).unwrap());
+        assert_eq!(digest.to_string().len(), 64);
+
+        let options = DigestOptions::new();
+        let digest = dataset_digest(&interleaved, &options);
+        assert_eq!(digest, dataset_digest(&planar, &options));
+        // pixel data is not part of the data set digest
+        assert_eq!(digest, dataset_digest(&different, &options));
+
+        let mut renamed = interleaved.clone();
+        renamed.put(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^Jane"));
+        assert_ne!(digest, dataset_digest(&renamed, &options));
+        let options = options.with_excluded(tags::PATIENT_NAME);
+        assert_eq!(
+            dataset_digest(&interleaved, &options),
+            dataset_digest(&renamed, &options)
+        );
+
+        // the creation time is no longer excluded
+        let options = DigestOptions::new().with_exclude([tags::PIXEL_DATA]);
+        assert_ne!(
+            dataset_digest(&interleaved, &options),
+            dataset_digest(&planar, &options)
+        );
+    }
+}
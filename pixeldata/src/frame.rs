@@ -0,0 +1,200 @@
+//! Raw frame buffer access.
+//!
+//! This module provides borrowed views over a single frame
+//! of decoded pixel data,
+//! together with the information required to interpret the buffer.
+//! This allows consumers such as GPU renderers
+//! to upload the samples directly,
+//! without going through an intermediate image representation.
+//!
+//! See [`DecodedPixelData::frame_bytes`](crate::DecodedPixelData::frame_bytes).
+use std::borrow::Cow;
+
+use crate::{PixelRepresentation, PlanarConfiguration};
+
+/// The memory layout of a frame of pixel data samples.
+///
+/// All strides are expressed in bytes.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub struct FrameLayout {
+    /// the number of columns (width of the frame)
+    pub columns: u32,
+    /// the number of rows (height of the frame)
+    pub rows: u32,
+    /// the number of samples per pixel
+    pub samples_per_pixel: u16,
+    /// the number of bytes of each sample (1 or 2)
+    pub bytes_per_sample: u16,
+    /// the number of bits stored in each sample
+    pub bits_stored: u16,
+    /// whether samples are signed or unsigned
+    pub pixel_representation: PixelRepresentation,
+    /// whether samples are interleaved by pixel or by plane
+    pub planar_configuration: PlanarConfiguration,
+    /// the distance between two consecutive pixels of the same sample
+    pub pixel_stride: usize,
+    /// the distance between two consecutive rows of the same sample
+    pub row_stride: usize,
+    /// the distance between two consecutive sample planes,
+    /// or `None` if the samples are interleaved by pixel
+    pub plane_stride: Option<usize>,
+}
+
+impl FrameLayout {
+    /// Calculate the layout of a frame with the given properties.
+    pub(crate) fn new(
+        columns: u32,
+        rows: u32,
+        samples_per_pixel: u16,
+        bytes_per_sample: u16,
+        bits_stored: u16,
+        pixel_representation: PixelRepresentation,
+        planar_configuration: PlanarConfiguration,
+    ) -> Self {
+        let sample_len = bytes_per_sample as usize;
+        let (pixel_stride, row_stride, plane_stride) =
+            if samples_per_pixel > 1 && planar_configuration == PlanarConfiguration::PixelFirst {
+                let row_stride = columns as usize * sample_len;
+                (sample_len, row_stride, Some(row_stride * rows as usize))
+            } else {
+                let pixel_stride = sample_len * samples_per_pixel as usize;
+                (pixel_stride, pixel_stride * columns as usize, None)
+            };
+        FrameLayout {
+            columns,
+            rows,
+            samples_per_pixel,
+            bytes_per_sample,
+            bits_stored,
+            pixel_representation,
+            planar_configuration,
+            pixel_stride,
+            row_stride,
+            plane_stride,
+        }
+    }
+
+    /// The total size of the frame in bytes.
+    pub fn len(&self) -> usize {
+        self.columns as usize
+            * self.rows as usize
+            * self.samples_per_pixel as usize
+            * self.bytes_per_sample as usize
+    }
+
+    /// Whether the frame has no samples.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The samples of a frame, typed by their allocated size.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameSamples<'a> {
+    /// 8-bit samples
+    U8(&'a [u8]),
+    /// 16-bit samples in native byte order,
+    /// borrowed whenever the underlying buffer is suitably aligned
+    /// and the platform is little endian
+    U16(Cow<'a, [u16]>),
+}
+
+/// A frame of decoded pixel data,
+/// accessible without copies as a byte slice or as typed samples,
+/// together with its memory layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameBytes<'a> {
+    bytes: &'a [u8],
+    samples: FrameSamples<'a>,
+    layout: FrameLayout,
+}
+
+impl<'a> FrameBytes<'a> {
+    pub(crate) fn new(bytes: &'a [u8], layout: FrameLayout) -> Self {
+        let samples = if layout.bytes_per_sample == 2 {
+            FrameSamples::U16(bytes_as_u16(bytes))
+        } else {
+            FrameSamples::U8(bytes)
+        };
+        FrameBytes {
+            bytes,
+            samples,
+            layout,
+        }
+    }
+
+    /// Retrieve the frame's samples as a slice of little endian bytes.
+    #[inline]
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Retrieve the frame's samples, typed by the bits allocated.
+    #[inline]
+    pub fn samples(&self) -> &FrameSamples<'a> {
+        &self.samples
+    }
+
+    /// Retrieve the memory layout of the frame.
+    #[inline]
+    pub fn layout(&self) -> &FrameLayout {
+        &self.layout
+    }
+
+    /// Convert into the frame's typed samples.
+    #[inline]
+    pub fn into_samples(self) -> FrameSamples<'a> {
+        self.samples
+    }
+}
+
+/// reinterpret little endian bytes as 16-bit samples,
+/// copying only if the bytes cannot be borrowed
+fn bytes_as_u16(bytes: &[u8]) -> Cow<'_, [u16]> {
+    #[cfg(target_endian = "little")]
+    if let Ok(samples) = safe_transmute::transmute_many_pedantic::<u16>(bytes) {
+        return Cow::Borrowed(samples);
+    }
+    Cow::Owned(
+        bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_strides() {
+        let layout = FrameLayout::new(
+            4,
+            3,
+            3,
+            1,
+            8,
+            PixelRepresentation::Unsigned,
+            PlanarConfiguration::Standard,
+        );
+        assert_eq!(layout.pixel_stride, 3);
+        assert_eq!(layout.row_stride, 12);
+        assert_eq!(layout.plane_stride, None);
+        assert_eq!(layout.len(), 36);
+
+        let layout = FrameLayout::new(
+            4,
+            3,
+            3,
+            2,
+            12,
+            PixelRepresentation::Unsigned,
+            PlanarConfiguration::PixelFirst,
+        );
+        assert_eq!(layout.pixel_stride, 2);
+        assert_eq!(layout.row_stride, 8);
+        assert_eq!(layout.plane_stride, Some(24));
+        assert_eq!(layout.len(), 72);
+    }
+}
@@ -25,6 +25,10 @@ where
     fn decode_pixel_data(&self) -> Result<DecodedPixelData<'_>> {
         use super::attribute::*;
 
+        if let Some(decoded) = crate::decode_float_pixel_data(self, None)? {
+            return Ok(decoded);
+        }
+
         let pixel_data = pixel_data(self)?;
 
         let cols = cols(self)?;
@@ -71,6 +75,11 @@ where
                     .collect()
             });
         let voi_lut_sequence = voi_lut_sequence(self);
+        let palette_color_lut = match &photometric_interpretation {
+            PhotometricInterpretation::PaletteColor => palette_color_lut(self)?,
+            _ if has_supplemental_palette(self) => palette_color_lut(self).ok().flatten(),
+            _ => None,
+        };
 
         ensure!(
             rescale_intercept.len() == rescale_slope.len(),
@@ -174,10 +183,13 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            float: false,
             rescale,
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping: crate::real_world::real_world_value_mappings(self),
             enforce_frame_fg_vm_match: false,
         })
     }
@@ -185,6 +197,10 @@ where
     fn decode_pixel_data_frame(&self, frame: u32) -> Result<DecodedPixelData<'_>> {
         use super::attribute::*;
 
+        if let Some(decoded) = crate::decode_float_pixel_data(self, Some(frame))? {
+            return Ok(decoded);
+        }
+
         let pixel_data = pixel_data(self)?;
 
         let cols = cols(self)?;
@@ -236,6 +252,11 @@ where
                     .collect()
             });
         let voi_lut_sequence = voi_lut_sequence(self);
+        let palette_color_lut = match &photometric_interpretation {
+            PhotometricInterpretation::PaletteColor => palette_color_lut(self)?,
+            _ if has_supplemental_palette(self) => palette_color_lut(self).ok().flatten(),
+            _ => None,
+        };
 
         let decoded_pixel_data = match pixel_data.value() {
             DicomValue::PixelSequence(v) => {
@@ -334,6 +355,13 @@ where
         } else {
             None
         };
+        let real_world_value_mapping = crate::real_world::real_world_value_mappings(self);
+        let real_world_value_mapping = real_world_value_mapping
+            .get(frame as usize)
+            .or(real_world_value_mapping.first())
+            .cloned()
+            .into_iter()
+            .collect();
         let rescale = zip(&rescale_intercept, &rescale_slope)
             .map(|(intercept, slope)| Rescale {
                 intercept: *intercept,
@@ -353,10 +381,13 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            float: false,
             rescale: rescale,
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
             enforce_frame_fg_vm_match: false,
         })
     }
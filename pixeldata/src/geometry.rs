@@ -0,0 +1,475 @@
+//! Image geometry in the patient coordinate system
+//!
+//! An [`ImagePlane`] combines the _Image Position (Patient)_,
+//! _Image Orientation (Patient)_ and _Pixel Spacing_ of a frame
+//! into an affine transform between pixel indices and patient coordinates
+//! (in millimeters),
+//! as described in [PS3.3 C.7.6.2.1.1](https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.7.6.2.html#sect_C.7.6.2.1.1).
+//!
+//! [`series_geometry`] sorts the planes of a series
+//! along the slice normal,
+//! checks that they share the same orientation and pixel spacing
+//! and that they are evenly spaced along the normal,
+//! and describes the stack as a [`VolumeGeometry`],
+//! relating voxel indices to patient coordinates.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::geometry::{ImagePlane, series_geometry};
+//!
+//! let planes = ["slice1.dcm", "slice2.dcm", "slice3.dcm"]
+//!     .into_iter()
+//!     .map(|path| Ok(ImagePlane::from_object(&open_file(path)?, 0)?))
+//!     .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+//! // patient coordinates of the pixel at column 10, row 20
+//! println!("{:?}", planes[0].to_patient([10., 20.]));
+//!
+//! let series = series_geometry(&planes)?;
+//! println!("slices in order: {:?}", series.order);
+//! println!("voxel (1, 2, 3) at {:?}", series.geometry.to_patient([1., 2., 3.]));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_core::DataDictionary;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+use crate::FrameInfo;
+
+/// The tolerance for comparing direction cosines and pixel spacings
+pub(crate) const GEOMETRY_TOLERANCE: f64 = 1e-4;
+
+/// The tolerance for the deviation of each slice gap
+/// from the mean slice spacing, relative to the latter
+pub(crate) const SLICE_SPACING_TOLERANCE: f64 = 0.01;
+
+/// An error occurred while resolving or checking image geometry.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Could not read the geometry attributes
+    ReadGeometry {
+        source: crate::attribute::GetAttributeError,
+    },
+
+    /// Missing {attribute}
+    MissingAttribute { attribute: &'static str },
+
+    /// Image orientation is not a pair of orthogonal unit vectors
+    InvalidOrientation,
+
+    /// Pixel spacing must be positive
+    InvalidPixelSpacing,
+
+    /// No image planes
+    NoPlanes,
+
+    /// Plane #{index} has a different orientation
+    InconsistentOrientation { index: usize },
+
+    /// Plane #{index} has a different pixel spacing
+    InconsistentPixelSpacing { index: usize },
+
+    /// Plane #{index} is at the same position as another plane
+    DuplicatePosition { index: usize },
+
+    /// Plane #{index} is not aligned with the others along the slice normal
+    Misaligned { index: usize },
+
+    /// Slices are not evenly spaced (gap of {gap} against a mean of {mean})
+    InconsistentSliceSpacing { gap: f64, mean: f64 },
+}
+
+/// Alias for the result of geometry operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The position and orientation of an image frame in the patient coordinate system.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ImagePlane {
+    /// the position of the center of the first pixel,
+    /// from the _Image Position (Patient)_
+    pub position: [f64; 3],
+    /// the direction cosines of increasing column index
+    /// (along each row)
+    pub row_direction: [f64; 3],
+    /// the direction cosines of increasing row index
+    /// (down each column)
+    pub column_direction: [f64; 3],
+    /// the _Pixel Spacing_,
+    /// as the spacing between rows
+    /// followed by the spacing between columns
+    pub pixel_spacing: [f64; 2],
+}
+
+impl ImagePlane {
+    /// Create an image plane from the values of
+    /// _Image Position (Patient)_, _Image Orientation (Patient)_ and _Pixel Spacing_.
+    ///
+    /// Fails if the direction cosines are not orthogonal unit vectors
+    /// or the pixel spacing is not positive.
+    pub fn new(position: [f64; 3], orientation: [f64; 6], pixel_spacing: [f64; 2]) -> Result<Self> {
+        let row_direction = [orientation[0], orientation[1], orientation[2]];
+        let column_direction = [orientation[3], orientation[4], orientation[5]];
+        // a looser tolerance, since direction cosines are often
+        // written with only a few decimal places
+        ensure!(
+            (norm(&row_direction) - 1.).abs() <= 1e-3
+                && (norm(&column_direction) - 1.).abs() <= 1e-3
+                && dot(&row_direction, &column_direction).abs() <= 1e-3,
+            InvalidOrientationSnafu
+        );
+        ensure!(
+            pixel_spacing.iter().all(|s| *s > 0.),
+            InvalidPixelSpacingSnafu
+        );
+        Ok(ImagePlane {
+            position,
+            row_direction,
+            column_direction,
+            pixel_spacing,
+        })
+    }
+
+    /// Resolve the image plane from the geometry of a frame.
+    pub fn from_frame_info(info: &FrameInfo) -> Result<Self> {
+        let position = info.image_position_patient.context(MissingAttributeSnafu {
+            attribute: "ImagePositionPatient",
+        })?;
+        let orientation = info
+            .image_orientation_patient
+            .context(MissingAttributeSnafu {
+                attribute: "ImageOrientationPatient",
+            })?;
+        let pixel_spacing = info.pixel_spacing.context(MissingAttributeSnafu {
+            attribute: "PixelSpacing",
+        })?;
+        Self::new(position, orientation, pixel_spacing)
+    }
+
+    /// Resolve the image plane of the given frame (starting at 0) of an object,
+    /// from the functional groups in the case of enhanced multi-frame objects.
+    pub fn from_object<D>(obj: &FileDicomObject<InMemDicomObject<D>>, frame: u32) -> Result<Self>
+    where
+        D: DataDictionary + Clone,
+    {
+        let info = FrameInfo::from_object(obj, frame).context(ReadGeometrySnafu)?;
+        Self::from_frame_info(&info)
+    }
+
+    /// Resolve the image planes of all frames of an object.
+    pub fn all_from_object<D>(obj: &FileDicomObject<InMemDicomObject<D>>) -> Result<Vec<Self>>
+    where
+        D: DataDictionary + Clone,
+    {
+        FrameInfo::all_from_object(obj)
+            .context(ReadGeometrySnafu)?
+            .iter()
+            .map(Self::from_frame_info)
+            .collect()
+    }
+
+    /// The unit normal of the image plane,
+    /// as the cross product of the row and column directions
+    pub fn normal(&self) -> [f64; 3] {
+        cross(&self.row_direction, &self.column_direction)
+    }
+
+    /// The position of the plane along its normal,
+    /// by which slices of a series can be sorted
+    pub fn distance(&self) -> f64 {
+        dot(&self.position, &self.normal())
+    }
+
+    /// The affine transform from pixel indices to patient coordinates,
+    /// as a 4x4 matrix in row-major order.
+    ///
+    /// It maps the homogeneous vector `[column, row, 0, 1]`
+    /// to `[x, y, z, 1]` in patient coordinates.
+    pub fn affine(&self) -> [[f64; 4]; 4] {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let (x, y, s) = (self.row_direction, self.column_direction, self.position);
+        [
+            [x[0] * column_spacing, y[0] * row_spacing, 0., s[0]],
+            [x[1] * column_spacing, y[1] * row_spacing, 0., s[1]],
+            [x[2] * column_spacing, y[2] * row_spacing, 0., s[2]],
+            [0., 0., 0., 1.],
+        ]
+    }
+
+    /// Convert a pixel index, given as `[column, row]`,
+    /// to patient coordinates.
+    pub fn to_patient(&self, [column, row]: [f64; 2]) -> [f64; 3] {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let (x, y, s) = (self.row_direction, self.column_direction, self.position);
+        [0, 1, 2].map(|i| s[i] + x[i] * column_spacing * column + y[i] * row_spacing * row)
+    }
+
+    /// Project a point in patient coordinates onto the image plane,
+    /// returning the fractional pixel index as `[column, row]`.
+    ///
+    /// The distance of the point from the plane,
+    /// along the normal,
+    /// is given by [`distance_from`](Self::distance_from).
+    pub fn to_pixel(&self, point: [f64; 3]) -> [f64; 2] {
+        let [row_spacing, column_spacing] = self.pixel_spacing;
+        let d = sub(&point, &self.position);
+        [
+            dot(&d, &self.row_direction) / column_spacing,
+            dot(&d, &self.column_direction) / row_spacing,
+        ]
+    }
+
+    /// The signed distance of a point in patient coordinates from the plane,
+    /// along the normal
+    pub fn distance_from(&self, point: [f64; 3]) -> f64 {
+        dot(&sub(&point, &self.position), &self.normal())
+    }
+
+    /// Whether both planes have the same orientation, within tolerance
+    fn is_parallel_to(&self, other: &ImagePlane) -> bool {
+        approx_eq(&self.row_direction, &other.row_direction)
+            && approx_eq(&self.column_direction, &other.column_direction)
+    }
+}
+
+/// The geometry of a volume in the patient coordinate system.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VolumeGeometry {
+    /// the position of the center of the first voxel,
+    /// from the _Image Position (Patient)_ of the first slice
+    pub origin: [f64; 3],
+    /// the distance between voxel centers
+    /// along columns, rows and slices respectively
+    pub spacing: [f64; 3],
+    /// the direction cosines of increasing column index,
+    /// increasing row index, and increasing slice index
+    pub direction: [[f64; 3]; 3],
+}
+
+impl VolumeGeometry {
+    /// The affine transform from voxel indices to patient coordinates,
+    /// as a 4x4 matrix in row-major order.
+    ///
+    /// It maps the homogeneous vector `[column, row, slice, 1]`
+    /// to `[x, y, z, 1]` in patient coordinates.
+    pub fn affine(&self) -> [[f64; 4]; 4] {
+        let mut affine = [[0.; 4]; 4];
+        for (i, row) in affine.iter_mut().take(3).enumerate() {
+            for (axis, value) in row.iter_mut().take(3).enumerate() {
+                *value = self.direction[axis][i] * self.spacing[axis];
+            }
+            row[3] = self.origin[i];
+        }
+        affine[3][3] = 1.;
+        affine
+    }
+
+    /// Convert a voxel index, given as `[column, row, slice]`,
+    /// to patient coordinates.
+    pub fn to_patient(&self, index: [f64; 3]) -> [f64; 3] {
+        [0, 1, 2].map(|i| {
+            self.origin[i]
+                + (0..3)
+                    .map(|axis| self.direction[axis][i] * self.spacing[axis] * index[axis])
+                    .sum::<f64>()
+        })
+    }
+
+    /// Convert a point in patient coordinates
+    /// to a fractional voxel index, given as `[column, row, slice]`.
+    ///
+    /// This assumes that the directions are orthonormal,
+    /// as is the case for the geometry resolved by [`series_geometry`].
+    pub fn to_voxel(&self, point: [f64; 3]) -> [f64; 3] {
+        let d = sub(&point, &self.origin);
+        [0, 1, 2].map(|axis| dot(&d, &self.direction[axis]) / self.spacing[axis])
+    }
+}
+
+/// The geometry of a series of image planes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeriesGeometry {
+    /// the indices of the planes given,
+    /// sorted by their position along the slice normal
+    pub order: Vec<usize>,
+    /// the geometry of the volume formed by the sorted planes
+    pub geometry: VolumeGeometry,
+}
+
+/// Sort the planes of a series along the slice normal
+/// and check that they form a regular volume.
+///
+/// An error is returned if the planes do not share
+/// the same orientation and pixel spacing,
+/// if two planes are at the same position,
+/// if the positions are not along the slice normal
+/// (as in images acquired with gantry tilt),
+/// or if the slices are not evenly spaced.
+/// A single plane forms a volume with a slice spacing of 1.
+pub fn series_geometry(planes: &[ImagePlane]) -> Result<SeriesGeometry> {
+    let reference = planes.first().context(NoPlanesSnafu)?;
+    for (index, plane) in planes.iter().enumerate() {
+        ensure!(
+            plane.is_parallel_to(reference),
+            InconsistentOrientationSnafu { index }
+        );
+        ensure!(
+            approx_eq(&plane.pixel_spacing, &reference.pixel_spacing),
+            InconsistentPixelSpacingSnafu { index }
+        );
+    }
+
+    let mut order: Vec<usize> = (0..planes.len()).collect();
+    order.sort_by(|a, b| planes[*a].distance().total_cmp(&planes[*b].distance()));
+    let first = &planes[order[0]];
+
+    let slice_spacing = if planes.len() > 1 {
+        let gaps: Vec<f64> = order
+            .windows(2)
+            .map(|w| planes[w[1]].distance() - planes[w[0]].distance())
+            .collect();
+        for (gap, w) in gaps.iter().zip(order.windows(2)) {
+            ensure!(
+                *gap > GEOMETRY_TOLERANCE,
+                DuplicatePositionSnafu { index: w[1] }
+            );
+        }
+        let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        for &gap in &gaps {
+            ensure!(
+                (gap - mean).abs() <= mean * SLICE_SPACING_TOLERANCE,
+                InconsistentSliceSpacingSnafu { gap, mean }
+            );
+        }
+        // the origin of each plane must be on the line through the first one
+        for &index in &order {
+            let [column, row] = first.to_pixel(planes[index].position);
+            let offset = [
+                column * first.pixel_spacing[1],
+                row * first.pixel_spacing[0],
+            ];
+            ensure!(
+                offset
+                    .iter()
+                    .all(|o| o.abs() <= mean * SLICE_SPACING_TOLERANCE),
+                MisalignedSnafu { index }
+            );
+        }
+        mean
+    } else {
+        1.
+    };
+
+    Ok(SeriesGeometry {
+        geometry: VolumeGeometry {
+            origin: first.position,
+            spacing: [
+                first.pixel_spacing[1],
+                first.pixel_spacing[0],
+                slice_spacing,
+            ],
+            direction: [first.row_direction, first.column_direction, first.normal()],
+        },
+        order,
+    })
+}
+
+pub(crate) fn approx_eq(a: &[f64], b: &[f64]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(a, b)| (a - b).abs() <= GEOMETRY_TOLERANCE)
+}
+
+pub(crate) fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: &[f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImagePlane, series_geometry};
+
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    /// a sagittal plane at the given x
+    fn sagittal(x: f64) -> ImagePlane {
+        ImagePlane::new([x, -100., 50.], [0., 1., 0., 0., 0., -1.], [0.5, 0.8]).unwrap()
+    }
+
+    #[test]
+    fn plane_transforms() {
+        let plane = sagittal(10.);
+        assert_close(&plane.normal(), &[-1., 0., 0.]);
+        assert_close(&[plane.distance()], &[-10.]);
+
+        // column 10 is 8 mm along +y, row 4 is 2 mm along -z
+        let point = plane.to_patient([10., 4.]);
+        assert_close(&point, &[10., -92., 48.]);
+        assert_close(&plane.to_pixel(point), &[10., 4.]);
+        assert_close(&[plane.distance_from([12., 0., 0.])], &[-2.]);
+
+        let affine = plane.affine();
+        let index = [10., 4., 0., 1.];
+        let mapped: Vec<f64> = affine
+            .iter()
+            .map(|row| row.iter().zip(&index).map(|(a, b)| a * b).sum())
+            .collect();
+        assert_close(&mapped, &[10., -92., 48., 1.]);
+
+        assert!(ImagePlane::new([0.; 3], [1., 0., 0., 1., 0., 0.], [1., 1.]).is_err());
+        assert!(ImagePlane::new([0.; 3], [1., 0., 0., 0., 1., 0.], [0., 1.]).is_err());
+    }
+
+    #[test]
+    fn sort_and_check_series() {
+        let planes = [sagittal(4.), sagittal(0.), sagittal(2.)];
+        let series = series_geometry(&planes).unwrap();
+        // sorted along the normal, which points to -x
+        assert_eq!(series.order, [0, 2, 1]);
+        let geometry = series.geometry;
+        assert_close(&geometry.origin, &[4., -100., 50.]);
+        assert_close(&geometry.spacing, &[0.8, 0.5, 2.]);
+        let point = geometry.to_patient([10., 4., 2.]);
+        assert_close(&point, &[0., -92., 48.]);
+        assert_close(&geometry.to_voxel(point), &[10., 4., 2.]);
+        let affine = geometry.affine();
+        assert_close(&affine[0], &[0., 0., -2., 4.]);
+        assert_close(&affine[3], &[0., 0., 0., 1.]);
+
+        // uneven spacing
+        assert!(series_geometry(&[sagittal(0.), sagittal(1.), sagittal(5.)]).is_err());
+        // duplicate position
+        assert!(series_geometry(&[sagittal(0.), sagittal(0.)]).is_err());
+        // shifted within the plane
+        let mut shifted = sagittal(2.);
+        shifted.position[1] += 5.;
+        assert!(series_geometry(&[sagittal(0.), shifted]).is_err());
+        // different orientation
+        let axial = ImagePlane::new([0.; 3], [1., 0., 0., 0., 1., 0.], [0.5, 0.8]).unwrap();
+        assert!(series_geometry(&[sagittal(0.), axial]).is_err());
+        assert!(series_geometry(&[]).is_err());
+    }
+}
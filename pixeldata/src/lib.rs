@@ -96,6 +96,29 @@
 //! # fn main() {}
 //! ```
 //!
+//! For rescaled values in floating point,
+//! or a view of the stored values without copying,
+//! see [`to_ndarray_volume`](DecodedPixelData::to_ndarray_volume)
+//! and [`to_ndarray_view`](DecodedPixelData::to_ndarray_view).
+//! To stack the images of a whole series into a volume,
+//! see the `volume` module,
+//! and for the geometry of images in the patient coordinate system,
+//! see the [`geometry`] module.
+//! The floating point samples of Parametric Map objects
+//! (_Float Pixel Data_ and _Double Float Pixel Data_)
+//! are decoded as well,
+//! and can be mapped into physical quantities
+//! as described in the [`real_world`] module.
+//! To wrap an ordinary image into a new secondary capture object,
+//! see the `secondary_capture` module.
+//! To render grayscale images to display values
+//! through the modality, VOI and presentation LUTs,
+//! see the [`rendering`] module,
+//! and to render an image through a grayscale softcopy presentation state,
+//! see the `presentation_state` module.
+//! To read the waveforms of ECG and other waveform objects,
+//! see the `waveforms` module.
+//!
 //! In order to parameterize the conversion,
 //! pass a conversion options value to the `_with_options` variant methods.
 //!
@@ -121,29 +144,26 @@
 //! including the default behavior for each method.
 //!
 
-use attribute::VoiLut;
+use attribute::{PaletteColorLut, VoiLut};
 use byteorder::{ByteOrder, NativeEndian};
-#[cfg(not(feature = "gdcm"))]
 use dicom_core::{DataDictionary, DicomValue};
 #[cfg(not(feature = "gdcm"))]
 use dicom_encoding::Codec;
 use dicom_encoding::adapters::DecodeError;
 #[cfg(not(feature = "gdcm"))]
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
-#[cfg(not(feature = "gdcm"))]
 use dicom_object::{FileDicomObject, InMemDicomObject};
 #[cfg(not(feature = "gdcm"))]
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 #[cfg(feature = "image")]
 use image::{DynamicImage, ImageBuffer, Luma, Rgb};
 #[cfg(feature = "ndarray")]
-use ndarray::{Array, Ix3, Ix4};
+use ndarray::{Array, Array4, ArrayView4, Ix3, Ix4, ShapeBuilder};
 use num_traits::NumCast;
 #[cfg(feature = "rayon")]
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 #[cfg(all(feature = "rayon", feature = "image"))]
 use rayon::slice::ParallelSliceMut;
-#[cfg(any(not(feature = "gdcm"), feature = "image"))]
 use snafu::OptionExt;
 #[cfg(not(feature = "gdcm"))]
 use snafu::ensure;
@@ -158,18 +178,39 @@ pub use image;
 pub use ndarray;
 
 mod attribute;
+mod frame;
 mod lut;
 mod transcode;
 
+pub mod digest;
 pub mod encapsulation;
+pub mod geometry;
+pub mod multiframe;
+pub mod overlays;
+pub mod packing;
+pub mod presentation_state;
+pub mod real_world;
+pub mod rendering;
+#[cfg(feature = "image")]
+pub mod secondary_capture;
 pub(crate) mod transform;
+pub mod video;
+#[cfg(feature = "ndarray")]
+pub mod volume;
+pub mod waveforms;
 
 // re-exports
 pub use attribute::{
-    AttributeName, PhotometricInterpretation, PixelRepresentation, PlanarConfiguration,
+    AttributeName, FrameInfo, PhotometricInterpretation, PixelRepresentation, PlanarConfiguration,
 };
+pub use frame::{FrameBytes, FrameLayout, FrameSamples};
 pub use lut::{CreateLutError, Lut};
-pub use transcode::{Error as TranscodeError, Result as TranscodeResult, Transcode};
+pub use real_world::{RealWorldValueMapping, RealWorldValueTransform, RealWorldValues};
+pub use rendering::{PresentationLut, PresentationLutTable, RenderingPipeline};
+pub use transcode::{
+    EncodedPixelData, Error as TranscodeError, PixelEncoder, Result as TranscodeResult,
+    SetPixelData, Transcode,
+};
 pub use transform::{Rescale, VoiLutFunction, WindowLevel, WindowLevelTransform};
 
 #[cfg(feature = "gdcm")]
@@ -227,6 +268,10 @@ enum InnerError {
     #[snafu(display("Invalid buffer when constructing ImageBuffer"))]
     InvalidImageBuffer { backtrace: Backtrace },
 
+    #[cfg(feature = "image")]
+    #[snafu(display("Missing palette color lookup tables"))]
+    MissingPaletteColorLut { backtrace: Backtrace },
+
     #[cfg(feature = "ndarray")]
     #[snafu(display("Invalid shape for ndarray"))]
     InvalidShape {
@@ -243,6 +288,10 @@ enum InnerError {
     #[snafu(display("Invalid data type for ndarray element"))]
     InvalidDataType { backtrace: Backtrace },
 
+    #[cfg(feature = "ndarray")]
+    #[snafu(display("Pixel data samples are not aligned for a zero-copy view"))]
+    UnalignedPixelData { backtrace: Backtrace },
+
     #[snafu(display("Could not decode pixel data"))]
     DecodePixelData { source: DecodeError },
 
@@ -251,6 +300,19 @@ enum InnerError {
         frame_number: u32,
         backtrace: Backtrace,
     },
+
+    #[snafu(display("No real world value mapping for frame #{frame_number}"))]
+    MissingRealWorldValueMapping {
+        frame_number: u32,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("No real world value mapping in `{units}` for frame #{frame_number}"))]
+    MissingRealWorldValueUnits {
+        units: String,
+        frame_number: u32,
+        backtrace: Backtrace,
+    },
     #[snafu(display(
         "Value multiplicity of VOI LUT Function must match the number of frames. Expected `{nr_frames:?}`, found `{vm:?}`"
     ))]
@@ -394,6 +456,15 @@ pub enum ModalityLutOption {
     /// and apply the VOI LUT transformations as normal,
     /// use the `Override` variant instead.
     None,
+    /// Map the pixel data values into real world values
+    /// through the frame's real world value mapping
+    /// (see the [`real_world`] module),
+    /// instead of rescaling them.
+    ///
+    /// Conversion fails if the frame has no real world value mapping.
+    /// When converting to an image,
+    /// only linear mappings are supported.
+    RealWorldValues,
 }
 
 /// VOI LUT function specifier.
@@ -486,6 +557,8 @@ pub enum PhotometricInterpretationOption {
 /// (see [`PixelDecoder`]).
 /// The decoded pixel data samples will be stored as raw bytes in native form
 /// without any LUT transformations applied.
+/// Bit-packed samples are unpacked into whole bytes,
+/// see the [`packing`] module.
 /// Whether to apply such transformations
 /// can be specified through one of the various `to_*` methods,
 /// such as [`to_dynamic_image`](Self::to_dynamic_image)
@@ -514,6 +587,9 @@ pub struct DecodedPixelData<'a> {
     high_bit: u16,
     /// the pixel representation: 0 for unsigned, 1 for signed
     pixel_representation: PixelRepresentation,
+    /// whether the samples are floating point numbers,
+    /// from _Float Pixel Data_ or _Double Float Pixel Data_
+    float: bool,
     /// Multiframe dicom objects can have rescale information, voi LUT and
     /// window level information once in the shared functional group sequence,
     /// or multiple times in the per-frame functional group sequence. This is a
@@ -527,6 +603,12 @@ pub struct DecodedPixelData<'a> {
     window: Option<Vec<WindowLevel>>,
     /// the explicit VOI LUTs
     voi_lut_sequence: Option<Vec<VoiLut>>,
+    /// the palette color lookup tables,
+    /// for _PALETTE COLOR_ images or as a supplemental palette
+    palette_color_lut: Option<PaletteColorLut>,
+    /// the real world value mappings,
+    /// for all frames or for each frame
+    real_world_value_mapping: Vec<Vec<RealWorldValueMapping>>,
 
     /// Enforce frame functional groups VMs match `number_of_frames`
     enforce_frame_fg_vm_match: bool,
@@ -572,6 +654,36 @@ impl DecodedPixelData<'_> {
         Ok(&self.data[frame_start..frame_end])
     }
 
+    /// Retrieve a frame's pixel data samples without copying,
+    /// along with the layout of the samples in memory.
+    ///
+    /// The samples are available both as bytes and as
+    /// 8-bit or 16-bit samples according to _Bits Allocated_,
+    /// so that they can be handed over directly
+    /// to a renderer (such as a GPU texture upload)
+    /// without an intermediate image conversion.
+    /// Only pixel data with 8 or 16 bits allocated is supported.
+    pub fn frame_bytes(&self, frame: u32) -> Result<FrameBytes<'_>> {
+        if self.bits_allocated != 8 && self.bits_allocated != 16 {
+            return UnsupportedOtherSnafu {
+                name: "BitsAllocated",
+                value: self.bits_allocated.to_string(),
+            }
+            .fail()?;
+        }
+        let data = self.frame_data(frame)?;
+        let layout = FrameLayout::new(
+            self.cols,
+            self.rows,
+            self.samples_per_pixel,
+            self.bits_allocated / 8,
+            self.bits_stored,
+            self.pixel_representation,
+            self.planar_configuration,
+        );
+        Ok(FrameBytes::new(data, layout))
+    }
+
     /// Retrieve a copy of a frame's raw pixel data samples
     /// as unsigned 16-bit integers.
     ///
@@ -630,6 +742,9 @@ impl DecodedPixelData<'_> {
     }
 
     /// Retrieve the number of bits allocated for each sample.
+    ///
+    /// For bit-packed pixel data, this is the number of bits
+    /// of the unpacked samples (8 for 1 bit allocated, 16 for 12 bits).
     #[inline]
     pub fn bits_allocated(&self) -> u16 {
         self.bits_allocated
@@ -647,6 +762,19 @@ impl DecodedPixelData<'_> {
         self.pixel_representation
     }
 
+    /// Check whether the samples are floating point numbers,
+    /// as decoded from _Float Pixel Data_ (32 bits allocated)
+    /// or _Double Float Pixel Data_ (64 bits allocated).
+    ///
+    /// Floating point samples can be retrieved
+    /// with [`to_vec`](Self::to_vec) and the other conversion methods
+    /// into arrays of numbers,
+    /// but not converted to an image.
+    #[inline]
+    pub fn is_float(&self) -> bool {
+        self.float
+    }
+
     /// Retrieve object's rescale parameters.
     #[inline]
     pub fn rescale(&self) -> Result<&[Rescale]> {
@@ -741,6 +869,51 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Retrieve the palette color lookup tables defined by the object, if any.
+    ///
+    /// These are available for images with the _PALETTE COLOR_
+    /// photometric interpretation,
+    /// and for monochrome images with a supplemental palette.
+    #[inline]
+    pub fn palette_color_lut(&self) -> Option<&PaletteColorLut> {
+        self.palette_color_lut.as_ref()
+    }
+
+    /// Retrieve the real world value mappings of the given frame,
+    /// which may be empty.
+    pub fn real_world_value_mappings(&self, frame: u32) -> &[RealWorldValueMapping] {
+        match self.real_world_value_mapping.as_slice() {
+            [mappings] => mappings,
+            mappings => mappings
+                .get(frame as usize)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Retrieve the first real world value mapping of the given frame, if any.
+    pub fn real_world_value_mapping(&self, frame: u32) -> Option<&RealWorldValueMapping> {
+        self.real_world_value_mappings(frame).first()
+    }
+
+    /// The real world value mapping of a frame as a rescale function,
+    /// for the conversions which only support linear mappings.
+    fn real_world_rescale(&self, frame: u32) -> Result<Rescale> {
+        let mapping =
+            self.real_world_value_mapping(frame)
+                .context(MissingRealWorldValueMappingSnafu {
+                    frame_number: frame,
+                })?;
+        match &mapping.transform {
+            RealWorldValueTransform::Linear(rescale) => Ok(*rescale),
+            RealWorldValueTransform::Lut(_) => UnsupportedOtherSnafu {
+                name: "RealWorldValueMapping",
+                value: "LUT",
+            }
+            .fail()?,
+        }
+    }
+
     /// Retrieve the VOI LUT sequence defined by the object, if any
     pub fn voi_lut_sequence(&self) -> Result<Option<&[VoiLut]>> {
         if let Some(inner) = &self.voi_lut_sequence {
@@ -818,6 +991,12 @@ impl DecodedPixelData<'_> {
         options: &ConvertOptions,
     ) -> Result<DynamicImage> {
         match self.samples_per_pixel {
+            1 if self.photometric_interpretation == PhotometricInterpretation::PaletteColor => {
+                self.build_palette_color_image(frame, options.bit_depth)
+            }
+            1 if self.palette_color_lut.is_some() => {
+                self.build_supplemental_palette_image(frame, options)
+            }
             1 => self.build_monochrome_image(frame, options),
             3 => {
                 // Modality LUT and VOI LUT
@@ -860,7 +1039,7 @@ impl DecodedPixelData<'_> {
                             PhotometricInterpretation::Rgb => pixel_array,
                             PhotometricInterpretation::YbrFull
                             | PhotometricInterpretation::YbrFull422 => {
-                                convert_colorspace_u16(&mut pixel_array);
+                                convert_colorspace_u16(&mut pixel_array, self.bits_stored);
                                 pixel_array
                             }
                             pi => UnsupportedPhotometricInterpretationSnafu { pi: pi.clone() }
@@ -876,6 +1055,51 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Produce a small 8-bit preview image of the first frame,
+    /// fitting within a square of `max_edge` pixels
+    /// while preserving the aspect ratio.
+    /// Images already small enough are not enlarged.
+    ///
+    /// The default transformations of
+    /// [`to_dynamic_image`](Self::to_dynamic_image) are applied,
+    /// so that the preview uses the first window described in the object,
+    /// or a min-max normalization of the pixel values otherwise.
+    ///
+    /// # Example
+    ///
+    /// Embed a thumbnail as an icon image in the object:
+    ///
+    /// ```no_run
+    /// # use dicom_object::open_file;
+    /// use dicom_pixeldata::PixelDecoder as _;
+    ///
+    /// let mut obj = open_file("image.dcm")?;
+    /// let thumbnail = obj.decode_pixel_data()?.thumbnail(64)?;
+    /// let samples_per_pixel = thumbnail.color().channel_count() as u16;
+    /// obj.put_icon_image(
+    ///     thumbnail.height() as u16,
+    ///     thumbnail.width() as u16,
+    ///     samples_per_pixel,
+    ///     thumbnail.into_bytes(),
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "image")]
+    pub fn thumbnail(&self, max_edge: u32) -> Result<DynamicImage> {
+        let options = ConvertOptions::new().force_8bit();
+        let image = self.to_dynamic_image_with_options(0, &options)?;
+        let image = if image.width() > max_edge || image.height() > max_edge {
+            image.thumbnail(max_edge, max_edge)
+        } else {
+            image
+        };
+        Ok(match image {
+            DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => image,
+            image if image.color().has_color() => DynamicImage::ImageRgb8(image.to_rgb8()),
+            image => DynamicImage::ImageLuma8(image.to_luma8()),
+        })
+    }
+
     #[cfg(feature = "image")]
     fn mono_image_with_narrow(
         &self,
@@ -1017,6 +1241,109 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Retrieve the stored values of a single-sample frame,
+    /// one per pixel.
+    #[cfg(feature = "image")]
+    fn frame_stored_values(&self, frame: u32) -> Result<Vec<i32>> {
+        let signed = self.pixel_representation == PixelRepresentation::Signed;
+        let mask = ((1_u32 << self.bits_stored.min(16)) - 1) as u16;
+        match self.bits_allocated {
+            8 => Ok(self
+                .frame_data(frame)?
+                .iter()
+                .map(|&v| if signed { v as i8 as i32 } else { v as i32 })
+                .collect()),
+            16 => Ok(self
+                .frame_data_ow(frame)?
+                .into_iter()
+                .map(|v| {
+                    if signed {
+                        v as i16 as i32
+                    } else {
+                        (v & mask) as i32
+                    }
+                })
+                .collect()),
+            _ => InvalidBitsAllocatedSnafu.fail()?,
+        }
+    }
+
+    /// Build an RGB image from a _PALETTE COLOR_ frame
+    /// by mapping each stored value through the palette color lookup tables.
+    #[cfg(feature = "image")]
+    fn build_palette_color_image(
+        &self,
+        frame: u32,
+        bit_depth: BitDepthOption,
+    ) -> Result<DynamicImage> {
+        let lut = self
+            .palette_color_lut
+            .as_ref()
+            .context(MissingPaletteColorLutSnafu)?;
+        let values = self.frame_stored_values(frame)?;
+
+        if lut.bits == 8 {
+            let pixels: Vec<u8> = values
+                .into_iter()
+                .flat_map(|v| lut.get(v).map(|c| c as u8))
+                .collect();
+            self.rgb_image_with_extend(pixels, bit_depth)
+        } else {
+            let pixels: Vec<u16> = values.into_iter().flat_map(|v| lut.get(v)).collect();
+            self.rgb_image_with_narrow(pixels, bit_depth)
+        }
+    }
+
+    /// Build an RGB image from a monochrome frame with a supplemental palette.
+    ///
+    /// Stored values within the range of the palette color lookup tables
+    /// are shown in color,
+    /// whereas all other values go through the usual grayscale pipeline.
+    #[cfg(feature = "image")]
+    fn build_supplemental_palette_image(
+        &self,
+        frame: u32,
+        options: &ConvertOptions,
+    ) -> Result<DynamicImage> {
+        let lut = self
+            .palette_color_lut
+            .as_ref()
+            .context(MissingPaletteColorLutSnafu)?;
+        let values = self.frame_stored_values(frame)?;
+        let gray = self.build_monochrome_image(frame, options)?;
+
+        match gray {
+            DynamicImage::ImageLuma8(gray) => {
+                let mut image = DynamicImage::ImageLuma8(gray).to_rgb8();
+                for (pixel, v) in image.pixels_mut().zip(values) {
+                    if lut.contains(v) {
+                        let color = lut.get(v);
+                        pixel.0 = if lut.bits == 8 {
+                            color.map(|c| c as u8)
+                        } else {
+                            color.map(|c| (c >> 8) as u8)
+                        };
+                    }
+                }
+                Ok(DynamicImage::ImageRgb8(image))
+            }
+            gray => {
+                let mut image = gray.to_rgb16();
+                for (pixel, v) in image.pixels_mut().zip(values) {
+                    if lut.contains(v) {
+                        let color = lut.get(v);
+                        pixel.0 = if lut.bits == 8 {
+                            color.map(|c| (c << 8) | c)
+                        } else {
+                            color
+                        };
+                    }
+                }
+                Ok(DynamicImage::ImageRgb16(image))
+            }
+        }
+    }
+
     #[cfg(feature = "image")]
     fn build_monochrome_image(&self, frame: u32, options: &ConvertOptions) -> Result<DynamicImage> {
         use transform::VoiLutTransform;
@@ -1038,11 +1365,15 @@ impl DecodedPixelData<'_> {
                         self.mono_image_with_extend(data.iter().copied(), *bit_depth)?
                     }
                     // other
-                    ModalityLutOption::Default | ModalityLutOption::Override(..) => {
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(..)
+                    | ModalityLutOption::RealWorldValues => {
                         let rescale = {
                             let default = self.rescale()?;
                             if let ModalityLutOption::Override(rescale) = modality_lut {
                                 *rescale
+                            } else if *modality_lut == ModalityLutOption::RealWorldValues {
+                                self.real_world_rescale(frame)?
                             } else if default.len() > 1 {
                                 default[frame as usize]
                             } else {
@@ -1201,11 +1532,15 @@ impl DecodedPixelData<'_> {
                         self.mono_image_with_narrow(buffer, *bit_depth)?
                     }
 
-                    ModalityLutOption::Default | ModalityLutOption::Override(..) => {
+                    ModalityLutOption::Default
+                    | ModalityLutOption::Override(..)
+                    | ModalityLutOption::RealWorldValues => {
                         let rescale = {
                             let default = self.rescale()?;
                             if let ModalityLutOption::Override(rescale) = modality_lut {
                                 *rescale
+                            } else if *modality_lut == ModalityLutOption::RealWorldValues {
+                                self.real_world_rescale(frame)?
                             } else if default.len() > 1 {
                                 self.rescale[frame as usize]
                             } else {
@@ -1485,6 +1820,43 @@ impl DecodedPixelData<'_> {
         self.convert_pixel_slice(self.frame_data(frame)?, frame, options)
     }
 
+    /// Map the stored values of a frame into real world values
+    /// through the first real world value mapping of the frame.
+    ///
+    /// The values are provided in standard order and layout:
+    /// pixels first, then columns, then rows.
+    ///
+    /// Fails if the frame has no real world value mapping.
+    pub fn to_real_world_values(&self, frame: u32) -> Result<RealWorldValues> {
+        let mapping =
+            self.real_world_value_mapping(frame)
+                .context(MissingRealWorldValueMappingSnafu {
+                    frame_number: frame,
+                })?;
+        Ok(mapping.map_values(self.sample_values(self.frame_data(frame)?)?))
+    }
+
+    /// Map the stored values of a frame into real world values
+    /// through the real world value mapping of the frame in the given units,
+    /// such as [`ucum::SUV_BODY_WEIGHT`](dicom_object::code::ucum::SUV_BODY_WEIGHT).
+    ///
+    /// Fails if the frame has no real world value mapping in those units.
+    pub fn to_real_world_values_in(
+        &self,
+        frame: u32,
+        units: &dicom_object::code::Code,
+    ) -> Result<RealWorldValues> {
+        let mapping = self
+            .real_world_value_mappings(frame)
+            .iter()
+            .find(|mapping| mapping.is_in_units(units))
+            .context(MissingRealWorldValueUnitsSnafu {
+                units: units.value.to_string(),
+                frame_number: frame,
+            })?;
+        Ok(mapping.map_values(self.sample_values(self.frame_data(frame)?)?))
+    }
+
     fn convert_pixel_slice<T>(
         &self,
         data: &[u8],
@@ -1501,6 +1873,10 @@ impl DecodedPixelData<'_> {
             photometric_interpretation: _,
         } = options;
 
+        if self.float || *modality_lut == ModalityLutOption::RealWorldValues {
+            return self.convert_real_values(data, frame, modality_lut);
+        }
+
         if self.samples_per_pixel > 1 && self.planar_configuration != PlanarConfiguration::Standard
         {
             // TODO #129
@@ -1763,6 +2139,68 @@ impl DecodedPixelData<'_> {
         }
     }
 
+    /// Convert floating point samples,
+    /// or any samples through their real world value mapping.
+    ///
+    /// No VOI LUT function is applied in this case.
+    fn convert_real_values<T>(
+        &self,
+        data: &[u8],
+        frame: u32,
+        modality_lut: &ModalityLutOption,
+    ) -> Result<Vec<T>>
+    where
+        T: NumCast + Send + Sync + Copy + 'static,
+    {
+        let map: Box<dyn Fn(f64) -> f64 + Send + Sync + '_> = match modality_lut {
+            ModalityLutOption::RealWorldValues => {
+                let mapping = self.real_world_value_mapping(frame).context(
+                    MissingRealWorldValueMappingSnafu {
+                        frame_number: frame,
+                    },
+                )?;
+                Box::new(|v| mapping.apply(v))
+            }
+            ModalityLutOption::Override(rescale) => Box::new(|v| rescale.apply(v)),
+            // floating point samples are not rescaled
+            _ => Box::new(|v| v),
+        };
+
+        let samples = self.sample_values(data)?;
+
+        #[cfg(feature = "rayon")]
+        let converted: Result<Vec<T>, _> = samples
+            .into_par_iter()
+            .map(|v| T::from(map(v)).ok_or(snafu::NoneError))
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let converted: Result<Vec<T>, _> = samples
+            .into_iter()
+            .map(|v| T::from(map(v)).ok_or(snafu::NoneError))
+            .collect();
+        converted.context(InvalidDataTypeSnafu).map_err(Error::from)
+    }
+
+    /// Read the stored sample values of the given pixel data,
+    /// whichever their type.
+    fn sample_values(&self, data: &[u8]) -> Result<Vec<f64>> {
+        let signed = self.pixel_representation == PixelRepresentation::Signed;
+        let read = |size: usize, read: fn(&[u8]) -> f64| -> Vec<f64> {
+            data.chunks_exact(size).map(read).collect()
+        };
+        Ok(match (self.bits_allocated, self.float, signed) {
+            (32, true, _) => read(4, |b| NativeEndian::read_f32(b) as f64),
+            (64, true, _) => read(8, NativeEndian::read_f64),
+            (8, false, false) => data.iter().map(|&v| v as f64).collect(),
+            (8, false, true) => data.iter().map(|&v| v as i8 as f64).collect(),
+            (16, false, false) => read(2, |b| NativeEndian::read_u16(b) as f64),
+            (16, false, true) => read(2, |b| NativeEndian::read_i16(b) as f64),
+            (32, false, false) => read(4, |b| NativeEndian::read_u32(b) as f64),
+            (32, false, true) => read(4, |b| NativeEndian::read_i32(b) as f64),
+            _ => InvalidBitsAllocatedSnafu.fail()?,
+        })
+    }
+
     /// Convert all of the decoded pixel data
     /// into a four dimensional array of a given type `T`.
     ///
@@ -1920,6 +2358,201 @@ impl DecodedPixelData<'_> {
             .map_err(Error::from)
     }
 
+    /// Convert all of the decoded pixel data
+    /// into a volume of a given type `T`,
+    /// with the shape `[N, R, C, S]`
+    /// (frames × rows × columns × samples per pixel).
+    ///
+    /// Unlike [`to_ndarray`](Self::to_ndarray),
+    /// the Modality LUT function is computed in double precision
+    /// for each sample before converting to `T`,
+    /// so that rescaled values are retained with full precision
+    /// when `T` is `f32` or `f64`.
+    /// Pixel data with a planar configuration of 1
+    /// is also laid out in the same shape.
+    ///
+    /// The rescale slope and intercept described in the object
+    /// are applied to monochrome pixel data.
+    /// To change this behavior,
+    /// see [`to_ndarray_volume_with_options`](Self::to_ndarray_volume_with_options).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use dicom_pixeldata::{DecodedPixelData, ndarray::Array4};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data: DecodedPixelData = unimplemented!();
+    /// // Hounsfield units of every voxel in a CT volume
+    /// let volume: Array4<f32> = data.to_ndarray_volume()?;
+    /// let [frames, rows, cols, samples] = volume.shape() else { unreachable!() };
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray_volume<T>(&self) -> Result<Array4<T>>
+    where
+        T: NumCast + Copy + 'static,
+    {
+        self.to_ndarray_volume_with_options(&Default::default())
+    }
+
+    /// Convert all of the decoded pixel data
+    /// into a volume of a given type `T`,
+    /// with the shape `[N, R, C, S]`
+    /// (frames × rows × columns × samples per pixel).
+    ///
+    /// Only the `modality_lut` option is considered:
+    /// use [`ModalityLutOption::None`] to obtain the stored values,
+    /// or [`ModalityLutOption::Override`]
+    /// to apply a different rescale slope and intercept.
+    /// VOI LUT functions are not applied.
+    ///
+    /// Fails if any of the resulting values
+    /// cannot be represented by `T`.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray_volume_with_options<T>(&self, options: &ConvertOptions) -> Result<Array4<T>>
+    where
+        T: NumCast + Copy + 'static,
+    {
+        let samples_per_pixel = self.samples_per_pixel as usize;
+        let pixels = self.rows as usize * self.cols as usize;
+        let signed = self.pixel_representation == PixelRepresentation::Signed;
+
+        let mut out =
+            Vec::with_capacity(self.number_of_frames as usize * pixels * samples_per_pixel);
+        for frame in 0..self.number_of_frames {
+            let rescale = match &options.modality_lut {
+                ModalityLutOption::Default if self.photometric_interpretation.is_monochrome() => {
+                    let rescale = self.rescale()?;
+                    Some(*rescale.get(frame as usize).unwrap_or(&rescale[0]))
+                }
+                ModalityLutOption::Override(rescale)
+                    if self.photometric_interpretation.is_monochrome() =>
+                {
+                    Some(*rescale)
+                }
+                _ => None,
+            };
+            let mapping = match &options.modality_lut {
+                ModalityLutOption::RealWorldValues => {
+                    Some(self.real_world_value_mapping(frame).context(
+                        MissingRealWorldValueMappingSnafu {
+                            frame_number: frame,
+                        },
+                    )?)
+                }
+                _ => None,
+            };
+
+            let data = self.frame_data(frame)?;
+            let sample: Box<dyn Fn(usize) -> f64> = match (self.bits_allocated, signed) {
+                (32, _) if self.float => {
+                    Box::new(|i| NativeEndian::read_f32(&data[i * 4..]) as f64)
+                }
+                (64, _) if self.float => Box::new(|i| NativeEndian::read_f64(&data[i * 8..])),
+                (8, false) => Box::new(|i| data[i] as f64),
+                (8, true) => Box::new(|i| data[i] as i8 as f64),
+                (16, false) => Box::new(|i| NativeEndian::read_u16(&data[i * 2..]) as f64),
+                (16, true) => Box::new(|i| NativeEndian::read_i16(&data[i * 2..]) as f64),
+                (32, false) => Box::new(|i| NativeEndian::read_u32(&data[i * 4..]) as f64),
+                (32, true) => Box::new(|i| NativeEndian::read_i32(&data[i * 4..]) as f64),
+                _ => InvalidBitsAllocatedSnafu.fail()?,
+            };
+
+            for i in 0..pixels * samples_per_pixel {
+                // index of the sample in the frame data
+                let j = match self.planar_configuration {
+                    PlanarConfiguration::Standard => i,
+                    PlanarConfiguration::PixelFirst => {
+                        (i % samples_per_pixel) * pixels + i / samples_per_pixel
+                    }
+                };
+                let value = match (mapping, &rescale) {
+                    (Some(mapping), _) => mapping.apply(sample(j)),
+                    (None, Some(rescale)) => rescale.apply(sample(j)),
+                    (None, None) => sample(j),
+                };
+                out.push(T::from(value).context(InvalidDataTypeSnafu)?);
+            }
+        }
+
+        Array::from_shape_vec(
+            [
+                self.number_of_frames as usize,
+                self.rows as usize,
+                self.cols as usize,
+                samples_per_pixel,
+            ],
+            out,
+        )
+        .context(InvalidShapeSnafu)
+        .map_err(Error::from)
+    }
+
+    /// Obtain a view of all of the decoded pixel data
+    /// as a volume of stored sample values,
+    /// with the shape `[N, R, C, S]`
+    /// (frames × rows × columns × samples per pixel),
+    /// without copying.
+    ///
+    /// `T` must match the stored sample type,
+    /// as given by the bits allocated and pixel representation
+    /// (for instance, `u16` for unsigned 16-bit samples
+    /// or `i16` for signed 16-bit samples).
+    /// No Modality LUT or VOI LUT function is applied.
+    /// Pixel data with a planar configuration of 1
+    /// are viewed in the same shape through the array strides.
+    ///
+    /// Fails if `T` does not match the stored sample type,
+    /// or if the decoded samples are not aligned in memory for `T`.
+    /// In the latter case,
+    /// [`to_ndarray_volume`](Self::to_ndarray_volume) can be used instead.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray_view<T>(&self) -> Result<ArrayView4<'_, T>>
+    where
+        T: safe_transmute::TriviallyTransmutable + num_traits::PrimInt,
+    {
+        let signed = T::min_value() < T::zero();
+        ensure!(
+            std::mem::size_of::<T>() * 8 == self.bits_allocated as usize
+                && signed == (self.pixel_representation == PixelRepresentation::Signed),
+            InvalidDataTypeSnafu
+        );
+
+        let (frames, rows, cols, samples) = (
+            self.number_of_frames as usize,
+            self.rows as usize,
+            self.cols as usize,
+            self.samples_per_pixel as usize,
+        );
+        let len = frames * rows * cols * samples * std::mem::size_of::<T>();
+        // with insufficient data, the shape check below fails
+        let data = self.data.get(..len).unwrap_or(&self.data);
+        let data: &[T] = safe_transmute::transmute_many_pedantic(data)
+            .ok()
+            .context(UnalignedPixelDataSnafu)?;
+
+        match self.planar_configuration {
+            PlanarConfiguration::Standard => {
+                ArrayView4::from_shape((frames, rows, cols, samples), data)
+            }
+            PlanarConfiguration::PixelFirst => {
+                // one plane per sample in each frame
+                ArrayView4::from_shape(
+                    (frames, rows, cols, samples).strides((
+                        rows * cols * samples,
+                        cols,
+                        1,
+                        rows * cols,
+                    )),
+                    data,
+                )
+            }
+        }
+        .context(InvalidShapeSnafu)
+        .map_err(Error::from)
+    }
+
     /// Obtain a version of the decoded pixel data
     /// that is independent from the original DICOM object,
     /// by making copies of any necessary data.
@@ -1958,11 +2591,187 @@ impl DecodedPixelData<'_> {
             voi_lut_function: self.voi_lut_function.clone(),
             window: self.window.clone(),
             voi_lut_sequence: self.voi_lut_sequence.clone(),
+            palette_color_lut: self.palette_color_lut.clone(),
+            real_world_value_mapping: self.real_world_value_mapping.clone(),
+            float: self.float,
             enforce_frame_fg_vm_match: self.enforce_frame_fg_vm_match,
         }
     }
 }
 
+/// Decode the samples of _Float Pixel Data_ or _Double Float Pixel Data_,
+/// of all frames or of a single frame,
+/// or return `None` if the object has no floating point pixel data.
+///
+/// Floating point samples are always native and monochrome,
+/// and they have no bits stored, rescale, nor VOI LUT attributes.
+fn decode_float_pixel_data<D>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+    frame: Option<u32>,
+) -> Result<Option<DecodedPixelData<'_>>>
+where
+    D: DataDictionary + Clone,
+{
+    use dicom_dictionary_std::tags;
+
+    if obj.get(tags::PIXEL_DATA).is_some() {
+        return Ok(None);
+    }
+    let Some((pixel_data, bits_allocated)) = obj
+        .get(tags::FLOAT_PIXEL_DATA)
+        .map(|e| (e, 32))
+        .or_else(|| obj.get(tags::DOUBLE_FLOAT_PIXEL_DATA).map(|e| (e, 64)))
+    else {
+        return Ok(None);
+    };
+    let DicomValue::Primitive(value) = pixel_data.value() else {
+        return InvalidPixelDataSnafu.fail()?;
+    };
+
+    let cols = attribute::cols(obj)?;
+    let rows = attribute::rows(obj)?;
+    let samples_per_pixel = attribute::samples_per_pixel(obj)?;
+    let photometric_interpretation = attribute::photometric_interpretation(obj)?;
+    let real_world_value_mapping = real_world::real_world_value_mappings(obj);
+
+    let data = value.to_bytes();
+    let (data, number_of_frames, real_world_value_mapping) = match frame {
+        Some(frame) => {
+            let frame_size = rows as usize
+                * cols as usize
+                * samples_per_pixel as usize
+                * (bits_allocated / 8) as usize;
+            let frame_offset = frame_size * frame as usize;
+            let data = data
+                .get(frame_offset..frame_offset + frame_size)
+                .context(FrameOutOfRangeSnafu {
+                    frame_number: frame,
+                })?
+                .to_vec();
+            let mapping = real_world_value_mapping
+                .get(frame as usize)
+                .or(real_world_value_mapping.first())
+                .cloned()
+                .into_iter()
+                .collect();
+            (data, 1, mapping)
+        }
+        None => (
+            data.into_owned(),
+            attribute::number_of_frames(obj)?,
+            real_world_value_mapping,
+        ),
+    };
+
+    Ok(Some(DecodedPixelData {
+        data: Cow::from(data),
+        cols: cols.into(),
+        rows: rows.into(),
+        number_of_frames,
+        photometric_interpretation,
+        samples_per_pixel,
+        planar_configuration: PlanarConfiguration::Standard,
+        bits_allocated,
+        bits_stored: bits_allocated,
+        high_bit: bits_allocated - 1,
+        pixel_representation: PixelRepresentation::Signed,
+        float: true,
+        rescale: Vec::new(),
+        voi_lut_function: None,
+        window: None,
+        voi_lut_sequence: None,
+        palette_color_lut: None,
+        real_world_value_mapping,
+        enforce_frame_fg_vm_match: false,
+    }))
+}
+
+/// Determine the photometric interpretation of pixel data
+/// with three samples per pixel
+/// after decoding with the pixel data decoder of the given transfer syntax.
+fn decoded_photometric_interpretation(
+    ts_uid: &str,
+    pi: PhotometricInterpretation,
+) -> PhotometricInterpretation {
+    use dicom_dictionary_std::uids;
+
+    match pi {
+        // JPEG 2000 decoders reverse the multiple component transformation
+        PhotometricInterpretation::YbrIct | PhotometricInterpretation::YbrRct => {
+            PhotometricInterpretation::Rgb
+        }
+        // lossy JPEG and JPEG XL decoders upsample the chroma samples
+        // and convert them to RGB
+        PhotometricInterpretation::YbrFull
+        | PhotometricInterpretation::YbrFull422
+        | PhotometricInterpretation::YbrPartial420
+            if matches!(
+                ts_uid,
+                uids::JPEG_BASELINE8_BIT
+                    | uids::JPEG_EXTENDED12_BIT
+                    | uids::JPEGXL_LOSSLESS
+                    | uids::JPEGXLJPEG_RECOMPRESSION
+                    | uids::JPEGXL
+            ) =>
+        {
+            PhotometricInterpretation::Rgb
+        }
+        PhotometricInterpretation::Other(pi) if pi == "YBR_PARTIAL_422" => {
+            PhotometricInterpretation::Rgb
+        }
+        // other decoders retain the original color space
+        pi => pi,
+    }
+}
+
+/// Retrieve the size of each frame of native _YBR_FULL_422_ pixel data
+/// if the chroma samples are effectively subsampled.
+///
+/// Some files declare _YBR_FULL_422_ on native pixel data
+/// with one chroma sample pair per pixel,
+/// in which case `None` is returned as well.
+fn native_ybr_422_frame_size(
+    pi: &PhotometricInterpretation,
+    cols: u16,
+    rows: u16,
+    bits_allocated: u16,
+    available_frame_size: usize,
+) -> Option<usize> {
+    if *pi != PhotometricInterpretation::YbrFull422 || bits_allocated % 8 != 0 {
+        return None;
+    }
+    let bytes_per_sample = bits_allocated as usize / 8;
+    let pixels = cols as usize * rows as usize;
+    let frame_size = (cols as usize).div_ceil(2) * rows as usize * 4 * bytes_per_sample;
+    (available_frame_size < pixels * 3 * bytes_per_sample && available_frame_size >= frame_size)
+        .then_some(frame_size)
+}
+
+/// Upsample a frame of native _YBR_FULL_422_ pixel data,
+/// in which each pair of horizontally adjacent pixels
+/// is encoded as `Y1 Y2 CB CR`,
+/// into _YBR_FULL_ pixel data with one `Y CB CR` triplet per pixel.
+fn upsample_ybr_422(data: &[u8], cols: u16, rows: u16, bits_allocated: u16) -> Vec<u8> {
+    let s = bits_allocated as usize / 8;
+    let cols = cols as usize;
+    let mut out = Vec::with_capacity(cols * rows as usize * 3 * s);
+    for row in data
+        .chunks_exact(cols.div_ceil(2) * 4 * s)
+        .take(rows as usize)
+    {
+        for (i, group) in row.chunks_exact(4 * s).enumerate() {
+            let (luma, chroma) = group.split_at(2 * s);
+            // the last pair of an odd row has a single pixel
+            let pixels = (cols - 2 * i).min(2);
+            for y in luma.chunks_exact(s).take(pixels) {
+                out.extend_from_slice(y);
+                out.extend_from_slice(chroma);
+            }
+        }
+    }
+    out
+}
+
 fn bytes_to_vec_u16(data: &[u8]) -> Vec<u16> {
     debug_assert!(data.len() % 2 == 0);
     let mut pixel_array: Vec<u16> = vec![0; data.len() / 2];
@@ -1989,7 +2798,7 @@ fn convert_colorspace_u8(i: &mut [u8]) {
         let r = r - 128.0;
 
         let cr = (y + 1.402 * r) + 0.5;
-        let cg = (y + (0.114 * 1.772 / 0.587) * b + (-0.299 * 1.402 / 0.587) * r) + 0.5;
+        let cg = (y - (0.114 * 1.772 / 0.587) * b - (0.299 * 1.402 / 0.587) * r) + 0.5;
         let cb = (y + 1.772 * b) + 0.5;
 
         let cr = cr.floor().clamp(0.0, u8::MAX as f32) as u8;
@@ -2017,9 +2826,13 @@ fn interleave<T: Copy>(data: &[T]) -> Vec<T> {
 }
 
 // Convert u16 pixel array from YBR_FULL or YBR_FULL_422 to RGB
-// Every pixel is replaced with an RGB value
+// Every pixel is replaced with an RGB value,
+// with the chroma offset and range given by the number of bits stored
 #[cfg(feature = "image")]
-fn convert_colorspace_u16(i: &mut [u16]) {
+fn convert_colorspace_u16(i: &mut [u16], bits_stored: u16) {
+    let max = ((1_u32 << bits_stored.clamp(1, 16)) - 1) as f32;
+    let half = (max + 1.) / 2.;
+
     #[cfg(feature = "rayon")]
     let iter = i.par_chunks_mut(3);
     #[cfg(not(feature = "rayon"))]
@@ -2031,16 +2844,16 @@ fn convert_colorspace_u16(i: &mut [u16]) {
         let y = pixel[0] as f32;
         let b: f32 = pixel[1] as f32;
         let r: f32 = pixel[2] as f32;
-        let b = b - 32768.0;
-        let r = r - 32768.0;
+        let b = b - half;
+        let r = r - half;
 
         let cr = (y + 1.402 * r) + 0.5;
-        let cg = (y + (0.114 * 1.772 / 0.587) * b + (-0.299 * 1.402 / 0.587) * r) + 0.5;
+        let cg = (y - (0.114 * 1.772 / 0.587) * b - (0.299 * 1.402 / 0.587) * r) + 0.5;
         let cb = (y + 1.772 * b) + 0.5;
 
-        let cr = cr.floor().clamp(0.0, u16::MAX as f32) as u16;
-        let cg = cg.floor().clamp(0.0, u16::MAX as f32) as u16;
-        let cb = cb.floor().clamp(0.0, u16::MAX as f32) as u16;
+        let cr = cr.floor().clamp(0.0, max) as u16;
+        let cg = cg.floor().clamp(0.0, max) as u16;
+        let cb = cb.floor().clamp(0.0, max) as u16;
 
         pixel[0] = cr;
         pixel[1] = cg;
@@ -2092,6 +2905,13 @@ pub trait PixelDecoder {
     /// such as [`to_vec_frame`](DecodedPixelData::to_vec_frame),
     /// assume the intended frame number to be `0`.
     ///
+    /// For encapsulated pixel data,
+    /// the implementation for in-memory DICOM objects
+    /// only decodes the fragments of the requested frame.
+    /// These are located through the basic offset table,
+    /// or by looking for the start of each frame's codestream
+    /// when the offset table is empty.
+    ///
     /// ---
     ///
     /// The default implementation decodes the full pixel data
@@ -2145,6 +2965,8 @@ pub(crate) struct ImagingProperties {
     pub(crate) voi_lut_function: Option<Vec<VoiLutFunction>>,
     pub(crate) window: Option<Vec<WindowLevel>>,
     pub(crate) voi_lut_sequence: Option<Vec<VoiLut>>,
+    pub(crate) palette_color_lut: Option<PaletteColorLut>,
+    pub(crate) real_world_value_mapping: Vec<Vec<RealWorldValueMapping>>,
 }
 
 #[cfg(not(feature = "gdcm"))]
@@ -2175,6 +2997,12 @@ impl ImagingProperties {
                 .collect()
         });
         let voi_lut_sequence = voi_lut_sequence(obj);
+        let real_world_value_mapping = real_world::real_world_value_mappings(obj);
+        let palette_color_lut = match &photometric_interpretation {
+            PhotometricInterpretation::PaletteColor => palette_color_lut(obj)?,
+            _ if has_supplemental_palette(obj) => palette_color_lut(obj).ok().flatten(),
+            _ => None,
+        };
 
         ensure!(
             rescale_intercept.len() == rescale_slope.len(),
@@ -2225,6 +3053,8 @@ impl ImagingProperties {
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
         })
     }
 }
@@ -2235,6 +3065,10 @@ where
     D: DataDictionary + Clone,
 {
     fn decode_pixel_data(&self) -> Result<DecodedPixelData<'_>> {
+        if let Some(decoded) = decode_float_pixel_data(self, None)? {
+            return Ok(decoded);
+        }
+
         let pixel_data = attribute::pixel_data(self)?;
 
         let ImagingProperties {
@@ -2253,6 +3087,8 @@ where
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
         } = ImagingProperties::from_obj(self)?;
 
         let transfer_syntax = &self.meta().transfer_syntax;
@@ -2286,7 +3122,7 @@ where
             // pixels are already interpreted,
             // set new photometric interpretation if necessary
             let new_pi = match samples_per_pixel {
-                3 => PhotometricInterpretation::Rgb,
+                3 => decoded_photometric_interpretation(ts.uid(), photometric_interpretation),
                 _ => photometric_interpretation,
             };
 
@@ -2302,14 +3138,28 @@ where
                 bits_stored,
                 high_bit,
                 pixel_representation,
+                float: false,
                 rescale,
                 voi_lut_function,
                 window,
                 voi_lut_sequence,
+                palette_color_lut,
+                real_world_value_mapping,
                 enforce_frame_fg_vm_match: false,
             });
         }
 
+        let ybr_422_frame_size = match pixel_data.value() {
+            DicomValue::Primitive(p) if samples_per_pixel == 3 => native_ybr_422_frame_size(
+                &photometric_interpretation,
+                cols,
+                rows,
+                bits_allocated,
+                p.calculate_byte_len() / number_of_frames.max(1) as usize,
+            ),
+            _ => None,
+        };
+
         let decoded_pixel_data = match pixel_data.value() {
             DicomValue::PixelSequence(v) => {
                 // Return all fragments concatenated
@@ -2320,21 +3170,22 @@ where
                 // Non-encoded, just return the pixel data for all frames
                 let data = p.to_bytes();
 
-                if bits_allocated == 1 {
-                    // Expand 1-bit samples to 0/255 bytes for all frames
-                    let frame_pixels = (rows as usize) * (cols as usize);
-                    let frame_samples = frame_pixels * (samples_per_pixel as usize);
-                    let frame_size = frame_samples / 8;
-                    let frame_size_all = frame_size * (number_of_frames as usize);
-
-                    let frame_data = data.get(0..frame_size_all).context(FrameOutOfRangeSnafu {
-                        frame_number: frame_size_all as u32,
-                    })?;
-                    // Map every bit in each byte to a separate byte of either 0 or 255
-                    frame_data
-                        .iter()
-                        .flat_map(|&byte| (0..8).map(move |bit| ((byte >> bit) & 1) * 255))
-                        .take(frame_pixels * number_of_frames as usize)
+                if packing::is_packed(bits_allocated) {
+                    // Unpack the samples of all frames
+                    let len = rows as usize
+                        * cols as usize
+                        * samples_per_pixel as usize
+                        * number_of_frames as usize;
+                    packing::unpack_native(&data, bits_allocated, 0, len).context(
+                        FrameOutOfRangeSnafu {
+                            frame_number: number_of_frames.saturating_sub(1),
+                        },
+                    )?
+                } else if let Some(frame_size) = ybr_422_frame_size {
+                    // Upsample the chroma samples of every frame
+                    data.chunks_exact(frame_size)
+                        .take(number_of_frames as usize)
+                        .flat_map(|frame| upsample_ybr_422(frame, cols, rows, bits_allocated))
                         .collect()
                 } else {
                     data.to_vec()
@@ -2342,6 +3193,17 @@ where
             }
             DicomValue::Sequence(..) => InvalidPixelDataSnafu.fail()?,
         };
+        let photometric_interpretation = if ybr_422_frame_size.is_some() {
+            PhotometricInterpretation::YbrFull
+        } else {
+            photometric_interpretation
+        };
+        // bit-packed samples were unpacked into whole bytes
+        let bits_allocated = if packing::is_packed(bits_allocated) {
+            packing::unpacked_bits_allocated(bits_allocated)
+        } else {
+            bits_allocated
+        };
         Ok(DecodedPixelData {
             data: Cow::from(decoded_pixel_data),
             cols: cols.into(),
@@ -2354,15 +3216,22 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            float: false,
             rescale,
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
             enforce_frame_fg_vm_match: false,
         })
     }
 
     fn decode_pixel_data_frame(&self, frame: u32) -> Result<DecodedPixelData<'_>> {
+        if let Some(decoded) = decode_float_pixel_data(self, Some(frame))? {
+            return Ok(decoded);
+        }
+
         let pixel_data = attribute::pixel_data(self)?;
 
         let ImagingProperties {
@@ -2381,6 +3250,8 @@ where
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
         } = ImagingProperties::from_obj(self)?;
 
         let transfer_syntax = &self.meta().transfer_syntax;
@@ -2427,6 +3298,13 @@ where
                 .map(|el| vec![el])
         });
 
+        let real_world_value_mapping = real_world_value_mapping
+            .get(frame as usize)
+            .or(real_world_value_mapping.first())
+            .cloned()
+            .into_iter()
+            .collect();
+
         // Try decoding it using a registered pixel data decoder
         if let Codec::EncapsulatedPixelData(Some(decoder), _) = ts.codec() {
             let mut data: Vec<u8> = Vec::new();
@@ -2437,7 +3315,7 @@ where
             // pixels are already interpreted,
             // set new photometric interpretation if necessary
             let new_pi = match samples_per_pixel {
-                3 => PhotometricInterpretation::Rgb,
+                3 => decoded_photometric_interpretation(ts.uid(), photometric_interpretation),
                 _ => photometric_interpretation,
             };
 
@@ -2453,14 +3331,28 @@ where
                 bits_stored,
                 high_bit,
                 pixel_representation,
+                float: false,
                 rescale,
                 voi_lut_function,
                 window,
                 voi_lut_sequence,
+                palette_color_lut,
+                real_world_value_mapping,
                 enforce_frame_fg_vm_match: false,
             });
         }
 
+        let ybr_422_frame_size = match pixel_data.value() {
+            DicomValue::Primitive(p) if samples_per_pixel == 3 => native_ybr_422_frame_size(
+                &photometric_interpretation,
+                cols,
+                rows,
+                bits_allocated,
+                p.calculate_byte_len() / number_of_frames.max(1) as usize,
+            ),
+            _ => None,
+        };
+
         let decoded_pixel_data = match pixel_data.value() {
             DicomValue::PixelSequence(v) => {
                 let fragments = v.fragments();
@@ -2476,34 +3368,54 @@ where
                 // Non-encoded, just return the pixel data for a single frame
                 let frame_pixels = (rows as usize) * (cols as usize);
                 let frame_samples = frame_pixels * (samples_per_pixel as usize);
-                let frame_size = if bits_allocated == 1 {
-                    frame_samples / 8
-                } else {
-                    frame_samples * (bits_allocated.div_ceil(8) as usize)
-                };
-                let frame_offset = frame_size * (frame as usize);
-
                 let data = p.to_bytes();
 
-                let frame_data = data.get(frame_offset..frame_offset + frame_size).context(
-                    FrameOutOfRangeSnafu {
+                if packing::is_packed(bits_allocated) {
+                    // frames of bit-packed samples are not aligned to bytes
+                    packing::unpack_native(
+                        &data,
+                        bits_allocated,
+                        frame_samples * frame as usize,
+                        frame_samples,
+                    )
+                    .context(FrameOutOfRangeSnafu {
                         frame_number: frame,
-                    },
-                )?;
-
-                if bits_allocated == 1 {
-                    // Map every bit in each byte to a separate byte of either 0 or 255
-                    frame_data
-                        .iter()
-                        .flat_map(|&byte| (0..8).map(move |bit| ((byte >> bit) & 1) * 255))
-                        .take(frame_pixels)
-                        .collect()
+                    })?
                 } else {
-                    frame_data.to_vec()
+                    let frame_size = if let Some(frame_size) = ybr_422_frame_size {
+                        frame_size
+                    } else {
+                        frame_samples * (bits_allocated.div_ceil(8) as usize)
+                    };
+                    let frame_offset = frame_size * (frame as usize);
+
+                    let frame_data = data.get(frame_offset..frame_offset + frame_size).context(
+                        FrameOutOfRangeSnafu {
+                            frame_number: frame,
+                        },
+                    )?;
+
+                    if ybr_422_frame_size.is_some() {
+                        // Upsample the chroma samples
+                        upsample_ybr_422(frame_data, cols, rows, bits_allocated)
+                    } else {
+                        frame_data.to_vec()
+                    }
                 }
             }
             DicomValue::Sequence(..) => InvalidPixelDataSnafu.fail()?,
         };
+        let photometric_interpretation = if ybr_422_frame_size.is_some() {
+            PhotometricInterpretation::YbrFull
+        } else {
+            photometric_interpretation
+        };
+        // bit-packed samples were unpacked into whole bytes
+        let bits_allocated = if packing::is_packed(bits_allocated) {
+            packing::unpacked_bits_allocated(bits_allocated)
+        } else {
+            bits_allocated
+        };
 
         Ok(DecodedPixelData {
             data: Cow::from(decoded_pixel_data),
@@ -2517,10 +3429,13 @@ where
             bits_stored,
             high_bit,
             pixel_representation,
+            float: false,
             rescale,
             voi_lut_function,
             window,
             voi_lut_sequence,
+            palette_color_lut,
+            real_world_value_mapping,
             enforce_frame_fg_vm_match: false,
         })
     }
@@ -3219,4 +4134,767 @@ mod tests {
             image.save(image_path).unwrap();
         }
     }
+
+    /// Single frames of a JPEG encoded multi-frame object
+    /// can be decoded without a basic offset table,
+    /// even if frames span multiple fragments.
+    #[cfg(feature = "native")]
+    #[test]
+    fn test_decode_pixel_data_frame_without_offset_table() {
+        use crate::Transcode as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR, value::PixelFragmentSequence};
+        use dicom_dictionary_std::{tags, uids};
+        use dicom_transfer_syntax_registry::entries::JPEG_BASELINE;
+
+        let (rows, columns, frames) = (32_u16, 32_u16, 4_u32);
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.118053998431490809498823246298095398931")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, rows));
+        obj.put(us(tags::COLUMNS, columns));
+        obj.put(us(tags::BITS_ALLOCATED, 8));
+        obj.put(us(tags::BITS_STORED, 8));
+        obj.put(us(tags::HIGH_BIT, 7));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            frames.to_string(),
+        ));
+        // each frame is filled with a different value
+        let samples: Vec<u8> = (0..frames)
+            .flat_map(|i| vec![40 + i as u8 * 50; rows as usize * columns as usize])
+            .collect();
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(samples),
+        ));
+
+        obj.transcode(&JPEG_BASELINE.erased()).unwrap();
+
+        // split each frame in two fragments and remove the offset table
+        let DicomValue::PixelSequence(seq) = obj.element(tags::PIXEL_DATA).unwrap().value() else {
+            panic!("pixel data should be encapsulated");
+        };
+        assert_eq!(seq.fragments().len(), frames as usize);
+        let fragments: Vec<Vec<u8>> = seq
+            .fragments()
+            .iter()
+            .flat_map(|fragment| {
+                let mid = fragment.len() / 4 * 2;
+                [fragment[..mid].to_vec(), fragment[mid..].to_vec()]
+            })
+            .collect();
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            DicomValue::PixelSequence(PixelFragmentSequence::new_fragments(fragments)),
+        ));
+
+        for frame in 0..frames {
+            let decoded = obj.decode_pixel_data_frame(frame).unwrap();
+            assert_eq!(decoded.number_of_frames(), 1);
+            let expected = 40 + frame as u8 * 50;
+            let data = decoded.data();
+            assert_eq!(data.len(), rows as usize * columns as usize);
+            assert!(
+                data.iter().all(|v| v.abs_diff(expected) <= 2),
+                "unexpected samples in frame #{frame}"
+            );
+        }
+        assert!(obj.decode_pixel_data_frame(frames).is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_thumbnail() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.270445054140113943515388848809104576486")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        // 200x100 16-bit monochrome gradient without windowing attributes
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 100));
+        obj.put(us(tags::COLUMNS, 200));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 12));
+        obj.put(us(tags::HIGH_BIT, 11));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(
+                (0..100u16)
+                    .flat_map(|_| (0..200u16).map(|x| x * 20))
+                    .collect(),
+            ),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let thumbnail = decoded.thumbnail(64).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (64, 32));
+        let thumbnail = thumbnail.as_luma8().expect("should be 8-bit monochrome");
+        // values are normalized to the full 8-bit range
+        assert!(thumbnail.get_pixel(0, 0).0[0] < 8);
+        assert!(thumbnail.get_pixel(63, 0).0[0] > 247);
+
+        // small images are not enlarged
+        let thumbnail = decoded.thumbnail(256).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (200, 100));
+    }
+
+    /// Palette color images are converted to RGB
+    /// through their palette color lookup tables,
+    /// and monochrome images may have a supplemental palette.
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_palette_color_to_image() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::ULTRASOUND_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.302301509022806367620833134961849256037")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "PALETTE COLOR",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 4));
+        obj.put(us(tags::BITS_ALLOCATED, 8));
+        obj.put(us(tags::BITS_STORED, 8));
+        obj.put(us(tags::HIGH_BIT, 7));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(vec![0_u8, 1, 2, 3, 4, 5, 6, 200]),
+        ));
+        // 8 entries, starting at stored value 1
+        for tag in [
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+            tags::BLUE_PALETTE_COLOR_LOOKUP_TABLE_DESCRIPTOR,
+        ] {
+            obj.put(DataElement::new(
+                tag,
+                VR::US,
+                PrimitiveValue::from([8_u16, 1, 16]),
+            ));
+        }
+        let red: Vec<u16> = (0..8).map(|i| i * 0x2000).collect();
+        let green: Vec<u16> = (0..8).map(|i| 0xFFFF - i * 0x2000).collect();
+        obj.put(DataElement::new(
+            tags::RED_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(red.into()),
+        ));
+        obj.put(DataElement::new(
+            tags::GREEN_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(green.into()),
+        ));
+        // blue is segmented: a discrete entry, then a linear ramp
+        obj.put(DataElement::new(
+            tags::SEGMENTED_BLUE_PALETTE_COLOR_LOOKUP_TABLE_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0_u16, 1, 0x1000, 1, 7, 0x8000].into()),
+        ));
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        let lut = decoded.palette_color_lut().unwrap();
+        assert_eq!(lut.first_mapped_value, 1);
+        assert_eq!(lut.blue.len(), 8);
+        assert_eq!(lut.blue[0], 0x1000);
+        assert_eq!(lut.blue[7], 0x8000);
+
+        let image = decoded.to_dynamic_image(0).unwrap();
+        let DynamicImage::ImageRgb16(image) = image else {
+            panic!("expected a 16-bit RGB image");
+        };
+        // below the first mapped value
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0xFFFF, 0x1000]);
+        assert_eq!(image.get_pixel(1, 0).0, [0, 0xFFFF, 0x1000]);
+        assert_eq!(image.get_pixel(3, 0).0, [0x4000, 0xBFFF, 0x3000]);
+        // past the last entry
+        assert_eq!(image.get_pixel(3, 1).0, [0xE000, 0x1FFF, 0x8000]);
+
+        let options = ConvertOptions::new().force_8bit();
+        let image = decoded.to_dynamic_image_with_options(0, &options).unwrap();
+        let DynamicImage::ImageRgb8(image) = image else {
+            panic!("expected an 8-bit RGB image");
+        };
+        assert_eq!(image.get_pixel(3, 0).0, [0x40, 0xBF, 0x30]);
+
+        // the same tables as a supplemental palette of a monochrome image
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(DataElement::new(tags::PIXEL_PRESENTATION, VR::CS, "COLOR"));
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(decoded.palette_color_lut().is_some());
+        let options = ConvertOptions::new().with_voi_lut(VoiLutOption::Identity);
+        let image = decoded.to_dynamic_image_with_options(0, &options).unwrap();
+        let DynamicImage::ImageRgb8(image) = image else {
+            panic!("expected an 8-bit RGB image");
+        };
+        // outside of the palette range: grayscale
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(3, 1).0, [200, 200, 200]);
+        // within the palette range: color
+        assert_eq!(image.get_pixel(3, 0).0, [0x40, 0xBF, 0x30]);
+    }
+
+    /// Native YBR_FULL_422 pixel data are upsampled to YBR_FULL,
+    /// and YBR_FULL pixel data with planar configuration 1
+    /// are converted to the expected RGB colors.
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_ybr_to_image() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.223140472417208673093369656042672396253")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 3));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "YBR_FULL_422",
+        ));
+        obj.put(us(tags::PLANAR_CONFIGURATION, 0));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 4));
+        obj.put(us(tags::BITS_ALLOCATED, 8));
+        obj.put(us(tags::BITS_STORED, 8));
+        obj.put(us(tags::HIGH_BIT, 7));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        // each pair of pixels: Y1 Y2 CB CR,
+        // gray pairs in the first frame, then red and blue in the second frame
+        #[rustfmt::skip]
+        let samples: Vec<u8> = vec![
+            0, 64, 128, 128, 128, 192, 128, 128,
+            255, 255, 128, 128, 32, 32, 128, 128,
+            76, 76, 85, 255, 29, 29, 255, 107,
+            76, 76, 85, 255, 29, 29, 255, 107,
+        ];
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(samples),
+        ));
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(
+            decoded.photometric_interpretation(),
+            &PhotometricInterpretation::YbrFull
+        );
+        assert_eq!(decoded.data().len(), 2 * 2 * 4 * 3);
+        assert_eq!(
+            &decoded.frame_data(0).unwrap()[..6],
+            &[0, 128, 128, 64, 128, 128]
+        );
+
+        let image = decoded.to_dynamic_image(0).unwrap().to_rgb8();
+        assert_eq!(image.get_pixel(1, 0).0, [64, 64, 64]);
+        assert_eq!(image.get_pixel(2, 1).0, [32, 32, 32]);
+
+        let check_red_and_blue = |image: image::RgbImage| {
+            for y in 0..2 {
+                let [r, g, b] = image.get_pixel(1, y).0;
+                assert!(
+                    r >= 250 && g <= 4 && b <= 4,
+                    "expected red, got {:?}",
+                    [r, g, b]
+                );
+                let [r, g, b] = image.get_pixel(2, y).0;
+                assert!(
+                    r <= 4 && g <= 4 && b >= 250,
+                    "expected blue, got {:?}",
+                    [r, g, b]
+                );
+            }
+        };
+        check_red_and_blue(decoded.to_dynamic_image(1).unwrap().to_rgb8());
+
+        // single frame decoding
+        let decoded = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(decoded.data().len(), 2 * 4 * 3);
+        check_red_and_blue(decoded.to_dynamic_image(0).unwrap().to_rgb8());
+
+        // the same colors in YBR_FULL with planar configuration 1
+        let pixel = |i: usize| match i % 4 {
+            0 | 1 => [76, 85, 255],
+            _ => [29, 255, 107],
+        };
+        let samples: Vec<u8> = (0..3)
+            .flat_map(|channel| (0..8).map(move |i| pixel(i)[channel]))
+            .collect();
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "YBR_FULL",
+        ));
+        obj.put(us(tags::PLANAR_CONFIGURATION, 1));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "1"));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(samples),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+        check_red_and_blue(decoded.to_dynamic_image(0).unwrap().to_rgb8());
+    }
+
+    #[test]
+    fn test_decoded_photometric_interpretation() {
+        use dicom_dictionary_std::uids;
+
+        // lossy JPEG decoding converts to RGB
+        assert_eq!(
+            decoded_photometric_interpretation(
+                uids::JPEG_BASELINE8_BIT,
+                PhotometricInterpretation::YbrFull422
+            ),
+            PhotometricInterpretation::Rgb
+        );
+        // RLE decoding retains the color space
+        assert_eq!(
+            decoded_photometric_interpretation(
+                uids::RLE_LOSSLESS,
+                PhotometricInterpretation::YbrFull
+            ),
+            PhotometricInterpretation::YbrFull
+        );
+        assert_eq!(
+            decoded_photometric_interpretation(uids::RLE_LOSSLESS, PhotometricInterpretation::Rgb),
+            PhotometricInterpretation::Rgb
+        );
+        // JPEG 2000 reverses the multiple component transformation
+        assert_eq!(
+            decoded_photometric_interpretation(
+                uids::JPEG2000_LOSSLESS,
+                PhotometricInterpretation::YbrRct
+            ),
+            PhotometricInterpretation::Rgb
+        );
+    }
+
+    #[test]
+    fn test_frame_bytes() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let meta = dicom_object::FileMetaTableBuilder::new()
+            .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+            .media_storage_sop_instance_uid("2.25.158846394053419598472053806089320185232")
+            .build()
+            .unwrap();
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+
+        // 2 frames of 2x2 unsigned 12-bit samples
+        let mut obj = FileDicomObject::new_empty_with_meta(meta);
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 2));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 12));
+        obj.put(us(tags::HIGH_BIT, 11));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![1, 2, 3, 4, 100, 200, 300, 4095].into()),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let frame = decoded.frame_bytes(1).unwrap();
+        assert_eq!(frame.bytes(), decoded.frame_data(1).unwrap());
+        match frame.samples() {
+            FrameSamples::U16(samples) => assert_eq!(&samples[..], &[100, 200, 300, 4095]),
+            FrameSamples::U8(_) => panic!("expected 16-bit samples"),
+        }
+        let layout = frame.layout();
+        assert_eq!((layout.columns, layout.rows), (2, 2));
+        assert_eq!(layout.bytes_per_sample, 2);
+        assert_eq!(layout.bits_stored, 12);
+        assert_eq!(layout.pixel_stride, 2);
+        assert_eq!(layout.row_stride, 4);
+        assert_eq!(layout.plane_stride, None);
+        assert_eq!(layout.len(), frame.bytes().len());
+
+        assert!(decoded.frame_bytes(2).is_err());
+    }
+
+    #[test]
+    fn test_decode_bit_packed() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let object = |bits_allocated: u16, frames: &str, pixel_data: Vec<u8>| {
+            let meta = dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.302436470101512342525472652841209817044")
+                .build()
+                .unwrap();
+            let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+            let mut obj = FileDicomObject::new_empty_with_meta(meta);
+            obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+            obj.put(DataElement::new(
+                tags::PHOTOMETRIC_INTERPRETATION,
+                VR::CS,
+                "MONOCHROME2",
+            ));
+            obj.put(us(tags::ROWS, 3));
+            obj.put(us(tags::COLUMNS, 3));
+            obj.put(us(tags::BITS_ALLOCATED, bits_allocated));
+            obj.put(us(tags::BITS_STORED, bits_allocated));
+            obj.put(us(tags::HIGH_BIT, bits_allocated - 1));
+            obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+            obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, frames));
+            obj.put(DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(pixel_data),
+            ));
+            obj
+        };
+
+        // 2 frames of 3x3 binary pixels,
+        // the second frame starting in the middle of a byte
+        let bits: Vec<bool> = (0..18).map(|i| i % 3 == 0 || i == 17).collect();
+        let obj = object(1, "2", packing::pack_bits(bits.iter().copied()));
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.bits_allocated(), 8);
+        assert_eq!(
+            decoded.data(),
+            bits.iter().map(|&b| b as u8).collect::<Vec<_>>()
+        );
+        let frame = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(frame.bits_allocated(), 8);
+        assert_eq!(frame.data(), &[1, 0, 0, 1, 0, 0, 1, 0, 1]);
+
+        // 12-bit samples, unpacked into 16 bits each
+        let samples: Vec<u16> = (0..9).map(|i| i * 500).collect();
+        let obj = object(12, "1", packing::pack_samples(samples.iter().copied(), 12));
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.bits_allocated(), 16);
+        assert_eq!(decoded.to_vec::<u16>().unwrap(), samples);
+    }
+
+    #[test]
+    fn test_decode_float_pixel_data() {
+        use crate::PixelDecoder as _;
+        use dicom_core::value::DataSetSequence;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let meta = dicom_object::FileMetaTableBuilder::new()
+            .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::PARAMETRIC_MAP_STORAGE)
+            .media_storage_sop_instance_uid("2.25.88915512734368914839869452634634757634")
+            .build()
+            .unwrap();
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let fd = |tag, value: f64| DataElement::new(tag, VR::FD, PrimitiveValue::from(value));
+        // a linear mapping in each frame
+        let frame_group = |slope: f64| {
+            InMemDicomObject::from_element_iter([DataElement::new(
+                tags::REAL_WORLD_VALUE_MAPPING_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    fd(tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_FIRST_VALUE_MAPPED, -10.),
+                    fd(tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_LAST_VALUE_MAPPED, 10.),
+                    fd(tags::REAL_WORLD_VALUE_SLOPE, slope),
+                    fd(tags::REAL_WORLD_VALUE_INTERCEPT, 1.),
+                ])]),
+            )])
+        };
+
+        // 2 frames of 2x2 32-bit floating point samples
+        let mut obj = FileDicomObject::new_empty_with_meta(meta);
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 2));
+        obj.put(us(tags::BITS_ALLOCATED, 32));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        obj.put(DataElement::new(
+            tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![frame_group(2.), frame_group(0.5)]),
+        ));
+        obj.put(DataElement::new(
+            tags::FLOAT_PIXEL_DATA,
+            VR::OF,
+            PrimitiveValue::F32(vec![-1.5, 0., 0.25, 8., 1., 2., 3., 4.].into()),
+        ));
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(decoded.is_float());
+        assert_eq!(decoded.bits_allocated(), 32);
+        assert_eq!(decoded.number_of_frames(), 2);
+        assert_eq!(
+            decoded.to_vec::<f32>().unwrap(),
+            [-1.5, 0., 0.25, 8., 1., 2., 3., 4.]
+        );
+        assert_eq!(decoded.real_world_value_mapping(1).unwrap().apply(4.), 3.);
+
+        let options = ConvertOptions::new().with_modality_lut(ModalityLutOption::RealWorldValues);
+        assert_eq!(
+            decoded.to_vec_with_options::<f64>(&options).unwrap(),
+            [-2., 1., 1.5, 17., 1.5, 2., 2.5, 3.]
+        );
+
+        // a single frame keeps its own mapping
+        let frame = obj.decode_pixel_data_frame(1).unwrap();
+        assert_eq!(frame.number_of_frames(), 1);
+        assert_eq!(
+            frame.to_vec_with_options::<f64>(&options).unwrap(),
+            [1.5, 2., 2.5, 3.]
+        );
+        assert!(obj.decode_pixel_data_frame(2).is_err());
+
+        // no mapping to apply
+        obj.remove_element(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE);
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(decoded.to_vec_with_options::<f64>(&options).is_err());
+    }
+
+    #[test]
+    fn test_to_real_world_values() {
+        use crate::PixelDecoder as _;
+        use dicom_core::value::DataSetSequence;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+        use dicom_object::code::{Code, ucum};
+
+        let meta = dicom_object::FileMetaTableBuilder::new()
+            .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(uids::POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE)
+            .media_storage_sop_instance_uid("2.25.215598800697946996684470895483093573624")
+            .build()
+            .unwrap();
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let fd = |tag, value: f64| DataElement::new(tag, VR::FD, PrimitiveValue::from(value));
+        let becquerel_per_ml = Code::new("Bq/ml", "UCUM", "Becquerels/milliliter");
+        let mapping = |units: &Code, slope: f64| {
+            InMemDicomObject::from_element_iter([
+                us(tags::REAL_WORLD_VALUE_FIRST_VALUE_MAPPED, 0),
+                us(tags::REAL_WORLD_VALUE_LAST_VALUE_MAPPED, 65535),
+                fd(tags::REAL_WORLD_VALUE_SLOPE, slope),
+                fd(tags::REAL_WORLD_VALUE_INTERCEPT, 0.),
+                units.to_sequence_element(tags::MEASUREMENT_UNITS_CODE_SEQUENCE),
+            ])
+        };
+
+        // 2x2 unsigned 16-bit samples, in Bq/ml and SUV
+        let mut obj = FileDicomObject::new_empty_with_meta(meta);
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 2));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 16));
+        obj.put(us(tags::HIGH_BIT, 15));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(
+            tags::REAL_WORLD_VALUE_MAPPING_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![
+                mapping(&becquerel_per_ml, 2.),
+                mapping(&ucum::SUV_BODY_WEIGHT, 0.001),
+            ]),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 1000, 2500, 4000].into()),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.real_world_value_mappings(0).len(), 2);
+
+        let activity = decoded.to_real_world_values(0).unwrap();
+        assert_eq!(activity.values, [0., 2000., 5000., 8000.]);
+        assert_eq!(activity.units, Some(becquerel_per_ml));
+
+        let suv = decoded
+            .to_real_world_values_in(0, &ucum::SUV_BODY_WEIGHT)
+            .unwrap();
+        assert_eq!(suv.values, [0., 1., 2.5, 4.]);
+        assert!(
+            decoded
+                .to_real_world_values_in(0, &ucum::MILLIMETER)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_to_ndarray_volume() {
+        use crate::PixelDecoder as _;
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+
+        let meta = || {
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::ENHANCED_CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.41296834882855169654184450973840896180")
+                .build()
+                .unwrap()
+        };
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+
+        // 2 frames of 2x3 signed 16-bit samples
+        let mut obj = FileDicomObject::new_empty_with_meta(meta());
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 3));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 16));
+        obj.put(us(tags::HIGH_BIT, 15));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 1));
+        obj.put(DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"));
+        obj.put(DataElement::new(tags::RESCALE_SLOPE, VR::DS, "0.5"));
+        obj.put(DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "-1024"));
+        let samples: Vec<i16> = vec![-2000, -1, 0, 1, 3, 2000, 5, 6, 7, 8, 9, 11];
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::from(
+                samples
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect::<Vec<u8>>(),
+            ),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let volume: Array4<f32> = decoded.to_ndarray_volume().unwrap();
+        assert_eq!(volume.shape(), &[2, 2, 3, 1]);
+        assert_eq!(volume[[0, 0, 0, 0]], -2024.);
+        assert_eq!(volume[[0, 0, 1, 0]], -1024.5);
+        assert_eq!(volume[[0, 1, 2, 0]], -24.);
+        assert_eq!(volume[[1, 1, 2, 0]], -1018.5);
+
+        // stored values
+        let options = ConvertOptions::new().with_modality_lut(ModalityLutOption::None);
+        let stored: Array4<i32> = decoded.to_ndarray_volume_with_options(&options).unwrap();
+        assert_eq!(
+            stored.iter().copied().collect::<Vec<_>>(),
+            samples.iter().map(|&v| v as i32).collect::<Vec<_>>()
+        );
+        // not representable by the target type
+        assert!(decoded.to_ndarray_volume::<u8>().is_err());
+
+        // zero-copy view of the stored values
+        let view = decoded.to_ndarray_view::<i16>().unwrap();
+        assert_eq!(view.shape(), &[2, 2, 3, 1]);
+        assert_eq!(view, stored.mapv(|v| v as i16));
+        assert!(decoded.to_ndarray_view::<u16>().is_err());
+        assert!(decoded.to_ndarray_view::<i32>().is_err());
+
+        // one frame of 2x2 RGB samples with planar configuration 1
+        let mut obj = FileDicomObject::new_empty_with_meta(meta());
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 3));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "RGB",
+        ));
+        obj.put(us(tags::PLANAR_CONFIGURATION, 1));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 2));
+        obj.put(us(tags::BITS_ALLOCATED, 8));
+        obj.put(us(tags::BITS_STORED, 8));
+        obj.put(us(tags::HIGH_BIT, 7));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from(vec![1_u8, 2, 3, 4, 11, 12, 13, 14, 21, 22, 23, 24]),
+        ));
+        let decoded = obj.decode_pixel_data().unwrap();
+
+        let volume: Array4<u8> = decoded.to_ndarray_volume().unwrap();
+        assert_eq!(volume.shape(), &[1, 2, 2, 3]);
+        assert_eq!(
+            volume.iter().copied().collect::<Vec<_>>(),
+            vec![1, 11, 21, 2, 12, 22, 3, 13, 23, 4, 14, 24]
+        );
+        let view = decoded.to_ndarray_view::<u8>().unwrap();
+        assert_eq!(view, volume);
+        assert_eq!(view[[0, 1, 0, 2]], 23);
+    }
 }
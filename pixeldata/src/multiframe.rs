@@ -0,0 +1,835 @@
+//! Conversion between multi-frame and single-frame objects
+//!
+//! [`split_frames`] breaks down a multi-frame object,
+//! such as an _Enhanced CT Image_,
+//! into one single-frame instance per frame.
+//! The attributes in the frame's item of the
+//! _Per-Frame Functional Groups Sequence_
+//! and in the _Shared Functional Groups Sequence_
+//! are moved to the root of each instance,
+//! where legacy image objects expect them.
+//!
+//! [`merge_frames`] does the converse,
+//! combining a series of single-frame instances
+//! into one multi-frame object.
+//! Well known image attributes,
+//! such as the _Image Position (Patient)_ or the _Window Center_,
+//! are placed in their respective functional group macros,
+//! which are shared if equal in all instances
+//! and recorded per frame otherwise.
+//! Any other attribute which changes from instance to instance
+//! is kept in the _Unassigned Per-Frame Converted Attributes Sequence_
+//! of each frame.
+//!
+//! The SOP class of legacy CT, MR and PET images
+//! is converted to the respective _Legacy Converted Enhanced_ SOP class
+//! when merging,
+//! and enhanced CT, MR, PET, XA and XRF images
+//! are converted to their legacy SOP class when splitting.
+//! Both operations keep the transfer syntax of the original objects,
+//! without decoding the pixel data.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::multiframe::{MultiFrameOptions, merge_frames, split_frames};
+//!
+//! let enhanced = open_file("enhanced_ct.dcm")?;
+//! let options = MultiFrameOptions::new();
+//! let slices = split_frames(&enhanced, &options)?;
+//! for (i, slice) in slices.iter().enumerate() {
+//!     slice.write_to_file(format!("slice_{:04}.dcm", i + 1))?;
+//! }
+//!
+//! // and back into a single object
+//! let merged = merge_frames(&slices, &options)?;
+//! merged.write_to_file("merged_ct.dcm")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::collections::BTreeSet;
+
+use dicom_core::uid::UidGenerator;
+use dicom_core::value::{DataSetSequence, PixelFragmentSequence};
+use dicom_core::{DataElement, DicomValue, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::adapters::PixelDataObject;
+use dicom_object::mem::InMemElement;
+use dicom_object::{DefaultDicomObject, InMemDicomObject};
+use snafu::{OptionExt, Snafu, ensure};
+
+/// An error occurred while splitting or merging multi-frame objects.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// No instances to merge
+    NoInstances,
+
+    /// Missing pixel data
+    MissingPixelData,
+
+    /// Missing {attribute}
+    MissingAttribute { attribute: &'static str },
+
+    /// Missing pixel data of frame #{frame}
+    MissingFrame { frame: u32 },
+
+    /// Frames of {bits} bits are not aligned to whole bytes
+    UnalignedFrame { bits: usize },
+
+    /// Instance #{index} already has multiple frames
+    UnexpectedMultiFrame { index: usize },
+
+    /// Instance #{index} has a different {attribute}
+    Inconsistent {
+        index: usize,
+        attribute: &'static str,
+    },
+}
+
+/// Alias for the result of splitting or merging multi-frame objects.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The functional group macros holding the attributes
+/// which are found at the root of legacy image objects,
+/// along with whether they may only be used per frame.
+const FUNCTIONAL_GROUPS: &[(Tag, &[Tag], bool)] = &[
+    (
+        tags::PIXEL_MEASURES_SEQUENCE,
+        &[
+            tags::PIXEL_SPACING,
+            tags::SLICE_THICKNESS,
+            tags::SPACING_BETWEEN_SLICES,
+        ],
+        false,
+    ),
+    (
+        tags::PLANE_POSITION_SEQUENCE,
+        &[tags::IMAGE_POSITION_PATIENT],
+        false,
+    ),
+    (
+        tags::PLANE_ORIENTATION_SEQUENCE,
+        &[tags::IMAGE_ORIENTATION_PATIENT],
+        false,
+    ),
+    (
+        tags::FRAME_VOILUT_SEQUENCE,
+        &[
+            tags::WINDOW_CENTER,
+            tags::WINDOW_WIDTH,
+            tags::WINDOW_CENTER_WIDTH_EXPLANATION,
+        ],
+        false,
+    ),
+    (
+        tags::PIXEL_VALUE_TRANSFORMATION_SEQUENCE,
+        &[
+            tags::RESCALE_INTERCEPT,
+            tags::RESCALE_SLOPE,
+            tags::RESCALE_TYPE,
+        ],
+        false,
+    ),
+    (
+        tags::FRAME_CONTENT_SEQUENCE,
+        &[
+            tags::FRAME_ACQUISITION_NUMBER,
+            tags::FRAME_REFERENCE_DATE_TIME,
+            tags::FRAME_ACQUISITION_DATE_TIME,
+            tags::FRAME_ACQUISITION_DURATION,
+            tags::STACK_ID,
+            tags::IN_STACK_POSITION_NUMBER,
+            tags::TEMPORAL_POSITION_INDEX,
+        ],
+        true,
+    ),
+];
+
+/// Attributes which only make sense in multi-frame objects,
+/// removed when splitting.
+const MULTI_FRAME_ATTRIBUTES: [Tag; 9] = [
+    tags::NUMBER_OF_FRAMES,
+    tags::FRAME_INCREMENT_POINTER,
+    tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+    tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+    tags::DIMENSION_ORGANIZATION_SEQUENCE,
+    tags::DIMENSION_INDEX_SEQUENCE,
+    tags::EXTENDED_OFFSET_TABLE,
+    tags::EXTENDED_OFFSET_TABLE_LENGTHS,
+    tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH,
+];
+
+/// Attributes identifying each instance,
+/// which are never carried over to the merged object.
+const INSTANCE_ATTRIBUTES: [Tag; 4] = [
+    tags::SOP_INSTANCE_UID,
+    tags::INSTANCE_NUMBER,
+    tags::NUMBER_OF_FRAMES,
+    tags::PIXEL_DATA,
+];
+
+/// Image pixel attributes which must be the same in all merged instances.
+const IMAGE_PIXEL_ATTRIBUTES: [(Tag, &str); 6] = [
+    (tags::ROWS, "Rows"),
+    (tags::COLUMNS, "Columns"),
+    (tags::SAMPLES_PER_PIXEL, "SamplesPerPixel"),
+    (tags::BITS_ALLOCATED, "BitsAllocated"),
+    (tags::PIXEL_REPRESENTATION, "PixelRepresentation"),
+    (
+        tags::PHOTOMETRIC_INTERPRETATION,
+        "PhotometricInterpretation",
+    ),
+];
+
+/// Pairs of single-frame and multi-frame SOP classes,
+/// by order of preference in the conversion to multi-frame objects.
+const SOP_CLASSES: [(&str, &str); 8] = [
+    (
+        uids::CT_IMAGE_STORAGE,
+        uids::LEGACY_CONVERTED_ENHANCED_CT_IMAGE_STORAGE,
+    ),
+    (
+        uids::MR_IMAGE_STORAGE,
+        uids::LEGACY_CONVERTED_ENHANCED_MR_IMAGE_STORAGE,
+    ),
+    (
+        uids::POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
+        uids::LEGACY_CONVERTED_ENHANCED_PET_IMAGE_STORAGE,
+    ),
+    (uids::CT_IMAGE_STORAGE, uids::ENHANCED_CT_IMAGE_STORAGE),
+    (uids::MR_IMAGE_STORAGE, uids::ENHANCED_MR_IMAGE_STORAGE),
+    (
+        uids::POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
+        uids::ENHANCED_PET_IMAGE_STORAGE,
+    ),
+    (
+        uids::X_RAY_ANGIOGRAPHIC_IMAGE_STORAGE,
+        uids::ENHANCED_XA_IMAGE_STORAGE,
+    ),
+    (
+        uids::X_RAY_RADIOFLUOROSCOPIC_IMAGE_STORAGE,
+        uids::ENHANCED_XRF_IMAGE_STORAGE,
+    ),
+];
+
+/// Options for splitting and merging multi-frame objects.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MultiFrameOptions {
+    /// the SOP class UID of the new objects
+    /// (derived from the original SOP class by default)
+    pub sop_class_uid: Option<String>,
+    /// the series instance UID of the new objects (generated by default)
+    pub series_instance_uid: Option<String>,
+    /// the generator of the UIDs which are not given
+    /// (UUID-derived UIDs under the `2.25` root by default)
+    pub uid_generator: UidGenerator,
+}
+
+impl MultiFrameOptions {
+    /// Create a new set of options with the default behavior:
+    /// the SOP class is converted if it has a known counterpart,
+    /// and the new objects are placed in a new series.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the SOP class UID of the new objects.
+    pub fn with_sop_class_uid(mut self, uid: impl Into<String>) -> Self {
+        self.sop_class_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the series instance UID of the new objects.
+    pub fn with_series_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.series_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the generator of the UIDs which are not given,
+    /// such as one under an organization root.
+    pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+        self.uid_generator = uid_generator;
+        self
+    }
+
+    fn series_instance_uid(&self) -> String {
+        self.series_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate())
+    }
+}
+
+/// Get the single-frame SOP class corresponding to a multi-frame SOP class,
+/// such as _CT Image Storage_ for _Enhanced CT Image Storage_.
+pub fn single_frame_sop_class(sop_class_uid: &str) -> Option<&'static str> {
+    SOP_CLASSES
+        .iter()
+        .find(|(_, multi_frame)| *multi_frame == sop_class_uid)
+        .map(|(single_frame, _)| *single_frame)
+}
+
+/// Get the multi-frame SOP class corresponding to a single-frame SOP class,
+/// such as _Legacy Converted Enhanced CT Image Storage_ for _CT Image Storage_.
+pub fn multi_frame_sop_class(sop_class_uid: &str) -> Option<&'static str> {
+    SOP_CLASSES
+        .iter()
+        .find(|(single_frame, _)| *single_frame == sop_class_uid)
+        .map(|(_, multi_frame)| *multi_frame)
+}
+
+/// Split a multi-frame object into one single-frame object per frame.
+///
+/// Each new object receives a new SOP instance UID
+/// and an _Instance Number_ matching the original frame number,
+/// and all of them are placed in the same new series
+/// (unless a series instance UID is given).
+/// The attributes of the functional groups which apply to each frame
+/// are moved to the root of the data set.
+pub fn split_frames(
+    obj: &DefaultDicomObject,
+    options: &MultiFrameOptions,
+) -> Result<Vec<DefaultDicomObject>> {
+    let pixel_data = obj.get(tags::PIXEL_DATA).context(MissingPixelDataSnafu)?;
+    let vr = pixel_data.header().vr;
+    let encapsulated = obj.number_of_fragments().is_some();
+    let number_of_frames = obj.number_of_frames().unwrap_or(1);
+
+    let original_sop_class_uid = obj.meta().media_storage_sop_class_uid();
+    let sop_class_uid = options.sop_class_uid.clone().unwrap_or_else(|| {
+        single_frame_sop_class(original_sop_class_uid)
+            .unwrap_or(original_sop_class_uid)
+            .to_string()
+    });
+    let series_instance_uid = options.series_instance_uid();
+
+    let mut template = obj.clone();
+    template.remove_element(tags::PIXEL_DATA);
+    for tag in MULTI_FRAME_ATTRIBUTES {
+        template.remove_element(tag);
+    }
+    if let Some(group) = obj
+        .get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)
+        .and_then(|e| e.items()?.first())
+    {
+        flatten_group(&mut template, group);
+    }
+    let per_frame_groups = obj
+        .get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+        .and_then(|e| e.items());
+
+    (0..number_of_frames)
+        .map(|frame| {
+            let data = frame_data(obj, frame)?;
+            let mut instance = template.clone();
+            if let Some(group) = per_frame_groups.and_then(|groups| groups.get(frame as usize)) {
+                flatten_group(&mut instance, group);
+            }
+
+            let sop_instance_uid = options.uid_generator.generate();
+            set_identification(
+                &mut instance,
+                &sop_class_uid,
+                &sop_instance_uid,
+                &series_instance_uid,
+                frame + 1,
+            );
+            instance.put(if encapsulated {
+                DataElement::new(
+                    tags::PIXEL_DATA,
+                    vr,
+                    DicomValue::PixelSequence(PixelFragmentSequence::new_fragments(vec![data])),
+                )
+            } else {
+                DataElement::new(tags::PIXEL_DATA, vr, PrimitiveValue::from(data))
+            });
+            Ok(instance)
+        })
+        .collect()
+}
+
+/// Merge a series of single-frame objects into one multi-frame object.
+///
+/// The instances are sorted by their _Instance Number_.
+/// They must have the same transfer syntax and image pixel description,
+/// of which the frames are taken as is.
+/// The other attributes of the merged object
+/// are taken from the first instance,
+/// save for those which are
+/// moved to the functional groups of each frame.
+///
+/// The merged object receives a new SOP instance UID
+/// and is placed in a new series
+/// (unless a series instance UID is given).
+pub fn merge_frames(
+    instances: &[DefaultDicomObject],
+    options: &MultiFrameOptions,
+) -> Result<DefaultDicomObject> {
+    let first = instances.first().context(NoInstancesSnafu)?;
+    let pixel_data = first.get(tags::PIXEL_DATA).context(MissingPixelDataSnafu)?;
+    let vr = pixel_data.header().vr;
+    let encapsulated = first.number_of_fragments().is_some();
+
+    for (index, obj) in instances.iter().enumerate() {
+        ensure!(
+            obj.number_of_frames().unwrap_or(1) <= 1,
+            UnexpectedMultiFrameSnafu { index }
+        );
+        ensure!(
+            obj.meta().transfer_syntax() == first.meta().transfer_syntax(),
+            InconsistentSnafu {
+                index,
+                attribute: "TransferSyntaxUID",
+            }
+        );
+        for (tag, attribute) in IMAGE_PIXEL_ATTRIBUTES {
+            ensure!(
+                obj.get(tag).and_then(|e| e.to_str().ok())
+                    == first.get(tag).and_then(|e| e.to_str().ok()),
+                InconsistentSnafu { index, attribute }
+            );
+        }
+    }
+
+    let mut instances: Vec<_> = instances.iter().collect();
+    instances.sort_by_key(|obj| {
+        obj.get(tags::INSTANCE_NUMBER)
+            .and_then(|e| e.to_int::<i32>().ok())
+    });
+    let first = instances[0];
+
+    let mut merged = first.clone();
+    for tag in INSTANCE_ATTRIBUTES {
+        merged.remove_element(tag);
+    }
+
+    // place grouped attributes in their functional group macros
+    let mut shared_group = InMemDicomObject::new_empty();
+    let mut per_frame_groups = vec![InMemDicomObject::new_empty(); instances.len()];
+    let mut grouped = BTreeSet::new();
+    for &(sequence, attributes, per_frame_only) in FUNCTIONAL_GROUPS {
+        grouped.extend(attributes.iter().copied());
+        let values: Vec<Vec<InMemElement>> = instances
+            .iter()
+            .map(|obj| {
+                attributes
+                    .iter()
+                    .filter_map(|tag| obj.get(*tag).cloned())
+                    .collect()
+            })
+            .collect();
+        for tag in attributes {
+            merged.remove_element(*tag);
+        }
+        if values.iter().all(Vec::is_empty) {
+            continue;
+        }
+        if !per_frame_only && values.iter().all(|v| *v == values[0]) {
+            shared_group.put(group_element(sequence, values[0].clone()));
+        } else {
+            for (group, elements) in per_frame_groups.iter_mut().zip(values) {
+                group.put(group_element(sequence, elements));
+            }
+        }
+    }
+
+    // keep the remaining attributes which change between instances
+    let all_tags: BTreeSet<Tag> = instances
+        .iter()
+        .flat_map(|obj| obj.tags())
+        .filter(|tag| !grouped.contains(tag) && !INSTANCE_ATTRIBUTES.contains(tag))
+        .collect();
+    let unassigned: Vec<Tag> = all_tags
+        .into_iter()
+        .filter(|tag| {
+            let value = first.get(*tag);
+            instances.iter().any(|obj| obj.get(*tag) != value)
+        })
+        .collect();
+    if !unassigned.is_empty() {
+        for (group, obj) in per_frame_groups.iter_mut().zip(&instances) {
+            let elements = unassigned
+                .iter()
+                .filter_map(|tag| obj.get(*tag).cloned())
+                .collect();
+            group.put(group_element(
+                tags::UNASSIGNED_PER_FRAME_CONVERTED_ATTRIBUTES_SEQUENCE,
+                elements,
+            ));
+        }
+        for tag in &unassigned {
+            merged.remove_element(*tag);
+        }
+    }
+
+    merged.put(DataElement::new(
+        tags::NUMBER_OF_FRAMES,
+        VR::IS,
+        instances.len().to_string(),
+    ));
+    merged.put(DataElement::new(
+        tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+        VR::SQ,
+        DataSetSequence::from(vec![shared_group]),
+    ));
+    merged.put(DataElement::new(
+        tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+        VR::SQ,
+        DataSetSequence::from(per_frame_groups),
+    ));
+
+    let original_sop_class_uid = first.meta().media_storage_sop_class_uid();
+    let sop_class_uid = options.sop_class_uid.clone().unwrap_or_else(|| {
+        multi_frame_sop_class(original_sop_class_uid)
+            .unwrap_or(original_sop_class_uid)
+            .to_string()
+    });
+    let sop_instance_uid = options.uid_generator.generate();
+    set_identification(
+        &mut merged,
+        &sop_class_uid,
+        &sop_instance_uid,
+        &options.series_instance_uid(),
+        1,
+    );
+
+    let frames = instances
+        .iter()
+        .map(|obj| frame_data(obj, 0))
+        .collect::<Result<Vec<_>>>()?;
+    merged.put(if encapsulated {
+        // one fragment per frame,
+        // indexed by the basic offset table
+        let mut offset_table = Vec::with_capacity(frames.len());
+        let mut offset = 0;
+        for frame in &frames {
+            offset_table.push(offset);
+            offset += frame.len() as u32 + 8;
+        }
+        DataElement::new(
+            tags::PIXEL_DATA,
+            vr,
+            DicomValue::PixelSequence(PixelFragmentSequence::new(offset_table, frames)),
+        )
+    } else {
+        DataElement::new(tags::PIXEL_DATA, vr, PrimitiveValue::from(frames.concat()))
+    });
+
+    Ok(merged)
+}
+
+/// Move the attributes of a functional groups item
+/// to the root of the data set.
+///
+/// The attributes of each functional group macro are taken
+/// from the first item of its sequence,
+/// and attributes found directly in the item are moved as is.
+fn flatten_group(obj: &mut DefaultDicomObject, group: &InMemDicomObject) {
+    for element in group.iter() {
+        match element.items() {
+            Some(items) if element.header().vr == VR::SQ => {
+                for inner in items.iter().take(1).flat_map(|item| item.iter()) {
+                    if inner.header().tag != tags::DIMENSION_INDEX_VALUES {
+                        obj.put(inner.clone());
+                    }
+                }
+            }
+            _ => {
+                obj.put(element.clone());
+            }
+        }
+    }
+}
+
+/// Create a functional group macro sequence with a single item.
+fn group_element(sequence: Tag, elements: Vec<InMemElement>) -> InMemElement {
+    DataElement::new(
+        sequence,
+        VR::SQ,
+        DataSetSequence::from(vec![InMemDicomObject::from_element_iter(elements)]),
+    )
+}
+
+/// Set the SOP common and instance identification attributes,
+/// both in the data set and in the file meta group.
+fn set_identification(
+    obj: &mut DefaultDicomObject,
+    sop_class_uid: &str,
+    sop_instance_uid: &str,
+    series_instance_uid: &str,
+    instance_number: u32,
+) {
+    obj.put(DataElement::new(tags::SOP_CLASS_UID, VR::UI, sop_class_uid));
+    obj.put(DataElement::new(
+        tags::SOP_INSTANCE_UID,
+        VR::UI,
+        sop_instance_uid,
+    ));
+    obj.put(DataElement::new(
+        tags::SERIES_INSTANCE_UID,
+        VR::UI,
+        series_instance_uid,
+    ));
+    obj.put(DataElement::new(
+        tags::INSTANCE_NUMBER,
+        VR::IS,
+        instance_number.to_string(),
+    ));
+    obj.update_meta(|meta| {
+        meta.media_storage_sop_class_uid = sop_class_uid.to_string();
+        meta.media_storage_sop_instance_uid = sop_instance_uid.to_string();
+    });
+}
+
+/// Fetch the data of a frame in its encoded form,
+/// padded to an even length in the case of encapsulated pixel data.
+fn frame_data(obj: &DefaultDicomObject, frame: u32) -> Result<Vec<u8>> {
+    if obj.number_of_fragments().is_some() {
+        let mut data = obj
+            .frame_pixel_data(frame)
+            .context(MissingFrameSnafu { frame })?
+            .into_owned();
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        return Ok(data);
+    }
+
+    let [rows, columns, samples_per_pixel, bits_allocated] = [
+        (obj.rows(), "Rows"),
+        (obj.cols(), "Columns"),
+        (obj.samples_per_pixel(), "SamplesPerPixel"),
+        (obj.bits_allocated(), "BitsAllocated"),
+    ]
+    .map(|(value, attribute)| value.context(MissingAttributeSnafu { attribute }));
+    let bits =
+        rows? as usize * columns? as usize * samples_per_pixel? as usize * bits_allocated? as usize;
+    ensure!(bits % 8 == 0, UnalignedFrameSnafu { bits });
+    let frame_size = bits / 8;
+
+    let data = obj.fragment(0).context(MissingPixelDataSnafu)?;
+    let start = frame as usize * frame_size;
+    Ok(data
+        .get(start..start + frame_size)
+        .context(MissingFrameSnafu { frame })?
+        .to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::dicom_value;
+    use dicom_object::FileMetaTableBuilder;
+
+    /// Create an enhanced CT object of 3 frames of 2x2 pixels,
+    /// with a shared pixel spacing and a position per frame.
+    fn enhanced_ct() -> DefaultDicomObject {
+        let group = |elements: Vec<InMemElement>| InMemDicomObject::from_element_iter(elements);
+        let per_frame = (0..3)
+            .map(|i| {
+                group(vec![
+                    group_element(
+                        tags::PLANE_POSITION_SEQUENCE,
+                        vec![DataElement::new(
+                            tags::IMAGE_POSITION_PATIENT,
+                            VR::DS,
+                            dicom_value!(Strs, ["0", "0", &(i * 5).to_string()]),
+                        )],
+                    ),
+                    group_element(
+                        tags::FRAME_CONTENT_SEQUENCE,
+                        vec![
+                            DataElement::new(
+                                tags::IN_STACK_POSITION_NUMBER,
+                                VR::UL,
+                                PrimitiveValue::from(i as u32 + 1),
+                            ),
+                            DataElement::new(
+                                tags::DIMENSION_INDEX_VALUES,
+                                VR::UL,
+                                PrimitiveValue::from(i as u32 + 1),
+                            ),
+                        ],
+                    ),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let shared = group(vec![group_element(
+            tags::PIXEL_MEASURES_SEQUENCE,
+            vec![DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_value!(Strs, ["0.5", "0.5"]),
+            )],
+        )]);
+
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::ENHANCED_CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.10"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "2.25.11"),
+            DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, PrimitiveValue::from(1_u16)),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "3"),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::COLUMNS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::BITS_STORED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::HIGH_BIT, VR::US, PrimitiveValue::from(7_u16)),
+            DataElement::new(
+                tags::PIXEL_REPRESENTATION,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ),
+            DataElement::new(
+                tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![shared]),
+            ),
+            DataElement::new(
+                tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(per_frame),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from((0..12).collect::<Vec<u8>>()),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::ENHANCED_CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.10"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn split_and_merge_frames() {
+        let obj = enhanced_ct();
+        let slices = split_frames(&obj, &MultiFrameOptions::new()).unwrap();
+        assert_eq!(slices.len(), 3);
+
+        let str_of =
+            |obj: &DefaultDicomObject, tag| obj.get(tag).map(|e| e.to_str().unwrap().to_string());
+        let series_uid = str_of(&slices[0], tags::SERIES_INSTANCE_UID).unwrap();
+        assert_ne!(series_uid, "2.25.11");
+        for (i, slice) in slices.iter().enumerate() {
+            assert_eq!(
+                slice.meta().media_storage_sop_class_uid(),
+                uids::CT_IMAGE_STORAGE
+            );
+            assert_eq!(
+                str_of(slice, tags::SOP_INSTANCE_UID).unwrap(),
+                slice.meta().media_storage_sop_instance_uid()
+            );
+            assert_eq!(
+                str_of(slice, tags::SERIES_INSTANCE_UID).unwrap(),
+                series_uid
+            );
+            assert_eq!(
+                str_of(slice, tags::INSTANCE_NUMBER).unwrap(),
+                (i + 1).to_string()
+            );
+            assert_eq!(str_of(slice, tags::PATIENT_ID).unwrap(), "12345");
+            assert_eq!(str_of(slice, tags::PIXEL_SPACING).unwrap(), "0.5\\0.5");
+            assert_eq!(
+                str_of(slice, tags::IMAGE_POSITION_PATIENT).unwrap(),
+                format!("0\\0\\{}", i * 5)
+            );
+            for tag in [
+                tags::NUMBER_OF_FRAMES,
+                tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+                tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+                tags::DIMENSION_INDEX_VALUES,
+            ] {
+                assert!(slice.get(tag).is_none());
+            }
+            let data = slice.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+            assert_eq!(
+                &*data,
+                &[
+                    4 * i as u8,
+                    4 * i as u8 + 1,
+                    4 * i as u8 + 2,
+                    4 * i as u8 + 3
+                ]
+            );
+        }
+
+        // merge them back, in a different order
+        let reversed: Vec<_> = slices.into_iter().rev().collect();
+        let merged = merge_frames(&reversed, &MultiFrameOptions::new()).unwrap();
+        assert_eq!(
+            merged.meta().media_storage_sop_class_uid(),
+            uids::LEGACY_CONVERTED_ENHANCED_CT_IMAGE_STORAGE
+        );
+        assert_eq!(str_of(&merged, tags::NUMBER_OF_FRAMES).unwrap(), "3");
+        assert_eq!(str_of(&merged, tags::PATIENT_ID).unwrap(), "12345");
+        for tag in [
+            tags::PIXEL_SPACING,
+            tags::IMAGE_POSITION_PATIENT,
+            tags::IN_STACK_POSITION_NUMBER,
+        ] {
+            assert!(merged.get(tag).is_none());
+        }
+        let data = merged.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+        assert_eq!(&*data, &(0..12).collect::<Vec<u8>>()[..]);
+
+        let shared = &merged
+            .get(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        assert!(shared.get(tags::PIXEL_MEASURES_SEQUENCE).is_some());
+        assert!(shared.get(tags::PLANE_POSITION_SEQUENCE).is_none());
+
+        let per_frame = merged
+            .get(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert_eq!(per_frame.len(), 3);
+        for (i, group) in per_frame.iter().enumerate() {
+            let position = group
+                .get(tags::PLANE_POSITION_SEQUENCE)
+                .unwrap()
+                .items()
+                .unwrap()[0]
+                .get(tags::IMAGE_POSITION_PATIENT)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            assert_eq!(position, format!("0\\0\\{}", i * 5));
+            assert!(group.get(tags::FRAME_CONTENT_SEQUENCE).is_some());
+            // only the identification of each instance differs
+            assert!(
+                group
+                    .get(tags::UNASSIGNED_PER_FRAME_CONVERTED_ATTRIBUTES_SEQUENCE)
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn merge_inconsistent_frames() {
+        let mut slices = split_frames(&enhanced_ct(), &MultiFrameOptions::new()).unwrap();
+        slices[1].put(DataElement::new(
+            tags::COLUMNS,
+            VR::US,
+            PrimitiveValue::from(1_u16),
+        ));
+        let err = merge_frames(&slices, &MultiFrameOptions::new()).unwrap_err();
+        assert_eq!(err.to_string(), "Instance #1 has a different Columns");
+
+        let err = merge_frames(&[], &MultiFrameOptions::new()).unwrap_err();
+        assert_eq!(err.to_string(), "No instances to merge");
+    }
+}
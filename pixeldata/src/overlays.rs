@@ -0,0 +1,447 @@
+//! Overlay plane support
+//!
+//! Overlays are bitmaps described in the repeating groups
+//! `(6000,xxxx)` to `(601E,xxxx)` of an image,
+//! usually holding annotations or regions of interest
+//! to be shown on top of the pixel data.
+//! This module parses them into [`Overlay`] values,
+//! which expose the bitmap of each overlay frame
+//! and can optionally burn them into a converted image
+//! (requires the `image` feature).
+//!
+//! Both the standard form,
+//! with the bitmap in _Overlay Data_ (60xx,3000),
+//! and the retired form embedded in the unused high bits
+//! of native pixel data are supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::overlays::read_overlays;
+//!
+//! let obj = open_file("image.dcm")?;
+//! for overlay in read_overlays(&obj)? {
+//!     let bitmap = overlay.frame_bitmap(0).expect("overlay should apply to frame #0");
+//!     let set = bitmap.iter().filter(|&&bit| bit != 0).count();
+//!     println!(
+//!         "overlay {:04X}: {}x{} at {:?}, {} pixels set",
+//!         overlay.group, overlay.columns, overlay.rows, overlay.origin, set
+//!     );
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_core::{DataDictionary, PrimitiveValue, Tag, dictionary::TagRange};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+/// An error occurred while reading the overlay planes of an object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Could not convert attribute {tag} of overlay group {group:04X}
+    ConvertValue {
+        group: u16,
+        tag: Tag,
+        #[snafu(source(from(dicom_core::value::ConvertValueError, Box::from)))]
+        source: Box<dicom_core::value::ConvertValueError>,
+    },
+
+    /// Invalid overlay origin in overlay group {group:04X}
+    InvalidOrigin { group: u16 },
+
+    /// Missing overlay data in overlay group {group:04X}
+    MissingOverlayData { group: u16 },
+
+    /// Overlay data in overlay group {group:04X} is too short
+    /// for {frames} frame(s) of {rows}x{columns} pixels
+    NotEnoughData {
+        group: u16,
+        rows: u16,
+        columns: u16,
+        frames: u32,
+    },
+}
+
+/// Alias for the result of overlay plane operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The kind of content in an overlay plane,
+/// as given by _Overlay Type_ (60xx,0040).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OverlayType {
+    /// `G`: graphics, such as annotations
+    Graphics,
+    /// `R`: a region of interest
+    Roi,
+    /// Any other overlay type
+    Other(String),
+}
+
+/// A decoded overlay plane of a DICOM image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    /// The group of the overlay attributes, from `0x6000` to `0x601E`
+    pub group: u16,
+    /// The number of rows in the overlay
+    pub rows: u16,
+    /// The number of columns in the overlay
+    pub columns: u16,
+    /// The position of the overlay's top left pixel
+    /// relative to the image, as a pair of 1-based row and column
+    pub origin: (i32, i32),
+    /// The kind of content in the overlay
+    pub overlay_type: OverlayType,
+    /// The overlay subtype, if defined
+    pub subtype: Option<String>,
+    /// The overlay label, if defined
+    pub label: Option<String>,
+    /// The overlay description, if defined
+    pub description: Option<String>,
+    /// The number of frames in the overlay
+    pub number_of_frames: u32,
+    /// The 1-based number of the first image frame
+    /// to which the overlay applies
+    pub image_frame_origin: u32,
+    /// one byte per overlay pixel, `1` if set and `0` otherwise,
+    /// for all overlay frames
+    bitmap: Vec<u8>,
+}
+
+impl Overlay {
+    /// Retrieve the bitmap of the overlay
+    /// which applies to the given 0-based image frame,
+    /// with one byte per overlay pixel in row-major order,
+    /// which is `1` if set and `0` otherwise.
+    ///
+    /// Returns `None` if the overlay does not apply to the frame.
+    pub fn frame_bitmap(&self, image_frame: u32) -> Option<&[u8]> {
+        let frame = (image_frame + 1).checked_sub(self.image_frame_origin)?;
+        if frame >= self.number_of_frames {
+            return None;
+        }
+        let len = self.rows as usize * self.columns as usize;
+        self.bitmap
+            .get(frame as usize * len..(frame as usize + 1) * len)
+    }
+
+    /// Check whether the overlay pixel at the given 0-based row and column
+    /// of the given 0-based image frame is set.
+    pub fn is_set(&self, image_frame: u32, row: u16, column: u16) -> bool {
+        row < self.rows
+            && column < self.columns
+            && self.frame_bitmap(image_frame).is_some_and(|bitmap| {
+                bitmap[row as usize * self.columns as usize + column as usize] != 0
+            })
+    }
+
+    /// Burn the overlay into an image of the given 0-based image frame,
+    /// painting the pixels which are set in white.
+    ///
+    /// Overlay pixels outside of the image are ignored.
+    #[cfg(feature = "image")]
+    pub fn burn_into(&self, image: &mut image::DynamicImage, image_frame: u32) {
+        use image::GenericImage;
+
+        let Some(bitmap) = self.frame_bitmap(image_frame) else {
+            return;
+        };
+        let (width, height) = (image.width() as i64, image.height() as i64);
+        let white = image::Rgba([0xFF, 0xFF, 0xFF, 0xFF]);
+        for (i, _) in bitmap.iter().enumerate().filter(|(_, bit)| **bit != 0) {
+            let y = self.origin.0 as i64 - 1 + (i / self.columns as usize) as i64;
+            let x = self.origin.1 as i64 - 1 + (i % self.columns as usize) as i64;
+            if (0..width).contains(&x) && (0..height).contains(&y) {
+                image.put_pixel(x as u32, y as u32, white);
+            }
+        }
+    }
+}
+
+/// Retrieve an attribute of the given overlay group.
+fn get<D>(obj: &InMemDicomObject<D>, group: u16, range: TagRange) -> Option<&PrimitiveValue>
+where
+    D: DataDictionary + Clone,
+{
+    obj.get(Tag(group, range.inner().element()))
+        .and_then(|e| e.value().primitive())
+}
+
+/// Retrieve a string attribute of the given overlay group,
+/// without padding.
+fn get_str<D>(obj: &InMemDicomObject<D>, group: u16, range: TagRange) -> Option<String>
+where
+    D: DataDictionary + Clone,
+{
+    get(obj, group, range)
+        .map(|v| {
+            v.to_str()
+                .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                .to_string()
+        })
+        .filter(|s| !s.is_empty())
+}
+
+/// Retrieve an integer attribute of the given overlay group.
+fn get_int<D, T>(obj: &InMemDicomObject<D>, group: u16, range: TagRange) -> Result<Option<T>>
+where
+    D: DataDictionary + Clone,
+    T: num_traits::NumCast + std::str::FromStr<Err = std::num::ParseIntError>,
+{
+    get(obj, group, range)
+        .map(|v| v.to_int())
+        .transpose()
+        .context(ConvertValueSnafu {
+            group,
+            tag: Tag(group, range.inner().element()),
+        })
+        .map_err(Error::from)
+}
+
+/// Unpack the bits of overlay data, least significant bit first.
+fn unpack_bits(data: &[u8], len: usize) -> Vec<u8> {
+    data.iter()
+        .flat_map(|&byte| (0..8).map(move |bit| (byte >> bit) & 1))
+        .take(len)
+        .collect()
+}
+
+/// Read all overlay planes of a DICOM object,
+/// in ascending group order.
+///
+/// Overlays embedded in the pixel data
+/// can only be read from native pixel data.
+pub fn read_overlays<D>(obj: &InMemDicomObject<D>) -> Result<Vec<Overlay>>
+where
+    D: DataDictionary + Clone,
+{
+    (0x6000..=0x601E)
+        .step_by(2)
+        .filter_map(|group| overlay(obj, group).transpose())
+        .collect()
+}
+
+/// Read the overlay plane in the given group,
+/// returning `None` if the group has no overlay plane.
+fn overlay<D>(obj: &InMemDicomObject<D>, group: u16) -> Result<Option<Overlay>>
+where
+    D: DataDictionary + Clone,
+{
+    let (Some(rows), Some(columns)) = (
+        get_int::<_, u16>(obj, group, tags::OVERLAY_ROWS)?,
+        get_int::<_, u16>(obj, group, tags::OVERLAY_COLUMNS)?,
+    ) else {
+        return Ok(None);
+    };
+
+    let origin = match get(obj, group, tags::OVERLAY_ORIGIN) {
+        Some(v) => {
+            let origin: Vec<i32> = v.to_multi_int().context(ConvertValueSnafu {
+                group,
+                tag: Tag(group, tags::OVERLAY_ORIGIN.inner().element()),
+            })?;
+            ensure!(origin.len() == 2, InvalidOriginSnafu { group });
+            (origin[0], origin[1])
+        }
+        None => (1, 1),
+    };
+    let overlay_type = match get_str(obj, group, tags::OVERLAY_TYPE).as_deref() {
+        Some("R") => OverlayType::Roi,
+        Some("G") | None => OverlayType::Graphics,
+        Some(other) => OverlayType::Other(other.to_string()),
+    };
+    let number_of_frames: u32 =
+        get_int(obj, group, tags::NUMBER_OF_FRAMES_IN_OVERLAY)?.unwrap_or(1);
+    let image_frame_origin: u32 = get_int(obj, group, tags::IMAGE_FRAME_ORIGIN)?.unwrap_or(1);
+
+    let len = rows as usize * columns as usize * number_of_frames as usize;
+    let bitmap = match obj.get(Tag(group, tags::OVERLAY_DATA.inner().element())) {
+        Some(e) => {
+            let data = match e.value().primitive() {
+                // words in little endian
+                Some(PrimitiveValue::U16(words)) => {
+                    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+                }
+                Some(v) => v.to_bytes().into_owned(),
+                None => {
+                    return MissingOverlayDataSnafu { group }
+                        .fail()
+                        .map_err(Error::from);
+                }
+            };
+            unpack_bits(&data, len)
+        }
+        None => embedded_overlay(obj, group, len)?.context(MissingOverlayDataSnafu { group })?,
+    };
+    ensure!(
+        bitmap.len() == len,
+        NotEnoughDataSnafu {
+            group,
+            rows,
+            columns,
+            frames: number_of_frames,
+        }
+    );
+
+    Ok(Some(Overlay {
+        group,
+        rows,
+        columns,
+        origin,
+        overlay_type,
+        subtype: get_str(obj, group, tags::OVERLAY_SUBTYPE),
+        label: get_str(obj, group, tags::OVERLAY_LABEL),
+        description: get_str(obj, group, tags::OVERLAY_DESCRIPTION),
+        number_of_frames,
+        image_frame_origin,
+        bitmap,
+    }))
+}
+
+/// Extract a retired overlay plane
+/// from the unused high bits of native pixel data.
+fn embedded_overlay<D>(obj: &InMemDicomObject<D>, group: u16, len: usize) -> Result<Option<Vec<u8>>>
+where
+    D: DataDictionary + Clone,
+{
+    let bits_allocated: Option<u16> = get_int(obj, group, tags::OVERLAY_BITS_ALLOCATED)?;
+    let bit_position: Option<u16> = get_int(obj, group, tags::OVERLAY_BIT_POSITION)?;
+    let (Some(16), Some(bit_position @ 1..=15)) = (bits_allocated, bit_position) else {
+        return Ok(None);
+    };
+    let Some(data) = obj
+        .get(tags::PIXEL_DATA)
+        .and_then(|e| e.value().primitive())
+    else {
+        return Ok(None);
+    };
+    let samples: Vec<u16> = match data {
+        PrimitiveValue::U16(words) => words.to_vec(),
+        v => v
+            .to_bytes()
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect(),
+    };
+    Ok(Some(
+        samples
+            .iter()
+            .map(|&v| ((v >> bit_position) & 1) as u8)
+            .take(len)
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, VR};
+
+    fn us(group: u16, range: TagRange, value: u16) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            Tag(group, range.inner().element()),
+            VR::US,
+            PrimitiveValue::from(value),
+        )
+    }
+
+    #[test]
+    fn read_overlay_data() {
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put(us(0x6002, tags::OVERLAY_ROWS, 2));
+        obj.put(us(0x6002, tags::OVERLAY_COLUMNS, 5));
+        obj.put(us(0x6002, tags::OVERLAY_BITS_ALLOCATED, 1));
+        obj.put(us(0x6002, tags::OVERLAY_BIT_POSITION, 0));
+        obj.put(DataElement::new(
+            Tag(0x6002, 0x0050),
+            VR::SS,
+            PrimitiveValue::from([2_i16, 3]),
+        ));
+        obj.put(DataElement::new(Tag(0x6002, 0x0040), VR::CS, "R "));
+        obj.put(DataElement::new(Tag(0x6002, 0x1500), VR::LO, "ROI "));
+        // bits 0, 4, 5 and 9 set
+        obj.put(DataElement::new(
+            Tag(0x6002, 0x3000),
+            VR::OW,
+            PrimitiveValue::from(0b10_0011_0001_u16),
+        ));
+        // group without overlay data
+        obj.put(us(0x6004, tags::OVERLAY_ROWS, 2));
+        obj.put(us(0x6004, tags::OVERLAY_COLUMNS, 5));
+
+        assert!(read_overlays(&obj).is_err());
+        obj.remove_element(Tag(0x6004, 0x0010));
+
+        let overlays = read_overlays(&obj).unwrap();
+        assert_eq!(overlays.len(), 1);
+        let overlay = &overlays[0];
+        assert_eq!(overlay.group, 0x6002);
+        assert_eq!(overlay.origin, (2, 3));
+        assert_eq!(overlay.overlay_type, OverlayType::Roi);
+        assert_eq!(overlay.label.as_deref(), Some("ROI"));
+        assert_eq!(overlay.description, None);
+        assert_eq!(
+            overlay.frame_bitmap(0),
+            Some(&[1, 0, 0, 0, 1, 1, 0, 0, 0, 1][..])
+        );
+        assert_eq!(overlay.frame_bitmap(1), None);
+        assert!(overlay.is_set(0, 1, 4));
+        assert!(!overlay.is_set(0, 1, 3));
+        assert!(!overlay.is_set(0, 2, 0));
+
+        #[cfg(feature = "image")]
+        {
+            let mut image = image::DynamicImage::new_luma8(6, 3);
+            overlay.burn_into(&mut image, 0);
+            let image = image.to_luma8();
+            let set: Vec<_> = image
+                .enumerate_pixels()
+                .filter(|(_, _, p)| p.0[0] == 0xFF)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            // shifted by the origin, clipped to the image
+            assert_eq!(set, vec![(2, 1), (2, 2)]);
+        }
+    }
+
+    #[test]
+    fn read_multiframe_and_embedded_overlays() {
+        let mut obj = InMemDicomObject::new_empty();
+        // 2 frames of 2x2, starting at image frame #2
+        obj.put(us(0x6000, tags::OVERLAY_ROWS, 2));
+        obj.put(us(0x6000, tags::OVERLAY_COLUMNS, 2));
+        obj.put(DataElement::new(Tag(0x6000, 0x0015), VR::IS, "2"));
+        obj.put(us(0x6000, tags::IMAGE_FRAME_ORIGIN, 2));
+        obj.put(DataElement::new(
+            Tag(0x6000, 0x3000),
+            VR::OB,
+            PrimitiveValue::from(vec![0b1000_0110_u8, 0]),
+        ));
+        // embedded in bit 12 of the pixel data
+        obj.put(us(0x6010, tags::OVERLAY_ROWS, 1));
+        obj.put(us(0x6010, tags::OVERLAY_COLUMNS, 3));
+        obj.put(us(0x6010, tags::OVERLAY_BITS_ALLOCATED, 16));
+        obj.put(us(0x6010, tags::OVERLAY_BIT_POSITION, 12));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0x1FFF, 0x0FFF, 0x1000].into()),
+        ));
+
+        let overlays = read_overlays(&obj).unwrap();
+        assert_eq!(overlays.len(), 2);
+
+        let overlay = &overlays[0];
+        assert_eq!(overlay.frame_bitmap(0), None);
+        assert_eq!(overlay.frame_bitmap(1), Some(&[0, 1, 1, 0][..]));
+        assert_eq!(overlay.frame_bitmap(2), Some(&[0, 0, 0, 1][..]));
+        assert_eq!(overlay.frame_bitmap(3), None);
+
+        let overlay = &overlays[1];
+        assert_eq!(overlay.group, 0x6010);
+        assert_eq!(overlay.frame_bitmap(0), Some(&[1, 0, 1][..]));
+    }
+}
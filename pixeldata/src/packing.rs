@@ -0,0 +1,176 @@
+//! Bit-packed native pixel data
+//!
+//! Native pixel data is bit-packed when _Bits Allocated_
+//! is not a multiple of 8:
+//! with 1 bit allocated (as in binary segmentations and overlays),
+//! or with the 12 bits allocated of older (retired) encodings.
+//! The samples then form a continuous stream of bits,
+//! from the least significant bit of each byte,
+//! without any padding between pixels, rows, or frames,
+//! so that two 12-bit samples take three bytes.
+//!
+//! Decoding pixel data unpacks it
+//! into one byte per sample for 1 bit allocated
+//! (with the values 0 and 1)
+//! and into 16 bits per sample for 12 bits allocated.
+//! The functions in this module do the same for any bit-packed buffer,
+//! and pack samples back for writing.
+//!
+//! # Example
+//!
+//! ```
+//! use dicom_pixeldata::packing::{pack_samples, unpack_samples};
+//!
+//! let packed = pack_samples([0x123, 0xABC], 12);
+//! assert_eq!(packed, [0x23, 0xC1, 0xAB, 0x00]);
+//! assert_eq!(unpack_samples(&packed, 12, 0, 2), Some(vec![0x123, 0xABC]));
+//! ```
+
+/// Check whether native pixel data with the given _Bits Allocated_
+/// is bit-packed.
+pub fn is_packed(bits_allocated: u16) -> bool {
+    (1..16).contains(&bits_allocated) && bits_allocated % 8 != 0
+}
+
+/// The _Bits Allocated_ of bit-packed pixel data once unpacked:
+/// 8 for up to 8 bits allocated, 16 otherwise.
+pub fn unpacked_bits_allocated(bits_allocated: u16) -> u16 {
+    if bits_allocated <= 8 { 8 } else { 16 }
+}
+
+/// Extract `len` samples of `bits_allocated` bits each
+/// from bit-packed data,
+/// starting at sample `offset`.
+///
+/// Returns `None` if the data is too short,
+/// or if `bits_allocated` is not between 1 and 16.
+pub fn unpack_samples(
+    data: &[u8],
+    bits_allocated: u16,
+    offset: usize,
+    len: usize,
+) -> Option<Vec<u16>> {
+    if !(1..=16).contains(&bits_allocated) {
+        return None;
+    }
+    let bits = bits_allocated as usize;
+    let end = (offset + len) * bits;
+    if end.div_ceil(8) > data.len() {
+        return None;
+    }
+    let mask = ((1u32 << bits) - 1) as u16;
+    Some(
+        (offset..offset + len)
+            .map(|i| {
+                let start = i * bits;
+                // the sample spans at most 3 bytes
+                let word = (0..3)
+                    .filter_map(|k| data.get(start / 8 + k))
+                    .enumerate()
+                    .fold(0u32, |word, (k, byte)| word | (u32::from(*byte) << (8 * k)));
+                (word >> (start % 8)) as u16 & mask
+            })
+            .collect(),
+    )
+}
+
+/// Pack samples into a bit stream of `bits_allocated` bits per sample,
+/// ignoring any higher bits of each sample.
+///
+/// The output is padded with zeros to an even length,
+/// as required for the value of the _Pixel Data_ attribute.
+///
+/// # Panics
+///
+/// Panics if `bits_allocated` is not between 1 and 16.
+pub fn pack_samples(samples: impl IntoIterator<Item = u16>, bits_allocated: u16) -> Vec<u8> {
+    assert!((1..=16).contains(&bits_allocated));
+    let bits = bits_allocated as usize;
+    let mask = (1u32 << bits) - 1;
+    let mut out = Vec::new();
+    for (i, sample) in samples.into_iter().enumerate() {
+        let start = i * bits;
+        let end = (start + bits).div_ceil(8);
+        if out.len() < end {
+            out.resize(end, 0);
+        }
+        let word = (u32::from(sample) & mask) << (start % 8);
+        for (k, byte) in out[start / 8..end].iter_mut().enumerate() {
+            *byte |= (word >> (8 * k)) as u8;
+        }
+    }
+    if out.len() % 2 == 1 {
+        out.push(0);
+    }
+    out
+}
+
+/// Extract `len` single-bit samples (0 or 1)
+/// from bit-packed data with 1 bit allocated,
+/// starting at sample `offset`.
+///
+/// Returns `None` if the data is too short.
+pub fn unpack_bits(data: &[u8], offset: usize, len: usize) -> Option<Vec<u8>> {
+    if (offset + len).div_ceil(8) > data.len() {
+        return None;
+    }
+    Some(
+        (offset..offset + len)
+            .map(|i| (data[i / 8] >> (i % 8)) & 1)
+            .collect(),
+    )
+}
+
+/// Pack binary samples into a bit stream with 1 bit allocated,
+/// padded with zeros to an even length.
+pub fn pack_bits(samples: impl IntoIterator<Item = bool>) -> Vec<u8> {
+    pack_samples(samples.into_iter().map(u16::from), 1)
+}
+
+/// Unpack `len` samples starting at sample `offset`
+/// into native bytes of the unpacked bits allocated.
+pub(crate) fn unpack_native(
+    data: &[u8],
+    bits_allocated: u16,
+    offset: usize,
+    len: usize,
+) -> Option<Vec<u8>> {
+    if bits_allocated == 1 {
+        return unpack_bits(data, offset, len);
+    }
+    let samples = unpack_samples(data, bits_allocated, offset, len)?;
+    Some(if unpacked_bits_allocated(bits_allocated) == 8 {
+        samples.into_iter().map(|v| v as u8).collect()
+    } else {
+        samples.into_iter().flat_map(u16::to_ne_bytes).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_and_unpack() {
+        let bits = pack_bits([true, false, true, true, false, false, false, false, true]);
+        assert_eq!(bits, [0b0000_1101, 0b0000_0001]);
+        assert_eq!(unpack_bits(&bits, 2, 3), Some(vec![1, 1, 0]));
+        assert_eq!(unpack_bits(&bits, 8, 9), None);
+
+        let samples = [0x000, 0xFFF, 0x123, 0x800, 0x07F];
+        let packed = pack_samples(samples, 12);
+        // 60 bits, rounded up to 8 bytes
+        assert_eq!(packed.len(), 8);
+        assert_eq!(unpack_samples(&packed, 12, 0, 5).unwrap(), samples);
+        assert_eq!(unpack_samples(&packed, 12, 3, 2).unwrap(), [0x800, 0x07F]);
+        assert_eq!(unpack_samples(&packed, 12, 3, 3), None);
+
+        // unpacked native bytes
+        assert_eq!(
+            unpack_native(&packed, 12, 1, 1).unwrap(),
+            0xFFF_u16.to_ne_bytes()
+        );
+        assert!(is_packed(1) && is_packed(12));
+        assert!(!is_packed(8) && !is_packed(16) && !is_packed(32));
+    }
+}
@@ -0,0 +1,1150 @@
+//! Grayscale softcopy presentation state support
+//!
+//! A [Grayscale Softcopy Presentation State][1] (GSPS)
+//! is a separate DICOM object describing how one or more images
+//! should be displayed:
+//! which modality, VOI and presentation LUTs apply,
+//! how the image is flipped and rotated,
+//! which area of the image is displayed,
+//! and which graphic and text annotations are drawn over it.
+//! This module parses these attributes into a [`PresentationState`],
+//! which can then render an image accordingly
+//! (requires the `image` feature),
+//! through a [`RenderingPipeline`](crate::RenderingPipeline).
+//!
+//! Shutters, bitmap overlays referenced by the presentation state,
+//! VOI LUT tables,
+//! and the true size presentation mode are not supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::{PixelDecoder as _, presentation_state::PresentationState};
+//!
+//! let obj = open_file("image.dcm")?;
+//! let pr = PresentationState::from_object(&*open_file("pr.dcm")?)?;
+//! # #[cfg(feature = "image")]
+//! # {
+//! let sop_instance_uid = obj.meta().media_storage_sop_instance_uid();
+//! let image = pr.render(&obj.decode_pixel_data()?, sop_instance_uid, 0)?;
+//! image.save("rendered.png")?;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.33.html
+use dicom_core::{Tag, value::ConvertValueError};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use snafu::{ResultExt, Snafu, ensure};
+
+use crate::{Rescale, VoiLutFunction, WindowLevel, rendering::PresentationLutTable};
+
+/// An error occurred while reading or applying a presentation state.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Object of SOP class {sop_class_uid} is not a grayscale softcopy presentation state
+    NotPresentationState { sop_class_uid: String },
+
+    /// Could not convert attribute {tag}
+    ConvertValue {
+        tag: Tag,
+        #[snafu(source(from(ConvertValueError, Box::from)))]
+        source: Box<ConvertValueError>,
+    },
+
+    /// Graphic of type {graphic_type} has an invalid number of points ({points})
+    InvalidGraphicData { graphic_type: String, points: usize },
+
+    /// Could not convert pixel data to image
+    #[cfg(feature = "image")]
+    ConvertImage {
+        #[snafu(source(from(crate::Error, Box::from)))]
+        source: Box<crate::Error>,
+    },
+}
+
+/// Alias for the result of presentation state operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A reference to an image, or to some of its frames,
+/// to which part of a presentation state applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReference {
+    /// the SOP instance UID of the image
+    pub sop_instance_uid: String,
+    /// the 1-based frame numbers,
+    /// or empty if all frames are referenced
+    pub frames: Vec<u32>,
+}
+
+impl ImageReference {
+    /// Check whether this reference includes
+    /// the given 0-based frame of the given image.
+    pub fn matches(&self, sop_instance_uid: &str, frame: u32) -> bool {
+        self.sop_instance_uid == sop_instance_uid
+            && (self.frames.is_empty() || self.frames.contains(&(frame + 1)))
+    }
+}
+
+/// A window to apply to some of the images of a presentation state,
+/// from the _Softcopy VOI LUT Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoftcopyVoi {
+    /// the images to which the window applies,
+    /// or empty if it applies to all images
+    pub references: Vec<ImageReference>,
+    /// the window center and width
+    pub window: WindowLevel,
+    /// the VOI LUT function
+    pub function: VoiLutFunction,
+}
+
+/// The area of some of the images to display,
+/// from the _Displayed Area Selection Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayedArea {
+    /// the images to which the area applies,
+    /// or empty if it applies to all images
+    pub references: Vec<ImageReference>,
+    /// the top left hand corner,
+    /// as a 1-based column and row which may lie outside of the image
+    pub top_left: (i32, i32),
+    /// the bottom right hand corner,
+    /// as a 1-based column and row which may lie outside of the image
+    pub bottom_right: (i32, i32),
+    /// the magnification ratio, in `MAGNIFY` presentation size mode
+    pub magnification: Option<f64>,
+}
+
+/// The coordinate system of annotation points.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnnotationUnits {
+    /// `PIXEL`: image pixel coordinates,
+    /// where the top left corner of the first pixel is (0, 0)
+    Pixel,
+    /// `DISPLAY`: fractions of the displayed area,
+    /// from (0, 0) at the top left corner to (1, 1) at the bottom right corner
+    Display,
+}
+
+/// The shape of a graphic annotation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GraphicType {
+    /// a single point
+    Point,
+    /// a series of connected line segments
+    Polyline,
+    /// a curve through the points,
+    /// here drawn as a polyline
+    Interpolated,
+    /// a circle, given its center and a point on the circumference
+    Circle,
+    /// an ellipse, given the end points of its major axis
+    /// and then of its minor axis
+    Ellipse,
+}
+
+/// A graphic annotation, from the _Graphic Object Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphicObject {
+    /// the coordinate system of the points
+    pub units: AnnotationUnits,
+    /// the shape of the graphic
+    pub graphic_type: GraphicType,
+    /// the points of the graphic, as (column, row) pairs
+    pub points: Vec<(f64, f64)>,
+    /// whether the inside of closed shapes is filled
+    pub filled: bool,
+}
+
+/// A text annotation, from the _Text Object Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextObject {
+    /// the text, possibly with multiple lines
+    pub text: String,
+    /// the box in which the text is drawn
+    pub bounding_box: Option<BoundingBox>,
+    /// the coordinate system of the point the text refers to, and the point
+    pub anchor: Option<(AnnotationUnits, (f64, f64))>,
+}
+
+/// The box enclosing a text annotation.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox {
+    /// the coordinate system of the corners
+    pub units: AnnotationUnits,
+    /// the top left hand corner, as a (column, row) pair
+    pub top_left: (f64, f64),
+    /// the bottom right hand corner, as a (column, row) pair
+    pub bottom_right: (f64, f64),
+}
+
+/// A group of annotations on a graphic layer,
+/// from the _Graphic Annotation Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphicAnnotation {
+    /// the images to which the annotations apply,
+    /// or empty if they apply to all images
+    pub references: Vec<ImageReference>,
+    /// the graphic layer of the annotations
+    pub layer: String,
+    /// the graphic objects
+    pub graphics: Vec<GraphicObject>,
+    /// the text objects
+    pub texts: Vec<TextObject>,
+}
+
+/// The display parameters of a grayscale softcopy presentation state.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct PresentationState {
+    /// the images to which the presentation state applies
+    pub references: Vec<ImageReference>,
+    /// the modality LUT, overriding the one in the image
+    pub rescale: Option<Rescale>,
+    /// the windows of the softcopy VOI LUT
+    pub voi: Vec<SoftcopyVoi>,
+    /// whether the presentation LUT shape is `INVERSE`
+    pub inverse: bool,
+    /// the presentation LUT table, replacing the presentation LUT shape
+    pub presentation_lut: Option<PresentationLutTable>,
+    /// whether the image is flipped horizontally
+    pub horizontal_flip: bool,
+    /// the clockwise rotation of the image in degrees
+    /// (0, 90, 180, or 270), applied after flipping
+    pub rotation: u16,
+    /// the displayed areas
+    pub displayed_areas: Vec<DisplayedArea>,
+    /// the graphic annotations
+    pub annotations: Vec<GraphicAnnotation>,
+}
+
+impl PresentationState {
+    /// Read the display parameters of a grayscale softcopy presentation state.
+    ///
+    /// VOI LUT items described by a table instead of a window are ignored.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let sop_class_uid = get_str(obj, tags::SOP_CLASS_UID).unwrap_or_default();
+        ensure!(
+            sop_class_uid == uids::GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE
+                || sop_class_uid == uids::XAXRF_GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
+            NotPresentationStateSnafu { sop_class_uid }
+        );
+
+        let references = items(obj, tags::REFERENCED_SERIES_SEQUENCE)
+            .iter()
+            .map(image_references)
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let rescale = match (
+            get_floats(obj, tags::RESCALE_SLOPE)?,
+            get_floats(obj, tags::RESCALE_INTERCEPT)?,
+        ) {
+            (Some(slope), Some(intercept)) if !slope.is_empty() && !intercept.is_empty() => {
+                Some(Rescale::new(slope[0], intercept[0]))
+            }
+            _ => None,
+        };
+
+        let mut voi = Vec::new();
+        for item in items(obj, tags::SOFTCOPY_VOILUT_SEQUENCE) {
+            let (Some(center), Some(width)) = (
+                get_floats(item, tags::WINDOW_CENTER)?,
+                get_floats(item, tags::WINDOW_WIDTH)?,
+            ) else {
+                tracing::warn!("Ignoring softcopy VOI LUT without a window");
+                continue;
+            };
+            let (Some(&center), Some(&width)) = (center.first(), width.first()) else {
+                continue;
+            };
+            let function = get_str(item, tags::VOILUT_FUNCTION)
+                .and_then(|f| VoiLutFunction::try_from(f.as_str()).ok())
+                .unwrap_or(VoiLutFunction::Linear);
+            voi.push(SoftcopyVoi {
+                references: image_references(item)?,
+                window: WindowLevel { center, width },
+                function,
+            });
+        }
+
+        let inverse = get_str(obj, tags::PRESENTATION_LUT_SHAPE).as_deref() == Some("INVERSE");
+        let presentation_lut = items(obj, tags::PRESENTATION_LUT_SEQUENCE)
+            .first()
+            .and_then(|item| {
+                let table = PresentationLutTable::from_item(item);
+                if table.is_none() {
+                    tracing::warn!("Ignoring invalid presentation LUT");
+                }
+                table
+            });
+        let horizontal_flip = get_str(obj, tags::IMAGE_HORIZONTAL_FLIP).as_deref() == Some("Y");
+        let rotation = match get_ints(obj, tags::IMAGE_ROTATION)?.first() {
+            Some(rotation) => rotation.rem_euclid(360) as u16 / 90 * 90,
+            None => 0,
+        };
+
+        let mut displayed_areas = Vec::new();
+        for item in items(obj, tags::DISPLAYED_AREA_SELECTION_SEQUENCE) {
+            let top_left = get_ints(item, tags::DISPLAYED_AREA_TOP_LEFT_HAND_CORNER)?;
+            let bottom_right = get_ints(item, tags::DISPLAYED_AREA_BOTTOM_RIGHT_HAND_CORNER)?;
+            let ([left, top, ..], [right, bottom, ..]) = (&top_left[..], &bottom_right[..]) else {
+                continue;
+            };
+            let magnification =
+                if get_str(item, tags::PRESENTATION_SIZE_MODE).as_deref() == Some("MAGNIFY") {
+                    get_floats(item, tags::PRESENTATION_PIXEL_MAGNIFICATION_RATIO)?
+                        .and_then(|ratio| ratio.first().copied())
+                        .filter(|ratio| *ratio > 0.)
+                } else {
+                    None
+                };
+            displayed_areas.push(DisplayedArea {
+                references: image_references(item)?,
+                top_left: (*left, *top),
+                bottom_right: (*right, *bottom),
+                magnification,
+            });
+        }
+
+        let annotations = items(obj, tags::GRAPHIC_ANNOTATION_SEQUENCE)
+            .iter()
+            .map(graphic_annotation)
+            .collect::<Result<_>>()?;
+
+        Ok(PresentationState {
+            references,
+            rescale,
+            voi,
+            inverse,
+            presentation_lut,
+            horizontal_flip,
+            rotation,
+            displayed_areas,
+            annotations,
+        })
+    }
+
+    /// Check whether the presentation state refers to
+    /// the given 0-based frame of the given image.
+    pub fn references_image(&self, sop_instance_uid: &str, frame: u32) -> bool {
+        self.references
+            .iter()
+            .any(|r| r.matches(sop_instance_uid, frame))
+    }
+
+    /// Render the given frame of an image with this presentation state,
+    /// using the default conversion options for everything else.
+    ///
+    /// `frame` is both the index of the frame in the decoded pixel data
+    /// and the frame number in the image identified by `sop_instance_uid`,
+    /// so the pixel data should contain all frames of the image.
+    #[cfg(feature = "image")]
+    pub fn render(
+        &self,
+        pixel: &crate::DecodedPixelData,
+        sop_instance_uid: &str,
+        frame: u32,
+    ) -> Result<image::DynamicImage> {
+        self.render_with_options(
+            pixel,
+            sop_instance_uid,
+            frame,
+            &crate::ConvertOptions::default(),
+        )
+    }
+
+    /// Render the given frame of an image with this presentation state.
+    ///
+    /// The modality LUT and window of the presentation state
+    /// take precedence over those in the options,
+    /// which otherwise apply as usual.
+    /// The presentation LUT replaces
+    /// the photometric interpretation of the image.
+    /// See [`RenderingPipeline::with_presentation_state`](crate::RenderingPipeline::with_presentation_state).
+    ///
+    /// `frame` is both the index of the frame in the decoded pixel data
+    /// and the frame number in the image identified by `sop_instance_uid`,
+    /// so the pixel data should contain all frames of the image.
+    #[cfg(feature = "image")]
+    pub fn render_with_options(
+        &self,
+        pixel: &crate::DecodedPixelData,
+        sop_instance_uid: &str,
+        frame: u32,
+        options: &crate::ConvertOptions,
+    ) -> Result<image::DynamicImage> {
+        use crate::RenderingPipeline;
+
+        let applies =
+            |references: &[ImageReference]| applies_to(references, sop_instance_uid, frame);
+
+        let mut image = RenderingPipeline::from_options(options)
+            .with_presentation_state(self, sop_instance_uid, frame)
+            .render(pixel, frame)
+            .context(ConvertImageSnafu)?;
+
+        let annotations: Vec<_> = self
+            .annotations
+            .iter()
+            .filter(|a| applies(&a.references))
+            .collect();
+
+        // pixel annotations are tied to the image before any transformation
+        for annotation in &annotations {
+            draw::annotation(&mut image, annotation, AnnotationUnits::Pixel)?;
+        }
+
+        if let Some(area) = self.displayed_areas.iter().find(|a| applies(&a.references)) {
+            image = draw::displayed_area(&image, area);
+        }
+        if self.horizontal_flip {
+            image = image.fliph();
+        }
+        image = match self.rotation {
+            90 => image.rotate90(),
+            180 => image.rotate180(),
+            270 => image.rotate270(),
+            _ => image,
+        };
+
+        for annotation in &annotations {
+            draw::annotation(&mut image, annotation, AnnotationUnits::Display)?;
+        }
+
+        Ok(image)
+    }
+}
+
+/// Check whether a part of a presentation state with the given references
+/// applies to the given 0-based frame of the given image.
+pub(crate) fn applies_to(
+    references: &[ImageReference],
+    sop_instance_uid: &str,
+    frame: u32,
+) -> bool {
+    references.is_empty()
+        || references
+            .iter()
+            .any(|r| r.matches(sop_instance_uid, frame))
+}
+
+/// Retrieve the items of a sequence, or none if the sequence is missing.
+fn items(obj: &InMemDicomObject, tag: Tag) -> &[InMemDicomObject] {
+    obj.get(tag).and_then(|e| e.items()).unwrap_or(&[])
+}
+
+/// Retrieve a string attribute without padding.
+fn get_str(obj: &InMemDicomObject, tag: Tag) -> Option<String> {
+    obj.get(tag)
+        .and_then(|e| e.to_str().ok())
+        .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+}
+
+fn get_floats(obj: &InMemDicomObject, tag: Tag) -> Result<Option<Vec<f64>>> {
+    obj.get(tag)
+        .map(|e| e.to_multi_float64().context(ConvertValueSnafu { tag }))
+        .transpose()
+        .map_err(Error)
+}
+
+fn get_ints(obj: &InMemDicomObject, tag: Tag) -> Result<Vec<i32>> {
+    Ok(obj
+        .get(tag)
+        .map(|e| e.to_multi_int::<i32>().context(ConvertValueSnafu { tag }))
+        .transpose()?
+        .unwrap_or_default())
+}
+
+/// Collect the image references in the _Referenced Image Sequence_ of an item.
+fn image_references(item: &InMemDicomObject) -> Result<Vec<ImageReference>> {
+    items(item, tags::REFERENCED_IMAGE_SEQUENCE)
+        .iter()
+        .filter_map(|image| {
+            let sop_instance_uid = get_str(image, tags::REFERENCED_SOP_INSTANCE_UID)?;
+            Some(
+                get_ints(image, tags::REFERENCED_FRAME_NUMBER).map(|frames| ImageReference {
+                    sop_instance_uid,
+                    frames: frames.into_iter().map(|f| f as u32).collect(),
+                }),
+            )
+        })
+        .collect()
+}
+
+fn units(obj: &InMemDicomObject, tag: Tag) -> AnnotationUnits {
+    match get_str(obj, tag).as_deref() {
+        Some("DISPLAY") => AnnotationUnits::Display,
+        _ => AnnotationUnits::Pixel,
+    }
+}
+
+fn point(values: &[f64]) -> Option<(f64, f64)> {
+    match values {
+        [x, y, ..] => Some((*x, *y)),
+        _ => None,
+    }
+}
+
+fn graphic_annotation(item: &InMemDicomObject) -> Result<GraphicAnnotation> {
+    let mut graphics = Vec::new();
+    for graphic in items(item, tags::GRAPHIC_OBJECT_SEQUENCE) {
+        let type_name = get_str(graphic, tags::GRAPHIC_TYPE).unwrap_or_default();
+        let graphic_type = match type_name.as_str() {
+            "POINT" => GraphicType::Point,
+            "POLYLINE" => GraphicType::Polyline,
+            "INTERPOLATED" => GraphicType::Interpolated,
+            "CIRCLE" => GraphicType::Circle,
+            "ELLIPSE" => GraphicType::Ellipse,
+            _ => {
+                tracing::warn!("Ignoring graphic of unknown type `{}`", type_name);
+                continue;
+            }
+        };
+        let points: Vec<_> = get_floats(graphic, tags::GRAPHIC_DATA)?
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|p| (p[0], p[1]))
+            .collect();
+        let required = match graphic_type {
+            GraphicType::Point => 1..=1,
+            GraphicType::Circle => 2..=2,
+            GraphicType::Ellipse => 4..=4,
+            GraphicType::Polyline | GraphicType::Interpolated => 1..=usize::MAX,
+        };
+        ensure!(
+            required.contains(&points.len()),
+            InvalidGraphicDataSnafu {
+                graphic_type: type_name,
+                points: points.len(),
+            }
+        );
+        graphics.push(GraphicObject {
+            units: units(graphic, tags::GRAPHIC_ANNOTATION_UNITS),
+            graphic_type,
+            points,
+            filled: get_str(graphic, tags::GRAPHIC_FILLED).as_deref() == Some("Y"),
+        });
+    }
+
+    let mut texts = Vec::new();
+    for text in items(item, tags::TEXT_OBJECT_SEQUENCE) {
+        let top_left = get_floats(text, tags::BOUNDING_BOX_TOP_LEFT_HAND_CORNER)?;
+        let bottom_right = get_floats(text, tags::BOUNDING_BOX_BOTTOM_RIGHT_HAND_CORNER)?;
+        let bounding_box = top_left
+            .as_deref()
+            .and_then(point)
+            .zip(bottom_right.as_deref().and_then(point))
+            .map(|(top_left, bottom_right)| BoundingBox {
+                units: units(text, tags::BOUNDING_BOX_ANNOTATION_UNITS),
+                top_left,
+                bottom_right,
+            });
+        let anchor = get_floats(text, tags::ANCHOR_POINT)?
+            .as_deref()
+            .and_then(point)
+            .map(|anchor| (units(text, tags::ANCHOR_POINT_ANNOTATION_UNITS), anchor));
+        texts.push(TextObject {
+            text: get_str(text, tags::UNFORMATTED_TEXT_VALUE).unwrap_or_default(),
+            bounding_box,
+            anchor,
+        });
+    }
+
+    Ok(GraphicAnnotation {
+        references: image_references(item)?,
+        layer: get_str(item, tags::GRAPHIC_LAYER).unwrap_or_default(),
+        graphics,
+        texts,
+    })
+}
+
+/// Rendering of displayed areas and annotations.
+#[cfg(feature = "image")]
+mod draw {
+    use image::{DynamicImage, GenericImage, GenericImageView, Rgba, imageops};
+
+    use super::{
+        AnnotationUnits, DisplayedArea, GraphicAnnotation, GraphicType, InvalidGraphicDataSnafu,
+        Result,
+    };
+
+    const WHITE: Rgba<u8> = Rgba([0xFF, 0xFF, 0xFF, 0xFF]);
+
+    /// Cut the displayed area out of the image,
+    /// padding with black where it extends beyond the image,
+    /// and apply its magnification.
+    pub(super) fn displayed_area(image: &DynamicImage, area: &DisplayedArea) -> DynamicImage {
+        let left = area.top_left.0.min(area.bottom_right.0) as i64 - 1;
+        let top = area.top_left.1.min(area.bottom_right.1) as i64 - 1;
+        let width = area.top_left.0.abs_diff(area.bottom_right.0) + 1;
+        let height = area.top_left.1.abs_diff(area.bottom_right.1) + 1;
+
+        let mut out = if (left, top, width, height) == (0, 0, image.width(), image.height()) {
+            image.clone()
+        } else {
+            let mut canvas = DynamicImage::new(width, height, image.color());
+            // overlay on the concrete buffers to retain the sample depth
+            match (&mut canvas, image) {
+                (DynamicImage::ImageLuma8(canvas), DynamicImage::ImageLuma8(image)) => {
+                    imageops::overlay(canvas, image, -left, -top)
+                }
+                (DynamicImage::ImageLuma16(canvas), DynamicImage::ImageLuma16(image)) => {
+                    imageops::overlay(canvas, image, -left, -top)
+                }
+                (DynamicImage::ImageRgb8(canvas), DynamicImage::ImageRgb8(image)) => {
+                    imageops::overlay(canvas, image, -left, -top)
+                }
+                (DynamicImage::ImageRgb16(canvas), DynamicImage::ImageRgb16(image)) => {
+                    imageops::overlay(canvas, image, -left, -top)
+                }
+                (canvas, image) => imageops::overlay(canvas, image, -left, -top),
+            }
+            canvas
+        };
+
+        if let Some(ratio) = area.magnification {
+            let width = (out.width() as f64 * ratio).round().max(1.) as u32;
+            let height = (out.height() as f64 * ratio).round().max(1.) as u32;
+            out = out.resize_exact(width, height, imageops::FilterType::Triangle);
+        }
+        out
+    }
+
+    /// Draw the annotations in the given units onto the image.
+    pub(super) fn annotation(
+        image: &mut DynamicImage,
+        annotation: &GraphicAnnotation,
+        units: AnnotationUnits,
+    ) -> Result<()> {
+        let (width, height) = (image.width() as f64, image.height() as f64);
+        let map = |(x, y): (f64, f64)| match units {
+            AnnotationUnits::Pixel => (x, y),
+            AnnotationUnits::Display => (x * width, y * height),
+        };
+
+        for graphic in annotation.graphics.iter().filter(|g| g.units == units) {
+            let points: Vec<_> = graphic.points.iter().copied().map(map).collect();
+            let outline = match graphic.graphic_type {
+                GraphicType::Point => {
+                    let (x, y) = points[0];
+                    for (dx, dy) in [(0., 0.), (-1., 0.), (1., 0.), (0., -1.), (0., 1.)] {
+                        put(image, x + dx, y + dy);
+                    }
+                    continue;
+                }
+                GraphicType::Polyline | GraphicType::Interpolated => points,
+                GraphicType::Circle => {
+                    let [center, edge] = points[..] else {
+                        return InvalidGraphicDataSnafu {
+                            graphic_type: "CIRCLE",
+                            points: points.len(),
+                        }
+                        .fail()?;
+                    };
+                    let radius = ((edge.0 - center.0).powi(2) + (edge.1 - center.1).powi(2)).sqrt();
+                    ellipse(center, (radius, 0.), (0., radius))
+                }
+                GraphicType::Ellipse => {
+                    let [a0, a1, b0, b1] = points[..] else {
+                        return InvalidGraphicDataSnafu {
+                            graphic_type: "ELLIPSE",
+                            points: points.len(),
+                        }
+                        .fail()?;
+                    };
+                    let center = ((a0.0 + a1.0) / 2., (a0.1 + a1.1) / 2.);
+                    ellipse(
+                        center,
+                        ((a1.0 - a0.0) / 2., (a1.1 - a0.1) / 2.),
+                        ((b1.0 - b0.0) / 2., (b1.1 - b0.1) / 2.),
+                    )
+                }
+            };
+
+            if graphic.filled {
+                fill(image, &outline);
+            }
+            for segment in outline.windows(2) {
+                line(image, segment[0], segment[1]);
+            }
+            if outline.len() == 1 {
+                put(image, outline[0].0, outline[0].1);
+            }
+        }
+
+        for text in &annotation.texts {
+            let lines: Vec<&str> = text.text.lines().collect();
+            let max_len = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+            if max_len == 0 {
+                continue;
+            }
+            let (origin, scale) = match (text.bounding_box, text.anchor) {
+                (Some(bounding_box), _) if bounding_box.units == units => {
+                    let (x0, y0) = map(bounding_box.top_left);
+                    let (x1, y1) = map(bounding_box.bottom_right);
+                    // as large as possible while fitting in the box
+                    let scale = ((y1 - y0).abs() / (GLYPH_HEIGHT * lines.len()) as f64)
+                        .min((x1 - x0).abs() / (GLYPH_WIDTH * max_len) as f64)
+                        .floor()
+                        .max(1.);
+                    ((x0.min(x1), y0.min(y1)), scale as u32)
+                }
+                (None, Some((anchor_units, anchor))) if anchor_units == units => {
+                    (map(anchor), (image.height() / 256).max(1))
+                }
+                _ => continue,
+            };
+            for (i, line) in lines.iter().enumerate() {
+                let y = origin.1 + (i * GLYPH_HEIGHT) as f64 * scale as f64;
+                for (j, c) in line.chars().enumerate() {
+                    let x = origin.0 + (j * GLYPH_WIDTH) as f64 * scale as f64;
+                    glyph(image, c, x, y, scale);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sample the outline of an ellipse with the given semi-axis vectors.
+    fn ellipse(center: (f64, f64), a: (f64, f64), b: (f64, f64)) -> Vec<(f64, f64)> {
+        let circumference = 2. * std::f64::consts::PI * a.0.hypot(a.1).max(b.0.hypot(b.1));
+        let steps = (circumference.ceil() as usize).clamp(16, 4096);
+        (0..=steps)
+            .map(|i| {
+                let t = i as f64 / steps as f64 * 2. * std::f64::consts::PI;
+                let (cos, sin) = (t.cos(), t.sin());
+                (
+                    center.0 + a.0 * cos + b.0 * sin,
+                    center.1 + a.1 * cos + b.1 * sin,
+                )
+            })
+            .collect()
+    }
+
+    /// Paint the pixel containing the given point, if inside the image.
+    fn put(image: &mut DynamicImage, x: f64, y: f64) {
+        let (x, y) = (x.floor(), y.floor());
+        if x >= 0. && y >= 0. && x < image.width() as f64 && y < image.height() as f64 {
+            image.put_pixel(x as u32, y as u32, WHITE);
+        }
+    }
+
+    /// Draw a line segment between two points.
+    fn line(image: &mut DynamicImage, from: (f64, f64), to: (f64, f64)) {
+        let steps = (to.0 - from.0)
+            .abs()
+            .max((to.1 - from.1).abs())
+            .ceil()
+            .max(1.);
+        let steps = steps.min(1e6) as usize;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            put(
+                image,
+                from.0 + (to.0 - from.0) * t,
+                from.1 + (to.1 - from.1) * t,
+            );
+        }
+    }
+
+    /// Fill a closed polygon, with the even-odd rule at pixel centers.
+    fn fill(image: &mut DynamicImage, polygon: &[(f64, f64)]) {
+        if polygon.len() < 3 {
+            return;
+        }
+        let (width, height) = image.dimensions();
+        let edges = polygon.iter().zip(polygon.iter().cycle().skip(1));
+        let edges: Vec<_> = edges.collect();
+        for row in 0..height {
+            let y = row as f64 + 0.5;
+            let mut crossings: Vec<f64> = edges
+                .iter()
+                .filter(|(p, q)| (p.1 <= y) != (q.1 <= y))
+                .map(|(p, q)| p.0 + (y - p.1) / (q.1 - p.1) * (q.0 - p.0))
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil().max(0.) as u32;
+                let end = ((span[1] - 0.5).floor().min(width as f64 - 1.)).max(-1.);
+                if end < start as f64 {
+                    continue;
+                }
+                for column in start..=end as u32 {
+                    image.put_pixel(column, row, WHITE);
+                }
+            }
+        }
+    }
+
+    /// The horizontal advance of a glyph, including spacing
+    const GLYPH_WIDTH: usize = 6;
+    /// The vertical advance of a line of text, including spacing
+    const GLYPH_HEIGHT: usize = 8;
+
+    /// Draw a character of the built-in 5x7 font
+    /// with its top left corner at the given point.
+    fn glyph(image: &mut DynamicImage, c: char, x: f64, y: f64, scale: u32) {
+        let index = match c {
+            ' '..='~' => c as usize - ' ' as usize,
+            _ => '?' as usize - ' ' as usize,
+        };
+        for (column, bits) in FONT_5X7[index].iter().enumerate() {
+            for row in 0..7 {
+                if bits & (1 << row) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        put(
+                            image,
+                            x + (column as u32 * scale + dx) as f64,
+                            y + (row * scale + dy) as f64,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The printable ASCII characters of a 5x7 font,
+    /// one byte per column, with the top row in the lowest bit.
+    const FONT_5X7: [[u8; 5]; 95] = [
+        [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+        [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+        [0x00, 0x07, 0x00, 0x07, 0x00], // "
+        [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+        [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+        [0x23, 0x13, 0x08, 0x64, 0x62], // %
+        [0x36, 0x49, 0x55, 0x22, 0x50], // &
+        [0x00, 0x05, 0x03, 0x00, 0x00], // '
+        [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+        [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+        [0x14, 0x08, 0x3E, 0x08, 0x14], // *
+        [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+        [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+        [0x08, 0x08, 0x08, 0x08, 0x08], // -
+        [0x00, 0x60, 0x60, 0x00, 0x00], // .
+        [0x20, 0x10, 0x08, 0x04, 0x02], // /
+        [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+        [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+        [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+        [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+        [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+        [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+        [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+        [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+        [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+        [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+        [0x00, 0x36, 0x36, 0x00, 0x00], // :
+        [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+        [0x08, 0x14, 0x22, 0x41, 0x00], // <
+        [0x14, 0x14, 0x14, 0x14, 0x14], // =
+        [0x00, 0x41, 0x22, 0x14, 0x08], // >
+        [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+        [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+        [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+        [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+        [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+        [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+        [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+        [0x7F, 0x09, 0x09, 0x09, 0x01], // F
+        [0x3E, 0x41, 0x49, 0x49, 0x7A], // G
+        [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+        [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+        [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+        [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+        [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+        [0x7F, 0x02, 0x0C, 0x02, 0x7F], // M
+        [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+        [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+        [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+        [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+        [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+        [0x46, 0x49, 0x49, 0x49, 0x31], // S
+        [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+        [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+        [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+        [0x3F, 0x40, 0x38, 0x40, 0x3F], // W
+        [0x63, 0x14, 0x08, 0x14, 0x63], // X
+        [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+        [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+        [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+        [0x02, 0x04, 0x08, 0x10, 0x20], // \
+        [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+        [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+        [0x40, 0x40, 0x40, 0x40, 0x40], // _
+        [0x00, 0x01, 0x02, 0x04, 0x00], // `
+        [0x20, 0x54, 0x54, 0x54, 0x78], // a
+        [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+        [0x38, 0x44, 0x44, 0x44, 0x20], // c
+        [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+        [0x38, 0x54, 0x54, 0x54, 0x18], // e
+        [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+        [0x0C, 0x52, 0x52, 0x52, 0x3E], // g
+        [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+        [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+        [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+        [0x7F, 0x10, 0x28, 0x44, 0x00], // k
+        [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+        [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+        [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+        [0x38, 0x44, 0x44, 0x44, 0x38], // o
+        [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+        [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+        [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+        [0x48, 0x54, 0x54, 0x54, 0x20], // s
+        [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+        [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+        [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+        [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+        [0x44, 0x28, 0x10, 0x28, 0x44], // x
+        [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+        [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+        [0x00, 0x08, 0x36, 0x41, 0x00], // {
+        [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+        [0x00, 0x41, 0x36, 0x08, 0x00], // }
+        [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, VR, dicom_value, value::DataSetSequence};
+
+    fn seq(
+        tag: Tag,
+        items: Vec<Vec<DataElement<InMemDicomObject>>>,
+    ) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            tag,
+            VR::SQ,
+            DataSetSequence::from(
+                items
+                    .into_iter()
+                    .map(InMemDicomObject::from_element_iter)
+                    .collect::<Vec<_>>(),
+            ),
+        )
+    }
+
+    fn referenced_image(uid: &str) -> DataElement<InMemDicomObject> {
+        seq(
+            tags::REFERENCED_IMAGE_SEQUENCE,
+            vec![vec![DataElement::new(
+                tags::REFERENCED_SOP_INSTANCE_UID,
+                VR::UI,
+                uid,
+            )]],
+        )
+    }
+
+    fn presentation_state() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::GRAYSCALE_SOFTCOPY_PRESENTATION_STATE_STORAGE,
+            ),
+            seq(
+                tags::REFERENCED_SERIES_SEQUENCE,
+                vec![vec![
+                    DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "2.25.10"),
+                    referenced_image("2.25.11"),
+                ]],
+            ),
+            seq(
+                tags::SOFTCOPY_VOILUT_SEQUENCE,
+                vec![vec![
+                    DataElement::new(tags::WINDOW_CENTER, VR::DS, "50"),
+                    DataElement::new(tags::WINDOW_WIDTH, VR::DS, "100"),
+                    DataElement::new(tags::VOILUT_FUNCTION, VR::CS, "LINEAR_EXACT"),
+                ]],
+            ),
+            DataElement::new(tags::PRESENTATION_LUT_SHAPE, VR::CS, "INVERSE"),
+            DataElement::new(tags::IMAGE_HORIZONTAL_FLIP, VR::CS, "Y"),
+            DataElement::new(tags::IMAGE_ROTATION, VR::US, dicom_value!(U16, [90])),
+            seq(
+                tags::DISPLAYED_AREA_SELECTION_SEQUENCE,
+                vec![vec![
+                    DataElement::new(
+                        tags::DISPLAYED_AREA_TOP_LEFT_HAND_CORNER,
+                        VR::SL,
+                        dicom_value!(I32, [1, 1]),
+                    ),
+                    DataElement::new(
+                        tags::DISPLAYED_AREA_BOTTOM_RIGHT_HAND_CORNER,
+                        VR::SL,
+                        dicom_value!(I32, [4, 2]),
+                    ),
+                    DataElement::new(tags::PRESENTATION_SIZE_MODE, VR::CS, "SCALE TO FIT"),
+                ]],
+            ),
+            seq(
+                tags::GRAPHIC_ANNOTATION_SEQUENCE,
+                vec![vec![
+                    referenced_image("2.25.11"),
+                    DataElement::new(tags::GRAPHIC_LAYER, VR::CS, "LAYER1"),
+                    seq(
+                        tags::GRAPHIC_OBJECT_SEQUENCE,
+                        vec![vec![
+                            DataElement::new(tags::GRAPHIC_ANNOTATION_UNITS, VR::CS, "PIXEL"),
+                            DataElement::new(tags::GRAPHIC_TYPE, VR::CS, "POINT"),
+                            DataElement::new(
+                                tags::GRAPHIC_DATA,
+                                VR::FL,
+                                dicom_value!(F32, [0.5, 0.5]),
+                            ),
+                        ]],
+                    ),
+                    seq(
+                        tags::TEXT_OBJECT_SEQUENCE,
+                        vec![vec![
+                            DataElement::new(tags::UNFORMATTED_TEXT_VALUE, VR::ST, "Lesion"),
+                            DataElement::new(
+                                tags::ANCHOR_POINT_ANNOTATION_UNITS,
+                                VR::CS,
+                                "DISPLAY",
+                            ),
+                            DataElement::new(
+                                tags::ANCHOR_POINT,
+                                VR::FL,
+                                dicom_value!(F32, [0.5, 0.5]),
+                            ),
+                        ]],
+                    ),
+                ]],
+            ),
+        ])
+    }
+
+    #[test]
+    fn read_presentation_state() {
+        let pr = PresentationState::from_object(&presentation_state()).unwrap();
+        assert!(pr.references_image("2.25.11", 0));
+        assert!(!pr.references_image("2.25.12", 0));
+        assert_eq!(pr.rescale, None);
+        assert_eq!(
+            pr.voi,
+            vec![SoftcopyVoi {
+                references: vec![],
+                window: WindowLevel {
+                    center: 50.,
+                    width: 100.
+                },
+                function: VoiLutFunction::LinearExact,
+            }]
+        );
+        assert!(pr.inverse);
+        assert!(pr.horizontal_flip);
+        assert_eq!(pr.rotation, 90);
+        assert_eq!(pr.displayed_areas.len(), 1);
+        assert_eq!(pr.displayed_areas[0].top_left, (1, 1));
+        assert_eq!(pr.displayed_areas[0].bottom_right, (4, 2));
+        assert_eq!(pr.displayed_areas[0].magnification, None);
+
+        let annotation = &pr.annotations[0];
+        assert_eq!(annotation.layer, "LAYER1");
+        assert_eq!(annotation.references[0].sop_instance_uid, "2.25.11");
+        assert_eq!(
+            annotation.graphics,
+            vec![GraphicObject {
+                units: AnnotationUnits::Pixel,
+                graphic_type: GraphicType::Point,
+                points: vec![(0.5, 0.5)],
+                filled: false,
+            }]
+        );
+        assert_eq!(annotation.texts[0].text, "Lesion");
+        assert_eq!(
+            annotation.texts[0].anchor,
+            Some((AnnotationUnits::Display, (0.5, 0.5)))
+        );
+
+        // not a presentation state
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            uids::CT_IMAGE_STORAGE,
+        )]);
+        assert!(PresentationState::from_object(&obj).is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_with_presentation_state() {
+        use crate::PixelDecoder as _;
+        use dicom_core::PrimitiveValue;
+        use dicom_object::FileMetaTableBuilder;
+        use image::GenericImageView;
+
+        // 4x4 image where each row has the same value
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let obj = InMemDicomObject::from_element_iter([
+            us(tags::SAMPLES_PER_PIXEL, 1),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            us(tags::ROWS, 4),
+            us(tags::COLUMNS, 4),
+            us(tags::BITS_ALLOCATED, 8),
+            us(tags::BITS_STORED, 8),
+            us(tags::HIGH_BIT, 7),
+            us(tags::PIXEL_REPRESENTATION, 0),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(
+                    [0_u8, 25, 75, 100]
+                        .iter()
+                        .flat_map(|v| [*v; 4])
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.11"),
+        )
+        .unwrap();
+        let pixel = obj.decode_pixel_data().unwrap();
+
+        let mut pr = PresentationState::from_object(&presentation_state()).unwrap();
+        // leave the text out
+        pr.annotations[0].texts.clear();
+        let image = pr.render(&pixel, "2.25.11", 0).unwrap();
+
+        // the displayed area keeps the first 2 rows of 4 columns,
+        // which are flipped and rotated into 2 columns of 4 rows
+        assert_eq!(image.dimensions(), (2, 4));
+        let value = |x, y| image.get_pixel(x, y).0[0];
+        // the point in the first pixel ends up at the top right
+        assert_eq!(value(1, 0), 255);
+        // first row: 0, inverted
+        assert_eq!(value(1, 1), 255);
+        // second row: 25, half of the way to the window's lower bound, inverted
+        assert!((185..=195).contains(&value(0, 2)), "{}", value(0, 2));
+
+        // annotations only apply to the referenced image
+        let image = pr.render(&pixel, "2.25.99", 0).unwrap();
+        assert_eq!(image.get_pixel(1, 0).0[0], 255);
+        assert_eq!(image.get_pixel(1, 1).0[0], 255);
+        let image = PresentationState {
+            inverse: false,
+            ..pr.clone()
+        }
+        .render(&pixel, "2.25.99", 0)
+        .unwrap();
+        assert_eq!(image.get_pixel(1, 0).0[0], 0);
+    }
+}
@@ -0,0 +1,340 @@
+//! Real World Value Mapping
+//!
+//! A _Real World Value Mapping_ maps stored pixel values
+//! to values of a physical quantity (such as a diffusion coefficient),
+//! independently of the Modality LUT.
+//! This is how Parametric Map objects,
+//! which hold their samples in _Float Pixel Data_ or _Double Float Pixel Data_
+//! without any rescale attributes,
+//! describe the quantity measured by each frame.
+//!
+//! An image may hold more than one mapping,
+//! one per quantity or units
+//! (for instance, PET images in both Bq/ml and SUV).
+//!
+//! The mappings of an object are kept in the decoded pixel data
+//! (see [`DecodedPixelData::real_world_value_mappings`]).
+//! [`DecodedPixelData::to_real_world_values`]
+//! maps the stored values of a frame into [`RealWorldValues`],
+//! which carry the units and the quantity along with the values,
+//! and [`DecodedPixelData::to_real_world_values_in`]
+//! picks the mapping in the given units.
+//! The first mapping is also applied
+//! in the other conversion methods
+//! through [`ModalityLutOption::RealWorldValues`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_object::code::ucum;
+//! use dicom_pixeldata::PixelDecoder as _;
+//!
+//! let obj = open_file("pet.dcm")?;
+//! let pixel = obj.decode_pixel_data()?;
+//! for mapping in pixel.real_world_value_mappings(0) {
+//!     println!("{:?} ({:?})", mapping.quantity, mapping.units);
+//! }
+//! let suv = pixel.to_real_world_values_in(0, &ucum::SUV_BODY_WEIGHT)?;
+//! let max = suv.values.iter().copied().fold(f64::MIN, f64::max);
+//! println!("SUVmax: {max} {}", suv.units.unwrap().value);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`DecodedPixelData::real_world_value_mappings`]: crate::DecodedPixelData::real_world_value_mappings
+//! [`DecodedPixelData::to_real_world_values`]: crate::DecodedPixelData::to_real_world_values
+//! [`DecodedPixelData::to_real_world_values_in`]: crate::DecodedPixelData::to_real_world_values_in
+//! [`ModalityLutOption::RealWorldValues`]: crate::ModalityLutOption::RealWorldValues
+
+use dicom_core::DataDictionary;
+use dicom_dictionary_std::tags;
+use dicom_object::code::{Code, sct};
+use dicom_object::{FileDicomObject, InMemDicomObject};
+
+use crate::Rescale;
+
+/// The function of a real world value mapping.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RealWorldValueTransform {
+    /// A linear function,
+    /// from the _Real World Value Slope_ and _Real World Value Intercept_
+    Linear(Rescale),
+    /// A lookup table from the _Real World Value LUT Data_,
+    /// with one value per stored value
+    /// starting at the first value mapped
+    Lut(Vec<f64>),
+}
+
+/// A mapping of stored pixel values into real world values,
+/// as in an item of the _Real World Value Mapping Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealWorldValueMapping {
+    /// the first stored value mapped
+    pub first_value_mapped: f64,
+    /// the last stored value mapped
+    pub last_value_mapped: f64,
+    /// the mapping function
+    pub transform: RealWorldValueTransform,
+    /// the _LUT Label_, if present
+    pub label: Option<String>,
+    /// the _LUT Explanation_, if present
+    pub explanation: Option<String>,
+    /// the units of the real world values,
+    /// from the _Measurement Units Code Sequence_
+    pub units: Option<Code>,
+    /// the quantity measured,
+    /// from the _Quantity Definition Sequence_
+    pub quantity: Option<Code>,
+}
+
+/// Stored pixel values mapped into real world values,
+/// along with the description of what they measure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealWorldValues {
+    /// the real world values,
+    /// in the same order as the stored values
+    pub values: Vec<f64>,
+    /// the units of the values
+    pub units: Option<Code>,
+    /// the quantity measured
+    pub quantity: Option<Code>,
+    /// the label of the mapping
+    pub label: Option<String>,
+}
+
+impl RealWorldValueMapping {
+    /// Read a real world value mapping from an item of the
+    /// _Real World Value Mapping Sequence_, if it is well formed.
+    ///
+    /// The range of stored values mapped is read
+    /// from the double float attributes when present
+    /// (as used for floating point pixel data),
+    /// and from the integer attributes otherwise.
+    pub fn from_item<D>(item: &InMemDicomObject<D>) -> Option<Self>
+    where
+        D: DataDictionary + Clone,
+    {
+        let float = |tag| item.get(tag).and_then(|e| e.to_float64().ok());
+        let text = |tag| {
+            item.get(tag)
+                .and_then(|e| e.to_str().ok())
+                .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let first_value_mapped = float(tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_FIRST_VALUE_MAPPED)
+            .or_else(|| float(tags::REAL_WORLD_VALUE_FIRST_VALUE_MAPPED))?;
+        let last_value_mapped = float(tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_LAST_VALUE_MAPPED)
+            .or_else(|| float(tags::REAL_WORLD_VALUE_LAST_VALUE_MAPPED))?;
+
+        let transform = match (
+            float(tags::REAL_WORLD_VALUE_SLOPE),
+            float(tags::REAL_WORLD_VALUE_INTERCEPT),
+        ) {
+            (Some(slope), Some(intercept)) => {
+                RealWorldValueTransform::Linear(Rescale::new(slope, intercept))
+            }
+            _ => {
+                let data = item
+                    .get(tags::REAL_WORLD_VALUE_LUT_DATA)
+                    .and_then(|e| e.to_multi_float64().ok())
+                    .filter(|data| !data.is_empty())?;
+                RealWorldValueTransform::Lut(data)
+            }
+        };
+
+        Some(RealWorldValueMapping {
+            first_value_mapped,
+            last_value_mapped,
+            transform,
+            label: text(tags::LUT_LABEL),
+            explanation: text(tags::LUT_EXPLANATION),
+            units: Code::from_sequence(item, tags::MEASUREMENT_UNITS_CODE_SEQUENCE).ok(),
+            quantity: quantity(item),
+        })
+    }
+
+    /// Check whether the real world values are in the given units,
+    /// regardless of the meaning of the codes.
+    pub fn is_in_units(&self, units: &Code) -> bool {
+        self.units
+            .as_ref()
+            .is_some_and(|u| u.is_same_concept(units))
+    }
+
+    /// Map a sequence of stored values into real world values.
+    pub fn map_values(&self, values: impl IntoIterator<Item = f64>) -> RealWorldValues {
+        RealWorldValues {
+            values: values.into_iter().map(|v| self.apply(v)).collect(),
+            units: self.units.clone(),
+            quantity: self.quantity.clone(),
+            label: self.label.clone(),
+        }
+    }
+
+    /// Map a stored value into a real world value.
+    ///
+    /// Stored values outside of the range of the lookup table
+    /// are clamped to its first or last entry.
+    pub fn apply(&self, value: f64) -> f64 {
+        match &self.transform {
+            RealWorldValueTransform::Linear(rescale) => rescale.apply(value),
+            RealWorldValueTransform::Lut(data) => {
+                let index = (value - self.first_value_mapped).round();
+                data[index.clamp(0., (data.len() - 1) as f64) as usize]
+            }
+        }
+    }
+}
+
+/// Read the quantity in the _Quantity Definition Sequence_ of a mapping,
+/// as the code of the content item named _Quantity_.
+fn quantity<D>(item: &InMemDicomObject<D>) -> Option<Code>
+where
+    D: DataDictionary + Clone,
+{
+    item.get(tags::QUANTITY_DEFINITION_SEQUENCE)?
+        .items()?
+        .iter()
+        .find(|definition| {
+            Code::from_sequence(definition, tags::CONCEPT_NAME_CODE_SEQUENCE)
+                .is_ok_and(|name| name.is_same_concept(&sct::QUANTITY))
+        })
+        .and_then(|definition| Code::from_sequence(definition, tags::CONCEPT_CODE_SEQUENCE).ok())
+}
+
+/// Collect the real world value mappings of an object.
+///
+/// Like the rescale parameters,
+/// these are either the mappings for all frames
+/// or the mappings of each frame,
+/// looked up in the _Per-Frame Functional Groups Sequence_,
+/// then in the _Shared Functional Groups Sequence_,
+/// and finally at the root of the object.
+/// Malformed items of the _Real World Value Mapping Sequence_ are skipped.
+pub(crate) fn real_world_value_mappings<D>(
+    obj: &FileDicomObject<InMemDicomObject<D>>,
+) -> Vec<Vec<RealWorldValueMapping>>
+where
+    D: DataDictionary + Clone,
+{
+    fn mappings<D>(item: &InMemDicomObject<D>) -> Option<Vec<RealWorldValueMapping>>
+    where
+        D: DataDictionary + Clone,
+    {
+        let mappings: Vec<_> = item
+            .get(tags::REAL_WORLD_VALUE_MAPPING_SEQUENCE)?
+            .items()?
+            .iter()
+            .filter_map(RealWorldValueMapping::from_item)
+            .collect();
+        (!mappings.is_empty()).then_some(mappings)
+    }
+
+    let group_items = |tag| obj.get(tag).and_then(|e| e.items()).unwrap_or_default();
+
+    let per_frame: Option<Vec<_>> = group_items(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE)
+        .iter()
+        .map(mappings)
+        .collect();
+    match per_frame {
+        Some(mappings) if !mappings.is_empty() => mappings,
+        _ => group_items(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE)
+            .first()
+            .and_then(mappings)
+            .or_else(|| mappings(obj))
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_object::code::{sct, ucum};
+
+    #[test]
+    fn read_and_apply_mappings() {
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_FIRST_VALUE_MAPPED,
+                VR::FD,
+                PrimitiveValue::from(-1000.),
+            ),
+            DataElement::new(
+                tags::DOUBLE_FLOAT_REAL_WORLD_VALUE_LAST_VALUE_MAPPED,
+                VR::FD,
+                PrimitiveValue::from(1000.),
+            ),
+            DataElement::new(
+                tags::REAL_WORLD_VALUE_SLOPE,
+                VR::FD,
+                PrimitiveValue::from(0.5),
+            ),
+            DataElement::new(
+                tags::REAL_WORLD_VALUE_INTERCEPT,
+                VR::FD,
+                PrimitiveValue::from(2.),
+            ),
+            DataElement::new(tags::LUT_LABEL, VR::SH, "ADC"),
+            ucum::MILLIMETER.to_sequence_element(tags::MEASUREMENT_UNITS_CODE_SEQUENCE),
+            DataElement::new(
+                tags::QUANTITY_DEFINITION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    sct::QUANTITY.to_sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE),
+                    sct::LENGTH.to_sequence_element(tags::CONCEPT_CODE_SEQUENCE),
+                ])]),
+            ),
+        ]);
+        let mapping = RealWorldValueMapping::from_item(&item).unwrap();
+        assert_eq!(mapping.first_value_mapped, -1000.);
+        assert_eq!(mapping.label.as_deref(), Some("ADC"));
+        assert_eq!(mapping.units.as_ref(), Some(&ucum::MILLIMETER));
+        assert_eq!(mapping.quantity.as_ref(), Some(&sct::LENGTH));
+        assert!(mapping.is_in_units(&ucum::MILLIMETER));
+        assert!(!mapping.is_in_units(&ucum::CENTIMETER));
+        assert_eq!(mapping.apply(10.), 7.);
+
+        let values = mapping.map_values([0., 2.]);
+        assert_eq!(values.values, [2., 3.]);
+        assert_eq!(values.units, Some(ucum::MILLIMETER));
+        assert_eq!(values.quantity, Some(sct::LENGTH));
+
+        let lut = RealWorldValueMapping {
+            first_value_mapped: 10.,
+            last_value_mapped: 12.,
+            transform: RealWorldValueTransform::Lut(vec![0.5, 1.5, 2.5]),
+            label: None,
+            explanation: None,
+            units: None,
+            quantity: None,
+        };
+        assert_eq!(lut.apply(11.), 1.5);
+        assert_eq!(lut.apply(0.), 0.5);
+        assert_eq!(lut.apply(20.), 2.5);
+
+        // shared by all frames
+        let meta = dicom_object::FileMetaTableBuilder::new()
+            .transfer_syntax(dicom_dictionary_std::uids::EXPLICIT_VR_LITTLE_ENDIAN)
+            .media_storage_sop_class_uid(dicom_dictionary_std::uids::PARAMETRIC_MAP_STORAGE)
+            .media_storage_sop_instance_uid("2.25.255265351781878136838832138421288188319")
+            .build()
+            .unwrap();
+        let mut obj = FileDicomObject::new_empty_with_meta(meta);
+        obj.put(DataElement::new(
+            tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::REAL_WORLD_VALUE_MAPPING_SEQUENCE,
+                    VR::SQ,
+                    DataSetSequence::from(vec![item]),
+                ),
+            ])]),
+        ));
+        assert_eq!(real_world_value_mappings(&obj), [vec![mapping]]);
+    }
+}
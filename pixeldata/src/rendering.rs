@@ -0,0 +1,545 @@
+//! Display rendering of grayscale images
+//!
+//! A [`RenderingPipeline`] turns the stored sample values
+//! of a monochrome image into presentation values (P-Values)
+//! through the grayscale pipeline described in [PS3.4 N.2][1]:
+//!
+//! 1. the Modality LUT, usually the _Rescale Slope_ and _Rescale Intercept_,
+//!    produces values in modality units (such as Hounsfield units);
+//! 2. the VOI LUT, either a window or a table,
+//!    selects the range of values of interest;
+//! 3. the Presentation LUT, either a shape (`IDENTITY` or `INVERSE`)
+//!    or a table, produces the P-Values;
+//! 4. the P-Values are scaled to the output bit depth,
+//!    8 bits for common displays and image formats.
+//!
+//! Each stage is configured from the attributes of the image by default,
+//! and may be overridden by an explicit option
+//! or by a grayscale softcopy presentation state
+//! with [`with_presentation_state`](RenderingPipeline::with_presentation_state).
+//! The whole pipeline is evaluated once per possible stored value
+//! and applied to the image as a single look-up table,
+//! so that images rendered by different tools with the same pipeline
+//! are identical.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::{PixelDecoder as _, PresentationLut, RenderingPipeline};
+//!
+//! let obj = open_file("image.dcm")?;
+//! let pixel = obj.decode_pixel_data()?;
+//! let pipeline = RenderingPipeline::new().with_presentation_lut(PresentationLut::Inverse);
+//! // P-Values of the first frame
+//! let values = pipeline.render_values(&pixel, 0)?;
+//! # #[cfg(feature = "image")]
+//! pipeline.render(&pixel, 0)?.save("rendered.png")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part04/sect_N.2.html
+use dicom_core::{DataDictionary, Tag};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+#[cfg(feature = "image")]
+use image::{DynamicImage, ImageBuffer, Luma};
+#[cfg(feature = "image")]
+use snafu::OptionExt;
+use snafu::ResultExt;
+
+use crate::{
+    BitDepthOption, ConvertOptions, CreateLutSnafu, DecodedPixelData, Lut, ModalityLutOption,
+    PhotometricInterpretation, PhotometricInterpretationOption, PixelRepresentation, Rescale,
+    Result, UnsupportedOtherSnafu, VoiLutFunction, VoiLutOption, WindowLevelTransform,
+    attribute::VoiLut,
+    presentation_state::{PresentationState, applies_to},
+};
+
+/// A Presentation LUT table,
+/// as in an item of the _Presentation LUT Sequence_.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresentationLutTable {
+    /// the number of bits of the P-Values in the table
+    pub bits: u16,
+    /// the P-Values, for VOI output values
+    /// spread evenly from the lowest to the highest
+    pub data: Vec<u16>,
+}
+
+impl PresentationLutTable {
+    /// Read a Presentation LUT table from an item of the
+    /// _Presentation LUT Sequence_, if it is well formed.
+    pub fn from_item<D>(item: &InMemDicomObject<D>) -> Option<Self>
+    where
+        D: DataDictionary + Clone,
+    {
+        let ints = |tag: Tag| {
+            item.get(tag)
+                .and_then(|e| e.to_multi_int::<i32>().ok())
+                .unwrap_or_default()
+        };
+        let [entries, _first, bits] = ints(tags::LUT_DESCRIPTOR)[..] else {
+            return None;
+        };
+        // 0 entries stands for 65536
+        let entries = if entries == 0 {
+            65536
+        } else {
+            entries as usize
+        };
+        if !(1..=16).contains(&bits) {
+            return None;
+        }
+        let data: Vec<u16> = ints(tags::LUT_DATA).into_iter().map(|v| v as u16).collect();
+        if data.len() != entries {
+            return None;
+        }
+        Some(PresentationLutTable {
+            bits: bits as u16,
+            data,
+        })
+    }
+
+    /// Map a VOI output value between 0 and 1
+    /// into a P-Value between 0 and 1.
+    fn apply(&self, value: f64) -> f64 {
+        let last = self.data.len().saturating_sub(1);
+        let index = (value * last as f64).round().clamp(0., last as f64) as usize;
+        let max = ((1u32 << self.bits) - 1) as f64;
+        self.data.get(index).map_or(0., |v| *v as f64 / max)
+    }
+}
+
+/// Presentation LUT specifier of a rendering pipeline.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PresentationLut {
+    /// _Default behavior:_
+    /// invert `MONOCHROME1` images, so that low values are displayed bright,
+    /// and keep other images as they are.
+    #[default]
+    Default,
+    /// The `IDENTITY` presentation LUT shape
+    Identity,
+    /// The `INVERSE` presentation LUT shape
+    Inverse,
+    /// An explicit Presentation LUT table
+    Table(PresentationLutTable),
+}
+
+/// A grayscale rendering pipeline,
+/// from stored sample values to presentation values.
+///
+/// See the [module-level documentation](self) for the stages involved.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RenderingPipeline {
+    /// Modality LUT option
+    pub modality_lut: ModalityLutOption,
+    /// VOI LUT option
+    pub voi_lut: VoiLutOption,
+    /// Presentation LUT option
+    pub presentation_lut: PresentationLut,
+    /// Output bit depth of the P-Values:
+    /// 8 bits, 16 bits,
+    /// or by default 8 bits for 8-bit images and 16 bits otherwise
+    pub bit_depth: BitDepthOption,
+}
+
+/// The VOI LUT stage once resolved against the pixel data
+enum VoiStage<'a> {
+    /// the full range of possible values, from lowest to highest
+    Range(f64, f64),
+    Window(WindowLevelTransform),
+    Table(&'a VoiLut),
+}
+
+impl VoiStage<'_> {
+    /// Map a value in modality units into a value between 0 and 1.
+    fn apply(&self, value: f64) -> f64 {
+        match self {
+            VoiStage::Range(low, high) => {
+                if high > low {
+                    ((value - low) / (high - low)).clamp(0., 1.)
+                } else {
+                    0.
+                }
+            }
+            VoiStage::Window(window) => window.apply(value, 1.),
+            VoiStage::Table(lut) => {
+                let last = lut.data.len().saturating_sub(1);
+                let index = (value.round() - lut.min_pixel_value as f64).clamp(0., last as f64);
+                let max = ((1u32 << lut.bits_stored) - 1) as f64;
+                lut.data.get(index as usize).map_or(0., |v| *v as f64 / max)
+            }
+        }
+    }
+}
+
+/// Pick the value of the given frame, or the first value for all frames.
+fn per_frame<T: Copy>(values: &[T], frame: u32) -> Option<T> {
+    values.get(frame as usize).or(values.first()).copied()
+}
+
+impl RenderingPipeline {
+    /// Create a rendering pipeline
+    /// configured from the attributes of the image.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a rendering pipeline from pixel data conversion options.
+    ///
+    /// The `PhotometricInterpretationOption::Ignore` option
+    /// selects the `IDENTITY` presentation LUT shape.
+    pub fn from_options(options: &ConvertOptions) -> Self {
+        RenderingPipeline {
+            modality_lut: options.modality_lut.clone(),
+            voi_lut: options.voi_lut.clone(),
+            presentation_lut: match options.photometric_interpretation {
+                PhotometricInterpretationOption::Ignore => PresentationLut::Identity,
+                _ => PresentationLut::Default,
+            },
+            bit_depth: options.bit_depth,
+        }
+    }
+
+    /// Set the modality LUT option.
+    pub fn with_modality_lut(mut self, modality_lut: ModalityLutOption) -> Self {
+        self.modality_lut = modality_lut;
+        self
+    }
+
+    /// Set the VOI LUT option.
+    pub fn with_voi_lut(mut self, voi_lut: VoiLutOption) -> Self {
+        self.voi_lut = voi_lut;
+        self
+    }
+
+    /// Set the presentation LUT option.
+    pub fn with_presentation_lut(mut self, presentation_lut: PresentationLut) -> Self {
+        self.presentation_lut = presentation_lut;
+        self
+    }
+
+    /// Set the output bit depth.
+    pub fn with_bit_depth(mut self, bit_depth: BitDepthOption) -> Self {
+        self.bit_depth = bit_depth;
+        self
+    }
+
+    /// Apply the LUTs of a presentation state
+    /// to the given 0-based frame of the image identified by `sop_instance_uid`.
+    ///
+    /// The modality LUT and the window of the presentation state,
+    /// when present, replace those of the pipeline,
+    /// and its presentation LUT replaces the photometric interpretation.
+    pub fn with_presentation_state(
+        mut self,
+        presentation_state: &PresentationState,
+        sop_instance_uid: &str,
+        frame: u32,
+    ) -> Self {
+        if let Some(rescale) = presentation_state.rescale {
+            self.modality_lut = ModalityLutOption::Override(rescale);
+        }
+        if let Some(voi) = presentation_state
+            .voi
+            .iter()
+            .find(|voi| applies_to(&voi.references, sop_instance_uid, frame))
+        {
+            self.voi_lut = VoiLutOption::CustomWithFunction(voi.window, voi.function);
+        }
+        self.presentation_lut = match &presentation_state.presentation_lut {
+            Some(table) => PresentationLut::Table(table.clone()),
+            None if presentation_state.inverse => PresentationLut::Inverse,
+            None => PresentationLut::Identity,
+        };
+        self
+    }
+
+    /// The number of bits of the P-Values produced for the given pixel data.
+    pub fn output_bits(&self, pixel: &DecodedPixelData) -> u16 {
+        match self.bit_depth {
+            BitDepthOption::Force8Bit => 8,
+            BitDepthOption::Force16Bit => 16,
+            BitDepthOption::Auto if pixel.bits_allocated() <= 8 => 8,
+            BitDepthOption::Auto => 16,
+        }
+    }
+
+    /// Build the look-up table from stored sample values to P-Values
+    /// for the given frame.
+    ///
+    /// Only monochrome images of up to 16 bits allocated are supported.
+    pub fn build_lut(&self, pixel: &DecodedPixelData, frame: u32) -> Result<Lut<u16>> {
+        let pi = pixel.photometric_interpretation();
+        if !pi.is_monochrome() || pixel.samples_per_pixel() != 1 {
+            return UnsupportedOtherSnafu {
+                name: "PhotometricInterpretation",
+                value: pi.to_string(),
+            }
+            .fail()?;
+        }
+        if !matches!(pixel.bits_allocated(), 8 | 16) {
+            return UnsupportedOtherSnafu {
+                name: "BitsAllocated",
+                value: pixel.bits_allocated().to_string(),
+            }
+            .fail()?;
+        }
+
+        let bits_stored = pixel.bits_stored().clamp(1, pixel.bits_allocated());
+        let signed = pixel.pixel_representation() == PixelRepresentation::Signed;
+        // the range of stored values
+        let (low, high) = if signed {
+            (
+                -(1i64 << (bits_stored - 1)),
+                (1i64 << (bits_stored - 1)) - 1,
+            )
+        } else {
+            (0, (1i64 << bits_stored) - 1)
+        };
+
+        // 1. Modality LUT
+        let rescale = match &self.modality_lut {
+            ModalityLutOption::None => Rescale::new(1., 0.),
+            ModalityLutOption::Override(rescale) => *rescale,
+            ModalityLutOption::RealWorldValues => pixel.real_world_rescale(frame)?,
+            ModalityLutOption::Default => {
+                per_frame(pixel.rescale()?, frame).unwrap_or(Rescale::new(1., 0.))
+            }
+        };
+        let range = |low: f64, high: f64| {
+            let (a, b) = (rescale.apply(low), rescale.apply(high));
+            VoiStage::Range(a.min(b), a.max(b))
+        };
+
+        // 2. VOI LUT
+        let function = || -> Result<VoiLutFunction> {
+            Ok(pixel
+                .voi_lut_function()?
+                .and_then(|f| per_frame(f, frame))
+                .unwrap_or(VoiLutFunction::Linear))
+        };
+        let voi = match (&self.modality_lut, &self.voi_lut) {
+            (ModalityLutOption::None, _) | (_, VoiLutOption::Identity) => {
+                range(low as f64, high as f64)
+            }
+            (_, VoiLutOption::Default | VoiLutOption::First) => {
+                if let Some(lut) = pixel
+                    .voi_lut_sequence()?
+                    .and_then(|luts| luts.get(frame as usize).or(luts.first()))
+                {
+                    VoiStage::Table(lut)
+                } else if let Some(window) = pixel.window()?.and_then(|w| per_frame(w, frame)) {
+                    VoiStage::Window(WindowLevelTransform::new(function()?, window))
+                } else {
+                    let (min, max) = self.stored_value_range(pixel, frame, bits_stored, signed)?;
+                    range(min, max)
+                }
+            }
+            (_, VoiLutOption::Custom(window)) => {
+                VoiStage::Window(WindowLevelTransform::new(function()?, *window))
+            }
+            (_, VoiLutOption::CustomWithFunction(window, function)) => {
+                VoiStage::Window(WindowLevelTransform::new(*function, *window))
+            }
+            (_, VoiLutOption::Normalize) => {
+                let (min, max) = self.stored_value_range(pixel, frame, bits_stored, signed)?;
+                range(min, max)
+            }
+        };
+
+        // 3. Presentation LUT
+        let presentation_lut = match &self.presentation_lut {
+            PresentationLut::Default if *pi == PhotometricInterpretation::Monochrome1 => {
+                &PresentationLut::Inverse
+            }
+            PresentationLut::Default => &PresentationLut::Identity,
+            lut => lut,
+        };
+
+        // 4. output bit depth
+        let y_max = ((1u32 << self.output_bits(pixel)) - 1) as f64;
+
+        Lut::new_with_fn(bits_stored, signed, |x| {
+            let v = voi.apply(rescale.apply(x));
+            let p = match presentation_lut {
+                PresentationLut::Inverse => 1. - v,
+                PresentationLut::Table(table) => table.apply(v),
+                _ => v,
+            };
+            (p * y_max).round().clamp(0., y_max)
+        })
+        .context(CreateLutSnafu)
+        .map_err(Into::into)
+    }
+
+    /// The lowest and highest stored sample values of a frame.
+    fn stored_value_range(
+        &self,
+        pixel: &DecodedPixelData,
+        frame: u32,
+        bits_stored: u16,
+        signed: bool,
+    ) -> Result<(f64, f64)> {
+        let values: Lut<f64> = Lut::new_with_fn(bits_stored, signed, |x| x)
+            .context(CreateLutSnafu)
+            .map_err(crate::Error::from)?;
+        let (min, max) = stored_samples(pixel, frame)?
+            .into_iter()
+            .map(|v| values.get(v))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        Ok(if min <= max { (min, max) } else { (0., 0.) })
+    }
+
+    /// Render the P-Values of the given frame,
+    /// one per pixel in row-major order.
+    ///
+    /// Only monochrome images of up to 16 bits allocated are supported.
+    pub fn render_values(&self, pixel: &DecodedPixelData, frame: u32) -> Result<Vec<u16>> {
+        let lut = self.build_lut(pixel, frame)?;
+        Ok(lut.map_iter(stored_samples(pixel, frame)?).collect())
+    }
+
+    /// Render the given frame into a grayscale image
+    /// of the output bit depth.
+    ///
+    /// Color images are not affected by the LUTs of the pipeline,
+    /// and are converted as they are.
+    #[cfg(feature = "image")]
+    pub fn render(&self, pixel: &DecodedPixelData, frame: u32) -> Result<DynamicImage> {
+        if !pixel.photometric_interpretation().is_monochrome() || pixel.samples_per_pixel() != 1 {
+            let options = ConvertOptions::new().with_bit_depth(self.bit_depth);
+            return pixel.to_dynamic_image_with_options(frame, &options);
+        }
+
+        let values = self.render_values(pixel, frame)?;
+        let (columns, rows) = (pixel.columns(), pixel.rows());
+        let image = if self.output_bits(pixel) == 8 {
+            let values = values.into_iter().map(|v| v as u8).collect();
+            ImageBuffer::<Luma<u8>, _>::from_raw(columns, rows, values).map(DynamicImage::from)
+        } else {
+            ImageBuffer::<Luma<u16>, _>::from_raw(columns, rows, values).map(DynamicImage::from)
+        };
+        Ok(image.context(crate::InvalidImageBufferSnafu)?)
+    }
+}
+
+/// The stored sample values of a frame, as unsigned integers.
+fn stored_samples(pixel: &DecodedPixelData, frame: u32) -> Result<Vec<u16>> {
+    Ok(match pixel.bits_allocated() {
+        8 => pixel
+            .frame_data(frame)?
+            .iter()
+            .map(|v| u16::from(*v))
+            .collect(),
+        _ => pixel.frame_data_ow(frame)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PresentationLut, PresentationLutTable, RenderingPipeline};
+    use crate::{
+        BitDepthOption, DecodedPixelData, ModalityLutOption, PixelDecoder as _, VoiLutFunction,
+        VoiLutOption, WindowLevel,
+    };
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+
+    /// A 2x2 image with 12 bits stored
+    /// and the stored values 0, 100, 200, and 4095.
+    fn image(photometric_interpretation: &str) -> DecodedPixelData<'static> {
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let obj = InMemDicomObject::from_element_iter([
+            us(tags::SAMPLES_PER_PIXEL, 1),
+            DataElement::new(
+                tags::PHOTOMETRIC_INTERPRETATION,
+                VR::CS,
+                photometric_interpretation,
+            ),
+            us(tags::ROWS, 2),
+            us(tags::COLUMNS, 2),
+            us(tags::BITS_ALLOCATED, 16),
+            us(tags::BITS_STORED, 12),
+            us(tags::HIGH_BIT, 11),
+            us(tags::PIXEL_REPRESENTATION, 0),
+            DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "-100"),
+            DataElement::new(tags::RESCALE_SLOPE, VR::DS, "1"),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OW,
+                PrimitiveValue::U16(vec![0, 100, 200, 4095].into()),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.11"),
+        )
+        .unwrap();
+        obj.decode_pixel_data().unwrap().to_owned()
+    }
+
+    #[test]
+    fn render_through_the_pipeline() {
+        let pixel = image("MONOCHROME2");
+        let window = WindowLevel {
+            center: 50.,
+            width: 200.,
+        };
+        let pipeline = RenderingPipeline::new()
+            .with_voi_lut(VoiLutOption::CustomWithFunction(
+                window,
+                VoiLutFunction::LinearExact,
+            ))
+            .with_bit_depth(BitDepthOption::Force8Bit);
+        // rescaled to -100, 0, 100, and 3995
+        assert_eq!(
+            pipeline.render_values(&pixel, 0).unwrap(),
+            [0, 64, 191, 255]
+        );
+
+        let inverse = pipeline
+            .clone()
+            .with_presentation_lut(PresentationLut::Inverse);
+        assert_eq!(inverse.render_values(&pixel, 0).unwrap(), [255, 191, 64, 0]);
+
+        // MONOCHROME1 is inverted by default, but not with an explicit shape
+        let pixel = image("MONOCHROME1");
+        assert_eq!(
+            pipeline.render_values(&pixel, 0).unwrap(),
+            [255, 191, 64, 0]
+        );
+        let identity = pipeline
+            .clone()
+            .with_presentation_lut(PresentationLut::Identity);
+        assert_eq!(
+            identity.render_values(&pixel, 0).unwrap(),
+            [0, 64, 191, 255]
+        );
+
+        // a presentation LUT table of 2-bit P-Values
+        let table = pipeline.with_presentation_lut(PresentationLut::Table(PresentationLutTable {
+            bits: 2,
+            data: vec![3, 1, 0],
+        }));
+        assert_eq!(table.render_values(&pixel, 0).unwrap(), [255, 85, 0, 0]);
+
+        // without modality LUT, the full range of stored values is used
+        let raw = RenderingPipeline::new()
+            .with_modality_lut(ModalityLutOption::None)
+            .with_presentation_lut(PresentationLut::Identity);
+        assert_eq!(
+            raw.render_values(&pixel, 0).unwrap(),
+            [0, 1600, 3201, 65535]
+        );
+    }
+}
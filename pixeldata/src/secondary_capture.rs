@@ -0,0 +1,290 @@
+//! Secondary capture creation
+//!
+//! This module wraps ordinary images,
+//! such as photographs and screenshots,
+//! into new DICOM objects of the
+//! [Secondary Capture Image Storage][1] SOP class.
+//! All required attributes are filled in
+//! (see [`SecondaryCaptureBuilder`]):
+//! new UIDs are generated for the instance and its series
+//! (and its study, unless one is provided),
+//! the image pixel attributes are set from the image,
+//! and the patient and study context
+//! can be copied from an existing DICOM object
+//! so that the capture is filed with the right study.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_pixeldata::secondary_capture::{
+//!     SecondaryCaptureOptions, secondary_capture_from_image,
+//! };
+//!
+//! let image = image::open("photo.jpg")?;
+//! let study = open_file("study.dcm")?;
+//! let options = SecondaryCaptureOptions::new().with_template(&study);
+//! let obj = secondary_capture_from_image(&image, &options)?;
+//! obj.write_to_file("photo.dcm")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.8.html
+use dicom_core::uid::UidGenerator;
+use dicom_dictionary_std::tags;
+use dicom_object::iod::{ImagePixels, SecondaryCaptureBuilder};
+use dicom_object::{DefaultDicomObject, InMemDicomObject};
+use image::DynamicImage;
+use snafu::{ResultExt, Snafu, ensure};
+
+/// An error occurred while creating a secondary capture object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Image size {width}x{height} is too large
+    UnsupportedDimensions { width: u32, height: u32 },
+
+    /// Could not build the secondary capture object
+    Build {
+        #[snafu(source(from(dicom_object::iod::Error, Box::new)))]
+        source: Box<dicom_object::iod::Error>,
+    },
+}
+
+/// Alias for the result of secondary capture creation.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Options for creating a secondary capture object.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct SecondaryCaptureOptions {
+    /// the SOP instance UID (generated by default)
+    pub sop_instance_uid: Option<String>,
+    /// the series instance UID (generated by default)
+    pub series_instance_uid: Option<String>,
+    /// the study instance UID
+    /// (taken from the template, or generated by default)
+    pub study_instance_uid: Option<String>,
+    /// the conversion type code (`WSD`, workstation, by default)
+    pub conversion_type: Option<String>,
+    /// the generator of the UIDs which are not given
+    /// (UUID-derived UIDs under the `2.25` root by default)
+    pub uid_generator: UidGenerator,
+    /// the template object holding the patient and study attributes
+    template: Option<InMemDicomObject>,
+}
+
+impl SecondaryCaptureOptions {
+    /// Create a new set of options with the default behavior:
+    /// all UIDs are generated
+    /// and the patient and study attributes are left empty.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the patient and study attributes
+    /// (_Patient Name_, _Patient ID_, _Study Instance UID_,
+    /// _Accession Number_, and so on)
+    /// from the given DICOM object,
+    /// so that the new object belongs to the same study.
+    pub fn with_template(mut self, template: &InMemDicomObject) -> Self {
+        // keep only the top level attributes,
+        // without pixel data
+        self.template = Some(InMemDicomObject::from_element_iter(
+            template
+                .iter()
+                .filter(|e| e.header().tag != tags::PIXEL_DATA)
+                .cloned(),
+        ));
+        self
+    }
+
+    /// Set the SOP instance UID of the new object.
+    pub fn with_sop_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.sop_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the series instance UID of the new object.
+    pub fn with_series_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.series_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the study instance UID of the new object,
+    /// overriding the one in the template.
+    pub fn with_study_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.study_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the generator of the UIDs which are not given,
+    /// such as one under an organization root.
+    pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+        self.uid_generator = uid_generator;
+        self
+    }
+
+    /// Set the conversion type code
+    /// (e.g. `DI` for digitized images, `SI` for scanned images).
+    pub fn with_conversion_type(mut self, conversion_type: impl Into<String>) -> Self {
+        self.conversion_type = Some(conversion_type.into());
+        self
+    }
+}
+
+/// Create a secondary capture DICOM object from the given image,
+/// encoded in Explicit VR Little Endian.
+///
+/// Grayscale images are stored as `MONOCHROME2`,
+/// and color images as `RGB`,
+/// with 8 or 16 bits per sample depending on the image.
+/// Alpha channels are discarded,
+/// and floating point images are converted to 16 bits per sample.
+///
+/// See [`SecondaryCaptureBuilder`] to create secondary captures
+/// from pixel buffers.
+pub fn secondary_capture_from_image(
+    image: &DynamicImage,
+    options: &SecondaryCaptureOptions,
+) -> Result<DefaultDicomObject> {
+    let mut builder = SecondaryCaptureBuilder::new(image_pixels(image)?)
+        .with_uid_generator(options.uid_generator.clone());
+    if let Some(template) = &options.template {
+        builder = builder.with_template(template);
+    }
+    if let Some(uid) = &options.sop_instance_uid {
+        builder = builder.with_sop_instance_uid(uid);
+    }
+    if let Some(uid) = &options.series_instance_uid {
+        builder = builder.with_series_instance_uid(uid);
+    }
+    if let Some(uid) = &options.study_instance_uid {
+        builder = builder.with_study_instance_uid(uid);
+    }
+    if let Some(conversion_type) = &options.conversion_type {
+        builder = builder.with_conversion_type(conversion_type);
+    }
+    Ok(builder.build().context(BuildSnafu)?)
+}
+
+/// Collect the pixels of the given image
+/// in a form suitable for the _Image Pixel_ module.
+fn image_pixels(image: &DynamicImage) -> Result<ImagePixels> {
+    let (width, height) = (image.width(), image.height());
+    ensure!(
+        width <= u16::MAX as u32 && height <= u16::MAX as u32,
+        UnsupportedDimensionsSnafu { width, height }
+    );
+    let (rows, columns) = (height as u16, width as u16);
+
+    Ok(match image {
+        DynamicImage::ImageLuma8(image) => {
+            ImagePixels::monochrome8(rows, columns, image.as_raw().clone())
+        }
+        DynamicImage::ImageLumaA8(_) => {
+            ImagePixels::monochrome8(rows, columns, image.to_luma8().into_raw())
+        }
+        DynamicImage::ImageLuma16(image) => {
+            ImagePixels::monochrome16(rows, columns, image.as_raw().clone())
+        }
+        DynamicImage::ImageLumaA16(_) => {
+            ImagePixels::monochrome16(rows, columns, image.to_luma16().into_raw())
+        }
+        DynamicImage::ImageRgb8(image) => ImagePixels::rgb8(rows, columns, image.as_raw().clone()),
+        DynamicImage::ImageRgb16(image) => {
+            ImagePixels::rgb16(rows, columns, image.as_raw().clone())
+        }
+        DynamicImage::ImageRgb32F(_)
+        | DynamicImage::ImageRgba16(_)
+        | DynamicImage::ImageRgba32F(_) => {
+            ImagePixels::rgb16(rows, columns, image.to_rgb16().into_raw())
+        }
+        _ => ImagePixels::rgb8(rows, columns, image.to_rgb8().into_raw()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelDecoder as _;
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::uids;
+
+    #[test]
+    fn secondary_capture_from_rgb_image() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(4, 3, |x, y| {
+            image::Rgba([x as u8 * 50, y as u8 * 80, 10, 128])
+        }));
+
+        let template = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "2.25.2"),
+            DataElement::new(tags::MODALITY, VR::CS, "US"),
+        ]);
+        let options = SecondaryCaptureOptions::new().with_template(&template);
+        let obj = secondary_capture_from_image(&image, &options).unwrap();
+
+        let str_of = |tag| obj.get(tag).unwrap().to_str().unwrap().to_string();
+        assert_eq!(
+            obj.meta().media_storage_sop_class_uid(),
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        assert_eq!(
+            obj.meta().media_storage_sop_instance_uid(),
+            str_of(tags::SOP_INSTANCE_UID)
+        );
+        assert_eq!(str_of(tags::PATIENT_NAME), "Doe^John");
+        assert_eq!(str_of(tags::PATIENT_ID), "12345");
+        // same study, but a new series
+        assert_eq!(str_of(tags::STUDY_INSTANCE_UID), "2.25.1");
+        assert_ne!(str_of(tags::SERIES_INSTANCE_UID), "2.25.2");
+        assert_eq!(str_of(tags::MODALITY), "OT");
+        assert_eq!(str_of(tags::CONVERSION_TYPE), "WSD");
+        assert!(obj.get(tags::ACCESSION_NUMBER).is_some());
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.columns(), 4);
+        assert_eq!(decoded.rows(), 3);
+        assert_eq!(decoded.samples_per_pixel(), 3);
+        assert_eq!(decoded.bits_allocated(), 8);
+        assert_eq!(&decoded.data()[..6], &[0, 0, 10, 50, 0, 10]);
+    }
+
+    #[test]
+    fn secondary_capture_from_16bit_grayscale_image() {
+        let image = DynamicImage::ImageLuma16(image::ImageBuffer::from_fn(5, 2, |x, y| {
+            image::Luma([(x * 1000 + y) as u16])
+        }));
+        let options = SecondaryCaptureOptions::new()
+            .with_sop_instance_uid("2.25.3")
+            .with_conversion_type("DI");
+        let obj = secondary_capture_from_image(&image, &options).unwrap();
+
+        assert_eq!(obj.meta().media_storage_sop_instance_uid(), "2.25.3");
+        assert_eq!(
+            obj.get(tags::CONVERSION_TYPE).unwrap().to_str().unwrap(),
+            "DI"
+        );
+        assert!(
+            obj.get(tags::STUDY_INSTANCE_UID)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("2.25.")
+        );
+        assert_eq!(obj.get(tags::PATIENT_NAME).unwrap().to_str().unwrap(), "");
+        assert!(obj.get(tags::PLANAR_CONFIGURATION).is_none());
+
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.photometric_interpretation().as_str(), "MONOCHROME2");
+        assert_eq!(decoded.bits_stored(), 16);
+        let values: Vec<u16> = decoded.to_vec().unwrap();
+        assert_eq!(&values[..5], &[0, 1000, 2000, 3000, 4000]);
+        assert_eq!(values[5], 1);
+    }
+}
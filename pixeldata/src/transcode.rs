@@ -6,19 +6,27 @@
 //! to different transfer syntaxes.
 //!
 //! See the [`Transcode`] trait for more information.
+//! To replace the imaging data of an object altogether,
+//! see [`SetPixelData`].
+//! To encode decoded pixel data into encapsulated pixel data fragments
+//! without a DICOM object, see [`PixelEncoder`].
 use std::borrow::Cow;
 
 use dicom_core::{
-    DataDictionary, DataElement, Length, PrimitiveValue, VR, ops::ApplyOp,
+    DataDictionary, DataElement, Length, PrimitiveValue, VR,
+    ops::{ApplyOp, AttributeOp},
     value::PixelFragmentSequence,
 };
 use dicom_dictionary_std::{tags, uids};
-use dicom_encoding::{Codec, TransferSyntax, TransferSyntaxIndex, adapters::EncodeOptions};
-use dicom_object::{FileDicomObject, InMemDicomObject};
+use dicom_encoding::{
+    Codec, TransferSyntax, TransferSyntaxIndex,
+    adapters::{DynPixelDataWriter, EncodeOptions, PixelDataObject, RawPixelData},
+};
+use dicom_object::{FileDicomObject, InMemDicomObject, mem::InMemElement};
 use dicom_transfer_syntax_registry::{TransferSyntaxRegistry, entries::EXPLICIT_VR_LITTLE_ENDIAN};
 use snafu::{OptionExt, ResultExt, Snafu};
 
-use crate::PixelDecoder;
+use crate::{DecodedPixelData, PixelDecoder, PlanarConfiguration};
 
 /// An error occurred during the object transcoding process.
 #[derive(Debug, Snafu)]
@@ -48,6 +56,12 @@ pub(crate) enum InnerError {
 
     /// Unsupported bits per sample ({bits_allocated})
     UnsupportedBitsAllocated { bits_allocated: u16 },
+
+    /// Pixel data length ({len}) does not match image dimensions (expected {expected})
+    PixelDataLengthMismatch { len: usize, expected: u64 },
+
+    /// Encoded pixel data is too large for a Basic Offset Table
+    OffsetTableOverflow,
 }
 
 /// Alias for the result of transcoding a DICOM object.
@@ -233,6 +247,415 @@ where
     }
 }
 
+/// Interface for replacing the imaging data of a DICOM object
+/// with the samples of an already decoded image.
+///
+/// The image pixel attributes of the object
+/// (_Rows_, _Columns_, _Samples per Pixel_, _Bits Allocated_,
+/// _Photometric Interpretation_, and so on)
+/// are updated to describe the new pixel data,
+/// and the pixel data is encoded according to the target transfer syntax,
+/// either as native pixel data
+/// or as encapsulated pixel data fragments.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_object::open_file;
+/// use dicom_pixeldata::{PixelDecoder as _, SetPixelData as _};
+/// use dicom_transfer_syntax_registry::entries::JPEG_BASELINE;
+///
+/// let source = open_file("image.dcm")?;
+/// let decoded = source.decode_pixel_data()?;
+///
+/// let mut obj = open_file("other.dcm")?;
+/// // take the image from `source` and store it as JPEG
+/// obj.set_pixel_data(&decoded, &JPEG_BASELINE.erased())?;
+/// obj.write_to_file("other_jpg.dcm")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait SetPixelData {
+    /// Replace the object's pixel data with the given decoded pixel data,
+    /// encoding it in the transfer syntax `ts`
+    /// according to the given encoding options.
+    ///
+    /// The meta group is updated to specify the new transfer syntax.
+    /// The encoding options only apply
+    /// if `ts` is an encapsulated pixel data transfer syntax.
+    ///
+    /// The object is not modified if the pixel data
+    /// cannot be encoded to `ts` at all.
+    /// In case of an encoding error,
+    /// the object may be left in an intermediate state,
+    /// which should not be assumed to be consistent.
+    fn set_pixel_data_with_options(
+        &mut self,
+        decoded: &DecodedPixelData,
+        ts: &TransferSyntax,
+        options: EncodeOptions,
+    ) -> Result<()>;
+
+    /// Replace the object's pixel data with the given decoded pixel data,
+    /// encoding it in the transfer syntax `ts`.
+    ///
+    /// The meta group is updated to specify the new transfer syntax.
+    ///
+    /// The object is not modified if the pixel data
+    /// cannot be encoded to `ts` at all.
+    /// In case of an encoding error,
+    /// the object may be left in an intermediate state,
+    /// which should not be assumed to be consistent.
+    fn set_pixel_data(&mut self, decoded: &DecodedPixelData, ts: &TransferSyntax) -> Result<()> {
+        self.set_pixel_data_with_options(decoded, ts, EncodeOptions::default())
+    }
+}
+
+impl<D> SetPixelData for FileDicomObject<InMemDicomObject<D>>
+where
+    D: Clone + DataDictionary,
+{
+    fn set_pixel_data_with_options(
+        &mut self,
+        decoded: &DecodedPixelData,
+        ts: &TransferSyntax,
+        options: EncodeOptions,
+    ) -> Result<()> {
+        // check encoding capabilities before making any changes
+        let writer = if ts.is_encapsulated_pixel_data() {
+            Some(pixel_data_writer(ts)?)
+        } else {
+            None
+        };
+
+        check_native_samples(decoded)?;
+        let bits_allocated = decoded.bits_allocated();
+
+        // update image pixel attributes
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        self.put(us(tags::SAMPLES_PER_PIXEL, decoded.samples_per_pixel()));
+        self.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            decoded.photometric_interpretation().as_str(),
+        ));
+        self.put(us(tags::ROWS, decoded.rows() as u16));
+        self.put(us(tags::COLUMNS, decoded.columns() as u16));
+        self.put(us(tags::BITS_ALLOCATED, bits_allocated));
+        self.put(us(tags::BITS_STORED, decoded.bits_stored()));
+        self.put(us(tags::HIGH_BIT, decoded.high_bit()));
+        self.put(us(
+            tags::PIXEL_REPRESENTATION,
+            decoded.pixel_representation() as u16,
+        ));
+        if decoded.samples_per_pixel() > 1 {
+            self.put(us(
+                tags::PLANAR_CONFIGURATION,
+                decoded.planar_configuration() as u16,
+            ));
+        } else {
+            self.remove_element(tags::PLANAR_CONFIGURATION);
+        }
+        // keep Number of Frames if it was already there,
+        // only add it for multi-frame images
+        if decoded.number_of_frames() > 1 || self.get(tags::NUMBER_OF_FRAMES).is_some() {
+            self.put(DataElement::new(
+                tags::NUMBER_OF_FRAMES,
+                VR::IS,
+                decoded.number_of_frames().to_string(),
+            ));
+        }
+        self.remove_element(tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH);
+
+        self.put(native_pixel_data(decoded)?);
+
+        match writer {
+            Some(writer) => {
+                self.update_meta(|meta| meta.set_transfer_syntax(&EXPLICIT_VR_LITTLE_ENDIAN));
+                encode_inline(self, ts, writer, options)
+            }
+            None => {
+                self.update_meta(|meta| meta.set_transfer_syntax(ts));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The outcome of encoding native pixel data
+/// into an encapsulated pixel data transfer syntax.
+///
+/// Holds the pixel data fragments,
+/// the Basic Offset Table pointing to the first fragment of each frame,
+/// and the attribute operations that a DICOM object
+/// should apply upon assuming the new encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedPixelData {
+    fragments: Vec<Vec<u8>>,
+    offset_table: Vec<u32>,
+    number_of_frames: u32,
+    attribute_ops: Vec<AttributeOp>,
+}
+
+impl EncodedPixelData {
+    /// Retrieve the encoded pixel data fragments.
+    #[inline]
+    pub fn fragments(&self) -> &[Vec<u8>] {
+        &self.fragments
+    }
+
+    /// Retrieve the Basic Offset Table,
+    /// with one byte offset per frame.
+    #[inline]
+    pub fn offset_table(&self) -> &[u32] {
+        &self.offset_table
+    }
+
+    /// Retrieve the number of frames encoded.
+    #[inline]
+    pub fn number_of_frames(&self) -> u32 {
+        self.number_of_frames
+    }
+
+    /// Retrieve the attribute operations to apply to the DICOM object
+    /// which is to hold the encoded pixel data.
+    #[inline]
+    pub fn attribute_ops(&self) -> &[AttributeOp] {
+        &self.attribute_ops
+    }
+
+    /// Calculate the total length of all fragments in bytes,
+    /// as expected in _Encapsulated Pixel Data Value Total Length_.
+    pub fn total_length(&self) -> u64 {
+        self.fragments.iter().map(|f| f.len() as u64).sum()
+    }
+
+    /// Convert into a pixel data fragment sequence,
+    /// discarding the attribute operations.
+    pub fn into_pixel_sequence(self) -> PixelFragmentSequence<Vec<u8>> {
+        PixelFragmentSequence::new(self.offset_table, self.fragments)
+    }
+
+    /// Split into the pixel data fragment sequence
+    /// and the attribute operations.
+    pub fn into_parts(self) -> (PixelFragmentSequence<Vec<u8>>, Vec<AttributeOp>) {
+        (
+            PixelFragmentSequence::new(self.offset_table, self.fragments),
+            self.attribute_ops,
+        )
+    }
+}
+
+/// Interface for encoding native pixel data
+/// into the encapsulated form of a transfer syntax,
+/// the counterpart of [`PixelDecoder`].
+///
+/// Each frame is encoded independently
+/// and split into one or more fragments,
+/// and a Basic Offset Table is generated for the outcome.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_object::open_file;
+/// use dicom_pixeldata::{PixelDecoder as _, PixelEncoder as _};
+/// use dicom_transfer_syntax_registry::entries::RLE_LOSSLESS;
+///
+/// let obj = open_file("image.dcm")?;
+/// let decoded = obj.decode_pixel_data()?;
+/// let encoded = decoded.encode_pixel_data(&RLE_LOSSLESS.erased())?;
+/// println!("{} fragments", encoded.fragments().len());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait PixelEncoder {
+    /// Encode all frames to the transfer syntax `ts`
+    /// according to the given encoding options.
+    ///
+    /// When `fragment_size` is not zero,
+    /// each encoded frame is split into fragments
+    /// of at most `fragment_size` bytes (rounded up to an even number).
+    /// Otherwise, each frame is stored in a single fragment.
+    fn encode_pixel_data_with_options(
+        &self,
+        ts: &TransferSyntax,
+        options: EncodeOptions,
+        fragment_size: u32,
+    ) -> Result<EncodedPixelData>;
+
+    /// Encode all frames to the transfer syntax `ts`,
+    /// one fragment per frame.
+    fn encode_pixel_data(&self, ts: &TransferSyntax) -> Result<EncodedPixelData> {
+        self.encode_pixel_data_with_options(ts, EncodeOptions::default(), 0)
+    }
+}
+
+impl PixelEncoder for DecodedPixelData<'_> {
+    fn encode_pixel_data_with_options(
+        &self,
+        ts: &TransferSyntax,
+        options: EncodeOptions,
+        fragment_size: u32,
+    ) -> Result<EncodedPixelData> {
+        if !ts.is_encapsulated_pixel_data() {
+            return UnsupportedTransferSyntaxSnafu.fail()?;
+        }
+        let writer = pixel_data_writer(ts)?;
+        check_native_samples(self)?;
+
+        let src = NativePixelData::new(self);
+        let fragment_size = match fragment_size {
+            0 => None,
+            n => Some(n as usize + n as usize % 2),
+        };
+
+        let mut fragments = Vec::new();
+        let mut offset_table = Vec::with_capacity(self.number_of_frames() as usize);
+        let mut attribute_ops = Vec::new();
+        let mut offset = 0_u64;
+        for frame in 0..self.number_of_frames() {
+            let mut frame_data = Vec::new();
+            attribute_ops = writer
+                .encode_frame(&src, frame, options.clone(), &mut frame_data)
+                .context(EncodePixelDataSnafu)?;
+            // fragments must have an even length
+            if frame_data.len() % 2 != 0 {
+                frame_data.push(0);
+            }
+
+            offset_table.push(
+                u32::try_from(offset)
+                    .ok()
+                    .context(OffsetTableOverflowSnafu)?,
+            );
+            match fragment_size {
+                Some(size) if frame_data.len() > size => {
+                    for chunk in frame_data.chunks(size) {
+                        offset += chunk.len() as u64 + 8;
+                        fragments.push(chunk.to_vec());
+                    }
+                }
+                _ => {
+                    offset += frame_data.len() as u64 + 8;
+                    fragments.push(frame_data);
+                }
+            }
+        }
+
+        Ok(EncodedPixelData {
+            fragments,
+            offset_table,
+            number_of_frames: self.number_of_frames(),
+            attribute_ops,
+        })
+    }
+}
+
+/// check that the decoded pixel data has whole byte samples
+/// and a length consistent with its image attributes
+fn check_native_samples(decoded: &DecodedPixelData) -> Result<()> {
+    let bits_allocated = decoded.bits_allocated();
+    if bits_allocated != 8 && bits_allocated != 16 {
+        return UnsupportedBitsAllocatedSnafu { bits_allocated }.fail()?;
+    }
+
+    let expected = decoded.rows() as u64
+        * decoded.columns() as u64
+        * decoded.samples_per_pixel() as u64
+        * (bits_allocated / 8) as u64
+        * decoded.number_of_frames() as u64;
+    let len = decoded.data().len();
+    if len as u64 != expected {
+        return PixelDataLengthMismatchSnafu { len, expected }.fail()?;
+    }
+    Ok(())
+}
+
+/// A view of decoded pixel data as a native pixel data object,
+/// with samples always interleaved by pixel
+/// as expected by the pixel data writers.
+struct NativePixelData<'a> {
+    decoded: &'a DecodedPixelData<'a>,
+    data: Cow<'a, [u8]>,
+}
+
+impl<'a> NativePixelData<'a> {
+    fn new(decoded: &'a DecodedPixelData<'a>) -> Self {
+        let samples_per_pixel = decoded.samples_per_pixel() as usize;
+        let data = if samples_per_pixel > 1
+            && decoded.planar_configuration() == PlanarConfiguration::PixelFirst
+        {
+            let bytes_per_sample = decoded.bits_allocated() as usize / 8;
+            let plane_len = decoded.rows() as usize * decoded.columns() as usize * bytes_per_sample;
+            let frame_len = plane_len * samples_per_pixel;
+            let mut data = Vec::with_capacity(decoded.data().len());
+            for frame in decoded.data().chunks_exact(frame_len) {
+                for i in (0..plane_len).step_by(bytes_per_sample) {
+                    for s in 0..samples_per_pixel {
+                        let start = s * plane_len + i;
+                        data.extend_from_slice(&frame[start..start + bytes_per_sample]);
+                    }
+                }
+            }
+            Cow::Owned(data)
+        } else {
+            Cow::Borrowed(decoded.data())
+        };
+        NativePixelData { decoded, data }
+    }
+}
+
+impl PixelDataObject for NativePixelData<'_> {
+    fn transfer_syntax_uid(&self) -> &str {
+        uids::EXPLICIT_VR_LITTLE_ENDIAN
+    }
+
+    fn rows(&self) -> Option<u16> {
+        u16::try_from(self.decoded.rows()).ok()
+    }
+
+    fn cols(&self) -> Option<u16> {
+        u16::try_from(self.decoded.columns()).ok()
+    }
+
+    fn samples_per_pixel(&self) -> Option<u16> {
+        Some(self.decoded.samples_per_pixel())
+    }
+
+    fn bits_allocated(&self) -> Option<u16> {
+        Some(self.decoded.bits_allocated())
+    }
+
+    fn bits_stored(&self) -> Option<u16> {
+        Some(self.decoded.bits_stored())
+    }
+
+    fn photometric_interpretation(&self) -> Option<&str> {
+        Some(self.decoded.photometric_interpretation().as_str())
+    }
+
+    fn number_of_frames(&self) -> Option<u32> {
+        Some(self.decoded.number_of_frames())
+    }
+
+    fn number_of_fragments(&self) -> Option<u32> {
+        None
+    }
+
+    fn fragment(&self, fragment: usize) -> Option<Cow<'_, [u8]>> {
+        (fragment == 0).then(|| Cow::Borrowed(&*self.data))
+    }
+
+    fn offset_table(&self) -> Option<Cow<'_, [u32]>> {
+        None
+    }
+
+    fn raw_pixel_data(&self) -> Option<RawPixelData> {
+        Some(RawPixelData {
+            fragments: vec![self.data.to_vec()].into(),
+            offset_table: Default::default(),
+        })
+    }
+}
+
 /// decode and override pixel data to native form
 /// (`ts` must be a native pixel data transfer syntax)
 fn decode_inline<D, T, U, V>(
@@ -244,32 +667,10 @@ where
 {
     // decode pixel data
     let decoded_pixeldata = obj.decode_pixel_data().context(DecodePixelDataSnafu)?;
-    let bits_allocated = decoded_pixeldata.bits_allocated();
 
     // apply change to pixel data attribute
-    match bits_allocated {
-        8 => {
-            // 8-bit samples
-            let pixels = decoded_pixeldata.data().to_vec();
-            obj.put(DataElement::new_with_len(
-                tags::PIXEL_DATA,
-                VR::OW,
-                Length::defined(pixels.len() as u32),
-                PrimitiveValue::from(pixels),
-            ));
-        }
-        16 => {
-            // 16-bit samples
-            let pixels = decoded_pixeldata.data_ow();
-            obj.put(DataElement::new_with_len(
-                tags::PIXEL_DATA,
-                VR::OW,
-                Length::defined(pixels.len() as u32 * 2),
-                PrimitiveValue::U16(pixels.into()),
-            ));
-        }
-        _ => return UnsupportedBitsAllocatedSnafu { bits_allocated }.fail()?,
-    };
+    let pixel_data = native_pixel_data(&decoded_pixeldata)?;
+    obj.put(pixel_data);
 
     // correct photometric interpretation if necessary
     let samples_per_pixel: u16 = obj
@@ -319,20 +720,40 @@ fn decode_and_encode<D>(
 where
     D: Clone + DataDictionary,
 {
-    let writer = match ts.codec() {
-        Codec::EncapsulatedPixelData(_, Some(writer)) => writer,
-        Codec::EncapsulatedPixelData(..) => return UnsupportedTransferSyntaxSnafu.fail()?,
-        Codec::Dataset(None) => return UnsupportedTransferSyntaxSnafu.fail()?,
-        Codec::Dataset(Some(_)) => return UnsupportedTranscodingSnafu.fail()?,
+    let writer = pixel_data_writer(ts)?;
+
+    // decode pixel data
+    decode_inline(obj, &EXPLICIT_VR_LITTLE_ENDIAN)?;
+
+    encode_inline(obj, ts, writer, options)
+}
+
+/// obtain the pixel data writer of the given transfer syntax
+/// (`ts` must be an encapsulated pixel data transfer syntax)
+fn pixel_data_writer(ts: &TransferSyntax) -> Result<&DynPixelDataWriter> {
+    match ts.codec() {
+        Codec::EncapsulatedPixelData(_, Some(writer)) => Ok(writer),
+        Codec::EncapsulatedPixelData(..) => UnsupportedTransferSyntaxSnafu.fail()?,
+        Codec::Dataset(None) => UnsupportedTransferSyntaxSnafu.fail()?,
+        Codec::Dataset(Some(_)) => UnsupportedTranscodingSnafu.fail()?,
         Codec::None => {
             // already tested in `is_codec_free`
             unreachable!("Unexpected codec from transfer syntax")
         }
-    };
-
-    // decode pixel data
-    decode_inline(obj, &EXPLICIT_VR_LITTLE_ENDIAN)?;
+    }
+}
 
+/// encode the native pixel data of the object
+/// into encapsulated pixel data using the given writer
+fn encode_inline<D>(
+    obj: &mut FileDicomObject<InMemDicomObject<D>>,
+    ts: &TransferSyntax,
+    writer: &DynPixelDataWriter,
+    options: EncodeOptions,
+) -> Result<()>
+where
+    D: Clone + DataDictionary,
+{
     // use pixel data writer API
     let mut offset_table = Vec::new();
     let mut fragments = Vec::new();
@@ -380,15 +801,46 @@ where
     Ok(())
 }
 
+/// create a native pixel data element
+/// with the samples of the decoded pixel data
+fn native_pixel_data<D>(decoded_pixeldata: &DecodedPixelData) -> Result<InMemElement<D>> {
+    let bits_allocated = decoded_pixeldata.bits_allocated();
+    match bits_allocated {
+        8 => {
+            // 8-bit samples
+            let pixels = decoded_pixeldata.data().to_vec();
+            Ok(DataElement::new_with_len(
+                tags::PIXEL_DATA,
+                VR::OW,
+                Length::defined(pixels.len() as u32),
+                PrimitiveValue::from(pixels),
+            ))
+        }
+        16 => {
+            // 16-bit samples
+            let pixels = decoded_pixeldata.data_ow();
+            Ok(DataElement::new_with_len(
+                tags::PIXEL_DATA,
+                VR::OW,
+                Length::defined(pixels.len() as u32 * 2),
+                PrimitiveValue::U16(pixels.into()),
+            ))
+        }
+        _ => UnsupportedBitsAllocatedSnafu { bits_allocated }.fail()?,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use dicom_core::header::Header;
     use dicom_dictionary_std::uids;
     use dicom_object::open_file;
     #[cfg(feature = "native")]
     use dicom_transfer_syntax_registry::entries::JPEG_BASELINE;
     use dicom_transfer_syntax_registry::entries::{
-        ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN, JPEG_EXTENDED,
+        ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN, IMPLICIT_VR_LITTLE_ENDIAN,
+        JPEG_EXTENDED,
     };
 
     #[cfg(feature = "native")]
@@ -497,7 +949,7 @@ mod tests {
         assert_sample_eq_approx("B0", pixels[2], 0);
 
         let y = 54;
-        assert_sample_eq_approx("R5400", pixels[cols * spp * y + 0], 128);
+        assert_sample_eq_approx("R5400", pixels[cols * spp * y], 128);
         assert_sample_eq_approx("G5400", pixels[cols * spp * y + 1], 128);
         assert_sample_eq_approx("G5400", pixels[cols * spp * y + 2], 255);
 
@@ -723,4 +1175,226 @@ mod tests {
         assert_eq!(fragments[0].len(), 100 * 100 * 3);
         assert_eq!(fragments[1].len(), 100 * 100 * 3);
     }
+
+    fn native_image(rows: u16, columns: u16, bits: u16) -> FileDicomObject<InMemDicomObject> {
+        let samples = rows as usize * columns as usize;
+        let pixel_data = if bits == 8 {
+            PrimitiveValue::from((0..samples).map(|i| i as u8).collect::<Vec<_>>())
+        } else {
+            PrimitiveValue::U16((0..samples).map(|i| i as u16 * 100).collect())
+        };
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.221743183549175336412959299516406387775")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, rows));
+        obj.put(us(tags::COLUMNS, columns));
+        obj.put(us(tags::BITS_ALLOCATED, bits));
+        obj.put(us(tags::BITS_STORED, bits));
+        obj.put(us(tags::HIGH_BIT, bits - 1));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 0));
+        obj.put(DataElement::new(tags::PIXEL_DATA, VR::OW, pixel_data));
+        obj
+    }
+
+    #[test]
+    fn set_pixel_data_native() {
+        let source = native_image(4, 3, 8);
+        let decoded = source.decode_pixel_data().unwrap();
+
+        let mut obj = native_image(2, 2, 16);
+        obj.set_pixel_data(&decoded, &EXPLICIT_VR_LITTLE_ENDIAN.erased())
+            .unwrap();
+
+        assert_eq!(obj.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 4);
+        assert_eq!(obj.get(tags::COLUMNS).unwrap().to_int::<u16>().unwrap(), 3);
+        assert_eq!(
+            obj.get(tags::BITS_ALLOCATED)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            8
+        );
+        assert_eq!(obj.get(tags::HIGH_BIT).unwrap().to_int::<u16>().unwrap(), 7);
+        assert!(obj.get(tags::NUMBER_OF_FRAMES).is_none());
+        let pixels = obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap();
+        assert_eq!(&*pixels, &(0..12).collect::<Vec<u8>>()[..]);
+
+        // the new pixel data can be decoded back
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert_eq!(decoded.rows(), 4);
+        assert_eq!(decoded.columns(), 3);
+    }
+
+    #[test]
+    fn set_pixel_data_encapsulated() {
+        let source = native_image(2, 2, 16);
+        let decoded = source.decode_pixel_data().unwrap();
+
+        let mut obj = native_image(4, 4, 8);
+        obj.set_pixel_data(
+            &decoded,
+            &ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN.uid()
+        );
+        assert_eq!(obj.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 2);
+        assert_eq!(
+            obj.get(tags::BITS_ALLOCATED)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            16
+        );
+        let fragments = obj.get(tags::PIXEL_DATA).unwrap().fragments().unwrap();
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].len(), 2 * 2 * 2);
+    }
+
+    #[test]
+    fn transcode_native_keeps_elements() {
+        let mut obj = native_image(2, 2, 16);
+        obj.put(DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"));
+        let before: Vec<_> = obj
+            .iter()
+            .filter(|e| e.tag() != tags::PIXEL_DATA)
+            .cloned()
+            .collect();
+        let group_length = obj.meta().information_group_length;
+
+        obj.transcode(&IMPLICIT_VR_LITTLE_ENDIAN.erased()).unwrap();
+
+        assert_eq!(
+            obj.meta().transfer_syntax(),
+            IMPLICIT_VR_LITTLE_ENDIAN.uid()
+        );
+        // transfer syntax UID is shorter by 2 bytes
+        assert_eq!(obj.meta().information_group_length, group_length - 2);
+        let after: Vec<_> = obj
+            .iter()
+            .filter(|e| e.tag() != tags::PIXEL_DATA)
+            .cloned()
+            .collect();
+        assert_eq!(before, after);
+    }
+
+    /// a native monochrome image with the given number of frames
+    fn native_frames(rows: u16, columns: u16, frames: u32) -> FileDicomObject<InMemDicomObject> {
+        let mut obj = native_image(rows, columns, 8);
+        let samples = rows as usize * columns as usize * frames as usize;
+        obj.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            frames.to_string(),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            PrimitiveValue::from((0..samples).map(|i| (i % 251) as u8).collect::<Vec<_>>()),
+        ));
+        obj
+    }
+
+    #[cfg(feature = "rle")]
+    #[test]
+    fn encode_pixel_data_rle_frames() {
+        use dicom_transfer_syntax_registry::entries::RLE_LOSSLESS;
+
+        let source = native_frames(8, 6, 3);
+        let decoded = source.decode_pixel_data().unwrap();
+        let encoded = decoded
+            .encode_pixel_data(&RLE_LOSSLESS.erased())
+            .expect("Should have encoded to RLE");
+
+        assert_eq!(encoded.number_of_frames(), 3);
+        let fragments = encoded.fragments();
+        assert_eq!(fragments.len(), 3);
+        assert!(fragments.iter().all(|f| f.len() % 2 == 0));
+        // offsets point to the first fragment of each frame
+        let offset_table = encoded.offset_table();
+        assert_eq!(offset_table.len(), 3);
+        assert_eq!(offset_table[0], 0);
+        assert_eq!(offset_table[1], fragments[0].len() as u32 + 8);
+        assert_eq!(
+            offset_table[2],
+            offset_table[1] + fragments[1].len() as u32 + 8
+        );
+
+        // decode it back
+        let mut obj = source.clone();
+        obj.put(DataElement::new_with_len(
+            tags::PIXEL_DATA,
+            VR::OB,
+            Length::UNDEFINED,
+            encoded.into_pixel_sequence(),
+        ));
+        obj.update_meta(|meta| meta.set_transfer_syntax(&RLE_LOSSLESS));
+        let roundtrip = obj.decode_pixel_data().unwrap();
+        assert_eq!(roundtrip.data(), decoded.data());
+    }
+
+    #[cfg(feature = "native")]
+    #[test]
+    fn encode_pixel_data_jpeg_fragmented() {
+        let source = native_frames(16, 16, 2);
+        let decoded = source.decode_pixel_data().unwrap();
+        let encoded = decoded
+            .encode_pixel_data_with_options(&JPEG_BASELINE.erased(), EncodeOptions::default(), 63)
+            .expect("Should have encoded to JPEG baseline");
+
+        assert_eq!(encoded.number_of_frames(), 2);
+        let fragments = encoded.fragments();
+        assert!(fragments.len() > 2);
+        // fragment size is rounded up to an even number
+        assert!(fragments.iter().all(|f| f.len() <= 64 && f.len() % 2 == 0));
+        assert_eq!(
+            encoded.total_length(),
+            fragments.iter().map(|f| f.len() as u64).sum::<u64>()
+        );
+
+        // second frame starts with a JPEG start of image marker
+        let offset_table = encoded.offset_table();
+        assert_eq!(offset_table.len(), 2);
+        assert_eq!(offset_table[0], 0);
+        let mut offset = 0;
+        let second = fragments
+            .iter()
+            .find(|f| {
+                let found = offset == offset_table[1];
+                offset += f.len() as u32 + 8;
+                found
+            })
+            .expect("offset table should point to a fragment");
+        assert_eq!(&second[..2], &[0xFF, 0xD8]);
+        assert_eq!(&fragments[0][..2], &[0xFF, 0xD8]);
+
+        // reports lossy image compression
+        assert!(!encoded.attribute_ops().is_empty());
+    }
+
+    #[test]
+    fn encode_pixel_data_native_unsupported() {
+        let obj = native_image(2, 2, 8);
+        let decoded = obj.decode_pixel_data().unwrap();
+        assert!(
+            decoded
+                .encode_pixel_data(&EXPLICIT_VR_LITTLE_ENDIAN.erased())
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,1200 @@
+//! DICOM video support
+//!
+//! In the MPEG-2, MPEG-4 AVC/H.264 and HEVC/H.265 transfer syntaxes,
+//! the pixel data of all frames is a single encapsulated video stream.
+//! This module provides the means to move these streams
+//! in and out of DICOM objects without decoding them:
+//!
+//! - [`VideoStream`] extracts the elementary stream of a DICOM video object,
+//!   which can then be saved to a `.mpg`, `.h264` or `.h265` file
+//!   and played back or remuxed by general purpose video tools;
+//! - [`SetVideoStream`] encapsulates an existing H.264 or HEVC stream
+//!   in Annex B byte stream format into a DICOM object,
+//!   filling in the image pixel and cine attributes
+//!   from the stream's sequence parameter set.
+//!
+//! Decoding video frames into native pixel data is not supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::video::{SetVideoStream as _, VideoCodec, VideoStream as _};
+//!
+//! // extract the video of a DICOM file
+//! let obj = open_file("video.dcm")?;
+//! let codec = obj.video_codec().expect("not a video");
+//! let mut out = std::fs::File::create(format!("video.{}", codec.file_extension()))?;
+//! obj.write_video_stream(&mut out)?;
+//!
+//! // put a new H.264 stream in its place
+//! let mut obj = obj;
+//! let stream = std::fs::read("recording.h264")?;
+//! let info = obj.set_video_stream(VideoCodec::H264, stream)?;
+//! println!("{}x{}, {} frames", info.width, info.height, info.number_of_frames);
+//! obj.write_to_file("recording.dcm")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::io::Write;
+
+use dicom_core::{
+    DataDictionary, DataElement, DicomValue, PrimitiveValue, VR, dicom_value,
+    value::PixelFragmentSequence,
+};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::TransferSyntaxIndex;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+/// An error occurred while handling a DICOM video stream.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Transfer syntax {uid} is not a video transfer syntax
+    NotVideo { uid: String },
+
+    /// Transfer syntax {uid} is unknown or does not apply to {codec:?} streams
+    TransferSyntaxMismatch { uid: String, codec: VideoCodec },
+
+    /// Missing pixel data
+    MissingPixelData,
+
+    /// Pixel data is not encapsulated in a pixel sequence
+    NotEncapsulated,
+
+    /// Could not write video stream
+    WriteStream { source: std::io::Error },
+
+    /// Encapsulation of {codec:?} streams is not supported
+    UnsupportedCodec { codec: VideoCodec },
+
+    /// No sequence parameter set found in the video stream
+    MissingParameterSet,
+
+    /// Sequence parameter set is truncated or malformed
+    InvalidParameterSet,
+
+    /// No pictures found in the video stream
+    NoPictures,
+
+    /// Unsupported profile {profile} for the video transfer syntaxes
+    UnsupportedProfile { profile: u8 },
+
+    /// Unsupported level {level} for the video transfer syntaxes
+    UnsupportedLevel { level: u8 },
+
+    /// Unsupported chroma format {chroma_format}
+    UnsupportedChromaFormat { chroma_format: u32 },
+
+    /// Picture size {width}x{height} is too large
+    UnsupportedDimensions { width: u32, height: u32 },
+
+    /// Video stream of {len} bytes does not fit in a single fragment
+    StreamTooLarge { len: usize },
+}
+
+/// Alias for the result of video stream operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The maximum length of a single pixel data fragment
+const MAX_FRAGMENT_LENGTH: usize = 0xFFFF_FFFE;
+
+/// The frame rate assumed when neither the options
+/// nor the video stream declare one
+const DEFAULT_FRAME_RATE: f64 = 30.;
+
+/// A video compression format
+/// which can be encapsulated in DICOM pixel data.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum VideoCodec {
+    /// MPEG-2 Video (ISO/IEC 13818-2)
+    Mpeg2,
+    /// MPEG-4 AVC/H.264 (ISO/IEC 14496-10)
+    H264,
+    /// HEVC/H.265 (ISO/IEC 23008-2)
+    Hevc,
+}
+
+impl VideoCodec {
+    /// Identify the video codec of a transfer syntax by its UID.
+    ///
+    /// Returns `None` if the transfer syntax is not a video transfer syntax.
+    pub fn from_transfer_syntax(uid: &str) -> Option<Self> {
+        match uid.trim_end_matches(|c: char| c.is_whitespace() || c == '\0') {
+            uids::MPEG2MPML | uids::MPEG2MPMLF | uids::MPEG2MPHL | uids::MPEG2MPHLF => {
+                Some(VideoCodec::Mpeg2)
+            }
+            uids::MPEG4HP41
+            | uids::MPEG4HP41F
+            | uids::MPEG4HP41BD
+            | uids::MPEG4HP41BDF
+            | uids::MPEG4HP422D
+            | uids::MPEG4HP422DF
+            | uids::MPEG4HP423D
+            | uids::MPEG4HP423DF
+            | uids::MPEG4HP42STEREO
+            | uids::MPEG4HP42STEREOF => Some(VideoCodec::H264),
+            uids::HEVCMP51 | uids::HEVCM10P51 => Some(VideoCodec::Hevc),
+            _ => None,
+        }
+    }
+
+    /// The conventional file extension for an elementary stream of this codec.
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            VideoCodec::Mpeg2 => "mpg",
+            VideoCodec::H264 => "h264",
+            VideoCodec::Hevc => "h265",
+        }
+    }
+
+    /// The defined term of _Lossy Image Compression Method_ for this codec.
+    pub fn lossy_compression_method(self) -> &'static str {
+        match self {
+            VideoCodec::Mpeg2 => "ISO_13818_2",
+            VideoCodec::H264 => "ISO_14496_10",
+            VideoCodec::Hevc => "ISO_23008_2",
+        }
+    }
+}
+
+/// Properties of a video stream,
+/// as declared by its first sequence parameter set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoStreamInfo {
+    /// the video codec
+    pub codec: VideoCodec,
+    /// the profile indicator (`profile_idc`)
+    pub profile: u8,
+    /// the level indicator (`level_idc`)
+    pub level: u8,
+    /// the width of the pictures after cropping
+    pub width: u32,
+    /// the height of the pictures after cropping
+    pub height: u32,
+    /// the luma sample bit depth
+    pub bit_depth: u8,
+    /// the chroma format indicator (0 for monochrome, 1 for 4:2:0, ...)
+    pub chroma_format: u32,
+    /// the frame rate declared in the stream timing information, if any
+    pub frame_rate: Option<f64>,
+    /// the number of pictures in the stream
+    pub number_of_frames: u32,
+}
+
+impl VideoStreamInfo {
+    /// Inspect a video stream in Annex B byte stream format.
+    ///
+    /// Only H.264 and HEVC streams are supported.
+    pub fn probe(codec: VideoCodec, stream: &[u8]) -> Result<Self> {
+        match codec {
+            VideoCodec::H264 => probe_h264(stream),
+            VideoCodec::Hevc => probe_hevc(stream),
+            VideoCodec::Mpeg2 => UnsupportedCodecSnafu { codec }.fail()?,
+        }
+    }
+
+    /// Choose the transfer syntax which fits the stream's profile and level.
+    ///
+    /// The fragmentable variant is chosen
+    /// if `len` bytes do not fit in a single pixel data fragment.
+    pub fn transfer_syntax(&self, len: usize) -> Result<&'static str> {
+        let fragmentable = len > MAX_FRAGMENT_LENGTH;
+        let uid = match self.codec {
+            VideoCodec::H264 => {
+                let (uid, uid_fragmentable, max_level) = match self.profile {
+                    // Constrained Baseline, Main and High profile streams
+                    // are all decodable by a High profile decoder
+                    66 | 77 | 100 if self.level <= 41 => (uids::MPEG4HP41, uids::MPEG4HP41F, 41),
+                    66 | 77 | 100 => (uids::MPEG4HP422D, uids::MPEG4HP422DF, 42),
+                    128 => (uids::MPEG4HP42STEREO, uids::MPEG4HP42STEREOF, 42),
+                    profile => return UnsupportedProfileSnafu { profile }.fail()?,
+                };
+                ensure!(
+                    self.level <= max_level,
+                    UnsupportedLevelSnafu { level: self.level }
+                );
+                if fragmentable { uid_fragmentable } else { uid }
+            }
+            VideoCodec::Hevc => {
+                ensure!(!fragmentable, StreamTooLargeSnafu { len });
+                ensure!(
+                    self.level <= 153,
+                    UnsupportedLevelSnafu { level: self.level }
+                );
+                match self.profile {
+                    1 => uids::HEVCMP51,
+                    2 => uids::HEVCM10P51,
+                    profile => return UnsupportedProfileSnafu { profile }.fail()?,
+                }
+            }
+            codec => return UnsupportedCodecSnafu { codec }.fail()?,
+        };
+        Ok(uid)
+    }
+}
+
+/// Options for encapsulating a video stream.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct VideoOptions {
+    /// the frame rate in frames per second,
+    /// overriding the one declared in the stream
+    pub frame_rate: Option<f64>,
+    /// the transfer syntax UID,
+    /// overriding the one chosen from the stream's profile and level
+    pub transfer_syntax: Option<String>,
+}
+
+impl VideoOptions {
+    /// Create a new set of options with the default behavior:
+    /// the frame rate is taken from the stream
+    /// (30 frames per second if unspecified),
+    /// and the transfer syntax is chosen from the stream's profile and level.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the frame rate in frames per second.
+    pub fn with_frame_rate(mut self, frame_rate: f64) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    /// Set the transfer syntax UID of the DICOM object.
+    pub fn with_transfer_syntax(mut self, uid: impl Into<String>) -> Self {
+        self.transfer_syntax = Some(uid.into());
+        self
+    }
+}
+
+/// Interface for retrieving the video stream of a DICOM video object.
+pub trait VideoStream {
+    /// Identify the video codec of the object's transfer syntax,
+    /// or return `None` if it is not a video transfer syntax.
+    fn video_codec(&self) -> Option<VideoCodec>;
+
+    /// Write the encapsulated video stream to the given writer.
+    fn write_video_stream<W: Write>(&self, to: W) -> Result<()>;
+
+    /// Retrieve the encapsulated video stream as a single byte vector.
+    fn video_stream(&self) -> Result<Vec<u8>> {
+        let mut stream = Vec::new();
+        self.write_video_stream(&mut stream)?;
+        Ok(stream)
+    }
+}
+
+impl<D> VideoStream for FileDicomObject<InMemDicomObject<D>>
+where
+    D: Clone + DataDictionary,
+{
+    fn video_codec(&self) -> Option<VideoCodec> {
+        VideoCodec::from_transfer_syntax(self.meta().transfer_syntax())
+    }
+
+    fn write_video_stream<W: Write>(&self, mut to: W) -> Result<()> {
+        ensure!(
+            self.video_codec().is_some(),
+            NotVideoSnafu {
+                uid: self.meta().transfer_syntax(),
+            }
+        );
+        let pixel_data = self.get(tags::PIXEL_DATA).context(MissingPixelDataSnafu)?;
+        let DicomValue::PixelSequence(seq) = pixel_data.value() else {
+            return NotEncapsulatedSnafu.fail()?;
+        };
+        // the stream is fragmented in order,
+        // regardless of frame boundaries
+        for fragment in seq.fragments() {
+            to.write_all(fragment).context(WriteStreamSnafu)?;
+        }
+        to.flush().context(WriteStreamSnafu)?;
+        Ok(())
+    }
+}
+
+/// Interface for replacing the pixel data of a DICOM object with a video stream.
+pub trait SetVideoStream {
+    /// Replace the object's pixel data with the given video stream
+    /// in Annex B byte stream format,
+    /// according to the given options.
+    ///
+    /// The image pixel, cine and lossy compression attributes
+    /// as well as the transfer syntax in the meta group
+    /// are updated to describe the stream.
+    /// The stream is not re-encoded,
+    /// nor validated beyond what is needed to obtain these attributes.
+    ///
+    /// The object is not modified if the stream cannot be encapsulated.
+    /// On success, the properties of the stream are returned.
+    fn set_video_stream_with_options(
+        &mut self,
+        codec: VideoCodec,
+        stream: Vec<u8>,
+        options: &VideoOptions,
+    ) -> Result<VideoStreamInfo>;
+
+    /// Replace the object's pixel data with the given video stream
+    /// in Annex B byte stream format.
+    ///
+    /// The image pixel, cine and lossy compression attributes
+    /// as well as the transfer syntax in the meta group
+    /// are updated to describe the stream.
+    ///
+    /// The object is not modified if the stream cannot be encapsulated.
+    /// On success, the properties of the stream are returned.
+    fn set_video_stream(&mut self, codec: VideoCodec, stream: Vec<u8>) -> Result<VideoStreamInfo> {
+        self.set_video_stream_with_options(codec, stream, &VideoOptions::default())
+    }
+}
+
+impl<D> SetVideoStream for FileDicomObject<InMemDicomObject<D>>
+where
+    D: Clone + DataDictionary,
+{
+    fn set_video_stream_with_options(
+        &mut self,
+        codec: VideoCodec,
+        mut stream: Vec<u8>,
+        options: &VideoOptions,
+    ) -> Result<VideoStreamInfo> {
+        let info = VideoStreamInfo::probe(codec, &stream)?;
+
+        let ts_uid = match &options.transfer_syntax {
+            Some(uid) => {
+                ensure!(
+                    VideoCodec::from_transfer_syntax(uid) == Some(codec),
+                    TransferSyntaxMismatchSnafu { uid, codec }
+                );
+                uid.as_str()
+            }
+            None => info.transfer_syntax(stream.len())?,
+        };
+        let ts = TransferSyntaxRegistry
+            .get(ts_uid)
+            .context(TransferSyntaxMismatchSnafu { uid: ts_uid, codec })?;
+        let fragmentable = ts_uid.ends_with(".1");
+        let len = stream.len();
+        ensure!(
+            fragmentable || len <= MAX_FRAGMENT_LENGTH,
+            StreamTooLargeSnafu { len }
+        );
+        ensure!(
+            info.width <= u16::MAX as u32 && info.height <= u16::MAX as u32,
+            UnsupportedDimensionsSnafu {
+                width: info.width,
+                height: info.height,
+            }
+        );
+
+        // monochrome streams only have luma samples
+        let (samples_per_pixel, photometric_interpretation) = match info.chroma_format {
+            0 => (1, "MONOCHROME2"),
+            1 => (3, "YBR_PARTIAL_420"),
+            chroma_format => return UnsupportedChromaFormatSnafu { chroma_format }.fail()?,
+        };
+        let bits_stored = info.bit_depth as u16;
+        let bits_allocated = if bits_stored > 8 { 16 } else { 8 };
+
+        // update image pixel attributes
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        self.put(us(tags::SAMPLES_PER_PIXEL, samples_per_pixel));
+        self.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            photometric_interpretation,
+        ));
+        self.put(us(tags::ROWS, info.height as u16));
+        self.put(us(tags::COLUMNS, info.width as u16));
+        self.put(us(tags::BITS_ALLOCATED, bits_allocated));
+        self.put(us(tags::BITS_STORED, bits_stored));
+        self.put(us(tags::HIGH_BIT, bits_stored - 1));
+        self.put(us(tags::PIXEL_REPRESENTATION, 0));
+        if samples_per_pixel > 1 {
+            self.put(us(tags::PLANAR_CONFIGURATION, 0));
+        } else {
+            self.remove_element(tags::PLANAR_CONFIGURATION);
+        }
+        self.put(DataElement::new(
+            tags::NUMBER_OF_FRAMES,
+            VR::IS,
+            info.number_of_frames.to_string(),
+        ));
+
+        // update cine attributes
+        let frame_rate = options
+            .frame_rate
+            .or(info.frame_rate)
+            .filter(|rate| rate.is_finite() && *rate > 0.)
+            .unwrap_or(DEFAULT_FRAME_RATE);
+        self.put(DataElement::new(
+            tags::FRAME_TIME,
+            VR::DS,
+            format_decimal(1000. / frame_rate),
+        ));
+        self.put(DataElement::new(
+            tags::FRAME_INCREMENT_POINTER,
+            VR::AT,
+            dicom_value!(Tags, [tags::FRAME_TIME]),
+        ));
+        self.put(DataElement::new(
+            tags::CINE_RATE,
+            VR::IS,
+            (frame_rate.round() as u32).to_string(),
+        ));
+
+        // update lossy image compression attributes
+        self.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION,
+            VR::CS,
+            "01",
+        ));
+        self.put(DataElement::new(
+            tags::LOSSY_IMAGE_COMPRESSION_METHOD,
+            VR::CS,
+            codec.lossy_compression_method(),
+        ));
+        self.remove_element(tags::LOSSY_IMAGE_COMPRESSION_RATIO);
+
+        for tag in [
+            tags::EXTENDED_OFFSET_TABLE,
+            tags::EXTENDED_OFFSET_TABLE_LENGTHS,
+            tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH,
+        ] {
+            self.remove_element(tag);
+        }
+        if fragmentable {
+            self.put(DataElement::new(
+                tags::ENCAPSULATED_PIXEL_DATA_VALUE_TOTAL_LENGTH,
+                VR::UV,
+                PrimitiveValue::from(len as u64),
+            ));
+        }
+
+        // split into fragments of even length
+        let fragments = if len <= MAX_FRAGMENT_LENGTH {
+            if len % 2 == 1 {
+                stream.push(0);
+            }
+            vec![stream]
+        } else {
+            stream
+                .chunks(MAX_FRAGMENT_LENGTH)
+                .map(|chunk| {
+                    let mut fragment = chunk.to_vec();
+                    if fragment.len() % 2 == 1 {
+                        fragment.push(0);
+                    }
+                    fragment
+                })
+                .collect()
+        };
+        self.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OB,
+            DicomValue::PixelSequence(PixelFragmentSequence::new_fragments(fragments)),
+        ));
+
+        self.update_meta(|meta| meta.set_transfer_syntax(ts));
+
+        Ok(info)
+    }
+}
+
+/// Format a decimal string value with up to 6 decimal places.
+fn format_decimal(value: f64) -> String {
+    let out = format!("{value:.6}");
+    out.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Iterate over the NAL units of a stream in Annex B byte stream format,
+/// excluding start codes.
+fn nal_units(stream: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = match find_start_code(stream) {
+        Some(pos) => &stream[pos + 3..],
+        None => &[][..],
+    };
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let (unit, next) = match find_start_code(rest) {
+            Some(pos) => (&rest[..pos], &rest[pos + 3..]),
+            None => (rest, &[][..]),
+        };
+        rest = next;
+        // trailing zero bytes belong to the next start code
+        let end = unit.iter().rposition(|b| *b != 0).map_or(0, |p| p + 1);
+        Some(&unit[..end])
+    })
+    .filter(|unit| !unit.is_empty())
+}
+
+/// Find the position of the next `00 00 01` start code prefix.
+fn find_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0, 0, 1])
+}
+
+/// Convert a NAL unit payload into its raw byte sequence payload,
+/// removing emulation prevention bytes.
+fn to_rbsp(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len());
+    let mut zeros = 0;
+    for &b in payload {
+        if zeros >= 2 && b == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        out.push(b);
+    }
+    out
+}
+
+/// A big endian bit reader over a raw byte sequence payload,
+/// with Exp-Golomb decoding.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn bit(&mut self) -> Result<u32> {
+        let byte = self
+            .data
+            .get(self.pos / 8)
+            .context(InvalidParameterSetSnafu)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1;
+        self.pos += 1;
+        Ok(bit as u32)
+    }
+
+    fn flag(&mut self) -> Result<bool> {
+        Ok(self.bit()? == 1)
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.bit()?;
+        }
+        Ok(value)
+    }
+
+    fn skip(&mut self, n: usize) -> Result<()> {
+        ensure!(
+            self.pos + n <= self.data.len() * 8,
+            InvalidParameterSetSnafu
+        );
+        self.pos += n;
+        Ok(())
+    }
+
+    /// unsigned Exp-Golomb code
+    fn ue(&mut self) -> Result<u32> {
+        let mut leading_zeros = 0;
+        while self.bit()? == 0 {
+            leading_zeros += 1;
+            ensure!(leading_zeros < 32, InvalidParameterSetSnafu);
+        }
+        Ok(((1_u64 << leading_zeros) - 1 + self.bits(leading_zeros)? as u64) as u32)
+    }
+
+    /// signed Exp-Golomb code
+    fn se(&mut self) -> Result<i32> {
+        let k = self.ue()? as i64;
+        Ok(if k % 2 == 1 { (k + 1) / 2 } else { -(k / 2) } as i32)
+    }
+}
+
+fn probe_h264(stream: &[u8]) -> Result<VideoStreamInfo> {
+    let mut sps = None;
+    let mut number_of_frames = 0;
+    for unit in nal_units(stream) {
+        match unit[0] & 0x1F {
+            // coded slice, first_mb_in_slice == 0 starts a new picture
+            1 | 5 if unit.get(1).is_some_and(|b| b & 0x80 != 0) => {
+                number_of_frames += 1;
+            }
+            // sequence parameter set
+            7 if sps.is_none() => {
+                sps = Some(to_rbsp(&unit[1..]));
+            }
+            _ => {}
+        }
+    }
+    let sps = sps.context(MissingParameterSetSnafu)?;
+    ensure!(number_of_frames > 0, NoPicturesSnafu);
+
+    let mut r = BitReader::new(&sps);
+    let profile = r.bits(8)? as u8;
+    let _constraint_flags = r.bits(8)?;
+    let level = r.bits(8)? as u8;
+    let _seq_parameter_set_id = r.ue()?;
+
+    let mut chroma_format = 1;
+    let mut separate_colour_plane = false;
+    let mut bit_depth = 8;
+    if matches!(
+        profile,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format = r.ue()?;
+        if chroma_format == 3 {
+            separate_colour_plane = r.flag()?;
+        }
+        bit_depth = 8 + r.ue()?;
+        let _bit_depth_chroma_minus8 = r.ue()?;
+        let _qpprime_y_zero_transform_bypass = r.flag()?;
+        if r.flag()? {
+            // sequence scaling matrix
+            let count = if chroma_format == 3 { 12 } else { 8 };
+            for i in 0..count {
+                if r.flag()? {
+                    let size = if i < 6 { 16 } else { 64 };
+                    let mut last_scale = 8;
+                    let mut next_scale = 8;
+                    for _ in 0..size {
+                        if next_scale != 0 {
+                            next_scale = (last_scale + r.se()? + 256) % 256;
+                        }
+                        if next_scale != 0 {
+                            last_scale = next_scale;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let _log2_max_frame_num_minus4 = r.ue()?;
+    match r.ue()? {
+        0 => {
+            let _log2_max_pic_order_cnt_lsb_minus4 = r.ue()?;
+        }
+        1 => {
+            let _delta_pic_order_always_zero = r.flag()?;
+            let _offset_for_non_ref_pic = r.se()?;
+            let _offset_for_top_to_bottom_field = r.se()?;
+            for _ in 0..r.ue()? {
+                let _offset_for_ref_frame = r.se()?;
+            }
+        }
+        _ => {}
+    }
+    let _max_num_ref_frames = r.ue()?;
+    let _gaps_in_frame_num_allowed = r.flag()?;
+    let width_in_mbs = r.ue()? + 1;
+    let height_in_map_units = r.ue()? + 1;
+    let frame_mbs_only = r.flag()?;
+    if !frame_mbs_only {
+        let _mb_adaptive_frame_field = r.flag()?;
+    }
+    let _direct_8x8_inference = r.flag()?;
+
+    let frame_height_factor = if frame_mbs_only { 1 } else { 2 };
+    let mut width = width_in_mbs * 16;
+    let mut height = height_in_map_units * 16 * frame_height_factor;
+    if r.flag()? {
+        // frame cropping, in units of chroma samples
+        let (crop_x, crop_y) = match (chroma_format, separate_colour_plane) {
+            (0, _) | (3, true) => (1, frame_height_factor),
+            (1, _) => (2, 2 * frame_height_factor),
+            (2, _) => (2, frame_height_factor),
+            _ => (1, frame_height_factor),
+        };
+        let left = r.ue()?;
+        let right = r.ue()?;
+        let top = r.ue()?;
+        let bottom = r.ue()?;
+        width = width
+            .checked_sub(crop_x * (left + right))
+            .context(InvalidParameterSetSnafu)?;
+        height = height
+            .checked_sub(crop_y * (top + bottom))
+            .context(InvalidParameterSetSnafu)?;
+    }
+
+    let frame_rate = if r.flag()? {
+        read_h264_vui_frame_rate(&mut r)?
+    } else {
+        None
+    };
+
+    Ok(VideoStreamInfo {
+        codec: VideoCodec::H264,
+        profile,
+        level,
+        width,
+        height,
+        bit_depth: bit_depth as u8,
+        chroma_format: if separate_colour_plane {
+            0
+        } else {
+            chroma_format
+        },
+        frame_rate,
+        number_of_frames,
+    })
+}
+
+/// Read the VUI parameters of an H.264 sequence parameter set
+/// up to the timing information.
+fn read_h264_vui_frame_rate(r: &mut BitReader) -> Result<Option<f64>> {
+    if r.flag()? {
+        // aspect ratio information
+        if r.bits(8)? == 255 {
+            r.skip(32)?;
+        }
+    }
+    if r.flag()? {
+        // overscan information
+        r.skip(1)?;
+    }
+    if r.flag()? {
+        // video signal type, maybe with colour description
+        r.skip(4)?;
+        if r.flag()? {
+            r.skip(24)?;
+        }
+    }
+    if r.flag()? {
+        // chroma sample location
+        r.ue()?;
+        r.ue()?;
+    }
+    if !r.flag()? {
+        return Ok(None);
+    }
+    let num_units_in_tick = r.bits(32)?;
+    let time_scale = r.bits(32)?;
+    if num_units_in_tick == 0 || time_scale == 0 {
+        return Ok(None);
+    }
+    // each frame spans two ticks
+    Ok(Some(time_scale as f64 / (2. * num_units_in_tick as f64)))
+}
+
+fn probe_hevc(stream: &[u8]) -> Result<VideoStreamInfo> {
+    let mut sps = None;
+    let mut number_of_frames = 0;
+    for unit in nal_units(stream) {
+        if unit.len() < 2 {
+            continue;
+        }
+        match (unit[0] >> 1) & 0x3F {
+            // coded slice segment,
+            // first_slice_segment_in_pic_flag starts a new picture
+            0..=9 | 16..=21 if unit.get(2).is_some_and(|b| b & 0x80 != 0) => {
+                number_of_frames += 1;
+            }
+            // sequence parameter set
+            33 if sps.is_none() => {
+                sps = Some(to_rbsp(&unit[2..]));
+            }
+            _ => {}
+        }
+    }
+    let sps = sps.context(MissingParameterSetSnafu)?;
+    ensure!(number_of_frames > 0, NoPicturesSnafu);
+
+    let mut r = BitReader::new(&sps);
+    let _sps_video_parameter_set_id = r.bits(4)?;
+    let max_sub_layers_minus1 = r.bits(3)?;
+    let _temporal_id_nesting = r.flag()?;
+
+    // general profile, tier and level
+    let _general_profile_space = r.bits(2)?;
+    let _general_tier = r.flag()?;
+    let profile_idc = r.bits(5)? as u8;
+    let compatibility_flags = r.bits(32)?;
+    r.skip(48)?;
+    let level = r.bits(8)? as u8;
+    let mut sub_layer_flags = Vec::new();
+    for _ in 0..max_sub_layers_minus1 {
+        sub_layer_flags.push((r.flag()?, r.flag()?));
+    }
+    if max_sub_layers_minus1 > 0 {
+        r.skip(2 * (8 - max_sub_layers_minus1 as usize))?;
+    }
+    for (profile_present, level_present) in sub_layer_flags {
+        if profile_present {
+            r.skip(88)?;
+        }
+        if level_present {
+            r.skip(8)?;
+        }
+    }
+
+    // streams compatible with the Main profile are reported as such
+    let compatible = |profile: u32| compatibility_flags & (1 << (31 - profile)) != 0;
+    let profile = if profile_idc == 1 || compatible(1) {
+        1
+    } else if profile_idc == 2 || compatible(2) {
+        2
+    } else {
+        profile_idc
+    };
+
+    let _sps_seq_parameter_set_id = r.ue()?;
+    let mut chroma_format = r.ue()?;
+    if chroma_format == 3 && r.flag()? {
+        // separate colour planes
+        chroma_format = 0;
+    }
+    let mut width = r.ue()?;
+    let mut height = r.ue()?;
+    if r.flag()? {
+        // conformance window, in units of chroma samples
+        let (crop_x, crop_y) = match chroma_format {
+            1 => (2, 2),
+            2 => (2, 1),
+            _ => (1, 1),
+        };
+        let left = r.ue()?;
+        let right = r.ue()?;
+        let top = r.ue()?;
+        let bottom = r.ue()?;
+        width = width
+            .checked_sub(crop_x * (left + right))
+            .context(InvalidParameterSetSnafu)?;
+        height = height
+            .checked_sub(crop_y * (top + bottom))
+            .context(InvalidParameterSetSnafu)?;
+    }
+    let bit_depth = 8 + r.ue()?;
+
+    Ok(VideoStreamInfo {
+        codec: VideoCodec::Hevc,
+        profile,
+        level,
+        width,
+        height,
+        bit_depth: bit_depth as u8,
+        chroma_format,
+        frame_rate: None,
+        number_of_frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::Value;
+
+    /// A big endian bit writer with Exp-Golomb encoding
+    #[derive(Default)]
+    struct BitWriter {
+        bits: Vec<bool>,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, n: u32, value: u32) -> &mut Self {
+            for i in (0..n).rev() {
+                self.bits.push((value >> i) & 1 == 1);
+            }
+            self
+        }
+
+        fn ue(&mut self, value: u32) -> &mut Self {
+            let v = value + 1;
+            let len = 32 - v.leading_zeros();
+            self.bits(len - 1, 0).bits(len, v)
+        }
+
+        /// finish with RBSP trailing bits
+        fn finish(&mut self) -> Vec<u8> {
+            self.bits.push(true);
+            while self.bits.len() % 8 != 0 {
+                self.bits.push(false);
+            }
+            self.bits
+                .chunks(8)
+                .map(|c| c.iter().fold(0, |acc, b| (acc << 1) | *b as u8))
+                .collect()
+        }
+    }
+
+    /// write a NAL unit with a start code,
+    /// inserting emulation prevention bytes
+    fn push_nal(stream: &mut Vec<u8>, header: &[u8], rbsp: &[u8]) {
+        stream.extend([0, 0, 0, 1]);
+        stream.extend(header);
+        let mut zeros = 0;
+        for &b in rbsp {
+            if zeros >= 2 && b <= 3 {
+                stream.push(3);
+                zeros = 0;
+            }
+            zeros = if b == 0 { zeros + 1 } else { 0 };
+            stream.push(b);
+        }
+    }
+
+    /// H.264 High profile stream with 1280x720 pictures at 25 fps
+    fn h264_stream(level: u8, frames: usize) -> Vec<u8> {
+        let mut sps = BitWriter::default();
+        sps.bits(8, 100).bits(8, 0).bits(8, level as u32).ue(0);
+        // chroma 4:2:0, 8 bits, no scaling matrix
+        sps.ue(1).ue(0).ue(0).bits(1, 0).bits(1, 0);
+        // frame num, picture order count type 0
+        sps.ue(0).ue(0).ue(0);
+        // reference frames, gaps
+        sps.ue(4).bits(1, 0);
+        // 80x45 macroblocks, progressive, no cropping
+        sps.ue(79).ue(44).bits(1, 1).bits(1, 1);
+        sps.bits(1, 0);
+        // VUI with timing information only
+        sps.bits(1, 1)
+            .bits(4, 0)
+            .bits(1, 1)
+            .bits(32, 1)
+            .bits(32, 50)
+            .bits(1, 1);
+        let sps = sps.finish();
+
+        let mut stream = Vec::new();
+        push_nal(&mut stream, &[0x67], &sps);
+        push_nal(&mut stream, &[0x68], &[0xCE, 0x38, 0x80]);
+        for i in 0..frames {
+            let header = if i == 0 { 0x65 } else { 0x41 };
+            // two slices per picture
+            push_nal(
+                &mut stream,
+                &[header],
+                &[0x88, 0x84, 0x00, 0x00, 0x00, 0x21],
+            );
+            push_nal(&mut stream, &[header], &[0x7F, 0x10, 0x20]);
+        }
+        stream
+    }
+
+    /// HEVC Main 10 stream with 1920x1080 pictures
+    fn hevc_stream(frames: usize) -> Vec<u8> {
+        let mut sps = BitWriter::default();
+        sps.bits(4, 0).bits(3, 0).bits(1, 1);
+        // Main 10 profile, level 4.1
+        sps.bits(2, 0).bits(1, 0).bits(5, 2).bits(32, 0x2000_0000);
+        sps.bits(16, 0x9000).bits(32, 0).bits(8, 123);
+        // chroma 4:2:0, 1920x1088 cropped to 1080 rows
+        sps.ue(0).ue(1).ue(1920).ue(1088);
+        sps.bits(1, 1).ue(0).ue(0).ue(0).ue(4);
+        // 10 bits
+        sps.ue(2).ue(2);
+        let sps = sps.finish();
+
+        let mut stream = Vec::new();
+        push_nal(&mut stream, &[0x40, 0x01], &[0x0C, 0x01, 0xFF, 0xFF]);
+        push_nal(&mut stream, &[0x42, 0x01], &sps);
+        push_nal(&mut stream, &[0x44, 0x01], &[0xC1, 0x72]);
+        for i in 0..frames {
+            let header = if i == 0 { [0x26, 0x01] } else { [0x02, 0x01] };
+            push_nal(&mut stream, &header, &[0xAF, 0x00, 0x00, 0x01, 0x18]);
+        }
+        stream
+    }
+
+    fn base_object() -> FileDicomObject<InMemDicomObject> {
+        FileDicomObject::new_empty_with_meta(
+            dicom_object::FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::VIDEO_ENDOSCOPIC_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.261604767753689210359726655517876339895")
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn codec_from_transfer_syntax() {
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::MPEG4HP41F),
+            Some(VideoCodec::H264)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax("1.2.840.10008.1.2.4.107\0"),
+            Some(VideoCodec::Hevc)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::MPEG2MPHL),
+            Some(VideoCodec::Mpeg2)
+        );
+        assert_eq!(
+            VideoCodec::from_transfer_syntax(uids::JPEG_BASELINE8_BIT),
+            None
+        );
+    }
+
+    #[test]
+    fn exp_golomb_and_emulation_prevention() {
+        let mut w = BitWriter::default();
+        w.ue(0).ue(1).ue(2).ue(254).bits(24, 0).bits(8, 1);
+        let rbsp = w.finish();
+        let mut stream = vec![];
+        push_nal(&mut stream, &[0x67], &rbsp);
+        assert!(stream[5..].windows(3).all(|w| w != [0, 0, 1]));
+
+        let units: Vec<_> = nal_units(&stream).collect();
+        assert_eq!(units.len(), 1);
+        let rbsp2 = to_rbsp(&units[0][1..]);
+        assert_eq!(rbsp2, rbsp);
+        let mut r = BitReader::new(&rbsp2);
+        assert_eq!(r.ue().unwrap(), 0);
+        assert_eq!(r.ue().unwrap(), 1);
+        assert_eq!(r.se().unwrap(), -1);
+        assert_eq!(r.ue().unwrap(), 254);
+        assert_eq!(r.bits(32).unwrap(), 1);
+    }
+
+    #[test]
+    fn probe_h264_stream() {
+        let info = VideoStreamInfo::probe(VideoCodec::H264, &h264_stream(41, 12)).unwrap();
+        assert_eq!(
+            info,
+            VideoStreamInfo {
+                codec: VideoCodec::H264,
+                profile: 100,
+                level: 41,
+                width: 1280,
+                height: 720,
+                bit_depth: 8,
+                chroma_format: 1,
+                frame_rate: Some(25.),
+                number_of_frames: 12,
+            }
+        );
+        assert_eq!(info.transfer_syntax(1_000).unwrap(), uids::MPEG4HP41);
+        assert_eq!(
+            info.transfer_syntax(0x1_0000_0000).unwrap(),
+            uids::MPEG4HP41F
+        );
+
+        let info = VideoStreamInfo::probe(VideoCodec::H264, &h264_stream(42, 1)).unwrap();
+        assert_eq!(info.transfer_syntax(1_000).unwrap(), uids::MPEG4HP422D);
+
+        let info = VideoStreamInfo::probe(VideoCodec::H264, &h264_stream(51, 1)).unwrap();
+        assert!(info.transfer_syntax(1_000).is_err());
+    }
+
+    #[test]
+    fn probe_hevc_stream() {
+        let info = VideoStreamInfo::probe(VideoCodec::Hevc, &hevc_stream(3)).unwrap();
+        assert_eq!(
+            info,
+            VideoStreamInfo {
+                codec: VideoCodec::Hevc,
+                profile: 2,
+                level: 123,
+                width: 1920,
+                height: 1080,
+                bit_depth: 10,
+                chroma_format: 1,
+                frame_rate: None,
+                number_of_frames: 3,
+            }
+        );
+        assert_eq!(info.transfer_syntax(1_000).unwrap(), uids::HEVCM10P51);
+    }
+
+    #[test]
+    fn probe_stream_without_parameter_sets() {
+        let err = VideoStreamInfo::probe(VideoCodec::H264, &[0, 0, 1, 0x65, 0x88]).unwrap_err();
+        assert!(matches!(err.0, InnerError::MissingParameterSet));
+        let err = VideoStreamInfo::probe(VideoCodec::Mpeg2, &[0, 0, 1, 0xB3]).unwrap_err();
+        assert!(matches!(err.0, InnerError::UnsupportedCodec { .. }));
+    }
+
+    #[test]
+    fn set_and_extract_h264_stream() {
+        let mut obj = base_object();
+        let stream = h264_stream(41, 30);
+        let info = obj
+            .set_video_stream(VideoCodec::H264, stream.clone())
+            .unwrap();
+        assert_eq!(info.number_of_frames, 30);
+
+        assert_eq!(obj.meta().transfer_syntax(), uids::MPEG4HP41);
+        assert_eq!(obj.video_codec(), Some(VideoCodec::H264));
+        let get_int = |tag| obj.element(tag).unwrap().to_int::<u32>().unwrap();
+        assert_eq!(get_int(tags::ROWS), 720);
+        assert_eq!(get_int(tags::COLUMNS), 1280);
+        assert_eq!(get_int(tags::SAMPLES_PER_PIXEL), 3);
+        assert_eq!(get_int(tags::BITS_ALLOCATED), 8);
+        assert_eq!(get_int(tags::NUMBER_OF_FRAMES), 30);
+        assert_eq!(get_int(tags::CINE_RATE), 25);
+        assert_eq!(
+            obj.element(tags::FRAME_TIME).unwrap().to_str().unwrap(),
+            "40"
+        );
+        assert_eq!(
+            obj.element(tags::PHOTOMETRIC_INTERPRETATION)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "YBR_PARTIAL_420"
+        );
+        assert_eq!(
+            obj.element(tags::LOSSY_IMAGE_COMPRESSION_METHOD)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "ISO_14496_10"
+        );
+
+        // stream is kept in a single fragment
+        let Value::PixelSequence(seq) = obj.element(tags::PIXEL_DATA).unwrap().value() else {
+            panic!("pixel data should be encapsulated");
+        };
+        assert_eq!(seq.fragments().len(), 1);
+        assert!(seq.offset_table().is_empty());
+
+        // extracted stream is the same, save for padding
+        let mut extracted = obj.video_stream().unwrap();
+        assert_eq!(extracted.len() % 2, 0);
+        extracted.truncate(stream.len());
+        assert_eq!(extracted, stream);
+    }
+
+    #[test]
+    fn set_hevc_stream_with_options() {
+        let mut obj = base_object();
+        let options = VideoOptions::new().with_frame_rate(60.);
+        obj.set_video_stream_with_options(VideoCodec::Hevc, hevc_stream(2), &options)
+            .unwrap();
+        assert_eq!(obj.meta().transfer_syntax(), uids::HEVCM10P51);
+        let get_int = |tag| obj.element(tag).unwrap().to_int::<u32>().unwrap();
+        assert_eq!(get_int(tags::BITS_ALLOCATED), 16);
+        assert_eq!(get_int(tags::BITS_STORED), 10);
+        assert_eq!(get_int(tags::HIGH_BIT), 9);
+        assert_eq!(get_int(tags::CINE_RATE), 60);
+        assert_eq!(
+            obj.element(tags::FRAME_TIME).unwrap().to_str().unwrap(),
+            "16.666667"
+        );
+
+        // transfer syntax must match the codec
+        let options = VideoOptions::new().with_transfer_syntax(uids::MPEG4HP41);
+        let err = obj
+            .set_video_stream_with_options(VideoCodec::Hevc, hevc_stream(2), &options)
+            .unwrap_err();
+        assert!(matches!(err.0, InnerError::TransferSyntaxMismatch { .. }));
+        // object was not modified
+        assert_eq!(obj.meta().transfer_syntax(), uids::HEVCM10P51);
+    }
+
+    #[test]
+    fn extract_from_non_video() {
+        let obj = base_object();
+        assert_eq!(obj.video_codec(), None);
+        let err = obj.video_stream().unwrap_err();
+        assert!(matches!(err.0, InnerError::NotVideo { .. }));
+    }
+}
@@ -0,0 +1,412 @@
+//! Volume assembly from a series of images
+//!
+//! This module stacks the frames of a series of DICOM instances,
+//! such as the single-frame files of a CT or MR series,
+//! into a contiguous 3D array of voxels.
+//! The frames are sorted by their position along the slice normal,
+//! and their orientation, dimensions and spacing are checked for consistency,
+//! so that the outcome can be described by a single [`VolumeGeometry`].
+//!
+//! Requires the `ndarray` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::volume::assemble_volume;
+//!
+//! let slices = ["slice1.dcm", "slice2.dcm", "slice3.dcm"]
+//!     .into_iter()
+//!     .map(open_file)
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! // voxels in Hounsfield units, indexed by slice, row and column
+//! let volume = assemble_volume::<f32, _>(&slices)?;
+//! println!(
+//!     "{:?} voxels, origin {:?}, spacing {:?}",
+//!     volume.voxels.shape(),
+//!     volume.geometry.origin,
+//!     volume.geometry.spacing,
+//! );
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_core::DataDictionary;
+use dicom_dictionary_std::tags;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use ndarray::{Array3, s};
+use num_traits::NumCast;
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+use crate::geometry::{GEOMETRY_TOLERANCE, SLICE_SPACING_TOLERANCE, approx_eq, cross, dot};
+use crate::{FrameInfo, PixelDecoder};
+
+pub use crate::geometry::VolumeGeometry;
+
+/// An error occurred while assembling a volume.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// No images to assemble
+    NoImages,
+
+    /// Instances belong to different series
+    MixedSeries,
+
+    /// Could not read the geometry of image #{index}
+    ReadGeometry {
+        index: usize,
+        source: crate::attribute::GetAttributeError,
+    },
+
+    /// Missing {attribute} in image #{index}
+    MissingGeometry {
+        index: usize,
+        attribute: &'static str,
+    },
+
+    /// Could not decode the pixel data of image #{index}
+    DecodePixelData { index: usize, source: crate::Error },
+
+    /// Image #{index} has {samples_per_pixel} samples per pixel, only monochrome is supported
+    UnsupportedSamplesPerPixel {
+        index: usize,
+        samples_per_pixel: u16,
+    },
+
+    /// Image #{index} has a different size ({rows}x{columns})
+    InconsistentDimensions {
+        index: usize,
+        rows: u32,
+        columns: u32,
+    },
+
+    /// Image #{index} has a different orientation
+    InconsistentOrientation { index: usize },
+
+    /// Image #{index} has a different pixel spacing
+    InconsistentPixelSpacing { index: usize },
+
+    /// Two slices are at the same position ({position})
+    DuplicatePosition { position: f64 },
+
+    /// Slices are not evenly spaced (gap of {gap} against a mean of {mean})
+    InconsistentSliceSpacing { gap: f64, mean: f64 },
+}
+
+/// Alias for the result of volume assembly.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A volume of voxels assembled from a series of images.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Volume<T> {
+    /// the voxels, with the shape `(slices, rows, columns)`
+    pub voxels: Array3<T>,
+    /// the position and orientation of the voxels
+    pub geometry: VolumeGeometry,
+}
+
+/// A frame to be placed in the volume
+struct Slice<'a, D>
+where
+    D: DataDictionary + Clone,
+{
+    /// index of the source object
+    index: usize,
+    frame: u32,
+    obj: &'a FileDicomObject<InMemDicomObject<D>>,
+    position: [f64; 3],
+    distance: f64,
+}
+
+/// Assemble a volume from the images of a single series.
+///
+/// Every frame of each object becomes a slice of the volume,
+/// so both single-frame series and enhanced multi-frame objects are supported.
+/// Voxel values go through the Modality LUT (rescale) of each frame,
+/// failing if any value cannot be represented by `T`.
+///
+/// Slices are sorted by their position along the normal of the image plane.
+/// An error is returned if the images do not share the same
+/// size, orientation and pixel spacing,
+/// or if the slices are not evenly spaced.
+pub fn assemble_volume<'a, T, D>(
+    objects: impl IntoIterator<Item = &'a FileDicomObject<InMemDicomObject<D>>>,
+) -> Result<Volume<T>>
+where
+    T: NumCast + Copy + Default + 'static,
+    D: DataDictionary + Clone + 'a,
+{
+    let mut slices = Vec::new();
+    let mut series_uid = None;
+    let mut reference: Option<([f64; 6], [f64; 2], f64)> = None;
+
+    for (index, obj) in objects.into_iter().enumerate() {
+        let uid = obj
+            .get(tags::SERIES_INSTANCE_UID)
+            .and_then(|e| e.to_str().ok())
+            .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string());
+        match (&series_uid, uid) {
+            (None, uid) => series_uid = Some(uid),
+            (Some(Some(expected)), Some(uid)) => ensure!(*expected == uid, MixedSeriesSnafu),
+            _ => {}
+        }
+
+        let frames = FrameInfo::all_from_object(obj).context(ReadGeometrySnafu { index })?;
+        for (frame, info) in frames.into_iter().enumerate() {
+            let position = info.image_position_patient.context(MissingGeometrySnafu {
+                index,
+                attribute: "ImagePositionPatient",
+            })?;
+            let orientation = info
+                .image_orientation_patient
+                .context(MissingGeometrySnafu {
+                    index,
+                    attribute: "ImageOrientationPatient",
+                })?;
+            let pixel_spacing = info.pixel_spacing.context(MissingGeometrySnafu {
+                index,
+                attribute: "PixelSpacing",
+            })?;
+
+            let (expected_orientation, expected_spacing, _) = *reference.get_or_insert((
+                orientation,
+                pixel_spacing,
+                info.spacing_between_slices
+                    .or(info.slice_thickness)
+                    .unwrap_or(1.),
+            ));
+            ensure!(
+                approx_eq(&orientation, &expected_orientation),
+                InconsistentOrientationSnafu { index }
+            );
+            ensure!(
+                approx_eq(&pixel_spacing, &expected_spacing),
+                InconsistentPixelSpacingSnafu { index }
+            );
+
+            let normal = normal(&orientation);
+            slices.push(Slice {
+                index,
+                frame: frame as u32,
+                obj,
+                position,
+                distance: dot(&position, &normal),
+            });
+        }
+    }
+
+    let (orientation, pixel_spacing, default_slice_spacing) = reference.context(NoImagesSnafu)?;
+    slices.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+    // check that slices are evenly spaced
+    let slice_spacing = if slices.len() > 1 {
+        let gaps: Vec<f64> = slices
+            .windows(2)
+            .map(|w| w[1].distance - w[0].distance)
+            .collect();
+        for (gap, w) in gaps.iter().zip(slices.windows(2)) {
+            ensure!(
+                *gap > GEOMETRY_TOLERANCE,
+                DuplicatePositionSnafu {
+                    position: w[0].distance
+                }
+            );
+        }
+        let mean = gaps.iter().sum::<f64>() / gaps.len() as f64;
+        for &gap in &gaps {
+            ensure!(
+                (gap - mean).abs() <= mean * SLICE_SPACING_TOLERANCE,
+                InconsistentSliceSpacingSnafu { gap, mean }
+            );
+        }
+        mean
+    } else {
+        default_slice_spacing
+    };
+
+    // gather the voxels
+    let mut voxels: Option<Array3<T>> = None;
+    // decode each object only once, in its first slice
+    let mut decoded_index = None;
+    let mut frames = None;
+    let num_slices = slices.len();
+    for (i, slice) in slices.iter().enumerate() {
+        let index = slice.index;
+        if decoded_index != Some(index) {
+            let decoded = slice
+                .obj
+                .decode_pixel_data()
+                .context(DecodePixelDataSnafu { index })?;
+            let samples_per_pixel = decoded.samples_per_pixel();
+            ensure!(
+                samples_per_pixel == 1,
+                UnsupportedSamplesPerPixelSnafu {
+                    index,
+                    samples_per_pixel
+                }
+            );
+            let (rows, columns) = (decoded.rows(), decoded.columns());
+            let voxels = voxels.get_or_insert_with(|| {
+                Array3::default((num_slices, rows as usize, columns as usize))
+            });
+            ensure!(
+                voxels.shape()[1..] == [rows as usize, columns as usize],
+                InconsistentDimensionsSnafu {
+                    index,
+                    rows,
+                    columns
+                }
+            );
+            frames = Some(
+                decoded
+                    .to_ndarray_volume::<T>()
+                    .context(DecodePixelDataSnafu { index })?,
+            );
+            decoded_index = Some(index);
+        }
+        let frames = frames.as_ref().expect("frames should be decoded");
+        let voxels = voxels.as_mut().expect("volume should be allocated");
+        voxels
+            .slice_mut(s![i, .., ..])
+            .assign(&frames.slice(s![slice.frame as usize, .., .., 0]));
+    }
+
+    let row_direction = [orientation[0], orientation[1], orientation[2]];
+    let column_direction = [orientation[3], orientation[4], orientation[5]];
+    Ok(Volume {
+        voxels: voxels.context(NoImagesSnafu)?,
+        geometry: VolumeGeometry {
+            origin: slices[0].position,
+            spacing: [pixel_spacing[1], pixel_spacing[0], slice_spacing],
+            direction: [row_direction, column_direction, normal(&orientation)],
+        },
+    })
+}
+
+/// the normal of the image plane,
+/// as the cross product of the row and column direction cosines
+fn normal(orientation: &[f64; 6]) -> [f64; 3] {
+    cross(
+        &[orientation[0], orientation[1], orientation[2]],
+        &[orientation[3], orientation[4], orientation[5]],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value};
+    use dicom_dictionary_std::uids;
+    use dicom_object::FileMetaTableBuilder;
+
+    /// a 2x3 CT slice at the given height
+    fn slice(z: f64, value: i16) -> FileDicomObject<InMemDicomObject> {
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.30673491369506701220577153633350324176")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(DataElement::new(
+            tags::SERIES_INSTANCE_UID,
+            VR::UI,
+            "2.25.95952161498154598894187023492198398400",
+        ));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 3));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 16));
+        obj.put(us(tags::HIGH_BIT, 15));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 1));
+        obj.put(DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "-1024"));
+        obj.put(DataElement::new(tags::RESCALE_SLOPE, VR::DS, "1"));
+        obj.put(DataElement::new(
+            tags::IMAGE_POSITION_PATIENT,
+            VR::DS,
+            dicom_value!(F64, [-10., 20., z]),
+        ));
+        obj.put(DataElement::new(
+            tags::IMAGE_ORIENTATION_PATIENT,
+            VR::DS,
+            dicom_value!(F64, [1., 0., 0., 0., 1., 0.]),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_SPACING,
+            VR::DS,
+            dicom_value!(F64, [0.5, 0.75]),
+        ));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::from(
+                (0..6_i16)
+                    .flat_map(|i| (value + i).to_le_bytes())
+                    .collect::<Vec<u8>>(),
+            ),
+        ));
+        obj
+    }
+
+    #[test]
+    fn assemble_sorted_volume() {
+        // given out of order
+        let slices = [slice(5., 200), slice(-5., 0), slice(0., 100)];
+        let volume = assemble_volume::<f32, _>(&slices).unwrap();
+
+        assert_eq!(volume.voxels.shape(), &[3, 2, 3]);
+        assert_eq!(volume.voxels[[0, 0, 0]], -1024.);
+        assert_eq!(volume.voxels[[1, 0, 0]], -924.);
+        assert_eq!(volume.voxels[[2, 1, 2]], -819.);
+
+        let geometry = volume.geometry;
+        assert_eq!(geometry.origin, [-10., 20., -5.]);
+        assert_eq!(geometry.spacing, [0.75, 0.5, 5.]);
+        assert_eq!(
+            geometry.direction,
+            [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]
+        );
+    }
+
+    #[test]
+    fn reject_inconsistent_series() {
+        assert!(assemble_volume::<f32, dicom_object::StandardDataDictionary>([]).is_err());
+
+        // uneven spacing
+        let slices = [slice(0., 0), slice(1., 0), slice(5., 0)];
+        assert!(assemble_volume::<f32, _>(&slices).is_err());
+
+        // duplicate position
+        let slices = [slice(0., 0), slice(0., 0)];
+        assert!(assemble_volume::<f32, _>(&slices).is_err());
+
+        // different orientation
+        let mut oblique = slice(1., 0);
+        oblique.put(DataElement::new(
+            tags::IMAGE_ORIENTATION_PATIENT,
+            VR::DS,
+            dicom_value!(F64, [1., 0., 0., 0., 0., 1.]),
+        ));
+        let slices = [slice(0., 0), oblique];
+        assert!(assemble_volume::<f32, _>(&slices).is_err());
+
+        // different series
+        let mut other = slice(1., 0);
+        other.put(DataElement::new(
+            tags::SERIES_INSTANCE_UID,
+            VR::UI,
+            "2.25.1",
+        ));
+        let slices = [slice(0., 0), other];
+        assert!(assemble_volume::<f32, _>(&slices).is_err());
+    }
+}
@@ -0,0 +1,623 @@
+//! Waveform support
+//!
+//! Waveform objects, such as 12-lead ECGs and hemodynamic recordings,
+//! hold their signals in the items of the _Waveform Sequence_ (5400,0100),
+//! one per multiplex group of channels sampled at the same frequency.
+//! This module parses each multiplex group into a [`Waveform`],
+//! with the samples of each channel converted to physical units
+//! by applying the channel sensitivity, correction factor and baseline.
+//!
+//! The samples can be written as CSV or NumPy (`.npy`) files,
+//! converted into an `ndarray` (requires the `ndarray` feature),
+//! or plotted into an image (requires the `image` feature).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_object::open_file;
+//! use dicom_pixeldata::waveforms::read_waveforms;
+//!
+//! let obj = open_file("ecg.dcm")?;
+//! for waveform in read_waveforms(&obj)? {
+//!     println!(
+//!         "{} channels at {} Hz, {} s",
+//!         waveform.channels.len(),
+//!         waveform.sampling_frequency,
+//!         waveform.duration()
+//!     );
+//!     for channel in &waveform.channels {
+//!         let max = channel.samples.iter().copied().fold(f64::MIN, f64::max);
+//!         println!("{}: max {max} {}", channel.name(), channel.units_symbol());
+//!     }
+//!     waveform.write_csv(std::fs::File::create("ecg.csv")?)?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use std::io::{self, Write};
+
+use dicom_core::{DataDictionary, PrimitiveValue, Tag};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_object::code::{Code, CodeError};
+use snafu::{OptionExt, ResultExt, Snafu, ensure};
+
+/// An error occurred while reading the waveforms of an object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+#[derive(Debug, Snafu)]
+pub(crate) enum InnerError {
+    /// Missing attribute {tag} in waveform #{index}
+    MissingAttribute { index: usize, tag: Tag },
+
+    /// Could not convert attribute {tag} in waveform #{index}
+    ConvertValue {
+        index: usize,
+        tag: Tag,
+        #[snafu(source(from(dicom_core::value::ConvertValueError, Box::from)))]
+        source: Box<dicom_core::value::ConvertValueError>,
+    },
+
+    /// Invalid code in {tag} of channel #{channel} in waveform #{index}
+    InvalidCode {
+        index: usize,
+        channel: usize,
+        tag: Tag,
+        source: CodeError,
+    },
+
+    /// Unsupported sample interpretation `{interpretation}`
+    /// with {bits_allocated} bits allocated in waveform #{index}
+    UnsupportedSampleFormat {
+        index: usize,
+        interpretation: String,
+        bits_allocated: u16,
+    },
+
+    /// Waveform data in waveform #{index} is too short
+    /// for {samples} samples of {channels} channel(s)
+    NotEnoughData {
+        index: usize,
+        samples: u32,
+        channels: u16,
+    },
+}
+
+/// Alias for the result of waveform operations.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A multiplex group of waveform channels,
+/// as in an item of the _Waveform Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// The _Multiplex Group Label_, if defined
+    pub label: Option<String>,
+    /// The _Waveform Originality_, `ORIGINAL` or `DERIVED`
+    pub originality: Option<String>,
+    /// The number of samples per second of each channel
+    pub sampling_frequency: f64,
+    /// The number of samples of each channel
+    pub number_of_samples: u32,
+    /// The channels of the multiplex group
+    pub channels: Vec<Channel>,
+}
+
+/// A channel of a waveform,
+/// as described in an item of the _Channel Definition Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    /// The _Channel Label_, if defined
+    pub label: Option<String>,
+    /// The source of the channel,
+    /// such as (5.6.3-9-1, SCPECG, "Lead I")
+    pub source: Option<Code>,
+    /// The units of the samples after applying the channel sensitivity,
+    /// such as (uV, UCUM, "microvolt")
+    pub units: Option<Code>,
+    /// The _Channel Sensitivity_,
+    /// the value in `units` of one unit of the stored samples,
+    /// if defined
+    pub sensitivity: Option<f64>,
+    /// The _Channel Sensitivity Correction Factor_ (1 by default)
+    pub correction_factor: f64,
+    /// The _Channel Baseline_ in `units` (0 by default)
+    pub baseline: f64,
+    /// The samples of the channel,
+    /// in `units` if a sensitivity is defined
+    /// or as stored otherwise
+    pub samples: Vec<f64>,
+}
+
+impl Channel {
+    /// The name of the channel,
+    /// from its label or else from the meaning of its source.
+    pub fn name(&self) -> &str {
+        self.label
+            .as_deref()
+            .or(self.source.as_ref().map(|code| code.meaning.as_ref()))
+            .unwrap_or_default()
+    }
+
+    /// The symbol of the units of the samples,
+    /// or an empty string if not defined.
+    pub fn units_symbol(&self) -> &str {
+        self.units
+            .as_ref()
+            .map(|code| code.value.as_ref())
+            .unwrap_or_default()
+    }
+}
+
+impl Waveform {
+    /// The duration of the waveform in seconds.
+    pub fn duration(&self) -> f64 {
+        self.number_of_samples as f64 / self.sampling_frequency
+    }
+
+    /// The time of the sample at the given index,
+    /// in seconds since the start of the waveform.
+    pub fn sample_time(&self, index: usize) -> f64 {
+        index as f64 / self.sampling_frequency
+    }
+
+    /// Look up a channel by its name.
+    pub fn channel(&self, name: &str) -> Option<&Channel> {
+        self.channels.iter().find(|channel| channel.name() == name)
+    }
+
+    /// Collect the samples into an array
+    /// with the shape `(samples, channels)`.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<f64> {
+        ndarray::Array2::from_shape_fn(
+            (self.number_of_samples as usize, self.channels.len()),
+            |(sample, channel)| self.channels[channel].samples[sample],
+        )
+    }
+
+    /// Write the samples as comma-separated values,
+    /// with a header row naming each channel and its units
+    /// and the time of each sample in seconds in the first column.
+    pub fn write_csv(&self, mut to: impl Write) -> io::Result<()> {
+        write!(to, "time [s]")?;
+        for (i, channel) in self.channels.iter().enumerate() {
+            let name = match channel.name() {
+                "" => format!("channel {}", i + 1),
+                name => name.to_string(),
+            };
+            let header = match channel.units_symbol() {
+                "" => name,
+                units => format!("{name} [{units}]"),
+            };
+            if header.contains([',', '"', '\n']) {
+                write!(to, ",\"{}\"", header.replace('"', "\"\""))?;
+            } else {
+                write!(to, ",{header}")?;
+            }
+        }
+        writeln!(to)?;
+        for sample in 0..self.number_of_samples as usize {
+            write!(to, "{}", self.sample_time(sample))?;
+            for channel in &self.channels {
+                write!(to, ",{}", channel.samples[sample])?;
+            }
+            writeln!(to)?;
+        }
+        to.flush()
+    }
+
+    /// Write the samples in the NumPy array format (`.npy`),
+    /// as 64-bit floating point numbers
+    /// with the shape `(samples, channels)`.
+    pub fn write_npy(&self, mut to: impl Write) -> io::Result<()> {
+        let header = format!(
+            "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+            self.number_of_samples,
+            self.channels.len()
+        );
+        // the magic string, version and header length take 10 bytes,
+        // and the header ends with a new line
+        // so that the data is aligned to 64 bytes
+        let padding = (10 + header.len() + 1).next_multiple_of(64) - (10 + header.len() + 1);
+        to.write_all(b"\x93NUMPY\x01\x00")?;
+        to.write_all(&((header.len() + padding + 1) as u16).to_le_bytes())?;
+        to.write_all(header.as_bytes())?;
+        to.write_all(&b" ".repeat(padding))?;
+        to.write_all(b"\n")?;
+        for sample in 0..self.number_of_samples as usize {
+            for channel in &self.channels {
+                to.write_all(&channel.samples[sample].to_le_bytes())?;
+            }
+        }
+        to.flush()
+    }
+
+    /// Plot the channels into an image,
+    /// one black trace over a white strip of `channel_height` pixels
+    /// per channel,
+    /// each scaled to the range of its samples.
+    #[cfg(feature = "image")]
+    pub fn plot(&self, width: u32, channel_height: u32) -> image::RgbImage {
+        let height = channel_height * self.channels.len().max(1) as u32;
+        let mut image = image::RgbImage::from_pixel(width, height, image::Rgb([0xFF; 3]));
+        let n = self.number_of_samples as usize;
+        let margin = channel_height as f64 / 10.;
+
+        for (c, channel) in self.channels.iter().enumerate() {
+            let top = (c as u32 * channel_height) as f64;
+            if c > 0 {
+                for x in 0..width {
+                    image.put_pixel(x, top as u32, image::Rgb([0xC0; 3]));
+                }
+            }
+
+            let (min, max) = channel
+                .samples
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+                    (min.min(v), max.max(v))
+                });
+            if min > max {
+                continue;
+            }
+            let range = (max - min).max(f64::EPSILON);
+            let to_point = |i: usize, v: f64| {
+                let x = if n > 1 {
+                    i as f64 * (width as f64 - 1.) / (n as f64 - 1.)
+                } else {
+                    0.
+                };
+                let y =
+                    top + margin + (max - v) / range * (channel_height as f64 - 1. - 2. * margin);
+                (x.round() as i64, y.round() as i64)
+            };
+
+            let mut previous = None;
+            for (i, &v) in channel.samples.iter().enumerate() {
+                if !v.is_finite() {
+                    previous = None;
+                    continue;
+                }
+                let point = to_point(i, v);
+                draw_line(&mut image, previous.unwrap_or(point), point);
+                previous = Some(point);
+            }
+        }
+        image
+    }
+}
+
+/// Draw a black line between two points,
+/// ignoring the pixels outside of the image.
+#[cfg(feature = "image")]
+fn draw_line(image: &mut image::RgbImage, (x0, y0): (i64, i64), (x1, y1): (i64, i64)) {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    loop {
+        if (0..image.width() as i64).contains(&x) && (0..image.height() as i64).contains(&y) {
+            image.put_pixel(x as u32, y as u32, image::Rgb([0; 3]));
+        }
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Read all waveforms of a DICOM object,
+/// one per item of the _Waveform Sequence_.
+///
+/// Samples encoded in µ-law or A-law are not supported.
+pub fn read_waveforms<D>(obj: &InMemDicomObject<D>) -> Result<Vec<Waveform>>
+where
+    D: DataDictionary + Clone,
+{
+    obj.get(tags::WAVEFORM_SEQUENCE)
+        .and_then(|e| e.items())
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, item)| waveform(item, index))
+        .collect()
+}
+
+/// Read a multiplex group from an item of the _Waveform Sequence_.
+fn waveform<D>(item: &InMemDicomObject<D>, index: usize) -> Result<Waveform>
+where
+    D: DataDictionary + Clone,
+{
+    let get = |tag| item.get(tag).and_then(|e| e.value().primitive());
+    let get_str = |tag| {
+        get(tag)
+            .map(|v| {
+                v.to_str()
+                    .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                    .to_string()
+            })
+            .filter(|s| !s.is_empty())
+    };
+    let required = |tag| get(tag).context(MissingAttributeSnafu { index, tag });
+
+    let channels: u16 = required(tags::NUMBER_OF_WAVEFORM_CHANNELS)?
+        .to_int()
+        .context(ConvertValueSnafu {
+            index,
+            tag: tags::NUMBER_OF_WAVEFORM_CHANNELS,
+        })?;
+    let samples: u32 = required(tags::NUMBER_OF_WAVEFORM_SAMPLES)?
+        .to_int()
+        .context(ConvertValueSnafu {
+            index,
+            tag: tags::NUMBER_OF_WAVEFORM_SAMPLES,
+        })?;
+    let sampling_frequency =
+        required(tags::SAMPLING_FREQUENCY)?
+            .to_float64()
+            .context(ConvertValueSnafu {
+                index,
+                tag: tags::SAMPLING_FREQUENCY,
+            })?;
+    let bits_allocated: u16 =
+        required(tags::WAVEFORM_BITS_ALLOCATED)?
+            .to_int()
+            .context(ConvertValueSnafu {
+                index,
+                tag: tags::WAVEFORM_BITS_ALLOCATED,
+            })?;
+    let interpretation =
+        get_str(tags::WAVEFORM_SAMPLE_INTERPRETATION).context(MissingAttributeSnafu {
+            index,
+            tag: tags::WAVEFORM_SAMPLE_INTERPRETATION,
+        })?;
+    let data = required(tags::WAVEFORM_DATA)?;
+
+    let raw = raw_samples(data, bits_allocated, &interpretation).context(
+        UnsupportedSampleFormatSnafu {
+            index,
+            interpretation: interpretation.clone(),
+            bits_allocated,
+        },
+    )?;
+    let len = samples as usize * channels as usize;
+    ensure!(
+        raw.len() >= len,
+        NotEnoughDataSnafu {
+            index,
+            samples,
+            channels
+        }
+    );
+
+    let definitions = item
+        .get(tags::CHANNEL_DEFINITION_SEQUENCE)
+        .and_then(|e| e.items())
+        .unwrap_or_default();
+    let channels = (0..channels as usize)
+        .map(|channel| {
+            let definition = definitions.get(channel);
+            let get = |tag| {
+                definition
+                    .and_then(|item| item.get(tag))
+                    .and_then(|e| e.value().primitive())
+            };
+            let float = |tag| {
+                get(tag)
+                    .filter(|v| !v.to_str().trim().is_empty())
+                    .map(|v| v.to_float64())
+                    .transpose()
+                    .context(ConvertValueSnafu { index, tag })
+            };
+            let code = |tag| {
+                definition
+                    .filter(|item| item.get(tag).is_some())
+                    .map(|item| Code::from_sequence(item, tag))
+                    .transpose()
+                    .context(InvalidCodeSnafu {
+                        index,
+                        channel,
+                        tag,
+                    })
+            };
+
+            let sensitivity = float(tags::CHANNEL_SENSITIVITY)?;
+            let correction_factor =
+                float(tags::CHANNEL_SENSITIVITY_CORRECTION_FACTOR)?.unwrap_or(1.);
+            let baseline = float(tags::CHANNEL_BASELINE)?.unwrap_or(0.);
+            let scale = sensitivity.unwrap_or(1.) * correction_factor;
+            let samples = raw[..len]
+                .iter()
+                .skip(channel)
+                .step_by(channels as usize)
+                .map(|&v| v as f64 * scale + baseline)
+                .collect();
+
+            Ok(Channel {
+                label: get(tags::CHANNEL_LABEL)
+                    .map(|v| v.to_str().trim().to_string())
+                    .filter(|s| !s.is_empty()),
+                source: code(tags::CHANNEL_SOURCE_SEQUENCE)?,
+                units: code(tags::CHANNEL_SENSITIVITY_UNITS_SEQUENCE)?,
+                sensitivity,
+                correction_factor,
+                baseline,
+                samples,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Waveform {
+        label: get_str(tags::MULTIPLEX_GROUP_LABEL),
+        originality: get_str(tags::WAVEFORM_ORIGINALITY),
+        sampling_frequency,
+        number_of_samples: samples,
+        channels,
+    })
+}
+
+/// Interpret the waveform data as stored samples,
+/// returning `None` if the sample format is not supported.
+fn raw_samples(
+    data: &PrimitiveValue,
+    bits_allocated: u16,
+    interpretation: &str,
+) -> Option<Vec<i64>> {
+    // words in little endian
+    let bytes = match data {
+        PrimitiveValue::U16(words) => words.iter().flat_map(|w| w.to_le_bytes()).collect(),
+        PrimitiveValue::I16(words) => words.iter().flat_map(|w| w.to_le_bytes()).collect(),
+        v => v.to_bytes().into_owned(),
+    };
+    let samples = match (bits_allocated, interpretation) {
+        (8, "SB") => bytes.iter().map(|&b| b as i8 as i64).collect(),
+        (8, "UB") => bytes.iter().map(|&b| b as i64).collect(),
+        (16, "SS") => bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as i64)
+            .collect(),
+        (16, "US") => bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as i64)
+            .collect(),
+        (32, "SL") => bytes
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as i64)
+            .collect(),
+        (32, "UL") => bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as i64)
+            .collect(),
+        _ => return None,
+    };
+    Some(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, VR};
+    use dicom_object::code::ucum;
+
+    fn waveform_object() -> InMemDicomObject {
+        let channel = |label: &str, sensitivity: &str, baseline: &str| {
+            InMemDicomObject::from_element_iter([
+                DataElement::new(tags::CHANNEL_LABEL, VR::SH, label),
+                DataElement::new(tags::CHANNEL_SENSITIVITY, VR::DS, sensitivity),
+                ucum::MILLIMETER.to_sequence_element(tags::CHANNEL_SENSITIVITY_UNITS_SEQUENCE),
+                DataElement::new(tags::CHANNEL_BASELINE, VR::DS, baseline),
+            ])
+        };
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::WAVEFORM_ORIGINALITY, VR::CS, "ORIGINAL"),
+            DataElement::new(
+                tags::NUMBER_OF_WAVEFORM_CHANNELS,
+                VR::US,
+                PrimitiveValue::from(2_u16),
+            ),
+            DataElement::new(
+                tags::NUMBER_OF_WAVEFORM_SAMPLES,
+                VR::UL,
+                PrimitiveValue::from(3_u32),
+            ),
+            DataElement::new(tags::SAMPLING_FREQUENCY, VR::DS, "500"),
+            DataElement::new(tags::MULTIPLEX_GROUP_LABEL, VR::SH, "RHYTHM"),
+            DataElement::new(
+                tags::CHANNEL_DEFINITION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![channel("I", "2.5", "0"), channel("X, Y", "1", "10")]),
+            ),
+            DataElement::new(
+                tags::WAVEFORM_BITS_ALLOCATED,
+                VR::US,
+                PrimitiveValue::from(16_u16),
+            ),
+            DataElement::new(tags::WAVEFORM_SAMPLE_INTERPRETATION, VR::CS, "SS"),
+            // interleaved samples of both channels
+            DataElement::new(
+                tags::WAVEFORM_DATA,
+                VR::OW,
+                PrimitiveValue::U16([4, 1, (-8_i16) as u16, 2, 0, 3].into_iter().collect()),
+            ),
+        ]);
+        InMemDicomObject::from_element_iter([DataElement::new(
+            tags::WAVEFORM_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![item]),
+        )])
+    }
+
+    #[test]
+    fn read_waveform_channels() {
+        let waveforms = read_waveforms(&waveform_object()).unwrap();
+        assert_eq!(waveforms.len(), 1);
+        let waveform = &waveforms[0];
+        assert_eq!(waveform.label.as_deref(), Some("RHYTHM"));
+        assert_eq!(waveform.sampling_frequency, 500.);
+        assert_eq!(waveform.duration(), 0.006);
+        assert_eq!(waveform.channels.len(), 2);
+
+        let lead = waveform.channel("I").unwrap();
+        assert_eq!(lead.samples, vec![10., -20., 0.]);
+        assert_eq!(lead.units_symbol(), "mm");
+        let other = &waveform.channels[1];
+        assert_eq!(other.samples, vec![11., 12., 13.]);
+
+        let mut csv = Vec::new();
+        waveform.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time [s],I [mm],\"X, Y [mm]\"\n0,10,11\n0.002,-20,12\n0.004,0,13\n"
+        );
+
+        let mut npy = Vec::new();
+        waveform.write_npy(&mut npy).unwrap();
+        assert_eq!(npy.len(), 128 + 6 * 8);
+        assert!(npy.starts_with(b"\x93NUMPY"));
+        assert_eq!(npy[127], b'\n');
+        assert_eq!(&npy[128 + 8..128 + 16], &11_f64.to_le_bytes());
+
+        #[cfg(feature = "ndarray")]
+        assert_eq!(
+            waveform.to_ndarray(),
+            ndarray::array![[10., 11.], [-20., 12.], [0., 13.]]
+        );
+
+        #[cfg(feature = "image")]
+        {
+            let plot = waveform.plot(30, 20);
+            assert_eq!(plot.dimensions(), (30, 40));
+            assert!(plot.pixels().any(|p| p.0 == [0; 3]));
+        }
+    }
+
+    #[test]
+    fn unsupported_waveforms() {
+        let mut obj = waveform_object();
+        obj.update_value(tags::WAVEFORM_SEQUENCE, |value| {
+            let item = &mut value.items_mut().unwrap()[0];
+            item.put(DataElement::new(
+                tags::WAVEFORM_SAMPLE_INTERPRETATION,
+                VR::CS,
+                "MB",
+            ));
+        });
+        assert!(read_waveforms(&obj).is_err());
+
+        // no waveforms at all
+        assert!(
+            read_waveforms(&InMemDicomObject::new_empty())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use clap::Parser;
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::{InMemDicomObject, open_file};
+use dicom_pixeldata::{ConvertOptions, PixelDecoder};
+use dicom_ul::{
+    association::client::ClientAssociationOptions,
+    dimse::print::{GrayscaleImage, PrintScu},
+};
+use snafu::{Whatever, prelude::*};
+use std::path::{Path, PathBuf};
+use tracing::{Level, debug, error, info};
+
+/// DICOM Basic Grayscale Print Management SCU
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// socket address to the print SCP,
+    /// optionally with AE title
+    /// (example: "PRINT-SCP@127.0.0.1:104")
+    addr: String,
+    /// the DICOM file containing the image to print
+    file: PathBuf,
+    /// verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// the calling AE title
+    #[arg(long = "calling-ae-title", default_value = "PRINTSCU")]
+    calling_ae_title: String,
+    /// the called Application Entity title,
+    /// overrides AE title in address if present [default: ANY-SCP]
+    #[arg(long = "called-ae-title")]
+    called_ae_title: Option<String>,
+    /// the frame of the image to print
+    #[arg(long = "frame", default_value = "0")]
+    frame: u32,
+    /// the number of copies to print
+    #[arg(long = "copies", default_value = "1")]
+    copies: u32,
+    /// the medium type (e.g. PAPER, BLUE FILM, CLEAR FILM)
+    #[arg(long = "medium-type")]
+    medium_type: Option<String>,
+    /// the film size ID (e.g. 8INX10IN, 14INX17IN, A4)
+    #[arg(long = "film-size")]
+    film_size: Option<String>,
+    /// the film orientation (PORTRAIT or LANDSCAPE)
+    #[arg(long = "film-orientation")]
+    film_orientation: Option<String>,
+}
+
+fn main() {
+    run().unwrap_or_else(|e| {
+        error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    })
+}
+
+fn run() -> Result<(), Whatever> {
+    let App {
+        addr,
+        file,
+        verbose,
+        calling_ae_title,
+        called_ae_title,
+        frame,
+        copies,
+        medium_type,
+        film_size,
+        film_orientation,
+    } = App::parse();
+
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(if verbose { Level::DEBUG } else { Level::INFO })
+            .finish(),
+    )
+    .whatever_context("Could not set up global logging subscriber")
+    .unwrap_or_else(|e: Whatever| {
+        eprintln!("[ERROR] {}", snafu::Report::from_error(e));
+    });
+
+    let image = load_image(&file, frame)?;
+    debug!(
+        "Loaded {}x{} image from {}",
+        image.columns(),
+        image.rows(),
+        file.display()
+    );
+
+    let mut film_session = InMemDicomObject::from_element_iter([DataElement::new(
+        tags::NUMBER_OF_COPIES,
+        VR::IS,
+        PrimitiveValue::from(copies.to_string()),
+    )]);
+    if let Some(medium_type) = medium_type {
+        film_session.put(DataElement::new(
+            tags::MEDIUM_TYPE,
+            VR::CS,
+            PrimitiveValue::from(medium_type),
+        ));
+    }
+
+    let mut film_box = InMemDicomObject::new_empty();
+    if let Some(film_size) = film_size {
+        film_box.put(DataElement::new(
+            tags::FILM_SIZE_ID,
+            VR::CS,
+            PrimitiveValue::from(film_size),
+        ));
+    }
+    if let Some(film_orientation) = film_orientation {
+        film_box.put(DataElement::new(
+            tags::FILM_ORIENTATION,
+            VR::CS,
+            PrimitiveValue::from(film_orientation),
+        ));
+    }
+
+    let mut association_opt = ClientAssociationOptions::new()
+        .with_abstract_syntax(uids::BASIC_GRAYSCALE_PRINT_MANAGEMENT_META)
+        .calling_ae_title(calling_ae_title);
+    if let Some(called_ae_title) = called_ae_title {
+        association_opt = association_opt.called_ae_title(called_ae_title);
+    }
+    let mut association = association_opt
+        .establish_with(&addr)
+        .whatever_context("Could not establish association with SCP")?;
+    debug!("Association with {} successful", addr);
+
+    let mut scu =
+        PrintScu::new(&mut association).whatever_context("Print management not accepted by SCP")?;
+    let film_box = scu
+        .print_images(film_session, film_box, &[image])
+        .whatever_context("Failed to print image")?;
+
+    info!("✓ Printed film box {}", film_box.sop_instance_uid);
+
+    // release association
+    let _ = association.release();
+
+    Ok(())
+}
+
+/// Read a frame from a DICOM file as an 8-bit grayscale image,
+/// applying the VOI LUT transformation described in the file.
+fn load_image(file: &Path, frame: u32) -> Result<GrayscaleImage, Whatever> {
+    let obj = open_file(file)
+        .with_whatever_context(|_| format!("Could not open DICOM file {}", file.display()))?;
+    let pixel_data = obj
+        .decode_pixel_data_frame(frame)
+        .whatever_context("Could not decode pixel data")?;
+    ensure_whatever!(
+        pixel_data.samples_per_pixel() == 1,
+        "Only grayscale images can be printed"
+    );
+    let image = pixel_data
+        .to_dynamic_image_with_options(0, &ConvertOptions::new().force_8bit())
+        .whatever_context("Could not convert pixel data to an image")?
+        .into_luma8();
+
+    let columns = u16::try_from(image.width()).whatever_context("Image is too wide")?;
+    let rows = u16::try_from(image.height()).whatever_context("Image is too tall")?;
+    Ok(GrayscaleImage::new_8bit(rows, columns, image.into_raw()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
@@ -0,0 +1,539 @@
+//! Matching of query/retrieve identifiers
+//! against the persistent index of the archive.
+//!
+//! The index is the SQLite database written by `dicom-index` and `dicom-storescp`.
+//! It is read again for each request,
+//! so instances recorded by other processes are found as soon as they are committed.
+//! UID keys are looked up in the database,
+//! and the other keys are matched against the indexed attributes.
+//! The full files are read again from disk on retrieval.
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use dicom_app_common::index::{self, IndexError, IndexedInstance};
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_ul::dimse::query::{self, QueryLevel};
+
+/// Patient level attributes of summaries
+const PATIENT_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::PATIENT_BIRTH_DATE,
+    tags::PATIENT_SEX,
+];
+
+/// Study level attributes of summaries
+const STUDY_TAGS: &[Tag] = &[
+    tags::STUDY_DATE,
+    tags::STUDY_TIME,
+    tags::ACCESSION_NUMBER,
+    tags::REFERRING_PHYSICIAN_NAME,
+    tags::STUDY_INSTANCE_UID,
+    tags::STUDY_ID,
+    tags::STUDY_DESCRIPTION,
+];
+
+/// Series level attributes of summaries
+const SERIES_TAGS: &[Tag] = &[
+    tags::MODALITY,
+    tags::SERIES_DESCRIPTION,
+    tags::SERIES_INSTANCE_UID,
+    tags::SERIES_NUMBER,
+    tags::BODY_PART_EXAMINED,
+];
+
+/// Image level attributes of summaries
+const IMAGE_TAGS: &[Tag] = &[
+    tags::SOP_CLASS_UID,
+    tags::SOP_INSTANCE_UID,
+    tags::INSTANCE_NUMBER,
+    tags::ROWS,
+    tags::COLUMNS,
+    tags::NUMBER_OF_FRAMES,
+];
+
+pub type Result<T, E = IndexError> = std::result::Result<T, E>;
+
+/// The attributes of the given level,
+/// including those of the levels above it
+fn level_tags(level: QueryLevel) -> impl Iterator<Item = Tag> {
+    let levels: &[&[Tag]] = match level {
+        QueryLevel::Patient => &[PATIENT_TAGS],
+        QueryLevel::Study => &[PATIENT_TAGS, STUDY_TAGS],
+        QueryLevel::Series => &[PATIENT_TAGS, STUDY_TAGS, SERIES_TAGS],
+        QueryLevel::Image => &[PATIENT_TAGS, STUDY_TAGS, SERIES_TAGS, IMAGE_TAGS],
+    };
+    levels.iter().flat_map(|tags| tags.iter().copied())
+}
+
+/// The UID keys of the identifier at the given level and above,
+/// with the UIDs to look up in the database.
+///
+/// Universal matching keys are left out.
+/// Since keys are only taken up to the query level,
+/// the instances found cover whole entities of that level.
+fn uid_keys(identifier: &InMemDicomObject, level: QueryLevel) -> Vec<(Tag, Vec<String>)> {
+    level_tags(level)
+        .filter_map(|tag| {
+            let key = identifier.get(tag).filter(|e| e.vr() == VR::UI)?;
+            let uids: Vec<_> = key
+                .to_str()
+                .ok()?
+                .split('\\')
+                .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string())
+                .filter(|uid| !uid.is_empty())
+                .collect();
+            (!uids.is_empty()).then_some((tag, uids))
+        })
+        .collect()
+}
+
+/// An indexed DICOM instance
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// the path to the DICOM file
+    pub path: PathBuf,
+    /// the transfer syntax of the file
+    pub transfer_syntax: String,
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+    pub patient_id: String,
+    pub study_instance_uid: String,
+    pub series_instance_uid: String,
+    /// the indexed attributes of all levels
+    pub attributes: InMemDicomObject,
+}
+
+impl From<IndexedInstance> for Instance {
+    fn from(instance: IndexedInstance) -> Self {
+        Instance {
+            sop_class_uid: instance.value(tags::SOP_CLASS_UID),
+            sop_instance_uid: instance.value(tags::SOP_INSTANCE_UID),
+            patient_id: instance.value(tags::PATIENT_ID),
+            study_instance_uid: instance.value(tags::STUDY_INSTANCE_UID),
+            series_instance_uid: instance.value(tags::SERIES_INSTANCE_UID),
+            path: instance.path,
+            transfer_syntax: instance.transfer_syntax,
+            attributes: instance.attributes,
+        }
+    }
+}
+
+impl Instance {
+    /// The unique key of the entity at the given level
+    /// to which this instance belongs
+    fn key(&self, level: QueryLevel) -> &str {
+        match level {
+            QueryLevel::Patient => &self.patient_id,
+            QueryLevel::Study => &self.study_instance_uid,
+            QueryLevel::Series => &self.series_instance_uid,
+            QueryLevel::Image => &self.sop_instance_uid,
+        }
+    }
+}
+
+/// The index of the DICOM instances in the archive
+#[derive(Debug)]
+pub struct Index {
+    index: index::Index,
+}
+
+impl Index {
+    /// Open the index in the given database file,
+    /// creating an empty one if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Index {
+            index: index::Index::open(path)?,
+        })
+    }
+
+    /// The number of indexed instances.
+    pub fn len(&self) -> Result<u64> {
+        self.index.len()
+    }
+
+    /// The indexed instances with the UID keys of the identifier
+    /// at the given level and above,
+    /// in the order in which they were indexed
+    fn candidates(
+        &self,
+        identifier: &InMemDicomObject,
+        level: QueryLevel,
+    ) -> Result<Vec<Instance>> {
+        Ok(self
+            .index
+            .instances(&uid_keys(identifier, level))?
+            .into_iter()
+            .map(Instance::from)
+            .collect())
+    }
+
+    /// The instances matching the given identifier,
+    /// as in the identifier of a C-GET or C-MOVE request at the given level.
+    pub fn instances_matching(
+        &self,
+        identifier: &InMemDicomObject,
+        level: QueryLevel,
+    ) -> Result<Vec<Instance>> {
+        Ok(self
+            .candidates(identifier, level)?
+            .into_iter()
+            .filter(|instance| query::matches(identifier, &instance.attributes))
+            .collect())
+    }
+
+    /// Build one summary per entity at the given level
+    /// which may match the given identifier,
+    /// in the order in which they were first indexed.
+    ///
+    /// Each summary contains the indexed attributes
+    /// of the level and the levels above
+    /// from the entity's first instance,
+    /// as well as the attributes computed from the entity's instances
+    /// (such as the number of related instances).
+    /// The summaries still need to be matched against the identifier.
+    pub fn summaries(
+        &self,
+        identifier: &InMemDicomObject,
+        level: QueryLevel,
+    ) -> Result<Vec<InMemDicomObject>> {
+        let instances = self.candidates(identifier, level)?;
+        let mut groups: Vec<Vec<&Instance>> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for instance in &instances {
+            let key = instance.key(level);
+            match positions.get(key) {
+                Some(&i) => groups[i].push(instance),
+                None => {
+                    positions.insert(key, groups.len());
+                    groups.push(vec![instance]);
+                }
+            }
+        }
+
+        Ok(groups
+            .into_iter()
+            .map(|instances| {
+                let first = instances[0];
+                let mut summary = InMemDicomObject::from_element_iter(
+                    level_tags(level).filter_map(|tag| first.attributes.get(tag).cloned()),
+                );
+                let count = |level| {
+                    instances
+                        .iter()
+                        .map(|instance| instance.key(level))
+                        .collect::<HashSet<_>>()
+                        .len()
+                };
+                let number = |tag, n: usize| DataElement::new(tag, VR::IS, n.to_string());
+                match level {
+                    QueryLevel::Patient => {
+                        summary.put(number(
+                            tags::NUMBER_OF_PATIENT_RELATED_STUDIES,
+                            count(QueryLevel::Study),
+                        ));
+                        summary.put(number(
+                            tags::NUMBER_OF_PATIENT_RELATED_SERIES,
+                            count(QueryLevel::Series),
+                        ));
+                        summary.put(number(
+                            tags::NUMBER_OF_PATIENT_RELATED_INSTANCES,
+                            instances.len(),
+                        ));
+                    }
+                    QueryLevel::Study => {
+                        summary.put(DataElement::new(
+                            tags::MODALITIES_IN_STUDY,
+                            VR::CS,
+                            distinct_values(&instances, tags::MODALITY),
+                        ));
+                        summary.put(DataElement::new(
+                            tags::SOP_CLASSES_IN_STUDY,
+                            VR::UI,
+                            distinct_values(&instances, tags::SOP_CLASS_UID),
+                        ));
+                        summary.put(number(
+                            tags::NUMBER_OF_STUDY_RELATED_SERIES,
+                            count(QueryLevel::Series),
+                        ));
+                        summary.put(number(
+                            tags::NUMBER_OF_STUDY_RELATED_INSTANCES,
+                            instances.len(),
+                        ));
+                    }
+                    QueryLevel::Series => {
+                        summary.put(number(
+                            tags::NUMBER_OF_SERIES_RELATED_INSTANCES,
+                            instances.len(),
+                        ));
+                    }
+                    QueryLevel::Image => {}
+                }
+                summary
+            })
+            .collect())
+    }
+}
+
+/// The distinct values of an attribute among the given instances,
+/// in order of appearance
+fn distinct_values(instances: &[&Instance], tag: Tag) -> PrimitiveValue {
+    let mut values: Vec<String> = Vec::new();
+    for instance in instances {
+        if let Some(value) = instance.attributes.get(tag).and_then(|e| e.to_str().ok()) {
+            let value = value.trim_end_matches(['\0', ' ']);
+            if !value.is_empty() && !values.iter().any(|v| v == value) {
+                values.push(value.to_string());
+            }
+        }
+    }
+    PrimitiveValue::Strs(values.into())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use dicom_dictionary_std::uids;
+    use dicom_object::FileMetaTableBuilder;
+
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dicom-qrscp-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a small 2x2 secondary capture image
+    /// with 8-bit monochrome pixel data
+    pub(crate) fn write_instance(
+        path: &Path,
+        patient_id: &str,
+        study_instance_uid: &str,
+        series_instance_uid: &str,
+        sop_instance_uid: &str,
+        modality: &str,
+    ) {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from(uids::SECONDARY_CAPTURE_IMAGE_STORAGE),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(sop_instance_uid),
+            ),
+            DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::from("20240315")),
+            DataElement::new(tags::MODALITY, VR::CS, PrimitiveValue::from(modality)),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, PrimitiveValue::from("Doe^John")),
+            DataElement::new(tags::PATIENT_ID, VR::LO, PrimitiveValue::from(patient_id)),
+            DataElement::new(
+                tags::STUDY_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(study_instance_uid),
+            ),
+            DataElement::new(
+                tags::SERIES_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from(series_instance_uid),
+            ),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, PrimitiveValue::from(1_u16)),
+            DataElement::new(
+                tags::PHOTOMETRIC_INTERPRETATION,
+                VR::CS,
+                PrimitiveValue::from("MONOCHROME2"),
+            ),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::COLUMNS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::BITS_STORED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::HIGH_BIT, VR::US, PrimitiveValue::from(7_u16)),
+            DataElement::new(
+                tags::PIXEL_REPRESENTATION,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0_u8, 64, 128, 255]),
+            ),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::SECONDARY_CAPTURE_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid),
+        )
+        .unwrap();
+        obj.write_to_file(path).unwrap();
+    }
+
+    /// Write an instance as in [`write_instance`]
+    /// and record it in the index, as `storescp` does
+    pub(crate) fn store_instance(
+        index: &index::Index,
+        path: &Path,
+        patient_id: &str,
+        study_instance_uid: &str,
+        series_instance_uid: &str,
+        sop_instance_uid: &str,
+        modality: &str,
+    ) {
+        write_instance(
+            path,
+            patient_id,
+            study_instance_uid,
+            series_instance_uid,
+            sop_instance_uid,
+            modality,
+        );
+        let obj = dicom_object::open_file(path).unwrap();
+        let instance = IndexedInstance::new(
+            path,
+            std::fs::metadata(path).unwrap().len(),
+            obj.meta().transfer_syntax(),
+            &obj,
+        )
+        .unwrap();
+        index.insert([&instance]).unwrap();
+    }
+
+    fn str_of(obj: &InMemDicomObject, tag: Tag) -> String {
+        obj.get(tag).unwrap().to_str().unwrap().trim().to_string()
+    }
+
+    fn query(level: &str, keys: &[(Tag, VR, &str)]) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter(
+            std::iter::once(DataElement::new(tags::QUERY_RETRIEVE_LEVEL, VR::CS, level)).chain(
+                keys.iter()
+                    .map(|(tag, vr, value)| DataElement::new(*tag, *vr, *value)),
+            ),
+        )
+    }
+
+    #[test]
+    fn match_and_summarize_indexed_instances() {
+        let dir = temp_dir("index");
+        let db = dir.join("index.db");
+        // the index is written and read through separate connections,
+        // as by storescp and qrscp
+        let writer = index::Index::open(&db).unwrap();
+        store_instance(
+            &writer,
+            &dir.join("1.dcm"),
+            "P1",
+            "1.1",
+            "1.1.1",
+            "1.1.1.1",
+            "CT",
+        );
+        store_instance(
+            &writer,
+            &dir.join("2.dcm"),
+            "P1",
+            "1.1",
+            "1.1.1",
+            "1.1.1.2",
+            "CT",
+        );
+
+        let index = Index::open(&db).unwrap();
+        assert_eq!(index.len().unwrap(), 2);
+
+        // instances recorded later are found right away
+        store_instance(
+            &writer,
+            &dir.join("3.dcm"),
+            "P1",
+            "1.1",
+            "1.1.2",
+            "1.1.2.1",
+            "SR",
+        );
+        store_instance(
+            &writer,
+            &dir.join("4.dcm"),
+            "P2",
+            "1.2",
+            "1.2.1",
+            "1.2.1.1",
+            "MR",
+        );
+        assert_eq!(index.len().unwrap(), 4);
+
+        let all = InMemDicomObject::new_empty();
+        let patients = index.summaries(&all, QueryLevel::Patient).unwrap();
+        assert_eq!(patients.len(), 2);
+        assert_eq!(str_of(&patients[0], tags::PATIENT_ID), "P1");
+        assert_eq!(
+            str_of(&patients[0], tags::NUMBER_OF_PATIENT_RELATED_SERIES),
+            "2"
+        );
+        // study attributes are not part of patient summaries
+        assert!(patients[0].get(tags::STUDY_INSTANCE_UID).is_none());
+
+        let studies = index.summaries(&all, QueryLevel::Study).unwrap();
+        assert_eq!(studies.len(), 2);
+        assert_eq!(str_of(&studies[0], tags::MODALITIES_IN_STUDY), "CT\\SR");
+        assert_eq!(
+            str_of(&studies[0], tags::NUMBER_OF_STUDY_RELATED_INSTANCES),
+            "3"
+        );
+        assert!(studies[0].get(tags::MODALITY).is_none());
+
+        // summaries of the studies looked up by UID cover all of their instances
+        let identifier = query("STUDY", &[(tags::STUDY_INSTANCE_UID, VR::UI, "1.1")]);
+        let studies = index.summaries(&identifier, QueryLevel::Study).unwrap();
+        assert_eq!(studies.len(), 1);
+        assert_eq!(
+            str_of(&studies[0], tags::NUMBER_OF_STUDY_RELATED_SERIES),
+            "2"
+        );
+
+        let series = index.summaries(&all, QueryLevel::Series).unwrap();
+        assert_eq!(series.len(), 3);
+        assert_eq!(
+            str_of(&series[0], tags::NUMBER_OF_SERIES_RELATED_INSTANCES),
+            "2"
+        );
+
+        let identifier = query(
+            "SERIES",
+            &[(tags::SERIES_INSTANCE_UID, VR::UI, "1.2.1\\1.1.2")],
+        );
+        let instances = index
+            .instances_matching(&identifier, QueryLevel::Series)
+            .unwrap();
+        assert_eq!(instances.len(), 2);
+        // in the order in which they were indexed
+        assert_eq!(instances[0].sop_instance_uid, "1.1.2.1");
+        assert_eq!(
+            instances[0].sop_class_uid,
+            uids::SECONDARY_CAPTURE_IMAGE_STORAGE
+        );
+        assert_eq!(instances[0].path, dir.join("3.dcm"));
+        assert_eq!(instances[1].patient_id, "P2");
+
+        // other keys are matched against the indexed attributes
+        let identifier = query(
+            "IMAGE",
+            &[
+                (tags::STUDY_INSTANCE_UID, VR::UI, "1.1"),
+                (tags::MODALITY, VR::CS, "CT"),
+            ],
+        );
+        let instances = index
+            .instances_matching(&identifier, QueryLevel::Image)
+            .unwrap();
+        assert_eq!(instances.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
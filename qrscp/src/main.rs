@@ -0,0 +1,197 @@
+//! A query/retrieve SCP serving the DICOM files in a persistent index.
+//!
+//! The index is the SQLite database
+//! written by `dicom-index` from directory trees
+//! and by `dicom-storescp` as it receives files,
+//! so that they can be found and retrieved as soon as they are indexed.
+//! The patient root and study root information models are supported
+//! for C-FIND, C-GET and C-MOVE.
+//! Move destinations are looked up in the AE configuration file.
+
+use std::{
+    net::{Ipv4Addr, SocketAddrV4, TcpStream},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use clap::Parser;
+use dicom_app_common::aeconfig::AeRegistry;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::association::{Association, ServerAssociationOptions, server::AcceptAny};
+use snafu::{Report, ResultExt, Whatever};
+use tracing::{Level, debug, error, info};
+use tracing_subscriber::EnvFilter;
+
+mod index;
+mod retrieve;
+mod scp;
+
+use index::Index;
+use scp::{ABSTRACT_SYNTAXES, AcceptRoles, Archive};
+
+/// DICOM query/retrieve SCP over an index of DICOM files
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The SQLite index of the DICOM files to serve,
+    /// as written by dicom-index or dicom-storescp
+    /// (created empty if it does not exist)
+    index: PathBuf,
+    /// Verbose mode
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+    /// The Application Entity title of this node
+    #[arg(long = "ae-title", default_value = "QR-SCP")]
+    ae_title: String,
+    /// Enforce max pdu length
+    #[arg(short = 's', long = "strict")]
+    strict: bool,
+    /// Maximum PDU length
+    #[arg(
+        short = 'm',
+        long = "max-pdu-length",
+        default_value = "16378",
+        value_parser(clap::value_parser!(u32).range(1018..))
+    )]
+    max_pdu_length: u32,
+    /// Which port to listen on
+    #[arg(short, default_value = "11112")]
+    port: u16,
+    /// Path to the configuration file with the move destinations
+    /// [default: $DICOM_RS_AE_CONFIG or ~/.config/dicom-rs/config.toml]
+    #[arg(long = "config", visible_alias = "ae-config", value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+fn main() {
+    let app = App::parse();
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::FmtSubscriber::builder()
+            .with_max_level(Level::INFO)
+            .with_env_filter(
+                EnvFilter::from_default_env().add_directive(
+                    if app.verbose {
+                        "dicom_qrscp=debug"
+                    } else {
+                        "dicom_qrscp=info"
+                    }
+                    .parse()
+                    .unwrap(),
+                ),
+            )
+            .finish(),
+    )
+    .whatever_context("Could not set up global logging subscriber")
+    .unwrap_or_else(|e: Whatever| {
+        eprintln!("[ERROR] {}", Report::from_error(e));
+    });
+
+    run(app).unwrap_or_else(|e| {
+        error!("{}", Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        index: index_path,
+        verbose: _,
+        ae_title,
+        strict,
+        max_pdu_length,
+        port,
+        config,
+    } = app;
+
+    let destinations = match &config {
+        Some(path) => AeRegistry::open(path),
+        None => AeRegistry::open_default(),
+    }
+    .whatever_context("Could not read the AE configuration file")?;
+
+    let index = Index::open(&index_path)
+        .with_whatever_context(|_| format!("Could not open {}", index_path.display()))?;
+    info!(
+        "Serving {} indexed instances from {}",
+        index.len().whatever_context("Could not read the index")?,
+        index_path.display()
+    );
+
+    let archive = Arc::new(Archive {
+        index,
+        ae_title,
+        max_pdu_length,
+        destinations,
+    });
+
+    let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), port);
+    let listener = std::net::TcpListener::bind(listen_addr)
+        .with_whatever_context(|_| format!("Could not listen on {listen_addr}"))?;
+    info!("{} listening on: tcp://{}", archive.ae_title, listen_addr);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let archive = Arc::clone(&archive);
+                std::thread::spawn(move || {
+                    if let Err(e) = run_association(stream, &archive, strict) {
+                        error!("{}", Report::from_error(e));
+                    }
+                });
+            }
+            Err(e) => error!("{}", Report::from_error(e)),
+        }
+    }
+    Ok(())
+}
+
+/// The options of associations requested to this node
+fn server_options(
+    archive: &Archive,
+    strict: bool,
+) -> ServerAssociationOptions<'_, AcceptAny, AcceptRoles> {
+    let mut options = ServerAssociationOptions::new()
+        .accept_any()
+        .ae_title(&archive.ae_title)
+        .strict(strict)
+        .max_pdu_length(archive.max_pdu_length)
+        .auto_echo(true)
+        // storage SOP classes are proposed by C-GET SCUs
+        .promiscuous(true)
+        .with_negotiation(AcceptRoles);
+    for ts in TransferSyntaxRegistry.iter() {
+        if !ts.is_unsupported() {
+            options = options.with_transfer_syntax(ts.uid());
+        }
+    }
+    for uid in ABSTRACT_SYNTAXES {
+        options = options.with_abstract_syntax(*uid);
+    }
+    options
+}
+
+fn run_association(stream: TcpStream, archive: &Archive, strict: bool) -> Result<(), Whatever> {
+    let mut association = server_options(archive, strict)
+        .establish(stream)
+        .whatever_context("could not establish association")?;
+    info!("New association from {}", association.peer_ae_title());
+    debug!(
+        "> Presentation contexts: {:?}",
+        association.presentation_contexts()
+    );
+
+    scp::serve(&mut association, archive).whatever_context("association failed")?;
+    info!("Association with {} finished", association.peer_ae_title());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
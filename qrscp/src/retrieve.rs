@@ -0,0 +1,760 @@
+//! Retrieval of instances with C-GET and C-MOVE,
+//! through C-STORE sub-operations.
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_encoding::TransferSyntaxIndex;
+use dicom_object::{InMemDicomObject, OpenFileOptions};
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    ClientAssociationOptions,
+    association::{Association, CloseSocket, SyncAssociation},
+    dimse::{
+        self, CGetRq, CGetRsp, CMoveRq, CMoveRsp, CStoreRq, CStoreRsp, CommandField, Message,
+        StatusType, SubOperations, query::QueryLevel, receive_message, send_message, status,
+    },
+    pdu::PresentationContextResultReason,
+};
+use tracing::{info, warn};
+
+use crate::index::Instance;
+use crate::scp::{Archive, InformationModel};
+
+/// A request refused before any sub-operation
+struct Refusal {
+    status: u16,
+    error_comment: String,
+}
+
+impl Refusal {
+    fn new(status: u16, error_comment: impl Into<String>) -> Self {
+        Refusal {
+            status,
+            error_comment: error_comment.into(),
+        }
+    }
+}
+
+/// The tally of the C-STORE sub-operations of a retrieval
+#[derive(Debug, Default)]
+struct Progress {
+    total: usize,
+    completed: u16,
+    failed: u16,
+    warning: u16,
+    /// the SOP instance UIDs of the failed sub-operations
+    failed_uids: Vec<String>,
+}
+
+impl Progress {
+    fn new(total: usize) -> Self {
+        Progress {
+            total,
+            ..Default::default()
+        }
+    }
+
+    /// Record the outcome of a sub-operation,
+    /// either the status of the C-STORE response
+    /// or `None` if the instance could not be sent.
+    fn record(&mut self, instance: &Instance, status: Option<u16>) {
+        match status.map(StatusType::from_code) {
+            Some(StatusType::Success) => self.completed += 1,
+            Some(StatusType::Warning) => self.warning += 1,
+            _ => {
+                self.failed += 1;
+                self.failed_uids.push(instance.sop_instance_uid.clone());
+            }
+        }
+    }
+
+    fn done(&self) -> usize {
+        usize::from(self.completed) + usize::from(self.failed) + usize::from(self.warning)
+    }
+
+    fn pending(&self) -> SubOperations {
+        SubOperations {
+            remaining: Some((self.total - self.done()) as u16),
+            completed: self.completed,
+            failed: self.failed,
+            warning: self.warning,
+        }
+    }
+
+    /// The status and counts of the final response,
+    /// and the identifier listing the failed instances, if any
+    fn finish(&self, cancelled: bool) -> (u16, SubOperations, Option<InMemDicomObject>) {
+        let status = if cancelled {
+            status::CANCEL
+        } else if self.failed == 0 && self.warning == 0 {
+            status::SUCCESS
+        } else if self.completed == 0 && self.warning == 0 {
+            status::OUT_OF_RESOURCES_SUB_OPERATIONS
+        } else {
+            status::SUB_OPERATIONS_COMPLETE_WITH_FAILURES
+        };
+        let sub_operations = SubOperations {
+            remaining: cancelled.then(|| (self.total - self.done()) as u16),
+            completed: self.completed,
+            failed: self.failed,
+            warning: self.warning,
+        };
+        let identifier = (!self.failed_uids.is_empty()).then(|| {
+            InMemDicomObject::from_element_iter([DataElement::new(
+                tags::FAILED_SOP_INSTANCE_UID_LIST,
+                VR::UI,
+                PrimitiveValue::Strs(self.failed_uids.iter().cloned().collect()),
+            )])
+        });
+        (status, sub_operations, identifier)
+    }
+}
+
+/// Look up the instances to retrieve for a C-GET or C-MOVE request.
+///
+/// The identifier must have the unique key of its level,
+/// so that requests cannot retrieve the whole archive by accident.
+fn instances_to_retrieve<A>(
+    association: &A,
+    archive: &Archive,
+    sop_class_uid: &str,
+    msg: &Message,
+) -> dimse::Result<Result<Vec<Instance>, Refusal>>
+where
+    A: Association,
+{
+    let Some(model) = InformationModel::from_sop_class(sop_class_uid) else {
+        return Ok(Err(Refusal::new(
+            status::NO_SUCH_SOP_CLASS,
+            "unsupported information model",
+        )));
+    };
+    let identifier = msg
+        .read_data_set(association)?
+        .unwrap_or_else(InMemDicomObject::new_empty);
+    let Some(level) = model.level(&identifier) else {
+        return Ok(Err(Refusal::new(
+            status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS,
+            "invalid Query/Retrieve Level",
+        )));
+    };
+    let unique_key = match level {
+        QueryLevel::Patient => tags::PATIENT_ID,
+        QueryLevel::Study => tags::STUDY_INSTANCE_UID,
+        QueryLevel::Series => tags::SERIES_INSTANCE_UID,
+        QueryLevel::Image => tags::SOP_INSTANCE_UID,
+    };
+    let has_unique_key = identifier
+        .get(unique_key)
+        .and_then(|e| e.to_str().ok())
+        .is_some_and(|v| !v.trim_end_matches(['\0', ' ']).is_empty());
+    if !has_unique_key {
+        return Ok(Err(Refusal::new(
+            status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS,
+            format!("missing unique key {unique_key} of the level"),
+        )));
+    }
+    match archive.index.instances_matching(&identifier, level) {
+        Ok(instances) => Ok(Ok(instances)),
+        Err(e) => {
+            warn!("Could not read the index: {}", snafu::Report::from_error(e));
+            Ok(Err(Refusal::new(
+                status::UNABLE_TO_PROCESS,
+                "could not read the index",
+            )))
+        }
+    }
+}
+
+/// Read an instance from disk and encode it
+/// for an accepted presentation context of its SOP class.
+///
+/// The file is sent in its own transfer syntax if possible,
+/// otherwise it is only converted between transfer syntaxes
+/// with native pixel data.
+/// Returns the presentation context ID and the encoded data set,
+/// or a description of why the instance cannot be sent.
+fn encode_instance<A>(association: &A, instance: &Instance) -> Result<(u8, Vec<u8>), String>
+where
+    A: Association + ?Sized,
+{
+    let candidates: Vec<_> = association
+        .presentation_contexts()
+        .iter()
+        .filter(|pc| {
+            pc.reason == PresentationContextResultReason::Acceptance
+                && pc.abstract_syntax.trim_end_matches('\0') == instance.sop_class_uid
+        })
+        .collect();
+    if candidates.is_empty() {
+        return Err(format!(
+            "no presentation context for SOP class {}",
+            instance.sop_class_uid
+        ));
+    }
+    let native = |uid: &str| {
+        TransferSyntaxRegistry
+            .get(uid)
+            .is_some_and(|ts| ts.is_codec_free() && !ts.is_encapsulated_pixel_data())
+    };
+    let file_ts = instance.transfer_syntax.trim_end_matches('\0');
+    let pc = candidates
+        .iter()
+        .find(|pc| pc.transfer_syntax.trim_end_matches('\0') == file_ts)
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|pc| native(file_ts) && native(&pc.transfer_syntax))
+        })
+        .ok_or_else(|| {
+            format!("cannot convert from transfer syntax {file_ts} to any of those accepted")
+        })?;
+    let ts = TransferSyntaxRegistry
+        .get(&pc.transfer_syntax)
+        .ok_or_else(|| format!("unsupported transfer syntax {}", pc.transfer_syntax))?;
+
+    let obj = OpenFileOptions::new()
+        .open_file(&instance.path)
+        .map_err(|e| format!("could not read {}: {}", instance.path.display(), e))?;
+    let mut data = Vec::new();
+    obj.write_dataset_with_ts(&mut data, ts)
+        .map_err(|e| format!("could not encode {}: {}", instance.path.display(), e))?;
+    Ok((pc.id, data))
+}
+
+/// Perform a C-STORE sub-operation,
+/// returning the status of the C-STORE response.
+///
+/// A C-CANCEL request of the retrieval
+/// received while waiting for the response sets `cancelled`.
+fn store<A, S>(
+    association: &mut A,
+    rq: &CStoreRq,
+    pc_id: u8,
+    data: &[u8],
+    cancelled: &mut bool,
+) -> dimse::Result<u16>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    send_message(association, pc_id, &rq.command(), Some(data))?;
+    loop {
+        let msg = receive_message(association)?;
+        if msg.command_field()? == CommandField::CCancelRq {
+            *cancelled = true;
+            continue;
+        }
+        // fails on any other command
+        let rsp = CStoreRsp::from_command(&msg.command)?;
+        if rsp.message_id_being_responded_to == rq.message_id {
+            return Ok(rsp.status);
+        }
+        warn!(
+            "Ignoring C-STORE response to message {}",
+            rsp.message_id_being_responded_to
+        );
+    }
+}
+
+/// Handle a C-GET request,
+/// sending the instances through C-STORE sub-operations
+/// on the same association.
+pub fn handle_get<A, S>(association: &mut A, archive: &Archive, msg: Message) -> dimse::Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let pc_id = msg.presentation_context_id;
+    let rq = CGetRq::from_command(&msg.command)?;
+    let respond = |association: &mut A,
+                   status: u16,
+                   error_comment: Option<String>,
+                   sub_operations: SubOperations,
+                   identifier: Option<InMemDicomObject>| {
+        let rsp = CGetRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            error_comment,
+            sub_operations,
+        };
+        let data = identifier
+            .map(|obj| dimse::write_data_set(&*association, pc_id, &obj))
+            .transpose()?;
+        send_message(
+            association,
+            pc_id,
+            &rsp.command(data.is_some()),
+            data.as_deref(),
+        )
+    };
+
+    let instances =
+        match instances_to_retrieve(&*association, archive, &rq.affected_sop_class_uid, &msg)? {
+            Ok(instances) => instances,
+            Err(refusal) => {
+                warn!("C-GET refused: {}", refusal.error_comment);
+                return respond(
+                    association,
+                    refusal.status,
+                    Some(refusal.error_comment),
+                    SubOperations::default(),
+                    None,
+                );
+            }
+        };
+    info!("C-GET: retrieving {} instances", instances.len());
+
+    let mut progress = Progress::new(instances.len());
+    let mut cancelled = false;
+    for (i, instance) in instances.iter().enumerate() {
+        let status = match encode_instance(&*association, instance) {
+            Ok((store_pc_id, data)) => {
+                let store_rq = CStoreRq {
+                    message_id: (i + 1) as u16,
+                    affected_sop_class_uid: instance.sop_class_uid.clone(),
+                    affected_sop_instance_uid: instance.sop_instance_uid.clone(),
+                    priority: rq.priority,
+                    move_originator_ae_title: None,
+                    move_originator_message_id: None,
+                };
+                Some(store(
+                    association,
+                    &store_rq,
+                    store_pc_id,
+                    &data,
+                    &mut cancelled,
+                )?)
+            }
+            Err(reason) => {
+                warn!("Could not send {}: {}", instance.sop_instance_uid, reason);
+                None
+            }
+        };
+        progress.record(instance, status);
+        if cancelled {
+            break;
+        }
+        if progress.done() < progress.total {
+            respond(association, status::PENDING, None, progress.pending(), None)?;
+        }
+    }
+
+    let (status, sub_operations, identifier) = progress.finish(cancelled);
+    respond(association, status, None, sub_operations, identifier)
+}
+
+/// Handle a C-MOVE request,
+/// sending the instances through C-STORE sub-operations
+/// on a new association with the move destination.
+pub fn handle_move<A, S>(association: &mut A, archive: &Archive, msg: Message) -> dimse::Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let pc_id = msg.presentation_context_id;
+    let rq = CMoveRq::from_command(&msg.command)?;
+    let respond = |association: &mut A,
+                   status: u16,
+                   error_comment: Option<String>,
+                   sub_operations: SubOperations,
+                   identifier: Option<InMemDicomObject>| {
+        let rsp = CMoveRsp {
+            message_id_being_responded_to: rq.message_id,
+            affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+            status,
+            error_comment,
+            sub_operations,
+        };
+        let data = identifier
+            .map(|obj| dimse::write_data_set(&*association, pc_id, &obj))
+            .transpose()?;
+        send_message(
+            association,
+            pc_id,
+            &rsp.command(data.is_some()),
+            data.as_deref(),
+        )
+    };
+
+    let instances =
+        match instances_to_retrieve(&*association, archive, &rq.affected_sop_class_uid, &msg)? {
+            Ok(instances) => instances,
+            Err(refusal) => {
+                warn!("C-MOVE refused: {}", refusal.error_comment);
+                return respond(
+                    association,
+                    refusal.status,
+                    Some(refusal.error_comment),
+                    SubOperations::default(),
+                    None,
+                );
+            }
+        };
+
+    // move destinations are looked up by name or by AE title
+    let destination = archive.destinations.get(&rq.move_destination).or_else(|| {
+        archive
+            .destinations
+            .iter()
+            .find(|(_, ae)| ae.ae_title == rq.move_destination)
+            .map(|(_, ae)| ae)
+    });
+    let Some(destination) = destination else {
+        warn!(
+            "C-MOVE refused: unknown destination {}",
+            rq.move_destination
+        );
+        return respond(
+            association,
+            status::MOVE_DESTINATION_UNKNOWN,
+            Some(format!("unknown destination {}", rq.move_destination)),
+            SubOperations::default(),
+            None,
+        );
+    };
+    info!(
+        "C-MOVE: moving {} instances to {}",
+        instances.len(),
+        destination.address()
+    );
+    if instances.is_empty() {
+        return respond(
+            association,
+            status::SUCCESS,
+            None,
+            SubOperations::default(),
+            None,
+        );
+    }
+
+    let store_association = if destination.tls {
+        Err("TLS move destinations are not supported".to_string())
+    } else {
+        destination_options(archive, &instances)
+            .called_ae_title(&destination.ae_title)
+            .max_pdu_length(destination.max_pdu_length.unwrap_or(archive.max_pdu_length))
+            .establish_with(&destination.address())
+            .map_err(|e| {
+                format!(
+                    "could not associate with {}: {}",
+                    destination.address(),
+                    snafu::Report::from_error(e)
+                )
+            })
+    };
+    let mut store_association = match store_association {
+        Ok(store_association) => store_association,
+        Err(reason) => {
+            warn!("C-MOVE failed: {reason}");
+            return respond(
+                association,
+                status::OUT_OF_RESOURCES_SUB_OPERATIONS,
+                Some(reason),
+                SubOperations {
+                    remaining: None,
+                    completed: 0,
+                    failed: instances.len() as u16,
+                    warning: 0,
+                },
+                None,
+            );
+        }
+    };
+
+    let originator = association.peer_ae_title().to_string();
+    let mut progress = Progress::new(instances.len());
+    for (i, instance) in instances.iter().enumerate() {
+        let status = match encode_instance(&store_association, instance) {
+            Ok((store_pc_id, data)) => {
+                let store_rq = CStoreRq {
+                    message_id: (i + 1) as u16,
+                    affected_sop_class_uid: instance.sop_class_uid.clone(),
+                    affected_sop_instance_uid: instance.sop_instance_uid.clone(),
+                    priority: rq.priority,
+                    move_originator_ae_title: Some(originator.clone()),
+                    move_originator_message_id: Some(rq.message_id),
+                };
+                match store(
+                    &mut store_association,
+                    &store_rq,
+                    store_pc_id,
+                    &data,
+                    &mut false,
+                ) {
+                    Ok(status) => Some(status),
+                    Err(e) => {
+                        warn!(
+                            "Could not move {}: {}",
+                            instance.sop_instance_uid,
+                            snafu::Report::from_error(e)
+                        );
+                        None
+                    }
+                }
+            }
+            Err(reason) => {
+                warn!("Could not move {}: {}", instance.sop_instance_uid, reason);
+                None
+            }
+        };
+        progress.record(instance, status);
+        if progress.done() < progress.total {
+            respond(association, status::PENDING, None, progress.pending(), None)?;
+        }
+    }
+    if let Err(e) = store_association.release() {
+        warn!(
+            "Could not release association with move destination: {}",
+            snafu::Report::from_error(e)
+        );
+    }
+
+    let (status, sub_operations, identifier) = progress.finish(false);
+    respond(association, status, None, sub_operations, identifier)
+}
+
+/// The association options proposing the presentation contexts
+/// needed to store the given instances:
+/// one per SOP class and transfer syntax,
+/// also admitting the native transfer syntaxes
+/// for instances which can be converted to them.
+fn destination_options<'a>(
+    archive: &'a Archive,
+    instances: &[Instance],
+) -> ClientAssociationOptions<'a> {
+    let mut proposed: Vec<(&str, &str)> = Vec::new();
+    for instance in instances {
+        let key = (
+            instance.sop_class_uid.as_str(),
+            instance.transfer_syntax.trim_end_matches('\0'),
+        );
+        if !proposed.contains(&key) {
+            proposed.push(key);
+        }
+    }
+    let mut options = ClientAssociationOptions::new().calling_ae_title(archive.ae_title.as_str());
+    for (sop_class_uid, ts) in proposed {
+        let mut transfer_syntaxes = vec![ts.to_string()];
+        let native = TransferSyntaxRegistry
+            .get(ts)
+            .is_some_and(|ts| ts.is_codec_free() && !ts.is_encapsulated_pixel_data());
+        if native {
+            for uid in ["1.2.840.10008.1.2.1", "1.2.840.10008.1.2"] {
+                if uid != ts {
+                    transfer_syntaxes.push(uid.to_string());
+                }
+            }
+        }
+        options = options.with_presentation_context(sop_class_uid.to_string(), transfer_syntaxes);
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::tests::temp_dir;
+    use crate::scp::serve;
+    use crate::scp::tests::archive;
+    use dicom_dictionary_std::uids;
+    use dicom_ul::test_utils::association_pair;
+
+    #[test]
+    fn get_series() {
+        let dir = temp_dir("get");
+        let archive = archive(&dir);
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new()
+                .with_abstract_syntax(uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET)
+                .with_presentation_context(
+                    uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+                    vec![uids::IMPLICIT_VR_LITTLE_ENDIAN],
+                )
+                .with_role_selection(uids::SECONDARY_CAPTURE_IMAGE_STORAGE, false, true),
+            &crate::server_options(&archive, false),
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve(&mut scp, &archive).unwrap());
+
+            let get_pc_id = dimse::presentation_context_for(
+                &scu,
+                uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+            )
+            .unwrap()
+            .id;
+            let rq = CGetRq {
+                message_id: 1,
+                affected_sop_class_uid: uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET
+                    .to_string(),
+                priority: 0,
+            };
+            let identifier = InMemDicomObject::from_element_iter([
+                DataElement::new(tags::QUERY_RETRIEVE_LEVEL, VR::CS, "SERIES"),
+                DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "1.1.1"),
+            ]);
+            let data = dimse::write_data_set(&scu, get_pc_id, &identifier).unwrap();
+            send_message(&mut scu, get_pc_id, &rq.command(), Some(&data)).unwrap();
+
+            let mut stored = Vec::new();
+            let mut pending = Vec::new();
+            let final_rsp = loop {
+                let msg = receive_message(&mut scu).unwrap();
+                match msg.command_field().unwrap() {
+                    CommandField::CStoreRq => {
+                        let store_rq = CStoreRq::from_command(&msg.command).unwrap();
+                        let obj = msg.read_data_set(&scu).unwrap().unwrap();
+                        assert_eq!(
+                            obj.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap()[..],
+                            [0, 64, 128, 255]
+                        );
+                        stored.push(store_rq.affected_sop_instance_uid.clone());
+                        let rsp = CStoreRsp {
+                            message_id_being_responded_to: store_rq.message_id,
+                            affected_sop_class_uid: Some(store_rq.affected_sop_class_uid),
+                            affected_sop_instance_uid: Some(store_rq.affected_sop_instance_uid),
+                            status: status::SUCCESS,
+                            error_comment: None,
+                        };
+                        send_message(&mut scu, msg.presentation_context_id, &rsp.command(), None)
+                            .unwrap();
+                    }
+                    CommandField::CGetRsp => {
+                        let rsp = CGetRsp::from_command(&msg.command).unwrap();
+                        if rsp.status != status::PENDING {
+                            break rsp;
+                        }
+                        pending.push(rsp.sub_operations);
+                    }
+                    command_field => panic!("unexpected command {command_field:?}"),
+                }
+            };
+            assert_eq!(stored, ["1.1.1.1", "1.1.1.2"]);
+            assert_eq!(
+                pending,
+                [SubOperations {
+                    remaining: Some(1),
+                    completed: 1,
+                    failed: 0,
+                    warning: 0,
+                }]
+            );
+            assert_eq!(final_rsp.status, status::SUCCESS);
+            assert_eq!(final_rsp.sub_operations.completed, 2);
+
+            scu.release().unwrap();
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn move_study() {
+        use dicom_app_common::aeconfig::AeRegistry;
+        use dicom_ul::ServerAssociationOptions;
+
+        let dir = temp_dir("move");
+        let mut archive = archive(&dir);
+
+        // a storage SCP as the move destination
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        archive.destinations = AeRegistry::parse(&format!(
+            "[remote.STORE]\nhost = \"127.0.0.1\"\nport = {port}\nae_title = \"STORE-SCP\"\n"
+        ))
+        .unwrap();
+        let destination = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut association = ServerAssociationOptions::new()
+                .accept_any()
+                .promiscuous(true)
+                .establish(stream)
+                .unwrap();
+            let mut stored = Vec::new();
+            loop {
+                let msg = match receive_message(&mut association) {
+                    Ok(msg) => msg,
+                    Err(dimse::Error::Released { .. }) => {
+                        SyncAssociation::send(&mut association, &dicom_ul::Pdu::ReleaseRP).unwrap();
+                        break;
+                    }
+                    Err(e) => panic!("{e}"),
+                };
+                let rq = CStoreRq::from_command(&msg.command).unwrap();
+                assert_eq!(rq.move_originator_ae_title.as_deref(), Some("MOVE-SCU"));
+                stored.push(rq.affected_sop_instance_uid.clone());
+                let rsp = CStoreRsp {
+                    message_id_being_responded_to: rq.message_id,
+                    affected_sop_class_uid: Some(rq.affected_sop_class_uid),
+                    affected_sop_instance_uid: Some(rq.affected_sop_instance_uid),
+                    status: status::SUCCESS,
+                    error_comment: None,
+                };
+                send_message(
+                    &mut association,
+                    msg.presentation_context_id,
+                    &rsp.command(),
+                    None,
+                )
+                .unwrap();
+            }
+            stored
+        });
+
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new()
+                .calling_ae_title("MOVE-SCU")
+                .with_abstract_syntax(uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE),
+            &crate::server_options(&archive, false),
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve(&mut scp, &archive).unwrap());
+
+            let pc_id = dimse::presentation_context_for(
+                &scu,
+                uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+            )
+            .unwrap()
+            .id;
+            let mut move_study = |message_id, move_destination: &str| {
+                let rq = CMoveRq {
+                    message_id,
+                    affected_sop_class_uid: uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE
+                        .to_string(),
+                    priority: 0,
+                    move_destination: move_destination.to_string(),
+                };
+                let identifier = InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::QUERY_RETRIEVE_LEVEL, VR::CS, "STUDY"),
+                    DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "1.1"),
+                ]);
+                let data = dimse::write_data_set(&scu, pc_id, &identifier).unwrap();
+                send_message(&mut scu, pc_id, &rq.command(), Some(&data)).unwrap();
+                loop {
+                    let msg = receive_message(&mut scu).unwrap();
+                    let rsp = CMoveRsp::from_command(&msg.command).unwrap();
+                    if rsp.status != status::PENDING {
+                        break rsp;
+                    }
+                }
+            };
+
+            let rsp = move_study(1, "ELSEWHERE");
+            assert_eq!(rsp.status, status::MOVE_DESTINATION_UNKNOWN);
+
+            // destinations are also found by AE title
+            let rsp = move_study(2, "STORE-SCP");
+            assert_eq!(rsp.status, status::SUCCESS);
+            assert_eq!(rsp.sub_operations.completed, 2);
+
+            scu.release().unwrap();
+        });
+
+        assert_eq!(destination.join().unwrap(), ["1.1.1.1", "1.1.1.2"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
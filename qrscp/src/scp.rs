@@ -0,0 +1,353 @@
+//! Service of query/retrieve requests over an established association.
+use dicom_app_common::aeconfig::AeRegistry;
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    Pdu,
+    association::{CloseSocket, SyncAssociation},
+    dimse::{
+        self, CFindRq, CFindRsp, CommandField, Message,
+        query::{self, QueryLevel},
+        receive_message, send_message, status, write_data_set,
+    },
+    pdu::RequestorRoles,
+};
+use tracing::{debug, info, warn};
+
+use crate::index::Index;
+use crate::retrieve::{handle_get, handle_move};
+
+/// The state shared by all associations
+#[derive(Debug)]
+pub struct Archive {
+    /// the index of the archive
+    pub index: Index,
+    /// the AE title of this node
+    pub ae_title: String,
+    /// the maximum PDU length of associations with move destinations
+    pub max_pdu_length: u32,
+    /// the known move destinations
+    pub destinations: AeRegistry,
+}
+
+/// The query/retrieve information model of a request
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InformationModel {
+    PatientRoot,
+    StudyRoot,
+}
+
+impl InformationModel {
+    /// Identify the information model of a C-FIND, C-GET or C-MOVE SOP class
+    pub fn from_sop_class(uid: &str) -> Option<Self> {
+        match uid {
+            uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND
+            | uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET
+            | uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE => {
+                Some(InformationModel::PatientRoot)
+            }
+            uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND
+            | uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET
+            | uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE => {
+                Some(InformationModel::StudyRoot)
+            }
+            _ => None,
+        }
+    }
+
+    /// Determine the level of a request identifier in this model.
+    ///
+    /// Returns `None` if the level is missing, unknown,
+    /// or not part of this model.
+    pub fn level(self, identifier: &InMemDicomObject) -> Option<QueryLevel> {
+        let level = identifier
+            .get(tags::QUERY_RETRIEVE_LEVEL)
+            .and_then(|e| e.to_str().ok())
+            .and_then(|code| QueryLevel::from_code(&code))?;
+        if self == InformationModel::StudyRoot && level == QueryLevel::Patient {
+            return None;
+        }
+        Some(level)
+    }
+}
+
+/// The SOP classes of the query/retrieve services provided
+pub static ABSTRACT_SYNTAXES: &[&str] = &[
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+    uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_GET,
+    uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_MOVE,
+];
+
+/// Role selection accepting the roles proposed by the requestor,
+/// so that C-GET SCUs can take the SCP role of the storage SOP classes
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AcceptRoles;
+
+impl dicom_ul::association::server::Negotiation for AcceptRoles {
+    fn negotiate_roles(
+        &self,
+        _sop_class_uid: &str,
+        scu_role: bool,
+        scp_role: bool,
+    ) -> Option<RequestorRoles> {
+        Some(RequestorRoles {
+            scu: scu_role,
+            scp: scp_role,
+        })
+    }
+}
+
+/// Serve query/retrieve requests over an established association
+/// until the peer releases or aborts the association.
+pub fn serve<A, S>(association: &mut A, archive: &Archive) -> dimse::Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    loop {
+        let msg = match receive_message(association) {
+            Ok(msg) => msg,
+            Err(dimse::Error::Released { .. }) => {
+                SyncAssociation::send(association, &Pdu::ReleaseRP)
+                    .map_err(|source| dimse::Error::Association { source })?;
+                return Ok(());
+            }
+            Err(dimse::Error::Aborted { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        match msg.command_field() {
+            Ok(CommandField::CFindRq) => handle_find(association, archive, msg)?,
+            Ok(CommandField::CGetRq) => handle_get(association, archive, msg)?,
+            Ok(CommandField::CMoveRq) => handle_move(association, archive, msg)?,
+            // cancellation of an operation which already completed
+            Ok(CommandField::CCancelRq) => debug!("Ignoring C-CANCEL request"),
+            _ => refuse(association, &msg)?,
+        }
+    }
+}
+
+/// Respond to an unsupported request
+/// with the status _Unrecognized Operation_ (0211H)
+fn refuse<A, S>(association: &mut A, msg: &Message) -> dimse::Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let command_field = msg
+        .command
+        .get(tags::COMMAND_FIELD)
+        .and_then(|e| e.to_int::<u16>().ok())
+        .unwrap_or_default();
+    let message_id = msg
+        .command
+        .get(tags::MESSAGE_ID)
+        .and_then(|e| e.to_int::<u16>().ok())
+        .unwrap_or_default();
+    warn!("Refusing unsupported command {command_field:04X}H");
+    let rsp = InMemDicomObject::command_from_element_iter([
+        DataElement::new(
+            tags::COMMAND_FIELD,
+            VR::US,
+            PrimitiveValue::from(command_field | 0x8000),
+        ),
+        DataElement::new(
+            tags::MESSAGE_ID_BEING_RESPONDED_TO,
+            VR::US,
+            PrimitiveValue::from(message_id),
+        ),
+        DataElement::new(
+            tags::COMMAND_DATA_SET_TYPE,
+            VR::US,
+            PrimitiveValue::from(dimse::DATA_SET_ABSENT),
+        ),
+        DataElement::new(
+            tags::STATUS,
+            VR::US,
+            PrimitiveValue::from(status::UNRECOGNIZED_OPERATION),
+        ),
+    ]);
+    send_message(association, msg.presentation_context_id, &rsp, None)
+}
+
+/// Handle a C-FIND request,
+/// sending back one pending response per match
+/// followed by the final response.
+pub fn handle_find<A, S>(association: &mut A, archive: &Archive, msg: Message) -> dimse::Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let pc_id = msg.presentation_context_id;
+    let rq = CFindRq::from_command(&msg.command)?;
+    let identifier = msg
+        .read_data_set(&*association)?
+        .unwrap_or_else(InMemDicomObject::new_empty);
+
+    let outcome = match InformationModel::from_sop_class(&rq.affected_sop_class_uid) {
+        None => Err((status::NO_SUCH_SOP_CLASS, None)),
+        Some(model) => match model.level(&identifier) {
+            None => Err((
+                status::IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS,
+                Some("invalid Query/Retrieve Level"),
+            )),
+            Some(level) => match archive.index.summaries(&identifier, level) {
+                Ok(summaries) => Ok(summaries
+                    .into_iter()
+                    .filter(|summary| query::matches(&identifier, summary))
+                    .map(|summary| query::response_identifier(&identifier, &summary))
+                    .collect::<Vec<_>>()),
+                Err(e) => {
+                    warn!("Could not read the index: {}", snafu::Report::from_error(e));
+                    Err((status::UNABLE_TO_PROCESS, Some("could not read the index")))
+                }
+            },
+        },
+    };
+
+    let (final_status, error_comment) = match outcome {
+        Ok(matches) => {
+            info!("C-FIND: {} matches", matches.len());
+            for identifier in matches {
+                let data = write_data_set(&*association, pc_id, &identifier)?;
+                let rsp = CFindRsp {
+                    message_id_being_responded_to: rq.message_id,
+                    affected_sop_class_uid: Some(rq.affected_sop_class_uid.clone()),
+                    status: status::PENDING,
+                    error_comment: None,
+                };
+                send_message(association, pc_id, &rsp.command(true), Some(&data))?;
+            }
+            (status::SUCCESS, None)
+        }
+        Err((status, comment)) => {
+            warn!("C-FIND refused with status {status:04X}H");
+            (status, comment.map(String::from))
+        }
+    };
+    let rsp = CFindRsp {
+        message_id_being_responded_to: rq.message_id,
+        affected_sop_class_uid: Some(rq.affected_sop_class_uid),
+        status: final_status,
+        error_comment,
+    };
+    send_message(association, pc_id, &rsp.command(false), None)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::index::tests::{store_instance, temp_dir};
+    use dicom_ul::{
+        ClientAssociationOptions,
+        dimse::{find::FindScu, query::QueryBuilder},
+        test_utils::association_pair,
+    };
+    use std::path::Path;
+
+    /// Create an indexed archive of 3 instances in 2 studies of the same patient
+    pub(crate) fn archive(dir: &Path) -> Archive {
+        let db = dir.join("index.db");
+        let index = dicom_app_common::index::Index::open(&db).unwrap();
+        store_instance(
+            &index,
+            &dir.join("1.dcm"),
+            "P1",
+            "1.1",
+            "1.1.1",
+            "1.1.1.1",
+            "CT",
+        );
+        store_instance(
+            &index,
+            &dir.join("2.dcm"),
+            "P1",
+            "1.1",
+            "1.1.1",
+            "1.1.1.2",
+            "CT",
+        );
+        store_instance(
+            &index,
+            &dir.join("3.dcm"),
+            "P1",
+            "1.2",
+            "1.2.1",
+            "1.2.1.1",
+            "MR",
+        );
+        Archive {
+            index: Index::open(&db).unwrap(),
+            ae_title: "QR-SCP".to_string(),
+            max_pdu_length: 16378,
+            destinations: AeRegistry::default(),
+        }
+    }
+
+    #[test]
+    fn find_studies_and_series() {
+        let dir = temp_dir("find");
+        let archive = archive(&dir);
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new()
+                .with_abstract_syntax(uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND),
+            &crate::server_options(&archive, false),
+        )
+        .unwrap();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| serve(&mut scp, &archive).unwrap());
+
+            let mut find = FindScu::new(
+                &mut scu,
+                uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+            )
+            .unwrap();
+            let query = QueryBuilder::new(QueryLevel::Study)
+                .with_key("PatientName", "doe*")
+                .with_return_key("ModalitiesInStudy")
+                .build()
+                .unwrap();
+            let studies: Vec<_> = find
+                .find(&query)
+                .unwrap()
+                .map(|item| item.unwrap().identifier)
+                .collect();
+            assert_eq!(studies.len(), 2);
+            let str_of = |obj: &InMemDicomObject, tag| {
+                obj.get(tag).unwrap().to_str().unwrap().trim().to_string()
+            };
+            assert_eq!(str_of(&studies[0], tags::STUDY_INSTANCE_UID), "1.1");
+            assert_eq!(str_of(&studies[1], tags::MODALITIES_IN_STUDY), "MR");
+
+            let query = QueryBuilder::new(QueryLevel::Series)
+                .with_key("StudyInstanceUID", "1.1")
+                .with_return_key("NumberOfSeriesRelatedInstances")
+                .build()
+                .unwrap();
+            let series: Vec<_> = find
+                .find(&query)
+                .unwrap()
+                .map(|item| item.unwrap().identifier)
+                .collect();
+            assert_eq!(series.len(), 1);
+            assert_eq!(
+                str_of(&series[0], tags::NUMBER_OF_SERIES_RELATED_INSTANCES),
+                "2"
+            );
+
+            // the patient level is not part of the study root model
+            let query = QueryBuilder::new(QueryLevel::Patient).build().unwrap();
+            let mut responses = find.find(&query).unwrap();
+            assert!(responses.next().unwrap().is_err());
+            drop(responses);
+
+            scu.release().unwrap();
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,398 @@
+//! Deformable Spatial Registration objects
+//!
+//! A _Deformable Spatial Registration_ object registers
+//! one or more source frames of reference with its own frame of reference,
+//! with one item in the _Deformable Registration Sequence_
+//! per source frame of reference.
+//! A point in the source frame of reference is mapped
+//! into the registered frame of reference
+//! by applying, in this order:
+//!
+//! 1. the _Pre Deformation Matrix Registration_, if any;
+//! 2. the displacement vector of the _Deformable Registration Grid_, if any,
+//!    interpolated at the point;
+//! 3. the _Post Deformation Matrix Registration_, if any.
+//!
+//! The inverse mapping of a deformation
+//! is found iteratively,
+//! which converges for the smooth, invertible deformations
+//! produced by registration algorithms.
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, ensure};
+
+use crate::{
+    InvalidOrientationSnafu, InvalidValueCountSnafu, MissingAttributeSnafu, Result, floats, items,
+    matrix::TransformMatrix, required_floats, required_ints, required_text,
+};
+
+/// The maximum number of iterations when inverting a deformation
+const MAX_INVERSE_ITERATIONS: usize = 100;
+
+/// The tolerance in millimeters when inverting a deformation
+const INVERSE_TOLERANCE: f64 = 1e-6;
+
+/// A regular grid of displacement vectors,
+/// as in an item of the _Deformable Registration Grid Sequence_.
+///
+/// The grid is defined in the coordinates
+/// obtained after the pre-deformation matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeformationGrid {
+    /// The position of the first grid point in millimeters
+    pub origin: [f64; 3],
+    /// The direction cosines of the grid rows
+    /// (the direction of increasing `x` index)
+    pub row_direction: [f64; 3],
+    /// The direction cosines of the grid columns
+    /// (the direction of increasing `y` index)
+    pub column_direction: [f64; 3],
+    /// The number of grid points along `x`, `y` and `z`
+    pub dimensions: [usize; 3],
+    /// The distance between grid points along `x`, `y` and `z`
+    /// in millimeters
+    pub resolution: [f64; 3],
+    /// The displacement vectors in millimeters,
+    /// with `x` varying fastest and `z` slowest
+    pub vectors: Vec<[f64; 3]>,
+}
+
+impl DeformationGrid {
+    /// Read a deformation grid from an item of the
+    /// _Deformable Registration Grid Sequence_.
+    pub fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        let origin = required_floats::<3>(item, tags::IMAGE_POSITION_PATIENT)?;
+        let orientation = required_floats::<6>(item, tags::IMAGE_ORIENTATION_PATIENT)?;
+        let row_direction = [orientation[0], orientation[1], orientation[2]];
+        let column_direction = [orientation[3], orientation[4], orientation[5]];
+        let normal = cross(&row_direction, &column_direction);
+        ensure!(
+            (dot(&normal, &normal) - 1.).abs() < 1e-3,
+            InvalidOrientationSnafu
+        );
+        let dimensions = required_ints::<3>(item, tags::GRID_DIMENSIONS)?.map(|d| d as usize);
+        let resolution = required_floats::<3>(item, tags::GRID_RESOLUTION)?;
+
+        let tag = tags::VECTOR_GRID_DATA;
+        let data = floats(item, tag)?.context(MissingAttributeSnafu { tag })?;
+        let expected = dimensions.iter().product::<usize>() * 3;
+        ensure!(
+            data.len() == expected,
+            InvalidValueCountSnafu {
+                tag,
+                expected,
+                found: data.len(),
+            }
+        );
+        let vectors = data.chunks_exact(3).map(|v| [v[0], v[1], v[2]]).collect();
+
+        Ok(DeformationGrid {
+            origin,
+            row_direction,
+            column_direction,
+            dimensions,
+            resolution,
+            vectors,
+        })
+    }
+
+    /// The displacement vector at the given point,
+    /// interpolated trilinearly between the nearest grid points.
+    ///
+    /// Outside of the grid,
+    /// the displacement at the nearest edge of the grid is used.
+    pub fn displacement(&self, point: [f64; 3]) -> [f64; 3] {
+        if self.vectors.is_empty() {
+            return [0.; 3];
+        }
+        let d = sub(&point, &self.origin);
+        let normal = cross(&self.row_direction, &self.column_direction);
+        let axes = [self.row_direction, self.column_direction, normal];
+
+        // the grid index of the point along each axis,
+        // split into the lower grid point and the weight of the upper one
+        let mut lower = [0; 3];
+        let mut weight = [0.; 3];
+        for i in 0..3 {
+            let n = self.dimensions[i];
+            let spacing = if self.resolution[i] == 0. {
+                1.
+            } else {
+                self.resolution[i]
+            };
+            let index = (dot(&d, &axes[i]) / spacing).clamp(0., (n - 1) as f64);
+            lower[i] = (index.floor() as usize).min(n.saturating_sub(2));
+            weight[i] = if n > 1 { index - lower[i] as f64 } else { 0. };
+        }
+
+        let [nx, ny, _] = self.dimensions;
+        let mut out = [0.; 3];
+        for corner in 0..8 {
+            let mut w = 1.;
+            let mut idx = [0; 3];
+            for i in 0..3 {
+                let upper = corner >> i & 1 == 1;
+                if upper && self.dimensions[i] < 2 {
+                    w = 0.;
+                    break;
+                }
+                idx[i] = lower[i] + usize::from(upper);
+                w *= if upper { weight[i] } else { 1. - weight[i] };
+            }
+            if w == 0. {
+                continue;
+            }
+            let v = &self.vectors[(idx[2] * ny + idx[1]) * nx + idx[0]];
+            for (o, v) in out.iter_mut().zip(v) {
+                *o += w * v;
+            }
+        }
+        out
+    }
+}
+
+/// The registration of a frame of reference
+/// into the registered frame of reference,
+/// as in an item of the _Deformable Registration Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deformation {
+    /// The source frame of reference
+    pub source_frame_of_reference_uid: String,
+    /// The matrix applied before the deformation
+    /// (the identity if not present)
+    pub pre_matrix: TransformMatrix,
+    /// The deformation grid, if any
+    pub grid: Option<DeformationGrid>,
+    /// The matrix applied after the deformation
+    /// (the identity if not present)
+    pub post_matrix: TransformMatrix,
+}
+
+impl Deformation {
+    /// Read a deformation from an item of the
+    /// _Deformable Registration Sequence_.
+    pub fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        let source_frame_of_reference_uid =
+            required_text(item, tags::SOURCE_FRAME_OF_REFERENCE_UID)?;
+        let matrix = |tag| {
+            items(item, tag)
+                .first()
+                .map(|m| TransformMatrix::from_matrix_registration(m).map(|(m, _)| m))
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        let pre_matrix = matrix(tags::PRE_DEFORMATION_MATRIX_REGISTRATION_SEQUENCE)?;
+        let post_matrix = matrix(tags::POST_DEFORMATION_MATRIX_REGISTRATION_SEQUENCE)?;
+        let grid = items(item, tags::DEFORMABLE_REGISTRATION_GRID_SEQUENCE)
+            .first()
+            .map(DeformationGrid::from_item)
+            .transpose()?;
+        Ok(Deformation {
+            source_frame_of_reference_uid,
+            pre_matrix,
+            grid,
+            post_matrix,
+        })
+    }
+
+    /// Map a point from the source frame of reference
+    /// into the registered frame of reference.
+    pub fn to_registered(&self, point: [f64; 3]) -> [f64; 3] {
+        let mut p = self.pre_matrix.transform_point(point);
+        if let Some(grid) = &self.grid {
+            p = add(&p, &grid.displacement(p));
+        }
+        self.post_matrix.transform_point(p)
+    }
+
+    /// Map a point from the registered frame of reference
+    /// into the source frame of reference.
+    ///
+    /// Returns `None` if either matrix is not invertible
+    /// or if the inverse of the deformation could not be found.
+    pub fn to_source(&self, point: [f64; 3]) -> Option<[f64; 3]> {
+        let target = self.post_matrix.inverse()?.transform_point(point);
+        let mut p = target;
+        if let Some(grid) = &self.grid {
+            // find `p` such that `p + displacement(p) = target`
+            let mut converged = false;
+            for _ in 0..MAX_INVERSE_ITERATIONS {
+                let next = sub(&target, &grid.displacement(p));
+                let step = sub(&next, &p);
+                p = next;
+                if dot(&step, &step).sqrt() < INVERSE_TOLERANCE {
+                    converged = true;
+                    break;
+                }
+            }
+            if !converged {
+                return None;
+            }
+        }
+        Some(self.pre_matrix.inverse()?.transform_point(p))
+    }
+}
+
+/// The registrations of a _Deformable Spatial Registration_ object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeformableRegistration {
+    /// The SOP instance UID of the registration object
+    pub sop_instance_uid: String,
+    /// The registered frame of reference,
+    /// into which all deformations map
+    pub frame_of_reference_uid: String,
+    /// The deformations, one per source frame of reference
+    pub deformations: Vec<Deformation>,
+}
+
+impl DeformableRegistration {
+    /// Read the registrations of a Deformable Spatial Registration object.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let sop_instance_uid = required_text(obj, tags::SOP_INSTANCE_UID)?;
+        let frame_of_reference_uid = required_text(obj, tags::FRAME_OF_REFERENCE_UID)?;
+        let sequence = items(obj, tags::DEFORMABLE_REGISTRATION_SEQUENCE);
+        ensure!(
+            !sequence.is_empty(),
+            MissingAttributeSnafu {
+                tag: tags::DEFORMABLE_REGISTRATION_SEQUENCE
+            }
+        );
+        let deformations = sequence
+            .iter()
+            .map(Deformation::from_item)
+            .collect::<Result<_>>()?;
+        Ok(DeformableRegistration {
+            sop_instance_uid,
+            frame_of_reference_uid,
+            deformations,
+        })
+    }
+
+    /// Find the deformation of the given source frame of reference.
+    pub fn deformation(&self, frame_of_reference_uid: &str) -> Option<&Deformation> {
+        self.deformations
+            .iter()
+            .find(|d| d.source_frame_of_reference_uid == frame_of_reference_uid)
+    }
+
+    /// Map a point in patient coordinates
+    /// from one frame of reference into another,
+    /// going through the registered frame of reference.
+    ///
+    /// Returns `None` if either frame of reference is not known
+    /// to this registration,
+    /// or if the point could not be mapped back
+    /// into the target frame of reference.
+    pub fn map_point(&self, point: [f64; 3], from: &str, to: &str) -> Option<[f64; 3]> {
+        let registered = if from == self.frame_of_reference_uid {
+            point
+        } else {
+            self.deformation(from)?.to_registered(point)
+        };
+        if from == to {
+            return Some(point);
+        }
+        if to == self.frame_of_reference_uid {
+            Some(registered)
+        } else {
+            self.deformation(to)?.to_source(registered)
+        }
+    }
+}
+
+fn add(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeformableRegistration;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for (a, b) in a.iter().zip(&b) {
+            assert!((a - b).abs() < 1e-4, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn map_points_through_deformation_grid() {
+        // a 2x2x2 grid with 10 mm spacing,
+        // displacing points along x by a tenth of their x position
+        let vectors: Vec<f32> = (0..8)
+            .flat_map(|i| [if i % 2 == 1 { 1. } else { 0. }, 0., 0.])
+            .collect();
+        let grid = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::IMAGE_POSITION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["0", "0", "0"]),
+            ),
+            DataElement::new(
+                tags::IMAGE_ORIENTATION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["1", "0", "0", "0", "1", "0"]),
+            ),
+            DataElement::new(tags::GRID_DIMENSIONS, VR::UL, dicom_value!(U32, [2, 2, 2])),
+            DataElement::new(
+                tags::GRID_RESOLUTION,
+                VR::FD,
+                dicom_value!(F64, [10., 10., 10.]),
+            ),
+            DataElement::new(
+                tags::VECTOR_GRID_DATA,
+                VR::OF,
+                PrimitiveValue::F32(vectors.into()),
+            ),
+        ]);
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOURCE_FRAME_OF_REFERENCE_UID, VR::UI, "2.25.20"),
+            DataElement::new(
+                tags::DEFORMABLE_REGISTRATION_GRID_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![grid]),
+            ),
+        ]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::FRAME_OF_REFERENCE_UID, VR::UI, "2.25.10"),
+            DataElement::new(
+                tags::DEFORMABLE_REGISTRATION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+        ]);
+
+        let reg = DeformableRegistration::from_object(&obj).unwrap();
+        let grid = reg.deformations[0].grid.as_ref().unwrap();
+        assert_eq!(grid.dimensions, [2, 2, 2]);
+        assert_close(grid.displacement([5., 3., 7.]), [0.5, 0., 0.]);
+        // clamped outside of the grid
+        assert_close(grid.displacement([20., -5., 0.]), [1., 0., 0.]);
+
+        let p = reg.map_point([5., 3., 7.], "2.25.20", "2.25.10").unwrap();
+        assert_close(p, [5.5, 3., 7.]);
+        let q = reg.map_point(p, "2.25.10", "2.25.20").unwrap();
+        assert_close(q, [5., 3., 7.]);
+        assert_eq!(reg.map_point(p, "2.25.10", "2.25.99"), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! DICOM spatial registration
+//!
+//! This crate reads the registrations between frames of reference
+//! held by DICOM registration objects,
+//! so that points in one frame of reference can be mapped into another,
+//! as needed to fuse images acquired in different frames of reference:
+//!
+//! - [`SpatialRegistration`] reads a _Spatial Registration_ object,
+//!   with one [`TransformMatrix`] per registered frame of reference
+//!   (rigid, rigid with scale, or affine).
+//! - [`DeformableRegistration`] reads a _Deformable Spatial Registration_
+//!   object, in which each registered frame of reference
+//!   is mapped by a pre-deformation matrix,
+//!   an optional [`DeformationGrid`] of displacement vectors,
+//!   and a post-deformation matrix.
+//!
+//! In both cases, the registrations map points
+//! from each _source_ frame of reference
+//! into the _registered_ frame of reference,
+//! which is the frame of reference of the registration object itself.
+//! Use `map_point` to map a point between any two of them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_registration::SpatialRegistration;
+//!
+//! let obj = open_file("reg.dcm")?;
+//! let registration = SpatialRegistration::from_object(&obj)?;
+//! let moving = &registration.registrations[0].frame_of_reference_uid;
+//! if let Some(point) =
+//!     registration.map_point([10., 20., 30.], moving, &registration.frame_of_reference_uid)
+//! {
+//!     println!("Registered point: {point:?}");
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_core::Tag;
+use dicom_core::header::HasLength;
+use dicom_object::InMemDicomObject;
+use dicom_object::mem::InMemElement;
+use snafu::{OptionExt, Snafu};
+
+pub mod deformable;
+pub mod matrix;
+pub mod spatial;
+
+pub use deformable::{DeformableRegistration, Deformation, DeformationGrid};
+pub use matrix::{MatrixType, TransformMatrix};
+pub use spatial::{MatrixRegistration, SpatialRegistration};
+
+/// An error which may occur when reading a registration object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for registration objects
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Missing attribute {tag}
+    MissingAttribute { tag: Tag },
+    /// Invalid value of attribute {tag}
+    InvalidAttribute { tag: Tag },
+    #[snafu(display("Expected {expected} values in {tag}, found {found}"))]
+    InvalidValueCount {
+        tag: Tag,
+        expected: usize,
+        found: usize,
+    },
+    /// Invalid grid orientation
+    InvalidOrientation,
+}
+
+/// Alias for the result of reading a registration object.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Retrieve the attribute with the given tag,
+/// if present and not empty.
+fn non_empty(obj: &InMemDicomObject, tag: Tag) -> Option<&InMemElement> {
+    obj.get(tag).filter(|e| !e.is_empty())
+}
+
+/// Read the text value of an attribute, if present,
+/// without padding.
+pub(crate) fn text(obj: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    non_empty(obj, tag)
+        .map(|e| {
+            e.to_str()
+                .map(|s| s.trim_matches([' ', '\0']).to_string())
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read the text value of a required attribute.
+pub(crate) fn required_text(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    Ok(text(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+/// Read a multi-valued numeric attribute, if present.
+pub(crate) fn floats(obj: &InMemDicomObject, tag: Tag) -> Result<Option<Vec<f64>>> {
+    non_empty(obj, tag)
+        .map(|e| {
+            e.to_multi_float64()
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read a required numeric attribute with exactly `N` values.
+pub(crate) fn required_floats<const N: usize>(
+    obj: &InMemDicomObject,
+    tag: Tag,
+) -> Result<[f64; N]> {
+    let values = floats(obj, tag)?.context(MissingAttributeSnafu { tag })?;
+    let found = values.len();
+    Ok(values.try_into().ok().context(InvalidValueCountSnafu {
+        tag,
+        expected: N,
+        found,
+    })?)
+}
+
+/// Read a required integer attribute with exactly `N` values.
+pub(crate) fn required_ints<const N: usize>(obj: &InMemDicomObject, tag: Tag) -> Result<[u32; N]> {
+    let values: Vec<u32> = non_empty(obj, tag)
+        .context(MissingAttributeSnafu { tag })?
+        .to_multi_int()
+        .ok()
+        .context(InvalidAttributeSnafu { tag })?;
+    let found = values.len();
+    Ok(values.try_into().ok().context(InvalidValueCountSnafu {
+        tag,
+        expected: N,
+        found,
+    })?)
+}
+
+/// Retrieve the items of a sequence,
+/// or none if the sequence is not present.
+pub(crate) fn items(obj: &InMemDicomObject, tag: Tag) -> &[InMemDicomObject] {
+    obj.get(tag).and_then(|e| e.items()).unwrap_or_default()
+}
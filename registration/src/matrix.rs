@@ -0,0 +1,203 @@
+//! Transformation matrices between frames of reference
+//!
+//! A _Frame of Reference Transformation Matrix_ is a 4×4 matrix
+//! in homogeneous coordinates, stored in row-major order,
+//! which maps a point `(x, y, z, 1)` in millimeters
+//! from one patient coordinate system into another.
+//! When a _Matrix Sequence_ holds more than one matrix,
+//! they are applied in the order of the sequence items,
+//! and [`TransformMatrix::from_matrix_registration`]
+//! composes them into a single matrix.
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::ensure;
+use std::fmt;
+
+use crate::{MissingAttributeSnafu, Result, items, required_floats, text};
+
+/// The type of a transformation matrix,
+/// as in the _Frame of Reference Transformation Matrix Type_ attribute.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum MatrixType {
+    /// Rotation and translation only (`RIGID`)
+    Rigid,
+    /// Rotation and translation with isotropic scaling (`RIGID_SCALE`)
+    RigidScale,
+    /// Any affine transformation (`AFFINE`)
+    Affine,
+    /// Any other type
+    Other(String),
+}
+
+impl MatrixType {
+    /// Obtain the matrix type from its defined term.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "RIGID" => MatrixType::Rigid,
+            "RIGID_SCALE" => MatrixType::RigidScale,
+            "AFFINE" => MatrixType::Affine,
+            code => MatrixType::Other(code.to_string()),
+        }
+    }
+
+    /// The defined term of the matrix type.
+    pub fn as_str(&self) -> &str {
+        match self {
+            MatrixType::Rigid => "RIGID",
+            MatrixType::RigidScale => "RIGID_SCALE",
+            MatrixType::Affine => "AFFINE",
+            MatrixType::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for MatrixType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A 4×4 transformation matrix in homogeneous coordinates,
+/// indexed by row and then column.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TransformMatrix(pub [[f64; 4]; 4]);
+
+impl Default for TransformMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl TransformMatrix {
+    /// The identity transformation
+    pub const IDENTITY: TransformMatrix = TransformMatrix([
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ]);
+
+    /// Create a matrix from its 16 values in row-major order,
+    /// as stored in the _Frame of Reference Transformation Matrix_.
+    pub fn from_row_major(values: [f64; 16]) -> Self {
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row.copy_from_slice(&values[i * 4..i * 4 + 4]);
+        }
+        TransformMatrix(m)
+    }
+
+    /// Read the composite matrix of an item of a
+    /// _Matrix Registration Sequence_
+    /// (or of a pre- or post-deformation matrix registration sequence),
+    /// together with the types of its matrices.
+    pub fn from_matrix_registration(item: &InMemDicomObject) -> Result<(Self, Vec<MatrixType>)> {
+        let matrices = items(item, tags::MATRIX_SEQUENCE);
+        ensure!(
+            !matrices.is_empty(),
+            MissingAttributeSnafu {
+                tag: tags::MATRIX_SEQUENCE
+            }
+        );
+
+        let mut matrix = TransformMatrix::IDENTITY;
+        let mut types = Vec::with_capacity(matrices.len());
+        for m in matrices {
+            let values = required_floats::<16>(m, tags::FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX)?;
+            matrix = TransformMatrix::from_row_major(values).then(&matrix);
+            if let Some(code) = text(m, tags::FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE)? {
+                types.push(MatrixType::from_code(&code));
+            }
+        }
+        Ok((matrix, types))
+    }
+
+    /// The matrix which applies this transformation
+    /// and then `other`.
+    pub fn then(&self, other: &TransformMatrix) -> TransformMatrix {
+        let (a, b) = (&other.0, &self.0);
+        let mut m = [[0.; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = (0..4).map(|k| a[i][k] * b[k][j]).sum();
+            }
+        }
+        TransformMatrix(m)
+    }
+
+    /// The inverse transformation,
+    /// or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<TransformMatrix> {
+        // Gauss-Jordan elimination with partial pivoting
+        let mut a = self.0;
+        let mut inv = TransformMatrix::IDENTITY.0;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap_or(col);
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let p = a[col][col];
+            for j in 0..4 {
+                a[col][j] /= p;
+                inv[col][j] /= p;
+            }
+            for i in (0..4).filter(|&i| i != col) {
+                let f = a[i][col];
+                for j in 0..4 {
+                    a[i][j] -= f * a[col][j];
+                    inv[i][j] -= f * inv[col][j];
+                }
+            }
+        }
+        Some(TransformMatrix(inv))
+    }
+
+    /// Map a point by this transformation.
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        let m = &self.0;
+        let p = [point[0], point[1], point[2], 1.];
+        let mut out = [0.; 4];
+        for (o, row) in out.iter_mut().zip(m) {
+            *o = row.iter().zip(&p).map(|(a, b)| a * b).sum();
+        }
+        let w = if out[3] == 0. { 1. } else { out[3] };
+        [out[0] / w, out[1] / w, out[2] / w]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransformMatrix;
+
+    #[test]
+    fn compose_and_invert_matrices() {
+        // rotate 90 degrees around z, then translate by (10, 0, 0)
+        let rotate = TransformMatrix::from_row_major([
+            0., -1., 0., 0., //
+            1., 0., 0., 0., //
+            0., 0., 1., 0., //
+            0., 0., 0., 1.,
+        ]);
+        let translate = TransformMatrix::from_row_major([
+            1., 0., 0., 10., //
+            0., 1., 0., 0., //
+            0., 0., 1., 0., //
+            0., 0., 0., 1.,
+        ]);
+        let m = rotate.then(&translate);
+        assert_eq!(m.transform_point([1., 2., 3.]), [8., 1., 3.]);
+
+        let inv = m.inverse().unwrap();
+        let p = inv.transform_point([8., 1., 3.]);
+        for (a, b) in p.iter().zip([1., 2., 3.]) {
+            assert!((a - b).abs() < 1e-9);
+        }
+
+        assert!(TransformMatrix([[0.; 4]; 4]).inverse().is_none());
+    }
+}
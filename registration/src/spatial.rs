@@ -0,0 +1,200 @@
+//! Spatial Registration objects
+//!
+//! A _Spatial Registration_ object registers one or more
+//! frames of reference with its own frame of reference
+//! (the _registered_ frame of reference),
+//! with one item in the _Registration Sequence_
+//! per source frame of reference.
+//! Each item holds the matrices that map points
+//! from the source frame of reference into the registered one.
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, ensure};
+
+use crate::{
+    MissingAttributeSnafu, Result, items, matrix::MatrixType, matrix::TransformMatrix,
+    required_text,
+};
+
+/// The registration of a frame of reference
+/// into the registered frame of reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixRegistration {
+    /// The source frame of reference
+    pub frame_of_reference_uid: String,
+    /// The composite matrix mapping points
+    /// from the source frame of reference
+    /// into the registered frame of reference
+    pub matrix: TransformMatrix,
+    /// The types of the matrices composed,
+    /// in order of application
+    pub matrix_types: Vec<MatrixType>,
+}
+
+impl MatrixRegistration {
+    /// Read a registration from an item of the _Registration Sequence_.
+    pub fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        let frame_of_reference_uid = required_text(item, tags::FRAME_OF_REFERENCE_UID)?;
+        let registration = items(item, tags::MATRIX_REGISTRATION_SEQUENCE)
+            .first()
+            .context(MissingAttributeSnafu {
+                tag: tags::MATRIX_REGISTRATION_SEQUENCE,
+            })?;
+        let (matrix, matrix_types) = TransformMatrix::from_matrix_registration(registration)?;
+        Ok(MatrixRegistration {
+            frame_of_reference_uid,
+            matrix,
+            matrix_types,
+        })
+    }
+}
+
+/// The registrations of a _Spatial Registration_ object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialRegistration {
+    /// The SOP instance UID of the registration object
+    pub sop_instance_uid: String,
+    /// The registered frame of reference,
+    /// into which all registrations map
+    pub frame_of_reference_uid: String,
+    /// The registrations, one per source frame of reference
+    pub registrations: Vec<MatrixRegistration>,
+}
+
+impl SpatialRegistration {
+    /// Read the registrations of a Spatial Registration object.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let sop_instance_uid = required_text(obj, tags::SOP_INSTANCE_UID)?;
+        let frame_of_reference_uid = required_text(obj, tags::FRAME_OF_REFERENCE_UID)?;
+        let sequence = items(obj, tags::REGISTRATION_SEQUENCE);
+        ensure!(
+            !sequence.is_empty(),
+            MissingAttributeSnafu {
+                tag: tags::REGISTRATION_SEQUENCE
+            }
+        );
+        let registrations = sequence
+            .iter()
+            .map(MatrixRegistration::from_item)
+            .collect::<Result<_>>()?;
+        Ok(SpatialRegistration {
+            sop_instance_uid,
+            frame_of_reference_uid,
+            registrations,
+        })
+    }
+
+    /// Find the registration of the given source frame of reference.
+    pub fn registration(&self, frame_of_reference_uid: &str) -> Option<&MatrixRegistration> {
+        self.registrations
+            .iter()
+            .find(|r| r.frame_of_reference_uid == frame_of_reference_uid)
+    }
+
+    /// The matrix mapping points from the registered frame of reference
+    /// or a source frame of reference into the registered frame of reference.
+    fn to_registered(&self, frame_of_reference_uid: &str) -> Option<TransformMatrix> {
+        if frame_of_reference_uid == self.frame_of_reference_uid {
+            return Some(TransformMatrix::IDENTITY);
+        }
+        self.registration(frame_of_reference_uid).map(|r| r.matrix)
+    }
+
+    /// The matrix mapping points from one frame of reference into another,
+    /// going through the registered frame of reference.
+    ///
+    /// Returns `None` if either frame of reference is not known
+    /// to this registration,
+    /// or if the matrix of the target frame of reference is not invertible.
+    pub fn transform(&self, from: &str, to: &str) -> Option<TransformMatrix> {
+        let forward = self.to_registered(from)?;
+        if from == to {
+            return Some(TransformMatrix::IDENTITY);
+        }
+        let backward = self.to_registered(to)?.inverse()?;
+        Some(forward.then(&backward))
+    }
+
+    /// Map a point in patient coordinates
+    /// from one frame of reference into another.
+    ///
+    /// Returns `None` if the mapping is not known, see [`transform`](Self::transform).
+    pub fn map_point(&self, point: [f64; 3], from: &str, to: &str) -> Option<[f64; 3]> {
+        self.transform(from, to).map(|m| m.transform_point(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpatialRegistration;
+    use crate::MatrixType;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
+
+    fn matrix_registration(values: [f64; 16], kind: &str) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([DataElement::new(
+            tags::MATRIX_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX,
+                    VR::DS,
+                    PrimitiveValue::Strs(values.iter().map(|v| v.to_string()).collect()),
+                ),
+                DataElement::new(
+                    tags::FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE,
+                    VR::CS,
+                    kind,
+                ),
+            ])]),
+        )])
+    }
+
+    #[test]
+    fn map_points_between_frames() {
+        let translation = |x: f64| {
+            [
+                1., 0., 0., x, //
+                0., 1., 0., 0., //
+                0., 0., 1., 0., //
+                0., 0., 0., 1.,
+            ]
+        };
+        let item = |uid: &str, x: f64| {
+            InMemDicomObject::from_element_iter([
+                DataElement::new(tags::FRAME_OF_REFERENCE_UID, VR::UI, uid),
+                DataElement::new(
+                    tags::MATRIX_REGISTRATION_SEQUENCE,
+                    VR::SQ,
+                    DataSetSequence::from(vec![matrix_registration(translation(x), "RIGID")]),
+                ),
+            ])
+        };
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::FRAME_OF_REFERENCE_UID, VR::UI, "2.25.10"),
+            DataElement::new(
+                tags::REGISTRATION_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item("2.25.10", 0.), item("2.25.20", 5.)]),
+            ),
+        ]);
+
+        let reg = SpatialRegistration::from_object(&obj).unwrap();
+        assert_eq!(reg.frame_of_reference_uid, "2.25.10");
+        assert_eq!(reg.registrations.len(), 2);
+        assert_eq!(reg.registrations[1].matrix_types, [MatrixType::Rigid]);
+
+        assert_eq!(
+            reg.map_point([1., 2., 3.], "2.25.20", "2.25.10"),
+            Some([6., 2., 3.])
+        );
+        assert_eq!(
+            reg.map_point([6., 2., 3.], "2.25.10", "2.25.20"),
+            Some([1., 2., 3.])
+        );
+        assert_eq!(reg.map_point([1., 2., 3.], "2.25.20", "2.25.99"), None);
+    }
+}
@@ -0,0 +1,133 @@
+//! A CLI tool for consistently replacing the instance UIDs of DICOM files.
+//!
+//! This command line tool replaces the study, series and SOP instance UIDs
+//! of a set of DICOM files,
+//! and the UIDs referring to them,
+//! through a [`UidRemapper`],
+//! so that the same original UID is replaced by the same new UID in all files.
+//! The mapping can be loaded from and saved to a file.
+
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use dicom_object::open_file;
+use dicom_object::remap::UidRemapper;
+use snafu::{ResultExt, Whatever, whatever};
+use walkdir::WalkDir;
+
+/// Replace the instance UIDs of DICOM files consistently
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The DICOM files to remap, or directories to look for them
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Directory for the remapped files
+    /// (default is to overwrite the input files)
+    #[arg(short = 'o', long = "out-dir")]
+    out_dir: Option<PathBuf>,
+    /// UID map file to read before remapping, if it exists,
+    /// and to save the mapping to
+    #[arg(short = 'm', long = "map")]
+    map: Option<PathBuf>,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        files,
+        out_dir,
+        map,
+    } = app;
+
+    let mut remapper = match &map {
+        Some(map) if map.exists() => UidRemapper::open_map(map)
+            .with_whatever_context(|_| format!("could not load UID map {}", map.display()))?,
+        _ => UidRemapper::new(),
+    };
+
+    let mut failed = 0;
+    for (path, output) in collect_files(&files, out_dir.as_deref()) {
+        if let Err(e) = remap_file(&mut remapper, &path, &output) {
+            tracing::error!("{}", snafu::Report::from_error(e));
+            failed += 1;
+        }
+    }
+
+    if let Some(map) = &map {
+        remapper
+            .save_map(map)
+            .with_whatever_context(|_| format!("could not save UID map {}", map.display()))?;
+    }
+    if failed > 0 {
+        whatever!("could not remap {failed} file(s)");
+    }
+    Ok(())
+}
+
+/// Gather the files to remap and where to save each of them,
+/// keeping their paths relative to the given directories
+/// in the output directory.
+fn collect_files(files: &[PathBuf], out_dir: Option<&Path>) -> Vec<(PathBuf, PathBuf)> {
+    let mut out = Vec::new();
+    for root in files {
+        if root.is_dir() {
+            for entry in WalkDir::new(root)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|e| e.file_type().is_file())
+            {
+                let path = entry.into_path();
+                let output = match out_dir {
+                    Some(out_dir) => out_dir.join(path.strip_prefix(root).unwrap_or(&path)),
+                    None => path.clone(),
+                };
+                out.push((path, output));
+            }
+        } else {
+            let output = match out_dir {
+                Some(out_dir) => out_dir.join(root.file_name().unwrap_or_default()),
+                None => root.clone(),
+            };
+            out.push((root.clone(), output));
+        }
+    }
+    out
+}
+
+fn remap_file(remapper: &mut UidRemapper, path: &Path, output: &Path) -> Result<(), Whatever> {
+    let mut obj =
+        open_file(path).with_whatever_context(|_| format!("could not open {}", path.display()))?;
+    let count = remapper.remap_file(&mut obj);
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)
+            .with_whatever_context(|_| format!("could not create {}", parent.display()))?;
+    }
+    obj.write_to_file(output)
+        .with_whatever_context(|_| format!("could not save {}", output.display()))?;
+    println!("Saved {} ({count} attributes changed)", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::App;
+    use clap::CommandFactory;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+}
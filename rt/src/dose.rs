@@ -0,0 +1,307 @@
+//! Dose grids of RT Dose objects
+//!
+//! An _RT Dose_ object holds a three-dimensional dose grid
+//! as a multi-frame image,
+//! with one frame per plane of the grid
+//! at the offsets in the _Grid Frame Offset Vector_.
+//! The stored pixel values are multiplied by the _Dose Grid Scaling_
+//! to obtain the dose in the _Dose Units_ of the object,
+//! which is usually gray (Gy).
+//!
+//! With the `ndarray` feature,
+//! [`DoseGrid::decode_values`] decodes the scaled dose values
+//! into an array indexed by frame, row and column.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "ndarray")] {
+//! use dicom_object::open_file;
+//! use dicom_rt::DoseGrid;
+//!
+//! let obj = open_file("rtdose.dcm")?;
+//! let grid = DoseGrid::from_object(&obj)?;
+//! let dose = grid.decode_values(&obj)?;
+//! let max = dose.iter().copied().fold(0., f64::max);
+//! println!("Maximum dose: {max} {}", grid.units);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, ensure};
+use std::fmt;
+
+use crate::{
+    InvalidValueCountSnafu, MissingAttributeSnafu, Result, float, floats, int, items,
+    required_floats, text,
+};
+
+/// The units of the dose values,
+/// as in the _Dose Units_ attribute.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum DoseUnits {
+    /// Gray (`GY`)
+    Gray,
+    /// Dose relative to an implicit reference value (`RELATIVE`)
+    Relative,
+    /// Any other units
+    Other(String),
+}
+
+impl DoseUnits {
+    /// Obtain the dose units from their defined term.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "GY" => DoseUnits::Gray,
+            "RELATIVE" => DoseUnits::Relative,
+            code => DoseUnits::Other(code.to_string()),
+        }
+    }
+
+    /// The defined term of the dose units.
+    pub fn as_str(&self) -> &str {
+        match self {
+            DoseUnits::Gray => "GY",
+            DoseUnits::Relative => "RELATIVE",
+            DoseUnits::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for DoseUnits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DoseUnits::Gray => f.write_str("Gy"),
+            units => f.write_str(units.as_str()),
+        }
+    }
+}
+
+/// The geometry and properties of the dose grid of an _RT Dose_ object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoseGrid {
+    /// The number of rows of each frame
+    pub rows: u32,
+    /// The number of columns of each frame
+    pub columns: u32,
+    /// The number of frames, one per plane of the grid
+    pub frames: u32,
+    /// The position of the center of the first voxel
+    /// in the patient based coordinate system, in millimeters
+    pub image_position: [f64; 3],
+    /// The direction cosines of the rows and the columns
+    pub image_orientation: [f64; 6],
+    /// The distance between the centers of adjacent rows
+    /// and of adjacent columns, in millimeters
+    pub pixel_spacing: [f64; 2],
+    /// The offset of each frame along the normal of the image plane,
+    /// in millimeters, relative to the first frame
+    pub frame_offsets: Vec<f64>,
+    /// The factor converting stored pixel values into dose units
+    pub scaling: f64,
+    /// The units of the dose values
+    pub units: DoseUnits,
+    /// The _Dose Type_, such as `PHYSICAL` or `EFFECTIVE`
+    pub dose_type: Option<String>,
+    /// The _Dose Summation Type_, such as `PLAN` or `BEAM`
+    pub summation_type: Option<String>,
+    /// The UID of the frame of reference of the grid
+    pub frame_of_reference_uid: Option<String>,
+    /// The SOP Instance UID of the RT Plan the dose was calculated for
+    pub referenced_plan_uid: Option<String>,
+}
+
+impl DoseGrid {
+    /// Read the dose grid properties from an _RT Dose_ object.
+    ///
+    /// The _Grid Frame Offset Vector_ may be given
+    /// relative to the _Image Position (Patient)_
+    /// or as absolute coordinates:
+    /// either way, the offsets are made relative to the first frame.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let dimension = |tag| -> Result<u32> {
+            Ok(int(obj, tag)?
+                .context(MissingAttributeSnafu { tag })?
+                .max(0) as u32)
+        };
+        let frames = int(obj, tags::NUMBER_OF_FRAMES)?.unwrap_or(1).max(0) as u32;
+
+        let mut frame_offsets = floats(obj, tags::GRID_FRAME_OFFSET_VECTOR)?.unwrap_or_default();
+        if frame_offsets.is_empty() && frames == 1 {
+            frame_offsets.push(0.);
+        }
+        ensure!(
+            frame_offsets.len() == frames as usize,
+            InvalidValueCountSnafu {
+                tag: tags::GRID_FRAME_OFFSET_VECTOR,
+                expected: frames as usize,
+                found: frame_offsets.len(),
+            }
+        );
+        if let Some(&first) = frame_offsets.first() {
+            for offset in &mut frame_offsets {
+                *offset -= first;
+            }
+        }
+
+        let referenced_plan_uid = match items(obj, tags::REFERENCED_RT_PLAN_SEQUENCE).first() {
+            Some(item) => text(item, tags::REFERENCED_SOP_INSTANCE_UID)?,
+            None => None,
+        };
+
+        Ok(DoseGrid {
+            rows: dimension(tags::ROWS)?,
+            columns: dimension(tags::COLUMNS)?,
+            frames,
+            image_position: required_floats(obj, tags::IMAGE_POSITION_PATIENT)?,
+            image_orientation: required_floats(obj, tags::IMAGE_ORIENTATION_PATIENT)?,
+            pixel_spacing: required_floats(obj, tags::PIXEL_SPACING)?,
+            frame_offsets,
+            scaling: float(obj, tags::DOSE_GRID_SCALING)?.unwrap_or(1.),
+            units: DoseUnits::from_code(&text(obj, tags::DOSE_UNITS)?.unwrap_or_default()),
+            dose_type: text(obj, tags::DOSE_TYPE)?,
+            summation_type: text(obj, tags::DOSE_SUMMATION_TYPE)?,
+            frame_of_reference_uid: text(obj, tags::FRAME_OF_REFERENCE_UID)?,
+            referenced_plan_uid,
+        })
+    }
+
+    /// The position of the center of the given voxel
+    /// in the patient based coordinate system, in millimeters.
+    ///
+    /// Returns `None` if the frame is out of the grid.
+    pub fn position(&self, frame: u32, row: u32, column: u32) -> Option<[f64; 3]> {
+        let offset = *self.frame_offsets.get(frame as usize)?;
+        let [rx, ry, rz, cx, cy, cz] = self.image_orientation;
+        let normal = [ry * cz - rz * cy, rz * cx - rx * cz, rx * cy - ry * cx];
+        let along_row = column as f64 * self.pixel_spacing[1];
+        let along_column = row as f64 * self.pixel_spacing[0];
+        Some(std::array::from_fn(|i| {
+            self.image_position[i]
+                + self.image_orientation[i] * along_row
+                + self.image_orientation[i + 3] * along_column
+                + normal[i] * offset
+        }))
+    }
+
+    /// Decode the dose values of the grid from the _RT Dose_ object,
+    /// scaled to the dose units of the grid,
+    /// into an array with the shape `[frames, rows, columns]`.
+    #[cfg(feature = "ndarray")]
+    pub fn decode_values(
+        &self,
+        obj: &dicom_object::FileDicomObject<InMemDicomObject>,
+    ) -> Result<dicom_pixeldata::ndarray::Array3<f64>> {
+        use dicom_pixeldata::ndarray::Axis;
+        use dicom_pixeldata::{ConvertOptions, ModalityLutOption, PixelDecoder, Rescale};
+        use snafu::ResultExt;
+
+        let options = ConvertOptions::new()
+            .with_modality_lut(ModalityLutOption::Override(Rescale::new(self.scaling, 0.)));
+        let volume = obj
+            .decode_pixel_data()
+            .context(crate::DecodeDoseSnafu)?
+            .to_ndarray_volume_with_options::<f64>(&options)
+            .context(crate::DecodeDoseSnafu)?;
+        Ok(volume.index_axis_move(Axis(3), 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value};
+
+    fn rtdose() -> InMemDicomObject {
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                dicom_dictionary_std::uids::RT_DOSE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            us(tags::SAMPLES_PER_PIXEL, 1),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, "2"),
+            us(tags::ROWS, 2),
+            us(tags::COLUMNS, 3),
+            us(tags::BITS_ALLOCATED, 16),
+            us(tags::BITS_STORED, 16),
+            us(tags::HIGH_BIT, 15),
+            us(tags::PIXEL_REPRESENTATION, 0),
+            DataElement::new(
+                tags::IMAGE_POSITION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["-10", "-20", "-30"]),
+            ),
+            DataElement::new(
+                tags::IMAGE_ORIENTATION_PATIENT,
+                VR::DS,
+                dicom_value!(Strs, ["1", "0", "0", "0", "1", "0"]),
+            ),
+            DataElement::new(
+                tags::PIXEL_SPACING,
+                VR::DS,
+                dicom_value!(Strs, ["2.5", "2"]),
+            ),
+            DataElement::new(
+                tags::GRID_FRAME_OFFSET_VECTOR,
+                VR::DS,
+                dicom_value!(Strs, ["-30", "-27"]),
+            ),
+            DataElement::new(tags::DOSE_GRID_SCALING, VR::DS, "0.001"),
+            DataElement::new(tags::DOSE_UNITS, VR::CS, "GY"),
+            DataElement::new(tags::DOSE_TYPE, VR::CS, "PHYSICAL"),
+            DataElement::new(tags::DOSE_SUMMATION_TYPE, VR::CS, "PLAN"),
+            DataElement::new(
+                tags::REFERENCED_RT_PLAN_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_SOP_INSTANCE_UID, VR::UI, "2.25.2"),
+                ])]),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OW,
+                PrimitiveValue::U16((0..12).map(|v| v * 1000).collect()),
+            ),
+        ])
+    }
+
+    #[test]
+    fn read_dose_grid() {
+        let grid = DoseGrid::from_object(&rtdose()).unwrap();
+        assert_eq!((grid.frames, grid.rows, grid.columns), (2, 2, 3));
+        assert_eq!(grid.frame_offsets, vec![0., 3.]);
+        assert_eq!(grid.scaling, 0.001);
+        assert_eq!(grid.units, DoseUnits::Gray);
+        assert_eq!(grid.units.to_string(), "Gy");
+        assert_eq!(grid.summation_type.as_deref(), Some("PLAN"));
+        assert_eq!(grid.referenced_plan_uid.as_deref(), Some("2.25.2"));
+
+        assert_eq!(grid.position(0, 0, 0), Some([-10., -20., -30.]));
+        assert_eq!(grid.position(1, 1, 2), Some([-6., -17.5, -27.]));
+        assert_eq!(grid.position(2, 0, 0), None);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn decode_dose_values() {
+        use dicom_object::FileMetaTableBuilder;
+
+        let obj = rtdose()
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(dicom_dictionary_std::uids::EXPLICIT_VR_LITTLE_ENDIAN),
+            )
+            .unwrap();
+        let grid = DoseGrid::from_object(&obj).unwrap();
+        let dose = grid.decode_values(&obj).unwrap();
+        assert_eq!(dose.shape(), &[2, 2, 3]);
+        assert!((dose[[0, 0, 1]] - 1.).abs() < 1e-9);
+        assert!((dose[[1, 1, 2]] - 11.).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,150 @@
+//! DICOM radiotherapy objects
+//!
+//! This crate provides typed access to the contents of
+//! the most common radiotherapy (RT) objects,
+//! which otherwise requires walking through deeply nested sequences:
+//!
+//! - [`StructureSet`] reads the regions of interest of an _RT Structure Set_
+//!   (`RTSTRUCT`) and their contours,
+//!   together with the images and frame of reference they refer to.
+//! - [`DoseGrid`] reads the geometry of an _RT Dose_ (`RTDOSE`) grid,
+//!   and with the `ndarray` feature (enabled by default)
+//!   decodes its dose values in gray
+//!   into a three-dimensional array.
+//! - [`Plan`] reads the basic properties of an _RT Plan_ (`RTPLAN`):
+//!   its prescriptions, fraction groups, and beams.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_rt::StructureSet;
+//!
+//! let obj = open_file("rtstruct.dcm")?;
+//! let structure_set = StructureSet::from_object(&obj)?;
+//! for roi in &structure_set.rois {
+//!     let points: usize = roi.contours.iter().map(|c| c.points.len()).sum();
+//!     println!("ROI #{} {}: {} points", roi.number, roi.name, points);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+use dicom_core::Tag;
+use dicom_core::header::HasLength;
+use dicom_object::InMemDicomObject;
+use dicom_object::mem::InMemElement;
+use snafu::{OptionExt, Snafu};
+
+pub mod dose;
+pub mod plan;
+pub mod structure_set;
+
+pub use dose::DoseGrid;
+pub use plan::Plan;
+pub use structure_set::StructureSet;
+
+/// An error which may occur when reading an RT object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for RT objects
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Missing attribute {tag}
+    MissingAttribute { tag: Tag },
+    /// Invalid value of attribute {tag}
+    InvalidAttribute { tag: Tag },
+    #[snafu(display("Expected {expected} values in {tag}, found {found}"))]
+    InvalidValueCount {
+        tag: Tag,
+        expected: usize,
+        found: usize,
+    },
+    /// Could not decode the dose grid
+    #[cfg(feature = "ndarray")]
+    DecodeDose {
+        #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
+        source: Box<dicom_pixeldata::Error>,
+    },
+}
+
+/// Alias for the result of reading an RT object.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Retrieve the attribute with the given tag,
+/// if present and not empty.
+fn non_empty(obj: &InMemDicomObject, tag: Tag) -> Option<&InMemElement> {
+    obj.get(tag).filter(|e| !e.is_empty())
+}
+
+/// Read the text value of an attribute, if present,
+/// without padding.
+pub(crate) fn text(obj: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    non_empty(obj, tag)
+        .map(|e| {
+            e.to_str()
+                .map(|s| s.trim_matches([' ', '\0']).to_string())
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read the text value of a required attribute.
+pub(crate) fn required_text(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    Ok(text(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+/// Read an integer attribute, if present.
+pub(crate) fn int(obj: &InMemDicomObject, tag: Tag) -> Result<Option<i32>> {
+    non_empty(obj, tag)
+        .map(|e| e.to_int().ok().context(InvalidAttributeSnafu { tag }))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read a required integer attribute.
+pub(crate) fn required_int(obj: &InMemDicomObject, tag: Tag) -> Result<i32> {
+    Ok(int(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+/// Read a numeric attribute, if present.
+pub(crate) fn float(obj: &InMemDicomObject, tag: Tag) -> Result<Option<f64>> {
+    non_empty(obj, tag)
+        .map(|e| e.to_float64().ok().context(InvalidAttributeSnafu { tag }))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read a multi-valued numeric attribute, if present.
+pub(crate) fn floats(obj: &InMemDicomObject, tag: Tag) -> Result<Option<Vec<f64>>> {
+    non_empty(obj, tag)
+        .map(|e| {
+            e.to_multi_float64()
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read a required numeric attribute with exactly `N` values.
+pub(crate) fn required_floats<const N: usize>(
+    obj: &InMemDicomObject,
+    tag: Tag,
+) -> Result<[f64; N]> {
+    let values = floats(obj, tag)?.context(MissingAttributeSnafu { tag })?;
+    let found = values.len();
+    Ok(values.try_into().ok().context(InvalidValueCountSnafu {
+        tag,
+        expected: N,
+        found,
+    })?)
+}
+
+/// Retrieve the items of a sequence,
+/// or none if the sequence is not present.
+pub(crate) fn items(obj: &InMemDicomObject, tag: Tag) -> &[InMemDicomObject] {
+    obj.get(tag).and_then(|e| e.items()).unwrap_or_default()
+}
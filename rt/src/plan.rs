@@ -0,0 +1,246 @@
+//! Basic properties of RT Plan objects
+//!
+//! [`Plan::from_object`] reads the identification of an _RT Plan_,
+//! its dose references (prescriptions),
+//! its fraction groups with the meterset of each beam,
+//! and the beams of the _Beam Sequence_,
+//! leaving out the control points of each beam.
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+
+use crate::{Result, float, int, items, required_int, text};
+
+/// The basic properties of an _RT Plan_ object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// The _RT Plan Label_
+    pub label: String,
+    /// The _RT Plan Name_, if any
+    pub name: Option<String>,
+    /// The _RT Plan Description_, if any
+    pub description: Option<String>,
+    /// The _RT Plan Date_, as in the object (`YYYYMMDD`)
+    pub date: Option<String>,
+    /// The _RT Plan Time_, as in the object (`HHMMSS.FFFFFF`)
+    pub time: Option<String>,
+    /// The _RT Plan Geometry_, `PATIENT` or `TREATMENT_DEVICE`
+    pub geometry: Option<String>,
+    /// The _Plan Intent_, such as `CURATIVE` or `PALLIATIVE`
+    pub intent: Option<String>,
+    /// The dose references of the plan
+    pub dose_references: Vec<DoseReference>,
+    /// The fraction groups of the plan
+    pub fraction_groups: Vec<FractionGroup>,
+    /// The treatment beams of the plan
+    pub beams: Vec<Beam>,
+}
+
+/// A dose reference of a plan,
+/// as in an item of the _Dose Reference Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoseReference {
+    /// The _Dose Reference Number_
+    pub number: i32,
+    /// The _Dose Reference Description_, if any
+    pub description: Option<String>,
+    /// The _Dose Reference Type_, `TARGET` or `ORGAN_AT_RISK`
+    pub reference_type: Option<String>,
+    /// The _Target Prescription Dose_ in Gy, if any
+    pub target_prescription_dose: Option<f64>,
+    /// The _Delivery Maximum Dose_ in Gy, if any
+    pub delivery_maximum_dose: Option<f64>,
+}
+
+/// A fraction group of a plan,
+/// as in an item of the _Fraction Group Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FractionGroup {
+    /// The _Fraction Group Number_
+    pub number: i32,
+    /// The _Number of Fractions Planned_, if known
+    pub fractions_planned: Option<i32>,
+    /// The beams delivered in each fraction,
+    /// with their meterset
+    pub beams: Vec<ReferencedBeam>,
+}
+
+/// A beam delivered in a fraction group,
+/// as in an item of the _Referenced Beam Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferencedBeam {
+    /// The number of the beam in the plan
+    pub number: i32,
+    /// The _Beam Meterset_ delivered in each fraction,
+    /// usually in monitor units, if known
+    pub meterset: Option<f64>,
+}
+
+/// A treatment beam of a plan,
+/// as in an item of the _Beam Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Beam {
+    /// The _Beam Number_
+    pub number: i32,
+    /// The _Beam Name_, if any
+    pub name: Option<String>,
+    /// The _Beam Description_, if any
+    pub description: Option<String>,
+    /// The _Beam Type_, `STATIC` or `DYNAMIC`
+    pub beam_type: Option<String>,
+    /// The _Radiation Type_, such as `PHOTON` or `ELECTRON`
+    pub radiation_type: Option<String>,
+    /// The _Treatment Machine Name_, if any
+    pub treatment_machine_name: Option<String>,
+    /// The _Number of Control Points_
+    pub control_points: u32,
+}
+
+impl Plan {
+    /// Read the basic properties of an _RT Plan_ object.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let dose_references = items(obj, tags::DOSE_REFERENCE_SEQUENCE)
+            .iter()
+            .map(|item| {
+                Ok(DoseReference {
+                    number: required_int(item, tags::DOSE_REFERENCE_NUMBER)?,
+                    description: text(item, tags::DOSE_REFERENCE_DESCRIPTION)?,
+                    reference_type: text(item, tags::DOSE_REFERENCE_TYPE)?,
+                    target_prescription_dose: float(item, tags::TARGET_PRESCRIPTION_DOSE)?,
+                    delivery_maximum_dose: float(item, tags::DELIVERY_MAXIMUM_DOSE)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let fraction_groups = items(obj, tags::FRACTION_GROUP_SEQUENCE)
+            .iter()
+            .map(|item| {
+                Ok(FractionGroup {
+                    number: required_int(item, tags::FRACTION_GROUP_NUMBER)?,
+                    fractions_planned: int(item, tags::NUMBER_OF_FRACTIONS_PLANNED)?,
+                    beams: items(item, tags::REFERENCED_BEAM_SEQUENCE)
+                        .iter()
+                        .map(|beam| {
+                            Ok(ReferencedBeam {
+                                number: required_int(beam, tags::REFERENCED_BEAM_NUMBER)?,
+                                meterset: float(beam, tags::BEAM_METERSET)?,
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let beams = items(obj, tags::BEAM_SEQUENCE)
+            .iter()
+            .map(|item| {
+                Ok(Beam {
+                    number: required_int(item, tags::BEAM_NUMBER)?,
+                    name: text(item, tags::BEAM_NAME)?,
+                    description: text(item, tags::BEAM_DESCRIPTION)?,
+                    beam_type: text(item, tags::BEAM_TYPE)?,
+                    radiation_type: text(item, tags::RADIATION_TYPE)?,
+                    treatment_machine_name: text(item, tags::TREATMENT_MACHINE_NAME)?,
+                    control_points: int(item, tags::NUMBER_OF_CONTROL_POINTS)?
+                        .unwrap_or_default()
+                        .max(0) as u32,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Plan {
+            label: text(obj, tags::RT_PLAN_LABEL)?.unwrap_or_default(),
+            name: text(obj, tags::RT_PLAN_NAME)?,
+            description: text(obj, tags::RT_PLAN_DESCRIPTION)?,
+            date: text(obj, tags::RT_PLAN_DATE)?,
+            time: text(obj, tags::RT_PLAN_TIME)?,
+            geometry: text(obj, tags::RT_PLAN_GEOMETRY)?,
+            intent: text(obj, tags::PLAN_INTENT)?,
+            dose_references,
+            fraction_groups,
+            beams,
+        })
+    }
+
+    /// Look up a beam by its number.
+    pub fn beam(&self, number: i32) -> Option<&Beam> {
+        self.beams.iter().find(|beam| beam.number == number)
+    }
+
+    /// The total number of fractions planned over all fraction groups.
+    pub fn fractions_planned(&self) -> i32 {
+        self.fraction_groups
+            .iter()
+            .filter_map(|group| group.fractions_planned)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, VR};
+
+    #[test]
+    fn read_plan() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::RT_PLAN_LABEL, VR::SH, "Prostate"),
+            DataElement::new(tags::RT_PLAN_GEOMETRY, VR::CS, "PATIENT"),
+            DataElement::new(tags::PLAN_INTENT, VR::CS, "CURATIVE"),
+            DataElement::new(
+                tags::DOSE_REFERENCE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::DOSE_REFERENCE_NUMBER, VR::IS, "1"),
+                    DataElement::new(tags::DOSE_REFERENCE_TYPE, VR::CS, "TARGET"),
+                    DataElement::new(tags::TARGET_PRESCRIPTION_DOSE, VR::DS, "78"),
+                ])]),
+            ),
+            DataElement::new(
+                tags::FRACTION_GROUP_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::FRACTION_GROUP_NUMBER, VR::IS, "1"),
+                    DataElement::new(tags::NUMBER_OF_FRACTIONS_PLANNED, VR::IS, "39"),
+                    DataElement::new(
+                        tags::REFERENCED_BEAM_SEQUENCE,
+                        VR::SQ,
+                        DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                            DataElement::new(tags::REFERENCED_BEAM_NUMBER, VR::IS, "1"),
+                            DataElement::new(tags::BEAM_METERSET, VR::DS, "120.5"),
+                        ])]),
+                    ),
+                ])]),
+            ),
+            DataElement::new(
+                tags::BEAM_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::BEAM_NUMBER, VR::IS, "1"),
+                    DataElement::new(tags::BEAM_NAME, VR::LO, "AP"),
+                    DataElement::new(tags::BEAM_TYPE, VR::CS, "DYNAMIC"),
+                    DataElement::new(tags::RADIATION_TYPE, VR::CS, "PHOTON"),
+                    DataElement::new(tags::NUMBER_OF_CONTROL_POINTS, VR::IS, "2"),
+                ])]),
+            ),
+        ]);
+
+        let plan = Plan::from_object(&obj).unwrap();
+        assert_eq!(plan.label, "Prostate");
+        assert_eq!(plan.intent.as_deref(), Some("CURATIVE"));
+        assert_eq!(plan.dose_references[0].target_prescription_dose, Some(78.));
+        assert_eq!(plan.fractions_planned(), 39);
+        assert_eq!(
+            plan.fraction_groups[0].beams,
+            vec![ReferencedBeam {
+                number: 1,
+                meterset: Some(120.5),
+            }]
+        );
+        let beam = plan.beam(1).unwrap();
+        assert_eq!(beam.name.as_deref(), Some("AP"));
+        assert_eq!(beam.radiation_type.as_deref(), Some("PHOTON"));
+        assert_eq!(beam.control_points, 2);
+        assert!(plan.beam(2).is_none());
+    }
+}
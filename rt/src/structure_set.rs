@@ -0,0 +1,358 @@
+//! Regions of interest of RT Structure Set objects
+//!
+//! An _RT Structure Set_ defines regions of interest (ROIs),
+//! such as target volumes and organs at risk,
+//! in the _Structure Set ROI Sequence_,
+//! and their contours in the _ROI Contour Sequence_,
+//! matched by ROI number.
+//! [`StructureSet::from_object`] joins both sequences,
+//! along with the _RT ROI Observations Sequence_,
+//! into one [`Roi`] per region.
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::ensure;
+
+use crate::{
+    InvalidAttributeSnafu, InvalidValueCountSnafu, Result, floats, int, items, required_int,
+    required_text, text,
+};
+
+/// The contents of an _RT Structure Set_ object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureSet {
+    /// The _Structure Set Label_
+    pub label: String,
+    /// The _Structure Set Name_, if any
+    pub name: Option<String>,
+    /// The _Structure Set Date_, as in the object (`YYYYMMDD`)
+    pub date: Option<String>,
+    /// The _Structure Set Time_, as in the object (`HHMMSS.FFFFFF`)
+    pub time: Option<String>,
+    /// The regions of interest, in the order of the _Structure Set ROI Sequence_
+    pub rois: Vec<Roi>,
+}
+
+/// A region of interest of a structure set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Roi {
+    /// The _ROI Number_, unique in the structure set
+    pub number: i32,
+    /// The _ROI Name_
+    pub name: String,
+    /// The UID of the frame of reference
+    /// in which the contour points are defined
+    pub frame_of_reference_uid: String,
+    /// The _ROI Generation Algorithm_
+    /// (`AUTOMATIC`, `SEMIAUTOMATIC` or `MANUAL`), if known
+    pub generation_algorithm: Option<String>,
+    /// The _RT ROI Interpreted Type_, such as `PTV` or `ORGAN`,
+    /// according to the first observation of the ROI
+    pub interpreted_type: Option<String>,
+    /// The _ROI Display Color_ as RGB
+    pub display_color: Option<[u8; 3]>,
+    /// The contours of the ROI
+    pub contours: Vec<Contour>,
+}
+
+/// The geometric type of a contour,
+/// as in the _Contour Geometric Type_ attribute.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum ContourGeometry {
+    /// A single point
+    Point,
+    /// An open contour of coplanar points
+    OpenPlanar,
+    /// An open contour of points which are not necessarily coplanar
+    OpenNonplanar,
+    /// A closed contour of coplanar points,
+    /// where the last point connects to the first one
+    ClosedPlanar,
+    /// Any other geometric type
+    Other(String),
+}
+
+impl ContourGeometry {
+    /// Obtain the geometric type from its defined term.
+    pub fn from_code(code: &str) -> Self {
+        match code {
+            "POINT" => ContourGeometry::Point,
+            "OPEN_PLANAR" => ContourGeometry::OpenPlanar,
+            "OPEN_NONPLANAR" => ContourGeometry::OpenNonplanar,
+            "CLOSED_PLANAR" => ContourGeometry::ClosedPlanar,
+            code => ContourGeometry::Other(code.to_string()),
+        }
+    }
+
+    /// The defined term of the geometric type.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ContourGeometry::Point => "POINT",
+            ContourGeometry::OpenPlanar => "OPEN_PLANAR",
+            ContourGeometry::OpenNonplanar => "OPEN_NONPLANAR",
+            ContourGeometry::ClosedPlanar => "CLOSED_PLANAR",
+            ContourGeometry::Other(code) => code,
+        }
+    }
+}
+
+/// A contour of a region of interest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    /// The geometric type of the contour
+    pub geometry: ContourGeometry,
+    /// The points of the contour,
+    /// as (x, y, z) coordinates in millimeters
+    /// in the patient based coordinate system
+    /// of the ROI's frame of reference
+    pub points: Vec<[f64; 3]>,
+    /// The images on which the contour was defined, if any
+    pub images: Vec<ContourImage>,
+}
+
+/// An image on which a contour was defined,
+/// as in an item of the _Contour Image Sequence_.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct ContourImage {
+    /// The SOP Class UID of the image
+    pub sop_class_uid: String,
+    /// The SOP Instance UID of the image
+    pub sop_instance_uid: String,
+    /// The frame number of the image, starting at 1,
+    /// if it is a multi-frame image
+    pub frame: Option<u32>,
+}
+
+impl StructureSet {
+    /// Read the regions of interest and their contours
+    /// from an _RT Structure Set_ object.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let mut rois = items(obj, tags::STRUCTURE_SET_ROI_SEQUENCE)
+            .iter()
+            .map(|item| {
+                Ok(Roi {
+                    number: required_int(item, tags::ROI_NUMBER)?,
+                    name: text(item, tags::ROI_NAME)?.unwrap_or_default(),
+                    frame_of_reference_uid: required_text(
+                        item,
+                        tags::REFERENCED_FRAME_OF_REFERENCE_UID,
+                    )?,
+                    generation_algorithm: text(item, tags::ROI_GENERATION_ALGORITHM)?,
+                    interpreted_type: None,
+                    display_color: None,
+                    contours: Vec::new(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for item in items(obj, tags::ROI_CONTOUR_SEQUENCE) {
+            let number = required_int(item, tags::REFERENCED_ROI_NUMBER)?;
+            let Some(roi) = rois.iter_mut().find(|roi| roi.number == number) else {
+                continue;
+            };
+            if let Some(&[r, g, b]) = floats(item, tags::ROI_DISPLAY_COLOR)?.as_deref() {
+                roi.display_color = Some([r as u8, g as u8, b as u8]);
+            }
+            roi.contours = items(item, tags::CONTOUR_SEQUENCE)
+                .iter()
+                .map(read_contour)
+                .collect::<Result<_>>()?;
+        }
+
+        for item in items(obj, tags::RTROI_OBSERVATIONS_SEQUENCE) {
+            let number = required_int(item, tags::REFERENCED_ROI_NUMBER)?;
+            if let Some(roi) = rois
+                .iter_mut()
+                .find(|roi| roi.number == number && roi.interpreted_type.is_none())
+            {
+                roi.interpreted_type = text(item, tags::RTROI_INTERPRETED_TYPE)?;
+            }
+        }
+
+        Ok(StructureSet {
+            label: text(obj, tags::STRUCTURE_SET_LABEL)?.unwrap_or_default(),
+            name: text(obj, tags::STRUCTURE_SET_NAME)?,
+            date: text(obj, tags::STRUCTURE_SET_DATE)?,
+            time: text(obj, tags::STRUCTURE_SET_TIME)?,
+            rois,
+        })
+    }
+
+    /// Look up a region of interest by its number.
+    pub fn roi(&self, number: i32) -> Option<&Roi> {
+        self.rois.iter().find(|roi| roi.number == number)
+    }
+
+    /// Look up a region of interest by its name,
+    /// ignoring case.
+    pub fn roi_by_name(&self, name: &str) -> Option<&Roi> {
+        self.rois
+            .iter()
+            .find(|roi| roi.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Roi {
+    /// Iterate over the contours of the ROI
+    /// which were defined on the image with the given SOP Instance UID.
+    pub fn contours_on_image<'a>(
+        &'a self,
+        sop_instance_uid: &'a str,
+    ) -> impl Iterator<Item = &'a Contour> + 'a {
+        self.contours.iter().filter(move |contour| {
+            contour
+                .images
+                .iter()
+                .any(|image| image.sop_instance_uid == sop_instance_uid)
+        })
+    }
+}
+
+fn read_contour(item: &InMemDicomObject) -> Result<Contour> {
+    let data = floats(item, tags::CONTOUR_DATA)?.unwrap_or_default();
+    ensure!(
+        data.len() % 3 == 0,
+        InvalidAttributeSnafu {
+            tag: tags::CONTOUR_DATA,
+        }
+    );
+    if let Some(count) = int(item, tags::NUMBER_OF_CONTOUR_POINTS)? {
+        let expected = count.max(0) as usize * 3;
+        ensure!(
+            expected == data.len(),
+            InvalidValueCountSnafu {
+                tag: tags::CONTOUR_DATA,
+                expected,
+                found: data.len(),
+            }
+        );
+    }
+    let points = data.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+
+    let images = items(item, tags::CONTOUR_IMAGE_SEQUENCE)
+        .iter()
+        .map(|image| {
+            Ok(ContourImage {
+                sop_class_uid: required_text(image, tags::REFERENCED_SOP_CLASS_UID)?,
+                sop_instance_uid: required_text(image, tags::REFERENCED_SOP_INSTANCE_UID)?,
+                frame: int(image, tags::REFERENCED_FRAME_NUMBER)?.map(|frame| frame as u32),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Contour {
+        geometry: ContourGeometry::from_code(
+            &text(item, tags::CONTOUR_GEOMETRIC_TYPE)?.unwrap_or_default(),
+        ),
+        points,
+        images,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, VR, dicom_value};
+
+    fn sequence(
+        tag: dicom_core::Tag,
+        items: Vec<InMemDicomObject>,
+    ) -> DataElement<InMemDicomObject> {
+        DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+    }
+
+    #[test]
+    fn read_structure_set() {
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::STRUCTURE_SET_LABEL, VR::SH, "Plan 1 "),
+            sequence(
+                tags::STRUCTURE_SET_ROI_SEQUENCE,
+                vec![
+                    InMemDicomObject::from_element_iter([
+                        DataElement::new(tags::ROI_NUMBER, VR::IS, "1"),
+                        DataElement::new(tags::REFERENCED_FRAME_OF_REFERENCE_UID, VR::UI, "2.25.9"),
+                        DataElement::new(tags::ROI_NAME, VR::LO, "PTV"),
+                    ]),
+                    InMemDicomObject::from_element_iter([
+                        DataElement::new(tags::ROI_NUMBER, VR::IS, "2"),
+                        DataElement::new(tags::REFERENCED_FRAME_OF_REFERENCE_UID, VR::UI, "2.25.9"),
+                        DataElement::new(tags::ROI_NAME, VR::LO, "Spinal Cord"),
+                        DataElement::new(tags::ROI_GENERATION_ALGORITHM, VR::CS, "MANUAL"),
+                    ]),
+                ],
+            ),
+            sequence(
+                tags::ROI_CONTOUR_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_ROI_NUMBER, VR::IS, "2"),
+                    DataElement::new(
+                        tags::ROI_DISPLAY_COLOR,
+                        VR::IS,
+                        dicom_value!(Strs, ["255", "128", "0"]),
+                    ),
+                    sequence(
+                        tags::CONTOUR_SEQUENCE,
+                        vec![InMemDicomObject::from_element_iter([
+                            sequence(
+                                tags::CONTOUR_IMAGE_SEQUENCE,
+                                vec![InMemDicomObject::from_element_iter([
+                                    DataElement::new(
+                                        tags::REFERENCED_SOP_CLASS_UID,
+                                        VR::UI,
+                                        "1.2.840.10008.5.1.4.1.1.2",
+                                    ),
+                                    DataElement::new(
+                                        tags::REFERENCED_SOP_INSTANCE_UID,
+                                        VR::UI,
+                                        "2.25.10",
+                                    ),
+                                ])],
+                            ),
+                            DataElement::new(tags::CONTOUR_GEOMETRIC_TYPE, VR::CS, "CLOSED_PLANAR"),
+                            DataElement::new(tags::NUMBER_OF_CONTOUR_POINTS, VR::IS, "3"),
+                            DataElement::new(
+                                tags::CONTOUR_DATA,
+                                VR::DS,
+                                dicom_value!(
+                                    Strs,
+                                    ["0", "0", "-10.5", "10", "0", "-10.5", "10", "10", "-10.5"]
+                                ),
+                            ),
+                        ])],
+                    ),
+                ])],
+            ),
+            sequence(
+                tags::RTROI_OBSERVATIONS_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([
+                    DataElement::new(tags::REFERENCED_ROI_NUMBER, VR::IS, "1"),
+                    DataElement::new(tags::RTROI_INTERPRETED_TYPE, VR::CS, "PTV"),
+                ])],
+            ),
+        ]);
+
+        let structure_set = StructureSet::from_object(&obj).unwrap();
+        assert_eq!(structure_set.label, "Plan 1");
+        assert_eq!(structure_set.rois.len(), 2);
+
+        let ptv = structure_set.roi(1).unwrap();
+        assert_eq!(ptv.interpreted_type.as_deref(), Some("PTV"));
+        assert!(ptv.contours.is_empty());
+
+        let cord = structure_set.roi_by_name("spinal cord").unwrap();
+        assert_eq!(cord.number, 2);
+        assert_eq!(cord.frame_of_reference_uid, "2.25.9");
+        assert_eq!(cord.generation_algorithm.as_deref(), Some("MANUAL"));
+        assert_eq!(cord.display_color, Some([255, 128, 0]));
+        let [contour] = &cord.contours[..] else {
+            panic!("expected a single contour");
+        };
+        assert_eq!(contour.geometry, ContourGeometry::ClosedPlanar);
+        assert_eq!(
+            contour.points,
+            vec![[0., 0., -10.5], [10., 0., -10.5], [10., 10., -10.5]]
+        );
+        assert_eq!(cord.contours_on_image("2.25.10").count(), 1);
+        assert_eq!(cord.contours_on_image("2.25.11").count(), 0);
+    }
+}
@@ -0,0 +1,476 @@
+//! Decoding of Segmentation objects
+use dicom_dictionary_std::tags;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use dicom_pixeldata::ndarray::{Array2, Array3};
+use dicom_pixeldata::packing::unpack_bits;
+use dicom_pixeldata::volume::VolumeGeometry;
+use dicom_pixeldata::{FrameInfo, PixelDecoder};
+use snafu::{OptionExt, ResultExt, ensure};
+
+use crate::{
+    DecodeFrameSnafu, FrameOutsideVolumeSnafu, InvalidAttributeSnafu, MisalignedFrameSnafu,
+    MissingAttributeSnafu, MissingFramePositionSnafu, NotEnoughPixelDataSnafu, ReadGeometrySnafu,
+    Result, Segment, SegmentationType, UnknownSegmentSnafu, UnsupportedBitsAllocatedSnafu, items,
+    required_int, text,
+};
+
+/// The maximum difference between direction cosines
+/// or pixel spacings considered equal
+const GEOMETRY_TOLERANCE: f64 = 1e-3;
+
+/// The maximum distance of a frame to the nearest voxel,
+/// as a fraction of the voxel spacing
+const VOXEL_TOLERANCE: f64 = 1e-2;
+
+/// A frame of a segmentation,
+/// holding the pixels of one segment in one plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentFrame {
+    /// The number of the segment represented in the frame
+    pub segment_number: u16,
+    /// The _Image Position (Patient)_ of the frame, if known
+    pub image_position: Option<[f64; 3]>,
+    /// The _Image Orientation (Patient)_ of the frame, if known
+    pub image_orientation: Option<[f64; 6]>,
+    /// The _Pixel Spacing_ of the frame, if known,
+    /// as the spacing between rows followed by the spacing between columns
+    pub pixel_spacing: Option<[f64; 2]>,
+    /// The SOP instance UIDs of the images which the frame was derived from
+    pub source_images: Vec<String>,
+    /// The pixel values, with the shape `(rows, columns)`:
+    /// 0 or 1 in binary segmentations,
+    /// or up to the maximum fractional value in fractional segmentations
+    pub pixels: Array2<u8>,
+}
+
+/// The segments and frames of a Segmentation object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation {
+    /// How the pixel values represent the segments
+    pub segmentation_type: SegmentationType,
+    /// The _Maximum Fractional Value_, or 1 in binary segmentations
+    pub max_fractional_value: u8,
+    /// The _Frame of Reference UID_ shared with the segmented images, if any
+    pub frame_of_reference_uid: Option<String>,
+    /// The segments of the segmentation
+    pub segments: Vec<Segment>,
+    /// The frames of the segmentation, in the order of the object
+    pub frames: Vec<SegmentFrame>,
+}
+
+impl Segmentation {
+    /// Read the segments of a Segmentation object
+    /// and decode all of its frames.
+    ///
+    /// Binary segmentations must have 1 bit allocated per pixel,
+    /// and fractional segmentations 8 bits.
+    /// Pixel data in encapsulated transfer syntaxes
+    /// is decoded frame by frame.
+    pub fn from_object(obj: &FileDicomObject<InMemDicomObject>) -> Result<Self> {
+        let segmentation_type = SegmentationType::from_object(obj)?;
+        let bits_allocated = required_int(obj, tags::BITS_ALLOCATED)?;
+        let max_fractional_value = if segmentation_type.is_fractional() {
+            ensure!(
+                bits_allocated == 8,
+                UnsupportedBitsAllocatedSnafu { bits_allocated }
+            );
+            u8::try_from(required_int(obj, tags::MAXIMUM_FRACTIONAL_VALUE)?)
+                .ok()
+                .filter(|&value| value > 0)
+                .context(InvalidAttributeSnafu {
+                    tag: tags::MAXIMUM_FRACTIONAL_VALUE,
+                })?
+        } else {
+            ensure!(
+                bits_allocated == 1,
+                UnsupportedBitsAllocatedSnafu { bits_allocated }
+            );
+            1
+        };
+
+        let rows = u32::from(required_int(obj, tags::ROWS)?);
+        let columns = u32::from(required_int(obj, tags::COLUMNS)?);
+        let segments = items(obj, tags::SEGMENT_SEQUENCE)
+            .iter()
+            .map(Segment::from_item)
+            .collect::<Result<_>>()?;
+
+        let infos = FrameInfo::all_from_object(obj)
+            .map_err(dicom_pixeldata::Error::from)
+            .context(ReadGeometrySnafu)?;
+        let number_of_frames = infos.len() as u32;
+
+        let pixel_data = obj.get(tags::PIXEL_DATA).context(MissingAttributeSnafu {
+            tag: tags::PIXEL_DATA,
+        })?;
+        let native = match pixel_data.value().fragments() {
+            Some(_) => None,
+            None => Some(pixel_data.to_bytes().ok().context(InvalidAttributeSnafu {
+                tag: tags::PIXEL_DATA,
+            })?),
+        };
+
+        let per_frame = items(obj, tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE);
+        let shared = items(obj, tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE).first();
+        // the items of a functional group macro for the given frame
+        let group = |frame: usize, tag| {
+            per_frame
+                .get(frame)
+                .and_then(|g| g.get(tag))
+                .or_else(|| shared.and_then(|g| g.get(tag)))
+                .and_then(|e| e.items())
+                .unwrap_or_default()
+        };
+
+        let frame_len = rows as usize * columns as usize;
+        let frames = infos
+            .into_iter()
+            .enumerate()
+            .map(|(frame, info)| {
+                let values = match &native {
+                    Some(bytes) => unpack(bytes, bits_allocated, frame * frame_len, frame_len),
+                    None => {
+                        let decoded = obj.decode_pixel_data_frame(frame as u32).context(
+                            DecodeFrameSnafu {
+                                frame: frame as u32,
+                            },
+                        )?;
+                        unpack(decoded.data(), decoded.bits_allocated(), 0, frame_len)
+                    }
+                }
+                .context(NotEnoughPixelDataSnafu {
+                    frames: number_of_frames,
+                    rows,
+                    columns,
+                })?;
+                let pixels = Array2::from_shape_vec((rows as usize, columns as usize), values)
+                    .expect("frame length should match its shape");
+
+                let segment_number = group(frame, tags::SEGMENT_IDENTIFICATION_SEQUENCE)
+                    .first()
+                    .context(MissingAttributeSnafu {
+                        tag: tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+                    })
+                    .map_err(Into::into)
+                    .and_then(|item| required_int(item, tags::REFERENCED_SEGMENT_NUMBER))?;
+
+                let mut source_images = Vec::new();
+                for derivation in group(frame, tags::DERIVATION_IMAGE_SEQUENCE) {
+                    for source in items(derivation, tags::SOURCE_IMAGE_SEQUENCE) {
+                        source_images.extend(text(source, tags::REFERENCED_SOP_INSTANCE_UID)?);
+                    }
+                }
+
+                Ok(SegmentFrame {
+                    segment_number,
+                    image_position: info.image_position_patient,
+                    image_orientation: info.image_orientation_patient,
+                    pixel_spacing: info.pixel_spacing,
+                    source_images,
+                    pixels,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Segmentation {
+            segmentation_type,
+            max_fractional_value,
+            frame_of_reference_uid: text(obj, tags::FRAME_OF_REFERENCE_UID)?,
+            segments,
+            frames,
+        })
+    }
+
+    /// Look up a segment by its number.
+    pub fn segment(&self, number: u16) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.number == number)
+    }
+
+    /// Build the mask of a single segment
+    /// in a volume of the given geometry and shape `(slices, rows, columns)`,
+    /// usually those of the segmented series.
+    ///
+    /// Each voxel is 0 or 1 in binary segmentations,
+    /// and the fraction of the maximum fractional value otherwise.
+    /// Fails if the segment is not defined,
+    /// or if any of its frames lacks a position,
+    /// is not aligned with the voxels of the volume,
+    /// or lies outside of it.
+    pub fn mask(
+        &self,
+        segment: u16,
+        geometry: &VolumeGeometry,
+        shape: (usize, usize, usize),
+    ) -> Result<Array3<f32>> {
+        ensure!(
+            self.segment(segment).is_some(),
+            UnknownSegmentSnafu { number: segment }
+        );
+        let scale = f32::from(self.max_fractional_value);
+        let mut mask = Array3::zeros(shape);
+        self.place(geometry, shape, Some(segment), |_, index, value| {
+            let value = f32::from(value) / scale;
+            if value > mask[index] {
+                mask[index] = value;
+            }
+        })?;
+        Ok(mask)
+    }
+
+    /// Build a label map of all segments
+    /// in a volume of the given geometry and shape `(slices, rows, columns)`,
+    /// usually those of the segmented series.
+    ///
+    /// Each voxel holds the number of the segment it belongs to,
+    /// or 0 if it belongs to none.
+    /// In fractional segmentations, voxels belong to a segment
+    /// if their value is at least half of the maximum fractional value.
+    /// Where segments overlap, the segment of the last frame prevails.
+    ///
+    /// Fails if any frame lacks a position,
+    /// is not aligned with the voxels of the volume,
+    /// or lies outside of it.
+    pub fn label_map(
+        &self,
+        geometry: &VolumeGeometry,
+        shape: (usize, usize, usize),
+    ) -> Result<Array3<u16>> {
+        let threshold = self.max_fractional_value.div_ceil(2);
+        let mut labels = Array3::zeros(shape);
+        self.place(geometry, shape, None, |segment, index, value| {
+            if value >= threshold {
+                labels[index] = segment;
+            }
+        })?;
+        Ok(labels)
+    }
+
+    /// Visit the non-zero pixels of the frames
+    /// of one or all segments
+    /// with their index in the volume.
+    fn place(
+        &self,
+        geometry: &VolumeGeometry,
+        shape: (usize, usize, usize),
+        segment: Option<u16>,
+        mut visit: impl FnMut(u16, [usize; 3], u8),
+    ) -> Result<()> {
+        let frames = self
+            .frames
+            .iter()
+            .enumerate()
+            .filter(|(_, frame)| segment.is_none_or(|number| frame.segment_number == number));
+        for (index, frame) in frames {
+            let [slice, row, column] = locate(index as u32, frame, geometry, shape)?;
+            for ((r, c), &value) in frame.pixels.indexed_iter() {
+                if value == 0 {
+                    continue;
+                }
+                let (row, column) = (row + r as isize, column + c as isize);
+                if row < 0 || column < 0 || row as usize >= shape.1 || column as usize >= shape.2 {
+                    continue;
+                }
+                visit(
+                    frame.segment_number,
+                    [slice as usize, row as usize, column as usize],
+                    value,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Locate the first pixel of a frame in a volume,
+/// as its slice, row and column indices.
+fn locate(
+    frame: u32,
+    segment_frame: &SegmentFrame,
+    geometry: &VolumeGeometry,
+    shape: (usize, usize, usize),
+) -> Result<[isize; 3]> {
+    let position = segment_frame
+        .image_position
+        .context(MissingFramePositionSnafu { frame })?;
+    if let Some(orientation) = segment_frame.image_orientation {
+        ensure!(
+            approx_eq(&orientation[..3], &geometry.direction[0])
+                && approx_eq(&orientation[3..], &geometry.direction[1]),
+            MisalignedFrameSnafu { frame }
+        );
+    }
+    if let Some([row_spacing, column_spacing]) = segment_frame.pixel_spacing {
+        ensure!(
+            approx_eq(&[column_spacing, row_spacing], &geometry.spacing[..2]),
+            MisalignedFrameSnafu { frame }
+        );
+    }
+
+    let offset = [0, 1, 2].map(|i| position[i] - geometry.origin[i]);
+    // the indices along columns, rows and slices
+    let mut indices = [0; 3];
+    for (axis, index) in indices.iter_mut().enumerate() {
+        let direction = &geometry.direction[axis];
+        let value =
+            (offset[0] * direction[0] + offset[1] * direction[1] + offset[2] * direction[2])
+                / geometry.spacing[axis];
+        ensure!(
+            (value - value.round()).abs() <= VOXEL_TOLERANCE,
+            MisalignedFrameSnafu { frame }
+        );
+        *index = value.round() as isize;
+    }
+    let [column, row, slice] = indices;
+    ensure!(
+        slice >= 0 && (slice as usize) < shape.0,
+        FrameOutsideVolumeSnafu { frame }
+    );
+    Ok([slice, row, column])
+}
+
+/// Extract `len` pixel values starting at pixel `offset`,
+/// or `None` if the data is too short.
+fn unpack(data: &[u8], bits_allocated: u16, offset: usize, len: usize) -> Option<Vec<u8>> {
+    if bits_allocated == 1 {
+        // pixels are packed without padding between frames
+        unpack_bits(data, offset, len)
+    } else {
+        data.get(offset..offset + len).map(<[u8]>::to_vec)
+    }
+}
+
+fn approx_eq(a: &[f64], b: &[f64]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(a, b)| (a - b).abs() <= GEOMETRY_TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value};
+    use dicom_dictionary_std::uids;
+    use dicom_object::FileMetaTableBuilder;
+    use dicom_object::code::sct;
+
+    fn sequence(
+        tag: dicom_core::Tag,
+        items: Vec<InMemDicomObject>,
+    ) -> dicom_object::mem::InMemElement {
+        DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+    }
+
+    fn fractional_seg(positions: [&str; 2]) -> FileDicomObject<InMemDicomObject> {
+        let segment = Segment::new(1, "Liver", sct::ANATOMICAL_STRUCTURE, sct::LIVER);
+        let frame = |position: &str| {
+            InMemDicomObject::from_element_iter([
+                sequence(
+                    tags::PLANE_POSITION_SEQUENCE,
+                    vec![InMemDicomObject::from_element_iter([DataElement::new(
+                        tags::IMAGE_POSITION_PATIENT,
+                        VR::DS,
+                        dicom_value!(Strs, ["0", "0", position]),
+                    )])],
+                ),
+                sequence(
+                    tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+                    vec![InMemDicomObject::from_element_iter([DataElement::new(
+                        tags::REFERENCED_SEGMENT_NUMBER,
+                        VR::US,
+                        PrimitiveValue::from(1_u16),
+                    )])],
+                ),
+            ])
+        };
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                PrimitiveValue::from(uids::SEGMENTATION_STORAGE),
+            ),
+            DataElement::new(
+                tags::SOP_INSTANCE_UID,
+                VR::UI,
+                PrimitiveValue::from("2.25.1"),
+            ),
+            DataElement::new(
+                tags::SEGMENTATION_TYPE,
+                VR::CS,
+                PrimitiveValue::from("FRACTIONAL"),
+            ),
+            DataElement::new(
+                tags::SEGMENTATION_FRACTIONAL_TYPE,
+                VR::CS,
+                PrimitiveValue::from("PROBABILITY"),
+            ),
+            us(tags::MAXIMUM_FRACTIONAL_VALUE, 200),
+            us(tags::ROWS, 2),
+            us(tags::COLUMNS, 3),
+            us(tags::BITS_ALLOCATED, 8),
+            DataElement::new(tags::NUMBER_OF_FRAMES, VR::IS, PrimitiveValue::from("2")),
+            sequence(tags::SEGMENT_SEQUENCE, vec![segment.to_item()]),
+            sequence(
+                tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE,
+                vec![InMemDicomObject::from_element_iter([sequence(
+                    tags::PLANE_ORIENTATION_SEQUENCE,
+                    vec![InMemDicomObject::from_element_iter([DataElement::new(
+                        tags::IMAGE_ORIENTATION_PATIENT,
+                        VR::DS,
+                        dicom_value!(Strs, ["1", "0", "0", "0", "1", "0"]),
+                    )])],
+                )])],
+            ),
+            sequence(
+                tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE,
+                positions.into_iter().map(frame).collect(),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0_u8, 50, 100, 150, 200, 0, 200, 0, 0, 0, 0, 0]),
+            ),
+        ])
+        .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+        .unwrap()
+    }
+
+    #[test]
+    fn decode_fractional_segmentation() {
+        let geometry = VolumeGeometry {
+            origin: [0., 0., 0.],
+            spacing: [1., 1., 2.],
+            direction: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        };
+
+        let seg = Segmentation::from_object(&fractional_seg(["2", "4"])).unwrap();
+        assert_eq!(seg.segmentation_type, SegmentationType::Probability);
+        assert_eq!(seg.segments[0].property_type, sct::LIVER);
+        assert_eq!(seg.frames.len(), 2);
+        assert_eq!(seg.frames[0].pixels[[1, 0]], 150);
+
+        let mask = seg.mask(1, &geometry, (3, 2, 3)).unwrap();
+        assert_eq!(mask[[0, 0, 1]], 0.);
+        assert_eq!(mask[[1, 0, 1]], 0.25);
+        assert_eq!(mask[[1, 1, 1]], 1.);
+        assert_eq!(mask[[2, 0, 0]], 1.);
+        assert!(seg.mask(2, &geometry, (3, 2, 3)).is_err());
+
+        let labels = seg.label_map(&geometry, (3, 2, 3)).unwrap();
+        assert_eq!(
+            labels.iter().filter(|&&label| label == 1).count(),
+            // the values of 100, 150, 200 and 200
+            4
+        );
+        assert_eq!(labels[[1, 0, 1]], 0);
+
+        // frames must lie on the slices of the volume
+        let seg = Segmentation::from_object(&fractional_seg(["2", "5"])).unwrap();
+        assert!(seg.label_map(&geometry, (3, 2, 3)).is_err());
+        let seg = Segmentation::from_object(&fractional_seg(["2", "6"])).unwrap();
+        assert!(seg.label_map(&geometry, (3, 2, 3)).is_err());
+    }
+}
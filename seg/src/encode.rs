@@ -0,0 +1,707 @@
+//! Encoding of label maps into Segmentation objects
+use dicom_core::value::DataSetSequence;
+use dicom_core::value::decimal::DecimalString;
+use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR, chrono, uid::UidGenerator};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::code::dcm;
+use dicom_object::mem::InMemElement;
+use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+use dicom_pixeldata::ndarray::{Array3, Axis};
+use dicom_pixeldata::packing::pack_bits;
+use dicom_pixeldata::volume::VolumeGeometry;
+use snafu::{ResultExt, ensure};
+
+use crate::{
+    CreateMetaSnafu, EmptyLabelMapSnafu, InvalidGeometrySnafu, Result, Segment,
+    SourceImageCountSnafu, UnknownSegmentSnafu, UnsupportedDimensionsSnafu, required_text,
+};
+
+/// The attributes of the _Patient_ and _General Study_ modules
+/// (plus the character set they are encoded in)
+/// which are copied from a template object.
+const CONTEXT_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::ISSUER_OF_PATIENT_ID,
+    tags::PATIENT_BIRTH_DATE,
+    tags::PATIENT_SEX,
+    tags::PATIENT_AGE,
+    tags::STUDY_INSTANCE_UID,
+    tags::STUDY_DATE,
+    tags::STUDY_TIME,
+    tags::REFERRING_PHYSICIAN_NAME,
+    tags::STUDY_ID,
+    tags::ACCESSION_NUMBER,
+    tags::STUDY_DESCRIPTION,
+    tags::FRAME_OF_REFERENCE_UID,
+    tags::POSITION_REFERENCE_INDICATOR,
+];
+
+/// The type 2 attributes of the Segmentation IOD,
+/// which are added empty if not known.
+const TYPE2_TAGS: &[(Tag, VR)] = &[
+    (tags::PATIENT_NAME, VR::PN),
+    (tags::PATIENT_ID, VR::LO),
+    (tags::PATIENT_BIRTH_DATE, VR::DA),
+    (tags::PATIENT_SEX, VR::CS),
+    (tags::STUDY_DATE, VR::DA),
+    (tags::STUDY_TIME, VR::TM),
+    (tags::REFERRING_PHYSICIAN_NAME, VR::PN),
+    (tags::STUDY_ID, VR::SH),
+    (tags::ACCESSION_NUMBER, VR::SH),
+    (tags::POSITION_REFERENCE_INDICATOR, VR::LO),
+    (tags::CONTENT_DESCRIPTION, VR::LO),
+    (tags::CONTENT_CREATOR_NAME, VR::PN),
+];
+
+/// An image of the segmented series,
+/// which the frames of one slice of a segmentation are derived from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceImage {
+    /// The SOP class UID of the image
+    pub sop_class_uid: String,
+    /// The SOP instance UID of the image
+    pub sop_instance_uid: String,
+}
+
+impl SourceImage {
+    /// Create a reference to a source image by its UIDs.
+    pub fn new(sop_class_uid: impl Into<String>, sop_instance_uid: impl Into<String>) -> Self {
+        SourceImage {
+            sop_class_uid: sop_class_uid.into(),
+            sop_instance_uid: sop_instance_uid.into(),
+        }
+    }
+
+    /// Create a reference to the given image.
+    pub fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        Ok(SourceImage {
+            sop_class_uid: required_text(obj, tags::SOP_CLASS_UID)?,
+            sop_instance_uid: required_text(obj, tags::SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// The equipment which created the segmentation.
+#[derive(Debug, Clone, PartialEq)]
+struct Equipment {
+    manufacturer: String,
+    model_name: String,
+    serial_number: String,
+    software_versions: String,
+}
+
+impl Default for Equipment {
+    fn default() -> Self {
+        Equipment {
+            manufacturer: "DICOM-rs".to_string(),
+            model_name: "dicom-seg".to_string(),
+            serial_number: "1".to_string(),
+            software_versions: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// A builder for a new binary Segmentation object
+/// from a label map.
+///
+/// Each voxel of the label map holds the number of the segment it belongs to,
+/// or 0 if it belongs to none.
+/// The object has one frame for every segment and slice
+/// with at least one voxel of the segment,
+/// placed according to the geometry of the label map.
+///
+/// # Example
+///
+/// ```no_run
+/// use dicom_object::code::sct;
+/// use dicom_object::open_file;
+/// use dicom_pixeldata::volume::{Volume, assemble_volume};
+/// use dicom_seg::{AlgorithmType, Segment, SegmentationBuilder, SourceImage};
+///
+/// let ct: Vec<_> = ["ct1.dcm", "ct2.dcm"]
+///     .into_iter()
+///     .map(open_file)
+///     .collect::<Result<_, _>>()?;
+/// let volume: Volume<i16> = assemble_volume(&ct)?;
+/// // a very simple segmentation algorithm
+/// let labels = volume.voxels.mapv(|v| if v > 200 { 1 } else { 0 });
+///
+/// let seg = SegmentationBuilder::new(labels, volume.geometry)
+///     .with_template(&ct[0])
+///     .with_source_images(
+///         ct.iter()
+///             .map(|obj| SourceImage::from_object(obj))
+///             .collect::<Result<Vec<_>, _>>()?,
+///     )
+///     .with_segment(
+///         Segment::new(1, "Bone", sct::TISSUE, sct::ANATOMICAL_STRUCTURE)
+///             .with_algorithm(AlgorithmType::Automatic, "Threshold"),
+///     )
+///     .build()?;
+/// seg.write_to_file("seg.dcm")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Note that the source images must be given in the order of the slices,
+/// which is not necessarily the order of the files.
+#[derive(Debug, Clone)]
+pub struct SegmentationBuilder {
+    label_map: Array3<u16>,
+    geometry: VolumeGeometry,
+    segments: Vec<Segment>,
+    source_images: Vec<SourceImage>,
+    referenced_series_instance_uid: Option<String>,
+    content_label: Option<String>,
+    series_description: Option<String>,
+    equipment: Equipment,
+    sop_instance_uid: Option<String>,
+    series_instance_uid: Option<String>,
+    study_instance_uid: Option<String>,
+    uid_generator: UidGenerator,
+    context: Vec<InMemElement>,
+}
+
+impl SegmentationBuilder {
+    /// Create a new segmentation builder
+    /// for a label map with the shape `(slices, rows, columns)`
+    /// and the given geometry.
+    pub fn new(label_map: Array3<u16>, geometry: VolumeGeometry) -> Self {
+        SegmentationBuilder {
+            label_map,
+            geometry,
+            segments: Vec::new(),
+            source_images: Vec::new(),
+            referenced_series_instance_uid: None,
+            content_label: None,
+            series_description: None,
+            equipment: Equipment::default(),
+            sop_instance_uid: None,
+            series_instance_uid: None,
+            study_instance_uid: None,
+            uid_generator: UidGenerator::default(),
+            context: Vec::new(),
+        }
+    }
+
+    /// Copy the patient, study and frame of reference attributes
+    /// (_Patient Name_, _Study Instance UID_, _Frame of Reference UID_,
+    /// and so on)
+    /// from the given DICOM object,
+    /// usually one of the segmented images,
+    /// so that the segmentation belongs to the same study
+    /// and shares its frame of reference.
+    ///
+    /// The series of the template is also recorded
+    /// as the series of the source images.
+    pub fn with_template(mut self, template: &InMemDicomObject) -> Self {
+        self.context = CONTEXT_TAGS
+            .iter()
+            .filter_map(|tag| template.get(*tag).cloned())
+            .collect();
+        self.referenced_series_instance_uid = template
+            .get(tags::SERIES_INSTANCE_UID)
+            .and_then(|e| e.to_str().ok())
+            .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string());
+        self
+    }
+
+    /// Add the definition of a segment.
+    ///
+    /// Every non-zero value of the label map must have a segment.
+    pub fn with_segment(mut self, segment: Segment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Set the images which the segmentation is derived from,
+    /// one for each slice of the label map, in slice order.
+    pub fn with_source_images(mut self, images: impl IntoIterator<Item = SourceImage>) -> Self {
+        self.source_images = images.into_iter().collect();
+        self
+    }
+
+    /// Set the _Content Label_ of the segmentation
+    /// (`SEGMENTATION` by default).
+    pub fn with_content_label(mut self, label: impl Into<String>) -> Self {
+        self.content_label = Some(label.into());
+        self
+    }
+
+    /// Set the _Series Description_ of the segmentation.
+    pub fn with_series_description(mut self, description: impl Into<String>) -> Self {
+        self.series_description = Some(description.into());
+        self
+    }
+
+    /// Describe the equipment which created the segmentation,
+    /// such as the software running a segmentation model
+    /// (DICOM-rs by default).
+    pub fn with_equipment(
+        mut self,
+        manufacturer: impl Into<String>,
+        model_name: impl Into<String>,
+        serial_number: impl Into<String>,
+        software_versions: impl Into<String>,
+    ) -> Self {
+        self.equipment = Equipment {
+            manufacturer: manufacturer.into(),
+            model_name: model_name.into(),
+            serial_number: serial_number.into(),
+            software_versions: software_versions.into(),
+        };
+        self
+    }
+
+    /// Set the SOP instance UID of the segmentation.
+    pub fn with_sop_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.sop_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the series instance UID of the segmentation.
+    pub fn with_series_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.series_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the study instance UID of the segmentation,
+    /// overriding the one in the template.
+    pub fn with_study_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.study_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the generator of the UIDs which are not given,
+    /// such as one under an organization root.
+    pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+        self.uid_generator = uid_generator;
+        self
+    }
+
+    /// Create the Segmentation object,
+    /// encoded in Explicit VR Little Endian.
+    ///
+    /// Fails if the label map holds a segment which was not defined,
+    /// if it has no segmented voxels,
+    /// or if the number of source images does not match its slices.
+    pub fn build(&self) -> Result<DefaultDicomObject> {
+        let (slices, rows, columns) = self.label_map.dim();
+        ensure!(
+            rows <= u16::MAX as usize && columns <= u16::MAX as usize,
+            UnsupportedDimensionsSnafu { rows, columns }
+        );
+        ensure!(
+            self.source_images.is_empty() || self.source_images.len() == slices,
+            SourceImageCountSnafu {
+                expected: slices,
+                found: self.source_images.len(),
+            }
+        );
+        if let Some(&number) = self
+            .label_map
+            .iter()
+            .find(|&&label| label != 0 && self.segments.iter().all(|s| s.number != label))
+        {
+            return UnknownSegmentSnafu { number }.fail().map_err(Into::into);
+        }
+
+        // one frame per segment and slice with any voxel of the segment
+        let frames: Vec<(&Segment, usize)> = self
+            .segments
+            .iter()
+            .flat_map(|segment| {
+                self.label_map
+                    .axis_iter(Axis(0))
+                    .enumerate()
+                    .filter(|(_, plane)| plane.iter().any(|&label| label == segment.number))
+                    .map(move |(slice, _)| (segment, slice))
+            })
+            .collect();
+        ensure!(!frames.is_empty(), EmptyLabelMapSnafu);
+
+        // one bit per pixel, without padding between frames
+        let pixel_data = pack_bits(frames.iter().flat_map(|(segment, slice)| {
+            self.label_map
+                .index_axis(Axis(0), *slice)
+                .into_iter()
+                .map(move |&label| label == segment.number)
+        }));
+
+        let mut obj = InMemDicomObject::new_empty();
+        for elem in &self.context {
+            obj.put(elem.clone());
+        }
+        for (tag, vr) in TYPE2_TAGS {
+            if obj.get(*tag).is_none() {
+                obj.put(DataElement::new(*tag, *vr, PrimitiveValue::Empty));
+            }
+        }
+
+        let existing_uid = |obj: &InMemDicomObject, tag| {
+            obj.get(tag)
+                .and_then(|e| e.to_str().ok())
+                .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string())
+                .filter(|uid| !uid.is_empty())
+        };
+        let study_instance_uid = match &self.study_instance_uid {
+            Some(uid) => uid.clone(),
+            None => existing_uid(&obj, tags::STUDY_INSTANCE_UID)
+                .unwrap_or_else(|| self.uid_generator.generate()),
+        };
+        let frame_of_reference_uid = existing_uid(&obj, tags::FRAME_OF_REFERENCE_UID)
+            .unwrap_or_else(|| self.uid_generator.generate());
+        let series_instance_uid = self
+            .series_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+        let sop_instance_uid = self
+            .sop_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+        let dimension_organization_uid = self.uid_generator.generate();
+
+        let now = chrono::Local::now();
+        let date = now.format("%Y%m%d").to_string();
+        let time = now.format("%H%M%S").to_string();
+
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        let item = |elements: Vec<InMemElement>| InMemDicomObject::from_element_iter(elements);
+
+        // the shared geometry of all frames
+        let spacing = self.geometry.spacing;
+        let [row_direction, column_direction, slice_direction] = self.geometry.direction;
+        let shared = item(vec![
+            sequence(
+                tags::PIXEL_MEASURES_SEQUENCE,
+                vec![item(vec![
+                    DataElement::new(tags::PIXEL_SPACING, VR::DS, ds(&[spacing[1], spacing[0]])?),
+                    DataElement::new(tags::SLICE_THICKNESS, VR::DS, ds(&[spacing[2]])?),
+                    DataElement::new(tags::SPACING_BETWEEN_SLICES, VR::DS, ds(&[spacing[2]])?),
+                ])],
+            ),
+            sequence(
+                tags::PLANE_ORIENTATION_SEQUENCE,
+                vec![item(vec![DataElement::new(
+                    tags::IMAGE_ORIENTATION_PATIENT,
+                    VR::DS,
+                    ds(&[
+                        row_direction[0],
+                        row_direction[1],
+                        row_direction[2],
+                        column_direction[0],
+                        column_direction[1],
+                        column_direction[2],
+                    ])?,
+                )])],
+            ),
+        ]);
+
+        let per_frame = frames
+            .iter()
+            .map(|&(segment, slice)| {
+                let offset = slice as f64 * spacing[2];
+                let position =
+                    [0, 1, 2].map(|i| self.geometry.origin[i] + offset * slice_direction[i]);
+                let mut group = item(vec![
+                    sequence(
+                        tags::FRAME_CONTENT_SEQUENCE,
+                        vec![item(vec![DataElement::new(
+                            tags::DIMENSION_INDEX_VALUES,
+                            VR::UL,
+                            PrimitiveValue::U32(
+                                [u32::from(segment.number), slice as u32 + 1].into(),
+                            ),
+                        )])],
+                    ),
+                    sequence(
+                        tags::PLANE_POSITION_SEQUENCE,
+                        vec![item(vec![DataElement::new(
+                            tags::IMAGE_POSITION_PATIENT,
+                            VR::DS,
+                            ds(&position)?,
+                        )])],
+                    ),
+                    sequence(
+                        tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+                        vec![item(vec![us(
+                            tags::REFERENCED_SEGMENT_NUMBER,
+                            segment.number,
+                        )])],
+                    ),
+                ]);
+                if let Some(source) = self.source_images.get(slice) {
+                    group.put(sequence(
+                        tags::DERIVATION_IMAGE_SEQUENCE,
+                        vec![item(vec![
+                            dcm::SEGMENTATION.to_sequence_element(tags::DERIVATION_CODE_SEQUENCE),
+                            sequence(
+                                tags::SOURCE_IMAGE_SEQUENCE,
+                                vec![item(vec![
+                                    str_elem(
+                                        tags::REFERENCED_SOP_CLASS_UID,
+                                        VR::UI,
+                                        &source.sop_class_uid,
+                                    ),
+                                    str_elem(
+                                        tags::REFERENCED_SOP_INSTANCE_UID,
+                                        VR::UI,
+                                        &source.sop_instance_uid,
+                                    ),
+                                    dcm::SOURCE_IMAGE_FOR_IMAGE_PROCESSING_OPERATION
+                                        .to_sequence_element(
+                                            tags::PURPOSE_OF_REFERENCE_CODE_SEQUENCE,
+                                        ),
+                                ])],
+                            ),
+                        ])],
+                    ));
+                }
+                Ok(group)
+            })
+            .collect::<Result<_>>()?;
+
+        // frames are indexed by segment, then by position
+        let dimension = |pointer: Tag, group: Tag, label: &str| {
+            item(vec![
+                str_elem(
+                    tags::DIMENSION_ORGANIZATION_UID,
+                    VR::UI,
+                    &dimension_organization_uid,
+                ),
+                DataElement::new(
+                    tags::DIMENSION_INDEX_POINTER,
+                    VR::AT,
+                    PrimitiveValue::from(pointer),
+                ),
+                DataElement::new(
+                    tags::FUNCTIONAL_GROUP_POINTER,
+                    VR::AT,
+                    PrimitiveValue::from(group),
+                ),
+                str_elem(tags::DIMENSION_DESCRIPTION_LABEL, VR::LO, label),
+            ])
+        };
+
+        for elem in [
+            // SOP Common
+            str_elem(tags::SOP_CLASS_UID, VR::UI, uids::SEGMENTATION_STORAGE),
+            str_elem(tags::SOP_INSTANCE_UID, VR::UI, &sop_instance_uid),
+            str_elem(tags::INSTANCE_CREATION_DATE, VR::DA, &date),
+            str_elem(tags::INSTANCE_CREATION_TIME, VR::TM, &time),
+            // General Study
+            str_elem(tags::STUDY_INSTANCE_UID, VR::UI, &study_instance_uid),
+            // Segmentation Series
+            str_elem(tags::MODALITY, VR::CS, "SEG"),
+            str_elem(tags::SERIES_INSTANCE_UID, VR::UI, &series_instance_uid),
+            str_elem(tags::SERIES_NUMBER, VR::IS, "1"),
+            // Frame of Reference
+            str_elem(
+                tags::FRAME_OF_REFERENCE_UID,
+                VR::UI,
+                &frame_of_reference_uid,
+            ),
+            // Enhanced General Equipment
+            str_elem(tags::MANUFACTURER, VR::LO, &self.equipment.manufacturer),
+            str_elem(
+                tags::MANUFACTURER_MODEL_NAME,
+                VR::LO,
+                &self.equipment.model_name,
+            ),
+            str_elem(
+                tags::DEVICE_SERIAL_NUMBER,
+                VR::LO,
+                &self.equipment.serial_number,
+            ),
+            str_elem(
+                tags::SOFTWARE_VERSIONS,
+                VR::LO,
+                &self.equipment.software_versions,
+            ),
+            // Segmentation Image
+            str_elem(tags::INSTANCE_NUMBER, VR::IS, "1"),
+            str_elem(tags::CONTENT_DATE, VR::DA, &date),
+            str_elem(tags::CONTENT_TIME, VR::TM, &time),
+            str_elem(tags::IMAGE_TYPE, VR::CS, "DERIVED\\PRIMARY"),
+            str_elem(
+                tags::CONTENT_LABEL,
+                VR::CS,
+                self.content_label.as_deref().unwrap_or("SEGMENTATION"),
+            ),
+            str_elem(tags::SEGMENTATION_TYPE, VR::CS, "BINARY"),
+            str_elem(tags::LOSSY_IMAGE_COMPRESSION, VR::CS, "00"),
+            sequence(
+                tags::SEGMENT_SEQUENCE,
+                self.segments.iter().map(Segment::to_item).collect(),
+            ),
+            // Multi-frame Functional Groups
+            str_elem(tags::NUMBER_OF_FRAMES, VR::IS, &frames.len().to_string()),
+            sequence(tags::SHARED_FUNCTIONAL_GROUPS_SEQUENCE, vec![shared]),
+            sequence(tags::PER_FRAME_FUNCTIONAL_GROUPS_SEQUENCE, per_frame),
+            // Multi-frame Dimension
+            sequence(
+                tags::DIMENSION_ORGANIZATION_SEQUENCE,
+                vec![item(vec![str_elem(
+                    tags::DIMENSION_ORGANIZATION_UID,
+                    VR::UI,
+                    &dimension_organization_uid,
+                )])],
+            ),
+            sequence(
+                tags::DIMENSION_INDEX_SEQUENCE,
+                vec![
+                    dimension(
+                        tags::REFERENCED_SEGMENT_NUMBER,
+                        tags::SEGMENT_IDENTIFICATION_SEQUENCE,
+                        "ReferencedSegmentNumber",
+                    ),
+                    dimension(
+                        tags::IMAGE_POSITION_PATIENT,
+                        tags::PLANE_POSITION_SEQUENCE,
+                        "ImagePositionPatient",
+                    ),
+                ],
+            ),
+            // Image Pixel
+            us(tags::SAMPLES_PER_PIXEL, 1),
+            str_elem(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            us(tags::ROWS, rows as u16),
+            us(tags::COLUMNS, columns as u16),
+            us(tags::BITS_ALLOCATED, 1),
+            us(tags::BITS_STORED, 1),
+            us(tags::HIGH_BIT, 0),
+            us(tags::PIXEL_REPRESENTATION, 0),
+            DataElement::new(tags::PIXEL_DATA, VR::OB, PrimitiveValue::from(pixel_data)),
+        ] {
+            obj.put(elem);
+        }
+        if let Some(description) = &self.series_description {
+            obj.put(str_elem(tags::SERIES_DESCRIPTION, VR::LO, description));
+        }
+
+        // Common Instance Reference
+        if let (Some(series_uid), false) = (
+            &self.referenced_series_instance_uid,
+            self.source_images.is_empty(),
+        ) {
+            let mut instances: Vec<&SourceImage> = Vec::new();
+            for image in &self.source_images {
+                if !instances.contains(&image) {
+                    instances.push(image);
+                }
+            }
+            obj.put(sequence(
+                tags::REFERENCED_SERIES_SEQUENCE,
+                vec![item(vec![
+                    str_elem(tags::SERIES_INSTANCE_UID, VR::UI, series_uid),
+                    sequence(
+                        tags::REFERENCED_INSTANCE_SEQUENCE,
+                        instances
+                            .into_iter()
+                            .map(|image| {
+                                item(vec![
+                                    str_elem(
+                                        tags::REFERENCED_SOP_CLASS_UID,
+                                        VR::UI,
+                                        &image.sop_class_uid,
+                                    ),
+                                    str_elem(
+                                        tags::REFERENCED_SOP_INSTANCE_UID,
+                                        VR::UI,
+                                        &image.sop_instance_uid,
+                                    ),
+                                ])
+                            })
+                            .collect(),
+                    ),
+                ])],
+            ));
+        }
+
+        Ok(obj
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .context(CreateMetaSnafu)?)
+    }
+}
+
+/// Create a multi-valued decimal string.
+fn ds(values: &[f64]) -> Result<PrimitiveValue> {
+    let values = values
+        .iter()
+        .map(|&value| DecimalString::from_f64(value).map(DecimalString::into_string))
+        .collect::<Result<_, _>>()
+        .context(InvalidGeometrySnafu)?;
+    Ok(PrimitiveValue::Strs(values))
+}
+
+fn sequence(tag: Tag, items: Vec<InMemDicomObject>) -> InMemElement {
+    DataElement::new(tag, VR::SQ, DataSetSequence::new(items, Length::UNDEFINED))
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_object::code::sct;
+    use dicom_pixeldata::ndarray::Array3;
+    use dicom_pixeldata::volume::VolumeGeometry;
+
+    use crate::{AlgorithmType, Segment, Segmentation, SegmentationBuilder, SourceImage};
+
+    #[test]
+    fn label_map_round_trip() {
+        let geometry = VolumeGeometry {
+            origin: [-10., 20., 5.],
+            spacing: [0.5, 0.5, 2.5],
+            direction: [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        };
+        let mut labels = Array3::zeros((4, 3, 5));
+        labels[[0, 0, 0]] = 1;
+        labels[[1, 1, 2]] = 1;
+        labels[[1, 2, 4]] = 2;
+        labels[[3, 2, 0]] = 2;
+        labels[[3, 0, 4]] = 1;
+
+        let images: Vec<_> = (1..=4)
+            .map(|i| SourceImage::new("1.2.840.10008.5.1.4.1.1.2", format!("2.25.{i}")))
+            .collect();
+        let builder = SegmentationBuilder::new(labels.clone(), geometry)
+            .with_source_images(images)
+            .with_segment(
+                Segment::new(1, "Liver", sct::ANATOMICAL_STRUCTURE, sct::LIVER)
+                    .with_algorithm(AlgorithmType::Automatic, "Model"),
+            )
+            .with_segment(
+                Segment::new(
+                    2,
+                    "Nodule",
+                    sct::MORPHOLOGICALLY_ALTERED_STRUCTURE,
+                    sct::NODULE,
+                )
+                .with_display_color([65535, 32768, 32768]),
+            );
+        let obj = builder.build().unwrap();
+
+        let seg = Segmentation::from_object(&obj).unwrap();
+        // liver in slices 1, 2 and 4, nodule in slices 2 and 4
+        assert_eq!(seg.frames.len(), 5);
+        assert_eq!(seg.frames[2].source_images, vec!["2.25.4".to_string()]);
+        assert_eq!(seg.segments[0].algorithm_name.as_deref(), Some("Model"));
+        assert_eq!(seg.segments[1].display_color, Some([65535, 32768, 32768]));
+        assert_eq!(seg.label_map(&geometry, labels.dim()).unwrap(), labels);
+        let mask = seg.mask(2, &geometry, labels.dim()).unwrap();
+        assert_eq!(mask.sum(), 2.);
+
+        // labels must have a segment
+        let builder = SegmentationBuilder::new(labels.clone(), geometry).with_segment(
+            Segment::new(1, "Liver", sct::ANATOMICAL_STRUCTURE, sct::LIVER),
+        );
+        assert!(builder.build().is_err());
+
+        // source images must match the slices
+        let builder = SegmentationBuilder::new(labels, geometry)
+            .with_source_images([SourceImage::new("1.2.840.10008.5.1.4.1.1.2", "2.25.1")]);
+        assert!(builder.build().is_err());
+    }
+}
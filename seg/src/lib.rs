@@ -0,0 +1,168 @@
+//! DICOM Segmentation objects
+//!
+//! This crate supports the [Segmentation IOD][1] (`SEG`),
+//! commonly used to exchange the output of segmentation algorithms:
+//!
+//! - [`Segmentation`] reads the segments of a Segmentation object
+//!   and decodes their binary or fractional frames.
+//!   The frames can then be placed in the geometry of the segmented series,
+//!   either as a mask of a single segment
+//!   ([`Segmentation::mask`])
+//!   or as a label map of all segments
+//!   ([`Segmentation::label_map`]).
+//! - [`SegmentationBuilder`] encodes a label map
+//!   into a new binary Segmentation object,
+//!   with per-frame functional groups
+//!   describing the segment and position of each frame.
+//!
+//! Volumes are indexed by slice, row and column,
+//! with the position and orientation of the voxels
+//! given by a [`VolumeGeometry`],
+//! such as the one obtained with [`assemble_volume`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_pixeldata::volume::{Volume, assemble_volume};
+//! use dicom_seg::Segmentation;
+//!
+//! let ct: Vec<_> = ["ct1.dcm", "ct2.dcm", "ct3.dcm"]
+//!     .into_iter()
+//!     .map(open_file)
+//!     .collect::<Result<_, _>>()?;
+//! let volume: Volume<i16> = assemble_volume(&ct)?;
+//!
+//! let seg = Segmentation::from_object(&open_file("seg.dcm")?)?;
+//! let labels = seg.label_map(&volume.geometry, volume.voxels.dim())?;
+//! for segment in &seg.segments {
+//!     let voxels = labels.iter().filter(|&&l| l == segment.number).count();
+//!     println!("{}: {} voxels", segment.label, voxels);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_A.51.html
+//! [`VolumeGeometry`]: dicom_pixeldata::volume::VolumeGeometry
+//! [`assemble_volume`]: dicom_pixeldata::volume::assemble_volume
+use dicom_core::Tag;
+use dicom_core::header::HasLength;
+use dicom_object::InMemDicomObject;
+use dicom_object::code::CodeError;
+use dicom_object::mem::InMemElement;
+use snafu::{OptionExt, Snafu};
+
+mod decode;
+mod encode;
+mod segment;
+
+pub use decode::{SegmentFrame, Segmentation};
+pub use encode::{SegmentationBuilder, SourceImage};
+pub use segment::{AlgorithmType, Segment, SegmentationType};
+
+/// An error which may occur when decoding or encoding a Segmentation object.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for Segmentation objects
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Missing attribute {tag}
+    MissingAttribute { tag: Tag },
+    /// Invalid value of attribute {tag}
+    InvalidAttribute { tag: Tag },
+    #[snafu(display("Invalid code in {tag}"))]
+    InvalidCode { tag: Tag, source: CodeError },
+    /// Unsupported segmentation type `{value}`
+    UnsupportedSegmentationType { value: String },
+    /// Unsupported Bits Allocated {bits_allocated} for this segmentation type
+    UnsupportedBitsAllocated { bits_allocated: u16 },
+    /// Could not read the geometry of the frames
+    ReadGeometry {
+        #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
+        source: Box<dicom_pixeldata::Error>,
+    },
+    /// Could not decode frame #{frame}
+    DecodeFrame {
+        frame: u32,
+        #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
+        source: Box<dicom_pixeldata::Error>,
+    },
+    /// Pixel data too short for {frames} frames of {rows}x{columns}
+    NotEnoughPixelData {
+        frames: u32,
+        rows: u32,
+        columns: u32,
+    },
+    /// Unknown segment #{number}
+    UnknownSegment { number: u16 },
+    /// Missing plane position of frame #{frame}
+    MissingFramePosition { frame: u32 },
+    /// Frame #{frame} is not aligned with the volume
+    MisalignedFrame { frame: u32 },
+    /// Frame #{frame} is outside of the volume
+    FrameOutsideVolume { frame: u32 },
+    /// Label map has no segmented voxels
+    EmptyLabelMap,
+    #[snafu(display("Label map size {rows}x{columns} is too large"))]
+    UnsupportedDimensions { rows: usize, columns: usize },
+    #[snafu(display("Expected {expected} source images, one per slice, found {found}"))]
+    SourceImageCount { expected: usize, found: usize },
+    /// Volume geometry is not finite
+    InvalidGeometry {
+        source: dicom_core::value::decimal::Error,
+    },
+    /// Could not create the file meta group
+    CreateMeta {
+        #[snafu(source(from(dicom_object::WithMetaError, Box::new)))]
+        source: Box<dicom_object::WithMetaError>,
+    },
+}
+
+/// Alias for the result of decoding or encoding a Segmentation object.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Retrieve the attribute with the given tag,
+/// if present and not empty.
+fn non_empty(obj: &InMemDicomObject, tag: Tag) -> Option<&InMemElement> {
+    obj.get(tag).filter(|e| !e.is_empty())
+}
+
+/// Read the text value of an attribute, if present,
+/// without padding.
+pub(crate) fn text(obj: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    non_empty(obj, tag)
+        .map(|e| {
+            e.to_str()
+                .map(|s| s.trim_matches([' ', '\0']).to_string())
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read the text value of a required attribute.
+pub(crate) fn required_text(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    Ok(text(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+/// Read an unsigned short attribute, if present.
+pub(crate) fn int(obj: &InMemDicomObject, tag: Tag) -> Result<Option<u16>> {
+    non_empty(obj, tag)
+        .map(|e| e.to_int().ok().context(InvalidAttributeSnafu { tag }))
+        .transpose()
+        .map_err(Into::into)
+}
+
+/// Read a required unsigned short attribute.
+pub(crate) fn required_int(obj: &InMemDicomObject, tag: Tag) -> Result<u16> {
+    Ok(int(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+/// Retrieve the items of a sequence,
+/// or none if the sequence is not present.
+pub(crate) fn items(obj: &InMemDicomObject, tag: Tag) -> &[InMemDicomObject] {
+    obj.get(tag).and_then(|e| e.items()).unwrap_or_default()
+}
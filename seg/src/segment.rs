@@ -0,0 +1,249 @@
+//! Segments and their properties
+use dicom_core::{DataElement, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_object::code::Code;
+use snafu::{OptionExt, ResultExt};
+use std::fmt;
+
+use crate::{
+    InvalidAttributeSnafu, InvalidCodeSnafu, Result, UnsupportedSegmentationTypeSnafu,
+    required_int, required_text, text,
+};
+
+/// How the frames of a segmentation represent its segments,
+/// as per the _Segmentation Type_
+/// and _Segmentation Fractional Type_ attributes.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum SegmentationType {
+    /// Each pixel either belongs to the segment or not (`BINARY`).
+    Binary,
+    /// Each pixel holds the probability that it belongs to the segment
+    /// (`FRACTIONAL`, `PROBABILITY`).
+    Probability,
+    /// Each pixel holds the fraction of it occupied by the segment
+    /// (`FRACTIONAL`, `OCCUPANCY`).
+    Occupancy,
+}
+
+impl SegmentationType {
+    /// Resolve the segmentation type from the attributes of an object.
+    pub(crate) fn from_object(obj: &InMemDicomObject) -> Result<Self> {
+        let value = required_text(obj, tags::SEGMENTATION_TYPE)?;
+        match value.as_str() {
+            "BINARY" => Ok(SegmentationType::Binary),
+            "FRACTIONAL" => match text(obj, tags::SEGMENTATION_FRACTIONAL_TYPE)?.as_deref() {
+                Some("PROBABILITY") => Ok(SegmentationType::Probability),
+                Some("OCCUPANCY") => Ok(SegmentationType::Occupancy),
+                _ => InvalidAttributeSnafu {
+                    tag: tags::SEGMENTATION_FRACTIONAL_TYPE,
+                }
+                .fail()
+                .map_err(Into::into),
+            },
+            _ => UnsupportedSegmentationTypeSnafu { value }
+                .fail()
+                .map_err(Into::into),
+        }
+    }
+
+    /// Whether pixel values are fractions of the _Maximum Fractional Value_.
+    pub fn is_fractional(self) -> bool {
+        self != SegmentationType::Binary
+    }
+}
+
+/// The type of algorithm which produced a segment,
+/// as per the _Segment Algorithm Type_ attribute.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum AlgorithmType {
+    /// Calculated segment, without user assistance
+    Automatic,
+    /// Calculated segment with user assistance
+    Semiautomatic,
+    /// Segment entirely specified by a user
+    Manual,
+}
+
+impl AlgorithmType {
+    /// Parse the segment algorithm type from its code string.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "AUTOMATIC" => Some(AlgorithmType::Automatic),
+            "SEMIAUTOMATIC" => Some(AlgorithmType::Semiautomatic),
+            "MANUAL" => Some(AlgorithmType::Manual),
+            _ => None,
+        }
+    }
+
+    /// The code string of the segment algorithm type.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlgorithmType::Automatic => "AUTOMATIC",
+            AlgorithmType::Semiautomatic => "SEMIAUTOMATIC",
+            AlgorithmType::Manual => "MANUAL",
+        }
+    }
+}
+
+impl fmt::Display for AlgorithmType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A segment of a segmentation,
+/// as in an item of the _Segment Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// The _Segment Number_, starting at 1
+    pub number: u16,
+    /// The _Segment Label_
+    pub label: String,
+    /// The _Segment Description_, if any
+    pub description: Option<String>,
+    /// The _Segment Algorithm Type_
+    pub algorithm_type: AlgorithmType,
+    /// The _Segment Algorithm Name_,
+    /// required unless the segment is manual
+    pub algorithm_name: Option<String>,
+    /// The category of the segmented property,
+    /// such as (123037004, SCT, "Anatomical Structure")
+    pub category: Code,
+    /// The type of the segmented property,
+    /// such as (10200004, SCT, "Liver")
+    pub property_type: Code,
+    /// The _Tracking ID_, if any
+    pub tracking_id: Option<String>,
+    /// The _Tracking UID_, if any
+    pub tracking_uid: Option<String>,
+    /// The _Recommended Display CIELab Value_, if any,
+    /// scaled to the range of 16-bit unsigned integers
+    pub display_color: Option<[u16; 3]>,
+}
+
+impl Segment {
+    /// Create a new manual segment.
+    pub fn new(number: u16, label: impl Into<String>, category: Code, property_type: Code) -> Self {
+        Segment {
+            number,
+            label: label.into(),
+            description: None,
+            algorithm_type: AlgorithmType::Manual,
+            algorithm_name: None,
+            category,
+            property_type,
+            tracking_id: None,
+            tracking_uid: None,
+            display_color: None,
+        }
+    }
+
+    /// Set the description of the segment.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Declare the segment as calculated by the named algorithm.
+    pub fn with_algorithm(
+        mut self,
+        algorithm_type: AlgorithmType,
+        name: impl Into<String>,
+    ) -> Self {
+        self.algorithm_type = algorithm_type;
+        self.algorithm_name = Some(name.into());
+        self
+    }
+
+    /// Set the tracking identifier and UID of the segment,
+    /// which identify the same finding across objects.
+    pub fn with_tracking(mut self, id: impl Into<String>, uid: impl Into<String>) -> Self {
+        self.tracking_id = Some(id.into());
+        self.tracking_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the recommended display color of the segment,
+    /// in scaled CIELab values.
+    pub fn with_display_color(mut self, color: [u16; 3]) -> Self {
+        self.display_color = Some(color);
+        self
+    }
+
+    /// Read a segment from an item of the _Segment Sequence_.
+    pub(crate) fn from_item(item: &InMemDicomObject) -> Result<Self> {
+        let code = |tag| Code::from_sequence(item, tag).context(InvalidCodeSnafu { tag });
+        let algorithm_type =
+            AlgorithmType::from_code(&required_text(item, tags::SEGMENT_ALGORITHM_TYPE)?).context(
+                InvalidAttributeSnafu {
+                    tag: tags::SEGMENT_ALGORITHM_TYPE,
+                },
+            )?;
+        let display_color = match item.get(tags::RECOMMENDED_DISPLAY_CIE_LAB_VALUE) {
+            Some(e) => {
+                let values: Vec<u16> = e.to_multi_int().ok().context(InvalidAttributeSnafu {
+                    tag: tags::RECOMMENDED_DISPLAY_CIE_LAB_VALUE,
+                })?;
+                Some(values.try_into().ok().context(InvalidAttributeSnafu {
+                    tag: tags::RECOMMENDED_DISPLAY_CIE_LAB_VALUE,
+                })?)
+            }
+            None => None,
+        };
+
+        Ok(Segment {
+            number: required_int(item, tags::SEGMENT_NUMBER)?,
+            label: required_text(item, tags::SEGMENT_LABEL)?,
+            description: text(item, tags::SEGMENT_DESCRIPTION)?,
+            algorithm_type,
+            algorithm_name: text(item, tags::SEGMENT_ALGORITHM_NAME)?,
+            category: code(tags::SEGMENTED_PROPERTY_CATEGORY_CODE_SEQUENCE)?,
+            property_type: code(tags::SEGMENTED_PROPERTY_TYPE_CODE_SEQUENCE)?,
+            tracking_id: text(item, tags::TRACKING_ID)?,
+            tracking_uid: text(item, tags::TRACKING_UID)?,
+            display_color,
+        })
+    }
+
+    /// Create an item of the _Segment Sequence_ for this segment.
+    pub(crate) fn to_item(&self) -> InMemDicomObject {
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+        let mut item = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SEGMENT_NUMBER,
+                VR::US,
+                PrimitiveValue::from(self.number),
+            ),
+            str_elem(tags::SEGMENT_LABEL, VR::LO, &self.label),
+            str_elem(
+                tags::SEGMENT_ALGORITHM_TYPE,
+                VR::CS,
+                self.algorithm_type.as_str(),
+            ),
+            self.category
+                .to_sequence_element(tags::SEGMENTED_PROPERTY_CATEGORY_CODE_SEQUENCE),
+            self.property_type
+                .to_sequence_element(tags::SEGMENTED_PROPERTY_TYPE_CODE_SEQUENCE),
+        ]);
+        for (tag, vr, value) in [
+            (tags::SEGMENT_DESCRIPTION, VR::ST, &self.description),
+            (tags::SEGMENT_ALGORITHM_NAME, VR::LO, &self.algorithm_name),
+            (tags::TRACKING_ID, VR::UT, &self.tracking_id),
+            (tags::TRACKING_UID, VR::UI, &self.tracking_uid),
+        ] {
+            if let Some(value) = value {
+                item.put(str_elem(tag, vr, value));
+            }
+        }
+        if let Some(color) = self.display_color {
+            item.put(DataElement::new(
+                tags::RECOMMENDED_DISPLAY_CIE_LAB_VALUE,
+                VR::US,
+                PrimitiveValue::U16(color.into_iter().collect()),
+            ));
+        }
+        item
+    }
+}
@@ -0,0 +1,65 @@
+//! Encoding of attributes for the calculation of MACs
+use dicom_core::{DicomValue, Tag, header::Header};
+use dicom_dictionary_std::tags;
+use dicom_encoding::text::SpecificCharacterSet;
+use dicom_object::{InMemDicomObject, mem::InMemElement};
+use dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN;
+use snafu::ResultExt;
+
+use crate::{EncodeElementSnafu, Result};
+
+const ITEM: Tag = Tag(0xFFFE, 0xE000);
+const ITEM_DELIMITATION_ITEM: Tag = Tag(0xFFFE, 0xE00D);
+const SEQUENCE_DELIMITATION_ITEM: Tag = Tag(0xFFFE, 0xE0DD);
+
+/// The character set declared in an object,
+/// or the given one if none is declared.
+pub(crate) fn charset(
+    obj: &InMemDicomObject,
+    inherited: &SpecificCharacterSet,
+) -> SpecificCharacterSet {
+    obj.get(tags::SPECIFIC_CHARACTER_SET)
+        .and_then(|e| e.value().to_multi_str().ok())
+        .and_then(|codes| SpecificCharacterSet::from_codes(codes.iter()))
+        .unwrap_or_else(|| inherited.clone())
+}
+
+/// Append the encoding of an attribute in Explicit VR Little Endian,
+/// with sequences and items encoded without their value lengths.
+pub(crate) fn encode_element(
+    elem: &InMemElement,
+    cs: &SpecificCharacterSet,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let tag = elem.tag();
+    match elem.value() {
+        DicomValue::Sequence(sequence) => {
+            put_tag(out, tag);
+            out.extend_from_slice(b"SQ\0\0");
+            for item in sequence.items() {
+                put_tag(out, ITEM);
+                let cs = charset(item, cs);
+                for elem in item {
+                    encode_element(elem, &cs, out)?;
+                }
+                put_tag(out, ITEM_DELIMITATION_ITEM);
+            }
+            put_tag(out, SEQUENCE_DELIMITATION_ITEM);
+        }
+        _ => {
+            InMemDicomObject::from_element_iter([elem.clone()])
+                .write_dataset_with_ts_cs(
+                    &mut *out,
+                    &EXPLICIT_VR_LITTLE_ENDIAN.erased(),
+                    cs.clone(),
+                )
+                .context(EncodeElementSnafu { tag })?;
+        }
+    }
+    Ok(())
+}
+
+fn put_tag(out: &mut Vec<u8>, tag: Tag) {
+    out.extend_from_slice(&tag.group().to_le_bytes());
+    out.extend_from_slice(&tag.element().to_le_bytes());
+}
@@ -0,0 +1,214 @@
+//! Signing keys and certificates
+use std::fmt;
+
+use ring::rand::SystemRandom;
+use ring::signature::{self as sig, EcdsaKeyPair, RsaKeyPair, UnparsedPublicKey};
+use snafu::{OptionExt, ResultExt, ensure};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::oid_registry::{
+    OID_EC_P256, OID_KEY_TYPE_EC_PUBLIC_KEY, OID_NIST_EC_P384, OID_PKCS1_RSAENCRYPTION,
+};
+use x509_parser::prelude::FromDer;
+
+use crate::{
+    InvalidPrivateKeySnafu, MacAlgorithm, ParseCertificateSnafu, ParsePemSnafu, Result, SignSnafu,
+    UnexpectedPemSnafu, UnsupportedKeyAlgorithmSnafu,
+};
+
+/// The kinds of public keys supported.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum KeyType {
+    Rsa,
+    EcdsaP256,
+    EcdsaP384,
+}
+
+#[derive(Debug)]
+enum KeyPair {
+    Rsa(RsaKeyPair),
+    Ecdsa(EcdsaKeyPair),
+}
+
+/// A private key to sign objects with,
+/// along with the X.509 certificate of its public key.
+pub struct SigningKey {
+    key_pair: KeyPair,
+    certificate: Vec<u8>,
+    rng: SystemRandom,
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("key_pair", &self.key_pair)
+            .field(
+                "certificate",
+                &format_args!("{} bytes", self.certificate.len()),
+            )
+            .finish()
+    }
+}
+
+impl SigningKey {
+    /// Load a private key in PKCS #8 DER format
+    /// and the DER-encoded certificate of its public key.
+    ///
+    /// The key must be an RSA key
+    /// or an ECDSA key over the P-256 or P-384 curves.
+    pub fn from_pkcs8_der(private_key: &[u8], certificate: impl Into<Vec<u8>>) -> Result<Self> {
+        let certificate = certificate.into();
+        let (key_type, _) = public_key(&certificate)?;
+        let rng = SystemRandom::new();
+        let rejected = |e: ring::error::KeyRejected| {
+            InvalidPrivateKeySnafu {
+                reason: e.to_string(),
+            }
+            .build()
+        };
+        let key_pair = match key_type {
+            KeyType::Rsa => KeyPair::Rsa(RsaKeyPair::from_pkcs8(private_key).map_err(rejected)?),
+            KeyType::EcdsaP256 => KeyPair::Ecdsa(
+                EcdsaKeyPair::from_pkcs8(&sig::ECDSA_P256_SHA256_ASN1_SIGNING, private_key, &rng)
+                    .map_err(rejected)?,
+            ),
+            KeyType::EcdsaP384 => KeyPair::Ecdsa(
+                EcdsaKeyPair::from_pkcs8(&sig::ECDSA_P384_SHA384_ASN1_SIGNING, private_key, &rng)
+                    .map_err(rejected)?,
+            ),
+        };
+        Ok(SigningKey {
+            key_pair,
+            certificate,
+            rng,
+        })
+    }
+
+    /// Load a private key in PKCS #8 PEM format (`PRIVATE KEY`)
+    /// and the PEM-encoded certificate of its public key (`CERTIFICATE`).
+    pub fn from_pem(private_key: &str, certificate: &str) -> Result<Self> {
+        let private_key = parse_pem(private_key, "PRIVATE KEY")?;
+        let certificate = parse_pem(certificate, "CERTIFICATE")?;
+        Self::from_pkcs8_der(&private_key, certificate)
+    }
+
+    /// The DER-encoded certificate of the signer.
+    pub fn certificate(&self) -> &[u8] {
+        &self.certificate
+    }
+
+    /// Sign a message,
+    /// with the hash function of the MAC algorithm in the case of RSA keys,
+    /// or the one of the curve in the case of ECDSA keys.
+    pub(crate) fn sign(&self, mac_algorithm: MacAlgorithm, message: &[u8]) -> Result<Vec<u8>> {
+        match &self.key_pair {
+            KeyPair::Rsa(key_pair) => {
+                let padding = match mac_algorithm {
+                    MacAlgorithm::Sha256 => &sig::RSA_PKCS1_SHA256,
+                    MacAlgorithm::Sha384 => &sig::RSA_PKCS1_SHA384,
+                    MacAlgorithm::Sha512 => &sig::RSA_PKCS1_SHA512,
+                };
+                let mut signature = vec![0; key_pair.public().modulus_len()];
+                key_pair
+                    .sign(padding, &self.rng, message, &mut signature)
+                    .ok()
+                    .context(SignSnafu)?;
+                Ok(signature)
+            }
+            KeyPair::Ecdsa(key_pair) => Ok(key_pair
+                .sign(&self.rng, message)
+                .ok()
+                .context(SignSnafu)?
+                .as_ref()
+                .to_vec()),
+        }
+    }
+}
+
+/// Check the signature of a message against the public key of a certificate.
+pub(crate) fn verify(
+    certificate: &[u8],
+    mac_algorithm: MacAlgorithm,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool> {
+    let (key_type, key) = public_key(certificate)?;
+    let algorithm: &dyn sig::VerificationAlgorithm = match (key_type, mac_algorithm) {
+        (KeyType::Rsa, MacAlgorithm::Sha256) => &sig::RSA_PKCS1_2048_8192_SHA256,
+        (KeyType::Rsa, MacAlgorithm::Sha384) => &sig::RSA_PKCS1_2048_8192_SHA384,
+        (KeyType::Rsa, MacAlgorithm::Sha512) => &sig::RSA_PKCS1_2048_8192_SHA512,
+        (KeyType::EcdsaP256, _) => &sig::ECDSA_P256_SHA256_ASN1,
+        (KeyType::EcdsaP384, _) => &sig::ECDSA_P384_SHA384_ASN1,
+    };
+    let signature = match key_type {
+        KeyType::Rsa => signature,
+        KeyType::EcdsaP256 | KeyType::EcdsaP384 => der_signature(signature),
+    };
+    Ok(UnparsedPublicKey::new(algorithm, key)
+        .verify(message, signature)
+        .is_ok())
+}
+
+/// Strip the padding from a DER-encoded ECDSA signature,
+/// which is added when its length is odd
+/// and the signature is written to a data set.
+fn der_signature(signature: &[u8]) -> &[u8] {
+    let len = match signature {
+        [0x30, len, ..] if *len < 0x80 => 2 + *len as usize,
+        [0x30, 0x81, len, ..] => 3 + *len as usize,
+        _ => return signature,
+    };
+    signature.get(..len).unwrap_or(signature)
+}
+
+/// Parse a DER-encoded certificate.
+pub(crate) fn parse_certificate(certificate: &[u8]) -> Result<X509Certificate<'_>> {
+    let (_, certificate) = X509Certificate::from_der(certificate)
+        .map_err(|e| match e {
+            x509_parser::nom::Err::Error(e) | x509_parser::nom::Err::Failure(e) => e,
+            x509_parser::nom::Err::Incomplete(_) => {
+                x509_parser::error::X509Error::InvalidCertificate
+            }
+        })
+        .context(ParseCertificateSnafu)?;
+    Ok(certificate)
+}
+
+/// Retrieve the type and the bytes of the public key of a certificate.
+fn public_key(certificate: &[u8]) -> Result<(KeyType, Vec<u8>)> {
+    let certificate = parse_certificate(certificate)?;
+    let spki = certificate.public_key();
+    let algorithm = &spki.algorithm.algorithm;
+    let key_type = if *algorithm == OID_PKCS1_RSAENCRYPTION {
+        KeyType::Rsa
+    } else if *algorithm == OID_KEY_TYPE_EC_PUBLIC_KEY {
+        let curve = spki
+            .algorithm
+            .parameters
+            .as_ref()
+            .and_then(|p| p.as_oid().ok())
+            .context(UnsupportedKeyAlgorithmSnafu)?;
+        if curve == OID_EC_P256 {
+            KeyType::EcdsaP256
+        } else if curve == OID_NIST_EC_P384 {
+            KeyType::EcdsaP384
+        } else {
+            return UnsupportedKeyAlgorithmSnafu.fail().map_err(Into::into);
+        }
+    } else {
+        return UnsupportedKeyAlgorithmSnafu.fail().map_err(Into::into);
+    };
+    Ok((key_type, spki.subject_public_key.data.to_vec()))
+}
+
+/// Decode PEM data with the expected label.
+fn parse_pem(text: &str, expected: &'static str) -> Result<Vec<u8>> {
+    let pem = pem::parse(text).context(ParsePemSnafu)?;
+    ensure!(
+        pem.tag() == expected,
+        UnexpectedPemSnafu {
+            expected,
+            found: pem.tag(),
+        }
+    );
+    Ok(pem.into_contents())
+}
@@ -0,0 +1,133 @@
+//! DICOM digital signatures
+//!
+//! This crate implements the [digital signatures][1] of DICOM data sets,
+//! which let a recipient check that the signed attributes of an object
+//! have not changed since it was signed,
+//! and identify the signer by their X.509 certificate.
+//!
+//! - [`Signer`] signs the attributes of an object with a [`SigningKey`],
+//!   adding an item to the _MAC Parameters Sequence_
+//!   describing how the message authentication code (MAC) was calculated,
+//!   and an item to the _Digital Signatures Sequence_
+//!   holding the signature and the certificate of the signer.
+//! - [`DigitalSignature`] reads these items back from an object
+//!   and verifies the signature against the current attributes,
+//!   while [`verify_signatures`] verifies all signatures of an object.
+//!
+//! Only signatures of the top level data set are supported,
+//! with MACs calculated with the _Explicit VR Little Endian_ transfer syntax
+//! using SHA-256, SHA-384 or SHA-512.
+//! Signatures with the other MAC algorithms defined by the standard
+//! (RIPEMD-160, SHA-1 and MD5) cannot be verified:
+//! reading them fails with an unsupported MAC algorithm error,
+//! which is distinct from the error for unknown MAC algorithms.
+//! Signatures are made with RSA (PKCS #1 v1.5) keys
+//! or ECDSA keys over the P-256 or P-384 curves.
+//!
+//! # Encoding rules
+//!
+//! The MAC is calculated over the attributes listed in _Data Elements Signed_,
+//! in ascending order of tag,
+//! followed by the _MAC ID Number_, _MAC Calculation Transfer Syntax UID_,
+//! _MAC Algorithm_ and _Data Elements Signed_ attributes of the MAC parameters.
+//! Each attribute is encoded as in the MAC calculation transfer syntax,
+//! with its values padded to an even length,
+//! except that sequences and their items are encoded
+//! without their value lengths but with their delimitation items,
+//! so that the MAC does not depend
+//! on whether they were written with explicit or undefined lengths.
+//! The signature is then made over the MAC
+//! followed by the _Digital Signature UID_, _Digital Signature DateTime_,
+//! _Certificate Type_ and _Certificate of Signer_ attributes.
+//!
+//! Verifying a signature of an object read from a file
+//! therefore requires its values to be kept as they were read,
+//! which is the case unless they are modified.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_signature::{Signer, SigningKey, verify_signatures};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let key = SigningKey::from_pem(
+//!     &std::fs::read_to_string("signer.key")?,
+//!     &std::fs::read_to_string("signer.crt")?,
+//! )?;
+//! let mut obj = open_file("report.dcm")?;
+//! Signer::new(&key).sign(&mut obj)?;
+//! obj.write_to_file("report-signed.dcm")?;
+//!
+//! let obj = open_file("report-signed.dcm")?;
+//! for signature in verify_signatures(&obj)? {
+//!     println!("valid signature #{} of {}", signature.mac_id, signature.signer()?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part15/chapter_C.html
+use dicom_core::Tag;
+
+mod encode;
+mod key;
+mod signature;
+
+pub use key::SigningKey;
+pub use signature::{DigitalSignature, MacAlgorithm, Signer, verify_signatures};
+
+/// An error which may occur when signing an object
+/// or verifying its digital signatures.
+#[derive(Debug, snafu::Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for digital signatures
+#[derive(Debug, snafu::Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Could not parse PEM data
+    ParsePem { source: pem::PemError },
+    /// Expected PEM data labeled {expected}, found {found}
+    UnexpectedPem {
+        expected: &'static str,
+        found: String,
+    },
+    /// Could not parse the X.509 certificate
+    ParseCertificate {
+        source: x509_parser::error::X509Error,
+    },
+    /// Unsupported public key algorithm in certificate
+    UnsupportedKeyAlgorithm,
+    #[snafu(display("Invalid private key: {reason}"))]
+    InvalidPrivateKey { reason: String },
+    /// Could not compute the signature
+    Sign,
+    /// Missing attribute {tag}
+    MissingAttribute { tag: Tag },
+    /// Invalid value of attribute {tag}
+    InvalidAttribute { tag: Tag },
+    /// Unknown MAC algorithm `{value}`
+    UnknownMacAlgorithm { value: String },
+    /// MAC algorithm `{value}` is not supported
+    UnsupportedMacAlgorithm { value: String },
+    /// Unsupported MAC calculation transfer syntax {uid}
+    UnsupportedTransferSyntax { uid: String },
+    /// No MAC parameters with the MAC ID number {mac_id}
+    MissingMacParameters { mac_id: u16 },
+    /// Signed attribute {tag} is missing from the object
+    MissingSignedElement { tag: Tag },
+    /// No attributes to sign
+    NoElementsToSign,
+    /// Could not encode attribute {tag}
+    EncodeElement {
+        tag: Tag,
+        #[snafu(source(from(dicom_object::WriteError, Box::new)))]
+        source: Box<dicom_object::WriteError>,
+    },
+    /// Signature #{mac_id} does not match the signed attributes
+    SignatureMismatch { mac_id: u16 },
+}
+
+/// Alias for the result of signing or verifying an object.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,601 @@
+//! Creation and verification of digital signatures
+use std::fmt;
+
+use dicom_core::header::{HasLength, Header};
+use dicom_core::value::DataSetSequence;
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR, chrono, uid::UidGenerator};
+use dicom_dictionary_std::{tags, uids};
+use dicom_encoding::text::SpecificCharacterSet;
+use dicom_object::InMemDicomObject;
+use snafu::{OptionExt, ensure};
+
+use crate::encode::{charset, encode_element};
+use crate::key::{self, SigningKey};
+use crate::{
+    InvalidAttributeSnafu, MissingAttributeSnafu, MissingMacParametersSnafu,
+    MissingSignedElementSnafu, NoElementsToSignSnafu, Result, SignatureMismatchSnafu,
+    UnknownMacAlgorithmSnafu, UnsupportedMacAlgorithmSnafu, UnsupportedTransferSyntaxSnafu,
+};
+
+/// The certificate type of X.509 signature certificates
+const X509_CERTIFICATE_TYPE: &str = "X509_1993_SIG";
+
+/// The attributes of the MAC parameters included in the MAC,
+/// after the signed attributes.
+const MAC_PARAMETERS_TAGS: [Tag; 4] = [
+    tags::MACID_NUMBER,
+    tags::MAC_CALCULATION_TRANSFER_SYNTAX_UID,
+    tags::MAC_ALGORITHM,
+    tags::DATA_ELEMENTS_SIGNED,
+];
+
+/// The attributes of the digital signature included in the signature,
+/// after the MAC.
+const SIGNATURE_TAGS: [Tag; 4] = [
+    tags::DIGITAL_SIGNATURE_UID,
+    tags::DIGITAL_SIGNATURE_DATE_TIME,
+    tags::CERTIFICATE_TYPE,
+    tags::CERTIFICATE_OF_SIGNER,
+];
+
+/// The algorithm of the message authentication code (MAC)
+/// of a digital signature.
+///
+/// Only the SHA-2 algorithms are supported.
+/// The legacy algorithms `RIPEMD160`, `SHA1` and `MD5`
+/// are recognized when reading digital signatures,
+/// but reported as unsupported.
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum MacAlgorithm {
+    /// SHA-256 (`SHA256`)
+    #[default]
+    Sha256,
+    /// SHA-384 (`SHA384`)
+    Sha384,
+    /// SHA-512 (`SHA512`)
+    Sha512,
+}
+
+/// The MAC algorithms defined by the standard
+/// which are not supported.
+const UNSUPPORTED_MAC_ALGORITHMS: [&str; 3] = ["RIPEMD160", "SHA1", "MD5"];
+
+impl MacAlgorithm {
+    /// Parse the MAC algorithm from its code string.
+    ///
+    /// Returns `None` for unknown and unsupported MAC algorithms.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "SHA256" => Some(MacAlgorithm::Sha256),
+            "SHA384" => Some(MacAlgorithm::Sha384),
+            "SHA512" => Some(MacAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    /// The code string of the MAC algorithm.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MacAlgorithm::Sha256 => "SHA256",
+            MacAlgorithm::Sha384 => "SHA384",
+            MacAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        let algorithm = match self {
+            MacAlgorithm::Sha256 => &ring::digest::SHA256,
+            MacAlgorithm::Sha384 => &ring::digest::SHA384,
+            MacAlgorithm::Sha512 => &ring::digest::SHA512,
+        };
+        ring::digest::digest(algorithm, data).as_ref().to_vec()
+    }
+}
+
+impl fmt::Display for MacAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A signer of DICOM objects.
+///
+/// # Example
+///
+/// ```no_run
+/// # use dicom_signature::{MacAlgorithm, Signer, SigningKey};
+/// # use dicom_dictionary_std::tags;
+/// # fn run(key: &SigningKey, obj: &mut dicom_object::InMemDicomObject) -> dicom_signature::Result<()> {
+/// // sign the patient and study identification only
+/// Signer::new(key)
+///     .with_mac_algorithm(MacAlgorithm::Sha512)
+///     .with_data_elements([tags::PATIENT_ID, tags::STUDY_INSTANCE_UID, tags::SOP_INSTANCE_UID])
+///     .sign(obj)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Signer<'a> {
+    key: &'a SigningKey,
+    mac_algorithm: MacAlgorithm,
+    data_elements: Option<Vec<Tag>>,
+    uid_generator: UidGenerator,
+}
+
+impl<'a> Signer<'a> {
+    /// Create a signer with the given key,
+    /// which signs all attributes of an object with a SHA-256 MAC.
+    pub fn new(key: &'a SigningKey) -> Self {
+        Signer {
+            key,
+            mac_algorithm: MacAlgorithm::default(),
+            data_elements: None,
+            uid_generator: UidGenerator::default(),
+        }
+    }
+
+    /// Set the algorithm of the MAC.
+    pub fn with_mac_algorithm(mut self, mac_algorithm: MacAlgorithm) -> Self {
+        self.mac_algorithm = mac_algorithm;
+        self
+    }
+
+    /// Sign only the attributes with the given tags,
+    /// instead of all attributes of the object.
+    pub fn with_data_elements(mut self, tags: impl IntoIterator<Item = Tag>) -> Self {
+        self.data_elements = Some(tags.into_iter().collect());
+        self
+    }
+
+    /// Set the generator of the digital signature UIDs,
+    /// such as one under an organization root.
+    pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+        self.uid_generator = uid_generator;
+        self
+    }
+
+    /// Sign the object,
+    /// adding an item to its _MAC Parameters Sequence_
+    /// and an item to its _Digital Signatures Sequence_.
+    ///
+    /// Unless other attributes were chosen,
+    /// all attributes of the object are signed
+    /// except for group lengths, existing signatures
+    /// and the _Data Set Trailing Padding_.
+    pub fn sign(&self, obj: &mut InMemDicomObject) -> Result<DigitalSignature> {
+        let mut data_elements = match &self.data_elements {
+            Some(tags) => tags.clone(),
+            None => obj
+                .iter()
+                .map(|e| e.tag())
+                .filter(|tag| is_signable(*tag))
+                .collect(),
+        };
+        data_elements.sort();
+        data_elements.dedup();
+        ensure!(!data_elements.is_empty(), NoElementsToSignSnafu);
+
+        let mac_id = items(obj, tags::MAC_PARAMETERS_SEQUENCE)
+            .iter()
+            .filter_map(|item| item.get(tags::MACID_NUMBER)?.to_int::<u16>().ok())
+            .max()
+            .map_or(1, |id| id.saturating_add(1));
+
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+        let parameters = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::MACID_NUMBER, VR::US, PrimitiveValue::from(mac_id)),
+            str_elem(
+                tags::MAC_CALCULATION_TRANSFER_SYNTAX_UID,
+                VR::UI,
+                uids::EXPLICIT_VR_LITTLE_ENDIAN,
+            ),
+            str_elem(tags::MAC_ALGORITHM, VR::CS, self.mac_algorithm.as_str()),
+            DataElement::new(
+                tags::DATA_ELEMENTS_SIGNED,
+                VR::AT,
+                PrimitiveValue::Tags(data_elements.iter().copied().collect()),
+            ),
+        ]);
+        let date_time = chrono::Local::now()
+            .format("%Y%m%d%H%M%S%.6f%z")
+            .to_string();
+        let mut item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::MACID_NUMBER, VR::US, PrimitiveValue::from(mac_id)),
+            str_elem(
+                tags::DIGITAL_SIGNATURE_UID,
+                VR::UI,
+                &self.uid_generator.generate(),
+            ),
+            str_elem(tags::DIGITAL_SIGNATURE_DATE_TIME, VR::DT, &date_time),
+            str_elem(tags::CERTIFICATE_TYPE, VR::CS, X509_CERTIFICATE_TYPE),
+            DataElement::new(
+                tags::CERTIFICATE_OF_SIGNER,
+                VR::OB,
+                PrimitiveValue::from(self.key.certificate().to_vec()),
+            ),
+        ]);
+
+        let cs = charset(obj, &SpecificCharacterSet::default());
+        let message = signed_message(
+            obj,
+            &cs,
+            &parameters,
+            &item,
+            self.mac_algorithm,
+            &data_elements,
+        )?;
+        let signature = self.key.sign(self.mac_algorithm, &message)?;
+        item.put(DataElement::new(
+            tags::SIGNATURE,
+            VR::OB,
+            PrimitiveValue::from(signature),
+        ));
+
+        let signature = DigitalSignature::from_items(&parameters, &item)?;
+        push_item(obj, tags::MAC_PARAMETERS_SEQUENCE, parameters);
+        push_item(obj, tags::DIGITAL_SIGNATURES_SEQUENCE, item);
+        Ok(signature)
+    }
+}
+
+/// A digital signature of a DICOM object,
+/// as in an item of the _Digital Signatures Sequence_
+/// and the corresponding item of the _MAC Parameters Sequence_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigitalSignature {
+    /// The _MAC ID Number_ identifying the MAC parameters
+    pub mac_id: u16,
+    /// The _Digital Signature UID_
+    pub uid: String,
+    /// The _Digital Signature DateTime_
+    pub date_time: String,
+    /// The _MAC Calculation Transfer Syntax UID_
+    pub transfer_syntax_uid: String,
+    /// The _MAC Algorithm_
+    pub mac_algorithm: MacAlgorithm,
+    /// The tags of the signed attributes
+    pub data_elements_signed: Vec<Tag>,
+    /// The _Certificate Type_
+    pub certificate_type: String,
+    /// The _Certificate of Signer_
+    pub certificate: Vec<u8>,
+    /// The _Signature_
+    pub signature: Vec<u8>,
+    /// the item of the MAC Parameters Sequence
+    parameters: InMemDicomObject,
+    /// the item of the Digital Signatures Sequence
+    item: InMemDicomObject,
+}
+
+impl DigitalSignature {
+    /// Read all digital signatures of the top level data set of an object.
+    pub fn read_all(obj: &InMemDicomObject) -> Result<Vec<Self>> {
+        let parameters = items(obj, tags::MAC_PARAMETERS_SEQUENCE);
+        items(obj, tags::DIGITAL_SIGNATURES_SEQUENCE)
+            .iter()
+            .map(|item| {
+                let mac_id = required_mac_id(item)?;
+                let parameters = parameters
+                    .iter()
+                    .find(|p| required_mac_id(p).ok() == Some(mac_id))
+                    .context(MissingMacParametersSnafu { mac_id })?;
+                Self::from_items(parameters, item)
+            })
+            .collect()
+    }
+
+    fn from_items(parameters: &InMemDicomObject, item: &InMemDicomObject) -> Result<Self> {
+        let mac_algorithm = required_text(parameters, tags::MAC_ALGORITHM)?;
+        let Some(mac_algorithm) = MacAlgorithm::from_code(&mac_algorithm) else {
+            if UNSUPPORTED_MAC_ALGORITHMS.contains(&mac_algorithm.as_str()) {
+                return UnsupportedMacAlgorithmSnafu {
+                    value: mac_algorithm,
+                }
+                .fail()
+                .map_err(Into::into);
+            }
+            return UnknownMacAlgorithmSnafu {
+                value: mac_algorithm,
+            }
+            .fail()
+            .map_err(Into::into);
+        };
+        let data_elements_signed = match parameters
+            .get(tags::DATA_ELEMENTS_SIGNED)
+            .context(MissingAttributeSnafu {
+                tag: tags::DATA_ELEMENTS_SIGNED,
+            })?
+            .value()
+            .primitive()
+        {
+            Some(PrimitiveValue::Tags(tags)) => tags.to_vec(),
+            _ => {
+                return InvalidAttributeSnafu {
+                    tag: tags::DATA_ELEMENTS_SIGNED,
+                }
+                .fail()
+                .map_err(Into::into);
+            }
+        };
+        let bytes = |tag| -> Result<Vec<u8>> {
+            Ok(item
+                .get(tag)
+                .context(MissingAttributeSnafu { tag })?
+                .to_bytes()
+                .ok()
+                .context(InvalidAttributeSnafu { tag })?
+                .to_vec())
+        };
+
+        Ok(DigitalSignature {
+            mac_id: required_mac_id(item)?,
+            uid: required_text(item, tags::DIGITAL_SIGNATURE_UID)?,
+            date_time: required_text(item, tags::DIGITAL_SIGNATURE_DATE_TIME)?,
+            transfer_syntax_uid: required_text(
+                parameters,
+                tags::MAC_CALCULATION_TRANSFER_SYNTAX_UID,
+            )?,
+            mac_algorithm,
+            data_elements_signed,
+            certificate_type: required_text(item, tags::CERTIFICATE_TYPE)?,
+            certificate: bytes(tags::CERTIFICATE_OF_SIGNER)?,
+            signature: bytes(tags::SIGNATURE)?,
+            parameters: parameters.clone(),
+            item: item.clone(),
+        })
+    }
+
+    /// The subject of the certificate of the signer,
+    /// such as `CN=Jane Doe, O=Hospital`.
+    pub fn signer(&self) -> Result<String> {
+        Ok(key::parse_certificate(&self.certificate)?
+            .subject()
+            .to_string())
+    }
+
+    /// Verify the signature against the current attributes of the object.
+    ///
+    /// Fails if any signed attribute is missing or was modified.
+    /// Note that the certificate of the signer is not validated.
+    pub fn verify(&self, obj: &InMemDicomObject) -> Result<()> {
+        ensure!(
+            self.transfer_syntax_uid == uids::EXPLICIT_VR_LITTLE_ENDIAN,
+            UnsupportedTransferSyntaxSnafu {
+                uid: &self.transfer_syntax_uid,
+            }
+        );
+        ensure!(
+            self.certificate_type == X509_CERTIFICATE_TYPE,
+            InvalidAttributeSnafu {
+                tag: tags::CERTIFICATE_TYPE,
+            }
+        );
+        let cs = charset(obj, &SpecificCharacterSet::default());
+        let message = signed_message(
+            obj,
+            &cs,
+            &self.parameters,
+            &self.item,
+            self.mac_algorithm,
+            &self.data_elements_signed,
+        )?;
+        ensure!(
+            key::verify(
+                &self.certificate,
+                self.mac_algorithm,
+                &message,
+                &self.signature
+            )?,
+            SignatureMismatchSnafu {
+                mac_id: self.mac_id
+            }
+        );
+        Ok(())
+    }
+}
+
+/// Read and verify all digital signatures of an object,
+/// failing on the first one which does not match the object.
+pub fn verify_signatures(obj: &InMemDicomObject) -> Result<Vec<DigitalSignature>> {
+    let signatures = DigitalSignature::read_all(obj)?;
+    for signature in &signatures {
+        signature.verify(obj)?;
+    }
+    Ok(signatures)
+}
+
+/// Build the message which is signed:
+/// the MAC of the signed attributes and the MAC parameters,
+/// followed by the attributes of the digital signature.
+fn signed_message(
+    obj: &InMemDicomObject,
+    cs: &SpecificCharacterSet,
+    parameters: &InMemDicomObject,
+    item: &InMemDicomObject,
+    mac_algorithm: MacAlgorithm,
+    data_elements: &[Tag],
+) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    for &tag in data_elements {
+        let elem = obj.get(tag).context(MissingSignedElementSnafu { tag })?;
+        encode_element(elem, cs, &mut data)?;
+    }
+    for tag in MAC_PARAMETERS_TAGS {
+        let elem = parameters.get(tag).context(MissingAttributeSnafu { tag })?;
+        encode_element(elem, cs, &mut data)?;
+    }
+
+    let mut message = mac_algorithm.digest(&data);
+    for tag in SIGNATURE_TAGS {
+        let elem = item.get(tag).context(MissingAttributeSnafu { tag })?;
+        encode_element(elem, cs, &mut message)?;
+    }
+    Ok(message)
+}
+
+/// Whether an attribute is signed by default.
+fn is_signable(tag: Tag) -> bool {
+    tag.element() != 0
+        && tag != tags::MAC_PARAMETERS_SEQUENCE
+        && tag != tags::DIGITAL_SIGNATURES_SEQUENCE
+        && tag != tags::DATA_SET_TRAILING_PADDING
+}
+
+fn items(obj: &InMemDicomObject, tag: Tag) -> &[InMemDicomObject] {
+    obj.get(tag).and_then(|e| e.items()).unwrap_or_default()
+}
+
+fn push_item(obj: &mut InMemDicomObject, tag: Tag, item: InMemDicomObject) {
+    let mut items = items(obj, tag).to_vec();
+    items.push(item);
+    obj.put(DataElement::new(tag, VR::SQ, DataSetSequence::from(items)));
+}
+
+fn required_text(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    let elem = obj
+        .get(tag)
+        .filter(|e| !e.is_empty())
+        .context(MissingAttributeSnafu { tag })?;
+    Ok(elem
+        .to_str()
+        .ok()
+        .context(InvalidAttributeSnafu { tag })?
+        .trim_matches([' ', '\0'])
+        .to_string())
+}
+
+fn required_mac_id(obj: &InMemDicomObject) -> Result<u16> {
+    Ok(obj
+        .get(tags::MACID_NUMBER)
+        .context(MissingAttributeSnafu {
+            tag: tags::MACID_NUMBER,
+        })?
+        .to_int()
+        .ok()
+        .context(InvalidAttributeSnafu {
+            tag: tags::MACID_NUMBER,
+        })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject, OpenFileOptions};
+
+    use crate::{DigitalSignature, MacAlgorithm, Signer, SigningKey, verify_signatures};
+
+    fn signing_key() -> SigningKey {
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "Jane Doe");
+        let certificate = params.self_signed(&key_pair).unwrap();
+        SigningKey::from_pem(&key_pair.serialize_pem(), &certificate.pem()).unwrap()
+    }
+
+    #[test]
+    fn sign_and_verify_after_round_trip() {
+        let key = signing_key();
+        let mut obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::BASIC_TEXT_SR_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "123"),
+            DataElement::new(
+                tags::CONTENT_SEQUENCE,
+                VR::SQ,
+                dicom_core::value::DataSetSequence::from(vec![
+                    InMemDicomObject::from_element_iter([DataElement::new(
+                        tags::TEXT_VALUE,
+                        VR::UT,
+                        "Nothing to report",
+                    )]),
+                ]),
+            ),
+        ]);
+
+        let signature = Signer::new(&key).sign(&mut obj).unwrap();
+        assert_eq!(signature.mac_id, 1);
+        assert_eq!(signature.data_elements_signed.len(), 5);
+        assert_eq!(signature.signer().unwrap(), "CN=Jane Doe");
+        // a second signature over fewer attributes
+        let second = Signer::new(&key)
+            .with_mac_algorithm(MacAlgorithm::Sha384)
+            .with_data_elements([tags::PATIENT_ID])
+            .sign(&mut obj)
+            .unwrap();
+        assert_eq!(second.mac_id, 2);
+        assert_eq!(verify_signatures(&obj).unwrap().len(), 2);
+
+        // write and read back
+        let file = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                    .media_storage_sop_class_uid(uids::BASIC_TEXT_SR_STORAGE)
+                    .media_storage_sop_instance_uid("2.25.1"),
+            )
+            .unwrap();
+        let mut bytes = Vec::new();
+        file.write_all(&mut bytes).unwrap();
+        let mut obj = OpenFileOptions::new()
+            .from_reader(&bytes[128..])
+            .unwrap()
+            .into_inner();
+        let signatures = verify_signatures(&obj).unwrap();
+        assert_eq!(signatures[0].uid, signature.uid);
+
+        // tampering with a signed attribute breaks the first signature only
+        obj.put(DataElement::new(
+            tags::PATIENT_NAME,
+            VR::PN,
+            PrimitiveValue::from("Doe^Jane"),
+        ));
+        assert!(signatures[0].verify(&obj).is_err());
+        assert!(signatures[1].verify(&obj).is_ok());
+        assert!(verify_signatures(&obj).is_err());
+    }
+
+    #[test]
+    fn legacy_mac_algorithms_are_unsupported() {
+        let obj_with = |mac_algorithm: &str| {
+            let item = |elements: Vec<DataElement<InMemDicomObject>>| {
+                dicom_core::value::DataSetSequence::from(vec![InMemDicomObject::from_element_iter(
+                    elements,
+                )])
+            };
+            InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::MAC_PARAMETERS_SEQUENCE,
+                    VR::SQ,
+                    item(vec![
+                        DataElement::new(tags::MACID_NUMBER, VR::US, PrimitiveValue::from(1_u16)),
+                        DataElement::new(tags::MAC_ALGORITHM, VR::CS, mac_algorithm),
+                    ]),
+                ),
+                DataElement::new(
+                    tags::DIGITAL_SIGNATURES_SEQUENCE,
+                    VR::SQ,
+                    item(vec![DataElement::new(
+                        tags::MACID_NUMBER,
+                        VR::US,
+                        PrimitiveValue::from(1_u16),
+                    )]),
+                ),
+            ])
+        };
+
+        for code in ["RIPEMD160", "SHA1", "MD5"] {
+            let err = DigitalSignature::read_all(&obj_with(code)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("MAC algorithm `{code}` is not supported")
+            );
+        }
+        let err = DigitalSignature::read_all(&obj_with("SHA3")).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown MAC algorithm `SHA3`");
+    }
+}
@@ -0,0 +1,392 @@
+//! A CLI tool for organizing DICOM files into directories
+//! by patient, study and series.
+//!
+//! The files found are scanned in parallel with a [`Scanner`],
+//! reading only the attributes referred to by the [`PathTemplate`],
+//! and then moved, copied or linked to the path given by the template
+//! in the output directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{ArgGroup, Parser, ValueEnum};
+use dicom_app_common::path_template::PathTemplate;
+use dicom_object::scan::Scanner;
+use snafu::{ResultExt, Whatever, whatever};
+
+/// Organize DICOM files into directories by patient, study and series
+#[derive(Debug, Parser)]
+#[command(version)]
+#[command(group(ArgGroup::new("mode").args(["move_files", "copy", "symlink"])))]
+struct App {
+    /// The directories to look for DICOM files (or individual files)
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+    /// The output directory
+    #[arg(short = 'o', long = "out-dir")]
+    out_dir: PathBuf,
+    /// Template for the path of each file in the output directory,
+    /// with attribute keywords or tags in braces
+    #[arg(
+        short = 't',
+        long = "template",
+        default_value = "{PatientID}/{StudyInstanceUID}/{SeriesInstanceUID}/{SOPInstanceUID}.dcm"
+    )]
+    template: PathTemplate,
+    /// Move the files (default is to copy them)
+    #[arg(long = "move")]
+    move_files: bool,
+    /// Copy the files
+    #[arg(long = "copy")]
+    copy: bool,
+    /// Create symbolic links to the files instead of copying them
+    #[arg(long = "symlink")]
+    symlink: bool,
+    /// What to do when a different file already exists at the destination
+    #[arg(long = "on-conflict", value_enum, default_value = "rename")]
+    on_conflict: OnConflict,
+    /// The number of threads reading files
+    /// (default is the available parallelism)
+    #[arg(short = 'j', long = "threads")]
+    threads: Option<usize>,
+    /// Also accept DICOM files without the 128-byte preamble
+    #[arg(long = "no-preamble")]
+    no_preamble: bool,
+    /// Print the destination of each file
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+}
+
+/// How to place each file at its destination
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Move,
+    Copy,
+    Symlink,
+}
+
+/// What to do when a different file is already at the destination
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum OnConflict {
+    /// Leave the existing file and skip the new one
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Add a numeric suffix to the new file name
+    Rename,
+    /// Report an error for the new file
+    Fail,
+}
+
+/// The outcome of placing a single file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Outcome {
+    Done,
+    /// the file was already at its destination
+    Present,
+    Skipped,
+}
+
+fn main() {
+    tracing::subscriber::set_global_default(tracing_subscriber::FmtSubscriber::new())
+        .unwrap_or_else(|e| {
+            eprintln!("{}", snafu::Report::from_error(e));
+        });
+
+    run(App::parse()).unwrap_or_else(|e| {
+        tracing::error!("{}", snafu::Report::from_error(e));
+        std::process::exit(-2);
+    });
+}
+
+fn run(app: App) -> Result<(), Whatever> {
+    let App {
+        paths,
+        out_dir,
+        template,
+        move_files,
+        copy: _,
+        symlink,
+        on_conflict,
+        threads,
+        no_preamble,
+        verbose,
+    } = app;
+    let mode = if move_files {
+        Mode::Move
+    } else if symlink {
+        Mode::Symlink
+    } else {
+        Mode::Copy
+    };
+
+    let mut scanner = Scanner::new()
+        .with_tags(template.tags())
+        .with_no_preamble(no_preamble);
+    if let Some(threads) = threads {
+        scanner = scanner.with_threads(threads);
+    }
+
+    // files already in the output directory are left alone
+    let out_dir_abs = fs::canonicalize(&out_dir).ok();
+    let mut done = 0;
+    let mut present = 0;
+    let mut skipped = 0;
+    let mut failed = 0;
+    for path in &paths {
+        for result in scanner.scan(path) {
+            let file = match result {
+                Ok(file) => file,
+                Err(e) => {
+                    tracing::warn!("{}", snafu::Report::from_error(e));
+                    continue;
+                }
+            };
+            if let Some(out_dir) = &out_dir_abs {
+                if fs::canonicalize(&file.path).is_ok_and(|p| p.starts_with(out_dir)) {
+                    continue;
+                }
+            }
+            let destination = out_dir.join(template.render(&file.attributes));
+            match place_file(&file.path, &destination, mode, on_conflict) {
+                Ok((Outcome::Done, destination)) => {
+                    if verbose {
+                        println!("{} -> {}", file.path.display(), destination.display());
+                    }
+                    done += 1;
+                }
+                Ok((Outcome::Present, _)) => present += 1,
+                Ok((Outcome::Skipped, destination)) => {
+                    tracing::warn!(
+                        "Skipped {}: {} already exists",
+                        file.path.display(),
+                        destination.display()
+                    );
+                    skipped += 1;
+                }
+                Err(e) => {
+                    tracing::error!("{}", snafu::Report::from_error(e));
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    let verb = match mode {
+        Mode::Move => "Moved",
+        Mode::Copy => "Copied",
+        Mode::Symlink => "Linked",
+    };
+    println!("{verb} {done} file(s), {present} already in place, {skipped} skipped");
+    if failed > 0 {
+        whatever!("could not organize {failed} file(s)");
+    }
+    Ok(())
+}
+
+/// Move, copy or link a file to its destination,
+/// resolving conflicts with existing files.
+///
+/// Returns the outcome and the final destination of the file.
+fn place_file(
+    path: &Path,
+    destination: &Path,
+    mode: Mode,
+    on_conflict: OnConflict,
+) -> Result<(Outcome, PathBuf), Whatever> {
+    let mut destination = destination.to_path_buf();
+    if fs::symlink_metadata(&destination).is_ok() {
+        if same_file(path, &destination) {
+            return already_present(path, destination, mode);
+        }
+        match on_conflict {
+            OnConflict::Skip => return Ok((Outcome::Skipped, destination)),
+            OnConflict::Fail => whatever!(
+                "could not place {}: {} already exists",
+                path.display(),
+                destination.display()
+            ),
+            OnConflict::Overwrite => fs::remove_file(&destination)
+                .with_whatever_context(|_| format!("could not remove {}", destination.display()))?,
+            OnConflict::Rename => {
+                let mut i = 1;
+                destination = loop {
+                    let candidate = with_suffix(&destination, i);
+                    if fs::symlink_metadata(&candidate).is_err() {
+                        break candidate;
+                    }
+                    if same_file(path, &candidate) {
+                        return already_present(path, candidate, mode);
+                    }
+                    i += 1;
+                };
+            }
+        }
+    }
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_whatever_context(|_| format!("could not create {}", parent.display()))?;
+    }
+    let context = || {
+        format!(
+            "could not place {} at {}",
+            path.display(),
+            destination.display()
+        )
+    };
+    match mode {
+        Mode::Copy => {
+            fs::copy(path, &destination).with_whatever_context(|_| context())?;
+        }
+        Mode::Move => {
+            // fall back to copying if the file cannot be renamed,
+            // such as when moving across file systems
+            if fs::rename(path, &destination).is_err() {
+                fs::copy(path, &destination).with_whatever_context(|_| context())?;
+                fs::remove_file(path)
+                    .with_whatever_context(|_| format!("could not remove {}", path.display()))?;
+            }
+        }
+        Mode::Symlink => {
+            let target = fs::canonicalize(path).with_whatever_context(|_| context())?;
+            symlink(&target, &destination).with_whatever_context(|_| context())?;
+        }
+    }
+    Ok((Outcome::Done, destination))
+}
+
+/// Conclude placing a file which is already at its destination,
+/// removing the original file if it was meant to be moved.
+fn already_present(
+    path: &Path,
+    destination: PathBuf,
+    mode: Mode,
+) -> Result<(Outcome, PathBuf), Whatever> {
+    let is_original = fs::canonicalize(path).ok() == fs::canonicalize(&destination).ok();
+    if mode == Mode::Move && !is_original {
+        fs::remove_file(path)
+            .with_whatever_context(|_| format!("could not remove {}", path.display()))?;
+    }
+    Ok((Outcome::Present, destination))
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform",
+    ))
+}
+
+/// Check whether the file at `destination` is the same file as `path`
+/// (or a link to it),
+/// or a file with the same content.
+fn same_file(path: &Path, destination: &Path) -> bool {
+    if let (Ok(a), Ok(b)) = (fs::canonicalize(path), fs::canonicalize(destination)) {
+        if a == b {
+            return true;
+        }
+    }
+    match (fs::metadata(path), fs::metadata(destination)) {
+        (Ok(a), Ok(b)) if a.len() == b.len() => {
+            matches!((fs::read(path), fs::read(destination)), (Ok(a), Ok(b)) if a == b)
+        }
+        _ => false,
+    }
+}
+
+/// Add a numeric suffix to a file name, before its extension.
+fn with_suffix(path: &Path, i: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}_{i}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_{i}"),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{App, OnConflict, run};
+    use clap::CommandFactory;
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::{tags, uids};
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+    use std::path::Path;
+
+    #[test]
+    fn verify_cli() {
+        App::command().debug_assert();
+    }
+
+    fn write_file(path: &Path, series: &str, sop_instance_uid: &str, patient_name: &str) {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_CLASS_UID, VR::UI, uids::CT_IMAGE_STORAGE),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, sop_instance_uid),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, patient_name),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "12345"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, series),
+        ])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid(sop_instance_uid),
+        )
+        .unwrap()
+        .write_to_file(path)
+        .unwrap();
+    }
+
+    #[test]
+    fn split_into_series() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        let out = dir.path().join("out");
+        std::fs::create_dir(&input).unwrap();
+        write_file(&input.join("a"), "2.25.10", "2.25.100", "Doe^John");
+        write_file(&input.join("b"), "2.25.20", "2.25.200", "Doe^John");
+        // a different file with the same SOP instance UID
+        write_file(&input.join("c"), "2.25.20", "2.25.200", "Doe^Jane");
+        std::fs::write(input.join("notes.txt"), "not DICOM").unwrap();
+
+        let app = |move_files| App {
+            paths: vec![input.clone()],
+            out_dir: out.clone(),
+            template: "{PatientID}/{SeriesInstanceUID}/{SOPInstanceUID}.dcm"
+                .parse()
+                .unwrap(),
+            move_files,
+            copy: false,
+            symlink: false,
+            on_conflict: OnConflict::Rename,
+            threads: Some(2),
+            no_preamble: false,
+            verbose: false,
+        };
+        run(app(false)).unwrap();
+        assert!(out.join("12345/2.25.10/2.25.100.dcm").is_file());
+        assert!(out.join("12345/2.25.20/2.25.200.dcm").is_file());
+        assert!(out.join("12345/2.25.20/2.25.200_1.dcm").is_file());
+        assert!(!out.join("12345/2.25.20/2.25.200_2.dcm").exists());
+        assert!(input.join("a").is_file());
+
+        // moving the same files finds them already in place
+        run(app(true)).unwrap();
+        assert!(!out.join("12345/2.25.20/2.25.200_2.dcm").exists());
+        assert!(!input.join("a").exists());
+        assert!(input.join("notes.txt").exists());
+    }
+}
@@ -0,0 +1,522 @@
+//! The model of the SR content tree
+use dicom_object::InMemDicomObject;
+use dicom_object::code::Code;
+use std::fmt;
+
+/// The relationship between a content item and its parent,
+/// as in the _Relationship Type_ attribute.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum RelationshipType {
+    /// `CONTAINS`
+    Contains,
+    /// `HAS PROPERTIES`
+    HasProperties,
+    /// `HAS OBS CONTEXT`
+    HasObsContext,
+    /// `HAS ACQ CONTEXT`
+    HasAcqContext,
+    /// `INFERRED FROM`
+    InferredFrom,
+    /// `SELECTED FROM`
+    SelectedFrom,
+    /// `HAS CONCEPT MOD`
+    HasConceptMod,
+}
+
+impl RelationshipType {
+    /// Obtain the relationship type from its defined term,
+    /// or `None` if it is not a known relationship type.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim_end_matches([' ', '\0']) {
+            "CONTAINS" => Some(RelationshipType::Contains),
+            "HAS PROPERTIES" => Some(RelationshipType::HasProperties),
+            "HAS OBS CONTEXT" => Some(RelationshipType::HasObsContext),
+            "HAS ACQ CONTEXT" => Some(RelationshipType::HasAcqContext),
+            "INFERRED FROM" => Some(RelationshipType::InferredFrom),
+            "SELECTED FROM" => Some(RelationshipType::SelectedFrom),
+            "HAS CONCEPT MOD" => Some(RelationshipType::HasConceptMod),
+            _ => None,
+        }
+    }
+
+    /// The defined term of the relationship type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationshipType::Contains => "CONTAINS",
+            RelationshipType::HasProperties => "HAS PROPERTIES",
+            RelationshipType::HasObsContext => "HAS OBS CONTEXT",
+            RelationshipType::HasAcqContext => "HAS ACQ CONTEXT",
+            RelationshipType::InferredFrom => "INFERRED FROM",
+            RelationshipType::SelectedFrom => "SELECTED FROM",
+            RelationshipType::HasConceptMod => "HAS CONCEPT MOD",
+        }
+    }
+}
+
+impl fmt::Display for RelationshipType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The type of value of a content item,
+/// as in the _Value Type_ attribute.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub enum ValueType {
+    /// `CONTAINER`
+    Container,
+    /// `TEXT`
+    Text,
+    /// `CODE`
+    Code,
+    /// `NUM`
+    Num,
+    /// `UIDREF`
+    UidRef,
+    /// `PNAME`
+    PersonName,
+    /// `IMAGE`
+    Image,
+    /// `SCOORD`
+    Scoord,
+    /// Any other value type, such as `DATE` or `TCOORD`
+    Other(String),
+}
+
+impl ValueType {
+    /// Obtain the value type from its defined term.
+    pub fn from_code(code: &str) -> Self {
+        match code.trim_end_matches([' ', '\0']) {
+            "CONTAINER" => ValueType::Container,
+            "TEXT" => ValueType::Text,
+            "CODE" => ValueType::Code,
+            "NUM" => ValueType::Num,
+            "UIDREF" => ValueType::UidRef,
+            "PNAME" => ValueType::PersonName,
+            "IMAGE" => ValueType::Image,
+            "SCOORD" => ValueType::Scoord,
+            code => ValueType::Other(code.to_string()),
+        }
+    }
+
+    /// The defined term of the value type.
+    pub fn as_str(&self) -> &str {
+        match self {
+            ValueType::Container => "CONTAINER",
+            ValueType::Text => "TEXT",
+            ValueType::Code => "CODE",
+            ValueType::Num => "NUM",
+            ValueType::UidRef => "UIDREF",
+            ValueType::PersonName => "PNAME",
+            ValueType::Image => "IMAGE",
+            ValueType::Scoord => "SCOORD",
+            ValueType::Other(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether the content items of a container
+/// are to be interpreted separately or as a continuous text,
+/// as in the _Continuity Of Content_ attribute.
+#[derive(Debug, Default, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum Continuity {
+    /// `SEPARATE`
+    #[default]
+    Separate,
+    /// `CONTINUOUS`
+    Continuous,
+}
+
+impl Continuity {
+    /// The defined term of the continuity of content.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Continuity::Separate => "SEPARATE",
+            Continuity::Continuous => "CONTINUOUS",
+        }
+    }
+}
+
+/// The identification of the template
+/// which a container follows,
+/// as in an item of the _Content Template Sequence_.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct TemplateId {
+    /// The mapping resource defining the template, usually `DCMR`
+    pub mapping_resource: String,
+    /// The template identifier, such as `1500`
+    pub identifier: String,
+}
+
+impl TemplateId {
+    /// Identify a template of the DICOM Content Mapping Resource (`DCMR`).
+    pub fn dcmr(identifier: impl Into<String>) -> Self {
+        TemplateId {
+            mapping_resource: "DCMR".to_string(),
+            identifier: identifier.into(),
+        }
+    }
+}
+
+/// A numeric measurement and its units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// The measured value
+    pub value: f64,
+    /// The units of measurement, usually a [UCUM](dicom_object::code::ucum) code
+    pub units: Code,
+}
+
+impl Measurement {
+    /// Create a new measurement.
+    pub fn new(value: f64, units: Code) -> Self {
+        Measurement { value, units }
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.units.value)
+    }
+}
+
+/// A reference to an image, or to some of its frames.
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+pub struct ImageReference {
+    /// The SOP Class UID of the image
+    pub sop_class_uid: String,
+    /// The SOP Instance UID of the image
+    pub sop_instance_uid: String,
+    /// The frame numbers referenced, starting at 1,
+    /// or empty to reference all frames
+    pub frames: Vec<u32>,
+}
+
+impl ImageReference {
+    /// Create a reference to a whole image.
+    pub fn new(sop_class_uid: impl Into<String>, sop_instance_uid: impl Into<String>) -> Self {
+        ImageReference {
+            sop_class_uid: sop_class_uid.into(),
+            sop_instance_uid: sop_instance_uid.into(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Create a reference to the image in the given DICOM object,
+    /// or `None` if it does not have a SOP Class UID and SOP Instance UID.
+    pub fn from_object(obj: &InMemDicomObject) -> Option<Self> {
+        use dicom_dictionary_std::tags;
+
+        let uid = |tag| {
+            obj.get(tag)
+                .and_then(|e| e.to_str().ok())
+                .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string())
+        };
+        Some(ImageReference::new(
+            uid(tags::SOP_CLASS_UID)?,
+            uid(tags::SOP_INSTANCE_UID)?,
+        ))
+    }
+
+    /// Reference only the given frames of the image.
+    pub fn with_frames(mut self, frames: impl IntoIterator<Item = u32>) -> Self {
+        self.frames = frames.into_iter().collect();
+        self
+    }
+}
+
+/// The shape of spatial coordinates,
+/// as in the _Graphic Type_ attribute.
+#[derive(Debug, Copy, Clone, Eq, Hash, PartialEq)]
+pub enum GraphicType {
+    /// A single point
+    Point,
+    /// A set of unconnected points
+    Multipoint,
+    /// Connected line segments,
+    /// closed if the first and last points are the same
+    Polyline,
+    /// A circle, given by its center and a point on its perimeter
+    Circle,
+    /// An ellipse, given by the end points of its major and minor axes
+    Ellipse,
+}
+
+impl GraphicType {
+    /// Obtain the graphic type from its defined term,
+    /// or `None` if it is not a known graphic type.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim_end_matches([' ', '\0']) {
+            "POINT" => Some(GraphicType::Point),
+            "MULTIPOINT" => Some(GraphicType::Multipoint),
+            "POLYLINE" => Some(GraphicType::Polyline),
+            "CIRCLE" => Some(GraphicType::Circle),
+            "ELLIPSE" => Some(GraphicType::Ellipse),
+            _ => None,
+        }
+    }
+
+    /// The defined term of the graphic type.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphicType::Point => "POINT",
+            GraphicType::Multipoint => "MULTIPOINT",
+            GraphicType::Polyline => "POLYLINE",
+            GraphicType::Circle => "CIRCLE",
+            GraphicType::Ellipse => "ELLIPSE",
+        }
+    }
+}
+
+/// Spatial coordinates in an image,
+/// as (column, row) pairs in pixels
+/// relative to the top left corner of the image.
+///
+/// The image is referenced by a child item with the relationship type
+/// [`SelectedFrom`](RelationshipType::SelectedFrom).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialCoordinates {
+    /// The shape described by the points
+    pub graphic_type: GraphicType,
+    /// The points, as (column, row) pairs
+    pub points: Vec<[f32; 2]>,
+}
+
+impl SpatialCoordinates {
+    /// Create new spatial coordinates.
+    pub fn new(graphic_type: GraphicType, points: impl IntoIterator<Item = [f32; 2]>) -> Self {
+        SpatialCoordinates {
+            graphic_type,
+            points: points.into_iter().collect(),
+        }
+    }
+}
+
+/// The value of a content item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentValue {
+    /// A container of other content items
+    Container {
+        /// Whether the child items form a continuous text
+        continuity: Continuity,
+        /// The template followed by the container, if identified
+        template: Option<TemplateId>,
+    },
+    /// Free text
+    Text(String),
+    /// A coded concept
+    Code(Code),
+    /// A numeric measurement,
+    /// or `None` if the measured value is not available
+    Num(Option<Measurement>),
+    /// A unique identifier
+    UidRef(String),
+    /// The name of a person
+    PersonName(String),
+    /// A reference to an image
+    Image(ImageReference),
+    /// Spatial coordinates in an image
+    Scoord(SpatialCoordinates),
+    /// A reference to another content item in the tree,
+    /// by the position of each item from the root, starting at 1
+    Reference(Vec<u32>),
+    /// A value of a type not covered by this model,
+    /// kept as the original attributes of the content item
+    /// (excluding the relationship type, concept name,
+    /// and content sequence)
+    Other {
+        /// The value type
+        value_type: String,
+        /// The attributes holding the value
+        attributes: InMemDicomObject,
+    },
+}
+
+impl ContentValue {
+    /// The value type of this value,
+    /// or `None` for a reference to another content item.
+    pub fn value_type(&self) -> Option<ValueType> {
+        Some(match self {
+            ContentValue::Container { .. } => ValueType::Container,
+            ContentValue::Text(_) => ValueType::Text,
+            ContentValue::Code(_) => ValueType::Code,
+            ContentValue::Num(_) => ValueType::Num,
+            ContentValue::UidRef(_) => ValueType::UidRef,
+            ContentValue::PersonName(_) => ValueType::PersonName,
+            ContentValue::Image(_) => ValueType::Image,
+            ContentValue::Scoord(_) => ValueType::Scoord,
+            ContentValue::Reference(_) => return None,
+            ContentValue::Other { value_type, .. } => ValueType::Other(value_type.clone()),
+        })
+    }
+}
+
+/// A node of an SR content tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentItem {
+    /// The relationship with the parent item,
+    /// `None` for the root of the tree
+    pub relationship: Option<RelationshipType>,
+    /// The concept name,
+    /// which may only be absent in some items
+    /// other than the root of the tree
+    pub concept_name: Option<Code>,
+    /// The value of the item
+    pub value: ContentValue,
+    /// The child items, in order
+    pub children: Vec<ContentItem>,
+}
+
+impl ContentItem {
+    /// Create a content item with the given concept name and value
+    /// and no children.
+    pub fn new(concept_name: Code, value: ContentValue) -> Self {
+        ContentItem {
+            relationship: None,
+            concept_name: Some(concept_name),
+            value,
+            children: Vec::new(),
+        }
+    }
+
+    /// Create an empty container of separate items.
+    pub fn container(concept_name: Code) -> Self {
+        ContentItem::new(
+            concept_name,
+            ContentValue::Container {
+                continuity: Continuity::Separate,
+                template: None,
+            },
+        )
+    }
+
+    /// Create a text content item.
+    pub fn text(concept_name: Code, text: impl Into<String>) -> Self {
+        ContentItem::new(concept_name, ContentValue::Text(text.into()))
+    }
+
+    /// Create a code content item.
+    pub fn code(concept_name: Code, code: Code) -> Self {
+        ContentItem::new(concept_name, ContentValue::Code(code))
+    }
+
+    /// Create a numeric content item.
+    pub fn num(concept_name: Code, measurement: Measurement) -> Self {
+        ContentItem::new(concept_name, ContentValue::Num(Some(measurement)))
+    }
+
+    /// Create a UID reference content item.
+    pub fn uid_ref(concept_name: Code, uid: impl Into<String>) -> Self {
+        ContentItem::new(concept_name, ContentValue::UidRef(uid.into()))
+    }
+
+    /// Create a person name content item.
+    pub fn person_name(concept_name: Code, name: impl Into<String>) -> Self {
+        ContentItem::new(concept_name, ContentValue::PersonName(name.into()))
+    }
+
+    /// Create an image content item without a concept name.
+    pub fn image(image: ImageReference) -> Self {
+        ContentItem {
+            relationship: None,
+            concept_name: None,
+            value: ContentValue::Image(image),
+            children: Vec::new(),
+        }
+    }
+
+    /// Create a spatial coordinates content item
+    /// selected from the given image.
+    pub fn scoord(
+        concept_name: Code,
+        coordinates: SpatialCoordinates,
+        image: ImageReference,
+    ) -> Self {
+        ContentItem::new(concept_name, ContentValue::Scoord(coordinates))
+            .with_child(RelationshipType::SelectedFrom, ContentItem::image(image))
+    }
+
+    /// Set the template identification of a container.
+    ///
+    /// Has no effect on other content items.
+    pub fn with_template(mut self, template_id: TemplateId) -> Self {
+        if let ContentValue::Container { template, .. } = &mut self.value {
+            *template = Some(template_id);
+        }
+        self
+    }
+
+    /// Add a child item with the given relationship.
+    pub fn with_child(mut self, relationship: RelationshipType, child: ContentItem) -> Self {
+        self.push_child(relationship, child);
+        self
+    }
+
+    /// Add a child item with the given relationship.
+    pub fn push_child(&mut self, relationship: RelationshipType, mut child: ContentItem) {
+        child.relationship = Some(relationship);
+        self.children.push(child);
+    }
+
+    /// Whether the concept name of this item
+    /// identifies the same concept as the given code.
+    pub fn has_concept_name(&self, concept_name: &Code) -> bool {
+        self.concept_name
+            .as_ref()
+            .is_some_and(|code| code.is_same_concept(concept_name))
+    }
+
+    /// Find the first item in the tree, in depth-first order,
+    /// with the given concept name.
+    pub fn find(&self, concept_name: &Code) -> Option<&ContentItem> {
+        self.iter().find(|item| item.has_concept_name(concept_name))
+    }
+
+    /// Iterate over the child items with the given concept name.
+    pub fn children_named<'a>(
+        &'a self,
+        concept_name: &'a Code,
+    ) -> impl Iterator<Item = &'a ContentItem> + 'a {
+        self.children
+            .iter()
+            .filter(move |item| item.has_concept_name(concept_name))
+    }
+
+    /// Iterate over this item and all of its descendants
+    /// in depth-first order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { stack: vec![self] }
+    }
+}
+
+impl<'a> IntoIterator for &'a ContentItem {
+    type Item = &'a ContentItem;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A depth-first iterator over the items of a content tree,
+/// created by [`ContentItem::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    stack: Vec<&'a ContentItem>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a ContentItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.stack.pop()?;
+        self.stack.extend(item.children.iter().rev());
+        Some(item)
+    }
+}
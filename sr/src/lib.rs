@@ -0,0 +1,99 @@
+//! DICOM Structured Reporting
+//!
+//! This crate provides a typed model of the content tree
+//! of DICOM Structured Report (SR) documents,
+//! as per [PS3.3 section C.17.3][1].
+//! Each node of the tree is a [`ContentItem`]
+//! made of an optional concept name, a [value](ContentValue)
+//! such as text, a code, a numeric measurement,
+//! an image reference or spatial coordinates,
+//! and the child items related to it
+//! by a [relationship type](RelationshipType).
+//!
+//! - [`ContentItem::from_dataset`] reads the content tree of an SR object.
+//! - [`ContentItem::to_dataset`] converts a content tree
+//!   back to DICOM attributes.
+//! - The [`tid1500`] module builds complete SR documents
+//!   following [TID 1500 Measurement Report][2].
+//!
+//! # Example
+//!
+//! ```
+//! use dicom_object::code::{dcm, sct, ucum};
+//! use dicom_sr::{ContentItem, ContentValue, Measurement, RelationshipType};
+//!
+//! let tree = ContentItem::container(dcm::IMAGING_MEASUREMENT_REPORT)
+//!     .with_child(
+//!         RelationshipType::Contains,
+//!         ContentItem::num(sct::DIAMETER, Measurement::new(12.5, ucum::MILLIMETER)),
+//!     );
+//!
+//! // content trees are written to and read from DICOM attributes
+//! let obj = tree.to_dataset()?;
+//! let tree = ContentItem::from_dataset(&obj)?;
+//! let diameter = tree.find(&sct::DIAMETER).expect("diameter should be there");
+//! let ContentValue::Num(Some(measurement)) = &diameter.value else {
+//!     panic!("diameter should be a measurement");
+//! };
+//! assert_eq!(measurement.value, 12.5);
+//! assert_eq!(measurement.to_string(), "12.5 mm");
+//! # Ok::<(), dicom_sr::Error>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part03/sect_C.17.3.html
+//! [2]: https://dicom.nema.org/medical/dicom/current/output/chtml/part16/chapter_A.html#sect_TID_1500
+use dicom_core::Tag;
+use snafu::Snafu;
+
+mod content;
+mod read;
+pub mod tid1500;
+mod write;
+
+pub use content::{
+    ContentItem, ContentValue, Continuity, GraphicType, ImageReference, Iter, Measurement,
+    RelationshipType, SpatialCoordinates, TemplateId, ValueType,
+};
+pub use dicom_object::code::Code;
+
+/// An error which may occur when reading or writing a structured report.
+#[derive(Debug, Snafu)]
+pub struct Error(InnerError);
+
+/// Inner error type for structured reports
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub(crate) enum InnerError {
+    /// Missing attribute {tag} in content item
+    MissingAttribute { tag: Tag },
+    /// Invalid value of attribute {tag} in content item
+    InvalidAttribute { tag: Tag },
+    /// Unknown value type `{value_type}`
+    UnknownValueType { value_type: String },
+    /// Unknown relationship type `{value}`
+    UnknownRelationshipType { value: String },
+    /// Unknown graphic type `{value}`
+    UnknownGraphicType { value: String },
+    #[snafu(display("Invalid code in {tag}"))]
+    InvalidCode {
+        tag: Tag,
+        source: dicom_object::code::CodeError,
+    },
+    /// Measured value {value} is not a finite number
+    InvalidNumericValue {
+        value: f64,
+        source: dicom_core::value::decimal::Error,
+    },
+    /// Missing procedure reported in measurement report
+    MissingProcedureReported,
+    /// Missing observer in measurement report
+    MissingObserver,
+    /// Could not create the file meta group
+    CreateMeta {
+        #[snafu(source(from(dicom_object::WithMetaError, Box::new)))]
+        source: Box<dicom_object::WithMetaError>,
+    },
+}
+
+/// Alias for the result of reading or writing a structured report.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,271 @@
+//! Reading content trees from DICOM attributes
+use dicom_core::Tag;
+use dicom_core::header::Header;
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use dicom_object::code::Code;
+use snafu::{OptionExt, ResultExt};
+
+use crate::{
+    ContentItem, ContentValue, Continuity, GraphicType, ImageReference, InvalidAttributeSnafu,
+    InvalidCodeSnafu, Measurement, MissingAttributeSnafu, RelationshipType, Result,
+    SpatialCoordinates, TemplateId, UnknownGraphicTypeSnafu, UnknownRelationshipTypeSnafu,
+};
+
+/// The attributes describing the content item itself
+/// rather than its value, or holding its children
+const STRUCTURE_TAGS: &[Tag] = &[
+    tags::RELATIONSHIP_TYPE,
+    tags::VALUE_TYPE,
+    tags::CONCEPT_NAME_CODE_SEQUENCE,
+    tags::CONTENT_SEQUENCE,
+    tags::OBSERVATION_UID,
+];
+
+impl ContentItem {
+    /// Read a content tree from the given data set,
+    /// which is either an SR document
+    /// or an item of a _Content Sequence_.
+    ///
+    /// Value types not covered by [`ContentValue`]
+    /// are kept as [`ContentValue::Other`].
+    pub fn from_dataset(obj: &InMemDicomObject) -> Result<Self> {
+        let relationship = match text(obj, tags::RELATIONSHIP_TYPE)? {
+            Some(value) => Some(
+                RelationshipType::from_code(&value)
+                    .context(UnknownRelationshipTypeSnafu { value })?,
+            ),
+            None => None,
+        };
+        let concept_name = code(obj, tags::CONCEPT_NAME_CODE_SEQUENCE)?;
+
+        let value = match text(obj, tags::VALUE_TYPE)? {
+            Some(value_type) => read_value(obj, value_type)?,
+            None => {
+                // a content item by reference has no value type
+                let path = obj
+                    .get(tags::REFERENCED_CONTENT_ITEM_IDENTIFIER)
+                    .context(MissingAttributeSnafu {
+                        tag: tags::VALUE_TYPE,
+                    })?
+                    .to_multi_int::<u32>()
+                    .ok()
+                    .context(InvalidAttributeSnafu {
+                        tag: tags::REFERENCED_CONTENT_ITEM_IDENTIFIER,
+                    })?;
+                ContentValue::Reference(path)
+            }
+        };
+
+        let children = match obj.get(tags::CONTENT_SEQUENCE).and_then(|e| e.items()) {
+            Some(items) => items
+                .iter()
+                .map(ContentItem::from_dataset)
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(ContentItem {
+            relationship,
+            concept_name,
+            value,
+            children,
+        })
+    }
+}
+
+fn read_value(obj: &InMemDicomObject, value_type: String) -> Result<ContentValue> {
+    Ok(match value_type.as_str() {
+        "CONTAINER" => ContentValue::Container {
+            continuity: match text(obj, tags::CONTINUITY_OF_CONTENT)?.as_deref() {
+                Some("CONTINUOUS") => Continuity::Continuous,
+                _ => Continuity::Separate,
+            },
+            template: first_item(obj, tags::CONTENT_TEMPLATE_SEQUENCE)
+                .map(|item| -> Result<_> {
+                    Ok(TemplateId {
+                        mapping_resource: required_text(item, tags::MAPPING_RESOURCE)?,
+                        identifier: required_text(item, tags::TEMPLATE_IDENTIFIER)?,
+                    })
+                })
+                .transpose()?,
+        },
+        "TEXT" => ContentValue::Text(required_text(obj, tags::TEXT_VALUE)?),
+        "CODE" => ContentValue::Code(code(obj, tags::CONCEPT_CODE_SEQUENCE)?.context(
+            MissingAttributeSnafu {
+                tag: tags::CONCEPT_CODE_SEQUENCE,
+            },
+        )?),
+        "NUM" => ContentValue::Num(match first_item(obj, tags::MEASURED_VALUE_SEQUENCE) {
+            Some(item) => Some(Measurement {
+                value: item
+                    .get(tags::NUMERIC_VALUE)
+                    .context(MissingAttributeSnafu {
+                        tag: tags::NUMERIC_VALUE,
+                    })?
+                    .to_float64()
+                    .ok()
+                    .context(InvalidAttributeSnafu {
+                        tag: tags::NUMERIC_VALUE,
+                    })?,
+                units: code(item, tags::MEASUREMENT_UNITS_CODE_SEQUENCE)?.context(
+                    MissingAttributeSnafu {
+                        tag: tags::MEASUREMENT_UNITS_CODE_SEQUENCE,
+                    },
+                )?,
+            }),
+            None => None,
+        }),
+        "UIDREF" => ContentValue::UidRef(required_text(obj, tags::UID)?),
+        "PNAME" => ContentValue::PersonName(required_text(obj, tags::PERSON_NAME)?),
+        "IMAGE" => {
+            let item =
+                first_item(obj, tags::REFERENCED_SOP_SEQUENCE).context(MissingAttributeSnafu {
+                    tag: tags::REFERENCED_SOP_SEQUENCE,
+                })?;
+            let frames = match item.get(tags::REFERENCED_FRAME_NUMBER) {
+                Some(e) => e
+                    .to_multi_int::<u32>()
+                    .ok()
+                    .context(InvalidAttributeSnafu {
+                        tag: tags::REFERENCED_FRAME_NUMBER,
+                    })?,
+                None => Vec::new(),
+            };
+            ContentValue::Image(ImageReference {
+                sop_class_uid: required_text(item, tags::REFERENCED_SOP_CLASS_UID)?,
+                sop_instance_uid: required_text(item, tags::REFERENCED_SOP_INSTANCE_UID)?,
+                frames,
+            })
+        }
+        "SCOORD" => {
+            let value = required_text(obj, tags::GRAPHIC_TYPE)?;
+            let graphic_type =
+                GraphicType::from_code(&value).context(UnknownGraphicTypeSnafu { value })?;
+            let data = obj
+                .get(tags::GRAPHIC_DATA)
+                .context(MissingAttributeSnafu {
+                    tag: tags::GRAPHIC_DATA,
+                })?
+                .to_multi_float32()
+                .ok()
+                .filter(|data| data.len() % 2 == 0)
+                .context(InvalidAttributeSnafu {
+                    tag: tags::GRAPHIC_DATA,
+                })?;
+            ContentValue::Scoord(SpatialCoordinates {
+                graphic_type,
+                points: data.chunks_exact(2).map(|p| [p[0], p[1]]).collect(),
+            })
+        }
+        _ => ContentValue::Other {
+            attributes: InMemDicomObject::from_element_iter(
+                obj.iter()
+                    .filter(|e| !STRUCTURE_TAGS.contains(&e.tag()))
+                    .cloned(),
+            ),
+            value_type,
+        },
+    })
+}
+
+/// Read the text value of an attribute, if present,
+/// without padding.
+fn text(obj: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    obj.get(tag)
+        .map(|e| {
+            e.to_str()
+                .map(|s| s.trim_end_matches([' ', '\0']).to_string())
+                .ok()
+                .context(InvalidAttributeSnafu { tag })
+        })
+        .transpose()
+        .map_err(Into::into)
+}
+
+fn required_text(obj: &InMemDicomObject, tag: Tag) -> Result<String> {
+    Ok(text(obj, tag)?.context(MissingAttributeSnafu { tag })?)
+}
+
+fn first_item(obj: &InMemDicomObject, tag: Tag) -> Option<&InMemDicomObject> {
+    obj.get(tag).and_then(|e| e.items()).and_then(|i| i.first())
+}
+
+/// Read the code in a code sequence, if present and not empty.
+fn code(obj: &InMemDicomObject, tag: Tag) -> Result<Option<Code>> {
+    first_item(obj, tag)
+        .map(|item| Code::from_item(item).context(InvalidCodeSnafu { tag }))
+        .transpose()
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
+    use dicom_object::code::{Code, dcm};
+
+    use crate::{ContentItem, ContentValue, RelationshipType, ValueType};
+
+    #[test]
+    fn read_other_value_types_and_references() {
+        let date = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::RELATIONSHIP_TYPE, VR::CS, "HAS OBS CONTEXT"),
+            DataElement::new(tags::VALUE_TYPE, VR::CS, "DATE"),
+            Code::new("111060", "DCM", "Study Date")
+                .to_sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE),
+            DataElement::new(tags::DATE, VR::DA, "20240131"),
+        ]);
+        let reference = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::RELATIONSHIP_TYPE, VR::CS, "INFERRED FROM "),
+            DataElement::new(
+                tags::REFERENCED_CONTENT_ITEM_IDENTIFIER,
+                VR::UL,
+                dicom_core::PrimitiveValue::U32([1, 2].into()),
+            ),
+        ]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::VALUE_TYPE, VR::CS, "CONTAINER"),
+            dcm::FINDINGS.to_sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE),
+            DataElement::new(
+                tags::CONTENT_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![date, reference]),
+            ),
+        ]);
+
+        let tree = ContentItem::from_dataset(&obj).unwrap();
+        assert_eq!(tree.relationship, None);
+        let [date_item, reference_item] = &tree.children[..] else {
+            panic!("expected 2 child items");
+        };
+        assert_eq!(
+            date_item.value.value_type(),
+            Some(ValueType::Other("DATE".to_string()))
+        );
+        let ContentValue::Other { attributes, .. } = &date_item.value else {
+            panic!("expected other value");
+        };
+        assert_eq!(attributes.iter().count(), 1);
+        assert_eq!(
+            &ContentItem::from_dataset(&date_item.to_dataset().unwrap()).unwrap(),
+            date_item
+        );
+
+        assert_eq!(
+            reference_item.relationship,
+            Some(RelationshipType::InferredFrom)
+        );
+        assert_eq!(reference_item.value, ContentValue::Reference(vec![1, 2]));
+
+        // unknown relationship types are rejected
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::RELATIONSHIP_TYPE, VR::CS, "REFERS TO"),
+            DataElement::new(tags::VALUE_TYPE, VR::CS, "TEXT"),
+            DataElement::new(tags::TEXT_VALUE, VR::UT, "text"),
+        ]);
+        assert!(ContentItem::from_dataset(&obj).is_err());
+    }
+}
@@ -0,0 +1,561 @@
+//! Building measurement reports
+//! following [TID 1500 Measurement Report][1].
+//!
+//! A [`MeasurementReportBuilder`] collects
+//! the procedure reported, the observers,
+//! the images measured,
+//! and groups of measurements about tracked findings,
+//! and creates a new _Comprehensive SR_ document with the
+//! content tree of the template.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::code::{dcm, sct, ucum};
+//! use dicom_object::open_file;
+//! use dicom_sr::tid1500::{MeasurementGroup, MeasurementReportBuilder};
+//! use dicom_sr::{GraphicType, ImageReference, Measurement, SpatialCoordinates};
+//!
+//! let ct = open_file("ct.dcm")?;
+//! let image = ImageReference::from_object(&ct).expect("image should have UIDs");
+//! let report = MeasurementReportBuilder::new()
+//!     .with_template(&ct)
+//!     .with_procedure_reported(dcm::COMPUTED_TOMOGRAPHY)
+//!     .with_person_observer("Doe^Jane")
+//!     .with_image(image.clone())
+//!     .with_measurement_group(
+//!         MeasurementGroup::new("Nodule 1")
+//!             .with_finding(sct::NODULE)
+//!             .with_finding_site(sct::LUNG)
+//!             .with_measurement_on_image(
+//!                 sct::DIAMETER,
+//!                 Measurement::new(8.5, ucum::MILLIMETER),
+//!                 SpatialCoordinates::new(GraphicType::Polyline, [[100., 120.], [108., 124.]]),
+//!                 image,
+//!             ),
+//!     )
+//!     .build()?;
+//! report.write_to_file("report.dcm")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [1]: https://dicom.nema.org/medical/dicom/current/output/chtml/part16/chapter_A.html#sect_TID_1500
+use dicom_core::uid::UidGenerator;
+use dicom_core::value::DataSetSequence;
+use dicom_core::{DataElement, Length, PrimitiveValue, Tag, VR, chrono};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::code::{Code, dcm, sct};
+use dicom_object::mem::InMemElement;
+use dicom_object::{DefaultDicomObject, FileMetaTableBuilder, InMemDicomObject};
+use snafu::{ResultExt, ensure};
+
+use crate::{
+    ContentItem, CreateMetaSnafu, ImageReference, Measurement, MissingObserverSnafu,
+    MissingProcedureReportedSnafu, RelationshipType, Result, SpatialCoordinates, TemplateId,
+};
+
+/// (en-US, RFC5646, "English (United States)"),
+/// the default language of content items
+pub const ENGLISH: Code = Code::new_static("en-US", "RFC5646", "English (United States)");
+
+/// (111030, DCM, "Image Region")
+const IMAGE_REGION: Code = Code::new_static("111030", "DCM", "Image Region");
+
+/// The attributes of the _Patient_ and _General Study_ modules
+/// (plus the character set they are encoded in)
+/// which are copied from a template object.
+const CONTEXT_TAGS: &[Tag] = &[
+    tags::SPECIFIC_CHARACTER_SET,
+    tags::PATIENT_NAME,
+    tags::PATIENT_ID,
+    tags::ISSUER_OF_PATIENT_ID,
+    tags::PATIENT_BIRTH_DATE,
+    tags::PATIENT_SEX,
+    tags::PATIENT_AGE,
+    tags::STUDY_INSTANCE_UID,
+    tags::STUDY_DATE,
+    tags::STUDY_TIME,
+    tags::REFERRING_PHYSICIAN_NAME,
+    tags::STUDY_ID,
+    tags::ACCESSION_NUMBER,
+    tags::STUDY_DESCRIPTION,
+];
+
+/// The type 2 attributes of the Comprehensive SR IOD,
+/// which are added empty if not known.
+const TYPE2_TAGS: &[(Tag, VR)] = &[
+    (tags::PATIENT_NAME, VR::PN),
+    (tags::PATIENT_ID, VR::LO),
+    (tags::PATIENT_BIRTH_DATE, VR::DA),
+    (tags::PATIENT_SEX, VR::CS),
+    (tags::STUDY_DATE, VR::DA),
+    (tags::STUDY_TIME, VR::TM),
+    (tags::REFERRING_PHYSICIAN_NAME, VR::PN),
+    (tags::STUDY_ID, VR::SH),
+    (tags::ACCESSION_NUMBER, VR::SH),
+    (tags::MANUFACTURER, VR::LO),
+];
+
+/// A group of measurements about a single finding,
+/// following TID 1501 _Measurement and Qualitative Evaluation Group_.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementGroup {
+    tracking_identifier: String,
+    tracking_uid: Option<String>,
+    finding: Option<Code>,
+    finding_site: Option<Code>,
+    measurements: Vec<ContentItem>,
+}
+
+impl MeasurementGroup {
+    /// Create a new measurement group
+    /// with the given human readable tracking identifier,
+    /// such as `Nodule 1`.
+    pub fn new(tracking_identifier: impl Into<String>) -> Self {
+        MeasurementGroup {
+            tracking_identifier: tracking_identifier.into(),
+            tracking_uid: None,
+            finding: None,
+            finding_site: None,
+            measurements: Vec::new(),
+        }
+    }
+
+    /// Set the tracking unique identifier of the finding.
+    ///
+    /// If not set, a new UID is generated.
+    pub fn with_tracking_uid(mut self, uid: impl Into<String>) -> Self {
+        self.tracking_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the type of finding measured, such as (27925004, SCT, "Nodule").
+    pub fn with_finding(mut self, finding: Code) -> Self {
+        self.finding = Some(finding);
+        self
+    }
+
+    /// Set the anatomic location of the finding, such as (39607008, SCT, "Lung").
+    pub fn with_finding_site(mut self, finding_site: Code) -> Self {
+        self.finding_site = Some(finding_site);
+        self
+    }
+
+    /// Add a measurement of the finding.
+    pub fn with_measurement(mut self, concept_name: Code, measurement: Measurement) -> Self {
+        self.measurements
+            .push(ContentItem::num(concept_name, measurement));
+        self
+    }
+
+    /// Add a measurement of the finding,
+    /// inferred from the given region of an image.
+    pub fn with_measurement_on_image(
+        mut self,
+        concept_name: Code,
+        measurement: Measurement,
+        region: SpatialCoordinates,
+        image: ImageReference,
+    ) -> Self {
+        self.measurements
+            .push(ContentItem::num(concept_name, measurement).with_child(
+                RelationshipType::InferredFrom,
+                ContentItem::scoord(IMAGE_REGION, region, image),
+            ));
+        self
+    }
+
+    fn into_content(self, uid_generator: &UidGenerator) -> ContentItem {
+        let mut group = ContentItem::container(dcm::MEASUREMENT_GROUP)
+            .with_template(TemplateId::dcmr("1501"))
+            .with_child(
+                RelationshipType::HasObsContext,
+                ContentItem::text(dcm::TRACKING_IDENTIFIER, self.tracking_identifier),
+            )
+            .with_child(
+                RelationshipType::HasObsContext,
+                ContentItem::uid_ref(
+                    dcm::TRACKING_UNIQUE_IDENTIFIER,
+                    self.tracking_uid
+                        .unwrap_or_else(|| uid_generator.generate()),
+                ),
+            );
+        if let Some(finding) = self.finding {
+            group.push_child(
+                RelationshipType::Contains,
+                ContentItem::code(dcm::FINDING, finding),
+            );
+        }
+        if let Some(finding_site) = self.finding_site {
+            group.push_child(
+                RelationshipType::HasConceptMod,
+                ContentItem::code(sct::FINDING_SITE, finding_site),
+            );
+        }
+        for measurement in self.measurements {
+            group.push_child(RelationshipType::Contains, measurement);
+        }
+        group
+    }
+}
+
+/// An observer of the measurements, as per TID 1002.
+#[derive(Debug, Clone, PartialEq)]
+enum Observer {
+    Person { name: String },
+    Device { uid: String, name: Option<String> },
+}
+
+/// A builder for a new SR document
+/// following TID 1500 _Measurement Report_.
+///
+/// See the [module-level documentation](crate::tid1500) for an example.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeasurementReportBuilder {
+    sop_instance_uid: Option<String>,
+    series_instance_uid: Option<String>,
+    study_instance_uid: Option<String>,
+    uid_generator: UidGenerator,
+    language: Option<Code>,
+    procedures: Vec<Code>,
+    observers: Vec<Observer>,
+    images: Vec<ImageReference>,
+    groups: Vec<MeasurementGroup>,
+    context: Vec<InMemElement>,
+}
+
+impl MeasurementReportBuilder {
+    /// Create a new measurement report builder,
+    /// with no procedure, observer or measurements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the patient and study attributes
+    /// (_Patient Name_, _Patient ID_, _Study Instance UID_,
+    /// _Accession Number_, and so on)
+    /// from the given DICOM object,
+    /// usually one of the images measured,
+    /// so that the report belongs to the same study.
+    pub fn with_template(mut self, template: &InMemDicomObject) -> Self {
+        self.context = CONTEXT_TAGS
+            .iter()
+            .filter_map(|tag| template.get(*tag).cloned())
+            .collect();
+        self
+    }
+
+    /// Set the SOP instance UID of the report.
+    pub fn with_sop_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.sop_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the series instance UID of the report.
+    pub fn with_series_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.series_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the study instance UID of the report,
+    /// overriding the one in the template.
+    pub fn with_study_instance_uid(mut self, uid: impl Into<String>) -> Self {
+        self.study_instance_uid = Some(uid.into());
+        self
+    }
+
+    /// Set the generator of the UIDs which are not given,
+    /// such as one under an organization root.
+    pub fn with_uid_generator(mut self, uid_generator: UidGenerator) -> Self {
+        self.uid_generator = uid_generator;
+        self
+    }
+
+    /// Set the language of the report content
+    /// ([`ENGLISH`] by default).
+    pub fn with_language(mut self, language: Code) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Add a procedure reported, such as (CT, DCM, "Computed Tomography").
+    ///
+    /// At least one procedure is required.
+    pub fn with_procedure_reported(mut self, procedure: Code) -> Self {
+        self.procedures.push(procedure);
+        self
+    }
+
+    /// Add a person observer by name.
+    ///
+    /// At least one observer is required.
+    pub fn with_person_observer(mut self, name: impl Into<String>) -> Self {
+        self.observers.push(Observer::Person { name: name.into() });
+        self
+    }
+
+    /// Add a device observer by its UID and optional name.
+    ///
+    /// At least one observer is required.
+    pub fn with_device_observer(mut self, uid: impl Into<String>, name: Option<String>) -> Self {
+        self.observers.push(Observer::Device {
+            uid: uid.into(),
+            name,
+        });
+        self
+    }
+
+    /// Add an image to the image library of the report.
+    pub fn with_image(mut self, image: ImageReference) -> Self {
+        self.images.push(image);
+        self
+    }
+
+    /// Add a group of measurements.
+    pub fn with_measurement_group(mut self, group: MeasurementGroup) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    /// Create the content tree of the report.
+    ///
+    /// Fails if no procedure reported or observer was given.
+    pub fn content_tree(&self) -> Result<ContentItem> {
+        ensure!(!self.procedures.is_empty(), MissingProcedureReportedSnafu);
+        ensure!(!self.observers.is_empty(), MissingObserverSnafu);
+
+        let mut root = ContentItem::container(dcm::IMAGING_MEASUREMENT_REPORT)
+            .with_template(TemplateId::dcmr("1500"))
+            .with_child(
+                RelationshipType::HasConceptMod,
+                ContentItem::code(
+                    dcm::LANGUAGE_OF_CONTENT_ITEM_AND_DESCENDANTS,
+                    self.language.clone().unwrap_or(ENGLISH),
+                ),
+            );
+
+        for observer in &self.observers {
+            match observer {
+                Observer::Person { name } => {
+                    root.push_child(
+                        RelationshipType::HasObsContext,
+                        ContentItem::code(dcm::OBSERVER_TYPE, dcm::PERSON),
+                    );
+                    root.push_child(
+                        RelationshipType::HasObsContext,
+                        ContentItem::person_name(dcm::PERSON_OBSERVER_NAME, name.clone()),
+                    );
+                }
+                Observer::Device { uid, name } => {
+                    root.push_child(
+                        RelationshipType::HasObsContext,
+                        ContentItem::code(dcm::OBSERVER_TYPE, dcm::DEVICE),
+                    );
+                    root.push_child(
+                        RelationshipType::HasObsContext,
+                        ContentItem::uid_ref(dcm::DEVICE_OBSERVER_UID, uid.clone()),
+                    );
+                    if let Some(name) = name {
+                        root.push_child(
+                            RelationshipType::HasObsContext,
+                            ContentItem::text(dcm::DEVICE_OBSERVER_NAME, name.clone()),
+                        );
+                    }
+                }
+            }
+        }
+
+        for procedure in &self.procedures {
+            root.push_child(
+                RelationshipType::HasConceptMod,
+                ContentItem::code(dcm::PROCEDURE_REPORTED, procedure.clone()),
+            );
+        }
+
+        let mut library = ContentItem::container(dcm::IMAGE_LIBRARY);
+        if !self.images.is_empty() {
+            let mut group = ContentItem::container(dcm::IMAGE_LIBRARY_GROUP);
+            for image in &self.images {
+                group.push_child(
+                    RelationshipType::Contains,
+                    ContentItem::image(image.clone()),
+                );
+            }
+            library.push_child(RelationshipType::Contains, group);
+        }
+        root.push_child(RelationshipType::Contains, library);
+
+        if !self.groups.is_empty() {
+            let mut measurements = ContentItem::container(dcm::IMAGING_MEASUREMENTS);
+            for group in &self.groups {
+                measurements.push_child(
+                    RelationshipType::Contains,
+                    group.clone().into_content(&self.uid_generator),
+                );
+            }
+            root.push_child(RelationshipType::Contains, measurements);
+        }
+
+        Ok(root)
+    }
+
+    /// Create the SR document,
+    /// encoded in Explicit VR Little Endian.
+    ///
+    /// Fails if no procedure reported or observer was given.
+    pub fn build(&self) -> Result<DefaultDicomObject> {
+        let mut obj = self.content_tree()?.to_dataset()?;
+
+        for elem in &self.context {
+            obj.put(elem.clone());
+        }
+        for (tag, vr) in TYPE2_TAGS {
+            if obj.get(*tag).is_none() {
+                obj.put(DataElement::new(*tag, *vr, PrimitiveValue::Empty));
+            }
+        }
+
+        let study_instance_uid = match &self.study_instance_uid {
+            Some(uid) => uid.clone(),
+            None => obj
+                .get(tags::STUDY_INSTANCE_UID)
+                .and_then(|e| e.to_str().ok())
+                .map(|uid| uid.to_string())
+                .unwrap_or_else(|| self.uid_generator.generate()),
+        };
+        let series_instance_uid = self
+            .series_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+        let sop_instance_uid = self
+            .sop_instance_uid
+            .clone()
+            .unwrap_or_else(|| self.uid_generator.generate());
+
+        let now = chrono::Local::now();
+        let date = now.format("%Y%m%d").to_string();
+        let time = now.format("%H%M%S").to_string();
+
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+        let empty_sequence = |tag| {
+            DataElement::new(
+                tag,
+                VR::SQ,
+                DataSetSequence::<InMemDicomObject>::new(Vec::new(), Length::UNDEFINED),
+            )
+        };
+        for elem in [
+            // SOP Common
+            str_elem(tags::SOP_CLASS_UID, VR::UI, uids::COMPREHENSIVE_SR_STORAGE),
+            str_elem(tags::SOP_INSTANCE_UID, VR::UI, &sop_instance_uid),
+            str_elem(tags::INSTANCE_CREATION_DATE, VR::DA, &date),
+            str_elem(tags::INSTANCE_CREATION_TIME, VR::TM, &time),
+            // General Study
+            str_elem(tags::STUDY_INSTANCE_UID, VR::UI, &study_instance_uid),
+            // SR Document Series
+            str_elem(tags::MODALITY, VR::CS, "SR"),
+            str_elem(tags::SERIES_INSTANCE_UID, VR::UI, &series_instance_uid),
+            str_elem(tags::SERIES_NUMBER, VR::IS, "1"),
+            empty_sequence(tags::REFERENCED_PERFORMED_PROCEDURE_STEP_SEQUENCE),
+            // SR Document General
+            str_elem(tags::INSTANCE_NUMBER, VR::IS, "1"),
+            str_elem(tags::COMPLETION_FLAG, VR::CS, "COMPLETE"),
+            str_elem(tags::VERIFICATION_FLAG, VR::CS, "UNVERIFIED"),
+            str_elem(tags::CONTENT_DATE, VR::DA, &date),
+            str_elem(tags::CONTENT_TIME, VR::TM, &time),
+            empty_sequence(tags::PERFORMED_PROCEDURE_CODE_SEQUENCE),
+        ] {
+            obj.put(elem);
+        }
+
+        Ok(obj
+            .with_meta(FileMetaTableBuilder::new().transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN))
+            .context(CreateMetaSnafu)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentValue, GraphicType};
+    use dicom_object::code::ucum;
+
+    #[test]
+    fn build_measurement_report() {
+        let image = ImageReference::new(uids::CT_IMAGE_STORAGE, "2.25.10");
+        let ct = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_ID, VR::LO, "P123"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.20"),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+        ]);
+
+        let builder = MeasurementReportBuilder::new()
+            .with_template(&ct)
+            .with_sop_instance_uid("2.25.30");
+        assert!(builder.build().is_err());
+
+        let report = builder
+            .with_procedure_reported(dcm::COMPUTED_TOMOGRAPHY)
+            .with_device_observer("2.25.40", Some("Nodule detector".to_string()))
+            .with_image(image.clone())
+            .with_measurement_group(
+                MeasurementGroup::new("Nodule 1")
+                    .with_tracking_uid("2.25.50")
+                    .with_finding(sct::NODULE)
+                    .with_finding_site(sct::LUNG)
+                    .with_measurement_on_image(
+                        sct::DIAMETER,
+                        Measurement::new(8.5, ucum::MILLIMETER),
+                        SpatialCoordinates::new(GraphicType::Polyline, [[1., 2.], [9., 2.]]),
+                        image.clone(),
+                    ),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(report.meta().media_storage_sop_instance_uid(), "2.25.30");
+        let str_of = |tag| report.get(tag).unwrap().to_str().unwrap().into_owned();
+        assert_eq!(str_of(tags::SOP_CLASS_UID), uids::COMPREHENSIVE_SR_STORAGE);
+        assert_eq!(str_of(tags::MODALITY), "SR");
+        assert_eq!(str_of(tags::PATIENT_ID), "P123");
+        assert_eq!(str_of(tags::STUDY_INSTANCE_UID), "2.25.20");
+        assert!(report.get(tags::PATIENT_NAME).is_some());
+
+        let tree = ContentItem::from_dataset(&report).unwrap();
+        assert!(tree.has_concept_name(&dcm::IMAGING_MEASUREMENT_REPORT));
+        assert_eq!(
+            tree.value,
+            ContentValue::Container {
+                continuity: Default::default(),
+                template: Some(TemplateId::dcmr("1500")),
+            }
+        );
+        let observer_type = tree.find(&dcm::OBSERVER_TYPE).unwrap();
+        assert_eq!(observer_type.value, ContentValue::Code(dcm::DEVICE));
+
+        let library = tree.find(&dcm::IMAGE_LIBRARY).unwrap();
+        assert_eq!(
+            library.children[0].children[0].value,
+            ContentValue::Image(image)
+        );
+
+        let group = tree
+            .find(&dcm::IMAGING_MEASUREMENTS)
+            .and_then(|m| m.find(&dcm::MEASUREMENT_GROUP))
+            .unwrap();
+        assert_eq!(
+            group.find(&dcm::TRACKING_UNIQUE_IDENTIFIER).unwrap().value,
+            ContentValue::UidRef("2.25.50".to_string())
+        );
+        let diameter = group.find(&sct::DIAMETER).unwrap();
+        assert_eq!(
+            diameter.value,
+            ContentValue::Num(Some(Measurement::new(8.5, ucum::MILLIMETER)))
+        );
+        assert_eq!(
+            diameter.children[0].relationship,
+            Some(RelationshipType::InferredFrom)
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Writing content trees to DICOM attributes
+use dicom_core::value::DataSetSequence;
+use dicom_core::value::decimal::DecimalString;
+use dicom_core::{DataElement, Length, PrimitiveValue, VR};
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::ResultExt;
+
+use crate::{ContentItem, ContentValue, InvalidNumericValueSnafu, Result};
+
+impl ContentItem {
+    /// Convert this content tree into DICOM attributes.
+    ///
+    /// The attributes of the root of the tree
+    /// are the content attributes of an SR document,
+    /// and those of its descendants
+    /// are nested in each _Content Sequence_.
+    ///
+    /// Fails if a numeric value is not a finite number.
+    pub fn to_dataset(&self) -> Result<InMemDicomObject> {
+        let mut obj = InMemDicomObject::new_empty();
+        let str_elem =
+            |tag, vr, value: &str| DataElement::new(tag, vr, PrimitiveValue::from(value));
+
+        if let Some(relationship) = self.relationship {
+            obj.put(str_elem(
+                tags::RELATIONSHIP_TYPE,
+                VR::CS,
+                relationship.as_str(),
+            ));
+        }
+        if let Some(value_type) = self.value.value_type() {
+            obj.put(str_elem(tags::VALUE_TYPE, VR::CS, value_type.as_str()));
+        }
+        if let Some(concept_name) = &self.concept_name {
+            obj.put(concept_name.to_sequence_element(tags::CONCEPT_NAME_CODE_SEQUENCE));
+        }
+
+        match &self.value {
+            ContentValue::Container {
+                continuity,
+                template,
+            } => {
+                obj.put(str_elem(
+                    tags::CONTINUITY_OF_CONTENT,
+                    VR::CS,
+                    continuity.as_str(),
+                ));
+                if let Some(template) = template {
+                    let item = InMemDicomObject::from_element_iter([
+                        str_elem(tags::MAPPING_RESOURCE, VR::CS, &template.mapping_resource),
+                        str_elem(tags::TEMPLATE_IDENTIFIER, VR::CS, &template.identifier),
+                    ]);
+                    obj.put(sequence(tags::CONTENT_TEMPLATE_SEQUENCE, vec![item]));
+                }
+            }
+            ContentValue::Text(text) => {
+                obj.put(str_elem(tags::TEXT_VALUE, VR::UT, text));
+            }
+            ContentValue::Code(code) => {
+                obj.put(code.to_sequence_element(tags::CONCEPT_CODE_SEQUENCE));
+            }
+            ContentValue::Num(measurement) => {
+                let items = match measurement {
+                    Some(measurement) => {
+                        let value = DecimalString::from_f64(measurement.value).context(
+                            InvalidNumericValueSnafu {
+                                value: measurement.value,
+                            },
+                        )?;
+                        vec![InMemDicomObject::from_element_iter([
+                            DataElement::new(
+                                tags::NUMERIC_VALUE,
+                                VR::DS,
+                                PrimitiveValue::from(value),
+                            ),
+                            measurement
+                                .units
+                                .to_sequence_element(tags::MEASUREMENT_UNITS_CODE_SEQUENCE),
+                        ])]
+                    }
+                    None => Vec::new(),
+                };
+                obj.put(sequence(tags::MEASURED_VALUE_SEQUENCE, items));
+            }
+            ContentValue::UidRef(uid) => {
+                obj.put(str_elem(tags::UID, VR::UI, uid));
+            }
+            ContentValue::PersonName(name) => {
+                obj.put(str_elem(tags::PERSON_NAME, VR::PN, name));
+            }
+            ContentValue::Image(image) => {
+                let mut item = InMemDicomObject::from_element_iter([
+                    str_elem(tags::REFERENCED_SOP_CLASS_UID, VR::UI, &image.sop_class_uid),
+                    str_elem(
+                        tags::REFERENCED_SOP_INSTANCE_UID,
+                        VR::UI,
+                        &image.sop_instance_uid,
+                    ),
+                ]);
+                if !image.frames.is_empty() {
+                    item.put(DataElement::new(
+                        tags::REFERENCED_FRAME_NUMBER,
+                        VR::IS,
+                        PrimitiveValue::Strs(image.frames.iter().map(u32::to_string).collect()),
+                    ));
+                }
+                obj.put(sequence(tags::REFERENCED_SOP_SEQUENCE, vec![item]));
+            }
+            ContentValue::Scoord(coordinates) => {
+                obj.put(str_elem(
+                    tags::GRAPHIC_TYPE,
+                    VR::CS,
+                    coordinates.graphic_type.as_str(),
+                ));
+                obj.put(DataElement::new(
+                    tags::GRAPHIC_DATA,
+                    VR::FL,
+                    PrimitiveValue::F32(coordinates.points.iter().flatten().copied().collect()),
+                ));
+            }
+            ContentValue::Reference(path) => {
+                obj.put(DataElement::new(
+                    tags::REFERENCED_CONTENT_ITEM_IDENTIFIER,
+                    VR::UL,
+                    PrimitiveValue::U32(path.iter().copied().collect()),
+                ));
+            }
+            ContentValue::Other { attributes, .. } => {
+                for elem in attributes {
+                    obj.put(elem.clone());
+                }
+            }
+        }
+
+        if !self.children.is_empty() {
+            let items = self
+                .children
+                .iter()
+                .map(ContentItem::to_dataset)
+                .collect::<Result<_>>()?;
+            obj.put(sequence(tags::CONTENT_SEQUENCE, items));
+        }
+
+        Ok(obj)
+    }
+}
+
+fn sequence(tag: dicom_core::Tag, items: Vec<InMemDicomObject>) -> dicom_object::mem::InMemElement {
+    DataElement::new(tag, VR::SQ, DataSetSequence::new(items, Length::UNDEFINED))
+}
+
+#[cfg(test)]
+mod tests {
+    use dicom_object::code::{Code, dcm, sct, ucum};
+
+    use crate::{
+        ContentItem, ContentValue, GraphicType, ImageReference, Measurement, RelationshipType,
+        SpatialCoordinates, TemplateId,
+    };
+
+    #[test]
+    fn content_tree_round_trip() {
+        let image = ImageReference::new("1.2.840.10008.5.1.4.1.1.2", "2.25.1").with_frames([2, 3]);
+        let tree = ContentItem::container(dcm::IMAGING_MEASUREMENT_REPORT)
+            .with_template(TemplateId::dcmr("1500"))
+            .with_child(
+                RelationshipType::HasConceptMod,
+                ContentItem::code(dcm::PROCEDURE_REPORTED, dcm::COMPUTED_TOMOGRAPHY),
+            )
+            .with_child(
+                RelationshipType::HasObsContext,
+                ContentItem::person_name(dcm::PERSON_OBSERVER_NAME, "Doe^Jane"),
+            )
+            .with_child(
+                RelationshipType::Contains,
+                ContentItem::container(dcm::FINDINGS)
+                    .with_child(
+                        RelationshipType::Contains,
+                        ContentItem::text(dcm::FINDING, "Nodule in the right lung"),
+                    )
+                    .with_child(
+                        RelationshipType::Contains,
+                        ContentItem::num(sct::DIAMETER, Measurement::new(8.25, ucum::MILLIMETER))
+                            .with_child(
+                                RelationshipType::InferredFrom,
+                                ContentItem::scoord(
+                                    Code::new("111030", "DCM", "Image Region"),
+                                    SpatialCoordinates::new(
+                                        GraphicType::Polyline,
+                                        [[10., 20.], [18.25, 20.]],
+                                    ),
+                                    image.clone(),
+                                ),
+                            ),
+                    )
+                    .with_child(
+                        RelationshipType::InferredFrom,
+                        ContentItem::uid_ref(dcm::TRACKING_UNIQUE_IDENTIFIER, "2.25.2"),
+                    ),
+            );
+
+        let obj = tree.to_dataset().unwrap();
+        let read = ContentItem::from_dataset(&obj).unwrap();
+        assert_eq!(read, tree);
+
+        assert_eq!(read.iter().count(), 9);
+        let region = read
+            .find(&Code::new("111030", "DCM", "Image Region"))
+            .unwrap();
+        assert_eq!(region.relationship, Some(RelationshipType::InferredFrom));
+        assert_eq!(region.children[0].value, ContentValue::Image(image));
+        assert_eq!(read.children_named(&dcm::FINDINGS).count(), 1,);
+
+        // non-finite measurements cannot be written
+        let tree = ContentItem::num(
+            sct::AREA,
+            Measurement::new(f64::NAN, ucum::SQUARE_MILLIMETER),
+        );
+        assert!(tree.to_dataset().is_err());
+    }
+}
@@ -0,0 +1,924 @@
+//! Forwarding of stored instances to other Store SCPs.
+//!
+//! Each destination has its own queue of instances to send
+//! and its own worker thread,
+//! so that a slow or unreachable destination never holds back the others.
+//! An instance which could not be sent goes back to the queue of that destination
+//! and is retried later, with a delay doubling after each failed attempt,
+//! until the maximum number of attempts is reached.
+//! The association with a destination is kept open between batches,
+//! and released once there has been nothing to send for a while.
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use clap::Args;
+use dicom_app_common::{aeconfig::AeRegistry, bandwidth::parse_bandwidth};
+use dicom_encoding::TransferSyntaxIndex;
+use dicom_object::OpenFileOptions;
+use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
+use dicom_ul::{
+    ClientAssociationOptions,
+    association::{BandwidthLimit, ClientAssociationPool, CloseSocket, SyncAssociation},
+    dimse::{CStoreRq, CStoreRsp, receive_message, send_message},
+    pdu::PresentationContextResultReason,
+};
+use serde_json::{Value, json};
+use snafu::{OptionExt, ResultExt, Whatever, whatever};
+use tracing::{debug, info, warn};
+
+/// The maximum number of instances sent in one batch
+const BATCH_SIZE: usize = 64;
+
+/// How long an association with a destination is kept open
+/// with nothing to send
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Forwarding options
+#[derive(Debug, Clone, Args)]
+pub struct ForwardOptions {
+    /// Forward each stored instance to this Store SCP
+    /// (`«ae_title»@«host»:«port»`
+    /// or the name of a remote AE in the configuration file);
+    /// can be repeated
+    #[arg(long = "forward", value_name = "DEST")]
+    pub destinations: Vec<String>,
+    /// Transfer syntaxes to propose to a destination, in order of preference
+    /// (e.g. `PACS1=1.2.840.10008.1.2.4.80,1.2.840.10008.1.2.1`);
+    /// can be repeated
+    #[arg(long = "forward-ts", value_name = "DEST=UIDS", value_parser = parse_ts_preference)]
+    pub transfer_syntaxes: Vec<(String, Vec<String>)>,
+    /// Maximum number of attempts to forward each instance
+    #[arg(long = "forward-attempts", value_name = "N", default_value = "5")]
+    pub max_attempts: u32,
+    /// Time to wait before retrying to forward an instance in seconds,
+    /// doubled after each failed attempt
+    #[arg(
+        long = "forward-retry-delay",
+        value_name = "SECONDS",
+        default_value = "1"
+    )]
+    pub retry_delay: u64,
+    /// Write the backlog of each destination as JSON to this file
+    /// whenever it changes
+    #[arg(long = "forward-status", value_name = "FILE")]
+    pub status_file: Option<PathBuf>,
+    /// Limit the throughput of forwarding to this many bytes per second,
+    /// shared by all destinations
+    /// (suffixes k, M and G are accepted, example: "20M")
+    #[arg(long = "forward-max-bandwidth", value_name = "RATE", value_parser = parse_bandwidth)]
+    pub max_bandwidth: Option<u64>,
+    /// Path to the configuration file with the forwarding destinations
+    /// [default: $DICOM_RS_AE_CONFIG or ~/.config/dicom-rs/config.toml]
+    #[arg(long = "config", visible_alias = "ae-config", value_name = "FILE")]
+    pub ae_config: Option<PathBuf>,
+}
+
+/// Parse a destination and its preferred transfer syntaxes (`DEST=UID,UID`).
+fn parse_ts_preference(value: &str) -> Result<(String, Vec<String>), String> {
+    let (destination, uids) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected DEST=UIDS, got `{value}`"))?;
+    let uids: Vec<String> = uids
+        .split(',')
+        .map(|uid| uid.trim().to_string())
+        .filter(|uid| !uid.is_empty())
+        .collect();
+    if uids.is_empty() {
+        return Err(format!("no transfer syntax given for {destination}"));
+    }
+    for uid in &uids {
+        if TransferSyntaxRegistry.get(uid).is_none() {
+            return Err(format!("unknown transfer syntax {uid}"));
+        }
+    }
+    Ok((destination.to_string(), uids))
+}
+
+/// A stored instance to forward
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredInstance {
+    pub path: PathBuf,
+    pub sop_class_uid: String,
+    pub sop_instance_uid: String,
+    pub transfer_syntax: String,
+}
+
+/// An instance waiting in the queue of a destination
+#[derive(Debug)]
+struct Pending {
+    instance: Arc<StoredInstance>,
+    /// the number of failed attempts so far
+    attempts: u32,
+    /// when the instance was received
+    queued_at: Instant,
+    /// the instance is not sent again before this moment
+    not_before: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    pending: VecDeque<Pending>,
+    in_flight: usize,
+    forwarded: u64,
+    failed: u64,
+    last_error: Option<String>,
+}
+
+/// How to reach a destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    address: String,
+    tls: bool,
+    calling_ae_title: String,
+    max_pdu_length: u32,
+}
+
+/// A Store SCP to forward instances to
+#[derive(Debug)]
+struct Destination {
+    /// the destination as given on the command line
+    name: String,
+    target: Target,
+    /// the preferred transfer syntaxes, from most to least preferred
+    transfer_syntaxes: Vec<String>,
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+/// The backlog of a destination at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestinationStatus {
+    pub name: String,
+    pub address: String,
+    /// instances waiting to be sent, retries included
+    pub queued: usize,
+    /// instances waiting for a retry
+    pub retrying: usize,
+    /// instances being sent
+    pub in_flight: usize,
+    /// instances forwarded successfully
+    pub forwarded: u64,
+    /// instances given up on after the maximum number of attempts
+    pub failed: u64,
+    /// how long the oldest instance not yet forwarded has been waiting
+    pub oldest: Option<Duration>,
+    pub last_error: Option<String>,
+}
+
+impl DestinationStatus {
+    /// The number of instances not forwarded yet
+    pub fn backlog(&self) -> usize {
+        self.queued + self.in_flight
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "address": self.address,
+            "backlog": self.backlog(),
+            "queued": self.queued,
+            "retrying": self.retrying,
+            "in_flight": self.in_flight,
+            "forwarded": self.forwarded,
+            "failed": self.failed,
+            "oldest_ms": self.oldest.map(|d| d.as_millis() as u64),
+            "last_error": self.last_error,
+        })
+    }
+}
+
+/// Fans out stored instances to all forwarding destinations
+#[derive(Debug)]
+pub struct Forwarder {
+    destinations: Vec<Destination>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    status_file: Option<PathBuf>,
+    /// the throughput budget shared by all destinations
+    bandwidth_limit: Option<BandwidthLimit>,
+    /// serializes writes to the status file
+    status_lock: Mutex<()>,
+}
+
+impl Forwarder {
+    /// Resolve the forwarding destinations.
+    ///
+    /// Destinations without a socket address
+    /// are looked up in the AE configuration file.
+    pub fn new(
+        options: &ForwardOptions,
+        calling_ae_title: &str,
+        max_pdu_length: u32,
+    ) -> Result<Self, Whatever> {
+        let mut registry = None;
+        let mut destinations: Vec<Destination> = Vec::new();
+        for name in &options.destinations {
+            if destinations.iter().any(|d| d.name == *name) {
+                whatever!("forwarding destination {name} is given more than once");
+            }
+            let target = if name.contains(':') {
+                Target {
+                    address: name.clone(),
+                    tls: false,
+                    calling_ae_title: calling_ae_title.to_string(),
+                    max_pdu_length,
+                }
+            } else {
+                if registry.is_none() {
+                    registry = Some(
+                        match &options.ae_config {
+                            Some(path) => AeRegistry::open(path),
+                            None => AeRegistry::open_default(),
+                        }
+                        .whatever_context("Could not read the AE configuration file")?,
+                    );
+                }
+                let registry = registry.as_ref().unwrap();
+                let ae = registry
+                    .get(name)
+                    .with_whatever_context(|| format!("unknown forwarding destination {name}"))?;
+                let defaults = registry.defaults();
+                Target {
+                    address: ae.address(),
+                    tls: ae.tls,
+                    calling_ae_title: ae
+                        .calling_ae_title
+                        .clone()
+                        .or_else(|| defaults.calling_ae_title.clone())
+                        .unwrap_or_else(|| calling_ae_title.to_string()),
+                    max_pdu_length: ae
+                        .max_pdu_length
+                        .or(defaults.max_pdu_length)
+                        .unwrap_or(max_pdu_length),
+                }
+            };
+            if target.tls {
+                whatever!("forwarding to {name} over TLS is not supported");
+            }
+            destinations.push(Destination {
+                name: name.clone(),
+                target,
+                transfer_syntaxes: Vec::new(),
+                queue: Mutex::new(Queue::default()),
+                ready: Condvar::new(),
+            });
+        }
+        for (name, uids) in &options.transfer_syntaxes {
+            let destination = destinations
+                .iter_mut()
+                .find(|d| d.name == *name)
+                .with_whatever_context(|| {
+                    format!("transfer syntaxes given for {name}, which is not a destination")
+                })?;
+            destination.transfer_syntaxes.extend(uids.iter().cloned());
+        }
+
+        Ok(Forwarder {
+            destinations,
+            max_attempts: options.max_attempts.max(1),
+            retry_delay: Duration::from_secs(options.retry_delay),
+            status_file: options.status_file.clone(),
+            bandwidth_limit: options.max_bandwidth.map(BandwidthLimit::new),
+            status_lock: Mutex::new(()),
+        })
+    }
+
+    /// Start one worker thread per destination.
+    pub fn start(self) -> Arc<Self> {
+        let forwarder = Arc::new(self);
+        for i in 0..forwarder.destinations.len() {
+            let forwarder = Arc::clone(&forwarder);
+            std::thread::spawn(move || forwarder.run(i));
+        }
+        forwarder.write_status();
+        forwarder
+    }
+
+    /// Queue a stored instance for all destinations.
+    pub fn enqueue(&self, instance: StoredInstance) {
+        let instance = Arc::new(instance);
+        let now = Instant::now();
+        for destination in &self.destinations {
+            destination
+                .queue
+                .lock()
+                .unwrap()
+                .pending
+                .push_back(Pending {
+                    instance: Arc::clone(&instance),
+                    attempts: 0,
+                    queued_at: now,
+                    not_before: now,
+                });
+            destination.ready.notify_one();
+        }
+        self.write_status();
+    }
+
+    /// Describe the backlog of each destination.
+    pub fn status(&self) -> Vec<DestinationStatus> {
+        let now = Instant::now();
+        self.destinations
+            .iter()
+            .map(|destination| {
+                let queue = destination.queue.lock().unwrap();
+                DestinationStatus {
+                    name: destination.name.clone(),
+                    address: destination.target.address.clone(),
+                    queued: queue.pending.len(),
+                    retrying: queue.pending.iter().filter(|p| p.attempts > 0).count(),
+                    in_flight: queue.in_flight,
+                    forwarded: queue.forwarded,
+                    failed: queue.failed,
+                    oldest: queue
+                        .pending
+                        .iter()
+                        .map(|p| p.queued_at)
+                        .min()
+                        .map(|t| now - t),
+                    last_error: queue.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// The status of all destinations as JSON.
+    pub fn status_json(&self) -> Value {
+        let updated_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        json!({
+            "updated_at": updated_at.as_secs(),
+            "destinations": self
+                .status()
+                .iter()
+                .map(DestinationStatus::to_json)
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write the status to the status file, if there is one.
+    fn write_status(&self) {
+        let Some(path) = &self.status_file else {
+            return;
+        };
+        let _lock = self.status_lock.lock().unwrap();
+        if let Err(e) = write_json(path, &self.status_json()) {
+            warn!(
+                "Could not write forwarding status to {}: {}",
+                path.display(),
+                snafu::Report::from_error(e)
+            );
+        }
+    }
+
+    /// The worker loop of the destination at the given index.
+    fn run(&self, index: usize) {
+        let destination = &self.destinations[index];
+        let pool = self.pool(&destination.target);
+        loop {
+            // only wake up early to release an idle association
+            let timeout = (pool.idle_count() > 0).then_some(IDLE_TIMEOUT);
+            let Some(batch) = self.next_batch(destination, timeout) else {
+                debug!("Releasing idle association with {}", destination.name);
+                pool.release_all();
+                continue;
+            };
+            debug!(
+                "Forwarding {} instances to {}",
+                batch.len(),
+                destination.name
+            );
+            let results = send_batch(destination, &pool, &batch);
+            self.complete(destination, batch, results);
+            self.write_status();
+        }
+    }
+
+    /// The pool keeping the association with a destination open
+    /// between batches.
+    fn pool(&self, target: &Target) -> ClientAssociationPool {
+        let mut options = ClientAssociationOptions::new()
+            .calling_ae_title(target.calling_ae_title.clone())
+            .max_pdu_length(target.max_pdu_length);
+        if let Some(limit) = &self.bandwidth_limit {
+            options = options.bandwidth_limit(limit.clone());
+        }
+        ClientAssociationPool::new(options).idle_timeout(Some(IDLE_TIMEOUT))
+    }
+
+    /// Wait for instances ready to be sent to the destination
+    /// and take up to [`BATCH_SIZE`] of them out of the queue.
+    ///
+    /// Returns `None` if none are ready within `timeout`.
+    fn next_batch(
+        &self,
+        destination: &Destination,
+        timeout: Option<Duration>,
+    ) -> Option<Vec<Pending>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut queue = destination.queue.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let batch = take_ready(&mut queue, now);
+            if !batch.is_empty() {
+                return Some(batch);
+            }
+            if deadline.is_some_and(|deadline| deadline <= now) {
+                return None;
+            }
+            let next = queue.pending.iter().map(|p| p.not_before).min();
+            let wake_up = match (next, deadline) {
+                (Some(next), Some(deadline)) => Some(next.min(deadline)),
+                (next, deadline) => next.or(deadline),
+            };
+            queue = match wake_up {
+                Some(wake_up) => {
+                    let timeout = wake_up.saturating_duration_since(now);
+                    destination.ready.wait_timeout(queue, timeout).unwrap().0
+                }
+                None => destination.ready.wait(queue).unwrap(),
+            };
+        }
+    }
+
+    /// Record the outcome of sending a batch,
+    /// putting the instances which failed back in the queue.
+    fn complete(
+        &self,
+        destination: &Destination,
+        batch: Vec<Pending>,
+        results: Vec<Result<u16, String>>,
+    ) {
+        let mut queue = destination.queue.lock().unwrap();
+        let now = Instant::now();
+        let mut forwarded = 0;
+        queue.in_flight -= batch.len();
+        for (mut pending, result) in batch.into_iter().zip(results) {
+            let error = match result {
+                Ok(status) if is_stored(status) => {
+                    forwarded += 1;
+                    continue;
+                }
+                Ok(status) => format!("C-STORE failed with status {status:04X}H"),
+                Err(reason) => reason,
+            };
+            pending.attempts += 1;
+            if pending.attempts >= self.max_attempts {
+                warn!(
+                    "Giving up on forwarding {} to {} after {} attempts: {}",
+                    pending.instance.sop_instance_uid, destination.name, pending.attempts, error
+                );
+                queue.failed += 1;
+            } else {
+                warn!(
+                    "Could not forward {} to {} (attempt {}): {}",
+                    pending.instance.sop_instance_uid, destination.name, pending.attempts, error
+                );
+                pending.not_before = now + retry_delay(self.retry_delay, pending.attempts - 1);
+                queue.pending.push_back(pending);
+            }
+            queue.last_error = Some(error);
+        }
+        queue.forwarded += forwarded;
+        if forwarded > 0 {
+            info!(
+                "Forwarded {} instances to {} ({} queued)",
+                forwarded,
+                destination.name,
+                queue.pending.len()
+            );
+        }
+    }
+}
+
+/// Take the instances ready to be sent out of the queue
+/// and count them as in flight.
+fn take_ready(queue: &mut Queue, now: Instant) -> Vec<Pending> {
+    let mut batch = Vec::new();
+    let mut i = 0;
+    while i < queue.pending.len() && batch.len() < BATCH_SIZE {
+        if queue.pending[i].not_before <= now {
+            batch.extend(queue.pending.remove(i));
+        } else {
+            i += 1;
+        }
+    }
+    queue.in_flight += batch.len();
+    batch
+}
+
+/// The time to wait before the given retry (counting from 0),
+/// doubling the base delay after each failed attempt.
+fn retry_delay(base: Duration, retry: u32) -> Duration {
+    base.saturating_mul(1 << retry.min(6))
+}
+
+/// Whether a C-STORE response status means that the instance was stored:
+/// success, or one of the warnings of the Storage service class.
+/// Any other status is a failure to retry.
+fn is_stored(status: u16) -> bool {
+    matches!(status, 0x0000 | 0x0001 | 0xB000..=0xBFFF)
+}
+
+/// Whether a transfer syntax can be converted to and from others
+/// without decoding pixel data
+fn is_native(uid: &str) -> bool {
+    TransferSyntaxRegistry
+        .get(uid)
+        .is_some_and(|ts| ts.is_codec_free() && !ts.is_encapsulated_pixel_data())
+}
+
+/// The transfer syntaxes to propose for an instance:
+/// the preferred ones first,
+/// then the transfer syntax of the file
+/// and the native transfer syntaxes it can be converted to.
+fn proposed_transfer_syntaxes(preferred: &[String], file_ts: &str) -> Vec<String> {
+    let mut proposed: Vec<String> = preferred
+        .iter()
+        .filter(|uid| *uid == file_ts || (is_native(uid) && is_native(file_ts)))
+        .cloned()
+        .collect();
+    let mut push = |uid: &str| {
+        if !proposed.iter().any(|p| p == uid) {
+            proposed.push(uid.to_string());
+        }
+    };
+    push(file_ts);
+    if is_native(file_ts) {
+        push("1.2.840.10008.1.2.1");
+        push("1.2.840.10008.1.2");
+    }
+    proposed
+}
+
+/// Choose among the accepted transfer syntaxes
+/// the one to send an instance in,
+/// by order of preference, then favoring the transfer syntax of the file.
+///
+/// Instances are only converted between native transfer syntaxes.
+fn select_transfer_syntax<'a>(
+    accepted: &[&'a str],
+    preferred: &[String],
+    file_ts: &str,
+) -> Option<&'a str> {
+    let convertible = |uid: &str| uid == file_ts || (is_native(uid) && is_native(file_ts));
+    preferred
+        .iter()
+        .find_map(|uid| accepted.iter().find(|a| **a == uid && convertible(a)))
+        .or_else(|| accepted.iter().find(|a| **a == file_ts))
+        .or_else(|| accepted.iter().find(|a| convertible(a)))
+        .copied()
+}
+
+/// Send a batch of instances to a destination over a single association,
+/// taken from the pool of the destination,
+/// returning the C-STORE response status of each instance
+/// or why it could not be sent.
+fn send_batch(
+    destination: &Destination,
+    pool: &ClientAssociationPool,
+    batch: &[Pending],
+) -> Vec<Result<u16, String>> {
+    let target = &destination.target;
+    let mut proposed: Vec<(&str, &str)> = Vec::new();
+    let mut presentation_contexts: Vec<(&str, Vec<String>)> = Vec::new();
+    for pending in batch {
+        let instance = &*pending.instance;
+        let key = (
+            instance.sop_class_uid.as_str(),
+            instance.transfer_syntax.as_str(),
+        );
+        if !proposed.contains(&key) {
+            proposed.push(key);
+            presentation_contexts.push((
+                instance.sop_class_uid.as_str(),
+                proposed_transfer_syntaxes(
+                    &destination.transfer_syntaxes,
+                    &instance.transfer_syntax,
+                ),
+            ));
+        }
+    }
+    let transfer_syntaxes: Vec<Vec<&str>> = presentation_contexts
+        .iter()
+        .map(|(_, uids)| uids.iter().map(String::as_str).collect())
+        .collect();
+    let presentation_contexts: Vec<(&str, &[&str])> = presentation_contexts
+        .iter()
+        .zip(&transfer_syntaxes)
+        .map(|((uid, _), uids)| (*uid, uids.as_slice()))
+        .collect();
+    let mut association =
+        match pool.get_with_presentation_contexts(&target.address, &presentation_contexts) {
+            Ok(association) => association,
+            Err(e) => {
+                let reason = format!(
+                    "could not associate with {}: {}",
+                    target.address,
+                    snafu::Report::from_error(e)
+                );
+                return batch.iter().map(|_| Err(reason.clone())).collect();
+            }
+        };
+
+    let mut results = Vec::with_capacity(batch.len());
+    for (i, pending) in batch.iter().enumerate() {
+        let instance = &*pending.instance;
+        let result = store(
+            &mut *association,
+            &destination.transfer_syntaxes,
+            instance,
+            (i + 1) as u16,
+        );
+        let broken = matches!(&result, Err(StoreError::Association(_)));
+        results.push(result.map_err(|e| e.to_string()));
+        if broken {
+            // the association cannot be used for the rest of the batch
+            let _ = association.abort();
+            let reason = format!("association with {} was lost", target.address);
+            results.extend(batch[i + 1..].iter().map(|_| Err(reason.clone())));
+            return results;
+        }
+    }
+    // the association goes back to the pool for the next batch
+    results
+}
+
+/// Why an instance could not be sent
+#[derive(Debug)]
+enum StoreError {
+    /// the instance cannot be sent over this association
+    Instance(String),
+    /// the association failed
+    Association(String),
+}
+
+impl std::fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::Instance(reason) | StoreError::Association(reason) => f.write_str(reason),
+        }
+    }
+}
+
+/// Send one instance with a C-STORE request,
+/// returning the status of the response.
+fn store<A, S>(
+    association: &mut A,
+    preferred: &[String],
+    instance: &StoredInstance,
+    message_id: u16,
+) -> Result<u16, StoreError>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let candidates: Vec<_> = association
+        .presentation_contexts()
+        .iter()
+        .filter(|pc| {
+            pc.reason == PresentationContextResultReason::Acceptance
+                && pc.abstract_syntax.trim_end_matches('\0') == instance.sop_class_uid
+        })
+        .collect();
+    let accepted: Vec<&str> = candidates
+        .iter()
+        .map(|pc| pc.transfer_syntax.trim_end_matches('\0'))
+        .collect();
+    let ts_uid = select_transfer_syntax(&accepted, preferred, &instance.transfer_syntax)
+        .ok_or_else(|| {
+            StoreError::Instance(format!(
+                "no presentation context accepted for SOP class {} in transfer syntax {}",
+                instance.sop_class_uid, instance.transfer_syntax
+            ))
+        })?;
+    let pc_id = candidates
+        .iter()
+        .find(|pc| pc.transfer_syntax.trim_end_matches('\0') == ts_uid)
+        .map(|pc| pc.id)
+        .unwrap();
+    let ts = TransferSyntaxRegistry
+        .get(ts_uid)
+        .ok_or_else(|| StoreError::Instance(format!("unsupported transfer syntax {ts_uid}")))?;
+
+    let obj = OpenFileOptions::new()
+        .open_file(&instance.path)
+        .map_err(|e| {
+            StoreError::Instance(format!("could not read {}: {}", instance.path.display(), e))
+        })?;
+    let mut data = Vec::new();
+    obj.write_dataset_with_ts(&mut data, ts).map_err(|e| {
+        StoreError::Instance(format!(
+            "could not encode {}: {}",
+            instance.path.display(),
+            e
+        ))
+    })?;
+
+    let rq = CStoreRq {
+        message_id,
+        affected_sop_class_uid: instance.sop_class_uid.clone(),
+        affected_sop_instance_uid: instance.sop_instance_uid.clone(),
+        priority: 0,
+        move_originator_ae_title: None,
+        move_originator_message_id: None,
+    };
+    let lost = |e: dicom_ul::dimse::Error| {
+        StoreError::Association(snafu::Report::from_error(e).to_string())
+    };
+    send_message(association, pc_id, &rq.command(), Some(&data)).map_err(lost)?;
+    let msg = receive_message(association).map_err(lost)?;
+    let rsp = CStoreRsp::from_command(&msg.command).map_err(lost)?;
+    Ok(rsp.status)
+}
+
+/// Write a JSON value to a file,
+/// replacing it only once it is complete.
+fn write_json(path: &Path, value: &Value) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let file = std::fs::File::create(&tmp)?;
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), value)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_dictionary_std::uids;
+
+    fn options(destinations: &[&str]) -> ForwardOptions {
+        ForwardOptions {
+            destinations: destinations.iter().map(|d| d.to_string()).collect(),
+            transfer_syntaxes: Vec::new(),
+            max_attempts: 2,
+            retry_delay: 10,
+            status_file: None,
+            max_bandwidth: None,
+            ae_config: None,
+        }
+    }
+
+    fn instance(uid: &str) -> StoredInstance {
+        StoredInstance {
+            path: format!("{uid}.dcm").into(),
+            sop_class_uid: uids::CT_IMAGE_STORAGE.to_string(),
+            sop_instance_uid: uid.to_string(),
+            transfer_syntax: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+        }
+    }
+
+    #[test]
+    fn independent_queues_per_destination() {
+        let mut options = options(&["A@localhost:1", "B@localhost:2"]);
+        options.transfer_syntaxes = vec![(
+            "B@localhost:2".to_string(),
+            vec![uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string()],
+        )];
+        let forwarder = Forwarder::new(&options, "STORE-SCP", 16378).unwrap();
+        assert!(forwarder.destinations[0].transfer_syntaxes.is_empty());
+        assert_eq!(
+            forwarder.destinations[1].transfer_syntaxes,
+            [uids::IMPLICIT_VR_LITTLE_ENDIAN]
+        );
+
+        forwarder.enqueue(instance("1.2.3.1"));
+        forwarder.enqueue(instance("1.2.3.2"));
+
+        // A fails to store the first instance, B stores both
+        let [a, b] = &forwarder.destinations[..] else {
+            unreachable!()
+        };
+        let batch = forwarder.next_batch(a, None).unwrap();
+        assert_eq!(batch.len(), 2);
+        forwarder.complete(a, batch, vec![Err("connection refused".into()), Ok(0)]);
+        let batch = forwarder.next_batch(b, None).unwrap();
+        forwarder.complete(b, batch, vec![Ok(0), Ok(0xB000)]);
+
+        let status = forwarder.status();
+        assert_eq!(status[0].name, "A@localhost:1");
+        assert_eq!(status[0].backlog(), 1);
+        assert_eq!(status[0].retrying, 1);
+        assert_eq!(status[0].forwarded, 1);
+        assert_eq!(status[0].last_error.as_deref(), Some("connection refused"));
+        assert_eq!(status[1].backlog(), 0);
+        assert_eq!(status[1].forwarded, 2);
+        assert_eq!(status[1].oldest, None);
+
+        // the retry waits for the delay
+        let mut queue = a.queue.lock().unwrap();
+        assert!(take_ready(&mut queue, Instant::now()).is_empty());
+        let batch = take_ready(&mut queue, Instant::now() + Duration::from_secs(10));
+        assert_eq!(batch[0].instance.sop_instance_uid, "1.2.3.1");
+        drop(queue);
+
+        // and the second failure is the last one
+        forwarder.complete(a, batch, vec![Ok(0xA700)]);
+        let status = forwarder.status();
+        assert_eq!(status[0].backlog(), 0);
+        assert_eq!(status[0].failed, 1);
+        assert_eq!(
+            status[0].last_error.as_deref(),
+            Some("C-STORE failed with status A700H")
+        );
+        let json = forwarder.status_json();
+        assert_eq!(json["destinations"][0]["failed"], 1);
+        assert_eq!(json["destinations"][1]["forwarded"], 2);
+    }
+
+    #[test]
+    fn unknown_destinations_are_rejected() {
+        let mut options = options(&["A@localhost:1"]);
+        options.transfer_syntaxes = vec![(
+            "B@localhost:2".to_string(),
+            vec![uids::IMPLICIT_VR_LITTLE_ENDIAN.to_string()],
+        )];
+        assert!(Forwarder::new(&options, "STORE-SCP", 16378).is_err());
+        assert!(
+            Forwarder::new(
+                &self::options(&["A@localhost:1", "A@localhost:1"]),
+                "STORE-SCP",
+                16378
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn only_success_and_warnings_are_stored() {
+        assert!(is_stored(0x0000));
+        assert!(is_stored(0x0001));
+        assert!(is_stored(0xB000));
+        assert!(is_stored(0xB007));
+        // pending statuses are not a completion
+        assert!(!is_stored(0xFF00));
+        assert!(!is_stored(0xFF01));
+        assert!(!is_stored(0xA700));
+        assert!(!is_stored(0xC000));
+        assert!(!is_stored(0x0110));
+    }
+
+    #[test]
+    fn one_bandwidth_limit_for_all_destinations() {
+        let mut options = options(&["A@localhost:1", "B@localhost:2"]);
+        assert!(
+            Forwarder::new(&options, "STORE-SCP", 16378)
+                .unwrap()
+                .bandwidth_limit
+                .is_none()
+        );
+        options.max_bandwidth = Some(2_000_000);
+        let forwarder = Forwarder::new(&options, "STORE-SCP", 16378).unwrap();
+        assert_eq!(
+            forwarder
+                .bandwidth_limit
+                .as_ref()
+                .map(BandwidthLimit::bytes_per_second),
+            Some(2_000_000)
+        );
+    }
+
+    #[test]
+    fn transfer_syntax_preferences() {
+        let jpeg_ls = uids::JPEGLS_LOSSLESS.to_string();
+        let explicit = uids::EXPLICIT_VR_LITTLE_ENDIAN;
+        let implicit = uids::IMPLICIT_VR_LITTLE_ENDIAN;
+        let preferred = vec![jpeg_ls.clone(), implicit.to_string()];
+
+        // compressed transfer syntaxes are only proposed for files in them
+        assert_eq!(
+            proposed_transfer_syntaxes(&preferred, explicit),
+            [implicit, explicit]
+        );
+        assert_eq!(
+            proposed_transfer_syntaxes(&preferred, &jpeg_ls),
+            [jpeg_ls.as_str()]
+        );
+
+        assert_eq!(
+            select_transfer_syntax(&[explicit, implicit], &preferred, explicit),
+            Some(implicit)
+        );
+        assert_eq!(
+            select_transfer_syntax(&[explicit, implicit], &[], implicit),
+            Some(implicit)
+        );
+        assert_eq!(
+            select_transfer_syntax(&[explicit], &preferred, &jpeg_ls),
+            None
+        );
+        assert_eq!(
+            parse_ts_preference("PACS1=1.2.840.10008.1.2.1, 1.2.840.10008.1.2"),
+            Ok((
+                "PACS1".to_string(),
+                vec![explicit.to_string(), implicit.to_string()]
+            ))
+        );
+        assert!(parse_ts_preference("PACS1=1.2.3").is_err());
+        assert!(parse_ts_preference("PACS1").is_err());
+    }
+}
@@ -1,19 +1,28 @@
 use std::{
     net::{Ipv4Addr, SocketAddrV4},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use clap::Parser;
-use dicom_app_common::{TlsAcceptorOptions, TlsOptions};
-use dicom_core::{DataElement, VR, dicom_value};
+use clap::{Parser, ValueEnum};
+use dicom_app_common::{
+    TlsAcceptorOptions, TlsOptions,
+    index::{Index, IndexedInstance},
+    path_template::PathTemplate,
+};
+use dicom_core::{
+    DataElement, DicomValue, VR, dicom_value, header::Header, value::DataSetSequence,
+};
 use dicom_dictionary_std::tags;
 use dicom_object::{InMemDicomObject, StandardDataDictionary};
-use snafu::{Report, ResultExt, Whatever};
-use tracing::{Level, error, info};
+use snafu::{Report, ResultExt, Whatever, whatever};
+use tracing::{Level, error, info, warn};
 
+mod forward;
 mod store_async;
 mod store_sync;
 mod transfer;
+use forward::{ForwardOptions, Forwarder};
 use store_async::run_store_async;
 use store_sync::run_store_sync;
 use tracing_subscriber::EnvFilter;
@@ -48,17 +57,235 @@ struct App {
     /// Output directory for incoming objects
     #[arg(short = 'o', default_value = ".")]
     out_dir: PathBuf,
+    /// Template for the path of each stored file in the output directory,
+    /// with attribute keywords in braces
+    /// (e.g. `{PatientID}/{StudyInstanceUID}/{SOPInstanceUID}.dcm`;
+    /// default is `{SOPInstanceUID}.dcm`)
+    #[arg(long = "path-template")]
+    path_template: Option<PathTemplate>,
     /// Which port to listen on
     #[arg(short, default_value = "11111")]
     port: u16,
     /// Run in non-blocking mode (spins up an async task to handle each incoming stream)
     #[arg(short, long)]
     non_blocking: bool,
+    /// How to fill in the SOP Class UID and SOP Instance UID
+    /// of the file meta group
+    /// when the incoming data set does not have them
+    #[arg(long, value_enum, default_value_t = CoerceMissingUids::Command)]
+    coerce_missing_uids: CoerceMissingUids,
+    /// Write a metadata file next to each stored file
+    /// (`json`: DICOM JSON of the data set without its bulk data)
+    #[arg(long, value_enum)]
+    sidecar: Option<Sidecar>,
+    /// Record each stored file in this SQLite index,
+    /// which can be served by dicom-qrscp
+    #[arg(long = "index", value_name = "FILE")]
+    index: Option<PathBuf>,
     /// TLS options
     #[command(flatten, next_help_heading = "TLS Options")]
     tls: TlsOptions,
     #[command(flatten)]
     tls_acceptor: TlsAcceptorOptions,
+    /// Forwarding options
+    #[command(flatten, next_help_heading = "Forwarding Options")]
+    forward: ForwardOptions,
+}
+
+/// How to handle incoming data sets without SOP Class UID or SOP Instance UID
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum CoerceMissingUids {
+    /// Refuse to store the object
+    Fail,
+    /// Use the affected SOP UIDs of the C-STORE request
+    #[default]
+    Command,
+    /// Use the affected SOP UIDs of the C-STORE request,
+    /// or synthesize them if the request does not have them either
+    Generate,
+}
+
+/// The kind of metadata file written next to each stored file
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Sidecar {
+    /// The data set in DICOM JSON, without bulk data
+    Json,
+}
+
+/// Binary values longer than this many bytes are left out of sidecar files
+const BULK_DATA_THRESHOLD: usize = 1024;
+
+/// Determine the SOP Class UID and SOP Instance UID
+/// to record in the file meta group of an incoming object,
+/// falling back to the C-STORE request's affected SOP UIDs
+/// (or the presentation context's abstract syntax and a new UID)
+/// according to the given strategy.
+fn resolve_sop_uids(
+    obj: &InMemDicomObject<StandardDataDictionary>,
+    command_uids: (&str, &str),
+    abstract_syntax: &str,
+    coerce: CoerceMissingUids,
+) -> Result<(String, String), Whatever> {
+    let from_obj = |tag| {
+        obj.get(tag)
+            .and_then(|e| e.to_str().ok())
+            .map(|uid| uid.trim_end_matches(['\0', ' ']).to_string())
+            .filter(|uid| !uid.is_empty())
+    };
+    let resolve = |tag, name, command_uid: &str, generate: &dyn Fn() -> String| {
+        if let Some(uid) = from_obj(tag) {
+            return Ok(uid);
+        }
+        let command_uid = command_uid.trim_end_matches(['\0', ' ']);
+        match coerce {
+            CoerceMissingUids::Fail => whatever!("missing {name}"),
+            CoerceMissingUids::Command | CoerceMissingUids::Generate if !command_uid.is_empty() => {
+                warn!("Missing {name}, using {command_uid} from the C-STORE request");
+                Ok(command_uid.to_string())
+            }
+            CoerceMissingUids::Command => {
+                whatever!("missing {name} in both the data set and the C-STORE request")
+            }
+            CoerceMissingUids::Generate => {
+                let uid = generate();
+                warn!("Missing {name}, using {uid}");
+                Ok(uid)
+            }
+        }
+    };
+    let sop_class_uid = resolve(
+        tags::SOP_CLASS_UID,
+        "SOP Class UID",
+        command_uids.0,
+        &|| abstract_syntax.trim_end_matches('\0').to_string(),
+    )?;
+    let sop_instance_uid = resolve(
+        tags::SOP_INSTANCE_UID,
+        "SOP Instance UID",
+        command_uids.1,
+        &generate_uid,
+    )?;
+    Ok((sop_class_uid, sop_instance_uid))
+}
+
+/// Determine where to store an incoming object,
+/// creating the parent directories as necessary.
+///
+/// Without a path template,
+/// the file is named after its SOP Instance UID.
+fn output_path(
+    out_dir: &Path,
+    path_template: Option<&PathTemplate>,
+    obj: &InMemDicomObject<StandardDataDictionary>,
+    sop_instance_uid: &str,
+) -> Result<PathBuf, Whatever> {
+    let file_path = match path_template {
+        Some(template) => out_dir.join(template.render(obj)),
+        None => out_dir.join(format!("{sop_instance_uid}.dcm")),
+    };
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_whatever_context(|_| format!("could not create {}", parent.display()))?;
+    }
+    Ok(file_path)
+}
+
+/// Copy a data set without its bulk data:
+/// encapsulated pixel data and long binary values,
+/// including those nested in sequences.
+fn without_bulk_data(
+    obj: &InMemDicomObject<StandardDataDictionary>,
+) -> InMemDicomObject<StandardDataDictionary> {
+    InMemDicomObject::from_element_iter(obj.iter().filter_map(|e| {
+        match e.value() {
+            DicomValue::PixelSequence(_) => None,
+            DicomValue::Sequence(seq) => Some(DataElement::new(
+                e.tag(),
+                VR::SQ,
+                DataSetSequence::from(
+                    seq.items()
+                        .iter()
+                        .map(without_bulk_data)
+                        .collect::<Vec<_>>(),
+                ),
+            )),
+            DicomValue::Primitive(value) => {
+                let binary = matches!(
+                    e.vr(),
+                    VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::UN
+                );
+                if binary && value.calculate_byte_len() > BULK_DATA_THRESHOLD {
+                    None
+                } else {
+                    Some(e.clone())
+                }
+            }
+        }
+    }))
+}
+
+/// Write the sidecar file of a stored file,
+/// replacing its `.dcm` extension (if any) with the sidecar's extension.
+fn write_sidecar(
+    file_path: &Path,
+    obj: &InMemDicomObject<StandardDataDictionary>,
+    sidecar: Sidecar,
+) -> Result<PathBuf, Whatever> {
+    let (extension, data) = match sidecar {
+        Sidecar::Json => (
+            "json",
+            dicom_json::to_vec(&without_bulk_data(obj))
+                .whatever_context("could not serialize DICOM JSON")?,
+        ),
+    };
+    let sidecar_path = if file_path.extension().is_some_and(|ext| ext == "dcm") {
+        file_path.with_extension(extension)
+    } else {
+        let mut path = file_path.as_os_str().to_owned();
+        path.push(".");
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    std::fs::write(&sidecar_path, data)
+        .with_whatever_context(|_| format!("could not write {}", sidecar_path.display()))?;
+    Ok(sidecar_path)
+}
+
+/// Record a stored file in the index.
+///
+/// Failures are only logged,
+/// since the file itself was stored.
+fn index_file(index: &Index, file_path: &Path, obj: &InMemDicomObject, transfer_syntax: &str) {
+    // the index is read from other working directories
+    let path = std::fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+    let size = std::fs::metadata(&path).map_or(0, |m| m.len());
+    let result = IndexedInstance::new(&path, size, transfer_syntax, obj)
+        .and_then(|instance| index.insert([&instance]));
+    if let Err(e) = result {
+        warn!(
+            "Could not index {}: {}",
+            file_path.display(),
+            Report::from_error(e)
+        );
+    }
+}
+
+/// Create a new random UID under the `2.25` root.
+fn generate_uid() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    let state = std::collections::hash_map::RandomState::new();
+    let mut value = 0u128;
+    for i in 0..2 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(i);
+        hasher.write_u128(time);
+        value = value << 64 | u128::from(hasher.finish());
+    }
+    format!("2.25.{value}")
 }
 
 fn create_cstore_response(
@@ -167,13 +394,37 @@ fn main() {
     }
 }
 
+/// Open the index of the stored files if one was given.
+fn open_index(args: &App) -> Result<Option<Arc<Index>>, Whatever> {
+    let Some(path) = &args.index else {
+        return Ok(None);
+    };
+    let index = Index::open(path)
+        .with_whatever_context(|_| format!("Could not open index {}", path.display()))?;
+    info!("Recording stored files in {}", path.display());
+    Ok(Some(Arc::new(index)))
+}
+
+/// Start forwarding the stored instances
+/// if any forwarding destination was given.
+fn start_forwarder(args: &App) -> Result<Option<Arc<Forwarder>>, Whatever> {
+    let options = &args.forward;
+    if options.destinations.is_empty() && options.transfer_syntaxes.is_empty() {
+        return Ok(None);
+    }
+    let forwarder = Forwarder::new(options, &args.calling_ae_title, args.max_pdu_length)?;
+    info!("Forwarding to {}", options.destinations.join(", "));
+    Ok(Some(forwarder.start()))
+}
+
 async fn run_async(args: App) -> Result<(), Box<dyn std::error::Error>> {
-    use std::sync::Arc;
     let args = Arc::new(args);
     std::fs::create_dir_all(&args.out_dir).unwrap_or_else(|e| {
         error!("Could not create output directory: {}", e);
         std::process::exit(-2);
     });
+    let forwarder = start_forwarder(&args)?;
+    let index = open_index(&args)?;
 
     let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
     let listener = tokio::net::TcpListener::bind(listen_addr).await?;
@@ -185,8 +436,12 @@ async fn run_async(args: App) -> Result<(), Box<dyn std::error::Error>> {
     loop {
         let (socket, _addr) = listener.accept().await?;
         let args = args.clone();
+        let forwarder = forwarder.clone();
+        let index = index.clone();
         tokio::task::spawn(async move {
-            if let Err(e) = run_store_async(socket, &args).await {
+            if let Err(e) =
+                run_store_async(socket, &args, forwarder.as_deref(), index.as_deref()).await
+            {
                 error!("{}", Report::from_error(e));
             }
         });
@@ -198,6 +453,8 @@ fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
         error!("Could not create output directory: {}", e);
         std::process::exit(-2);
     });
+    let forwarder = start_forwarder(&args)?;
+    let index = open_index(&args)?;
 
     let listen_addr = SocketAddrV4::new(Ipv4Addr::from(0), args.port);
     let listener = std::net::TcpListener::bind(listen_addr)?;
@@ -209,7 +466,9 @@ fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
     for stream in listener.incoming() {
         match stream {
             Ok(scu_stream) => {
-                if let Err(e) = run_store_sync(scu_stream, &args) {
+                if let Err(e) =
+                    run_store_sync(scu_stream, &args, forwarder.as_deref(), index.as_deref())
+                {
                     error!("{}", snafu::Report::from_error(e));
                 }
             }
@@ -224,11 +483,98 @@ fn run_sync(args: App) -> Result<(), Box<dyn std::error::Error>> {
 
 #[cfg(test)]
 mod tests {
-    use crate::App;
+    use crate::{App, CoerceMissingUids, Sidecar, generate_uid, resolve_sop_uids, write_sidecar};
     use clap::CommandFactory;
+    use dicom_core::{DataElement, PrimitiveValue, VR, value::DataSetSequence};
+    use dicom_dictionary_std::tags;
+    use dicom_object::InMemDicomObject;
 
     #[test]
     fn verify_cli() {
         App::command().debug_assert();
     }
+
+    #[test]
+    fn resolve_missing_sop_uids() {
+        let obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::SOP_CLASS_UID,
+            VR::UI,
+            "1.2.840.10008.5.1.4.1.1.7\0",
+        )]);
+        let ct = "1.2.840.10008.5.1.4.1.1.2";
+
+        assert!(resolve_sop_uids(&obj, ("", "2.25.1"), ct, CoerceMissingUids::Fail).is_err());
+        assert_eq!(
+            resolve_sop_uids(&obj, ("", "2.25.1\0"), ct, CoerceMissingUids::Command).unwrap(),
+            (
+                "1.2.840.10008.5.1.4.1.1.7".to_string(),
+                "2.25.1".to_string()
+            )
+        );
+        assert!(resolve_sop_uids(&obj, ("", ""), ct, CoerceMissingUids::Command).is_err());
+
+        let obj = InMemDicomObject::new_empty();
+        let (sop_class_uid, sop_instance_uid) =
+            resolve_sop_uids(&obj, ("", ""), ct, CoerceMissingUids::Generate).unwrap();
+        assert_eq!(sop_class_uid, ct);
+        assert!(sop_instance_uid.starts_with("2.25."));
+        assert_ne!(sop_instance_uid, generate_uid());
+    }
+
+    #[test]
+    fn write_json_sidecar_without_bulk_data() {
+        let item = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::ICON_IMAGE_SEQUENCE, VR::SQ, DataSetSequence::empty()),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OB,
+                PrimitiveValue::from(vec![0u8; 2048]),
+            ),
+        ]);
+        let obj = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "ID0001"),
+            DataElement::new(
+                tags::ENCAPSULATED_DOCUMENT,
+                VR::OB,
+                PrimitiveValue::from(vec![1u8; 4]),
+            ),
+            DataElement::new(
+                tags::ICON_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item]),
+            ),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                VR::OW,
+                PrimitiveValue::U16(vec![0; 4096].into()),
+            ),
+        ]);
+
+        let dir = std::env::temp_dir().join(format!("storescp-sidecar-{}", generate_uid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = write_sidecar(&dir.join("2.25.1.dcm"), &obj, Sidecar::Json).unwrap();
+        assert_eq!(path, dir.join("2.25.1.json"));
+        // without the `.dcm` extension, the sidecar extension is appended
+        let other = write_sidecar(&dir.join("2.25.1"), &obj, Sidecar::Json).unwrap();
+        assert_eq!(other, dir.join("2.25.1.json"));
+
+        let json = std::fs::read(&path).unwrap();
+        let json: InMemDicomObject = dicom_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json.get(tags::PATIENT_ID).unwrap().to_str().unwrap(),
+            "ID0001"
+        );
+        // short binary values are kept
+        assert!(json.get(tags::ENCAPSULATED_DOCUMENT).is_some());
+        assert!(json.get(tags::PIXEL_DATA).is_none());
+        let items = json
+            .get(tags::ICON_IMAGE_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        assert!(items[0].get(tags::PIXEL_DATA).is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,6 +1,7 @@
 use std::path::Path;
 
-use dicom_dictionary_std::tags;
+use dicom_app_common::{index::Index, path_template::PathTemplate};
+use dicom_dictionary_std::{StandardUidDictionary, tags};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
@@ -13,10 +14,18 @@ use dicom_ul::{
 use snafu::{OptionExt, Report, ResultExt, Whatever};
 use tracing::{debug, info, warn};
 
-use crate::{App, create_cecho_response, create_cstore_response, transfer::ABSTRACT_SYNTAXES};
+use crate::{
+    App, CoerceMissingUids, Sidecar, create_cecho_response, create_cstore_response,
+    forward::{Forwarder, StoredInstance},
+    index_file, output_path, resolve_sop_uids,
+    transfer::ABSTRACT_SYNTAXES,
+    write_sidecar,
+};
 pub async fn run_store_async(
     scu_stream: tokio::net::TcpStream,
     args: &App,
+    forwarder: Option<&Forwarder>,
+    index: Option<&Index>,
 ) -> Result<(), Whatever> {
     let App {
         verbose,
@@ -26,12 +35,17 @@ pub async fn run_store_async(
         promiscuous,
         max_pdu_length,
         out_dir,
+        path_template,
         port: _,
         non_blocking: _,
+        coerce_missing_uids,
+        sidecar,
+        index: _,
         #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
         tls,
         #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
         tls_acceptor,
+        forward: _,
     } = args;
 
     let mut options = dicom_ul::association::ServerAssociationOptions::new()
@@ -86,7 +100,17 @@ pub async fn run_store_async(
             association.requestor_max_pdu_length(),
         );
         let peer_title = association.peer_ae_title().to_string();
-        inner(association, *verbose, out_dir).await?;
+        inner(
+            association,
+            *verbose,
+            out_dir,
+            path_template.as_ref(),
+            *coerce_missing_uids,
+            *sidecar,
+            forwarder,
+            index,
+        )
+        .await?;
 
         if let Some(peer_addr) = peer_addr {
             info!("Dropping connection with {peer_title} ({peer_addr})");
@@ -119,7 +143,17 @@ pub async fn run_store_async(
         association.requestor_max_pdu_length(),
     );
     let peer_title = association.peer_ae_title().to_string();
-    inner(association, *verbose, out_dir).await?;
+    inner(
+        association,
+        *verbose,
+        out_dir,
+        path_template.as_ref(),
+        *coerce_missing_uids,
+        *sidecar,
+        forwarder,
+        index,
+    )
+    .await?;
 
     if let Some(peer_addr) = peer_addr {
         info!("Dropping connection with {peer_title} ({peer_addr})");
@@ -130,10 +164,16 @@ pub async fn run_store_async(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn inner<T>(
     mut association: AsyncServerAssociation<T>,
     verbose: bool,
     out_dir: &Path,
+    path_template: Option<&PathTemplate>,
+    coerce_missing_uids: CoerceMissingUids,
+    sidecar: Option<Sidecar>,
+    forwarder: Option<&Forwarder>,
+    index: Option<&Index>,
 ) -> Result<(), Whatever>
 where
     T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
@@ -206,21 +246,16 @@ where
                                         .whatever_context("Missing Message ID")?
                                         .to_int()
                                         .whatever_context("Message ID is not an integer")?;
+                                    // may be recovered from the data set later
                                     sop_class_uid = obj
-                                        .element(tags::AFFECTED_SOP_CLASS_UID)
-                                        .whatever_context("missing Affected SOP Class UID")?
-                                        .to_str()
-                                        .whatever_context(
-                                            "could not retrieve Affected SOP Class UID",
-                                        )?
+                                        .get(tags::AFFECTED_SOP_CLASS_UID)
+                                        .and_then(|e| e.to_str().ok())
+                                        .unwrap_or_default()
                                         .to_string();
                                     sop_instance_uid = obj
-                                        .element(tags::AFFECTED_SOP_INSTANCE_UID)
-                                        .whatever_context("missing Affected SOP Instance UID")?
-                                        .to_str()
-                                        .whatever_context(
-                                            "could not retrieve Affected SOP Instance UID",
-                                        )?
+                                        .get(tags::AFFECTED_SOP_INSTANCE_UID)
+                                        .and_then(|e| e.to_str().ok())
+                                        .unwrap_or_default()
                                         .to_string();
                                 }
                                 instance_buffer.clear();
@@ -241,35 +276,54 @@ where
                                     TransferSyntaxRegistry.get(ts).unwrap(),
                                 )
                                 .whatever_context("failed to read DICOM data object")?;
+                                let (obj_sop_class_uid, obj_sop_instance_uid) = resolve_sop_uids(
+                                    &obj,
+                                    (&sop_class_uid, &sop_instance_uid),
+                                    &presentation_context.abstract_syntax,
+                                    coerce_missing_uids,
+                                )?;
                                 let file_meta = FileMetaTableBuilder::new()
-                                    .media_storage_sop_class_uid(
-                                        obj.element(tags::SOP_CLASS_UID)
-                                            .whatever_context("missing SOP Class UID")?
-                                            .to_str()
-                                            .whatever_context("could not retrieve SOP Class UID")?,
-                                    )
-                                    .media_storage_sop_instance_uid(
-                                        obj.element(tags::SOP_INSTANCE_UID)
-                                            .whatever_context("missing SOP Instance UID")?
-                                            .to_str()
-                                            .whatever_context("missing SOP Instance UID")?,
-                                    )
+                                    .media_storage_sop_class_uid(&obj_sop_class_uid)
+                                    .media_storage_sop_instance_uid(&obj_sop_instance_uid)
                                     .transfer_syntax(ts)
                                     .build()
                                     .whatever_context(
                                         "failed to build DICOM meta file information",
                                     )?;
+                                let file_path = output_path(
+                                    out_dir,
+                                    path_template,
+                                    &obj,
+                                    &obj_sop_instance_uid,
+                                )?;
                                 let file_obj = obj.with_exact_meta(file_meta);
 
-                                // write the files to the current directory with their SOPInstanceUID as filenames
-                                let mut file_path = out_dir.to_path_buf();
-                                file_path.push(
-                                    sop_instance_uid.trim_end_matches('\0').to_string() + ".dcm",
-                                );
                                 file_obj
                                     .write_to_file(&file_path)
                                     .whatever_context("could not save DICOM object to file")?;
-                                info!("Stored {}", file_path.display());
+                                info!(
+                                    "Stored {} ({})",
+                                    file_path.display(),
+                                    StandardUidDictionary
+                                        .name_of(&obj_sop_class_uid)
+                                        .unwrap_or(&obj_sop_class_uid)
+                                );
+                                if let Some(sidecar) = sidecar {
+                                    let sidecar_path =
+                                        write_sidecar(&file_path, &file_obj, sidecar)?;
+                                    debug!("Wrote metadata to {}", sidecar_path.display());
+                                }
+                                if let Some(index) = index {
+                                    index_file(index, &file_path, &file_obj, ts);
+                                }
+                                if let Some(forwarder) = forwarder {
+                                    forwarder.enqueue(StoredInstance {
+                                        path: file_path,
+                                        sop_class_uid: obj_sop_class_uid.clone(),
+                                        sop_instance_uid: obj_sop_instance_uid.clone(),
+                                        transfer_syntax: ts.trim_end_matches('\0').to_string(),
+                                    });
+                                }
 
                                 // send C-STORE-RSP object
                                 // commands are always in implicit VR LE
@@ -277,6 +331,12 @@ where
                                     dicom_transfer_syntax_registry::entries::IMPLICIT_VR_LITTLE_ENDIAN
                                         .erased();
 
+                                if sop_class_uid.is_empty() {
+                                    sop_class_uid = obj_sop_class_uid;
+                                }
+                                if sop_instance_uid.is_empty() {
+                                    sop_instance_uid = obj_sop_instance_uid;
+                                }
                                 let obj = create_cstore_response(
                                     msgid,
                                     &sop_class_uid,
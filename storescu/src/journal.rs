@@ -0,0 +1,118 @@
+//! Journal of the instances successfully stored,
+//! so that an interrupted batch can be resumed.
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// The set of SOP instances stored so far.
+///
+/// When backed by a file,
+/// the SOP Instance UID of each stored instance is appended to it
+/// as a line of text as soon as the Store SCP confirms it,
+/// and the instances already listed there are skipped on the next run.
+#[derive(Debug, Default)]
+pub struct Journal {
+    stored: HashSet<String>,
+    file: Option<File>,
+}
+
+impl Journal {
+    /// Create a journal which is only kept in memory.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open the journal file at the given path,
+    /// creating it if it does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        let mut stored = HashSet::new();
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    let uid = line.trim();
+                    if !uid.is_empty() {
+                        stored.insert(uid.to_string());
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Journal {
+            stored,
+            file: Some(file),
+        })
+    }
+
+    /// Check whether the instance with the given SOP Instance UID
+    /// was already stored.
+    pub fn contains(&self, sop_instance_uid: &str) -> bool {
+        self.stored.contains(sop_instance_uid)
+    }
+
+    /// Record that the instance with the given SOP Instance UID was stored.
+    pub fn record(&mut self, sop_instance_uid: &str) -> std::io::Result<()> {
+        if !self.stored.insert(sop_instance_uid.to_string()) {
+            return Ok(());
+        }
+        if let Some(file) = &mut self.file {
+            // a single write per line,
+            // so that an interruption cannot leave a partial entry behind
+            // other than at the very end of the file
+            file.write_all(format!("{sop_instance_uid}\n").as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// The time to wait before the given retry (counting from 0),
+/// doubling the base delay after each failed attempt.
+pub fn retry_delay(base: Duration, retry: u32) -> Duration {
+    base.saturating_mul(1 << retry.min(6))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn journal_survives_reopening() {
+        let path =
+            std::env::temp_dir().join(format!("dicom-storescu-journal-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut journal = Journal::open(&path).unwrap();
+        assert!(!journal.contains("1.2.3.4"));
+        journal.record("1.2.3.4").unwrap();
+        journal.record("1.2.3.5").unwrap();
+        journal.record("1.2.3.4").unwrap();
+        assert!(journal.contains("1.2.3.4"));
+        drop(journal);
+
+        let mut journal = Journal::open(&path).unwrap();
+        assert!(journal.contains("1.2.3.4"));
+        assert!(journal.contains("1.2.3.5"));
+        assert!(!journal.contains("1.2.3.6"));
+        journal.record("1.2.3.6").unwrap();
+        drop(journal);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "1.2.3.4\n1.2.3.5\n1.2.3.6\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn retry_delay_backs_off() {
+        let base = Duration::from_secs(2);
+        assert_eq!(retry_delay(base, 0), Duration::from_secs(2));
+        assert_eq!(retry_delay(base, 1), Duration::from_secs(4));
+        assert_eq!(retry_delay(base, 3), Duration::from_secs(16));
+        // capped at 64 times the base delay
+        assert_eq!(retry_delay(base, 20), Duration::from_secs(128));
+    }
+}
@@ -1,16 +1,16 @@
 use clap::Parser;
-use dicom_app_common::TlsOptions;
+use dicom_app_common::{TlsOptions, aeconfig::AeConfigOptions, bandwidth::parse_bandwidth};
 use dicom_core::{DataElement, VR, dicom_value, header::Tag};
 use dicom_dictionary_std::{tags, uids};
 use dicom_encoding::TransferSyntax;
 use dicom_encoding::transfer_syntax;
 use dicom_object::{DefaultDicomObject, StandardDataDictionary, mem::InMemDicomObject};
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
-use dicom_ul::ClientAssociationOptions;
+use dicom_ul::{ClientAssociationOptions, association::BandwidthLimit};
 use indicatif::{ProgressBar, ProgressStyle};
 use snafu::prelude::*;
 use snafu::{Report, Whatever};
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -22,6 +22,11 @@ use tracing_subscriber::filter::EnvFilter;
 use transfer_syntax::TransferSyntaxIndex;
 use walkdir::WalkDir;
 
+use crate::journal::{Journal, retry_delay};
+use crate::report::{FileReport, Outcome, RunReport};
+
+mod journal;
+mod report;
 mod store_async;
 mod store_sync;
 
@@ -31,28 +36,32 @@ mod store_sync;
 struct App {
     /// socket address to Store SCP,
     /// optionally with AE title
-    /// (example: "STORE-SCP@127.0.0.1:104")
-    addr: String,
+    /// (example: "STORE-SCP@127.0.0.1:104"),
+    /// or the name of an AE in the configuration file
+    /// (omit if `--remote` is given)
+    #[arg(required_unless_present = "to")]
+    addr: Option<String>,
     /// the DICOM file(s) to store
-    #[arg(required = true)]
+    #[arg(required_unless_present = "to")]
     files: Vec<PathBuf>,
     /// verbose mode
     #[arg(short = 'v', long = "verbose")]
     verbose: bool,
     /// the calling Application Entity title
-    #[arg(long = "calling-ae-title", default_value = "STORE-SCU")]
-    calling_ae_title: String,
+    /// [default: as in the configuration, or STORE-SCU]
+    #[arg(long = "calling-ae-title")]
+    calling_ae_title: Option<String>,
     /// the called Application Entity title,
     /// overrides AE title in address if present [default: ANY-SCP]
     #[arg(long = "called-ae-title")]
     called_ae_title: Option<String>,
     /// the maximum PDU length accepted by the SCU
+    /// [default: from the AE configuration, or 16378]
     #[arg(
         long = "max-pdu-length",
-        default_value = "16378",
         value_parser(clap::value_parser!(u32).range(1018..))
     )]
-    max_pdu_length: u32,
+    max_pdu_length: Option<u32>,
     /// fail if not all DICOM files can be transferred
     #[arg(long = "fail-first")]
     fail_first: bool,
@@ -61,6 +70,12 @@ struct App {
     // hide option if transcoding is disabled
     #[cfg_attr(not(feature = "transcode"), arg(hide(true)))]
     never_transcode: bool,
+    /// allow transcoding to a lossy transfer syntax
+    /// if the SCP does not accept a lossless one
+    #[arg(long("allow-lossy"), conflicts_with("never_transcode"))]
+    // hide option if transcoding is disabled
+    #[cfg_attr(not(feature = "transcode"), arg(hide(true)))]
+    allow_lossy: bool,
     /// ignore SOP class in presentation context selection
     #[arg(long)]
     ignore_sop_class: bool,
@@ -102,11 +117,85 @@ struct App {
     /// Dispatch these many service users to send files in parallel
     #[arg(short = 'c', long = "concurrency")]
     concurrency: Option<usize>,
+    /// retry these many times after a network failure,
+    /// resuming with the files not yet stored
+    #[arg(long = "retry", value_name = "N", default_value = "0")]
+    retry: u32,
+    /// the time to wait before the first retry in seconds,
+    /// doubled after each failed attempt
+    #[arg(long = "retry-delay", value_name = "SECONDS", default_value = "1")]
+    retry_delay: u64,
+    /// record the stored instances in this file,
+    /// skipping the instances already recorded there
+    #[arg(long = "journal", value_name = "FILE")]
+    journal: Option<PathBuf>,
+    /// limit the outgoing throughput to this many bytes per second,
+    /// shared by all service users
+    /// (suffixes k, M and G are accepted, example: "20M")
+    #[arg(long = "max-bandwidth", value_name = "RATE", value_parser = parse_bandwidth)]
+    max_bandwidth: Option<u64>,
+    /// write a JSON report of the run to this file,
+    /// with the outcome of each file and totals per SOP class
+    #[arg(long = "report", value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    #[command(flatten, next_help_heading = "AE Configuration Options")]
+    ae_config: AeConfigOptions,
 
     #[command(flatten, next_help_heading = "TLS Options")]
     tls: TlsOptions,
 }
 
+/// Transfer syntaxes with encapsulated pixel data
+/// which are always lossless.
+const LOSSLESS_TRANSFER_SYNTAXES: &[&str] = &[
+    uids::RLE_LOSSLESS,
+    uids::JPEG_LOSSLESS,
+    uids::JPEG_LOSSLESS_SV1,
+    uids::JPEGLS_LOSSLESS,
+    uids::JPEG2000_LOSSLESS,
+    uids::JPEG2000MC_LOSSLESS,
+    uids::HTJ2K_LOSSLESS,
+    uids::HTJ2K_LOSSLESS_RPCL,
+    uids::JPEGXL_LOSSLESS,
+];
+
+/// The maximum number of presentation contexts in an association.
+const MAX_PRESENTATION_CONTEXTS: usize = 128;
+
+/// The transfer syntax conversions allowed
+/// when the SCP does not accept a file's transfer syntax.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Transcoding {
+    /// Only send files in their original transfer syntax
+    Never,
+    /// Decompress, or recompress to a lossless transfer syntax
+    Lossless,
+    /// Decompress, or recompress to any transfer syntax
+    Lossy,
+}
+
+impl Transcoding {
+    /// Check whether files may be recompressed to the given transfer syntax.
+    fn can_recompress_to(self, ts: &TransferSyntax) -> bool {
+        ts.is_encapsulated_pixel_data()
+            && ts.can_encode()
+            && match self {
+                Transcoding::Never => false,
+                Transcoding::Lossless => is_lossless(ts.uid()),
+                Transcoding::Lossy => true,
+            }
+    }
+}
+
+/// Check whether a transfer syntax is known to preserve pixel data exactly.
+fn is_lossless(ts_uid: &str) -> bool {
+    TransferSyntaxRegistry
+        .get(ts_uid)
+        .is_some_and(|ts| !ts.is_encapsulated_pixel_data())
+        || LOSSLESS_TRANSFER_SYNTAXES.contains(&ts_uid)
+}
+
 #[derive(Debug)]
 struct DicomFile {
     /// File path
@@ -180,6 +269,73 @@ enum Error {
     Tls {
         source: dicom_app_common::TlsError,
     },
+
+    /// Could not resolve the Store SCP
+    AeConfig {
+        source: dicom_app_common::aeconfig::AeConfigError,
+    },
+
+    /// Could not access the journal file
+    Journal {
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Could not store instance {sop_instance_uid} (status code {status:04X}H)"))]
+    StoreFailed {
+        sop_instance_uid: String,
+        status: u16,
+    },
+
+    /// Could not write the report
+    WriteReport {
+        source: std::io::Error,
+    },
+}
+
+impl Error {
+    /// Whether the error is a failure of the connection to the Store SCP,
+    /// after which sending may be retried on a new association.
+    fn is_transient(&self) -> bool {
+        matches!(self, Error::Scu { .. } | Error::WriteIO { .. })
+    }
+}
+
+/// Open the journal file if one was requested.
+fn open_journal(path: Option<&Path>) -> Result<Journal, Error> {
+    match path {
+        Some(path) => Journal::open(path).context(JournalSnafu),
+        None => Ok(Journal::in_memory()),
+    }
+}
+
+/// Remove the files recorded in the journal as already stored.
+fn skip_stored(
+    dicom_files: Vec<DicomFile>,
+    journal: &Journal,
+    report: &mut RunReport,
+) -> Vec<DicomFile> {
+    let (stored, dicom_files): (Vec<_>, Vec<_>) = dicom_files
+        .into_iter()
+        .partition(|file| journal.contains(&file.sop_instance_uid));
+    if !stored.is_empty() {
+        info!(
+            "Skipping {} files already stored according to the journal",
+            stored.len()
+        );
+    }
+    for file in &stored {
+        report.record(FileReport::new(file, Outcome::Skipped));
+    }
+    dicom_files
+}
+
+/// Write the report of the run if one was requested.
+fn write_report(report: &RunReport, path: Option<&Path>) -> Result<(), Error> {
+    if let Some(path) = path {
+        report.write_to(path).context(WriteReportSnafu)?;
+        info!("Report written to {}", path.display());
+    }
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -193,12 +349,17 @@ pub fn get_scu_options<'a>(
     saml_assertion: Option<String>,
     jwt: Option<String>,
     presentation_contexts: &'a HashSet<(String, String)>,
+    bandwidth_limit: Option<BandwidthLimit>,
     #[cfg(feature = "tls")] tls_options: rustls::ClientConfig,
 ) -> ClientAssociationOptions<'a> {
     let mut scu_init = ClientAssociationOptions::new()
         .calling_ae_title(calling_ae_title)
         .max_pdu_length(max_pdu_length);
 
+    if let Some(limit) = bandwidth_limit {
+        scu_init = scu_init.bandwidth_limit(limit);
+    }
+
     #[cfg(feature = "tls")]
     {
         scu_init = scu_init.server_name("localhost").tls_config(tls_options);
@@ -292,7 +453,7 @@ fn main() {
 fn check_files(
     files: Vec<PathBuf>,
     verbose: bool,
-    never_transcode: bool,
+    transcoding: Transcoding,
 ) -> (Vec<DicomFile>, HashSet<(String, String)>) {
     let mut checked_files: Vec<PathBuf> = vec![];
     let mut dicom_files: Vec<DicomFile> = vec![];
@@ -327,7 +488,7 @@ fn check_files(
                 // also accept uncompressed transfer syntaxes
                 // as mandated by the standard
                 // (though it might not always be able to fulfill this)
-                if !never_transcode {
+                if transcoding != Transcoding::Never {
                     presentation_contexts.insert((
                         dicom_file.sop_class_uid.to_string(),
                         uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
@@ -350,6 +511,35 @@ fn check_files(
         eprintln!("No supported files to transfer");
         std::process::exit(-1);
     }
+
+    // also propose the transfer syntaxes which files can be recompressed to,
+    // in case the SCP does not accept uncompressed data
+    let recompress_targets: Vec<_> = TransferSyntaxRegistry
+        .iter()
+        .filter(|ts| transcoding.can_recompress_to(ts))
+        .map(|ts| ts.uid().to_string())
+        .collect();
+    let sop_classes: HashSet<_> = dicom_files
+        .iter()
+        .map(|f| f.sop_class_uid.clone())
+        .collect();
+    let recompress_contexts: HashSet<_> = sop_classes
+        .iter()
+        .flat_map(|sop_class_uid| {
+            recompress_targets
+                .iter()
+                .map(move |ts| (sop_class_uid.clone(), ts.clone()))
+        })
+        .filter(|pc| !presentation_contexts.contains(pc))
+        .collect();
+    if presentation_contexts.len() + recompress_contexts.len() <= MAX_PRESENTATION_CONTEXTS {
+        presentation_contexts.extend(recompress_contexts);
+    } else if !recompress_contexts.is_empty() {
+        warn!(
+            "Too many presentation contexts, not proposing recompression to other transfer syntaxes"
+        );
+    }
+
     (dicom_files, presentation_contexts)
 }
 
@@ -362,7 +552,8 @@ fn run(app: App) -> Result<(), Error> {
         called_ae_title,
         max_pdu_length,
         fail_first,
-        mut never_transcode,
+        never_transcode,
+        allow_lossy,
         ignore_sop_class,
         username,
         password,
@@ -370,14 +561,36 @@ fn run(app: App) -> Result<(), Error> {
         saml_assertion,
         jwt,
         concurrency: _,
+        retry,
+        retry_delay: retry_delay_secs,
+        journal,
+        max_bandwidth,
+        report: report_path,
+        ae_config,
         tls,
     } = app;
 
+    let (peer, first_file) = ae_config.resolve(addr).context(AeConfigSnafu)?;
+    let max_pdu_length = peer.max_pdu_length_or(max_pdu_length, 16378);
+    let addr = peer.address;
+    let calling_ae_title = calling_ae_title
+        .or(peer.calling_ae_title)
+        .unwrap_or_else(|| "STORE-SCU".to_string());
+    let files: Vec<PathBuf> = first_file
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(files)
+        .collect();
+
     // never transcode if the feature is disabled
-    if cfg!(not(feature = "transcode")) {
-        never_transcode = true;
-    }
-    let tls_enabled = tls.enabled;
+    let transcoding = if never_transcode || cfg!(not(feature = "transcode")) {
+        Transcoding::Never
+    } else if allow_lossy {
+        Transcoding::Lossy
+    } else {
+        Transcoding::Lossless
+    };
+    let tls_enabled = tls.enabled || peer.tls;
 
     #[cfg(not(feature = "tls"))]
     if tls_enabled {
@@ -388,25 +601,20 @@ fn run(app: App) -> Result<(), Error> {
 
     #[cfg(feature = "tls")]
     let config = tls.client_config().context(TlsSnafu)?;
+    let bandwidth_limit = max_bandwidth.map(BandwidthLimit::new);
 
     if verbose {
         info!("Establishing association with '{}'...", &addr);
     }
-    let (dicom_files, presentation_contexts) = check_files(files, verbose, never_transcode);
+    let mut journal = open_journal(journal.as_deref())?;
+    let mut report = RunReport::default();
+    let (dicom_files, presentation_contexts) = check_files(files, verbose, transcoding);
+    let mut dicom_files: VecDeque<_> = skip_stored(dicom_files, &journal, &mut report).into();
+    if dicom_files.is_empty() {
+        info!("All files were already stored");
+        return write_report(&report, report_path.as_deref());
+    }
 
-    let scu_options = get_scu_options(
-        calling_ae_title,
-        called_ae_title,
-        max_pdu_length,
-        username,
-        password,
-        kerberos_service_ticket,
-        saml_assertion,
-        jwt,
-        &presentation_contexts,
-        #[cfg(feature = "tls")]
-        config,
-    );
     let progress_bar;
     if !verbose {
         progress_bar = Some(ProgressBar::new(dicom_files.len() as u64));
@@ -422,38 +630,75 @@ fn run(app: App) -> Result<(), Error> {
         progress_bar = None;
     }
 
-    #[cfg(feature = "tls")]
-    if tls_enabled {
-        let scu = scu_options
-            .establish_with_tls(&addr)
-            .map_err(Box::from)
-            .context(ScuSnafu)?;
-        store_sync::inner(
-            scu,
-            dicom_files,
-            &progress_bar,
-            fail_first,
-            verbose,
-            never_transcode,
-            ignore_sop_class,
-        )?;
-        return Ok(());
-    }
-
-    let scu = scu_options
-        .establish_with(&addr)
-        .map_err(Box::from)
-        .context(ScuSnafu)?;
-    store_sync::inner(
-        scu,
-        dicom_files,
-        &progress_bar,
-        fail_first,
-        verbose,
-        never_transcode,
-        ignore_sop_class,
-    )?;
-    Ok(())
+    let mut attempt = 0;
+    let result = loop {
+        let scu_options = get_scu_options(
+            calling_ae_title.clone(),
+            called_ae_title.clone(),
+            max_pdu_length,
+            username.clone(),
+            password.clone(),
+            kerberos_service_ticket.clone(),
+            saml_assertion.clone(),
+            jwt.clone(),
+            &presentation_contexts,
+            bandwidth_limit.clone(),
+            #[cfg(feature = "tls")]
+            config.clone(),
+        );
+        let result = (|| {
+            #[cfg(feature = "tls")]
+            if tls_enabled {
+                let scu = scu_options
+                    .establish_with_tls(&addr)
+                    .map_err(Box::from)
+                    .context(ScuSnafu)?;
+                return store_sync::inner(
+                    scu,
+                    &mut dicom_files,
+                    &mut journal,
+                    &mut report,
+                    &progress_bar,
+                    fail_first,
+                    verbose,
+                    transcoding,
+                    ignore_sop_class,
+                );
+            }
+
+            let scu = scu_options
+                .establish_with(&addr)
+                .map_err(Box::from)
+                .context(ScuSnafu)?;
+            store_sync::inner(
+                scu,
+                &mut dicom_files,
+                &mut journal,
+                &mut report,
+                &progress_bar,
+                fail_first,
+                verbose,
+                transcoding,
+                ignore_sop_class,
+            )
+        })();
+        match result {
+            Err(e) if e.is_transient() && attempt < retry => {
+                let delay = retry_delay(Duration::from_secs(retry_delay_secs), attempt);
+                warn!(
+                    "{}; retrying in {} s ({} files left)",
+                    Report::from_error(e),
+                    delay.as_secs(),
+                    dicom_files.len()
+                );
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+    let written = write_report(&report, report_path.as_deref());
+    result.and(written)
 }
 
 async fn run_async() -> Result<(), Error> {
@@ -465,7 +710,8 @@ async fn run_async() -> Result<(), Error> {
         called_ae_title,
         max_pdu_length,
         fail_first,
-        mut never_transcode,
+        never_transcode,
+        allow_lossy,
         ignore_sop_class,
         username,
         password,
@@ -473,15 +719,36 @@ async fn run_async() -> Result<(), Error> {
         saml_assertion,
         jwt,
         concurrency,
+        retry,
+        retry_delay: retry_delay_secs,
+        journal,
+        max_bandwidth,
+        report: report_path,
+        ae_config,
         tls,
     } = App::parse();
 
-    // never transcode if the feature is disabled
-    if cfg!(not(feature = "transcode")) {
-        never_transcode = true;
-    }
+    let (peer, first_file) = ae_config.resolve(addr).context(AeConfigSnafu)?;
+    let max_pdu_length = peer.max_pdu_length_or(max_pdu_length, 16378);
+    let addr = peer.address;
+    let calling_ae_title = calling_ae_title
+        .or(peer.calling_ae_title)
+        .unwrap_or_else(|| "STORE-SCU".to_string());
+    let files: Vec<PathBuf> = first_file
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(files)
+        .collect();
 
-    let tls_enabled = tls.enabled;
+    // never transcode if the feature is disabled
+    let transcoding = if never_transcode || cfg!(not(feature = "transcode")) {
+        Transcoding::Never
+    } else if allow_lossy {
+        Transcoding::Lossy
+    } else {
+        Transcoding::Lossless
+    };
+    let tls_enabled = tls.enabled || peer.tls;
     #[cfg(not(feature = "tls"))]
     if tls_enabled {
         return Err(Error::Tls {
@@ -491,14 +758,24 @@ async fn run_async() -> Result<(), Error> {
 
     #[cfg(feature = "tls")]
     let config = tls.client_config().context(TlsSnafu)?;
+    let bandwidth_limit = max_bandwidth.map(BandwidthLimit::new);
 
     if verbose {
         info!("Establishing association with '{}'...", &addr);
     }
     let (dicom_files, presentation_contexts) =
-        tokio::task::spawn_blocking(move || check_files(files, verbose, never_transcode))
+        tokio::task::spawn_blocking(move || check_files(files, verbose, transcoding))
             .await
             .unwrap();
+    let journal = open_journal(journal.as_deref())?;
+    let mut report = RunReport::default();
+    let dicom_files = skip_stored(dicom_files, &journal, &mut report);
+    if dicom_files.is_empty() {
+        info!("All files were already stored");
+        return write_report(&report, report_path.as_deref());
+    }
+    let journal = Arc::new(Mutex::new(journal));
+    let report = Arc::new(Mutex::new(report));
     let num_files = dicom_files.len();
     let dicom_files = Arc::new(Mutex::new(dicom_files));
     let mut tasks = tokio::task::JoinSet::new();
@@ -522,6 +799,9 @@ async fn run_async() -> Result<(), Error> {
     for _ in 0..concurrency.unwrap_or(1) {
         let pbx = progress_bar.clone();
         let d_files = dicom_files.clone();
+        let journal = journal.clone();
+        let report = report.clone();
+        let bandwidth_limit = bandwidth_limit.clone();
         let pc = presentation_contexts.clone();
         let addr = addr.clone();
         let jwt = jwt.clone();
@@ -534,69 +814,89 @@ async fn run_async() -> Result<(), Error> {
         #[cfg(feature = "tls")]
         let tls_config_clone = config.clone();
         tasks.spawn(async move {
-            let scu_options = get_scu_options(
-                calling_ae_title,
-                called_ae_title,
-                max_pdu_length,
-                username,
-                password,
-                kerberos_service_ticket,
-                saml_assertion,
-                jwt,
-                &pc,
-                #[cfg(feature = "tls")]
-                tls_config_clone,
-            );
-            #[cfg(feature = "tls")]
-            if tls_enabled {
-                let scu = scu_options
-                    .establish_with_async_tls(&addr)
+            let mut attempt = 0;
+            loop {
+                let scu_options = get_scu_options(
+                    calling_ae_title.clone(),
+                    called_ae_title.clone(),
+                    max_pdu_length,
+                    username.clone(),
+                    password.clone(),
+                    kerberos_service_ticket.clone(),
+                    saml_assertion.clone(),
+                    jwt.clone(),
+                    &pc,
+                    bandwidth_limit.clone(),
+                    #[cfg(feature = "tls")]
+                    tls_config_clone.clone(),
+                );
+                let result = async {
+                    #[cfg(feature = "tls")]
+                    if tls_enabled {
+                        let scu = scu_options
+                            .establish_with_async_tls(&addr)
+                            .await
+                            .map_err(Box::from)
+                            .context(ScuSnafu)?;
+                        return store_async::inner(
+                            scu,
+                            d_files.clone(),
+                            journal.clone(),
+                            report.clone(),
+                            pbx.clone(),
+                            transcoding,
+                            fail_first,
+                            verbose,
+                            ignore_sop_class,
+                        )
+                        .await;
+                    }
+                    let scu = scu_options
+                        .establish_with_async(&addr)
+                        .await
+                        .map_err(Box::from)
+                        .context(ScuSnafu)?;
+                    store_async::inner(
+                        scu,
+                        d_files.clone(),
+                        journal.clone(),
+                        report.clone(),
+                        pbx.clone(),
+                        transcoding,
+                        fail_first,
+                        verbose,
+                        ignore_sop_class,
+                    )
                     .await
-                    .map_err(Box::from)
-                    .context(ScuSnafu)?;
-                return store_async::inner(
-                    scu,
-                    d_files,
-                    pbx,
-                    never_transcode,
-                    fail_first,
-                    verbose,
-                    ignore_sop_class,
-                )
+                }
                 .await;
+                match result {
+                    Err(e) if e.is_transient() && attempt < retry => {
+                        let delay = retry_delay(Duration::from_secs(retry_delay_secs), attempt);
+                        warn!(
+                            "{}; retrying in {} s",
+                            Report::from_error(e),
+                            delay.as_secs()
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    result => return result,
+                }
             }
-            let scu = scu_options
-                .establish_with_async(&addr)
-                .await
-                .map_err(Box::from)
-                .context(ScuSnafu)?;
-            store_async::inner(
-                scu,
-                d_files,
-                pbx,
-                never_transcode,
-                fail_first,
-                verbose,
-                ignore_sop_class,
-            )
-            .await
         });
     }
     while let Some(result) = tasks.join_next().await {
         match result {
-            Ok(Ok(())) => {}
-            Ok(Err(e)) => {
+            Ok(Ok(())) => continue,
+            Ok(Err(e)) => error!("{}", Report::from_error(e)),
+            Err(e) => error!("{}", Report::from_error(e)),
+        }
+        if fail_first {
+            if let Err(e) = write_report(&*report.lock().await, report_path.as_deref()) {
                 error!("{}", Report::from_error(e));
-                if fail_first {
-                    std::process::exit(-2)
-                }
-            }
-            Err(e) => {
-                error!("{}", Report::from_error(e));
-                if fail_first {
-                    std::process::exit(-2)
-                }
             }
+            std::process::exit(-2)
         }
     }
 
@@ -604,7 +904,7 @@ async fn run_async() -> Result<(), Error> {
         pb.lock().await.finish_with_message("done")
     };
 
-    Ok(())
+    write_report(&*report.lock().await, report_path.as_deref())
 }
 fn store_req_command(
     storage_sop_class_uid: &str,
@@ -676,7 +976,7 @@ fn check_presentation_contexts(
     file: &DicomFile,
     pcs: &[dicom_ul::pdu::PresentationContextNegotiated],
     ignore_sop_class: bool,
-    never_transcode: bool,
+    transcoding: Transcoding,
 ) -> Result<(dicom_ul::pdu::PresentationContextNegotiated, String), Error> {
     debug!("Testing file {file:?}");
 
@@ -717,20 +1017,33 @@ fn check_presentation_contexts(
     let pc = match pc {
         Some(pc) => pc,
         None => {
-            if never_transcode || !file_ts.can_decode_all() {
+            if transcoding == Transcoding::Never || !file_ts.can_decode_all() {
                 NoPresentationContextSnafu.fail()?
             }
 
             // Else, if transcoding is possible, we go for it.
-            pcs.iter()
-                // SOP class
-                .filter(|pc| ignore_sop_class || pc.abstract_syntax == file.sop_class_uid)
+            let candidates = || {
+                pcs.iter()
+                    // SOP class
+                    .filter(|pc| ignore_sop_class || pc.abstract_syntax == file.sop_class_uid)
+            };
+            candidates()
                 // accept explicit VR little endian
                 .find(|pc| pc.transfer_syntax == uids::EXPLICIT_VR_LITTLE_ENDIAN)
-                .or_else(||
                 // accept implicit VR little endian
-                pcs.iter()
-                    .find(|pc| pc.transfer_syntax == uids::IMPLICIT_VR_LITTLE_ENDIAN))
+                .or_else(|| {
+                    candidates().find(|pc| pc.transfer_syntax == uids::IMPLICIT_VR_LITTLE_ENDIAN)
+                })
+                // recompress, preferably to a lossless transfer syntax
+                .or_else(|| {
+                    candidates()
+                        .filter(|pc| {
+                            TransferSyntaxRegistry
+                                .get(&pc.transfer_syntax)
+                                .is_some_and(|ts| transcoding.can_recompress_to(ts))
+                        })
+                        .min_by_key(|pc| !is_lossless(&pc.transfer_syntax))
+                })
                 .context(NoPresentationContextSnafu)?
         }
     };
@@ -782,11 +1095,53 @@ fn into_ts(
 
 #[cfg(test)]
 mod tests {
-    use crate::App;
+    use crate::{App, DicomFile, Transcoding, check_presentation_contexts};
     use clap::CommandFactory;
+    use dicom_dictionary_std::uids;
+    use dicom_ul::pdu::{PresentationContextNegotiated, PresentationContextResultReason};
 
     #[test]
     fn verify_cli() {
         App::command().debug_assert();
     }
+
+    #[test]
+    fn select_presentation_context_with_transcoding() {
+        let file = DicomFile {
+            file: "image.dcm".into(),
+            sop_class_uid: uids::CT_IMAGE_STORAGE.to_string(),
+            sop_instance_uid: "2.25.1".to_string(),
+            file_transfer_syntax: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+            ts_selected: None,
+            pc_selected: None,
+        };
+        let accepted = |id, transfer_syntax: &str| PresentationContextNegotiated {
+            id,
+            reason: PresentationContextResultReason::Acceptance,
+            transfer_syntax: transfer_syntax.to_string(),
+            abstract_syntax: uids::CT_IMAGE_STORAGE.to_string(),
+        };
+
+        let pcs = [
+            accepted(1, uids::JPEG_BASELINE8_BIT),
+            accepted(3, uids::RLE_LOSSLESS),
+        ];
+        assert!(check_presentation_contexts(&file, &pcs, false, Transcoding::Never).is_err());
+        let (pc, ts) =
+            check_presentation_contexts(&file, &pcs, false, Transcoding::Lossless).unwrap();
+        assert_eq!(pc.id, 3);
+        assert_eq!(ts, uids::RLE_LOSSLESS);
+        // lossless transfer syntaxes are still preferred
+        let (pc, _) = check_presentation_contexts(&file, &pcs, false, Transcoding::Lossy).unwrap();
+        assert_eq!(pc.id, 3);
+
+        // uncompressed transfer syntaxes are preferred over recompression
+        let pcs = [
+            accepted(1, uids::RLE_LOSSLESS),
+            accepted(3, uids::IMPLICIT_VR_LITTLE_ENDIAN),
+        ];
+        let (pc, _) =
+            check_presentation_contexts(&file, &pcs, false, Transcoding::Lossless).unwrap();
+        assert_eq!(pc.id, 3);
+    }
 }
@@ -0,0 +1,310 @@
+//! Machine-readable report of a storage run.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde_json::{Value, json};
+
+use crate::DicomFile;
+
+/// The outcome of sending one file
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The Store SCP stored the instance
+    Success,
+    /// The Store SCP stored the instance with a warning
+    Warning,
+    /// The instance could not be stored
+    Failure,
+    /// The file was not sent
+    Skipped,
+}
+
+impl Outcome {
+    /// Classify the status of a C-STORE response.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            0 => Outcome::Success,
+            1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF | 0xFF00 | 0xFF01 => Outcome::Warning,
+            _ => Outcome::Failure,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Warning => "warning",
+            Outcome::Failure => "failure",
+            Outcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// What happened to one file
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    path: PathBuf,
+    sop_class_uid: String,
+    sop_instance_uid: String,
+    file_transfer_syntax: String,
+    /// the transfer syntax of the presentation context selected, if any
+    transfer_syntax: Option<String>,
+    file_size: Option<u64>,
+    outcome: Outcome,
+    /// the status of the C-STORE response, if one was received
+    status: Option<u16>,
+    /// the size of the C-STORE request, command included
+    bytes_sent: u64,
+    /// the time from encoding the request to receiving the response
+    duration: Duration,
+    error: Option<String>,
+}
+
+impl FileReport {
+    /// Start the report of a file with the given outcome.
+    pub fn new(file: &DicomFile, outcome: Outcome) -> Self {
+        FileReport {
+            path: file.file.clone(),
+            sop_class_uid: file.sop_class_uid.clone(),
+            sop_instance_uid: file.sop_instance_uid.clone(),
+            file_transfer_syntax: file.file_transfer_syntax.clone(),
+            transfer_syntax: file.ts_selected.clone(),
+            file_size: std::fs::metadata(&file.file).ok().map(|m| m.len()),
+            outcome,
+            status: None,
+            bytes_sent: 0,
+            duration: Duration::ZERO,
+            error: None,
+        }
+    }
+
+    /// Report a file sent, with the status of the C-STORE response,
+    /// the size of the request and the time taken.
+    pub fn from_response(
+        file: &DicomFile,
+        status: u16,
+        bytes_sent: usize,
+        duration: Duration,
+    ) -> Self {
+        FileReport {
+            status: Some(status),
+            bytes_sent: bytes_sent as u64,
+            duration,
+            ..FileReport::new(file, Outcome::from_status(status))
+        }
+    }
+
+    /// Attach the error which prevented the file from being stored.
+    pub fn with_error(mut self, error: impl std::error::Error) -> Self {
+        self.error = Some(snafu::Report::from_error(error).to_string());
+        self
+    }
+
+    /// Whether the file was converted to another transfer syntax for sending
+    fn transcoded(&self) -> bool {
+        self.transfer_syntax
+            .as_ref()
+            .is_some_and(|ts| *ts != self.file_transfer_syntax)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "path": self.path.display().to_string(),
+            "sop_class_uid": self.sop_class_uid,
+            "sop_instance_uid": self.sop_instance_uid,
+            "file_transfer_syntax": self.file_transfer_syntax,
+            "transfer_syntax": self.transfer_syntax,
+            "transcoded": self.transcoded(),
+            "outcome": self.outcome.as_str(),
+            "status": self.status,
+            "file_size": self.file_size,
+            "bytes_sent": self.bytes_sent,
+            "duration_ms": duration_ms(self.duration),
+            "error": self.error,
+        })
+    }
+}
+
+/// Counters over a group of files
+#[derive(Debug, Default)]
+struct Totals {
+    files: u64,
+    success: u64,
+    warning: u64,
+    failure: u64,
+    skipped: u64,
+    transcoded: u64,
+    file_size: u64,
+    bytes_sent: u64,
+    duration: Duration,
+}
+
+impl Totals {
+    fn add(&mut self, file: &FileReport) {
+        self.files += 1;
+        match file.outcome {
+            Outcome::Success => self.success += 1,
+            Outcome::Warning => self.warning += 1,
+            Outcome::Failure => self.failure += 1,
+            Outcome::Skipped => self.skipped += 1,
+        }
+        if file.transcoded() {
+            self.transcoded += 1;
+        }
+        if file.outcome != Outcome::Skipped {
+            self.file_size += file.file_size.unwrap_or_default();
+        }
+        self.bytes_sent += file.bytes_sent;
+        self.duration += file.duration;
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "files": self.files,
+            "success": self.success,
+            "warning": self.warning,
+            "failure": self.failure,
+            "skipped": self.skipped,
+            "transcoded": self.transcoded,
+            "file_size": self.file_size,
+            "bytes_sent": self.bytes_sent,
+            "duration_ms": duration_ms(self.duration),
+        })
+    }
+}
+
+/// The report of a whole run,
+/// with one entry per file to send
+#[derive(Debug)]
+pub struct RunReport {
+    started: SystemTime,
+    start: Instant,
+    files: Vec<FileReport>,
+}
+
+impl Default for RunReport {
+    fn default() -> Self {
+        RunReport {
+            started: SystemTime::now(),
+            start: Instant::now(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl RunReport {
+    /// Record what happened to a file,
+    /// replacing any previous entry for the same file
+    /// (such as a failure before a successful retry).
+    pub fn record(&mut self, entry: FileReport) {
+        match self.files.iter_mut().find(|f| f.path == entry.path) {
+            Some(existing) => *existing = entry,
+            None => self.files.push(entry),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut summary = Totals::default();
+        let mut sop_classes: BTreeMap<&str, Totals> = BTreeMap::new();
+        for file in &self.files {
+            summary.add(file);
+            sop_classes
+                .entry(&file.sop_class_uid)
+                .or_default()
+                .add(file);
+        }
+        let started = self
+            .started
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        json!({
+            "started_at": started.as_secs(),
+            "duration_ms": duration_ms(self.start.elapsed()),
+            "summary": summary.to_json(),
+            "sop_classes": sop_classes
+                .into_iter()
+                .map(|(uid, totals)| {
+                    let mut value = totals.to_json();
+                    value["sop_class_uid"] = Value::from(uid);
+                    value
+                })
+                .collect::<Vec<_>>(),
+            "files": self.files.iter().map(FileReport::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write the report as JSON to the given file.
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), &self.to_json())?;
+        Ok(())
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_dictionary_std::uids;
+
+    fn file(name: &str, sop_class_uid: &str, ts_selected: &str) -> DicomFile {
+        DicomFile {
+            file: name.into(),
+            sop_class_uid: sop_class_uid.to_string(),
+            sop_instance_uid: format!("2.25.{}", name.len()),
+            file_transfer_syntax: uids::EXPLICIT_VR_LITTLE_ENDIAN.to_string(),
+            ts_selected: Some(ts_selected.to_string()),
+            pc_selected: None,
+        }
+    }
+
+    #[test]
+    fn aggregate_per_sop_class() {
+        let mut report = RunReport::default();
+        let ct1 = file("ct1.dcm", uids::CT_IMAGE_STORAGE, uids::RLE_LOSSLESS);
+        let ct2 = file(
+            "ct22.dcm",
+            uids::CT_IMAGE_STORAGE,
+            uids::EXPLICIT_VR_LITTLE_ENDIAN,
+        );
+        let mr = file(
+            "mr333.dcm",
+            uids::MR_IMAGE_STORAGE,
+            uids::EXPLICIT_VR_LITTLE_ENDIAN,
+        );
+
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        report.record(FileReport::new(&ct1, Outcome::Failure).with_error(reset));
+        // replaced after a retry
+        let duration = Duration::from_millis(20);
+        report.record(FileReport::from_response(&ct1, 0, 1_000, duration));
+        report.record(FileReport::from_response(&ct2, 0xB000, 500, duration));
+        report.record(FileReport::from_response(&mr, 0xA700, 200, duration));
+
+        let json = report.to_json();
+        let summary = &json["summary"];
+        assert_eq!(summary["files"], 3);
+        assert_eq!(summary["success"], 1);
+        assert_eq!(summary["warning"], 1);
+        assert_eq!(summary["failure"], 1);
+        assert_eq!(summary["transcoded"], 1);
+        assert_eq!(summary["bytes_sent"], 1_700);
+        assert_eq!(summary["duration_ms"], 60);
+
+        let sop_classes = json["sop_classes"].as_array().unwrap();
+        assert_eq!(sop_classes.len(), 2);
+        assert_eq!(sop_classes[0]["sop_class_uid"], uids::CT_IMAGE_STORAGE);
+        assert_eq!(sop_classes[0]["files"], 2);
+        assert_eq!(sop_classes[0]["bytes_sent"], 1_500);
+        assert_eq!(sop_classes[1]["failure"], 1);
+
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files[0]["outcome"], "success");
+        assert_eq!(files[0]["transfer_syntax"], uids::RLE_LOSSLESS);
+        assert_eq!(files[0]["error"], Value::Null);
+        assert_eq!(files[2]["status"], 0xA700);
+    }
+}
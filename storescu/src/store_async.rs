@@ -1,4 +1,4 @@
-use std::{io::stderr, sync::Arc};
+use std::{io::stderr, sync::Arc, time::Instant};
 
 use dicom_dictionary_std::tags;
 use dicom_encoding::TransferSyntaxIndex;
@@ -14,16 +14,22 @@ use snafu::{OptionExt, Report, ResultExt};
 use tokio::{io::AsyncWriteExt, sync::Mutex};
 use tracing::{debug, error, info, warn};
 
+use crate::journal::Journal;
+use crate::report::{FileReport, Outcome, RunReport};
 use crate::{
-    ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error, MissingAttributeSnafu,
-    ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu, UnsupportedFileTransferSyntaxSnafu,
-    WriteDatasetSnafu, check_presentation_contexts, into_ts, store_req_command,
+    ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error, JournalSnafu, MissingAttributeSnafu,
+    ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu, StoreFailedSnafu, Transcoding,
+    UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu, WriteIOSnafu,
+    check_presentation_contexts, into_ts, store_req_command,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub async fn send_file<T>(
     mut scu: AsyncClientAssociation<T>,
-    file: DicomFile,
+    file: &DicomFile,
     message_id: u16,
+    journal: &Mutex<Journal>,
+    report: &Mutex<RunReport>,
     progress_bar: Option<&Arc<tokio::sync::Mutex<ProgressBar>>>,
     verbose: bool,
     fail_first: bool,
@@ -31,7 +37,8 @@ pub async fn send_file<T>(
 where
     T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
-    if let (Some(pc_selected), Some(ts_uid_selected)) = (file.pc_selected, file.ts_selected) {
+    if let (Some(pc_selected), Some(ts_uid_selected)) = (&file.pc_selected, &file.ts_selected) {
+        let start = Instant::now();
         let cmd = store_req_command(&file.sop_class_uid, &file.sop_instance_uid, message_id);
 
         let mut cmd_data = Vec::with_capacity(128);
@@ -49,7 +56,7 @@ where
                 path: file.file.display().to_string(),
             })?;
         let ts_selected = TransferSyntaxRegistry
-            .get(&ts_uid_selected)
+            .get(ts_uid_selected)
             .with_context(|| UnsupportedFileTransferSyntaxSnafu {
                 uid: ts_uid_selected.to_string(),
             })?;
@@ -109,8 +116,7 @@ where
 
             {
                 let mut pdata = scu.send_pdata(pc_selected.id);
-                pdata.write_all(&object_data).await.unwrap();
-                //.whatever_context("Failed to send C-STORE-RQ P-Data")?;
+                pdata.write_all(&object_data).await.context(WriteIOSnafu)?;
             }
         }
 
@@ -141,6 +147,8 @@ where
                 let storage_sop_instance_uid = file
                     .sop_instance_uid
                     .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+                let entry = FileReport::from_response(file, status, nbytes, start.elapsed());
+                report.lock().await.record(entry);
 
                 match status {
                     // Success
@@ -148,6 +156,11 @@ where
                         if verbose {
                             info!("Successfully stored instance {}", storage_sop_instance_uid);
                         }
+                        journal
+                            .lock()
+                            .await
+                            .record(storage_sop_instance_uid)
+                            .context(JournalSnafu)?;
                     }
                     // Warning
                     1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => {
@@ -155,6 +168,11 @@ where
                             "Possible issue storing instance `{}` (status code {:04X}H)",
                             storage_sop_instance_uid, status
                         );
+                        journal
+                            .lock()
+                            .await
+                            .record(storage_sop_instance_uid)
+                            .context(JournalSnafu)?;
                     }
                     0xFF00 | 0xFF01 => {
                         warn!(
@@ -169,7 +187,11 @@ where
                         );
                         if fail_first {
                             let _ = scu.abort().await;
-                            std::process::exit(-2);
+                            return StoreFailedSnafu {
+                                sop_instance_uid: storage_sop_instance_uid,
+                                status,
+                            }
+                            .fail();
                         }
                     }
                     _ => {
@@ -179,7 +201,11 @@ where
                         );
                         if fail_first {
                             let _ = scu.abort().await;
-                            std::process::exit(-2);
+                            return StoreFailedSnafu {
+                                sop_instance_uid: storage_sop_instance_uid,
+                                status,
+                            }
+                            .fail();
                         }
                     }
                 }
@@ -204,11 +230,18 @@ where
     Ok(scu)
 }
 
+/// Send files taken from the shared queue over the association
+/// until the queue is empty.
+///
+/// On error, the file being sent is put back in the queue.
+#[allow(clippy::too_many_arguments)]
 pub async fn inner<T>(
     mut scu: AsyncClientAssociation<T>,
     d_files: Arc<Mutex<Vec<DicomFile>>>,
+    journal: Arc<Mutex<Journal>>,
+    report: Arc<Mutex<RunReport>>,
     pbx: Option<Arc<Mutex<ProgressBar>>>,
-    never_transcode: bool,
+    transcoding: Transcoding,
     fail_first: bool,
     verbose: bool,
     ignore_sop_class: bool,
@@ -230,7 +263,7 @@ where
             &file,
             scu.presentation_contexts(),
             ignore_sop_class,
-            never_transcode,
+            transcoding,
         );
         match r {
             Ok((pc, ts)) => {
@@ -245,14 +278,44 @@ where
                 file.ts_selected = Some(ts);
             }
             Err(e) => {
-                error!("{}", Report::from_error(e));
+                report
+                    .lock()
+                    .await
+                    .record(FileReport::new(&file, Outcome::Skipped).with_error(&e));
                 if fail_first {
                     let _ = scu.abort().await;
-                    std::process::exit(-2);
+                    return Err(e);
+                }
+                error!("{}", Report::from_error(e));
+            }
+        }
+        match send_file(
+            scu,
+            &file,
+            message_id,
+            &journal,
+            &report,
+            pbx.as_ref(),
+            verbose,
+            fail_first,
+        )
+        .await
+        {
+            Ok(s) => scu = s,
+            Err(e) => {
+                if !matches!(e, Error::StoreFailed { .. }) {
+                    report
+                        .lock()
+                        .await
+                        .record(FileReport::new(&file, Outcome::Failure).with_error(&e));
+                }
+                // leave it to be sent again after reconnecting
+                if e.is_transient() {
+                    d_files.lock().await.push(file);
                 }
+                return Err(e);
             }
         }
-        scu = send_file(scu, file, message_id, pbx.as_ref(), verbose, fail_first).await?;
         message_id += 1;
     }
     let _ = scu.release().await;
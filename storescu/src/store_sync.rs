@@ -1,4 +1,6 @@
+use std::collections::VecDeque;
 use std::io::{Write, stderr};
+use std::time::Instant;
 
 use dicom_dictionary_std::tags;
 use dicom_encoding::TransferSyntaxIndex;
@@ -13,16 +15,22 @@ use indicatif::ProgressBar;
 use snafu::{OptionExt, Report, ResultExt};
 use tracing::{debug, error, info, warn};
 
+use crate::journal::Journal;
+use crate::report::{FileReport, Outcome, RunReport};
 use crate::{
-    ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error, MissingAttributeSnafu,
-    ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu, UnsupportedFileTransferSyntaxSnafu,
-    WriteDatasetSnafu, WriteIOSnafu, check_presentation_contexts, into_ts, store_req_command,
+    ConvertFieldSnafu, CreateCommandSnafu, DicomFile, Error, JournalSnafu, MissingAttributeSnafu,
+    ReadDatasetSnafu, ReadFilePathSnafu, ScuSnafu, StoreFailedSnafu, Transcoding,
+    UnsupportedFileTransferSyntaxSnafu, WriteDatasetSnafu, WriteIOSnafu,
+    check_presentation_contexts, into_ts, store_req_command,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn send_file<T>(
     mut scu: ClientAssociation<T>,
-    file: DicomFile,
+    file: &DicomFile,
     message_id: u16,
+    journal: &mut Journal,
+    report: &mut RunReport,
     progress_bar: Option<&ProgressBar>,
     verbose: bool,
     fail_first: bool,
@@ -30,7 +38,8 @@ pub fn send_file<T>(
 where
     T: std::io::Read + std::io::Write + CloseSocket,
 {
-    if let (Some(pc_selected), Some(ts_uid_selected)) = (file.pc_selected, file.ts_selected) {
+    if let (Some(pc_selected), Some(ts_uid_selected)) = (&file.pc_selected, &file.ts_selected) {
+        let start = Instant::now();
         if let Some(pb) = &progress_bar {
             pb.set_message(file.sop_instance_uid.clone());
         }
@@ -51,7 +60,7 @@ where
                 path: file.file.display().to_string(),
             })?;
         let ts_selected = TransferSyntaxRegistry
-            .get(&ts_uid_selected)
+            .get(ts_uid_selected)
             .with_context(|| UnsupportedFileTransferSyntaxSnafu {
                 uid: ts_uid_selected.to_string(),
             })?;
@@ -142,6 +151,8 @@ where
                 let storage_sop_instance_uid = file
                     .sop_instance_uid
                     .trim_end_matches(|c: char| c.is_whitespace() || c == '\0');
+                let entry = FileReport::from_response(file, status, nbytes, start.elapsed());
+                report.record(entry);
 
                 match status {
                     // Success
@@ -149,6 +160,9 @@ where
                         if verbose {
                             info!("Successfully stored instance {}", storage_sop_instance_uid);
                         }
+                        journal
+                            .record(storage_sop_instance_uid)
+                            .context(JournalSnafu)?;
                     }
                     // Warning
                     1 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => {
@@ -156,6 +170,9 @@ where
                             "Possible issue storing instance `{}` (status code {:04X}H)",
                             storage_sop_instance_uid, status
                         );
+                        journal
+                            .record(storage_sop_instance_uid)
+                            .context(JournalSnafu)?;
                     }
                     0xFF00 | 0xFF01 => {
                         warn!(
@@ -170,7 +187,11 @@ where
                         );
                         if fail_first {
                             let _ = scu.abort();
-                            std::process::exit(-2);
+                            return StoreFailedSnafu {
+                                sop_instance_uid: storage_sop_instance_uid,
+                                status,
+                            }
+                            .fail();
                         }
                     }
                     _ => {
@@ -180,7 +201,11 @@ where
                         );
                         if fail_first {
                             let _ = scu.abort();
-                            std::process::exit(-2);
+                            return StoreFailedSnafu {
+                                sop_instance_uid: storage_sop_instance_uid,
+                                status,
+                            }
+                            .fail();
                         }
                     }
                 }
@@ -205,25 +230,33 @@ where
     Ok(scu)
 }
 
+/// Send the given files over the association,
+/// removing each file from the queue once the Store SCP has responded.
+///
+/// On error, the file being sent is left at the front of the queue.
+#[allow(clippy::too_many_arguments)]
 pub fn inner<T>(
     mut scu: ClientAssociation<T>,
-    d_files: Vec<DicomFile>,
+    d_files: &mut VecDeque<DicomFile>,
+    journal: &mut Journal,
+    report: &mut RunReport,
     pbx: &Option<ProgressBar>,
     fail_first: bool,
     verbose: bool,
-    never_transcode: bool,
+    transcoding: Transcoding,
     ignore_sop_class: bool,
 ) -> Result<(), Error>
 where
     T: std::io::Read + std::io::Write + CloseSocket,
 {
-    for (message_id, mut file) in (1..).zip(d_files) {
+    let mut message_id = 1;
+    while let Some(file) = d_files.front_mut() {
         // identify the right transfer syntax to use
         let r: Result<_, Error> = check_presentation_contexts(
-            &file,
+            file,
             scu.presentation_contexts(),
             ignore_sop_class,
-            never_transcode,
+            transcoding,
         );
         match r {
             Ok((pc, ts)) => {
@@ -238,14 +271,34 @@ where
                 file.ts_selected = Some(ts);
             }
             Err(e) => {
-                error!("{}", Report::from_error(e));
+                report.record(FileReport::new(file, Outcome::Skipped).with_error(&e));
                 if fail_first {
                     let _ = scu.abort();
-                    std::process::exit(-2);
+                    return Err(e);
+                }
+                error!("{}", Report::from_error(e));
+            }
+        }
+        match send_file(
+            scu,
+            file,
+            message_id,
+            journal,
+            report,
+            pbx.as_ref(),
+            verbose,
+            fail_first,
+        ) {
+            Ok(s) => scu = s,
+            Err(e) => {
+                if !matches!(e, Error::StoreFailed { .. }) {
+                    report.record(FileReport::new(file, Outcome::Failure).with_error(&e));
                 }
+                return Err(e);
             }
         }
-        scu = send_file(scu, file, message_id, pbx.as_ref(), verbose, fail_first)?;
+        d_files.pop_front();
+        message_id += 1;
     }
     scu.release().map_err(Box::from).context(ScuSnafu)?;
     if let Some(pb) = pbx {
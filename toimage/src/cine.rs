@@ -0,0 +1,312 @@
+//! Export of multi-frame objects as animations or video files.
+//!
+//! Animated GIF files are written directly.
+//! Other video containers (MP4, WebM, ...)
+//! are produced by piping the raw frames to `ffmpeg`,
+//! which must then be available in the `PATH`.
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    process::{Child, Command, Stdio},
+};
+
+use dicom_dictionary_std::tags;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+
+/// The frame rate assumed
+/// when the object does not declare any frame timing
+const DEFAULT_FRAME_RATE: f64 = 30.;
+
+/// Determine the display duration of each frame in milliseconds,
+/// from the _Frame Time Vector_, the _Frame Time_,
+/// or the frame rate declared in the object,
+/// unless a frame rate is given.
+pub fn frame_durations(
+    obj: &FileDicomObject<InMemDicomObject>,
+    number_of_frames: usize,
+    frame_rate: Option<f64>,
+) -> Vec<f64> {
+    let get_float = |tag| obj.get(tag).and_then(|e| e.to_float64().ok());
+
+    if let Some(frame_rate) = frame_rate.filter(|r| *r > 0.) {
+        return vec![1_000. / frame_rate; number_of_frames];
+    }
+
+    // the first entry is 0,
+    // each other entry is the interval since the previous frame
+    if let Some(vector) = obj
+        .get(tags::FRAME_TIME_VECTOR)
+        .and_then(|e| e.to_multi_float64().ok())
+        .filter(|v| v.len() == number_of_frames && v.iter().skip(1).all(|t| *t > 0.))
+    {
+        if number_of_frames > 1 {
+            let mut durations = vector[1..].to_vec();
+            durations.push(vector[number_of_frames - 1]);
+            return durations;
+        }
+    }
+
+    let frame_time = get_float(tags::FRAME_TIME)
+        .filter(|t| *t > 0.)
+        .or_else(|| {
+            get_float(tags::RECOMMENDED_DISPLAY_FRAME_RATE)
+                .or_else(|| get_float(tags::CINE_RATE))
+                .filter(|r| *r > 0.)
+                .map(|r| 1_000. / r)
+        })
+        .unwrap_or(1_000. / DEFAULT_FRAME_RATE);
+    vec![frame_time; number_of_frames]
+}
+
+/// A writer of frames into an animation or video file.
+pub enum VideoWriter {
+    /// animated GIF
+    Gif {
+        writer: GifWriter<BufWriter<File>>,
+        /// the presentation time of the next frame, in milliseconds
+        elapsed: f64,
+    },
+    /// any other format, through `ffmpeg`
+    Ffmpeg(Child),
+}
+
+impl VideoWriter {
+    /// Create a writer of frames with the given dimensions
+    /// and pixel format (8-bit grayscale or 8-bit RGB),
+    /// picking the output format by the file extension.
+    ///
+    /// The frame rate only applies to video files,
+    /// animated GIF files follow the duration of each frame.
+    pub fn create(
+        path: &Path,
+        width: u32,
+        height: u32,
+        grayscale: bool,
+        frame_rate: f64,
+    ) -> io::Result<Self> {
+        let is_gif = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"));
+        if is_gif {
+            let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+                return Err(io::Error::other("image is too large for a GIF file"));
+            };
+            let file = BufWriter::new(File::create(path)?);
+            return Ok(VideoWriter::Gif {
+                writer: GifWriter::new(file, width, height, grayscale)?,
+                elapsed: 0.,
+            });
+        }
+
+        let child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt"])
+            .arg(if grayscale { "gray" } else { "rgb24" })
+            .arg("-s")
+            .arg(format!("{width}x{height}"))
+            .arg("-framerate")
+            .arg(frame_rate.to_string())
+            .args(["-i", "-", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("could not run ffmpeg, which is required for this video format: {e}"),
+                )
+            })?;
+        Ok(VideoWriter::Ffmpeg(child))
+    }
+
+    /// Write a frame of samples, to be displayed for the given duration.
+    pub fn write_frame(&mut self, pixels: &[u8], duration: f64) -> io::Result<()> {
+        match self {
+            VideoWriter::Gif { writer, elapsed } => {
+                // round on the accumulated time to avoid drifting
+                let start = (*elapsed / 10.).round();
+                *elapsed += duration;
+                let delay = ((*elapsed / 10.).round() - start).clamp(2., u16::MAX as f64);
+                writer.write_frame(pixels, delay as u16)
+            }
+            VideoWriter::Ffmpeg(child) => child.stdin.as_mut().unwrap().write_all(pixels),
+        }
+    }
+
+    /// Complete the file.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            VideoWriter::Gif { writer, .. } => writer.finish()?.flush(),
+            VideoWriter::Ffmpeg(mut child) => {
+                // close the input so that ffmpeg can finish
+                drop(child.stdin.take());
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("ffmpeg failed ({status})")));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A minimal animated GIF encoder.
+///
+/// Grayscale frames are written with a palette of 256 gray levels,
+/// while color frames are reduced to a fixed palette
+/// of 3 bits of red, 3 bits of green, and 2 bits of blue.
+pub struct GifWriter<W> {
+    to: W,
+    width: u16,
+    height: u16,
+    grayscale: bool,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Write the header of a looping animation.
+    pub fn new(mut to: W, width: u16, height: u16, grayscale: bool) -> io::Result<Self> {
+        to.write_all(b"GIF89a")?;
+        to.write_all(&width.to_le_bytes())?;
+        to.write_all(&height.to_le_bytes())?;
+        // global color table of 256 entries, 8 bits per primary color
+        to.write_all(&[0xF7, 0, 0])?;
+        let scale = |value: u8, max: u16| (u16::from(value) * 255 / max) as u8;
+        let palette: Vec<u8> = (0..=255_u8)
+            .flat_map(|i| {
+                if grayscale {
+                    [i, i, i]
+                } else {
+                    [
+                        scale(i >> 5, 7),
+                        scale((i >> 2) & 0b111, 7),
+                        scale(i & 0b11, 3),
+                    ]
+                }
+            })
+            .collect();
+        to.write_all(&palette)?;
+        // loop forever
+        to.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(GifWriter {
+            to,
+            width,
+            height,
+            grayscale,
+        })
+    }
+
+    /// Write a frame of 8-bit grayscale or RGB samples,
+    /// with the given delay in hundredths of a second.
+    pub fn write_frame(&mut self, pixels: &[u8], delay: u16) -> io::Result<()> {
+        let indices: Vec<u8> = if self.grayscale {
+            pixels.to_vec()
+        } else {
+            pixels
+                .chunks_exact(3)
+                .map(|rgb| (rgb[0] & 0xE0) | ((rgb[1] >> 3) & 0x1C) | (rgb[2] >> 6))
+                .collect()
+        };
+        if indices.len() != self.width as usize * self.height as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame size does not match the animation",
+            ));
+        }
+
+        // graphic control extension
+        let [delay_lo, delay_hi] = delay.to_le_bytes();
+        self.to
+            .write_all(&[0x21, 0xF9, 0x04, 0x00, delay_lo, delay_hi, 0x00, 0x00])?;
+        // image descriptor, covering the whole canvas
+        self.to.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.to.write_all(&self.width.to_le_bytes())?;
+        self.to.write_all(&self.height.to_le_bytes())?;
+        self.to.write_all(&[0x00])?;
+
+        let data = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8)
+            .encode(&indices)
+            .map_err(io::Error::other)?;
+        self.to.write_all(&[8])?;
+        for block in data.chunks(255) {
+            self.to.write_all(&[block.len() as u8])?;
+            self.to.write_all(block)?;
+        }
+        self.to.write_all(&[0x00])
+    }
+
+    /// Write the trailer of the file,
+    /// returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.to.write_all(&[0x3B])?;
+        Ok(self.to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, VR, dicom_value};
+    use dicom_dictionary_std::uids;
+    use dicom_object::FileMetaTableBuilder;
+
+    fn object(elements: Vec<DataElement<InMemDicomObject>>) -> FileDicomObject<InMemDicomObject> {
+        InMemDicomObject::from_element_iter(elements)
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                    .media_storage_sop_class_uid(uids::ULTRASOUND_MULTI_FRAME_IMAGE_STORAGE)
+                    .media_storage_sop_instance_uid("2.25.1"),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn durations_from_frame_timing() {
+        let obj = object(vec![DataElement::new(
+            tags::FRAME_TIME_VECTOR,
+            VR::DS,
+            dicom_value!(F64, [0., 40., 40., 80.]),
+        )]);
+        assert_eq!(frame_durations(&obj, 4, None), vec![40., 40., 80., 80.]);
+        // vector does not match the number of frames
+        assert_eq!(frame_durations(&obj, 3, None), vec![1_000. / 30.; 3]);
+        assert_eq!(frame_durations(&obj, 4, Some(10.)), vec![100.; 4]);
+
+        let obj = object(vec![DataElement::new(tags::FRAME_TIME, VR::DS, "33.3")]);
+        assert_eq!(frame_durations(&obj, 2, None), vec![33.3, 33.3]);
+
+        let obj = object(vec![DataElement::new(tags::CINE_RATE, VR::IS, "25")]);
+        assert_eq!(frame_durations(&obj, 2, None), vec![40., 40.]);
+    }
+
+    #[test]
+    fn write_animated_gif() {
+        let mut writer = GifWriter::new(Vec::new(), 3, 2, false).unwrap();
+        writer.write_frame(&[255; 18], 4).unwrap();
+        writer.write_frame(&[0; 18], 4).unwrap();
+        assert!(writer.write_frame(&[0; 6], 4).is_err());
+        let gif = writer.finish().unwrap();
+
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(&gif[6..10], &[3, 0, 2, 0]);
+        // white is the last palette entry
+        assert_eq!(&gif[13 + 255 * 3..13 + 256 * 3], &[255, 255, 255]);
+        let frames = gif
+            .windows(4)
+            .filter(|w| *w == [0x21, 0xF9, 0x04, 0x00])
+            .count();
+        assert_eq!(frames, 2);
+        assert_eq!(gif.last(), Some(&0x3B));
+
+        // header, color table, and loop extension,
+        // followed by the control extension and descriptor of the first frame
+        let data = &gif[13 + 768 + 19 + 8 + 10..];
+        assert_eq!(data[0], 8);
+        let len = data[1] as usize;
+        let indices = weezl::decode::Decoder::new(weezl::BitOrder::Lsb, 8)
+            .decode(&data[2..2 + len])
+            .unwrap();
+        assert_eq!(indices, vec![255; 6]);
+    }
+}
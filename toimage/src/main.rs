@@ -1,15 +1,29 @@
 //! A CLI tool for converting a DICOM image file
 //! into a general purpose image file (e.g. PNG).
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use clap::Parser;
-use dicom_dictionary_std::uids;
+use clap::{Parser, ValueEnum};
+use dicom_dictionary_std::{tags, uids};
 use dicom_encoding::adapters::PixelDataObject;
 use dicom_object::{FileDicomObject, InMemDicomObject, open_file};
-use dicom_pixeldata::{ConvertOptions, PixelDecoder};
+use dicom_pixeldata::{
+    ConvertOptions, DecodedPixelData, FrameBytes, ModalityLutOption, PixelDecoder,
+    PixelRepresentation, RenderingPipeline, Rescale, VoiLutFunction, VoiLutOption, WindowLevel,
+    image::DynamicImage, overlays::read_overlays, presentation_state::PresentationState,
+    video::VideoStream,
+};
+use mosaic::{MosaicGrid, Tile};
+use rayon::prelude::*;
 use snafu::{OptionExt, Report, ResultExt, Snafu, Whatever};
 use tracing::{Level, error, warn};
 
+mod cine;
+mod mosaic;
+mod waveform;
+
 /// Convert DICOM files into image files
 #[derive(Debug, Parser)]
 #[command(version)]
@@ -37,9 +51,60 @@ struct App {
     #[arg(short = 'e', long = "ext", conflicts_with = "output")]
     ext: Option<String>,
 
-    /// Frame number (0-indexed)
-    #[arg(short = 'F', long = "frame", default_value = "0")]
-    frame_number: u32,
+    /// Frame number (0-indexed),
+    /// an inclusive range of frames (e.g. `10-20`),
+    /// or `all` to convert every frame;
+    /// a numbered suffix is added to each output file
+    /// unless a single frame number is given
+    /// (default is 0, or all frames with `--to-video`)
+    #[arg(short = 'F', long = "frame", visible_alias = "frames")]
+    frames: Option<FrameSelection>,
+
+    /// Assemble the frames into an animated GIF or a video file
+    /// (e.g. `cine.gif`, or `cine.mp4` which requires `ffmpeg`)
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["output", "outdir", "ext", "unwrap", "raw"]
+    )]
+    to_video: Option<PathBuf>,
+
+    /// Frame rate of the video in frames per second
+    /// (default is to follow the frame timing of the object)
+    #[arg(long, requires = "to_video")]
+    fps: Option<f64>,
+
+    /// Tile the frames, or the instances of the given files or directory,
+    /// into a single contact sheet image
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["output", "outdir", "ext", "unwrap", "raw", "to_video"]
+    )]
+    mosaic: Option<PathBuf>,
+
+    /// The size of the contact sheet grid,
+    /// as a number of columns (e.g. `5`) or columns by rows (e.g. `5x4`);
+    /// frames are evenly sampled if they do not all fit
+    /// (default is a near-square grid)
+    #[arg(long, value_name = "COLUMNS[xROWS]", requires = "mosaic")]
+    grid: Option<MosaicGrid>,
+
+    /// Do not label the tiles of the contact sheet
+    /// with their instance number, slice location, and frame number
+    #[arg(long, requires = "mosaic")]
+    no_labels: bool,
+
+    /// Render through a grayscale softcopy presentation state,
+    /// applying its LUTs, spatial transformations,
+    /// displayed area, and annotations
+    #[arg(
+        long = "pr",
+        visible_alias = "presentation-state",
+        value_name = "FILE",
+        conflicts_with_all = ["unwrap", "raw", "overlays"]
+    )]
+    presentation_state: Option<PathBuf>,
 
     #[clap(flatten)]
     image_options: ImageOptions,
@@ -64,16 +129,354 @@ struct ImageOptions {
     #[arg(long = "16bit", conflicts_with = "force_8bit")]
     force_16bit: bool,
 
+    /// Force output bit depth in bits per sample
+    /// (a 16-bit output requires a format such as PNG, TIFF or PGM)
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "force_8bit",
+        conflicts_with = "force_16bit"
+    )]
+    bit_depth: Option<BitDepthArg>,
+
     /// Output the raw pixel data instead of decoding it
+    /// (the whole video stream in the case of video transfer syntaxes)
     #[arg(
         long = "unwrap",
         conflicts_with = "force_8bit",
         conflicts_with = "force_16bit"
     )]
     unwrap: bool,
+
+    /// Output the decoded pixel data samples untouched
+    /// (no LUT transformations), with a JSON sidecar file
+    /// describing their layout
+    #[arg(
+        long,
+        conflicts_with = "unwrap",
+        conflicts_with = "force_8bit",
+        conflicts_with = "force_16bit",
+        conflicts_with = "bit_depth",
+        conflicts_with = "overlays"
+    )]
+    raw: bool,
+
     /// Decode all pixel data frames instead of just the one intended
     #[arg(hide(true), long)]
     decode_all: bool,
+
+    /// Do not apply the modality LUT (rescale slope and intercept),
+    /// nor any VOI LUT transformation
+    #[arg(
+        long = "no-rescale",
+        conflicts_with = "rescale_slope",
+        conflicts_with = "rescale_intercept"
+    )]
+    no_rescale: bool,
+    /// Override the rescale slope of the modality LUT (default is 1)
+    #[arg(long, allow_negative_numbers = true)]
+    rescale_slope: Option<f64>,
+    /// Override the rescale intercept of the modality LUT (default is 0)
+    #[arg(long, allow_negative_numbers = true)]
+    rescale_intercept: Option<f64>,
+
+    /// Apply a custom window center instead of the object's VOI LUT
+    #[arg(
+        long,
+        allow_negative_numbers = true,
+        requires = "window_width",
+        conflicts_with = "no_rescale"
+    )]
+    window_center: Option<f64>,
+    /// Apply a custom window width instead of the object's VOI LUT
+    #[arg(long, requires = "window_center")]
+    window_width: Option<f64>,
+    /// The VOI LUT function of the custom window
+    /// (default is the one in the object, or linear)
+    #[arg(long, value_enum, requires = "window_center")]
+    voi_lut_function: Option<VoiLutFunctionArg>,
+    /// Apply a window computed from the sample values of each frame
+    /// instead of the object's VOI LUT
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "window_center",
+        conflicts_with = "no_rescale"
+    )]
+    auto_window: Option<AutoWindowArg>,
+    /// The lower and upper percentiles of sample values
+    /// delimiting the window in `--auto-window percentile` mode
+    #[arg(long, value_name = "LOW,HIGH", default_value = "1,99")]
+    percentiles: Percentiles,
+    /// Apply the window of the object at the given index (0-indexed)
+    /// instead of the first one
+    #[arg(
+        long,
+        conflicts_with = "window_center",
+        conflicts_with = "auto_window",
+        conflicts_with = "no_rescale"
+    )]
+    voi_lut_index: Option<usize>,
+    /// Normalize sample values to the full output range
+    /// instead of applying the object's VOI LUT
+    #[arg(
+        long,
+        conflicts_with = "window_center",
+        conflicts_with = "auto_window",
+        conflicts_with = "voi_lut_index",
+        conflicts_with = "no_voi_lut",
+        conflicts_with = "no_rescale"
+    )]
+    normalize: bool,
+    /// Do not apply any VOI LUT transformation
+    #[arg(
+        long = "no-voi-lut",
+        conflicts_with = "window_center",
+        conflicts_with = "auto_window",
+        conflicts_with = "voi_lut_index",
+        conflicts_with = "no_rescale"
+    )]
+    no_voi_lut: bool,
+
+    /// Burn the overlay planes of the image into the output
+    #[arg(long, conflicts_with = "unwrap")]
+    overlays: bool,
+}
+
+/// Output bit depth
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum BitDepthArg {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+}
+
+/// VOI LUT function
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum VoiLutFunctionArg {
+    Linear,
+    LinearExact,
+    Sigmoid,
+}
+
+impl From<VoiLutFunctionArg> for VoiLutFunction {
+    fn from(value: VoiLutFunctionArg) -> Self {
+        match value {
+            VoiLutFunctionArg::Linear => VoiLutFunction::Linear,
+            VoiLutFunctionArg::LinearExact => VoiLutFunction::LinearExact,
+            VoiLutFunctionArg::Sigmoid => VoiLutFunction::Sigmoid,
+        }
+    }
+}
+
+/// How to compute a window from the sample values
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum AutoWindowArg {
+    /// cover the full range of sample values
+    #[value(name = "minmax")]
+    MinMax,
+    /// cover the sample values between two percentiles,
+    /// discarding outliers
+    Percentile,
+}
+
+/// A pair of percentiles of sample values
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Percentiles {
+    low: f64,
+    high: f64,
+}
+
+impl FromStr for Percentiles {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<f64>()
+                .map_err(|e| format!("invalid percentile `{n}`: {e}"))
+        };
+        let (low, high) = s
+            .split_once(',')
+            .ok_or_else(|| format!("expected two percentiles `LOW,HIGH`, got `{s}`"))?;
+        let (low, high) = (parse(low)?, parse(high)?);
+        if !(0. ..=100.).contains(&low) || !(0. ..=100.).contains(&high) || low >= high {
+            return Err(format!("invalid percentile range `{s}`"));
+        }
+        Ok(Percentiles { low, high })
+    }
+}
+
+/// Compute a window covering the given sample values,
+/// returning `None` if there are no samples
+fn auto_window(
+    mut values: Vec<f64>,
+    mode: AutoWindowArg,
+    percentiles: Percentiles,
+) -> Option<WindowLevel> {
+    values.retain(|v| v.is_finite());
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    let (low, high) = match mode {
+        AutoWindowArg::MinMax => (values[0], values[values.len() - 1]),
+        AutoWindowArg::Percentile => {
+            let at = |p: f64| values[((values.len() - 1) as f64 * p / 100.).round() as usize];
+            (at(percentiles.low), at(percentiles.high))
+        }
+    };
+    Some(WindowLevel {
+        center: (low + high) / 2.,
+        width: (high - low).max(f64::MIN_POSITIVE),
+    })
+}
+
+/// The frames to convert
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum FrameSelection {
+    /// a single frame number
+    Single(u32),
+    /// an inclusive range of frame numbers
+    Range(u32, u32),
+    /// all frames
+    All,
+}
+
+impl FromStr for FrameSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|e| format!("invalid frame number `{n}`: {e}"))
+        };
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(FrameSelection::All);
+        }
+        match s.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(format!("invalid frame range `{s}`"));
+                }
+                Ok(FrameSelection::Range(start, end))
+            }
+            None => parse(s).map(FrameSelection::Single),
+        }
+    }
+}
+
+impl FrameSelection {
+    /// Obtain the frame numbers to convert
+    /// in an object with the given number of frames
+    fn resolve(self, number_of_frames: u32) -> Result<std::ops::Range<u32>, Error> {
+        let (start, end) = match self {
+            FrameSelection::Single(frame) => (frame, frame),
+            FrameSelection::Range(start, end) => (start, end),
+            FrameSelection::All => (0, number_of_frames.saturating_sub(1)),
+        };
+        snafu::ensure!(
+            end < number_of_frames,
+            FrameOutOfBoundsSnafu { frame_number: end }
+        );
+        Ok(start..end + 1)
+    }
+
+    /// Whether output file names should be numbered by frame
+    fn numbered(self) -> bool {
+        !matches!(self, FrameSelection::Single(_))
+    }
+}
+
+impl ImageOptions {
+    /// Whether the output is pixel data rather than an image file,
+    /// so that the output file extension is chosen later
+    fn writes_data(&self) -> bool {
+        self.unwrap || self.raw
+    }
+
+    /// Build the pixel data conversion options
+    fn convert_options(&self) -> ConvertOptions {
+        let mut options = ConvertOptions::new();
+
+        if self.force_16bit || self.bit_depth == Some(BitDepthArg::Sixteen) {
+            options = options.force_16bit();
+        } else if self.force_8bit || self.bit_depth == Some(BitDepthArg::Eight) {
+            options = options.force_8bit();
+        }
+
+        if self.no_rescale {
+            options = options.with_modality_lut(ModalityLutOption::None);
+        } else if self.rescale_slope.is_some() || self.rescale_intercept.is_some() {
+            options = options.with_modality_lut(ModalityLutOption::Override(Rescale::new(
+                self.rescale_slope.unwrap_or(1.),
+                self.rescale_intercept.unwrap_or(0.),
+            )));
+        }
+
+        if let (Some(center), Some(width)) = (self.window_center, self.window_width) {
+            let window = WindowLevel { center, width };
+            options = options.with_voi_lut(match self.voi_lut_function {
+                Some(function) => VoiLutOption::CustomWithFunction(window, function.into()),
+                None => VoiLutOption::Custom(window),
+            });
+        } else if self.normalize {
+            options = options.with_voi_lut(VoiLutOption::Normalize);
+        } else if self.no_voi_lut {
+            options = options.with_voi_lut(VoiLutOption::Identity);
+        }
+
+        options
+    }
+
+    /// Build the pixel data conversion options for a frame,
+    /// resolving the VOI LUT options which depend on the pixel data
+    fn frame_convert_options(
+        &self,
+        options: &ConvertOptions,
+        pixel: &DecodedPixelData,
+        frame: u32,
+    ) -> Result<ConvertOptions, Error> {
+        if let Some(index) = self.voi_lut_index {
+            let window = pixel
+                .window()
+                .context(ConvertImageSnafu)?
+                .and_then(|windows| windows.get(index))
+                .context(VoiLutIndexOutOfBoundsSnafu { index })?;
+            let function = pixel
+                .voi_lut_function()
+                .context(ConvertImageSnafu)?
+                .and_then(|functions| functions.get(index).or(functions.first()))
+                .copied()
+                .unwrap_or(VoiLutFunction::Linear);
+            return Ok(options
+                .clone()
+                .with_voi_lut(VoiLutOption::CustomWithFunction(*window, function)));
+        }
+
+        if let Some(mode) = self.auto_window {
+            // the window applies to the output of the modality LUT
+            let values_options =
+                ConvertOptions::new().with_modality_lut(options.modality_lut.clone());
+            let values: Vec<f64> = pixel
+                .to_vec_frame_with_options(frame, &values_options)
+                .context(ConvertImageSnafu)?;
+            return Ok(match auto_window(values, mode, self.percentiles) {
+                Some(window) => options
+                    .clone()
+                    .with_voi_lut(VoiLutOption::CustomWithFunction(
+                        window,
+                        VoiLutFunction::LinearExact,
+                    )),
+                None => options.clone().with_voi_lut(VoiLutOption::Normalize),
+            });
+        }
+
+        Ok(options.clone())
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -95,11 +498,38 @@ enum Error {
     MissingProperty { name: &'static str },
     /// pixel data of frame #{frame_number} is out of bounds
     FrameOutOfBounds { frame_number: u32 },
+    /// no window at index {index} in the DICOM object
+    VoiLutIndexOutOfBounds { index: usize },
+    /// video export requires a single DICOM file
+    VideoInput,
+    /// failed to write video file
+    ExportVideo { source: std::io::Error },
     /// failed to convert pixel data to image
     ConvertImage {
         #[snafu(source(from(dicom_pixeldata::Error, Box::new)))]
         source: Box<dicom_pixeldata::Error>,
     },
+    #[snafu(display("could not read presentation state {}", path.display()))]
+    ReadPresentationState {
+        #[snafu(source(from(dicom_pixeldata::presentation_state::Error, Box::new)))]
+        source: Box<dicom_pixeldata::presentation_state::Error>,
+        path: PathBuf,
+    },
+    /// failed to apply presentation state
+    ApplyPresentationState {
+        #[snafu(source(from(dicom_pixeldata::presentation_state::Error, Box::new)))]
+        source: Box<dicom_pixeldata::presentation_state::Error>,
+    },
+    /// failed to read overlay planes
+    ReadOverlays {
+        #[snafu(source(from(dicom_pixeldata::overlays::Error, Box::new)))]
+        source: Box<dicom_pixeldata::overlays::Error>,
+    },
+    /// failed to read waveforms
+    ReadWaveforms {
+        #[snafu(source(from(dicom_pixeldata::waveforms::Error, Box::new)))]
+        source: Box<dicom_pixeldata::waveforms::Error>,
+    },
     /// failed to save image to file
     SaveImage {
         #[snafu(source(from(dicom_pixeldata::image::ImageError, Box::new)))]
@@ -107,6 +537,11 @@ enum Error {
     },
     /// failed to save pixel data to file
     SaveData { source: std::io::Error },
+    /// failed to save video stream to file
+    SaveVideo {
+        #[snafu(source(from(dicom_pixeldata::video::Error, Box::new)))]
+        source: Box<dicom_pixeldata::video::Error>,
+    },
     /// Unexpected DICOM pixel data as data set sequence
     UnexpectedPixelData,
     /// No files given
@@ -118,15 +553,22 @@ enum Error {
 impl Error {
     fn to_exit_code(&self) -> i32 {
         match self {
-            Error::ReadFile { .. } => -1,
+            Error::ReadFile { .. } | Error::ReadPresentationState { .. } => -1,
             Error::DecodePixelData { .. }
             | Error::MissingOffsetEntry { .. }
             | Error::MissingProperty { .. }
-            | Error::FrameOutOfBounds { .. } => -2,
-            Error::ConvertImage { .. } => -3,
-            Error::SaveData { .. } | Error::SaveImage { .. } => -4,
+            | Error::FrameOutOfBounds { .. }
+            | Error::VoiLutIndexOutOfBounds { .. } => -2,
+            Error::ConvertImage { .. }
+            | Error::ReadOverlays { .. }
+            | Error::ReadWaveforms { .. }
+            | Error::ApplyPresentationState { .. } => -3,
+            Error::SaveData { .. }
+            | Error::SaveImage { .. }
+            | Error::SaveVideo { .. }
+            | Error::ExportVideo { .. } => -4,
             Error::UnexpectedPixelData => -7,
-            Error::NoFiles => -8,
+            Error::NoFiles | Error::VideoInput => -8,
             Error::ReadDir { .. } => -9,
         }
     }
@@ -163,7 +605,13 @@ fn run(args: App) -> Result<(), Error> {
         outdir,
         output,
         ext,
-        frame_number,
+        frames,
+        to_video,
+        fps,
+        mosaic,
+        grid,
+        no_labels,
+        presentation_state,
         image_options,
         fail_first,
         verbose,
@@ -173,37 +621,95 @@ fn run(args: App) -> Result<(), Error> {
         return Err(Error::NoFiles);
     };
 
+    let presentation_state = presentation_state
+        .map(|path| {
+            let obj = open_file(&path).with_context(|_| ReadFileSnafu { path: path.clone() })?;
+            PresentationState::from_object(&obj).context(ReadPresentationStateSnafu { path })
+        })
+        .transpose()?;
+    let presentation_state = presentation_state.as_ref();
+
+    if let Some(video) = to_video {
+        snafu::ensure!(files.len() == 1 && !files[0].is_dir(), VideoInputSnafu);
+        let dcm = open_file(&files[0]).with_context(|_| ReadFileSnafu {
+            path: files[0].clone(),
+        })?;
+        let frames = frames.unwrap_or(FrameSelection::All);
+        return export_video(
+            &dcm,
+            &video,
+            frames,
+            fps,
+            presentation_state,
+            image_options,
+            verbose,
+        );
+    }
+
+    if let Some(mosaic) = mosaic {
+        return export_mosaic(
+            &files,
+            recursive,
+            &mosaic,
+            frames,
+            grid.unwrap_or_default(),
+            !no_labels,
+            presentation_state,
+            image_options,
+            fail_first,
+            verbose,
+        );
+    }
+
+    let frames = frames.unwrap_or(FrameSelection::Single(0));
+
     if files.len() == 1 {
         let file = &files[0];
         if file.is_dir() {
             // single directory
-            let dicoms: Vec<(FileDicomObject<InMemDicomObject>, PathBuf)> =
-                collect_dicom_files(file, recursive)?;
+            let paths = collect_files(file, recursive)?;
 
-            if dicoms.is_empty() {
+            if paths.is_empty() {
                 return Err(Error::NoFiles);
             }
 
-            for file in dicoms.iter() {
+            // convert files in parallel
+            paths.par_iter().try_for_each(|path| {
+                let dicom_file = match open_file(path) {
+                    Ok(obj) => obj,
+                    Err(e) => {
+                        warn!("Error reading file {:?}: {}", path, e);
+                        return Ok(());
+                    }
+                };
+
                 let output = build_output_path(
                     false,
-                    file.1.clone(),
+                    path.clone(),
                     outdir.clone(),
                     ext.clone(),
-                    image_options.unwrap,
+                    image_options.writes_data(),
                 );
 
-                convert_single_file(&file.0, false, output, frame_number, image_options, verbose)
-                    .or_else(|e| {
+                convert_single_file(
+                    &dicom_file,
+                    false,
+                    output,
+                    frames,
+                    presentation_state,
+                    image_options,
+                    verbose,
+                )
+                .or_else(|e| {
                     if fail_first {
                         Err(e)
                     } else {
                         let report = Report::from_error(e);
-                        error!("Converting {}: {}", file.1.display(), report);
+                        error!("Converting {}: {}", path.display(), report);
                         Ok(())
                     }
-                })?;
-            }
+                })
+            })?;
         } else {
             // single DICOM file
             let dcm = open_file(file).with_context(|_| ReadFileSnafu { path: file.clone() })?;
@@ -214,14 +720,15 @@ fn run(args: App) -> Result<(), Error> {
                 output.unwrap_or(files[0].clone()),
                 outdir.clone(),
                 ext.clone(),
-                image_options.unwrap,
+                image_options.writes_data(),
             );
 
             convert_single_file(
                 &dcm,
                 output_is_set,
                 output,
-                frame_number,
+                frames,
+                presentation_state,
                 image_options,
                 verbose,
             )?;
@@ -247,14 +754,15 @@ fn run(args: App) -> Result<(), Error> {
                 file.clone(),
                 outdir.clone(),
                 ext.clone(),
-                image_options.unwrap,
+                image_options.writes_data(),
             );
 
             convert_single_file(
                 &dicom_file,
                 false,
                 output,
-                frame_number,
+                frames,
+                presentation_state,
                 image_options,
                 verbose,
             )
@@ -303,22 +811,55 @@ fn build_output_path(
     output
 }
 
+/// Add the frame number to the file name of the output path,
+/// before the extension
+fn numbered_output_path(output: &Path, frame_number: u32) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match output.extension() {
+        Some(extension) => format!("{stem}_{frame_number:04}.{}", extension.to_string_lossy()),
+        None => format!("{stem}_{frame_number:04}"),
+    };
+    output.with_file_name(file_name)
+}
+
 fn convert_single_file(
     file: &FileDicomObject<InMemDicomObject>,
     output_is_set: bool,
     mut output: PathBuf,
-    frame_number: u32,
+    frames: FrameSelection,
+    presentation_state: Option<&PresentationState>,
     image_options: ImageOptions,
     verbose: bool,
 ) -> Result<(), Error> {
     let ImageOptions {
-        force_8bit,
-        force_16bit,
         unwrap,
+        raw,
         decode_all,
+        overlays,
+        ..
     } = image_options;
 
+    // waveform objects are plotted or exported as sample tables
+    if waveform::is_waveform(file) {
+        return waveform::convert_waveforms(file, &output, verbose);
+    }
+
     if unwrap {
+        // video pixel data is a single stream spanning all frames
+        if let Some(codec) = file.video_codec() {
+            if !output_is_set {
+                output.set_extension(codec.file_extension());
+            }
+            std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+            let out_file = std::fs::File::create(&output).context(SaveDataSnafu)?;
+            file.write_video_stream(std::io::BufWriter::new(out_file))
+                .context(SaveVideoSnafu)?;
+            if verbose {
+                println!("Video stream saved to {}", output.display());
+            }
+            return Ok(());
+        }
+
         if !output_is_set {
             match file.meta().transfer_syntax() {
                 uids::JPEG_BASELINE8_BIT
@@ -344,18 +885,81 @@ fn convert_single_file(
                 }
             }
         }
+    }
 
-        let out_data = file
-            .frame_pixel_data(frame_number)
-            .with_context(|| FrameOutOfBoundsSnafu { frame_number })?;
-        std::fs::create_dir_all(output.parent().unwrap()).unwrap();
-        std::fs::write(output, out_data).context(SaveDataSnafu)?;
-    } else {
-        let pixel = if decode_all {
-            file.decode_pixel_data().context(DecodePixelDataSnafu)?
+    if raw && !output_is_set {
+        output.set_extension("raw");
+    }
+
+    let frame_numbers = frames.resolve(file.number_of_frames().unwrap_or(1))?;
+    let frame_output = |frame_number| {
+        if frames.numbered() {
+            numbered_output_path(&output, frame_number)
         } else {
-            file.decode_pixel_data_frame(frame_number)
-                .context(DecodePixelDataSnafu)?
+            output.clone()
+        }
+    };
+
+    if unwrap {
+        for frame_number in frame_numbers {
+            let output = frame_output(frame_number);
+            let out_data = file
+                .frame_pixel_data(frame_number)
+                .with_context(|| FrameOutOfBoundsSnafu { frame_number })?;
+            std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+            std::fs::write(output, out_data).context(SaveDataSnafu)?;
+        }
+        return Ok(());
+    }
+
+    if raw {
+        for frame_number in frame_numbers {
+            let output = frame_output(frame_number);
+            let pixel = file
+                .decode_pixel_data_frame(frame_number)
+                .context(DecodePixelDataSnafu)?;
+            let frame = pixel.frame_bytes(0).context(DecodePixelDataSnafu)?;
+            std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+            std::fs::write(&output, frame.bytes()).context(SaveDataSnafu)?;
+            let sidecar = output.with_extension("json");
+            std::fs::write(&sidecar, raw_sidecar(&pixel, &frame, frame_number))
+                .context(SaveDataSnafu)?;
+            if verbose {
+                println!(
+                    "Raw pixel data saved to {} (layout in {})",
+                    output.display(),
+                    sidecar.display()
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let options = image_options.convert_options();
+    let overlays = if overlays {
+        read_overlays(file).context(ReadOverlaysSnafu)?
+    } else {
+        Vec::new()
+    };
+    // presentation states refer to frames by their number in the object
+    let all_frames = if decode_all || presentation_state.is_some() {
+        Some(file.decode_pixel_data().context(DecodePixelDataSnafu)?)
+    } else {
+        None
+    };
+
+    for frame_number in frame_numbers {
+        let output = frame_output(frame_number);
+        let frame_pixel;
+        // the decoded pixel data and the effective frame number in it
+        let (pixel, frame_num) = match &all_frames {
+            Some(pixel) => (pixel, frame_number),
+            None => {
+                frame_pixel = file
+                    .decode_pixel_data_frame(frame_number)
+                    .context(DecodePixelDataSnafu)?;
+                (&frame_pixel, 0)
+            }
         };
 
         if verbose {
@@ -368,20 +972,19 @@ fn convert_single_file(
             );
         }
 
-        let mut options = ConvertOptions::new();
+        let frame_options = image_options.frame_convert_options(&options, pixel, frame_num)?;
+        let mut image = render_frame(file, pixel, frame_num, &frame_options, presentation_state)?;
 
-        if force_16bit {
-            options = options.force_16bit();
-        } else if force_8bit {
-            options = options.force_8bit();
+        for overlay in &overlays {
+            if verbose {
+                println!(
+                    "Burning {}x{} overlay {:04X} into image",
+                    overlay.columns, overlay.rows, overlay.group
+                );
+            }
+            overlay.burn_into(&mut image, frame_number);
         }
 
-        // the effective frame number
-        let frame_num = if decode_all { frame_number } else { 0 };
-        let image = pixel
-            .to_dynamic_image_with_options(frame_num, &options)
-            .context(ConvertImageSnafu)?;
-
         std::fs::create_dir_all(output.parent().unwrap()).unwrap();
 
         image.save(&output).context(SaveImageSnafu)?;
@@ -394,27 +997,323 @@ fn convert_single_file(
     Ok(())
 }
 
-fn collect_dicom_files(
-    file: &PathBuf,
+/// Assemble the frames of a DICOM object into an animation or video file
+fn export_video(
+    file: &FileDicomObject<InMemDicomObject>,
+    output: &Path,
+    frames: FrameSelection,
+    fps: Option<f64>,
+    presentation_state: Option<&PresentationState>,
+    image_options: ImageOptions,
+    verbose: bool,
+) -> Result<(), Error> {
+    let number_of_frames = file.number_of_frames().unwrap_or(1);
+    let frame_numbers = frames.resolve(number_of_frames)?;
+    let durations = cine::frame_durations(file, number_of_frames as usize, fps);
+    let durations = &durations[frame_numbers.start as usize..frame_numbers.end as usize];
+    let frame_rate = 1_000. * durations.len() as f64 / durations.iter().sum::<f64>();
+
+    // video formats take 8 bits per sample
+    let options = image_options.convert_options().force_8bit();
+    let overlays = if image_options.overlays {
+        read_overlays(file).context(ReadOverlaysSnafu)?
+    } else {
+        Vec::new()
+    };
+    let pixel = file.decode_pixel_data().context(DecodePixelDataSnafu)?;
+
+    if verbose {
+        println!(
+            "{}x{}x{} video, {} frames at {:.2} frames per second",
+            pixel.columns(),
+            pixel.rows(),
+            pixel.samples_per_pixel(),
+            durations.len(),
+            frame_rate
+        );
+    }
+
+    let mut writer = None;
+    for (frame_number, duration) in frame_numbers.zip(durations) {
+        let frame_options = image_options.frame_convert_options(&options, &pixel, frame_number)?;
+        let mut image = render_frame(
+            file,
+            &pixel,
+            frame_number,
+            &frame_options,
+            presentation_state,
+        )?;
+        for overlay in &overlays {
+            overlay.burn_into(&mut image, frame_number);
+        }
+
+        let (writer, grayscale) = match &mut writer {
+            Some((writer, grayscale)) => (writer, *grayscale),
+            None => {
+                let grayscale = !image.color().has_color();
+                if let Some(parent) = output.parent() {
+                    std::fs::create_dir_all(parent).context(ExportVideoSnafu)?;
+                }
+                let video = cine::VideoWriter::create(
+                    output,
+                    image.width(),
+                    image.height(),
+                    grayscale,
+                    frame_rate,
+                )
+                .context(ExportVideoSnafu)?;
+                let (writer, _) = writer.insert((video, grayscale));
+                (writer, grayscale)
+            }
+        };
+        let samples = if grayscale {
+            image.into_luma8().into_raw()
+        } else {
+            image.into_rgb8().into_raw()
+        };
+        writer
+            .write_frame(&samples, *duration)
+            .context(ExportVideoSnafu)?;
+    }
+
+    if let Some((writer, _)) = writer {
+        writer.finish().context(ExportVideoSnafu)?;
+    }
+
+    if verbose {
+        println!("Video saved to {}", output.display());
+    }
+
+    Ok(())
+}
+
+/// Tile frames into a contact sheet:
+/// all frames of a single file by default,
+/// or the first frame of each instance
+/// of a directory or multiple files, ordered by instance number
+#[allow(clippy::too_many_arguments)]
+fn export_mosaic(
+    files: &[PathBuf],
     recursive: bool,
-) -> Result<Vec<(FileDicomObject<InMemDicomObject>, PathBuf)>, Error> {
-    let mut dicoms = Vec::new();
+    output: &Path,
+    frames: Option<FrameSelection>,
+    grid: MosaicGrid,
+    labels: bool,
+    presentation_state: Option<&PresentationState>,
+    image_options: ImageOptions,
+    fail_first: bool,
+    verbose: bool,
+) -> Result<(), Error> {
+    let paths = match files {
+        [dir] if dir.is_dir() => collect_files(dir, recursive)?,
+        files => files.to_vec(),
+    };
+    snafu::ensure!(!paths.is_empty(), NoFilesSnafu);
+    let frames = frames.unwrap_or(if paths.len() == 1 {
+        FrameSelection::All
+    } else {
+        FrameSelection::Single(0)
+    });
+
+    let mut objects = Vec::with_capacity(paths.len());
+    for path in &paths {
+        match open_file(path).with_context(|_| ReadFileSnafu { path: path.clone() }) {
+            Ok(obj) => objects.push(obj),
+            Err(e) if fail_first => return Err(e),
+            Err(e) => warn!("{}", Report::from_error(e)),
+        }
+    }
+    snafu::ensure!(!objects.is_empty(), NoFilesSnafu);
+    // stable sort, so that instances without a number keep the path order
+    objects.sort_by_key(|obj| {
+        obj.get(tags::INSTANCE_NUMBER)
+            .and_then(|e| e.to_int::<i32>().ok())
+            .unwrap_or(i32::MAX)
+    });
+
+    // the frames of each object to show, as (object index, frame number)
+    let mut entries = Vec::new();
+    for (i, obj) in objects.iter().enumerate() {
+        let frame_numbers = match frames.resolve(obj.number_of_frames().unwrap_or(1)) {
+            Ok(frame_numbers) => frame_numbers,
+            Err(e) if fail_first => return Err(e),
+            Err(e) => {
+                warn!("{}", Report::from_error(e));
+                continue;
+            }
+        };
+        entries.extend(frame_numbers.map(|frame| (i, frame)));
+    }
+    let entries: Vec<_> = grid
+        .select(entries.len())
+        .into_iter()
+        .map(|i| entries[i])
+        .collect();
+
+    // 8 bits per sample are enough for a quick look
+    let options = image_options.convert_options().force_8bit();
+    let tiles: Vec<Option<Tile>> = entries
+        .par_iter()
+        .map(|&(i, frame)| {
+            let obj = &objects[i];
+            let tile = render_tile(obj, frame, &options, presentation_state, image_options);
+            match tile {
+                Ok(tile) => Ok(Some(tile)),
+                Err(e) if fail_first => Err(e),
+                Err(e) => {
+                    let report = Report::from_error(e);
+                    error!(
+                        "Rendering frame #{} of {}: {}",
+                        frame,
+                        paths[i].display(),
+                        report
+                    );
+                    Ok(None)
+                }
+            }
+        })
+        .collect::<Result<_, Error>>()?;
+    let tiles: Vec<Tile> = tiles.into_iter().flatten().collect();
+    snafu::ensure!(!tiles.is_empty(), NoFilesSnafu);
+
+    let sheet = mosaic::compose(&tiles, grid, labels);
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent).context(SaveDataSnafu)?;
+    }
+    sheet.save(output).context(SaveImageSnafu)?;
+
+    if verbose {
+        println!(
+            "{}x{} contact sheet of {} frames saved to {}",
+            sheet.width(),
+            sheet.height(),
+            tiles.len(),
+            output.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Render a frame of a DICOM object as a labelled tile of a contact sheet
+fn render_tile(
+    file: &FileDicomObject<InMemDicomObject>,
+    frame: u32,
+    options: &ConvertOptions,
+    presentation_state: Option<&PresentationState>,
+    image_options: ImageOptions,
+) -> Result<Tile, Error> {
+    let number_of_frames = file.number_of_frames().unwrap_or(1);
+    // presentation states refer to frames by their number in the object
+    let (pixel, frame_num) = if presentation_state.is_some() {
+        (
+            file.decode_pixel_data().context(DecodePixelDataSnafu)?,
+            frame,
+        )
+    } else {
+        (
+            file.decode_pixel_data_frame(frame)
+                .context(DecodePixelDataSnafu)?,
+            0,
+        )
+    };
+    let frame_options = image_options.frame_convert_options(options, &pixel, frame_num)?;
+    let mut image = render_frame(file, &pixel, frame_num, &frame_options, presentation_state)?;
+    if image_options.overlays {
+        for overlay in read_overlays(file).context(ReadOverlaysSnafu)? {
+            overlay.burn_into(&mut image, frame);
+        }
+    }
+    Ok(Tile {
+        image,
+        label: mosaic::tile_label(file, frame, number_of_frames),
+    })
+}
+
+/// Convert a frame of decoded pixel data into an image,
+/// through the presentation state if one is given
+fn render_frame(
+    file: &FileDicomObject<InMemDicomObject>,
+    pixel: &DecodedPixelData,
+    frame: u32,
+    options: &ConvertOptions,
+    presentation_state: Option<&PresentationState>,
+) -> Result<DynamicImage, Error> {
+    let Some(presentation_state) = presentation_state else {
+        return RenderingPipeline::from_options(options)
+            .render(pixel, frame)
+            .context(ConvertImageSnafu);
+    };
+    let sop_instance_uid = file.meta().media_storage_sop_instance_uid();
+    if !presentation_state.references_image(sop_instance_uid, frame) {
+        warn!(
+            "Frame #{} of {} is not referenced by the presentation state",
+            frame, sop_instance_uid
+        );
+    }
+    presentation_state
+        .render_with_options(pixel, sop_instance_uid, frame, options)
+        .context(ApplyPresentationStateSnafu)
+}
+
+/// Describe the layout of a raw pixel data frame in JSON
+fn raw_sidecar(pixel: &DecodedPixelData, frame: &FrameBytes, frame_number: u32) -> String {
+    let layout = frame.layout();
+    let rescale = pixel.rescale().ok().and_then(|r| r.first().copied());
+    let mut json = format!(
+        concat!(
+            "{{\n",
+            "  \"frame\": {},\n",
+            "  \"columns\": {},\n",
+            "  \"rows\": {},\n",
+            "  \"samples_per_pixel\": {},\n",
+            "  \"bits_allocated\": {},\n",
+            "  \"bits_stored\": {},\n",
+            "  \"signed\": {},\n",
+            "  \"planar_configuration\": {},\n",
+            "  \"photometric_interpretation\": {:?},\n",
+            "  \"byte_order\": \"little\",\n",
+            "  \"pixel_stride\": {},\n",
+            "  \"row_stride\": {}",
+        ),
+        frame_number,
+        layout.columns,
+        layout.rows,
+        layout.samples_per_pixel,
+        layout.bytes_per_sample * 8,
+        layout.bits_stored,
+        layout.pixel_representation == PixelRepresentation::Signed,
+        layout.planar_configuration,
+        pixel.photometric_interpretation().as_str(),
+        layout.pixel_stride,
+        layout.row_stride,
+    );
+    if let Some(plane_stride) = layout.plane_stride {
+        json.push_str(&format!(",\n  \"plane_stride\": {plane_stride}"));
+    }
+    if let Some(rescale) = rescale {
+        json.push_str(&format!(
+            ",\n  \"rescale_slope\": {:?},\n  \"rescale_intercept\": {:?}",
+            rescale.slope, rescale.intercept
+        ));
+    }
+    json.push_str("\n}\n");
+    json
+}
+
+/// Collect the paths of the files in the given directory,
+/// including the files in subdirectories if `recursive` is set
+fn collect_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
     let mut dirs: Vec<PathBuf> = Vec::new();
-    let entries = std::fs::read_dir(file).with_context(|_| ReadDirSnafu)?;
+    let entries = std::fs::read_dir(dir).with_context(|_| ReadDirSnafu)?;
     entries.for_each(|entry| match entry {
         Ok(entry) => {
             let path = entry.path();
             if path.is_dir() {
                 dirs.push(path);
             } else {
-                let obj = match open_file(&path) {
-                    Ok(obj) => obj,
-                    Err(e) => {
-                        warn!("Error reading file {:?}: {}", path, e);
-                        return;
-                    }
-                };
-                dicoms.push((obj, path));
+                files.push(path);
             }
         }
         Err(e) => {
@@ -423,12 +1322,13 @@ fn collect_dicom_files(
     });
     if recursive {
         dirs.iter()
-            .for_each(|dir| match collect_dicom_files(dir, recursive) {
-                Ok(mut d) => dicoms.append(&mut d),
+            .for_each(|dir| match collect_files(dir, recursive) {
+                Ok(mut d) => files.append(&mut d),
                 Err(e) => error!("Error reading directory {:?}: {}", dir, e),
             });
     }
-    Ok(dicoms)
+    files.sort();
+    Ok(files)
 }
 
 #[cfg(test)]
@@ -440,4 +1340,248 @@ mod tests {
     fn verify_cli() {
         App::command().debug_assert();
     }
+
+    #[test]
+    fn convert_options_from_cli() {
+        use clap::Parser;
+        use dicom_pixeldata::{
+            ConvertOptions, ModalityLutOption, Rescale, VoiLutFunction, VoiLutOption, WindowLevel,
+        };
+
+        let app = App::parse_from(["dicom-toimage", "image.dcm"]);
+        assert_eq!(app.image_options.convert_options(), ConvertOptions::new());
+
+        let app = App::parse_from([
+            "dicom-toimage",
+            "--16bit",
+            "--rescale-intercept",
+            "-1024",
+            "--window-center",
+            "40",
+            "--window-width",
+            "400",
+            "--voi-lut-function",
+            "sigmoid",
+            "image.dcm",
+        ]);
+        assert_eq!(
+            app.image_options.convert_options(),
+            ConvertOptions::new()
+                .force_16bit()
+                .with_modality_lut(ModalityLutOption::Override(Rescale::new(1., -1024.)))
+                .with_voi_lut(VoiLutOption::CustomWithFunction(
+                    WindowLevel {
+                        center: 40.,
+                        width: 400.,
+                    },
+                    VoiLutFunction::Sigmoid,
+                )),
+        );
+
+        let app = App::parse_from(["dicom-toimage", "--bit-depth", "16", "image.dcm"]);
+        assert_eq!(
+            app.image_options.convert_options(),
+            ConvertOptions::new().force_16bit(),
+        );
+        assert!(
+            App::try_parse_from(["dicom-toimage", "--bit-depth", "16", "--8bit", "image.dcm"])
+                .is_err()
+        );
+
+        let app = App::parse_from(["dicom-toimage", "--8bit", "--normalize", "image.dcm"]);
+        assert_eq!(
+            app.image_options.convert_options(),
+            ConvertOptions::new()
+                .force_8bit()
+                .with_voi_lut(VoiLutOption::Normalize),
+        );
+
+        // a window requires both center and width
+        assert!(
+            App::try_parse_from(["dicom-toimage", "--window-center", "40", "image.dcm"]).is_err()
+        );
+    }
+
+    #[test]
+    fn frame_selection_from_cli() {
+        use crate::FrameSelection;
+        use clap::Parser;
+
+        // the default depends on the output
+        let app = App::parse_from(["dicom-toimage", "image.dcm"]);
+        assert_eq!(app.frames, None);
+        assert_eq!(FrameSelection::Single(0).resolve(1).unwrap(), 0..1);
+        assert!(!FrameSelection::Single(0).numbered());
+
+        let app = App::parse_from(["dicom-toimage", "--frame", "all", "image.dcm"]);
+        let frames = app.frames.unwrap();
+        assert_eq!(frames, FrameSelection::All);
+        assert_eq!(frames.resolve(30).unwrap(), 0..30);
+        assert!(frames.numbered());
+
+        let app = App::parse_from(["dicom-toimage", "--frames", "10-20", "image.dcm"]);
+        let frames = app.frames.unwrap();
+        assert_eq!(frames, FrameSelection::Range(10, 20));
+        assert_eq!(frames.resolve(30).unwrap(), 10..21);
+        assert!(frames.resolve(20).is_err());
+
+        assert!(App::try_parse_from(["dicom-toimage", "-F", "20-10", "image.dcm"]).is_err());
+        assert!(App::try_parse_from(["dicom-toimage", "-F", "first", "image.dcm"]).is_err());
+
+        let app = App::parse_from(["dicom-toimage", "--to-video", "cine.gif", "cine.dcm"]);
+        assert_eq!(app.to_video.as_deref(), Some("cine.gif".as_ref()));
+        assert!(
+            App::try_parse_from([
+                "dicom-toimage",
+                "--to-video",
+                "cine.mp4",
+                "-o",
+                "cine.png",
+                "cine.dcm"
+            ])
+            .is_err()
+        );
+        assert!(App::try_parse_from(["dicom-toimage", "--fps", "25", "cine.dcm"]).is_err());
+    }
+
+    #[test]
+    fn presentation_state_from_cli() {
+        use clap::Parser;
+
+        let app = App::parse_from(["dicom-toimage", "--pr", "pr.dcm", "image.dcm"]);
+        assert_eq!(app.presentation_state.as_deref(), Some("pr.dcm".as_ref()));
+        let app = App::parse_from([
+            "dicom-toimage",
+            "--presentation-state",
+            "pr.dcm",
+            "--to-video",
+            "cine.gif",
+            "cine.dcm",
+        ]);
+        assert_eq!(app.presentation_state.as_deref(), Some("pr.dcm".as_ref()));
+        for flag in ["--raw", "--unwrap", "--overlays"] {
+            assert!(
+                App::try_parse_from(["dicom-toimage", "--pr", "pr.dcm", flag, "image.dcm"])
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn numbered_output_path() {
+        use std::path::{Path, PathBuf};
+
+        assert_eq!(
+            crate::numbered_output_path(Path::new("out/1.2.3.png"), 7),
+            PathBuf::from("out/1.2.3_0007.png")
+        );
+        assert_eq!(
+            crate::numbered_output_path(Path::new("image"), 12),
+            PathBuf::from("image_0012")
+        );
+    }
+
+    #[test]
+    fn raw_sidecar_describes_layout() {
+        use dicom_core::{DataElement, PrimitiveValue, VR};
+        use dicom_dictionary_std::{tags, uids};
+        use dicom_object::{FileDicomObject, FileMetaTableBuilder};
+        use dicom_pixeldata::PixelDecoder;
+
+        let mut obj = FileDicomObject::new_empty_with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(uids::EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(uids::CT_IMAGE_STORAGE)
+                .media_storage_sop_instance_uid("2.25.270445054140113943515388848809104576486")
+                .build()
+                .unwrap(),
+        );
+        let us = |tag, value: u16| DataElement::new(tag, VR::US, PrimitiveValue::from(value));
+        obj.put(us(tags::SAMPLES_PER_PIXEL, 1));
+        obj.put(DataElement::new(
+            tags::PHOTOMETRIC_INTERPRETATION,
+            VR::CS,
+            "MONOCHROME2",
+        ));
+        obj.put(us(tags::ROWS, 2));
+        obj.put(us(tags::COLUMNS, 3));
+        obj.put(us(tags::BITS_ALLOCATED, 16));
+        obj.put(us(tags::BITS_STORED, 12));
+        obj.put(us(tags::HIGH_BIT, 11));
+        obj.put(us(tags::PIXEL_REPRESENTATION, 1));
+        obj.put(DataElement::new(tags::RESCALE_SLOPE, VR::DS, "2"));
+        obj.put(DataElement::new(tags::RESCALE_INTERCEPT, VR::DS, "-1024"));
+        obj.put(DataElement::new(
+            tags::PIXEL_DATA,
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 1, 2, 3, 4, 5].into()),
+        ));
+
+        let pixel = obj.decode_pixel_data().unwrap();
+        let frame = pixel.frame_bytes(0).unwrap();
+        assert_eq!(frame.bytes().len(), 12);
+        let json = super::raw_sidecar(&pixel, &frame, 0);
+        for line in [
+            "\"columns\": 3,",
+            "\"rows\": 2,",
+            "\"bits_allocated\": 16,",
+            "\"bits_stored\": 12,",
+            "\"signed\": true,",
+            "\"photometric_interpretation\": \"MONOCHROME2\",",
+            "\"row_stride\": 6,",
+            "\"rescale_slope\": 2.0,",
+            "\"rescale_intercept\": -1024.0",
+        ] {
+            assert!(json.contains(line), "{line} missing from {json}");
+        }
+    }
+
+    #[test]
+    fn auto_window_from_samples() {
+        use super::{AutoWindowArg, Percentiles, auto_window};
+        use clap::Parser;
+        use dicom_pixeldata::WindowLevel;
+
+        let percentiles: Percentiles = "1,99".parse().unwrap();
+        assert_eq!(percentiles, Percentiles { low: 1., high: 99. });
+        assert!("99,1".parse::<Percentiles>().is_err());
+        assert!("0,101".parse::<Percentiles>().is_err());
+        assert!("50".parse::<Percentiles>().is_err());
+
+        // 0 to 100, plus one outlier
+        let mut values: Vec<f64> = (0..=100).map(f64::from).collect();
+        values.push(3000.);
+
+        assert_eq!(
+            auto_window(values.clone(), AutoWindowArg::MinMax, percentiles),
+            Some(WindowLevel {
+                center: 1500.,
+                width: 3000.
+            }),
+        );
+        let window = auto_window(values, AutoWindowArg::Percentile, percentiles).unwrap();
+        assert!(window.center < 100.);
+        assert!(window.width < 100.);
+
+        assert_eq!(
+            auto_window(vec![], AutoWindowArg::MinMax, percentiles),
+            None
+        );
+
+        let app = App::parse_from(["dicom-toimage", "--auto-window", "minmax", "ct.dcm"]);
+        assert_eq!(app.image_options.auto_window, Some(AutoWindowArg::MinMax));
+        assert!(
+            App::try_parse_from([
+                "dicom-toimage",
+                "--auto-window",
+                "percentile",
+                "--voi-lut-index",
+                "1",
+                "ct.dcm"
+            ])
+            .is_err()
+        );
+        let app = App::parse_from(["dicom-toimage", "--voi-lut-index", "1", "ct.dcm"]);
+        assert_eq!(app.image_options.voi_lut_index, Some(1));
+    }
 }
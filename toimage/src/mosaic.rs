@@ -0,0 +1,272 @@
+//! Contact sheets of frames or instances.
+//!
+//! A mosaic tiles rendered frames into a grid on a single image,
+//! each tile fitted into a cell of the size of the first one
+//! and optionally labelled with a few key attributes
+//! (instance number, slice location, frame number).
+//! Labels are drawn with a small built-in bitmap font
+//! covering digits and the few letters used.
+use std::str::FromStr;
+
+use dicom_dictionary_std::tags;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use dicom_pixeldata::image::{DynamicImage, Rgb, RgbImage, imageops::FilterType};
+
+/// The space between two cells in pixels
+const GAP: u32 = 2;
+
+/// The size of the mosaic grid.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MosaicGrid {
+    /// the number of columns, or automatic for a near-square grid
+    pub columns: Option<u32>,
+    /// the number of rows, or as many as needed
+    pub rows: Option<u32>,
+}
+
+impl FromStr for MosaicGrid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| match n.trim().parse::<u32>() {
+            Ok(0) => Err(format!("invalid grid size `{s}`")),
+            Ok(n) => Ok(n),
+            Err(e) => Err(format!("invalid grid size `{s}`: {e}")),
+        };
+        match s.split_once(['x', 'X']) {
+            Some((columns, rows)) => Ok(MosaicGrid {
+                columns: Some(parse(columns)?),
+                rows: Some(parse(rows)?),
+            }),
+            None => Ok(MosaicGrid {
+                columns: Some(parse(s)?),
+                rows: None,
+            }),
+        }
+    }
+}
+
+impl MosaicGrid {
+    /// Determine the number of columns and rows
+    /// to lay out the given number of tiles.
+    pub fn layout(self, tiles: usize) -> (u32, u32) {
+        let tiles = tiles.max(1) as u32;
+        match (self.columns, self.rows) {
+            (Some(columns), Some(rows)) => (columns, rows),
+            (Some(columns), None) => (columns, tiles.div_ceil(columns)),
+            (None, Some(rows)) => (tiles.div_ceil(rows), rows),
+            (None, None) => {
+                let columns = (tiles as f64).sqrt().ceil() as u32;
+                (columns, tiles.div_ceil(columns))
+            }
+        }
+    }
+
+    /// Select the tiles to show out of the given number of tiles,
+    /// evenly spaced when there are more tiles than cells.
+    pub fn select(self, tiles: usize) -> Vec<usize> {
+        let (columns, rows) = self.layout(tiles);
+        let cells = (columns * rows) as usize;
+        if tiles <= cells {
+            (0..tiles).collect()
+        } else {
+            (0..cells).map(|i| i * tiles / cells).collect()
+        }
+    }
+}
+
+/// A rendered tile of the mosaic.
+#[derive(Debug)]
+pub struct Tile {
+    /// the rendered frame
+    pub image: DynamicImage,
+    /// the lines of the label shown on the tile
+    pub label: Vec<String>,
+}
+
+/// Build the label lines of a frame of a DICOM object:
+/// its instance number, its slice location,
+/// and its frame number in multi-frame objects.
+pub fn tile_label(
+    obj: &FileDicomObject<InMemDicomObject>,
+    frame: u32,
+    number_of_frames: u32,
+) -> Vec<String> {
+    let mut label = Vec::new();
+    if let Some(number) = obj
+        .get(tags::INSTANCE_NUMBER)
+        .and_then(|e| e.to_int::<i32>().ok())
+    {
+        label.push(format!("IN {number}"));
+    }
+    if let Some(location) = obj
+        .get(tags::SLICE_LOCATION)
+        .and_then(|e| e.to_float64().ok())
+    {
+        label.push(format!("SL {location:.1}"));
+    }
+    if number_of_frames > 1 {
+        label.push(format!("#{frame}"));
+    }
+    label
+}
+
+/// Tile the given images into a contact sheet.
+///
+/// Each tile is fitted into a cell of the size of the first tile,
+/// preserving its aspect ratio.
+/// Labels are drawn on the top left corner of each tile
+/// if `labels` is set.
+pub fn compose(tiles: &[Tile], grid: MosaicGrid, labels: bool) -> RgbImage {
+    let (columns, rows) = grid.layout(tiles.len());
+    let (cell_width, cell_height) = tiles
+        .first()
+        .map(|tile| (tile.image.width(), tile.image.height()))
+        .unwrap_or((1, 1));
+    let mut sheet = RgbImage::new(
+        columns * (cell_width + GAP) - GAP,
+        rows * (cell_height + GAP) - GAP,
+    );
+    let scale = (cell_width / 128).max(1);
+
+    for (i, tile) in tiles.iter().take((columns * rows) as usize).enumerate() {
+        let image = if (tile.image.width(), tile.image.height()) == (cell_width, cell_height) {
+            tile.image.to_rgb8()
+        } else {
+            tile.image
+                .resize(cell_width, cell_height, FilterType::Triangle)
+                .into_rgb8()
+        };
+        let x = (i as u32 % columns) * (cell_width + GAP) + (cell_width - image.width()) / 2;
+        let y = (i as u32 / columns) * (cell_height + GAP) + (cell_height - image.height()) / 2;
+        for (px, py, pixel) in image.enumerate_pixels() {
+            sheet.put_pixel(x + px, y + py, *pixel);
+        }
+        if labels {
+            let x = (i as u32 % columns) * (cell_width + GAP);
+            let y = (i as u32 / columns) * (cell_height + GAP);
+            draw_label(&mut sheet, x, y, cell_width, &tile.label, scale);
+        }
+    }
+    sheet
+}
+
+/// The glyphs of the label font, 3 pixels wide and 5 pixels high,
+/// one row per byte from the top, most significant bit on the left
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'N' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        _ => [0; 5],
+    }
+}
+
+/// Draw the lines of a label in white over a black box,
+/// clipped to the given width.
+fn draw_label(sheet: &mut RgbImage, x: u32, y: u32, width: u32, label: &[String], scale: u32) {
+    let Some(longest) = label
+        .iter()
+        .map(|line| line.chars().count() as u32)
+        .max()
+        .filter(|&n| n > 0)
+    else {
+        return;
+    };
+    let box_width = (longest * 4 + 1) * scale;
+    let box_height = (label.len() as u32 * 6 + 1) * scale;
+    for by in y..(y + box_height).min(sheet.height()) {
+        for bx in x..(x + box_width.min(width)).min(sheet.width()) {
+            sheet.put_pixel(bx, by, Rgb([0, 0, 0]));
+        }
+    }
+
+    for (row, line) in label.iter().enumerate() {
+        let top = y + (row as u32 * 6 + 1) * scale;
+        for (column, c) in line.chars().enumerate() {
+            let left = x + (column as u32 * 4 + 1) * scale;
+            for (gy, bits) in glyph(c).into_iter().enumerate() {
+                for gx in 0..3 {
+                    if bits & (0b100 >> gx) == 0 {
+                        continue;
+                    }
+                    let px = left + gx * scale;
+                    let py = top + gy as u32 * scale;
+                    for (dx, dy) in (0..scale).flat_map(|dx| (0..scale).map(move |dy| (dx, dy))) {
+                        if px + dx < (x + width).min(sheet.width()) && py + dy < sheet.height() {
+                            sheet.put_pixel(px + dx, py + dy, Rgb([255, 255, 255]));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_pixeldata::image::GrayImage;
+
+    #[test]
+    fn grid_layout() {
+        assert_eq!(
+            "4x3".parse(),
+            Ok(MosaicGrid {
+                columns: Some(4),
+                rows: Some(3)
+            })
+        );
+        assert_eq!(
+            "5".parse(),
+            Ok(MosaicGrid {
+                columns: Some(5),
+                rows: None
+            })
+        );
+        assert!("0x2".parse::<MosaicGrid>().is_err());
+
+        assert_eq!(MosaicGrid::default().layout(10), (4, 3));
+        assert_eq!(MosaicGrid::default().layout(1), (1, 1));
+        assert_eq!("3".parse::<MosaicGrid>().unwrap().layout(10), (3, 4));
+        // more tiles than cells: evenly spaced
+        assert_eq!("2x2".parse::<MosaicGrid>().unwrap().select(8), [0, 2, 4, 6]);
+        assert_eq!("2x2".parse::<MosaicGrid>().unwrap().select(3), [0, 1, 2]);
+    }
+
+    #[test]
+    fn compose_labelled_tiles() {
+        let tile = |value: u8, label: &str| Tile {
+            image: DynamicImage::ImageLuma8(GrayImage::from_pixel(8, 6, [value].into())),
+            label: vec![label.to_string()],
+        };
+        let tiles = [tile(50, "1"), tile(100, ""), tile(150, "")];
+        let sheet = compose(&tiles, MosaicGrid::default(), true);
+        assert_eq!(sheet.dimensions(), (8 * 2 + GAP, 6 * 2 + GAP));
+
+        // the first tile has a label box with a white glyph
+        assert_eq!(sheet.get_pixel(0, 0), &Rgb([0, 0, 0]));
+        assert_eq!(sheet.get_pixel(2, 1), &Rgb([255, 255, 255]));
+        assert_eq!(sheet.get_pixel(7, 5), &Rgb([50, 50, 50]));
+        // the other tiles, without any label
+        assert_eq!(sheet.get_pixel(8 + GAP, 0), &Rgb([100, 100, 100]));
+        assert_eq!(sheet.get_pixel(0, 6 + GAP), &Rgb([150, 150, 150]));
+        // the empty cell and the gaps are black
+        assert_eq!(sheet.get_pixel(8 + GAP, 6 + GAP), &Rgb([0, 0, 0]));
+        assert_eq!(sheet.get_pixel(8, 0), &Rgb([0, 0, 0]));
+    }
+}
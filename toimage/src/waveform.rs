@@ -0,0 +1,83 @@
+//! Export of waveform objects, such as ECGs,
+//! as plots or as sample tables.
+//!
+//! The output format is chosen by the file extension:
+//! `.csv` and `.npy` files receive the samples of each channel,
+//! any other image format receives a plot of the channels.
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use dicom_dictionary_std::tags;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use dicom_pixeldata::waveforms::read_waveforms;
+use snafu::ResultExt;
+
+use crate::{Error, ReadWaveformsSnafu, SaveDataSnafu, SaveImageSnafu};
+
+/// The width of waveform plots in pixels
+const PLOT_WIDTH: u32 = 1200;
+/// The height of each channel in waveform plots in pixels
+const PLOT_CHANNEL_HEIGHT: u32 = 120;
+
+/// Whether the object holds waveforms instead of an image.
+pub fn is_waveform(obj: &FileDicomObject<InMemDicomObject>) -> bool {
+    obj.get(tags::PIXEL_DATA).is_none() && obj.get(tags::WAVEFORM_SEQUENCE).is_some()
+}
+
+/// Write each multiplex group of waveforms to the output path,
+/// numbering the output files if there are more than one.
+pub fn convert_waveforms(
+    obj: &FileDicomObject<InMemDicomObject>,
+    output: &Path,
+    verbose: bool,
+) -> Result<(), Error> {
+    let waveforms = read_waveforms(obj).context(ReadWaveformsSnafu)?;
+    let extension = output
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    for (i, waveform) in waveforms.iter().enumerate() {
+        let output: PathBuf = if waveforms.len() > 1 {
+            crate::numbered_output_path(output, i as u32)
+        } else {
+            output.to_path_buf()
+        };
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent).context(SaveDataSnafu)?;
+        }
+        match extension.as_str() {
+            "csv" => {
+                let file = File::create(&output).context(SaveDataSnafu)?;
+                waveform
+                    .write_csv(BufWriter::new(file))
+                    .context(SaveDataSnafu)?;
+            }
+            "npy" => {
+                let file = File::create(&output).context(SaveDataSnafu)?;
+                waveform
+                    .write_npy(BufWriter::new(file))
+                    .context(SaveDataSnafu)?;
+            }
+            _ => {
+                waveform
+                    .plot(PLOT_WIDTH, PLOT_CHANNEL_HEIGHT)
+                    .save(&output)
+                    .context(SaveImageSnafu)?;
+            }
+        }
+        if verbose {
+            println!(
+                "Waveform of {} channels, {} samples at {} Hz saved to {}",
+                waveform.channels.len(),
+                waveform.number_of_samples,
+                waveform.sampling_frequency,
+                output.display()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1657 @@
+//! Support for JPEG 2000 image encoding.
+//!
+//! This is a native implementation of a JPEG 2000 Part 1 encoder.
+//! It produces a single tile codestream
+//! with one quality layer in LRCP progression order
+//! and no multiple component transformation.
+//! Lossless encoding uses the reversible 5-3 wavelet filter.
+//! Lossy encoding uses the irreversible 9-7 wavelet filter,
+//! followed by post-compression rate-distortion optimization
+//! so that the requested compression ratio is met.
+//!
+//! Only unsigned samples of up to 16 bits are supported.
+//! Decoding JPEG 2000 is provided separately by the [`jpeg2k`] module.
+//!
+//! [`jpeg2k`]: super::jpeg2k
+use dicom_core::Tag;
+use dicom_core::ops::{AttributeAction, AttributeOp};
+use dicom_encoding::adapters::{
+    EncodeOptions, EncodeResult, PixelDataObject, PixelDataWriter, encode_error,
+};
+use dicom_encoding::snafu::prelude::*;
+
+/// Pixel data writer for JPEG 2000 Image Compression,
+/// supporting both lossy and lossless encoding.
+///
+/// The target compression ratio is taken from
+/// [`compression_ratio`](EncodeOptions::compression_ratio) if specified,
+/// or derived from [`quality`](EncodeOptions::quality) otherwise.
+/// A quality of 100 results in lossless encoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Jpeg2000Encoder;
+
+/// Pixel data writer specifically for JPEG 2000 lossless.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Jpeg2000LosslessEncoder;
+
+/// The default quality when none is specified
+const DEFAULT_QUALITY: u8 = 85;
+
+/// The maximum number of wavelet decomposition levels
+const MAX_DECOMPOSITION_LEVELS: u32 = 5;
+
+/// Base 2 logarithm of the code-block width and height
+const CODE_BLOCK_EXPONENT: u32 = 6;
+
+/// The code-block width and height
+const CODE_BLOCK_SIZE: usize = 1 << CODE_BLOCK_EXPONENT;
+
+/// The maximum image width and height,
+/// so that every resolution fits in a single precinct
+const MAX_DIMENSION: u16 = 1 << 15;
+
+/// The maximum number of guard bits which can be declared
+const MAX_GUARD_BITS: u32 = 7;
+
+/// L2 norms of the 9-7 synthesis basis vectors, per decomposition level,
+/// for the low-pass band of a single dimension
+const NORMS_97_LOW: [f64; 6] = [
+    1.0,
+    1.402_108_167_929_744,
+    2.030_371_856_081_801,
+    2.901_162_556_278_577,
+    4.115_285_175_175_846,
+    5.824_510_863_772_892,
+];
+
+/// L2 norms of the 9-7 synthesis basis vectors, per decomposition level,
+/// for the high-pass band of a single dimension
+const NORMS_97_HIGH: [f64; 6] = [
+    1.0,
+    0.721_261_382_508_076,
+    0.983_471_304_122_789,
+    1.441_962_404_139_456,
+    2.073_760_419_671_671,
+    2.947_324_876_533_931,
+];
+
+impl PixelDataWriter for Jpeg2000Encoder {
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        let cols = src
+            .cols()
+            .context(encode_error::MissingAttributeSnafu { name: "Columns" })?;
+        let rows = src
+            .rows()
+            .context(encode_error::MissingAttributeSnafu { name: "Rows" })?;
+        let samples_per_pixel =
+            src.samples_per_pixel()
+                .context(encode_error::MissingAttributeSnafu {
+                    name: "SamplesPerPixel",
+                })?;
+        let bits_allocated = src
+            .bits_allocated()
+            .context(encode_error::MissingAttributeSnafu {
+                name: "BitsAllocated",
+            })?;
+        let bits_stored = src
+            .bits_stored()
+            .context(encode_error::MissingAttributeSnafu { name: "BitsStored" })?;
+
+        ensure_whatever!(
+            bits_allocated == 8 || bits_allocated == 16,
+            "BitsAllocated other than 8 or 16 is not supported"
+        );
+        ensure_whatever!(
+            bits_stored >= 1 && bits_stored <= bits_allocated,
+            "Unsupported BitsStored {}",
+            bits_stored
+        );
+        ensure_whatever!(
+            samples_per_pixel == 1 || samples_per_pixel == 3,
+            "SamplesPerPixel other than 1 or 3 is not supported"
+        );
+        ensure_whatever!(
+            cols > 0 && rows > 0 && cols <= MAX_DIMENSION && rows <= MAX_DIMENSION,
+            "Unsupported image size {}x{}",
+            cols,
+            rows
+        );
+
+        let bytes_per_sample = (bits_allocated / 8) as usize;
+        let width = cols as usize;
+        let height = rows as usize;
+        let num_components = samples_per_pixel as usize;
+        let frame_size = width * height * num_components * bytes_per_sample;
+
+        // identify frame data using the frame index
+        let frame_data = src
+            .frame_pixel_data(frame)
+            .context(encode_error::FrameRangeOutOfBoundsSnafu)?;
+        ensure_whatever!(
+            frame_data.len() >= frame_size,
+            "Frame #{} is too short ({} bytes, expected {})",
+            frame,
+            frame_data.len(),
+            frame_size
+        );
+
+        let pmi = src.photometric_interpretation();
+
+        // decide on the compression ratio, if lossy
+        let compression_ratio = if pmi == Some("PALETTE COLOR") {
+            // force lossless encoding of palette color samples
+            None
+        } else if let Some(ratio) = options.compression_ratio {
+            ensure_whatever!(
+                ratio.is_finite() && ratio >= 1.,
+                "Invalid compression ratio {}",
+                ratio
+            );
+            Some(ratio as f64)
+        } else {
+            let quality = options.quality.unwrap_or(DEFAULT_QUALITY).min(100);
+            if quality == 100 {
+                None
+            } else {
+                Some(1. + f64::from(100 - quality) / 2.)
+            }
+        };
+
+        // de-interleave samples into components
+        let mask = ((1_u32 << bits_stored) - 1) as u16;
+        let mut components = vec![Vec::with_capacity(width * height); num_components];
+        for (i, sample) in frame_data[..frame_size]
+            .chunks_exact(bytes_per_sample)
+            .enumerate()
+        {
+            let value = match sample {
+                [v] => u16::from(*v),
+                [lo, hi] => u16::from_le_bytes([*lo, *hi]),
+                _ => unreachable!(),
+            };
+            components[i % num_components].push(value & mask);
+        }
+
+        let target_len =
+            compression_ratio.map(|ratio| (frame_size as f64 / ratio).floor() as usize);
+        let codestream = encode_codestream(
+            &components,
+            width,
+            height,
+            u32::from(bits_stored),
+            target_len,
+        )?;
+        dst.extend_from_slice(&codestream);
+
+        let mut changes = if compression_ratio.is_some() {
+            let compression_ratio = frame_size as f64 / codestream.len() as f64;
+            let compression_ratio = format!("{compression_ratio:.6}");
+
+            // provide attribute changes
+            vec![
+                // lossy image compression
+                AttributeOp::new(Tag(0x0028, 0x2110), AttributeAction::SetStr("01".into())),
+                // lossy image compression ratio
+                AttributeOp::new(
+                    Tag(0x0028, 0x2112),
+                    AttributeAction::PushStr(compression_ratio.into()),
+                ),
+            ]
+        } else {
+            vec![
+                // lossless image compression
+                AttributeOp::new(
+                    Tag(0x0028, 0x2110),
+                    AttributeAction::SetIfMissing("00".into()),
+                ),
+            ]
+        };
+
+        if samples_per_pixel == 1 {
+            // set Photometric Interpretation to MONOCHROME2
+            // if it was neither of the expected 1-channel formats
+            if pmi != Some("MONOCHROME1")
+                && pmi != Some("MONOCHROME2")
+                && pmi != Some("PALETTE COLOR")
+            {
+                changes.push(AttributeOp::new(
+                    Tag(0x0028, 0x0004),
+                    AttributeAction::SetStr("MONOCHROME2".into()),
+                ));
+            }
+        } else if samples_per_pixel == 3 {
+            // set Photometric Interpretation to RGB
+            // if it was not already set to RGB
+            if pmi != Some("RGB") {
+                changes.push(AttributeOp::new(
+                    Tag(0x0028, 0x0004),
+                    AttributeAction::SetStr("RGB".into()),
+                ));
+            }
+        }
+
+        Ok(changes)
+    }
+}
+
+impl PixelDataWriter for Jpeg2000LosslessEncoder {
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        mut options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        // override rate options and defer to the main encoder
+        options.quality = Some(100);
+        options.compression_ratio = None;
+        Jpeg2000Encoder.encode_frame(src, frame, options, dst)
+    }
+}
+
+/// Encode an image into a JPEG 2000 codestream.
+///
+/// Each component is a sequence of unsigned samples in row-major order.
+/// If `target_len` is specified,
+/// the image is encoded lossily so that the codestream
+/// does not exceed the given number of bytes whenever possible.
+/// Otherwise, the image is encoded losslessly.
+fn encode_codestream(
+    components: &[Vec<u16>],
+    width: usize,
+    height: usize,
+    precision: u32,
+    target_len: Option<usize>,
+) -> EncodeResult<Vec<u8>> {
+    let reversible = target_len.is_none();
+    let levels = decomposition_levels(width, height);
+    let resolutions = band_layout(width, height, levels, precision, reversible);
+
+    // apply DC level shift and the discrete wavelet transform
+    let dc_offset = 1_i32 << (precision - 1);
+    let coefficients: Vec<Vec<f64>> = components
+        .iter()
+        .map(|samples| {
+            if reversible {
+                let mut data: Vec<i32> =
+                    samples.iter().map(|&s| i32::from(s) - dc_offset).collect();
+                forward_dwt_53(&mut data, width, height, levels);
+                data.into_iter().map(f64::from).collect()
+            } else {
+                let mut data: Vec<f64> = samples
+                    .iter()
+                    .map(|&s| f64::from(i32::from(s) - dc_offset))
+                    .collect();
+                forward_dwt_97(&mut data, width, height, levels);
+                data
+            }
+        })
+        .collect();
+
+    // the number of guard bits must cover the largest quantized coefficient
+    let mut guard_bits = 2;
+    for coefficients in &coefficients {
+        for band in resolutions.iter().flatten() {
+            let max_magnitude = (0..band.height)
+                .flat_map(|y| {
+                    let row = (band.y0 + y) * width + band.x0;
+                    &coefficients[row..row + band.width]
+                })
+                .map(|c| (c.abs() / band.step) as u32)
+                .max()
+                .unwrap_or(0);
+            let bitplanes = u32::BITS - max_magnitude.leading_zeros();
+            guard_bits = guard_bits.max((bitplanes + 1).saturating_sub(band.exponent));
+        }
+    }
+    ensure_whatever!(
+        guard_bits <= MAX_GUARD_BITS,
+        "Wavelet coefficients exceed the representable range"
+    );
+
+    // entropy code each code-block
+    let mut tile: Vec<Vec<Vec<EncodedBand>>> = coefficients
+        .iter()
+        .map(|coefficients| {
+            resolutions
+                .iter()
+                .map(|bands| {
+                    bands
+                        .iter()
+                        .map(|band| encode_band(band, coefficients, width, guard_bits))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+
+    let header = main_header(
+        components.len(),
+        width,
+        height,
+        precision,
+        levels,
+        &resolutions,
+        guard_bits,
+        reversible,
+    );
+    // SOT, SOD and EOC markers
+    let overhead = header.len() + 12 + 2 + 2;
+
+    let packets = match target_len {
+        None => {
+            for block in tile
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .flat_map(|b| &mut b.blocks)
+            {
+                block.included_passes = block.passes.len();
+            }
+            write_packets(&tile, levels)
+        }
+        Some(target_len) => {
+            let hulls: Vec<Vec<(usize, f64)>> = tile
+                .iter()
+                .flatten()
+                .flatten()
+                .flat_map(|b| &b.blocks)
+                .map(|block| convex_hull(&block.passes))
+                .collect();
+
+            // packet headers are only known after rate allocation,
+            // so adjust the budget until the codestream fits
+            let mut budget = target_len.saturating_sub(overhead);
+            let mut packets;
+            let mut attempts = 0;
+            loop {
+                allocate(&mut tile, &hulls, budget);
+                packets = write_packets(&tile, levels);
+                let total = overhead + packets.len();
+                attempts += 1;
+                if total <= target_len || budget == 0 || attempts == 8 {
+                    break;
+                }
+                budget = budget.saturating_sub(total - target_len);
+            }
+            packets
+        }
+    };
+
+    let mut out = header;
+    // SOT
+    put_u16(&mut out, 0xFF90);
+    put_u16(&mut out, 10);
+    put_u16(&mut out, 0);
+    put_u32(&mut out, (12 + 2 + packets.len()) as u32);
+    out.push(0);
+    out.push(1);
+    // SOD
+    put_u16(&mut out, 0xFF93);
+    out.extend_from_slice(&packets);
+    // EOC
+    put_u16(&mut out, 0xFFD9);
+    Ok(out)
+}
+
+/// Determine the number of wavelet decomposition levels for an image,
+/// ensuring that the lowest resolution is not too small.
+fn decomposition_levels(width: usize, height: usize) -> u32 {
+    let min_dimension = width.min(height);
+    let mut levels = 0;
+    while levels < MAX_DECOMPOSITION_LEVELS && (min_dimension >> (levels + 1)) >= 4 {
+        levels += 1;
+    }
+    levels
+}
+
+/// Write the main header of the codestream,
+/// from the start of codestream marker up to the quantization default.
+#[allow(clippy::too_many_arguments)]
+fn main_header(
+    num_components: usize,
+    width: usize,
+    height: usize,
+    precision: u32,
+    levels: u32,
+    resolutions: &[Vec<Band>],
+    guard_bits: u32,
+    reversible: bool,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    // SOC
+    put_u16(&mut out, 0xFF4F);
+
+    // SIZ
+    put_u16(&mut out, 0xFF51);
+    put_u16(&mut out, (38 + 3 * num_components) as u16);
+    // capabilities
+    put_u16(&mut out, 0);
+    // image size and offset
+    put_u32(&mut out, width as u32);
+    put_u32(&mut out, height as u32);
+    put_u32(&mut out, 0);
+    put_u32(&mut out, 0);
+    // tile size and offset
+    put_u32(&mut out, width as u32);
+    put_u32(&mut out, height as u32);
+    put_u32(&mut out, 0);
+    put_u32(&mut out, 0);
+    put_u16(&mut out, num_components as u16);
+    for _ in 0..num_components {
+        // unsigned samples, no subsampling
+        out.push((precision - 1) as u8);
+        out.push(1);
+        out.push(1);
+    }
+
+    // COD
+    put_u16(&mut out, 0xFF52);
+    put_u16(&mut out, 12);
+    // default precincts, no SOP or EPH markers
+    out.push(0);
+    // LRCP progression order
+    out.push(0);
+    // number of layers
+    put_u16(&mut out, 1);
+    // no multiple component transformation
+    out.push(0);
+    out.push(levels as u8);
+    out.push((CODE_BLOCK_EXPONENT - 2) as u8);
+    out.push((CODE_BLOCK_EXPONENT - 2) as u8);
+    // code-block style
+    out.push(0);
+    // wavelet filter
+    out.push(if reversible { 1 } else { 0 });
+
+    // QCD
+    let num_bands = resolutions.iter().map(|bands| bands.len()).sum::<usize>();
+    put_u16(&mut out, 0xFF5C);
+    if reversible {
+        put_u16(&mut out, (3 + num_bands) as u16);
+        out.push((guard_bits << 5) as u8);
+        for band in resolutions.iter().flatten() {
+            out.push((band.exponent << 3) as u8);
+        }
+    } else {
+        // scalar expounded quantization
+        put_u16(&mut out, (3 + 2 * num_bands) as u16);
+        out.push((guard_bits << 5 | 2) as u8);
+        for band in resolutions.iter().flatten() {
+            put_u16(&mut out, (band.exponent << 11 | band.mantissa) as u16);
+        }
+    }
+    out
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+// --- wavelet transform ---
+
+/// Apply the forward reversible 5-3 wavelet transform in place,
+/// leaving the subbands of each level in the usual Mallat layout.
+fn forward_dwt_53(data: &mut [i32], width: usize, height: usize, levels: u32) {
+    forward_dwt(data, width, height, levels, forward_53);
+}
+
+/// Apply the forward irreversible 9-7 wavelet transform in place,
+/// leaving the subbands of each level in the usual Mallat layout.
+fn forward_dwt_97(data: &mut [f64], width: usize, height: usize, levels: u32) {
+    forward_dwt(data, width, height, levels, forward_97);
+}
+
+/// Apply a 2D wavelet transform
+/// by transforming each column and then each row of the region
+/// covered by the low-pass band of the previous level.
+fn forward_dwt<T: Copy + Default>(
+    data: &mut [T],
+    width: usize,
+    height: usize,
+    levels: u32,
+    filter: fn(&mut [T]),
+) {
+    let mut line = Vec::with_capacity(width.max(height));
+    let (mut w, mut h) = (width, height);
+    for _ in 0..levels {
+        for x in 0..w {
+            line.clear();
+            line.extend((0..h).map(|y| data[y * width + x]));
+            filter(&mut line);
+            for (y, v) in line.iter().enumerate() {
+                data[y * width + x] = *v;
+            }
+        }
+        for y in 0..h {
+            filter(&mut data[y * width..y * width + w]);
+        }
+        w = w.div_ceil(2);
+        h = h.div_ceil(2);
+    }
+}
+
+/// Reflect an index into the range `0..n` by whole-sample symmetric extension.
+fn reflect(i: isize, n: usize) -> usize {
+    let n = n as isize;
+    let i = if i < 0 { -i } else { i };
+    (if i >= n { 2 * (n - 1) - i } else { i }) as usize
+}
+
+/// One dimensional reversible 5-3 transform of a signal starting at an even position,
+/// placing the low-pass coefficients first.
+fn forward_53(x: &mut [i32]) {
+    let n = x.len();
+    if n < 2 {
+        return;
+    }
+    let at = |x: &[i32], i: isize| x[reflect(i, n)];
+    let mut y = x.to_vec();
+    for i in (1..n).step_by(2) {
+        let i = i as isize;
+        y[i as usize] = x[i as usize] - ((at(x, i - 1) + at(x, i + 1)) >> 1);
+    }
+    for i in (0..n).step_by(2) {
+        let i = i as isize;
+        y[i as usize] = x[i as usize] + ((at(&y, i - 1) + at(&y, i + 1) + 2) >> 2);
+    }
+    deinterleave(&y, x);
+}
+
+/// One dimensional irreversible 9-7 transform of a signal starting at an even position,
+/// placing the low-pass coefficients first.
+fn forward_97(x: &mut [f64]) {
+    const ALPHA: f64 = -1.586_134_342_059_924;
+    const BETA: f64 = -0.052_980_118_572_961;
+    const GAMMA: f64 = 0.882_911_075_530_934;
+    const DELTA: f64 = 0.443_506_852_043_971;
+    const K: f64 = 1.230_174_104_914_001;
+
+    let n = x.len();
+    if n < 2 {
+        return;
+    }
+    let mut y = x.to_vec();
+    for (coefficient, parity) in [(ALPHA, 1), (BETA, 0), (GAMMA, 1), (DELTA, 0)] {
+        for i in (parity..n).step_by(2) {
+            let i = i as isize;
+            y[i as usize] += coefficient * (y[reflect(i - 1, n)] + y[reflect(i + 1, n)]);
+        }
+    }
+    for (i, v) in y.iter_mut().enumerate() {
+        if i % 2 == 0 {
+            *v /= K;
+        } else {
+            *v *= K;
+        }
+    }
+    deinterleave(&y, x);
+}
+
+/// Move the even samples of `src` to the first half of `dst`
+/// and the odd samples to the second half.
+fn deinterleave<T: Copy>(src: &[T], dst: &mut [T]) {
+    let low = src.len().div_ceil(2);
+    for (i, v) in src.iter().enumerate() {
+        dst[if i % 2 == 0 { i / 2 } else { low + i / 2 }] = *v;
+    }
+}
+
+// --- subbands and quantization ---
+
+/// Subband orientation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Orientation {
+    LL,
+    HL,
+    LH,
+    HH,
+}
+
+impl Orientation {
+    /// The base 2 logarithm of the nominal gain of the subband
+    fn gain(self) -> u32 {
+        match self {
+            Orientation::LL => 0,
+            Orientation::HL | Orientation::LH => 1,
+            Orientation::HH => 2,
+        }
+    }
+}
+
+/// A subband of the tile,
+/// as a region of the transformed coefficients
+/// along with its quantization parameters.
+#[derive(Debug, Clone)]
+struct Band {
+    orientation: Orientation,
+    x0: usize,
+    y0: usize,
+    width: usize,
+    height: usize,
+    /// quantization step size exponent
+    exponent: u32,
+    /// quantization step size mantissa
+    mantissa: u32,
+    /// quantization step size
+    step: f64,
+    /// contribution of a squared quantization error to the image distortion
+    weight: f64,
+}
+
+impl Band {
+    fn new(
+        orientation: Orientation,
+        level: u32,
+        (x0, y0, width, height): (usize, usize, usize, usize),
+        precision: u32,
+        reversible: bool,
+    ) -> Self {
+        let dynamic_range = precision + orientation.gain();
+        if reversible {
+            return Band {
+                orientation,
+                x0,
+                y0,
+                width,
+                height,
+                exponent: dynamic_range,
+                mantissa: 0,
+                step: 1.,
+                weight: 1.,
+            };
+        }
+
+        let level = level as usize;
+        let (low, high) = (NORMS_97_LOW[level], NORMS_97_HIGH[level]);
+        let norm = match orientation {
+            Orientation::LL => low * low,
+            Orientation::HL | Orientation::LH => low * high,
+            Orientation::HH => high * high,
+        };
+        // equalize the contribution of each band to the image distortion
+        let step = 1. / norm;
+        let mut log = step.log2().floor() as i32;
+        let mut mantissa = ((step / 2_f64.powi(log) - 1.) * 2048.).round() as u32;
+        if mantissa >= 2048 {
+            mantissa = 0;
+            log += 1;
+        }
+        let exponent = (dynamic_range as i32 - log).clamp(0, 31) as u32;
+        let step =
+            2_f64.powi(dynamic_range as i32 - exponent as i32) * (1. + mantissa as f64 / 2048.);
+        Band {
+            orientation,
+            x0,
+            y0,
+            width,
+            height,
+            exponent,
+            mantissa,
+            step,
+            weight: (norm * step).powi(2),
+        }
+    }
+}
+
+/// Lay out the subbands of the tile, grouped by resolution level.
+fn band_layout(
+    width: usize,
+    height: usize,
+    levels: u32,
+    precision: u32,
+    reversible: bool,
+) -> Vec<Vec<Band>> {
+    let low_size = |level: u32| (width.div_ceil(1 << level), height.div_ceil(1 << level));
+
+    let (w, h) = low_size(levels);
+    let mut resolutions = vec![vec![Band::new(
+        Orientation::LL,
+        levels,
+        (0, 0, w, h),
+        precision,
+        reversible,
+    )]];
+    for level in (1..=levels).rev() {
+        let (pw, ph) = low_size(level - 1);
+        let (lw, lh) = low_size(level);
+        resolutions.push(vec![
+            Band::new(
+                Orientation::HL,
+                level,
+                (lw, 0, pw - lw, lh),
+                precision,
+                reversible,
+            ),
+            Band::new(
+                Orientation::LH,
+                level,
+                (0, lh, lw, ph - lh),
+                precision,
+                reversible,
+            ),
+            Band::new(
+                Orientation::HH,
+                level,
+                (lw, lh, pw - lw, ph - lh),
+                precision,
+                reversible,
+            ),
+        ]);
+    }
+    resolutions
+}
+
+// --- tier-1 coding ---
+
+/// A subband after entropy coding
+#[derive(Debug)]
+struct EncodedBand {
+    /// number of code-blocks in each row
+    blocks_wide: usize,
+    /// number of code-blocks in each column
+    blocks_high: usize,
+    /// the code-blocks in raster order
+    blocks: Vec<EncodedBlock>,
+}
+
+/// An entropy coded code-block
+#[derive(Debug)]
+struct EncodedBlock {
+    data: Vec<u8>,
+    passes: Vec<CodingPass>,
+    /// the number of missing most significant bit-planes
+    zero_bitplanes: u32,
+    /// the number of coding passes to place in the codestream
+    included_passes: usize,
+}
+
+impl EncodedBlock {
+    /// The number of bytes of the included coding passes
+    fn included_len(&self) -> usize {
+        match self.included_passes {
+            0 => 0,
+            n => self.passes[n - 1].rate,
+        }
+    }
+}
+
+/// The truncation point at the end of a coding pass
+#[derive(Debug, Copy, Clone)]
+struct CodingPass {
+    /// the number of bytes needed to decode up to this pass
+    rate: usize,
+    /// the total reduction of distortion up to this pass
+    distortion: f64,
+}
+
+/// Partition a subband into code-blocks and encode each of them.
+fn encode_band(band: &Band, coefficients: &[f64], width: usize, guard_bits: u32) -> EncodedBand {
+    let blocks_wide = band.width.div_ceil(CODE_BLOCK_SIZE);
+    let blocks_high = band.height.div_ceil(CODE_BLOCK_SIZE);
+    let bitplanes = guard_bits + band.exponent - 1;
+    let mut blocks = Vec::with_capacity(blocks_wide * blocks_high);
+    for by in 0..blocks_high {
+        for bx in 0..blocks_wide {
+            let x0 = bx * CODE_BLOCK_SIZE;
+            let y0 = by * CODE_BLOCK_SIZE;
+            let w = CODE_BLOCK_SIZE.min(band.width - x0);
+            let h = CODE_BLOCK_SIZE.min(band.height - y0);
+
+            let mut values = Vec::with_capacity(w * h);
+            for y in 0..h {
+                let row = (band.y0 + y0 + y) * width + band.x0 + x0;
+                values.extend(coefficients[row..row + w].iter().map(|c| c / band.step));
+            }
+            let mut block = BlockCoder::new(w, h, band.orientation, &values).encode();
+            block.zero_bitplanes = bitplanes.saturating_sub(block.zero_bitplanes);
+            for pass in &mut block.passes {
+                pass.distortion *= band.weight;
+            }
+            blocks.push(block);
+        }
+    }
+    EncodedBand {
+        blocks_wide,
+        blocks_high,
+        blocks,
+    }
+}
+
+/// coefficient is significant
+const SIG: u8 = 1;
+/// coefficient was visited in the significance propagation pass
+const VISIT: u8 = 2;
+/// coefficient has been refined before
+const REFINED: u8 = 4;
+/// coefficient is negative
+const NEG: u8 = 8;
+
+/// first sign coding context
+const CTX_SC: usize = 9;
+/// first magnitude refinement context
+const CTX_MR: usize = 14;
+/// run-length context
+const CTX_RL: usize = 17;
+/// uniform context
+const CTX_UNI: usize = 18;
+
+/// Bit-plane coder for a single code-block
+struct BlockCoder<'a> {
+    width: usize,
+    height: usize,
+    orientation: Orientation,
+    /// signed coefficient values in units of the quantization step
+    values: &'a [f64],
+    /// quantized magnitudes
+    magnitudes: Vec<u32>,
+    /// coefficient state, with a border of one coefficient
+    flags: Vec<u8>,
+    /// current reconstructed magnitudes
+    reconstructed: Vec<f64>,
+    /// reduction of distortion so far
+    distortion: f64,
+    mq: MqEncoder,
+}
+
+impl<'a> BlockCoder<'a> {
+    fn new(width: usize, height: usize, orientation: Orientation, values: &'a [f64]) -> Self {
+        BlockCoder {
+            width,
+            height,
+            orientation,
+            values,
+            magnitudes: values.iter().map(|v| v.abs() as u32).collect(),
+            flags: vec![0; (width + 2) * (height + 2)],
+            reconstructed: vec![0.; width * height],
+            distortion: 0.,
+            mq: MqEncoder::new(),
+        }
+    }
+
+    /// Encode all bit-planes of the code-block.
+    ///
+    /// The number of bit-planes coded is returned in `zero_bitplanes`,
+    /// to be adjusted by the caller.
+    fn encode(mut self) -> EncodedBlock {
+        let max_magnitude = self.magnitudes.iter().copied().max().unwrap_or(0);
+        let bitplanes = u32::BITS - max_magnitude.leading_zeros();
+        let mut passes = Vec::new();
+        for plane in (0..bitplanes).rev() {
+            if plane + 1 != bitplanes {
+                self.significance_pass(plane);
+                passes.push(self.truncation_point());
+                self.refinement_pass(plane);
+                passes.push(self.truncation_point());
+            }
+            self.cleanup_pass(plane);
+            passes.push(self.truncation_point());
+        }
+        if passes.is_empty() {
+            return EncodedBlock {
+                data: Vec::new(),
+                passes,
+                zero_bitplanes: 0,
+                included_passes: 0,
+            };
+        }
+
+        let data = self.mq.flush();
+        let mut previous = 0;
+        for pass in &mut passes {
+            pass.rate = pass.rate.min(data.len()).max(previous);
+            // avoid ending a truncated codeword with 0xFF
+            if pass.rate > previous.max(1) && data[pass.rate - 1] == 0xFF {
+                pass.rate -= 1;
+            }
+            previous = pass.rate;
+        }
+        if let Some(pass) = passes.last_mut() {
+            pass.rate = data.len();
+        }
+        EncodedBlock {
+            data,
+            passes,
+            zero_bitplanes: bitplanes,
+            included_passes: 0,
+        }
+    }
+
+    fn truncation_point(&self) -> CodingPass {
+        CodingPass {
+            rate: self.mq.num_bytes() + 3,
+            distortion: self.distortion,
+        }
+    }
+
+    #[inline]
+    fn flag_index(&self, x: usize, y: usize) -> usize {
+        (y + 1) * (self.width + 2) + x + 1
+    }
+
+    /// Count the significant horizontal, vertical and diagonal neighbors.
+    #[inline]
+    fn neighbors(&self, i: usize) -> (u32, u32, u32) {
+        let stride = self.width + 2;
+        let sig = |j: usize| u32::from(self.flags[j] & SIG);
+        (
+            sig(i - 1) + sig(i + 1),
+            sig(i - stride) + sig(i + stride),
+            sig(i - stride - 1) + sig(i - stride + 1) + sig(i + stride - 1) + sig(i + stride + 1),
+        )
+    }
+
+    /// Get the zero coding context of a coefficient.
+    fn zero_context(&self, i: usize) -> usize {
+        let (h, v, d) = self.neighbors(i);
+        match self.orientation {
+            Orientation::HH => match (d, h + v) {
+                (0, 0) => 0,
+                (0, 1) => 1,
+                (0, _) => 2,
+                (1, 0) => 3,
+                (1, 1) => 4,
+                (1, _) => 5,
+                (2, 0) => 6,
+                (2, _) => 7,
+                _ => 8,
+            },
+            orientation => {
+                let (h, v) = if orientation == Orientation::HL {
+                    (v, h)
+                } else {
+                    (h, v)
+                };
+                match (h, v, d) {
+                    (0, 0, 0) => 0,
+                    (0, 0, 1) => 1,
+                    (0, 0, _) => 2,
+                    (0, 1, _) => 3,
+                    (0, _, _) => 4,
+                    (1, 0, 0) => 5,
+                    (1, 0, _) => 6,
+                    (1, _, _) => 7,
+                    _ => 8,
+                }
+            }
+        }
+    }
+
+    /// Encode the sign of a coefficient which just became significant.
+    fn encode_sign(&mut self, i: usize, k: usize, plane: u32) {
+        let stride = self.width + 2;
+        let contribution = |j: usize| match self.flags[j] & (SIG | NEG) {
+            0 => 0,
+            SIG => 1,
+            _ => -1,
+        };
+        let h = (contribution(i - 1) + contribution(i + 1)).clamp(-1, 1);
+        let v = (contribution(i - stride) + contribution(i + stride)).clamp(-1, 1);
+        let (context, xor) = match (h, v) {
+            (1, 1) => (4, 0),
+            (1, 0) => (3, 0),
+            (1, -1) => (2, 0),
+            (0, 1) => (1, 0),
+            (0, 0) => (0, 0),
+            (0, -1) => (1, 1),
+            (-1, 1) => (2, 1),
+            (-1, 0) => (3, 1),
+            _ => (4, 1),
+        };
+        let negative = self.values[k] < 0.;
+        self.mq.encode(u32::from(negative) ^ xor, CTX_SC + context);
+        self.flags[i] |= if negative { SIG | NEG } else { SIG };
+        self.reconstruct(k, 1.5 * f64::from(1 << plane));
+    }
+
+    /// Update the reconstructed magnitude of a coefficient
+    /// and the resulting distortion.
+    fn reconstruct(&mut self, k: usize, magnitude: f64) {
+        let value = self.values[k].abs();
+        let previous = self.reconstructed[k];
+        self.distortion += (value - previous).powi(2) - (value - magnitude).powi(2);
+        self.reconstructed[k] = magnitude;
+    }
+
+    /// Iterate over the coefficient positions of the code-block in stripe order.
+    fn stripe_order(width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+        (0..height).step_by(4).flat_map(move |y0| {
+            (0..width).flat_map(move |x| (y0..height.min(y0 + 4)).map(move |y| (x, y)))
+        })
+    }
+
+    fn significance_pass(&mut self, plane: u32) {
+        for (x, y) in Self::stripe_order(self.width, self.height) {
+            let i = self.flag_index(x, y);
+            if self.flags[i] & SIG != 0 {
+                continue;
+            }
+            let context = self.zero_context(i);
+            if context == 0 {
+                continue;
+            }
+            let k = y * self.width + x;
+            let bit = (self.magnitudes[k] >> plane) & 1;
+            self.mq.encode(bit, context);
+            if bit == 1 {
+                self.encode_sign(i, k, plane);
+            }
+            self.flags[i] |= VISIT;
+        }
+    }
+
+    fn refinement_pass(&mut self, plane: u32) {
+        for (x, y) in Self::stripe_order(self.width, self.height) {
+            let i = self.flag_index(x, y);
+            if self.flags[i] & (SIG | VISIT) != SIG {
+                continue;
+            }
+            let context = if self.flags[i] & REFINED != 0 {
+                CTX_MR + 2
+            } else {
+                let (h, v, d) = self.neighbors(i);
+                if h + v + d == 0 { CTX_MR } else { CTX_MR + 1 }
+            };
+            let k = y * self.width + x;
+            let bit = (self.magnitudes[k] >> plane) & 1;
+            self.mq.encode(bit, context);
+            self.flags[i] |= REFINED;
+            let magnitude = ((self.magnitudes[k] >> plane) << plane) as f64;
+            self.reconstruct(k, magnitude + f64::from(1 << plane) / 2.);
+        }
+    }
+
+    fn cleanup_pass(&mut self, plane: u32) {
+        for y0 in (0..self.height).step_by(4) {
+            let y1 = self.height.min(y0 + 4);
+            for x in 0..self.width {
+                let mut start = y0;
+                let run_mode = y1 - y0 == 4
+                    && (y0..y1).all(|y| {
+                        let i = self.flag_index(x, y);
+                        self.flags[i] & (SIG | VISIT) == 0 && self.zero_context(i) == 0
+                    });
+                if run_mode {
+                    let run = (y0..y1)
+                        .position(|y| (self.magnitudes[y * self.width + x] >> plane) & 1 == 1);
+                    let Some(run) = run else {
+                        self.mq.encode(0, CTX_RL);
+                        continue;
+                    };
+                    self.mq.encode(1, CTX_RL);
+                    self.mq.encode((run >> 1) as u32, CTX_UNI);
+                    self.mq.encode((run & 1) as u32, CTX_UNI);
+                    let y = y0 + run;
+                    let i = self.flag_index(x, y);
+                    self.encode_sign(i, y * self.width + x, plane);
+                    start = y + 1;
+                }
+                for y in start..y1 {
+                    let i = self.flag_index(x, y);
+                    if self.flags[i] & (SIG | VISIT) == 0 {
+                        let k = y * self.width + x;
+                        let bit = (self.magnitudes[k] >> plane) & 1;
+                        self.mq.encode(bit, self.zero_context(i));
+                        if bit == 1 {
+                            self.encode_sign(i, k, plane);
+                        }
+                    }
+                    self.flags[i] &= !VISIT;
+                }
+            }
+        }
+    }
+}
+
+/// Probability estimation table of the MQ coder:
+/// (Qe, next state on MPS, next state on LPS, MPS switch)
+#[rustfmt::skip]
+const MQ_STATES: [(u32, u8, u8, bool); 47] = [
+    (0x5601, 1, 1, true), (0x3401, 2, 6, false), (0x1801, 3, 9, false),
+    (0x0AC1, 4, 12, false), (0x0521, 5, 29, false), (0x0221, 38, 33, false),
+    (0x5601, 7, 6, true), (0x5401, 8, 14, false), (0x4801, 9, 14, false),
+    (0x3801, 10, 14, false), (0x3001, 11, 17, false), (0x2401, 12, 18, false),
+    (0x1C01, 13, 20, false), (0x1601, 29, 21, false), (0x5601, 15, 14, true),
+    (0x5401, 16, 14, false), (0x5101, 17, 15, false), (0x4801, 18, 16, false),
+    (0x3801, 19, 17, false), (0x3401, 20, 18, false), (0x3001, 21, 19, false),
+    (0x2801, 22, 19, false), (0x2401, 23, 20, false), (0x2201, 24, 21, false),
+    (0x1C01, 25, 22, false), (0x1801, 26, 23, false), (0x1601, 27, 24, false),
+    (0x1401, 28, 25, false), (0x1201, 29, 26, false), (0x1101, 30, 27, false),
+    (0x0AC1, 31, 28, false), (0x09C1, 32, 29, false), (0x08A1, 33, 30, false),
+    (0x0521, 34, 31, false), (0x0441, 35, 32, false), (0x02A1, 36, 33, false),
+    (0x0221, 37, 34, false), (0x0141, 38, 35, false), (0x0111, 39, 36, false),
+    (0x0085, 40, 37, false), (0x0049, 41, 38, false), (0x0025, 42, 39, false),
+    (0x0015, 43, 40, false), (0x0009, 44, 41, false), (0x0005, 45, 42, false),
+    (0x0001, 45, 43, false), (0x5601, 46, 46, false),
+];
+
+/// MQ arithmetic encoder with the contexts used in EBCOT
+#[derive(Debug)]
+struct MqEncoder {
+    a: u32,
+    c: u32,
+    ct: u32,
+    /// output bytes, starting with a placeholder byte
+    /// and ending with the byte currently being built
+    buf: Vec<u8>,
+    states: [u8; 19],
+    mps: [u8; 19],
+}
+
+impl MqEncoder {
+    fn new() -> Self {
+        let mut states = [0; 19];
+        states[0] = 4;
+        states[CTX_RL] = 3;
+        states[CTX_UNI] = 46;
+        MqEncoder {
+            a: 0x8000,
+            c: 0,
+            ct: 12,
+            buf: vec![0],
+            states,
+            mps: [0; 19],
+        }
+    }
+
+    /// The number of complete bytes produced so far
+    fn num_bytes(&self) -> usize {
+        self.buf.len().saturating_sub(2)
+    }
+
+    fn encode(&mut self, d: u32, context: usize) {
+        let state = self.states[context] as usize;
+        let (qe, nmps, nlps, switch) = MQ_STATES[state];
+        self.a -= qe;
+        if d == u32::from(self.mps[context]) {
+            if self.a & 0x8000 != 0 {
+                self.c += qe;
+                return;
+            }
+            if self.a < qe {
+                self.a = qe;
+            } else {
+                self.c += qe;
+            }
+            self.states[context] = nmps;
+        } else {
+            if self.a < qe {
+                self.c += qe;
+            } else {
+                self.a = qe;
+            }
+            if switch {
+                self.mps[context] ^= 1;
+            }
+            self.states[context] = nlps;
+        }
+        // renormalize
+        loop {
+            self.a <<= 1;
+            self.c <<= 1;
+            self.ct -= 1;
+            if self.ct == 0 {
+                self.byte_out();
+            }
+            if self.a & 0x8000 != 0 {
+                break;
+            }
+        }
+    }
+
+    fn byte_out(&mut self) {
+        let last = self.buf.len() - 1;
+        if self.buf[last] != 0xFF && self.c & 0x800_0000 != 0 {
+            // propagate carry
+            self.buf[last] += 1;
+            self.c &= 0x7FF_FFFF;
+        }
+        if self.buf[last] == 0xFF {
+            // bit stuffing
+            self.buf.push((self.c >> 20) as u8);
+            self.c &= 0xF_FFFF;
+            self.ct = 7;
+        } else {
+            self.buf.push((self.c >> 19) as u8);
+            self.c &= 0x7_FFFF;
+            self.ct = 8;
+        }
+    }
+
+    /// Terminate the codeword and retrieve the encoded bytes.
+    fn flush(mut self) -> Vec<u8> {
+        let temp = self.c + self.a;
+        self.c |= 0xFFFF;
+        if self.c >= temp {
+            self.c -= 0x8000;
+        }
+        self.c <<= self.ct;
+        self.byte_out();
+        self.c <<= self.ct;
+        self.byte_out();
+        if self.buf.last() == Some(&0xFF) {
+            self.buf.pop();
+        }
+        self.buf.remove(0);
+        self.buf
+    }
+}
+
+// --- rate allocation ---
+
+/// Determine the truncation points of a code-block
+/// lying on the convex hull of its rate-distortion curve,
+/// as pairs of the pass count and the distortion-rate slope.
+fn convex_hull(passes: &[CodingPass]) -> Vec<(usize, f64)> {
+    let point = |n: usize| match n {
+        0 => (0, 0.),
+        n => (passes[n - 1].rate, passes[n - 1].distortion),
+    };
+    let slope = |a: usize, b: usize| {
+        let ((ra, da), (rb, db)) = (point(a), point(b));
+        if rb <= ra {
+            f64::INFINITY
+        } else {
+            (db - da) / (rb - ra) as f64
+        }
+    };
+
+    let mut hull = vec![0];
+    for n in 1..=passes.len() {
+        if point(n).1 <= point(hull[hull.len() - 1]).1 {
+            continue;
+        }
+        while hull.len() > 1 {
+            let last = hull[hull.len() - 1];
+            let previous = hull[hull.len() - 2];
+            if slope(last, n) >= slope(previous, last) {
+                hull.pop();
+            } else {
+                break;
+            }
+        }
+        hull.push(n);
+    }
+    hull.windows(2).map(|w| (w[1], slope(w[0], w[1]))).collect()
+}
+
+/// Choose the number of coding passes of each code-block
+/// minimizing distortion for the given number of bytes.
+fn allocate(tile: &mut [Vec<Vec<EncodedBand>>], hulls: &[Vec<(usize, f64)>], budget: usize) {
+    let passes_for = |hull: &[(usize, f64)], threshold: f64| {
+        hull.iter()
+            .take_while(|(_, slope)| *slope >= threshold)
+            .last()
+            .map(|(n, _)| *n)
+            .unwrap_or(0)
+    };
+    let blocks = || tile.iter().flatten().flatten().flat_map(|b| &b.blocks);
+    let total_len = |threshold: f64| {
+        blocks()
+            .zip(hulls)
+            .map(|(block, hull)| match passes_for(hull, threshold) {
+                0 => 0,
+                n => block.passes[n - 1].rate,
+            })
+            .sum::<usize>()
+    };
+
+    let threshold = if total_len(0.) <= budget {
+        0.
+    } else {
+        let mut low = 0.;
+        let mut high = hulls
+            .iter()
+            .flatten()
+            .map(|(_, slope)| *slope)
+            .filter(|slope| slope.is_finite())
+            .fold(0., f64::max)
+            * 2.
+            + 1.;
+        for _ in 0..64 {
+            let mid = (low + high) / 2.;
+            if total_len(mid) <= budget {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        high
+    };
+
+    for (block, hull) in tile
+        .iter_mut()
+        .flatten()
+        .flatten()
+        .flat_map(|b| &mut b.blocks)
+        .zip(hulls)
+    {
+        block.included_passes = passes_for(hull, threshold);
+    }
+}
+
+// --- tier-2 coding ---
+
+/// Write all packets of the tile in LRCP progression order.
+fn write_packets(tile: &[Vec<Vec<EncodedBand>>], levels: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    for resolution in 0..=levels as usize {
+        for component in tile {
+            write_packet(&component[resolution], &mut out);
+        }
+    }
+    out
+}
+
+/// Write the packet of a single layer, resolution, component and precinct.
+fn write_packet(bands: &[EncodedBand], out: &mut Vec<u8>) {
+    let mut header = BitWriter::new();
+    let non_empty = bands
+        .iter()
+        .flat_map(|band| &band.blocks)
+        .any(|block| block.included_passes > 0);
+    header.put_bit(u32::from(non_empty));
+    if !non_empty {
+        out.extend(header.flush());
+        return;
+    }
+
+    for band in bands {
+        if band.blocks.is_empty() {
+            continue;
+        }
+        let mut inclusion = TagTree::new(band.blocks_wide, band.blocks_high);
+        let mut zero_bitplanes = TagTree::new(band.blocks_wide, band.blocks_high);
+        for (i, block) in band.blocks.iter().enumerate() {
+            inclusion.set_value(i, if block.included_passes > 0 { 0 } else { 1 });
+            zero_bitplanes.set_value(i, block.zero_bitplanes);
+        }
+        inclusion.update();
+        zero_bitplanes.update();
+
+        for (i, block) in band.blocks.iter().enumerate() {
+            inclusion.encode(&mut header, i, 1);
+            if block.included_passes == 0 {
+                continue;
+            }
+            zero_bitplanes.encode(&mut header, i, u32::MAX);
+            put_num_passes(&mut header, block.included_passes as u32);
+
+            // code-block length, starting from the initial Lblock of 3
+            let len = block.included_len() as u32;
+            let len_bits = u32::BITS - len.leading_zeros();
+            let pass_bits = floor_log2(block.included_passes as u32);
+            let increment = len_bits.saturating_sub(3 + pass_bits);
+            for _ in 0..increment {
+                header.put_bit(1);
+            }
+            header.put_bit(0);
+            header.write(len, 3 + increment + pass_bits);
+        }
+    }
+    out.extend(header.flush());
+
+    for block in bands.iter().flat_map(|band| &band.blocks) {
+        out.extend_from_slice(&block.data[..block.included_len()]);
+    }
+}
+
+fn floor_log2(n: u32) -> u32 {
+    u32::BITS - 1 - n.leading_zeros()
+}
+
+/// Write the codeword for the number of coding passes.
+fn put_num_passes(header: &mut BitWriter, n: u32) {
+    match n {
+        1 => header.write(0, 1),
+        2 => header.write(0b10, 2),
+        3..=5 => header.write(0b1100 | (n - 3), 4),
+        6..=36 => header.write(0x1E0 | (n - 6), 9),
+        _ => header.write(0xFF80 | (n - 37), 16),
+    }
+}
+
+/// Bit writer for packet headers, with bit stuffing after 0xFF bytes
+#[derive(Debug)]
+struct BitWriter {
+    buf: u32,
+    ct: u32,
+    out: Vec<u8>,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            buf: 0,
+            ct: 8,
+            out: Vec::new(),
+        }
+    }
+
+    fn byte_out(&mut self) {
+        self.buf = (self.buf << 8) & 0xFFFF;
+        self.ct = if self.buf == 0xFF00 { 7 } else { 8 };
+        self.out.push((self.buf >> 8) as u8);
+    }
+
+    fn put_bit(&mut self, bit: u32) {
+        if self.ct == 0 {
+            self.byte_out();
+        }
+        self.ct -= 1;
+        self.buf |= bit << self.ct;
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            self.put_bit((value >> i) & 1);
+        }
+    }
+
+    fn flush(mut self) -> Vec<u8> {
+        self.byte_out();
+        if self.ct == 7 {
+            self.byte_out();
+        }
+        self.out
+    }
+}
+
+/// A tag tree node
+#[derive(Debug, Clone)]
+struct TagNode {
+    parent: Option<usize>,
+    value: u32,
+    low: u32,
+    known: bool,
+}
+
+/// Tag tree for coding code-block inclusion and zero bit-planes
+#[derive(Debug)]
+struct TagTree {
+    nodes: Vec<TagNode>,
+    num_leaves: usize,
+}
+
+impl TagTree {
+    fn new(width: usize, height: usize) -> Self {
+        let mut nodes = Vec::new();
+        let (mut w, mut h) = (width, height);
+        let mut level_start = 0;
+        loop {
+            let next_start = level_start + w * h;
+            let is_root = w == 1 && h == 1;
+            let next_w = w.div_ceil(2);
+            for y in 0..h {
+                for x in 0..w {
+                    nodes.push(TagNode {
+                        parent: (!is_root).then(|| next_start + (y / 2) * next_w + x / 2),
+                        value: u32::MAX,
+                        low: 0,
+                        known: false,
+                    });
+                }
+            }
+            if is_root {
+                break;
+            }
+            level_start = next_start;
+            w = next_w;
+            h = h.div_ceil(2);
+        }
+        TagTree {
+            nodes,
+            num_leaves: width * height,
+        }
+    }
+
+    fn set_value(&mut self, leaf: usize, value: u32) {
+        self.nodes[leaf].value = value;
+    }
+
+    /// Propagate the minimum leaf values to the parent nodes.
+    fn update(&mut self) {
+        for i in self.num_leaves..self.nodes.len() {
+            self.nodes[i].value = u32::MAX;
+        }
+        for i in 0..self.nodes.len() {
+            if let Some(parent) = self.nodes[i].parent {
+                self.nodes[parent].value = self.nodes[parent].value.min(self.nodes[i].value);
+            }
+        }
+    }
+
+    /// Encode the value of a leaf up to the given threshold.
+    fn encode(&mut self, writer: &mut BitWriter, leaf: usize, threshold: u32) {
+        let mut path = vec![leaf];
+        while let Some(parent) = self.nodes[path[path.len() - 1]].parent {
+            path.push(parent);
+        }
+        let mut low = 0;
+        for &i in path.iter().rev() {
+            let node = &mut self.nodes[i];
+            if low > node.low {
+                node.low = low;
+            } else {
+                low = node.low;
+            }
+            while low < threshold {
+                if low >= node.value {
+                    if !node.known {
+                        writer.put_bit(1);
+                        node.known = true;
+                    }
+                    break;
+                }
+                writer.put_bit(0);
+                low += 1;
+            }
+            node.low = low;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mq_encoder_reference_sequence() {
+        // test sequence from ITU-T T.88 Annex H.2
+        let input: [u8; 32] = [
+            0x00, 0x02, 0x00, 0x51, 0x00, 0x00, 0x00, 0xC0, 0x03, 0x52, 0x87, 0x2A, 0xAA, 0xAA,
+            0xAA, 0xAA, 0x82, 0xC0, 0x20, 0x00, 0xFC, 0xD7, 0x9E, 0xF6, 0xBF, 0x7F, 0xED, 0x90,
+            0x4F, 0x46, 0xA3, 0xBF,
+        ];
+        let expected: [u8; 28] = [
+            0x84, 0xC7, 0x3B, 0xFC, 0xE1, 0xA1, 0x43, 0x04, 0x02, 0x20, 0x00, 0x00, 0x41, 0x0D,
+            0xBB, 0x86, 0xF4, 0x31, 0x7F, 0xFF, 0x88, 0xFF, 0x37, 0x47, 0x1A, 0xDB, 0x6A, 0xDF,
+        ];
+
+        let mut mq = MqEncoder::new();
+        mq.states[0] = 0;
+        for byte in input {
+            for i in (0..8).rev() {
+                mq.encode(u32::from(byte >> i) & 1, 0);
+            }
+        }
+        let data = mq.flush();
+        assert_eq!(&data[..expected.len()], &expected[..]);
+    }
+
+    #[test]
+    fn dwt_53_preserves_dc() {
+        let mut data = vec![7; 13 * 9];
+        forward_dwt_53(&mut data, 13, 9, 2);
+        // the low-pass band retains the value, other bands are zero
+        let (lw, lh) = (13_usize.div_ceil(4), 9_usize.div_ceil(4));
+        for y in 0..9 {
+            for x in 0..13 {
+                let expected = if x < lw && y < lh { 7 } else { 0 };
+                assert_eq!(data[y * 13 + x], expected, "at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn band_layout_covers_image() {
+        for (width, height) in [(1, 1), (7, 5), (64, 64), (517, 300)] {
+            let levels = decomposition_levels(width, height);
+            let resolutions = band_layout(width, height, levels, 12, true);
+            assert_eq!(resolutions.len(), levels as usize + 1);
+            let area: usize = resolutions
+                .iter()
+                .flatten()
+                .map(|band| band.width * band.height)
+                .sum();
+            assert_eq!(area, width * height);
+        }
+    }
+
+    #[test]
+    fn tag_tree_encoding() {
+        let values = [
+            1, 3, 2, 3, 2, 3, 2, 2, 1, 4, 3, 2, 2, 2, 2, 1, 2, 3, 3, 2, 2, 2, 2, 2,
+        ];
+        let mut tree = TagTree::new(6, 4);
+        for (i, v) in values.iter().enumerate() {
+            tree.set_value(i, *v);
+        }
+        tree.update();
+        let mut writer = BitWriter::new();
+        // 01111 for the first leaf (value 1 at every level),
+        // then 001 for the second leaf (value 3, ancestors already known)
+        tree.encode(&mut writer, 0, u32::MAX);
+        tree.encode(&mut writer, 1, u32::MAX);
+        assert_eq!(writer.flush(), vec![0b0111_1001]);
+    }
+
+    #[test]
+    fn lossy_codestream_meets_target() {
+        let (width, height) = (96, 80);
+        let samples: Vec<u16> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                (((x * 37 + y * 11) % 4096) ^ ((x * y) % 512)) as u16
+            })
+            .collect();
+        let full_size = width * height * 2;
+        let target = full_size / 10;
+        let codestream = encode_codestream(&[samples], width, height, 12, Some(target)).unwrap();
+        assert!(codestream.len() <= target);
+        // more than just the headers
+        assert!(codestream.len() > target / 2);
+        assert_eq!(&codestream[..2], &[0xFF, 0x4F]);
+        assert_eq!(&codestream[codestream.len() - 2..], &[0xFF, 0xD9]);
+    }
+}
@@ -67,24 +67,12 @@ impl PixelDataReader for JpegXlAdapter {
         let stride: usize = bytes_per_sample as usize * cols as usize * rows as usize;
         dst.reserve_exact(samples_per_pixel as usize * stride);
 
-        let raw = src
-            .raw_pixel_data()
-            .whatever_context("Expected to have raw pixel data available")?;
-
-        ensure_whatever!(
-            raw.fragments.len() == nr_frames,
-            "Unexpected number of fragments"
-        );
-        let frame_data =
-            // assuming 1:1 frame-to-fragment mapping
-            raw.fragments
-                .get(frame as usize)
-                .with_whatever_context(|| {
-                    format!("Missing fragment #{frame} for the frame requested")
-                })?;
+        let frame_data = src
+            .frame_pixel_data(frame)
+            .context(decode_error::FrameBoundariesSnafu)?;
 
         let image = JxlImage::builder()
-            .read(&**frame_data)
+            .read(&*frame_data)
             .whatever_context("failed to read JPEG XL data")?;
         let frame = image
             .render_frame(0)
@@ -16,11 +16,14 @@
 //!   Alternatively, feature `openjp2` provides native JPEG 2000 decoding
 //!   via the [Rust port of OpenJPEG][OpenJPEG-rs],
 //!   which is maintained separately.
+//! - [`jpeg2k_encoder`] provides native JPEG 2000 encoding
+//!   (lossless and lossy).
+//!   Requires the `jpeg2k-encoder` feature.
 //! - [`jpegxl`] contains JPEG XL support.
 //!   `jxl-oxide` enables decoding via [jxl-oxide],
 //!   and `zune-jpegxl` adds lossless encoding via [zune-jpegxl].
 //!   Currently, the `jpegxl` feature enables both.
-//! - [`rle_lossless`] provides native RLE lossless decoding.
+//! - [`rle_lossless`] provides native RLE lossless decoding and encoding.
 //!   Requires the `rle` feature,
 //!   enabled by default.
 //!
@@ -34,6 +37,8 @@ pub mod deflated;
 pub mod jpeg;
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
 pub mod jpeg2k;
+#[cfg(feature = "jpeg2k-encoder")]
+pub mod jpeg2k_encoder;
 #[cfg(feature = "charls")]
 pub mod jpegls;
 #[cfg(any(feature = "jxl-oxide", feature = "zune-jpegxl"))]
@@ -53,6 +58,11 @@ pub mod jpeg {}
 #[cfg(not(any(feature = "openjp2", feature = "openjpeg-sys")))]
 pub mod jpeg2k {}
 
+/// **Note:** This module is a stub.
+/// Enable the `jpeg2k-encoder` feature to use this module.
+#[cfg(not(feature = "jpeg2k-encoder"))]
+pub mod jpeg2k_encoder {}
+
 /// **Note:** This module is a stub.
 /// Enable the `rle` feature to use this module.
 #[cfg(not(feature = "rle"))]
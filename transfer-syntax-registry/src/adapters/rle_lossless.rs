@@ -1,4 +1,4 @@
-//! Support for RLE Lossless image decoding.
+//! Support for RLE Lossless image decoding and encoding.
 //!
 //! implementation taken from Pydicom:
 //! <https://github.com/pydicom/pydicom/blob/master/pydicom/pixel_data_handlers/rle_handler.py>
@@ -8,7 +8,12 @@
 //! License: <https://github.com/pydicom/pydicom/blob/master/LICENSE>
 use byteordered::byteorder::{ByteOrder, LittleEndian};
 
-use dicom_encoding::adapters::{DecodeResult, PixelDataObject, PixelDataReader, decode_error};
+use dicom_core::Tag;
+use dicom_core::ops::{AttributeAction, AttributeOp};
+use dicom_encoding::adapters::{
+    DecodeResult, EncodeOptions, EncodeResult, PixelDataObject, PixelDataReader, PixelDataWriter,
+    decode_error, encode_error,
+};
 use dicom_encoding::snafu::prelude::*;
 use std::io::{self, Read, Seek};
 
@@ -99,12 +104,8 @@ impl PixelDataReader for RleLosslessAdapter {
                     // MSB G channel: 5, 11, 17, ...
                     // LSB G channel: 4, 10, 16, ...
                     let frame_start = i * frame_size;
-                    let start = frame_start
-                        + if samples_per_pixel == 3 {
-                            sample_number * bytes_per_sample + byte_offset
-                        } else {
-                            sample_number * bytes_per_sample + samples_per_pixel - byte_offset
-                        };
+                    let start =
+                        frame_start + byte_position(sample_number, bytes_per_sample, byte_offset);
 
                     let end = (i + 1) * frame_size;
                     for (decoded_index, dst_index) in (start..end)
@@ -202,11 +203,7 @@ impl PixelDataReader for RleLosslessAdapter {
                     .unwrap();
 
                 // Interleave pixels as described in the example above.
-                let start = if samples_per_pixel == 3 {
-                    sample_number * bytes_per_sample + byte_offset
-                } else {
-                    sample_number * bytes_per_sample + samples_per_pixel - byte_offset
-                };
+                let start = byte_position(sample_number, bytes_per_sample, byte_offset);
 
                 let end = frame_size;
                 for (decoded_index, dst_index) in (start..end)
@@ -221,7 +218,142 @@ impl PixelDataReader for RleLosslessAdapter {
     }
 }
 
-// TODO(#125) implement `encode`
+/// Pixel data encoder for RLE Lossless (UID `1.2.840.10008.1.2.5`)
+impl PixelDataWriter for RleLosslessAdapter {
+    /// Encode a single frame of the DICOM image into RLE Lossless.
+    ///
+    /// Each sample byte plane is written as a separate segment,
+    /// in which each row is compressed independently with PackBits.
+    ///
+    /// See <https://dicom.nema.org/medical/dicom/2023e/output/chtml/part05/chapter_G.html>
+    fn encode_frame(
+        &self,
+        src: &dyn PixelDataObject,
+        frame: u32,
+        _options: EncodeOptions,
+        dst: &mut Vec<u8>,
+    ) -> EncodeResult<Vec<AttributeOp>> {
+        let cols = src
+            .cols()
+            .context(encode_error::MissingAttributeSnafu { name: "Columns" })?;
+        let rows = src
+            .rows()
+            .context(encode_error::MissingAttributeSnafu { name: "Rows" })?;
+        let samples_per_pixel =
+            src.samples_per_pixel()
+                .context(encode_error::MissingAttributeSnafu {
+                    name: "SamplesPerPixel",
+                })?;
+        let bits_allocated = src
+            .bits_allocated()
+            .context(encode_error::MissingAttributeSnafu {
+                name: "BitsAllocated",
+            })?;
+
+        ensure_whatever!(
+            bits_allocated == 8 || bits_allocated == 16,
+            "BitsAllocated other than 8 or 16 is not supported"
+        );
+
+        let bytes_per_sample = (bits_allocated / 8) as usize;
+        let samples_per_pixel = samples_per_pixel as usize;
+        // the RLE header has room for up to 15 segments
+        let nr_segments = bytes_per_sample * samples_per_pixel;
+        ensure_whatever!(
+            nr_segments <= 15,
+            "Too many samples per pixel for RLE Lossless"
+        );
+
+        let pixel_size = bytes_per_sample * samples_per_pixel;
+        let frame_size = cols as usize * rows as usize * pixel_size;
+
+        let frame_data = src
+            .frame_pixel_data(frame)
+            .context(encode_error::FrameRangeOutOfBoundsSnafu)?;
+        ensure_whatever!(
+            frame_data.len() >= frame_size,
+            "Frame pixel data is shorter than expected"
+        );
+
+        // RLE header: number of segments and offsets to each segment
+        let header_offset = dst.len();
+        dst.resize(header_offset + 64, 0);
+        LittleEndian::write_u32(&mut dst[header_offset..], nr_segments as u32);
+
+        let mut segment = Vec::with_capacity(cols as usize * rows as usize);
+        for sample_number in 0..samples_per_pixel {
+            // the most significant byte comes first,
+            // native pixel data is in little endian
+            for byte_offset in (0..bytes_per_sample).rev() {
+                let ii = sample_number * bytes_per_sample + (bytes_per_sample - 1 - byte_offset);
+                let segment_offset = (dst.len() - header_offset) as u32;
+                LittleEndian::write_u32(&mut dst[header_offset + 4 + 4 * ii..], segment_offset);
+
+                segment.clear();
+                segment.extend(
+                    frame_data[sample_number * bytes_per_sample + byte_offset..frame_size]
+                        .iter()
+                        .step_by(pixel_size),
+                );
+                for row in segment.chunks(cols as usize) {
+                    pack_bits(row, dst);
+                }
+                // segments have an even length
+                if (dst.len() - header_offset) % 2 != 0 {
+                    dst.push(0);
+                }
+            }
+        }
+
+        Ok(vec![
+            // lossless image compression
+            AttributeOp::new(
+                Tag(0x0028, 0x2110),
+                AttributeAction::SetIfMissing("00".into()),
+            ),
+        ])
+    }
+}
+
+/// Compress a sequence of bytes with PackBits,
+/// appending the outcome to `out`.
+fn pack_bits(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..]
+            .iter()
+            .take(128)
+            .take_while(|b| **b == data[i])
+            .count();
+        if run >= 2 {
+            // replicate run
+            out.push((257 - run) as u8);
+            out.push(data[i]);
+            i += run;
+        } else {
+            // literal run, until the next replicate run
+            let start = i;
+            i += 1;
+            while i < data.len() && i - start < 128 && data.get(i + 1) != Some(&data[i]) {
+                i += 1;
+            }
+            out.push((i - start - 1) as u8);
+            out.extend_from_slice(&data[start..i]);
+        }
+    }
+}
+
+/// Obtain the position of the bytes from segment `byte_offset`
+/// of the given sample within each decoded pixel.
+///
+/// Segments of a sample start with its most significant byte,
+/// whereas decoded samples are in little endian.
+/// As an example, for 16-bit RGB,
+/// the byte from segment 0 (R MSB) goes to position 1
+/// and the byte from segment 1 (R LSB) goes to position 0.
+fn byte_position(sample_number: usize, bytes_per_sample: usize, byte_offset: usize) -> usize {
+    sample_number * bytes_per_sample + (bytes_per_sample - 1 - byte_offset)
+}
 
 // Read the RLE header and return the offsets
 fn read_rle_header(fragment: &[u8]) -> Vec<u32> {
@@ -310,4 +442,25 @@ mod test {
         ];
         assert_eq!(decoded, expected);
     }
+
+    #[test]
+    fn test_packbits_roundtrip() {
+        let mut data = vec![0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0x2A, 0x01];
+        data.extend([0x55; 300]);
+        data.extend(0..=255);
+        data.push(7);
+
+        let mut encoded = Vec::new();
+        pack_bits(&data, &mut encoded);
+        assert!(encoded.len() < data.len());
+
+        let encoded_len = encoded.len();
+        let (len, mut decoder) =
+            PackBitsReader::new(io::Cursor::new(encoded), encoded_len).unwrap();
+        assert_eq!(len, data.len());
+
+        let mut decoded = Vec::new();
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
 }
@@ -27,7 +27,11 @@ use dicom_encoding::transfer_syntax::{AdapterFreeTransferSyntax as Ts, Codec};
 
 use dicom_encoding::transfer_syntax::{NeverAdapter, TransferSyntax};
 
-#[cfg(any(feature = "rle", feature = "openjp2", feature = "openjpeg-sys"))]
+#[cfg(any(
+    feature = "openjp2",
+    feature = "openjpeg-sys",
+    feature = "jpeg2k-encoder"
+))]
 use dicom_encoding::NeverPixelAdapter;
 
 #[cfg(feature = "deflate")]
@@ -36,6 +40,8 @@ use crate::adapters::deflated::DeflatedImageFrameAdapter;
 use crate::adapters::jpeg::JpegAdapter;
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
 use crate::adapters::jpeg2k::Jpeg2000Adapter;
+#[cfg(feature = "jpeg2k-encoder")]
+use crate::adapters::jpeg2k_encoder::{Jpeg2000Encoder, Jpeg2000LosslessEncoder};
 #[cfg(feature = "charls")]
 use crate::adapters::jpegls::{JpegLsAdapter, JpegLsLosslessWriter};
 #[cfg(feature = "jpegxl")]
@@ -87,11 +93,11 @@ pub const ENCAPSULATED_UNCOMPRESSED_EXPLICIT_VR_LITTLE_ENDIAN: TransferSyntax<
 
 /// **Implemented:** RLE Lossless
 #[cfg(feature = "rle")]
-pub const RLE_LOSSLESS: TransferSyntax<NeverAdapter, RleLosslessAdapter, NeverPixelAdapter> =
+pub const RLE_LOSSLESS: TransferSyntax<NeverAdapter, RleLosslessAdapter, RleLosslessAdapter> =
     TransferSyntax::new_ele(
         "1.2.840.10008.1.2.5",
         "RLE Lossless",
-        Codec::EncapsulatedPixelData(Some(RleLosslessAdapter), None),
+        Codec::EncapsulatedPixelData(Some(RleLosslessAdapter), Some(RleLosslessAdapter)),
     );
 /// **Stub:** RLE Lossless
 ///
@@ -274,25 +280,85 @@ const fn create_ts_jpeg2k(uid: &'static str, name: &'static str) -> Jpeg2000Ts {
     )
 }
 
-/// **Decoder implementation:** JPEG 2000 Image Compression (Lossless Only)
+/// The JPEG 2000 pixel data reader, if available
+#[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+const JPEG2000_READER: Option<Jpeg2000Adapter> = Some(Jpeg2000Adapter);
+/// The JPEG 2000 pixel data reader, if available
+#[cfg(all(
+    feature = "jpeg2k-encoder",
+    not(any(feature = "openjp2", feature = "openjpeg-sys"))
+))]
+const JPEG2000_READER: Option<NeverPixelAdapter> = None;
+
+/// The JPEG 2000 pixel data reader type
 #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+type Jpeg2000Reader = Jpeg2000Adapter;
+/// The JPEG 2000 pixel data reader type
+#[cfg(all(
+    feature = "jpeg2k-encoder",
+    not(any(feature = "openjp2", feature = "openjpeg-sys"))
+))]
+type Jpeg2000Reader = NeverPixelAdapter;
+
+/// **Decoder implementation:** JPEG 2000 Image Compression (Lossless Only)
+#[cfg(all(
+    any(feature = "openjp2", feature = "openjpeg-sys"),
+    not(feature = "jpeg2k-encoder")
+))]
 pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: Jpeg2000Ts = create_ts_jpeg2k(
     "1.2.840.10008.1.2.4.90",
     "JPEG 2000 Image Compression (Lossless Only)",
 );
+/// **Encoder implementation:** JPEG 2000 Image Compression (Lossless Only)
+///
+/// Decoding is also supported if either `openjp2` or `openjpeg-sys` is enabled.
+#[cfg(feature = "jpeg2k-encoder")]
+pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: TransferSyntax<
+    NeverAdapter,
+    Jpeg2000Reader,
+    Jpeg2000LosslessEncoder,
+> = TransferSyntax::new_ele(
+    "1.2.840.10008.1.2.4.90",
+    "JPEG 2000 Image Compression (Lossless Only)",
+    Codec::EncapsulatedPixelData(JPEG2000_READER, Some(Jpeg2000LosslessEncoder)),
+);
 /// **Stub descriptor:** JPEG 2000 Image Compression (Lossless Only)
-#[cfg(not(any(feature = "openjp2", feature = "openjpeg-sys")))]
+#[cfg(not(any(
+    feature = "openjp2",
+    feature = "openjpeg-sys",
+    feature = "jpeg2k-encoder"
+)))]
 pub const JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY: Ts = create_ts_stub(
     "1.2.840.10008.1.2.4.90",
     "JPEG 2000 Image Compression (Lossless Only)",
 );
 
 /// **Decoder implementation:** JPEG 2000 Image Compression
-#[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+#[cfg(all(
+    any(feature = "openjp2", feature = "openjpeg-sys"),
+    not(feature = "jpeg2k-encoder")
+))]
 pub const JPEG_2000_IMAGE_COMPRESSION: Jpeg2000Ts =
     create_ts_jpeg2k("1.2.840.10008.1.2.4.91", "JPEG 2000 Image Compression");
+/// **Encoder implementation:** JPEG 2000 Image Compression
+///
+/// Decoding is also supported if either `openjp2` or `openjpeg-sys` is enabled.
+#[cfg(feature = "jpeg2k-encoder")]
+pub const JPEG_2000_IMAGE_COMPRESSION: TransferSyntax<
+    NeverAdapter,
+    Jpeg2000Reader,
+    Jpeg2000Encoder,
+> = TransferSyntax::new_ele(
+    "1.2.840.10008.1.2.4.91",
+    "JPEG 2000 Image Compression",
+    Codec::EncapsulatedPixelData(JPEG2000_READER, Some(Jpeg2000Encoder)),
+);
 /// **Stub descriptor:** JPEG 2000 Image Compression
-#[cfg(not(any(feature = "openjp2", feature = "openjpeg-sys")))]
+#[cfg(not(any(
+    feature = "openjp2",
+    feature = "openjpeg-sys",
+    feature = "jpeg2k-encoder"
+)))]
 pub const JPEG_2000_IMAGE_COMPRESSION: Ts =
     create_ts_stub("1.2.840.10008.1.2.4.91", "JPEG 2000 Image Compression");
 
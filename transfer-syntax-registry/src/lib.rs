@@ -59,8 +59,8 @@
 //! | JPEG Lossless, Non-Hierarchical, First-Order Prediction (Process 14 [Selection Value 1]) | Cargo feature `jpeg` | x |
 //! | JPEG-LS Lossless              | Cargo feature `charls` | ✓ |
 //! | JPEG-LS Lossy (Near-Lossless) | Cargo feature `charls` | ✓ |
-//! | JPEG 2000 (Lossless Only)     | Cargo feature `openjp2` or `openjpeg-sys` | x |
-//! | JPEG 2000                     | Cargo feature `openjp2` or `openjpeg-sys` | x |
+//! | JPEG 2000 (Lossless Only)     | Cargo feature `openjp2` or `openjpeg-sys` | ✓ (Cargo feature `jpeg2k-encoder`) |
+//! | JPEG 2000                     | Cargo feature `openjp2` or `openjpeg-sys` | ✓ (Cargo feature `jpeg2k-encoder`) |
 //! | JPEG 2000 Part 2 Multi-component Image Compression (Lossless Only) | Cargo feature `openjp2` or `openjpeg-sys` | x |
 //! | JPEG 2000 Part 2 Multi-component Image Compression | Cargo feature `openjp2` or `openjpeg-sys` | x |
 //! | JPIP Referenced Deflate       | Cargo feature `deflate` | ✓ |
@@ -71,7 +71,7 @@
 //! | JPEG XL Lossless              | Cargo feature `jxl-oxide` | ✓ (Cargo feature `zune-jpegxl`) |
 //! | JPEG XL Recompression         | Cargo feature `jxl-oxide` | x |
 //! | JPEG XL                       | Cargo feature `jxl-oxide` | ✓ (Cargo feature `zune-jpegxl`) |
-//! | RLE Lossless                  | Cargo feature `rle` | x |
+//! | RLE Lossless                  | Cargo feature `rle` | ✓ |
 //! | Deflated Image Frame          | Cargo feature `deflate` | ✓ |
 //!
 //! Cargo features behind `native` (`jpeg`, `rle`, `deflate`)
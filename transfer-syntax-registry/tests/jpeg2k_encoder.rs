@@ -0,0 +1,202 @@
+//! Test suite for JPEG 2000 pixel data writing
+#![cfg(feature = "jpeg2k-encoder")]
+
+mod adapters;
+
+use adapters::TestDataObject;
+use dicom_core::{
+    Tag,
+    ops::{AttributeAction, AttributeOp},
+};
+use dicom_encoding::{
+    Codec,
+    adapters::{EncodeOptions, PixelDataWriter},
+};
+use dicom_transfer_syntax_registry::entries::{
+    JPEG_2000_IMAGE_COMPRESSION, JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY,
+};
+
+/// Create a test image with a gradient and some noise.
+fn test_image(
+    rows: u16,
+    columns: u16,
+    bits_allocated: u16,
+    bits_stored: u16,
+    samples_per_pixel: u16,
+) -> TestDataObject {
+    let max_value = (1_u32 << bits_stored) - 1;
+    let mut seed = 0xcfcf_acab_u32;
+    let mut samples = vec![];
+    for y in 0..rows as u32 {
+        for x in 0..columns as u32 {
+            for s in 0..samples_per_pixel as u32 {
+                seed = seed.wrapping_mul(4_294_967_291).wrapping_add(67291);
+                let value = ((x * 5 + y * 3 + s * 40) * (max_value / 256 + 1) + (seed >> 26))
+                    % (max_value + 1);
+                if bits_allocated == 8 {
+                    samples.push(value as u8);
+                } else {
+                    samples.extend((value as u16).to_le_bytes());
+                }
+            }
+        }
+    }
+
+    TestDataObject {
+        // Explicit VR Little Endian
+        ts_uid: "1.2.840.10008.1.2.1".to_string(),
+        rows,
+        columns,
+        bits_allocated,
+        bits_stored,
+        samples_per_pixel,
+        photometric_interpretation: if samples_per_pixel == 1 {
+            "MONOCHROME2"
+        } else {
+            "RGB"
+        },
+        number_of_frames: 1,
+        flat_pixel_data: Some(samples),
+        pixel_data_sequence: None,
+    }
+}
+
+/// Check the main markers of a JPEG 2000 codestream
+/// and the image properties declared in it.
+fn check_codestream(data: &[u8], obj: &TestDataObject, reversible: bool) {
+    // SOC, followed by SIZ
+    assert_eq!(&data[0..4], &[0xFF, 0x4F, 0xFF, 0x51]);
+    let siz = &data[4..];
+    let width = u32::from_be_bytes([siz[4], siz[5], siz[6], siz[7]]);
+    let height = u32::from_be_bytes([siz[8], siz[9], siz[10], siz[11]]);
+    assert_eq!(width, obj.columns as u32);
+    assert_eq!(height, obj.rows as u32);
+    let num_components = u16::from_be_bytes([siz[36], siz[37]]);
+    assert_eq!(num_components, obj.samples_per_pixel);
+    // unsigned samples
+    assert_eq!(siz[38] as u16, obj.bits_stored - 1);
+
+    // COD follows SIZ
+    let cod = &siz[u16::from_be_bytes([siz[0], siz[1]]) as usize..];
+    assert_eq!(&cod[0..2], &[0xFF, 0x52]);
+    // wavelet transformation
+    assert_eq!(cod[13], if reversible { 1 } else { 0 });
+
+    // ends with EOC
+    assert_eq!(&data[data.len() - 2..], &[0xFF, 0xD9]);
+}
+
+fn check_lossless_encoding(bits_allocated: u16, bits_stored: u16, samples_per_pixel: u16) {
+    let obj = test_image(100, 64, bits_allocated, bits_stored, samples_per_pixel);
+
+    assert!(JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY.can_encode());
+    let Codec::EncapsulatedPixelData(_, Some(writer)) =
+        JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY.codec()
+    else {
+        panic!("JPEG 2000 lossless pixel data writer not found")
+    };
+
+    let mut encoded = vec![];
+    let ops = writer
+        .encode_frame(&obj, 0, EncodeOptions::default(), &mut encoded)
+        .expect("JPEG 2000 frame encoding failed");
+    check_codestream(&encoded, &obj, true);
+
+    // flagged as lossless
+    assert!(ops.contains(&AttributeOp::new(
+        Tag(0x0028, 0x2110),
+        AttributeAction::SetIfMissing("00".into())
+    )));
+
+    #[cfg(any(feature = "openjp2", feature = "openjpeg-sys"))]
+    {
+        use dicom_core::value::PixelFragmentSequence;
+        use dicom_encoding::adapters::PixelDataReader;
+
+        let Codec::EncapsulatedPixelData(Some(reader), _) =
+            JPEG_2000_IMAGE_COMPRESSION_LOSSLESS_ONLY.codec()
+        else {
+            panic!("JPEG 2000 pixel data reader not found")
+        };
+
+        let samples = obj.flat_pixel_data.clone().unwrap();
+        let obj = TestDataObject {
+            ts_uid: "1.2.840.10008.1.2.4.90".to_string(),
+            flat_pixel_data: None,
+            pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![encoded])),
+            ..obj
+        };
+        let mut decoded = vec![];
+        reader
+            .decode_frame(&obj, 0, &mut decoded)
+            .expect("JPEG 2000 frame decoding failed");
+        assert_eq!(decoded, samples);
+    }
+}
+
+#[test]
+fn write_jpeg2k_lossless_8bit_monochrome() {
+    check_lossless_encoding(8, 8, 1);
+}
+
+#[test]
+fn write_jpeg2k_lossless_16bit_monochrome() {
+    check_lossless_encoding(16, 12, 1);
+}
+
+#[test]
+fn write_jpeg2k_lossless_8bit_rgb() {
+    check_lossless_encoding(8, 8, 3);
+}
+
+#[test]
+fn write_jpeg2k_lossy_with_compression_ratio() {
+    let obj = test_image(128, 128, 16, 12, 1);
+    let native_size = 128 * 128 * 2;
+
+    let Codec::EncapsulatedPixelData(_, Some(writer)) = JPEG_2000_IMAGE_COMPRESSION.codec() else {
+        panic!("JPEG 2000 pixel data writer not found")
+    };
+
+    for ratio in [4., 10., 25.] {
+        let mut options = EncodeOptions::new();
+        options.compression_ratio = Some(ratio);
+        let mut encoded = vec![];
+        let ops = writer
+            .encode_frame(&obj, 0, options, &mut encoded)
+            .expect("JPEG 2000 frame encoding failed");
+        check_codestream(&encoded, &obj, false);
+        assert!(
+            encoded.len() as f32 <= native_size as f32 / ratio,
+            "{} bytes exceeds the target for ratio {}",
+            encoded.len(),
+            ratio
+        );
+
+        // flagged as lossy, with the compression ratio
+        assert!(ops.contains(&AttributeOp::new(
+            Tag(0x0028, 0x2110),
+            AttributeAction::SetStr("01".into())
+        )));
+        assert!(
+            ops.iter()
+                .any(|op| op.selector == Tag(0x0028, 0x2112).into())
+        );
+    }
+}
+
+#[test]
+fn write_jpeg2k_quality_100_is_lossless() {
+    let obj = test_image(32, 48, 8, 8, 3);
+
+    let Codec::EncapsulatedPixelData(_, Some(writer)) = JPEG_2000_IMAGE_COMPRESSION.codec() else {
+        panic!("JPEG 2000 pixel data writer not found")
+    };
+    let mut options = EncodeOptions::new();
+    options.quality = Some(100);
+    let mut encoded = vec![];
+    writer
+        .encode_frame(&obj, 0, options, &mut encoded)
+        .expect("JPEG 2000 frame encoding failed");
+    check_codestream(&encoded, &obj, true);
+}
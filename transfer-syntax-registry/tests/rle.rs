@@ -11,7 +11,10 @@ use std::{
 
 use adapters::TestDataObject;
 use dicom_core::value::PixelFragmentSequence;
-use dicom_encoding::{Codec, adapters::PixelDataReader};
+use dicom_encoding::{
+    Codec,
+    adapters::{EncodeOptions, PixelDataReader, PixelDataWriter},
+};
 use dicom_transfer_syntax_registry::entries::RLE_LOSSLESS;
 
 fn read_data_piece(test_file: impl AsRef<Path>, offset: u64, length: usize) -> Vec<u8> {
@@ -163,3 +166,152 @@ fn read_rle_2() {
 
     check_u16_rgb_pixel(&dest, 100, 10, 95, [0xFFFF, 0xFFFF, 0xFFFF]);
 }
+
+#[test]
+fn read_rle_16bit_rgb_fragment() {
+    // 3x1 RGB image with 16 bits per sample:
+    // (0x1122, 0x3344, 0x5566), (0x1020, 0x3040, 0x5060), (0xA1B2, 0xC3D4, 0xE5F6)
+    let mut fragment = vec![0; 64];
+    // number of segments
+    fragment[0] = 6;
+    // segment offsets
+    for (i, offset) in [64_u8, 68, 72, 76, 80, 84].into_iter().enumerate() {
+        fragment[4 + 4 * i] = offset;
+    }
+    // one literal run per segment,
+    // from the most to the least significant byte of each sample
+    fragment.extend([
+        0x02, 0x11, 0x10, 0xA1, // R MSB
+        0x02, 0x22, 0x20, 0xB2, // R LSB
+        0x02, 0x33, 0x30, 0xC3, // G MSB
+        0x02, 0x44, 0x40, 0xD4, // G LSB
+        0x02, 0x55, 0x50, 0xE5, // B MSB
+        0x02, 0x66, 0x60, 0xF6, // B LSB
+    ]);
+
+    let obj = TestDataObject {
+        // RLE lossless
+        ts_uid: "1.2.840.10008.1.2.5".to_string(),
+        rows: 1,
+        columns: 3,
+        bits_allocated: 16,
+        bits_stored: 16,
+        samples_per_pixel: 3,
+        photometric_interpretation: "RGB",
+        number_of_frames: 1,
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![fragment])),
+    };
+
+    let Codec::EncapsulatedPixelData(Some(adapter), _) = RLE_LOSSLESS.codec() else {
+        panic!("RLE lossless pixel data reader not found")
+    };
+
+    // interleaved samples in little endian
+    let expected = [
+        0x22, 0x11, 0x44, 0x33, 0x66, 0x55, // pixel 0
+        0x20, 0x10, 0x40, 0x30, 0x60, 0x50, // pixel 1
+        0xB2, 0xA1, 0xD4, 0xC3, 0xF6, 0xE5, // pixel 2
+    ];
+
+    let mut dest = vec![];
+    adapter
+        .decode(&obj, &mut dest)
+        .expect("RLE frame decoding failed");
+    assert_eq!(dest, expected);
+    check_u16_rgb_pixel(&dest, 3, 2, 0, [0xA1B2, 0xC3D4, 0xE5F6]);
+
+    let mut dest = vec![];
+    adapter
+        .decode_frame(&obj, 0, &mut dest)
+        .expect("RLE frame decoding failed");
+    assert_eq!(dest, expected);
+}
+
+/// Encode a native image to RLE Lossless and decode it back,
+/// expecting the exact same samples.
+fn check_rle_roundtrip(
+    bits_allocated: u16,
+    samples_per_pixel: u16,
+    photometric_interpretation: &'static str,
+) {
+    let rows: u16 = 64;
+    let columns: u16 = 100;
+    let bytes_per_sample = bits_allocated as usize / 8;
+    let frame_size =
+        rows as usize * columns as usize * samples_per_pixel as usize * bytes_per_sample;
+
+    // mix of flat regions and noise
+    let mut seed = 0xcfcf_acab_u32;
+    let samples: Vec<u8> = (0..frame_size)
+        .map(|i| {
+            if (i / 256) % 2 == 0 {
+                (i / 512) as u8
+            } else {
+                seed = seed.wrapping_mul(4_294_967_291).wrapping_add(67291);
+                (seed >> 7) as u8
+            }
+        })
+        .collect();
+
+    let obj = TestDataObject {
+        // Explicit VR Little Endian
+        ts_uid: "1.2.840.10008.1.2.1".to_string(),
+        rows,
+        columns,
+        bits_allocated,
+        bits_stored: bits_allocated,
+        samples_per_pixel,
+        photometric_interpretation,
+        number_of_frames: 1,
+        flat_pixel_data: Some(samples.clone()),
+        pixel_data_sequence: None,
+    };
+
+    assert!(RLE_LOSSLESS.can_encode());
+    assert!(RLE_LOSSLESS.can_decode());
+    let Codec::EncapsulatedPixelData(Some(reader), Some(writer)) = RLE_LOSSLESS.codec() else {
+        panic!("RLE lossless pixel data adapters not found")
+    };
+
+    let mut encoded = vec![];
+    let ops = writer
+        .encode_frame(&obj, 0, EncodeOptions::default(), &mut encoded)
+        .expect("RLE frame encoding failed");
+    assert_eq!(encoded.len() % 2, 0);
+    assert!(!ops.is_empty());
+
+    let obj = TestDataObject {
+        ts_uid: "1.2.840.10008.1.2.5".to_string(),
+        flat_pixel_data: None,
+        pixel_data_sequence: Some(PixelFragmentSequence::new(vec![], vec![encoded])),
+        ..obj
+    };
+
+    let mut decoded = vec![];
+    reader
+        .decode_frame(&obj, 0, &mut decoded)
+        .expect("RLE frame decoding failed");
+
+    assert_eq!(decoded, samples);
+}
+
+#[test]
+fn write_and_read_rle_16bit_monochrome() {
+    check_rle_roundtrip(16, 1, "MONOCHROME2");
+}
+
+#[test]
+fn write_and_read_rle_8bit_rgb() {
+    check_rle_roundtrip(8, 3, "RGB");
+}
+
+#[test]
+fn write_and_read_rle_16bit_rgb() {
+    check_rle_roundtrip(16, 3, "RGB");
+}
+
+#[test]
+fn write_and_read_rle_8bit_monochrome() {
+    check_rle_roundtrip(8, 1, "MONOCHROME2");
+}
@@ -0,0 +1,139 @@
+//! Limiting the bandwidth of outgoing PDUs.
+//!
+//! Bulk transfers can easily saturate a network link
+//! shared with other services.
+//! [`BandwidthLimit`] paces the PDUs written to the socket
+//! so that the mean outgoing throughput of an association
+//! does not exceed a given number of bytes per second.
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A shared limit to the throughput of outgoing PDUs.
+///
+/// Each PDU reserves its share of the link before it is written,
+/// and waits for the PDUs reserved before it
+/// to have gone through at the given rate.
+/// Time spent idle is not saved up for later,
+/// so a transfer never bursts beyond the limit after a pause.
+///
+/// Cloning this value produces a handle to the same limit,
+/// so that all PDUs of an association share the same budget,
+/// which can also be shared between associations.
+///
+/// # Example
+///
+/// Bandwidth limits are usually set through
+/// [`ClientAssociationOptions::max_bandwidth`](crate::ClientAssociationOptions::max_bandwidth).
+/// It can also be used on its own:
+///
+/// ```
+/// # use dicom_ul::association::BandwidthLimit;
+/// # use std::time::Duration;
+/// let limit = BandwidthLimit::new(1_000_000);
+///
+/// // the first PDU can be sent right away
+/// assert_eq!(limit.reserve(500_000), Duration::ZERO);
+/// // the next one waits for the first one to go through
+/// assert!(limit.reserve(500_000) > Duration::from_millis(400));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BandwidthLimit {
+    bytes_per_second: u64,
+    /// the moment at which all PDUs reserved so far
+    /// will have gone through
+    next_free: Arc<Mutex<Option<Instant>>>,
+}
+
+impl BandwidthLimit {
+    /// Create a new bandwidth limit.
+    ///
+    /// A limit of 0 is raised to 1 byte per second.
+    pub fn new(bytes_per_second: u64) -> Self {
+        BandwidthLimit {
+            bytes_per_second: bytes_per_second.max(1),
+            next_free: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The maximum mean throughput in bytes per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Reserve the link for sending the given number of bytes,
+    /// returning how long to wait before sending them.
+    pub fn reserve(&self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let mut next_free = self.next_free.lock().unwrap();
+        let start = next_free.filter(|t| *t > now).unwrap_or(now);
+        let transfer_time = Duration::from_secs_f64(bytes as f64 / self.bytes_per_second as f64);
+        *next_free = Some(start + transfer_time);
+        start - now
+    }
+
+    /// Block the current thread until the given number of bytes can be sent.
+    pub(crate) fn wait(&self, bytes: usize) {
+        let delay = self.reserve(bytes);
+        if !delay.is_zero() {
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Wait until the given number of bytes can be sent.
+    #[cfg(feature = "async")]
+    pub(crate) async fn wait_async(&self, bytes: usize) {
+        let delay = self.reserve(bytes);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Block the current thread until the given number of bytes can be sent
+/// under the optional limit.
+pub(crate) fn wait_opt(limit: Option<&BandwidthLimit>, bytes: usize) {
+    if let Some(limit) = limit {
+        limit.wait(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BandwidthLimit;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn reservations_add_up() {
+        let limit = BandwidthLimit::new(10_000);
+        assert_eq!(limit.reserve(1_000), Duration::ZERO);
+        let second = limit.reserve(1_000);
+        assert!(second > Duration::from_millis(90) && second <= Duration::from_millis(100));
+        let third = limit.reserve(1_000);
+        assert!(third > Duration::from_millis(190) && third <= Duration::from_millis(200));
+        // handles share the same budget
+        let other = limit.clone();
+        assert!(other.reserve(1_000) > Duration::from_millis(290));
+    }
+
+    #[test]
+    fn idle_time_is_not_saved_up() {
+        let limit = BandwidthLimit::new(100_000);
+        assert_eq!(limit.reserve(1_000), Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(limit.reserve(1_000), Duration::ZERO);
+        assert!(limit.reserve(1_000) > Duration::from_millis(5));
+    }
+
+    #[test]
+    fn wait_paces_writes() {
+        let limit = BandwidthLimit::new(50_000);
+        let start = Instant::now();
+        for _ in 0..5 {
+            limit.wait(1_000);
+        }
+        // the first write goes through immediately, the other 4 take 20 ms each
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+}
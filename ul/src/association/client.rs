@@ -17,12 +17,14 @@ use crate::association::AsyncAssociation;
 use crate::{
     AeAddr, IMPLEMENTATION_CLASS_UID, IMPLEMENTATION_VERSION_NAME,
     association::{
-        Association, NegotiatedOptions, SocketOptions, SyncAssociation, encode_pdu,
-        private::SyncAssociationSealed, read_pdu_from_wire,
+        AdaptivePduLength, Association, BandwidthLimit, NegotiatedOptions, PDataReader,
+        PDataWriter, SocketOptions, SyncAssociation, bandwidth, encode_pdu,
+        private::SyncAssociationSealed, read_pdu_from_wire_captured,
     },
+    capture::{Direction, PduCapture},
     pdu::{
         AbortRQSource, AssociationAC, AssociationRQ, DEFAULT_MAX_PDU, LARGE_PDU_SIZE,
-        MAXIMUM_PDU_SIZE, PDU_HEADER_SIZE, Pdu, PresentationContextNegotiated,
+        MAXIMUM_PDU_SIZE, MINIMUM_PDU_SIZE, PDU_HEADER_SIZE, Pdu, PresentationContextNegotiated,
         PresentationContextProposed, PresentationContextResultReason, RequestorRoles, UserIdentity,
         UserIdentityType, UserVariableItem, write_pdu,
     },
@@ -264,6 +266,8 @@ pub struct ClientAssociationOptions<'a> {
     max_pdu_length: u32,
     /// whether to receive PDUs in strict mode
     strict: bool,
+    /// whether to adapt the length of outgoing P-Data PDUs
+    adaptive_pdu_length: bool,
     /// User identity username
     username: Option<Cow<'a, str>>,
     /// User identity password
@@ -280,6 +284,10 @@ pub struct ClientAssociationOptions<'a> {
     scu_scp_role_selection: Vec<(Cow<'a, str>, bool, bool)>,
     /// Socket options for TCP connections
     socket_options: SocketOptions,
+    /// where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
+    /// the limit to the throughput of outgoing PDUs, if any
+    bandwidth_limit: Option<BandwidthLimit>,
     /// TLS configuration to use for the connection
     #[cfg(feature = "sync-tls")]
     tls_config: Option<std::sync::Arc<rustls::ClientConfig>>,
@@ -302,6 +310,7 @@ impl Default for ClientAssociationOptions<'_> {
             protocol_version: 1,
             max_pdu_length: DEFAULT_MAX_PDU,
             strict: true,
+            adaptive_pdu_length: false,
             username: None,
             password: None,
             kerberos_service_ticket: None,
@@ -314,6 +323,8 @@ impl Default for ClientAssociationOptions<'_> {
                 write_timeout: None,
                 connection_timeout: None,
             },
+            capture: None,
+            bandwidth_limit: None,
             #[cfg(feature = "sync-tls")]
             tls_config: None,
             #[cfg(feature = "sync-tls")]
@@ -389,6 +400,15 @@ impl<'a> ClientAssociationOptions<'a> {
         self.with_presentation_context(abstract_syntax_uid.into(), default_transfer_syntaxes)
     }
 
+    /// Check whether a presentation context
+    /// for the given abstract syntax is already proposed.
+    pub(crate) fn proposes_abstract_syntax(&self, abstract_syntax_uid: &str) -> bool {
+        let abstract_syntax_uid = trim_uid(abstract_syntax_uid.into());
+        self.presentation_contexts
+            .iter()
+            .any(|(uid, _)| *uid == abstract_syntax_uid)
+    }
+
     /// Override the maximum PDU length
     /// that this application entity will admit.
     /// Values larger than MAXIMUM_PDU_SIZE will
@@ -406,6 +426,51 @@ impl<'a> ClientAssociationOptions<'a> {
         self
     }
 
+    /// Override adaptive PDU sizing:
+    /// whether the length of outgoing P-Data PDUs
+    /// should be adapted to the observed socket throughput and latency,
+    /// up to the maximum PDU length admitted by the association acceptor.
+    ///
+    /// This only applies to data sent through
+    /// [`send_pdata`](SyncAssociation::send_pdata),
+    /// and may improve transfer performance over wide area networks.
+    /// See [`AdaptivePduLength`] for details.
+    ///
+    /// The default is `false`,
+    /// meaning that P-Data PDUs are always as large as
+    /// the maximum PDU length admitted by the acceptor.
+    pub fn adaptive_pdu_length(mut self, adaptive: bool) -> Self {
+        self.adaptive_pdu_length = adaptive;
+        self
+    }
+
+    /// Record every PDU sent and received by the association
+    /// to the given capture,
+    /// starting with the association request.
+    ///
+    /// See the [`capture`](crate::capture) module for details.
+    pub fn pdu_capture(mut self, capture: PduCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Limit the mean throughput of the PDUs sent by the association
+    /// to the given number of bytes per second.
+    ///
+    /// See [`BandwidthLimit`] for details.
+    /// By default, PDUs are sent as fast as the network allows.
+    pub fn max_bandwidth(self, bytes_per_second: u64) -> Self {
+        self.bandwidth_limit(BandwidthLimit::new(bytes_per_second))
+    }
+
+    /// Pace the PDUs sent by the association with the given bandwidth limit,
+    /// which may be shared with other associations
+    /// so that their combined throughput stays within the limit.
+    pub fn bandwidth_limit(mut self, limit: BandwidthLimit) -> Self {
+        self.bandwidth_limit = Some(limit);
+        self
+    }
+
     /// Sets the user identity username
     pub fn username<T>(mut self, username: T) -> Self
     where
@@ -912,26 +977,42 @@ impl<'a> ClientAssociationOptions<'a> {
     }
 
     /// Establish the association with the given AE address.
-    fn establish_impl<T, S>(
+    fn establish_impl<T, S>(self, ae_address: AeAddr<T>, socket: S) -> Result<ClientAssociation<S>>
+    where
+        T: ToSocketAddrs,
+        S: CloseSocket + std::io::Read + std::io::Write,
+    {
+        self.establish_stream(ae_address.ae_title(), socket)
+    }
+
+    /// Request a new association over an already connected stream,
+    /// optionally overriding the called AE title.
+    pub(crate) fn establish_stream<S>(
         self,
-        ae_address: AeAddr<T>,
+        ae_title: Option<&str>,
         mut socket: S,
     ) -> Result<ClientAssociation<S>>
     where
-        T: ToSocketAddrs,
         S: CloseSocket + std::io::Read + std::io::Write,
     {
-        let (pc_proposed, a_associate) = self.create_a_associate_req(ae_address.ae_title())?;
+        let (pc_proposed, a_associate) = self.create_a_associate_req(ae_title)?;
         let mut buffer: Vec<u8> = Vec::with_capacity((DEFAULT_MAX_PDU + PDU_HEADER_SIZE) as usize);
 
         write_pdu(&mut buffer, &a_associate).context(super::SendPduSnafu)?;
         socket.write_all(&buffer).context(super::WireSendSnafu)?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &buffer);
         buffer.clear();
 
         let mut buf = BytesMut::with_capacity(
             (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
         );
-        let resp = read_pdu_from_wire(&mut socket, &mut buf, self.max_pdu_length, self.strict);
+        let resp = read_pdu_from_wire_captured(
+            &mut socket,
+            &mut buf,
+            self.max_pdu_length,
+            self.strict,
+            self.capture.as_ref(),
+        );
         // If we're in non-TLS mode and `read_pdu_from_wire` fails, it
         // could be because the server expects TLS but we sent a
         // plaintext request.  In that case, we make a best effort to
@@ -979,7 +1060,9 @@ impl<'a> ClientAssociationOptions<'a> {
                         source: AbortRQSource::ServiceUser,
                     },
                 );
-                let _ = socket.write_all(&buffer);
+                if socket.write_all(&buffer).is_ok() {
+                    PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &buffer);
+                }
                 buffer.clear();
                 Err(e)
             }
@@ -993,6 +1076,11 @@ impl<'a> ClientAssociationOptions<'a> {
                     presentation_contexts,
                     requestor_max_pdu_length: self.max_pdu_length,
                     acceptor_max_pdu_length: peer_max_pdu_length,
+                    pdu_sizing: self
+                        .adaptive_pdu_length
+                        .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                    capture: self.capture.clone(),
+                    bandwidth_limit: self.bandwidth_limit.clone(),
                     socket,
                     write_buffer: buffer,
                     strict: self.strict,
@@ -1002,6 +1090,7 @@ impl<'a> ClientAssociationOptions<'a> {
                     write_timeout: self.socket_options.write_timeout,
                     user_variables,
                     peer_ae_title,
+                    next_echo_message_id: 1,
                 })
             }
         }
@@ -1089,6 +1178,12 @@ pub struct ClientAssociation<S> {
     requestor_max_pdu_length: u32,
     /// The maximum PDU length that the remote application entity accepts
     acceptor_max_pdu_length: u32,
+    /// The controller for the length of outgoing P-Data PDUs, if adaptive
+    pdu_sizing: Option<AdaptivePduLength>,
+    /// Where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
+    /// The limit to the throughput of outgoing PDUs, if any
+    bandwidth_limit: Option<BandwidthLimit>,
     /// The TCP stream to the other DICOM node
     socket: S,
     /// Buffer to write PDUs to the wire, prevents needing to allocate on every send
@@ -1105,6 +1200,8 @@ pub struct ClientAssociation<S> {
     user_variables: Vec<UserVariableItem>,
     /// The AE title of the peer
     peer_ae_title: String,
+    /// The message ID of the next C-ECHO request
+    next_echo_message_id: u16,
 }
 
 impl<S> Association for ClientAssociation<S>
@@ -1186,6 +1283,39 @@ where
     pub fn presentation_contexts(&self) -> &[PresentationContextNegotiated] {
         &self.presentation_contexts
     }
+
+    /// Retrieve the controller for the length of outgoing P-Data PDUs,
+    /// if adaptive PDU sizing was enabled.
+    pub fn adaptive_pdu_length(&self) -> Option<&AdaptivePduLength> {
+        self.pdu_sizing.as_ref()
+    }
+
+    /// Send a verification request (C-ECHO) to the association acceptor
+    /// and wait for its response,
+    /// returning the status code of the response (0 on success).
+    ///
+    /// This requires a presentation context for the Verification SOP class
+    /// (`1.2.840.10008.1.1`) to have been accepted.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use dicom_ul::association::client::ClientAssociationOptions;
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut association = ClientAssociationOptions::new()
+    ///     .with_abstract_syntax("1.2.840.10008.1.1")
+    ///     .establish_with("ECHO-SCP@10.0.0.100:104")?;
+    /// let status = association.echo()?;
+    /// assert_eq!(status, 0);
+    /// association.release()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn echo(&mut self) -> Result<u16> {
+        let message_id = self.next_echo_message_id;
+        self.next_echo_message_id = message_id.wrapping_add(1);
+        super::verification::echo(self, message_id)
+    }
 }
 
 // compatibility filler, remove in 0.10.0
@@ -1270,18 +1400,22 @@ where
             pdu,
             self.acceptor_max_pdu_length + PDU_HEADER_SIZE,
         )?;
+        bandwidth::wait_opt(self.bandwidth_limit.as_ref(), self.write_buffer.len());
         self.socket
             .write_all(&self.write_buffer)
-            .context(super::WireSendSnafu)
+            .context(super::WireSendSnafu)?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.write_buffer);
+        Ok(())
     }
 
     /// Read a PDU message from the other intervenient.
     fn receive(&mut self) -> Result<Pdu> {
-        read_pdu_from_wire(
+        read_pdu_from_wire_captured(
             &mut self.socket,
             &mut self.read_buffer,
             self.requestor_max_pdu_length,
             self.strict,
+            self.capture.as_ref(),
         )
     }
 
@@ -1306,6 +1440,29 @@ where
         } = self;
         (socket, read_buffer)
     }
+
+    fn send_pdata(&mut self, presentation_context_id: u8) -> PDataWriter<&mut S> {
+        let writer = PDataWriter::new(
+            &mut self.socket,
+            presentation_context_id,
+            self.acceptor_max_pdu_length,
+        )
+        .with_capture(self.capture.clone())
+        .with_bandwidth_limit(self.bandwidth_limit.clone());
+        match &self.pdu_sizing {
+            Some(sizing) => writer.with_adaptive_length(sizing.clone()),
+            None => writer,
+        }
+    }
+
+    fn receive_pdata(&mut self) -> PDataReader<'_, &mut S> {
+        PDataReader::new(
+            &mut self.socket,
+            self.requestor_max_pdu_length,
+            &mut self.read_buffer,
+        )
+        .with_capture(self.capture.clone())
+    }
 }
 
 /// Trait with the behavior to synchronously release an association
@@ -1398,6 +1555,12 @@ pub struct AsyncClientAssociation<S> {
     requestor_max_pdu_length: u32,
     /// The maximum PDU length that the remote application entity accepts
     acceptor_max_pdu_length: u32,
+    /// The controller for the length of outgoing P-Data PDUs, if adaptive
+    pdu_sizing: Option<AdaptivePduLength>,
+    /// Where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
+    /// The limit to the throughput of outgoing PDUs, if any
+    bandwidth_limit: Option<BandwidthLimit>,
     /// The TCP stream to the other DICOM node
     socket: S,
     /// Buffer to assemble PDU before sending it on wire
@@ -1421,14 +1584,28 @@ impl<'a> ClientAssociationOptions<'a> {
     async fn establish_impl_async<T, S>(
         self,
         ae_address: AeAddr<T>,
-        mut socket: S,
+        socket: S,
     ) -> Result<AsyncClientAssociation<S>>
     where
         T: tokio::net::ToSocketAddrs,
         S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send,
+    {
+        self.establish_stream_async(ae_address.ae_title(), socket)
+            .await
+    }
+
+    /// Request a new association over an already connected stream,
+    /// optionally overriding the called AE title.
+    pub(crate) async fn establish_stream_async<S>(
+        self,
+        ae_title: Option<&str>,
+        mut socket: S,
+    ) -> Result<AsyncClientAssociation<S>>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send,
     {
         use tokio::io::AsyncWriteExt;
-        let (pc_proposed, a_associate) = self.create_a_associate_req(ae_address.ae_title())?;
+        let (pc_proposed, a_associate) = self.create_a_associate_req(ae_title)?;
         let mut write_buffer: Vec<u8> =
             Vec::with_capacity((DEFAULT_MAX_PDU + PDU_HEADER_SIZE) as usize);
 
@@ -1442,6 +1619,7 @@ impl<'a> ClientAssociationOptions<'a> {
             Ok(())
         })
         .await?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
         write_buffer.clear();
 
         // read buffer is prepared according to the requestor's max pdu length
@@ -1449,11 +1627,12 @@ impl<'a> ClientAssociationOptions<'a> {
             (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
         );
         let resp = super::timeout(self.socket_options.read_timeout, async {
-            super::read_pdu_from_wire_async(
+            super::read_pdu_from_wire_async_captured(
                 &mut socket,
                 &mut read_buffer,
                 self.max_pdu_length,
                 self.strict,
+                self.capture.as_ref(),
             )
             .await
         })
@@ -1508,6 +1687,7 @@ impl<'a> ClientAssociationOptions<'a> {
                     .write_all(&write_buffer)
                     .await
                     .context(crate::association::WireSendSnafu)?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                 write_buffer.clear();
                 Err(e)
             }
@@ -1521,6 +1701,11 @@ impl<'a> ClientAssociationOptions<'a> {
                     presentation_contexts,
                     requestor_max_pdu_length: self.max_pdu_length,
                     acceptor_max_pdu_length: peer_max_pdu_length,
+                    pdu_sizing: self
+                        .adaptive_pdu_length
+                        .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                    capture: self.capture.clone(),
+                    bandwidth_limit: self.bandwidth_limit.clone(),
                     socket,
                     write_buffer,
                     strict: self.strict,
@@ -1749,6 +1934,12 @@ impl<S> AsyncClientAssociation<S> {
     pub fn presentation_contexts(&self) -> &[PresentationContextNegotiated] {
         &self.presentation_contexts
     }
+
+    /// Retrieve the controller for the length of outgoing P-Data PDUs,
+    /// if adaptive PDU sizing was enabled.
+    pub fn adaptive_pdu_length(&self) -> Option<&AdaptivePduLength> {
+        self.pdu_sizing.as_ref()
+    }
 }
 
 // compatibility filler, remove in 0.10.0
@@ -1836,23 +2027,29 @@ where
             msg,
             self.acceptor_max_pdu_length + PDU_HEADER_SIZE,
         )?;
+        if let Some(limit) = &self.bandwidth_limit {
+            limit.wait_async(self.write_buffer.len()).await;
+        }
         super::timeout(self.write_timeout, async {
             self.socket
                 .write_all(&self.write_buffer)
                 .await
                 .context(crate::association::WireSendSnafu)
         })
-        .await
+        .await?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.write_buffer);
+        Ok(())
     }
 
     async fn receive(&mut self) -> Result<Pdu> {
-        use crate::association::read_pdu_from_wire_async;
+        use crate::association::read_pdu_from_wire_async_captured;
         super::timeout(self.read_timeout, async {
-            read_pdu_from_wire_async(
+            read_pdu_from_wire_async_captured(
                 &mut self.socket,
                 &mut self.read_buffer,
                 self.requestor_max_pdu_length,
                 self.strict,
+                self.capture.as_ref(),
             )
             .await
         })
@@ -1882,11 +2079,38 @@ where
         } = self;
         (socket, read_buffer)
     }
+
+    fn send_pdata(
+        &mut self,
+        presentation_context_id: u8,
+    ) -> crate::association::AsyncPDataWriter<&mut S> {
+        let writer = crate::association::AsyncPDataWriter::new(
+            &mut self.socket,
+            presentation_context_id,
+            self.acceptor_max_pdu_length,
+        )
+        .with_capture(self.capture.clone())
+        .with_bandwidth_limit(self.bandwidth_limit.clone());
+        match &self.pdu_sizing {
+            Some(sizing) => writer.with_adaptive_length(sizing.clone()),
+            None => writer,
+        }
+    }
+
+    fn receive_pdata(&mut self) -> crate::association::PDataReader<'_, &mut S> {
+        crate::association::PDataReader::new(
+            &mut self.socket,
+            self.requestor_max_pdu_length,
+            &mut self.read_buffer,
+        )
+        .with_capture(self.capture.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::association::read_pdu_from_wire;
     #[cfg(feature = "async")]
     use crate::association::read_pdu_from_wire_async;
     use std::io::Write;
@@ -1936,6 +2160,11 @@ mod tests {
                 presentation_contexts,
                 requestor_max_pdu_length: self.max_pdu_length,
                 acceptor_max_pdu_length: peer_max_pdu_length,
+                pdu_sizing: self
+                    .adaptive_pdu_length
+                    .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                capture: self.capture.clone(),
+                bandwidth_limit: self.bandwidth_limit.clone(),
                 socket,
                 write_buffer,
                 strict: self.strict,
@@ -1945,6 +2174,7 @@ mod tests {
                 write_timeout: self.socket_options.write_timeout,
                 user_variables,
                 peer_ae_title,
+                next_echo_message_id: 1,
             })
         }
 
@@ -1993,6 +2223,11 @@ mod tests {
                 presentation_contexts,
                 requestor_max_pdu_length: self.max_pdu_length,
                 acceptor_max_pdu_length: peer_max_pdu_length,
+                pdu_sizing: self
+                    .adaptive_pdu_length
+                    .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                capture: self.capture.clone(),
+                bandwidth_limit: self.bandwidth_limit.clone(),
                 socket,
                 write_buffer: buffer,
                 strict: self.strict,
@@ -2040,6 +2275,11 @@ mod tests {
                 presentation_contexts,
                 requestor_max_pdu_length: self.max_pdu_length,
                 acceptor_max_pdu_length: peer_max_pdu_length,
+                pdu_sizing: self
+                    .adaptive_pdu_length
+                    .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                capture: self.capture.clone(),
+                bandwidth_limit: self.bandwidth_limit.clone(),
                 socket,
                 write_buffer: buffer,
                 strict: self.strict,
@@ -2050,6 +2290,7 @@ mod tests {
                 write_timeout: self.socket_options.write_timeout,
                 user_variables,
                 peer_ae_title,
+                next_echo_message_id: 1,
             })
         }
 
@@ -2094,6 +2335,11 @@ mod tests {
                 presentation_contexts,
                 requestor_max_pdu_length: self.max_pdu_length,
                 acceptor_max_pdu_length: peer_max_pdu_length,
+                pdu_sizing: self
+                    .adaptive_pdu_length
+                    .then(|| AdaptivePduLength::new(MINIMUM_PDU_SIZE, peer_max_pdu_length)),
+                capture: self.capture.clone(),
+                bandwidth_limit: self.bandwidth_limit.clone(),
                 socket,
                 write_buffer: buffer,
                 strict: self.strict,
@@ -14,16 +14,23 @@
 //! a newly created [TCP stream][1] can be passed to
 //! a previously prepared [`ServerAssociationOptions`].
 //!
+//! Tools which send many messages to the same nodes
+//! can keep associations open for reuse
+//! through a [`ClientAssociationPool`].
 //!
 //! [1]: std::net::TcpStream
 pub mod client;
+pub mod pool;
 pub mod server;
 #[cfg(test)]
 mod tests;
 
 mod uid;
 
+pub(crate) mod bandwidth;
 pub(crate) mod pdata;
+pub(crate) mod pdu_sizing;
+pub(crate) mod verification;
 
 use std::{
     backtrace::Backtrace,
@@ -31,6 +38,7 @@ use std::{
     time::Duration,
 };
 
+pub use bandwidth::BandwidthLimit;
 use bytes::{Buf, BytesMut};
 #[cfg(feature = "async")]
 pub use client::AsyncClientAssociation;
@@ -38,6 +46,8 @@ pub use client::{ClientAssociation, ClientAssociationOptions};
 #[cfg(feature = "async")]
 pub use pdata::non_blocking::AsyncPDataWriter;
 pub use pdata::{PDataReader, PDataWriter};
+pub use pdu_sizing::AdaptivePduLength;
+pub use pool::{ClientAssociationPool, PooledAssociation};
 #[cfg(feature = "async")]
 pub use server::AsyncServerAssociation;
 pub use server::{ServerAssociation, ServerAssociationOptions};
@@ -45,6 +55,7 @@ use snafu::{ResultExt, Snafu, ensure};
 
 use crate::{
     Pdu,
+    capture::{Direction, PduCapture},
     pdu::{
         self, AssociationRJ, PresentationContextNegotiated, ReadPduSnafu, RequestorRoles,
         UserVariableItem,
@@ -169,6 +180,12 @@ pub enum Error {
     #[snafu(display("connection closed by peer"))]
     ConnectionClosed { backtrace: Backtrace },
 
+    /// no accepted presentation context for the verification SOP class
+    NoVerificationContext { backtrace: Backtrace },
+
+    /// invalid C-ECHO response from peer
+    InvalidEchoResponse { backtrace: Backtrace },
+
     /// TLS configuration is missing
     #[cfg(feature = "sync-tls")]
     #[snafu(display("TLS configuration is required but not provided"))]
@@ -657,6 +674,21 @@ pub fn read_pdu_from_wire<R>(
     max_pdu_length: u32,
     strict: bool,
 ) -> Result<Pdu>
+where
+    R: Read,
+{
+    read_pdu_from_wire_captured(reader, read_buffer, max_pdu_length, strict, None)
+}
+
+/// Get a PDU from a reader,
+/// recording its bytes to the given capture.
+pub(crate) fn read_pdu_from_wire_captured<R>(
+    reader: &mut R,
+    read_buffer: &mut BytesMut,
+    max_pdu_length: u32,
+    strict: bool,
+    capture: Option<&PduCapture>,
+) -> Result<Pdu>
 where
     R: Read,
 {
@@ -666,7 +698,9 @@ where
         // try to read a PDU according to what's in the buffer
         match pdu::read_pdu(&mut buf, max_pdu_length, strict).context(ReceivePduSnafu)? {
             Some(pdu) => {
-                read_buffer.advance(buf.position() as usize);
+                let len = buf.position() as usize;
+                PduCapture::record_opt(capture, Direction::Received, &read_buffer[..len]);
+                read_buffer.advance(len);
                 break pdu;
             }
             None => {
@@ -698,6 +732,19 @@ pub async fn read_pdu_from_wire_async<R: tokio::io::AsyncRead + Unpin>(
     read_buffer: &mut BytesMut,
     max_pdu_length: u32,
     strict: bool,
+) -> Result<Pdu> {
+    read_pdu_from_wire_async_captured(reader, read_buffer, max_pdu_length, strict, None).await
+}
+
+/// Get a PDU from an async reader,
+/// recording its bytes to the given capture.
+#[cfg(feature = "async")]
+pub(crate) async fn read_pdu_from_wire_async_captured<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+    read_buffer: &mut BytesMut,
+    max_pdu_length: u32,
+    strict: bool,
+    capture: Option<&PduCapture>,
 ) -> Result<Pdu> {
     use tokio::io::AsyncReadExt;
     // receive response
@@ -706,7 +753,9 @@ pub async fn read_pdu_from_wire_async<R: tokio::io::AsyncRead + Unpin>(
         let mut buf = Cursor::new(&read_buffer[..]);
         match pdu::read_pdu(&mut buf, max_pdu_length, strict).context(ReceivePduSnafu)? {
             Some(pdu) => {
-                read_buffer.advance(buf.position() as usize);
+                let len = buf.position() as usize;
+                PduCapture::record_opt(capture, Direction::Received, &read_buffer[..len]);
+                read_buffer.advance(len);
                 break pdu;
             }
             None => {
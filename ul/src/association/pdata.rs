@@ -1,6 +1,7 @@
 use std::{
     collections::VecDeque,
     io::{BufRead, BufReader, Cursor, Read, Write},
+    time::Instant,
 };
 
 use bytes::{Buf, BytesMut};
@@ -8,6 +9,8 @@ use tracing::warn;
 
 use crate::{
     Pdu,
+    association::{bandwidth::BandwidthLimit, pdu_sizing::AdaptivePduLength},
+    capture::{Direction, PduCapture},
     pdu::{LARGE_PDU_SIZE, PDU_HEADER_SIZE, PDV_HEADER_SIZE},
     read_pdu,
 };
@@ -89,6 +92,12 @@ pub struct PDataWriter<W: Write> {
     buffer: Vec<u8>,
     stream: W,
     max_pdu_length: u32,
+    /// controller for the length of each PDU, if adaptive
+    sizing: Option<AdaptivePduLength>,
+    /// where to record the PDUs sent, if capturing
+    capture: Option<PduCapture>,
+    /// the limit to the throughput of the PDUs sent, if any
+    bandwidth_limit: Option<BandwidthLimit>,
 }
 
 impl<W> PDataWriter<W>
@@ -126,9 +135,38 @@ where
             stream,
             max_pdu_length,
             buffer,
+            sizing: None,
+            capture: None,
+            bandwidth_limit: None,
         }
     }
 
+    /// Let the length of each PDU sent be decided
+    /// by the given adaptive PDU length controller,
+    /// never surpassing the maximum PDU length of this writer.
+    /// A maximum PDU length of 0 is not a bound,
+    /// leaving the length to the controller alone.
+    pub(crate) fn with_adaptive_length(mut self, sizing: AdaptivePduLength) -> Self {
+        self.max_pdu_length = match self.max_pdu_length {
+            0 => sizing.current(),
+            max_pdu_length => sizing.current().min(max_pdu_length),
+        };
+        self.sizing = Some(sizing);
+        self
+    }
+
+    /// Record each PDU sent to the given capture, if any.
+    pub(crate) fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Pace the PDUs sent with the given bandwidth limit, if any.
+    pub(crate) fn with_bandwidth_limit(mut self, limit: Option<BandwidthLimit>) -> Self {
+        self.bandwidth_limit = limit;
+        self
+    }
+
     /// Declare to have finished sending P-Data fragments,
     /// thus emitting the last P-Data fragment PDU.
     ///
@@ -142,7 +180,9 @@ where
         if !self.buffer.is_empty() {
             // send last PDU
             setup_pdata_header(&mut self.buffer, true);
+            super::bandwidth::wait_opt(self.bandwidth_limit.as_ref(), self.buffer.len());
             self.stream.write_all(&self.buffer[..])?;
+            PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.buffer);
             // clear buffer so that subsequent calls to `finish_impl`
             // do not send any more PDUs
             self.buffer.clear();
@@ -158,7 +198,14 @@ where
         debug_assert!(self.buffer.len() >= PDU_PDV_HEADER_SIZE);
         // send PDU now
         setup_pdata_header(&mut self.buffer, false);
+        super::bandwidth::wait_opt(self.bandwidth_limit.as_ref(), self.buffer.len());
+        let start = Instant::now();
         self.stream.write_all(&self.buffer)?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.buffer);
+        if let Some(sizing) = &self.sizing {
+            sizing.record(self.buffer.len(), start.elapsed());
+            self.max_pdu_length = sizing.current();
+        }
 
         // back to just the header
         self.buffer.truncate(PDU_PDV_HEADER_SIZE);
@@ -249,6 +296,7 @@ pub struct PDataReader<'a, R> {
     max_pdu_length: u32,
     last_pdu: bool,
     read_buffer: &'a mut BytesMut,
+    capture: Option<PduCapture>,
 }
 
 impl<'a, R> PDataReader<'a, R> {
@@ -262,9 +310,16 @@ impl<'a, R> PDataReader<'a, R> {
             max_pdu_length,
             last_pdu: false,
             read_buffer: remaining,
+            capture: None,
         }
     }
 
+    /// Record each PDU received to the given capture, if any.
+    pub(crate) fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+
     /// Declare no intention to read more PDUs from the remote node.
     ///
     /// Attempting to read more bytes
@@ -294,7 +349,13 @@ where
                     .map_err(std::io::Error::other)?
                 {
                     Some(pdu) => {
-                        self.read_buffer.advance(buf.position() as usize);
+                        let len = buf.position() as usize;
+                        PduCapture::record_opt(
+                            self.capture.as_ref(),
+                            Direction::Received,
+                            &self.read_buffer[..len],
+                        );
+                        self.read_buffer.advance(len);
                         break pdu;
                     }
                     None => {
@@ -343,9 +404,11 @@ where
 #[cfg(feature = "async")]
 pub mod non_blocking {
     use std::{
+        future::Future,
         io::Cursor,
         pin::Pin,
         task::{Context, Poll, ready},
+        time::Instant,
     };
 
     use bytes::{Buf, BufMut};
@@ -356,6 +419,8 @@ pub mod non_blocking {
 
     use crate::{
         Pdu,
+        association::{bandwidth::BandwidthLimit, pdu_sizing::AdaptivePduLength},
+        capture::{Direction, PduCapture},
         pdu::{PDU_HEADER_SIZE, PDV_HEADER_SIZE},
         read_pdu,
     };
@@ -432,6 +497,16 @@ pub mod non_blocking {
         // State machine tracking whether we're currently writing to the
         // underlying stream and how much of the buffer we've written
         state: WriteState,
+        // Controller for the length of each PDU, if adaptive
+        sizing: Option<AdaptivePduLength>,
+        // When the PDU currently being written started to be written
+        write_start: Option<Instant>,
+        // Where to record the PDUs sent, if capturing
+        capture: Option<PduCapture>,
+        // The limit to the throughput of the PDUs sent, if any
+        bandwidth_limit: Option<BandwidthLimit>,
+        // The wait for the bandwidth limit before sending the next PDU
+        throttle: Option<Pin<Box<tokio::time::Sleep>>>,
     }
 
     #[cfg(feature = "async")]
@@ -473,7 +548,68 @@ pub mod non_blocking {
                 max_pdu_length,
                 buffer,
                 state: WriteState::Ready,
+                sizing: None,
+                write_start: None,
+                capture: None,
+                bandwidth_limit: None,
+                throttle: None,
+            }
+        }
+
+        /// Let the length of each PDU sent be decided
+        /// by the given adaptive PDU length controller,
+        /// never surpassing the maximum PDU length of this writer.
+        /// A maximum PDU length of 0 is not a bound,
+        /// leaving the length to the controller alone.
+        pub(crate) fn with_adaptive_length(mut self, sizing: AdaptivePduLength) -> Self {
+            self.max_pdu_length = match self.max_pdu_length {
+                0 => sizing.current(),
+                max_pdu_length => sizing.current().min(max_pdu_length),
+            };
+            self.sizing = Some(sizing);
+            self
+        }
+
+        /// Record each PDU sent to the given capture, if any.
+        pub(crate) fn with_capture(mut self, capture: Option<PduCapture>) -> Self {
+            self.capture = capture;
+            self
+        }
+
+        /// Pace the PDUs sent with the given bandwidth limit, if any.
+        pub(crate) fn with_bandwidth_limit(mut self, limit: Option<BandwidthLimit>) -> Self {
+            self.bandwidth_limit = limit;
+            self
+        }
+
+        /// Wait until a PDU of the given length can be sent
+        /// under the bandwidth limit, if any.
+        fn wait_for_bandwidth(&mut self, cx: &mut Context<'_>, pdu_len: usize) -> Poll<()> {
+            if self.throttle.is_none() {
+                let Some(limit) = &self.bandwidth_limit else {
+                    return Poll::Ready(());
+                };
+                let delay = limit.reserve(pdu_len);
+                if delay.is_zero() {
+                    return Poll::Ready(());
+                }
+                self.throttle = Some(Box::pin(tokio::time::sleep(delay)));
             }
+            let sleep = self.throttle.as_mut().unwrap();
+            ready!(sleep.as_mut().poll(cx));
+            self.throttle = None;
+            Poll::Ready(())
+        }
+
+        /// Reset the buffer after a full PDU was written,
+        /// updating the PDU length if adaptive.
+        fn pdu_written(&mut self) {
+            PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.buffer);
+            if let (Some(sizing), Some(start)) = (&self.sizing, self.write_start.take()) {
+                sizing.record(self.buffer.len(), start.elapsed());
+                self.max_pdu_length = sizing.current();
+            }
+            self.buffer.truncate(PDU_PDV_HEADER_SIZE);
         }
 
         /// Declare to have finished sending P-Data fragments,
@@ -499,7 +635,14 @@ pub mod non_blocking {
             if !self.buffer.is_empty() {
                 // send last PDU
                 setup_pdata_header(&mut self.buffer, true);
+                if let Some(sleep) = self.throttle.take() {
+                    sleep.await;
+                }
+                if let Some(limit) = &self.bandwidth_limit {
+                    limit.wait_async(self.buffer.len()).await;
+                }
                 self.stream.write_all(&self.buffer[..]).await?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.buffer);
                 // clear buffer so that subsequent calls to `finish_impl`
                 // do not send any more PDUs
                 self.buffer.clear();
@@ -530,6 +673,11 @@ pub mod non_blocking {
                         self.buffer.extend(buf);
                         Poll::Ready(Ok(buf.len()))
                     } else {
+                        // wait for the bandwidth limit, if any,
+                        // before taking anything from the caller buffer
+                        if !self.wait_for_bandwidth(cx, total_len).is_ready() {
+                            return Poll::Pending;
+                        }
                         // `self.buffer` is full, fill in the rest of the
                         // buffer, prepare to send PDU
                         let slice = &buf[..total_len - self.buffer.len()];
@@ -537,6 +685,7 @@ pub mod non_blocking {
                         let consumed = slice.len();
                         debug_assert_eq!(self.buffer.len(), total_len);
                         setup_pdata_header(&mut self.buffer, false);
+                        self.write_start = Some(Instant::now());
                         let mut written = 0;
 
                         loop {
@@ -565,7 +714,7 @@ pub mod non_blocking {
                                     // underlying writer becomes ready to write
                                     if written == this.buffer.len() {
                                         // If we wrote the whole buffer, reset `self.buffer`
-                                        this.buffer.truncate(PDU_PDV_HEADER_SIZE);
+                                        this.pdu_written();
                                         return Poll::Ready(Ok(consumed));
                                     }
                                 }
@@ -606,7 +755,7 @@ pub mod non_blocking {
                                 written += n;
                                 if (written + pos) == this.buffer.len() {
                                     // If we wrote the whole buffer, reset `self.buffer` and change state back to ready
-                                    this.buffer.truncate(PDU_PDV_HEADER_SIZE);
+                                    this.pdu_written();
                                     this.state = WriteState::Ready;
                                     return Poll::Ready(Ok(consumed));
                                 }
@@ -673,6 +822,7 @@ pub mod non_blocking {
                     ref mut stream,
                     ref mut read_buffer,
                     max_pdu_length,
+                    ref capture,
                     ..
                 } = &mut *self;
                 let mut reader = BufReader::new(stream);
@@ -682,7 +832,13 @@ pub mod non_blocking {
                         .map_err(std::io::Error::other)?
                     {
                         Some(pdu) => {
-                            read_buffer.advance(buf.position() as usize);
+                            let len = buf.position() as usize;
+                            PduCapture::record_opt(
+                                capture.as_ref(),
+                                Direction::Received,
+                                &read_buffer[..len],
+                            );
+                            read_buffer.advance(len);
                             break pdu;
                         }
                         None => {
@@ -998,6 +1154,131 @@ mod tests {
         assert_eq!(cursor.len(), 0);
     }
 
+    #[test]
+    fn test_write_adaptive_pdata_and_finish() {
+        use crate::association::AdaptivePduLength;
+
+        let presentation_context_id = 8;
+        let max_pdu_length = 100_000;
+
+        let my_data: Vec<_> = (0..1_000_000).map(|x: u32| x as u8).collect();
+
+        let mut buf = Vec::new();
+        let sizing = AdaptivePduLength::new(MINIMUM_PDU_SIZE, max_pdu_length);
+        {
+            let mut writer = PDataWriter::new(&mut buf, presentation_context_id, max_pdu_length)
+                .with_adaptive_length(sizing.clone());
+            writer.write_all(&my_data).unwrap();
+            writer.finish().unwrap();
+        }
+
+        // all PDUs respect the bounds and carry all data in order
+        let mut cursor = &buf[..];
+        let mut all_data: Vec<u8> = Vec::new();
+        let mut is_last = false;
+        while !cursor.is_empty() {
+            assert!(!is_last, "no more PDUs expected after the last one");
+            match read_pdu(&mut cursor, max_pdu_length, true).unwrap() {
+                Some(Pdu::PData { data }) => {
+                    assert_eq!(data.len(), 1);
+                    let data = &data[0];
+                    assert_eq!(data.presentation_context_id, presentation_context_id);
+                    assert!(data.data.len() <= (max_pdu_length - PDV_HEADER_SIZE) as usize);
+                    is_last = data.is_last;
+                    if !is_last {
+                        assert!(data.data.len() >= (sizing.min() - PDV_HEADER_SIZE) as usize);
+                    }
+                    all_data.extend(&data.data);
+                }
+                pdu => panic!("Expected PData, got {:?}", pdu),
+            }
+        }
+        assert!(is_last);
+        assert_eq!(all_data, my_data);
+        assert!(sizing.current() <= max_pdu_length);
+    }
+
+    #[test]
+    fn test_write_adaptive_pdata_without_max_pdu_length() {
+        use crate::association::AdaptivePduLength;
+
+        let presentation_context_id = 8;
+        let my_data: Vec<_> = (0..100_000).map(|x: u32| x as u8).collect();
+
+        let mut buf = Vec::new();
+        let sizing = AdaptivePduLength::new(MINIMUM_PDU_SIZE, 32_768);
+        {
+            // a maximum PDU length of 0 leaves the length to the controller
+            let mut writer =
+                PDataWriter::new(&mut buf, presentation_context_id, 0).with_adaptive_length(sizing);
+            writer.write_all(&my_data).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let mut cursor = &buf[..];
+        let mut all_data: Vec<u8> = Vec::new();
+        while !cursor.is_empty() {
+            match read_pdu(&mut cursor, 32_768, true).unwrap() {
+                Some(Pdu::PData { data }) => all_data.extend(&data[0].data),
+                pdu => panic!("Expected PData, got {:?}", pdu),
+            }
+        }
+        assert_eq!(all_data, my_data);
+    }
+
+    /// read back the data of all P-Data PDUs written
+    fn read_all_pdata(mut cursor: &[u8]) -> Vec<u8> {
+        let mut all_data = Vec::new();
+        while !cursor.is_empty() {
+            match read_pdu(&mut cursor, MINIMUM_PDU_SIZE, true).unwrap() {
+                Some(Pdu::PData { data }) => all_data.extend(&data[0].data),
+                pdu => panic!("Expected PData, got {:?}", pdu),
+            }
+        }
+        all_data
+    }
+
+    #[test]
+    fn test_write_pdata_with_bandwidth_limit() {
+        use crate::association::BandwidthLimit;
+
+        let my_data: Vec<_> = (0..20_000).map(|x: u32| x as u8).collect();
+        // about 10 ms per PDU
+        let limit = BandwidthLimit::new(410_000);
+
+        let mut buf = Vec::new();
+        let start = std::time::Instant::now();
+        {
+            let mut writer =
+                PDataWriter::new(&mut buf, 1, MINIMUM_PDU_SIZE).with_bandwidth_limit(Some(limit));
+            writer.write_all(&my_data).unwrap();
+            writer.finish().unwrap();
+        }
+        // 5 PDUs, the first of them sent right away
+        assert!(start.elapsed() >= std::time::Duration::from_millis(35));
+        assert_eq!(read_all_pdata(&buf), my_data);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_write_pdata_with_bandwidth_limit() {
+        use crate::association::BandwidthLimit;
+
+        let my_data: Vec<_> = (0..20_000).map(|x: u32| x as u8).collect();
+        let limit = BandwidthLimit::new(410_000);
+
+        let mut buf = Vec::new();
+        let start = std::time::Instant::now();
+        {
+            let mut writer = AsyncPDataWriter::new(&mut buf, 1, MINIMUM_PDU_SIZE)
+                .with_bandwidth_limit(Some(limit));
+            writer.write_all(&my_data).await.unwrap();
+            writer.finish().await.unwrap();
+        }
+        assert!(start.elapsed() >= std::time::Duration::from_millis(35));
+        assert_eq!(read_all_pdata(&buf), my_data);
+    }
+
     #[test]
     fn test_read_large_pdata_and_finish() {
         use std::collections::VecDeque;
@@ -1113,11 +1394,11 @@ mod tests {
                 }
                 Some(None) => {
                     cx.waker().wake_by_ref();
-                    return Poll::Pending;
+                    Poll::Pending
                 }
                 None => {
                     self.inner.extend_from_slice(buf);
-                    return Poll::Ready(Ok(buf.len()));
+                    Poll::Ready(Ok(buf.len()))
                 }
             }
         }
@@ -0,0 +1,271 @@
+//! Adaptive sizing of outgoing P-Data PDUs.
+//!
+//! Large PDUs reduce per-PDU overhead and tend to perform better
+//! over high latency links,
+//! whereas smaller PDUs are friendlier to congested or slow peers.
+//! [`AdaptivePduLength`] probes for a good compromise at run time
+//! by measuring how long each P-Data PDU takes to be written to the socket,
+//! never going beyond the maximum PDU length negotiated with the peer.
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::pdu::{DEFAULT_MAX_PDU, MINIMUM_PDU_SIZE};
+
+/// Number of PDU writes measured before deciding on the next PDU length
+const SAMPLES_PER_STEP: u32 = 4;
+
+/// Relative throughput loss tolerated before backing off
+const THROUGHPUT_TOLERANCE: f64 = 0.1;
+
+/// Number of steps to hold the PDU length after backing off,
+/// before probing for a larger length again
+const HOLD_STEPS: u32 = 8;
+
+/// The default mean write latency per PDU above which
+/// the PDU length is reduced regardless of throughput
+const DEFAULT_TARGET_LATENCY: Duration = Duration::from_millis(500);
+
+/// A shared controller for the length of outgoing P-Data PDUs,
+/// adapted from the observed socket throughput and latency.
+///
+/// The controller starts from a conservative PDU length
+/// and doubles it for as long as throughput does not degrade,
+/// up to the given maximum (usually the peer's maximum PDU length).
+/// When throughput drops,
+/// or when writing a single PDU takes longer than the target latency,
+/// the PDU length is halved and held for a while before probing again.
+///
+/// Cloning this value produces a handle to the same controller,
+/// so that the measurements carry over
+/// across P-Data writers of the same association.
+///
+/// # Example
+///
+/// Adaptive PDU sizing is usually enabled through
+/// [`ClientAssociationOptions::adaptive_pdu_length`](crate::ClientAssociationOptions::adaptive_pdu_length).
+/// It can also be used on its own:
+///
+/// ```
+/// # use dicom_ul::association::AdaptivePduLength;
+/// # use std::time::Duration;
+/// let sizing = AdaptivePduLength::new(4_096, 262_144);
+/// let initial = sizing.current();
+///
+/// for _ in 0..4 {
+///     sizing.record(initial as usize, Duration::from_millis(1));
+/// }
+/// // throughput was measured for the first time, try a larger PDU length
+/// assert_eq!(sizing.current(), initial * 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdaptivePduLength {
+    state: Arc<Mutex<SizingState>>,
+}
+
+#[derive(Debug)]
+struct SizingState {
+    /// the smallest PDU length admitted
+    min: u32,
+    /// the largest PDU length admitted
+    max: u32,
+    /// the PDU length to use in the next PDUs
+    current: u32,
+    /// mean write latency above which the PDU length is reduced
+    target_latency: Duration,
+    /// number of bytes written in the current step
+    step_bytes: u64,
+    /// total time spent writing in the current step
+    step_time: Duration,
+    /// number of PDUs written in the current step
+    step_samples: u32,
+    /// the throughput (bytes per second) measured in the previous step
+    last_throughput: Option<f64>,
+    /// number of steps left before probing for a larger PDU length
+    hold: u32,
+}
+
+impl AdaptivePduLength {
+    /// Create a new adaptive PDU length controller.
+    ///
+    /// `min` and `max` are bounds to the PDU-length property
+    /// of the P-Data PDUs to send.
+    /// `min` is raised to the minimum PDU size admitted by the standard
+    /// and never surpasses `max`.
+    /// The initial PDU length is the default maximum PDU length,
+    /// clamped to these bounds.
+    pub fn new(min: u32, max: u32) -> Self {
+        let max = max.max(MINIMUM_PDU_SIZE);
+        let min = min.clamp(MINIMUM_PDU_SIZE, max);
+        AdaptivePduLength {
+            state: Arc::new(Mutex::new(SizingState {
+                min,
+                max,
+                current: DEFAULT_MAX_PDU.clamp(min, max),
+                target_latency: DEFAULT_TARGET_LATENCY,
+                step_bytes: 0,
+                step_time: Duration::ZERO,
+                step_samples: 0,
+                last_throughput: None,
+                hold: 0,
+            })),
+        }
+    }
+
+    /// Override the mean write latency per PDU
+    /// above which the PDU length is reduced.
+    ///
+    /// The default is 500 milliseconds.
+    pub fn target_latency(self, latency: Duration) -> Self {
+        self.lock().target_latency = latency;
+        self
+    }
+
+    /// Retrieve the PDU length to use in the next P-Data PDU.
+    pub fn current(&self) -> u32 {
+        self.lock().current
+    }
+
+    /// Retrieve the upper bound of the PDU length.
+    pub fn max(&self) -> u32 {
+        self.lock().max
+    }
+
+    /// Retrieve the lower bound of the PDU length.
+    pub fn min(&self) -> u32 {
+        self.lock().min
+    }
+
+    /// Record that a PDU of `bytes` bytes took `elapsed` to be written,
+    /// possibly updating the PDU length to use next.
+    pub fn record(&self, bytes: usize, elapsed: Duration) {
+        let mut state = self.lock();
+        state.step_bytes += bytes as u64;
+        state.step_time += elapsed;
+        state.step_samples += 1;
+        if state.step_samples < SAMPLES_PER_STEP {
+            return;
+        }
+
+        // 1 nanosecond minimum, to keep the throughput finite
+        let secs = state.step_time.as_secs_f64().max(1e-9);
+        let throughput = state.step_bytes as f64 / secs;
+        let mean_latency = state.step_time / state.step_samples;
+        state.step_bytes = 0;
+        state.step_time = Duration::ZERO;
+        state.step_samples = 0;
+
+        if mean_latency > state.target_latency {
+            // too slow for the peer, back off right away
+            state.back_off();
+            state.last_throughput = Some(throughput);
+            return;
+        }
+
+        match state.last_throughput {
+            Some(last) if throughput < last * (1. - THROUGHPUT_TOLERANCE) => {
+                state.back_off();
+            }
+            _ if state.hold > 0 => {
+                state.hold -= 1;
+            }
+            _ => {
+                state.current = state.current.saturating_mul(2).min(state.max);
+            }
+        }
+        state.last_throughput = Some(throughput);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SizingState> {
+        // a poisoned lock does not leave the state inconsistent
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SizingState {
+    fn back_off(&mut self) {
+        self.current = (self.current / 2).max(self.min);
+        self.hold = HOLD_STEPS;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptivePduLength;
+    use crate::pdu::{DEFAULT_MAX_PDU, MINIMUM_PDU_SIZE};
+    use std::time::Duration;
+
+    /// record one full step with the given throughput in bytes per millisecond
+    fn step(sizing: &AdaptivePduLength, bytes_per_ms: u64) {
+        let len = sizing.current() as u64;
+        for _ in 0..super::SAMPLES_PER_STEP {
+            sizing.record(
+                len as usize,
+                Duration::from_micros(len * 1_000 / bytes_per_ms),
+            );
+        }
+    }
+
+    #[test]
+    fn starts_from_default_within_bounds() {
+        let sizing = AdaptivePduLength::new(0, u32::MAX);
+        assert_eq!(sizing.min(), MINIMUM_PDU_SIZE);
+        assert_eq!(sizing.current(), DEFAULT_MAX_PDU);
+
+        let sizing = AdaptivePduLength::new(0, 4_000);
+        assert_eq!(sizing.max(), 4_000);
+        assert_eq!(sizing.current(), 4_000);
+
+        let sizing = AdaptivePduLength::new(100_000, 200_000);
+        assert_eq!(sizing.current(), 100_000);
+    }
+
+    #[test]
+    fn grows_up_to_max_while_throughput_holds() {
+        let sizing = AdaptivePduLength::new(4_096, 100_000);
+        assert_eq!(sizing.current(), DEFAULT_MAX_PDU);
+        step(&sizing, 1_000);
+        assert_eq!(sizing.current(), DEFAULT_MAX_PDU * 2);
+        step(&sizing, 1_000);
+        assert_eq!(sizing.current(), 100_000);
+        step(&sizing, 1_000);
+        assert_eq!(sizing.current(), 100_000);
+    }
+
+    #[test]
+    fn backs_off_on_throughput_drop_and_holds() {
+        let sizing = AdaptivePduLength::new(4_096, 1_000_000);
+        step(&sizing, 1_000);
+        let grown = sizing.current();
+        assert_eq!(grown, DEFAULT_MAX_PDU * 2);
+
+        // throughput halved at the larger length
+        step(&sizing, 500);
+        assert_eq!(sizing.current(), DEFAULT_MAX_PDU);
+
+        // stays put while holding, even if throughput is fine
+        for _ in 0..super::HOLD_STEPS {
+            step(&sizing, 500);
+            assert_eq!(sizing.current(), DEFAULT_MAX_PDU);
+        }
+        // then probes again
+        step(&sizing, 500);
+        assert_eq!(sizing.current(), grown);
+    }
+
+    #[test]
+    fn backs_off_on_high_latency() {
+        let sizing = AdaptivePduLength::new(0, 1_000_000).target_latency(Duration::from_millis(10));
+        for _ in 0..super::SAMPLES_PER_STEP {
+            sizing.record(DEFAULT_MAX_PDU as usize, Duration::from_millis(20));
+        }
+        assert_eq!(sizing.current(), DEFAULT_MAX_PDU / 2);
+
+        // never goes below the minimum
+        for _ in 0..20 {
+            step(&sizing, 1);
+        }
+        assert_eq!(sizing.current(), MINIMUM_PDU_SIZE);
+    }
+}
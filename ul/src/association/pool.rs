@@ -0,0 +1,434 @@
+//! Association pooling for client tools.
+//!
+//! Establishing an association involves a TCP connection
+//! and a round trip for the association negotiation,
+//! which can amount to a significant share of the time taken
+//! to send a single instance.
+//! A [`ClientAssociationPool`] keeps associations to frequently used
+//! application entities open between operations,
+//! so that they can be reused.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::association::{ClientAssociationOptions, ClientAssociationPool};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let pool = ClientAssociationPool::new(
+//!     ClientAssociationOptions::new().calling_ae_title("STORE-SCU"),
+//! );
+//!
+//! for _ in 0..10 {
+//!     // only the first iteration establishes a new association
+//!     let mut association = pool.get(
+//!         "STORE-SCP@10.0.0.100:104",
+//!         &["1.2.840.10008.5.1.4.1.1.7"],
+//!     )?;
+//!     // ... send a C-STORE request through `association` ...
+//!     // the association goes back to the pool when dropped
+//! }
+//!
+//! pool.release_all();
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::HashMap,
+    net::TcpStream,
+    ops::{Deref, DerefMut},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use tracing::debug;
+
+use super::{
+    Error, Result,
+    client::{ClientAssociation, ClientAssociationOptions},
+    verification::VERIFICATION_SOP_CLASS,
+};
+
+/// An association kept in the pool
+#[derive(Debug)]
+struct PoolEntry {
+    association: ClientAssociation<TcpStream>,
+    /// the presentation contexts proposed on top of the pool's base options,
+    /// with no transfer syntaxes for the default ones
+    proposed: Vec<(String, Vec<String>)>,
+    /// when the association was last returned to the pool
+    since: Instant,
+}
+
+/// A pool of client associations,
+/// keeping associations to frequently used application entities open
+/// so that they can be reused across operations.
+///
+/// Associations are obtained via [`get`](Self::get),
+/// which hands out a [`PooledAssociation`].
+/// Once the pooled association is dropped,
+/// it is returned to the pool for later use.
+///
+/// Before reusing an idle association,
+/// the pool makes sure that:
+///
+/// - it has been idle for no longer than the [idle timeout](Self::idle_timeout);
+/// - it was negotiated with all of the requested abstract syntaxes,
+///   otherwise it is released and a new association is negotiated
+///   with the previous and the requested abstract syntaxes combined;
+/// - the peer still responds to a verification request (C-ECHO),
+///   unless [liveness checks](Self::check_liveness) are disabled.
+///
+/// Associations are keyed by the address string passed to `get`,
+/// which follows the syntax of
+/// [`establish_with`](ClientAssociationOptions::establish_with).
+/// The pool can be shared across threads.
+#[derive(Debug)]
+pub struct ClientAssociationPool {
+    /// the options used to establish new associations
+    options: ClientAssociationOptions<'static>,
+    /// the maximum number of idle associations kept per peer
+    max_idle_per_peer: usize,
+    /// the maximum time that an association may stay idle in the pool
+    idle_timeout: Option<Duration>,
+    /// whether to check that idle associations are alive before reuse
+    check_liveness: bool,
+    /// the idle associations, by peer address
+    idle: Mutex<HashMap<String, Vec<PoolEntry>>>,
+}
+
+impl ClientAssociationPool {
+    /// Create a new association pool,
+    /// using the given options to establish new associations.
+    ///
+    /// The presentation contexts in `options`
+    /// are proposed in every association.
+    /// More abstract syntaxes can be requested on each call to
+    /// [`get`](Self::get).
+    pub fn new(options: ClientAssociationOptions<'static>) -> Self {
+        ClientAssociationPool {
+            options,
+            max_idle_per_peer: 1,
+            idle_timeout: Some(Duration::from_secs(60)),
+            check_liveness: true,
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Override the maximum number of idle associations
+    /// kept open to each peer.
+    ///
+    /// The default is 1.
+    /// With 0, associations are released as soon as they are dropped.
+    pub fn max_idle_per_peer(mut self, value: usize) -> Self {
+        self.max_idle_per_peer = value;
+        self
+    }
+
+    /// Override the maximum time that an association
+    /// may stay idle in the pool before it is no longer reused.
+    /// `None` means that idle associations never expire.
+    ///
+    /// The default is 60 seconds.
+    /// Peers often close associations after a period of inactivity,
+    /// so this should be kept below the peer's own timeout.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Override whether to check that idle associations are still alive,
+    /// by sending a verification request (C-ECHO),
+    /// before handing them out again.
+    ///
+    /// The default is `true`.
+    /// When enabled, the Verification SOP class
+    /// is proposed in every new association.
+    pub fn check_liveness(mut self, check_liveness: bool) -> Self {
+        self.check_liveness = check_liveness;
+        self
+    }
+
+    /// Obtain an association to the given address
+    /// in which the given abstract syntaxes were proposed,
+    /// reusing an idle association if possible.
+    ///
+    /// Abstract syntaxes not already in the pool's base options
+    /// are proposed with the default transfer syntaxes
+    /// (explicit and implicit VR little endian).
+    /// Note that the peer may still reject some of them,
+    /// so the [accepted presentation contexts][1]
+    /// should be checked as usual.
+    ///
+    /// [1]: ClientAssociation::presentation_contexts
+    pub fn get(
+        &self,
+        ae_address: &str,
+        abstract_syntaxes: &[&str],
+    ) -> Result<PooledAssociation<'_>> {
+        let wanted: Vec<_> = abstract_syntaxes
+            .iter()
+            .map(|uid| (uid.to_string(), Vec::new()))
+            .collect();
+        self.get_proposing(ae_address, wanted)
+    }
+
+    /// Obtain an association to the given address
+    /// in which the given presentation contexts,
+    /// each an abstract syntax with its transfer syntaxes,
+    /// were proposed,
+    /// reusing an idle association if possible.
+    ///
+    /// An idle association is only reused
+    /// if each abstract syntax was proposed
+    /// with the same transfer syntaxes in the same order.
+    pub fn get_with_presentation_contexts(
+        &self,
+        ae_address: &str,
+        presentation_contexts: &[(&str, &[&str])],
+    ) -> Result<PooledAssociation<'_>> {
+        let wanted: Vec<_> = presentation_contexts
+            .iter()
+            .map(|(uid, transfer_syntaxes)| {
+                (
+                    uid.to_string(),
+                    transfer_syntaxes.iter().map(|ts| ts.to_string()).collect(),
+                )
+            })
+            .collect();
+        self.get_proposing(ae_address, wanted)
+    }
+
+    fn get_proposing(
+        &self,
+        ae_address: &str,
+        wanted: Vec<(String, Vec<String>)>,
+    ) -> Result<PooledAssociation<'_>> {
+        // presentation contexts of released associations,
+        // to be proposed again in a new association
+        let mut previous: Vec<(String, Vec<String>)> = Vec::new();
+
+        while let Some(mut entry) = self.take_idle(ae_address) {
+            if self
+                .idle_timeout
+                .is_some_and(|timeout| entry.since.elapsed() > timeout)
+            {
+                debug!("Releasing association to {} after idle timeout", ae_address);
+                let _ = entry.association.release();
+                continue;
+            }
+
+            if !wanted
+                .iter()
+                .all(|(uid, transfer_syntaxes)| self.covers(&entry, uid, transfer_syntaxes))
+            {
+                debug!(
+                    "Releasing association to {} to negotiate more presentation contexts",
+                    ae_address
+                );
+                previous.extend(entry.proposed);
+                let _ = entry.association.release();
+                continue;
+            }
+
+            if self.check_liveness && !Self::is_alive(&mut entry.association) {
+                debug!("Discarding dead association to {}", ae_address);
+                let _ = entry.association.abort();
+                continue;
+            }
+
+            return Ok(PooledAssociation {
+                pool: self,
+                key: ae_address.to_string(),
+                entry: Some(entry),
+            });
+        }
+
+        let mut options = self.options.clone();
+        let mut proposed: Vec<(String, Vec<String>)> = Vec::new();
+        let wanted = self
+            .check_liveness
+            .then(|| (VERIFICATION_SOP_CLASS.to_string(), Vec::new()))
+            .into_iter()
+            .chain(previous)
+            .chain(wanted);
+        for (uid, transfer_syntaxes) in wanted {
+            if transfer_syntaxes.is_empty() {
+                if options.proposes_abstract_syntax(&uid) || proposed.iter().any(|(p, _)| *p == uid)
+                {
+                    continue;
+                }
+                options = options.with_abstract_syntax(uid.clone());
+            } else {
+                if proposed
+                    .iter()
+                    .any(|p| p.0 == uid && p.1 == transfer_syntaxes)
+                {
+                    continue;
+                }
+                options = options.with_presentation_context(uid.clone(), transfer_syntaxes.clone());
+            }
+            proposed.push((uid, transfer_syntaxes));
+        }
+
+        debug!("Establishing new association to {}", ae_address);
+        let association = options.establish_with(ae_address)?;
+        Ok(PooledAssociation {
+            pool: self,
+            key: ae_address.to_string(),
+            entry: Some(PoolEntry {
+                association,
+                proposed,
+                since: Instant::now(),
+            }),
+        })
+    }
+
+    /// Retrieve the number of idle associations currently in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.lock().values().map(Vec::len).sum()
+    }
+
+    /// Gracefully release all idle associations in the pool.
+    ///
+    /// Associations currently handed out are not affected,
+    /// and may still be returned to the pool afterwards.
+    pub fn release_all(&self) {
+        let idle: Vec<_> = self.lock().drain().collect();
+        for (ae_address, entries) in idle {
+            for entry in entries {
+                if let Err(e) = entry.association.release() {
+                    debug!("Failed to release association to {}: {}", ae_address, e);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<PoolEntry>>> {
+        // the map remains consistent even if a thread panicked
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take the most recently used idle association to the given peer
+    fn take_idle(&self, ae_address: &str) -> Option<PoolEntry> {
+        self.lock().get_mut(ae_address)?.pop()
+    }
+
+    /// Whether the abstract syntax was proposed in the pooled association,
+    /// with the given transfer syntaxes unless there are none
+    fn covers(
+        &self,
+        entry: &PoolEntry,
+        abstract_syntax_uid: &str,
+        transfer_syntaxes: &[String],
+    ) -> bool {
+        if transfer_syntaxes.is_empty() {
+            self.options.proposes_abstract_syntax(abstract_syntax_uid)
+                || entry
+                    .proposed
+                    .iter()
+                    .any(|(uid, _)| uid == abstract_syntax_uid)
+        } else {
+            entry
+                .proposed
+                .iter()
+                .any(|(uid, ts)| uid == abstract_syntax_uid && ts == transfer_syntaxes)
+        }
+    }
+
+    fn is_alive(association: &mut ClientAssociation<TcpStream>) -> bool {
+        match association.echo() {
+            Ok(status) => status == 0,
+            // verification was rejected by the peer, cannot check
+            Err(Error::NoVerificationContext { .. }) => true,
+            Err(e) => {
+                debug!("C-ECHO liveness check failed: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Put an association back into the pool,
+    /// or release it if there is no more room
+    fn put_back(&self, ae_address: String, mut entry: PoolEntry) {
+        entry.since = Instant::now();
+        let rejected = {
+            let mut idle = self.lock();
+            let entries = idle.entry(ae_address).or_default();
+            if entries.len() < self.max_idle_per_peer {
+                entries.push(entry);
+                None
+            } else {
+                Some(entry)
+            }
+        };
+        if let Some(entry) = rejected {
+            let _ = entry.association.release();
+        }
+    }
+}
+
+impl Drop for ClientAssociationPool {
+    fn drop(&mut self) {
+        self.release_all();
+    }
+}
+
+/// An association handed out by a [`ClientAssociationPool`].
+///
+/// It dereferences to the underlying [`ClientAssociation`],
+/// and is returned to the pool when dropped.
+/// Associations left in an inconsistent state
+/// (such as in the middle of a DIMSE message exchange)
+/// should be removed from the pool
+/// via [`discard`](Self::discard) or [`abort`](Self::abort) instead.
+#[derive(Debug)]
+pub struct PooledAssociation<'a> {
+    pool: &'a ClientAssociationPool,
+    key: String,
+    entry: Option<PoolEntry>,
+}
+
+impl PooledAssociation<'_> {
+    /// Gracefully release the association
+    /// instead of returning it to the pool.
+    pub fn discard(mut self) -> Result<()> {
+        let entry = self.entry.take().expect("pooled association is present");
+        entry.association.release()
+    }
+
+    /// Abort the association
+    /// instead of returning it to the pool.
+    pub fn abort(mut self) -> Result<()> {
+        let entry = self.entry.take().expect("pooled association is present");
+        entry.association.abort()
+    }
+}
+
+impl Deref for PooledAssociation<'_> {
+    type Target = ClientAssociation<TcpStream>;
+
+    fn deref(&self) -> &Self::Target {
+        &self
+            .entry
+            .as_ref()
+            .expect("pooled association is present")
+            .association
+    }
+}
+
+impl DerefMut for PooledAssociation<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self
+            .entry
+            .as_mut()
+            .expect("pooled association is present")
+            .association
+    }
+}
+
+impl Drop for PooledAssociation<'_> {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            self.pool.put_back(std::mem::take(&mut self.key), entry);
+        }
+    }
+}
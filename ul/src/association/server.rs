@@ -13,10 +13,11 @@ use std::{io::Write, net::TcpStream};
 
 use crate::association::private::SyncAssociationSealed;
 use crate::association::{
-    AbortedSnafu, Association, CloseSocket, MissingAbstractSyntaxSnafu, RejectedSnafu,
-    SendPduSnafu, SocketOptions, SyncAssociation, UnexpectedPduSnafu, UnknownPduSnafu,
-    WireSendSnafu, encode_pdu, read_pdu_from_wire,
+    AbortedSnafu, Association, CloseSocket, MissingAbstractSyntaxSnafu, PDataReader, PDataWriter,
+    RejectedSnafu, SendPduSnafu, SocketOptions, SyncAssociation, UnexpectedPduSnafu,
+    UnknownPduSnafu, WireSendSnafu, encode_pdu, read_pdu_from_wire_captured,
 };
+use crate::capture::{Direction, PduCapture};
 use dicom_encoding::transfer_syntax::TransferSyntaxIndex;
 use dicom_transfer_syntax_registry::TransferSyntaxRegistry;
 use snafu::{ResultExt, ensure};
@@ -28,14 +29,19 @@ use crate::{
     pdu::{
         AbortRQServiceProviderReason, AbortRQSource, AssociationAC, AssociationRJ,
         AssociationRJResult, AssociationRJServiceUserReason, AssociationRJSource, AssociationRQ,
-        DEFAULT_MAX_PDU, PDU_HEADER_SIZE, Pdu, PresentationContextResult,
-        PresentationContextResultReason, UserIdentity, UserVariableItem, write_pdu,
+        DEFAULT_MAX_PDU, PDU_HEADER_SIZE, Pdu, PresentationContextProposed,
+        PresentationContextResult, PresentationContextResultReason, UserIdentity, UserVariableItem,
+        write_pdu,
     },
 };
 #[cfg(feature = "sync-tls")]
 use tracing::{error, warn};
 
-use super::{Error, Result, uid::trim_uid};
+use super::{
+    Error, Result,
+    uid::trim_uid,
+    verification::{VERIFICATION_SOP_CLASS, auto_echo_response},
+};
 
 #[cfg(feature = "async")]
 use crate::association::AsyncAssociation;
@@ -111,6 +117,102 @@ impl AccessControl for AcceptCalledAeTitle {
     }
 }
 
+/// The parts of an incoming association request
+/// given to an association policy
+/// (see [`ServerAssociationOptions::association_policy`]).
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct AssociationRequestInfo<'a> {
+    /// the AE title of the requesting node
+    pub calling_ae_title: &'a str,
+    /// the AE title called by the requesting node
+    pub called_ae_title: &'a str,
+    /// the user identity of the request, if any
+    pub user_identity: Option<&'a UserIdentity>,
+    /// the presentation contexts proposed
+    pub presentation_contexts: &'a [PresentationContextProposed],
+}
+
+/// The decision of an association policy on one presentation context.
+#[derive(Debug, Default, Clone, Eq, Hash, PartialEq)]
+pub enum PresentationContextDecision {
+    /// Decide as without a policy,
+    /// according to the abstract syntaxes and transfer syntaxes
+    /// of the association options
+    #[default]
+    Default,
+    /// Accept the abstract syntax,
+    /// with the first transfer syntax proposed which is supported
+    Accept,
+    /// Accept the abstract syntax with the given transfer syntax,
+    /// provided that it was proposed
+    AcceptWith(String),
+    /// Reject the presentation context for the given reason
+    /// (a reason of acceptance is taken as a user rejection)
+    Reject(PresentationContextResultReason),
+}
+
+/// The decision of an association policy on an association request.
+///
+/// Built with [`accept`](AssociationDecision::accept)
+/// or [`reject`](AssociationDecision::reject),
+/// and a decision for each presentation context
+/// which should not be decided as by default.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct AssociationDecision {
+    rejection: Option<AssociationRJServiceUserReason>,
+    presentation_contexts: Vec<(u8, PresentationContextDecision)>,
+}
+
+impl AssociationDecision {
+    /// Accept the association,
+    /// deciding on presentation contexts as by default.
+    pub fn accept() -> Self {
+        Self::default()
+    }
+
+    /// Reject the association as a whole (permanently).
+    pub fn reject(reason: AssociationRJServiceUserReason) -> Self {
+        AssociationDecision {
+            rejection: Some(reason),
+            presentation_contexts: Vec::new(),
+        }
+    }
+
+    /// Decide on the presentation context with the given identifier.
+    pub fn with_presentation_context(
+        mut self,
+        id: u8,
+        decision: PresentationContextDecision,
+    ) -> Self {
+        self.presentation_contexts.retain(|(pc_id, _)| *pc_id != id);
+        self.presentation_contexts.push((id, decision));
+        self
+    }
+
+    /// The decision on the presentation context with the given identifier
+    fn presentation_context(&self, id: u8) -> &PresentationContextDecision {
+        self.presentation_contexts
+            .iter()
+            .find(|(pc_id, _)| *pc_id == id)
+            .map_or(&PresentationContextDecision::Default, |(_, decision)| {
+                decision
+            })
+    }
+}
+
+/// A shareable association policy function
+#[derive(Clone)]
+struct AssociationPolicy(
+    std::sync::Arc<dyn Fn(&AssociationRequestInfo<'_>) -> AssociationDecision + Send + Sync>,
+);
+
+impl std::fmt::Debug for AssociationPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AssociationPolicy")
+    }
+}
+
 /// Interface for negotiation of certain aspects of the association.
 pub trait Negotiation {
     /// User-provided extended negotiation. The result of
@@ -558,10 +660,16 @@ pub struct ServerAssociationOptions<'a, A, N> {
     strict: bool,
     /// whether to accept unknown abstract syntaxes
     promiscuous: bool,
+    /// whether to respond to C-ECHO requests automatically
+    auto_echo: bool,
     /// extended negotiation handler
     negotiation: N,
     /// Options for the underlying TCP socket
     socket_options: SocketOptions,
+    /// where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
+    /// the policy deciding on each association request, if any
+    association_policy: Option<AssociationPolicy>,
     /// TLS configuration for the underlying TCP socket
     #[cfg(feature = "sync-tls")]
     tls_config: Option<std::sync::Arc<rustls::ServerConfig>>,
@@ -579,8 +687,11 @@ impl Default for ServerAssociationOptions<'_, AcceptAny, DefaultNegotiation> {
             max_pdu_length: DEFAULT_MAX_PDU,
             strict: true,
             promiscuous: false,
+            auto_echo: false,
             negotiation: DefaultNegotiation,
             socket_options: SocketOptions::default(),
+            capture: None,
+            association_policy: None,
             #[cfg(feature = "sync-tls")]
             tls_config: None,
         }
@@ -633,9 +744,12 @@ where
             max_pdu_length,
             strict,
             promiscuous,
+            auto_echo,
             ae_access_control: _,
             negotiation,
             socket_options,
+            capture,
+            association_policy,
             #[cfg(feature = "sync-tls")]
             tls_config,
         } = self;
@@ -650,8 +764,11 @@ where
             max_pdu_length,
             strict,
             promiscuous,
+            auto_echo,
             negotiation,
             socket_options,
+            capture,
+            association_policy,
             #[cfg(feature = "sync-tls")]
             tls_config,
         }
@@ -712,6 +829,19 @@ where
         self
     }
 
+    /// Override automatic echo responses:
+    /// whether to respond to verification requests (C-ECHO) automatically.
+    ///
+    /// When enabled, the Verification SOP class is always accepted,
+    /// and any C-ECHO request obtained via `receive`
+    /// is answered with a success status
+    /// without being handed to the caller.
+    /// The default is `false`.
+    pub fn auto_echo(mut self, auto_echo: bool) -> Self {
+        self.auto_echo = auto_echo;
+        self
+    }
+
     /// Set the read timeout for the underlying TCP socket
     ///
     /// This is used to set both the read and write timeout.
@@ -738,6 +868,64 @@ where
         }
     }
 
+    /// Record every PDU sent and received by the associations established
+    /// to the given capture,
+    /// starting with the association request.
+    ///
+    /// See the [`capture`](crate::capture) module for details.
+    pub fn pdu_capture(mut self, capture: PduCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Decide on each association request with the given policy.
+    ///
+    /// The policy is called with the parts of the request
+    /// once the [access control](AccessControl) gives clearance,
+    /// and may reject the association
+    /// or decide on each of the presentation contexts proposed,
+    /// instead of the static list of abstract syntaxes of these options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use dicom_ul::association::server::{
+    /// #     AssociationDecision, PresentationContextDecision, ServerAssociationOptions,
+    /// # };
+    /// # use dicom_ul::pdu::{AssociationRJServiceUserReason, PresentationContextResultReason};
+    /// let options = ServerAssociationOptions::new()
+    ///     .with_abstract_syntax("1.2.840.10008.1.1")
+    ///     .association_policy(|request| {
+    ///         if !request.calling_ae_title.starts_with("MODALITY") {
+    ///             return AssociationDecision::reject(
+    ///                 AssociationRJServiceUserReason::CallingAETitleNotRecognized,
+    ///             );
+    ///         }
+    ///         // accept any storage, but not study root queries
+    ///         request.presentation_contexts.iter().fold(
+    ///             AssociationDecision::accept(),
+    ///             |decision, pc| match pc.abstract_syntax.as_str() {
+    ///                 uid if uid.starts_with("1.2.840.10008.5.1.4.1.1.") => decision
+    ///                     .with_presentation_context(pc.id, PresentationContextDecision::Accept),
+    ///                 "1.2.840.10008.5.1.4.1.2.2.1" => decision.with_presentation_context(
+    ///                     pc.id,
+    ///                     PresentationContextDecision::Reject(
+    ///                         PresentationContextResultReason::UserRejection,
+    ///                     ),
+    ///                 ),
+    ///                 _ => decision,
+    ///             },
+    ///         )
+    ///     });
+    /// ```
+    pub fn association_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(&AssociationRequestInfo<'_>) -> AssociationDecision + Send + Sync + 'static,
+    {
+        self.association_policy = Some(AssociationPolicy(std::sync::Arc::new(policy)));
+        self
+    }
+
     /// Set the extended negotiation handler
     pub fn with_negotiation<NN>(self, negotiation: NN) -> ServerAssociationOptions<'a, A, NN>
     where
@@ -753,8 +941,11 @@ where
             max_pdu_length,
             strict,
             promiscuous,
+            auto_echo,
             negotiation: _,
             socket_options,
+            capture,
+            association_policy,
             #[cfg(feature = "sync-tls")]
             tls_config,
         } = self;
@@ -769,8 +960,11 @@ where
             max_pdu_length,
             strict,
             promiscuous,
+            auto_echo,
             negotiation,
             socket_options,
+            capture,
+            association_policy,
             #[cfg(feature = "sync-tls")]
             tls_config,
         }
@@ -921,13 +1115,75 @@ where
                         Err((pdu, RejectedSnafu { association_rj }.build()))
                     })?;
 
+                let decision = self
+                    .association_policy
+                    .as_ref()
+                    .map(|policy| {
+                        (policy.0)(&AssociationRequestInfo {
+                            calling_ae_title: &calling_ae_title,
+                            called_ae_title: &called_ae_title,
+                            user_identity: user_identity.as_ref(),
+                            presentation_contexts: &presentation_contexts,
+                        })
+                    })
+                    .unwrap_or_default();
+                if let Some(reason) = decision.rejection.clone() {
+                    let association_rj = AssociationRJ {
+                        result: AssociationRJResult::Permanent,
+                        source: AssociationRJSource::ServiceUser(reason),
+                    };
+                    let pdu = Pdu::AssociationRJ(association_rj.clone());
+                    return Err((pdu, RejectedSnafu { association_rj }.build()));
+                }
+
                 let presentation_contexts_negotiated: Vec<_> = presentation_contexts
                     .into_iter()
                     .map(|pc| {
                         let abstract_syntax = trim_uid(Cow::from(pc.abstract_syntax));
-                        if !self.abstract_syntax_uids.contains(&abstract_syntax)
-                            && !self.promiscuous
-                        {
+                        let supported = match decision.presentation_context(pc.id) {
+                            PresentationContextDecision::Default => {
+                                self.promiscuous
+                                    || self.abstract_syntax_uids.contains(&abstract_syntax)
+                                    || (self.auto_echo && abstract_syntax == VERIFICATION_SOP_CLASS)
+                            }
+                            PresentationContextDecision::Accept => true,
+                            PresentationContextDecision::AcceptWith(ts) => {
+                                let ts = trim_uid(Cow::from(ts.as_str()));
+                                let proposed = pc
+                                    .transfer_syntaxes
+                                    .iter()
+                                    .any(|proposed| trim_uid(Cow::from(proposed.as_str())) == ts);
+                                let (transfer_syntax, reason) = if proposed {
+                                    (ts.to_string(), PresentationContextResultReason::Acceptance)
+                                } else {
+                                    (
+                                        "1.2.840.10008.1.2".to_string(),
+                                        PresentationContextResultReason::TransferSyntaxesNotSupported,
+                                    )
+                                };
+                                return PresentationContextNegotiated {
+                                    id: pc.id,
+                                    reason,
+                                    transfer_syntax,
+                                    abstract_syntax: abstract_syntax.to_string(),
+                                };
+                            }
+                            PresentationContextDecision::Reject(reason) => {
+                                let reason = match reason {
+                                    PresentationContextResultReason::Acceptance => {
+                                        PresentationContextResultReason::UserRejection
+                                    }
+                                    reason => reason.clone(),
+                                };
+                                return PresentationContextNegotiated {
+                                    id: pc.id,
+                                    reason,
+                                    transfer_syntax: "1.2.840.10008.1.2".to_string(),
+                                    abstract_syntax: abstract_syntax.to_string(),
+                                };
+                            }
+                        };
+                        if !supported {
                             return PresentationContextNegotiated {
                                 id: pc.id,
                                 reason: PresentationContextResultReason::AbstractSyntaxNotSupported,
@@ -1006,12 +1262,7 @@ where
     }
 
     /// Negotiate an association with the given TCP stream.
-    pub fn establish(&self, mut socket: TcpStream) -> Result<ServerAssociation<TcpStream>> {
-        ensure!(
-            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
-            MissingAbstractSyntaxSnafu
-        );
-
+    pub fn establish(&self, socket: TcpStream) -> Result<ServerAssociation<TcpStream>> {
         socket
             .set_read_timeout(self.socket_options.read_timeout)
             .context(super::SetReadTimeoutSnafu)?;
@@ -1019,14 +1270,30 @@ where
             .set_write_timeout(self.socket_options.write_timeout)
             .context(super::SetWriteTimeoutSnafu)?;
 
+        self.establish_stream(socket)
+    }
+
+    /// Negotiate an association over an already connected stream.
+    ///
+    /// Socket timeouts are not applied to the stream.
+    pub(crate) fn establish_stream<S>(&self, mut socket: S) -> Result<ServerAssociation<S>>
+    where
+        S: std::io::Read + Write,
+    {
+        ensure!(
+            !self.abstract_syntax_uids.is_empty() || self.promiscuous || self.auto_echo,
+            MissingAbstractSyntaxSnafu
+        );
+
         let mut read_buffer = BytesMut::with_capacity(
             (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
         );
-        let msg = read_pdu_from_wire(
+        let msg = read_pdu_from_wire_captured(
             &mut socket,
             &mut read_buffer,
             self.max_pdu_length,
             self.strict,
+            self.capture.as_ref(),
         );
         // If we're compiling with the sync-tls feature, check to see if the error
         // may have been caused by the client associating with TLS but the server
@@ -1058,6 +1325,7 @@ where
             )) => {
                 write_pdu(&mut write_buffer, &pdu).context(SendPduSnafu)?;
                 socket.write_all(&write_buffer).context(WireSendSnafu)?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                 Ok(ServerAssociation {
                     presentation_contexts,
                     requestor_max_pdu_length: peer_max_pdu_length,
@@ -1069,12 +1337,15 @@ where
                     read_buffer,
                     user_variables,
                     called_ae_title,
+                    auto_echo: self.auto_echo,
+                    capture: self.capture.clone(),
                 })
             }
             Err((pdu, err)) => {
                 // send the rejection/abort PDU
                 write_pdu(&mut write_buffer, &pdu).context(SendPduSnafu)?;
                 socket.write_all(&write_buffer).context(WireSendSnafu)?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                 Err(err)
             }
         }
@@ -1084,7 +1355,7 @@ where
     #[cfg(feature = "sync-tls")]
     pub fn establish_tls(&self, mut socket: TcpStream) -> Result<ServerAssociation<TlsStream>> {
         ensure!(
-            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
+            !self.abstract_syntax_uids.is_empty() || self.promiscuous || self.auto_echo,
             MissingAbstractSyntaxSnafu
         );
         let tls_config = self
@@ -1105,11 +1376,12 @@ where
             (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
         );
 
-        let msg = read_pdu_from_wire(
+        let msg = read_pdu_from_wire_captured(
             &mut tls_stream,
             &mut read_buffer,
             self.max_pdu_length,
             self.strict,
+            self.capture.as_ref(),
         )?;
         let mut write_buffer: Vec<u8> =
             Vec::with_capacity((DEFAULT_MAX_PDU + PDU_HEADER_SIZE) as usize);
@@ -1126,6 +1398,7 @@ where
             )) => {
                 write_pdu(&mut write_buffer, &pdu).context(SendPduSnafu)?;
                 tls_stream.write_all(&write_buffer).context(WireSendSnafu)?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                 Ok(ServerAssociation {
                     presentation_contexts,
                     requestor_max_pdu_length: peer_max_pdu_length,
@@ -1137,12 +1410,15 @@ where
                     read_buffer,
                     user_variables,
                     called_ae_title,
+                    auto_echo: self.auto_echo,
+                    capture: self.capture.clone(),
                 })
             }
             Err((pdu, err)) => {
                 // send the rejection/abort PDU
                 write_pdu(&mut write_buffer, &pdu).context(SendPduSnafu)?;
                 tls_stream.write_all(&write_buffer).context(WireSendSnafu)?;
+                PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                 Err(err)
             }
         }
@@ -1210,6 +1486,10 @@ pub struct ServerAssociation<S> {
     read_buffer: bytes::BytesMut,
     /// User variables received from the peer
     user_variables: Vec<UserVariableItem>,
+    /// whether to respond to C-ECHO requests automatically
+    auto_echo: bool,
+    /// Where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
 }
 
 // compatibility filler, remove in 0.10.0
@@ -1362,16 +1642,28 @@ where
         )?;
         self.socket
             .write_all(&self.write_buffer)
-            .context(WireSendSnafu)
+            .context(WireSendSnafu)?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.write_buffer);
+        Ok(())
     }
 
     fn receive(&mut self) -> Result<Pdu> {
-        read_pdu_from_wire(
-            &mut self.socket,
-            &mut self.read_buffer,
-            self.acceptor_max_pdu_length,
-            self.strict,
-        )
+        loop {
+            let pdu = read_pdu_from_wire_captured(
+                &mut self.socket,
+                &mut self.read_buffer,
+                self.acceptor_max_pdu_length,
+                self.strict,
+                self.capture.as_ref(),
+            )?;
+            if self.auto_echo {
+                if let Some(rsp) = auto_echo_response(&self.presentation_contexts, &pdu) {
+                    SyncAssociationSealed::send(self, &rsp)?;
+                    continue;
+                }
+            }
+            return Ok(pdu);
+        }
     }
 
     fn close(&mut self) -> std::io::Result<()> {
@@ -1395,6 +1687,24 @@ where
         } = self;
         (socket, read_buffer)
     }
+
+    fn send_pdata(&mut self, presentation_context_id: u8) -> PDataWriter<&mut S> {
+        PDataWriter::new(
+            &mut self.socket,
+            presentation_context_id,
+            self.requestor_max_pdu_length,
+        )
+        .with_capture(self.capture.clone())
+    }
+
+    fn receive_pdata(&mut self) -> PDataReader<'_, &mut S> {
+        PDataReader::new(
+            &mut self.socket,
+            self.acceptor_max_pdu_length,
+            &mut self.read_buffer,
+        )
+        .with_capture(self.capture.clone())
+    }
 }
 
 /// Check that a transfer syntax repository
@@ -1486,11 +1796,22 @@ where
     /// Negotiate an association with the given TCP stream.
     pub async fn establish_async(
         &self,
-        mut socket: tokio::net::TcpStream,
+        socket: tokio::net::TcpStream,
     ) -> Result<AsyncServerAssociation<tokio::net::TcpStream>> {
+        self.establish_stream_async(socket).await
+    }
+
+    /// Negotiate an association over an already connected stream.
+    pub(crate) async fn establish_stream_async<S>(
+        &self,
+        mut socket: S,
+    ) -> Result<AsyncServerAssociation<S>>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
         use tokio::io::AsyncWriteExt;
         ensure!(
-            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
+            !self.abstract_syntax_uids.is_empty() || self.promiscuous || self.auto_echo,
             MissingAbstractSyntaxSnafu
         );
         let read_timeout = self.socket_options.read_timeout;
@@ -1498,11 +1819,12 @@ where
             let mut read_buffer = BytesMut::with_capacity(
                 (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
             );
-            let pdu = match super::read_pdu_from_wire_async(
+            let pdu = match super::read_pdu_from_wire_async_captured(
                 &mut socket,
                 &mut read_buffer,
                 self.max_pdu_length,
                 self.strict,
+                self.capture.as_ref(),
             )
             .await
             {
@@ -1545,6 +1867,7 @@ where
                         .write_all(&write_buffer)
                         .await
                         .context(WireSendSnafu)?;
+                    PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                     Ok(AsyncServerAssociation {
                         presentation_contexts,
                         requestor_max_pdu_length: peer_max_pdu_length,
@@ -1558,6 +1881,8 @@ where
                         write_timeout: self.socket_options.write_timeout,
                         user_variables,
                         called_ae_title,
+                        auto_echo: self.auto_echo,
+                        capture: self.capture.clone(),
                     })
                 }
                 Err((pdu, err)) => {
@@ -1567,6 +1892,7 @@ where
                         .write_all(&write_buffer)
                         .await
                         .context(WireSendSnafu)?;
+                    PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                     Err(err)
                 }
             }
@@ -1583,7 +1909,7 @@ where
         use tokio::io::AsyncWriteExt;
 
         ensure!(
-            !self.abstract_syntax_uids.is_empty() || self.promiscuous,
+            !self.abstract_syntax_uids.is_empty() || self.promiscuous || self.auto_echo,
             MissingAbstractSyntaxSnafu
         );
         let tls_config = self
@@ -1596,11 +1922,12 @@ where
             let mut read_buffer = BytesMut::with_capacity(
                 (self.max_pdu_length.min(LARGE_PDU_SIZE) + PDU_HEADER_SIZE) as usize,
             );
-            let pdu = super::read_pdu_from_wire_async(
+            let pdu = super::read_pdu_from_wire_async_captured(
                 &mut socket,
                 &mut read_buffer,
                 self.max_pdu_length,
                 self.strict,
+                self.capture.as_ref(),
             )
             .await?;
 
@@ -1622,6 +1949,7 @@ where
                         .write_all(&write_buffer)
                         .await
                         .context(WireSendSnafu)?;
+                    PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                     Ok(AsyncServerAssociation {
                         presentation_contexts,
                         requestor_max_pdu_length: peer_max_pdu_length,
@@ -1635,6 +1963,8 @@ where
                         write_timeout: self.socket_options.write_timeout,
                         user_variables,
                         called_ae_title,
+                        auto_echo: self.auto_echo,
+                        capture: self.capture.clone(),
                     })
                 }
                 Err((pdu, err)) => {
@@ -1644,6 +1974,7 @@ where
                         .write_all(&write_buffer)
                         .await
                         .context(WireSendSnafu)?;
+                    PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &write_buffer);
                     Err(err)
                 }
             }
@@ -1690,6 +2021,10 @@ pub struct AsyncServerAssociation<S> {
     write_timeout: Option<std::time::Duration>,
     /// User variables received from the peer
     user_variables: Vec<UserVariableItem>,
+    /// whether to respond to C-ECHO requests automatically
+    auto_echo: bool,
+    /// Where to record the PDUs exchanged, if capturing
+    capture: Option<PduCapture>,
 }
 
 #[cfg(feature = "async")]
@@ -1756,21 +2091,34 @@ where
                 .await
                 .context(WireSendSnafu)
         })
-        .await
+        .await?;
+        PduCapture::record_opt(self.capture.as_ref(), Direction::Sent, &self.write_buffer);
+        Ok(())
     }
 
     /// Read a PDU message from the other intervenient.
     async fn receive(&mut self) -> Result<Pdu> {
-        super::timeout(self.read_timeout, async {
-            super::read_pdu_from_wire_async(
-                &mut self.socket,
-                &mut self.read_buffer,
-                self.acceptor_max_pdu_length,
-                self.strict,
-            )
-            .await
-        })
-        .await
+        use crate::association::private::AsyncAssociationSealed;
+        loop {
+            let pdu = super::timeout(self.read_timeout, async {
+                super::read_pdu_from_wire_async_captured(
+                    &mut self.socket,
+                    &mut self.read_buffer,
+                    self.acceptor_max_pdu_length,
+                    self.strict,
+                    self.capture.as_ref(),
+                )
+                .await
+            })
+            .await?;
+            if self.auto_echo {
+                if let Some(rsp) = auto_echo_response(&self.presentation_contexts, &pdu) {
+                    AsyncAssociationSealed::send(self, &rsp).await?;
+                    continue;
+                }
+            }
+            return Ok(pdu);
+        }
     }
 
     async fn close(&mut self) -> std::io::Result<()> {
@@ -1796,6 +2144,27 @@ where
         } = self;
         (socket, read_buffer)
     }
+
+    fn send_pdata(
+        &mut self,
+        presentation_context_id: u8,
+    ) -> crate::association::AsyncPDataWriter<&mut S> {
+        crate::association::AsyncPDataWriter::new(
+            &mut self.socket,
+            presentation_context_id,
+            self.requestor_max_pdu_length,
+        )
+        .with_capture(self.capture.clone())
+    }
+
+    fn receive_pdata(&mut self) -> PDataReader<'_, &mut S> {
+        PDataReader::new(
+            &mut self.socket,
+            self.acceptor_max_pdu_length,
+            &mut self.read_buffer,
+        )
+        .with_capture(self.capture.clone())
+    }
 }
 
 // compatibility filler, remove in 0.10.0
@@ -1907,6 +2276,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::association::read_pdu_from_wire;
 
     #[test]
     fn test_choose_supported() {
@@ -1928,6 +2298,112 @@ mod tests {
         );
     }
 
+    #[test]
+    fn association_policy_decides_presentation_contexts() {
+        use crate::{ClientAssociationOptions, test_utils::association_pair};
+
+        const CT_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+        const MR_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4";
+        const EXPLICIT_VR_LE: &str = "1.2.840.10008.1.2.1";
+
+        let (_scu, scp) = association_pair(
+            ClientAssociationOptions::new()
+                .calling_ae_title("MODALITY")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+                .with_abstract_syntax(CT_STORAGE)
+                .with_abstract_syntax(MR_STORAGE),
+            &ServerAssociationOptions::new()
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+                .association_policy(|request| {
+                    assert_eq!(request.calling_ae_title, "MODALITY");
+                    assert_eq!(request.presentation_contexts.len(), 3);
+                    let id = |uid| {
+                        request
+                            .presentation_contexts
+                            .iter()
+                            .find(|pc| pc.abstract_syntax == uid)
+                            .unwrap()
+                            .id
+                    };
+                    AssociationDecision::accept()
+                        .with_presentation_context(
+                            id(CT_STORAGE),
+                            PresentationContextDecision::AcceptWith(EXPLICIT_VR_LE.to_string()),
+                        )
+                        .with_presentation_context(
+                            id(MR_STORAGE),
+                            PresentationContextDecision::Reject(
+                                PresentationContextResultReason::UserRejection,
+                            ),
+                        )
+                }),
+        )
+        .unwrap();
+
+        let reasons: Vec<_> = scp
+            .presentation_contexts()
+            .iter()
+            .map(|pc| {
+                (
+                    pc.abstract_syntax.as_str(),
+                    pc.reason.clone(),
+                    pc.transfer_syntax.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (
+                    VERIFICATION_SOP_CLASS,
+                    PresentationContextResultReason::Acceptance,
+                    EXPLICIT_VR_LE
+                ),
+                (
+                    CT_STORAGE,
+                    PresentationContextResultReason::Acceptance,
+                    EXPLICIT_VR_LE
+                ),
+                (
+                    MR_STORAGE,
+                    PresentationContextResultReason::UserRejection,
+                    "1.2.840.10008.1.2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn association_policy_rejects_association() {
+        use crate::{ClientAssociationOptions, test_utils::association_pair};
+
+        let result = association_pair(
+            ClientAssociationOptions::new()
+                .calling_ae_title("UNKNOWN")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS),
+            &ServerAssociationOptions::new()
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+                .association_policy(|request| {
+                    if request.calling_ae_title == "UNKNOWN" {
+                        AssociationDecision::reject(
+                            AssociationRJServiceUserReason::CallingAETitleNotRecognized,
+                        )
+                    } else {
+                        AssociationDecision::accept()
+                    }
+                }),
+        );
+        let Err(Error::Rejected { association_rj, .. }) = result else {
+            panic!("association should be rejected");
+        };
+        assert_eq!(
+            association_rj.source,
+            AssociationRJSource::ServiceUser(
+                AssociationRJServiceUserReason::CallingAETitleNotRecognized
+            )
+        );
+    }
+
     impl<'a, A, N> ServerAssociationOptions<'a, A, N>
     where
         A: AccessControl,
@@ -1980,6 +2456,8 @@ mod tests {
                 strict: self.strict,
                 user_variables,
                 called_ae_title,
+                auto_echo: self.auto_echo,
+                capture: self.capture.clone(),
             })
         }
 
@@ -2041,6 +2519,8 @@ mod tests {
                 read_timeout: self.socket_options.read_timeout,
                 write_timeout: self.socket_options.write_timeout,
                 called_ae_title,
+                auto_echo: self.auto_echo,
+                capture: self.capture.clone(),
             })
         }
 
@@ -2086,6 +2566,8 @@ mod tests {
                 read_buffer,
                 user_variables,
                 called_ae_title,
+                auto_echo: self.auto_echo,
+                capture: self.capture.clone(),
             })
         }
 
@@ -2142,6 +2624,8 @@ mod tests {
                 write_timeout: self.socket_options.write_timeout,
                 user_variables,
                 called_ae_title,
+                auto_echo: self.auto_echo,
+                capture: self.capture.clone(),
             })
         }
     }
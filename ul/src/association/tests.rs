@@ -127,10 +127,9 @@ mod successive_pdus_during_client_association {
                 .with_abstract_syntax(VERIFICATION)
                 .ae_title("THIS-SCP");
 
-            let association = server_options
+            server_options
                 .establish_with_extra_pdus(stream, vec![server_pdu])
-                .unwrap();
-            association
+                .unwrap()
         });
 
         // Give server time to start
@@ -248,11 +247,10 @@ mod successive_pdus_during_client_association {
                 .with_abstract_syntax(VERIFICATION)
                 .ae_title("THIS-SCP");
 
-            let association = server_options
+            server_options
                 .establish_with_extra_pdus_async(stream, vec![server_pdu])
                 .await
-                .unwrap();
-            association
+                .unwrap()
         });
 
         // Give server time to start
@@ -308,10 +306,9 @@ mod successive_pdus_during_client_association {
                 .with_abstract_syntax(VERIFICATION)
                 .ae_title("THIS-SCP");
 
-            let association = server_options
+            server_options
                 .establish_with_extra_pdus(stream, vec![echo_pdu])
-                .unwrap();
-            association
+                .unwrap()
         });
         // Give server time to start
         std::thread::sleep(std::time::Duration::from_millis(10));
@@ -375,11 +372,10 @@ mod successive_pdus_during_client_association {
                 .with_abstract_syntax(VERIFICATION)
                 .ae_title("THIS-SCP");
 
-            let association = server_options
+            server_options
                 .establish_with_extra_pdus_async(stream, vec![echo_pdu])
                 .await
-                .unwrap();
-            association
+                .unwrap()
         });
 
         // Give server time to start
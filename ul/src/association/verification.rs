@@ -0,0 +1,302 @@
+//! Minimal support for the Verification service (C-ECHO),
+//! used for checking whether an association is still alive.
+//!
+//! Command sets are encoded and decoded by hand in
+//! implicit VR little endian,
+//! so that this does not depend on a full DICOM object implementation.
+use super::{
+    CloseSocket, InvalidEchoResponseSnafu, NoVerificationContextSnafu, Result, SyncAssociation,
+    UnexpectedPduSnafu,
+};
+use crate::Pdu;
+use crate::pdu::{
+    PDataValue, PDataValueType, PresentationContextNegotiated, PresentationContextResultReason,
+};
+use snafu::{OptionExt, ensure};
+
+/// The Verification SOP Class UID
+pub(crate) const VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+
+/// Command Field value of a C-ECHO-RQ
+const C_ECHO_RQ: u16 = 0x0030;
+/// Command Field value of a C-ECHO-RSP
+const C_ECHO_RSP: u16 = 0x8030;
+/// Command Data Set Type value for no data set
+const DATA_SET_ABSENT: u16 = 0x0101;
+
+/// The fields of a command set which are relevant to verification
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct EchoCommand {
+    pub command_field: Option<u16>,
+    pub message_id: Option<u16>,
+    pub message_id_being_responded_to: Option<u16>,
+    pub status: Option<u16>,
+}
+
+fn put_element(out: &mut Vec<u8>, element: u16, value: &[u8]) {
+    out.extend_from_slice(&0_u16.to_le_bytes());
+    out.extend_from_slice(&element.to_le_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// Encode a command set from the given command group elements,
+/// prepending the command group length.
+fn encode_command(elements: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (element, value) in elements {
+        put_element(&mut body, *element, value);
+    }
+    let mut out = Vec::with_capacity(body.len() + 12);
+    put_element(&mut out, 0x0000, &(body.len() as u32).to_le_bytes());
+    out.extend(body);
+    out
+}
+
+/// Encode a C-ECHO-RQ command set.
+pub(crate) fn echo_rq(message_id: u16) -> Vec<u8> {
+    encode_command(&[
+        (0x0002, b"1.2.840.10008.1.1\0"),
+        (0x0100, &C_ECHO_RQ.to_le_bytes()),
+        (0x0110, &message_id.to_le_bytes()),
+        (0x0800, &DATA_SET_ABSENT.to_le_bytes()),
+    ])
+}
+
+/// Encode a C-ECHO-RSP command set.
+pub(crate) fn echo_rsp(message_id_being_responded_to: u16, status: u16) -> Vec<u8> {
+    encode_command(&[
+        (0x0002, b"1.2.840.10008.1.1\0"),
+        (0x0100, &C_ECHO_RSP.to_le_bytes()),
+        (0x0120, &message_id_being_responded_to.to_le_bytes()),
+        (0x0800, &DATA_SET_ABSENT.to_le_bytes()),
+        (0x0900, &status.to_le_bytes()),
+    ])
+}
+
+/// Decode the relevant parts of a command set
+/// in implicit VR little endian.
+///
+/// Returns `None` if the command set is malformed.
+pub(crate) fn parse_command(mut data: &[u8]) -> Option<EchoCommand> {
+    let mut command = EchoCommand::default();
+    while !data.is_empty() {
+        let header = data.get(..8)?;
+        let group = u16::from_le_bytes([header[0], header[1]]);
+        let element = u16::from_le_bytes([header[2], header[3]]);
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let value = data.get(8..8 + len)?;
+        data = &data[8 + len..];
+        if group != 0x0000 {
+            return None;
+        }
+        let value_us = || -> Option<u16> { Some(u16::from_le_bytes(value.try_into().ok()?)) };
+        match element {
+            0x0100 => command.command_field = Some(value_us()?),
+            0x0110 => command.message_id = Some(value_us()?),
+            0x0120 => command.message_id_being_responded_to = Some(value_us()?),
+            0x0900 => command.status = Some(value_us()?),
+            _ => {}
+        }
+    }
+    Some(command)
+}
+
+/// Whether the presentation context with the given ID
+/// was accepted for the Verification SOP class.
+fn is_verification_context(
+    presentation_contexts: &[PresentationContextNegotiated],
+    presentation_context_id: u8,
+) -> bool {
+    presentation_contexts.iter().any(|pc| {
+        pc.id == presentation_context_id
+            && pc.reason == PresentationContextResultReason::Acceptance
+            && pc.abstract_syntax == VERIFICATION_SOP_CLASS
+    })
+}
+
+/// Build the response to the given PDU
+/// if it contains a complete C-ECHO request
+/// on an accepted verification presentation context.
+///
+/// Returns `None` for any other PDU.
+pub(crate) fn auto_echo_response(
+    presentation_contexts: &[PresentationContextNegotiated],
+    pdu: &Pdu,
+) -> Option<Pdu> {
+    let Pdu::PData { data } = pdu else {
+        return None;
+    };
+    let [pdv] = data.as_slice() else {
+        return None;
+    };
+    if pdv.value_type != PDataValueType::Command
+        || !pdv.is_last
+        || !is_verification_context(presentation_contexts, pdv.presentation_context_id)
+    {
+        return None;
+    }
+    let command = parse_command(&pdv.data)?;
+    if command.command_field != Some(C_ECHO_RQ) {
+        return None;
+    }
+    Some(Pdu::PData {
+        data: vec![PDataValue {
+            presentation_context_id: pdv.presentation_context_id,
+            value_type: PDataValueType::Command,
+            is_last: true,
+            data: echo_rsp(command.message_id?, 0),
+        }],
+    })
+}
+
+/// Obtain the ID of the accepted presentation context
+/// for the Verification SOP class, if any.
+pub(crate) fn verification_context_id<A>(association: &A) -> Option<u8>
+where
+    A: super::Association,
+{
+    association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| {
+            pc.reason == PresentationContextResultReason::Acceptance
+                && pc.abstract_syntax == VERIFICATION_SOP_CLASS
+        })
+        .map(|pc| pc.id)
+}
+
+/// Send a C-ECHO request through the given association
+/// and wait for its response,
+/// returning the status of the response.
+pub(crate) fn echo<A, S>(association: &mut A, message_id: u16) -> Result<u16>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let presentation_context_id =
+        verification_context_id(association).context(NoVerificationContextSnafu)?;
+
+    SyncAssociation::send(
+        association,
+        &Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: echo_rq(message_id),
+            }],
+        },
+    )?;
+
+    let mut command_data = Vec::new();
+    loop {
+        match SyncAssociation::receive(association)? {
+            Pdu::PData { data } => {
+                let mut done = false;
+                for pdv in data {
+                    ensure!(
+                        pdv.value_type == PDataValueType::Command,
+                        InvalidEchoResponseSnafu
+                    );
+                    command_data.extend(pdv.data);
+                    done |= pdv.is_last;
+                }
+                if done {
+                    break;
+                }
+            }
+            pdu => return UnexpectedPduSnafu { pdu }.fail(),
+        }
+    }
+
+    let command = parse_command(&command_data).context(InvalidEchoResponseSnafu)?;
+    ensure!(
+        command.command_field == Some(C_ECHO_RSP)
+            && command.message_id_being_responded_to == Some(message_id),
+        InvalidEchoResponseSnafu
+    );
+    command.status.context(InvalidEchoResponseSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn echo_command_round_trip() {
+        let rq = echo_rq(7);
+        // group length + 4 elements
+        assert_eq!(rq.len(), 12 + 26 + 10 + 10 + 10);
+        assert_eq!(
+            parse_command(&rq),
+            Some(EchoCommand {
+                command_field: Some(C_ECHO_RQ),
+                message_id: Some(7),
+                message_id_being_responded_to: None,
+                status: None,
+            })
+        );
+
+        let rsp = echo_rsp(7, 0);
+        assert_eq!(
+            parse_command(&rsp),
+            Some(EchoCommand {
+                command_field: Some(C_ECHO_RSP),
+                message_id: None,
+                message_id_being_responded_to: Some(7),
+                status: Some(0),
+            })
+        );
+    }
+
+    #[test]
+    fn auto_echo_response_only_for_echo_requests() {
+        let presentation_contexts = [
+            PresentationContextNegotiated {
+                id: 1,
+                reason: PresentationContextResultReason::Acceptance,
+                transfer_syntax: "1.2.840.10008.1.2".to_string(),
+                abstract_syntax: VERIFICATION_SOP_CLASS.to_string(),
+            },
+            PresentationContextNegotiated {
+                id: 3,
+                reason: PresentationContextResultReason::Acceptance,
+                transfer_syntax: "1.2.840.10008.1.2".to_string(),
+                abstract_syntax: "1.2.840.10008.5.1.4.1.1.7".to_string(),
+            },
+        ];
+        let pdu = |presentation_context_id, data| Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data,
+            }],
+        };
+
+        let rsp = auto_echo_response(&presentation_contexts, &pdu(1, echo_rq(5)));
+        assert_eq!(rsp, Some(pdu(1, echo_rsp(5, 0))));
+
+        // not on a verification presentation context
+        assert_eq!(
+            auto_echo_response(&presentation_contexts, &pdu(3, echo_rq(5))),
+            None
+        );
+        // not a C-ECHO request
+        assert_eq!(
+            auto_echo_response(&presentation_contexts, &pdu(1, echo_rsp(5, 0))),
+            None
+        );
+        assert_eq!(
+            auto_echo_response(&presentation_contexts, &Pdu::ReleaseRQ),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_truncated_command() {
+        let rq = echo_rq(1);
+        assert_eq!(parse_command(&rq[..rq.len() - 1]), None);
+    }
+}
@@ -0,0 +1,335 @@
+//! Capture of the PDUs exchanged in associations.
+//!
+//! A [`PduCapture`] given to [`ClientAssociationOptions`] or
+//! [`ServerAssociationOptions`]
+//! records every PDU sent or received by the associations established,
+//! including the association negotiation,
+//! exactly as found on the wire.
+//! This is meant for diagnosing interoperability issues with other nodes,
+//! with the `dicom-pdu-inspect` tool
+//! or through the [`CaptureReader`] in this module.
+//!
+//! A capture file starts with the 8 bytes of [`CAPTURE_MAGIC`],
+//! followed by one record per PDU:
+//!
+//! - the direction (1 byte: 0 for sent, 1 for received);
+//! - the time of capture in microseconds since the Unix epoch
+//!   (8 bytes, big endian);
+//! - the length of the PDU (4 bytes, big endian);
+//! - the PDU itself, including its 6-byte header.
+//!
+//! Failing to write to the capture does not affect the association:
+//! the error is logged once and capturing stops.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_ul::{ClientAssociationOptions, capture::PduCapture};
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let capture = PduCapture::create("echo.pducap")?;
+//! let association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax("1.2.840.10008.1.1")
+//!     .pdu_capture(capture)
+//!     .establish_with("ECHO-SCP@10.0.0.100:104")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`ClientAssociationOptions`]: crate::ClientAssociationOptions
+//! [`ServerAssociationOptions`]: crate::ServerAssociationOptions
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::pdu::{self, Pdu};
+
+/// The bytes at the start of every capture file
+pub const CAPTURE_MAGIC: [u8; 8] = *b"DCMPDU\x00\x01";
+
+/// The direction of a captured PDU
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// the PDU was sent to the peer
+    Sent,
+    /// the PDU was received from the peer
+    Received,
+}
+
+/// A shared sink of captured PDUs.
+///
+/// Clones write to the same destination,
+/// so that a single capture can follow several associations.
+#[derive(Clone)]
+pub struct PduCapture {
+    sink: Arc<Mutex<Option<Box<dyn Write + Send>>>>,
+}
+
+impl fmt::Debug for PduCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PduCapture").finish_non_exhaustive()
+    }
+}
+
+impl PduCapture {
+    /// Create a capture file at the given path,
+    /// replacing any existing file.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        PduCapture::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Capture PDUs into the given writer,
+    /// starting with the capture file header.
+    pub fn new(mut writer: impl Write + Send + 'static) -> io::Result<Self> {
+        writer.write_all(&CAPTURE_MAGIC)?;
+        Ok(PduCapture {
+            sink: Arc::new(Mutex::new(Some(Box::new(writer)))),
+        })
+    }
+
+    /// Record the bytes of a PDU.
+    pub(crate) fn record(&self, direction: Direction, pdu: &[u8]) {
+        let Ok(mut sink) = self.sink.lock() else {
+            return;
+        };
+        let Some(writer) = sink.as_mut() else {
+            return;
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_micros() as u64);
+        let direction = match direction {
+            Direction::Sent => 0u8,
+            Direction::Received => 1,
+        };
+        let result = (|| {
+            writer.write_all(&[direction])?;
+            writer.write_all(&timestamp.to_be_bytes())?;
+            writer.write_all(&(pdu.len() as u32).to_be_bytes())?;
+            writer.write_all(pdu)?;
+            writer.flush()
+        })();
+        if let Err(e) = result {
+            tracing::warn!("Could not write PDU capture, stopping capture: {}", e);
+            *sink = None;
+        }
+    }
+
+    /// Record the bytes of a PDU, if capturing.
+    pub(crate) fn record_opt(capture: Option<&PduCapture>, direction: Direction, pdu: &[u8]) {
+        if let Some(capture) = capture {
+            capture.record(direction, pdu);
+        }
+    }
+}
+
+/// A PDU read from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPdu {
+    /// whether the PDU was sent or received
+    pub direction: Direction,
+    /// the time of capture
+    pub timestamp: SystemTime,
+    /// the bytes of the PDU, including its header
+    pub data: Vec<u8>,
+}
+
+impl CapturedPdu {
+    /// Decode the captured bytes into a PDU.
+    pub fn pdu(&self) -> Result<Pdu, pdu::ReadError> {
+        let max_pdu_length =
+            (self.data.len() as u32).clamp(pdu::MINIMUM_PDU_SIZE, pdu::MAXIMUM_PDU_SIZE);
+        pdu::read_pdu(&self.data[..], max_pdu_length, false)?.ok_or_else(|| {
+            pdu::ReadError::NoPduAvailable {
+                backtrace: std::backtrace::Backtrace::capture(),
+            }
+        })
+    }
+}
+
+/// An iterator over the PDUs of a capture file.
+#[derive(Debug)]
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl CaptureReader<io::BufReader<File>> {
+    /// Open the capture file at the given path.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        CaptureReader::new(io::BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Start reading a capture,
+    /// checking its header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a PDU capture file",
+            ));
+        }
+        Ok(CaptureReader { reader })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<CapturedPdu>> {
+        let mut direction = [0; 1];
+        if self.reader.read(&mut direction)? == 0 {
+            return Ok(None);
+        }
+        let direction = match direction[0] {
+            0 => Direction::Sent,
+            1 => Direction::Received,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid PDU direction in capture",
+                ));
+            }
+        };
+        let mut timestamp = [0; 8];
+        self.reader.read_exact(&mut timestamp)?;
+        let timestamp = UNIX_EPOCH + Duration::from_micros(u64::from_be_bytes(timestamp));
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut data = vec![0; u32::from_be_bytes(len) as usize];
+        self.reader.read_exact(&mut data)?;
+        Ok(Some(CapturedPdu {
+            direction,
+            timestamp,
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CapturedPdu>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::write_pdu;
+
+    /// A writer which can be inspected after being moved into a capture
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_and_read_capture() {
+        let buffer = SharedBuffer::default();
+        let capture = PduCapture::new(buffer.clone()).unwrap();
+
+        let mut release = Vec::new();
+        write_pdu(&mut release, &Pdu::ReleaseRQ).unwrap();
+        capture.record(Direction::Sent, &release);
+        let mut reply = Vec::new();
+        write_pdu(&mut reply, &Pdu::ReleaseRP).unwrap();
+        capture.clone().record(Direction::Received, &reply);
+
+        let data = buffer.0.lock().unwrap().clone();
+        let records: Vec<_> = CaptureReader::new(&data[..])
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Sent);
+        assert_eq!(records[0].data, release);
+        assert_eq!(records[0].pdu().unwrap(), Pdu::ReleaseRQ);
+        assert_eq!(records[1].direction, Direction::Received);
+        assert_eq!(records[1].pdu().unwrap(), Pdu::ReleaseRP);
+        assert!(records[0].timestamp <= records[1].timestamp);
+
+        assert!(CaptureReader::new(&b"not a capture"[..]).is_err());
+    }
+
+    #[test]
+    fn capture_association() {
+        use crate::{
+            ClientAssociationOptions, ServerAssociationOptions, test_utils::association_pair,
+        };
+
+        let scu_buffer = SharedBuffer::default();
+        let scp_buffer = SharedBuffer::default();
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new()
+                .with_abstract_syntax("1.2.840.10008.1.1")
+                .pdu_capture(PduCapture::new(scu_buffer.clone()).unwrap()),
+            &ServerAssociationOptions::new()
+                .with_abstract_syntax("1.2.840.10008.1.1")
+                .pdu_capture(PduCapture::new(scp_buffer.clone()).unwrap()),
+        )
+        .unwrap();
+
+        let presentation_context_id = scu.presentation_contexts()[0].id;
+        let mut pdata = scu.send_pdata(presentation_context_id);
+        pdata.write_all(&[0x55; 16]).unwrap();
+        pdata.finish().unwrap();
+        let mut data = Vec::new();
+        scp.receive_pdata().read_to_end(&mut data).unwrap();
+        assert_eq!(data, [0x55; 16]);
+
+        let scp = std::thread::spawn(move || {
+            assert_eq!(scp.receive().unwrap(), Pdu::ReleaseRQ);
+            scp.send(&Pdu::ReleaseRP).unwrap();
+        });
+        scu.release().unwrap();
+        scp.join().unwrap();
+
+        let read = |buffer: &SharedBuffer| -> Vec<(Direction, Pdu)> {
+            let data = buffer.0.lock().unwrap().clone();
+            CaptureReader::new(&data[..])
+                .unwrap()
+                .map(|record| {
+                    let record = record.unwrap();
+                    (record.direction, record.pdu().unwrap())
+                })
+                .collect()
+        };
+        let scu_records = read(&scu_buffer);
+        let scp_records = read(&scp_buffer);
+        assert_eq!(scu_records.len(), 5);
+        assert_eq!(scp_records.len(), 5);
+        assert!(matches!(
+            scu_records[0],
+            (Direction::Sent, Pdu::AssociationRQ(_))
+        ));
+        assert!(matches!(
+            scu_records[1],
+            (Direction::Received, Pdu::AssociationAC(_))
+        ));
+        assert!(matches!(
+            scu_records[2],
+            (Direction::Sent, Pdu::PData { .. })
+        ));
+        assert_eq!(scu_records[3], (Direction::Sent, Pdu::ReleaseRQ));
+        assert_eq!(scu_records[4], (Direction::Received, Pdu::ReleaseRP));
+        // the server sees the same PDUs the other way around
+        for (scu, scp) in scu_records.iter().zip(&scp_records) {
+            assert_ne!(scu.0, scp.0);
+            assert_eq!(scu.1, scp.1);
+        }
+    }
+}
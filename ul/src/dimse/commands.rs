@@ -0,0 +1,1179 @@
+//! Typed DIMSE command sets
+//!
+//! Each type in this module represents the command set of
+//! a DIMSE request or response message (PS3.7 chapters 9 and 10).
+//! They can be converted into a command set object via `command`
+//! and read back from a command set object via `from_command`.
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR, dicom_value};
+use dicom_dictionary_std::tags;
+use dicom_object::{InMemDicomObject, mem::InMemElement};
+
+use super::{
+    CommandField, DATA_SET_ABSENT, DATA_SET_PRESENT, InvalidAttributeSnafu, MissingAttributeSnafu,
+    Result, UnexpectedCommandSnafu, read_u16, read_uid, read_uid_opt,
+};
+use snafu::{OptionExt, ensure};
+
+fn us(tag: Tag, value: u16) -> InMemElement {
+    DataElement::new(tag, VR::US, dicom_value!(U16, [value]))
+}
+
+fn ui(tag: Tag, uid: &str) -> InMemElement {
+    DataElement::new(tag, VR::UI, PrimitiveValue::from(uid))
+}
+
+fn data_set_type(present: bool) -> InMemElement {
+    us(
+        tags::COMMAND_DATA_SET_TYPE,
+        if present {
+            DATA_SET_PRESENT
+        } else {
+            DATA_SET_ABSENT
+        },
+    )
+}
+
+fn ensure_command_field(command: &InMemDicomObject, expected: CommandField) -> Result<()> {
+    let command_field = read_u16(command, tags::COMMAND_FIELD)?;
+    ensure!(
+        command_field == expected.code(),
+        UnexpectedCommandSnafu { command_field }
+    );
+    Ok(())
+}
+
+fn read_error_comment(command: &InMemDicomObject) -> Option<String> {
+    command
+        .get(tags::ERROR_COMMENT)
+        .and_then(|e| e.to_str().ok())
+        .map(|s| s.trim_end().to_string())
+}
+
+/// Build the command set elements common to all N-* responses.
+fn response_elements(
+    command_field: CommandField,
+    message_id_being_responded_to: u16,
+    affected_sop_class_uid: Option<&str>,
+    affected_sop_instance_uid: Option<&str>,
+    status: u16,
+    error_comment: Option<&str>,
+    data_set_present: bool,
+) -> Vec<InMemElement> {
+    let mut elements = vec![
+        us(tags::COMMAND_FIELD, command_field.code()),
+        us(
+            tags::MESSAGE_ID_BEING_RESPONDED_TO,
+            message_id_being_responded_to,
+        ),
+        data_set_type(data_set_present),
+        us(tags::STATUS, status),
+    ];
+    if let Some(uid) = affected_sop_class_uid {
+        elements.push(ui(tags::AFFECTED_SOP_CLASS_UID, uid));
+    }
+    if let Some(uid) = affected_sop_instance_uid {
+        elements.push(ui(tags::AFFECTED_SOP_INSTANCE_UID, uid));
+    }
+    if let Some(comment) = error_comment {
+        elements.push(DataElement::new(
+            tags::ERROR_COMMENT,
+            VR::LO,
+            PrimitiveValue::from(comment),
+        ));
+    }
+    elements
+}
+
+/// Build a command set object with the elements common to all N-* responses.
+fn response_command(
+    command_field: CommandField,
+    message_id_being_responded_to: u16,
+    affected_sop_class_uid: Option<&str>,
+    affected_sop_instance_uid: Option<&str>,
+    status: u16,
+    error_comment: Option<&str>,
+    data_set_present: bool,
+) -> InMemDicomObject {
+    InMemDicomObject::command_from_element_iter(response_elements(
+        command_field,
+        message_id_being_responded_to,
+        affected_sop_class_uid,
+        affected_sop_instance_uid,
+        status,
+        error_comment,
+        data_set_present,
+    ))
+}
+
+/// Fields common to all N-* response command sets
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResponseFields {
+    message_id_being_responded_to: u16,
+    affected_sop_class_uid: Option<String>,
+    affected_sop_instance_uid: Option<String>,
+    status: u16,
+    error_comment: Option<String>,
+}
+
+fn read_response(command: &InMemDicomObject, expected: CommandField) -> Result<ResponseFields> {
+    ensure_command_field(command, expected)?;
+    Ok(ResponseFields {
+        message_id_being_responded_to: read_u16(command, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+        affected_sop_class_uid: read_uid_opt(command, tags::AFFECTED_SOP_CLASS_UID)?,
+        affected_sop_instance_uid: read_uid_opt(command, tags::AFFECTED_SOP_INSTANCE_UID)?,
+        status: read_u16(command, tags::STATUS)?,
+        error_comment: read_error_comment(command),
+    })
+}
+
+/// The command set of an N-CREATE request (PS3.7 10.3.5).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NCreateRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to create
+    pub affected_sop_class_uid: String,
+    /// the UID of the instance to create,
+    /// or `None` if it should be assigned by the SCP
+    pub affected_sop_instance_uid: Option<String>,
+}
+
+impl NCreateRq {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        let mut elements = vec![
+            ui(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::NCreateRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            data_set_type(data_set_present),
+        ];
+        if let Some(uid) = &self.affected_sop_instance_uid {
+            elements.push(ui(tags::AFFECTED_SOP_INSTANCE_UID, uid));
+        }
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::NCreateRq)?;
+        Ok(NCreateRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_uid(command, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_uid_opt(command, tags::AFFECTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// The command set of an N-CREATE response (PS3.7 10.3.5).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NCreateRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the created instance
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the created instance
+    pub affected_sop_instance_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl NCreateRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        response_command(
+            CommandField::NCreateRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::NCreateRsp)?;
+        Ok(NCreateRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of an N-SET request (PS3.7 10.3.3).
+///
+/// The respective message always contains a data set
+/// with the modifications to apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NSetRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to modify
+    pub requested_sop_class_uid: String,
+    /// the UID of the instance to modify
+    pub requested_sop_instance_uid: String,
+}
+
+impl NSetRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::NSetRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            data_set_type(true),
+            ui(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::NSetRq)?;
+        Ok(NSetRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_uid(command, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_uid(command, tags::REQUESTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// The command set of an N-SET response (PS3.7 10.3.3).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NSetRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the modified instance
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the modified instance
+    pub affected_sop_instance_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl NSetRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        response_command(
+            CommandField::NSetRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::NSetRsp)?;
+        Ok(NSetRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of an N-GET request (PS3.7 10.3.2).
+///
+/// The respective message never contains a data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NGetRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to retrieve attributes from
+    pub requested_sop_class_uid: String,
+    /// the UID of the instance to retrieve attributes from
+    pub requested_sop_instance_uid: String,
+    /// the attributes to retrieve,
+    /// an empty list meaning all attributes
+    pub attribute_identifier_list: Vec<Tag>,
+}
+
+impl NGetRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        let mut elements = vec![
+            ui(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::NGetRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            data_set_type(false),
+            ui(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ];
+        if !self.attribute_identifier_list.is_empty() {
+            elements.push(DataElement::new(
+                tags::ATTRIBUTE_IDENTIFIER_LIST,
+                VR::AT,
+                PrimitiveValue::Tags(self.attribute_identifier_list.iter().copied().collect()),
+            ));
+        }
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::NGetRq)?;
+        let attribute_identifier_list = match command.get(tags::ATTRIBUTE_IDENTIFIER_LIST) {
+            Some(e) => match e.value().primitive() {
+                Some(PrimitiveValue::Tags(tags)) => tags.to_vec(),
+                Some(PrimitiveValue::Empty) => Vec::new(),
+                _ => {
+                    return InvalidAttributeSnafu {
+                        tag: tags::ATTRIBUTE_IDENTIFIER_LIST,
+                    }
+                    .fail();
+                }
+            },
+            None => Vec::new(),
+        };
+        Ok(NGetRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_uid(command, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_uid(command, tags::REQUESTED_SOP_INSTANCE_UID)?,
+            attribute_identifier_list,
+        })
+    }
+}
+
+/// The command set of an N-GET response (PS3.7 10.3.2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NGetRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the instance
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the instance
+    pub affected_sop_instance_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl NGetRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        response_command(
+            CommandField::NGetRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::NGetRsp)?;
+        Ok(NGetRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of an N-ACTION request (PS3.7 10.3.4).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NActionRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to act upon
+    pub requested_sop_class_uid: String,
+    /// the UID of the instance to act upon
+    pub requested_sop_instance_uid: String,
+    /// the action to perform, as defined by the SOP class
+    pub action_type_id: u16,
+}
+
+impl NActionRq {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::NActionRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            data_set_type(data_set_present),
+            ui(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+            us(tags::ACTION_TYPE_ID, self.action_type_id),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::NActionRq)?;
+        Ok(NActionRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_uid(command, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_uid(command, tags::REQUESTED_SOP_INSTANCE_UID)?,
+            action_type_id: read_u16(command, tags::ACTION_TYPE_ID)?,
+        })
+    }
+}
+
+/// The command set of an N-ACTION response (PS3.7 10.3.4).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NActionRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the instance acted upon
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the instance acted upon
+    pub affected_sop_instance_uid: Option<String>,
+    /// the action performed
+    pub action_type_id: Option<u16>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl NActionRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        let mut elements = response_elements(
+            CommandField::NActionRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        );
+        if let Some(action_type_id) = self.action_type_id {
+            elements.push(us(tags::ACTION_TYPE_ID, action_type_id));
+        }
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::NActionRsp)?;
+        let action_type_id = match command.get(tags::ACTION_TYPE_ID) {
+            Some(_) => Some(read_u16(command, tags::ACTION_TYPE_ID)?),
+            None => None,
+        };
+        Ok(NActionRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            action_type_id,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of an N-DELETE request (PS3.7 10.3.6).
+///
+/// The respective message never contains a data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NDeleteRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to delete
+    pub requested_sop_class_uid: String,
+    /// the UID of the instance to delete
+    pub requested_sop_instance_uid: String,
+}
+
+impl NDeleteRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::REQUESTED_SOP_CLASS_UID, &self.requested_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::NDeleteRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            data_set_type(false),
+            ui(
+                tags::REQUESTED_SOP_INSTANCE_UID,
+                &self.requested_sop_instance_uid,
+            ),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::NDeleteRq)?;
+        Ok(NDeleteRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            requested_sop_class_uid: read_uid(command, tags::REQUESTED_SOP_CLASS_UID)?,
+            requested_sop_instance_uid: read_uid(command, tags::REQUESTED_SOP_INSTANCE_UID)?,
+        })
+    }
+}
+
+/// The command set of an N-DELETE response (PS3.7 10.3.6).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NDeleteRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the deleted instance
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the deleted instance
+    pub affected_sop_instance_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl NDeleteRsp {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        response_command(
+            CommandField::NDeleteRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            false,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::NDeleteRsp)?;
+        Ok(NDeleteRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of a C-FIND request (PS3.7 9.3.2.1).
+///
+/// The respective message always contains the query identifier
+/// as its data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFindRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the query information model
+    pub affected_sop_class_uid: String,
+    /// the priority of the request
+    /// (0000H for medium, 0001H for high, 0002H for low)
+    pub priority: u16,
+}
+
+impl CFindRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::CFindRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            us(tags::PRIORITY, self.priority),
+            data_set_type(true),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::CFindRq)?;
+        Ok(CFindRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_uid(command, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_u16(command, tags::PRIORITY)?,
+        })
+    }
+}
+
+/// The command set of a C-FIND response (PS3.7 9.3.2.2).
+///
+/// Pending responses contain a matching identifier as their data set,
+/// whereas the final response does not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CFindRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the query information model
+    pub affected_sop_class_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl CFindRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        response_command(
+            CommandField::CFindRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            None,
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::CFindRsp)?;
+        Ok(CFindRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of a C-CANCEL request (PS3.7 9.3.2.3).
+///
+/// The respective message never contains a data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CCancelRq {
+    /// the ID of the request message to cancel
+    pub message_id_being_responded_to: u16,
+}
+
+impl CCancelRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            us(tags::COMMAND_FIELD, CommandField::CCancelRq.code()),
+            us(
+                tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                self.message_id_being_responded_to,
+            ),
+            data_set_type(false),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::CCancelRq)?;
+        Ok(CCancelRq {
+            message_id_being_responded_to: read_u16(command, tags::MESSAGE_ID_BEING_RESPONDED_TO)?,
+        })
+    }
+}
+
+/// The command set of a C-STORE request (PS3.7 9.3.1.1).
+///
+/// The respective message always contains the SOP instance
+/// as its data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CStoreRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the instance to store
+    pub affected_sop_class_uid: String,
+    /// the UID of the instance to store
+    pub affected_sop_instance_uid: String,
+    /// the priority of the request
+    /// (0000H for medium, 0001H for high, 0002H for low)
+    pub priority: u16,
+    /// the AE title of the C-MOVE SCU
+    /// on whose behalf this sub-operation is performed, if any
+    pub move_originator_ae_title: Option<String>,
+    /// the ID of the C-MOVE request
+    /// on whose behalf this sub-operation is performed, if any
+    pub move_originator_message_id: Option<u16>,
+}
+
+impl CStoreRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        let mut elements = vec![
+            ui(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::CStoreRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            us(tags::PRIORITY, self.priority),
+            data_set_type(true),
+            ui(
+                tags::AFFECTED_SOP_INSTANCE_UID,
+                &self.affected_sop_instance_uid,
+            ),
+        ];
+        if let Some(ae_title) = &self.move_originator_ae_title {
+            elements.push(DataElement::new(
+                tags::MOVE_ORIGINATOR_APPLICATION_ENTITY_TITLE,
+                VR::AE,
+                PrimitiveValue::from(ae_title.as_str()),
+            ));
+        }
+        if let Some(message_id) = self.move_originator_message_id {
+            elements.push(us(tags::MOVE_ORIGINATOR_MESSAGE_ID, message_id));
+        }
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::CStoreRq)?;
+        let move_originator_message_id = if command.get(tags::MOVE_ORIGINATOR_MESSAGE_ID).is_some()
+        {
+            Some(read_u16(command, tags::MOVE_ORIGINATOR_MESSAGE_ID)?)
+        } else {
+            None
+        };
+        Ok(CStoreRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_uid(command, tags::AFFECTED_SOP_CLASS_UID)?,
+            affected_sop_instance_uid: read_uid(command, tags::AFFECTED_SOP_INSTANCE_UID)?,
+            priority: read_u16(command, tags::PRIORITY)?,
+            move_originator_ae_title: command
+                .get(tags::MOVE_ORIGINATOR_APPLICATION_ENTITY_TITLE)
+                .and_then(|e| e.to_str().ok())
+                .map(|s| s.trim().to_string()),
+            move_originator_message_id,
+        })
+    }
+}
+
+/// The command set of a C-STORE response (PS3.7 9.3.1.2).
+///
+/// The respective message never contains a data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CStoreRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the stored instance
+    pub affected_sop_class_uid: Option<String>,
+    /// the UID of the stored instance
+    pub affected_sop_instance_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+}
+
+impl CStoreRsp {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        response_command(
+            CommandField::CStoreRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            self.affected_sop_instance_uid.as_deref(),
+            self.status,
+            self.error_comment.as_deref(),
+            false,
+        )
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::CStoreRsp)?;
+        Ok(CStoreRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            affected_sop_instance_uid: r.affected_sop_instance_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+        })
+    }
+}
+
+/// The command set of a C-GET request (PS3.7 9.3.3.1).
+///
+/// The respective message always contains the identifier
+/// of the instances to retrieve as its data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CGetRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the query/retrieve information model
+    pub affected_sop_class_uid: String,
+    /// the priority of the request
+    /// (0000H for medium, 0001H for high, 0002H for low)
+    pub priority: u16,
+}
+
+impl CGetRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::CGetRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            us(tags::PRIORITY, self.priority),
+            data_set_type(true),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::CGetRq)?;
+        Ok(CGetRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_uid(command, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_u16(command, tags::PRIORITY)?,
+        })
+    }
+}
+
+/// The command set of a C-MOVE request (PS3.7 9.3.4.1).
+///
+/// The respective message always contains the identifier
+/// of the instances to retrieve as its data set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CMoveRq {
+    /// the message ID
+    pub message_id: u16,
+    /// the SOP class of the query/retrieve information model
+    pub affected_sop_class_uid: String,
+    /// the priority of the request
+    /// (0000H for medium, 0001H for high, 0002H for low)
+    pub priority: u16,
+    /// the AE title of the application entity
+    /// to which the instances should be sent
+    pub move_destination: String,
+}
+
+impl CMoveRq {
+    /// Build the command set object.
+    pub fn command(&self) -> InMemDicomObject {
+        InMemDicomObject::command_from_element_iter([
+            ui(tags::AFFECTED_SOP_CLASS_UID, &self.affected_sop_class_uid),
+            us(tags::COMMAND_FIELD, CommandField::CMoveRq.code()),
+            us(tags::MESSAGE_ID, self.message_id),
+            us(tags::PRIORITY, self.priority),
+            data_set_type(true),
+            DataElement::new(
+                tags::MOVE_DESTINATION,
+                VR::AE,
+                PrimitiveValue::from(self.move_destination.as_str()),
+            ),
+        ])
+    }
+
+    /// Read the request from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        ensure_command_field(command, CommandField::CMoveRq)?;
+        let tag = tags::MOVE_DESTINATION;
+        let move_destination = command
+            .get(tag)
+            .context(MissingAttributeSnafu { tag })?
+            .to_str()
+            .ok()
+            .context(InvalidAttributeSnafu { tag })?
+            .trim()
+            .to_string();
+        Ok(CMoveRq {
+            message_id: read_u16(command, tags::MESSAGE_ID)?,
+            affected_sop_class_uid: read_uid(command, tags::AFFECTED_SOP_CLASS_UID)?,
+            priority: read_u16(command, tags::PRIORITY)?,
+            move_destination,
+        })
+    }
+}
+
+/// The numbers of C-STORE sub-operations of a C-GET or C-MOVE operation,
+/// as reported in its responses.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SubOperations {
+    /// the number of sub-operations yet to be performed,
+    /// only reported in pending responses
+    pub remaining: Option<u16>,
+    /// the number of sub-operations completed successfully
+    pub completed: u16,
+    /// the number of failed sub-operations
+    pub failed: u16,
+    /// the number of sub-operations completed with warnings
+    pub warning: u16,
+}
+
+impl SubOperations {
+    fn elements(&self) -> Vec<InMemElement> {
+        let mut elements = Vec::with_capacity(4);
+        if let Some(remaining) = self.remaining {
+            elements.push(us(tags::NUMBER_OF_REMAINING_SUBOPERATIONS, remaining));
+        }
+        elements.push(us(tags::NUMBER_OF_COMPLETED_SUBOPERATIONS, self.completed));
+        elements.push(us(tags::NUMBER_OF_FAILED_SUBOPERATIONS, self.failed));
+        elements.push(us(tags::NUMBER_OF_WARNING_SUBOPERATIONS, self.warning));
+        elements
+    }
+
+    fn read(command: &InMemDicomObject) -> Result<Self> {
+        let read_opt = |tag| {
+            if command.get(tag).is_some() {
+                read_u16(command, tag).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        Ok(SubOperations {
+            remaining: read_opt(tags::NUMBER_OF_REMAINING_SUBOPERATIONS)?,
+            completed: read_opt(tags::NUMBER_OF_COMPLETED_SUBOPERATIONS)?.unwrap_or(0),
+            failed: read_opt(tags::NUMBER_OF_FAILED_SUBOPERATIONS)?.unwrap_or(0),
+            warning: read_opt(tags::NUMBER_OF_WARNING_SUBOPERATIONS)?.unwrap_or(0),
+        })
+    }
+}
+
+/// The command set of a C-GET response (PS3.7 9.3.3.2).
+///
+/// Only final responses with failed sub-operations
+/// may contain a data set,
+/// which lists the instances which could not be retrieved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CGetRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the query/retrieve information model
+    pub affected_sop_class_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+    /// the progress of the C-STORE sub-operations
+    pub sub_operations: SubOperations,
+}
+
+impl CGetRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        let mut elements = response_elements(
+            CommandField::CGetRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            None,
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        );
+        elements.extend(self.sub_operations.elements());
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::CGetRsp)?;
+        Ok(CGetRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+            sub_operations: SubOperations::read(command)?,
+        })
+    }
+}
+
+/// The command set of a C-MOVE response (PS3.7 9.3.4.2).
+///
+/// Only final responses with failed sub-operations
+/// may contain a data set,
+/// which lists the instances which could not be moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CMoveRsp {
+    /// the ID of the request message
+    pub message_id_being_responded_to: u16,
+    /// the SOP class of the query/retrieve information model
+    pub affected_sop_class_uid: Option<String>,
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+    /// the progress of the C-STORE sub-operations
+    pub sub_operations: SubOperations,
+}
+
+impl CMoveRsp {
+    /// Build the command set object.
+    pub fn command(&self, data_set_present: bool) -> InMemDicomObject {
+        let mut elements = response_elements(
+            CommandField::CMoveRsp,
+            self.message_id_being_responded_to,
+            self.affected_sop_class_uid.as_deref(),
+            None,
+            self.status,
+            self.error_comment.as_deref(),
+            data_set_present,
+        );
+        elements.extend(self.sub_operations.elements());
+        InMemDicomObject::command_from_element_iter(elements)
+    }
+
+    /// Read the response from a command set object.
+    pub fn from_command(command: &InMemDicomObject) -> Result<Self> {
+        let r = read_response(command, CommandField::CMoveRsp)?;
+        Ok(CMoveRsp {
+            message_id_being_responded_to: r.message_id_being_responded_to,
+            affected_sop_class_uid: r.affected_sop_class_uid,
+            status: r.status,
+            error_comment: r.error_comment,
+            sub_operations: SubOperations::read(command)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dimse::{read_command, write_command};
+
+    #[test]
+    fn n_create_rq_roundtrip() {
+        let rq = NCreateRq {
+            message_id: 7,
+            affected_sop_class_uid: "1.2.840.10008.3.1.2.3.3".to_string(),
+            affected_sop_instance_uid: Some("1.2.3.4.5".to_string()),
+        };
+        let bytes = write_command(&rq.command(true)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(NCreateRq::from_command(&command).unwrap(), rq);
+        // wrong command type
+        assert!(NSetRq::from_command(&command).is_err());
+    }
+
+    #[test]
+    fn n_get_rq_roundtrip() {
+        let rq = NGetRq {
+            message_id: 2,
+            requested_sop_class_uid: "1.2.840.10008.3.1.2.3.3".to_string(),
+            requested_sop_instance_uid: "1.2.3.4.5.6".to_string(),
+            attribute_identifier_list: vec![
+                tags::PATIENT_NAME,
+                tags::PERFORMED_PROCEDURE_STEP_STATUS,
+            ],
+        };
+        let bytes = write_command(&rq.command()).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(NGetRq::from_command(&command).unwrap(), rq);
+    }
+
+    #[test]
+    fn n_set_rsp_roundtrip() {
+        let rsp = NSetRsp {
+            message_id_being_responded_to: 3,
+            affected_sop_class_uid: Some("1.2.840.10008.3.1.2.3.3".to_string()),
+            affected_sop_instance_uid: Some("1.2.3.4.5.6".to_string()),
+            status: 0x0110,
+            error_comment: Some("step already completed".to_string()),
+        };
+        let bytes = write_command(&rsp.command(false)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(NSetRsp::from_command(&command).unwrap(), rsp);
+    }
+
+    #[test]
+    fn n_action_roundtrip() {
+        let rq = NActionRq {
+            message_id: 4,
+            requested_sop_class_uid: "1.2.840.10008.5.1.1.2".to_string(),
+            requested_sop_instance_uid: "1.2.3.4.5.6".to_string(),
+            action_type_id: 1,
+        };
+        let bytes = write_command(&rq.command(false)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(NActionRq::from_command(&command).unwrap(), rq);
+
+        let rsp = NActionRsp {
+            message_id_being_responded_to: 4,
+            affected_sop_class_uid: Some("1.2.840.10008.5.1.1.2".to_string()),
+            affected_sop_instance_uid: Some("1.2.3.4.5.6".to_string()),
+            action_type_id: Some(1),
+            status: 0,
+            error_comment: None,
+        };
+        let bytes = write_command(&rsp.command(false)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(NActionRsp::from_command(&command).unwrap(), rsp);
+    }
+
+    #[test]
+    fn c_find_roundtrip() {
+        let rq = CFindRq {
+            message_id: 5,
+            affected_sop_class_uid: "1.2.840.10008.5.1.4.1.2.2.1".to_string(),
+            priority: 0,
+        };
+        let bytes = write_command(&rq.command()).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CFindRq::from_command(&command).unwrap(), rq);
+
+        let rsp = CFindRsp {
+            message_id_being_responded_to: 5,
+            affected_sop_class_uid: Some("1.2.840.10008.5.1.4.1.2.2.1".to_string()),
+            status: 0xFF00,
+            error_comment: None,
+        };
+        let bytes = write_command(&rsp.command(true)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CFindRsp::from_command(&command).unwrap(), rsp);
+
+        let cancel = CCancelRq {
+            message_id_being_responded_to: 5,
+        };
+        let bytes = write_command(&cancel.command()).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CCancelRq::from_command(&command).unwrap(), cancel);
+        assert!(CFindRq::from_command(&command).is_err());
+    }
+
+    #[test]
+    fn c_store_and_retrieve_roundtrip() {
+        let rq = CStoreRq {
+            message_id: 9,
+            affected_sop_class_uid: "1.2.840.10008.5.1.4.1.1.7".to_string(),
+            affected_sop_instance_uid: "1.2.3.4.5.6".to_string(),
+            priority: 0,
+            move_originator_ae_title: Some("MOVESCU".to_string()),
+            move_originator_message_id: Some(3),
+        };
+        let bytes = write_command(&rq.command()).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CStoreRq::from_command(&command).unwrap(), rq);
+
+        let rq = CMoveRq {
+            message_id: 3,
+            affected_sop_class_uid: "1.2.840.10008.5.1.4.1.2.2.2".to_string(),
+            priority: 0,
+            move_destination: "STORESCP".to_string(),
+        };
+        let bytes = write_command(&rq.command()).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CMoveRq::from_command(&command).unwrap(), rq);
+        assert!(CGetRq::from_command(&command).is_err());
+
+        let rsp = CGetRsp {
+            message_id_being_responded_to: 3,
+            affected_sop_class_uid: Some("1.2.840.10008.5.1.4.1.2.2.3".to_string()),
+            status: 0xFF00,
+            error_comment: None,
+            sub_operations: SubOperations {
+                remaining: Some(2),
+                completed: 1,
+                failed: 0,
+                warning: 0,
+            },
+        };
+        let bytes = write_command(&rsp.command(false)).unwrap();
+        let command = read_command(&bytes).unwrap();
+        assert_eq!(CGetRsp::from_command(&command).unwrap(), rsp);
+    }
+}
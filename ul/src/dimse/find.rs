@@ -0,0 +1,402 @@
+//! C-FIND service class user
+//!
+//! This module implements the SCU side of the C-FIND operation
+//! (PS3.4 Annex C and K) for any query information model,
+//! such as Patient Root or Study Root Query/Retrieve
+//! or Modality Worklist.
+//!
+//! [`FindScu::find`] sends the query
+//! and returns a [`FindResponses`] iterator,
+//! which receives each pending response lazily as it is requested.
+//! Dropping the iterator before the final response
+//! (for instance, after taking the first few matches)
+//! cancels the operation with a C-CANCEL request
+//! and consumes the outstanding responses,
+//! so that the association can be used for further operations.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_core::{DataElement, PrimitiveValue, VR};
+//! # use dicom_dictionary_std::{tags, uids};
+//! # use dicom_object::InMemDicomObject;
+//! # use dicom_ul::ClientAssociationOptions;
+//! # use dicom_ul::dimse::find::FindScu;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax(uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND)
+//!     .establish_with("QUERY-SCP@10.0.0.100:104")?;
+//!
+//! let mut scu = FindScu::new(
+//!     &mut association,
+//!     uids::STUDY_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND,
+//! )?;
+//! let query = InMemDicomObject::from_element_iter([
+//!     DataElement::new(tags::QUERY_RETRIEVE_LEVEL, VR::CS, "STUDY"),
+//!     DataElement::new(tags::PATIENT_NAME, VR::PN, "DOE^*"),
+//!     DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, PrimitiveValue::Empty),
+//! ]);
+//! // stop after the first 10 studies
+//! for result in scu.find(&query)?.take(10) {
+//!     let item = result?;
+//!     println!("{:?}", item.identifier.get(tags::STUDY_INSTANCE_UID));
+//! }
+//! association.release()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::marker::PhantomData;
+
+use dicom_dictionary_std::tags;
+use dicom_object::InMemDicomObject;
+use snafu::ensure;
+
+use super::{
+    CCancelRq, CFindRq, CFindRsp, InvalidAttributeSnafu, OperationFailedSnafu, Result, StatusType,
+    presentation_context_for, receive_message, send_message, write_data_set,
+};
+use crate::association::{CloseSocket, SyncAssociation};
+
+/// A C-FIND SCU operating over an established association.
+///
+/// The association must have an accepted presentation context
+/// for the query information model.
+/// Message IDs are assigned incrementally, starting from 1.
+pub struct FindScu<'a, A, S> {
+    association: &'a mut A,
+    presentation_context_id: u8,
+    sop_class_uid: String,
+    priority: u16,
+    message_id: u16,
+    _stream: PhantomData<fn(S)>,
+}
+
+impl<'a, A, S> FindScu<'a, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    /// Prepare a C-FIND SCU over the given association
+    /// for the given query information model.
+    ///
+    /// Returns an error if no presentation context
+    /// was accepted for the SOP class.
+    pub fn new(association: &'a mut A, sop_class_uid: &str) -> Result<Self> {
+        let presentation_context_id = presentation_context_for(&*association, sop_class_uid)?.id;
+        Ok(FindScu {
+            association,
+            presentation_context_id,
+            sop_class_uid: sop_class_uid.to_string(),
+            priority: 0,
+            message_id: 1,
+            _stream: PhantomData,
+        })
+    }
+
+    /// Set the priority of the requests
+    /// (0000H for medium, the default, 0001H for high, 0002H for low).
+    pub fn with_priority(mut self, priority: u16) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Send a C-FIND request with the given query identifier.
+    ///
+    /// The matches are received as the returned iterator is advanced.
+    pub fn find(&mut self, query: &InMemDicomObject) -> Result<FindResponses<'_, A, S>> {
+        let message_id = self.message_id;
+        self.message_id = self.message_id.wrapping_add(1).max(1);
+
+        let rq = CFindRq {
+            message_id,
+            affected_sop_class_uid: self.sop_class_uid.clone(),
+            priority: self.priority,
+        };
+        let data = write_data_set(&*self.association, self.presentation_context_id, query)?;
+        send_message(
+            self.association,
+            self.presentation_context_id,
+            &rq.command(),
+            Some(&data),
+        )?;
+        Ok(FindResponses {
+            association: &mut *self.association,
+            presentation_context_id: self.presentation_context_id,
+            message_id,
+            final_status: None,
+            done: false,
+            _stream: PhantomData,
+        })
+    }
+}
+
+/// A match of a C-FIND query,
+/// as received in a pending response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindMatch {
+    /// the DIMSE status code of the response:
+    /// FF00H, or FF01H if some optional keys were not supported
+    pub status: u16,
+    /// the matching identifier
+    pub identifier: InMemDicomObject,
+}
+
+/// An iterator over the matches of an ongoing C-FIND operation.
+///
+/// Each call to `next` receives one response from the association.
+/// Iteration ends at the final response,
+/// whose status is then available through [`final_status`].
+/// A final response with a failure status
+/// is yielded as an [`OperationFailed`] error.
+///
+/// If dropped before the final response,
+/// the operation is cancelled as in [`cancel`].
+///
+/// [`final_status`]: FindResponses::final_status
+/// [`cancel`]: FindResponses::cancel
+/// [`OperationFailed`]: super::Error::OperationFailed
+pub struct FindResponses<'a, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    association: &'a mut A,
+    presentation_context_id: u8,
+    message_id: u16,
+    final_status: Option<u16>,
+    done: bool,
+    _stream: PhantomData<fn(S)>,
+}
+
+impl<A, S> FindResponses<'_, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    /// The status code of the final response,
+    /// if already received.
+    pub fn final_status(&self) -> Option<u16> {
+        self.final_status
+    }
+
+    /// Cancel the operation with a C-CANCEL request,
+    /// and receive the remaining responses until the final one.
+    ///
+    /// Returns the status of the final response,
+    /// which is usually Cancel (FE00H),
+    /// but may also be Success if the SCP had already completed.
+    /// If the final response had already been received,
+    /// no request is sent.
+    pub fn cancel(mut self) -> Result<Option<u16>> {
+        self.cancel_impl()?;
+        Ok(self.final_status)
+    }
+
+    fn cancel_impl(&mut self) -> Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        let rq = CCancelRq {
+            message_id_being_responded_to: self.message_id,
+        };
+        if let Err(e) = send_message(
+            self.association,
+            self.presentation_context_id,
+            &rq.command(),
+            None,
+        ) {
+            self.done = true;
+            return Err(e);
+        }
+        for item in self.by_ref() {
+            item?;
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Option<FindMatch>> {
+        let msg = receive_message(self.association)?;
+        let rsp = CFindRsp::from_command(&msg.command)?;
+        ensure!(
+            rsp.message_id_being_responded_to == self.message_id,
+            InvalidAttributeSnafu {
+                tag: tags::MESSAGE_ID_BEING_RESPONDED_TO
+            }
+        );
+        match StatusType::from_code(rsp.status) {
+            StatusType::Pending => {
+                let identifier = msg
+                    .read_data_set(&*self.association)?
+                    .unwrap_or_else(InMemDicomObject::new_empty);
+                Ok(Some(FindMatch {
+                    status: rsp.status,
+                    identifier,
+                }))
+            }
+            StatusType::Success | StatusType::Warning | StatusType::Cancel => {
+                self.final_status = Some(rsp.status);
+                Ok(None)
+            }
+            StatusType::Failure => {
+                self.final_status = Some(rsp.status);
+                OperationFailedSnafu {
+                    operation: "C-FIND",
+                    status: rsp.status,
+                    error_comment: rsp.error_comment,
+                }
+                .fail()
+            }
+        }
+    }
+}
+
+impl<A, S> Iterator for FindResponses<'_, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    type Item = Result<FindMatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.receive();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+        result.transpose()
+    }
+}
+
+impl<A, S> Drop for FindResponses<'_, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    fn drop(&mut self) {
+        if let Err(e) = self.cancel_impl() {
+            tracing::warn!(
+                "Failed to cancel C-FIND operation: {}",
+                snafu::Report::from_error(e)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ClientAssociationOptions, ServerAssociationOptions,
+        dimse::{CommandField, Message, status},
+        test_utils::association_pair,
+    };
+    use dicom_core::{DataElement, VR};
+    use dicom_dictionary_std::uids;
+
+    const SOP_CLASS: &str = uids::PATIENT_ROOT_QUERY_RETRIEVE_INFORMATION_MODEL_FIND;
+
+    fn patient(id: u32) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([DataElement::new(
+            tags::PATIENT_ID,
+            VR::LO,
+            id.to_string(),
+        )])
+    }
+
+    fn patient_id(obj: &InMemDicomObject) -> String {
+        obj.get(tags::PATIENT_ID)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+
+    fn respond<A, S>(scp: &mut A, rq: &Message, status: u16, identifier: Option<u32>)
+    where
+        A: SyncAssociation<S>,
+        S: std::io::Read + std::io::Write + CloseSocket,
+    {
+        let rsp = CFindRsp {
+            message_id_being_responded_to: CFindRq::from_command(&rq.command).unwrap().message_id,
+            affected_sop_class_uid: Some(SOP_CLASS.to_string()),
+            status,
+            error_comment: None,
+        };
+        let data = identifier
+            .map(|id| write_data_set(&*scp, rq.presentation_context_id, &patient(id)).unwrap());
+        send_message(
+            scp,
+            rq.presentation_context_id,
+            &rsp.command(data.is_some()),
+            data.as_deref(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn find_cancel_after_first_matches() {
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new().with_abstract_syntax(SOP_CLASS),
+            &ServerAssociationOptions::new().with_abstract_syntax(SOP_CLASS),
+        )
+        .unwrap();
+
+        let scp = std::thread::spawn(move || {
+            // first query: 10 matches, until cancelled
+            let rq = receive_message(&mut scp).unwrap();
+            assert_eq!(rq.command_field().unwrap(), CommandField::CFindRq);
+            let query = rq.read_data_set(&scp).unwrap().unwrap();
+            assert!(query.get(tags::PATIENT_ID).is_some());
+            for id in 0..10 {
+                respond(&mut scp, &rq, status::PENDING, Some(id));
+            }
+            let cancel = receive_message(&mut scp).unwrap();
+            let cancel = CCancelRq::from_command(&cancel.command).unwrap();
+            assert_eq!(cancel.message_id_being_responded_to, 1);
+            respond(&mut scp, &rq, status::CANCEL, None);
+
+            // second query: runs to completion
+            let rq = receive_message(&mut scp).unwrap();
+            respond(&mut scp, &rq, status::PENDING, Some(20));
+            respond(&mut scp, &rq, 0xFF01, Some(21));
+            respond(&mut scp, &rq, status::SUCCESS, None);
+
+            // third query: fails
+            let rq = receive_message(&mut scp).unwrap();
+            respond(&mut scp, &rq, 0xC000, None);
+        });
+
+        let mut find = FindScu::new(&mut scu, SOP_CLASS).unwrap();
+        let query = patient(0);
+        let first: Vec<_> = find
+            .find(&query)
+            .unwrap()
+            .take(3)
+            .map(|item| patient_id(&item.unwrap().identifier))
+            .collect();
+        assert_eq!(first, ["0", "1", "2"]);
+
+        // the association is still usable after the cancellation
+        let mut responses = find.find(&query).unwrap();
+        let item = responses.next().unwrap().unwrap();
+        assert_eq!(item.status, status::PENDING);
+        assert_eq!(patient_id(&item.identifier), "20");
+        let item = responses.next().unwrap().unwrap();
+        assert_eq!(item.status, 0xFF01);
+        assert!(responses.next().is_none());
+        assert_eq!(responses.final_status(), Some(status::SUCCESS));
+        // nothing left to cancel
+        assert_eq!(responses.cancel().unwrap(), Some(status::SUCCESS));
+
+        let mut responses = find.find(&query).unwrap();
+        assert!(responses.next().unwrap().is_err());
+        assert!(responses.next().is_none());
+        assert_eq!(responses.final_status(), Some(0xC000));
+        drop(responses);
+
+        scp.join().unwrap();
+    }
+}
@@ -0,0 +1,678 @@
+//! DIMSE message layer
+//!
+//! This module provides the building blocks for exchanging
+//! DICOM Message Service Element (DIMSE) messages
+//! over an established association:
+//! command sets are composed and interpreted as DICOM objects,
+//! while the respective data sets are sent and received
+//! as P-Data values in the transfer syntax of the presentation context.
+//!
+//! - [`send_message`] and [`receive_message`]
+//!   send and receive a full DIMSE message (command and optional data set).
+//! - The [`commands`] module
+//!   contains typed representations of DIMSE command sets,
+//!   such as [`NCreateRq`](commands::NCreateRq).
+//! - The [`find`] module
+//!   implements a C-FIND service class user
+//!   which streams the matches of a query as they arrive.
+//! - The [`mpps`] module
+//!   implements the Modality Performed Procedure Step SOP class,
+//!   both as a service class user and as a service class provider.
+//! - The [`print`] module
+//!   implements a Print Management service class user
+//!   for the Basic Grayscale Print Management meta SOP class.
+//! - The [`query`] module
+//!   builds query identifiers from attribute keywords and values,
+//!   and matches them against candidate objects.
+//!
+//! This module requires the Cargo feature `dimse`.
+use std::io::Write;
+
+use dicom_core::Tag;
+use dicom_dictionary_std::tags;
+use dicom_encoding::TransferSyntaxIndex;
+use dicom_object::InMemDicomObject;
+use dicom_transfer_syntax_registry::{TransferSyntaxRegistry, entries::IMPLICIT_VR_LITTLE_ENDIAN};
+use snafu::{OptionExt, ResultExt, Snafu};
+
+use crate::{
+    Pdu,
+    association::{Association, CloseSocket, SyncAssociation},
+    pdu::{
+        PDataValue, PDataValueType, PresentationContextNegotiated, PresentationContextResultReason,
+    },
+};
+
+pub mod commands;
+pub mod find;
+pub mod mpps;
+pub mod print;
+pub mod query;
+
+pub use commands::{
+    CCancelRq, CFindRq, CFindRsp, CGetRq, CGetRsp, CMoveRq, CMoveRsp, CStoreRq, CStoreRsp,
+    NActionRq, NActionRsp, NCreateRq, NCreateRsp, NDeleteRq, NDeleteRsp, NGetRq, NGetRsp, NSetRq,
+    NSetRsp, SubOperations,
+};
+
+/// An error which may occur when exchanging DIMSE messages
+#[derive(Debug, Snafu)]
+#[non_exhaustive]
+pub enum Error {
+    /// failed to send or receive a PDU
+    #[snafu(display("association failure"))]
+    Association {
+        #[snafu(backtrace)]
+        source: crate::association::Error,
+    },
+
+    /// failed to encode command set
+    WriteCommand {
+        #[snafu(source(from(dicom_object::WriteError, Box::from)))]
+        source: Box<dicom_object::WriteError>,
+    },
+
+    /// failed to decode command set
+    ReadCommand {
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    /// failed to encode data set
+    WriteDataSet {
+        #[snafu(source(from(dicom_object::WriteError, Box::from)))]
+        source: Box<dicom_object::WriteError>,
+    },
+
+    /// failed to decode data set
+    ReadDataSet {
+        #[snafu(source(from(dicom_object::ReadError, Box::from)))]
+        source: Box<dicom_object::ReadError>,
+    },
+
+    /// failed to send P-Data fragments
+    SendData {
+        source: std::io::Error,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("missing attribute {tag} in command set"))]
+    MissingAttribute {
+        tag: Tag,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("invalid value for attribute {tag} in command set"))]
+    InvalidAttribute {
+        tag: Tag,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("unexpected command field {command_field:04X}H"))]
+    UnexpectedCommand {
+        command_field: u16,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("no accepted presentation context for abstract syntax {abstract_syntax}"))]
+    NoPresentationContext {
+        abstract_syntax: String,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("unsupported transfer syntax {uid}"))]
+    UnsupportedTransferSyntax {
+        uid: String,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("unexpected PDU `{}`", pdu.short_description()))]
+    UnexpectedPdu {
+        /// the PDU received
+        pdu: Box<Pdu>,
+    },
+
+    #[snafu(display("{operation} failed with status {status:04X}H{}", error_comment.as_ref().map(|c| format!(": {c}")).unwrap_or_default()))]
+    OperationFailed {
+        /// a description of the operation attempted
+        operation: &'static str,
+        /// the DIMSE status code responded
+        status: u16,
+        /// the error comment responded, if any
+        error_comment: Option<String>,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("unknown attribute keyword `{keyword}`"))]
+    UnknownKeyword {
+        keyword: String,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    #[snafu(display("invalid matching key value `{value}` for {keyword} ({vr})"))]
+    InvalidMatchingKey {
+        keyword: String,
+        value: String,
+        vr: dicom_core::VR,
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    /// peer requested to release the association
+    Released {
+        backtrace: std::backtrace::Backtrace,
+    },
+
+    /// peer aborted the association
+    Aborted {
+        backtrace: std::backtrace::Backtrace,
+    },
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The value of _Command Data Set Type_ (0000,0800)
+/// which indicates that no data set is present in the message.
+pub const DATA_SET_ABSENT: u16 = 0x0101;
+
+/// The value of _Command Data Set Type_ (0000,0800)
+/// used in this implementation to indicate
+/// that a data set is present in the message.
+///
+/// Any other value than [`DATA_SET_ABSENT`] is admitted when reading.
+pub const DATA_SET_PRESENT: u16 = 0x0000;
+
+/// Well known DIMSE status codes (PS3.7 Annex C).
+pub mod status {
+    /// Success
+    pub const SUCCESS: u16 = 0x0000;
+    /// Warning: attribute list error
+    pub const ATTRIBUTE_LIST_ERROR: u16 = 0x0107;
+    /// Failure: no such attribute
+    pub const NO_SUCH_ATTRIBUTE: u16 = 0x0105;
+    /// Failure: invalid attribute value
+    pub const INVALID_ATTRIBUTE_VALUE: u16 = 0x0106;
+    /// Failure: processing failure
+    pub const PROCESSING_FAILURE: u16 = 0x0110;
+    /// Failure: duplicate SOP instance
+    pub const DUPLICATE_SOP_INSTANCE: u16 = 0x0111;
+    /// Failure: no such SOP instance
+    pub const NO_SUCH_SOP_INSTANCE: u16 = 0x0112;
+    /// Failure: no such SOP class
+    pub const NO_SUCH_SOP_CLASS: u16 = 0x0118;
+    /// Failure: missing attribute
+    pub const MISSING_ATTRIBUTE: u16 = 0x0120;
+    /// Failure: missing attribute value
+    pub const MISSING_ATTRIBUTE_VALUE: u16 = 0x0121;
+    /// Failure: unrecognized operation
+    pub const UNRECOGNIZED_OPERATION: u16 = 0x0211;
+    /// Failure: refused, out of resources (C-STORE)
+    pub const OUT_OF_RESOURCES: u16 = 0xA700;
+    /// Failure: refused, out of resources,
+    /// unable to calculate number of matches (C-FIND, C-GET, C-MOVE)
+    pub const OUT_OF_RESOURCES_MATCHES: u16 = 0xA701;
+    /// Failure: refused, out of resources,
+    /// unable to perform sub-operations (C-GET, C-MOVE)
+    pub const OUT_OF_RESOURCES_SUB_OPERATIONS: u16 = 0xA702;
+    /// Failure: refused, move destination unknown (C-MOVE)
+    pub const MOVE_DESTINATION_UNKNOWN: u16 = 0xA801;
+    /// Failure: identifier does not match SOP class
+    /// (C-FIND, C-GET, C-MOVE)
+    pub const IDENTIFIER_DOES_NOT_MATCH_SOP_CLASS: u16 = 0xA900;
+    /// Failure: unable to process (C-FIND, C-GET, C-MOVE)
+    pub const UNABLE_TO_PROCESS: u16 = 0xC000;
+    /// Warning: sub-operations complete, one or more failures or warnings
+    /// (C-GET, C-MOVE)
+    pub const SUB_OPERATIONS_COMPLETE_WITH_FAILURES: u16 = 0xB000;
+    /// Cancel
+    pub const CANCEL: u16 = 0xFE00;
+    /// Pending
+    pub const PENDING: u16 = 0xFF00;
+}
+
+/// A DIMSE command type, as found in _Command Field_ (0000,0100).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u16)]
+#[non_exhaustive]
+pub enum CommandField {
+    CStoreRq = 0x0001,
+    CStoreRsp = 0x8001,
+    CGetRq = 0x0010,
+    CGetRsp = 0x8010,
+    CFindRq = 0x0020,
+    CFindRsp = 0x8020,
+    CMoveRq = 0x0021,
+    CMoveRsp = 0x8021,
+    CEchoRq = 0x0030,
+    CEchoRsp = 0x8030,
+    NEventReportRq = 0x0100,
+    NEventReportRsp = 0x8100,
+    NGetRq = 0x0110,
+    NGetRsp = 0x8110,
+    NSetRq = 0x0120,
+    NSetRsp = 0x8120,
+    NActionRq = 0x0130,
+    NActionRsp = 0x8130,
+    NCreateRq = 0x0140,
+    NCreateRsp = 0x8140,
+    NDeleteRq = 0x0150,
+    NDeleteRsp = 0x8150,
+    CCancelRq = 0x0FFF,
+}
+
+impl CommandField {
+    /// Obtain the command type from its code, if known.
+    pub fn from_code(code: u16) -> Option<Self> {
+        use CommandField::*;
+        Some(match code {
+            0x0001 => CStoreRq,
+            0x8001 => CStoreRsp,
+            0x0010 => CGetRq,
+            0x8010 => CGetRsp,
+            0x0020 => CFindRq,
+            0x8020 => CFindRsp,
+            0x0021 => CMoveRq,
+            0x8021 => CMoveRsp,
+            0x0030 => CEchoRq,
+            0x8030 => CEchoRsp,
+            0x0100 => NEventReportRq,
+            0x8100 => NEventReportRsp,
+            0x0110 => NGetRq,
+            0x8110 => NGetRsp,
+            0x0120 => NSetRq,
+            0x8120 => NSetRsp,
+            0x0130 => NActionRq,
+            0x8130 => NActionRsp,
+            0x0140 => NCreateRq,
+            0x8140 => NCreateRsp,
+            0x0150 => NDeleteRq,
+            0x8150 => NDeleteRsp,
+            0x0FFF => CCancelRq,
+            _ => return None,
+        })
+    }
+
+    /// Obtain the code of this command type.
+    pub fn code(self) -> u16 {
+        self as u16
+    }
+
+    /// Whether this command type is a response.
+    pub fn is_response(self) -> bool {
+        self.code() & 0x8000 != 0
+    }
+}
+
+/// The category of a DIMSE status code,
+/// as described in PS3.7 Annex C.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StatusType {
+    /// the operation was successful (0000H)
+    Success,
+    /// the operation was completed with warnings
+    /// (0001H, 0107H, 0116H, and Bxxx)
+    Warning,
+    /// the operation was cancelled (FE00H)
+    Cancel,
+    /// the operation is ongoing (FF00H and FF01H)
+    Pending,
+    /// the operation failed
+    Failure,
+}
+
+impl StatusType {
+    /// Classify a DIMSE status code.
+    pub fn from_code(status: u16) -> Self {
+        match status {
+            0x0000 => StatusType::Success,
+            0x0001 | 0x0107 | 0x0116 | 0xB000..=0xBFFF => StatusType::Warning,
+            0xFE00 => StatusType::Cancel,
+            0xFF00 | 0xFF01 => StatusType::Pending,
+            _ => StatusType::Failure,
+        }
+    }
+}
+
+/// A full DIMSE message, as received from the peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// the presentation context in which the message was sent
+    pub presentation_context_id: u8,
+    /// the command set
+    pub command: InMemDicomObject,
+    /// the encoded data set, if present
+    pub data: Option<Vec<u8>>,
+}
+
+impl Message {
+    /// Retrieve the command type of this message.
+    ///
+    /// Returns an error if the command field is missing or unknown.
+    pub fn command_field(&self) -> Result<CommandField> {
+        let code = read_u16(&self.command, tags::COMMAND_FIELD)?;
+        CommandField::from_code(code).context(UnexpectedCommandSnafu {
+            command_field: code,
+        })
+    }
+
+    /// Decode the data set of this message
+    /// according to the transfer syntax of its presentation context.
+    ///
+    /// Returns `None` if the message does not have a data set.
+    pub fn read_data_set<A>(&self, association: &A) -> Result<Option<InMemDicomObject>>
+    where
+        A: Association + ?Sized,
+    {
+        let Some(data) = &self.data else {
+            return Ok(None);
+        };
+        let pc = presentation_context_by_id(association, self.presentation_context_id)?;
+        let ts = TransferSyntaxRegistry.get(&pc.transfer_syntax).context(
+            UnsupportedTransferSyntaxSnafu {
+                uid: pc.transfer_syntax.clone(),
+            },
+        )?;
+        InMemDicomObject::read_dataset_with_ts(&data[..], ts)
+            .context(ReadDataSetSnafu)
+            .map(Some)
+    }
+}
+
+/// The outcome of a DIMSE-N operation, as responded by the peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NResponse {
+    /// the DIMSE status code
+    pub status: u16,
+    /// a description of the failure, if any
+    pub error_comment: Option<String>,
+    /// the UID of the affected SOP instance, if provided
+    pub sop_instance_uid: Option<String>,
+    /// the attributes returned by the peer, if any
+    pub attributes: Option<InMemDicomObject>,
+}
+
+impl NResponse {
+    /// Classify the status code of the response.
+    pub fn status_type(&self) -> StatusType {
+        StatusType::from_code(self.status)
+    }
+
+    /// Whether the operation succeeded,
+    /// possibly with warnings.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self.status_type(),
+            StatusType::Success | StatusType::Warning
+        )
+    }
+}
+
+/// Encode a command set in its transfer syntax (Implicit VR Little Endian).
+pub fn write_command(command: &InMemDicomObject) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(128);
+    command
+        .write_dataset_with_ts(&mut out, &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .context(WriteCommandSnafu)?;
+    Ok(out)
+}
+
+/// Decode a command set from its transfer syntax (Implicit VR Little Endian).
+pub fn read_command(data: &[u8]) -> Result<InMemDicomObject> {
+    InMemDicomObject::read_dataset_with_ts(data, &IMPLICIT_VR_LITTLE_ENDIAN.erased())
+        .context(ReadCommandSnafu)
+}
+
+/// Encode a data set in the transfer syntax
+/// of the given presentation context.
+pub fn write_data_set<A>(
+    association: &A,
+    presentation_context_id: u8,
+    data_set: &InMemDicomObject,
+) -> Result<Vec<u8>>
+where
+    A: Association + ?Sized,
+{
+    let pc = presentation_context_by_id(association, presentation_context_id)?;
+    let ts = TransferSyntaxRegistry.get(&pc.transfer_syntax).context(
+        UnsupportedTransferSyntaxSnafu {
+            uid: pc.transfer_syntax.clone(),
+        },
+    )?;
+    let mut out = Vec::with_capacity(1024);
+    data_set
+        .write_dataset_with_ts(&mut out, ts)
+        .context(WriteDataSetSnafu)?;
+    Ok(out)
+}
+
+/// Find the first accepted presentation context
+/// for the given abstract syntax.
+pub fn presentation_context_for<'a, A>(
+    association: &'a A,
+    abstract_syntax: &str,
+) -> Result<&'a PresentationContextNegotiated>
+where
+    A: Association + ?Sized,
+{
+    association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| {
+            pc.reason == PresentationContextResultReason::Acceptance
+                && pc.abstract_syntax == abstract_syntax
+        })
+        .context(NoPresentationContextSnafu { abstract_syntax })
+}
+
+fn presentation_context_by_id<A>(
+    association: &A,
+    presentation_context_id: u8,
+) -> Result<&PresentationContextNegotiated>
+where
+    A: Association + ?Sized,
+{
+    association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| pc.id == presentation_context_id)
+        .context(NoPresentationContextSnafu {
+            abstract_syntax: format!("(presentation context #{presentation_context_id})"),
+        })
+}
+
+/// Send a DIMSE message through the association.
+///
+/// The command set is encoded in Implicit VR Little Endian,
+/// whereas `data` should already be encoded
+/// in the transfer syntax of the presentation context.
+/// The _Command Data Set Type_ in `command` should reflect
+/// whether `data` is present.
+///
+/// The message is sent in a single PDU if possible,
+/// otherwise the data set is split into multiple P-Data PDUs.
+pub fn send_message<A, S>(
+    association: &mut A,
+    presentation_context_id: u8,
+    command: &InMemDicomObject,
+    data: Option<&[u8]>,
+) -> Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let command_data = write_command(command)?;
+    let command_value = PDataValue {
+        presentation_context_id,
+        value_type: PDataValueType::Command,
+        is_last: true,
+        data: command_data,
+    };
+
+    let Some(data) = data else {
+        return SyncAssociation::send(
+            association,
+            &Pdu::PData {
+                data: vec![command_value],
+            },
+        )
+        .context(AssociationSnafu);
+    };
+
+    let nbytes = command_value.data.len() + data.len();
+    if nbytes < association.peer_max_pdu_length().saturating_sub(100) as usize {
+        SyncAssociation::send(
+            association,
+            &Pdu::PData {
+                data: vec![
+                    command_value,
+                    PDataValue {
+                        presentation_context_id,
+                        value_type: PDataValueType::Data,
+                        is_last: true,
+                        data: data.to_vec(),
+                    },
+                ],
+            },
+        )
+        .context(AssociationSnafu)
+    } else {
+        SyncAssociation::send(
+            association,
+            &Pdu::PData {
+                data: vec![command_value],
+            },
+        )
+        .context(AssociationSnafu)?;
+        let mut pdata = association.send_pdata(presentation_context_id);
+        pdata.write_all(data).context(SendDataSnafu)?;
+        pdata.finish().context(SendDataSnafu)
+    }
+}
+
+/// Receive a full DIMSE message from the association.
+///
+/// The command set is decoded,
+/// whereas the data set (if present) is gathered in its encoded form,
+/// to be decoded with [`Message::read_data_set`].
+///
+/// If the peer requests to release the association,
+/// [`Error::Released`] is returned,
+/// and the caller is expected to reply with an A-RELEASE-RP.
+/// An A-ABORT results in [`Error::Aborted`].
+pub fn receive_message<A, S>(association: &mut A) -> Result<Message>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    let mut command_data: Vec<u8> = Vec::new();
+    let mut command: Option<(u8, InMemDicomObject)> = None;
+    let mut data: Vec<u8> = Vec::new();
+
+    loop {
+        let pdu = SyncAssociation::receive(association).context(AssociationSnafu)?;
+        let values = match pdu {
+            Pdu::PData { data } => data,
+            Pdu::ReleaseRQ => return ReleasedSnafu.fail(),
+            Pdu::AbortRQ { .. } => return AbortedSnafu.fail(),
+            pdu => {
+                return UnexpectedPduSnafu { pdu }.fail();
+            }
+        };
+
+        for mut value in values {
+            match (&value.value_type, &command) {
+                (PDataValueType::Command, None) => {
+                    command_data.append(&mut value.data);
+                    if value.is_last {
+                        let obj = read_command(&command_data)?;
+                        let absent = read_u16(&obj, tags::COMMAND_DATA_SET_TYPE)
+                            .map(|v| v == DATA_SET_ABSENT)
+                            .unwrap_or(true);
+                        if absent {
+                            return Ok(Message {
+                                presentation_context_id: value.presentation_context_id,
+                                command: obj,
+                                data: None,
+                            });
+                        }
+                        command = Some((value.presentation_context_id, obj));
+                    }
+                }
+                (PDataValueType::Data, Some(_)) => {
+                    data.append(&mut value.data);
+                    if value.is_last {
+                        let (presentation_context_id, command) = command.take().unwrap();
+                        return Ok(Message {
+                            presentation_context_id,
+                            command,
+                            data: Some(data),
+                        });
+                    }
+                }
+                (PDataValueType::Command, Some(_)) | (PDataValueType::Data, None) => {
+                    return UnexpectedPduSnafu {
+                        pdu: Box::new(Pdu::PData { data: vec![value] }),
+                    }
+                    .fail();
+                }
+            }
+        }
+    }
+}
+
+/// Read an unsigned 16-bit attribute from a command set.
+pub(crate) fn read_u16(command: &InMemDicomObject, tag: Tag) -> Result<u16> {
+    command
+        .get(tag)
+        .context(MissingAttributeSnafu { tag })?
+        .to_int::<u16>()
+        .ok()
+        .context(InvalidAttributeSnafu { tag })
+}
+
+/// Read a UID attribute from a command set,
+/// trimming trailing padding.
+pub(crate) fn read_uid(command: &InMemDicomObject, tag: Tag) -> Result<String> {
+    let uid = command
+        .get(tag)
+        .context(MissingAttributeSnafu { tag })?
+        .to_str()
+        .ok()
+        .context(InvalidAttributeSnafu { tag })?;
+    Ok(uid.trim_end_matches(['\0', ' ']).to_string())
+}
+
+/// Read a UID attribute from a command set if present,
+/// trimming trailing padding.
+pub(crate) fn read_uid_opt(command: &InMemDicomObject, tag: Tag) -> Result<Option<String>> {
+    if command.get(tag).is_none() {
+        return Ok(None);
+    }
+    read_uid(command, tag).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandField, StatusType};
+
+    #[test]
+    fn command_field_codes() {
+        for code in [0x0001, 0x8030, 0x0140, 0x8120, 0x0110, 0x0FFF] {
+            let field = CommandField::from_code(code).unwrap();
+            assert_eq!(field.code(), code);
+        }
+        assert_eq!(CommandField::from_code(0x1234), None);
+        assert!(CommandField::NCreateRsp.is_response());
+        assert!(!CommandField::NSetRq.is_response());
+    }
+
+    #[test]
+    fn status_types() {
+        assert_eq!(StatusType::from_code(0), StatusType::Success);
+        assert_eq!(StatusType::from_code(0x0116), StatusType::Warning);
+        assert_eq!(StatusType::from_code(0xB007), StatusType::Warning);
+        assert_eq!(StatusType::from_code(0xFE00), StatusType::Cancel);
+        assert_eq!(StatusType::from_code(0xFF01), StatusType::Pending);
+        assert_eq!(StatusType::from_code(0x0110), StatusType::Failure);
+        assert_eq!(StatusType::from_code(0xC000), StatusType::Failure);
+    }
+}
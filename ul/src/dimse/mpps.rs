@@ -0,0 +1,655 @@
+//! Modality Performed Procedure Step (MPPS) SOP class
+//!
+//! This module implements the Modality Performed Procedure Step SOP class
+//! (PS3.4 Annex F.7) on top of the DIMSE-N services
+//! N-CREATE, N-SET, and N-GET.
+//!
+//! - [`MppsScu`] lets a modality (or a modality simulator)
+//!   create a performed procedure step
+//!   and later mark it as completed or discontinued.
+//! - The [`MppsScp`] trait describes the behavior of an SCP,
+//!   which can be served over an association with [`serve`].
+//!   [`InMemoryMpps`] is a simple implementation
+//!   keeping all performed procedure steps in memory.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_core::{DataElement, VR};
+//! # use dicom_dictionary_std::{tags, uids};
+//! # use dicom_object::InMemDicomObject;
+//! # use dicom_ul::ClientAssociationOptions;
+//! # use dicom_ul::dimse::mpps::MppsScu;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax(uids::MODALITY_PERFORMED_PROCEDURE_STEP)
+//!     .establish_with("MPPS-SCP@10.0.0.100:104")?;
+//!
+//! let mut scu = MppsScu::new(&mut association)?;
+//! let step_uid = "2.25.123456789";
+//! let attributes = InMemDicomObject::from_element_iter([
+//!     DataElement::new(tags::MODALITY, VR::CS, "CT"),
+//! ]);
+//! let rsp = scu.create(Some(step_uid), attributes)?;
+//! assert!(rsp.is_success());
+//!
+//! // ... acquire images ...
+//!
+//! let rsp = scu.complete(step_uid, InMemDicomObject::new_empty())?;
+//! assert!(rsp.is_success());
+//! association.release()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{collections::HashMap, marker::PhantomData};
+
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use snafu::ResultExt;
+
+use super::{
+    AssociationSnafu, CommandField, Message, NCreateRq, NCreateRsp, NGetRq, NGetRsp, NResponse,
+    NSetRq, NSetRsp, Result, presentation_context_for, read_u16, receive_message, send_message,
+    status, write_data_set,
+};
+use crate::{
+    Pdu,
+    association::{CloseSocket, SyncAssociation},
+};
+
+/// The SOP class UID of Modality Performed Procedure Step
+pub const MPPS_SOP_CLASS_UID: &str = uids::MODALITY_PERFORMED_PROCEDURE_STEP;
+
+/// The state of a performed procedure step,
+/// as in _Performed Procedure Step Status_ (0040,0252).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MppsStatus {
+    /// `IN PROGRESS`
+    InProgress,
+    /// `COMPLETED`
+    Completed,
+    /// `DISCONTINUED`
+    Discontinued,
+}
+
+impl MppsStatus {
+    /// Obtain the code string of this status.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MppsStatus::InProgress => "IN PROGRESS",
+            MppsStatus::Completed => "COMPLETED",
+            MppsStatus::Discontinued => "DISCONTINUED",
+        }
+    }
+
+    /// Interpret a code string as a procedure step status.
+    pub fn from_code_string(value: &str) -> Option<Self> {
+        match value.trim_end_matches([' ', '\0']) {
+            "IN PROGRESS" => Some(MppsStatus::InProgress),
+            "COMPLETED" => Some(MppsStatus::Completed),
+            "DISCONTINUED" => Some(MppsStatus::Discontinued),
+            _ => None,
+        }
+    }
+
+    /// Whether the procedure step can no longer be updated.
+    pub fn is_final(self) -> bool {
+        self != MppsStatus::InProgress
+    }
+
+    fn element(self) -> DataElement<InMemDicomObject> {
+        DataElement::new(
+            tags::PERFORMED_PROCEDURE_STEP_STATUS,
+            VR::CS,
+            PrimitiveValue::from(self.as_str()),
+        )
+    }
+}
+
+/// Read the performed procedure step status of an MPPS instance.
+fn step_status(obj: &InMemDicomObject) -> Option<Option<MppsStatus>> {
+    obj.get(tags::PERFORMED_PROCEDURE_STEP_STATUS).map(|e| {
+        e.to_str()
+            .ok()
+            .and_then(|s| MppsStatus::from_code_string(&s))
+    })
+}
+
+/// A Modality Performed Procedure Step SCU
+/// operating over an established association.
+///
+/// The association must have an accepted presentation context
+/// for the MPPS SOP class.
+/// Message IDs are assigned incrementally, starting from 1.
+pub struct MppsScu<'a, A, S> {
+    association: &'a mut A,
+    presentation_context_id: u8,
+    message_id: u16,
+    _stream: PhantomData<fn(S)>,
+}
+
+impl<'a, A, S> MppsScu<'a, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    /// Prepare an MPPS SCU over the given association.
+    ///
+    /// Returns an error if no presentation context
+    /// was accepted for the MPPS SOP class.
+    pub fn new(association: &'a mut A) -> Result<Self> {
+        let presentation_context_id =
+            presentation_context_for(&*association, MPPS_SOP_CLASS_UID)?.id;
+        Ok(MppsScu {
+            association,
+            presentation_context_id,
+            message_id: 1,
+            _stream: PhantomData,
+        })
+    }
+
+    fn next_message_id(&mut self) -> u16 {
+        let id = self.message_id;
+        self.message_id = self.message_id.wrapping_add(1).max(1);
+        id
+    }
+
+    /// Create a new performed procedure step with an N-CREATE request.
+    ///
+    /// If `sop_instance_uid` is `None`,
+    /// the SCP is expected to assign a UID,
+    /// which is then available in the response.
+    /// _Performed Procedure Step Status_ is set to `IN PROGRESS`
+    /// if not present in `attributes`.
+    pub fn create(
+        &mut self,
+        sop_instance_uid: Option<&str>,
+        mut attributes: InMemDicomObject,
+    ) -> Result<NResponse> {
+        if attributes
+            .get(tags::PERFORMED_PROCEDURE_STEP_STATUS)
+            .is_none()
+        {
+            attributes.put(MppsStatus::InProgress.element());
+        }
+        let rq = NCreateRq {
+            message_id: self.next_message_id(),
+            affected_sop_class_uid: MPPS_SOP_CLASS_UID.to_string(),
+            affected_sop_instance_uid: sop_instance_uid.map(String::from),
+        };
+        let data = write_data_set(
+            &*self.association,
+            self.presentation_context_id,
+            &attributes,
+        )?;
+        send_message(
+            self.association,
+            self.presentation_context_id,
+            &rq.command(true),
+            Some(&data),
+        )?;
+        let msg = receive_message(self.association)?;
+        let rsp = NCreateRsp::from_command(&msg.command)?;
+        let attributes = msg.read_data_set(&*self.association)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp
+                .affected_sop_instance_uid
+                .or_else(|| sop_instance_uid.map(String::from)),
+            attributes,
+        })
+    }
+
+    /// Modify an existing performed procedure step with an N-SET request.
+    pub fn set(
+        &mut self,
+        sop_instance_uid: &str,
+        modifications: InMemDicomObject,
+    ) -> Result<NResponse> {
+        let rq = NSetRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: MPPS_SOP_CLASS_UID.to_string(),
+            requested_sop_instance_uid: sop_instance_uid.to_string(),
+        };
+        let data = write_data_set(
+            &*self.association,
+            self.presentation_context_id,
+            &modifications,
+        )?;
+        send_message(
+            self.association,
+            self.presentation_context_id,
+            &rq.command(),
+            Some(&data),
+        )?;
+        let msg = receive_message(self.association)?;
+        let rsp = NSetRsp::from_command(&msg.command)?;
+        let attributes = msg.read_data_set(&*self.association)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes,
+        })
+    }
+
+    /// Mark the performed procedure step as `COMPLETED`,
+    /// along with any other final modifications.
+    pub fn complete(
+        &mut self,
+        sop_instance_uid: &str,
+        mut modifications: InMemDicomObject,
+    ) -> Result<NResponse> {
+        modifications.put(MppsStatus::Completed.element());
+        self.set(sop_instance_uid, modifications)
+    }
+
+    /// Mark the performed procedure step as `DISCONTINUED`,
+    /// along with any other final modifications
+    /// (such as the discontinuation reason).
+    pub fn discontinue(
+        &mut self,
+        sop_instance_uid: &str,
+        mut modifications: InMemDicomObject,
+    ) -> Result<NResponse> {
+        modifications.put(MppsStatus::Discontinued.element());
+        self.set(sop_instance_uid, modifications)
+    }
+
+    /// Retrieve attributes of a performed procedure step
+    /// with an N-GET request.
+    ///
+    /// An empty list of attributes requests all attributes.
+    pub fn get(&mut self, sop_instance_uid: &str, attributes: &[Tag]) -> Result<NResponse> {
+        let rq = NGetRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: MPPS_SOP_CLASS_UID.to_string(),
+            requested_sop_instance_uid: sop_instance_uid.to_string(),
+            attribute_identifier_list: attributes.to_vec(),
+        };
+        send_message(
+            self.association,
+            self.presentation_context_id,
+            &rq.command(),
+            None,
+        )?;
+        let msg = receive_message(self.association)?;
+        let rsp = NGetRsp::from_command(&msg.command)?;
+        let attributes = msg.read_data_set(&*self.association)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes,
+        })
+    }
+}
+
+/// A failed MPPS operation, to be reported back to the SCU.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScpFailure {
+    /// the DIMSE status code (see [`status`](super::status))
+    pub status: u16,
+    /// a description of the failure
+    pub error_comment: Option<String>,
+}
+
+impl ScpFailure {
+    /// Create a failure with the given status code and no comment.
+    pub fn new(status: u16) -> Self {
+        ScpFailure {
+            status,
+            error_comment: None,
+        }
+    }
+
+    /// Create a failure with the given status code and comment.
+    pub fn with_comment(status: u16, comment: impl Into<String>) -> Self {
+        ScpFailure {
+            status,
+            error_comment: Some(comment.into()),
+        }
+    }
+}
+
+/// The outcome of an MPPS operation on the SCP side.
+pub type ScpResult<T> = std::result::Result<T, ScpFailure>;
+
+/// The behavior of a Modality Performed Procedure Step SCP.
+///
+/// Implementations are driven by [`serve`],
+/// which takes care of the DIMSE message exchange.
+pub trait MppsScp {
+    /// Handle the creation of a performed procedure step (N-CREATE).
+    ///
+    /// `sop_instance_uid` is `None` if the SCU requests the SCP
+    /// to assign a UID.
+    /// Returns the UID of the new performed procedure step.
+    fn create(
+        &mut self,
+        sop_instance_uid: Option<&str>,
+        attributes: InMemDicomObject,
+    ) -> ScpResult<String>;
+
+    /// Handle the modification of a performed procedure step (N-SET).
+    fn set(&mut self, sop_instance_uid: &str, modifications: InMemDicomObject) -> ScpResult<()>;
+
+    /// Handle the retrieval of attributes of a performed procedure step (N-GET).
+    ///
+    /// An empty list of attributes requests all attributes.
+    /// The default implementation refuses the operation.
+    fn get(&mut self, sop_instance_uid: &str, attributes: &[Tag]) -> ScpResult<InMemDicomObject> {
+        let _ = (sop_instance_uid, attributes);
+        Err(ScpFailure::new(status::UNRECOGNIZED_OPERATION))
+    }
+}
+
+/// A Modality Performed Procedure Step SCP
+/// which keeps all procedure steps in memory.
+///
+/// This implementation follows the state rules of PS3.4 F.7.2:
+/// steps are created `IN PROGRESS`,
+/// and can no longer be modified once `COMPLETED` or `DISCONTINUED`.
+/// It does not assign UIDs,
+/// so N-CREATE requests must provide the SOP instance UID.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryMpps {
+    instances: HashMap<String, InMemDicomObject>,
+}
+
+impl InMemoryMpps {
+    /// Create an empty MPPS store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieve a performed procedure step by its UID.
+    pub fn instance(&self, sop_instance_uid: &str) -> Option<&InMemDicomObject> {
+        self.instances.get(sop_instance_uid)
+    }
+
+    /// Iterate over all performed procedure steps and their UIDs.
+    pub fn instances(&self) -> impl Iterator<Item = (&str, &InMemDicomObject)> {
+        self.instances.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+impl MppsScp for InMemoryMpps {
+    fn create(
+        &mut self,
+        sop_instance_uid: Option<&str>,
+        attributes: InMemDicomObject,
+    ) -> ScpResult<String> {
+        let Some(sop_instance_uid) = sop_instance_uid else {
+            return Err(ScpFailure::with_comment(
+                status::MISSING_ATTRIBUTE,
+                "Affected SOP Instance UID is required",
+            ));
+        };
+        if self.instances.contains_key(sop_instance_uid) {
+            return Err(ScpFailure::new(status::DUPLICATE_SOP_INSTANCE));
+        }
+        if step_status(&attributes) != Some(Some(MppsStatus::InProgress)) {
+            return Err(ScpFailure::with_comment(
+                status::INVALID_ATTRIBUTE_VALUE,
+                "Performed Procedure Step Status must be IN PROGRESS",
+            ));
+        }
+        self.instances
+            .insert(sop_instance_uid.to_string(), attributes);
+        Ok(sop_instance_uid.to_string())
+    }
+
+    fn set(&mut self, sop_instance_uid: &str, modifications: InMemDicomObject) -> ScpResult<()> {
+        let instance = self
+            .instances
+            .get_mut(sop_instance_uid)
+            .ok_or_else(|| ScpFailure::new(status::NO_SUCH_SOP_INSTANCE))?;
+        if let Some(Some(current)) = step_status(instance)
+            && current.is_final()
+        {
+            return Err(ScpFailure::with_comment(
+                status::PROCESSING_FAILURE,
+                "Performed Procedure Step Object may no longer be updated",
+            ));
+        }
+        if step_status(&modifications) == Some(None) {
+            return Err(ScpFailure::with_comment(
+                status::INVALID_ATTRIBUTE_VALUE,
+                "Invalid Performed Procedure Step Status",
+            ));
+        }
+        for element in modifications {
+            instance.put(element);
+        }
+        Ok(())
+    }
+
+    fn get(&mut self, sop_instance_uid: &str, attributes: &[Tag]) -> ScpResult<InMemDicomObject> {
+        let instance = self
+            .instances
+            .get(sop_instance_uid)
+            .ok_or_else(|| ScpFailure::new(status::NO_SUCH_SOP_INSTANCE))?;
+        if attributes.is_empty() {
+            return Ok(instance.clone());
+        }
+        Ok(InMemDicomObject::from_element_iter(
+            attributes
+                .iter()
+                .filter_map(|tag| instance.get(*tag).cloned()),
+        ))
+    }
+}
+
+/// Serve MPPS requests over an established association
+/// until the peer releases or aborts the association.
+///
+/// N-CREATE, N-SET, and N-GET requests on the MPPS SOP class
+/// are passed to `scp`, and the outcome is sent back to the SCU.
+/// Other requests are refused with the status
+/// _Unrecognized Operation_ (0211H).
+pub fn serve<A, S, P>(association: &mut A, scp: &mut P) -> Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+    P: MppsScp + ?Sized,
+{
+    loop {
+        let msg = match receive_message(association) {
+            Ok(msg) => msg,
+            Err(super::Error::Released { .. }) => {
+                SyncAssociation::send(association, &Pdu::ReleaseRP).context(AssociationSnafu)?;
+                return Ok(());
+            }
+            Err(super::Error::Aborted { .. }) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        handle_message(association, scp, msg)?;
+    }
+}
+
+/// Handle a single DIMSE message as an MPPS SCP,
+/// sending back the respective response.
+pub fn handle_message<A, S, P>(association: &mut A, scp: &mut P, msg: Message) -> Result<()>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+    P: MppsScp + ?Sized,
+{
+    let pc_id = msg.presentation_context_id;
+    let message_id = read_u16(&msg.command, tags::MESSAGE_ID)?;
+
+    match msg.command_field() {
+        Ok(CommandField::NCreateRq) => {
+            let rq = NCreateRq::from_command(&msg.command)?;
+            let outcome = if rq.affected_sop_class_uid != MPPS_SOP_CLASS_UID {
+                Err(ScpFailure::new(status::NO_SUCH_SOP_CLASS))
+            } else {
+                let attributes = msg
+                    .read_data_set(&*association)?
+                    .unwrap_or_else(InMemDicomObject::new_empty);
+                scp.create(rq.affected_sop_instance_uid.as_deref(), attributes)
+            };
+            let (status, error_comment, uid) = match outcome {
+                Ok(uid) => (status::SUCCESS, None, Some(uid)),
+                Err(f) => (f.status, f.error_comment, rq.affected_sop_instance_uid),
+            };
+            let rsp = NCreateRsp {
+                message_id_being_responded_to: message_id,
+                affected_sop_class_uid: Some(rq.affected_sop_class_uid),
+                affected_sop_instance_uid: uid,
+                status,
+                error_comment,
+            };
+            send_message(association, pc_id, &rsp.command(false), None)
+        }
+        Ok(CommandField::NSetRq) => {
+            let rq = NSetRq::from_command(&msg.command)?;
+            let outcome = if rq.requested_sop_class_uid != MPPS_SOP_CLASS_UID {
+                Err(ScpFailure::new(status::NO_SUCH_SOP_CLASS))
+            } else {
+                let modifications = msg
+                    .read_data_set(&*association)?
+                    .unwrap_or_else(InMemDicomObject::new_empty);
+                scp.set(&rq.requested_sop_instance_uid, modifications)
+            };
+            let (status, error_comment) = match outcome {
+                Ok(()) => (status::SUCCESS, None),
+                Err(f) => (f.status, f.error_comment),
+            };
+            let rsp = NSetRsp {
+                message_id_being_responded_to: message_id,
+                affected_sop_class_uid: Some(rq.requested_sop_class_uid),
+                affected_sop_instance_uid: Some(rq.requested_sop_instance_uid),
+                status,
+                error_comment,
+            };
+            send_message(association, pc_id, &rsp.command(false), None)
+        }
+        Ok(CommandField::NGetRq) => {
+            let rq = NGetRq::from_command(&msg.command)?;
+            let outcome = if rq.requested_sop_class_uid != MPPS_SOP_CLASS_UID {
+                Err(ScpFailure::new(status::NO_SUCH_SOP_CLASS))
+            } else {
+                scp.get(
+                    &rq.requested_sop_instance_uid,
+                    &rq.attribute_identifier_list,
+                )
+            };
+            let (status, error_comment, data) = match outcome {
+                Ok(obj) => (
+                    status::SUCCESS,
+                    None,
+                    Some(write_data_set(&*association, pc_id, &obj)?),
+                ),
+                Err(f) => (f.status, f.error_comment, None),
+            };
+            let rsp = NGetRsp {
+                message_id_being_responded_to: message_id,
+                affected_sop_class_uid: Some(rq.requested_sop_class_uid),
+                affected_sop_instance_uid: Some(rq.requested_sop_instance_uid),
+                status,
+                error_comment,
+            };
+            send_message(
+                association,
+                pc_id,
+                &rsp.command(data.is_some()),
+                data.as_deref(),
+            )
+        }
+        _ => {
+            let command_field = read_u16(&msg.command, tags::COMMAND_FIELD)?;
+            let rsp = InMemDicomObject::command_from_element_iter([
+                DataElement::new(
+                    tags::COMMAND_FIELD,
+                    VR::US,
+                    PrimitiveValue::from(command_field | 0x8000),
+                ),
+                DataElement::new(
+                    tags::MESSAGE_ID_BEING_RESPONDED_TO,
+                    VR::US,
+                    PrimitiveValue::from(message_id),
+                ),
+                DataElement::new(
+                    tags::COMMAND_DATA_SET_TYPE,
+                    VR::US,
+                    PrimitiveValue::from(super::DATA_SET_ABSENT),
+                ),
+                DataElement::new(
+                    tags::STATUS,
+                    VR::US,
+                    PrimitiveValue::from(status::UNRECOGNIZED_OPERATION),
+                ),
+            ]);
+            send_message(association, pc_id, &rsp, None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_progress() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            MppsStatus::InProgress.element(),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+        ])
+    }
+
+    #[test]
+    fn status_code_strings() {
+        for status in [
+            MppsStatus::InProgress,
+            MppsStatus::Completed,
+            MppsStatus::Discontinued,
+        ] {
+            assert_eq!(MppsStatus::from_code_string(status.as_str()), Some(status));
+        }
+        assert_eq!(
+            MppsStatus::from_code_string("COMPLETED "),
+            Some(MppsStatus::Completed)
+        );
+        assert_eq!(MppsStatus::from_code_string("DONE"), None);
+    }
+
+    #[test]
+    fn in_memory_state_transitions() {
+        let mut scp = InMemoryMpps::new();
+        assert_eq!(scp.create(Some("1.2.3"), in_progress()), Ok("1.2.3".into()));
+        assert_eq!(
+            scp.create(Some("1.2.3"), in_progress()),
+            Err(ScpFailure::new(status::DUPLICATE_SOP_INSTANCE))
+        );
+        assert_eq!(
+            scp.create(None, in_progress()).unwrap_err().status,
+            status::MISSING_ATTRIBUTE
+        );
+
+        let completed = InMemDicomObject::from_element_iter([MppsStatus::Completed.element()]);
+        assert_eq!(scp.set("1.2.3", completed.clone()), Ok(()));
+        assert_eq!(
+            step_status(scp.instance("1.2.3").unwrap()),
+            Some(Some(MppsStatus::Completed))
+        );
+        // the other attributes are retained
+        assert!(scp.instance("1.2.3").unwrap().get(tags::MODALITY).is_some());
+
+        // no longer modifiable
+        assert_eq!(
+            scp.set("1.2.3", completed.clone()).unwrap_err().status,
+            status::PROCESSING_FAILURE
+        );
+        assert_eq!(
+            scp.set("9.9.9", completed).unwrap_err().status,
+            status::NO_SUCH_SOP_INSTANCE
+        );
+
+        let obj = scp.get("1.2.3", &[tags::MODALITY]).unwrap();
+        assert_eq!(obj.iter().count(), 1);
+        assert_eq!(obj.get(tags::MODALITY).unwrap().to_str().unwrap(), "CT");
+    }
+}
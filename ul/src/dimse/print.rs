@@ -0,0 +1,600 @@
+//! Print Management service class user
+//!
+//! This module implements the SCU side of the
+//! Basic Grayscale Print Management meta SOP class (PS3.4 Annex H),
+//! which comprises the following SOP classes:
+//!
+//! - Basic Film Session (N-CREATE, N-SET, N-ACTION, N-DELETE)
+//! - Basic Film Box (N-CREATE, N-ACTION, N-DELETE)
+//! - Basic Grayscale Image Box (N-SET)
+//! - Printer (N-GET)
+//!
+//! The association must have an accepted presentation context
+//! for the meta SOP class,
+//! through which all messages are sent.
+//!
+//! A typical print job creates a film session,
+//! creates a film box in that session
+//! (which makes the SCP create the respective image boxes),
+//! fills each image box with pixel data,
+//! and then prints the film box.
+//! [`PrintScu::print_images`] performs all of these steps at once.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use dicom_dictionary_std::uids;
+//! # use dicom_object::InMemDicomObject;
+//! # use dicom_ul::ClientAssociationOptions;
+//! # use dicom_ul::dimse::print::{GrayscaleImage, PrintScu};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut association = ClientAssociationOptions::new()
+//!     .with_abstract_syntax(uids::BASIC_GRAYSCALE_PRINT_MANAGEMENT_META)
+//!     .establish_with("PRINT-SCP@10.0.0.100:104")?;
+//!
+//! let image = GrayscaleImage::new_8bit(256, 256, vec![0x80; 256 * 256]);
+//! let mut scu = PrintScu::new(&mut association)?;
+//! scu.print_images(
+//!     InMemDicomObject::new_empty(),
+//!     InMemDicomObject::new_empty(),
+//!     &[image],
+//! )?;
+//! association.release()?;
+//! # Ok(())
+//! # }
+//! ```
+use std::marker::PhantomData;
+
+use dicom_core::{DataElement, PrimitiveValue, VR, dicom_value, value::DataSetSequence};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use snafu::ensure;
+
+use super::{
+    Message, NActionRq, NActionRsp, NCreateRq, NCreateRsp, NDeleteRq, NDeleteRsp, NGetRq, NGetRsp,
+    NResponse, NSetRq, NSetRsp, OperationFailedSnafu, Result, presentation_context_for,
+    read_uid_opt, receive_message, send_message, write_data_set,
+};
+use crate::association::{CloseSocket, SyncAssociation};
+
+/// The UID of the Basic Grayscale Print Management meta SOP class
+pub const META_SOP_CLASS_UID: &str = uids::BASIC_GRAYSCALE_PRINT_MANAGEMENT_META;
+
+/// The well-known SOP instance UID of the Printer SOP instance
+pub const PRINTER_SOP_INSTANCE_UID: &str = "1.2.840.10008.5.1.1.17";
+
+/// The N-ACTION action type ID for printing a film session or film box
+pub const ACTION_PRINT: u16 = 1;
+
+/// A grayscale image to place in an image box.
+///
+/// Images are always sent with the photometric interpretation
+/// `MONOCHROME2` (lowest value is black).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrayscaleImage {
+    rows: u16,
+    columns: u16,
+    bits_stored: u16,
+    pixel_data: PrimitiveValue,
+}
+
+impl GrayscaleImage {
+    /// Create an image with 8 bits per pixel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of samples does not match
+    /// the given image dimensions.
+    pub fn new_8bit(rows: u16, columns: u16, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), rows as usize * columns as usize);
+        GrayscaleImage {
+            rows,
+            columns,
+            bits_stored: 8,
+            pixel_data: PrimitiveValue::from(pixels),
+        }
+    }
+
+    /// Create an image with 12 bits per pixel,
+    /// stored in 16-bit samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of samples does not match
+    /// the given image dimensions.
+    pub fn new_12bit(rows: u16, columns: u16, pixels: Vec<u16>) -> Self {
+        assert_eq!(pixels.len(), rows as usize * columns as usize);
+        GrayscaleImage {
+            rows,
+            columns,
+            bits_stored: 12,
+            pixel_data: PrimitiveValue::U16(pixels.into()),
+        }
+    }
+
+    /// The number of rows in the image.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// The number of columns in the image.
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    /// The number of bits stored per pixel (8 or 12).
+    pub fn bits_stored(&self) -> u16 {
+        self.bits_stored
+    }
+
+    /// Build the item of _Basic Grayscale Image Sequence_ (2020,0110).
+    fn to_item(&self) -> InMemDicomObject {
+        let bits_allocated = if self.bits_stored > 8 { 16 } else { 8 };
+        InMemDicomObject::from_element_iter([
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, dicom_value!(U16, [1])),
+            DataElement::new(
+                tags::PHOTOMETRIC_INTERPRETATION,
+                VR::CS,
+                PrimitiveValue::from("MONOCHROME2"),
+            ),
+            DataElement::new(tags::ROWS, VR::US, dicom_value!(U16, [self.rows])),
+            DataElement::new(tags::COLUMNS, VR::US, dicom_value!(U16, [self.columns])),
+            DataElement::new(
+                tags::PIXEL_ASPECT_RATIO,
+                VR::IS,
+                dicom_value!(Strs, ["1", "1"]),
+            ),
+            DataElement::new(
+                tags::BITS_ALLOCATED,
+                VR::US,
+                dicom_value!(U16, [bits_allocated]),
+            ),
+            DataElement::new(
+                tags::BITS_STORED,
+                VR::US,
+                dicom_value!(U16, [self.bits_stored]),
+            ),
+            DataElement::new(
+                tags::HIGH_BIT,
+                VR::US,
+                dicom_value!(U16, [self.bits_stored - 1]),
+            ),
+            DataElement::new(tags::PIXEL_REPRESENTATION, VR::US, dicom_value!(U16, [0])),
+            DataElement::new(
+                tags::PIXEL_DATA,
+                if bits_allocated == 8 { VR::OB } else { VR::OW },
+                self.pixel_data.clone(),
+            ),
+        ])
+    }
+}
+
+/// A film box created by the print SCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilmBox {
+    /// the SOP instance UID of the film box
+    pub sop_instance_uid: String,
+    /// the SOP instance UIDs of the image boxes in the film box,
+    /// in image box position order
+    pub image_box_uids: Vec<String>,
+}
+
+impl FilmBox {
+    /// Obtain the film box from the response to its creation.
+    ///
+    /// Returns `None` if the response does not identify the film box.
+    pub fn from_response(rsp: &NResponse) -> Option<Self> {
+        let sop_instance_uid = rsp.sop_instance_uid.clone()?;
+        let image_box_uids = rsp
+            .attributes
+            .as_ref()
+            .and_then(|obj| obj.get(tags::REFERENCED_IMAGE_BOX_SEQUENCE))
+            .and_then(|e| e.items())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        read_uid_opt(item, tags::REFERENCED_SOP_INSTANCE_UID)
+                            .ok()
+                            .flatten()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(FilmBox {
+            sop_instance_uid,
+            image_box_uids,
+        })
+    }
+}
+
+/// A Basic Grayscale Print Management SCU
+/// operating over an established association.
+///
+/// Message IDs are assigned incrementally, starting from 1.
+pub struct PrintScu<'a, A, S> {
+    association: &'a mut A,
+    presentation_context_id: u8,
+    message_id: u16,
+    _stream: PhantomData<fn(S)>,
+}
+
+impl<'a, A, S> PrintScu<'a, A, S>
+where
+    A: SyncAssociation<S>,
+    S: std::io::Read + std::io::Write + CloseSocket,
+{
+    /// Prepare a print SCU over the given association.
+    ///
+    /// Returns an error if no presentation context
+    /// was accepted for the Basic Grayscale Print Management meta SOP class.
+    pub fn new(association: &'a mut A) -> Result<Self> {
+        let presentation_context_id =
+            presentation_context_for(&*association, META_SOP_CLASS_UID)?.id;
+        Ok(PrintScu {
+            association,
+            presentation_context_id,
+            message_id: 1,
+            _stream: PhantomData,
+        })
+    }
+
+    fn next_message_id(&mut self) -> u16 {
+        let id = self.message_id;
+        self.message_id = self.message_id.wrapping_add(1).max(1);
+        id
+    }
+
+    /// Send a request and wait for its response.
+    fn request(
+        &mut self,
+        command: &InMemDicomObject,
+        data: Option<&InMemDicomObject>,
+    ) -> Result<Message> {
+        let data = data
+            .map(|obj| write_data_set(&*self.association, self.presentation_context_id, obj))
+            .transpose()?;
+        send_message(
+            self.association,
+            self.presentation_context_id,
+            command,
+            data.as_deref(),
+        )?;
+        receive_message(self.association)
+    }
+
+    fn create(&mut self, sop_class_uid: &str, attributes: &InMemDicomObject) -> Result<NResponse> {
+        let rq = NCreateRq {
+            message_id: self.next_message_id(),
+            affected_sop_class_uid: sop_class_uid.to_string(),
+            affected_sop_instance_uid: None,
+        };
+        let msg = self.request(&rq.command(true), Some(attributes))?;
+        let rsp = NCreateRsp::from_command(&msg.command)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes: msg.read_data_set(&*self.association)?,
+        })
+    }
+
+    fn set(
+        &mut self,
+        sop_class_uid: &str,
+        sop_instance_uid: &str,
+        modifications: &InMemDicomObject,
+    ) -> Result<NResponse> {
+        let rq = NSetRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: sop_class_uid.to_string(),
+            requested_sop_instance_uid: sop_instance_uid.to_string(),
+        };
+        let msg = self.request(&rq.command(), Some(modifications))?;
+        let rsp = NSetRsp::from_command(&msg.command)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes: msg.read_data_set(&*self.association)?,
+        })
+    }
+
+    fn action(&mut self, sop_class_uid: &str, sop_instance_uid: &str) -> Result<NResponse> {
+        let rq = NActionRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: sop_class_uid.to_string(),
+            requested_sop_instance_uid: sop_instance_uid.to_string(),
+            action_type_id: ACTION_PRINT,
+        };
+        let msg = self.request(&rq.command(false), None)?;
+        let rsp = NActionRsp::from_command(&msg.command)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes: msg.read_data_set(&*self.association)?,
+        })
+    }
+
+    fn delete(&mut self, sop_class_uid: &str, sop_instance_uid: &str) -> Result<NResponse> {
+        let rq = NDeleteRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: sop_class_uid.to_string(),
+            requested_sop_instance_uid: sop_instance_uid.to_string(),
+        };
+        let msg = self.request(&rq.command(), None)?;
+        let rsp = NDeleteRsp::from_command(&msg.command)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes: None,
+        })
+    }
+
+    /// Create a film session with an N-CREATE request.
+    ///
+    /// `attributes` may contain attributes such as
+    /// _Number of Copies_, _Medium Type_, or _Film Destination_.
+    /// The SOP instance UID of the film session
+    /// is assigned by the SCP and available in the response.
+    pub fn create_film_session(&mut self, attributes: &InMemDicomObject) -> Result<NResponse> {
+        self.create(uids::BASIC_FILM_SESSION, attributes)
+    }
+
+    /// Create a film box in a film session with an N-CREATE request.
+    ///
+    /// `attributes` may contain attributes such as
+    /// _Image Display Format_, _Film Orientation_, or _Film Size ID_.
+    /// _Image Display Format_ defaults to `STANDARD\1,1`,
+    /// and the reference to the film session is added automatically.
+    /// See [`FilmBox::from_response`]
+    /// to retrieve the image boxes created.
+    pub fn create_film_box(
+        &mut self,
+        film_session_uid: &str,
+        attributes: &InMemDicomObject,
+    ) -> Result<NResponse> {
+        let mut attributes = attributes.clone();
+        if attributes.get(tags::IMAGE_DISPLAY_FORMAT).is_none() {
+            attributes.put(DataElement::new(
+                tags::IMAGE_DISPLAY_FORMAT,
+                VR::ST,
+                PrimitiveValue::from("STANDARD\\1,1"),
+            ));
+        }
+        attributes.put(DataElement::new(
+            tags::REFERENCED_FILM_SESSION_SEQUENCE,
+            VR::SQ,
+            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::REFERENCED_SOP_CLASS_UID,
+                    VR::UI,
+                    PrimitiveValue::from(uids::BASIC_FILM_SESSION),
+                ),
+                DataElement::new(
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    PrimitiveValue::from(film_session_uid),
+                ),
+            ])]),
+        ));
+        self.create(uids::BASIC_FILM_BOX, &attributes)
+    }
+
+    /// Place an image in an image box with an N-SET request.
+    ///
+    /// `position` is the _Image Box Position_,
+    /// starting from 1.
+    pub fn set_image_box(
+        &mut self,
+        image_box_uid: &str,
+        position: u16,
+        image: &GrayscaleImage,
+    ) -> Result<NResponse> {
+        let modifications = InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::IMAGE_BOX_POSITION,
+                VR::US,
+                dicom_value!(U16, [position]),
+            ),
+            DataElement::new(
+                tags::BASIC_GRAYSCALE_IMAGE_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![image.to_item()]),
+            ),
+        ]);
+        self.set(
+            uids::BASIC_GRAYSCALE_IMAGE_BOX,
+            image_box_uid,
+            &modifications,
+        )
+    }
+
+    /// Print a film box with an N-ACTION request.
+    pub fn print_film_box(&mut self, film_box_uid: &str) -> Result<NResponse> {
+        self.action(uids::BASIC_FILM_BOX, film_box_uid)
+    }
+
+    /// Print all film boxes of a film session with an N-ACTION request.
+    pub fn print_film_session(&mut self, film_session_uid: &str) -> Result<NResponse> {
+        self.action(uids::BASIC_FILM_SESSION, film_session_uid)
+    }
+
+    /// Delete a film box with an N-DELETE request.
+    pub fn delete_film_box(&mut self, film_box_uid: &str) -> Result<NResponse> {
+        self.delete(uids::BASIC_FILM_BOX, film_box_uid)
+    }
+
+    /// Delete a film session
+    /// (and all of its film boxes)
+    /// with an N-DELETE request.
+    pub fn delete_film_session(&mut self, film_session_uid: &str) -> Result<NResponse> {
+        self.delete(uids::BASIC_FILM_SESSION, film_session_uid)
+    }
+
+    /// Retrieve the status of the printer with an N-GET request.
+    ///
+    /// An empty list of attributes requests all attributes.
+    pub fn printer_status(&mut self, attributes: &[dicom_core::Tag]) -> Result<NResponse> {
+        let rq = NGetRq {
+            message_id: self.next_message_id(),
+            requested_sop_class_uid: uids::PRINTER.to_string(),
+            requested_sop_instance_uid: PRINTER_SOP_INSTANCE_UID.to_string(),
+            attribute_identifier_list: attributes.to_vec(),
+        };
+        let msg = self.request(&rq.command(), None)?;
+        let rsp = NGetRsp::from_command(&msg.command)?;
+        Ok(NResponse {
+            status: rsp.status,
+            error_comment: rsp.error_comment,
+            sop_instance_uid: rsp.affected_sop_instance_uid,
+            attributes: msg.read_data_set(&*self.association)?,
+        })
+    }
+
+    /// Print the given images in a single film.
+    ///
+    /// This creates a film session with `film_session` attributes,
+    /// creates a film box with `film_box` attributes,
+    /// fills the image boxes in order,
+    /// prints the film box,
+    /// and finally deletes the film session.
+    /// If _Image Display Format_ is not specified in `film_box`,
+    /// it is set to fit all images in a single row.
+    ///
+    /// Returns an error if any of these operations fails,
+    /// or if the film box has fewer image boxes than there are images.
+    /// The film session is not deleted in case of failure.
+    pub fn print_images(
+        &mut self,
+        film_session: InMemDicomObject,
+        mut film_box: InMemDicomObject,
+        images: &[GrayscaleImage],
+    ) -> Result<FilmBox> {
+        let rsp = self.create_film_session(&film_session)?;
+        ensure_success("create film session", &rsp)?;
+        let film_session_uid = rsp
+            .sop_instance_uid
+            .ok_or_else(|| missing_uid("create film session"))?;
+
+        if film_box.get(tags::IMAGE_DISPLAY_FORMAT).is_none() {
+            film_box.put(DataElement::new(
+                tags::IMAGE_DISPLAY_FORMAT,
+                VR::ST,
+                PrimitiveValue::from(format!("STANDARD\\{},1", images.len().max(1))),
+            ));
+        }
+        let rsp = self.create_film_box(&film_session_uid, &film_box)?;
+        ensure_success("create film box", &rsp)?;
+        let film_box =
+            FilmBox::from_response(&rsp).ok_or_else(|| missing_uid("create film box"))?;
+        ensure!(
+            film_box.image_box_uids.len() >= images.len(),
+            OperationFailedSnafu {
+                operation: "create film box",
+                status: rsp.status,
+                error_comment: Some(format!(
+                    "film box has {} image boxes, {} required",
+                    film_box.image_box_uids.len(),
+                    images.len()
+                )),
+            }
+        );
+
+        for (i, (image, image_box_uid)) in images.iter().zip(&film_box.image_box_uids).enumerate() {
+            let rsp = self.set_image_box(image_box_uid, i as u16 + 1, image)?;
+            ensure_success("set image box", &rsp)?;
+        }
+
+        let rsp = self.print_film_box(&film_box.sop_instance_uid)?;
+        ensure_success("print film box", &rsp)?;
+
+        let rsp = self.delete_film_session(&film_session_uid)?;
+        ensure_success("delete film session", &rsp)?;
+
+        Ok(film_box)
+    }
+}
+
+fn ensure_success(operation: &'static str, rsp: &NResponse) -> Result<()> {
+    ensure!(
+        rsp.is_success(),
+        OperationFailedSnafu {
+            operation,
+            status: rsp.status,
+            error_comment: rsp.error_comment.clone(),
+        }
+    );
+    Ok(())
+}
+
+fn missing_uid(operation: &'static str) -> super::Error {
+    OperationFailedSnafu {
+        operation,
+        status: super::status::SUCCESS,
+        error_comment: Some("missing affected SOP instance UID".to_string()),
+    }
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grayscale_image_item() {
+        let image = GrayscaleImage::new_12bit(2, 3, vec![0, 1, 2, 3, 4, 0x0FFF]);
+        let item = image.to_item();
+        assert_eq!(item.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 2);
+        assert_eq!(item.get(tags::COLUMNS).unwrap().to_int::<u16>().unwrap(), 3);
+        assert_eq!(
+            item.get(tags::BITS_ALLOCATED)
+                .unwrap()
+                .to_int::<u16>()
+                .unwrap(),
+            16
+        );
+        assert_eq!(
+            item.get(tags::HIGH_BIT).unwrap().to_int::<u16>().unwrap(),
+            11
+        );
+        assert_eq!(item.get(tags::PIXEL_DATA).unwrap().vr(), VR::OW);
+    }
+
+    #[test]
+    fn film_box_from_response() {
+        let item = |uid: &str| {
+            InMemDicomObject::from_element_iter([
+                DataElement::new(
+                    tags::REFERENCED_SOP_CLASS_UID,
+                    VR::UI,
+                    PrimitiveValue::from(uids::BASIC_GRAYSCALE_IMAGE_BOX),
+                ),
+                DataElement::new(
+                    tags::REFERENCED_SOP_INSTANCE_UID,
+                    VR::UI,
+                    PrimitiveValue::from(uid),
+                ),
+            ])
+        };
+        let rsp = NResponse {
+            status: 0,
+            error_comment: None,
+            sop_instance_uid: Some("1.2.3".to_string()),
+            attributes: Some(InMemDicomObject::from_element_iter([DataElement::new(
+                tags::REFERENCED_IMAGE_BOX_SEQUENCE,
+                VR::SQ,
+                DataSetSequence::from(vec![item("1.2.3.1\0"), item("1.2.3.2")]),
+            )])),
+        };
+        assert_eq!(
+            FilmBox::from_response(&rsp),
+            Some(FilmBox {
+                sop_instance_uid: "1.2.3".to_string(),
+                image_box_uids: vec!["1.2.3.1".to_string(), "1.2.3.2".to_string()],
+            })
+        );
+    }
+}
@@ -0,0 +1,650 @@
+//! Construction of query identifiers
+//!
+//! [`QueryBuilder`] composes the identifier of a C-FIND request
+//! (or the parameters of an equivalent QIDO-RS search)
+//! from pairs of attribute keywords and matching values.
+//! Each value is validated against the kind of matching
+//! admitted by the value representation of the attribute
+//! (PS3.4 C.2.2.2), as classified by [`MatchingType`].
+//! The _Query/Retrieve Level_ and the required return keys of the level
+//! are inserted automatically.
+//!
+//! On the side of the service class provider,
+//! [`matches`] evaluates a query identifier against a candidate object,
+//! and [`response_identifier`] builds the identifier
+//! to respond for each match.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_dictionary_std::tags;
+//! # use dicom_ul::dimse::query::{QueryBuilder, QueryLevel};
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let query = QueryBuilder::new(QueryLevel::Study)
+//!     .with_key("PatientName", "DOE^*")
+//!     .with_key("StudyDate", "20240101-20240131")
+//!     .with_return_key("StudyDescription");
+//!
+//! let identifier = query.build()?;
+//! assert_eq!(identifier.get(tags::QUERY_RETRIEVE_LEVEL).unwrap().to_str()?, "STUDY");
+//! // required return key of the study level
+//! assert!(identifier.get(tags::STUDY_INSTANCE_UID).is_some());
+//!
+//! let params = query.to_qido_params()?;
+//! assert_eq!(params[0], ("PatientName".to_string(), "DOE^*".to_string()));
+//! # Ok(())
+//! # }
+//! ```
+use dicom_core::{
+    DataDictionary, DataElement, PrimitiveValue, Tag, VR, dictionary::DataDictionaryEntry,
+};
+use dicom_dictionary_std::{StandardDataDictionary, tags};
+use dicom_object::InMemDicomObject;
+use snafu::OptionExt;
+
+use super::{InvalidMatchingKeySnafu, Result, UnknownKeywordSnafu};
+
+/// The level of a query in the query/retrieve information model,
+/// as in _Query/Retrieve Level_ (0008,0052).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum QueryLevel {
+    /// `PATIENT`
+    Patient,
+    /// `STUDY`
+    Study,
+    /// `SERIES`
+    Series,
+    /// `IMAGE`
+    Image,
+}
+
+impl QueryLevel {
+    /// Obtain the code string of this level.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryLevel::Patient => "PATIENT",
+            QueryLevel::Study => "STUDY",
+            QueryLevel::Series => "SERIES",
+            QueryLevel::Image => "IMAGE",
+        }
+    }
+
+    /// Obtain the level from its code string,
+    /// ignoring trailing padding.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim_end_matches(['\0', ' ']) {
+            "PATIENT" => Some(QueryLevel::Patient),
+            "STUDY" => Some(QueryLevel::Study),
+            "SERIES" => Some(QueryLevel::Series),
+            "IMAGE" => Some(QueryLevel::Image),
+            _ => None,
+        }
+    }
+
+    /// The required keys of this level (PS3.4 C.6.1.1 and C.6.2.1),
+    /// which the SCP always returns.
+    pub fn required_keys(self) -> &'static [Tag] {
+        match self {
+            QueryLevel::Patient => &[tags::PATIENT_NAME, tags::PATIENT_ID],
+            QueryLevel::Study => &[
+                tags::STUDY_DATE,
+                tags::STUDY_TIME,
+                tags::ACCESSION_NUMBER,
+                tags::PATIENT_NAME,
+                tags::PATIENT_ID,
+                tags::STUDY_ID,
+                tags::STUDY_INSTANCE_UID,
+            ],
+            QueryLevel::Series => &[
+                tags::MODALITY,
+                tags::SERIES_NUMBER,
+                tags::SERIES_INSTANCE_UID,
+            ],
+            QueryLevel::Image => &[tags::INSTANCE_NUMBER, tags::SOP_INSTANCE_UID],
+        }
+    }
+}
+
+/// The kind of matching requested by a matching key value (PS3.4 C.2.2.2).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MatchingType {
+    /// an empty value, which matches everything
+    /// and requests the attribute to be returned
+    Universal,
+    /// a single value to match exactly
+    SingleValue,
+    /// a value with `*` or `?` wildcards
+    Wildcard,
+    /// a date or time range, such as `20240101-20240131`,
+    /// in which either bound may be omitted
+    Range,
+    /// a list of UIDs separated by `\`
+    UidList,
+}
+
+impl MatchingType {
+    /// Classify a matching key value of an attribute
+    /// with the given value representation.
+    ///
+    /// Returns `None` if the value is not admitted
+    /// for the value representation,
+    /// such as wildcards in dates or UIDs,
+    /// or if attributes of this value representation
+    /// cannot be used as matching keys.
+    pub fn classify(vr: VR, value: &str) -> Option<Self> {
+        if value.is_empty() {
+            return Some(MatchingType::Universal);
+        }
+        match vr {
+            VR::UI => {
+                let uids: Vec<_> = value.split('\\').collect();
+                if !uids.iter().all(|uid| {
+                    !uid.is_empty() && uid.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+                }) {
+                    return None;
+                }
+                Some(if uids.len() > 1 {
+                    MatchingType::UidList
+                } else {
+                    MatchingType::SingleValue
+                })
+            }
+            VR::DA | VR::TM | VR::DT => {
+                let valid = |s: &str| {
+                    s.bytes()
+                        .all(|b| b.is_ascii_digit() || (vr != VR::DA && matches!(b, b'.' | b'+')))
+                };
+                match value.split_once('-') {
+                    Some((lower, upper)) => {
+                        (valid(lower) && valid(upper) && !upper.contains('-') && value.len() > 1)
+                            .then_some(MatchingType::Range)
+                    }
+                    None => valid(value).then_some(MatchingType::SingleValue),
+                }
+            }
+            VR::US | VR::UL | VR::UV => {
+                value.parse::<u64>().ok().map(|_| MatchingType::SingleValue)
+            }
+            VR::SS | VR::SL | VR::SV | VR::IS => value
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .map(|_| MatchingType::SingleValue),
+            VR::FL | VR::FD | VR::DS => value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .map(|_| MatchingType::SingleValue),
+            VR::AE
+            | VR::AS
+            | VR::CS
+            | VR::LO
+            | VR::LT
+            | VR::PN
+            | VR::SH
+            | VR::ST
+            | VR::UC
+            | VR::UR
+            | VR::UT => {
+                if value.contains('\\') {
+                    None
+                } else if value.contains(['*', '?']) {
+                    Some(MatchingType::Wildcard)
+                } else {
+                    Some(MatchingType::SingleValue)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A matching or return key resolved from its keyword
+struct Key<'a> {
+    keyword: &'a str,
+    tag: Tag,
+    vr: VR,
+    value: &'a str,
+    matching: MatchingType,
+}
+
+/// A builder of query identifiers from attribute keywords and values.
+///
+/// Keys are kept in the order given,
+/// and setting the same keyword again replaces its value.
+/// Keywords and values are only validated
+/// once the query is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryBuilder {
+    level: QueryLevel,
+    keys: Vec<(String, String)>,
+}
+
+impl QueryBuilder {
+    /// Start a query at the given level.
+    pub fn new(level: QueryLevel) -> Self {
+        QueryBuilder {
+            level,
+            keys: Vec::new(),
+        }
+    }
+
+    /// The level of the query.
+    pub fn level(&self) -> QueryLevel {
+        self.level
+    }
+
+    /// Add a matching key by its attribute keyword,
+    /// such as `PatientName`.
+    ///
+    /// An empty value requests universal matching,
+    /// same as [`with_return_key`](Self::with_return_key).
+    pub fn with_key(mut self, keyword: impl Into<String>, value: impl Into<String>) -> Self {
+        let keyword = keyword.into();
+        let value = value.into();
+        match self.keys.iter_mut().find(|(k, _)| *k == keyword) {
+            Some((_, v)) => *v = value,
+            None => self.keys.push((keyword, value)),
+        }
+        self
+    }
+
+    /// Request an attribute to be returned
+    /// without constraining the matches.
+    pub fn with_return_key(self, keyword: impl Into<String>) -> Self {
+        self.with_key(keyword, "")
+    }
+
+    /// Resolve and validate the keys given.
+    fn resolve(&self) -> Result<Vec<Key<'_>>> {
+        self.keys
+            .iter()
+            .map(|(keyword, value)| {
+                let entry = StandardDataDictionary
+                    .by_name(keyword)
+                    .context(UnknownKeywordSnafu { keyword })?;
+                let vr = entry.vr().relaxed();
+                let matching = MatchingType::classify(vr, value)
+                    .context(InvalidMatchingKeySnafu { keyword, value, vr })?;
+                Ok(Key {
+                    keyword,
+                    tag: entry.tag(),
+                    vr,
+                    value,
+                    matching,
+                })
+            })
+            .collect()
+    }
+
+    /// Build the query identifier,
+    /// for use as the data set of a C-FIND request.
+    ///
+    /// The identifier contains the _Query/Retrieve Level_,
+    /// the keys given,
+    /// and the required keys of the level
+    /// which were not given, with universal matching.
+    pub fn build(&self) -> Result<InMemDicomObject> {
+        let mut obj = InMemDicomObject::from_element_iter([DataElement::new(
+            tags::QUERY_RETRIEVE_LEVEL,
+            VR::CS,
+            self.level.as_str(),
+        )]);
+        for &tag in self.level.required_keys() {
+            let vr = StandardDataDictionary
+                .by_tag(tag)
+                .map(|e| e.vr().relaxed())
+                .unwrap_or(VR::LO);
+            obj.put(DataElement::new(tag, vr, PrimitiveValue::Empty));
+        }
+        for key in self.resolve()? {
+            obj.put(DataElement::new(key.tag, key.vr, key_value(&key)));
+        }
+        Ok(obj)
+    }
+
+    /// Build the query parameters of an equivalent QIDO-RS search.
+    ///
+    /// Matching keys are given by keyword,
+    /// with lists of UIDs separated by commas,
+    /// whereas keys with universal matching
+    /// are requested through `includefield`.
+    /// The required keys of the level are not included,
+    /// since QIDO-RS services always return them.
+    pub fn to_qido_params(&self) -> Result<Vec<(String, String)>> {
+        let keys = self.resolve()?;
+        let mut params: Vec<_> = keys
+            .iter()
+            .filter(|key| key.matching != MatchingType::Universal)
+            .map(|key| (key.keyword.to_string(), key.value.replace('\\', ",")))
+            .collect();
+        let include: Vec<_> = keys
+            .iter()
+            .filter(|key| key.matching == MatchingType::Universal)
+            .map(|key| key.keyword)
+            .collect();
+        if !include.is_empty() {
+            params.push(("includefield".to_string(), include.join(",")));
+        }
+        Ok(params)
+    }
+}
+
+/// Convert a validated key value into a primitive value of its VR.
+fn key_value(key: &Key) -> PrimitiveValue {
+    let value = key.value;
+    match (key.matching, key.vr) {
+        (MatchingType::Universal, _) => PrimitiveValue::Empty,
+        (MatchingType::UidList, _) => {
+            PrimitiveValue::Strs(value.split('\\').map(String::from).collect())
+        }
+        // numeric values were checked when classified
+        (_, VR::US) => value
+            .parse::<u16>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::UL) => value
+            .parse::<u32>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::UV) => value
+            .parse::<u64>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::SS) => value
+            .parse::<i16>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::SL) => value
+            .parse::<i32>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::SV) => value
+            .parse::<i64>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::FL) => value
+            .parse::<f32>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        (_, VR::FD) => value
+            .parse::<f64>()
+            .map_or(PrimitiveValue::Empty, From::from),
+        _ => PrimitiveValue::from(value),
+    }
+}
+
+/// Whether a candidate object matches
+/// all matching keys of a query identifier (PS3.4 C.2.2.2).
+///
+/// Keys with universal matching,
+/// _Query/Retrieve Level_ and _Specific Character Set_
+/// do not constrain the matches,
+/// and neither do sequence keys, which are not supported for matching.
+/// A candidate without the attribute of a matching key does not match.
+/// Multi-valued attributes match if any of their values matches.
+pub fn matches(identifier: &InMemDicomObject, candidate: &InMemDicomObject) -> bool {
+    identifier.iter().all(|key| {
+        let tag = key.header().tag;
+        if tag == tags::QUERY_RETRIEVE_LEVEL
+            || tag == tags::SPECIFIC_CHARACTER_SET
+            || key.header().vr() == VR::SQ
+        {
+            return true;
+        }
+        let Ok(pattern) = key.to_str() else {
+            return true;
+        };
+        let pattern = pattern.trim_end_matches(['\0', ' ']);
+        if pattern.is_empty() {
+            return true;
+        }
+        let Some(values) = candidate.get(tag).and_then(|e| e.to_multi_str().ok()) else {
+            return false;
+        };
+        values
+            .iter()
+            .any(|value| match_value(key.header().vr(), value.trim(), pattern))
+    })
+}
+
+/// Whether a single value matches a matching key value
+/// of an attribute with the given value representation.
+///
+/// UID lists, date and time ranges and wildcards are supported,
+/// and person names are matched case-insensitively.
+pub fn match_value(vr: VR, value: &str, pattern: &str) -> bool {
+    match vr {
+        VR::UI => pattern
+            .split('\\')
+            .any(|uid| uid.trim_end_matches('\0') == value.trim_end_matches('\0')),
+        VR::DA | VR::TM | VR::DT if pattern.contains('-') => {
+            let (lower, upper) = pattern.split_once('-').unwrap_or((pattern, pattern));
+            (lower.is_empty() || value >= lower)
+                && (upper.is_empty() || value.get(..upper.len()).unwrap_or(value) <= upper)
+        }
+        VR::US | VR::UL | VR::UV | VR::SS | VR::SL | VR::SV | VR::IS | VR::FL | VR::FD | VR::DS => {
+            match (value.trim().parse::<f64>(), pattern.trim().parse::<f64>()) {
+                (Ok(value), Ok(pattern)) => value == pattern,
+                _ => false,
+            }
+        }
+        VR::PN => wildcard_match(&value.to_lowercase(), &pattern.to_lowercase()),
+        _ => wildcard_match(value, pattern),
+    }
+}
+
+/// Match text against a pattern in which
+/// `*` matches any sequence of characters
+/// and `?` matches a single character
+fn wildcard_match(text: &str, pattern: &str) -> bool {
+    let text: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut t, mut p) = (0, 0);
+    // position of the last `*` in the pattern and the text position it matched
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Build the identifier of a C-FIND response
+/// from the keys of the query identifier and a matching object.
+///
+/// Each key is filled with the attribute of the match,
+/// or left empty if the match does not have it.
+/// _Query/Retrieve Level_ and _Specific Character Set_
+/// are copied from the query and the match respectively.
+pub fn response_identifier(
+    identifier: &InMemDicomObject,
+    candidate: &InMemDicomObject,
+) -> InMemDicomObject {
+    let mut obj = InMemDicomObject::new_empty();
+    if let Some(charset) = candidate.get(tags::SPECIFIC_CHARACTER_SET) {
+        obj.put(charset.clone());
+    }
+    for key in identifier.iter() {
+        let tag = key.header().tag;
+        if tag == tags::SPECIFIC_CHARACTER_SET {
+            continue;
+        }
+        if tag == tags::QUERY_RETRIEVE_LEVEL {
+            obj.put(key.clone());
+            continue;
+        }
+        match candidate.get(tag) {
+            Some(element) => obj.put(element.clone()),
+            None => obj.put(DataElement::new(
+                tag,
+                key.header().vr(),
+                PrimitiveValue::Empty,
+            )),
+        };
+    }
+    obj
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_matching_keys() {
+        use MatchingType::*;
+        assert_eq!(MatchingType::classify(VR::PN, ""), Some(Universal));
+        assert_eq!(
+            MatchingType::classify(VR::PN, "DOE^JOHN"),
+            Some(SingleValue)
+        );
+        assert_eq!(MatchingType::classify(VR::PN, "DOE^*"), Some(Wildcard));
+        assert_eq!(MatchingType::classify(VR::CS, "CT\\MR"), None);
+        assert_eq!(MatchingType::classify(VR::DA, "20240101-"), Some(Range));
+        assert_eq!(
+            MatchingType::classify(VR::DA, "20240101-20240131"),
+            Some(Range)
+        );
+        assert_eq!(MatchingType::classify(VR::DA, "2024*"), None);
+        assert_eq!(MatchingType::classify(VR::DA, "-"), None);
+        assert_eq!(MatchingType::classify(VR::TM, "080000.5-"), Some(Range));
+        assert_eq!(MatchingType::classify(VR::UI, "1.2.3"), Some(SingleValue));
+        assert_eq!(
+            MatchingType::classify(VR::UI, "1.2.3\\1.2.4"),
+            Some(UidList)
+        );
+        assert_eq!(MatchingType::classify(VR::UI, "1.2.*"), None);
+        assert_eq!(MatchingType::classify(VR::IS, "12"), Some(SingleValue));
+        assert_eq!(MatchingType::classify(VR::US, "x"), None);
+        assert_eq!(MatchingType::classify(VR::SQ, "x"), None);
+    }
+
+    #[test]
+    fn build_identifier() {
+        let query = QueryBuilder::new(QueryLevel::Series)
+            .with_key("StudyInstanceUID", "1.2.3")
+            .with_key("Modality", "MR")
+            .with_key("SeriesInstanceUID", "1.2.3.4\\1.2.3.5")
+            .with_return_key("SeriesDescription")
+            .with_key("Modality", "CT");
+        let obj = query.build().unwrap();
+        let str_of = |tag| obj.get(tag).unwrap().to_str().unwrap().to_string();
+
+        assert_eq!(str_of(tags::QUERY_RETRIEVE_LEVEL), "SERIES");
+        assert_eq!(str_of(tags::STUDY_INSTANCE_UID), "1.2.3");
+        // the last value given is kept
+        assert_eq!(str_of(tags::MODALITY), "CT");
+        assert_eq!(
+            obj.get(tags::SERIES_INSTANCE_UID)
+                .unwrap()
+                .to_multi_str()
+                .unwrap()
+                .to_vec(),
+            ["1.2.3.4", "1.2.3.5"]
+        );
+        assert!(
+            obj.get(tags::SERIES_DESCRIPTION)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .is_empty()
+        );
+        // inserted required key
+        assert!(
+            obj.get(tags::SERIES_NUMBER)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(obj.iter().count(), 6);
+
+        assert_eq!(
+            query.to_qido_params().unwrap(),
+            [
+                ("StudyInstanceUID".to_string(), "1.2.3".to_string()),
+                ("Modality".to_string(), "CT".to_string()),
+                (
+                    "SeriesInstanceUID".to_string(),
+                    "1.2.3.4,1.2.3.5".to_string()
+                ),
+                ("includefield".to_string(), "SeriesDescription".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let query = QueryBuilder::new(QueryLevel::Study).with_key("PatientsName", "DOE");
+        assert!(matches!(
+            query.build(),
+            Err(crate::dimse::Error::UnknownKeyword { .. })
+        ));
+        let query = QueryBuilder::new(QueryLevel::Study).with_key("StudyDate", "2024*");
+        assert!(matches!(
+            query.to_qido_params(),
+            Err(crate::dimse::Error::InvalidMatchingKey { .. })
+        ));
+    }
+
+    #[test]
+    fn match_candidates() {
+        let candidate = InMemDicomObject::from_element_iter([
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::STUDY_DATE, VR::DA, "20240115"),
+            DataElement::new(tags::MODALITY, VR::CS, "CT"),
+            DataElement::new(tags::SERIES_NUMBER, VR::IS, "3"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "1.2.3"),
+        ]);
+        let query = |keys: &[(&str, &str)]| {
+            keys.iter()
+                .fold(QueryBuilder::new(QueryLevel::Series), |q, (k, v)| {
+                    q.with_key(*k, *v)
+                })
+                .build()
+                .unwrap()
+        };
+
+        assert!(matches(&query(&[]), &candidate));
+        assert!(matches(
+            &query(&[("PatientName", "DOE^*"), ("StudyDate", "20240101-")]),
+            &candidate
+        ));
+        assert!(matches(
+            &query(&[("StudyInstanceUID", "1.2.4\\1.2.3"), ("SeriesNumber", "3")]),
+            &candidate
+        ));
+        assert!(!matches(&query(&[("Modality", "MR")]), &candidate));
+        assert!(!matches(&query(&[("StudyDate", "-20231231")]), &candidate));
+        // attribute missing from the candidate
+        assert!(!matches(&query(&[("AccessionNumber", "A1")]), &candidate));
+
+        let identifier = query(&[("Modality", "CT"), ("SeriesDescription", "")]);
+        let rsp = response_identifier(&identifier, &candidate);
+        assert_eq!(
+            rsp.get(tags::QUERY_RETRIEVE_LEVEL)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "SERIES"
+        );
+        assert_eq!(rsp.get(tags::MODALITY).unwrap().to_str().unwrap(), "CT");
+        assert!(
+            rsp.get(tags::SERIES_DESCRIPTION)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .is_empty()
+        );
+        assert!(rsp.get(tags::PATIENT_NAME).is_none());
+    }
+}
@@ -16,6 +16,12 @@
 //!   comprises abstractions for establishing and negotiating associations
 //!   between application entities,
 //!   via the upper layer protocol by TCP.
+//! - The [`capture`] module
+//!   records the PDUs exchanged in associations to a file,
+//!   for inspection with the `dicom-pdu-inspect` tool.
+//! - The [`dimse`] module (requires the `dimse` feature)
+//!   provides DIMSE message exchange on top of an association,
+//!   as well as SCU and SCP helpers for some services.
 //!
 //! DICOM Associations on top of TLS is also supported,
 //! thus offering a Secure Transport Connection.
@@ -30,12 +36,21 @@
 //! * `sync-tls` (or `tls`): Enables TLS support for synchronous associations.
 //! * `async-tls`: Enables TLS support for asynchronous associations.
 //!   Implies `async` and `sync-tls`.
-//! * `full`: Enables all capabilities: `async-tls`
+//! * `dimse`: Enables the [`dimse`] module,
+//!   which depends on `dicom-object` for command and data set handling.
+//! * `test-utils`: Enables the [`test_utils`] module,
+//!   for testing SCUs and SCPs over in-memory streams.
+//! * `full`: Enables all capabilities: `async-tls` and `dimse`
 
 pub mod address;
 pub mod association;
+pub mod capture;
+#[cfg(feature = "dimse")]
+pub mod dimse;
 pub mod pdu;
 pub mod prelude;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
 /// The current implementation class UID generically referring to DICOM-rs.
 ///
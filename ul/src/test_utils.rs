@@ -0,0 +1,352 @@
+//! Test support for DICOM network applications.
+//!
+//! This module (requires the `test-utils` feature)
+//! provides an in-memory [`DuplexStream`]
+//! and functions for establishing a client association
+//! and a server association on both ends of it,
+//! so that SCU and SCP behavior can be tested in-process
+//! without binding real TCP ports.
+//!
+//! # Example
+//!
+//! ```
+//! # use dicom_ul::{ClientAssociationOptions, ServerAssociationOptions};
+//! # use dicom_ul::association::SyncAssociation;
+//! use dicom_ul::test_utils::association_pair;
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let (mut scu, mut scp) = association_pair(
+//!     ClientAssociationOptions::new().with_abstract_syntax("1.2.840.10008.1.1"),
+//!     &ServerAssociationOptions::new().auto_echo(true),
+//! )?;
+//!
+//! let scp = std::thread::spawn(move || {
+//!     // C-ECHO requests are answered automatically
+//!     let pdu = scp.receive()?;
+//!     assert_eq!(pdu, dicom_ul::Pdu::ReleaseRQ);
+//!     scp.send(&dicom_ul::Pdu::ReleaseRP)
+//! });
+//!
+//! assert_eq!(scu.echo()?, 0);
+//! scu.release()?;
+//! scp.join().unwrap()?;
+//! # Ok(())
+//! # }
+//! # run().unwrap();
+//! ```
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crate::association::{
+    CloseSocket, Error,
+    client::{ClientAssociation, ClientAssociationOptions},
+    server::{AccessControl, Negotiation, ServerAssociation, ServerAssociationOptions},
+};
+
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The bytes in transit in one direction of a duplex stream
+#[derive(Debug, Default)]
+struct Pipe {
+    buffer: VecDeque<u8>,
+    closed: bool,
+}
+
+#[derive(Debug, Default)]
+struct Channel {
+    pipe: Mutex<Pipe>,
+    ready: Condvar,
+}
+
+impl Channel {
+    fn lock(&self) -> MutexGuard<'_, Pipe> {
+        self.pipe.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn close(&self) {
+        self.lock().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// One end of an in-memory, bidirectional byte stream,
+/// created via [`duplex`].
+///
+/// Reads block until the other end writes some data
+/// or the stream is closed.
+/// Writes never block:
+/// pending data is buffered without a size limit.
+/// The stream is closed once either end is closed or dropped,
+/// after which reads return the remaining data followed by end of file,
+/// and writes fail.
+#[derive(Debug)]
+pub struct DuplexStream {
+    incoming: Arc<Channel>,
+    outgoing: Arc<Channel>,
+    read_timeout: Option<Duration>,
+}
+
+/// Create an in-memory, bidirectional byte stream,
+/// returning both of its ends.
+///
+/// Data written to one end can be read from the other.
+pub fn duplex() -> (DuplexStream, DuplexStream) {
+    let a = Arc::new(Channel::default());
+    let b = Arc::new(Channel::default());
+    (
+        DuplexStream {
+            incoming: a.clone(),
+            outgoing: b.clone(),
+            read_timeout: None,
+        },
+        DuplexStream {
+            incoming: b,
+            outgoing: a,
+            read_timeout: None,
+        },
+    )
+}
+
+impl DuplexStream {
+    /// Set the maximum time that a read may wait for data,
+    /// after which it fails with [`TimedOut`](std::io::ErrorKind::TimedOut).
+    /// `None` means that reads wait indefinitely,
+    /// which is the default.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    fn shutdown(&self) {
+        self.incoming.close();
+        self.outgoing.close();
+    }
+}
+
+impl Read for DuplexStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        let mut pipe = self.incoming.lock();
+        while pipe.buffer.is_empty() && !pipe.closed {
+            pipe = match deadline {
+                None => self
+                    .incoming
+                    .ready
+                    .wait(pipe)
+                    .unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(std::io::ErrorKind::TimedOut.into());
+                    }
+                    self.incoming
+                        .ready
+                        .wait_timeout(pipe, remaining)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+            };
+        }
+        pipe.buffer.read(buf)
+    }
+}
+
+impl Write for DuplexStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut pipe = self.outgoing.lock();
+        if pipe.closed {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        }
+        pipe.buffer.extend(buf);
+        self.outgoing.ready.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CloseSocket for DuplexStream {
+    fn close(&mut self) -> std::io::Result<()> {
+        self.shutdown();
+        Ok(())
+    }
+}
+
+impl Drop for DuplexStream {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Establish a client association and a server association
+/// with each other over an in-memory [`DuplexStream`].
+///
+/// The negotiation of the client side runs in a separate thread
+/// while the server side negotiates in the current one.
+/// If the negotiation fails on the client side,
+/// the client's error is returned
+/// (for instance, when the association is rejected).
+pub fn association_pair<A, N>(
+    client_options: ClientAssociationOptions<'_>,
+    server_options: &ServerAssociationOptions<'_, A, N>,
+) -> Result<(
+    ClientAssociation<DuplexStream>,
+    ServerAssociation<DuplexStream>,
+)>
+where
+    A: AccessControl,
+    N: Negotiation,
+{
+    let (client_stream, server_stream) = duplex();
+    std::thread::scope(|scope| {
+        let client = scope.spawn(move || client_options.establish_stream(None, client_stream));
+        let server = server_options.establish_stream(server_stream);
+        let client = client.join().expect("client negotiation panicked")?;
+        Ok((client, server?))
+    })
+}
+
+/// The maximum number of bytes in transit in each direction
+/// of the streams created by [`association_pair_async`]
+#[cfg(feature = "async")]
+pub const ASYNC_DUPLEX_BUFFER_SIZE: usize = 1 << 20;
+
+/// Establish an asynchronous client association
+/// and an asynchronous server association
+/// with each other over an in-memory [Tokio duplex stream][1].
+///
+/// Unlike [`DuplexStream`],
+/// writes wait once more than [`ASYNC_DUPLEX_BUFFER_SIZE`] bytes
+/// are pending to be read on the other end.
+/// If the negotiation fails on the client side,
+/// the client's error is returned.
+///
+/// [1]: tokio::io::DuplexStream
+#[cfg(feature = "async")]
+pub async fn association_pair_async<A, N>(
+    client_options: ClientAssociationOptions<'_>,
+    server_options: &ServerAssociationOptions<'_, A, N>,
+) -> Result<(
+    crate::association::AsyncClientAssociation<tokio::io::DuplexStream>,
+    crate::association::AsyncServerAssociation<tokio::io::DuplexStream>,
+)>
+where
+    A: AccessControl,
+    N: Negotiation,
+{
+    let (client_stream, server_stream) = tokio::io::duplex(ASYNC_DUPLEX_BUFFER_SIZE);
+    let (client, server) = tokio::join!(
+        client_options.establish_stream_async(None, client_stream),
+        server_options.establish_stream_async(server_stream),
+    );
+    Ok((client?, server?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Pdu,
+        association::{Association, Error},
+        pdu::{PDataValue, PDataValueType},
+    };
+
+    static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+
+    #[test]
+    fn duplex_stream_round_trip() {
+        let (mut a, mut b) = duplex();
+        a.write_all(b"DICM").unwrap();
+        b.write_all(b"reply").unwrap();
+
+        let mut buf = [0; 4];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"DICM");
+
+        // remaining data is still readable after closing
+        b.close().unwrap();
+        let mut buf = Vec::new();
+        a.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"reply");
+        assert_eq!(
+            a.write(b"more").unwrap_err().kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test]
+    fn duplex_stream_read_timeout() {
+        let (mut a, _b) = duplex();
+        a.set_read_timeout(Some(Duration::from_millis(10)));
+        assert_eq!(
+            a.read(&mut [0; 4]).unwrap_err().kind(),
+            std::io::ErrorKind::TimedOut
+        );
+    }
+
+    #[test]
+    fn association_pair_exchanges_pdus() {
+        let (mut scu, mut scp) = association_pair(
+            ClientAssociationOptions::new()
+                .calling_ae_title("TEST-SCU")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS),
+            &ServerAssociationOptions::new()
+                .ae_title("TEST-SCP")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS),
+        )
+        .unwrap();
+        assert_eq!(scp.peer_ae_title(), "TEST-SCU");
+
+        let pdu = Pdu::PData {
+            data: vec![PDataValue {
+                presentation_context_id: scu.presentation_contexts()[0].id,
+                value_type: PDataValueType::Command,
+                is_last: true,
+                data: vec![0x55; 16],
+            }],
+        };
+        scu.send(&pdu).unwrap();
+        assert_eq!(scp.receive().unwrap(), pdu);
+
+        let scp = std::thread::spawn(move || {
+            assert_eq!(scp.receive().unwrap(), Pdu::ReleaseRQ);
+            scp.send(&Pdu::ReleaseRP).unwrap();
+        });
+        scu.release().unwrap();
+        scp.join().unwrap();
+    }
+
+    #[test]
+    fn association_pair_rejected() {
+        let result = association_pair(
+            ClientAssociationOptions::new()
+                .called_ae_title("OTHER-SCP")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS),
+            &ServerAssociationOptions::new()
+                .accept_called_ae_title()
+                .ae_title("TEST-SCP")
+                .with_abstract_syntax(VERIFICATION_SOP_CLASS),
+        );
+        assert!(matches!(result, Err(Error::Rejected { .. })));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn association_pair_async_exchanges_pdus() {
+        let (mut scu, mut scp) = association_pair_async(
+            ClientAssociationOptions::new().with_abstract_syntax(VERIFICATION_SOP_CLASS),
+            &ServerAssociationOptions::new().with_abstract_syntax(VERIFICATION_SOP_CLASS),
+        )
+        .await
+        .unwrap();
+
+        scu.send(&Pdu::ReleaseRQ).await.unwrap();
+        assert_eq!(scp.receive().await.unwrap(), Pdu::ReleaseRQ);
+        scp.send(&Pdu::ReleaseRP).await.unwrap();
+        assert_eq!(scu.receive().await.unwrap(), Pdu::ReleaseRP);
+    }
+}
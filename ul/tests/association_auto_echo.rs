@@ -0,0 +1,146 @@
+use dicom_ul::{
+    ClientAssociationOptions, Pdu, ServerAssociationOptions,
+    association::Error,
+    pdu::{PDataValue, PDataValueType},
+};
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "ECHO-SCU";
+static SCP_AE_TITLE: &str = "ECHO-SCP";
+
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+
+fn bogus_command(presentation_context_id: u8) -> Pdu {
+    Pdu::PData {
+        data: vec![PDataValue {
+            presentation_context_id,
+            value_type: PDataValueType::Command,
+            is_last: true,
+            data: vec![0x55; 8],
+        }],
+    }
+}
+
+/// Spawn an SCP which responds to C-ECHO automatically,
+/// collecting all other PDUs until the association is released
+fn spawn_scp(auto_echo: bool) -> Result<(std::thread::JoinHandle<Result<Vec<Pdu>>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(CT_IMAGE_STORAGE)
+        .auto_echo(auto_echo);
+
+    let h = std::thread::spawn(move || -> Result<_> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish(stream)?;
+        let mut pdus = Vec::new();
+        loop {
+            let pdu = association.receive()?;
+            if pdu == Pdu::ReleaseRQ {
+                association.send(&Pdu::ReleaseRP)?;
+                return Ok(pdus);
+            }
+            pdus.push(pdu);
+        }
+    });
+    Ok((h, addr))
+}
+
+fn establish(addr: SocketAddr) -> dicom_ul::ClientAssociation<std::net::TcpStream> {
+    ClientAssociationOptions::new()
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .with_abstract_syntax(CT_IMAGE_STORAGE)
+        .establish(addr)
+        .unwrap()
+}
+
+#[test]
+fn client_echo_with_auto_echo_scp() {
+    let (scp_handle, scp_addr) = spawn_scp(true).unwrap();
+    let mut association = establish(scp_addr);
+
+    assert_eq!(association.echo().unwrap(), 0);
+    assert_eq!(association.echo().unwrap(), 0);
+
+    // other messages still reach the SCP
+    let ct_pc_id = association
+        .presentation_contexts()
+        .iter()
+        .find(|pc| pc.abstract_syntax == CT_IMAGE_STORAGE)
+        .unwrap()
+        .id;
+    association.send(&bogus_command(ct_pc_id)).unwrap();
+
+    assert_eq!(association.echo().unwrap(), 0);
+    association.release().unwrap();
+
+    let pdus = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(pdus, vec![bogus_command(ct_pc_id)]);
+}
+
+#[test]
+fn client_echo_without_verification_context() {
+    let (scp_handle, scp_addr) = spawn_scp(false).unwrap();
+    let mut association = establish(scp_addr);
+
+    // Verification SOP class was rejected
+    assert!(matches!(
+        association.echo(),
+        Err(Error::NoVerificationContext { .. })
+    ));
+    association.release().unwrap();
+
+    let pdus = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert!(pdus.is_empty());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread")]
+async fn client_echo_with_auto_echo_scp_async() {
+    let listener = tokio::net::TcpListener::bind("localhost:0").await.unwrap();
+    let scp_addr = listener.local_addr().unwrap();
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .auto_echo(true);
+
+    let scp_handle = tokio::spawn(async move {
+        let (stream, _addr) = listener.accept().await?;
+        let mut association = scp.establish_async(stream).await?;
+        let pdu = association.receive().await?;
+        assert_eq!(pdu, Pdu::ReleaseRQ);
+        association.send(&Pdu::ReleaseRP).await?;
+        Result::Ok(())
+    });
+
+    tokio::task::spawn_blocking(move || {
+        let mut association = ClientAssociationOptions::new()
+            .calling_ae_title(SCU_AE_TITLE)
+            .called_ae_title(SCP_AE_TITLE)
+            .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+            .establish(scp_addr)
+            .unwrap();
+        assert_eq!(association.echo().unwrap(), 0);
+        association.release().unwrap();
+    })
+    .await
+    .unwrap();
+
+    scp_handle
+        .await
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+}
@@ -398,9 +398,9 @@ fn run_scu_scp_association_test(max_is_client: bool) {
             DIGITAL_MG_STORAGE_SOP_CLASS,
             vec![IMPLICIT_VR_LE, EXPLICIT_VR_LE, JPEG_BASELINE],
         )
-        .with_extended_negotiation("1.2.3.4", &[1, 1, 0, 0])
-        .with_extended_negotiation("1.2.3.5", &[0, 1, 1, 1])
-        .with_extended_negotiation("1.2.3.6", &[1, 1, 1, 1])
+        .with_extended_negotiation("1.2.3.4", [1, 1, 0, 0])
+        .with_extended_negotiation("1.2.3.5", [0, 1, 1, 1])
+        .with_extended_negotiation("1.2.3.6", [1, 1, 1, 1])
         .with_role_selection("1.2.3.4", true, true)
         .with_role_selection("1.2.3.5", true, true)
         .with_role_selection("1.2.3.6", true, true)
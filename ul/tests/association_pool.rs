@@ -0,0 +1,254 @@
+use dicom_ul::{
+    ClientAssociationOptions, Pdu, ServerAssociationOptions,
+    association::ClientAssociationPool,
+    pdu::{PDataValue, PDataValueType, PresentationContextResultReason},
+};
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "POOL-SCU";
+static SCP_AE_TITLE: &str = "POOL-SCP";
+
+static VERIFICATION_SOP_CLASS: &str = "1.2.840.10008.1.1";
+static CT_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.2";
+static MR_IMAGE_STORAGE: &str = "1.2.840.10008.5.1.4.1.1.4";
+
+/// What the SCP saw in each association
+#[derive(Debug, Default)]
+struct AssociationLog {
+    abstract_syntaxes: Vec<String>,
+    echoes: usize,
+}
+
+/// Encode an implicit VR little endian element of the command group
+fn put_element(out: &mut Vec<u8>, element: u16, value: &[u8]) {
+    out.extend_from_slice(&0_u16.to_le_bytes());
+    out.extend_from_slice(&element.to_le_bytes());
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// Build a C-ECHO-RSP for the given C-ECHO-RQ command set
+fn echo_response(mut rq: &[u8]) -> Vec<u8> {
+    let mut message_id = None;
+    while rq.len() >= 8 {
+        let element = u16::from_le_bytes([rq[2], rq[3]]);
+        let len = u32::from_le_bytes([rq[4], rq[5], rq[6], rq[7]]) as usize;
+        if element == 0x0110 {
+            message_id = Some([rq[8], rq[9]]);
+        }
+        rq = &rq[8 + len..];
+    }
+    let mut body = Vec::new();
+    put_element(&mut body, 0x0002, b"1.2.840.10008.1.1\0");
+    put_element(&mut body, 0x0100, &0x8030_u16.to_le_bytes());
+    put_element(&mut body, 0x0120, &message_id.expect("message ID"));
+    put_element(&mut body, 0x0800, &0x0101_u16.to_le_bytes());
+    put_element(&mut body, 0x0900, &0_u16.to_le_bytes());
+    let mut out = Vec::new();
+    put_element(&mut out, 0x0000, &(body.len() as u32).to_le_bytes());
+    out.extend(body);
+    out
+}
+
+/// Spawn an SCP which accepts the given number of associations
+/// one after the other, answering C-ECHO requests
+fn spawn_scp(
+    associations: usize,
+) -> Result<(
+    std::thread::JoinHandle<Result<Vec<AssociationLog>>>,
+    SocketAddr,
+)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(VERIFICATION_SOP_CLASS)
+        .with_abstract_syntax(CT_IMAGE_STORAGE)
+        .with_abstract_syntax(MR_IMAGE_STORAGE);
+
+    let h = std::thread::spawn(move || -> Result<_> {
+        let mut logs = Vec::new();
+        for _ in 0..associations {
+            let (stream, _addr) = listener.accept()?;
+            let mut association = scp.establish(stream)?;
+            let mut log = AssociationLog {
+                abstract_syntaxes: association
+                    .presentation_contexts()
+                    .iter()
+                    .filter(|pc| pc.reason == PresentationContextResultReason::Acceptance)
+                    .map(|pc| pc.abstract_syntax.clone())
+                    .collect(),
+                echoes: 0,
+            };
+            loop {
+                match association.receive()? {
+                    Pdu::PData { data } => {
+                        let pdv = &data[0];
+                        log.echoes += 1;
+                        association.send(&Pdu::PData {
+                            data: vec![PDataValue {
+                                presentation_context_id: pdv.presentation_context_id,
+                                value_type: PDataValueType::Command,
+                                is_last: true,
+                                data: echo_response(&pdv.data),
+                            }],
+                        })?;
+                    }
+                    Pdu::ReleaseRQ => {
+                        association.send(&Pdu::ReleaseRP)?;
+                        break;
+                    }
+                    pdu => panic!("unexpected PDU {pdu:?}"),
+                }
+            }
+            logs.push(log);
+        }
+        Ok(logs)
+    });
+    Ok((h, addr))
+}
+
+fn pool() -> ClientAssociationPool {
+    ClientAssociationPool::new(
+        ClientAssociationOptions::new()
+            .calling_ae_title(SCU_AE_TITLE)
+            .called_ae_title(SCP_AE_TITLE),
+    )
+}
+
+#[test]
+fn pool_reuses_idle_association() {
+    let (scp_handle, scp_addr) = spawn_scp(1).unwrap();
+    let scp_addr = scp_addr.to_string();
+    let pool = pool();
+
+    let association = pool.get(&scp_addr, &[CT_IMAGE_STORAGE]).unwrap();
+    drop(association);
+    assert_eq!(pool.idle_count(), 1);
+
+    // reused after a liveness check
+    let association = pool.get(&scp_addr, &[CT_IMAGE_STORAGE]).unwrap();
+    assert_eq!(pool.idle_count(), 0);
+    assert!(
+        association
+            .presentation_contexts()
+            .iter()
+            .any(|pc| pc.abstract_syntax == CT_IMAGE_STORAGE)
+    );
+    drop(association);
+
+    pool.release_all();
+    assert_eq!(pool.idle_count(), 0);
+
+    let logs = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].echoes, 1);
+}
+
+#[test]
+fn pool_renegotiates_missing_presentation_contexts() {
+    let (scp_handle, scp_addr) = spawn_scp(2).unwrap();
+    let scp_addr = scp_addr.to_string();
+    let pool = pool();
+
+    drop(pool.get(&scp_addr, &[CT_IMAGE_STORAGE]).unwrap());
+
+    // a new association is needed for MR
+    let association = pool.get(&scp_addr, &[MR_IMAGE_STORAGE]).unwrap();
+    drop(association);
+    assert_eq!(pool.idle_count(), 1);
+
+    // which covers CT as well
+    drop(pool.get(&scp_addr, &[CT_IMAGE_STORAGE]).unwrap());
+    drop(pool);
+
+    let logs = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(logs.len(), 2);
+    assert_eq!(
+        logs[0].abstract_syntaxes,
+        vec![VERIFICATION_SOP_CLASS, CT_IMAGE_STORAGE]
+    );
+    assert_eq!(logs[0].echoes, 0);
+    assert_eq!(
+        logs[1].abstract_syntaxes,
+        vec![VERIFICATION_SOP_CLASS, CT_IMAGE_STORAGE, MR_IMAGE_STORAGE]
+    );
+    assert_eq!(logs[1].echoes, 1);
+}
+
+#[test]
+fn pool_discarded_association_is_not_reused() {
+    let (scp_handle, scp_addr) = spawn_scp(2).unwrap();
+    let scp_addr = scp_addr.to_string();
+    let pool = pool().check_liveness(false);
+
+    pool.get(&scp_addr, &[CT_IMAGE_STORAGE])
+        .unwrap()
+        .discard()
+        .unwrap();
+    assert_eq!(pool.idle_count(), 0);
+
+    drop(pool.get(&scp_addr, &[CT_IMAGE_STORAGE]).unwrap());
+    pool.release_all();
+
+    let logs = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(logs.len(), 2);
+    // no verification without liveness checks
+    assert_eq!(logs[1].abstract_syntaxes, vec![CT_IMAGE_STORAGE]);
+}
+
+#[test]
+fn pool_matches_transfer_syntaxes() {
+    let (scp_handle, scp_addr) = spawn_scp(2).unwrap();
+    let scp_addr = scp_addr.to_string();
+    let pool = pool();
+    let explicit: &[&str] = &["1.2.840.10008.1.2.1"];
+    let implicit: &[&str] = &["1.2.840.10008.1.2"];
+
+    drop(
+        pool.get_with_presentation_contexts(&scp_addr, &[(CT_IMAGE_STORAGE, explicit)])
+            .unwrap(),
+    );
+    // reused with the same transfer syntaxes
+    drop(
+        pool.get_with_presentation_contexts(&scp_addr, &[(CT_IMAGE_STORAGE, explicit)])
+            .unwrap(),
+    );
+    // but not with other ones
+    let association = pool
+        .get_with_presentation_contexts(&scp_addr, &[(CT_IMAGE_STORAGE, implicit)])
+        .unwrap();
+    assert_eq!(
+        association
+            .presentation_contexts()
+            .iter()
+            .filter(|pc| pc.abstract_syntax == CT_IMAGE_STORAGE)
+            .count(),
+        2
+    );
+    drop(association);
+    drop(pool);
+
+    let logs = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].echoes, 1);
+    assert_eq!(
+        logs[1].abstract_syntaxes,
+        vec![VERIFICATION_SOP_CLASS, CT_IMAGE_STORAGE, CT_IMAGE_STORAGE]
+    );
+}
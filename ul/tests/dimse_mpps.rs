@@ -0,0 +1,102 @@
+#![cfg(feature = "dimse")]
+use dicom_core::{DataElement, VR};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    ClientAssociationOptions, ServerAssociationOptions,
+    dimse::{
+        mpps::{InMemoryMpps, MppsScu, serve},
+        status,
+    },
+};
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "MPPS-SCU";
+static SCP_AE_TITLE: &str = "MPPS-SCP";
+
+static STEP_UID: &str = "2.25.290104848129166368217534836597580882326";
+
+fn spawn_scp() -> Result<(std::thread::JoinHandle<Result<InMemoryMpps>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(uids::MODALITY_PERFORMED_PROCEDURE_STEP);
+
+    let h = std::thread::spawn(move || -> Result<_> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish(stream)?;
+        let mut mpps = InMemoryMpps::new();
+        serve(&mut association, &mut mpps)?;
+        Ok(mpps)
+    });
+    Ok((h, addr))
+}
+
+#[test]
+fn mpps_create_complete_and_get() {
+    let (scp_handle, scp_addr) = spawn_scp().unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .with_abstract_syntax(uids::MODALITY_PERFORMED_PROCEDURE_STEP)
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .establish(scp_addr)
+        .unwrap();
+
+    let mut scu = MppsScu::new(&mut association).unwrap();
+
+    let attributes = InMemDicomObject::from_element_iter([
+        DataElement::new(tags::MODALITY, VR::CS, "MR"),
+        DataElement::new(tags::PERFORMED_PROCEDURE_STEP_ID, VR::SH, "STEP01"),
+    ]);
+    let rsp = scu.create(Some(STEP_UID), attributes.clone()).unwrap();
+    assert!(rsp.is_success(), "unexpected response {rsp:?}");
+    assert_eq!(rsp.sop_instance_uid.as_deref(), Some(STEP_UID));
+
+    // creating it again is refused
+    let rsp = scu.create(Some(STEP_UID), attributes).unwrap();
+    assert_eq!(rsp.status, status::DUPLICATE_SOP_INSTANCE);
+
+    let rsp = scu
+        .complete(STEP_UID, InMemDicomObject::new_empty())
+        .unwrap();
+    assert!(rsp.is_success(), "unexpected response {rsp:?}");
+
+    let rsp = scu
+        .get(
+            STEP_UID,
+            &[tags::PERFORMED_PROCEDURE_STEP_STATUS, tags::MODALITY],
+        )
+        .unwrap();
+    assert!(rsp.is_success(), "unexpected response {rsp:?}");
+    let obj = rsp
+        .attributes
+        .expect("N-GET response should have a data set");
+    assert_eq!(
+        obj.get(tags::PERFORMED_PROCEDURE_STEP_STATUS)
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "COMPLETED"
+    );
+    assert_eq!(obj.get(tags::MODALITY).unwrap().to_str().unwrap(), "MR");
+
+    // a completed step may no longer be updated
+    let rsp = scu
+        .discontinue(STEP_UID, InMemDicomObject::new_empty())
+        .unwrap();
+    assert_eq!(rsp.status, status::PROCESSING_FAILURE);
+    assert!(rsp.error_comment.is_some());
+
+    association.release().unwrap();
+
+    let mpps = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(mpps.instances().count(), 1);
+}
@@ -0,0 +1,231 @@
+#![cfg(feature = "dimse")]
+use dicom_core::{DataElement, PrimitiveValue, VR, value::DataSetSequence};
+use dicom_dictionary_std::{tags, uids};
+use dicom_object::InMemDicomObject;
+use dicom_ul::{
+    ClientAssociationOptions, Pdu, ServerAssociationOptions,
+    dimse::{
+        CommandField, Error, NActionRq, NActionRsp, NCreateRq, NCreateRsp, NDeleteRq, NDeleteRsp,
+        NSetRq, NSetRsp,
+        print::{ACTION_PRINT, GrayscaleImage, PrintScu},
+        receive_message, send_message, write_data_set,
+    },
+};
+use std::net::SocketAddr;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+static SCU_AE_TITLE: &str = "PRINT-SCU";
+static SCP_AE_TITLE: &str = "PRINT-SCP";
+
+static FILM_SESSION_UID: &str = "1.2.826.0.1.3680043.9.7133.1";
+static FILM_BOX_UID: &str = "1.2.826.0.1.3680043.9.7133.2";
+static IMAGE_BOX_UID: &str = "1.2.826.0.1.3680043.9.7133.3";
+
+/// What the fake printer went through
+#[derive(Debug, Default)]
+struct PrintLog {
+    commands: Vec<CommandField>,
+    film_box: Option<InMemDicomObject>,
+    image_box: Option<InMemDicomObject>,
+}
+
+/// A minimal print SCP with a single film box of one image box
+fn spawn_scp() -> Result<(std::thread::JoinHandle<Result<PrintLog>>, SocketAddr)> {
+    let listener = std::net::TcpListener::bind("localhost:0")?;
+    let addr = listener.local_addr()?;
+    let scp = ServerAssociationOptions::new()
+        .accept_called_ae_title()
+        .ae_title(SCP_AE_TITLE)
+        .with_abstract_syntax(uids::BASIC_GRAYSCALE_PRINT_MANAGEMENT_META);
+
+    let h = std::thread::spawn(move || -> Result<_> {
+        let (stream, _addr) = listener.accept()?;
+        let mut association = scp.establish(stream)?;
+        let mut log = PrintLog::default();
+
+        loop {
+            let msg = match receive_message(&mut association) {
+                Ok(msg) => msg,
+                Err(Error::Released { .. }) => {
+                    association.send(&Pdu::ReleaseRP)?;
+                    return Ok(log);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let pc_id = msg.presentation_context_id;
+            let command_field = msg.command_field()?;
+            log.commands.push(command_field);
+            match command_field {
+                CommandField::NCreateRq => {
+                    let rq = NCreateRq::from_command(&msg.command)?;
+                    let attributes = msg.read_data_set(&association)?.unwrap();
+                    let (uid, data) = if rq.affected_sop_class_uid == uids::BASIC_FILM_BOX {
+                        log.film_box = Some(attributes);
+                        let rsp_data = InMemDicomObject::from_element_iter([DataElement::new(
+                            tags::REFERENCED_IMAGE_BOX_SEQUENCE,
+                            VR::SQ,
+                            DataSetSequence::from(vec![InMemDicomObject::from_element_iter([
+                                DataElement::new(
+                                    tags::REFERENCED_SOP_CLASS_UID,
+                                    VR::UI,
+                                    PrimitiveValue::from(uids::BASIC_GRAYSCALE_IMAGE_BOX),
+                                ),
+                                DataElement::new(
+                                    tags::REFERENCED_SOP_INSTANCE_UID,
+                                    VR::UI,
+                                    PrimitiveValue::from(IMAGE_BOX_UID),
+                                ),
+                            ])]),
+                        )]);
+                        (
+                            FILM_BOX_UID,
+                            Some(write_data_set(&association, pc_id, &rsp_data)?),
+                        )
+                    } else {
+                        assert_eq!(rq.affected_sop_class_uid, uids::BASIC_FILM_SESSION);
+                        (FILM_SESSION_UID, None)
+                    };
+                    let rsp = NCreateRsp {
+                        message_id_being_responded_to: rq.message_id,
+                        affected_sop_class_uid: Some(rq.affected_sop_class_uid),
+                        affected_sop_instance_uid: Some(uid.to_string()),
+                        status: 0,
+                        error_comment: None,
+                    };
+                    send_message(
+                        &mut association,
+                        pc_id,
+                        &rsp.command(data.is_some()),
+                        data.as_deref(),
+                    )?;
+                }
+                CommandField::NSetRq => {
+                    let rq = NSetRq::from_command(&msg.command)?;
+                    assert_eq!(rq.requested_sop_class_uid, uids::BASIC_GRAYSCALE_IMAGE_BOX);
+                    assert_eq!(rq.requested_sop_instance_uid, IMAGE_BOX_UID);
+                    log.image_box = msg.read_data_set(&association)?;
+                    let rsp = NSetRsp {
+                        message_id_being_responded_to: rq.message_id,
+                        affected_sop_class_uid: Some(rq.requested_sop_class_uid),
+                        affected_sop_instance_uid: Some(rq.requested_sop_instance_uid),
+                        status: 0,
+                        error_comment: None,
+                    };
+                    send_message(&mut association, pc_id, &rsp.command(false), None)?;
+                }
+                CommandField::NActionRq => {
+                    let rq = NActionRq::from_command(&msg.command)?;
+                    assert_eq!(rq.requested_sop_instance_uid, FILM_BOX_UID);
+                    assert_eq!(rq.action_type_id, ACTION_PRINT);
+                    let rsp = NActionRsp {
+                        message_id_being_responded_to: rq.message_id,
+                        affected_sop_class_uid: Some(rq.requested_sop_class_uid),
+                        affected_sop_instance_uid: Some(rq.requested_sop_instance_uid),
+                        action_type_id: Some(rq.action_type_id),
+                        status: 0,
+                        error_comment: None,
+                    };
+                    send_message(&mut association, pc_id, &rsp.command(false), None)?;
+                }
+                CommandField::NDeleteRq => {
+                    let rq = NDeleteRq::from_command(&msg.command)?;
+                    assert_eq!(rq.requested_sop_instance_uid, FILM_SESSION_UID);
+                    let rsp = NDeleteRsp {
+                        message_id_being_responded_to: rq.message_id,
+                        affected_sop_class_uid: Some(rq.requested_sop_class_uid),
+                        affected_sop_instance_uid: Some(rq.requested_sop_instance_uid),
+                        status: 0,
+                        error_comment: None,
+                    };
+                    send_message(&mut association, pc_id, &rsp.command(), None)?;
+                }
+                other => panic!("unexpected command {other:?}"),
+            }
+        }
+    });
+    Ok((h, addr))
+}
+
+#[test]
+fn print_single_image() {
+    let (scp_handle, scp_addr) = spawn_scp().unwrap();
+
+    let mut association = ClientAssociationOptions::new()
+        .with_abstract_syntax(uids::BASIC_GRAYSCALE_PRINT_MANAGEMENT_META)
+        .calling_ae_title(SCU_AE_TITLE)
+        .called_ae_title(SCP_AE_TITLE)
+        .establish(scp_addr)
+        .unwrap();
+
+    let pixels: Vec<u8> = (0..64 * 32).map(|i| (i % 256) as u8).collect();
+    let image = GrayscaleImage::new_8bit(64, 32, pixels.clone());
+
+    let mut scu = PrintScu::new(&mut association).unwrap();
+    let film_box = scu
+        .print_images(
+            InMemDicomObject::from_element_iter([DataElement::new(
+                tags::NUMBER_OF_COPIES,
+                VR::IS,
+                PrimitiveValue::from("1"),
+            )]),
+            InMemDicomObject::new_empty(),
+            &[image],
+        )
+        .unwrap();
+    assert_eq!(film_box.sop_instance_uid, FILM_BOX_UID);
+    assert_eq!(film_box.image_box_uids, vec![IMAGE_BOX_UID.to_string()]);
+
+    association.release().unwrap();
+
+    let log = scp_handle
+        .join()
+        .expect("SCP panicked")
+        .expect("Error at the SCP");
+    assert_eq!(
+        log.commands,
+        vec![
+            CommandField::NCreateRq,
+            CommandField::NCreateRq,
+            CommandField::NSetRq,
+            CommandField::NActionRq,
+            CommandField::NDeleteRq,
+        ]
+    );
+
+    let film_box = log.film_box.unwrap();
+    assert_eq!(
+        film_box
+            .get(tags::IMAGE_DISPLAY_FORMAT)
+            .unwrap()
+            .to_str()
+            .unwrap(),
+        "STANDARD\\1,1"
+    );
+    let session_ref = &film_box
+        .get(tags::REFERENCED_FILM_SESSION_SEQUENCE)
+        .unwrap()
+        .items()
+        .unwrap()[0];
+    assert_eq!(
+        session_ref
+            .get(tags::REFERENCED_SOP_INSTANCE_UID)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .trim_end_matches('\0'),
+        FILM_SESSION_UID
+    );
+
+    let image_box = log.image_box.unwrap();
+    let item = &image_box
+        .get(tags::BASIC_GRAYSCALE_IMAGE_SEQUENCE)
+        .unwrap()
+        .items()
+        .unwrap()[0];
+    assert_eq!(item.get(tags::ROWS).unwrap().to_int::<u16>().unwrap(), 64);
+    assert_eq!(
+        &*item.get(tags::PIXEL_DATA).unwrap().to_bytes().unwrap(),
+        &pixels[..]
+    );
+}
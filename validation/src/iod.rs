@@ -0,0 +1,430 @@
+//! Built-in definitions of information object definitions (IODs)
+//! and the modules which compose them.
+//!
+//! Only a subset of the attributes of each module is described here,
+//! covering what is needed to check the presence of
+//! mandatory attributes and their most common value constraints.
+use dicom_core::Tag;
+use dicom_dictionary_std::{tags, uids};
+
+pub use dicom_core::dictionary::Vm;
+
+/// The attribute type, as defined in PS3.5 section 7.4,
+/// which determines whether an attribute must be present
+/// and whether it may be empty.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+    /// Required, must not be empty
+    Type1,
+    /// Conditionally required, must not be empty if present
+    Type1C,
+    /// Required, may be empty
+    Type2,
+    /// Conditionally required, may be empty if present
+    Type2C,
+    /// Optional
+    Type3,
+}
+
+impl AttributeType {
+    /// Whether the attribute must be present in the module.
+    ///
+    /// Conditional attributes are never considered required,
+    /// since their conditions are not evaluated.
+    pub fn is_required(self) -> bool {
+        matches!(self, AttributeType::Type1 | AttributeType::Type2)
+    }
+
+    /// Whether the attribute must have a value when present.
+    pub fn is_non_empty(self) -> bool {
+        matches!(self, AttributeType::Type1 | AttributeType::Type1C)
+    }
+}
+
+impl std::fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AttributeType::Type1 => "1",
+            AttributeType::Type1C => "1C",
+            AttributeType::Type2 => "2",
+            AttributeType::Type2C => "2C",
+            AttributeType::Type3 => "3",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Constraints on the values that an attribute may take.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EnumeratedValues {
+    /// Every value must be one of these
+    All(&'static [&'static str]),
+    /// The value at each position must be one of the values
+    /// in the list at the same position.
+    /// Values beyond the given lists are not constrained.
+    Positional(&'static [&'static [&'static str]]),
+}
+
+impl EnumeratedValues {
+    /// Obtain the list of accepted values at the given value position,
+    /// or `None` if the value at this position is not constrained.
+    pub fn at(&self, index: usize) -> Option<&'static [&'static str]> {
+        match self {
+            EnumeratedValues::All(values) => Some(values),
+            EnumeratedValues::Positional(lists) => lists.get(index).copied(),
+        }
+    }
+}
+
+/// The definition of an attribute in a module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AttributeDef {
+    /// the attribute tag
+    pub tag: Tag,
+    /// the attribute type
+    pub ty: AttributeType,
+    /// the expected value multiplicity
+    pub vm: Vm,
+    /// the enumerated values, if any
+    pub enumerated: Option<EnumeratedValues>,
+}
+
+impl AttributeDef {
+    const fn new(tag: Tag, ty: AttributeType) -> Self {
+        AttributeDef {
+            tag,
+            ty,
+            vm: Vm::ONE,
+            enumerated: None,
+        }
+    }
+
+    const fn vm(mut self, vm: Vm) -> Self {
+        self.vm = vm;
+        self
+    }
+
+    const fn enumerated(mut self, values: &'static [&'static str]) -> Self {
+        self.enumerated = Some(EnumeratedValues::All(values));
+        self
+    }
+
+    const fn positional(mut self, values: &'static [&'static [&'static str]]) -> Self {
+        self.enumerated = Some(EnumeratedValues::Positional(values));
+        self
+    }
+}
+
+/// A module definition: a named group of attributes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Module {
+    /// the name of the module
+    pub name: &'static str,
+    /// the attributes of the module
+    pub attributes: &'static [AttributeDef],
+}
+
+/// How a module is used by an IOD.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Usage {
+    /// Mandatory (M)
+    Mandatory,
+    /// Conditional (C)
+    ///
+    /// Since conditions are not evaluated,
+    /// the module is only validated if
+    /// at least one of its attributes is present.
+    Conditional,
+    /// User option (U)
+    ///
+    /// The module is only validated if
+    /// at least one of its attributes is present.
+    UserOption,
+}
+
+/// An information object definition (IOD).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Iod {
+    /// the name of the IOD
+    pub name: &'static str,
+    /// the SOP class UIDs which use this IOD
+    pub sop_class_uids: &'static [&'static str],
+    /// the modules of the IOD and their usage
+    pub modules: &'static [(&'static Module, Usage)],
+}
+
+use AttributeType::*;
+
+const MONOCHROME: &[&str] = &["MONOCHROME1", "MONOCHROME2"];
+const ORIGINAL_PRIMARY: &[&[&str]] = &[&["ORIGINAL", "DERIVED"], &["PRIMARY", "SECONDARY"]];
+
+/// Patient Module (PS3.3 C.7.1.1)
+pub static PATIENT: Module = Module {
+    name: "Patient",
+    attributes: &[
+        AttributeDef::new(tags::PATIENT_NAME, Type2),
+        AttributeDef::new(tags::PATIENT_ID, Type2),
+        AttributeDef::new(tags::PATIENT_BIRTH_DATE, Type2),
+        AttributeDef::new(tags::PATIENT_SEX, Type2).enumerated(&["M", "F", "O"]),
+    ],
+};
+
+/// General Study Module (PS3.3 C.7.2.1)
+pub static GENERAL_STUDY: Module = Module {
+    name: "General Study",
+    attributes: &[
+        AttributeDef::new(tags::STUDY_INSTANCE_UID, Type1),
+        AttributeDef::new(tags::STUDY_DATE, Type2),
+        AttributeDef::new(tags::STUDY_TIME, Type2),
+        AttributeDef::new(tags::REFERRING_PHYSICIAN_NAME, Type2),
+        AttributeDef::new(tags::STUDY_ID, Type2),
+        AttributeDef::new(tags::ACCESSION_NUMBER, Type2),
+    ],
+};
+
+/// General Series Module (PS3.3 C.7.3.1)
+pub static GENERAL_SERIES: Module = Module {
+    name: "General Series",
+    attributes: &[
+        AttributeDef::new(tags::MODALITY, Type1),
+        AttributeDef::new(tags::SERIES_INSTANCE_UID, Type1),
+        AttributeDef::new(tags::SERIES_NUMBER, Type2),
+        AttributeDef::new(tags::LATERALITY, Type2C).enumerated(&["R", "L"]),
+    ],
+};
+
+/// Frame of Reference Module (PS3.3 C.7.4.1)
+pub static FRAME_OF_REFERENCE: Module = Module {
+    name: "Frame of Reference",
+    attributes: &[
+        AttributeDef::new(tags::FRAME_OF_REFERENCE_UID, Type1),
+        AttributeDef::new(tags::POSITION_REFERENCE_INDICATOR, Type2),
+    ],
+};
+
+/// General Equipment Module (PS3.3 C.7.5.1)
+pub static GENERAL_EQUIPMENT: Module = Module {
+    name: "General Equipment",
+    attributes: &[AttributeDef::new(tags::MANUFACTURER, Type2)],
+};
+
+/// SC Equipment Module (PS3.3 C.8.6.1)
+pub static SC_EQUIPMENT: Module = Module {
+    name: "SC Equipment",
+    attributes: &[AttributeDef::new(tags::CONVERSION_TYPE, Type1)
+        .enumerated(&["DV", "DI", "DF", "WSD", "SD", "SI", "DRW", "SYN"])],
+};
+
+/// General Image Module (PS3.3 C.7.6.1)
+pub static GENERAL_IMAGE: Module = Module {
+    name: "General Image",
+    attributes: &[
+        AttributeDef::new(tags::INSTANCE_NUMBER, Type2),
+        AttributeDef::new(tags::PATIENT_ORIENTATION, Type2C).vm(Vm::exactly(2)),
+        AttributeDef::new(tags::IMAGE_TYPE, Type3).vm(Vm::at_least(2)),
+    ],
+};
+
+/// Image Plane Module (PS3.3 C.7.6.2)
+pub static IMAGE_PLANE: Module = Module {
+    name: "Image Plane",
+    attributes: &[
+        AttributeDef::new(tags::PIXEL_SPACING, Type1).vm(Vm::exactly(2)),
+        AttributeDef::new(tags::IMAGE_ORIENTATION_PATIENT, Type1).vm(Vm::exactly(6)),
+        AttributeDef::new(tags::IMAGE_POSITION_PATIENT, Type1).vm(Vm::exactly(3)),
+        AttributeDef::new(tags::SLICE_THICKNESS, Type2),
+    ],
+};
+
+/// Image Pixel Module (PS3.3 C.7.6.3)
+pub static IMAGE_PIXEL: Module = Module {
+    name: "Image Pixel",
+    attributes: &[
+        AttributeDef::new(tags::SAMPLES_PER_PIXEL, Type1),
+        AttributeDef::new(tags::PHOTOMETRIC_INTERPRETATION, Type1),
+        AttributeDef::new(tags::ROWS, Type1),
+        AttributeDef::new(tags::COLUMNS, Type1),
+        AttributeDef::new(tags::BITS_ALLOCATED, Type1),
+        AttributeDef::new(tags::BITS_STORED, Type1),
+        AttributeDef::new(tags::HIGH_BIT, Type1),
+        AttributeDef::new(tags::PIXEL_REPRESENTATION, Type1).enumerated(&["0", "1"]),
+        AttributeDef::new(tags::PLANAR_CONFIGURATION, Type1C).enumerated(&["0", "1"]),
+        AttributeDef::new(tags::PIXEL_DATA, Type1C),
+    ],
+};
+
+/// Contrast/Bolus Module (PS3.3 C.7.6.4)
+pub static CONTRAST_BOLUS: Module = Module {
+    name: "Contrast/Bolus",
+    attributes: &[AttributeDef::new(tags::CONTRAST_BOLUS_AGENT, Type2)],
+};
+
+/// CT Image Module (PS3.3 C.8.2.1)
+pub static CT_IMAGE: Module = Module {
+    name: "CT Image",
+    attributes: &[
+        AttributeDef::new(tags::IMAGE_TYPE, Type1)
+            .vm(Vm::at_least(2))
+            .positional(ORIGINAL_PRIMARY),
+        AttributeDef::new(tags::SAMPLES_PER_PIXEL, Type1).enumerated(&["1"]),
+        AttributeDef::new(tags::PHOTOMETRIC_INTERPRETATION, Type1).enumerated(MONOCHROME),
+        AttributeDef::new(tags::BITS_ALLOCATED, Type1).enumerated(&["16"]),
+        AttributeDef::new(tags::BITS_STORED, Type1),
+        AttributeDef::new(tags::HIGH_BIT, Type1),
+        AttributeDef::new(tags::RESCALE_INTERCEPT, Type1),
+        AttributeDef::new(tags::RESCALE_SLOPE, Type1),
+        AttributeDef::new(tags::KVP, Type2),
+        AttributeDef::new(tags::ACQUISITION_NUMBER, Type2),
+    ],
+};
+
+/// MR Image Module (PS3.3 C.8.3.1)
+pub static MR_IMAGE: Module = Module {
+    name: "MR Image",
+    attributes: &[
+        AttributeDef::new(tags::IMAGE_TYPE, Type1)
+            .vm(Vm::at_least(2))
+            .positional(ORIGINAL_PRIMARY),
+        AttributeDef::new(tags::SAMPLES_PER_PIXEL, Type1).enumerated(&["1"]),
+        AttributeDef::new(tags::PHOTOMETRIC_INTERPRETATION, Type1).enumerated(MONOCHROME),
+        AttributeDef::new(tags::BITS_ALLOCATED, Type1).enumerated(&["16"]),
+        AttributeDef::new(tags::SCANNING_SEQUENCE, Type1)
+            .vm(Vm::ONE_OR_MORE)
+            .enumerated(&["SE", "IR", "GR", "EP", "RM"]),
+        AttributeDef::new(tags::SEQUENCE_VARIANT, Type1)
+            .vm(Vm::ONE_OR_MORE)
+            .enumerated(&["SK", "MTC", "SS", "TRSS", "SP", "MP", "OSP", "NONE"]),
+        AttributeDef::new(tags::SCAN_OPTIONS, Type2).vm(Vm::ONE_OR_MORE),
+        AttributeDef::new(tags::MR_ACQUISITION_TYPE, Type2).enumerated(&["2D", "3D"]),
+        AttributeDef::new(tags::ECHO_TIME, Type2),
+        AttributeDef::new(tags::ECHO_TRAIN_LENGTH, Type2),
+    ],
+};
+
+/// SC Image Module (PS3.3 C.8.6.2)
+pub static SC_IMAGE: Module = Module {
+    name: "SC Image",
+    attributes: &[
+        AttributeDef::new(tags::DATE_OF_SECONDARY_CAPTURE, Type3),
+        AttributeDef::new(tags::TIME_OF_SECONDARY_CAPTURE, Type3),
+    ],
+};
+
+/// VOI LUT Module (PS3.3 C.11.2)
+pub static VOI_LUT: Module = Module {
+    name: "VOI LUT",
+    attributes: &[
+        AttributeDef::new(tags::WINDOW_CENTER, Type1C).vm(Vm::ONE_OR_MORE),
+        AttributeDef::new(tags::WINDOW_WIDTH, Type1C).vm(Vm::ONE_OR_MORE),
+    ],
+};
+
+/// SOP Common Module (PS3.3 C.12.1)
+pub static SOP_COMMON: Module = Module {
+    name: "SOP Common",
+    attributes: &[
+        AttributeDef::new(tags::SOP_CLASS_UID, Type1),
+        AttributeDef::new(tags::SOP_INSTANCE_UID, Type1),
+        AttributeDef::new(tags::SPECIFIC_CHARACTER_SET, Type1C).vm(Vm::ONE_OR_MORE),
+    ],
+};
+
+/// CT Image IOD (PS3.3 A.3)
+pub static CT_IMAGE_IOD: Iod = Iod {
+    name: "CT Image",
+    sop_class_uids: &[uids::CT_IMAGE_STORAGE],
+    modules: &[
+        (&PATIENT, Usage::Mandatory),
+        (&GENERAL_STUDY, Usage::Mandatory),
+        (&GENERAL_SERIES, Usage::Mandatory),
+        (&FRAME_OF_REFERENCE, Usage::Mandatory),
+        (&GENERAL_EQUIPMENT, Usage::Mandatory),
+        (&GENERAL_IMAGE, Usage::Mandatory),
+        (&IMAGE_PLANE, Usage::Mandatory),
+        (&IMAGE_PIXEL, Usage::Mandatory),
+        (&CONTRAST_BOLUS, Usage::Conditional),
+        (&CT_IMAGE, Usage::Mandatory),
+        (&VOI_LUT, Usage::UserOption),
+        (&SOP_COMMON, Usage::Mandatory),
+    ],
+};
+
+/// MR Image IOD (PS3.3 A.4)
+pub static MR_IMAGE_IOD: Iod = Iod {
+    name: "MR Image",
+    sop_class_uids: &[uids::MR_IMAGE_STORAGE],
+    modules: &[
+        (&PATIENT, Usage::Mandatory),
+        (&GENERAL_STUDY, Usage::Mandatory),
+        (&GENERAL_SERIES, Usage::Mandatory),
+        (&FRAME_OF_REFERENCE, Usage::Mandatory),
+        (&GENERAL_EQUIPMENT, Usage::Mandatory),
+        (&GENERAL_IMAGE, Usage::Mandatory),
+        (&IMAGE_PLANE, Usage::Mandatory),
+        (&IMAGE_PIXEL, Usage::Mandatory),
+        (&CONTRAST_BOLUS, Usage::Conditional),
+        (&MR_IMAGE, Usage::Mandatory),
+        (&VOI_LUT, Usage::UserOption),
+        (&SOP_COMMON, Usage::Mandatory),
+    ],
+};
+
+/// Secondary Capture Image IOD (PS3.3 A.8.1)
+pub static SECONDARY_CAPTURE_IMAGE_IOD: Iod = Iod {
+    name: "Secondary Capture Image",
+    sop_class_uids: &[uids::SECONDARY_CAPTURE_IMAGE_STORAGE],
+    modules: &[
+        (&PATIENT, Usage::Mandatory),
+        (&GENERAL_STUDY, Usage::Mandatory),
+        (&GENERAL_SERIES, Usage::Mandatory),
+        (&GENERAL_EQUIPMENT, Usage::UserOption),
+        (&SC_EQUIPMENT, Usage::Mandatory),
+        (&GENERAL_IMAGE, Usage::Mandatory),
+        (&IMAGE_PIXEL, Usage::Mandatory),
+        (&SC_IMAGE, Usage::Mandatory),
+        (&VOI_LUT, Usage::UserOption),
+        (&SOP_COMMON, Usage::Mandatory),
+    ],
+};
+
+/// All built-in IODs.
+pub static IODS: &[&Iod] = &[&CT_IMAGE_IOD, &MR_IMAGE_IOD, &SECONDARY_CAPTURE_IMAGE_IOD];
+
+/// Look up the built-in IOD for the given SOP class UID.
+///
+/// Trailing null characters and whitespace in the UID are ignored.
+pub fn iod_for_sop_class(uid: &str) -> Option<&'static Iod> {
+    let uid = uid.trim_end_matches(|c: char| c == '\0' || c.is_whitespace());
+    IODS.iter()
+        .copied()
+        .find(|iod| iod.sop_class_uids.contains(&uid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vm_accepts() {
+        assert!(Vm::ONE.accepts(1));
+        assert!(!Vm::ONE.accepts(2));
+        assert!(Vm::ONE_OR_MORE.accepts(5));
+        assert!(!Vm::at_least(2).accepts(1));
+        let even = Vm {
+            min: 2,
+            max: None,
+            step: 2,
+        };
+        assert!(even.accepts(4));
+        assert!(!even.accepts(3));
+        assert_eq!(even.to_string(), "2-2n");
+        assert_eq!(Vm::exactly(3).to_string(), "3");
+    }
+
+    #[test]
+    fn lookup_iod() {
+        let iod = iod_for_sop_class("1.2.840.10008.5.1.4.1.1.2\0").unwrap();
+        assert_eq!(iod.name, "CT Image");
+        assert!(iod_for_sop_class("1.2.3.4").is_none());
+    }
+}
@@ -0,0 +1,576 @@
+//! DICOM object validation
+//!
+//! This library checks DICOM objects against
+//! the information object definition (IOD)
+//! indicated by their _SOP Class UID_,
+//! looking for:
+//!
+//! - missing type 1 and type 2 attributes in each module;
+//! - empty type 1 attributes;
+//! - values outside of an attribute's enumerated values;
+//! - value representations which do not match the standard dictionary;
+//! - value multiplicities which do not conform to the module definition.
+//!
+//! The result is a [`ValidationReport`]
+//! listing every issue found.
+//! Only a [set of built-in IODs](iod::IODS) is currently known.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use dicom_object::open_file;
+//! use dicom_validation::ValidateExt;
+//!
+//! let obj = open_file("path/to/file.dcm")?;
+//! let report = obj.validate();
+//! if !report.is_valid() {
+//!     for issue in report.issues() {
+//!         println!("{issue}");
+//!     }
+//! }
+//! # Result::<(), Box<dyn std::error::Error>>::Ok(())
+//! ```
+use dicom_core::dictionary::{DataDictionary, DataDictionaryEntry, VirtualVr};
+use dicom_core::header::HasLength;
+use dicom_core::value::Value;
+use dicom_core::{Tag, VR};
+use dicom_dictionary_std::{StandardDataDictionary, tags};
+use dicom_object::mem::InMemElement;
+use dicom_object::{FileDicomObject, InMemDicomObject};
+use std::fmt;
+
+pub mod iod;
+
+use iod::{AttributeDef, Iod, Usage, Vm};
+
+/// The severity of a validation issue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The object may still be usable, but is likely to be incorrect
+    Warning,
+    /// The object does not conform to the IOD
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// The kind of problem found in a validation issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IssueKind {
+    /// The object has no SOP Class UID,
+    /// so the IOD could not be determined
+    MissingSopClass,
+    /// The SOP class is not covered by any of the known IODs
+    UnsupportedSopClass(String),
+    /// A required attribute is missing
+    MissingAttribute,
+    /// A type 1 attribute is present but empty
+    EmptyValue,
+    /// A value is not one of the enumerated values of the attribute
+    InvalidEnumeratedValue {
+        /// the offending value
+        value: String,
+        /// the position of the value in the element
+        index: usize,
+    },
+    /// The element's VR does not match the one in the dictionary
+    VrMismatch {
+        /// the expected value representation
+        expected: VirtualVr,
+        /// the value representation found
+        found: VR,
+    },
+    /// The number of values does not conform to the attribute definition
+    VmMismatch {
+        /// the expected value multiplicity
+        expected: Vm,
+        /// the number of values found
+        found: u32,
+    },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::MissingSopClass => f.write_str("missing SOP Class UID"),
+            IssueKind::UnsupportedSopClass(uid) => write!(f, "unsupported SOP class {uid}"),
+            IssueKind::MissingAttribute => f.write_str("missing attribute"),
+            IssueKind::EmptyValue => f.write_str("type 1 attribute is empty"),
+            IssueKind::InvalidEnumeratedValue { value, index } => {
+                write!(
+                    f,
+                    "value #{} `{}` is not an enumerated value",
+                    index + 1,
+                    value
+                )
+            }
+            IssueKind::VrMismatch { expected, found } => {
+                write!(f, "expected VR ")?;
+                match expected {
+                    VirtualVr::Exact(vr) => write!(f, "{vr}")?,
+                    other => write!(f, "{other:?}")?,
+                }
+                write!(f, ", found {found}")
+            }
+            IssueKind::VmMismatch { expected, found } => {
+                write!(f, "expected VM {expected}, found {found} values")
+            }
+        }
+    }
+}
+
+/// A single problem found while validating an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    /// how severe the issue is
+    pub severity: Severity,
+    /// the name of the module in which the issue was found,
+    /// if applicable
+    pub module: Option<&'static str>,
+    /// the tag of the attribute concerned
+    pub tag: Tag,
+    /// the kind of issue
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.tag)?;
+        if let Some(entry) = StandardDataDictionary.by_tag(self.tag) {
+            write!(f, " {}", entry.alias())?;
+        }
+        if let Some(module) = self.module {
+            write!(f, " ({module} module)")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// The outcome of validating a DICOM object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    sop_class_uid: Option<String>,
+    iod: Option<&'static Iod>,
+    issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// The SOP class UID of the object, if present.
+    pub fn sop_class_uid(&self) -> Option<&str> {
+        self.sop_class_uid.as_deref()
+    }
+
+    /// The IOD that the object was validated against,
+    /// or `None` if it could not be determined.
+    pub fn iod(&self) -> Option<&'static Iod> {
+        self.iod
+    }
+
+    /// All issues found, in the order in which they were detected.
+    pub fn issues(&self) -> &[Issue] {
+        &self.issues
+    }
+
+    /// Iterate over the issues of error severity.
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    /// Iterate over the issues of warning severity.
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// Whether no errors were found.
+    ///
+    /// Warnings do not make an object invalid.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Obtain the issues found, consuming the report.
+    pub fn into_issues(self) -> Vec<Issue> {
+        self.issues
+    }
+}
+
+/// Extension trait for validating DICOM objects.
+pub trait ValidateExt {
+    /// Validate the object against the IOD of its SOP class.
+    fn validate(&self) -> ValidationReport;
+
+    /// Validate the object against the given IOD,
+    /// regardless of its SOP class.
+    fn validate_as(&self, iod: &'static Iod) -> ValidationReport;
+}
+
+impl<D> ValidateExt for InMemDicomObject<D>
+where
+    D: DataDictionary + Clone,
+{
+    fn validate(&self) -> ValidationReport {
+        let sop_class_uid = sop_class_of(self);
+        validate_impl(self, sop_class_uid, None)
+    }
+
+    fn validate_as(&self, iod: &'static Iod) -> ValidationReport {
+        let sop_class_uid = sop_class_of(self);
+        validate_impl(self, sop_class_uid, Some(iod))
+    }
+}
+
+/// File objects fall back to the media storage SOP class UID
+/// in the file meta group when the data set does not have one.
+impl<D> ValidateExt for FileDicomObject<InMemDicomObject<D>>
+where
+    D: DataDictionary + Clone,
+{
+    fn validate(&self) -> ValidationReport {
+        let sop_class_uid = sop_class_of(self).or_else(|| {
+            Some(trim_uid(&self.meta().media_storage_sop_class_uid).to_string())
+                .filter(|uid| !uid.is_empty())
+        });
+        validate_impl(self, sop_class_uid, None)
+    }
+
+    fn validate_as(&self, iod: &'static Iod) -> ValidationReport {
+        (**self).validate_as(iod)
+    }
+}
+
+fn trim_uid(uid: &str) -> &str {
+    uid.trim_end_matches(|c: char| c == '\0' || c.is_whitespace())
+}
+
+fn sop_class_of<D>(obj: &InMemDicomObject<D>) -> Option<String>
+where
+    D: DataDictionary + Clone,
+{
+    obj.get(tags::SOP_CLASS_UID)
+        .and_then(|e| e.to_str().ok())
+        .map(|uid| trim_uid(&uid).to_string())
+        .filter(|uid| !uid.is_empty())
+}
+
+fn validate_impl<D>(
+    obj: &InMemDicomObject<D>,
+    sop_class_uid: Option<String>,
+    iod: Option<&'static Iod>,
+) -> ValidationReport
+where
+    D: DataDictionary + Clone,
+{
+    let mut issues = Vec::new();
+
+    let iod = iod.or_else(|| {
+        match &sop_class_uid {
+            None => issues.push(Issue {
+                severity: Severity::Error,
+                module: None,
+                tag: tags::SOP_CLASS_UID,
+                kind: IssueKind::MissingSopClass,
+            }),
+            Some(uid) => {
+                let iod = iod::iod_for_sop_class(uid);
+                if iod.is_none() {
+                    issues.push(Issue {
+                        severity: Severity::Warning,
+                        module: None,
+                        tag: tags::SOP_CLASS_UID,
+                        kind: IssueKind::UnsupportedSopClass(uid.clone()),
+                    });
+                }
+                return iod;
+            }
+        }
+        None
+    });
+
+    if let Some(iod) = iod {
+        for (module, usage) in iod.modules {
+            if *usage != Usage::Mandatory
+                && !module
+                    .attributes
+                    .iter()
+                    .any(|attr| obj.get(attr.tag).is_some())
+            {
+                continue;
+            }
+            for attr in module.attributes {
+                check_attribute(obj, module.name, attr, &mut issues);
+            }
+        }
+    }
+
+    ValidationReport {
+        sop_class_uid,
+        iod,
+        issues,
+    }
+}
+
+fn check_attribute<D>(
+    obj: &InMemDicomObject<D>,
+    module: &'static str,
+    attr: &AttributeDef,
+    issues: &mut Vec<Issue>,
+) where
+    D: DataDictionary + Clone,
+{
+    let mut push = |severity, kind| {
+        issues.push(Issue {
+            severity,
+            module: Some(module),
+            tag: attr.tag,
+            kind,
+        })
+    };
+
+    let Some(elem) = obj.get(attr.tag) else {
+        if attr.ty.is_required() {
+            push(Severity::Error, IssueKind::MissingAttribute);
+        }
+        return;
+    };
+
+    if is_empty(elem) {
+        if attr.ty.is_non_empty() {
+            push(Severity::Error, IssueKind::EmptyValue);
+        }
+        return;
+    }
+
+    // value representation
+    let vr = elem.vr();
+    if let Some(entry) = StandardDataDictionary.by_tag(attr.tag) {
+        let expected = entry.vr();
+        if !vr_matches(expected, vr) {
+            // an unknown VR is tolerated, as it may come from
+            // an implicit VR data set with a private dictionary
+            let severity = if vr == VR::UN {
+                Severity::Warning
+            } else {
+                Severity::Error
+            };
+            push(
+                severity,
+                IssueKind::VrMismatch {
+                    expected,
+                    found: vr,
+                },
+            );
+        }
+    }
+
+    let Value::Primitive(value) = elem.value() else {
+        return;
+    };
+
+    // value multiplicity, only for VRs which may hold multiple values
+    if !matches!(
+        vr,
+        VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::UN | VR::LT | VR::ST | VR::UT
+    ) {
+        let count = value.multiplicity();
+        if !attr.vm.accepts(count) {
+            push(
+                Severity::Error,
+                IssueKind::VmMismatch {
+                    expected: attr.vm,
+                    found: count,
+                },
+            );
+        }
+    }
+
+    // enumerated values
+    if let Some(enumerated) = &attr.enumerated {
+        for (index, v) in value.to_multi_str().iter().enumerate() {
+            let Some(accepted) = enumerated.at(index) else {
+                continue;
+            };
+            let v = v.trim_end_matches([' ', '\0']);
+            if !accepted.contains(&v) {
+                push(
+                    Severity::Error,
+                    IssueKind::InvalidEnumeratedValue {
+                        value: v.to_string(),
+                        index,
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn is_empty(elem: &InMemElement<impl DataDictionary + Clone>) -> bool {
+    match elem.value() {
+        Value::Primitive(v) => {
+            v.is_empty()
+                || v.to_multi_str()
+                    .iter()
+                    .all(|s| s.trim_end_matches([' ', '\0']).is_empty())
+        }
+        Value::Sequence(seq) => seq.items().is_empty(),
+        Value::PixelSequence(seq) => seq.fragments().is_empty(),
+    }
+}
+
+fn vr_matches(expected: VirtualVr, vr: VR) -> bool {
+    match expected {
+        VirtualVr::Exact(expected) => expected == vr,
+        VirtualVr::Xs => matches!(vr, VR::US | VR::SS),
+        VirtualVr::Ox | VirtualVr::Px => matches!(vr, VR::OB | VR::OW),
+        VirtualVr::Lt => matches!(vr, VR::US | VR::SS | VR::OW),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom_core::{DataElement, PrimitiveValue, dicom_value};
+    use dicom_dictionary_std::uids;
+
+    fn secondary_capture() -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                tags::SOP_CLASS_UID,
+                VR::UI,
+                uids::SECONDARY_CAPTURE_IMAGE_STORAGE,
+            ),
+            DataElement::new(tags::SOP_INSTANCE_UID, VR::UI, "2.25.1234"),
+            DataElement::new(tags::STUDY_DATE, VR::DA, PrimitiveValue::Empty),
+            DataElement::new(tags::STUDY_TIME, VR::TM, PrimitiveValue::Empty),
+            DataElement::new(tags::ACCESSION_NUMBER, VR::SH, PrimitiveValue::Empty),
+            DataElement::new(tags::MODALITY, VR::CS, "OT"),
+            DataElement::new(tags::CONVERSION_TYPE, VR::CS, "WSD"),
+            DataElement::new(
+                tags::REFERRING_PHYSICIAN_NAME,
+                VR::PN,
+                PrimitiveValue::Empty,
+            ),
+            DataElement::new(tags::PATIENT_NAME, VR::PN, "Doe^John"),
+            DataElement::new(tags::PATIENT_ID, VR::LO, "1"),
+            DataElement::new(tags::PATIENT_BIRTH_DATE, VR::DA, PrimitiveValue::Empty),
+            DataElement::new(tags::PATIENT_SEX, VR::CS, "O"),
+            DataElement::new(tags::STUDY_INSTANCE_UID, VR::UI, "2.25.1"),
+            DataElement::new(tags::SERIES_INSTANCE_UID, VR::UI, "2.25.2"),
+            DataElement::new(tags::STUDY_ID, VR::SH, PrimitiveValue::Empty),
+            DataElement::new(tags::SERIES_NUMBER, VR::IS, "1"),
+            DataElement::new(tags::INSTANCE_NUMBER, VR::IS, "1"),
+            DataElement::new(tags::SAMPLES_PER_PIXEL, VR::US, PrimitiveValue::from(1_u16)),
+            DataElement::new(tags::PHOTOMETRIC_INTERPRETATION, VR::CS, "MONOCHROME2"),
+            DataElement::new(tags::ROWS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::COLUMNS, VR::US, PrimitiveValue::from(2_u16)),
+            DataElement::new(tags::BITS_ALLOCATED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::BITS_STORED, VR::US, PrimitiveValue::from(8_u16)),
+            DataElement::new(tags::HIGH_BIT, VR::US, PrimitiveValue::from(7_u16)),
+            DataElement::new(
+                tags::PIXEL_REPRESENTATION,
+                VR::US,
+                PrimitiveValue::from(0_u16),
+            ),
+            DataElement::new(tags::PIXEL_DATA, VR::OB, dicom_value!(U8, [0, 1, 2, 3])),
+        ])
+    }
+
+    #[test]
+    fn valid_secondary_capture() {
+        let obj = secondary_capture();
+        let report = obj.validate();
+        assert_eq!(report.iod().unwrap().name, "Secondary Capture Image");
+        assert_eq!(report.issues(), &[]);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn detect_issues() {
+        let mut obj = secondary_capture();
+        // missing type 2
+        obj.remove_element(tags::PATIENT_ID);
+        // empty type 1
+        obj.put(DataElement::new(
+            tags::SERIES_INSTANCE_UID,
+            VR::UI,
+            PrimitiveValue::Empty,
+        ));
+        // bad enumerated value
+        obj.put(DataElement::new(tags::PATIENT_SEX, VR::CS, "X"));
+        // bad VR
+        obj.put(DataElement::new(
+            tags::ROWS,
+            VR::UL,
+            PrimitiveValue::from(2_u32),
+        ));
+        // bad VM
+        obj.put(DataElement::new(
+            tags::COLUMNS,
+            VR::US,
+            dicom_value!(U16, [2, 2]),
+        ));
+
+        let report = obj.validate();
+        assert!(!report.is_valid());
+        let kinds: Vec<_> = report
+            .issues()
+            .iter()
+            .map(|issue| (issue.tag, issue.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (tags::PATIENT_ID, IssueKind::MissingAttribute),
+                (
+                    tags::PATIENT_SEX,
+                    IssueKind::InvalidEnumeratedValue {
+                        value: "X".to_string(),
+                        index: 0
+                    }
+                ),
+                (tags::SERIES_INSTANCE_UID, IssueKind::EmptyValue),
+                (
+                    tags::ROWS,
+                    IssueKind::VrMismatch {
+                        expected: VirtualVr::Exact(VR::US),
+                        found: VR::UL
+                    }
+                ),
+                (
+                    tags::COLUMNS,
+                    IssueKind::VmMismatch {
+                        expected: Vm::ONE,
+                        found: 2
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_sop_class() {
+        let mut obj = secondary_capture();
+        obj.put(DataElement::new(tags::SOP_CLASS_UID, VR::UI, "1.2.3.4"));
+        let report = obj.validate();
+        assert!(report.iod().is_none());
+        assert!(report.is_valid());
+        assert_eq!(report.warnings().count(), 1);
+
+        obj.remove_element(tags::SOP_CLASS_UID);
+        let report = obj.validate();
+        assert!(!report.is_valid());
+        assert_eq!(report.issues()[0].kind, IssueKind::MissingSopClass);
+    }
+}
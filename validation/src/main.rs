@@ -0,0 +1,75 @@
+//! A CLI tool for validating DICOM files
+//! against the information object definition of their SOP class.
+use clap::Parser;
+use dicom_object::open_file;
+use dicom_validation::ValidateExt;
+use snafu::Report;
+use std::path::PathBuf;
+
+/// Exit code for when an error emerged while reading a DICOM file.
+const ERROR_READ: i32 = -2;
+/// Exit code for when a DICOM file did not pass validation.
+const ERROR_INVALID: i32 = -3;
+
+/// Validate DICOM files against their information object definition
+#[derive(Debug, Parser)]
+#[command(version)]
+struct App {
+    /// The DICOM file(s) to validate
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+    /// Only print the files which did not pass validation
+    #[clap(short = 'q', long = "quiet")]
+    quiet: bool,
+    /// Treat warnings as errors
+    #[clap(long = "strict")]
+    strict: bool,
+}
+
+fn main() {
+    let App {
+        files,
+        quiet,
+        strict,
+    } = App::parse();
+
+    let mut status = 0;
+
+    for path in &files {
+        let obj = match open_file(path) {
+            Ok(obj) => obj,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), Report::from_error(e));
+                status = ERROR_READ;
+                continue;
+            }
+        };
+
+        let report = obj.validate();
+        let valid = if strict {
+            report.issues().is_empty()
+        } else {
+            report.is_valid()
+        };
+
+        if valid && quiet {
+            continue;
+        }
+
+        let iod = report.iod().map(|iod| iod.name).unwrap_or("unknown IOD");
+        println!(
+            "{}: {} ({iod})",
+            path.display(),
+            if valid { "OK" } else { "FAILED" }
+        );
+        for issue in report.issues() {
+            println!("    {issue}");
+        }
+
+        if !valid && status == 0 {
+            status = ERROR_INVALID;
+        }
+    }
+
+    std::process::exit(status);
+}